@@ -1,25 +1,98 @@
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
+use crossterm::event::KeyEvent;
 use fuzzy_matcher::skim::SkimMatcherV2;
 use fuzzy_matcher::FuzzyMatcher;
 use ratatui::layout::Rect;
-use ratatui::text::Line;
-use syntect::highlighting::Theme;
-use syntect::parsing::SyntaxSet;
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span};
+use serde::{Deserialize, Serialize};
 use tokio::sync::mpsc;
 
-use crate::components::help::HelpState;
+#[cfg(feature = "archives")]
+use crate::archive;
+use crate::blame::{self, BlameGutter, BlameRange, BlameResult, BlameState};
+use crate::bookmarks::Bookmarks;
+use crate::bounded_map::MapStats;
+use crate::components::dialog;
+use crate::components::help::{HelpOverlay, HelpSection, HelpState};
+use crate::components::status_bar::StatusBarCache;
+use crate::components::tree::TreeRowCache;
 use crate::config::AppConfig;
+use crate::confirm::{ConfirmationPolicy, Decision, Intent, Level, PREFLIGHT_MAX_ENTRIES};
+use crate::dir_size::{self, DirSize, DirSizes};
+#[cfg(feature = "editor")]
+use crate::edit_positions::EditPosition;
+use crate::edit_positions::EditPositions;
+#[cfg(feature = "editor")]
 use crate::editor::EditorState;
-use crate::error::Result;
+use crate::error::{describe_errors, OperationError, Result};
+use crate::file_info::FileInfo;
+use crate::flash::{self, FlashMap, FlashStep, LineFlash};
+use crate::fs::bulk_rename::BulkRenameForm;
 use crate::fs::clipboard::{ClipboardOp, ClipboardState};
-use crate::fs::tree::{NodeType, TreeState};
+use crate::fs::export::{self, ExportOptions, ExportScope};
+use crate::fs::filter_class::{self, FilterClass};
+use crate::fs::location;
+use crate::fs::name_check::{NameCheck, Siblings};
+use crate::fs::operations::{self, DiskUsage};
+use crate::fs::overwrite::{self, Backup, BackupTo, Collision, ConflictChoice};
+use crate::fs::places::{self, MountSource, Place, PlaceStatus};
+use crate::fs::portability::{self, Report as PortabilityReport};
+use crate::fs::retry::{self, RetryOp, RetryState};
+use crate::fs::scaffold::{self, Scaffold};
+use crate::fs::send_to::{self, SendToTarget};
+use crate::fs::sequence::{self, SequenceSpec, SortKey};
+use crate::fs::trash::{self, Trash, TrashedEntry};
+#[cfg(feature = "editor")]
+use crate::fs::tree::SpecialFile;
+use crate::fs::tree::{NodeType, TreeState, TreeStep};
+use crate::fs::undo_check::{self, Drift, StampedPath, UndoConflict};
+use crate::fs::walk::WalkOptions;
+use crate::fs::watcher::{
+    self as watcher, EventBatch, FsEvent, SystemWatcherFactory, WatchMode, WatcherState,
+    WatcherSupervisor,
+};
+use crate::git_status::{self, GitStatusResult, GitStatusState};
+use crate::grep::{self, GrepBatch, GrepOptions, GrepState};
+use crate::idle_refresh::{self, IdleRefreshResult, IdleRefreshState};
+use crate::in_flight::{Admission, InFlightOps, PendingTransfer, WhenBusy};
+use crate::key_repeat::KeyRepeat;
+use crate::open_action::{self, Launcher, OpenAction, OpenRules, SystemLauncher};
+use crate::paste_guard::{self, PasteGuard};
+use crate::path_display::{self, PathStyle};
+use crate::path_index::{self, Coverage, IndexBatch, IndexOptions, PathIndex};
+use crate::pattern::Pattern;
+use crate::permissions::{self, ModeChange, PermissionsField, PermissionsForm};
+use crate::prefetch::{self, PrefetchResult, PrefetchState};
+use crate::preview_cache::{CacheKey, PreviewCache};
 use crate::preview_content;
+use crate::preview_load::{
+    KeptView, LoadPoll, LoadRequest, Loaded, LoadedContent, PreviewLoadState, PreviewLoader,
+    SystemLoader,
+};
+use crate::preview_search::{self, PreviewSearch};
+use crate::profile;
+use crate::recent_ops::{CompletedOp, RecentOps};
+use crate::refresh_defer::{DeferChange, DeferState};
+use crate::session::{self, AutosaveState, SessionSnapshot};
+use crate::stat_service::{self, StatBatch, StatSource, StatState, SystemStat};
+use crate::syntax::{self, LazySyntax};
+use crate::system_clipboard::{
+    self, ClipboardProvider, ClipboardReport, CommandClipboard, Selection,
+};
+use crate::tabs::{TabState, Tabs};
+use crate::tasks::{Priority, TaskCounters, TaskId, TaskInfo, TaskKind, TaskManager, TaskToken};
+use crate::template;
 use crate::terminal::TerminalState;
+use crate::text;
 use crate::theme::{self, ThemeColors};
+use crate::title::{self, TitleState};
+use crate::whitespace::FinalNewline;
+use crate::work_queue::{Slice, ViewRestore, WorkId, WorkKind, WorkQueue};
 
 /// The kind of dialog being displayed.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -32,17 +105,348 @@ pub enum DialogKind {
     },
     DeleteConfirm {
         targets: Vec<PathBuf>,
+        /// Combined size of the targets, counted in the background when
+        /// they include directories.
+        sizing: Sizing,
+        /// First target shown in the list.
+        scroll: usize,
+        /// Move the targets to the trash rather than removing them.
+        to_trash: bool,
+    },
+    /// A paste of directories, open while they are counted and kept for a
+    /// second keystroke when the paste turns out large.
+    TransferConfirm {
+        items: usize,
+        was_cut: bool,
+        sizing: Sizing,
     },
     Error {
         message: String,
     },
+    /// A running copy, move or delete. Copies also count bytes:
+    /// `bytes_total` is 0 when unknown, and `rate` is the average
+    /// throughput in bytes per second since `started`, once there is one.
     Progress {
         message: String,
         current: usize,
-        total: usize,
+        /// `None` while the total isn't known (a spinner replaces the bar).
+        total: Option<usize>,
+        bytes_done: u64,
+        bytes_total: u64,
+        started: Instant,
+        rate: Option<u64>,
+        /// Spinner frame, advanced on every tick.
+        spin: usize,
     },
     /// Save confirmation when exiting edit mode with unsaved changes.
     SaveConfirm,
+    /// Review of items that changed since the operation being undone.
+    UndoConfirm {
+        conflicts: Vec<UndoConflict>,
+        selected: usize,
+        /// The undo was recorded before the last restart.
+        from_previous_session: bool,
+    },
+    /// Renumber several files as `<base><NNN>.<ext>`.
+    SequenceRename {
+        form: SequenceForm,
+    },
+    /// Pasted items whose names the destination already has, asked about
+    /// one at a time: overwrite, keep both or skip.
+    PasteConflict {
+        collisions: Vec<Collision>,
+        /// The collision being asked about.
+        current: usize,
+        /// Where overwritten files are backed up, if anywhere.
+        backup: Option<BackupTo>,
+    },
+    /// A shell is already running: `cd` it to `dir` or restart it there.
+    TerminalHere {
+        dir: PathBuf,
+    },
+    /// Export the tree as text or JSON to a file or the clipboard.
+    ExportTree {
+        form: ExportForm,
+    },
+    /// Prompt for a `path[:line[:col]]` to jump to.
+    GotoPath,
+    /// Prompt for the file to export the settings profile to.
+    ExportProfile,
+    /// Why the filesystem watcher failed or is partial, with retry and
+    /// polling options.
+    WatcherDetails {
+        lines: Vec<String>,
+    },
+    /// Names in a directory that break on Windows or macOS, with suggested
+    /// renames.
+    Portability {
+        report: PortabilityReport,
+        selected: usize,
+    },
+    /// Recently completed copy/move operations, newest first, with the
+    /// selected one's details when `expanded`.
+    RecentOperations {
+        ops: Vec<CompletedOp>,
+        /// Operation Ctrl+Z would reverse, when it is one of `ops`.
+        undoable: Option<u64>,
+        selected: usize,
+        expanded: bool,
+    },
+    /// Running and waiting background tasks, refreshed every tick.
+    Tasks {
+        tasks: Vec<TaskInfo>,
+        selected: usize,
+    },
+    /// Scaffolds to instantiate into `dest`.
+    ScaffoldPicker {
+        scaffolds: Vec<Scaffold>,
+        selected: usize,
+        dest: PathBuf,
+    },
+    /// Values for the chosen scaffold's variables.
+    ScaffoldForm {
+        form: Box<ScaffoldForm>,
+    },
+    /// A rename or delete failed because another program has the item
+    /// open: retry now, retry automatically, or skip. `attempt` is the
+    /// attempt and the most there will be while a retry runs.
+    BusyRetry {
+        op: RetryOp,
+        message: String,
+        attempt: Option<(usize, usize)>,
+    },
+    /// What the filesystem records about `path` (`i`).
+    Info {
+        path: PathBuf,
+        info: Box<FileInfo>,
+    },
+    /// New permission bits for the selected items (`M`).
+    Permissions {
+        form: PermissionsForm,
+    },
+    /// Rename the multi-selected items through one pattern (`r`).
+    BulkRename {
+        form: BulkRenameForm,
+    },
+}
+
+impl DialogKind {
+    /// Whether the dialog is a one-line text prompt (`dialog_state.input`).
+    pub fn takes_text(&self) -> bool {
+        self.takes_name() || matches!(self, DialogKind::GotoPath | DialogKind::ExportProfile)
+    }
+
+    /// Whether the dialog's input is a single file name, held to
+    /// `paste_guard::NAME_MAX` bytes.
+    pub fn takes_name(&self) -> bool {
+        matches!(
+            self,
+            DialogKind::CreateFile | DialogKind::CreateDirectory | DialogKind::Rename { .. }
+        )
+    }
+
+    /// Title naming the dialog and, for prompts, the step being answered
+    /// and its position ("Delete 3 items — confirm 1/1"), so the title
+    /// alone tells a screen reader where input goes.
+    pub fn title(&self) -> String {
+        fn step(base: &str, step: &str, position: usize, total: usize) -> String {
+            format!("{} — {} {}/{}", base, step, position, total)
+        }
+        fn items(n: usize) -> String {
+            format!("{} item{}", n, if n == 1 { "" } else { "s" })
+        }
+        match self {
+            DialogKind::CreateFile => step("Create New File", "name", 1, 1),
+            DialogKind::CreateDirectory => step("Create New Directory", "name", 1, 1),
+            DialogKind::Rename { .. } => step("Rename", "name", 1, 1),
+            DialogKind::GotoPath => step("Go to path[:line[:col]]", "path", 1, 1),
+            DialogKind::ExportProfile => step("Export settings profile", "file", 1, 1),
+            DialogKind::DeleteConfirm {
+                targets,
+                sizing,
+                to_trash,
+                ..
+            } => {
+                let (position, total) = sizing.step();
+                let title = if *to_trash {
+                    format!("Move {} to Trash", items(targets.len()))
+                } else {
+                    format!("Delete {} permanently", items(targets.len()))
+                };
+                step(&title, "confirm", position, total)
+            }
+            DialogKind::TransferConfirm {
+                items: count,
+                was_cut,
+                sizing,
+            } => {
+                let verb = if *was_cut { "Move" } else { "Copy" };
+                let (position, total) = sizing.step();
+                step(
+                    &format!("{} {}", verb, items(*count)),
+                    "confirm",
+                    position,
+                    total,
+                )
+            }
+            DialogKind::Error { .. } => "Error".to_string(),
+            DialogKind::Progress {
+                current,
+                total: None,
+                ..
+            } => format!("Processing {}", current),
+            DialogKind::Progress {
+                current,
+                total: Some(total),
+                ..
+            } => format!("Processing {}/{}", current, total),
+            DialogKind::SaveConfirm => step("Unsaved Changes", "save", 1, 1),
+            DialogKind::UndoConfirm {
+                conflicts,
+                selected,
+                ..
+            } => step(
+                "Undo: Changes Detected",
+                "review",
+                selected + 1,
+                conflicts.len().max(1),
+            ),
+            DialogKind::SequenceRename { form } => {
+                let (name, position) = form.field.step();
+                step(
+                    &format!("Sequence Rename ({})", items(form.sources.len())),
+                    name,
+                    position,
+                    4,
+                )
+            }
+            DialogKind::PasteConflict {
+                collisions,
+                current,
+                ..
+            } => step("Already Exists", "choose", current + 1, collisions.len()),
+            DialogKind::TerminalHere { .. } => step("Open Terminal Here", "choose", 1, 1),
+            DialogKind::ExportTree { form } => {
+                let (name, position) = form.field.step();
+                step("Export Tree", name, position, 6)
+            }
+            DialogKind::WatcherDetails { .. } => "Filesystem Watcher".to_string(),
+            DialogKind::Portability { report, selected } => {
+                let name = report
+                    .root
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_else(|| report.root.display().to_string());
+                let problems = report.findings.len();
+                step(
+                    &format!(
+                        "Portability: {} ({} problem{})",
+                        name,
+                        problems,
+                        if problems == 1 { "" } else { "s" }
+                    ),
+                    "review",
+                    selected + 1,
+                    problems.max(1),
+                )
+            }
+            DialogKind::RecentOperations { ops, selected, .. } => step(
+                "Recent Operations",
+                "review",
+                selected + 1,
+                ops.len().max(1),
+            ),
+            DialogKind::Tasks { tasks, selected } => step(
+                &format!("Tasks ({})", tasks.len()),
+                "review",
+                (selected + 1).min(tasks.len()),
+                tasks.len(),
+            ),
+            DialogKind::ScaffoldPicker {
+                scaffolds,
+                selected,
+                ..
+            } => step(
+                "New from Scaffold",
+                "choose",
+                selected + 1,
+                scaffolds.len().max(1),
+            ),
+            DialogKind::ScaffoldForm { form } => {
+                let (name, position, total) = form.step();
+                step(
+                    &format!("Scaffold: {}", form.scaffold.name()),
+                    name,
+                    position,
+                    total,
+                )
+            }
+            DialogKind::BusyRetry { op, .. } => {
+                step(&format!("{}: In Use", op.describe()), "choose", 1, 1)
+            }
+            DialogKind::Info { path, .. } => format!("Info: {}", dir_label(path)),
+            DialogKind::Permissions { form } => {
+                let base = match form.paths.as_slice() {
+                    [path] => format!("Permissions: {}", dir_label(path)),
+                    paths => format!("Permissions ({})", items(paths.len())),
+                };
+                let (name, position) = match form.field {
+                    PermissionsField::Grid => ("bits", 1),
+                    PermissionsField::Octal => ("octal", 2),
+                };
+                step(&base, name, position, 2)
+            }
+            DialogKind::BulkRename { form } => step(
+                &format!("Bulk Rename ({})", items(form.sources.len())),
+                "pattern",
+                1,
+                1,
+            ),
+        }
+    }
+}
+
+/// What is known about the size of a paste or delete awaiting
+/// confirmation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sizing {
+    /// The pre-flight count is still running; `started` drives the spinner.
+    Counting { started: Instant },
+    /// Counted. A `large` operation takes `y` and then Enter; `armed` is
+    /// set by the `y`.
+    Counted {
+        usage: DiskUsage,
+        large: bool,
+        armed: bool,
+    },
+}
+
+impl Sizing {
+    /// Confirmation step and step count: large operations take two.
+    pub fn step(self) -> (usize, usize) {
+        match self {
+            Sizing::Counted {
+                large: true, armed, ..
+            } => (1 + usize::from(armed), 2),
+            _ => (1, 1),
+        }
+    }
+
+    /// Entries to count progress in, when the count finished.
+    pub fn entries(self) -> Option<usize> {
+        match self {
+            Sizing::Counted { usage, .. } if usage.complete => Some(usage.entries),
+            _ => None,
+        }
+    }
+
+    /// Bytes to measure progress against, when the count finished.
+    pub fn bytes(self) -> Option<u64> {
+        match self {
+            Sizing::Counted { usage, .. } if usage.complete => Some(usage.bytes),
+            _ => None,
+        }
+    }
 }
 
 /// Which panel currently has focus.
@@ -55,6 +459,17 @@ pub enum FocusedPanel {
     Terminal,
 }
 
+impl FocusedPanel {
+    /// Name shown in the panel title and the status bar.
+    pub fn label(self) -> &'static str {
+        match self {
+            FocusedPanel::Tree => "Tree",
+            FocusedPanel::Preview => "Preview",
+            FocusedPanel::Terminal => "Terminal",
+        }
+    }
+}
+
 /// View mode for large-file head+tail preview.
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 #[allow(dead_code)]
@@ -75,6 +490,8 @@ pub struct PreviewState {
     pub content_lines: Vec<Line<'static>>,
     /// Vertical scroll offset (line index of topmost visible line).
     pub scroll_offset: usize,
+    /// Horizontal scroll offset in display columns.
+    pub h_offset: usize,
     /// Current view mode for large files.
     pub view_mode: ViewMode,
     /// Whether long lines wrap.
@@ -87,6 +504,29 @@ pub struct PreviewState {
     pub head_lines: usize,
     /// Number of tail lines to show in head+tail mode.
     pub tail_lines: usize,
+    /// Tab width chosen for this file with `t`; `None` uses
+    /// `preview.tab_width`.
+    pub tab_width: Option<usize>,
+    /// How the previewed text file ends; `None` for directories, binaries
+    /// and notebooks.
+    pub final_newline: Option<FinalNewline>,
+    /// Plain text of each row of `content_lines`, built for the preview
+    /// search; emptied wherever the rows are replaced.
+    pub text: Vec<String>,
+}
+
+impl PreviewState {
+    /// Row showing 1-based file `line`. Rows only differ from lines below
+    /// a front matter card, which stands in for the lines of its block
+    /// (`total_lines` counts file lines, `content_lines` the rows); lines
+    /// inside the block land on the card.
+    pub fn row_for_line(&self, line: usize) -> usize {
+        let rows = self.content_lines.len();
+        let total = self.total_lines.max(1);
+        (line.saturating_sub(1) + rows)
+            .saturating_sub(total)
+            .min(rows.saturating_sub(1))
+    }
 }
 
 /// A single fuzzy search result.
@@ -114,8 +554,41 @@ pub struct SearchState {
     pub results: Vec<SearchResult>,
     /// Currently selected result index.
     pub selected_index: usize,
-    /// Cached file path index (lazily built, invalidated on tree mutations).
+    /// Cached file path index: built in the background, updated in place
+    /// by watcher events, invalidated on tree mutations.
     pub cached_paths: Option<Vec<PathBuf>>,
+    /// How much of the tree `cached_paths` covers.
+    pub coverage: Coverage,
+    /// Background index streaming into `cached_paths`.
+    pub indexing: Option<Indexing>,
+    /// Job of the newest background index; batches of older ones are
+    /// dropped.
+    pub index_job: u64,
+    /// Re-index with the raised cap in flight (Ctrl+R).
+    pub reindex: Option<Reindex>,
+    /// Line and column from a `name:line[:col]` query, matched separately.
+    pub target_line: Option<usize>,
+    pub target_col: Option<usize>,
+}
+
+/// A background build of the fuzzy finder's index.
+#[derive(Debug)]
+pub struct Indexing {
+    pub job: u64,
+    pub root: PathBuf,
+    /// For the spinner.
+    pub started: Instant,
+    /// Entries seen so far.
+    pub seen: usize,
+    pub cancel: Arc<AtomicBool>,
+}
+
+/// A re-index of the fuzzy finder with the cap raised.
+#[derive(Debug)]
+pub struct Reindex {
+    /// For the spinner.
+    pub started: Instant,
+    pub cancel: Arc<AtomicBool>,
 }
 
 /// State for the search action menu overlay.
@@ -129,6 +602,10 @@ pub struct SearchActionState {
     pub is_directory: bool,
     /// Whether the target is a binary file.
     pub is_binary: bool,
+    /// Line (and column) to land on, from a `name:line[:col]` query.
+    pub line: Option<usize>,
+    #[cfg_attr(not(feature = "editor"), allow(dead_code))]
+    pub col: Option<usize>,
 }
 
 /// Application mode.
@@ -139,9 +616,73 @@ pub enum AppMode {
     Dialog(DialogKind),
     Search,
     SearchAction,
+    Grep,
     Filter,
     Help,
     Edit,
+    SendTo,
+    Places,
+    Bookmarks,
+    PreviewSearch,
+}
+
+impl AppMode {
+    /// Upper-case mode name for the status bar ("NORMAL", "SEARCH").
+    pub fn label(&self) -> &'static str {
+        match self {
+            AppMode::Normal => "NORMAL",
+            AppMode::Dialog(_) => "DIALOG",
+            AppMode::Search => "SEARCH",
+            AppMode::SearchAction => "SEARCH ACTIONS",
+            AppMode::Grep => "GREP",
+            AppMode::Filter => "FILTER",
+            AppMode::Help => "HELP",
+            AppMode::Edit => "EDIT",
+            AppMode::SendTo => "SEND TO",
+            AppMode::Places => "PLACES",
+            AppMode::Bookmarks => "BOOKMARKS",
+            AppMode::PreviewSearch => "FIND",
+        }
+    }
+}
+
+/// State for the "send to" overlay.
+#[derive(Debug, Default)]
+pub struct SendToState {
+    /// Configured targets with their last checked status.
+    pub targets: Vec<SendToTarget>,
+    /// Selected row; `targets.len()` is the "add current directory" entry.
+    pub selected_index: usize,
+}
+
+impl SendToState {
+    /// Whether the "add current directory as target" row is selected.
+    pub fn add_entry_selected(&self) -> bool {
+        self.selected_index == self.targets.len()
+    }
+}
+
+/// State for the places overlay.
+#[derive(Debug, Default)]
+pub struct PlacesState {
+    /// Home, user directories, root, volumes and bookmarks, in display order.
+    pub places: Vec<Place>,
+    pub selected_index: usize,
+}
+
+/// A row of the bookmarks overlay.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BookmarkEntry {
+    pub path: PathBuf,
+    /// Checked when the overlay opens; missing ones are greyed out.
+    pub status: PlaceStatus,
+}
+
+/// State for the bookmarks overlay.
+#[derive(Debug, Default)]
+pub struct BookmarksState {
+    pub entries: Vec<BookmarkEntry>,
+    pub selected_index: usize,
 }
 
 /// State for a dialog's text input.
@@ -150,17 +691,649 @@ pub enum AppMode {
 pub struct DialogState {
     pub input: String,
     pub cursor_position: usize,
+    /// What was removed from pasted text, shown above the input.
+    pub warning: Option<String>,
+    /// Entries next to the item a Rename dialog names, read on opening.
+    pub siblings: Option<Siblings>,
+    /// Problems with the name typed so far, and a fix to offer on Tab.
+    pub name_check: Option<NameCheck>,
+}
+
+/// Field focused in the sequence rename dialog.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SequenceField {
+    #[default]
+    Base,
+    Start,
+    Width,
+    Sort,
+}
+
+impl SequenceField {
+    fn next(self) -> Self {
+        match self {
+            SequenceField::Base => SequenceField::Start,
+            SequenceField::Start => SequenceField::Width,
+            SequenceField::Width => SequenceField::Sort,
+            SequenceField::Sort => SequenceField::Base,
+        }
+    }
+
+    fn prev(self) -> Self {
+        match self {
+            SequenceField::Base => SequenceField::Sort,
+            SequenceField::Start => SequenceField::Base,
+            SequenceField::Width => SequenceField::Start,
+            SequenceField::Sort => SequenceField::Width,
+        }
+    }
+
+    /// Step name and 1-based position, for the dialog title.
+    fn step(self) -> (&'static str, usize) {
+        match self {
+            SequenceField::Base => ("base name", 1),
+            SequenceField::Start => ("start", 2),
+            SequenceField::Width => ("width", 3),
+            SequenceField::Sort => ("sort", 4),
+        }
+    }
+}
+
+/// Largest count prefix accepted (`99999j` moves 9999 rows).
+pub const MAX_COUNT: usize = 9999;
+
+/// Rows of targets shown at once in the delete confirmation.
+pub const DELETE_LIST_ROWS: usize = 10;
+
+/// Entries walked to size a delete before showing a lower bound.
+const DELETE_USAGE_MAX_ENTRIES: usize = 50_000;
+
+/// Entries a portability check walks before reporting partial results.
+const PORTABILITY_MAX_ENTRIES: usize = 200_000;
+
+/// Entries processed between progress updates when progress counts
+/// entries rather than top-level items.
+const PROGRESS_STRIDE: usize = 64;
+
+/// Bytes copied between progress updates.
+const PROGRESS_BYTES: u64 = 4 * 1024 * 1024;
+
+/// Time a copy runs before its throughput is shown; earlier figures are
+/// mostly noise from the page cache.
+const THROUGHPUT_MIN_ELAPSED: Duration = Duration::from_millis(500);
+
+/// Time in which a second `q` quits while a file operation is running.
+pub const QUIT_CONFIRM_WINDOW: Duration = Duration::from_secs(3);
+
+/// Columns moved per horizontal preview scroll step.
+const PREVIEW_H_SCROLL_STEP: usize = 4;
+
+/// Number of renames shown at each end of the sequence rename preview.
+const SEQUENCE_PREVIEW_EDGE: usize = 3;
+
+/// Largest accepted zero-padding width.
+const SEQUENCE_MAX_WIDTH: usize = 12;
+
+/// Most directories a batch of watcher events reloads one by one; beyond
+/// this the whole tree is refreshed once the changes settle.
+pub const MAX_DIRS_RELOADED_PER_FRAME: usize = 16;
+
+/// Input state of the sequence rename dialog.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SequenceForm {
+    /// Files to renumber.
+    pub sources: Vec<PathBuf>,
+    pub base: String,
+    pub start: String,
+    pub width: String,
+    pub sort: SortKey,
+    pub field: SequenceField,
+    /// Preview lines for the current input, or why it can't be applied.
+    pub preview: std::result::Result<Vec<String>, String>,
+}
+
+impl SequenceForm {
+    /// New form; the base name defaults to the first file's stem without
+    /// its trailing digits (`IMG_2031.jpg` → `IMG_`).
+    pub fn new(sources: Vec<PathBuf>) -> Self {
+        let base = sequence::order_sources(&sources, SortKey::Name)
+            .first()
+            .and_then(|p| p.file_stem())
+            .map(|stem| {
+                stem.to_string_lossy()
+                    .trim_end_matches(|c: char| c.is_ascii_digit())
+                    .to_string()
+            })
+            .unwrap_or_default();
+        let mut form = Self {
+            sources,
+            base,
+            start: "1".to_string(),
+            width: "3".to_string(),
+            sort: SortKey::Name,
+            field: SequenceField::Base,
+            preview: Ok(Vec::new()),
+        };
+        form.refresh();
+        form
+    }
+
+    /// Parse the numeric fields into a spec.
+    pub fn spec(&self) -> std::result::Result<SequenceSpec, String> {
+        let start = self
+            .start
+            .parse::<usize>()
+            .map_err(|_| "start index must be a number".to_string())?;
+        let width = self
+            .width
+            .parse::<usize>()
+            .map_err(|_| "padding width must be a number".to_string())?;
+        if width > SEQUENCE_MAX_WIDTH {
+            return Err(format!("padding width is at most {}", SEQUENCE_MAX_WIDTH));
+        }
+        Ok(SequenceSpec {
+            base: self.base.clone(),
+            start,
+            width,
+            sort: self.sort,
+        })
+    }
+
+    /// Plan the renames for the current input.
+    pub fn plan(&self) -> std::result::Result<Vec<(PathBuf, PathBuf)>, String> {
+        let spec = self.spec()?;
+        sequence::plan(&self.sources, &spec).map_err(|e| e.to_string())
+    }
+
+    /// Recompute the live preview.
+    pub fn refresh(&mut self) {
+        self.preview = self
+            .plan()
+            .map(|pairs| sequence::preview(&pairs, SEQUENCE_PREVIEW_EDGE));
+    }
+
+    /// Type into the focused field. Numeric fields accept digits only;
+    /// on the sort field any key toggles name/mtime.
+    pub fn input_char(&mut self, c: char) {
+        match self.field {
+            SequenceField::Base => self.base.push(c),
+            SequenceField::Start if c.is_ascii_digit() => self.start.push(c),
+            SequenceField::Width if c.is_ascii_digit() => self.width.push(c),
+            SequenceField::Sort => self.sort = self.sort.toggle(),
+            _ => return,
+        }
+        self.refresh();
+    }
+
+    /// Delete the last character of the focused field.
+    pub fn delete_char(&mut self) {
+        match self.field {
+            SequenceField::Base => {
+                self.base.pop();
+            }
+            SequenceField::Start => {
+                self.start.pop();
+            }
+            SequenceField::Width => {
+                self.width.pop();
+            }
+            SequenceField::Sort => return,
+        }
+        self.refresh();
+    }
+
+    pub fn next_field(&mut self) {
+        self.field = self.field.next();
+    }
+
+    pub fn prev_field(&mut self) {
+        self.field = self.field.prev();
+    }
+}
+
+/// Field focused in the tree export dialog.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ExportField {
+    #[default]
+    Path,
+    Format,
+    Scope,
+    Sizes,
+    Hidden,
+    LoadMore,
+}
+
+impl ExportField {
+    fn next(self) -> Self {
+        match self {
+            ExportField::Path => ExportField::Format,
+            ExportField::Format => ExportField::Scope,
+            ExportField::Scope => ExportField::Sizes,
+            ExportField::Sizes => ExportField::Hidden,
+            ExportField::Hidden => ExportField::LoadMore,
+            ExportField::LoadMore => ExportField::Path,
+        }
+    }
+
+    fn prev(self) -> Self {
+        match self {
+            ExportField::Path => ExportField::LoadMore,
+            ExportField::Format => ExportField::Path,
+            ExportField::Scope => ExportField::Format,
+            ExportField::Sizes => ExportField::Scope,
+            ExportField::Hidden => ExportField::Sizes,
+            ExportField::LoadMore => ExportField::Hidden,
+        }
+    }
+
+    /// Step name and 1-based position, for the dialog title.
+    fn step(self) -> (&'static str, usize) {
+        match self {
+            ExportField::Path => ("path", 1),
+            ExportField::Format => ("format", 2),
+            ExportField::Scope => ("rows", 3),
+            ExportField::Sizes => ("sizes", 4),
+            ExportField::Hidden => ("hidden", 5),
+            ExportField::LoadMore => ("load more", 6),
+        }
+    }
+}
+
+/// Input state of the tree export dialog. An empty path exports to the
+/// system clipboard.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportForm {
+    pub path: String,
+    pub options: ExportOptions,
+    pub field: ExportField,
+}
+
+impl ExportForm {
+    /// New form targeting `tree.txt` in `dir`.
+    pub fn new(dir: &Path) -> Self {
+        let options = ExportOptions::default();
+        Self {
+            path: dir
+                .join(format!("tree.{}", options.format.extension()))
+                .to_string_lossy()
+                .to_string(),
+            options,
+            field: ExportField::Path,
+        }
+    }
+
+    /// Type into the focused field; on the option fields any key toggles.
+    /// Switching the format also swaps a matching file extension.
+    pub fn input_char(&mut self, c: char) {
+        let options = &mut self.options;
+        match self.field {
+            ExportField::Path => self.path.push(c),
+            ExportField::Format => {
+                let old = format!(".{}", options.format.extension());
+                options.format = options.format.toggle();
+                if let Some(stem) = self.path.strip_suffix(&old) {
+                    self.path = format!("{}.{}", stem, options.format.extension());
+                }
+            }
+            ExportField::Scope => options.scope = options.scope.toggle(),
+            ExportField::Sizes => options.sizes = !options.sizes,
+            ExportField::Hidden => options.hidden_markers = !options.hidden_markers,
+            ExportField::LoadMore => options.load_more = !options.load_more,
+        }
+    }
+
+    /// Delete the last character of the path.
+    pub fn delete_char(&mut self) {
+        if self.field == ExportField::Path {
+            self.path.pop();
+        }
+    }
+
+    pub fn next_field(&mut self) {
+        self.field = self.field.next();
+    }
+
+    pub fn prev_field(&mut self) {
+        self.field = self.field.prev();
+    }
+}
+
+/// Input state of the scaffold dialog: a value per variable, prefilled
+/// with the defaults.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScaffoldForm {
+    pub scaffold: Scaffold,
+    /// Directory the scaffold is instantiated into.
+    pub dest: PathBuf,
+    pub variables: Vec<scaffold::Variable>,
+    pub values: Vec<String>,
+    /// Index of the focused variable.
+    pub field: usize,
+    /// Targets found to exist by the last Enter; shown with the option to
+    /// overwrite them, and cleared by any edit.
+    pub conflicts: Vec<PathBuf>,
+    /// Why the last Enter couldn't plan the scaffold.
+    pub error: Option<String>,
+}
+
+impl ScaffoldForm {
+    pub fn new(scaffold: Scaffold, dest: PathBuf, variables: Vec<scaffold::Variable>) -> Self {
+        let values = variables.iter().map(|v| v.default.clone()).collect();
+        Self {
+            scaffold,
+            dest,
+            variables,
+            values,
+            field: 0,
+            conflicts: Vec::new(),
+            error: None,
+        }
+    }
+
+    /// The entered values by variable name.
+    pub fn vars(&self) -> template::Vars {
+        self.variables
+            .iter()
+            .map(|v| v.name.clone())
+            .zip(self.values.iter().cloned())
+            .collect()
+    }
+
+    /// Step name and 1-based position, for the dialog title.
+    fn step(&self) -> (&str, usize, usize) {
+        match self.variables.get(self.field) {
+            Some(variable) => (&variable.name, self.field + 1, self.variables.len()),
+            None => ("confirm", 1, 1),
+        }
+    }
+
+    pub fn input_char(&mut self, c: char) {
+        if let Some(value) = self.values.get_mut(self.field) {
+            value.push(c);
+            self.edited();
+        }
+    }
+
+    pub fn delete_char(&mut self) {
+        if let Some(value) = self.values.get_mut(self.field) {
+            value.pop();
+            self.edited();
+        }
+    }
+
+    pub fn next_field(&mut self) {
+        if !self.values.is_empty() {
+            self.field = (self.field + 1) % self.values.len();
+        }
+    }
+
+    pub fn prev_field(&mut self) {
+        if !self.values.is_empty() {
+            self.field = (self.field + self.values.len() - 1) % self.values.len();
+        }
+    }
+
+    fn edited(&mut self) {
+        self.conflicts.clear();
+        self.error = None;
+    }
 }
 
 /// A reversible operation that can be undone.
-#[derive(Debug, Clone)]
+///
+/// Paths the undo would touch carry the metadata they had when the operation
+/// finished, so changes made since then can be detected before applying.
+/// Serialized into the session directory so it survives a restart.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
 pub enum UndoAction {
     /// Undo a rename: rename back from `to` to `from`.
-    Rename { from: PathBuf, to: PathBuf },
-    /// Undo a copy-paste: delete the created paths.
-    CopyPaste { created_paths: Vec<PathBuf> },
-    /// Undo a move-paste: move files back from `to` to `from`.
-    MovePaste { moves: Vec<(PathBuf, PathBuf)> },
+    Rename { from: PathBuf, to: StampedPath },
+    /// Undo a copy-paste: delete the created paths, then put back the
+    /// files they overwrote.
+    CopyPaste {
+        created_paths: Vec<StampedPath>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        backups: Vec<Backup>,
+    },
+    /// Undo a move-paste: move files back from `to` to `from`, then put
+    /// back the files they overwrote.
+    MovePaste {
+        moves: Vec<(PathBuf, StampedPath)>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        backups: Vec<Backup>,
+    },
+    /// Undo a sequence rename, a bulk rename or the renames suggested by a
+    /// portability check: restore every `(original, renamed)` pair.
+    SequenceRename {
+        renames: Vec<(PathBuf, StampedPath)>,
+    },
+    /// Undo a move to the trash: put every entry back where it was.
+    Trash { entries: Vec<TrashedEntry> },
+    /// Undo a permissions change: give every item its old mode back.
+    Permissions { changes: Vec<ModeChange> },
+}
+
+impl UndoAction {
+    /// Whether anything the undo would act on still exists, i.e. whether
+    /// it is worth keeping across a restart.
+    pub fn is_actionable(&self) -> bool {
+        let exists = |s: &StampedPath| std::fs::symlink_metadata(&s.path).is_ok();
+        match self {
+            UndoAction::Rename { to, .. } => exists(to),
+            UndoAction::CopyPaste {
+                created_paths,
+                backups,
+            } => created_paths
+                .iter()
+                .chain(backups.iter().map(Backup::kept))
+                .any(exists),
+            UndoAction::MovePaste { moves, backups } => moves
+                .iter()
+                .map(|(_, to)| to)
+                .chain(backups.iter().map(Backup::kept))
+                .any(exists),
+            UndoAction::SequenceRename { renames } => renames.iter().any(|(_, to)| exists(to)),
+            UndoAction::Trash { entries } => entries.iter().any(|e| exists(&e.trashed)),
+            UndoAction::Permissions { changes } => changes
+                .iter()
+                .any(|c| std::fs::symlink_metadata(&c.path).is_ok()),
+        }
+    }
+
+    /// Compare every path the undo would touch against the filesystem.
+    /// Paths in messages are shown relative to `root`.
+    pub fn verify(&self, root: &Path) -> Vec<UndoConflict> {
+        let show = |p: &Path| path_display::relative(p, root);
+        let mut conflicts = Vec::new();
+        match self {
+            UndoAction::Rename { from, to } => {
+                match undo_check::check_stamped(to) {
+                    Some(Drift::Missing) => conflicts.push(UndoConflict::new(
+                        0,
+                        Drift::Missing,
+                        format!("{} no longer exists — skipped", show(&to.path)),
+                    )),
+                    Some(drift) => conflicts.push(UndoConflict::new(
+                        0,
+                        drift,
+                        format!(
+                            "{} was modified after the rename — rename back anyway?",
+                            show(&to.path)
+                        ),
+                    )),
+                    None => {}
+                }
+                if let Some(drift) = undo_check::check_vacant(from) {
+                    conflicts.push(UndoConflict::new(
+                        0,
+                        drift,
+                        format!("{} exists again — overwrite it?", show(from)),
+                    ));
+                }
+            }
+            UndoAction::CopyPaste { created_paths, .. } => {
+                for (i, created) in created_paths.iter().enumerate() {
+                    match undo_check::check_stamped(created) {
+                        Some(Drift::Missing) => conflicts.push(UndoConflict::new(
+                            i,
+                            Drift::Missing,
+                            format!("{} no longer exists — skipped", show(&created.path)),
+                        )),
+                        Some(drift) => conflicts.push(UndoConflict::new(
+                            i,
+                            drift,
+                            format!(
+                                "{} was modified after the copy — delete anyway?",
+                                show(&created.path)
+                            ),
+                        )),
+                        None => {}
+                    }
+                }
+            }
+            UndoAction::MovePaste { moves, .. } => {
+                for (i, (original, current)) in moves.iter().enumerate() {
+                    match undo_check::check_stamped(current) {
+                        Some(Drift::Missing) => conflicts.push(UndoConflict::new(
+                            i,
+                            Drift::Missing,
+                            format!("{} no longer exists — skipped", show(&current.path)),
+                        )),
+                        Some(drift) => conflicts.push(UndoConflict::new(
+                            i,
+                            drift,
+                            format!(
+                                "{} was modified after the move — move back anyway?",
+                                show(&current.path)
+                            ),
+                        )),
+                        None => {}
+                    }
+                    if let Some(drift) = undo_check::check_vacant(original) {
+                        conflicts.push(UndoConflict::new(
+                            i,
+                            drift,
+                            format!("{} was recreated — move back alongside it?", show(original)),
+                        ));
+                    }
+                }
+            }
+            UndoAction::SequenceRename { renames } => {
+                for (i, (original, current)) in renames.iter().enumerate() {
+                    match undo_check::check_stamped(current) {
+                        Some(Drift::Missing) => conflicts.push(UndoConflict::new(
+                            i,
+                            Drift::Missing,
+                            format!("{} no longer exists — skipped", show(&current.path)),
+                        )),
+                        Some(drift) => conflicts.push(UndoConflict::new(
+                            i,
+                            drift,
+                            format!(
+                                "{} was modified after the rename — rename back anyway?",
+                                show(&current.path)
+                            ),
+                        )),
+                        None => {}
+                    }
+                    // Originals are expected to be occupied by renumbered siblings
+                    let freed = renames.iter().any(|(_, c)| &c.path == original);
+                    if !freed {
+                        if let Some(drift) = undo_check::check_vacant(original) {
+                            conflicts.push(UndoConflict::new(
+                                i,
+                                drift,
+                                format!(
+                                    "{} is taken by another file — keep it skipped?",
+                                    show(original)
+                                ),
+                            ));
+                        }
+                    }
+                }
+            }
+            UndoAction::Trash { entries } => {
+                for (i, entry) in entries.iter().enumerate() {
+                    match undo_check::check_stamped(&entry.trashed) {
+                        Some(Drift::Missing) => conflicts.push(UndoConflict::new(
+                            i,
+                            Drift::Missing,
+                            format!(
+                                "{} is no longer in the trash — skipped",
+                                show(&entry.original)
+                            ),
+                        )),
+                        Some(drift) => conflicts.push(UndoConflict::new(
+                            i,
+                            drift,
+                            format!(
+                                "{} was modified in the trash — restore anyway?",
+                                show(&entry.original)
+                            ),
+                        )),
+                        None => {}
+                    }
+                    if let Some(drift) = undo_check::check_vacant(&entry.original) {
+                        conflicts.push(UndoConflict::new(
+                            i,
+                            drift,
+                            format!(
+                                "{} was recreated — restore alongside it?",
+                                show(&entry.original)
+                            ),
+                        ));
+                    }
+                }
+            }
+            UndoAction::Permissions { changes } => {
+                for (i, change) in changes.iter().enumerate() {
+                    match operations::file_mode(&change.path) {
+                        Err(_) => conflicts.push(UndoConflict::new(
+                            i,
+                            Drift::Missing,
+                            format!("{} no longer exists — skipped", show(&change.path)),
+                        )),
+                        Ok(mode) if mode != change.new_mode => conflicts.push(UndoConflict::new(
+                            i,
+                            Drift::Modified,
+                            format!(
+                                "{} is {} now — restore {} anyway?",
+                                show(&change.path),
+                                permissions::octal(mode),
+                                permissions::octal(change.old_mode)
+                            ),
+                        )),
+                        Ok(_) => {}
+                    }
+                }
+            }
+        }
+        conflicts
+    }
+}
+
+/// Approximate memory held by the larger in-memory buffers.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryStats {
+    /// Bytes held by the currently displayed preview lines.
+    pub preview_bytes: usize,
+    /// Bytes held by the preview cache.
+    pub preview_cache_bytes: usize,
+    /// Number of files in the preview cache.
+    pub preview_cache_entries: usize,
+    /// Prefetched previews later served from the cache.
+    pub prefetch_hits: u64,
+    /// Prefetched previews that were gone by the time they were shown.
+    pub prefetch_misses: u64,
+    /// Bytes held by the terminal scrollback buffer.
+    pub scrollback_bytes: usize,
+    /// Lines held in the terminal scrollback buffer.
+    pub scrollback_lines: usize,
+    /// Background task counts.
+    pub tasks: TaskCounters,
+    /// Entries and evictions across the bounded per-path maps.
+    pub maps: MapStats,
 }
 
 /// Main application state.
@@ -171,6 +1344,9 @@ pub struct App {
     pub theme_colors: ThemeColors,
     pub tree_state: TreeState,
     pub should_quit: bool,
+    /// File to open in `$EDITOR` once the current key is handled; the
+    /// main loop suspends the TUI for it.
+    pub external_edit: Option<PathBuf>,
     #[allow(dead_code)]
     pub mode: AppMode,
     #[allow(dead_code)]
@@ -178,97 +1354,364 @@ pub struct App {
     #[allow(dead_code)]
     pub status_message: Option<(String, Instant)>,
     pub preview_state: PreviewState,
+    /// Text search inside the preview (`/` with the preview focused).
+    pub preview_search: PreviewSearch,
     #[allow(dead_code)]
     pub focused_panel: FocusedPanel,
-    pub syntax_set: SyntaxSet,
-    pub syntax_theme: Theme,
+    /// Syntax set and theme, loaded the first time something is highlighted.
+    pub syntax: Arc<LazySyntax>,
     /// Tracks which tree index was last previewed, to avoid re-loading on every frame.
     pub last_previewed_index: Option<usize>,
     /// Internal clipboard for copy/cut/paste operations.
     pub clipboard: ClipboardState,
-    /// Cancellation token for async operations.
-    pub cancel_token: Arc<AtomicBool>,
+    /// Background tasks: copies, deletes, counts, blame, autosave, prefetch.
+    pub tasks: TaskManager,
+    /// Tree sorts, filters and refreshes waiting for their next slice.
+    pub work: WorkQueue,
+    /// Tabs other than the active one, whose tree, preview and focus are
+    /// the fields above.
+    pub tabs: Tabs,
+    /// When `q` was refused because a file operation is running; another
+    /// `q` within [`QUIT_CONFIRM_WINDOW`] quits anyway.
+    pub quit_armed: Option<Instant>,
+    /// Running file operation and transfers queued behind it.
+    pub in_flight: InFlightOps,
+    /// Latest pre-flight count; results of earlier ones are dropped.
+    pub preflight_id: u64,
+    /// Task running the pre-flight count, stopped when its dialog is
+    /// dismissed.
+    pub preflight_task: Option<TaskId>,
+    /// Paste waiting on its conflicts, pre-flight count or second
+    /// confirmation.
+    pub preflight_transfer: Option<PendingTransfer>,
+    /// Latest portability check; results of earlier ones are dropped.
+    pub portability_id: u64,
+    /// Task running the portability check, stopped when another starts.
+    pub portability_task: Option<TaskId>,
     /// Last reversible operation (single-level undo).
     pub last_undo: Option<UndoAction>,
+    /// Recently completed copy/move operations, for the `O` overlay.
+    pub recent_ops: RecentOps,
+    /// Started in safe mode (`--safe-mode`); the status bar shows a badge.
+    pub safe_mode: bool,
+    /// `last_undo` was restored from the previous run.
+    pub undo_from_previous_session: bool,
+    /// Undo awaiting confirmation in the `UndoConfirm` dialog.
+    pub pending_undo: Option<UndoAction>,
     /// State for the fuzzy finder overlay (Ctrl+P).
     pub search_state: SearchState,
+    /// State for the content search overlay (Ctrl+Shift+F).
+    pub grep_state: GrepState,
     /// Fuzzy matcher instance (reused across searches).
     pub fuzzy_matcher: SkimMatcherV2,
     /// Whether the filesystem watcher is currently active.
     pub watcher_active: bool,
+    /// The filesystem watcher and the outcome of its last start.
+    pub watcher: WatcherSupervisor,
+    /// When filesystem changes were last applied to the tree.
+    pub last_refresh: Instant,
+    /// Where the watcher state glyph sits in the tree title (for mouse
+    /// click mapping).
+    pub watcher_glyph_area: Rect,
     /// State for the help overlay.
     pub help_state: HelpState,
     /// Last rendered tree panel area (for mouse click mapping).
     pub tree_area: Rect,
+    /// Tree rows drawn by the last frame, reused while nothing they
+    /// depend on has changed.
+    pub tree_rows: TreeRowCache,
+    /// Status bar drawn by the last frame, reused the same way.
+    pub status_bar: StatusBarCache,
     /// Last rendered preview panel area (for mouse click mapping).
     pub preview_area: Rect,
     /// Embedded terminal state (PTY + emulator).
     pub terminal_state: TerminalState,
     /// Last rendered terminal panel area (for mouse click mapping).
     pub terminal_area: Rect,
+    /// Bumped whenever panel visibility or sizes change (see
+    /// [`App::layout_changed`]).
+    pub layout_generation: u64,
+    /// `layout_generation` the stored panel areas were rendered at; mouse
+    /// events are dropped while it lags behind.
+    pub areas_generation: u64,
+    /// Tree item rows the last frame drew: fewer than the panel holds when
+    /// the list ends above its bottom.
+    pub tree_rows_drawn: usize,
     /// Editor state for the preview panel edit mode.
+    #[cfg(feature = "editor")]
     pub editor_state: Option<EditorState>,
     /// State for the search action menu overlay.
     pub search_action_state: Option<SearchActionState>,
+    /// Recently rendered previews, bounded by `preview.cache_max_bytes`.
+    pub preview_cache: PreviewCache,
+    /// Markdown front matter is previewed as raw YAML instead of a card.
+    pub raw_front_matter: bool,
+    /// Background loads of the previews next to the selection.
+    pub prefetch: PrefetchState,
+    /// Spots pasted key bursts in the one-line inputs.
+    pub paste_guard: PasteGuard,
+    /// Deferred stats of tree rows being fetched in the background.
+    pub stat_state: StatState,
+    /// Where deferred stats come from.
+    pub stat_source: Arc<dyn StatSource>,
+    /// Recursive sizes of directories, and the walk computing one.
+    pub dir_sizes: DirSizes,
+    /// The selected item's preview load, while running or after a cancel.
+    pub preview_load: PreviewLoadState,
+    /// Reads previews on the load thread.
+    pub preview_loader: Arc<dyn PreviewLoader>,
+    /// Whether the debug overlay (memory usage) is shown (F12).
+    pub show_debug_overlay: bool,
+    /// State for the "send to" overlay.
+    pub send_to_state: SendToState,
+    /// State for the places overlay.
+    pub places_state: PlacesState,
+    /// Directories bookmarked with `b`.
+    pub bookmarks: Bookmarks,
+    /// fm's config directory, where bookmarks are saved; `None` keeps
+    /// them in memory.
+    pub config_dir: Option<PathBuf>,
+    /// Where deletes go when `general.use_trash` is on; `None` when the
+    /// platform has no trash, so deletes stay permanent.
+    pub trash: Option<Trash>,
+    /// Retry running for the `BusyRetry` dialog.
+    pub busy_retry: RetryState,
+    /// `--config` file, for exporting the running configuration.
+    pub cli_config_path: Option<PathBuf>,
+    /// State for the bookmarks overlay.
+    pub bookmarks_state: BookmarksState,
+    /// Platform mount listing used by the places overlay.
+    pub mount_source: Box<dyn MountSource>,
+    /// Rows changed by recent FS refreshes: highlighted while they fade,
+    /// then kept as `c` jump targets for `watcher.changed_window_secs`.
+    pub flash: FlashMap,
+    /// Where the last `c` jump landed; the next one continues after it.
+    pub changed_anchor: Option<PathBuf>,
+    /// Preview line highlighted after a `path:line` jump.
+    pub line_flash: Option<LineFlash>,
+    /// Git blame gutter for the preview panel.
+    pub blame: BlameState,
+    /// Git status markers in the tree.
+    pub git_status: GitStatusState,
+    /// Idle clock and in-flight background refreshes of stale directories.
+    pub idle_refresh: IdleRefreshState,
+    /// Refreshes held back while the terminal streams build output.
+    pub refresh_defer: DeferState,
+    /// Watcher events received since the last frame.
+    pub fs_pending: EventBatch,
+    /// While changes touch too many directories to reload one by one: when
+    /// the last of them arrived. The tree is refreshed once they settle.
+    pub fs_storm: Option<Instant>,
+    /// Vim-style count typed before the next motion (`12` of `12j`).
+    pub pending_count: Option<usize>,
+    /// Tree selection before a `g`, put back when a `t` or `T` after it
+    /// makes it `gt`/`gT`.
+    pub pending_g: Option<usize>,
+    /// Run of the latest key, for accelerating held navigation keys.
+    pub key_repeat: KeyRepeat,
+    /// Periodic session autosave for crash recovery.
+    pub autosave: AutosaveState,
+    /// Cursor and scroll of recently edited files, restored on the next edit.
+    pub edit_positions: EditPositions,
+    /// Debounce for the outer terminal's window title.
+    pub title: TitleState,
+    /// Backend for system clipboard / primary selection writes.
+    pub system_clipboard: Arc<dyn ClipboardProvider>,
+    /// OSC 52 copies waiting to be written to the terminal after the next
+    /// frame.
+    pub pending_osc52: Option<String>,
+    /// Compiled `[open]` rules: what Enter does on a file.
+    pub open_rules: OpenRules,
+    /// File classes the filter chip cycles through, from `[filter]`.
+    pub filter_classes: Vec<FilterClass>,
+    /// Spawner for external openers and terminal commands.
+    pub launcher: Box<dyn Launcher>,
+    /// How paths are shown (`general.path_display`, Alt+A cycles).
+    pub path_style: PathStyle,
+    /// Home directory forms shown as `~` (see [`path_display::homes`]).
+    pub homes: Vec<PathBuf>,
 }
 
 impl App {
     /// Create a new App rooted at the given path, using the provided config.
     pub fn new(path: &Path, config: AppConfig) -> Result<Self> {
         let page_size = config.max_entries_per_page();
-        let mut tree_state = TreeState::with_page_size(path, page_size)?;
+        let mount_source = places::system_source();
+        let defer_stats = config.deferred_stats().defers(path, &mount_source.mounts());
+        let mut tree_state = TreeState::with_options(path, page_size, defer_stats)?;
         // Apply config: show_hidden
         tree_state.show_hidden = config.show_hidden();
         // Apply config: sort settings
         tree_state.sort_by = crate::fs::tree::SortBy::from_str(config.sort_by());
         tree_state.dirs_first = config.dirs_first();
+        tree_state.max_depth = config.tree_max_depth();
+        tree_state.set_respect_gitignore(config.respect_gitignore());
+        let capacities = config.map_capacities();
+        tree_state
+            .sort_overrides
+            .set_capacity(capacities.sort_overrides);
         tree_state.sort_all_children();
         tree_state.flatten();
 
-        let syntax_set = SyntaxSet::load_defaults_newlines();
-        let syntax_theme = preview_content::load_theme(Some(config.syntax_theme_name()));
+        let syntax = Arc::new(LazySyntax::new(
+            config.syntax_theme_name(),
+            syntax::user_syntaxes_dir(),
+        ));
         let theme_colors = theme::resolve_theme(&config.theme);
-        Ok(Self {
+        let preview_cache = PreviewCache::new(config.preview_cache_max_bytes());
+        let terminal_state = TerminalState::with_scrollback(config.terminal_scrollback());
+        let (open_rules, open_warnings) = OpenRules::compile(&config.open);
+        let launcher = SystemLauncher::new(config.opener());
+        let git_status = GitStatusState::new(config.git_status());
+        let path_style = PathStyle::from_str(config.path_display());
+        let filter_classes = config.filter_classes();
+        let mut app = Self {
             config,
             theme_colors,
             tree_state,
             should_quit: false,
+            external_edit: None,
             mode: AppMode::Normal,
             dialog_state: DialogState::default(),
             status_message: None,
             preview_state: PreviewState::default(),
+            preview_search: PreviewSearch::default(),
             focused_panel: FocusedPanel::default(),
-            syntax_set,
-            syntax_theme,
+            syntax,
             last_previewed_index: None,
             clipboard: ClipboardState::new(),
-            cancel_token: Arc::new(AtomicBool::new(false)),
+            tasks: TaskManager::default(),
+            work: WorkQueue::default(),
+            tabs: Tabs::default(),
+            quit_armed: None,
+            in_flight: InFlightOps::default(),
+            preflight_id: 0,
+            preflight_task: None,
+            preflight_transfer: None,
+            portability_id: 0,
+            portability_task: None,
             last_undo: None,
+            recent_ops: RecentOps::default(),
+            safe_mode: false,
+            undo_from_previous_session: false,
+            pending_undo: None,
             search_state: SearchState::default(),
+            grep_state: GrepState::default(),
             fuzzy_matcher: SkimMatcherV2::default(),
             watcher_active: true,
+            watcher: WatcherSupervisor::new(Box::new(SystemWatcherFactory)),
+            last_refresh: Instant::now(),
+            watcher_glyph_area: Rect::default(),
             help_state: HelpState::default(),
             tree_area: Rect::default(),
+            tree_rows: TreeRowCache::default(),
+            status_bar: StatusBarCache::default(),
             preview_area: Rect::default(),
-            terminal_state: TerminalState::default(),
+            terminal_state,
             terminal_area: Rect::default(),
+            layout_generation: 0,
+            areas_generation: 0,
+            tree_rows_drawn: 0,
+            #[cfg(feature = "editor")]
             editor_state: None,
             search_action_state: None,
-        })
+            preview_cache,
+            raw_front_matter: false,
+            prefetch: PrefetchState::with_capacity(capacities.prefetch),
+            paste_guard: PasteGuard::default(),
+            stat_state: StatState::default(),
+            stat_source: Arc::new(SystemStat),
+            dir_sizes: DirSizes::with_capacity(capacities.dir_sizes),
+            preview_load: PreviewLoadState::default(),
+            preview_loader: Arc::new(SystemLoader),
+            show_debug_overlay: false,
+            send_to_state: SendToState::default(),
+            places_state: PlacesState::default(),
+            bookmarks: Bookmarks::default(),
+            config_dir: None,
+            trash: Trash::system(),
+            busy_retry: RetryState::default(),
+            cli_config_path: None,
+            bookmarks_state: BookmarksState::default(),
+            mount_source,
+            flash: FlashMap::with_capacity(capacities.flash),
+            changed_anchor: None,
+            line_flash: None,
+            blame: BlameState::with_capacity(capacities.blame_heads),
+            git_status,
+            idle_refresh: IdleRefreshState::default(),
+            refresh_defer: DeferState::default(),
+            fs_pending: EventBatch::default(),
+            fs_storm: None,
+            pending_count: None,
+            pending_g: None,
+            key_repeat: KeyRepeat::default(),
+            autosave: AutosaveState::default(),
+            edit_positions: EditPositions::default(),
+            title: TitleState::default(),
+            system_clipboard: Arc::new(CommandClipboard),
+            pending_osc52: None,
+            open_rules,
+            filter_classes,
+            launcher: Box::new(launcher),
+            path_style,
+            homes: path_display::homes(),
+        };
+        if let Some(first) = open_warnings.first() {
+            let more = open_warnings.len() - 1;
+            app.set_status_message(if more > 0 {
+                format!("⚠ [open] {} (+{} more)", first, more)
+            } else {
+                format!("⚠ [open] {}", first)
+            });
+        }
+        Ok(app)
     }
 
     /// Open a dialog of the given kind.
     #[allow(dead_code)]
     pub fn open_dialog(&mut self, kind: DialogKind) {
         self.dialog_state = DialogState::default();
+        self.paste_guard = PasteGuard::default();
         if let DialogKind::Rename { ref original } = kind {
             if let Some(name) = original.file_name() {
                 let name = name.to_string_lossy().to_string();
                 self.dialog_state.cursor_position = name.len();
                 self.dialog_state.input = name;
             }
+            if let Some(parent) = original.parent() {
+                self.dialog_state.siblings = Some(Siblings::read(parent));
+            }
         }
         self.mode = AppMode::Dialog(kind);
+        self.refresh_name_check();
+    }
+
+    /// Check the name typed in a Rename dialog against its siblings.
+    pub fn refresh_name_check(&mut self) {
+        let state = &mut self.dialog_state;
+        state.name_check = match (&self.mode, &state.siblings) {
+            (AppMode::Dialog(DialogKind::Rename { original }), Some(siblings))
+                if !state.input.is_empty() =>
+            {
+                Some(siblings.check(&state.input, Some(original)))
+            }
+            _ => None,
+        };
+    }
+
+    /// Replace the typed name with the suggested one.
+    pub fn accept_name_suggestion(&mut self) {
+        let suggestion = self
+            .dialog_state
+            .name_check
+            .as_ref()
+            .and_then(|check| check.suggestion.clone());
+        if let Some(name) = suggestion {
+            self.dialog_state.cursor_position = name.len();
+            self.dialog_state.input = name;
+            self.refresh_name_check();
+        }
     }
 
     /// Close the current dialog and return to normal mode.
@@ -278,53 +1721,83 @@ impl App {
         self.dialog_state = DialogState::default();
     }
 
-    /// Insert a character at the current cursor position.
+    /// Insert a character at the current cursor position. Control
+    /// characters, and characters past the name limit in name dialogs,
+    /// are ignored.
     #[allow(dead_code)]
     pub fn dialog_input_char(&mut self, c: char) {
+        if c.is_control() || self.dialog_room() < c.len_utf8() {
+            return;
+        }
         self.dialog_state
             .input
             .insert(self.dialog_state.cursor_position, c);
         self.dialog_state.cursor_position += c.len_utf8();
+        self.refresh_name_check();
+    }
+
+    /// Bytes the dialog input can still take.
+    fn dialog_room(&self) -> usize {
+        match &self.mode {
+            AppMode::Dialog(kind) if kind.takes_name() => {
+                paste_guard::NAME_MAX.saturating_sub(self.dialog_state.input.len())
+            }
+            _ => usize::MAX,
+        }
     }
 
-    /// Delete the character before the cursor (backspace).
+    /// Insert pasted text at the cursor, flattened onto one line and, in
+    /// name dialogs, cut at the name limit. Never confirms the dialog.
+    pub fn dialog_paste(&mut self, pasted: &str) {
+        let sanitized = paste_guard::sanitize(pasted);
+        let text = paste_guard::fit(&sanitized.text, self.dialog_room());
+        let mut notes: Vec<String> = sanitized.warning().into_iter().collect();
+        if text.len() < sanitized.text.len() {
+            notes.push(format!("name cut at {} bytes", paste_guard::NAME_MAX));
+        }
+        let state = &mut self.dialog_state;
+        state.input.insert_str(state.cursor_position, text);
+        state.cursor_position += text.len();
+        if !notes.is_empty() {
+            state.warning = Some(notes.join("; "));
+        }
+        self.refresh_name_check();
+    }
+
+    /// Show what was removed from pasted text: in the open dialog, or on
+    /// the status line for search and filter.
+    pub fn warn_paste(&mut self, warning: String) {
+        if matches!(self.mode, AppMode::Dialog(_)) {
+            self.dialog_state.warning = Some(warning);
+        } else {
+            self.set_status_message(warning);
+        }
+    }
+
+    /// Delete the grapheme before the cursor (backspace).
     #[allow(dead_code)]
     pub fn dialog_delete_char(&mut self) {
-        if self.dialog_state.cursor_position > 0 {
-            let byte_pos = self.dialog_state.cursor_position;
-            let prev_char = self.dialog_state.input[..byte_pos]
-                .chars()
-                .next_back()
-                .expect("cursor > 0 guarantees at least one char");
-            self.dialog_state.cursor_position -= prev_char.len_utf8();
-            self.dialog_state
-                .input
-                .remove(self.dialog_state.cursor_position);
+        let state = &mut self.dialog_state;
+        if state.cursor_position > 0 {
+            let start = text::prev_boundary(&state.input, state.cursor_position);
+            state.input.replace_range(start..state.cursor_position, "");
+            state.cursor_position = start;
         }
+        self.refresh_name_check();
     }
 
-    /// Move cursor left by one character.
+    /// Move cursor left by one grapheme.
     #[allow(dead_code)]
     pub fn dialog_move_cursor_left(&mut self) {
-        if self.dialog_state.cursor_position > 0 {
-            let prev_char = self.dialog_state.input[..self.dialog_state.cursor_position]
-                .chars()
-                .next_back()
-                .expect("cursor > 0 guarantees at least one char");
-            self.dialog_state.cursor_position -= prev_char.len_utf8();
-        }
+        let state = &mut self.dialog_state;
+        state.cursor_position = text::prev_boundary(&state.input, state.cursor_position);
     }
 
-    /// Move cursor right by one character.
+    /// Move cursor right by one grapheme.
     #[allow(dead_code)]
     pub fn dialog_move_cursor_right(&mut self) {
-        if self.dialog_state.cursor_position < self.dialog_state.input.len() {
-            let next_char = self.dialog_state.input[self.dialog_state.cursor_position..]
-                .chars()
-                .next()
-                .expect("cursor < len guarantees at least one char");
-            self.dialog_state.cursor_position += next_char.len_utf8();
-        }
+        let state = &mut self.dialog_state;
+        state.cursor_position = text::next_boundary(&state.input, state.cursor_position);
     }
 
     /// Move cursor to the beginning of the input.
@@ -356,7 +1829,6 @@ impl App {
     }
 
     /// Get the directory of the currently selected item.
-    #[allow(dead_code)]
     pub fn current_dir(&self) -> PathBuf {
         if let Some(item) = self
             .tree_state
@@ -422,13 +1894,52 @@ impl App {
         }
     }
 
+    /// Open the help overlay at the section for whatever has focus: the
+    /// editor, a dialog (file operations), the preview or the terminal.
+    pub fn open_help(&mut self) {
+        let section = match (&self.mode, self.focused_panel) {
+            (AppMode::Edit, _) | (AppMode::Dialog(DialogKind::SaveConfirm), _) => {
+                Some(HelpSection::Editor)
+            }
+            (AppMode::Dialog(_), _) => Some(HelpSection::FileOps),
+            (AppMode::Search | AppMode::Grep, _) => Some(HelpSection::Search),
+            (AppMode::SearchAction, _) => Some(HelpSection::SearchAction),
+            (_, FocusedPanel::Preview) => Some(HelpSection::Preview),
+            (_, FocusedPanel::Terminal) => Some(HelpSection::Terminal),
+            (_, FocusedPanel::Tree) => None,
+        };
+        self.help_state = HelpState {
+            scroll_offset: section.map(HelpOverlay::section_start).unwrap_or(0),
+            return_mode: std::mem::replace(&mut self.mode, AppMode::Help),
+            ..Default::default()
+        };
+    }
+
+    /// Close the help overlay, returning to the mode it was opened from.
+    pub fn close_help(&mut self) {
+        self.mode = std::mem::take(&mut self.help_state.return_mode);
+        self.help_state.query.clear();
+        self.help_state.searching = false;
+    }
+
+    /// Note that panel visibility or sizes changed: the areas stored by
+    /// the last frame no longer match the screen until the next one.
+    pub fn layout_changed(&mut self) {
+        self.layout_generation += 1;
+    }
+
+    /// Whether the stored panel areas are those of the current layout, so
+    /// a click can be mapped through them.
+    pub fn areas_current(&self) -> bool {
+        self.areas_generation == self.layout_generation
+    }
+
     /// Toggle the terminal panel visibility. Spawns PTY on first open.
     pub fn toggle_terminal(&mut self, event_tx: &mpsc::UnboundedSender<crate::event::Event>) {
-        // Check if terminal is enabled in config
-        if !self.config.terminal_enabled() {
-            self.set_status_message("Terminal disabled (--no-terminal or config)".to_string());
+        if self.terminal_unavailable() {
             return;
         }
+        self.layout_changed();
 
         if self.terminal_state.visible {
             // Hide the terminal panel
@@ -451,44 +1962,10 @@ impl App {
                     .unwrap_or(false);
 
             if needs_spawn {
-                self.terminal_state.exited = false;
                 let cwd = self.current_dir();
-                let shell = self.config.terminal_shell();
-
-                // Calculate terminal dimensions from terminal_area
-                let rows = self.terminal_area.height.saturating_sub(2).max(1);
-                let cols = self.terminal_area.width.saturating_sub(2).max(1);
-                // Use defaults if area hasn't been set yet
-                let rows = if rows == 0 { 24 } else { rows };
-                let cols = if cols == 0 { 80 } else { cols };
-
-                let (pty_tx, mut pty_rx) = tokio::sync::mpsc::unbounded_channel::<Vec<u8>>();
-
-                match crate::terminal::pty::PtyProcess::spawn(&shell, &cwd, rows, cols, pty_tx) {
-                    Ok(pty) => {
-                        self.terminal_state.pty = Some(pty);
-                        self.terminal_state
-                            .emulator
-                            .resize(rows as usize, cols as usize);
-
-                        // Bridge PTY output to the main event loop
-                        let event_tx = event_tx.clone();
-                        tokio::spawn(async move {
-                            while let Some(data) = pty_rx.recv().await {
-                                if event_tx
-                                    .send(crate::event::Event::TerminalOutput(data))
-                                    .is_err()
-                                {
-                                    break;
-                                }
-                            }
-                        });
-                    }
-                    Err(e) => {
-                        self.set_status_message(format!("⚠ Terminal: {}", e));
-                        self.terminal_state.visible = false;
-                        return;
-                    }
+                self.terminal_state.title = None;
+                if !self.spawn_shell(&cwd, event_tx) {
+                    return;
                 }
             }
 
@@ -496,11 +1973,171 @@ impl App {
         }
     }
 
+    /// Report why the terminal can't be opened: left out of this build, or
+    /// turned off by config.
+    fn terminal_unavailable(&mut self) -> bool {
+        if !cfg!(feature = "embedded-terminal") {
+            self.set_status_message(format!("⚠ Terminal: {}", crate::terminal::pty::UNSUPPORTED));
+        } else if !self.config.terminal_enabled() {
+            self.set_status_message("Terminal disabled (--no-terminal or config)".to_string());
+        } else {
+            return false;
+        }
+        true
+    }
+
+    /// Spawn the shell in `cwd` and bridge its output into the event loop.
+    ///
+    /// On failure the panel is hidden again and the error reported;
+    /// returns whether a shell is now running.
+    fn spawn_shell(
+        &mut self,
+        cwd: &Path,
+        event_tx: &mpsc::UnboundedSender<crate::event::Event>,
+    ) -> bool {
+        self.terminal_state.exited = false;
+        let shell = self.config.terminal_shell();
+
+        // Calculate terminal dimensions from terminal_area
+        let rows = self.terminal_area.height.saturating_sub(2).max(1);
+        let cols = self.terminal_area.width.saturating_sub(2).max(1);
+        // Use defaults if area hasn't been set yet
+        let rows = if rows == 0 { 24 } else { rows };
+        let cols = if cols == 0 { 80 } else { cols };
+
+        let (pty_tx, pty_rx) = tokio::sync::mpsc::unbounded_channel::<Vec<u8>>();
+
+        match crate::terminal::pty::PtyProcess::spawn(&shell, cwd, rows, cols, pty_tx) {
+            Ok(pty) => {
+                self.terminal_state.pty = Some(pty);
+                self.terminal_state.started_in = Some(cwd.to_path_buf());
+                self.terminal_state.emulator.clear_reported_cwd();
+                self.terminal_state
+                    .emulator
+                    .resize(rows as usize, cols as usize);
+
+                // Bridge PTY output to the main event loop in batches
+                let event_tx = event_tx.clone();
+                let foreground = Arc::clone(&self.terminal_state.output_foreground);
+                tokio::spawn(crate::terminal::bridge::run(
+                    pty_rx,
+                    foreground,
+                    move |data| {
+                        event_tx
+                            .send(crate::event::Event::TerminalOutput(data))
+                            .is_ok()
+                    },
+                ));
+                true
+            }
+            Err(e) => {
+                self.set_status_message(format!("⚠ Terminal: {}", e));
+                self.terminal_state.visible = false;
+                self.layout_changed();
+                false
+            }
+        }
+    }
+
+    /// Let the terminal output bridge batch less while the panel is visible
+    /// and focused, and more otherwise.
+    pub fn sync_terminal_pacing(&self) {
+        let foreground =
+            self.terminal_state.visible && self.focused_panel == FocusedPanel::Terminal;
+        self.terminal_state
+            .output_foreground
+            .store(foreground, Ordering::Relaxed);
+    }
+
+    /// Directory for "Open terminal here": the selected directory, or the
+    /// parent of a selected file.
+    fn terminal_here_dir(&self) -> Option<PathBuf> {
+        let item = self
+            .tree_state
+            .flat_items
+            .get(self.tree_state.selected_index)?;
+        match item.node_type {
+            NodeType::Directory => Some(item.path.clone()),
+            NodeType::LoadMore => None,
+            _ => item.path.parent().map(Path::to_path_buf),
+        }
+    }
+
+    /// Whether the embedded shell is still running.
+    fn shell_alive(&self) -> bool {
+        self.terminal_state
+            .pty
+            .as_ref()
+            .map(|p| p.is_alive())
+            .unwrap_or(false)
+    }
+
+    /// Open a shell in the selected directory (`T`).
+    ///
+    /// Without a running shell one is started there, creating and focusing
+    /// the panel. The panel holds a single session, so if a shell is
+    /// already running the user picks between `cd`-ing it or restarting it
+    /// in the new directory.
+    pub fn open_terminal_here(&mut self, event_tx: &mpsc::UnboundedSender<crate::event::Event>) {
+        if self.terminal_unavailable() {
+            return;
+        }
+        let dir = match self.terminal_here_dir() {
+            Some(dir) => dir,
+            None => return,
+        };
+        if !dir.is_dir() {
+            self.set_status_message(format!("⚠ Directory no longer exists: {}", dir.display()));
+            return;
+        }
+        if self.shell_alive() {
+            self.open_dialog(DialogKind::TerminalHere { dir });
+        } else {
+            self.restart_terminal_in(&dir, event_tx);
+        }
+    }
+
+    /// Type `cd <dir>` into the running shell and focus the panel.
+    pub fn terminal_cd(&mut self, dir: &Path) {
+        self.terminal_state.visible = true;
+        self.layout_changed();
+        if let Some(ref pty) = self.terminal_state.pty {
+            let cd_cmd = format!("cd {}\n", dir.to_string_lossy());
+            let _ = pty.write(cd_cmd.as_bytes());
+            self.terminal_state.started_in = Some(dir.to_path_buf());
+        }
+        self.focused_panel = FocusedPanel::Terminal;
+        self.set_status_message(format!("Terminal: cd {}", dir.to_string_lossy()));
+    }
+
+    /// Replace the shell with a fresh one started in `dir`, titled after it.
+    pub fn restart_terminal_in(
+        &mut self,
+        dir: &Path,
+        event_tx: &mpsc::UnboundedSender<crate::event::Event>,
+    ) {
+        // The directory may have gone away while the dialog was open
+        if !dir.is_dir() {
+            self.set_status_message(format!("⚠ Directory no longer exists: {}", dir.display()));
+            return;
+        }
+        self.shutdown_terminal();
+        self.terminal_state.visible = true;
+        self.layout_changed();
+        if self.spawn_shell(dir, event_tx) {
+            let title = crate::terminal::session_title(dir);
+            self.set_status_message(format!("Terminal: {}", dir.display()));
+            self.terminal_state.title = Some(title);
+            self.focused_panel = FocusedPanel::Terminal;
+        }
+    }
+
     /// Resize the terminal panel upward (smaller terminal, bigger main area).
     pub fn resize_terminal_up(&mut self) {
         if self.terminal_state.visible && self.terminal_state.height_percent > 10 {
             self.terminal_state.height_percent =
                 self.terminal_state.height_percent.saturating_sub(5).max(10);
+            self.layout_changed();
         }
     }
 
@@ -508,6 +2145,7 @@ impl App {
     pub fn resize_terminal_down(&mut self) {
         if self.terminal_state.visible && self.terminal_state.height_percent < 80 {
             self.terminal_state.height_percent = (self.terminal_state.height_percent + 5).min(80);
+            self.layout_changed();
         }
     }
 
@@ -519,19 +2157,23 @@ impl App {
         self.terminal_state.pty = None;
     }
 
-    /// Collect paths for clipboard: multi-selected if any, else focused item.
+    /// Collect paths to act on: multi-selected if any, else focused item.
+    /// "Load more" rows are never included.
     fn collect_target_paths(&self) -> Vec<PathBuf> {
         if !self.tree_state.multi_selected.is_empty() {
-            self.tree_state
-                .multi_selected
-                .iter()
-                .filter_map(|&idx| self.tree_state.flat_items.get(idx))
+            let mut indices: Vec<usize> = self.tree_state.multi_selected.iter().copied().collect();
+            indices.sort_unstable();
+            indices
+                .into_iter()
+                .filter_map(|idx| self.tree_state.flat_items.get(idx))
+                .filter(|item| item.node_type != NodeType::LoadMore)
                 .map(|item| item.path.clone())
                 .collect()
         } else if let Some(item) = self
             .tree_state
             .flat_items
             .get(self.tree_state.selected_index)
+            .filter(|item| item.node_type != NodeType::LoadMore)
         {
             vec![item.path.clone()]
         } else {
@@ -569,2742 +2211,12510 @@ impl App {
         ));
     }
 
+    /// Empty the clipboard, dropping the cut/copy marks from the tree.
+    pub fn clear_clipboard(&mut self) {
+        if self.clipboard.is_empty() {
+            self.set_status_message("Clipboard is empty".to_string());
+            return;
+        }
+        self.clipboard.clear();
+        self.set_status_message("Clipboard cleared".to_string());
+    }
+
+    /// "on clipboard (cut, 3 items total)" when the selected item is on
+    /// the clipboard.
+    pub fn selected_clipboard_note(&self) -> Option<String> {
+        let item = self
+            .tree_state
+            .flat_items
+            .get(self.tree_state.selected_index)?;
+        let op = match self.clipboard.mark(&item.path)? {
+            ClipboardOp::Copy => "copy",
+            ClipboardOp::Cut => "cut",
+        };
+        Some(format!(
+            "on clipboard ({}, {} total)",
+            op,
+            item_count(self.clipboard.len())
+        ))
+    }
+
     /// Paste clipboard contents — async version that spawns a tokio task.
+    ///
+    /// Repeats of the same paste (key auto-repeat) are ignored, and a paste
+    /// is refused while another operation is running.
     pub fn paste_clipboard_async(&mut self, event_tx: mpsc::UnboundedSender<crate::event::Event>) {
-        use crate::event::{Event, OperationResult, ProgressUpdate};
-        use crate::fs::operations;
-
         if self.clipboard.is_empty() {
             self.set_status_message("Clipboard is empty".to_string());
             return;
         }
 
         let dest_dir = self.current_dir();
-        let op = self.clipboard.operation;
         let paths = self.clipboard.paths.clone();
-        let cancel = self.cancel_token.clone();
+        if self
+            .in_flight
+            .is_repeated_paste(&paths, &dest_dir, Instant::now())
+        {
+            return;
+        }
+        let decision = self.confirmation_policy().decide(
+            &Intent::Paste {
+                sources: &paths,
+                dest: &dest_dir,
+            },
+            None,
+        );
+        if let Decision::Deny { reason } = decision {
+            self.set_status_message(reason);
+            return;
+        }
+        let was_cut = self.clipboard.operation == Some(ClipboardOp::Cut);
+        let transfer = PendingTransfer {
+            paths,
+            dest_dir,
+            was_cut,
+            send_to: None,
+            entries: None,
+            bytes: None,
+            overwrite: Vec::new(),
+            backup: None,
+            event_tx,
+        };
+        if !self.in_flight.is_busy() {
+            let collisions = overwrite::collisions(&transfer.paths, &transfer.dest_dir);
+            if !collisions.is_empty() {
+                let backup = self.config.backup_on_overwrite().then(|| {
+                    match self.trash.clone().filter(|_| self.config.use_trash()) {
+                        Some(trash) => BackupTo::Trash(trash),
+                        None => BackupTo::Rename,
+                    }
+                });
+                self.open_dialog(DialogKind::PasteConflict {
+                    collisions,
+                    current: 0,
+                    backup,
+                });
+                self.preflight_transfer = Some(transfer);
+                return;
+            }
+        }
+        self.continue_paste(transfer);
+    }
+
+    /// Settle the paste conflict being asked about, or with `all` every one
+    /// left, then start the paste once none are left. Skipped items are
+    /// dropped from it; kept ones go next to the existing item as `_copy`.
+    pub fn resolve_paste_conflict(&mut self, choice: ConflictChoice, all: bool) {
+        let AppMode::Dialog(DialogKind::PasteConflict {
+            collisions,
+            current,
+            backup,
+        }) = &mut self.mode
+        else {
+            return;
+        };
+        let Some(transfer) = self.preflight_transfer.as_mut() else {
+            self.close_dialog();
+            return;
+        };
+        let end = if all { collisions.len() } else { *current + 1 };
+        for collision in collisions.get(*current..end).unwrap_or(&[]) {
+            match choice {
+                ConflictChoice::Overwrite => transfer.overwrite.push(collision.source.clone()),
+                ConflictChoice::KeepBoth => {}
+                ConflictChoice::Skip => transfer.paths.retain(|p| p != &collision.source),
+            }
+        }
+        *current = end;
+        if *current < collisions.len() {
+            return;
+        }
+        if !transfer.overwrite.is_empty() {
+            transfer.backup = backup.take();
+        }
+        self.close_dialog();
+        let Some(transfer) = self.preflight_transfer.take() else {
+            return;
+        };
+        if transfer.paths.is_empty() {
+            self.set_status_message("Nothing to paste: every item skipped".to_string());
+            return;
+        }
+        self.continue_paste(transfer);
+    }
+
+    /// Drop the paste waiting on its conflicts.
+    pub fn cancel_paste_conflict(&mut self) {
+        self.close_dialog();
+        self.preflight_transfer = None;
+        self.set_status_message("Paste cancelled".to_string());
+    }
+
+    /// Start a paste with its conflicts settled, counting directories
+    /// first when they need it.
+    fn continue_paste(&mut self, transfer: PendingTransfer) {
+        if self.in_flight.is_busy() || !self.needs_preflight(&transfer.paths) {
+            self.start_transfer(transfer, WhenBusy::Reject);
+            return;
+        }
+
+        // Directories: count them first, starting right away unless the
+        // policy asks
+        self.open_dialog(DialogKind::TransferConfirm {
+            items: transfer.paths.len(),
+            was_cut: transfer.was_cut,
+            sizing: Sizing::Counting {
+                started: Instant::now(),
+            },
+        });
+        self.spawn_preflight(transfer.paths.clone(), transfer.event_tx.clone());
+        self.preflight_transfer = Some(transfer);
+    }
+
+    /// Start the paste waiting in the transfer confirmation, counting
+    /// progress in its pre-counted entries.
+    pub fn confirm_transfer(&mut self) {
+        let sizing = match &self.mode {
+            AppMode::Dialog(DialogKind::TransferConfirm { sizing, .. }) => Some(*sizing),
+            _ => None,
+        };
+        self.close_dialog();
+        if let Some(mut transfer) = self.preflight_transfer.take() {
+            transfer.entries = sizing.and_then(Sizing::entries);
+            transfer.bytes = sizing.and_then(Sizing::bytes);
+            self.start_transfer(transfer, WhenBusy::Reject);
+        }
+    }
+
+    /// Start `transfer` through the in-flight registry: right away when
+    /// nothing is running, otherwise queued or refused per `when_busy`.
+    fn start_transfer(&mut self, transfer: PendingTransfer, when_busy: WhenBusy) {
+        match self.in_flight.admit(transfer, when_busy) {
+            Admission::Start(transfer) => self.spawn_transfer_async(transfer),
+            Admission::Queued => {
+                let waiting = self.in_flight.queued();
+                self.set_status_message(format!(
+                    "Queued: starts after the running operation ({} waiting)",
+                    waiting
+                ));
+            }
+            Admission::Busy => {
+                self.set_status_message("Operation already in progress".to_string());
+            }
+        }
+    }
+
+    /// Copy (or move, when `was_cut`) the transfer's paths into its
+    /// destination on a background task, showing the progress dialog and
+    /// finishing with `OperationComplete`.
+    ///
+    /// `send_to` carries the target name for "send to" transfers so the
+    /// completion handler can report the destination and leave the clipboard
+    /// alone.
+    fn spawn_transfer_async(&mut self, transfer: PendingTransfer) {
+        use crate::event::{Event, OperationResult, ProgressUpdate};
 
-        // Reset cancel token
-        cancel.store(false, Ordering::SeqCst);
+        let PendingTransfer {
+            paths,
+            dest_dir,
+            was_cut,
+            send_to,
+            entries,
+            bytes,
+            overwrite,
+            backup,
+            event_tx,
+        } = transfer;
+        // Files about to be overwritten are backed up one step each
+        let backup_steps = match backup {
+            Some(_) => overwrite
+                .iter()
+                .filter(|src| overwritten_file(src, &dest_dir).is_some())
+                .count(),
+            None => 0,
+        };
+        // Leave the disk to the operation
+        self.prefetch.cancel_all();
 
-        // Show progress dialog
+        // Show progress dialog, sized by the pre-count when there was one
         self.open_dialog(DialogKind::Progress {
             message: "Preparing...".to_string(),
             current: 0,
-            total: paths.len(),
+            total: Some(entries.unwrap_or(paths.len()) + backup_steps),
+            bytes_done: 0,
+            bytes_total: bytes.unwrap_or(0),
+            started: Instant::now(),
+            rate: None,
+            spin: 0,
         });
 
-        let was_cut = op == Some(ClipboardOp::Cut);
+        let verb = if was_cut { "move" } else { "copy" };
+        let target = match &send_to {
+            Some(name) => name.clone(),
+            None => dest_dir.display().to_string(),
+        };
+        let description = format!("{} {} to {}", verb, item_count(paths.len()), target);
+        self.tasks.spawn(
+            TaskKind::Transfer,
+            description,
+            Priority::User,
+            move |token| async move {
+                let started = Instant::now();
+                let total = paths.len() + backup_steps;
+                let mut step = 0;
+                let mut success_count = 0;
+                let mut errors = Vec::new();
+                let mut created_paths = Vec::new();
+                let mut replaced = Vec::new();
+                let mut backups = Vec::new();
+                let mut failed_sources = Vec::new();
+
+                // Without a pre-count, size the paths now unless that means
+                // a long walk
+                let bytes_total = bytes.unwrap_or_else(|| {
+                    let usage = operations::disk_usage_until(
+                        &paths,
+                        PREFLIGHT_MAX_ENTRIES,
+                        token.cancel_flag(),
+                    );
+                    if usage.complete {
+                        usage.bytes
+                    } else {
+                        0
+                    }
+                });
+                let mut progress = TransferProgress {
+                    token: &token,
+                    event_tx: &event_tx,
+                    entries: entries.map(|n| n + backup_steps),
+                    counting: false,
+                    done: 0,
+                    update: ProgressUpdate {
+                        current_file: String::new(),
+                        current: 0,
+                        total: Some(entries.map_or(total, |n| n + backup_steps)),
+                        bytes_done: 0,
+                        bytes_total,
+                    },
+                    unsent_bytes: 0,
+                };
 
-        tokio::spawn(async move {
-            let total = paths.len();
-            let mut success_count = 0;
-            let mut errors = Vec::new();
-            let mut created_paths = Vec::new();
+                for src in &paths {
+                    if token.is_cancelled() {
+                        break;
+                    }
 
-            for (i, src) in paths.iter().enumerate() {
-                if cancel.load(Ordering::SeqCst) {
-                    break;
-                }
+                    // Overwriting: the existing file steps aside first
+                    let target = src
+                        .file_name()
+                        .filter(|_| overwrite.contains(src))
+                        .map(|name| dest_dir.join(name));
+                    let mut kept = None;
+                    let overwritten = target
+                        .as_ref()
+                        .and_then(|_| overwritten_file(src, &dest_dir));
+                    if let (Some(to), Some(file)) = (&backup, overwritten) {
+                        step += 1;
+                        if entries.is_none() {
+                            token.progress(step, total);
+                            progress.update.current_file =
+                                format!("Backing up {}", file_label(&file));
+                            progress.update.current = step;
+                            progress.send();
+                        } else {
+                            operations::CopyProgress::entry(&mut progress, &file);
+                        }
+                        match overwrite::back_up(&file, to) {
+                            Ok(made) => kept = Some(made),
+                            Err(e) => {
+                                errors.push(e);
+                                failed_sources.push(src.clone());
+                                continue;
+                            }
+                        }
+                    }
 
-                let filename = src
-                    .file_name()
-                    .map(|n| n.to_string_lossy().to_string())
-                    .unwrap_or_default();
+                    step += 1;
+                    if entries.is_none() {
+                        token.progress(step, total);
+                        progress.update.current_file = file_label(src);
+                        progress.update.current = step;
+                        progress.send();
+                    }
 
-                let _ = event_tx.send(Event::Progress(ProgressUpdate {
-                    current_file: filename,
-                    current: i + 1,
-                    total,
-                }));
+                    let result = match &target {
+                        Some(dest) => {
+                            let existed = std::fs::symlink_metadata(dest).is_ok();
+                            let moved = if was_cut {
+                                operations::move_to(src, dest, &mut progress)
+                            } else {
+                                operations::copy_to(src, dest, &mut progress)
+                            };
+                            if moved.is_ok() && existed {
+                                replaced.push(dest.clone());
+                            }
+                            moved.map(|()| dest.clone())
+                        }
+                        None if was_cut => {
+                            operations::move_item_with_progress(src, &dest_dir, &mut progress)
+                        }
+                        None => {
+                            operations::copy_recursive_with_progress(src, &dest_dir, &mut progress)
+                        }
+                    };
 
-                let result = match op {
-                    Some(ClipboardOp::Copy) => operations::copy_recursive(src, &dest_dir),
-                    Some(ClipboardOp::Cut) => operations::move_item(src, &dest_dir),
-                    None => continue,
-                };
+                    // A failed overwrite puts the backed-up file back; one
+                    // that can't go back stays listed with the backups
+                    if let Some(made) = kept {
+                        if result.is_ok() || overwrite::restore(&made).is_err() {
+                            backups.push(made);
+                        }
+                    }
 
-                match result {
-                    Ok(created) => {
-                        success_count += 1;
-                        created_paths.push(created);
+                    // Cancelled mid-file: the partial file is gone and the
+                    // item counts as not started
+                    if matches!(result, Err(OperationError::Cancelled)) {
+                        break;
+                    }
+                    match result {
+                        Ok(created) => {
+                            success_count += 1;
+                            created_paths.push(created);
+                        }
+                        Err(e) => {
+                            errors.push(e);
+                            failed_sources.push(src.clone());
+                        }
                     }
-                    Err(e) => errors.push(format!(
-                        "{}: {}",
-                        src.file_name()
-                            .map(|n| n.to_string_lossy().to_string())
-                            .unwrap_or_default(),
-                        e
-                    )),
                 }
-            }
 
-            let _ = event_tx.send(Event::OperationComplete(OperationResult {
-                success_count,
-                errors,
-                created_paths,
-                source_paths: paths,
-                dest_dir,
-                was_cut,
-            }));
-        });
+                let _ = event_tx.send(Event::OperationComplete(OperationResult {
+                    success_count,
+                    errors,
+                    created_paths,
+                    replaced,
+                    backups,
+                    failed_sources,
+                    source_paths: paths,
+                    dest_dir,
+                    was_cut,
+                    send_to,
+                    elapsed: started.elapsed(),
+                }));
+            },
+        );
     }
 
-    /// Spawn an async directory snapshot collection.
-    ///
-    /// For large directories (> page_size entries), this collects a `DirSnapshot`
-    /// on a blocking thread and sends `DirScanComplete` when done. The UI remains
-    /// responsive while the snapshot is being built.
-    ///
-    /// The node is marked as expanded immediately with a "Scanning..." placeholder.
-    #[allow(dead_code)]
-    pub fn spawn_async_snapshot(
-        &mut self,
-        dir_path: &Path,
-        event_tx: &mpsc::UnboundedSender<crate::event::Event>,
-    ) {
-        let path = dir_path.to_path_buf();
-        let tx = event_tx.clone();
-
-        self.set_status_message(format!(
-            "📂 Scanning {}...",
-            path.file_name()
-                .map(|n| n.to_string_lossy().to_string())
-                .unwrap_or_else(|| path.to_string_lossy().to_string())
-        ));
+    // === Delete ===
 
-        tokio::spawn(async move {
-            // Use spawn_blocking since read_dir is a synchronous I/O operation
-            let result = tokio::task::spawn_blocking(move || {
-                crate::fs::tree::DirSnapshot::collect(&path).map(|snapshot| (path, snapshot))
+    /// Items the delete and duplicate keys act on: the multi-selection,
+    /// else the focused row. The root is never included, nor anything inside another target
+    /// (deleting the parent already removes it).
+    pub fn delete_targets(&self) -> Vec<PathBuf> {
+        let root = &self.tree_state.root.path;
+        let paths: Vec<PathBuf> = self
+            .collect_target_paths()
+            .into_iter()
+            .filter(|p| p != root)
+            .collect();
+        paths
+            .iter()
+            .filter(|p| {
+                !paths
+                    .iter()
+                    .any(|other| other != *p && p.starts_with(other))
             })
-            .await;
-
-            match result {
-                Ok(Ok((path, snapshot))) => {
-                    let _ = tx.send(crate::event::Event::DirScanComplete { path, snapshot });
-                }
-                Ok(Err(_)) | Err(_) => {
-                    // Snapshot collection failed — silently ignore
-                    // The directory will just not expand
-                }
-            }
-        });
+            .cloned()
+            .collect()
     }
 
-    /// Spawn an async child count for a directory.
-    ///
-    /// Performs `read_dir().count()` on a blocking thread and sends
-    /// `DirCountComplete` when done. Used for non-blocking badge updates.
-    #[allow(dead_code)]
-    pub fn spawn_async_child_count(
+    /// Ask to delete `targets`, with their count and combined size.
+    /// Directories are counted in the background while the dialog shows.
+    /// The targets go to the trash when it is in use, unless `permanent`.
+    /// Without `confirm_delete`, a delete that isn't large starts right
+    /// away.
+    pub fn open_delete_confirm(
         &mut self,
-        dir_path: &Path,
+        targets: Vec<PathBuf>,
+        permanent: bool,
         event_tx: &mpsc::UnboundedSender<crate::event::Event>,
     ) {
-        let path = dir_path.to_path_buf();
-        let tx = event_tx.clone();
-
-        tokio::spawn(async move {
-            let result = tokio::task::spawn_blocking(move || {
-                std::fs::read_dir(&path).map(|rd| (path, rd.count()))
-            })
-            .await;
-
-            if let Ok(Ok((path, count))) = result {
-                let _ = tx.send(crate::event::Event::DirCountComplete { path, count });
+        if targets.is_empty() {
+            return;
+        }
+        let decision = self
+            .confirmation_policy()
+            .decide(&Intent::Delete { targets: &targets }, None);
+        if let Decision::Deny { reason } = decision {
+            self.set_status_message(reason);
+            return;
+        }
+        let to_trash = !permanent && self.config.use_trash() && self.trash.is_some();
+        let sizing = if self.needs_preflight(&targets) {
+            self.spawn_preflight(targets.clone(), event_tx.clone());
+            Sizing::Counting {
+                started: Instant::now(),
+            }
+        } else {
+            let sizing = Sizing::Counted {
+                usage: operations::disk_usage(&targets, DELETE_USAGE_MAX_ENTRIES),
+                large: false,
+                armed: false,
+            };
+            if decision == Decision::Proceed {
+                self.delete_async(targets, sizing.entries(), to_trash, event_tx.clone());
+                return;
             }
+            sizing
+        };
+        self.open_dialog(DialogKind::DeleteConfirm {
+            targets,
+            sizing,
+            scroll: 0,
+            to_trash,
         });
     }
 
-    /// Spawn an async directory summary scan.
-    ///
-    /// Walks the directory tree recursively, sending periodic `DirSummaryUpdate`
-    /// events with running totals. Useful for directory preview.
-    #[allow(dead_code)]
-    pub fn spawn_async_dir_summary(
+    /// Scroll the delete confirmation list by `delta` rows.
+    pub fn scroll_delete_confirm(&mut self, delta: isize) {
+        if let AppMode::Dialog(DialogKind::DeleteConfirm {
+            targets, scroll, ..
+        }) = &mut self.mode
+        {
+            let max = targets.len().saturating_sub(DELETE_LIST_ROWS);
+            *scroll = scroll.saturating_add_signed(delta).min(max);
+        }
+    }
+
+    /// Delete `targets` on a background task, showing the progress dialog
+    /// and finishing with `DeleteComplete`. Progress counts `entries` when
+    /// they were counted beforehand, else the targets. With `to_trash`
+    /// they are moved to the trash instead, one step per target. Refused
+    /// while another operation is running.
+    pub fn delete_async(
         &mut self,
-        dir_path: &Path,
-        event_tx: &mpsc::UnboundedSender<crate::event::Event>,
+        targets: Vec<PathBuf>,
+        entries: Option<usize>,
+        to_trash: bool,
+        event_tx: mpsc::UnboundedSender<crate::event::Event>,
     ) {
-        let path = dir_path.to_path_buf();
-        let tx = event_tx.clone();
+        use crate::event::{DeleteResult, Event, ProgressUpdate};
 
-        tokio::spawn(async move {
-            let result = tokio::task::spawn_blocking(move || {
-                let mut files: u64 = 0;
-                let mut dirs: u64 = 0;
-                let mut size: u64 = 0;
-                let mut stack = vec![path.clone()];
-                let mut items_since_update: u64 = 0;
-
-                let mut visited = crate::fs::tree::VisitedDirs::new();
-                visited.visit(&path);
-
-                while let Some(dir) = stack.pop() {
-                    let entries = match std::fs::read_dir(&dir) {
-                        Ok(e) => e,
-                        Err(_) => continue,
-                    };
-                    for entry in entries {
-                        let entry = match entry {
-                            Ok(e) => e,
-                            Err(_) => continue,
-                        };
-                        let meta = match entry.metadata() {
-                            Ok(m) => m,
-                            Err(_) => continue,
-                        };
-                        if meta.is_dir() {
-                            let entry_path = entry.path();
-                            // Skip symlink loops
-                            if visited.visit(&entry_path) {
-                                dirs += 1;
-                                stack.push(entry_path);
+        if !self.in_flight.begin() {
+            self.close_dialog();
+            self.set_status_message("Operation already in progress".to_string());
+            return;
+        }
+        self.prefetch.cancel_all();
+
+        let trash = if to_trash { self.trash.clone() } else { None };
+        // Moving to the trash is a rename per target, so entries don't matter
+        let entries = if trash.is_some() { None } else { entries };
+        // A delete whose pre-count didn't finish counts entries as it goes
+        let total = match &trash {
+            Some(_) => Some(targets.len()),
+            None => entries,
+        };
+        self.open_dialog(DialogKind::Progress {
+            message: "Preparing...".to_string(),
+            current: 0,
+            total,
+            bytes_done: 0,
+            bytes_total: 0,
+            started: Instant::now(),
+            rate: None,
+            spin: 0,
+        });
+
+        let to_trash = trash.is_some();
+        let verb = if to_trash { "trash" } else { "delete" };
+        let description = format!("{} {}", verb, item_count(targets.len()));
+        self.tasks.spawn(
+            TaskKind::Delete,
+            description,
+            Priority::User,
+            move |token| async move {
+                let total = targets.len();
+                let mut deleted = Vec::new();
+                let mut trashed = Vec::new();
+                let mut errors = Vec::new();
+                let mut done = 0;
+
+                for (i, target) in targets.iter().enumerate() {
+                    if token.is_cancelled() {
+                        errors.push(OperationError::Cancelled);
+                        break;
+                    }
+                    if let Some(trash) = &trash {
+                        token.progress(i + 1, total);
+                        let _ = event_tx.send(Event::Progress(ProgressUpdate {
+                            current_file: file_label(target),
+                            current: i + 1,
+                            total: Some(total),
+                            bytes_done: 0,
+                            bytes_total: 0,
+                        }));
+                        match trash.trash(target) {
+                            Ok(entry) => {
+                                trashed.push(entry);
+                                deleted.push(target.clone());
                             }
-                        } else {
-                            files += 1;
-                            size += meta.len();
+                            Err(e) => errors.push(e),
                         }
-                        items_since_update += 1;
-
-                        // Send progress every 1000 items
-                        if items_since_update >= 1000 {
-                            let _ = tx.send(crate::event::Event::DirSummaryUpdate {
-                                path: path.clone(),
-                                files,
-                                dirs,
-                                size,
-                                done: false,
-                            });
-                            items_since_update = 0;
+                        continue;
+                    }
+
+                    // Entry by entry, so the bar (or the running count,
+                    // without a pre-count) moves inside big directories
+                    let tx = event_tx.clone();
+                    let task = token.clone();
+                    let offset = done;
+                    let progress: operations::DeleteProgressFn = Box::new(move |name, count| {
+                        if count % PROGRESS_STRIDE == 0 {
+                            let current = offset + count + 1;
+                            let current = entries.map_or(current, |entries| current.min(entries));
+                            task.progress(current, entries.unwrap_or(0));
+                            let _ = tx.send(Event::Progress(ProgressUpdate {
+                                current_file: name.to_string(),
+                                current,
+                                total: entries,
+                                bytes_done: 0,
+                                bytes_total: 0,
+                            }));
                         }
+                    });
+                    let (count, target_errors) = operations::delete_recursive_with_progress(
+                        target,
+                        &progress,
+                        token.cancel_flag(),
+                    );
+                    done += count;
+                    if target_errors.is_empty() {
+                        deleted.push(target.clone());
+                        continue;
+                    }
+                    let cancelled = target_errors
+                        .iter()
+                        .any(|e| matches!(e, OperationError::Cancelled));
+                    errors.extend(target_errors);
+                    if cancelled {
+                        break;
                     }
                 }
 
-                // Send final update
-                let _ = tx.send(crate::event::Event::DirSummaryUpdate {
-                    path,
-                    files,
-                    dirs,
-                    size,
-                    done: true,
-                });
-            })
-            .await;
+                let _ = event_tx.send(Event::DeleteComplete(DeleteResult {
+                    targets,
+                    deleted,
+                    trashed,
+                    errors,
+                    to_trash,
+                }));
+            },
+        );
+    }
+
+    // === Duplicate ===
+
+    /// Copy the multi-selection, else the focused row, next to itself under
+    /// a "(copy)" name on a background task with the progress dialog,
+    /// which counts entries as they are copied. The copies are the undo; a
+    /// single copy is selected when it is done.
+    pub fn duplicate_selected(&mut self, event_tx: mpsc::UnboundedSender<crate::event::Event>) {
+        use crate::event::{DuplicateResult, Event, ProgressUpdate};
 
-            // Ignore join errors
-            let _ = result;
+        let sources = self.delete_targets();
+        if sources.is_empty() {
+            return;
+        }
+        if !self.in_flight.begin() {
+            self.set_status_message("Operation already in progress".to_string());
+            return;
+        }
+        self.prefetch.cancel_all();
+
+        self.open_dialog(DialogKind::Progress {
+            message: "Preparing...".to_string(),
+            current: 0,
+            total: None,
+            bytes_done: 0,
+            bytes_total: 0,
+            started: Instant::now(),
+            rate: None,
+            spin: 0,
         });
+
+        let description = format!("duplicate {}", item_count(sources.len()));
+        self.tasks.spawn(
+            TaskKind::Duplicate,
+            description,
+            Priority::User,
+            move |token| async move {
+                let mut created = Vec::new();
+                let mut errors = Vec::new();
+                let mut progress = TransferProgress {
+                    token: &token,
+                    event_tx: &event_tx,
+                    entries: None,
+                    counting: true,
+                    done: 0,
+                    update: ProgressUpdate {
+                        current_file: String::new(),
+                        current: 0,
+                        total: None,
+                        bytes_done: 0,
+                        bytes_total: 0,
+                    },
+                    unsent_bytes: 0,
+                };
+                for src in &sources {
+                    if token.is_cancelled() {
+                        errors.push(OperationError::Cancelled);
+                        break;
+                    }
+                    match operations::duplicate_with_progress(src, &mut progress) {
+                        Ok(copy) => created.push(copy),
+                        Err(OperationError::Cancelled) => {
+                            errors.push(OperationError::Cancelled);
+                            break;
+                        }
+                        Err(e) => errors.push(e),
+                    }
+                }
+                let _ = event_tx.send(Event::DuplicateComplete(DuplicateResult {
+                    sources,
+                    created,
+                    errors,
+                }));
+            },
+        );
     }
 
-    /// Handle an async operation completion.
-    pub fn handle_operation_complete(&mut self, result: crate::event::OperationResult) {
+    /// Handle finished duplicates: show the copies, select a single one,
+    /// and make removing them the undo.
+    pub fn handle_duplicate_complete(&mut self, result: crate::event::DuplicateResult) {
         self.close_dialog();
+        let next = self.in_flight.finish();
 
-        // Refresh dest dir
-        self.tree_state.reload_dir(&result.dest_dir);
+        self.reload_parents(result.sources.iter());
         self.invalidate_search_cache();
 
-        // For cut/move, also refresh source parents
-        if result.was_cut {
-            for src in &result.source_paths {
-                if let Some(parent) = src.parent() {
-                    self.tree_state.reload_dir(parent);
-                }
-            }
-            // Clear clipboard after successful cut
-            if result.errors.is_empty() {
-                self.clipboard.clear();
-            }
+        if !result.created.is_empty() {
+            self.record_undo(UndoAction::CopyPaste {
+                created_paths: result
+                    .created
+                    .iter()
+                    .cloned()
+                    .map(StampedPath::capture)
+                    .collect(),
+                backups: Vec::new(),
+            });
         }
-
+        let cancelled = result
+            .errors
+            .iter()
+            .any(|e| matches!(e, OperationError::Cancelled));
         if result.errors.is_empty() {
-            // Record undo action
-            if result.was_cut {
-                // Build move pairs: (original_src, created_dest)
-                let moves: Vec<(PathBuf, PathBuf)> = result
-                    .source_paths
-                    .iter()
-                    .zip(result.created_paths.iter())
-                    .map(|(src, dest)| (src.clone(), dest.clone()))
-                    .collect();
-                self.last_undo = Some(UndoAction::MovePaste { moves });
-            } else {
-                self.last_undo = Some(UndoAction::CopyPaste {
-                    created_paths: result.created_paths.clone(),
-                });
+            self.tree_state.clear_multi_select();
+            match result.created.as_slice() {
+                [copy] => {
+                    self.navigate_to_path(copy);
+                    self.set_status_message(format!("Duplicated as {}", file_label(copy)));
+                }
+                created => {
+                    self.set_status_message(format!("Duplicated {}", item_count(created.len())))
+                }
             }
-
-            let op_name = if result.was_cut { "Moved" } else { "Pasted" };
+        } else if cancelled && result.errors.len() == 1 {
             self.set_status_message(format!(
-                "{} {} item{}",
-                op_name,
-                result.success_count,
-                if result.success_count == 1 { "" } else { "s" }
+                "Duplicate cancelled after {} of {}",
+                result.created.len(),
+                result.sources.len()
             ));
         } else {
-            self.set_status_message(format!("Error: {}", result.errors.join("; ")));
+            self.set_status_message(format!(
+                "Error: duplicated {} of {}; {}",
+                result.created.len(),
+                result.sources.len(),
+                describe_errors(&result.errors)
+            ));
         }
-    }
 
-    /// Handle a progress update from an async operation.
-    pub fn handle_progress(&mut self, update: crate::event::ProgressUpdate) {
-        if let AppMode::Dialog(DialogKind::Progress { .. }) = &self.mode {
-            self.mode = AppMode::Dialog(DialogKind::Progress {
-                message: update.current_file,
-                current: update.current,
-                total: update.total,
-            });
+        if let Some(next) = next {
+            self.spawn_transfer_async(next);
         }
     }
 
-    /// Cancel an ongoing async operation.
-    pub fn cancel_operation(&mut self) {
-        self.cancel_token.store(true, Ordering::SeqCst);
-    }
+    // === Extract ===
 
-    /// Undo the last reversible operation.
-    pub fn undo(&mut self) {
-        use crate::fs::operations;
+    /// Extract the selected archive into a new directory next to it, named
+    /// after it, on a background task with the progress dialog. Refused
+    /// when that directory already exists or another operation is running.
+    #[cfg(feature = "archives")]
+    pub fn extract_selected(&mut self, event_tx: mpsc::UnboundedSender<crate::event::Event>) {
+        use crate::event::{Event, ExtractResult, ProgressUpdate};
 
-        let action = match self.last_undo.take() {
-            Some(a) => a,
-            None => {
-                self.set_status_message("Nothing to undo".to_string());
-                return;
-            }
+        let Some(archive) = self
+            .tree_state
+            .flat_items
+            .get(self.tree_state.selected_index)
+            .filter(|item| item.node_type == NodeType::File)
+            .map(|item| item.path.clone())
+        else {
+            return;
+        };
+        let Some(format) = archive::Format::detect(&archive) else {
+            self.set_status_message(format!("Not an archive: {}", file_label(&archive)));
+            return;
+        };
+        let Some(target) = archive::extract_target(&archive, format) else {
+            return;
         };
+        if std::fs::symlink_metadata(&target).is_ok() {
+            self.set_status_message(format!(
+                "Cannot extract: {}/ already exists",
+                file_label(&target)
+            ));
+            return;
+        }
+        if !self.in_flight.begin() {
+            self.set_status_message("Operation already in progress".to_string());
+            return;
+        }
+        self.prefetch.cancel_all();
 
-        match action {
-            UndoAction::Rename { from, to } => {
-                // Rename back: from is original, to is what it was renamed to
-                match operations::rename(&to, &from) {
-                    Ok(()) => {
-                        if let Some(parent) = from.parent() {
-                            self.tree_state.reload_dir(parent);
-                        }
-                        self.set_status_message("Undo: rename reverted".to_string());
+        let archive_size = std::fs::metadata(&archive).map(|m| m.len()).unwrap_or(0);
+        self.open_dialog(DialogKind::Progress {
+            message: "Preparing...".to_string(),
+            current: 0,
+            total: None,
+            bytes_done: 0,
+            bytes_total: archive_size,
+            started: Instant::now(),
+            rate: None,
+            spin: 0,
+        });
+
+        let description = format!("extract {}", file_label(&archive));
+        self.tasks.spawn(
+            TaskKind::Extract,
+            description,
+            Priority::User,
+            move |token| async move {
+                let mut sent_read = 0;
+                let mut progress = |at: &archive::Extracting| {
+                    if !at.done.is_multiple_of(PROGRESS_STRIDE)
+                        && at.read - sent_read < PROGRESS_BYTES
+                        && Some(at.done) != at.total
+                    {
+                        return;
                     }
-                    Err(e) => self.set_status_message(format!("Undo failed: {}", e)),
-                }
+                    sent_read = at.read;
+                    token.progress(at.done, at.total.unwrap_or(0));
+                    // Tar streams don't know their entry count
+                    let _ = event_tx.send(Event::Progress(ProgressUpdate {
+                        current_file: at.entry.to_string(),
+                        current: at.done,
+                        total: at.total,
+                        bytes_done: at.read,
+                        bytes_total: archive_size,
+                    }));
+                };
+                let result = archive::extract(
+                    &archive,
+                    format,
+                    &target,
+                    &mut progress,
+                    token.cancel_flag(),
+                );
+                let _ = event_tx.send(Event::ExtractComplete(ExtractResult {
+                    archive,
+                    target,
+                    result,
+                }));
+            },
+        );
+    }
+
+    /// Handle a finished extraction: show the new directory, and make it
+    /// the undo (Ctrl+Z removes the extracted tree).
+    #[cfg(feature = "archives")]
+    pub fn handle_extract_complete(&mut self, extract: crate::event::ExtractResult) {
+        self.close_dialog();
+        let next = self.in_flight.finish();
+        if let Some(parent) = extract.target.parent() {
+            self.tree_state.reload_dir(parent);
+        }
+        self.invalidate_search_cache();
+
+        let name = file_label(&extract.target);
+        match extract.result {
+            Ok(entries) => {
+                self.record_undo(UndoAction::CopyPaste {
+                    created_paths: vec![StampedPath::capture(extract.target.clone())],
+                    backups: Vec::new(),
+                });
+                self.set_status_message(format!(
+                    "Extracted {} entr{} to {}/",
+                    entries,
+                    if entries == 1 { "y" } else { "ies" },
+                    name
+                ));
             }
-            UndoAction::CopyPaste { created_paths } => {
-                let mut errors = Vec::new();
-                for path in &created_paths {
-                    if let Err(e) = operations::delete(path) {
-                        errors.push(format!("{}: {}", path.display(), e));
-                    } else if let Some(parent) = path.parent() {
-                        self.tree_state.reload_dir(parent);
-                    }
-                }
-                if errors.is_empty() {
-                    self.set_status_message(format!(
-                        "Undo: deleted {} copied item{}",
-                        created_paths.len(),
-                        if created_paths.len() == 1 { "" } else { "s" }
-                    ));
-                } else {
-                    self.set_status_message(format!("Undo partial: {}", errors.join("; ")));
-                }
+            Err(archive::ArchiveError::Cancelled) => {
+                self.set_status_message("Extraction cancelled".to_string());
             }
-            UndoAction::MovePaste { moves } => {
-                let mut errors = Vec::new();
-                for (original_src, current_dest) in &moves {
-                    // Move back: current_dest → original_src
-                    if let Some(parent) = original_src.parent() {
-                        match operations::move_item(current_dest, parent) {
-                            Ok(_) => {
-                                self.tree_state.reload_dir(parent);
-                                if let Some(dest_parent) = current_dest.parent() {
-                                    self.tree_state.reload_dir(dest_parent);
-                                }
-                            }
-                            Err(e) => errors.push(format!("{}: {}", current_dest.display(), e)),
-                        }
-                    }
-                }
-                if errors.is_empty() {
-                    self.set_status_message(format!(
-                        "Undo: moved {} item{} back",
-                        moves.len(),
-                        if moves.len() == 1 { "" } else { "s" }
-                    ));
-                } else {
-                    self.set_status_message(format!("Undo partial: {}", errors.join("; ")));
-                }
+            Err(e) => {
+                self.set_status_message(format!(
+                    "Error: extracting {}: {}",
+                    file_label(&extract.archive),
+                    e
+                ));
             }
         }
-    }
-
-    /// Scroll preview down by one line.
-    pub fn preview_scroll_down(&mut self) {
-        let max = self.preview_max_scroll_offset();
-        self.preview_state.scroll_offset = (self.preview_state.scroll_offset + 1).min(max);
-    }
 
-    /// Scroll preview up by one line.
-    pub fn preview_scroll_up(&mut self) {
-        self.clamp_preview_scroll();
-        if self.preview_state.scroll_offset > 0 {
-            self.preview_state.scroll_offset -= 1;
+        if let Some(next) = next {
+            self.spawn_transfer_async(next);
         }
     }
 
-    /// Jump preview to the first line.
-    pub fn preview_jump_top(&mut self) {
-        self.preview_state.scroll_offset = 0;
-    }
-
-    /// Jump preview to the last line.
-    pub fn preview_jump_bottom(&mut self) {
-        self.preview_state.scroll_offset = self.preview_max_scroll_offset();
-    }
-
-    /// Scroll preview down by half a page.
-    pub fn preview_half_page_down(&mut self, visible_height: usize) {
-        let half = visible_height / 2;
-        let max = self.preview_max_scroll_offset();
-        self.preview_state.scroll_offset = (self.preview_state.scroll_offset + half).min(max);
+    /// Built without archive support: say so rather than do nothing.
+    #[cfg(not(feature = "archives"))]
+    pub fn extract_selected(&mut self, _: mpsc::UnboundedSender<crate::event::Event>) {
+        self.set_status_message("Cannot extract: built without archive support".to_string());
     }
 
-    /// Scroll preview up by half a page.
-    pub fn preview_half_page_up(&mut self, visible_height: usize) {
-        self.clamp_preview_scroll();
-        let half = visible_height / 2;
-        self.preview_state.scroll_offset = self.preview_state.scroll_offset.saturating_sub(half);
-    }
+    // === Scaffolds ===
 
-    /// Clamp preview scroll offset to valid bounds for the current viewport.
-    pub fn clamp_preview_scroll(&mut self) {
-        let max = self.preview_max_scroll_offset();
-        self.preview_state.scroll_offset = self.preview_state.scroll_offset.min(max);
+    /// Open the list of scaffolds in the config directory, to instantiate
+    /// one into the focused directory.
+    pub fn open_scaffold_picker(&mut self) {
+        let Some(dir) = self
+            .config_dir
+            .as_ref()
+            .map(|dir| dir.join(scaffold::SCAFFOLDS_DIR))
+        else {
+            self.set_status_message("No config directory to read scaffolds from".to_string());
+            return;
+        };
+        let scaffolds = scaffold::discover(&dir);
+        if scaffolds.is_empty() {
+            self.set_status_message(format!("No scaffolds in {}", dir.display()));
+            return;
+        }
+        self.open_dialog(DialogKind::ScaffoldPicker {
+            scaffolds,
+            selected: 0,
+            dest: self.current_dir(),
+        });
     }
 
-    fn preview_max_scroll_offset(&self) -> usize {
-        self.preview_line_count()
-            .saturating_sub(self.preview_visible_height())
+    pub fn scaffold_picker_select(&mut self, forward: bool) {
+        if let AppMode::Dialog(DialogKind::ScaffoldPicker {
+            scaffolds,
+            selected,
+            ..
+        }) = &mut self.mode
+        {
+            let len = scaffolds.len();
+            if len > 0 {
+                *selected = if forward {
+                    (*selected + 1) % len
+                } else {
+                    (*selected + len - 1) % len
+                };
+            }
+        }
     }
 
-    fn preview_visible_height(&self) -> usize {
-        self.preview_area.height.saturating_sub(2).max(1) as usize
+    /// Ask for the picked scaffold's variables. A manifest that doesn't
+    /// parse is reported here, before anything is written.
+    pub fn scaffold_pick(&mut self) {
+        let (scaffold, dest) = match &self.mode {
+            AppMode::Dialog(DialogKind::ScaffoldPicker {
+                scaffolds,
+                selected,
+                dest,
+            }) => match scaffolds.get(*selected) {
+                Some(scaffold) => (scaffold.clone(), dest.clone()),
+                None => return,
+            },
+            _ => return,
+        };
+        match scaffold.variables() {
+            Ok(variables) => {
+                self.mode = AppMode::Dialog(DialogKind::ScaffoldForm {
+                    form: Box::new(ScaffoldForm::new(scaffold, dest, variables)),
+                });
+            }
+            Err(e) => self.open_dialog(DialogKind::Error {
+                message: format!("Scaffold {}: {}", scaffold.name(), e),
+            }),
+        }
     }
 
-    fn preview_line_count(&self) -> usize {
-        if self.preview_state.content_lines.is_empty() {
-            self.preview_state.total_lines.max(1)
-        } else {
-            self.preview_state.content_lines.len()
+    /// The open scaffold form, if any.
+    pub fn scaffold_form_mut(&mut self) -> Option<&mut ScaffoldForm> {
+        match &mut self.mode {
+            AppMode::Dialog(DialogKind::ScaffoldForm { form }) => Some(form),
+            _ => None,
         }
     }
 
-    /// Enter edit mode for the currently previewed file.
-    /// Returns false if editing is not possible (binary, directory, etc.).
-    pub fn enter_edit_mode(&mut self) -> bool {
-        // Must be in Normal mode with preview focused
-        if self.mode != AppMode::Normal || self.focused_panel != FocusedPanel::Preview {
-            return false;
-        }
+    /// Instantiate the scaffold with the entered values on a background
+    /// task. Targets that already exist stop it in the form, listed, until
+    /// it is confirmed again with `force`, which fills in existing
+    /// directories and overwrites existing files.
+    pub fn scaffold_confirm(
+        &mut self,
+        force: bool,
+        event_tx: mpsc::UnboundedSender<crate::event::Event>,
+    ) {
+        use crate::event::{Event, ProgressUpdate, ScaffoldResult};
 
-        let path = match &self.preview_state.current_path {
-            Some(p) => p.clone(),
-            None => return false,
+        let Some(form) = self.scaffold_form_mut() else {
+            return;
         };
-
-        // Guard: directories cannot be edited
-        if let Some(item) = self
-            .tree_state
-            .flat_items
-            .get(self.tree_state.selected_index)
-        {
-            if item.node_type == NodeType::Directory {
-                return false;
+        let vars = form.vars();
+        let plan = match scaffold::plan(&form.scaffold.root, &form.dest, &vars) {
+            Ok(plan) => plan,
+            Err(e) => {
+                form.error = Some(e.to_string());
+                return;
             }
+        };
+        if !plan.conflicts.is_empty() && !force {
+            form.conflicts = plan.conflicts;
+            return;
         }
-
-        // Guard: binary files cannot be edited
-        if crate::preview_content::is_binary_file(&path) {
-            self.set_status_message("Cannot edit binary files".to_string());
-            return false;
+        let name = form.scaffold.name();
+        let dest = form.dest.clone();
+        if !self.in_flight.begin() {
+            self.set_status_message("Operation already in progress".to_string());
+            return;
         }
+        self.prefetch.cancel_all();
 
-        // Warn about large files
-        let file_size = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
-        let max_preview = self.config.max_full_preview_bytes();
-        if file_size > max_preview {
-            self.set_status_message(format!(
-                "⚠ Large file ({:.1} KB) — editing may be slow",
-                file_size as f64 / 1024.0
-            ));
-        }
+        let total = plan.entries.len();
+        self.open_dialog(DialogKind::Progress {
+            message: "Preparing...".to_string(),
+            current: 0,
+            total: Some(total),
+            bytes_done: 0,
+            bytes_total: 0,
+            started: Instant::now(),
+            rate: None,
+            spin: 0,
+        });
 
-        // Load file into editor state
-        match EditorState::from_file(&path) {
-            Ok(state) => {
-                self.editor_state = Some(state);
-                self.mode = AppMode::Edit;
-                // Pause watcher to avoid conflicts during editing
-                self.watcher_active = false;
-                true
+        let description = format!("scaffold {} into {}", name, file_label(&dest));
+        self.tasks.spawn(
+            TaskKind::Scaffold,
+            description,
+            Priority::User,
+            move |token| async move {
+                let mut progress = |done: usize, target: &Path| {
+                    if !done.is_multiple_of(PROGRESS_STRIDE) && done != total {
+                        return;
+                    }
+                    token.progress(done, total);
+                    let _ = event_tx.send(Event::Progress(ProgressUpdate {
+                        current_file: file_label(target),
+                        current: done,
+                        total: Some(total),
+                        bytes_done: 0,
+                        bytes_total: 0,
+                    }));
+                };
+                let result =
+                    scaffold::apply(&plan, &vars, force, &mut progress, token.cancel_flag());
+                let _ = event_tx.send(Event::ScaffoldComplete(ScaffoldResult {
+                    name,
+                    dest,
+                    result,
+                }));
+            },
+        );
+    }
+
+    /// Handle a finished scaffold: show what it created, and make removing
+    /// all of it the undo.
+    pub fn handle_scaffold_complete(&mut self, done: crate::event::ScaffoldResult) {
+        self.close_dialog();
+        let next = self.in_flight.finish();
+        self.tree_state.reload_dir(&done.dest);
+        self.invalidate_search_cache();
+
+        match done.result {
+            Ok(applied) => {
+                if !applied.created.is_empty() {
+                    self.record_undo(UndoAction::CopyPaste {
+                        created_paths: applied
+                            .created
+                            .into_iter()
+                            .map(StampedPath::capture)
+                            .collect(),
+                        backups: Vec::new(),
+                    });
+                }
+                let overwrote = match applied.overwritten {
+                    0 => String::new(),
+                    1 => ", overwrote 1 file".to_string(),
+                    n => format!(", overwrote {} files", n),
+                };
+                self.set_status_message(format!(
+                    "Created {} entr{} from {}{}",
+                    applied.entries,
+                    if applied.entries == 1 { "y" } else { "ies" },
+                    done.name,
+                    overwrote
+                ));
+            }
+            Err(scaffold::ScaffoldError::Cancelled) => {
+                self.set_status_message("Scaffold cancelled".to_string());
             }
             Err(e) => {
-                self.set_status_message(format!("Cannot edit: {}", e));
-                false
+                self.set_status_message(format!("Error: scaffold {}: {}", done.name, e));
             }
         }
+
+        if let Some(next) = next {
+            self.spawn_transfer_async(next);
+        }
     }
 
-    /// Exit edit mode and return to normal mode.
-    /// Does NOT check for unsaved changes — caller should handle save confirmation.
-    pub fn exit_edit_mode(&mut self) {
-        self.editor_state = None;
-        self.mode = AppMode::Normal;
-        // Resume watcher
-        self.watcher_active = true;
-        // Force re-preview of the current file to reflect any saved changes
-        self.last_previewed_index = None;
+    // === Pre-flight sizing ===
+
+    /// Whether `paths` are counted before a paste or delete: counting is
+    /// on and one of them is a directory.
+    fn needs_preflight(&self, paths: &[PathBuf]) -> bool {
+        self.config.preflight_count()
+            && paths.iter().any(|p| {
+                std::fs::symlink_metadata(p)
+                    .map(|m| m.is_dir())
+                    .unwrap_or(false)
+            })
     }
 
-    /// Save the editor buffer to disk.
-    /// Returns Ok(()) on success or Err with message on failure.
-    pub fn save_editor_buffer(&mut self) -> std::result::Result<(), String> {
-        if let Some(ref mut editor) = self.editor_state {
-            match editor.save() {
-                Ok(()) => {
-                    self.set_status_message("File saved".to_string());
-                    Ok(())
+    /// The confirmation rules from the config, for the current root.
+    pub fn confirmation_policy(&self) -> ConfirmationPolicy {
+        ConfirmationPolicy::from_config(&self.config, &self.tree_state.root.path)
+    }
+
+    /// Count `paths` on a background task, finishing with
+    /// `PreflightComplete`. A count still running is cancelled.
+    fn spawn_preflight(
+        &mut self,
+        paths: Vec<PathBuf>,
+        event_tx: mpsc::UnboundedSender<crate::event::Event>,
+    ) {
+        use crate::event::Event;
+
+        if let Some(task) = self.preflight_task {
+            self.tasks.cancel(task);
+        }
+        self.preflight_id += 1;
+        let id = self.preflight_id;
+        let description = format!("count {}", item_count(paths.len()));
+        let token = self.tasks.spawn(
+            TaskKind::Preflight,
+            description,
+            Priority::User,
+            move |token| async move {
+                let usage = operations::disk_usage_until(
+                    &paths,
+                    PREFLIGHT_MAX_ENTRIES,
+                    token.cancel_flag(),
+                );
+                if !token.is_cancelled() {
+                    let _ = event_tx.send(Event::PreflightComplete { id, usage });
                 }
-                Err(e) => {
-                    let msg = format!("Save failed: {}", e);
-                    self.set_status_message(msg.clone());
-                    Err(msg)
+            },
+        );
+        self.preflight_task = Some(token.id());
+    }
+
+    /// Stop the running pre-flight count and drop the paste waiting on it.
+    pub fn cancel_preflight(&mut self) {
+        if let Some(task) = self.preflight_task.take() {
+            self.tasks.cancel(task);
+        }
+        self.preflight_transfer = None;
+    }
+
+    /// Handle a finished pre-flight count: size the waiting confirmation,
+    /// or start an operation the policy lets through once counted.
+    pub fn handle_preflight_complete(
+        &mut self,
+        id: u64,
+        usage: DiskUsage,
+        event_tx: &mpsc::UnboundedSender<crate::event::Event>,
+    ) {
+        if id != self.preflight_id {
+            return;
+        }
+        let policy = self.confirmation_policy();
+        // The delete to start if the policy lets it through
+        let (decision, delete) = match &self.mode {
+            AppMode::Dialog(DialogKind::DeleteConfirm {
+                targets,
+                sizing: Sizing::Counting { .. },
+                to_trash,
+                ..
+            }) => (
+                policy.decide(&Intent::Delete { targets }, Some(usage)),
+                Some((targets.clone(), *to_trash)),
+            ),
+            AppMode::Dialog(DialogKind::TransferConfirm {
+                sizing: Sizing::Counting { .. },
+                ..
+            }) => match &self.preflight_transfer {
+                Some(transfer) => (
+                    policy.decide(
+                        &Intent::Paste {
+                            sources: &transfer.paths,
+                            dest: &transfer.dest_dir,
+                        },
+                        Some(usage),
+                    ),
+                    None,
+                ),
+                None => return,
+            },
+            _ => return,
+        };
+        let counted = Sizing::Counted {
+            usage,
+            large: decision
+                == Decision::Confirm {
+                    level: Level::Twice,
+                },
+            armed: false,
+        };
+        if let AppMode::Dialog(
+            DialogKind::DeleteConfirm { sizing, .. } | DialogKind::TransferConfirm { sizing, .. },
+        ) = &mut self.mode
+        {
+            *sizing = counted;
+        }
+        if decision == Decision::Proceed {
+            match delete {
+                Some((targets, to_trash)) => {
+                    self.delete_async(targets, counted.entries(), to_trash, event_tx.clone())
                 }
+                None => self.confirm_transfer(),
             }
-        } else {
-            Err("No editor state".to_string())
         }
     }
 
-    /// Update preview content when the selected tree item changes.
-    pub fn update_preview(&mut self) {
-        let idx = self.tree_state.selected_index;
-        if self.last_previewed_index == Some(idx) {
-            return; // No change
+    /// First `y` on a large operation: the next Enter goes ahead.
+    pub fn arm_large_confirm(&mut self) {
+        if let AppMode::Dialog(
+            DialogKind::DeleteConfirm {
+                sizing: Sizing::Counted { armed, .. },
+                ..
+            }
+            | DialogKind::TransferConfirm {
+                sizing: Sizing::Counted { armed, .. },
+                ..
+            },
+        ) = &mut self.mode
+        {
+            *armed = true;
         }
-        self.last_previewed_index = Some(idx);
+    }
 
-        let item = match self.tree_state.flat_items.get(idx) {
-            Some(item) => item,
-            None => return,
-        };
+    /// Handle a finished delete: refresh the affected directories and
+    /// report per-item results. The multi-selection is cleared only when
+    /// everything was deleted; otherwise it is narrowed to what is left.
+    pub fn handle_delete_complete(&mut self, result: crate::event::DeleteResult) {
+        self.close_dialog();
+        let next = self.in_flight.finish();
 
-        // Check if we're reloading the same path (e.g., after FS watcher event).
-        // If so, preserve the current scroll offset.
-        let same_path = self
-            .preview_state
-            .current_path
-            .as_ref()
-            .map(|p| p == &item.path)
-            .unwrap_or(false);
-        let preserved_scroll = if same_path {
-            self.preview_state.scroll_offset
-        } else {
-            0
-        };
+        self.reload_parents(result.targets.iter());
+        self.invalidate_search_cache();
 
-        // Only preview files, not directories
-        if item.node_type == NodeType::Directory {
-            let path = item.path.clone();
-            let (lines, total) = preview_content::load_directory_summary(&path);
-            self.preview_state = PreviewState {
-                current_path: Some(path),
-                content_lines: lines,
-                scroll_offset: preserved_scroll,
-                view_mode: ViewMode::default(),
-                line_wrap: false,
-                total_lines: total,
-                is_large_file: false,
-                head_lines: self.config.head_lines(),
-                tail_lines: self.config.tail_lines(),
+        let to_trash = !result.trashed.is_empty();
+        if to_trash {
+            self.record_undo(UndoAction::Trash {
+                entries: result.trashed,
+            });
+        }
+
+        if result.errors.is_empty() {
+            self.tree_state.clear_multi_select();
+            let names: Vec<String> = result
+                .deleted
+                .iter()
+                .filter_map(|t| t.file_name().map(|n| n.to_string_lossy().to_string()))
+                .collect();
+            let label = if to_trash {
+                "Moved to trash"
+            } else {
+                "Deleted"
             };
-            self.clamp_preview_scroll();
-            return;
+            self.set_status_message(format!("{}: {}", label, names.join(", ")));
+        } else {
+            self.tree_state.multi_selected = result
+                .targets
+                .iter()
+                .filter(|t| !result.deleted.contains(t))
+                .filter_map(|t| self.tree_state.find_index_by_path(t))
+                .collect();
+            self.tree_state.touch();
+            let label = if to_trash {
+                "moved to trash"
+            } else {
+                "deleted"
+            };
+            self.set_status_message(format!(
+                "Error: {} {} of {}; {}",
+                label,
+                result.deleted.len(),
+                result.targets.len(),
+                describe_errors(&result.errors)
+            ));
+            // Targets another program has open may come free shortly
+            let busy = result.errors.iter().find(|e| e.is_sharing_violation());
+            let cancelled = result
+                .errors
+                .iter()
+                .any(|e| matches!(e, OperationError::Cancelled));
+            if let (Some(err), false, None) = (busy, cancelled, &next) {
+                let paths = result
+                    .targets
+                    .iter()
+                    .filter(|t| !result.deleted.contains(t))
+                    .cloned()
+                    .collect();
+                let op = RetryOp::Delete {
+                    paths,
+                    to_trash: result.to_trash,
+                };
+                self.offer_retry(op, err);
+            }
         }
 
-        if item.node_type != NodeType::File {
-            self.preview_state = PreviewState::default();
-            return;
+        if let Some(next) = next {
+            self.spawn_transfer_async(next);
         }
+    }
 
-        let path = item.path.clone();
+    // === Busy item retry ===
 
-        // Check for notebook files
-        if path.extension().and_then(|e| e.to_str()) == Some("ipynb") {
-            let (lines, total) =
-                preview_content::load_notebook_content(&path, &self.syntax_set, &self.syntax_theme);
-            self.preview_state = PreviewState {
-                current_path: Some(path),
-                content_lines: lines,
-                scroll_offset: preserved_scroll,
-                view_mode: ViewMode::default(),
-                line_wrap: false,
-                total_lines: total,
-                is_large_file: false,
-                head_lines: self.config.head_lines(),
-                tail_lines: self.config.tail_lines(),
-            };
-            self.clamp_preview_scroll();
-            return;
-        }
+    /// Offer the `BusyRetry` dialog for `op`, which failed with `err`.
+    pub fn offer_retry(&mut self, op: RetryOp, err: &OperationError) {
+        self.open_dialog(DialogKind::BusyRetry {
+            op,
+            message: err.user_message(),
+            attempt: None,
+        });
+    }
 
-        // Check if binary file
-        if preview_content::is_binary_file(&path) {
-            let (lines, total) = preview_content::load_binary_metadata(&path);
-            self.preview_state = PreviewState {
-                current_path: Some(path),
-                content_lines: lines,
-                scroll_offset: preserved_scroll,
-                view_mode: ViewMode::default(),
-                line_wrap: false,
-                total_lines: total,
-                is_large_file: false,
-                head_lines: self.config.head_lines(),
-                tail_lines: self.config.tail_lines(),
-            };
-            self.clamp_preview_scroll();
+    /// Retry the operation of the open `BusyRetry` dialog on a background
+    /// task: once at once, or (`auto`) every `retry::RETRY_DELAY` up to
+    /// `retry::MAX_AUTO_ATTEMPTS` times while the item stays in use.
+    pub fn retry_busy(
+        &mut self,
+        auto: bool,
+        event_tx: &mpsc::UnboundedSender<crate::event::Event>,
+    ) {
+        use crate::event::{Event, RetryResult};
+
+        let AppMode::Dialog(DialogKind::BusyRetry { op, attempt, .. }) = &mut self.mode else {
+            return;
+        };
+        if attempt.is_some() {
             return;
         }
+        let (attempts, delay) = if auto {
+            (retry::MAX_AUTO_ATTEMPTS, retry::RETRY_DELAY)
+        } else {
+            (1, Duration::ZERO)
+        };
+        *attempt = Some((0, attempts));
+        let mut remaining = op.clone();
+        let trash = match op {
+            RetryOp::Delete { to_trash: true, .. } => self.trash.clone(),
+            _ => None,
+        };
 
-        // Check file size for large-file mode (using config values)
-        let file_size = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
-        let max_preview = self.config.max_full_preview_bytes();
-        let head = self.config.head_lines();
-        let tail = self.config.tail_lines();
+        let job = self.busy_retry.next_job();
+        let tx = event_tx.clone();
+        let description = format!("retry {}", remaining.describe());
+        let token = self.tasks.spawn(
+            TaskKind::Retry,
+            description,
+            Priority::User,
+            move |token| async move {
+                let cancel = Arc::clone(token.cancel_flag());
+                let _ = tokio::task::spawn_blocking(move || {
+                    let mut deleted = Vec::new();
+                    let mut trashed = Vec::new();
+                    let result = retry::run(
+                        &mut || match &mut remaining {
+                            RetryOp::Rename { from, to } => operations::rename(from, to),
+                            RetryOp::Delete { paths, .. } => {
+                                while let Some(path) = paths.first() {
+                                    match &trash {
+                                        Some(trash) => trashed.push(trash.trash(path)?),
+                                        None => operations::delete(path)?,
+                                    }
+                                    deleted.push(paths.remove(0));
+                                }
+                                Ok(())
+                            }
+                        },
+                        attempts,
+                        delay,
+                        &cancel,
+                        &mut retry::wait,
+                        &mut |attempt| {
+                            let _ = tx.send(Event::RetryAttempt { job, attempt });
+                        },
+                    );
+                    let _ = tx.send(Event::RetryComplete(RetryResult {
+                        job,
+                        op: remaining,
+                        deleted,
+                        trashed,
+                        result,
+                    }));
+                })
+                .await;
+            },
+        );
+        self.busy_retry.begin(job, Arc::clone(token.cancel_flag()));
+    }
 
-        let is_large = file_size > max_preview;
+    /// Stop an automatic retry, leaving the dialog's choices.
+    pub fn stop_retry(&mut self) {
+        self.busy_retry.cancel();
+        if let AppMode::Dialog(DialogKind::BusyRetry { attempt, .. }) = &mut self.mode {
+            *attempt = None;
+        }
+    }
 
-        if is_large {
-            let (lines, total) = preview_content::load_head_tail_content(
-                &path,
-                &self.syntax_set,
-                &self.syntax_theme,
-                head,
-                tail,
-                ViewMode::HeadAndTail,
-            );
-            self.preview_state = PreviewState {
-                current_path: Some(path),
-                content_lines: lines,
-                scroll_offset: preserved_scroll,
-                view_mode: ViewMode::HeadAndTail,
-                line_wrap: false,
-                total_lines: total,
-                is_large_file: true,
-                head_lines: head,
-                tail_lines: tail,
-            };
-        } else {
-            let (lines, total) = preview_content::load_highlighted_content(
-                &path,
-                &self.syntax_set,
-                &self.syntax_theme,
-            );
-            self.preview_state = PreviewState {
-                current_path: Some(path),
-                content_lines: lines,
-                scroll_offset: preserved_scroll,
-                view_mode: ViewMode::default(),
-                line_wrap: false,
-                total_lines: total,
-                is_large_file: false,
-                head_lines: head,
-                tail_lines: tail,
-            };
+    /// Give up on the operation of the `BusyRetry` dialog.
+    pub fn skip_retry(&mut self) {
+        self.busy_retry.cancel();
+        if let AppMode::Dialog(DialogKind::BusyRetry { op, .. }) = &self.mode {
+            let skipped = op.describe();
+            self.close_dialog();
+            self.set_status_message(format!("Skipped: {}", skipped));
         }
-        self.clamp_preview_scroll();
     }
 
-    /// Cycle view mode for large file preview (Ctrl+T).
-    #[allow(dead_code)]
-    pub fn cycle_view_mode(&mut self) {
-        if !self.preview_state.is_large_file {
+    /// Show which attempt a running retry is on.
+    pub fn handle_retry_attempt(&mut self, job: u64, number: usize) {
+        if !self.busy_retry.is_current(job) {
             return;
         }
-        self.preview_state.view_mode = match self.preview_state.view_mode {
-            ViewMode::HeadAndTail => ViewMode::HeadOnly,
-            ViewMode::HeadOnly => ViewMode::TailOnly,
-            ViewMode::TailOnly => ViewMode::HeadAndTail,
-        };
-        self.reload_large_preview();
+        if let AppMode::Dialog(DialogKind::BusyRetry {
+            attempt: Some((current, _)),
+            ..
+        }) = &mut self.mode
+        {
+            *current = number;
+        }
     }
 
-    /// Adjust head/tail line counts by a delta (+/- keys).
-    pub fn adjust_preview_lines(&mut self, delta: isize) {
-        if !self.preview_state.is_large_file {
+    /// Finish a retry: apply what it did, then close the dialog, or offer
+    /// the retry again while the item is still in use.
+    pub fn handle_retry_complete(&mut self, result: crate::event::RetryResult) {
+        if !self.busy_retry.finish(result.job) {
             return;
         }
-        let step = delta.unsigned_abs();
-        if delta > 0 {
-            self.preview_state.head_lines += step;
-            self.preview_state.tail_lines += step;
-        } else {
-            self.preview_state.head_lines =
-                self.preview_state.head_lines.saturating_sub(step).max(5);
-            self.preview_state.tail_lines =
-                self.preview_state.tail_lines.saturating_sub(step).max(5);
+        if let RetryOp::Delete { .. } = result.op {
+            self.apply_retried_delete(result.deleted, result.trashed);
+        }
+        let showing = matches!(self.mode, AppMode::Dialog(DialogKind::BusyRetry { .. }));
+        match result.result {
+            Ok(()) => {
+                if showing {
+                    self.close_dialog();
+                }
+                match result.op {
+                    RetryOp::Rename { from, to } => self.apply_retried_rename(from, to),
+                    RetryOp::Delete { to_trash, .. } => {
+                        self.tree_state.clear_multi_select();
+                        let label = if to_trash {
+                            "Moved to trash"
+                        } else {
+                            "Deleted"
+                        };
+                        self.set_status_message(format!("{} after retrying", label));
+                    }
+                }
+            }
+            Err(OperationError::Cancelled) => self.stop_retry(),
+            Err(e) if e.is_sharing_violation() => self.offer_retry(result.op, &e),
+            Err(e) => {
+                if showing {
+                    self.close_dialog();
+                }
+                self.set_status_message(format!("Error: {}", e.user_message()));
+            }
         }
-        self.reload_large_preview();
     }
 
-    /// Reload the large file preview with current settings.
-    fn reload_large_preview(&mut self) {
-        if let Some(ref path) = self.preview_state.current_path {
-            let path = path.clone();
-            let (lines, total) = preview_content::load_head_tail_content(
-                &path,
-                &self.syntax_set,
-                &self.syntax_theme,
-                self.preview_state.head_lines,
-                self.preview_state.tail_lines,
-                self.preview_state.view_mode,
-            );
-            self.preview_state.content_lines = lines;
-            self.preview_state.total_lines = total;
-            self.preview_state.scroll_offset = 0;
+    fn apply_retried_rename(&mut self, from: PathBuf, to: PathBuf) {
+        let name = file_label(&to);
+        let parent = to.parent().map(Path::to_path_buf);
+        self.record_undo(UndoAction::Rename {
+            from,
+            to: StampedPath::capture(to),
+        });
+        self.set_status_message(format!("Renamed to: {}", name));
+        if let Some(parent) = parent {
+            self.tree_state.reload_dir(&parent);
         }
+        self.invalidate_search_cache();
     }
 
-    /// Quit the application.
-    pub fn quit(&mut self) {
-        self.should_quit = true;
+    /// Refresh after a retried delete removed `deleted`, and make its moves
+    /// to the trash undoable.
+    fn apply_retried_delete(&mut self, deleted: Vec<PathBuf>, trashed: Vec<TrashedEntry>) {
+        if !trashed.is_empty() {
+            self.record_undo(UndoAction::Trash { entries: trashed });
+        }
+        self.reload_parents(deleted.iter());
+        if !deleted.is_empty() {
+            self.invalidate_search_cache();
+        }
     }
 
-    /// Move selection down by one item.
-    pub fn select_next(&mut self) {
-        let len = self.tree_state.flat_items.len();
-        if len > 0 && self.tree_state.selected_index < len - 1 {
-            self.tree_state.selected_index += 1;
-        }
-    }
+    // === Send to ===
 
-    /// Move selection up by one item.
-    pub fn select_previous(&mut self) {
-        if self.tree_state.selected_index > 0 {
-            self.tree_state.selected_index -= 1;
-        }
+    /// Open the "send to" overlay, checking every configured target.
+    pub fn open_send_to(&mut self) {
+        self.send_to_state = SendToState {
+            targets: send_to::resolve_targets(&self.config.send_to),
+            selected_index: 0,
+        };
+        self.mode = AppMode::SendTo;
     }
 
-    /// Jump to the first item.
-    pub fn select_first(&mut self) {
-        self.tree_state.selected_index = 0;
+    /// Close the "send to" overlay.
+    pub fn close_send_to(&mut self) {
+        self.mode = AppMode::Normal;
     }
 
-    /// Jump to the last item.
-    pub fn select_last(&mut self) {
-        let len = self.tree_state.flat_items.len();
-        if len > 0 {
-            self.tree_state.selected_index = len - 1;
-        }
+    /// Move the overlay selection down (wraps), re-checking the new target.
+    pub fn send_to_select_next(&mut self) {
+        let rows = self.send_to_state.targets.len() + 1;
+        self.send_to_state.selected_index = (self.send_to_state.selected_index + 1) % rows;
+        self.refresh_selected_send_to_target();
     }
 
-    /// Expand the selected directory (or no-op on files).
-    pub fn expand_selected(&mut self) {
-        self.tree_state.expand_selected();
-        self.invalidate_search_cache();
+    /// Move the overlay selection up (wraps), re-checking the new target.
+    pub fn send_to_select_previous(&mut self) {
+        let rows = self.send_to_state.targets.len() + 1;
+        self.send_to_state.selected_index = (self.send_to_state.selected_index + rows - 1) % rows;
+        self.refresh_selected_send_to_target();
     }
 
-    /// Collapse the selected directory, or jump to parent if on a file or collapsed directory.
-    pub fn collapse_selected(&mut self) {
-        self.tree_state.collapse_selected();
+    fn refresh_selected_send_to_target(&mut self) {
+        let idx = self.send_to_state.selected_index;
+        if let Some(target) = self.send_to_state.targets.get_mut(idx) {
+            target.refresh();
+        }
     }
 
-    /// Toggle hidden file visibility.
-    pub fn toggle_hidden(&mut self) {
-        self.tree_state.toggle_hidden();
-        self.invalidate_search_cache();
+    /// Act on the selected overlay row: send the selected/multi-selected items
+    /// to the target (moving when `move_items`), or add the current directory
+    /// as a new target.
+    pub fn send_to_confirm(
+        &mut self,
+        move_items: bool,
+        event_tx: mpsc::UnboundedSender<crate::event::Event>,
+    ) {
+        if self.send_to_state.add_entry_selected() {
+            match crate::config::writable_config_path() {
+                Some(config_path) => self.add_current_dir_as_send_to_target(&config_path),
+                None => self.set_status_message("Error: no config directory found".to_string()),
+            }
+            return;
+        }
+
+        self.refresh_selected_send_to_target();
+        let target = match self
+            .send_to_state
+            .targets
+            .get(self.send_to_state.selected_index)
+        {
+            Some(t) => t.clone(),
+            None => return,
+        };
+        if !target.status.is_usable() {
+            self.set_status_message(format!(
+                "Error: send-to target '{}' is unavailable ({})",
+                target.name,
+                target.status.label()
+            ));
+            return;
+        }
+
+        let paths: Vec<PathBuf> = self
+            .collect_target_paths()
+            .into_iter()
+            .filter(|p| p.parent().is_some() && p != &self.tree_state.root.path)
+            .collect();
+        if paths.is_empty() {
+            self.close_send_to();
+            self.set_status_message("Nothing to send".to_string());
+            return;
+        }
+
+        self.mode = AppMode::Normal;
+        // Picking a destination is deliberate, so wait for a running paste
+        self.start_transfer(
+            PendingTransfer {
+                paths,
+                dest_dir: target.path,
+                was_cut: move_items,
+                send_to: Some(target.name),
+                entries: None,
+                bytes: None,
+                overwrite: Vec::new(),
+                backup: None,
+                event_tx,
+            },
+            WhenBusy::Queue,
+        );
     }
 
-    // === Search (Ctrl+P) methods ===
+    /// Register the current directory as a send-to target, appending it to
+    /// the `[send_to]` table of `config_path` and to the live config.
+    pub fn add_current_dir_as_send_to_target(&mut self, config_path: &Path) {
+        let dir = self.current_dir();
+        if let Some(existing) = self.send_to_state.targets.iter().find(|t| t.path == dir) {
+            let msg = format!("'{}' is already a send-to target", existing.name);
+            self.set_status_message(msg);
+            return;
+        }
 
-    /// Open the fuzzy finder overlay.
-    pub fn open_search(&mut self) {
-        // Build path index lazily if not cached
-        if self.search_state.cached_paths.is_none() {
-            self.search_state.cached_paths = Some(self.build_path_index());
+        let name = send_to::unique_target_name(&dir, &self.config.send_to);
+        match crate::config::append_send_to_target(config_path, &name, &dir) {
+            Ok(()) => {
+                self.config
+                    .send_to
+                    .insert(name.clone(), dir.to_string_lossy().to_string());
+                self.send_to_state.targets = send_to::resolve_targets(&self.config.send_to);
+                self.send_to_state.selected_index = self
+                    .send_to_state
+                    .targets
+                    .iter()
+                    .position(|t| t.name == name)
+                    .unwrap_or(0);
+                self.set_status_message(format!(
+                    "Added send-to target '{}' ({})",
+                    name,
+                    config_path.display()
+                ));
+            }
+            Err(e) => self.set_status_message(format!("Error: {}", e)),
         }
-        self.search_state.query.clear();
-        self.search_state.cursor_position = 0;
-        self.search_state.results.clear();
-        self.search_state.selected_index = 0;
-        self.mode = AppMode::Search;
     }
 
-    /// Close the fuzzy finder overlay without navigating.
-    pub fn close_search(&mut self) {
+    // === Places ===
+
+    /// Open the places overlay. Mounts and free space are re-read on every
+    /// open, so drives plugged in since the last one show up.
+    pub fn open_places(&mut self) {
+        let places = places::collect(self.mount_source.as_ref(), &self.config.bookmarks);
+        let root = &self.tree_state.root.path;
+        let selected_index = places.iter().position(|p| &p.path == root).unwrap_or(0);
+        self.places_state = PlacesState {
+            places,
+            selected_index,
+        };
+        self.mode = AppMode::Places;
+    }
+
+    /// Close the places overlay.
+    pub fn close_places(&mut self) {
         self.mode = AppMode::Normal;
-        // Filesystem events were silently dropped while in Search mode,
-        // so invalidate the cache so the next open_search() rebuilds it.
-        self.invalidate_search_cache();
-        // Force preview refresh in case selection changed externally.
-        self.last_previewed_index = None;
     }
 
-    /// Insert a character into the search query and re-score.
-    pub fn search_input_char(&mut self, c: char) {
-        self.search_state
-            .query
-            .insert(self.search_state.cursor_position, c);
-        self.search_state.cursor_position += c.len_utf8();
-        self.update_search_results();
+    /// Move the overlay selection down (wraps).
+    pub fn places_select_next(&mut self) {
+        let rows = self.places_state.places.len().max(1);
+        self.places_state.selected_index = (self.places_state.selected_index + 1) % rows;
     }
 
-    /// Delete the character before the cursor in the search query.
-    pub fn search_delete_char(&mut self) {
-        if self.search_state.cursor_position > 0 {
-            let byte_pos = self.search_state.cursor_position;
-            let prev_char = self.search_state.query[..byte_pos]
-                .chars()
-                .next_back()
-                .expect("cursor > 0 guarantees at least one char");
-            self.search_state.cursor_position -= prev_char.len_utf8();
-            self.search_state
-                .query
-                .remove(self.search_state.cursor_position);
-            self.update_search_results();
-        }
+    /// Move the overlay selection up (wraps).
+    pub fn places_select_previous(&mut self) {
+        let rows = self.places_state.places.len().max(1);
+        self.places_state.selected_index = (self.places_state.selected_index + rows - 1) % rows;
     }
 
-    /// Move search result selection down.
-    pub fn search_select_next(&mut self) {
-        if !self.search_state.results.is_empty()
-            && self.search_state.selected_index < self.search_state.results.len() - 1
-        {
-            self.search_state.selected_index += 1;
+    /// Open the selected place: select it when it lies under the current
+    /// root, otherwise re-root the tree there.
+    pub fn places_confirm(&mut self) {
+        let idx = self.places_state.selected_index;
+        let place = match self.places_state.places.get_mut(idx) {
+            Some(place) => {
+                place.status = places::check_place(&place.path);
+                place.clone()
+            }
+            None => return,
+        };
+        if !place.status.is_usable() {
+            self.set_status_message(format!(
+                "Error: '{}' is unavailable ({})",
+                place.label,
+                place.status.label()
+            ));
+            return;
         }
+
+        self.close_places();
+        self.open_dir(&place.path);
     }
 
-    /// Move search result selection up.
-    pub fn search_select_previous(&mut self) {
-        if self.search_state.selected_index > 0 {
-            self.search_state.selected_index -= 1;
+    /// Show directory `path`: select it when it lies under the current
+    /// root, otherwise re-root the tree there.
+    fn open_dir(&mut self, path: &Path) {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let root = self.tree_state.root.path.clone();
+        if path == root {
+            self.tree_state.selected_index = 0;
+        } else if path.starts_with(&root) {
+            self.navigate_to_path(&path);
+        } else {
+            match self.reroot(&path) {
+                Ok(()) => self.set_status_message(format!("Opened {}", path.display())),
+                Err(e) => self.set_status_message(format!("Error: {}", e)),
+            }
         }
     }
 
-    /// Confirm the selected search result: open action menu for that file.
-    pub fn search_confirm(&mut self) {
-        if let Some(result) = self
-            .search_state
-            .results
-            .get(self.search_state.selected_index)
+    // === Bookmarks ===
+
+    /// Bookmark the selected directory.
+    pub fn add_bookmark(&mut self) {
+        let item = match self
+            .tree_state
+            .flat_items
+            .get(self.tree_state.selected_index)
         {
-            let path = result.path.clone();
-            let display = result.display.clone();
-            let (is_directory, is_binary) = Self::detect_file_type(&path);
-            self.search_action_state = Some(SearchActionState {
-                path,
-                display,
-                is_directory,
-                is_binary,
-            });
-            self.mode = AppMode::SearchAction;
+            Some(item) if item.node_type == NodeType::Directory => item,
+            _ => {
+                self.set_status_message("Only directories can be bookmarked".to_string());
+                return;
+            }
+        };
+        let path = item
+            .path
+            .canonicalize()
+            .unwrap_or_else(|_| item.path.clone());
+        if !self.bookmarks.add(&path) {
+            self.set_status_message(format!("Already bookmarked: {}", path.display()));
+            return;
+        }
+        match self.save_bookmarks() {
+            Ok(()) => self.set_status_message(format!("Bookmarked {}", path.display())),
+            Err(e) => self.set_status_message(format!("Error: could not save bookmarks: {}", e)),
         }
     }
 
-    /// Detect whether a path is a directory and/or a binary file.
-    pub fn detect_file_type(path: &Path) -> (bool, bool) {
-        let is_directory = path.is_dir();
-        let is_binary = if is_directory {
-            false
-        } else {
-            crate::preview_content::is_binary_file(path)
-        };
-        (is_directory, is_binary)
+    /// Write the bookmarks to `config_dir`, if set.
+    fn save_bookmarks(&self) -> std::io::Result<()> {
+        match &self.config_dir {
+            Some(dir) => self.bookmarks.save(dir),
+            None => Ok(()),
+        }
     }
 
-    /// Return from the action menu to the search overlay (preserving query/results).
-    pub fn search_action_back(&mut self) {
-        self.search_action_state = None;
-        self.mode = AppMode::Search;
+    /// Open the bookmarks overlay, checking which bookmarks still exist.
+    pub fn open_bookmarks(&mut self) {
+        let entries: Vec<BookmarkEntry> = self
+            .bookmarks
+            .paths()
+            .iter()
+            .map(|path| BookmarkEntry {
+                path: path.clone(),
+                status: places::check_place(path),
+            })
+            .collect();
+        let root = &self.tree_state.root.path;
+        let selected_index = entries.iter().position(|e| &e.path == root).unwrap_or(0);
+        self.bookmarks_state = BookmarksState {
+            entries,
+            selected_index,
+        };
+        self.mode = AppMode::Bookmarks;
     }
 
-    /// Close both search action and search overlays, return to Normal mode.
-    pub fn close_search_action(&mut self) {
-        self.search_action_state = None;
+    /// Close the bookmarks overlay.
+    pub fn close_bookmarks(&mut self) {
         self.mode = AppMode::Normal;
-        self.invalidate_search_cache();
-        self.last_previewed_index = None;
     }
 
-    /// Search action: navigate to the file in the tree.
-    pub fn search_action_navigate(&mut self) {
-        if let Some(state) = self.search_action_state.take() {
-            self.mode = AppMode::Normal;
-            self.invalidate_search_cache();
-            self.last_previewed_index = None;
-            self.navigate_to_path(&state.path);
-        }
+    /// Move the overlay selection down (wraps).
+    pub fn bookmarks_select_next(&mut self) {
+        let rows = self.bookmarks_state.entries.len().max(1);
+        self.bookmarks_state.selected_index = (self.bookmarks_state.selected_index + 1) % rows;
     }
 
-    /// Search action: navigate to file and focus preview panel.
-    pub fn search_action_preview(&mut self) {
-        if let Some(state) = self.search_action_state.take() {
-            self.mode = AppMode::Normal;
-            self.invalidate_search_cache();
-            self.last_previewed_index = None;
-            self.navigate_to_path(&state.path);
-            self.focused_panel = FocusedPanel::Preview;
+    /// Move the overlay selection up (wraps).
+    pub fn bookmarks_select_previous(&mut self) {
+        let rows = self.bookmarks_state.entries.len().max(1);
+        self.bookmarks_state.selected_index =
+            (self.bookmarks_state.selected_index + rows - 1) % rows;
+    }
+
+    /// Jump to the selected bookmark.
+    pub fn bookmarks_confirm(&mut self) {
+        let idx = self.bookmarks_state.selected_index;
+        let entry = match self.bookmarks_state.entries.get_mut(idx) {
+            Some(entry) => {
+                entry.status = places::check_place(&entry.path);
+                entry.clone()
+            }
+            None => return,
+        };
+        if !entry.status.is_usable() {
+            self.set_status_message(format!(
+                "Error: '{}' is unavailable ({}); d deletes the bookmark",
+                entry.path.display(),
+                entry.status.label()
+            ));
+            return;
         }
+        self.close_bookmarks();
+        self.open_dir(&entry.path);
     }
 
-    /// Search action: navigate to file and enter edit mode.
-    pub fn search_action_edit(&mut self) {
-        if let Some(state) = self.search_action_state.take() {
-            self.mode = AppMode::Normal;
-            self.invalidate_search_cache();
-            self.last_previewed_index = None;
-            self.navigate_to_path(&state.path);
-            self.focused_panel = FocusedPanel::Preview;
-            // Force preview update so enter_edit_mode can find the file
-            self.update_preview();
-            self.enter_edit_mode();
+    /// Delete the selected bookmark.
+    pub fn bookmarks_delete_selected(&mut self) {
+        let idx = self.bookmarks_state.selected_index;
+        let path = match self.bookmarks_state.entries.get(idx) {
+            Some(entry) => entry.path.clone(),
+            None => return,
+        };
+        self.bookmarks_state.entries.remove(idx);
+        if let Some(pos) = self.bookmarks.paths().iter().position(|p| p == &path) {
+            self.bookmarks.remove(pos);
+        }
+        let rows = self.bookmarks_state.entries.len();
+        self.bookmarks_state.selected_index = idx.min(rows.saturating_sub(1));
+        match self.save_bookmarks() {
+            Ok(()) => self.set_status_message(format!("Removed bookmark {}", path.display())),
+            Err(e) => self.set_status_message(format!("Error: could not save bookmarks: {}", e)),
         }
     }
 
-    /// Search action: copy the absolute path to the system clipboard.
-    pub fn search_action_copy_path(&mut self) {
-        if let Some(state) = self.search_action_state.take() {
-            let path_str = state.path.to_string_lossy().to_string();
-            match copy_to_system_clipboard(&path_str) {
-                Ok(()) => {
-                    self.set_status_message(format!("📋 Path copied: {}", path_str));
-                }
-                Err(msg) => {
-                    self.set_status_message(format!("📋 {}: {}", msg, path_str));
-                }
+    /// Re-root the tree at `path`, keeping view settings (hidden files,
+    /// sorting, depth limit) and dropping state tied to the old tree.
+    pub fn reroot(&mut self, path: &Path) -> Result<()> {
+        let tree_state = self.tree_like_current(path)?;
+        self.work.clear(&self.tasks);
+        self.tree_state = tree_state;
+        self.forget_root();
+        Ok(())
+    }
+
+    /// A tree rooted at `path` with the view settings of the current one:
+    /// hidden and ignored files, sort, depth limit and per-directory sort
+    /// overrides.
+    fn tree_like_current(&self, path: &Path) -> Result<TreeState> {
+        let defer_stats = self
+            .config
+            .deferred_stats()
+            .defers(path, &self.mount_source.mounts());
+        let mut tree_state = TreeState::with_options(path, self.tree_state.page_size, defer_stats)?;
+        tree_state.show_hidden = self.tree_state.show_hidden;
+        tree_state.sort_by = self.tree_state.sort_by.clone();
+        tree_state.dirs_first = self.tree_state.dirs_first;
+        tree_state.sort_overrides = self.tree_state.sort_overrides.clone();
+        tree_state.max_depth = self.tree_state.max_depth;
+        tree_state.set_respect_gitignore(self.tree_state.respect_gitignore);
+        tree_state.sort_all_children();
+        tree_state.flatten();
+        Ok(tree_state)
+    }
+
+    /// Drop what was kept about the rows of the tree just replaced.
+    fn forget_root(&mut self) {
+        self.stat_state.reset();
+        self.flash = FlashMap::with_capacity(self.config.map_capacities().flash);
+        self.changed_anchor = None;
+        self.line_flash = None;
+        self.invalidate_search_cache();
+        self.last_previewed_index = None;
+    }
+
+    // === Tabs ===
+
+    /// Open a tab rooted at the selected directory (the selected file's
+    /// directory) right after the active one, and switch to it.
+    pub fn new_tab(&mut self) {
+        let dir = self.current_dir();
+        let tree_state = match self.tree_like_current(&dir) {
+            Ok(tree_state) => tree_state,
+            Err(e) => {
+                self.set_status_message(format!("Error: {}", e));
+                return;
             }
-            self.mode = AppMode::Normal;
-            self.invalidate_search_cache();
-            self.last_previewed_index = None;
+        };
+        self.finish_work();
+        let current = self.swap_tab(TabState {
+            tree_state,
+            preview_state: PreviewState::default(),
+            preview_search: PreviewSearch::default(),
+            focused_panel: FocusedPanel::Tree,
+        });
+        self.tabs.open(current);
+        // The tab bar shows up or grows, and the rows belong to a new tree
+        self.layout_changed();
+        self.forget_root();
+        self.set_status_message(format!(
+            "Tab {}/{}: {}",
+            self.tabs.active() + 1,
+            self.tabs.len(),
+            dir.display()
+        ));
+    }
+
+    /// Switch to the tab `steps` to the right (left when negative) of the
+    /// active one, wrapping around.
+    pub fn cycle_tab(&mut self, steps: isize) {
+        if self.tabs.len() == 1 {
+            self.set_status_message("Only one tab (Ctrl+N opens another)".to_string());
+            return;
         }
+        self.select_tab(self.tabs.offset(steps));
     }
 
-    /// Search action: navigate to file and open rename dialog.
-    pub fn search_action_rename(&mut self) {
-        if let Some(state) = self.search_action_state.take() {
-            self.mode = AppMode::Normal;
-            self.invalidate_search_cache();
-            self.last_previewed_index = None;
-            self.navigate_to_path(&state.path);
-            self.open_dialog(DialogKind::Rename {
-                original: state.path,
-            });
+    /// Switch to tab `index`.
+    pub fn select_tab(&mut self, index: usize) {
+        let Some(next) = self.tabs.take(index) else {
+            return;
+        };
+        // Queued work finishes on the tree it was queued for
+        self.finish_work();
+        let current = self.swap_tab(next);
+        self.tabs.park(current, index);
+        self.layout_changed();
+        self.enter_tab();
+    }
+
+    /// Close the active tab and switch to its neighbour. The last tab
+    /// stays open.
+    pub fn close_tab(&mut self) {
+        let Some(next) = self.tabs.close() else {
+            self.set_status_message("Only one tab open".to_string());
+            return;
+        };
+        self.work.clear(&self.tasks);
+        let closed = self.swap_tab(next);
+        self.layout_changed();
+        self.enter_tab();
+        self.set_status_message(format!("Closed tab {}", closed.tree_state.root.name));
+    }
+
+    /// Put `tab` in the active tab's fields, returning what they held.
+    fn swap_tab(&mut self, tab: TabState) -> TabState {
+        TabState {
+            tree_state: std::mem::replace(&mut self.tree_state, tab.tree_state),
+            preview_state: std::mem::replace(&mut self.preview_state, tab.preview_state),
+            preview_search: std::mem::replace(&mut self.preview_search, tab.preview_search),
+            focused_panel: std::mem::replace(&mut self.focused_panel, tab.focused_panel),
         }
     }
 
-    /// Search action: navigate to file and open delete confirm.
-    pub fn search_action_delete(&mut self) {
-        if let Some(state) = self.search_action_state.take() {
-            self.mode = AppMode::Normal;
-            self.invalidate_search_cache();
-            self.last_previewed_index = None;
-            self.navigate_to_path(&state.path);
-            self.open_dialog(DialogKind::DeleteConfirm {
-                targets: vec![state.path],
-            });
+    /// Catch up a tab switched back to: nothing watched its root while it
+    /// was parked, so it is refreshed from there.
+    fn enter_tab(&mut self) {
+        self.forget_root();
+        if self.focused_panel == FocusedPanel::Terminal && !self.terminal_state.visible {
+            self.focused_panel = FocusedPanel::Tree;
         }
+        self.handle_fs_change(vec![self.tree_state.root.path.clone()]);
     }
 
-    /// Search action: add file to internal clipboard for copy.
-    pub fn search_action_copy_clipboard(&mut self) {
-        if let Some(state) = self.search_action_state.take() {
-            use crate::fs::clipboard::ClipboardOp;
-            self.clipboard
-                .set(vec![state.path.clone()], ClipboardOp::Copy);
-            let name = state
-                .path
-                .file_name()
-                .map(|n| n.to_string_lossy().to_string())
-                .unwrap_or_default();
-            self.set_status_message(format!("📋 Copied: {}", name));
-            self.mode = AppMode::Normal;
-            self.invalidate_search_cache();
-            self.last_previewed_index = None;
-        }
-    }
+    /// Spawn an async directory snapshot collection.
+    ///
+    /// For large directories (> page_size entries), this collects a `DirSnapshot`
+    /// on a blocking thread and sends `DirScanComplete` when done. The UI remains
+    /// responsive while the snapshot is being built.
+    ///
+    /// The node is marked as expanded immediately with a "Scanning..." placeholder.
+    #[allow(dead_code)]
+    pub fn spawn_async_snapshot(
+        &mut self,
+        dir_path: &Path,
+        event_tx: &mpsc::UnboundedSender<crate::event::Event>,
+    ) {
+        let path = dir_path.to_path_buf();
+        let tx = event_tx.clone();
 
-    /// Search action: cut file to internal clipboard for move.
-    pub fn search_action_cut_clipboard(&mut self) {
-        if let Some(state) = self.search_action_state.take() {
-            use crate::fs::clipboard::ClipboardOp;
-            self.clipboard
-                .set(vec![state.path.clone()], ClipboardOp::Cut);
-            let name = state
-                .path
-                .file_name()
+        self.set_status_message(format!(
+            "📂 Scanning {}...",
+            path.file_name()
                 .map(|n| n.to_string_lossy().to_string())
-                .unwrap_or_default();
-            self.set_status_message(format!("✂ Cut: {}", name));
-            self.mode = AppMode::Normal;
-            self.invalidate_search_cache();
-            self.last_previewed_index = None;
-        }
+                .unwrap_or_else(|| path.to_string_lossy().to_string())
+        ));
+
+        let description = format!("scan {}", path.display());
+        self.tasks.spawn(
+            TaskKind::DirScan,
+            description,
+            Priority::Normal,
+            move |_| async move {
+                // Use spawn_blocking since read_dir is a synchronous I/O operation
+                let result = tokio::task::spawn_blocking(move || {
+                    crate::fs::tree::DirSnapshot::collect(&path).map(|snapshot| (path, snapshot))
+                })
+                .await;
+
+                match result {
+                    Ok(Ok((path, snapshot))) => {
+                        let _ = tx.send(crate::event::Event::DirScanComplete { path, snapshot });
+                    }
+                    Ok(Err(_)) | Err(_) => {
+                        // Snapshot collection failed — silently ignore
+                        // The directory will just not expand
+                    }
+                }
+            },
+        );
     }
 
-    /// Search action: open file's parent directory in the embedded terminal.
-    pub fn search_action_open_terminal(
+    /// Spawn an async child count for a directory.
+    ///
+    /// Performs `read_dir().count()` on a blocking thread and sends
+    /// `DirCountComplete` when done. Used for non-blocking badge updates.
+    #[allow(dead_code)]
+    pub fn spawn_async_child_count(
         &mut self,
+        dir_path: &Path,
         event_tx: &mpsc::UnboundedSender<crate::event::Event>,
     ) {
-        if let Some(state) = self.search_action_state.take() {
-            self.mode = AppMode::Normal;
-            self.invalidate_search_cache();
-            self.last_previewed_index = None;
-
-            let parent_dir = if state.is_directory {
-                state.path.clone()
-            } else {
-                state
-                    .path
-                    .parent()
-                    .map(|p| p.to_path_buf())
-                    .unwrap_or_else(|| state.path.clone())
-            };
+        let path = dir_path.to_path_buf();
+        let tx = event_tx.clone();
 
-            // Ensure terminal is visible (spawns PTY if needed)
-            if !self.terminal_state.visible {
-                self.toggle_terminal(event_tx);
-            }
+        let description = format!("count {}", path.display());
+        self.tasks.spawn(
+            TaskKind::ChildCount,
+            description,
+            Priority::Background,
+            move |_| async move {
+                let result = tokio::task::spawn_blocking(move || {
+                    std::fs::read_dir(&path).map(|rd| (path, rd.count()))
+                })
+                .await;
 
-            // Send cd command to PTY
-            if let Some(ref pty) = self.terminal_state.pty {
-                let cd_cmd = format!("cd {}\n", parent_dir.to_string_lossy());
-                let _ = pty.write(cd_cmd.as_bytes());
-            }
+                if let Ok(Ok((path, count))) = result {
+                    let _ = tx.send(crate::event::Event::DirCountComplete { path, count });
+                }
+            },
+        );
+    }
 
-            self.focused_panel = FocusedPanel::Terminal;
-            self.set_status_message(format!("Terminal: cd {}", parent_dir.to_string_lossy()));
+    /// Size the selected directory now, even if it already has a size
+    /// or automatic sizes are off.
+    pub fn measure_dir_size(&mut self, event_tx: &mpsc::UnboundedSender<crate::event::Event>) {
+        match self.selected_dir() {
+            Some(path) => self.spawn_dir_size(path, event_tx),
+            None => self.set_status_message("Size: select a directory".to_string()),
         }
     }
 
-    /// Update search results by scoring cached paths against the query.
-    fn update_search_results(&mut self) {
-        let query = &self.search_state.query;
-        if query.is_empty() {
-            self.search_state.results.clear();
-            self.search_state.selected_index = 0;
+    /// Show what the filesystem records about the selected entry (`i`).
+    /// A directory without a computed size is sized in the background.
+    pub fn open_file_info(&mut self, event_tx: &mpsc::UnboundedSender<crate::event::Event>) {
+        let Some(path) = self
+            .tree_state
+            .flat_items
+            .get(self.tree_state.selected_index)
+            .filter(|item| item.node_type != NodeType::LoadMore)
+            .map(|item| item.path.clone())
+        else {
             return;
-        }
-
-        let paths = match &self.search_state.cached_paths {
-            Some(p) => p,
-            None => return,
         };
-
-        let root = &self.tree_state.root.path;
-        let mut results: Vec<SearchResult> = paths
-            .iter()
-            .filter_map(|path| {
-                let display = path
-                    .strip_prefix(root)
-                    .unwrap_or(path)
-                    .to_string_lossy()
-                    .to_string();
-                let (score, indices) = self.fuzzy_matcher.fuzzy_indices(&display, query)?;
-                Some(SearchResult {
-                    path: path.clone(),
-                    display,
-                    score,
-                    match_indices: indices,
-                })
-            })
-            .collect();
-
-        // Sort by score descending
-        results.sort_by(|a, b| b.score.cmp(&a.score));
-        // Limit to top 50
-        results.truncate(50);
-
-        self.search_state.results = results;
-        self.search_state.selected_index = 0;
+        let children = TreeState::find_node_mut_pub(&mut self.tree_state.root, &path)
+            .and_then(|node| node.child_count_cached());
+        let size = self.dir_sizes.get(&path);
+        let info = FileInfo::read(&path, children, size, self.config.apparent_sizes());
+        if path.is_dir() && size.is_none() && self.dir_sizes.running() != Some(path.as_path()) {
+            self.spawn_dir_size(path.clone(), event_tx);
+        }
+        self.open_dialog(DialogKind::Info {
+            path,
+            info: Box::new(info),
+        });
     }
 
-    /// Build a flat list of file paths using a hybrid approach:
-    /// 1. Walk loaded tree nodes in-memory (instant, no I/O)
-    /// 2. For un-expanded directories, do a time-bounded filesystem walk
-    ///
-    /// Capped by `search_max_entries` config and a 500ms time limit.
-    fn build_path_index(&self) -> Vec<PathBuf> {
-        let max_entries = self.config.search_max_entries();
-        let mut paths = Vec::new();
-        let mut unloaded_dirs = Vec::new();
-        Self::collect_loaded_paths(&self.tree_state.root, &mut paths, &mut unloaded_dirs);
-
-        // Phase 2: walk unloaded directories with entry cap + time limit
-        if paths.len() < max_entries {
-            let deadline = Instant::now() + std::time::Duration::from_millis(500);
-            let mut stack = unloaded_dirs;
-            while let Some(dir) = stack.pop() {
-                if paths.len() >= max_entries || Instant::now() >= deadline {
-                    break;
-                }
-                let entries = match std::fs::read_dir(&dir) {
-                    Ok(e) => e,
-                    Err(_) => continue,
-                };
-                for entry in entries {
-                    if paths.len() >= max_entries || Instant::now() >= deadline {
-                        break;
-                    }
-                    let entry = match entry {
-                        Ok(e) => e,
-                        Err(_) => continue,
-                    };
-                    let path = entry.path();
-                    if path.is_dir() {
-                        stack.push(path);
-                    } else {
-                        paths.push(path);
-                    }
+    /// Open the permissions editor for the selected items, starting from
+    /// the first one's mode.
+    pub fn open_permissions(&mut self) {
+        if !cfg!(unix) {
+            self.set_status_message("Changing permissions is not supported here".to_string());
+            return;
+        }
+        let paths = self.collect_target_paths();
+        if paths.is_empty() {
+            return;
+        }
+        let mut modes = Vec::with_capacity(paths.len());
+        for path in &paths {
+            match operations::file_mode(path) {
+                Ok(mode) => modes.push(mode),
+                Err(e) => {
+                    self.set_status_message(format!("Error: {}", e.user_message()));
+                    return;
                 }
             }
         }
-
-        paths
+        self.open_dialog(DialogKind::Permissions {
+            form: PermissionsForm::new(paths, &modes),
+        });
     }
 
-    /// Collect paths from loaded tree nodes, and record unloaded directories.
-    fn collect_loaded_paths(
-        node: &crate::fs::tree::TreeNode,
-        paths: &mut Vec<PathBuf>,
-        unloaded_dirs: &mut Vec<PathBuf>,
-    ) {
-        if let Some(children) = &node.children {
-            for child in children {
-                paths.push(child.path.clone());
-                if child.node_type == crate::fs::tree::NodeType::Directory {
-                    if child.children.is_some() {
-                        // Recursively collect from loaded children
-                        Self::collect_loaded_paths(child, paths, unloaded_dirs);
-                    } else {
-                        // Not yet loaded — schedule for filesystem walk
-                        unloaded_dirs.push(child.path.clone());
-                    }
-                }
-            }
+    /// The open permissions form, if any.
+    pub fn permissions_form_mut(&mut self) -> Option<&mut PermissionsForm> {
+        match &mut self.mode {
+            AppMode::Dialog(DialogKind::Permissions { form }) => Some(form),
+            _ => None,
         }
     }
 
-    /// Build a flat list of all file paths by walking the filesystem.
-    ///
-    /// Uses iterative stack-based walk with configurable entry cap.
-    /// Used for "Search deeper..." functionality.
-    #[allow(dead_code)]
-    fn build_deep_path_index(&self) -> Vec<PathBuf> {
-        let max_entries = self.config.search_max_entries();
-        let mut paths = Vec::new();
-        let mut stack: Vec<PathBuf> = vec![self.tree_state.root.path.clone()];
-
-        while let Some(dir) = stack.pop() {
-            if paths.len() >= max_entries {
-                break;
-            }
-            let entries = match std::fs::read_dir(&dir) {
-                Ok(e) => e,
-                Err(_) => continue,
-            };
-            for entry in entries {
-                if paths.len() >= max_entries {
-                    break;
-                }
-                let entry = match entry {
-                    Ok(e) => e,
-                    Err(_) => continue,
-                };
-                let path = entry.path();
-                if path.is_dir() {
-                    stack.push(path);
-                } else {
-                    paths.push(path);
+    /// Give every target the mode in the dialog, collecting per-item
+    /// errors, and record the old modes for undo.
+    pub fn permissions_confirm(&mut self) {
+        let (paths, mode) = match self.permissions_form_mut() {
+            Some(form) => match form.parsed() {
+                Ok(mode) => (form.paths.clone(), mode),
+                Err(msg) => {
+                    self.set_status_message(format!("Error: {}", msg));
+                    return;
                 }
+            },
+            None => return,
+        };
+        self.close_dialog();
+
+        let mut changes = Vec::new();
+        let mut errors = Vec::new();
+        for path in paths {
+            let result = operations::file_mode(&path)
+                .and_then(|old_mode| operations::set_permissions(&path, mode).map(|()| old_mode));
+            match result {
+                Ok(old_mode) => changes.push(ModeChange {
+                    path,
+                    old_mode,
+                    new_mode: mode,
+                }),
+                Err(e) => errors.push(e),
             }
         }
-        paths
-    }
-
-    /// Invalidate the cached path index (call after tree mutations).
-    pub fn invalidate_search_cache(&mut self) {
-        self.search_state.cached_paths = None;
-    }
 
-    /// Navigate tree to a specific path: expand all ancestors, select the target.
-    pub fn navigate_to_path(&mut self, target: &Path) {
-        // Collect ancestor directories that need to be expanded
-        let root_path = self.tree_state.root.path.clone();
-        let mut ancestors = Vec::new();
-        let mut current = target.parent();
-        while let Some(p) = current {
-            if p == root_path {
-                break;
-            }
-            ancestors.push(p.to_path_buf());
-            current = p.parent();
+        self.reload_parents(changes.iter().map(|c| &c.path));
+        self.invalidate_search_cache();
+        self.last_previewed_index = None;
+        let done = changes.len();
+        if done > 0 {
+            self.tree_state.clear_multi_select();
+            self.record_undo(UndoAction::Permissions { changes });
         }
-        ancestors.reverse();
+        if errors.is_empty() {
+            self.set_status_message(format!(
+                "Permissions set to {} on {} item{}",
+                permissions::describe(mode),
+                done,
+                if done == 1 { "" } else { "s" }
+            ));
+        } else {
+            self.set_status_message(format!(
+                "Permissions set on {} of {}: {}",
+                done,
+                done + errors.len(),
+                describe_errors(&errors)
+            ));
+        }
+    }
 
-        // Clone sort fields before mutable borrow
-        let sort_by = self.tree_state.sort_by.clone();
-        let dirs_first = self.tree_state.dirs_first;
-        let page_size = self.tree_state.page_size;
+    // === Bulk rename ===
 
-        // Expand each ancestor and apply sorting
-        for ancestor in &ancestors {
-            if let Some(node) = TreeState::find_node_mut_pub(&mut self.tree_state.root, ancestor) {
-                if !node.is_expanded {
-                    let _ = node.load_children_paged_with_sort(page_size, &sort_by, dirs_first);
-                    TreeState::sort_children_of_pub(node, &sort_by, dirs_first);
-                    node.is_expanded = true;
-                }
-            }
+    /// Open the bulk rename dialog for the multi-selected items.
+    pub fn open_bulk_rename(&mut self) {
+        let sources = self.collect_target_paths();
+        if sources.is_empty() {
+            return;
         }
+        self.open_dialog(DialogKind::BulkRename {
+            form: BulkRenameForm::new(sources),
+        });
+    }
 
-        // Re-flatten to reflect expansions
-        self.tree_state.flatten();
-
-        // Find and select the target in flat_items
-        for (i, item) in self.tree_state.flat_items.iter().enumerate() {
-            if item.path == target {
-                self.tree_state.selected_index = i;
-                break;
-            }
+    /// The open bulk rename form, if any.
+    pub fn bulk_rename_form_mut(&mut self) -> Option<&mut BulkRenameForm> {
+        match &mut self.mode {
+            AppMode::Dialog(DialogKind::BulkRename { form }) => Some(form),
+            _ => None,
         }
     }
 
-    // === Filter (/) methods ===
+    /// Rename every item as the dialog's preview shows, one at a time, and
+    /// record the renames that went through for undo.
+    pub fn bulk_rename_confirm(&mut self) {
+        let pairs = match self.bulk_rename_form_mut().map(|form| form.plan()) {
+            Some(Ok(plan)) => plan.pairs,
+            Some(Err(msg)) => {
+                self.set_status_message(format!("Error: {}", msg));
+                return;
+            }
+            None => return,
+        };
+        self.close_dialog();
 
-    /// Activate inline tree filter mode.
-    pub fn start_filter(&mut self) {
-        self.tree_state.filter_query.clear();
-        self.tree_state.is_filtering = false;
-        self.mode = AppMode::Filter;
-    }
+        let mut renamed = Vec::new();
+        let mut errors = Vec::new();
+        for (src, dst) in pairs {
+            // `rename` replaces files silently; one may have appeared since planning
+            let result = if std::fs::symlink_metadata(&dst).is_ok() {
+                Err(OperationError::DestinationExists { path: dst.clone() })
+            } else {
+                operations::rename(&src, &dst)
+            };
+            match result {
+                Ok(()) => renamed.push((src, dst)),
+                Err(e) => errors.push(e),
+            }
+        }
 
-    /// Clear the filter and restore the full tree.
-    pub fn clear_filter(&mut self) {
-        self.tree_state.filter_query.clear();
-        self.tree_state.is_filtering = false;
-        self.tree_state.flatten();
-        self.mode = AppMode::Normal;
-        // Filesystem events were silently dropped while in Filter mode,
-        // so invalidate the search cache and force preview refresh.
+        self.reload_parents(renamed.iter().map(|(src, _)| src));
         self.invalidate_search_cache();
         self.last_previewed_index = None;
+        let done = renamed.len();
+        if done > 0 {
+            self.tree_state.clear_multi_select();
+            self.record_undo(UndoAction::SequenceRename {
+                renames: renamed
+                    .into_iter()
+                    .map(|(src, dst)| (src, StampedPath::capture(dst)))
+                    .collect(),
+            });
+        }
+        if errors.is_empty() {
+            self.set_status_message(format!(
+                "Renamed {} item{}",
+                done,
+                if done == 1 { "" } else { "s" }
+            ));
+        } else {
+            self.set_status_message(format!(
+                "Renamed {} of {}: {}",
+                done,
+                done + errors.len(),
+                describe_errors(&errors)
+            ));
+        }
     }
 
-    /// Accept the current filter and return to normal mode (filtered view stays).
-    pub fn accept_filter(&mut self) {
-        self.mode = AppMode::Normal;
+    /// The selected row's path, if it is a directory.
+    fn selected_dir(&self) -> Option<PathBuf> {
+        self.tree_state
+            .flat_items
+            .get(self.tree_state.selected_index)
+            .filter(|item| item.node_type == NodeType::Directory)
+            .map(|item| item.path.clone())
     }
 
-    /// Insert a character into the filter query and re-filter.
-    pub fn filter_input_char(&mut self, c: char) {
-        self.tree_state.filter_query.push(c);
-        self.tree_state.apply_filter();
+    /// Walk `path` on a background task, streaming `DirSizeUpdate` events
+    /// with the running totals.
+    fn spawn_dir_size(
+        &mut self,
+        path: PathBuf,
+        event_tx: &mpsc::UnboundedSender<crate::event::Event>,
+    ) {
+        let job = self.dir_sizes.next_job();
+        let root = path.clone();
+        let one_file_system = self.config.one_file_system();
+        let tx = event_tx.clone();
+        let description = format!("size {}", file_label(&path));
+        let token = self.tasks.spawn(
+            TaskKind::DirSummary,
+            description,
+            Priority::Normal,
+            move |token| async move {
+                let cancel = Arc::clone(token.cancel_flag());
+                let _ = tokio::task::spawn_blocking(move || {
+                    let options = WalkOptions {
+                        max_entries: dir_size::MAX_ENTRIES,
+                        deadline: Some(Instant::now() + dir_size::TIME_LIMIT),
+                        one_file_system,
+                    };
+                    let send = |size: DirSize| {
+                        let _ = tx.send(crate::event::Event::DirSizeUpdate {
+                            path: root.clone(),
+                            job,
+                            size,
+                        });
+                    };
+                    let total = dir_size::walk(&root, &options, &cancel, &mut |size| send(size));
+                    if let Some(size) = total {
+                        send(size);
+                    }
+                })
+                .await;
+            },
+        );
+        self.dir_sizes
+            .start(path, job, Arc::clone(token.cancel_flag()));
     }
 
-    /// Delete the last character from the filter query and re-filter.
-    pub fn filter_delete_char(&mut self) {
-        self.tree_state.filter_query.pop();
-        if self.tree_state.filter_query.is_empty() {
-            self.tree_state.is_filtering = false;
-            self.tree_state.flatten();
+    /// Handle an async operation completion.
+    pub fn handle_operation_complete(&mut self, result: crate::event::OperationResult) {
+        self.close_dialog();
+        let next = self.in_flight.finish();
+        let op_id = self.recent_ops.record(&result);
+
+        // Refresh dest dir
+        self.tree_state.reload_dir(&result.dest_dir);
+        self.invalidate_search_cache();
+
+        // For cut/move, also refresh source parents
+        if result.was_cut {
+            self.reload_parents(result.source_paths.iter());
+            // Clear clipboard after successful cut (send-to never uses it)
+            if result.errors.is_empty() && result.send_to.is_none() {
+                self.clipboard.clear();
+            }
+        }
+
+        if result.errors.is_empty() {
+            // Record undo action. An item that replaced one without a
+            // backup is left out: undoing it can't bring the old one back,
+            // and would take a merged directory's old contents with it.
+            let undoable = |dest: &PathBuf| {
+                !result.replaced.contains(dest)
+                    || result.backups.iter().any(|b| b.original() == dest)
+            };
+            let backups = result.backups.clone();
+            if result.was_cut {
+                // Build move pairs: (original_src, created_dest)
+                let moves: Vec<(PathBuf, StampedPath)> = result
+                    .source_paths
+                    .iter()
+                    .zip(result.created_paths.iter())
+                    .filter(|(_, dest)| undoable(dest))
+                    .map(|(src, dest)| (src.clone(), StampedPath::capture(dest.clone())))
+                    .collect();
+                self.record_undo(UndoAction::MovePaste { moves, backups });
+            } else {
+                self.record_undo(UndoAction::CopyPaste {
+                    created_paths: result
+                        .created_paths
+                        .iter()
+                        .filter(|p| undoable(p))
+                        .map(|p| StampedPath::capture(p.clone()))
+                        .collect(),
+                    backups,
+                });
+            }
+            self.recent_ops.set_undo_owner(Some(op_id));
+
+            let plural = if result.success_count == 1 { "" } else { "s" };
+            if let Some(ref target) = result.send_to {
+                let op_name = if result.was_cut { "Moved" } else { "Sent" };
+                self.set_status_message(format!(
+                    "{} {} item{} to {} ({})",
+                    op_name,
+                    result.success_count,
+                    plural,
+                    target,
+                    result.dest_dir.display()
+                ));
+            } else {
+                let op_name = if result.was_cut { "Moved" } else { "Pasted" };
+                let backed_up = match result.backups.len() {
+                    0 => String::new(),
+                    n => format!(" ({} backed up)", n),
+                };
+                self.set_status_message(format!(
+                    "{} {} item{}{}",
+                    op_name, result.success_count, plural, backed_up
+                ));
+            }
         } else {
-            self.tree_state.apply_filter();
+            self.set_status_message(format!("Error: {}", describe_errors(&result.errors)));
+        }
+
+        if let Some(next) = next {
+            self.spawn_transfer_async(next);
         }
     }
 
-    // === Filesystem watcher methods ===
+    /// Handle a progress update from an async operation.
+    /// Copies also update the byte count and the throughput. An update
+    /// that brings a total switches the dialog from the spinner to the bar.
+    pub fn handle_progress(&mut self, update: crate::event::ProgressUpdate) {
+        if let AppMode::Dialog(DialogKind::Progress { started, spin, .. }) = self.mode {
+            self.mode = AppMode::Dialog(DialogKind::Progress {
+                message: update.current_file,
+                current: update.current,
+                total: update.total,
+                bytes_done: update.bytes_done,
+                bytes_total: update.bytes_total,
+                started,
+                rate: throughput(update.bytes_done, started.elapsed()),
+                spin,
+            });
+        }
+    }
 
-    /// Handle filesystem change events by refreshing affected subtrees.
-    ///
-    /// Preserves: selected path, scroll offset, expanded directories.
-    /// Clears: multi-select, search cache.
-    ///
-    /// Skipped when in Search or Filter mode to avoid destroying the search
-    /// cache or overwriting the filtered flat_items view.
-    pub fn handle_fs_change(&mut self, paths: Vec<PathBuf>) {
-        // Don't process filesystem changes while search/filter is active:
-        // - Search: would invalidate_search_cache(), clearing cached_paths so
-        //   fuzzy scoring returns no results.
-        // - Filter: would call flatten() which rebuilds flat_items without the
-        //   filter, undoing the filtered view.
-        if matches!(self.mode, AppMode::Search | AppMode::Filter) {
+    /// Cancel an ongoing async operation and drop any queued behind it.
+    pub fn cancel_operation(&mut self) {
+        self.tasks.cancel_where(|task| task.kind.touches_files());
+        self.in_flight.clear_queue();
+    }
+
+    // === Tasks overlay ===
+
+    /// Open the list of running and waiting background tasks.
+    pub fn open_tasks(&mut self) {
+        let tasks = self.tasks.tasks();
+        if tasks.is_empty() {
+            self.set_status_message("No background tasks running".to_string());
             return;
         }
-        // Capture current state
-        let selected_path = self
-            .tree_state
-            .flat_items
-            .get(self.tree_state.selected_index)
-            .map(|item| item.path.clone());
-        let scroll_offset = self.tree_state.scroll_offset;
-        let expanded = self.tree_state.collect_expanded_paths();
+        self.open_dialog(DialogKind::Tasks { tasks, selected: 0 });
+    }
+
+    /// Bring the open tasks overlay up to date, keeping the selected task
+    /// selected while it is listed.
+    fn refresh_tasks_dialog(&mut self) {
+        if let AppMode::Dialog(DialogKind::Tasks { tasks, selected }) = &mut self.mode {
+            let current = tasks.get(*selected).map(|t| t.id);
+            *tasks = self.tasks.tasks();
+            *selected = current
+                .and_then(|id| tasks.iter().position(|t| t.id == id))
+                .unwrap_or(*selected)
+                .min(tasks.len().saturating_sub(1));
+        }
+    }
 
-        // Deduplicate parent directories to reload
-        let mut dirs_to_reload = std::collections::HashSet::new();
-        for path in &paths {
-            // If the changed path IS the root, do a full reload
-            if path == &self.tree_state.root.path {
-                dirs_to_reload.clear();
-                dirs_to_reload.insert(self.tree_state.root.path.clone());
-                break;
+    /// Move the selection in the tasks overlay.
+    pub fn tasks_select(&mut self, forward: bool) {
+        if let AppMode::Dialog(DialogKind::Tasks { tasks, selected }) = &mut self.mode {
+            let len = tasks.len();
+            if len > 0 {
+                *selected = if forward {
+                    (*selected + 1) % len
+                } else {
+                    (*selected + len - 1) % len
+                };
             }
-            // Otherwise reload the parent directory of the changed file
-            if let Some(parent) = path.parent() {
-                dirs_to_reload.insert(parent.to_path_buf());
+        }
+    }
+
+    /// Cancel the selected task.
+    pub fn tasks_cancel_selected(&mut self) {
+        let task = match &self.mode {
+            AppMode::Dialog(DialogKind::Tasks { tasks, selected }) => tasks.get(*selected).cloned(),
+            _ => None,
+        };
+        if let Some(task) = task {
+            if self.tasks.cancel(task.id) {
+                self.set_status_message(format!("Cancelling {}", task.description));
             }
+            self.refresh_tasks_dialog();
         }
+    }
 
-        // Clone sort fields before mutable borrow (avoids borrow checker conflict)
-        let sort_by = self.tree_state.sort_by.clone();
-        let dirs_first = self.tree_state.dirs_first;
-        let page_size = self.tree_state.page_size;
+    /// Cancel every background task, including a running copy or delete
+    /// and the transfers queued behind it.
+    pub fn tasks_cancel_all(&mut self) {
+        self.in_flight.clear_queue();
+        let count = self.tasks.cancel_all();
+        self.set_status_message(format!(
+            "Cancelling {} task{}",
+            count,
+            if count == 1 { "" } else { "s" }
+        ));
+        self.refresh_tasks_dialog();
+    }
 
-        // Reload each affected directory and apply sorting
-        // For paginated dirs with snapshots: mark stale (lazy re-scan on interaction)
-        // For non-paginated dirs: reload immediately
-        for dir in &dirs_to_reload {
-            if let Some(node) =
-                crate::fs::tree::TreeState::find_node_mut_pub(&mut self.tree_state.root, dir)
-            {
-                if node.node_type == crate::fs::tree::NodeType::Directory {
-                    if node.snapshot.is_some() {
-                        // Paginated dir: mark stale, avoid expensive re-scan
-                        node.is_stale = true;
-                    } else {
-                        // Non-paginated dir: reload immediately
-                        let _ = node.load_children_paged_with_sort(page_size, &sort_by, dirs_first);
-                        TreeState::sort_children_of_pub(node, &sort_by, dirs_first);
-                    }
-                }
+    // === Recent operations ===
+
+    /// Id of the recent operation Ctrl+Z would reverse, if any.
+    pub fn undoable_recent_op(&self) -> Option<u64> {
+        self.last_undo.as_ref().and(self.recent_ops.undo_owner())
+    }
+
+    /// Open the list of recently completed operations, newest first.
+    pub fn open_recent_operations(&mut self) {
+        if self.recent_ops.is_empty() {
+            self.set_status_message("No completed operations yet".to_string());
+            return;
+        }
+        self.open_dialog(DialogKind::RecentOperations {
+            ops: self.recent_ops.entries().cloned().collect(),
+            undoable: self.undoable_recent_op(),
+            selected: 0,
+            expanded: false,
+        });
+    }
+
+    /// Move the selection in the recent operations list.
+    pub fn recent_ops_select(&mut self, forward: bool) {
+        if let AppMode::Dialog(DialogKind::RecentOperations { ops, selected, .. }) = &mut self.mode
+        {
+            let len = ops.len();
+            if len > 0 {
+                *selected = if forward {
+                    (*selected + 1) % len
+                } else {
+                    (*selected + len - 1) % len
+                };
             }
         }
+    }
 
-        // Restore expanded directories then re-flatten
-        self.tree_state.restore_expanded(&expanded);
-        self.tree_state.flatten();
+    /// Show or hide the per-item details of the selected operation.
+    pub fn recent_ops_toggle_details(&mut self) {
+        if let AppMode::Dialog(DialogKind::RecentOperations { expanded, .. }) = &mut self.mode {
+            *expanded = !*expanded;
+        }
+    }
 
-        // Restore selection
-        if let Some(ref prev_path) = selected_path {
-            if let Some(new_idx) = self.tree_state.find_index_by_path(prev_path) {
-                self.tree_state.selected_index = new_idx;
-            } else {
-                // Selected path was deleted — find nearest surviving
-                if let Some(fallback) = self.tree_state.find_nearest_surviving(prev_path) {
-                    self.tree_state.selected_index = fallback;
-                }
+    fn selected_recent_op(&self) -> Option<&CompletedOp> {
+        match &self.mode {
+            AppMode::Dialog(DialogKind::RecentOperations { ops, selected, .. }) => {
+                ops.get(*selected)
             }
+            _ => None,
         }
+    }
 
-        // Restore scroll offset (clamped)
-        let max_scroll = self.tree_state.flat_items.len().saturating_sub(1);
-        self.tree_state.scroll_offset = scroll_offset.min(max_scroll);
-
-        // Invalidate caches
-        self.invalidate_search_cache();
-        // Force preview refresh
+    /// Close the list and select what the chosen operation created, or
+    /// its destination when nothing it created is left.
+    pub fn recent_ops_reveal(&mut self) {
+        let target = match self.selected_recent_op() {
+            Some(op) => op
+                .created
+                .iter()
+                .find(|p| p.exists())
+                .unwrap_or(&op.dest_dir)
+                .clone(),
+            None => return,
+        };
+        self.close_dialog();
+        if !target.starts_with(&self.tree_state.root.path) {
+            self.set_status_message(format!(
+                "Error: {} is outside the tree root",
+                target.display()
+            ));
+            return;
+        }
+        self.navigate_to_path(&target);
         self.last_previewed_index = None;
     }
 
-    /// Force a full tree refresh from root, preserving state.
-    ///
-    /// Used by F5 keybinding; works regardless of watcher state.
-    pub fn full_refresh(&mut self) {
-        self.handle_fs_change(vec![self.tree_state.root.path.clone()]);
-        self.set_status_message("🔄 Tree refreshed".to_string());
+    /// Undo the chosen operation, if it is still the one Ctrl+Z reverses.
+    pub fn recent_ops_undo(&mut self) {
+        let id = match self.selected_recent_op() {
+            Some(op) => op.id,
+            None => return,
+        };
+        if self.undoable_recent_op() != Some(id) {
+            self.set_status_message("That operation can no longer be undone".to_string());
+            return;
+        }
+        self.close_dialog();
+        self.undo();
+    }
+
+    /// Copy or move the items the chosen operation failed (or never got
+    /// to) again, into the same destination.
+    pub fn recent_ops_retry(&mut self, event_tx: mpsc::UnboundedSender<crate::event::Event>) {
+        let transfer = match self.selected_recent_op() {
+            Some(op) if !op.retry.is_empty() => PendingTransfer {
+                paths: op.retry.clone(),
+                dest_dir: op.dest_dir.clone(),
+                was_cut: op.was_cut,
+                send_to: op.send_to.clone(),
+                entries: None,
+                bytes: None,
+                overwrite: Vec::new(),
+                backup: None,
+                event_tx,
+            },
+            Some(_) => {
+                self.set_status_message("Nothing to retry".to_string());
+                return;
+            }
+            None => return,
+        };
+        self.close_dialog();
+        self.start_transfer(transfer, WhenBusy::Queue);
     }
 
-    /// Toggle the filesystem watcher active state.
+    /// Undo the last reversible operation.
     ///
-    /// Returns the new state (true = active, false = paused).
-    pub fn toggle_watcher(&mut self) -> bool {
-        self.watcher_active = !self.watcher_active;
-        if self.watcher_active {
-            self.set_status_message("👁 Watcher resumed".to_string());
+    /// If anything the undo would touch changed since the operation, a
+    /// confirmation dialog lists the changes instead of applying directly.
+    pub fn undo(&mut self) {
+        let action = match self.last_undo.take() {
+            Some(a) => a,
+            None => {
+                self.set_status_message("Nothing to undo".to_string());
+                return;
+            }
+        };
+
+        let conflicts = action.verify(&self.tree_state.root.path);
+        if conflicts.is_empty() {
+            self.undo_from_previous_session = false;
+            self.apply_undo(action, &[]);
         } else {
-            self.set_status_message("⏸ Watcher paused".to_string());
+            self.pending_undo = Some(action);
+            self.mode = AppMode::Dialog(DialogKind::UndoConfirm {
+                conflicts,
+                selected: 0,
+                from_previous_session: self.undo_from_previous_session,
+            });
         }
-        self.watcher_active
     }
 
-    // === Async directory operation handlers ===
+    /// Record `action` as the operation Ctrl+Z reverses.
+    pub fn record_undo(&mut self, action: UndoAction) {
+        self.last_undo = Some(action);
+        self.undo_from_previous_session = false;
+        self.recent_ops.set_undo_owner(None);
+    }
+
+    /// Reinstate an undo saved by the previous run. Actions with nothing
+    /// left to act on are dropped; changed ones are kept and reviewed in
+    /// the confirmation dialog on Ctrl+Z like any other.
+    pub fn restore_undo(&mut self, action: UndoAction) {
+        if !action.is_actionable() {
+            return;
+        }
+        let changed = action.verify(&self.tree_state.root.path).len();
+        self.last_undo = Some(action);
+        self.undo_from_previous_session = true;
+        self.recent_ops.set_undo_owner(None);
+        let msg = if changed == 0 {
+            "Undo from previous session available (Ctrl+Z)".to_string()
+        } else {
+            format!(
+                "Undo from previous session available — {} item{} changed since (Ctrl+Z to review)",
+                changed,
+                if changed == 1 { "" } else { "s" }
+            )
+        };
+        self.set_status_message(msg);
+    }
+
+    /// The undo to persist on exit, including one awaiting confirmation.
+    pub fn persistable_undo(&self) -> Option<&UndoAction> {
+        self.last_undo
+            .as_ref()
+            .or(self.pending_undo.as_ref())
+            .filter(|action| action.is_actionable())
+    }
+
+    // === Sequence rename ===
+
+    /// Open the sequence rename dialog for the multi-selected items, or for
+    /// the files directly inside the focused directory.
+    pub fn open_sequence_rename(&mut self) {
+        let sources = self.sequence_sources();
+        if sources.is_empty() {
+            self.set_status_message("Select files or a directory to renumber".to_string());
+            return;
+        }
+        self.mode = AppMode::Dialog(DialogKind::SequenceRename {
+            form: SequenceForm::new(sources),
+        });
+    }
+
+    fn sequence_sources(&self) -> Vec<PathBuf> {
+        if !self.tree_state.multi_selected.is_empty() {
+            return self.collect_target_paths();
+        }
+        let dir = match self
+            .tree_state
+            .flat_items
+            .get(self.tree_state.selected_index)
+        {
+            Some(item) if item.node_type == NodeType::Directory => item.path.clone(),
+            _ => return Vec::new(),
+        };
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(_) => return Vec::new(),
+        };
+        entries
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().map(|t| t.is_file()).unwrap_or(false))
+            .filter(|e| {
+                self.tree_state.show_hidden || !e.file_name().to_string_lossy().starts_with('.')
+            })
+            .map(|e| e.path())
+            .collect()
+    }
+
+    /// The open sequence rename form, if any.
+    pub fn sequence_form_mut(&mut self) -> Option<&mut SequenceForm> {
+        match &mut self.mode {
+            AppMode::Dialog(DialogKind::SequenceRename { form }) => Some(form),
+            _ => None,
+        }
+    }
+
+    /// Run the renumbering shown in the dialog and record it for undo.
+    pub fn sequence_confirm(&mut self) {
+        let pairs = match self.sequence_form_mut().map(|form| form.plan()) {
+            Some(Ok(pairs)) => pairs,
+            Some(Err(msg)) => {
+                self.set_status_message(format!("Error: {}", msg));
+                return;
+            }
+            None => return,
+        };
+        self.close_dialog();
+
+        match sequence::apply(&pairs) {
+            Ok(()) => {
+                self.record_undo(UndoAction::SequenceRename {
+                    renames: pairs
+                        .iter()
+                        .map(|(src, dst)| (src.clone(), StampedPath::capture(dst.clone())))
+                        .collect(),
+                });
+                self.tree_state.clear_multi_select();
+                self.reload_parents(pairs.iter().map(|(src, _)| src));
+                self.invalidate_search_cache();
+                self.set_status_message(format!(
+                    "Renumbered {} item{}",
+                    pairs.len(),
+                    if pairs.len() == 1 { "" } else { "s" }
+                ));
+            }
+            Err(e) => self.set_status_message(format!("Error: {}", e.user_message())),
+        }
+    }
+
+    /// Reload the parent directory of each path once.
+    fn reload_parents<'p>(&mut self, paths: impl Iterator<Item = &'p PathBuf>) {
+        let mut parents: Vec<PathBuf> = paths
+            .filter_map(|p| p.parent().map(Path::to_path_buf))
+            .collect();
+        parents.sort();
+        parents.dedup();
+        for parent in parents {
+            self.tree_state.reload_dir(&parent);
+        }
+    }
+
+    // === Portability check ===
+
+    /// Check the focused directory for names that break on Windows or
+    /// macOS, on a background task finishing with `PortabilityComplete`.
+    /// A check still running is cancelled.
+    pub fn start_portability_check(
+        &mut self,
+        event_tx: &mpsc::UnboundedSender<crate::event::Event>,
+    ) {
+        use crate::event::Event;
+
+        let dir = match self
+            .tree_state
+            .flat_items
+            .get(self.tree_state.selected_index)
+        {
+            Some(item) if item.node_type == NodeType::Directory => item.path.clone(),
+            _ => {
+                self.set_status_message("Select a directory to check for portability".to_string());
+                return;
+            }
+        };
+
+        if let Some(task) = self.portability_task {
+            self.tasks.cancel(task);
+        }
+        self.portability_id += 1;
+        let id = self.portability_id;
+        self.set_status_message(format!("Checking {} for portability…", dir.display()));
+
+        let ignore = watcher::default_ignore_patterns();
+        let event_tx = event_tx.clone();
+        let description = format!("check {}", dir.display());
+        let token = self.tasks.spawn(
+            TaskKind::Portability,
+            description,
+            Priority::Normal,
+            move |token| async move {
+                let scan_cancel = Arc::clone(token.cancel_flag());
+                let result = tokio::task::spawn_blocking(move || {
+                    portability::scan(&dir, &ignore, PORTABILITY_MAX_ENTRIES, &scan_cancel)
+                })
+                .await;
+                if let Ok(report) = result {
+                    if !token.is_cancelled() {
+                        let _ = event_tx.send(Event::PortabilityComplete { id, report });
+                    }
+                }
+            },
+        );
+        self.portability_task = Some(token.id());
+    }
+
+    /// Show a finished portability check: the results dialog, or a status
+    /// line when nothing was found or another dialog is open.
+    pub fn handle_portability_complete(&mut self, id: u64, report: PortabilityReport) {
+        if id != self.portability_id {
+            return;
+        }
+        let partial = if report.complete {
+            ""
+        } else {
+            ", stopped early"
+        };
+        let problems = report.findings.len();
+        if problems == 0 {
+            self.set_status_message(format!(
+                "No portability problems in {} ({} entr{} checked{})",
+                report.root.display(),
+                report.entries,
+                if report.entries == 1 { "y" } else { "ies" },
+                partial
+            ));
+        } else if self.mode != AppMode::Normal {
+            self.set_status_message(format!(
+                "Portability check found {} problem{} in {} — run it again to review",
+                problems,
+                if problems == 1 { "" } else { "s" },
+                report.root.display()
+            ));
+        } else {
+            self.mode = AppMode::Dialog(DialogKind::Portability {
+                report,
+                selected: 0,
+            });
+        }
+    }
+
+    /// Move the selection in the portability results.
+    pub fn portability_select(&mut self, forward: bool) {
+        if let AppMode::Dialog(DialogKind::Portability { report, selected }) = &mut self.mode {
+            let len = report.findings.len();
+            if len > 0 {
+                *selected = if forward {
+                    (*selected + 1) % len
+                } else {
+                    (*selected + len - 1) % len
+                };
+            }
+        }
+    }
+
+    /// Close the results and select the chosen entry in the tree.
+    pub fn portability_reveal(&mut self) {
+        let path = match &self.mode {
+            AppMode::Dialog(DialogKind::Portability { report, selected }) => {
+                match report.findings.get(*selected) {
+                    Some(finding) => finding.path.clone(),
+                    None => return,
+                }
+            }
+            _ => return,
+        };
+        self.close_dialog();
+        if !path.starts_with(&self.tree_state.root.path) {
+            self.set_status_message(format!(
+                "Error: {} is outside the tree root",
+                path.display()
+            ));
+            return;
+        }
+        self.navigate_to_path(&path);
+        self.last_previewed_index = None;
+    }
+
+    /// Apply every suggested rename as one batch and record it for undo.
+    /// Entries inside a renamed directory are left for another check.
+    pub fn portability_rename_all(&mut self) {
+        let (pairs, deferred) = match &self.mode {
+            AppMode::Dialog(DialogKind::Portability { report, .. }) => {
+                portability::rename_plan(&report.findings)
+            }
+            _ => return,
+        };
+        if pairs.is_empty() {
+            self.set_status_message("No suggested renames to apply".to_string());
+            return;
+        }
+        self.close_dialog();
+
+        match sequence::apply(&pairs) {
+            Ok(()) => {
+                self.record_undo(UndoAction::SequenceRename {
+                    renames: pairs
+                        .iter()
+                        .map(|(src, dst)| (src.clone(), StampedPath::capture(dst.clone())))
+                        .collect(),
+                });
+                self.reload_parents(pairs.iter().map(|(src, _)| src));
+                self.invalidate_search_cache();
+                let later = if deferred == 0 {
+                    String::new()
+                } else {
+                    format!(
+                        "; {} inside renamed directories — check again for those",
+                        deferred
+                    )
+                };
+                self.set_status_message(format!(
+                    "Renamed {} item{}{}",
+                    pairs.len(),
+                    if pairs.len() == 1 { "" } else { "s" },
+                    later
+                ));
+            }
+            Err(e) => self.set_status_message(format!("Error: {}", e.user_message())),
+        }
+    }
+
+    // === Tree export ===
+
+    /// Open the tree export dialog.
+    pub fn open_export_dialog(&mut self) {
+        self.mode = AppMode::Dialog(DialogKind::ExportTree {
+            form: ExportForm::new(&self.tree_state.root.path),
+        });
+    }
+
+    /// Ask where to export the settings profile, offering
+    /// [`profile::DEFAULT_FILE`] in the root.
+    pub fn open_export_profile(&mut self) {
+        self.open_dialog(DialogKind::ExportProfile);
+        self.dialog_state.input = profile::DEFAULT_FILE.to_string();
+        self.dialog_state.cursor_position = profile::DEFAULT_FILE.len();
+    }
+
+    /// Export the running configuration as a profile to `input`, resolved
+    /// against the root. Safe mode runs on the defaults, so it exports no
+    /// config files.
+    pub fn export_profile(&mut self, input: &str) {
+        let config_dir = match &self.config_dir {
+            Some(dir) => dir.clone(),
+            None => {
+                self.set_status_message("Error: no config directory".to_string());
+                return;
+            }
+        };
+        let out = self.tree_state.root.path.join(input);
+        let sources = if self.safe_mode {
+            Vec::new()
+        } else {
+            crate::config::source_paths(self.cli_config_path.as_deref())
+        };
+        match profile::export(&sources, &config_dir, &out) {
+            Ok(names) => {
+                if let Some(parent) = out.parent() {
+                    self.tree_state.reload_dir(parent);
+                    self.invalidate_search_cache();
+                }
+                self.set_status_message(format!(
+                    "Exported profile ({} file{}) to {}",
+                    names.len(),
+                    if names.len() == 1 { "" } else { "s" },
+                    out.display()
+                ));
+            }
+            Err(e) => self.set_status_message(format!("Error: profile export failed: {}", e)),
+        }
+    }
+
+    /// The open export form, if any.
+    pub fn export_form_mut(&mut self) -> Option<&mut ExportForm> {
+        match &mut self.mode {
+            AppMode::Dialog(DialogKind::ExportTree { form }) => Some(form),
+            _ => None,
+        }
+    }
+
+    /// Write the export described by the dialog. Relative paths resolve
+    /// against the tree root; an empty path copies the text to the system
+    /// clipboard instead.
+    pub fn export_confirm(&mut self, event_tx: &mpsc::UnboundedSender<crate::event::Event>) {
+        let form = match self.export_form_mut() {
+            Some(form) => form.clone(),
+            None => return,
+        };
+        self.close_dialog();
+
+        let items = match form.options.scope {
+            ExportScope::Visible => self.tree_state.flat_items.clone(),
+            ExportScope::Loaded => self.tree_state.loaded_items(),
+        };
+        let format = form.options.format.label();
+
+        let target = form.path.trim();
+        if target.is_empty() {
+            let mut buf = Vec::new();
+            if let Err(e) = export::write(&items, &form.options, &mut buf) {
+                self.set_status_message(format!("Error: {}", e));
+                return;
+            }
+            let targets = system_clipboard::copy_targets(self.config.primary_selection());
+            self.copy_to_system_clipboard(
+                "Export".to_string(),
+                String::from_utf8_lossy(&buf).to_string(),
+                targets,
+                event_tx,
+            );
+            return;
+        }
+
+        let mut path = PathBuf::from(target);
+        if path.is_relative() {
+            path = self.tree_state.root.path.join(path);
+        }
+        match export::write_file(&items, &form.options, &path) {
+            Ok(rows) => {
+                self.set_status_message(format!(
+                    "Exported {} row{} as {} to {}",
+                    rows,
+                    if rows == 1 { "" } else { "s" },
+                    format,
+                    path.display()
+                ));
+            }
+            Err(e) => self.set_status_message(format!("Error: {}: {}", path.display(), e)),
+        }
+    }
+
+    /// Move the selection in the undo confirmation dialog.
+    pub fn undo_confirm_select(&mut self, forward: bool) {
+        if let AppMode::Dialog(DialogKind::UndoConfirm {
+            conflicts,
+            selected,
+            ..
+        }) = &mut self.mode
+        {
+            let len = conflicts.len();
+            if len > 0 {
+                *selected = if forward {
+                    (*selected + 1) % len
+                } else {
+                    (*selected + len - 1) % len
+                };
+            }
+        }
+    }
+
+    /// Toggle apply/skip for the selected changed item.
+    pub fn undo_confirm_toggle(&mut self) {
+        if let AppMode::Dialog(DialogKind::UndoConfirm {
+            conflicts,
+            selected,
+            ..
+        }) = &mut self.mode
+        {
+            if let Some(c) = conflicts.get_mut(*selected) {
+                if c.can_apply() {
+                    c.apply = !c.apply;
+                }
+            }
+        }
+    }
+
+    /// Set apply/skip for every changed item at once.
+    pub fn undo_confirm_set_all(&mut self, apply: bool) {
+        if let AppMode::Dialog(DialogKind::UndoConfirm { conflicts, .. }) = &mut self.mode {
+            for c in conflicts.iter_mut() {
+                c.apply = apply && c.can_apply();
+            }
+        }
+    }
+
+    /// Apply the pending undo, skipping items the user did not approve.
+    pub fn undo_confirm_accept(&mut self) {
+        let skipped: Vec<usize> = match &self.mode {
+            AppMode::Dialog(DialogKind::UndoConfirm { conflicts, .. }) => conflicts
+                .iter()
+                .filter(|c| !c.apply)
+                .map(|c| c.item)
+                .collect(),
+            _ => return,
+        };
+        self.close_dialog();
+        if let Some(action) = self.pending_undo.take() {
+            self.undo_from_previous_session = false;
+            self.apply_undo(action, &skipped);
+        }
+    }
+
+    /// Abort the pending undo; it stays available for a later Ctrl+Z.
+    pub fn undo_confirm_cancel(&mut self) {
+        self.close_dialog();
+        self.last_undo = self.pending_undo.take();
+        self.set_status_message("Undo cancelled".to_string());
+    }
+
+    /// Apply an undo action, leaving items whose index is in `skipped` alone.
+    fn apply_undo(&mut self, action: UndoAction, skipped: &[usize]) {
+        let skip_note = |n: usize| {
+            if n == 0 {
+                String::new()
+            } else {
+                format!(" ({} skipped)", n)
+            }
+        };
+        let restored_note = |n: usize| {
+            if n == 0 {
+                String::new()
+            } else {
+                format!(
+                    ", restored {} overwritten file{}",
+                    n,
+                    if n == 1 { "" } else { "s" }
+                )
+            }
+        };
+
+        match action {
+            UndoAction::Rename { from, to } => {
+                if skipped.contains(&0) {
+                    self.set_status_message("Undo skipped".to_string());
+                    return;
+                }
+                // Rename back: from is original, to is what it was renamed to
+                match operations::rename(&to.path, &from) {
+                    Ok(()) => {
+                        if let Some(parent) = from.parent() {
+                            self.tree_state.reload_dir(parent);
+                        }
+                        self.set_status_message("Undo: rename reverted".to_string());
+                    }
+                    Err(e) => self.set_status_message(format!("Undo failed: {}", e.user_message())),
+                }
+            }
+            UndoAction::CopyPaste {
+                created_paths,
+                backups,
+            } => {
+                let mut errors = Vec::new();
+                let mut deleted = Vec::new();
+                for (i, created) in created_paths.iter().enumerate() {
+                    if skipped.contains(&i) {
+                        continue;
+                    }
+                    match operations::delete(&created.path) {
+                        Ok(()) => deleted.push(created.path.clone()),
+                        Err(e) => errors.push(e),
+                    }
+                }
+                let restored = self.restore_backups(&backups, &deleted, &mut errors);
+                self.reload_parents(deleted.iter());
+                let done = deleted.len();
+                if errors.is_empty() {
+                    self.set_status_message(format!(
+                        "Undo: deleted {} copied item{}{}{}",
+                        done,
+                        if done == 1 { "" } else { "s" },
+                        restored_note(restored),
+                        skip_note(created_paths.len() - done)
+                    ));
+                } else {
+                    self.set_status_message(format!("Undo partial: {}", describe_errors(&errors)));
+                }
+            }
+            UndoAction::MovePaste { moves, backups } => {
+                let mut errors = Vec::new();
+                let mut moved_away = Vec::new();
+                for (i, (original_src, current_dest)) in moves.iter().enumerate() {
+                    if skipped.contains(&i) {
+                        continue;
+                    }
+                    let current_dest = &current_dest.path;
+                    // Move back: current_dest → original_src
+                    if let Some(parent) = original_src.parent() {
+                        match operations::move_item(current_dest, parent) {
+                            Ok(_) => {
+                                moved_away.push(current_dest.clone());
+                                self.tree_state.reload_dir(parent);
+                                if let Some(dest_parent) = current_dest.parent() {
+                                    self.tree_state.reload_dir(dest_parent);
+                                }
+                            }
+                            Err(e) => errors.push(e),
+                        }
+                    }
+                }
+                let restored = self.restore_backups(&backups, &moved_away, &mut errors);
+                let done = moved_away.len();
+                if errors.is_empty() {
+                    self.set_status_message(format!(
+                        "Undo: moved {} item{} back{}{}",
+                        done,
+                        if done == 1 { "" } else { "s" },
+                        restored_note(restored),
+                        skip_note(moves.len() - done)
+                    ));
+                } else {
+                    self.set_status_message(format!("Undo partial: {}", describe_errors(&errors)));
+                }
+            }
+            UndoAction::SequenceRename { renames } => {
+                let pairs: Vec<(PathBuf, PathBuf)> = renames
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| !skipped.contains(i))
+                    .map(|(_, (original, current))| (current.path.clone(), original.clone()))
+                    .collect();
+                match sequence::apply(&pairs) {
+                    Ok(()) => {
+                        self.reload_parents(pairs.iter().map(|(_, original)| original));
+                        self.set_status_message(format!(
+                            "Undo: restored {} name{}{}",
+                            pairs.len(),
+                            if pairs.len() == 1 { "" } else { "s" },
+                            skip_note(renames.len() - pairs.len())
+                        ));
+                    }
+                    Err(e) => self.set_status_message(format!("Undo failed: {}", e.user_message())),
+                }
+            }
+            UndoAction::Trash { entries } => {
+                let mut errors = Vec::new();
+                let mut restored = Vec::new();
+                for (i, entry) in entries.iter().enumerate() {
+                    if skipped.contains(&i) {
+                        continue;
+                    }
+                    match trash::restore(entry) {
+                        Ok(path) => restored.push(path),
+                        Err(e) => errors.push(e),
+                    }
+                }
+                self.reload_parents(restored.iter());
+                if errors.is_empty() {
+                    self.set_status_message(format!(
+                        "Undo: restored {} item{} from the trash{}",
+                        restored.len(),
+                        if restored.len() == 1 { "" } else { "s" },
+                        skip_note(entries.len() - restored.len())
+                    ));
+                } else {
+                    self.set_status_message(format!("Undo partial: {}", describe_errors(&errors)));
+                }
+            }
+            UndoAction::Permissions { changes } => {
+                let mut errors = Vec::new();
+                let mut restored = Vec::new();
+                for (i, change) in changes.iter().enumerate() {
+                    if skipped.contains(&i) {
+                        continue;
+                    }
+                    match operations::set_permissions(&change.path, change.old_mode) {
+                        Ok(()) => restored.push(&change.path),
+                        Err(e) => errors.push(e),
+                    }
+                }
+                self.reload_parents(restored.iter().copied());
+                if errors.is_empty() {
+                    self.set_status_message(format!(
+                        "Undo: restored permissions of {} item{}{}",
+                        restored.len(),
+                        if restored.len() == 1 { "" } else { "s" },
+                        skip_note(changes.len() - restored.len())
+                    ));
+                } else {
+                    self.set_status_message(format!("Undo partial: {}", describe_errors(&errors)));
+                }
+            }
+        }
+        self.invalidate_search_cache();
+    }
+
+    /// Put back the backups of the overwritten files among `freed`, the
+    /// paths an undo just cleared. Returns how many came back.
+    fn restore_backups(
+        &mut self,
+        backups: &[Backup],
+        freed: &[PathBuf],
+        errors: &mut Vec<OperationError>,
+    ) -> usize {
+        let mut restored = Vec::new();
+        for backup in backups {
+            if !freed.iter().any(|p| p == backup.original()) {
+                continue;
+            }
+            match overwrite::restore(backup) {
+                Ok(path) => restored.push(path),
+                Err(e) => errors.push(e),
+            }
+        }
+        self.reload_parents(restored.iter());
+        restored.len()
+    }
+
+    /// Scroll preview down by one line.
+    pub fn preview_scroll_down(&mut self) {
+        let max = self.preview_max_scroll_offset();
+        self.preview_state.scroll_offset = (self.preview_state.scroll_offset + 1).min(max);
+    }
+
+    /// Scroll preview up by one line.
+    pub fn preview_scroll_up(&mut self) {
+        self.clamp_preview_scroll();
+        if self.preview_state.scroll_offset > 0 {
+            self.preview_state.scroll_offset -= 1;
+        }
+    }
+
+    /// Jump preview to the first line.
+    pub fn preview_jump_top(&mut self) {
+        self.preview_state.scroll_offset = 0;
+    }
+
+    /// Jump preview to the last line.
+    pub fn preview_jump_bottom(&mut self) {
+        self.preview_state.scroll_offset = self.preview_max_scroll_offset();
+    }
+
+    /// Scroll preview down by half a page.
+    pub fn preview_half_page_down(&mut self, visible_height: usize) {
+        let half = visible_height / 2;
+        let max = self.preview_max_scroll_offset();
+        self.preview_state.scroll_offset = (self.preview_state.scroll_offset + half).min(max);
+    }
+
+    /// Scroll preview up by half a page.
+    pub fn preview_half_page_up(&mut self, visible_height: usize) {
+        self.clamp_preview_scroll();
+        let half = visible_height / 2;
+        self.preview_state.scroll_offset = self.preview_state.scroll_offset.saturating_sub(half);
+    }
+
+    /// Scroll preview content left by a few columns.
+    pub fn preview_scroll_left(&mut self) {
+        self.preview_state.h_offset = self
+            .preview_state
+            .h_offset
+            .saturating_sub(PREVIEW_H_SCROLL_STEP);
+    }
+
+    /// Scroll preview content right, stopping once the widest line's end
+    /// is in view.
+    pub fn preview_scroll_right(&mut self) {
+        let widest = self
+            .preview_state
+            .content_lines
+            .iter()
+            .map(|line| line.width())
+            .max()
+            .unwrap_or(0);
+        let max = widest.saturating_sub(self.preview_visible_width());
+        self.preview_state.h_offset =
+            (self.preview_state.h_offset + PREVIEW_H_SCROLL_STEP).min(max);
+    }
+
+    /// Content columns of the preview panel, excluding borders and the
+    /// blame gutter.
+    fn preview_visible_width(&self) -> usize {
+        let inner = self.preview_area.width.saturating_sub(2);
+        let gutter = if self.blame_gutter().is_some() {
+            blame::GUTTER_WIDTH
+        } else {
+            0
+        };
+        inner.saturating_sub(gutter).max(1) as usize
+    }
+
+    /// Clamp preview scroll offset to valid bounds for the current viewport.
+    pub fn clamp_preview_scroll(&mut self) {
+        let max = self.preview_max_scroll_offset();
+        self.preview_state.scroll_offset = self.preview_state.scroll_offset.min(max);
+    }
+
+    fn preview_max_scroll_offset(&self) -> usize {
+        self.preview_line_count()
+            .saturating_sub(self.preview_visible_height())
+    }
+
+    /// Rows of the preview panel showing content, excluding borders and
+    /// the search bar.
+    fn preview_visible_height(&self) -> usize {
+        let bar = u16::from(self.preview_search.is_active());
+        self.preview_area.height.saturating_sub(2 + bar).max(1) as usize
+    }
+
+    fn preview_line_count(&self) -> usize {
+        if self.preview_state.content_lines.is_empty() {
+            self.preview_state.total_lines.max(1)
+        } else {
+            self.preview_state.content_lines.len()
+        }
+    }
+
+    /// Open the preview search bar (`/` with the preview focused).
+    pub fn open_preview_search(&mut self) {
+        if self.preview_state.content_lines.is_empty() {
+            return;
+        }
+        self.preview_search = PreviewSearch {
+            typing: true,
+            path: self.preview_state.current_path.clone(),
+            origin: self.preview_state.scroll_offset,
+            ..Default::default()
+        };
+        self.paste_guard = PasteGuard::default();
+        self.mode = AppMode::PreviewSearch;
+        if let Some(part) = self.partial_preview() {
+            self.set_status_message(format!("Large file: searching the loaded {} only", part));
+        }
+    }
+
+    /// Which part of a large file the preview holds, if it holds a part.
+    fn partial_preview(&self) -> Option<&'static str> {
+        if !self.preview_state.is_large_file {
+            return None;
+        }
+        Some(match self.preview_state.view_mode {
+            ViewMode::HeadAndTail => "head and tail",
+            ViewMode::HeadOnly => "head",
+            ViewMode::TailOnly => "tail",
+        })
+    }
+
+    /// Append `c` to the preview search and jump to the first match.
+    pub fn preview_search_input_char(&mut self, c: char) {
+        self.preview_search.query.push(c);
+        self.rerun_preview_search();
+    }
+
+    pub fn preview_search_delete_char(&mut self) {
+        self.preview_search.query.pop();
+        self.rerun_preview_search();
+    }
+
+    /// Paste into the preview search, flattened to one line.
+    pub fn preview_search_paste(&mut self, pasted: &str) {
+        let sanitized = paste_guard::sanitize(pasted);
+        if !sanitized.text.is_empty() {
+            self.preview_search.query.push_str(&sanitized.text);
+            self.rerun_preview_search();
+        }
+        if let Some(warning) = sanitized.warning() {
+            self.set_status_message(warning);
+        }
+    }
+
+    /// Keep the query and its highlights and hand the keys back (Enter).
+    pub fn accept_preview_search(&mut self) {
+        self.mode = AppMode::Normal;
+        self.preview_search.typing = false;
+        if self.preview_search.query.is_empty() {
+            self.close_preview_search();
+        } else if self.preview_search.matches.is_empty() {
+            let within = match self.partial_preview() {
+                Some(part) => format!(" in the loaded {}", part),
+                None => String::new(),
+            };
+            self.set_status_message(format!(
+                "No matches for \"{}\"{}",
+                self.preview_search.query, within
+            ));
+        }
+    }
+
+    /// Drop the preview search and its highlights (Esc).
+    pub fn close_preview_search(&mut self) {
+        self.preview_search = PreviewSearch::default();
+        self.preview_state.text.clear();
+        if self.mode == AppMode::PreviewSearch {
+            self.mode = AppMode::Normal;
+        }
+        self.clamp_preview_scroll();
+    }
+
+    /// Jump `count` matches forward (`n`) or back (`N`), scrolling the
+    /// match into view.
+    pub fn preview_search_step(&mut self, forward: bool, count: usize) {
+        self.sync_preview_search();
+        if !self.preview_search.is_active() {
+            return;
+        }
+        match self.preview_search.step(forward, count) {
+            Some((_, wrapped)) => {
+                self.reveal_preview_match();
+                if wrapped {
+                    self.set_status_message(
+                        if forward {
+                            "Search wrapped to the top"
+                        } else {
+                            "Search wrapped to the bottom"
+                        }
+                        .to_string(),
+                    );
+                }
+            }
+            None => {
+                self.set_status_message(format!("No matches for \"{}\"", self.preview_search.query))
+            }
+        }
+    }
+
+    /// Bring the preview search up to date with the rows on screen:
+    /// rebuild their text and matches after the rows were replaced, and
+    /// end the search once another file is shown.
+    pub fn sync_preview_search(&mut self) {
+        if !self.preview_search.is_active() {
+            return;
+        }
+        if self.preview_search.path != self.preview_state.current_path {
+            self.close_preview_search();
+            return;
+        }
+        let state = &mut self.preview_state;
+        if state.text.len() != state.content_lines.len() {
+            state.text = state
+                .content_lines
+                .iter()
+                .map(preview_search::row_text)
+                .collect();
+            self.preview_search.find(&state.text);
+        }
+    }
+
+    /// Re-run the search after the query changed and jump to the first
+    /// match below where the view was when the search opened, or back
+    /// there when nothing matches.
+    fn rerun_preview_search(&mut self) {
+        self.sync_preview_search();
+        self.preview_search.find(&self.preview_state.text);
+        let origin = self.preview_search.origin;
+        if self.preview_search.select_from(origin).is_some() {
+            self.reveal_preview_match();
+        } else {
+            self.preview_state.scroll_offset = origin;
+            self.clamp_preview_scroll();
+        }
+    }
+
+    /// Scroll the current match into view, centering it when it is off
+    /// screen.
+    fn reveal_preview_match(&mut self) {
+        let Some(found) = self.preview_search.current() else {
+            return;
+        };
+        let height = self.preview_visible_height();
+        let scroll = self.preview_state.scroll_offset;
+        if found.row < scroll || found.row >= scroll + height {
+            self.preview_state.scroll_offset = found.row.saturating_sub(height / 2);
+            self.clamp_preview_scroll();
+        }
+        if self.preview_state.line_wrap {
+            return;
+        }
+        let Some(row) = self.preview_state.text.get(found.row) else {
+            return;
+        };
+        let start = text::width(&row[..found.start]);
+        let end = start + text::width(&row[found.start..found.end]);
+        let width = self.preview_visible_width();
+        let h_offset = self.preview_state.h_offset;
+        if start < h_offset || end > h_offset + width {
+            self.preview_state.h_offset = if end <= width {
+                0
+            } else {
+                start.saturating_sub(width / 2)
+            };
+        }
+    }
+
+    /// Enter edit mode for the currently previewed file, optionally with
+    /// the cursor on 0-based `cursor_line` (centered in the view).
+    /// Returns false if editing is not possible (binary, directory, etc.).
+    pub fn enter_edit_mode(&mut self, cursor_line: Option<usize>) -> bool {
+        // Must be in Normal mode with preview focused
+        if self.mode != AppMode::Normal || self.focused_panel != FocusedPanel::Preview {
+            return false;
+        }
+
+        let path = match &self.preview_state.current_path {
+            Some(p) => p.clone(),
+            None => return false,
+        };
+
+        #[cfg(feature = "editor")]
+        {
+            self.open_editor(path, cursor_line)
+        }
+        #[cfg(not(feature = "editor"))]
+        {
+            let _ = (path, cursor_line);
+            self.set_status_message("Cannot edit: built without editor support".to_string());
+            false
+        }
+    }
+
+    /// Load `path` into the editor for [`App::enter_edit_mode`], unless it
+    /// is a directory, special file or binary.
+    #[cfg(feature = "editor")]
+    fn open_editor(&mut self, path: PathBuf, cursor_line: Option<usize>) -> bool {
+        // Guard: directories cannot be edited
+        if let Some(item) = self
+            .tree_state
+            .flat_items
+            .get(self.tree_state.selected_index)
+        {
+            if item.node_type == NodeType::Directory {
+                return false;
+            }
+        }
+
+        // Guard: FIFOs and device nodes would block or never end
+        if let Some(special) = SpecialFile::at(&path) {
+            self.set_status_message(format!(
+                "Cannot edit {}: {}",
+                dir_label(&path),
+                special.label()
+            ));
+            return false;
+        }
+
+        // Guard: binary files cannot be edited
+        if crate::preview_content::is_binary_file(&path) {
+            self.set_status_message("Cannot edit binary files".to_string());
+            return false;
+        }
+
+        // Warn about large files
+        let file_size = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        let max_preview = self.config.max_full_preview_bytes();
+        if file_size > max_preview {
+            self.set_status_message(format!(
+                "⚠ Large file ({:.1} KB) — editing may be slow",
+                file_size as f64 / 1024.0
+            ));
+        }
+
+        // Load file into editor state
+        match EditorState::from_file(&path) {
+            Ok(mut state) => {
+                state.tab_width = self.preview_tab_width();
+                if let Some(line) = cursor_line {
+                    state.set_cursor_position(line, 0);
+                    state.scroll_offset = state
+                        .cursor_line
+                        .saturating_sub(self.preview_visible_height() / 2);
+                } else if let Some(position) = self.edit_positions.get(&path) {
+                    // Clamped to the buffer: the file may have shrunk since
+                    state.set_cursor_position(position.line, position.col);
+                    state.scroll_offset = position.scroll.min(state.cursor_line);
+                    state.ensure_cursor_visible();
+                    if state.cursor_line > 0 {
+                        self.set_status_message(format!(
+                            "Restored position (line {})",
+                            state.cursor_line + 1
+                        ));
+                    }
+                }
+                self.editor_state = Some(state);
+                self.mode = AppMode::Edit;
+                // Pause watcher to avoid conflicts during editing
+                self.watcher_active = false;
+                true
+            }
+            Err(e) => {
+                self.set_status_message(format!("Cannot edit: {}", e));
+                false
+            }
+        }
+    }
+
+    /// Exit edit mode and return to normal mode.
+    /// Does NOT check for unsaved changes — caller should handle save confirmation.
+    #[cfg(feature = "editor")]
+    pub fn exit_edit_mode(&mut self) {
+        self.remember_edit_position();
+        self.editor_state = None;
+        self.mode = AppMode::Normal;
+        // Resume watcher
+        self.watcher_active = true;
+        // Force re-preview of the current file to reflect any saved changes
+        self.last_previewed_index = None;
+    }
+
+    /// Ask the main loop to open the selected file in `$EDITOR`.
+    pub fn request_external_edit(&mut self) {
+        let Some(item) = self
+            .tree_state
+            .flat_items
+            .get(self.tree_state.selected_index)
+        else {
+            return;
+        };
+        if item.node_type == NodeType::LoadMore {
+            return;
+        }
+        if !item.path.is_file() {
+            self.set_status_message(format!("Not a file: {}", item.name));
+            return;
+        }
+        self.external_edit = Some(item.path.clone());
+    }
+
+    /// Handle the end of an external edit of `path`: the watcher was
+    /// paused, so reload its directory and preview here.
+    pub fn finish_external_edit(&mut self, path: &Path, result: std::result::Result<(), String>) {
+        self.preview_cache.remove_path(path);
+        self.handle_fs_change(vec![path.to_path_buf()]);
+        self.last_previewed_index = None;
+        match result {
+            Ok(()) => {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                self.set_status_message(format!("Edited: {}", name));
+            }
+            Err(message) => self.set_status_message(message),
+        }
+    }
+
+    /// Window title for the current selection, `None` with
+    /// `general.set_title` off.
+    pub fn window_title(&self) -> Option<String> {
+        if !self.config.set_title() {
+            return None;
+        }
+        let root = &self.tree_state.root.path;
+        let selected = self
+            .tree_state
+            .flat_items
+            .get(self.tree_state.selected_index)
+            .map(|item| item.path.as_path())
+            .unwrap_or(root);
+        Some(title::render(
+            self.config.title_template(),
+            &path_display::home_relative(selected, &self.homes),
+            &path_display::home_relative(root, &self.homes),
+            &self.mode.label().to_lowercase(),
+        ))
+    }
+
+    /// Window title to write now, if one is due (see [`TitleState`]).
+    pub fn poll_title(&mut self, now: Instant) -> Option<String> {
+        let wanted = self.window_title()?;
+        self.title.poll(wanted, now)
+    }
+
+    /// Record the open editor's cursor and scroll for its file.
+    #[cfg(feature = "editor")]
+    pub fn remember_edit_position(&mut self) {
+        if let Some(editor) = &self.editor_state {
+            let position = EditPosition {
+                line: editor.cursor_line,
+                col: editor.cursor_col,
+                scroll: editor.scroll_offset,
+            };
+            self.edit_positions.remember(&editor.file_path, position);
+        }
+    }
+
+    /// Save the editor buffer to disk.
+    /// Returns Ok(()) on success or Err with message on failure.
+    #[cfg(feature = "editor")]
+    pub fn save_editor_buffer(&mut self) -> std::result::Result<(), String> {
+        if let Some(ref mut editor) = self.editor_state {
+            editor.normalize_for_save(
+                self.config.trim_trailing_whitespace(),
+                self.config.ensure_final_newline(),
+            );
+            match editor.save() {
+                Ok(()) => {
+                    self.preview_cache.remove_path(&editor.file_path);
+                    self.set_status_message("File saved".to_string());
+                    Ok(())
+                }
+                Err(e) => {
+                    let msg = format!("Save failed: {}", e);
+                    self.set_status_message(msg.clone());
+                    Err(msg)
+                }
+            }
+        } else {
+            Err("No editor state".to_string())
+        }
+    }
+
+    /// Update preview content when the selected tree item changes.
+    pub fn update_preview(&mut self) {
+        let idx = self.tree_state.selected_index;
+        if self.last_previewed_index == Some(idx) {
+            return; // No change
+        }
+        self.last_previewed_index = Some(idx);
+
+        let item = match self.tree_state.flat_items.get(idx) {
+            Some(item) => item,
+            None => return,
+        };
+
+        // Check if we're reloading the same path (e.g., after FS watcher event).
+        // If so, preserve the current scroll offset.
+        let same_path = self
+            .preview_state
+            .current_path
+            .as_ref()
+            .map(|p| p == &item.path)
+            .unwrap_or(false);
+        let (preserved_scroll, preserved_h_offset, preserved_tab_width) = if same_path {
+            (
+                self.preview_state.scroll_offset,
+                self.preview_state.h_offset,
+                self.preview_state.tab_width,
+            )
+        } else {
+            (0, 0, None)
+        };
+        let tab_width = preserved_tab_width.unwrap_or_else(|| self.config.tab_width());
+
+        if item.node_type == NodeType::Symlink {
+            self.preview_load.abandon();
+            let (content_lines, total_lines) =
+                crate::preview_content::load_symlink_summary(&item.path);
+            self.preview_state = PreviewState {
+                current_path: Some(item.path.clone()),
+                content_lines,
+                total_lines,
+                ..Default::default()
+            };
+            return;
+        }
+        if let Some(special) = item.special {
+            self.preview_load.abandon();
+            let (content_lines, total_lines) =
+                crate::preview_content::load_special_summary(&item.path, special);
+            self.preview_state = PreviewState {
+                current_path: Some(item.path.clone()),
+                content_lines,
+                total_lines,
+                ..Default::default()
+            };
+            return;
+        }
+        if item.node_type != NodeType::Directory && item.node_type != NodeType::File {
+            self.preview_load.abandon();
+            self.preview_state = PreviewState::default();
+            return;
+        }
+
+        let path = item.path.clone();
+        let request = LoadRequest {
+            is_dir: item.node_type == NodeType::Directory,
+            tab_width,
+            head_lines: self.config.head_lines(),
+            tail_lines: self.config.tail_lines(),
+            max_full_bytes: self.config.max_full_preview_bytes(),
+            raw_front_matter: self.raw_front_matter,
+            cached: self
+                .preview_cache
+                .key_for(&path, tab_width, self.raw_front_matter),
+            syntax: Arc::clone(&self.syntax),
+            path,
+        };
+        let view = KeptView {
+            scroll: preserved_scroll,
+            h_offset: preserved_h_offset,
+            tab_width: preserved_tab_width,
+        };
+        self.start_preview_load(request, view);
+    }
+
+    /// Start loading a preview, showing it right away if it is quick and
+    /// the loading placeholder otherwise.
+    fn start_preview_load(&mut self, request: LoadRequest, view: KeptView) {
+        let path = request.path.clone();
+        let head_lines = request.head_lines;
+        let tail_lines = request.tail_lines;
+        let loader = Arc::clone(&self.preview_loader);
+        let now = Instant::now();
+        match self.preview_load.start(loader, request, view, now) {
+            Some((request, view, loaded)) => self.apply_preview_load(request, view, loaded),
+            None => {
+                self.preview_state = PreviewState {
+                    current_path: Some(path),
+                    head_lines,
+                    tail_lines,
+                    tab_width: view.tab_width,
+                    ..Default::default()
+                };
+                self.show_load_placeholder(now);
+            }
+        }
+    }
+
+    /// Show a finished load in the preview.
+    fn apply_preview_load(&mut self, request: LoadRequest, view: KeptView, loaded: Loaded) {
+        let (lines, total) = match loaded.content {
+            LoadedContent::Cached(key) => match self.preview_cache.get(&key) {
+                Some(hit) => {
+                    self.prefetch.record_lookup(&request.path, true);
+                    hit
+                }
+                None => {
+                    // Evicted while the file was checked: render it after all
+                    let request = LoadRequest {
+                        cached: None,
+                        ..request
+                    };
+                    self.start_preview_load(request, view);
+                    return;
+                }
+            },
+            LoadedContent::Rendered {
+                lines,
+                total,
+                cache_key,
+            } => {
+                if let Some(key) = cache_key {
+                    self.prefetch.record_lookup(&request.path, false);
+                    self.preview_cache.insert(key, lines.clone(), total);
+                }
+                (lines, total)
+            }
+        };
+        self.preview_state = PreviewState {
+            current_path: Some(request.path),
+            content_lines: lines,
+            scroll_offset: view.scroll,
+            h_offset: view.h_offset,
+            view_mode: if loaded.is_large {
+                ViewMode::HeadAndTail
+            } else {
+                ViewMode::default()
+            },
+            line_wrap: false,
+            total_lines: total,
+            is_large_file: loaded.is_large,
+            head_lines: request.head_lines,
+            tail_lines: request.tail_lines,
+            tab_width: view.tab_width,
+            final_newline: loaded.final_newline,
+            text: Vec::new(),
+        };
+        if request.is_dir {
+            self.show_dir_size_in_preview();
+        }
+        self.clamp_preview_scroll();
+        self.report_syntax_warnings();
+    }
+
+    /// Point out user syntax files that failed to load, once the syntax
+    /// set has been loaded by something.
+    fn report_syntax_warnings(&mut self) {
+        let warnings = self.syntax.take_warnings();
+        if let Some(first) = warnings.first() {
+            let more = warnings.len() - 1;
+            self.set_status_message(if more > 0 {
+                format!("⚠ syntax {} (+{} more)", first, more)
+            } else {
+                format!("⚠ syntax {}", first)
+            });
+        }
+    }
+
+    /// Put the loading (or cancelled) placeholder in the preview, as long
+    /// as it still shows the path being loaded.
+    fn show_load_placeholder(&mut self, now: Instant) {
+        if self.preview_state.current_path.as_deref() != self.preview_load.path() {
+            return;
+        }
+        if let Some(text) = self.preview_load.placeholder(now) {
+            self.preview_state.content_lines = vec![Line::from(Span::styled(
+                text,
+                Style::default().fg(Color::DarkGray),
+            ))];
+            self.preview_state.total_lines = 1;
+            self.preview_state.text.clear();
+        }
+    }
+
+    /// Pick up a preview load that finished in the background, time out a
+    /// stuck one, and keep the placeholder's elapsed time current. Called
+    /// on every tick.
+    pub fn poll_preview_load(&mut self, now: Instant) {
+        match self
+            .preview_load
+            .poll(now, self.config.preview_load_timeout())
+        {
+            LoadPoll::Idle => {}
+            LoadPoll::Waiting => self.show_load_placeholder(now),
+            LoadPoll::Finished(finished) => {
+                let (request, view, loaded) = *finished;
+                self.apply_preview_load(request, view, loaded)
+            }
+        }
+        self.poll_preview_highlight();
+    }
+
+    /// Put the rows highlighted in the background over their plain text,
+    /// and cache the preview once the last of them are in.
+    pub fn poll_preview_highlight(&mut self) {
+        if self.preview_state.current_path.as_deref() != self.preview_load.highlighting_path() {
+            return;
+        }
+        let highlighted = self.preview_load.take_highlighted();
+        let content = &mut self.preview_state.content_lines;
+        for rows in highlighted.rows {
+            let end = (rows.start + rows.lines.len()).min(content.len());
+            if rows.start < end {
+                content.splice(
+                    rows.start..end,
+                    rows.lines.into_iter().take(end - rows.start),
+                );
+            }
+        }
+        if let Some(key) = highlighted.cache_key {
+            self.preview_cache.insert(
+                key,
+                self.preview_state.content_lines.clone(),
+                self.preview_state.total_lines,
+            );
+        }
+    }
+
+    /// Show another chunk of highlighted rows, unless the preview has
+    /// moved on since.
+    pub fn handle_preview_highlighted(&mut self, generation: u64) {
+        if self.preview_load.is_highlighting(generation) {
+            self.poll_preview_highlight();
+        }
+    }
+
+    /// Show a load that just finished on its thread, unless a newer one
+    /// has replaced it since.
+    pub fn handle_preview_ready(&mut self, generation: u64) {
+        if self.preview_load.is_running(generation) {
+            self.poll_preview_load(Instant::now());
+        }
+    }
+
+    /// Cancel the running preview load (Esc / `x` in the preview).
+    pub fn cancel_preview_load(&mut self) {
+        if self.preview_load.cancel() {
+            self.show_load_placeholder(Instant::now());
+        }
+    }
+
+    /// Start a cancelled or timed-out preview load again (`r`).
+    pub fn retry_preview_load(&mut self) {
+        if let Some((request, view)) = self.preview_load.take_retry() {
+            self.start_preview_load(request, view);
+        }
+    }
+
+    /// Cycle view mode for large file preview (Ctrl+T).
+    #[allow(dead_code)]
+    pub fn cycle_view_mode(&mut self) {
+        if !self.preview_state.is_large_file {
+            return;
+        }
+        self.preview_state.view_mode = match self.preview_state.view_mode {
+            ViewMode::HeadAndTail => ViewMode::HeadOnly,
+            ViewMode::HeadOnly => ViewMode::TailOnly,
+            ViewMode::TailOnly => ViewMode::HeadAndTail,
+        };
+        self.reload_large_preview();
+    }
+
+    /// Tab width the current preview is rendered with.
+    pub fn preview_tab_width(&self) -> usize {
+        self.preview_state
+            .tab_width
+            .unwrap_or_else(|| self.config.tab_width())
+    }
+
+    /// Step the current preview's tab width through 2 → 4 → 8 (`t`).
+    ///
+    /// The choice sticks while the same file stays selected, including
+    /// watcher reloads, and resets to `preview.tab_width` on the next file.
+    /// Switch Markdown front matter between the card and its raw YAML.
+    pub fn toggle_front_matter(&mut self) {
+        self.raw_front_matter = !self.raw_front_matter;
+        let shown = if self.raw_front_matter { "raw" } else { "card" };
+        self.set_status_message(format!("Front matter: {}", shown));
+        // Same path, so update_preview keeps the scroll position
+        self.last_previewed_index = None;
+        self.update_preview();
+    }
+
+    pub fn cycle_preview_tab_width(&mut self) {
+        match &self.preview_state.current_path {
+            Some(path) if path.is_file() => {}
+            _ => return,
+        }
+        let next = match self.preview_tab_width() {
+            w if w < 4 => 4,
+            w if w < 8 => 8,
+            _ => 2,
+        };
+        self.preview_state.tab_width = Some(next);
+        if self.preview_state.is_large_file {
+            let scroll = self.preview_state.scroll_offset;
+            self.reload_large_preview();
+            self.preview_state.scroll_offset = scroll;
+            self.clamp_preview_scroll();
+        } else {
+            // Same path, so update_preview keeps scroll and the override
+            self.last_previewed_index = None;
+            self.update_preview();
+        }
+        self.set_status_message(format!("Tab width: {}", next));
+    }
+
+    /// Preview the next (or previous) file in the selected file's directory
+    /// (`]` / `[`), keeping focus on the preview.
+    /// `count` steps at once (`3]`) load only the file landed on.
+    pub fn preview_step_file(&mut self, forward: bool, count: usize) {
+        let wrap = self.config.wrap_file_navigation();
+        let mut stepped = false;
+        for _ in 0..count {
+            if !self.tree_state.step_sibling_file(forward, wrap) {
+                break;
+            }
+            stepped = true;
+        }
+        if stepped {
+            self.invalidate_search_cache();
+            self.last_previewed_index = None;
+            self.update_preview();
+        } else if self.tree_state.sibling_file_position().is_some() {
+            self.set_status_message(
+                if forward {
+                    "Last file in directory"
+                } else {
+                    "First file in directory"
+                }
+                .to_string(),
+            );
+        }
+    }
+
+    /// "7/23": the previewed file's position among its directory's files.
+    pub fn preview_file_position(&self) -> Option<String> {
+        let item = self
+            .tree_state
+            .flat_items
+            .get(self.tree_state.selected_index)?;
+        if self.preview_state.current_path.as_ref() != Some(&item.path) {
+            return None;
+        }
+        let (position, total) = self.tree_state.sibling_file_position()?;
+        Some(format!("{}/{}", position, total))
+    }
+
+    /// Adjust head/tail line counts by a delta (+/- keys).
+    pub fn adjust_preview_lines(&mut self, delta: isize) {
+        if !self.preview_state.is_large_file {
+            return;
+        }
+        let step = delta.unsigned_abs();
+        if delta > 0 {
+            self.preview_state.head_lines += step;
+            self.preview_state.tail_lines += step;
+        } else {
+            self.preview_state.head_lines =
+                self.preview_state.head_lines.saturating_sub(step).max(5);
+            self.preview_state.tail_lines =
+                self.preview_state.tail_lines.saturating_sub(step).max(5);
+        }
+        self.reload_large_preview();
+    }
+
+    /// Reload the large file preview with current settings.
+    fn reload_large_preview(&mut self) {
+        if let Some(ref path) = self.preview_state.current_path {
+            let path = path.clone();
+            let syntax = self.syntax.get();
+            let (lines, total) = preview_content::load_head_tail_content(
+                &path,
+                syntax,
+                self.preview_state.head_lines,
+                self.preview_state.tail_lines,
+                self.preview_state.view_mode,
+                self.preview_tab_width(),
+            );
+            self.preview_state.content_lines = lines;
+            self.preview_state.total_lines = total;
+            self.preview_state.scroll_offset = 0;
+            self.preview_state.text.clear();
+        }
+    }
+
+    /// Append `digit` to the pending count, capped at [`MAX_COUNT`].
+    pub fn push_count_digit(&mut self, digit: u32) {
+        let count = self.pending_count.unwrap_or(0);
+        self.pending_count = Some((count * 10 + digit as usize).min(MAX_COUNT));
+    }
+
+    /// Consume the pending count; 1 when none was typed.
+    pub fn take_count(&mut self) -> usize {
+        self.pending_count.take().unwrap_or(1).max(1)
+    }
+
+    /// Note a key press at `now` for navigation acceleration.
+    pub fn note_key(&mut self, key: &KeyEvent, now: Instant) {
+        if let Some(settings) = self.config.key_repeat() {
+            self.key_repeat.press(key, now, settings.window);
+        }
+    }
+
+    /// Rows the latest navigation key moves: more than one while it is
+    /// held, when acceleration is on.
+    pub fn repeat_step(&self) -> usize {
+        self.config
+            .key_repeat()
+            .map_or(1, |settings| self.key_repeat.step(&settings))
+    }
+
+    /// Whether the latest key is a held navigation key being accelerated;
+    /// the redraw after it can wait for the events queued behind it.
+    pub fn accelerating(&self) -> bool {
+        self.config
+            .key_repeat()
+            .is_some_and(|settings| self.key_repeat.accelerating(&settings))
+    }
+
+    /// Quit the application, cancelling every background task. While a
+    /// copy or delete is still running the first request only warns.
+    pub fn quit(&mut self) {
+        let now = Instant::now();
+        let armed = self
+            .quit_armed
+            .is_some_and(|at| now.duration_since(at) < QUIT_CONFIRM_WINDOW);
+        if !armed && self.tasks.running_where(|task| task.kind.touches_files()) > 0 {
+            self.quit_armed = Some(now);
+            self.set_status_message(
+                "A file operation is still running — q again to cancel it and quit".to_string(),
+            );
+            return;
+        }
+        self.tasks.cancel_all();
+        self.should_quit = true;
+    }
+
+    /// Move selection down by one item.
+    pub fn select_next(&mut self) {
+        let len = self.tree_state.flat_items.len();
+        if len > 0 && self.tree_state.selected_index < len - 1 {
+            self.tree_state.selected_index += 1;
+        }
+    }
+
+    /// Move selection up by one item.
+    pub fn select_previous(&mut self) {
+        if self.tree_state.selected_index > 0 {
+            self.tree_state.selected_index -= 1;
+        }
+    }
+
+    /// Jump to the first item.
+    pub fn select_first(&mut self) {
+        self.tree_state.selected_index = 0;
+    }
+
+    /// Jump to the last item.
+    pub fn select_last(&mut self) {
+        let len = self.tree_state.flat_items.len();
+        if len > 0 {
+            self.tree_state.selected_index = len - 1;
+        }
+    }
+
+    /// Expand the selected directory or directory symlink (or no-op on
+    /// files). A symlink leading back to one of its ancestors stays
+    /// collapsed.
+    pub fn expand_selected(&mut self) {
+        let Some(item) = self
+            .tree_state
+            .flat_items
+            .get(self.tree_state.selected_index)
+            .filter(|item| item.is_expandable())
+        else {
+            return;
+        };
+        if item.depth >= self.tree_state.max_depth {
+            self.set_status_message(self.depth_limit_message());
+            return;
+        }
+        if item.node_type == NodeType::Symlink {
+            if let Some(ancestor) = self.tree_state.link_loops(&item.path) {
+                let message = format!(
+                    "Not expanding {}: it links back to {}",
+                    item.name,
+                    path_display::relative(&ancestor, &self.tree_state.root.path)
+                );
+                self.set_status_message(message);
+                return;
+            }
+        }
+        self.tree_state.expand_selected();
+        self.invalidate_search_cache();
+        self.sync_watches();
+    }
+
+    fn depth_limit_message(&self) -> String {
+        format!(
+            "Depth limit reached (tree.max_depth = {})",
+            self.tree_state.max_depth
+        )
+    }
+
+    /// Collapse the selected directory, or jump to parent if on a file or collapsed directory.
+    pub fn collapse_selected(&mut self) {
+        self.tree_state.collapse_selected();
+        self.sync_watches();
+    }
+
+    /// Toggle hidden file visibility.
+    pub fn toggle_hidden(&mut self) {
+        self.tree_state.toggle_hidden();
+        self.invalidate_search_cache();
+        self.sync_watches();
+    }
+
+    /// `I`: toggle hiding what `.gitignore` leaves out, in the tree, the
+    /// filter, the fuzzy finder and the watcher.
+    pub fn toggle_gitignore(&mut self) {
+        let on = !self.tree_state.respect_gitignore;
+        self.tree_state.set_respect_gitignore(on);
+        self.watcher.set_respect_gitignore(on);
+        self.invalidate_search_cache();
+        self.sync_watches();
+        self.set_status_message(if on {
+            "Hiding gitignored files".to_string()
+        } else {
+            "Showing gitignored files".to_string()
+        });
+    }
+
+    /// `path` in the current path style.
+    pub fn show_path(&self, path: &Path) -> String {
+        path_display::show(
+            path,
+            self.path_style,
+            &self.tree_state.root.path,
+            &self.homes,
+        )
+    }
+
+    /// Cycle how paths are shown: relative, absolute, `~/`.
+    pub fn cycle_path_style(&mut self) {
+        self.path_style = self.path_style.next();
+        self.set_status_message(format!("Paths: {}", self.path_style.label()));
+    }
+
+    // === Per-directory sort ===
+
+    /// Directory the per-directory sort keys apply to: the selected
+    /// directory, or the one containing the selected entry.
+    fn sort_target_dir(&self) -> Option<PathBuf> {
+        let item = self
+            .tree_state
+            .flat_items
+            .get(self.tree_state.selected_index)?;
+        match item.node_type {
+            NodeType::Directory => Some(item.path.clone()),
+            NodeType::LoadMore => item.load_more_parent.clone(),
+            _ => item.path.parent().map(Path::to_path_buf),
+        }
+    }
+
+    /// "name: Size" for the sort override on the selected directory, if any.
+    pub fn selected_sort_override(&self) -> Option<String> {
+        let dir = self.sort_target_dir()?;
+        let (sort_by, dirs_first) = self.tree_state.sort_overrides.get(&dir)?;
+        Some(format!(
+            "{}: {}",
+            dir_label(&dir),
+            sort_label(sort_by, *dirs_first)
+        ))
+    }
+
+    /// Cycle the sort mode of the selected directory only.
+    pub fn cycle_dir_sort(&mut self) {
+        self.change_dir_sort(TreeState::cycle_dir_sort);
+    }
+
+    /// Toggle dirs-first for the selected directory only.
+    pub fn toggle_dir_dirs_first(&mut self) {
+        self.change_dir_sort(TreeState::toggle_dir_dirs_first);
+    }
+
+    /// Drop the selected directory's override so it follows the global sort.
+    pub fn clear_dir_sort(&mut self) {
+        let dir = match self.sort_target_dir() {
+            Some(dir) => dir,
+            None => return,
+        };
+        let selected = self.selected_path();
+        if self.tree_state.clear_sort_override(&dir) {
+            self.reselect_after_sort(selected, &dir);
+            self.set_status_message(format!("Sort override cleared for {}", dir_label(&dir)));
+        } else {
+            self.set_status_message(format!("No sort override on {}", dir_label(&dir)));
+        }
+    }
+
+    fn change_dir_sort(&mut self, change: fn(&mut TreeState, &Path)) {
+        let dir = match self.sort_target_dir() {
+            Some(dir) => dir,
+            None => return,
+        };
+        let selected = self.selected_path();
+        change(&mut self.tree_state, &dir);
+        self.reselect_after_sort(selected, &dir);
+        let (sort_by, dirs_first) = self.tree_state.sort_for(&dir);
+        self.set_status_message(format!(
+            "Sort for {}: {} (override)",
+            dir_label(&dir),
+            sort_label(&sort_by, dirs_first)
+        ));
+    }
+
+    fn selected_path(&self) -> Option<PathBuf> {
+        self.tree_state
+            .flat_items
+            .get(self.tree_state.selected_index)
+            .map(|item| item.path.clone())
+    }
+
+    /// Keep the selection on the same entry after re-sorting `dir`, falling
+    /// back to `dir` itself if the entry is no longer on a loaded page.
+    fn reselect_after_sort(&mut self, selected: Option<PathBuf>, dir: &Path) {
+        let index = selected
+            .and_then(|path| self.tree_state.find_index_by_path(&path))
+            .or_else(|| self.tree_state.find_index_by_path(dir));
+        if let Some(index) = index {
+            self.tree_state.selected_index = index;
+        }
+        self.invalidate_search_cache();
+        self.last_previewed_index = None;
+    }
+
+    // === Open (Enter on files) ===
+
+    /// Path of the selected item if it is a file (or a symlink to one).
+    pub fn selected_file_path(&self) -> Option<PathBuf> {
+        let item = self
+            .tree_state
+            .flat_items
+            .get(self.tree_state.selected_index)?;
+        match item.node_type {
+            NodeType::File => Some(item.path.clone()),
+            NodeType::Symlink if !item.path.is_dir() => Some(item.path.clone()),
+            _ => None,
+        }
+    }
+
+    /// Run the `[open]` action for the selected file.
+    /// Returns the dispatched action, or `None` if the selection is not a file.
+    pub fn open_selected_file(
+        &mut self,
+        event_tx: &mpsc::UnboundedSender<crate::event::Event>,
+    ) -> Option<OpenAction> {
+        let path = self.selected_file_path()?;
+        let action = self.open_rules.action_for(&path).clone();
+        match action {
+            OpenAction::Edit => {
+                self.focused_panel = FocusedPanel::Preview;
+                // Force preview update so enter_edit_mode can find the file
+                self.update_preview();
+                self.enter_edit_mode(None);
+            }
+            OpenAction::PreviewFocus => self.focused_panel = FocusedPanel::Preview,
+            OpenAction::External => self.open_external(&path),
+            OpenAction::Terminal(ref template) => {
+                let command = open_action::expand_command(template, &path);
+                self.run_in_terminal(&command, event_tx);
+            }
+        }
+        Some(action)
+    }
+
+    /// Open the selected file with the system opener, ignoring `[open]`.
+    pub fn open_selected_external(&mut self) {
+        if let Some(path) = self.selected_file_path() {
+            self.open_external(&path);
+        }
+    }
+
+    fn open_external(&mut self, path: &Path) {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        match self.launcher.open_external(path) {
+            Ok(()) => self.set_status_message(format!("Opened {} externally", name)),
+            Err(e) => self.set_status_message(format!("Error: {}", e)),
+        }
+    }
+
+    fn run_in_terminal(
+        &mut self,
+        command: &str,
+        event_tx: &mpsc::UnboundedSender<crate::event::Event>,
+    ) {
+        // Ensure terminal is visible (spawns PTY if needed)
+        if !self.terminal_state.visible {
+            self.toggle_terminal(event_tx);
+            if !self.terminal_state.visible {
+                return; // disabled; toggle_terminal already reported why
+            }
+        }
+        match self.launcher.run_in_terminal(&self.terminal_state, command) {
+            Ok(()) => {
+                self.focused_panel = FocusedPanel::Terminal;
+                self.set_status_message(format!("Terminal: {}", command));
+            }
+            Err(e) => self.set_status_message(format!("Error: {}", e)),
+        }
+    }
+
+    // === Search (Ctrl+P) methods ===
+
+    /// Open the fuzzy finder overlay.
+    pub fn open_search(&mut self) {
+        // The background index normally got here first; without one, index
+        // now, within `path_index::TIME_LIMIT`
+        if self.search_state.cached_paths.is_none() {
+            let index = self.build_path_index();
+            self.search_state.cached_paths = Some(index.paths);
+            self.search_state.coverage = index.coverage;
+        }
+        self.search_state.query.clear();
+        self.search_state.cursor_position = 0;
+        self.paste_guard = PasteGuard::default();
+        self.search_state.results.clear();
+        self.search_state.selected_index = 0;
+        self.mode = AppMode::Search;
+    }
+
+    /// Close the fuzzy finder overlay without navigating.
+    pub fn close_search(&mut self) {
+        self.mode = AppMode::Normal;
+        // Filesystem events kept the index current but left the tree
+        // alone while in Search mode; force preview refresh in case
+        // selection changed externally.
+        self.last_previewed_index = None;
+    }
+
+    /// Insert a character into the search query and re-score.
+    pub fn search_input_char(&mut self, c: char) {
+        if c.is_control() {
+            return;
+        }
+        self.search_state
+            .query
+            .insert(self.search_state.cursor_position, c);
+        self.search_state.cursor_position += c.len_utf8();
+        self.update_search_results();
+    }
+
+    /// Insert pasted text into the search query, flattened onto one line.
+    pub fn search_paste(&mut self, pasted: &str) {
+        let sanitized = paste_guard::sanitize(pasted);
+        let state = &mut self.search_state;
+        state
+            .query
+            .insert_str(state.cursor_position, &sanitized.text);
+        state.cursor_position += sanitized.text.len();
+        self.update_search_results();
+        if let Some(warning) = sanitized.warning() {
+            self.set_status_message(warning);
+        }
+    }
+
+    /// Delete the character before the cursor in the search query.
+    pub fn search_delete_char(&mut self) {
+        if self.search_state.cursor_position > 0 {
+            let byte_pos = self.search_state.cursor_position;
+            let prev_char = self.search_state.query[..byte_pos]
+                .chars()
+                .next_back()
+                .expect("cursor > 0 guarantees at least one char");
+            self.search_state.cursor_position -= prev_char.len_utf8();
+            self.search_state
+                .query
+                .remove(self.search_state.cursor_position);
+            self.update_search_results();
+        }
+    }
+
+    /// Move search result selection down.
+    pub fn search_select_next(&mut self) {
+        if !self.search_state.results.is_empty()
+            && self.search_state.selected_index < self.search_state.results.len() - 1
+        {
+            self.search_state.selected_index += 1;
+        }
+    }
+
+    /// Move search result selection up.
+    pub fn search_select_previous(&mut self) {
+        if self.search_state.selected_index > 0 {
+            self.search_state.selected_index -= 1;
+        }
+    }
+
+    /// Confirm the selected search result: open action menu for that file.
+    pub fn search_confirm(&mut self) {
+        if let Some(result) = self
+            .search_state
+            .results
+            .get(self.search_state.selected_index)
+        {
+            let path = result.path.clone();
+            let display = self.show_path(&path);
+            let (is_directory, is_binary) = Self::detect_file_type(&path);
+            self.search_action_state = Some(SearchActionState {
+                path,
+                display,
+                is_directory,
+                is_binary,
+                line: self.search_state.target_line,
+                col: self.search_state.target_col,
+            });
+            self.mode = AppMode::SearchAction;
+        }
+    }
+
+    /// Detect whether a path is a directory and/or a binary file.
+    pub fn detect_file_type(path: &Path) -> (bool, bool) {
+        let is_directory = path.is_dir();
+        let is_binary = if is_directory {
+            false
+        } else {
+            crate::preview_content::is_binary_file(path)
+        };
+        (is_directory, is_binary)
+    }
+
+    /// Return from the action menu to the search overlay (preserving query/results).
+    pub fn search_action_back(&mut self) {
+        self.search_action_state = None;
+        self.mode = AppMode::Search;
+    }
+
+    /// Close both search action and search overlays, return to Normal mode.
+    pub fn close_search_action(&mut self) {
+        self.search_action_state = None;
+        self.mode = AppMode::Normal;
+        self.invalidate_search_cache();
+        self.last_previewed_index = None;
+    }
+
+    /// Search action: navigate to the file in the tree.
+    pub fn search_action_navigate(&mut self) {
+        if let Some(state) = self.search_action_state.take() {
+            self.mode = AppMode::Normal;
+            self.invalidate_search_cache();
+            self.last_previewed_index = None;
+            match state.line {
+                Some(line) => self.navigate_to_location(&state.path, line),
+                None => self.navigate_to_path(&state.path),
+            }
+        }
+    }
+
+    /// Search action: navigate to file and focus preview panel.
+    pub fn search_action_preview(&mut self) {
+        if let Some(state) = self.search_action_state.take() {
+            self.mode = AppMode::Normal;
+            self.invalidate_search_cache();
+            self.last_previewed_index = None;
+            match state.line {
+                Some(line) => self.navigate_to_location(&state.path, line),
+                None => self.navigate_to_path(&state.path),
+            }
+            self.focused_panel = FocusedPanel::Preview;
+        }
+    }
+
+    /// Search action: navigate to file and enter edit mode.
+    pub fn search_action_edit(&mut self) {
+        if let Some(state) = self.search_action_state.take() {
+            self.mode = AppMode::Normal;
+            self.invalidate_search_cache();
+            self.last_previewed_index = None;
+            self.navigate_to_path(&state.path);
+            self.focused_panel = FocusedPanel::Preview;
+            // Force preview update so enter_edit_mode can find the file
+            self.update_preview();
+            let line = state.line.map(|l| l.max(1) - 1);
+            #[cfg(feature = "editor")]
+            if self.enter_edit_mode(line) {
+                if let (Some(editor), Some(col)) = (self.editor_state.as_mut(), state.col) {
+                    let line = editor.cursor_line;
+                    editor.set_cursor_position(line, col.max(1) - 1);
+                }
+            }
+            #[cfg(not(feature = "editor"))]
+            self.enter_edit_mode(line);
+        }
+    }
+
+    /// Search action: copy the path, in the current path style, to the
+    /// system clipboard, and to the PRIMARY selection too when
+    /// `general.primary_selection` is set.
+    pub fn search_action_copy_path(
+        &mut self,
+        event_tx: &mpsc::UnboundedSender<crate::event::Event>,
+    ) {
+        self.search_action_copy_path_as(self.path_style, event_tx);
+    }
+
+    /// Search action: copy the path in `style`, whatever the current one.
+    pub fn search_action_copy_path_as(
+        &mut self,
+        style: PathStyle,
+        event_tx: &mpsc::UnboundedSender<crate::event::Event>,
+    ) {
+        let targets = system_clipboard::copy_targets(self.config.primary_selection());
+        self.search_action_copy_path_to(targets, style, event_tx);
+    }
+
+    /// Search action: copy the path to the PRIMARY selection only.
+    pub fn search_action_copy_path_primary(
+        &mut self,
+        event_tx: &mpsc::UnboundedSender<crate::event::Event>,
+    ) {
+        self.search_action_copy_path_to(
+            system_clipboard::primary_only(),
+            self.path_style,
+            event_tx,
+        );
+    }
+
+    /// Copy the action menu's path in `style` to `targets` and close the
+    /// menu.
+    fn search_action_copy_path_to(
+        &mut self,
+        targets: Vec<Selection>,
+        style: PathStyle,
+        event_tx: &mpsc::UnboundedSender<crate::event::Event>,
+    ) {
+        if let Some(state) = self.search_action_state.take() {
+            self.copy_paths(&[state.path], style, targets, event_tx);
+            self.mode = AppMode::Normal;
+            self.invalidate_search_cache();
+            self.last_previewed_index = None;
+        }
+    }
+
+    /// Copy the selected items' paths (or the focused one's) in `style`,
+    /// one per line, to the system clipboard and, with
+    /// `general.primary_selection`, the PRIMARY selection.
+    pub fn copy_selected_paths(
+        &mut self,
+        style: PathStyle,
+        event_tx: &mpsc::UnboundedSender<crate::event::Event>,
+    ) {
+        let paths = self.collect_target_paths();
+        if paths.is_empty() {
+            return;
+        }
+        let targets = system_clipboard::copy_targets(self.config.primary_selection());
+        self.copy_paths(&paths, style, targets, event_tx);
+    }
+
+    /// Copy `paths` in `style`, one per line, to `targets`.
+    fn copy_paths(
+        &mut self,
+        paths: &[PathBuf],
+        style: PathStyle,
+        targets: Vec<Selection>,
+        event_tx: &mpsc::UnboundedSender<crate::event::Event>,
+    ) {
+        if targets.is_empty() {
+            self.set_status_message("📋 Primary selection is only available on Linux".to_string());
+            return;
+        }
+        let root = &self.tree_state.root.path;
+        let text = paths
+            .iter()
+            .map(|p| path_display::show(p, style, root, &self.homes))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let what = match paths.len() {
+            1 => "Path".to_string(),
+            n => format!("{} paths", n),
+        };
+        self.copy_to_system_clipboard(what, text, targets, event_tx);
+    }
+
+    /// Write `text` to `targets` on a background task; the outcome arrives
+    /// as `ClipboardComplete`, with `what` naming the text in the status
+    /// bar. Each selection gets at most [`system_clipboard::WRITE_TIMEOUT`],
+    /// so a hung provider can't freeze the UI. With `general.osc52` the
+    /// text is also queued for the terminal as an OSC 52 escape.
+    fn copy_to_system_clipboard(
+        &mut self,
+        what: String,
+        text: String,
+        targets: Vec<Selection>,
+        event_tx: &mpsc::UnboundedSender<crate::event::Event>,
+    ) {
+        let escape = self
+            .config
+            .osc52()
+            .then(|| system_clipboard::osc52(&text, &targets))
+            .flatten();
+        let osc52 = escape.is_some();
+        if let Some(escape) = escape {
+            self.pending_osc52
+                .get_or_insert_with(String::new)
+                .push_str(&escape);
+        }
+        let provider = Arc::clone(&self.system_clipboard);
+        let tx = event_tx.clone();
+        tokio::spawn(async move {
+            let report = system_clipboard::write(
+                provider,
+                targets,
+                what,
+                text,
+                system_clipboard::WRITE_TIMEOUT,
+                osc52,
+            )
+            .await;
+            let _ = tx.send(crate::event::Event::ClipboardComplete(report));
+        });
+    }
+
+    /// Report a finished system clipboard write in the status bar.
+    pub fn handle_clipboard_complete(&mut self, report: ClipboardReport) {
+        self.set_status_message(report.status_message());
+    }
+
+    /// Search action: navigate to file and open rename dialog.
+    pub fn search_action_rename(&mut self) {
+        if let Some(state) = self.search_action_state.take() {
+            self.mode = AppMode::Normal;
+            self.invalidate_search_cache();
+            self.last_previewed_index = None;
+            self.navigate_to_path(&state.path);
+            self.open_dialog(DialogKind::Rename {
+                original: state.path,
+            });
+        }
+    }
+
+    /// Search action: navigate to file and open delete confirm.
+    pub fn search_action_delete(&mut self, event_tx: &mpsc::UnboundedSender<crate::event::Event>) {
+        if let Some(state) = self.search_action_state.take() {
+            self.mode = AppMode::Normal;
+            self.invalidate_search_cache();
+            self.last_previewed_index = None;
+            self.navigate_to_path(&state.path);
+            self.open_delete_confirm(vec![state.path], false, event_tx);
+        }
+    }
+
+    /// Search action: add file to internal clipboard for copy.
+    pub fn search_action_copy_clipboard(&mut self) {
+        if let Some(state) = self.search_action_state.take() {
+            use crate::fs::clipboard::ClipboardOp;
+            self.clipboard
+                .set(vec![state.path.clone()], ClipboardOp::Copy);
+            let name = state
+                .path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            self.set_status_message(format!("📋 Copied: {}", name));
+            self.mode = AppMode::Normal;
+            self.invalidate_search_cache();
+            self.last_previewed_index = None;
+        }
+    }
+
+    /// Search action: cut file to internal clipboard for move.
+    pub fn search_action_cut_clipboard(&mut self) {
+        if let Some(state) = self.search_action_state.take() {
+            use crate::fs::clipboard::ClipboardOp;
+            self.clipboard
+                .set(vec![state.path.clone()], ClipboardOp::Cut);
+            let name = state
+                .path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            self.set_status_message(format!("✂ Cut: {}", name));
+            self.mode = AppMode::Normal;
+            self.invalidate_search_cache();
+            self.last_previewed_index = None;
+        }
+    }
+
+    /// Search action: open the file with the system opener.
+    pub fn search_action_open_external(&mut self) {
+        if let Some(state) = self.search_action_state.take() {
+            self.mode = AppMode::Normal;
+            self.invalidate_search_cache();
+            self.last_previewed_index = None;
+            self.open_external(&state.path);
+        }
+    }
+
+    /// Search action: open file's parent directory in the embedded terminal.
+    pub fn search_action_open_terminal(
+        &mut self,
+        event_tx: &mpsc::UnboundedSender<crate::event::Event>,
+    ) {
+        if let Some(state) = self.search_action_state.take() {
+            self.mode = AppMode::Normal;
+            self.invalidate_search_cache();
+            self.last_previewed_index = None;
+
+            let parent_dir = if state.is_directory {
+                state.path.clone()
+            } else {
+                state
+                    .path
+                    .parent()
+                    .map(|p| p.to_path_buf())
+                    .unwrap_or_else(|| state.path.clone())
+            };
+
+            // Ensure terminal is visible (spawns PTY if needed)
+            if !self.terminal_state.visible {
+                self.toggle_terminal(event_tx);
+                if !self.terminal_state.visible {
+                    return; // disabled or failed; toggle_terminal reported why
+                }
+            }
+
+            self.terminal_cd(&parent_dir);
+        }
+    }
+
+    /// Update search results by scoring cached paths against the query.
+    fn update_search_results(&mut self) {
+        // `name:line[:col]` matches on the name and remembers the position
+        let parsed = location::parse(&self.search_state.query);
+        self.search_state.target_line = parsed.line;
+        self.search_state.target_col = parsed.col;
+        let query = parsed.path.to_string_lossy().into_owned();
+        let query = query.as_str();
+        if query.is_empty() {
+            self.search_state.results.clear();
+            self.search_state.selected_index = 0;
+            return;
+        }
+
+        let paths = match &self.search_state.cached_paths {
+            Some(p) => p,
+            None => return,
+        };
+
+        let root = &self.tree_state.root.path;
+        let mut results: Vec<SearchResult> = paths
+            .iter()
+            .filter_map(|path| {
+                let display = path_display::relative(path, root);
+                let (score, indices) = self.fuzzy_matcher.fuzzy_indices(&display, query)?;
+                Some(SearchResult {
+                    path: path.clone(),
+                    display,
+                    score,
+                    match_indices: indices,
+                })
+            })
+            .collect();
+
+        // Sort by score descending
+        results.sort_by(|a, b| b.score.cmp(&a.score));
+        // Only files of the filter chip's class, when the finder follows it
+        if let Some(class) = self
+            .tree_state
+            .filter_class
+            .as_ref()
+            .filter(|_| self.config.filter_in_finder())
+        {
+            let now = std::time::SystemTime::now();
+            results = results
+                .into_iter()
+                .filter(|result| class.matches_path(&result.path, now))
+                .take(50)
+                .collect();
+        }
+        // Limit to top 50
+        results.truncate(50);
+
+        self.search_state.results = results;
+        self.search_state.selected_index = 0;
+    }
+
+    /// Build the fuzzy finder's path index breadth first from the loaded
+    /// tree and the disk, capped by `search_max_entries` and
+    /// `path_index::TIME_LIMIT`.
+    fn build_path_index(&self) -> PathIndex {
+        let options = IndexOptions {
+            max_entries: self.config.search_max_entries(),
+            max_depth: self.tree_state.max_depth,
+            deadline: Instant::now() + path_index::TIME_LIMIT,
+            ignore: watcher::default_ignore_patterns(),
+            gitignore: self.tree_state.root.gitignore.clone(),
+        };
+        let root = &self.tree_state.root;
+        path_index::build(&root.path, Some(root), &options, &AtomicBool::new(false))
+            .unwrap_or_default()
+    }
+
+    /// Ctrl+R in the fuzzy finder: when the index was truncated, rebuild it
+    /// once in the background with the cap raised `path_index::RAISE_FACTOR`
+    /// times.
+    pub fn reindex_search(&mut self, event_tx: &mpsc::UnboundedSender<crate::event::Event>) {
+        let coverage = &self.search_state.coverage;
+        if !coverage.truncated || self.search_state.reindex.is_some() {
+            return;
+        }
+        let options = IndexOptions {
+            max_entries: coverage.cap.saturating_mul(path_index::RAISE_FACTOR),
+            max_depth: self.tree_state.max_depth,
+            deadline: Instant::now() + path_index::RAISED_TIME_LIMIT,
+            ignore: watcher::default_ignore_patterns(),
+            gitignore: self.tree_state.root.gitignore.clone(),
+        };
+        let root = self.tree_state.root.path.clone();
+        let tx = event_tx.clone();
+        let token = self.tasks.spawn(
+            TaskKind::Index,
+            format!(
+                "index {} entries",
+                dialog::group_thousands(options.max_entries)
+            ),
+            Priority::User,
+            move |token| async move {
+                let cancel = Arc::clone(token.cancel_flag());
+                let built = tokio::task::spawn_blocking(move || {
+                    path_index::build(&root, None, &options, &cancel).map(|index| (root, index))
+                })
+                .await;
+                if let Ok(Some((root, index))) = built {
+                    let _ = tx.send(crate::event::Event::IndexComplete(root, index));
+                }
+            },
+        );
+        self.search_state.reindex = Some(Reindex {
+            started: Instant::now(),
+            cancel: Arc::clone(token.cancel_flag()),
+        });
+    }
+
+    /// Take a re-index with the raised cap, if the finder still waits for it.
+    pub fn handle_index_complete(&mut self, root: PathBuf, index: PathIndex) {
+        if self.search_state.reindex.is_none() || root != self.tree_state.root.path {
+            return;
+        }
+        self.search_state.reindex = None;
+        self.search_state.coverage = index.coverage;
+        self.search_state.cached_paths = Some(index.paths);
+        self.update_search_results();
+    }
+
+    /// Index the tree in the background, streaming the paths into the
+    /// finder as `Event::IndexBatch`es.
+    pub fn start_path_index(&mut self, event_tx: &mpsc::UnboundedSender<crate::event::Event>) {
+        if let Some(indexing) = self.search_state.indexing.take() {
+            indexing.cancel.store(true, Ordering::Relaxed);
+        }
+        let options = IndexOptions {
+            max_entries: self.config.search_max_entries(),
+            max_depth: self.tree_state.max_depth,
+            deadline: Instant::now() + path_index::BACKGROUND_TIME_LIMIT,
+            ignore: watcher::default_ignore_patterns(),
+            gitignore: self.tree_state.root.gitignore.clone(),
+        };
+        self.search_state.index_job += 1;
+        let job = self.search_state.index_job;
+        let root = self.tree_state.root.path.clone();
+        let tx = event_tx.clone();
+        let token = self.tasks.spawn(
+            TaskKind::Index,
+            format!("index {}", root.display()),
+            Priority::Background,
+            {
+                let root = root.clone();
+                move |token| async move {
+                    let cancel = Arc::clone(token.cancel_flag());
+                    let _ = tokio::task::spawn_blocking(move || {
+                        let send = |paths, seen, coverage| {
+                            let batch = IndexBatch {
+                                job,
+                                root: root.clone(),
+                                paths,
+                                seen,
+                                coverage,
+                            };
+                            let _ = tx.send(crate::event::Event::IndexBatch(batch));
+                        };
+                        let coverage = path_index::stream(
+                            &root,
+                            None,
+                            &options,
+                            &cancel,
+                            path_index::BATCH_SIZE,
+                            &mut |paths, seen| send(paths, seen, None),
+                        );
+                        if let Some(coverage) = coverage {
+                            send(Vec::new(), coverage.seen, Some(coverage));
+                        }
+                    })
+                    .await;
+                }
+            },
+        );
+        self.search_state.cached_paths = Some(Vec::new());
+        self.search_state.coverage = Coverage::default();
+        self.search_state.indexing = Some(Indexing {
+            job,
+            root,
+            started: Instant::now(),
+            seen: 0,
+            cancel: Arc::clone(token.cancel_flag()),
+        });
+    }
+
+    /// Start the background index when there is none for the current
+    /// root: at startup, after an invalidation, after re-rooting.
+    pub fn poll_path_index(&mut self, event_tx: &mpsc::UnboundedSender<crate::event::Event>) {
+        let root = &self.tree_state.root.path;
+        let stale = match &self.search_state.indexing {
+            Some(indexing) => &indexing.root != root,
+            None => self.search_state.cached_paths.is_none(),
+        };
+        if stale {
+            self.start_path_index(event_tx);
+        }
+    }
+
+    /// Add a batch from the background index, re-matching the query so the
+    /// results refine as the index fills.
+    pub fn handle_index_batch(&mut self, batch: IndexBatch) {
+        let current = self
+            .search_state
+            .indexing
+            .as_ref()
+            .is_some_and(|indexing| indexing.job == batch.job);
+        if !current || batch.root != self.tree_state.root.path {
+            return;
+        }
+        self.search_state
+            .cached_paths
+            .get_or_insert_with(Vec::new)
+            .extend(batch.paths);
+        match batch.coverage {
+            Some(coverage) => {
+                self.search_state.coverage = coverage;
+                self.search_state.indexing = None;
+            }
+            None => {
+                if let Some(indexing) = &mut self.search_state.indexing {
+                    indexing.seen = batch.seen;
+                }
+            }
+        }
+        if self.mode == AppMode::Search {
+            let selected = self
+                .search_state
+                .results
+                .get(self.search_state.selected_index)
+                .map(|result| result.path.clone());
+            self.update_search_results();
+            if let Some(selected) = selected {
+                if let Some(i) = self
+                    .search_state
+                    .results
+                    .iter()
+                    .position(|result| result.path == selected)
+                {
+                    self.search_state.selected_index = i;
+                }
+            }
+        }
+    }
+
+    /// Invalidate the cached path index (call after tree mutations).
+    pub fn invalidate_search_cache(&mut self) {
+        self.search_state.cached_paths = None;
+        if let Some(indexing) = self.search_state.indexing.take() {
+            indexing.cancel.store(true, Ordering::Relaxed);
+        }
+        if let Some(reindex) = self.search_state.reindex.take() {
+            reindex.cancel.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Apply watcher events to a finished index; one still being built is
+    /// started over, as it may or may not have listed them.
+    fn update_search_index(&mut self, paths: &[PathBuf]) {
+        if self.search_state.indexing.is_some() {
+            self.invalidate_search_cache();
+            return;
+        }
+        let Some(indexed) = &mut self.search_state.cached_paths else {
+            return;
+        };
+        path_index::apply_changes(
+            indexed,
+            &mut self.search_state.coverage,
+            &self.tree_state.root.path,
+            paths,
+            self.tree_state.max_depth,
+            &watcher::default_ignore_patterns(),
+            self.tree_state.root.gitignore.as_ref(),
+        );
+        if self.mode == AppMode::Search {
+            self.update_search_results();
+        }
+    }
+
+    /// Navigate tree to a specific path: expand all ancestors, select the target.
+    pub fn navigate_to_path(&mut self, target: &Path) {
+        // Collect ancestor directories that need to be expanded
+        let root_path = self.tree_state.root.path.clone();
+        let mut ancestors = Vec::new();
+        let mut current = target.parent();
+        while let Some(p) = current {
+            if p == root_path {
+                break;
+            }
+            ancestors.push(p.to_path_buf());
+            current = p.parent();
+        }
+        ancestors.reverse();
+        // ancestors[i] sits at depth i + 1; expanding it shows depth i + 2
+        let capped = ancestors.len() >= self.tree_state.max_depth;
+        ancestors.truncate(self.tree_state.max_depth.saturating_sub(1));
+
+        let page_size = self.tree_state.page_size;
+        let defer_stats = self.tree_state.defer_stats;
+
+        // Expand each ancestor and apply sorting
+        for ancestor in &ancestors {
+            // Resolve sort before the mutable borrow
+            let (sort_by, dirs_first) = self.tree_state.sort_for(ancestor);
+            if let Some(node) = TreeState::find_node_mut_pub(&mut self.tree_state.root, ancestor) {
+                if !node.is_expanded {
+                    let _ = node.load_children_paged_with_sort(
+                        page_size,
+                        &sort_by,
+                        dirs_first,
+                        defer_stats,
+                    );
+                    TreeState::sort_children_of_pub(node, &sort_by, dirs_first);
+                    node.is_expanded = true;
+                }
+            }
+        }
+
+        // Re-flatten to reflect expansions
+        self.tree_state.flatten();
+        self.sync_watches();
+
+        // Find and select the target in flat_items
+        for (i, item) in self.tree_state.flat_items.iter().enumerate() {
+            if item.path == target {
+                self.tree_state.selected_index = i;
+                break;
+            }
+        }
+
+        // Beyond the depth limit: settle on the deepest shown ancestor
+        if capped {
+            let deepest = target
+                .ancestors()
+                .find_map(|p| self.tree_state.find_index_by_path(p));
+            if let Some(index) = deepest {
+                self.tree_state.selected_index = index;
+            }
+            self.set_status_message(self.depth_limit_message());
+        }
+    }
+
+    /// Navigate to `path` and bring 1-based `line` into view in the
+    /// preview, centered and briefly highlighted. Lines past the end clamp
+    /// to the last line with a status note.
+    pub fn navigate_to_location(&mut self, path: &Path, line: usize) {
+        self.navigate_to_path(path);
+        let landed = self
+            .tree_state
+            .flat_items
+            .get(self.tree_state.selected_index)
+            .is_some_and(|item| item.path == path && item.node_type == NodeType::File);
+        if !landed {
+            return;
+        }
+        self.last_previewed_index = None;
+        self.update_preview();
+
+        let line = line.max(1);
+        let state = &self.preview_state;
+        let mut note = None;
+        let row = if state.is_large_file {
+            // Only the head section lines up with file line numbers
+            if state.view_mode == ViewMode::TailOnly || line > state.head_lines {
+                let msg = format!(
+                    "Line {} is outside the large-file preview (first {} lines shown)",
+                    line, state.head_lines
+                );
+                self.set_status_message(msg);
+                return;
+            }
+            line - 1
+        } else {
+            if state.content_lines.is_empty() {
+                return;
+            }
+            let last = match state.total_lines {
+                0 => state.content_lines.len(),
+                total => total,
+            };
+            if line > last {
+                note = Some(format!(
+                    "Line {} is past the end of the file; showing line {}",
+                    line, last
+                ));
+            }
+            state.row_for_line(line.min(last))
+        };
+
+        self.preview_state.scroll_offset = row.saturating_sub(self.preview_visible_height() / 2);
+        self.clamp_preview_scroll();
+        self.line_flash = Some(LineFlash {
+            path: path.to_path_buf(),
+            row,
+            at: Instant::now(),
+        });
+        if let Some(note) = note {
+            self.set_status_message(note);
+        }
+    }
+
+    /// Jump to a `path[:line[:col]]` reference typed in the go-to prompt or
+    /// given to `--select`. Relative paths resolve against `base`; a file
+    /// whose name really contains `:12` wins over the line-number reading.
+    pub fn goto_location(&mut self, input: &str, base: &Path) {
+        let resolve = |raw: &Path| -> PathBuf {
+            let expanded = send_to::expand_target_path(&raw.to_string_lossy());
+            let joined = if expanded.is_absolute() {
+                expanded
+            } else {
+                base.join(expanded)
+            };
+            joined.canonicalize().unwrap_or(joined)
+        };
+
+        let literal = resolve(Path::new(input.trim()));
+        let (path, line) = if literal.exists() {
+            (literal, None)
+        } else {
+            let parsed = location::parse(input);
+            (resolve(&parsed.path), parsed.line)
+        };
+
+        if !path.exists() {
+            self.set_status_message(format!("Error: {} not found", path.display()));
+            return;
+        }
+        if !path.starts_with(&self.tree_state.root.path) {
+            self.set_status_message(format!(
+                "Error: {} is outside the tree root",
+                path.display()
+            ));
+            return;
+        }
+        match line {
+            Some(line) => self.navigate_to_location(&path, line),
+            None => {
+                self.navigate_to_path(&path);
+                self.last_previewed_index = None;
+            }
+        }
+    }
+
+    /// Follow a `path[:line[:col]]` reference printed in the terminal:
+    /// resolved against the shell's working directory, select it in the
+    /// tree and bring the line into view in the preview. Returns false,
+    /// leaving everything as it was, when it names nothing in the tree.
+    pub fn follow_terminal_link(&mut self, location: &location::Location) -> bool {
+        let base = self
+            .terminal_state
+            .cwd()
+            .unwrap_or(&self.tree_state.root.path)
+            .to_path_buf();
+        let raw = send_to::expand_target_path(&location.path.to_string_lossy());
+        let joined = if raw.is_absolute() {
+            raw
+        } else {
+            base.join(raw)
+        };
+        let path = joined.canonicalize().unwrap_or(joined);
+        if !path.exists() || !path.starts_with(&self.tree_state.root.path) {
+            return false;
+        }
+        match location.line {
+            Some(line) => self.navigate_to_location(&path, line),
+            None => {
+                self.navigate_to_path(&path);
+                self.last_previewed_index = None;
+            }
+        }
+        true
+    }
+
+    /// Ctrl+Click in the terminal panel at screen `column`, `row`: follow
+    /// the reference under it, if any.
+    pub fn follow_terminal_link_at(&mut self, column: u16, row: u16) {
+        let area = self.terminal_area;
+        // Inside the panel border
+        let (Some(row), Some(col)) = (row.checked_sub(area.y + 1), column.checked_sub(area.x + 1))
+        else {
+            return;
+        };
+        if let Some(location) = self.terminal_state.link_at(row as usize, col as usize) {
+            self.follow_terminal_link(&location);
+        }
+    }
+
+    /// Alt+G in the terminal: follow the next reference up from the last
+    /// one followed (the bottom one at first), skipping those that name
+    /// nothing in the tree.
+    pub fn follow_next_terminal_link(&mut self) {
+        let locations: Vec<location::Location> = self
+            .terminal_state
+            .links()
+            .into_iter()
+            .rev()
+            .map(|(_, link)| link.location)
+            .collect();
+        let first = self.terminal_state.link_pick.map_or(0, |pick| pick + 1);
+        for i in 0..locations.len() {
+            let pick = (first + i) % locations.len();
+            if self.follow_terminal_link(&locations[pick]) {
+                self.terminal_state.link_pick = Some(pick);
+                return;
+            }
+        }
+    }
+
+    // === Content search (Ctrl+Shift+F) methods ===
+
+    /// Open the content search overlay, keeping the last query and results.
+    pub fn open_grep(&mut self) {
+        self.paste_guard = PasteGuard::default();
+        self.mode = AppMode::Grep;
+    }
+
+    /// Close the content search overlay, cancelling a running search.
+    pub fn close_grep(&mut self) {
+        self.grep_state.cancel();
+        self.mode = AppMode::Normal;
+    }
+
+    /// Insert a character into the content search query.
+    pub fn grep_input_char(&mut self, c: char) {
+        if c.is_control() {
+            return;
+        }
+        let state = &mut self.grep_state;
+        state.query.insert(state.cursor_position, c);
+        state.cursor_position += c.len_utf8();
+    }
+
+    /// Insert pasted text into the content search query, flattened onto
+    /// one line.
+    pub fn grep_paste(&mut self, pasted: &str) {
+        let sanitized = paste_guard::sanitize(pasted);
+        let state = &mut self.grep_state;
+        state
+            .query
+            .insert_str(state.cursor_position, &sanitized.text);
+        state.cursor_position += sanitized.text.len();
+        if let Some(warning) = sanitized.warning() {
+            self.set_status_message(warning);
+        }
+    }
+
+    /// Delete the character before the cursor in the content search query.
+    pub fn grep_delete_char(&mut self) {
+        let state = &mut self.grep_state;
+        if let Some(prev) = state.query[..state.cursor_position].chars().next_back() {
+            state.cursor_position -= prev.len_utf8();
+            state.query.remove(state.cursor_position);
+        }
+    }
+
+    /// Switch the query between plain text and regex.
+    pub fn grep_toggle_regex(&mut self) {
+        self.grep_state.regex = !self.grep_state.regex;
+    }
+
+    /// Search the tree for the current query, replacing the results.
+    pub fn start_grep(&mut self, event_tx: &mpsc::UnboundedSender<crate::event::Event>) {
+        let pattern = match Pattern::new(&self.grep_state.query, self.grep_state.regex) {
+            Ok(pattern) => pattern,
+            Err(e) => {
+                self.grep_state.fail(e);
+                return;
+            }
+        };
+        let options = GrepOptions {
+            max_file_bytes: self.config.grep_max_file_bytes(),
+            max_results: self.config.grep_max_results(),
+            max_depth: self.tree_state.max_depth,
+            show_hidden: self.tree_state.show_hidden,
+        };
+        let job = self.grep_state.next_job();
+        let root = self.tree_state.root.path.clone();
+        let tx = event_tx.clone();
+        let description = format!("grep {}", self.grep_state.query);
+        let token = self.tasks.spawn(
+            TaskKind::Grep,
+            description,
+            Priority::User,
+            move |token| async move {
+                let cancel = Arc::clone(token.cancel_flag());
+                let _ = tokio::task::spawn_blocking(move || {
+                    grep::search(&root, &pattern, &options, job, &cancel, &mut |batch| {
+                        let _ = tx.send(crate::event::Event::GrepResults(batch));
+                    });
+                })
+                .await;
+            },
+        );
+        self.grep_state.begin(job, Arc::clone(token.cancel_flag()));
+    }
+
+    /// Search the query once it has rested for `grep::SETTLE`.
+    pub fn poll_grep(&mut self, event_tx: &mpsc::UnboundedSender<crate::event::Event>) {
+        if self.mode != AppMode::Grep
+            || self.grep_state.query.is_empty()
+            || !self.grep_state.is_stale()
+            || !self.idle_refresh.is_idle(Instant::now(), grep::SETTLE)
+        {
+            return;
+        }
+        self.start_grep(event_tx);
+    }
+
+    /// Enter: search a query that has not been searched yet, otherwise go
+    /// to the selected match.
+    pub fn grep_confirm(&mut self, event_tx: &mpsc::UnboundedSender<crate::event::Event>) {
+        if self.grep_state.is_stale() {
+            if !self.grep_state.query.is_empty() {
+                self.start_grep(event_tx);
+            }
+            return;
+        }
+        let Some(found) = self.grep_state.selected() else {
+            return;
+        };
+        let (path, line) = (found.path.clone(), found.line);
+        self.mode = AppMode::Normal;
+        self.navigate_to_location(&path, line);
+    }
+
+    /// Add a batch of content search results.
+    pub fn handle_grep_results(&mut self, batch: GrepBatch) {
+        self.grep_state.accept(batch);
+    }
+
+    // === Filter (/) methods ===
+
+    /// Activate inline tree filter mode.
+    /// A filter class chip stays on, narrowing what the query matches.
+    pub fn start_filter(&mut self) {
+        self.tree_state.filter_query.clear();
+        if self.tree_state.filter_class.is_some() {
+            self.refilter();
+        } else {
+            self.tree_state.is_filtering = false;
+        }
+        self.paste_guard = PasteGuard::default();
+        self.mode = AppMode::Filter;
+    }
+
+    /// Clear the filter, class chip included, and restore the full tree.
+    pub fn clear_filter(&mut self) {
+        self.tree_state.filter_query.clear();
+        self.tree_state.filter_class = None;
+        self.tree_state.is_filtering = false;
+        self.queue_tree_work(WorkKind::Filter, vec![TreeStep::flatten()], None);
+        self.mode = AppMode::Normal;
+        // Filesystem events were silently dropped while in Filter mode,
+        // so invalidate the search cache and force preview refresh.
+        self.invalidate_search_cache();
+        self.last_previewed_index = None;
+    }
+
+    /// Accept the current filter and return to normal mode (filtered view stays).
+    pub fn accept_filter(&mut self) {
+        self.mode = AppMode::Normal;
+    }
+
+    /// Insert a character into the filter query and re-filter.
+    pub fn filter_input_char(&mut self, c: char) {
+        if c.is_control() {
+            return;
+        }
+        self.tree_state.filter_query.push(c);
+        self.refilter();
+    }
+
+    /// Append pasted text to the filter query, flattened onto one line.
+    pub fn filter_paste(&mut self, pasted: &str) {
+        let sanitized = paste_guard::sanitize(pasted);
+        if !sanitized.text.is_empty() {
+            self.tree_state.filter_query.push_str(&sanitized.text);
+            self.refilter();
+        }
+        if let Some(warning) = sanitized.warning() {
+            self.set_status_message(warning);
+        }
+    }
+
+    /// Delete the last character from the filter query and re-filter.
+    pub fn filter_delete_char(&mut self) {
+        self.tree_state.filter_query.pop();
+        self.refilter();
+    }
+
+    /// Switch the filter chip to the next file class (`backward`: the
+    /// previous one), passing through "no class" after the last. The chip
+    /// combines with the text query.
+    pub fn cycle_filter_class(&mut self, backward: bool) {
+        let current = self.tree_state.filter_class.as_ref();
+        self.tree_state.filter_class = if backward {
+            filter_class::previous(&self.filter_classes, current)
+        } else {
+            filter_class::next(&self.filter_classes, current)
+        };
+        self.refilter();
+        self.last_previewed_index = None;
+    }
+
+    /// Re-run the filter as a tree job, superseding the one for the
+    /// previous query.
+    fn refilter(&mut self) {
+        self.queue_tree_work(WorkKind::Filter, vec![TreeStep::filter()], None);
+    }
+
+    // === Tree work (sort, filter, refresh) ===
+
+    /// Cycle the global sort and re-sort directories without an override.
+    pub fn cycle_sort(&mut self) {
+        self.tree_state.sort_by = self.tree_state.sort_by.next();
+        self.resort_tree();
+    }
+
+    /// Toggle dirs-first and re-sort directories without an override.
+    pub fn toggle_dirs_first(&mut self) {
+        self.tree_state.dirs_first = !self.tree_state.dirs_first;
+        self.resort_tree();
+    }
+
+    fn resort_tree(&mut self) {
+        self.queue_tree_work(
+            WorkKind::Sort,
+            vec![TreeState::sort_step(true), TreeStep::flatten()],
+            None,
+        );
+    }
+
+    /// Queue a tree job and run its first slice now, which finishes it on
+    /// a small tree; the rest runs a slice per frame.
+    fn queue_tree_work(&mut self, kind: WorkKind, steps: Vec<TreeStep>, then: Option<ViewRestore>) {
+        self.work.push(&self.tasks, kind, steps, then);
+        let slice = self.work.run(&self.tasks, &mut self.tree_state);
+        self.finish_slice(slice);
+    }
+
+    /// Run the slice `Event::ContinueWork` asked for.
+    pub fn continue_work(&mut self, id: WorkId) {
+        let slice = self
+            .work
+            .continue_job(id, &self.tasks, &mut self.tree_state);
+        self.finish_slice(slice);
+    }
+
+    fn finish_slice(&mut self, slice: Slice) {
+        if let Slice::Done(Some(view)) = slice {
+            self.restore_view(view);
+        }
+    }
+
+    /// Run queued tree work to the end, before its tree is parked.
+    fn finish_work(&mut self) {
+        while self.work.busy().is_some() {
+            let slice = self.work.run(&self.tasks, &mut self.tree_state);
+            self.finish_slice(slice);
+        }
+    }
+
+    /// Ask for the next slice of queued tree work, once per slice.
+    pub fn poll_work(&mut self, event_tx: &mpsc::UnboundedSender<crate::event::Event>) {
+        if let Some(id) = self.work.schedule() {
+            let _ = event_tx.send(crate::event::Event::ContinueWork(id));
+        }
+    }
+
+    // === Filesystem watcher methods ===
+
+    /// Handle filesystem change events by refreshing affected subtrees.
+    ///
+    /// Preserves: selected path, scroll offset, expanded directories.
+    /// Clears: multi-select, search cache.
+    ///
+    /// Skipped when in Search or Filter mode to avoid destroying the search
+    /// cache or overwriting the filtered flat_items view.
+    pub fn handle_fs_change(&mut self, paths: Vec<PathBuf>) {
+        self.handle_fs_events(paths.into_iter().map(FsEvent::Changed).collect());
+    }
+
+    /// Handle what the watcher saw during one debounce window. Creations,
+    /// removals and renames are applied to the loaded tree in place, so a
+    /// renamed directory keeps its expanded subdirectories; anything else,
+    /// or anything under a paginated, stale or deferred directory, reloads
+    /// the parent directory as `handle_fs_change` does.
+    pub fn handle_fs_events(&mut self, events: Vec<FsEvent>) {
+        let paths: Vec<PathBuf> = events.iter().flat_map(FsEvent::paths).cloned().collect();
+        for path in &paths {
+            self.dir_sizes.invalidate(path);
+        }
+        self.git_status.request();
+        self.update_search_index(&paths);
+
+        // Don't reload the tree while search/filter is active:
+        // - Search: the finder's results point at the tree as it is.
+        // - Filter: would call flatten() which rebuilds flat_items without the
+        //   filter, undoing the filtered view.
+        if matches!(self.mode, AppMode::Search | AppMode::Filter) {
+            return;
+        }
+        // A flood collapsed to the root, a refresh still being applied, or
+        // changes still settling
+        if paths.contains(&self.tree_state.root.path)
+            || self.work.busy().is_some()
+            || self.fs_storm.is_some()
+        {
+            return self.reload_changed(&paths);
+        }
+
+        let mut selected_path = self
+            .tree_state
+            .flat_items
+            .get(self.tree_state.selected_index)
+            .map(|item| item.path.clone());
+        let scroll_offset = self.tree_state.scroll_offset;
+        let mut changed: Vec<PathBuf> = Vec::new();
+        let mut flashed: Vec<PathBuf> = Vec::new();
+        let mut applied = false;
+        for event in &events {
+            let deferred = event
+                .paths()
+                .any(|p| p.parent().is_some_and(|dir| self.refresh_defer.covers(dir)));
+            let done = !deferred
+                && match event {
+                    FsEvent::Created(path) => self.tree_state.insert_path(path),
+                    FsEvent::Removed(path) => self.tree_state.remove_path(path),
+                    FsEvent::Renamed { from, to } => self.tree_state.rename_path(from, to),
+                    FsEvent::Changed(_) => false,
+                };
+            if !done {
+                changed.extend(event.paths().cloned());
+                continue;
+            }
+            applied = true;
+            match event {
+                FsEvent::Created(path) => flashed.push(path.clone()),
+                FsEvent::Removed(path) => flashed.extend(path.parent().map(Path::to_path_buf)),
+                FsEvent::Renamed { from, to } => {
+                    if let Some(rest) = selected_path
+                        .as_ref()
+                        .and_then(|s| s.strip_prefix(from).ok())
+                    {
+                        selected_path = Some(to.join(rest));
+                    }
+                    flashed.push(to.clone());
+                }
+                FsEvent::Changed(_) => {}
+            }
+        }
+
+        if applied {
+            if self.config.highlight_changes() {
+                let now = Instant::now();
+                for path in flashed {
+                    self.flash.mark(path, now);
+                }
+            }
+            self.tree_state.flatten();
+            self.restore_view(ViewRestore {
+                selected: selected_path,
+                scroll_offset,
+            });
+            self.last_refresh = Instant::now();
+        }
+        if !changed.is_empty() {
+            self.reload_changed(&changed);
+        }
+    }
+
+    /// Queue watcher events for the next frame, so bursts arriving in
+    /// between are applied together.
+    pub fn queue_fs_events(&mut self, events: Vec<FsEvent>) {
+        self.fs_pending.extend(events);
+    }
+
+    /// Per frame: apply the queued watcher events, and refresh the whole
+    /// tree once a burst too large to reload directory by directory has
+    /// been quiet for a debounce interval.
+    pub fn poll_fs_events(&mut self, now: Instant) {
+        if !self.fs_pending.is_empty() {
+            let events = self.fs_pending.take();
+            self.handle_fs_events(events);
+        }
+        let quiet = Duration::from_millis(self.config.debounce_ms());
+        let settled = self
+            .fs_storm
+            .is_some_and(|last| now.saturating_duration_since(last) >= quiet);
+        if settled && !matches!(self.mode, AppMode::Search | AppMode::Filter) {
+            self.fs_storm = None;
+            self.handle_fs_change(vec![self.tree_state.root.path.clone()]);
+        }
+    }
+
+    /// Reload the parent directory of each of `paths`, or the whole tree
+    /// when the root is among them, unless a busy terminal holds it back.
+    /// Collapsed directories are only marked stale, to be re-read when
+    /// expanded.
+    fn reload_changed(&mut self, paths: &[PathBuf]) {
+        if let Some(last) = &mut self.fs_storm {
+            *last = Instant::now();
+            return;
+        }
+
+        // Deduplicate parent directories to reload
+        let mut dirs_to_reload = std::collections::HashSet::new();
+        let mut full_reload = false;
+        for path in paths {
+            // If the changed path IS the root, do a full reload
+            if path == &self.tree_state.root.path {
+                dirs_to_reload.clear();
+                dirs_to_reload.insert(self.tree_state.root.path.clone());
+                full_reload = true;
+                // A flood collapsed to the root names no paths to index
+                self.invalidate_search_cache();
+                break;
+            }
+            // Otherwise reload the parent directory of the changed file
+            if let Some(parent) = path.parent() {
+                dirs_to_reload.insert(parent.to_path_buf());
+            }
+        }
+
+        // Hold back changes under a busy terminal's directory. A flood
+        // collapsed to the root most likely comes from the build too.
+        if self.refresh_defer.dir().is_some() {
+            let held: Vec<PathBuf> = dirs_to_reload
+                .iter()
+                .filter(|dir| full_reload || self.refresh_defer.covers(dir))
+                .cloned()
+                .collect();
+            for dir in held {
+                dirs_to_reload.remove(&dir);
+                self.defer_reload(&dir);
+            }
+            if dirs_to_reload.is_empty() {
+                return;
+            }
+        }
+
+        if !full_reload {
+            let mut reloads = 0;
+            dirs_to_reload.retain(|dir| {
+                match TreeState::find_node_mut_pub(&mut self.tree_state.root, dir) {
+                    Some(node) if node.is_expandable() && !node.is_expanded => {
+                        node.is_stale = true;
+                        false
+                    }
+                    Some(node) => {
+                        reloads += usize::from(node.is_expandable() && node.snapshot.is_none());
+                        true
+                    }
+                    None => false,
+                }
+            });
+            if reloads > MAX_DIRS_RELOADED_PER_FRAME {
+                self.tree_state.root.is_stale = true;
+                self.fs_storm = Some(Instant::now());
+                return;
+            }
+            if dirs_to_reload.is_empty() {
+                return;
+            }
+        }
+
+        self.reload_dirs(&dirs_to_reload);
+        self.last_refresh = Instant::now();
+    }
+
+    /// Mark the nearest loaded directory at or above `dir` stale and keep
+    /// it for the refresh that follows the deferral.
+    fn defer_reload(&mut self, dir: &Path) {
+        let mut current = Some(dir);
+        while let Some(path) = current {
+            if let Some(node) = TreeState::find_node_mut_pub(&mut self.tree_state.root, path) {
+                if node.is_expandable() {
+                    node.is_stale = true;
+                    self.idle_refresh.forget(path);
+                    self.refresh_defer.hold(path.to_path_buf());
+                    return;
+                }
+            }
+            current = path.parent();
+        }
+    }
+
+    /// Reload `dirs_to_reload` and re-flatten, keeping selection, scroll
+    /// and expanded directories and highlighting what changed.
+    fn reload_dirs(&mut self, dirs_to_reload: &std::collections::HashSet<PathBuf>) {
+        // Capture current state
+        let selected_path = self
+            .tree_state
+            .flat_items
+            .get(self.tree_state.selected_index)
+            .map(|item| item.path.clone());
+        let scroll_offset = self.tree_state.scroll_offset;
+        let expanded = self.tree_state.collect_expanded_paths();
+
+        let page_size = self.tree_state.page_size;
+        let defer_stats = self.tree_state.defer_stats;
+
+        // Reload each affected directory and apply sorting
+        // For paginated dirs with snapshots: mark stale (lazy re-scan on interaction)
+        // For non-paginated dirs: reload immediately
+        let highlight = self.config.highlight_changes();
+        let mut flashed: Vec<PathBuf> = Vec::new();
+        for dir in dirs_to_reload {
+            // Resolve sort before the mutable borrow (avoids borrow checker conflict)
+            let (sort_by, dirs_first) = self.tree_state.sort_for(dir);
+            if let Some(node) =
+                crate::fs::tree::TreeState::find_node_mut_pub(&mut self.tree_state.root, dir)
+            {
+                if node.is_expandable() {
+                    if node.snapshot.is_some() {
+                        // Paginated dir: mark stale, avoid expensive re-scan.
+                        // A refresh already scanning it would miss this change.
+                        node.is_stale = true;
+                        self.idle_refresh.forget(dir);
+                    } else {
+                        // Non-paginated dir: reload immediately
+                        let before = if highlight {
+                            flash::child_signatures(node)
+                        } else {
+                            None
+                        };
+                        let _ = node.load_children_paged_with_sort(
+                            page_size,
+                            &sort_by,
+                            dirs_first,
+                            defer_stats,
+                        );
+                        TreeState::sort_children_of_pub(node, &sort_by, dirs_first);
+                        // Diff child lists to find what changed (removals flash the parent)
+                        if let (Some(before), Some(after)) = (before, flash::child_signatures(node))
+                        {
+                            let diff = flash::diff_children(&before, &after);
+                            flashed.extend(diff.changed);
+                            if diff.removed {
+                                flashed.push(dir.clone());
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        let now = Instant::now();
+        for path in flashed {
+            self.flash.mark(path, now);
+        }
+
+        self.restore_tree_view(selected_path, scroll_offset, &expanded);
+    }
+
+    /// Re-flatten after a refresh, keeping expanded directories, the
+    /// selected path (or its nearest survivor) and the scroll offset. Runs
+    /// as a tree job, so a large tree is restored over several frames.
+    fn restore_tree_view(
+        &mut self,
+        selected_path: Option<PathBuf>,
+        scroll_offset: usize,
+        expanded: &std::collections::HashSet<PathBuf>,
+    ) {
+        // Restore expanded directories then re-flatten
+        self.queue_tree_work(
+            WorkKind::Refresh,
+            vec![TreeState::restore_step(expanded), TreeStep::flatten()],
+            Some(ViewRestore {
+                selected: selected_path,
+                scroll_offset,
+            }),
+        );
+    }
+
+    /// Put back the selected path (or its nearest survivor) and the
+    /// scroll offset once a refresh has re-flattened the tree.
+    fn restore_view(&mut self, view: ViewRestore) {
+        let ViewRestore {
+            selected: selected_path,
+            scroll_offset,
+        } = view;
+        // Restore selection
+        if let Some(ref prev_path) = selected_path {
+            if let Some(new_idx) = self.tree_state.find_index_by_path(prev_path) {
+                self.tree_state.selected_index = new_idx;
+            } else {
+                // Selected path was deleted — find nearest surviving
+                if let Some(fallback) = self.tree_state.find_nearest_surviving(prev_path) {
+                    self.tree_state.selected_index = fallback;
+                }
+            }
+        }
+
+        // Restore scroll offset (clamped)
+        let max_scroll = self.tree_state.flat_items.len().saturating_sub(1);
+        self.tree_state.scroll_offset = scroll_offset.min(max_scroll);
+        self.sync_watches();
+
+        // The search index was updated from the events themselves.
+        // Force preview refresh
+        self.last_previewed_index = None;
+    }
+
+    /// Periodic tick: drop change highlights that have fully faded.
+    pub fn handle_tick(&mut self) {
+        let now = Instant::now();
+        self.refresh_tasks_dialog();
+        if let AppMode::Dialog(DialogKind::Progress {
+            total: None, spin, ..
+        }) = &mut self.mode
+        {
+            *spin = spin.wrapping_add(1);
+        }
+        if !self.flash.is_empty() {
+            self.flash.prune(now, self.changed_window());
+        }
+        if self
+            .line_flash
+            .as_ref()
+            .is_some_and(|f| f.step(now).is_none())
+        {
+            self.line_flash = None;
+        }
+        for failure in self.launcher.finished() {
+            self.set_status_message(failure);
+        }
+    }
+
+    fn changed_window(&self) -> Duration {
+        Duration::from_secs(self.config.changed_window_secs())
+    }
+
+    /// Select the next path changed by a refresh (`c`), most recent first
+    /// and wrapping, expanding its ancestors. A deleted path lands on its
+    /// nearest existing ancestor. With a filter on, a target the filter
+    /// hides is named in the status bar instead.
+    pub fn jump_to_changed(&mut self) {
+        let now = Instant::now();
+        let window = self.changed_window();
+        let recent = self.flash.recent(now, window);
+        let targets = flash::jump_targets(&recent, &self.tree_state.root.path, |p| p.exists());
+        if targets.is_empty() {
+            let msg = if self.config.highlight_changes() {
+                format!("No changes in the last {}", flash::format_age(window))
+            } else {
+                "No changes tracked (watcher.highlight_changes is off)".to_string()
+            };
+            self.set_status_message(msg);
+            return;
+        }
+
+        let index = flash::next_target(&targets, self.changed_anchor.as_deref());
+        let target = &targets[index];
+        self.changed_anchor = Some(target.path.clone());
+        let mut msg = format!(
+            "changed {}/{} ({} ago)",
+            index + 1,
+            targets.len(),
+            flash::format_age(now.saturating_duration_since(target.at))
+        );
+        if target.deleted {
+            msg.push_str(" · deleted, showing its parent");
+        }
+
+        if self.tree_state.is_filtering {
+            // Navigating would re-flatten and drop the filtered view
+            match self.tree_state.find_index_by_path(&target.path) {
+                Some(index) => self.tree_state.selected_index = index,
+                None => {
+                    let name = target
+                        .path
+                        .file_name()
+                        .map(|n| n.to_string_lossy().into_owned())
+                        .unwrap_or_else(|| target.path.display().to_string());
+                    self.set_status_message(format!(
+                        "{} · {} is hidden by the filter {} (/ then Esc clears it)",
+                        msg,
+                        name,
+                        self.tree_state.filter_label()
+                    ));
+                    return;
+                }
+            }
+        } else {
+            let path = target.path.clone();
+            self.navigate_to_path(&path);
+            let landed = self
+                .tree_state
+                .flat_items
+                .get(self.tree_state.selected_index)
+                .is_some_and(|item| item.path == path);
+            if !landed {
+                msg = format!("{} · {}", msg, self.depth_limit_message());
+            }
+        }
+        self.set_status_message(msg);
+    }
+
+    /// Preview row to highlight and its fade step, while a jump's line
+    /// flash is live and its file is still the one previewed.
+    pub fn line_flash_step(&self) -> Option<(usize, FlashStep)> {
+        let flash = self.line_flash.as_ref()?;
+        if self.preview_state.current_path.as_ref() != Some(&flash.path) {
+            return None;
+        }
+        flash.step(Instant::now()).map(|step| (flash.row, step))
+    }
+
+    // === Deferred refresh under a busy terminal ===
+
+    /// Feed shell output to the emulator and count it toward the output
+    /// rate that decides whether refreshes are deferred.
+    pub fn handle_terminal_output(&mut self, data: &[u8]) {
+        self.terminal_state.process(data);
+        self.refresh_defer.record_output(Instant::now(), data.len());
+    }
+
+    /// Start deferring refreshes under the terminal's directory once its
+    /// output has been busy long enough, and catch up once it calms down.
+    pub fn poll_refresh_defer(&mut self, now: Instant) {
+        if !self.config.defer_during_builds() {
+            return;
+        }
+        let root = self.tree_state.root.path.clone();
+        let cwd = self.terminal_state.cwd().map(Path::to_path_buf);
+        match self.refresh_defer.poll(now, cwd.as_deref(), &root) {
+            Some(DeferChange::Started(dir)) => {
+                let label = self.deferred_label(&dir);
+                self.set_status_message(format!(
+                    "Auto-refresh deferred for {} (build running)",
+                    label
+                ));
+            }
+            Some(DeferChange::Lifted { dir, pending }) => {
+                let label = self.deferred_label(&dir);
+                for path in &pending {
+                    // Paginated directories stay stale for the idle refresh
+                    if let Some(node) =
+                        TreeState::find_node_mut_pub(&mut self.tree_state.root, path)
+                    {
+                        if node.snapshot.is_none() {
+                            node.is_stale = false;
+                        }
+                    }
+                }
+                if !pending.is_empty() {
+                    self.reload_dirs(&pending);
+                }
+                self.set_status_message(format!("Auto-refresh resumed for {}", label));
+            }
+            None => {}
+        }
+    }
+
+    /// Status bar note while refreshes are deferred.
+    pub fn deferred_refresh_note(&self) -> Option<String> {
+        let dir = self.refresh_defer.dir()?;
+        Some(format!(
+            "auto-refresh deferred for {} (build running)",
+            self.deferred_label(dir)
+        ))
+    }
+
+    /// `dir` relative to the tree root, as `target/`.
+    fn deferred_label(&self, dir: &Path) -> String {
+        let root = &self.tree_state.root;
+        let relative = match dir.strip_prefix(&root.path) {
+            Ok(rel) if !rel.as_os_str().is_empty() => rel.display().to_string(),
+            _ => root.name.clone(),
+        };
+        format!("{}/", relative)
+    }
+
+    // === Idle refresh ===
+
+    /// Record a key or mouse event: restarts the idle clock and aborts any
+    /// background refresh still running.
+    pub fn note_input(&mut self) {
+        self.idle_refresh.note_input(Instant::now());
+    }
+
+    /// Once the user has been idle for `watcher.idle_refresh_ms`, refresh up
+    /// to `watcher.idle_refresh_batch` stale paginated directories in the
+    /// viewport on background tasks.
+    pub fn poll_idle_refresh(&mut self, event_tx: &mpsc::UnboundedSender<crate::event::Event>) {
+        let period = match self.config.idle_refresh_ms() {
+            0 => return,
+            ms => Duration::from_millis(ms),
+        };
+        if self.idle_refresh.is_busy()
+            || self.in_flight.is_busy()
+            || !self.idle_refresh.is_idle(Instant::now(), period)
+            || matches!(
+                self.mode,
+                AppMode::Search | AppMode::Filter | AppMode::Dialog(DialogKind::Progress { .. })
+            )
+        {
+            return;
+        }
+
+        let targets = self.stale_visible_dirs(self.config.idle_refresh_batch());
+        if targets.is_empty() {
+            return;
+        }
+
+        let (generation, cancel) = self
+            .idle_refresh
+            .begin(targets.iter().map(|(path, _, _)| path.clone()));
+        let defer_stats = self.tree_state.defer_stats;
+        for (path, want, child_depth) in targets {
+            let cancel = Arc::clone(&cancel);
+            let (sort_by, dirs_first) = self.tree_state.sort_for(&path);
+            let tx = event_tx.clone();
+            tokio::spawn(async move {
+                let result = tokio::task::spawn_blocking(move || {
+                    idle_refresh::collect(
+                        &path,
+                        want,
+                        child_depth,
+                        &sort_by,
+                        dirs_first,
+                        defer_stats,
+                        &cancel,
+                    )
+                    .map(|(snapshot, children, consumed)| IdleRefreshResult {
+                        path,
+                        generation,
+                        snapshot,
+                        children,
+                        consumed,
+                    })
+                })
+                .await;
+                if let Ok(Some(result)) = result {
+                    let _ = tx.send(crate::event::Event::IdleRefreshComplete(result));
+                }
+            });
+        }
+    }
+
+    /// Stale paginated directories with a row in the viewport, as
+    /// `(path, entries to reload, child depth)`, at most `limit` of them.
+    ///
+    /// Reloads at least one page, and as many entries as were already
+    /// loaded so "Load more..." depth survives the refresh.
+    fn stale_visible_dirs(&mut self, limit: usize) -> Vec<(PathBuf, usize, usize)> {
+        let rows = self.tree_area.height.saturating_sub(2) as usize;
+        let mut candidates: Vec<PathBuf> = Vec::new();
+        for item in self
+            .tree_state
+            .flat_items
+            .iter()
+            .skip(self.tree_state.scroll_offset)
+            .take(rows)
+        {
+            let dir = if let Some(parent) = &item.load_more_parent {
+                Some(parent.clone())
+            } else if item.is_expandable() && item.is_expanded {
+                Some(item.path.clone())
+            } else {
+                item.path.parent().map(Path::to_path_buf)
+            };
+            if let Some(dir) = dir {
+                if !candidates.contains(&dir) {
+                    candidates.push(dir);
+                }
+            }
+        }
+
+        let page_size = self.tree_state.page_size;
+        let mut targets = Vec::new();
+        for dir in candidates {
+            if targets.len() >= limit {
+                break;
+            }
+            if let Some(node) = TreeState::find_node_mut_pub(&mut self.tree_state.root, &dir) {
+                if node.is_stale && node.is_expanded && node.snapshot.is_some() {
+                    let want = node.loaded_offset.max(page_size);
+                    targets.push((dir, want, node.depth + 1));
+                }
+            }
+        }
+        targets
+    }
+
+    /// Install a finished idle refresh, unless input aborted it, the
+    /// directory changed again, or it was reloaded some other way meanwhile.
+    pub fn handle_idle_refresh_complete(&mut self, result: IdleRefreshResult) {
+        if !self.idle_refresh.accept(&result.path, result.generation)
+            || matches!(self.mode, AppMode::Search | AppMode::Filter)
+        {
+            return;
+        }
+
+        let selected_path = self
+            .tree_state
+            .flat_items
+            .get(self.tree_state.selected_index)
+            .map(|item| item.path.clone());
+        let scroll_offset = self.tree_state.scroll_offset;
+        let expanded = self.tree_state.collect_expanded_paths();
+        let (sort_by, dirs_first) = self.tree_state.sort_for(&result.path);
+        let page_size = self.tree_state.page_size;
+
+        let node = match TreeState::find_node_mut_pub(&mut self.tree_state.root, &result.path) {
+            Some(node) if node.is_stale && node.snapshot.is_some() => node,
+            _ => return,
+        };
+        let IdleRefreshResult {
+            snapshot,
+            children,
+            consumed,
+            ..
+        } = result;
+        let total = snapshot.len();
+        node.total_child_count = Some(total);
+        node.loaded_child_count = children.len();
+        node.children = Some(children);
+        node.has_more_children = consumed < total;
+        if total <= page_size {
+            // Shrunk to a single page: drop the snapshot like a fresh load
+            node.snapshot = None;
+            node.loaded_offset = 0;
+        } else {
+            node.snapshot = Some(snapshot);
+            node.loaded_offset = consumed;
+        }
+        node.is_stale = false;
+        TreeState::sort_children_of_pub(node, &sort_by, dirs_first);
+
+        self.restore_tree_view(selected_path, scroll_offset, &expanded);
+    }
+
+    // === Session autosave ===
+
+    /// Capture the state worth restoring after a crash.
+    pub fn session_snapshot(&self) -> SessionSnapshot {
+        // The root is always expanded, so it isn't worth saving
+        let mut expanded: Vec<PathBuf> = self
+            .tree_state
+            .collect_expanded_paths()
+            .into_iter()
+            .filter(|path| *path != self.tree_state.root.path)
+            .collect();
+        expanded.sort();
+        let mut sort_overrides: Vec<session::SortOverride> = self
+            .tree_state
+            .sort_overrides
+            .iter()
+            .map(|(dir, (sort_by, dirs_first))| session::SortOverride {
+                dir: dir.clone(),
+                sort_by: sort_by.as_str().to_string(),
+                dirs_first: *dirs_first,
+            })
+            .collect();
+        sort_overrides.sort_by(|a, b| a.dir.cmp(&b.dir));
+        SessionSnapshot {
+            root: self.tree_state.root.path.clone(),
+            expanded,
+            selected: self
+                .tree_state
+                .flat_items
+                .get(self.tree_state.selected_index)
+                .map(|item| item.path.clone()),
+            scroll_offset: self.tree_state.scroll_offset,
+            sort_by: self.tree_state.sort_by.as_str().to_string(),
+            dirs_first: self.tree_state.dirs_first,
+            show_hidden: self.tree_state.show_hidden,
+            sort_overrides,
+            saved_at_ms: session::now_ms(),
+        }
+    }
+
+    /// Apply a recovered session to a freshly built tree rooted at
+    /// `snapshot.root`.
+    pub fn restore_session(&mut self, snapshot: &SessionSnapshot) {
+        self.tree_state.sort_by = crate::fs::tree::SortBy::from_str(&snapshot.sort_by);
+        self.tree_state.dirs_first = snapshot.dirs_first;
+        self.tree_state.show_hidden = snapshot.show_hidden;
+        self.tree_state.sort_overrides.clear();
+        for o in &snapshot.sort_overrides {
+            let sort_by = crate::fs::tree::SortBy::from_str(&o.sort_by);
+            self.tree_state
+                .sort_overrides
+                .insert(o.dir.clone(), (sort_by, o.dirs_first));
+        }
+        self.queue_tree_work(WorkKind::Sort, vec![TreeState::sort_step(false)], None);
+        self.restore_tree_view(
+            snapshot.selected.clone(),
+            snapshot.scroll_offset,
+            &snapshot.expanded_set(),
+        );
+        self.autosave.last_saved = Some(snapshot.clone());
+    }
+
+    /// Autosave the session on a background task when a save is due: soon
+    /// after layout changes, or every `general.autosave_secs` while the user
+    /// is active.
+    pub fn poll_autosave(&mut self, event_tx: &mpsc::UnboundedSender<crate::event::Event>) {
+        let interval = match self.config.autosave_secs() {
+            0 => return,
+            secs => Duration::from_secs(secs),
+        };
+        let dir = match &self.autosave.dir {
+            Some(dir) => dir.clone(),
+            None => return,
+        };
+        let now = Instant::now();
+        if self.autosave.in_flight
+            || now.duration_since(self.autosave.last_check) < session::CHECK_INTERVAL
+        {
+            return;
+        }
+        self.autosave.last_check = now;
+
+        let snapshot = self.session_snapshot();
+        let active = self.idle_refresh.last_input > self.autosave.last_saved_at;
+        if !session::save_due(
+            self.autosave.last_saved.as_ref(),
+            &snapshot,
+            active,
+            now.duration_since(self.autosave.last_saved_at),
+            interval,
+        ) {
+            return;
+        }
+
+        self.autosave.in_flight = true;
+        self.autosave.last_saved = Some(snapshot.clone());
+        self.autosave.last_saved_at = now;
+        let tx = event_tx.clone();
+        self.tasks.spawn(
+            TaskKind::Autosave,
+            "save session",
+            Priority::Background,
+            move |_| async move {
+                let result =
+                    tokio::task::spawn_blocking(move || session::save(&dir, &snapshot)).await;
+                let result = match result {
+                    Ok(saved) => saved.map_err(|e| e.to_string()),
+                    Err(e) => Err(e.to_string()),
+                };
+                let _ = tx.send(crate::event::Event::AutosaveComplete(result));
+            },
+        );
+    }
+
+    /// Finish an autosave; the first failure in a row is reported.
+    pub fn handle_autosave_complete(&mut self, result: std::result::Result<(), String>) {
+        self.autosave.in_flight = false;
+        match result {
+            Ok(()) => self.autosave.error_reported = false,
+            Err(msg) => {
+                if !self.autosave.error_reported {
+                    self.autosave.error_reported = true;
+                    self.set_status_message(format!("⚠ Autosave failed: {}", msg));
+                }
+            }
+        }
+    }
+
+    // === Blame gutter ===
+
+    /// Toggle the git blame gutter in the preview panel.
+    pub fn toggle_blame(&mut self) {
+        self.blame.enabled = !self.blame.enabled;
+        self.blame.settling = None;
+        if self.blame.enabled {
+            // Re-check HEAD so new commits are picked up
+            self.blame.heads.clear();
+            self.set_status_message("Blame: on".to_string());
+        } else {
+            self.blame.gutter = None;
+            self.blame.shown = None;
+            self.set_status_message("Blame: off".to_string());
+        }
+    }
+
+    /// Visible line range of the previewed file, if blame applies to it.
+    fn blame_viewport(&self) -> Option<BlameRange> {
+        if !self.blame.enabled || self.preview_state.is_large_file {
+            return None;
+        }
+        let path = self.preview_state.current_path.as_ref()?;
+        if !path.is_file() || path.extension().and_then(|e| e.to_str()) == Some("ipynb") {
+            return None;
+        }
+        let total = self.preview_state.content_lines.len();
+        if total == 0 || total != self.preview_state.total_lines {
+            return None;
+        }
+        let visible = self.preview_visible_height();
+        let start = self
+            .preview_state
+            .scroll_offset
+            .min(total.saturating_sub(visible));
+        let end = (start + visible).min(total);
+        Some(BlameRange {
+            path: path.clone(),
+            start: start + 1,
+            end,
+        })
+    }
+
+    /// Called on tick: once the viewport has been still for
+    /// [`blame::BLAME_DEBOUNCE`], show cached blame or fetch it.
+    pub fn poll_blame(&mut self, event_tx: &mpsc::UnboundedSender<crate::event::Event>) {
+        let range = match self.blame_viewport() {
+            Some(range) => range,
+            None => {
+                self.blame.settling = None;
+                return;
+            }
+        };
+
+        let now = Instant::now();
+        match &self.blame.settling {
+            Some((seen, since)) if *seen == range => {
+                if now.duration_since(*since) < blame::BLAME_DEBOUNCE {
+                    return;
+                }
+            }
+            _ => {
+                self.blame.settling = Some((range, now));
+                return;
+            }
+        }
+
+        if self.blame.shown.as_ref() == Some(&range)
+            || self.blame.pending.is_some()
+            || self.blame.is_untracked(&range.path)
+        {
+            return;
+        }
+        if let Some(lines) = self.blame.cached(&range).cloned() {
+            self.show_blame(range, &lines);
+            return;
+        }
+
+        self.blame.pending = Some(range.clone());
+        let runner = Arc::clone(&self.blame.runner);
+        let tx = event_tx.clone();
+        let description = format!("blame {}", file_label(&range.path));
+        self.tasks.spawn(
+            TaskKind::Blame,
+            description,
+            Priority::Normal,
+            move |_| async move {
+                let result = tokio::task::spawn_blocking(move || {
+                    let mut result = blame::fetch(runner.as_ref(), range);
+                    if preview_content::is_binary_file(&result.range.path) {
+                        result.lines.clear();
+                    }
+                    result
+                })
+                .await;
+                if let Ok(result) = result {
+                    let _ = tx.send(crate::event::Event::BlameComplete(result));
+                }
+            },
+        );
+    }
+
+    /// Called on tick: run `git status` for the tree markers when one was
+    /// asked for, one run at a time (see [`git_status`]).
+    pub fn poll_git_status(&mut self, event_tx: &mpsc::UnboundedSender<crate::event::Event>) {
+        let root = self.tree_state.root.path.clone();
+        if !self.git_status.start(&root, Instant::now()) {
+            return;
+        }
+        let tx = event_tx.clone();
+        self.tasks.spawn(
+            TaskKind::GitStatus,
+            "git status",
+            Priority::Background,
+            move |_| async move {
+                let result = tokio::task::spawn_blocking(move || git_status::fetch(&root)).await;
+                if let Ok(result) = result {
+                    let _ = tx.send(crate::event::Event::GitStatusComplete(result));
+                }
+            },
+        );
+    }
+
+    /// Store a finished `git status` run for the tree.
+    pub fn handle_git_status_complete(&mut self, result: GitStatusResult) {
+        self.git_status.finish(result);
+    }
+
+    /// Once the selected preview is shown and input has settled, load the
+    /// neighbouring files' previews into the cache in the background.
+    pub fn poll_prefetch(&mut self, event_tx: &mpsc::UnboundedSender<crate::event::Event>) {
+        let selected = self.tree_state.selected_index;
+        self.prefetch.cancel_if_far(selected);
+        if !self.config.preview_prefetch() || self.in_flight.is_busy() {
+            self.prefetch.cancel_all();
+            return;
+        }
+        if self.prefetch.origin == Some(selected)
+            || self.last_previewed_index != Some(selected)
+            || !self
+                .idle_refresh
+                .is_idle(Instant::now(), prefetch::PREFETCH_IDLE)
+        {
+            return;
+        }
+        self.prefetch.origin = Some(selected);
+
+        let tab_width = self.config.tab_width();
+        let max_bytes = self.config.max_full_preview_bytes();
+        for path in prefetch::neighbors(&self.tree_state.flat_items, selected) {
+            if self.prefetch.running() >= prefetch::MAX_IN_FLIGHT {
+                break;
+            }
+            if self.prefetch.is_running(&path)
+                || path.extension().and_then(|e| e.to_str()) == Some("ipynb")
+            {
+                continue;
+            }
+            let key = match CacheKey::for_path(&path, tab_width, self.raw_front_matter) {
+                Some(key) if key.size <= max_bytes => key,
+                _ => continue,
+            };
+            if self.preview_cache.contains(&key) {
+                continue;
+            }
+
+            let syntax = Arc::clone(&self.syntax);
+            let tx = event_tx.clone();
+            let description = format!("preview {}", file_label(&path));
+            let token = self.tasks.spawn(
+                TaskKind::Prefetch,
+                description,
+                Priority::Background,
+                move |token| async move {
+                    let cancel = Arc::clone(token.cancel_flag());
+                    let result =
+                        tokio::task::spawn_blocking(move || prefetch::load(key, &syntax, &cancel))
+                            .await;
+                    if let Ok(result) = result {
+                        let _ = tx.send(crate::event::Event::PrefetchComplete(result));
+                    }
+                },
+            );
+            self.prefetch.start(path, Arc::clone(token.cancel_flag()));
+        }
+    }
+
+    /// Cancel the size walk once the selection leaves its directory, and
+    /// size a newly selected directory once the selection has settled on
+    /// it.
+    pub fn poll_dir_size(&mut self, event_tx: &mpsc::UnboundedSender<crate::event::Event>) {
+        let selected = self.selected_dir();
+        self.dir_sizes.cancel_unless(selected.as_deref());
+        let Some(path) = selected else {
+            return;
+        };
+        if !self.config.dir_sizes()
+            || self.dir_sizes.get(&path).is_some()
+            || self.dir_sizes.running() == Some(path.as_path())
+            || !self.idle_refresh.is_idle(Instant::now(), dir_size::SETTLE)
+        {
+            return;
+        }
+        self.spawn_dir_size(path, event_tx);
+    }
+
+    /// Stat pending tree rows in the background, the viewport first, while
+    /// fewer than `stat_service::MAX_IN_FLIGHT` batches are running.
+    pub fn poll_stats(&mut self, event_tx: &mpsc::UnboundedSender<crate::event::Event>) {
+        if !self.tree_state.defer_stats
+            || self.stat_state.batches_in_flight() >= stat_service::MAX_IN_FLIGHT
+        {
+            return;
+        }
+        let rows = self.tree_area.height.saturating_sub(2) as usize;
+        let pending = stat_service::pending_in_priority(
+            &self.tree_state.flat_items,
+            self.tree_state.scroll_offset,
+            rows,
+        );
+        let generation = self.stat_state.generation;
+        for paths in self.stat_state.take_batches(pending) {
+            let source = Arc::clone(&self.stat_source);
+            let tx = event_tx.clone();
+            let description = format!("stat {} entries", paths.len());
+            self.tasks.spawn(
+                TaskKind::Stat,
+                description,
+                Priority::Normal,
+                move |token| async move {
+                    let cancel = Arc::clone(token.cancel_flag());
+                    let result = tokio::task::spawn_blocking(move || {
+                        let results = paths
+                            .iter()
+                            .take_while(|_| !cancel.load(Ordering::Relaxed))
+                            .map(|path| (path.clone(), source.stat(path)))
+                            .collect();
+                        StatBatch {
+                            generation,
+                            paths,
+                            results,
+                        }
+                    })
+                    .await;
+                    if let Ok(batch) = result {
+                        let _ = tx.send(crate::event::Event::StatsReady(batch));
+                    }
+                },
+            );
+        }
+    }
+
+    /// Patch tree rows with a finished stat batch. Batches from before a
+    /// re-root are dropped.
+    pub fn handle_stats_ready(&mut self, batch: StatBatch) {
+        if !self.stat_state.accept(&batch) {
+            return;
+        }
+        self.tree_state.apply_stats(batch.results);
+    }
+
+    /// Whether the tree title should say the order on screen is
+    /// provisional: rows in view sorted by size or mtime still lack stats.
+    pub fn sort_is_provisional(&self) -> bool {
+        let rows = self.tree_area.height.saturating_sub(2) as usize;
+        self.tree_state
+            .order_is_provisional(self.tree_state.scroll_offset, rows)
+    }
+
+    /// Cache a finished prefetch unless it was cancelled, keeping the
+    /// preview on screen in the cache.
+    pub fn handle_prefetch_complete(&mut self, result: PrefetchResult) {
+        let PrefetchResult { key, content } = result;
+        if !self.prefetch.finish(&key.path) {
+            return;
+        }
+        if let Some((lines, total)) = content {
+            let path = key.path.clone();
+            let shown = self.preview_state.current_path.clone();
+            if self
+                .preview_cache
+                .insert_keeping(key, lines, total, shown.as_deref())
+            {
+                self.prefetch.mark_warmed(path);
+            }
+        }
+    }
+
+    /// Store a finished blame fetch and display it if still relevant.
+    /// Failures arrive as empty results and leave the gutter blank.
+    pub fn handle_blame_complete(&mut self, result: BlameResult) {
+        if self.blame.pending.as_ref() == Some(&result.range) {
+            self.blame.pending = None;
+        }
+        let BlameResult { range, head, lines } = result;
+        self.blame.heads.insert(range.path.clone(), head.clone());
+
+        let current = self.blame_viewport();
+        match head {
+            Some(head) => {
+                self.blame
+                    .insert_cache((range.clone(), head), lines.clone());
+                if current.as_ref() == Some(&range) {
+                    self.show_blame(range, &lines);
+                }
+            }
+            None => {
+                if current.is_some_and(|c| c.path == range.path) {
+                    self.blame.gutter = None;
+                    self.blame.shown = None;
+                    self.set_status_message("Blame: not tracked".to_string());
+                }
+            }
+        }
+    }
+
+    fn show_blame(&mut self, range: BlameRange, lines: &[(usize, blame::BlameLine)]) {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+        self.blame.gutter = Some(BlameGutter::build(lines, range.start, range.end, now));
+        self.blame.shown = Some(range);
+    }
+
+    /// Gutter to draw for the current preview, if any.
+    pub fn blame_gutter(&self) -> Option<&BlameGutter> {
+        if !self.blame.enabled {
+            return None;
+        }
+        let shown = self.blame.shown.as_ref()?;
+        if self.preview_state.current_path.as_ref() != Some(&shown.path) {
+            return None;
+        }
+        self.blame.gutter.as_ref()
+    }
+
+    /// Preview title tag while blame is on (`blame` / `not tracked`).
+    pub fn blame_label(&self) -> Option<&'static str> {
+        if !self.blame.enabled {
+            return None;
+        }
+        let path = self.preview_state.current_path.as_ref()?;
+        if self.blame.is_untracked(path) {
+            Some("not tracked")
+        } else {
+            Some("blame")
+        }
+    }
+
+    /// Force a full tree refresh from root, preserving state.
+    ///
+    /// Used by F5 keybinding; works regardless of watcher state.
+    pub fn full_refresh(&mut self) {
+        // Overrides a deferral; everything it held back is reloaded below
+        self.refresh_defer.lift();
+        self.fs_storm = None;
+        self.handle_fs_change(vec![self.tree_state.root.path.clone()]);
+        self.set_status_message("🔄 Tree refreshed".to_string());
+    }
+
+    /// Toggle the filesystem watcher active state.
+    ///
+    /// Returns the new state (true = active, false = paused).
+    pub fn toggle_watcher(&mut self) -> bool {
+        self.watcher_active = !self.watcher_active;
+        if self.watcher_active {
+            self.set_status_message("👁 Watcher resumed".to_string());
+        } else {
+            self.set_status_message("⏸ Watcher paused".to_string());
+        }
+        self.watcher_active
+    }
+
+    /// (Re)start the filesystem watcher on the tree root, reporting a
+    /// failure or partial start in the status bar.
+    pub fn start_watcher(&mut self, event_tx: &mpsc::UnboundedSender<crate::event::Event>) {
+        if !self.config.watcher_enabled() {
+            self.watcher.stop();
+            self.watcher_active = false;
+            return;
+        }
+        let root = self.tree_state.root.path.clone();
+        let had_failed = self.watcher.error.is_some();
+        self.watcher.respect_gitignore = self.tree_state.respect_gitignore;
+        match self.watcher.start(&root, &self.config, event_tx.clone()) {
+            Ok(()) => {
+                // Resume after a failed start, unless editing keeps it paused
+                if had_failed && self.mode != AppMode::Edit {
+                    self.watcher_active = true;
+                }
+                let unwatched = self.watcher.unwatched().len();
+                if unwatched > 0 {
+                    self.set_status_message(format!(
+                        "⚠ Watching partially: {} subtree{} unwatched (W for details)",
+                        unwatched,
+                        if unwatched == 1 { "" } else { "s" }
+                    ));
+                } else if self.watcher.mode == WatchMode::Poll {
+                    self.set_status_message("👁 Watching by polling".to_string());
+                }
+                self.sync_watches();
+            }
+            Err(e) => {
+                self.watcher_active = false;
+                self.set_status_message(format!("⚠ Watcher unavailable: {} (W for details)", e));
+            }
+        }
+    }
+
+    /// Give each shown expanded directory a watch of its own where the
+    /// root watch doesn't reach it (`watcher.recursive = false`, or a
+    /// partial start), and drop the watches of collapsed ones.
+    fn sync_watches(&mut self) {
+        if !self.watcher.is_running() {
+            return;
+        }
+        let expanded = self.tree_state.collect_expanded_paths();
+        if let Err(e) = self.watcher.sync_dirs(&expanded) {
+            self.set_status_message(format!("⚠ Not watching every expanded directory: {}", e));
+        }
+    }
+
+    /// Show why the watcher failed or is partial (`W`).
+    pub fn open_watcher_details(&mut self) {
+        let problem = match self.watcher.problem() {
+            Some(problem) => problem,
+            None => {
+                self.explain_watcher_state();
+                return;
+            }
+        };
+        let mut lines = vec![problem.to_string(), String::new()];
+        lines.extend(problem.details());
+        let unwatched = self.watcher.unwatched();
+        if !unwatched.is_empty() {
+            lines.push(String::new());
+            lines.push(format!("Unwatched subtrees ({}):", unwatched.len()));
+            let root = &self.tree_state.root.path;
+            for path in unwatched.iter().take(5) {
+                lines.push(format!("  {}", path_display::relative(path, root)));
+            }
+            if unwatched.len() > 5 {
+                lines.push(format!("  … and {} more", unwatched.len() - 5));
+            }
+        }
+        self.open_dialog(DialogKind::WatcherDetails { lines });
+    }
+
+    /// What the tree title glyph shows. A watcher that is off for any
+    /// reason other than a pause counts as unavailable.
+    pub fn watcher_state(&self) -> WatcherState {
+        if !self.config.watcher_enabled() || self.watcher.error.is_some() {
+            WatcherState::Unavailable
+        } else if !self.watcher_active {
+            WatcherState::Paused
+        } else if self.refresh_defer.dir().is_some() {
+            WatcherState::Flooded
+        } else {
+            WatcherState::Live
+        }
+    }
+
+    /// One line on the watcher state and how long ago the tree last
+    /// refreshed, as of `now`.
+    pub fn watcher_state_note(&self, now: Instant) -> String {
+        let state = match self.watcher_state() {
+            WatcherState::Live if self.watcher.mode == WatchMode::Poll => {
+                "👁 Watching by polling".to_string()
+            }
+            WatcherState::Live => match self.watcher.watch_count() {
+                Some(count) if count > 1 => {
+                    format!("👁 Watcher running normally ({} watches)", count)
+                }
+                _ => "👁 Watcher running normally".to_string(),
+            },
+            WatcherState::Paused if self.mode == AppMode::Edit => {
+                "⏸ Watcher paused while editing".to_string()
+            }
+            WatcherState::Paused => {
+                "⏸ Watcher paused (Ctrl+R to resume, F5 to refresh)".to_string()
+            }
+            WatcherState::Flooded => match self.deferred_refresh_note() {
+                Some(note) => format!("⚠ {}", note),
+                None => "⚠ Auto-refresh deferred".to_string(),
+            },
+            WatcherState::Unavailable if !self.config.watcher_enabled() => {
+                "✕ Watcher disabled by config or --no-watcher".to_string()
+            }
+            WatcherState::Unavailable => "✕ Watcher unavailable (W for details)".to_string(),
+        };
+        let age = now.saturating_duration_since(self.last_refresh).as_secs() as i64;
+        let ago = match blame::format_age(age).as_str() {
+            "now" => "just now".to_string(),
+            age => format!("{} ago", age),
+        };
+        format!("{} · last refresh {}", state, ago)
+    }
+
+    /// Explain the watcher state glyph in the status bar (`W`, or a click
+    /// on the glyph).
+    pub fn explain_watcher_state(&mut self) {
+        let note = self.watcher_state_note(Instant::now());
+        self.set_status_message(note);
+    }
+
+    /// Close the details dialog and start the watcher again in `mode`.
+    pub fn retry_watcher(
+        &mut self,
+        mode: WatchMode,
+        event_tx: &mpsc::UnboundedSender<crate::event::Event>,
+    ) {
+        self.close_dialog();
+        self.watcher.mode = mode;
+        self.start_watcher(event_tx);
+        if self.watcher.problem().is_none() {
+            let message = match mode {
+                WatchMode::Native => "👁 Watcher started",
+                WatchMode::Poll => "👁 Watching by polling",
+            };
+            self.set_status_message(message.to_string());
+        }
+    }
+
+    /// Toggle the debug overlay showing memory usage (F12).
+    pub fn toggle_debug_overlay(&mut self) {
+        self.show_debug_overlay = !self.show_debug_overlay;
+    }
+
+    /// Snapshot the approximate memory held by preview content, the preview
+    /// cache, the terminal scrollback, and the bounded per-path maps.
+    pub fn memory_stats(&self) -> MemoryStats {
+        MemoryStats {
+            preview_bytes: preview_content::approx_lines_bytes(&self.preview_state.content_lines),
+            preview_cache_bytes: self.preview_cache.total_bytes(),
+            preview_cache_entries: self.preview_cache.len(),
+            prefetch_hits: self.prefetch.hits,
+            prefetch_misses: self.prefetch.misses,
+            scrollback_bytes: self.terminal_state.emulator.scrollback_bytes(),
+            scrollback_lines: self.terminal_state.emulator.scrollback_len(),
+            tasks: self.tasks.counters(),
+            maps: self.map_stats(),
+        }
+    }
+
+    /// Entries and evictions summed over the bounded per-path maps
+    /// (flashes, sort overrides, prefetch, directory sizes, blame).
+    pub fn map_stats(&self) -> MapStats {
+        self.flash
+            .stats()
+            .add(self.tree_state.sort_overrides.stats())
+            .add(self.prefetch.stats())
+            .add(self.dir_sizes.stats())
+            .add(self.blame.stats())
+    }
+
+    // === Async directory operation handlers ===
+
+    /// Handle an async directory scan completion.
+    ///
+    /// Installs the snapshot into the tree node and loads the first page.
+    #[allow(dead_code)]
+    pub fn handle_dir_scan_complete(
+        &mut self,
+        path: &std::path::Path,
+        mut snapshot: crate::fs::tree::DirSnapshot,
+    ) {
+        let (sort_by, dirs_first) = self.tree_state.sort_for(path);
+        let page_size = self.tree_state.page_size;
+        let defer_stats = self.tree_state.defer_stats;
+
+        if let Some(node) =
+            crate::fs::tree::TreeState::find_node_mut_pub(&mut self.tree_state.root, path)
+        {
+            // Sort the snapshot with current settings
+            snapshot.sort(&sort_by, dirs_first);
+
+            let total = snapshot.len();
+            node.total_child_count = Some(total);
+
+            if total <= page_size {
+                // Small enough → just load all (no snapshot needed)
+                let _ = node.load_children_paged_with_sort(
+                    page_size,
+                    &sort_by,
+                    dirs_first,
+                    defer_stats,
+                );
+            } else {
+                // Install snapshot and load first page
+                let page_entries = snapshot.page(0, page_size);
+                let children = crate::fs::tree::TreeNode::load_nodes_from_snapshot(
+                    page_entries,
+                    &node.path,
+                    node.depth + 1,
+                    defer_stats,
+                );
+                let loaded = children.len();
+                node.children = Some(children);
+                node.loaded_child_count = loaded;
+                node.loaded_offset = loaded;
+                node.has_more_children = loaded < total;
+                node.snapshot = Some(snapshot);
+            }
+
+            TreeState::sort_children_of_pub(node, &sort_by, dirs_first);
+            node.is_expanded = true;
+            self.tree_state.flatten();
+        }
+    }
+
+    /// Handle an async child count completion.
+    ///
+    /// Updates the node's total_child_count cache for badge display.
+    #[allow(dead_code)]
+    pub fn handle_dir_count_complete(&mut self, path: &std::path::Path, count: usize) {
+        if let Some(node) =
+            crate::fs::tree::TreeState::find_node_mut_pub(&mut self.tree_state.root, path)
+        {
+            node.total_child_count = Some(count);
+        }
+        // No flatten needed — badge display reads from TreeNode directly
+    }
+
+    /// Store a directory's running or final size and show it in its
+    /// preview.
+    pub fn handle_dir_size_update(&mut self, path: &Path, job: u64, size: DirSize) {
+        if !self.dir_sizes.update(path, job, size) {
+            return;
+        }
+        if self.preview_state.current_path.as_deref() == Some(path) {
+            self.show_dir_size_in_preview();
+        }
+        if let AppMode::Dialog(DialogKind::Info { path: shown, info }) = &mut self.mode {
+            if shown == path {
+                info.set_dir_size(&size, self.config.apparent_sizes());
+            }
+        }
+    }
+
+    /// Put the selected directory's computed size into its summary.
+    fn show_dir_size_in_preview(&mut self) {
+        let Some(size) = self
+            .preview_state
+            .current_path
+            .as_deref()
+            .and_then(|path| self.dir_sizes.get(path))
+        else {
+            return;
+        };
+        preview_content::update_directory_summary(
+            &mut self.preview_state.content_lines,
+            size,
+            self.config.apparent_sizes(),
+        );
+        self.preview_state.total_lines = self.preview_state.content_lines.len();
+        self.preview_state.text.clear();
+    }
+}
+
+/// Sort description for status messages, e.g. "Size, dirs mixed".
+fn sort_label(sort_by: &crate::fs::tree::SortBy, dirs_first: bool) -> String {
+    if dirs_first {
+        sort_by.label().to_string()
+    } else {
+        format!("{}, dirs mixed", sort_by.label())
+    }
+}
+
+/// Directory name for status messages (the full path for `/`).
+fn dir_label(dir: &Path) -> String {
+    dir.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| dir.display().to_string())
+}
+
+/// Progress of a transfer task, sent to the progress dialog: entries as
+/// they are reached when the paste was pre-counted or `counting` is set
+/// (top-level items are filled in by the task otherwise) and bytes every
+/// [`PROGRESS_BYTES`].
+struct TransferProgress<'a> {
+    token: &'a TaskToken,
+    event_tx: &'a mpsc::UnboundedSender<crate::event::Event>,
+    entries: Option<usize>,
+    /// Report entries even without a total, as a running count.
+    counting: bool,
+    /// Entries reached so far.
+    done: usize,
+    /// The last update, kept current between sends.
+    update: crate::event::ProgressUpdate,
+    /// Bytes written since the last send.
+    unsent_bytes: u64,
+}
+
+impl TransferProgress<'_> {
+    fn send(&mut self) {
+        self.unsent_bytes = 0;
+        let _ = self
+            .event_tx
+            .send(crate::event::Event::Progress(self.update.clone()));
+    }
+}
+
+impl operations::CopyProgress for TransferProgress<'_> {
+    fn entry(&mut self, path: &Path) {
+        if self.entries.is_none() && !self.counting {
+            return;
+        }
+        if self.done.is_multiple_of(PROGRESS_STRIDE) {
+            let current = self
+                .entries
+                .map_or(self.done + 1, |entries| (self.done + 1).min(entries));
+            self.token.progress(current, self.entries.unwrap_or(0));
+            self.update.current_file = file_label(path);
+            self.update.current = current;
+            self.send();
+        }
+        self.done += 1;
+    }
+
+    fn bytes(&mut self, n: u64) {
+        self.update.bytes_done += n;
+        self.unsent_bytes += n;
+        if self.unsent_bytes >= PROGRESS_BYTES {
+            self.send();
+        }
+    }
+
+    fn cancelled(&self) -> bool {
+        self.token.is_cancelled()
+    }
+}
+
+/// The file at `src`'s name in `dest_dir` that pasting `src` over it
+/// would replace. Directories are merged into, so they aren't one.
+fn overwritten_file(src: &Path, dest_dir: &Path) -> Option<PathBuf> {
+    let dest = dest_dir.join(src.file_name()?);
+    let meta = std::fs::symlink_metadata(&dest).ok()?;
+    (!meta.is_dir() && dest != src).then_some(dest)
+}
+
+/// Average bytes per second of `bytes` written in `elapsed`, once there is
+/// enough of both to mean something.
+fn throughput(bytes: u64, elapsed: Duration) -> Option<u64> {
+    if bytes == 0 || elapsed < THROUGHPUT_MIN_ELAPSED {
+        return None;
+    }
+    Some((bytes as f64 / elapsed.as_secs_f64()) as u64)
+}
+
+/// File name shown in the progress dialog (empty when there is none).
+fn file_label(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// "1 item", "3 items", for task descriptions.
+fn item_count(n: usize) -> String {
+    format!("{} item{}", n, if n == 1 { "" } else { "s" })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::preview_content::PendingHighlight;
+    use std::fs::{self, File};
+    use std::sync::atomic::AtomicBool;
+    use tempfile::TempDir;
+
+    fn setup_app() -> (TempDir, App) {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir(dir.path().join("alpha")).unwrap();
+        fs::create_dir(dir.path().join("beta")).unwrap();
+        File::create(dir.path().join("file_a.txt")).unwrap();
+        File::create(dir.path().join("file_b.rs")).unwrap();
+        File::create(dir.path().join(".hidden")).unwrap();
+        let app = App::new(dir.path(), crate::config::AppConfig::default()).unwrap();
+        (dir, app)
+    }
+
+    #[test]
+    fn progress_updates_carry_throughput() {
+        let (_dir, mut app) = setup_app();
+        let started = Instant::now() - Duration::from_secs(2);
+        app.open_dialog(DialogKind::Progress {
+            message: String::new(),
+            current: 0,
+            total: Some(1),
+            bytes_done: 0,
+            bytes_total: 0,
+            started,
+            rate: None,
+            spin: 0,
+        });
+        app.handle_progress(crate::event::ProgressUpdate {
+            current_file: "big.iso".to_string(),
+            current: 1,
+            total: Some(1),
+            bytes_done: 200,
+            bytes_total: 1000,
+        });
+        match app.mode {
+            AppMode::Dialog(DialogKind::Progress {
+                bytes_done,
+                bytes_total,
+                rate: Some(rate),
+                ..
+            }) => {
+                assert_eq!((bytes_done, bytes_total), (200, 1000));
+                // 200 bytes over a little more than two seconds
+                assert!((90..=100).contains(&rate), "rate {}", rate);
+            }
+            ref other => panic!("expected progress with a rate, got {:?}", other),
+        }
+
+        // Too early, or nothing copied: no figure yet
+        assert_eq!(throughput(4096, Duration::from_millis(100)), None);
+        assert_eq!(throughput(0, Duration::from_secs(5)), None);
+    }
+
+    #[test]
+    fn progress_spinner_ticks_until_a_total_arrives() {
+        let (_dir, mut app) = setup_app();
+        app.open_dialog(DialogKind::Progress {
+            message: String::new(),
+            current: 0,
+            total: None,
+            bytes_done: 0,
+            bytes_total: 0,
+            started: Instant::now(),
+            rate: None,
+            spin: 0,
+        });
+        let spin = |app: &App| match app.mode {
+            AppMode::Dialog(DialogKind::Progress { spin, total, .. }) => (spin, total),
+            ref other => panic!("expected progress, got {:?}", other),
+        };
+        app.handle_tick();
+        app.handle_tick();
+        assert_eq!(spin(&app), (2, None));
+
+        let update = |current, total| crate::event::ProgressUpdate {
+            current_file: "entry".to_string(),
+            current,
+            total,
+            bytes_done: 0,
+            bytes_total: 0,
+        };
+        // Updates keep the frame; the title shows the running count
+        app.handle_progress(update(150, None));
+        assert_eq!(spin(&app), (2, None));
+        match &app.mode {
+            AppMode::Dialog(kind) => assert_eq!(kind.title(), "Processing 150"),
+            other => panic!("expected a dialog, got {:?}", other),
+        }
+
+        // A total switches to the bar, which doesn't need ticks
+        app.handle_progress(update(200, Some(800)));
+        app.handle_tick();
+        assert_eq!(spin(&app), (2, Some(800)));
+        match &app.mode {
+            AppMode::Dialog(kind) => assert_eq!(kind.title(), "Processing 200/800"),
+            other => panic!("expected a dialog, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn delete_without_a_count_reports_an_unknown_total() {
+        let (dir, mut app) = setup_app();
+        for i in 0..3 {
+            fs::write(dir.path().join("alpha").join(format!("{}.txt", i)), "x").unwrap();
+        }
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        app.delete_async(vec![dir.path().join("alpha")], None, false, tx);
+        assert!(matches!(
+            app.mode,
+            AppMode::Dialog(DialogKind::Progress { total: None, .. })
+        ));
+        let mut updates = 0;
+        while let Some(event) = rx.recv().await {
+            match event {
+                crate::event::Event::Progress(update) => {
+                    assert_eq!(update.total, None);
+                    updates += 1;
+                    app.handle_progress(update);
+                }
+                crate::event::Event::DeleteComplete(result) => {
+                    app.handle_delete_complete(result);
+                    break;
+                }
+                _ => {}
+            }
+        }
+        assert!(updates > 0);
+        assert!(!dir.path().join("alpha").exists());
+        assert!(matches!(app.mode, AppMode::Normal));
+    }
+
+    #[test]
+    fn select_next_moves_down() {
+        let (_dir, mut app) = setup_app();
+        assert_eq!(app.tree_state.selected_index, 0);
+        app.select_next();
+        assert_eq!(app.tree_state.selected_index, 1);
+    }
+
+    #[test]
+    fn select_next_clamps_at_end() {
+        let (_dir, mut app) = setup_app();
+        let last = app.tree_state.flat_items.len() - 1;
+        app.tree_state.selected_index = last;
+        app.select_next();
+        assert_eq!(app.tree_state.selected_index, last);
+    }
+
+    #[test]
+    fn select_previous_moves_up() {
+        let (_dir, mut app) = setup_app();
+        app.tree_state.selected_index = 2;
+        app.select_previous();
+        assert_eq!(app.tree_state.selected_index, 1);
+    }
+
+    #[test]
+    fn select_previous_clamps_at_start() {
+        let (_dir, mut app) = setup_app();
+        app.select_previous();
+        assert_eq!(app.tree_state.selected_index, 0);
+    }
+
+    #[test]
+    fn select_first_and_last() {
+        let (_dir, mut app) = setup_app();
+        app.select_last();
+        assert_eq!(
+            app.tree_state.selected_index,
+            app.tree_state.flat_items.len() - 1
+        );
+        app.select_first();
+        assert_eq!(app.tree_state.selected_index, 0);
+    }
+
+    #[test]
+    fn toggle_hidden_changes_count() {
+        let (_dir, mut app) = setup_app();
+        let without_hidden = app.tree_state.flat_items.len();
+        app.toggle_hidden();
+        let with_hidden = app.tree_state.flat_items.len();
+        assert!(with_hidden > without_hidden);
+    }
+
+    #[test]
+    fn toggle_gitignore_hides_ignored_files() {
+        let (dir, _) = setup_app();
+        fs::create_dir(dir.path().join(".git")).unwrap();
+        fs::write(dir.path().join(".gitignore"), "*.rs\n").unwrap();
+        let mut app = App::new(dir.path(), crate::config::AppConfig::default()).unwrap();
+        assert!(app.tree_state.respect_gitignore);
+        let shown = |app: &App| {
+            app.tree_state
+                .flat_items
+                .iter()
+                .any(|i| i.name == "file_b.rs")
+        };
+        assert!(!shown(&app));
+
+        app.toggle_gitignore();
+        assert!(!app.tree_state.respect_gitignore);
+        assert!(!app.watcher.respect_gitignore);
+        assert!(shown(&app));
+        assert!(app
+            .build_path_index()
+            .paths
+            .contains(&dir.path().join("file_b.rs")));
+
+        app.toggle_gitignore();
+        assert!(!shown(&app));
+        assert!(!app
+            .build_path_index()
+            .paths
+            .contains(&dir.path().join("file_b.rs")));
+    }
+
+    #[test]
+    fn expand_directory() {
+        let (_dir, mut app) = setup_app();
+        // Select first child (should be a directory: "alpha")
+        app.select_next();
+        assert_eq!(app.tree_state.flat_items[1].name, "alpha");
+        app.expand_selected();
+        // alpha is empty so flat items count stays same, but it's now expanded
+        assert!(app.tree_state.flat_items[1].is_expanded);
+    }
+
+    #[test]
+    fn quit_sets_flag() {
+        let (_dir, mut app) = setup_app();
+        assert!(!app.should_quit);
+        app.quit();
+        assert!(app.should_quit);
+    }
+
+    #[test]
+    fn open_dialog_sets_mode() {
+        let (_dir, mut app) = setup_app();
+        app.open_dialog(DialogKind::CreateFile);
+        assert_eq!(app.mode, AppMode::Dialog(DialogKind::CreateFile));
+    }
+
+    #[test]
+    fn close_dialog_returns_to_normal() {
+        let (_dir, mut app) = setup_app();
+        app.open_dialog(DialogKind::CreateDirectory);
+        app.close_dialog();
+        assert_eq!(app.mode, AppMode::Normal);
+        assert!(app.dialog_state.input.is_empty());
+        assert_eq!(app.dialog_state.cursor_position, 0);
+    }
+
+    #[test]
+    fn dialog_input_char_inserts() {
+        let (_dir, mut app) = setup_app();
+        app.open_dialog(DialogKind::CreateFile);
+        app.dialog_input_char('a');
+        app.dialog_input_char('b');
+        app.dialog_input_char('c');
+        assert_eq!(app.dialog_state.input, "abc");
+        assert_eq!(app.dialog_state.cursor_position, 3);
+    }
+
+    #[test]
+    fn dialog_delete_char_removes() {
+        let (_dir, mut app) = setup_app();
+        app.open_dialog(DialogKind::CreateFile);
+        app.dialog_input_char('a');
+        app.dialog_input_char('b');
+        app.dialog_delete_char();
+        assert_eq!(app.dialog_state.input, "a");
+        assert_eq!(app.dialog_state.cursor_position, 1);
+    }
+
+    #[test]
+    fn dialog_delete_char_at_start_is_noop() {
+        let (_dir, mut app) = setup_app();
+        app.open_dialog(DialogKind::CreateFile);
+        app.dialog_delete_char();
+        assert!(app.dialog_state.input.is_empty());
+        assert_eq!(app.dialog_state.cursor_position, 0);
+    }
+
+    #[test]
+    fn dialog_cursor_left_right() {
+        let (_dir, mut app) = setup_app();
+        app.open_dialog(DialogKind::CreateFile);
+        app.dialog_input_char('a');
+        app.dialog_input_char('b');
+        app.dialog_move_cursor_left();
+        assert_eq!(app.dialog_state.cursor_position, 1);
+        app.dialog_move_cursor_right();
+        assert_eq!(app.dialog_state.cursor_position, 2);
+    }
+
+    #[test]
+    fn dialog_cursor_boundaries() {
+        let (_dir, mut app) = setup_app();
+        app.open_dialog(DialogKind::CreateFile);
+        app.dialog_move_cursor_left();
+        assert_eq!(app.dialog_state.cursor_position, 0);
+        app.dialog_input_char('x');
+        app.dialog_move_cursor_right();
+        assert_eq!(app.dialog_state.cursor_position, 1);
+    }
+
+    #[test]
+    fn dialog_cursor_home_end() {
+        let (_dir, mut app) = setup_app();
+        app.open_dialog(DialogKind::CreateFile);
+        app.dialog_input_char('a');
+        app.dialog_input_char('b');
+        app.dialog_input_char('c');
+        app.dialog_cursor_home();
+        assert_eq!(app.dialog_state.cursor_position, 0);
+        app.dialog_cursor_end();
+        assert_eq!(app.dialog_state.cursor_position, 3);
+    }
+
+    #[test]
+    fn dialog_cursor_moves_over_combining_marks_and_zwj() {
+        let (_dir, mut app) = setup_app();
+        app.open_dialog(DialogKind::CreateFile);
+        for c in "e\u{301}\u{1F469}\u{200D}\u{1F4BB}x".chars() {
+            app.dialog_input_char(c);
+        }
+        app.dialog_move_cursor_left();
+        app.dialog_move_cursor_left();
+        assert_eq!(app.dialog_state.cursor_position, "e\u{301}".len());
+        app.dialog_move_cursor_left();
+        assert_eq!(app.dialog_state.cursor_position, 0);
+        app.dialog_move_cursor_right();
+        app.dialog_move_cursor_right();
+        assert_eq!(
+            app.dialog_state.cursor_position,
+            "e\u{301}\u{1F469}\u{200D}\u{1F4BB}".len()
+        );
+    }
+
+    #[test]
+    fn dialog_delete_char_removes_whole_cluster() {
+        let (_dir, mut app) = setup_app();
+        app.open_dialog(DialogKind::CreateFile);
+        for c in "a\u{1F469}\u{200D}\u{1F4BB}".chars() {
+            app.dialog_input_char(c);
+        }
+        app.dialog_delete_char();
+        assert_eq!(app.dialog_state.input, "a");
+        assert_eq!(app.dialog_state.cursor_position, 1);
+
+        app.dialog_input_char('e');
+        app.dialog_input_char('\u{301}');
+        app.dialog_delete_char();
+        assert_eq!(app.dialog_state.input, "a");
+    }
+
+    #[test]
+    fn rename_prefills_input() {
+        let (_dir, mut app) = setup_app();
+        let path = PathBuf::from("/some/dir/hello.txt");
+        app.open_dialog(DialogKind::Rename { original: path });
+        assert_eq!(app.dialog_state.input, "hello.txt");
+        assert_eq!(app.dialog_state.cursor_position, 9);
+    }
+
+    #[test]
+    fn set_status_message_stores_message() {
+        let (_dir, mut app) = setup_app();
+        app.set_status_message("test message".to_string());
+        assert!(app.status_message.is_some());
+        let (msg, _) = app.status_message.as_ref().unwrap();
+        assert_eq!(msg, "test message");
+    }
+
+    #[test]
+    fn clear_expired_status_keeps_recent() {
+        let (_dir, mut app) = setup_app();
+        app.set_status_message("fresh".to_string());
+        app.clear_expired_status();
+        assert!(app.status_message.is_some());
+    }
+
+    #[test]
+    fn clear_expired_status_removes_old() {
+        let (_dir, mut app) = setup_app();
+        app.status_message = Some((
+            "old".to_string(),
+            Instant::now() - std::time::Duration::from_secs(5),
+        ));
+        app.clear_expired_status();
+        assert!(app.status_message.is_none());
+    }
+
+    #[test]
+    fn current_dir_returns_root_for_directory() {
+        let (dir, app) = setup_app();
+        // selected_index 0 is root, which is a directory
+        assert_eq!(app.current_dir(), dir.path().to_path_buf());
+    }
+
+    #[test]
+    fn current_dir_returns_parent_for_file() {
+        let (dir, mut app) = setup_app();
+        // Navigate to a file (files come after directories in flat_items)
+        // flat_items: root(dir), alpha(dir), beta(dir), file_a.txt, file_b.rs
+        app.tree_state.selected_index = 3; // file_a.txt
+        assert_eq!(app.current_dir(), dir.path().to_path_buf());
+    }
+
+    // === Preview state tests ===
+
+    #[test]
+    fn default_focused_panel_is_tree() {
+        let (_dir, app) = setup_app();
+        assert_eq!(app.focused_panel, FocusedPanel::Tree);
+    }
+
+    #[test]
+    fn toggle_focus_switches_panel() {
+        let (_dir, mut app) = setup_app();
+        assert_eq!(app.focused_panel, FocusedPanel::Tree);
+        app.toggle_focus();
+        assert_eq!(app.focused_panel, FocusedPanel::Preview);
+        app.toggle_focus();
+        assert_eq!(app.focused_panel, FocusedPanel::Tree);
+    }
+
+    #[test]
+    fn preview_state_defaults() {
+        let (_dir, app) = setup_app();
+        assert!(app.preview_state.current_path.is_none());
+        assert!(app.preview_state.content_lines.is_empty());
+        assert_eq!(app.preview_state.scroll_offset, 0);
+        assert_eq!(app.preview_state.view_mode, ViewMode::HeadAndTail);
+        assert!(!app.preview_state.line_wrap);
+        assert_eq!(app.preview_state.total_lines, 0);
+    }
+
+    #[test]
+    fn preview_scroll_down_up() {
+        let (_dir, mut app) = setup_app();
+        app.preview_state.total_lines = 100;
+        app.preview_scroll_down();
+        assert_eq!(app.preview_state.scroll_offset, 1);
+        app.preview_scroll_down();
+        assert_eq!(app.preview_state.scroll_offset, 2);
+        app.preview_scroll_up();
+        assert_eq!(app.preview_state.scroll_offset, 1);
+    }
+
+    #[test]
+    fn preview_scroll_clamps_at_boundaries() {
+        let (_dir, mut app) = setup_app();
+        app.preview_state.total_lines = 3;
+        // Can't scroll past end
+        app.preview_state.scroll_offset = 2;
+        app.preview_scroll_down();
+        assert_eq!(app.preview_state.scroll_offset, 2);
+        // Can't scroll before start
+        app.preview_state.scroll_offset = 0;
+        app.preview_scroll_up();
+        assert_eq!(app.preview_state.scroll_offset, 0);
+    }
+
+    #[test]
+    fn preview_scroll_down_noop_when_empty() {
+        let (_dir, mut app) = setup_app();
+        app.preview_state.total_lines = 0;
+        app.preview_scroll_down();
+        assert_eq!(app.preview_state.scroll_offset, 0);
+    }
+
+    #[test]
+    fn preview_jump_top_bottom() {
+        let (_dir, mut app) = setup_app();
+        app.preview_state.total_lines = 100;
+        app.preview_jump_bottom();
+        assert_eq!(app.preview_state.scroll_offset, 99);
+        app.preview_jump_top();
+        assert_eq!(app.preview_state.scroll_offset, 0);
+    }
+
+    #[test]
+    fn preview_half_page_scroll() {
+        let (_dir, mut app) = setup_app();
+        app.preview_state.total_lines = 100;
+        app.preview_half_page_down(20);
+        assert_eq!(app.preview_state.scroll_offset, 10);
+        app.preview_half_page_down(20);
+        assert_eq!(app.preview_state.scroll_offset, 20);
+        app.preview_half_page_up(20);
+        assert_eq!(app.preview_state.scroll_offset, 10);
+    }
+
+    #[test]
+    fn preview_half_page_clamps() {
+        let (_dir, mut app) = setup_app();
+        app.preview_state.total_lines = 10;
+        app.preview_half_page_down(100);
+        assert_eq!(app.preview_state.scroll_offset, 9);
+        app.preview_half_page_up(100);
+        assert_eq!(app.preview_state.scroll_offset, 0);
+    }
+
+    #[test]
+    fn preview_jump_bottom_respects_viewport_height() {
+        let (_dir, mut app) = setup_app();
+        app.preview_state.content_lines =
+            (0..100).map(|i| Line::from(format!("line {i}"))).collect();
+        app.preview_state.total_lines = 100;
+        app.preview_area = Rect::new(0, 0, 80, 12); // inner height = 10
+        app.preview_jump_bottom();
+        assert_eq!(app.preview_state.scroll_offset, 90);
+    }
+
+    #[test]
+    fn clamp_preview_scroll_after_resize() {
+        let (_dir, mut app) = setup_app();
+        app.preview_state.content_lines =
+            (0..100).map(|i| Line::from(format!("line {i}"))).collect();
+        app.preview_state.total_lines = 100;
+        app.preview_area = Rect::new(0, 0, 80, 12); // inner height = 10
+        app.preview_jump_bottom();
+        assert_eq!(app.preview_state.scroll_offset, 90);
+
+        app.preview_area = Rect::new(0, 0, 80, 22); // inner height = 20
+        app.clamp_preview_scroll();
+        assert_eq!(app.preview_state.scroll_offset, 80);
+    }
+
+    fn searchable_preview(app: &mut App, path: PathBuf) {
+        app.preview_state.current_path = Some(path);
+        app.preview_state.content_lines = (1..=100)
+            .map(|i| {
+                let text = if i % 40 == 11 { "a needle here" } else { "hay" };
+                Line::from(format!(
+                    "{:>3}{}{}",
+                    i,
+                    preview_content::LINE_NUMBER_SEP,
+                    text
+                ))
+            })
+            .collect();
+        app.preview_state.total_lines = 100;
+        app.preview_area = Rect::new(0, 0, 80, 12); // 9 rows under the search bar
+        app.focused_panel = FocusedPanel::Preview;
+    }
+
+    #[test]
+    fn preview_search_jumps_through_matches() {
+        let (dir, mut app) = setup_app();
+        searchable_preview(&mut app, dir.path().join("file_a.txt"));
+        app.open_preview_search();
+        assert_eq!(app.mode, AppMode::PreviewSearch);
+
+        // Typing finds matches on rows 10, 50 and 90 and scrolls to the first
+        "NEEDLE"
+            .chars()
+            .for_each(|c| app.preview_search_input_char(c));
+        let rows: Vec<usize> = app.preview_search.matches.iter().map(|m| m.row).collect();
+        assert_eq!(rows, vec![10, 50, 90]);
+        assert_eq!(app.preview_state.scroll_offset, 6);
+        app.accept_preview_search();
+        assert_eq!(app.mode, AppMode::Normal);
+        assert!(app.preview_search.is_active());
+
+        app.preview_search_step(true, 1);
+        assert_eq!(app.preview_state.scroll_offset, 46);
+        app.preview_search_step(true, 1);
+        // Clamped so the last row still shows above the bar
+        assert_eq!(app.preview_state.scroll_offset, 86);
+        app.preview_search_step(true, 1);
+        assert_eq!(app.preview_search.counter(), "(1/3)");
+        assert_eq!(
+            app.status_message.as_ref().map(|(m, _)| m.as_str()),
+            Some("Search wrapped to the top")
+        );
+        app.preview_search_step(false, 2);
+        assert_eq!(app.preview_search.current().map(|m| m.row), Some(50));
+
+        app.close_preview_search();
+        assert!(!app.preview_search.is_active());
+        assert!(app.preview_state.text.is_empty());
+    }
+
+    #[test]
+    fn preview_search_in_a_partial_file_says_so_and_ends_on_another_file() {
+        let (dir, mut app) = setup_app();
+        searchable_preview(&mut app, dir.path().join("file_a.txt"));
+        app.preview_state.is_large_file = true;
+        app.preview_state.view_mode = ViewMode::HeadOnly;
+        app.preview_state.scroll_offset = 20;
+        app.open_preview_search();
+        assert_eq!(
+            app.status_message.as_ref().map(|(m, _)| m.as_str()),
+            Some("Large file: searching the loaded head only")
+        );
+
+        // No match leaves the view where the search started
+        "needlex"
+            .chars()
+            .for_each(|c| app.preview_search_input_char(c));
+        assert!(app.preview_search.matches.is_empty());
+        assert_eq!(app.preview_state.scroll_offset, 20);
+        app.accept_preview_search();
+        assert_eq!(
+            app.status_message.as_ref().map(|(m, _)| m.as_str()),
+            Some("No matches for \"needlex\" in the loaded head")
+        );
+
+        // New rows for the same file are searched again
+        app.preview_search.query = "needle".to_string();
+        app.preview_state.text.clear();
+        app.sync_preview_search();
+        assert_eq!(app.preview_search.matches.len(), 3);
+
+        app.preview_state.current_path = Some(dir.path().join("file_b.rs"));
+        app.sync_preview_search();
+        assert!(!app.preview_search.is_active());
+        assert!(app.preview_search.matches.is_empty());
+    }
+
+    // === Integration tests: preview update flow ===
+
+    #[test]
+    fn update_preview_loads_file_content() {
+        let (dir, mut app) = setup_app();
+        // Write content to a file
+        std::fs::write(dir.path().join("file_a.txt"), "hello world\n").unwrap();
+        // Select file_a.txt (index 3)
+        app.tree_state.selected_index = 3;
+        app.update_preview();
+        assert!(app.preview_state.current_path.is_some());
+        assert!(!app.preview_state.content_lines.is_empty());
+        assert!(app.preview_state.total_lines >= 1);
+    }
+
+    #[test]
+    fn update_preview_directory_shows_summary() {
+        let (_dir, mut app) = setup_app();
+        // Select alpha directory (index 1)
+        app.tree_state.selected_index = 1;
+        app.update_preview();
+        assert!(app.preview_state.current_path.is_some());
+        let all_text: String = app
+            .preview_state
+            .content_lines
+            .iter()
+            .flat_map(|l| l.spans.iter().map(|s| s.content.as_ref()))
+            .collect();
+        assert!(all_text.contains("Directory:"));
+    }
+
+    #[test]
+    fn update_preview_binary_file_shows_metadata() {
+        let (dir, mut app) = setup_app();
+        // Create a binary file
+        let bin_path = dir.path().join("model.pt");
+        std::fs::write(&bin_path, &[0u8; 100]).unwrap();
+        app.tree_state.reload_dir(dir.path());
+
+        // Find the .pt file in flat_items
+        let idx = app
+            .tree_state
+            .flat_items
+            .iter()
+            .position(|item| item.name == "model.pt")
+            .unwrap();
+        app.tree_state.selected_index = idx;
+        app.update_preview();
+
+        let all_text: String = app
+            .preview_state
+            .content_lines
+            .iter()
+            .flat_map(|l| l.spans.iter().map(|s| s.content.as_ref()))
+            .collect();
+        assert!(all_text.contains("Binary file"));
+        assert!(all_text.contains("model.pt"));
+    }
+
+    #[test]
+    fn update_preview_notebook_file() {
+        let (dir, mut app) = setup_app();
+        let nb_path = dir.path().join("test.ipynb");
+        std::fs::write(
+            &nb_path,
+            r#"{"cells":[{"cell_type":"code","source":["x=1"],"outputs":[]}],"metadata":{}}"#,
+        )
+        .unwrap();
+        app.tree_state.reload_dir(dir.path());
+
+        let idx = app
+            .tree_state
+            .flat_items
+            .iter()
+            .position(|item| item.name == "test.ipynb")
+            .unwrap();
+        app.tree_state.selected_index = idx;
+        app.update_preview();
+
+        let all_text: String = app
+            .preview_state
+            .content_lines
+            .iter()
+            .flat_map(|l| l.spans.iter().map(|s| s.content.as_ref()))
+            .collect();
+        assert!(all_text.contains("Cell 1"));
+        assert!(all_text.contains("code"));
+    }
+
+    #[test]
+    fn update_preview_resets_scroll_on_selection_change() {
+        let (dir, mut app) = setup_app();
+        std::fs::write(dir.path().join("file_a.txt"), "line1\nline2\nline3\n").unwrap();
+        // Select file
+        app.tree_state.selected_index = 3;
+        app.update_preview();
+        app.preview_state.scroll_offset = 2;
+        // Change selection
+        app.tree_state.selected_index = 1; // directory
+        app.last_previewed_index = None; // force update
+        app.update_preview();
+        assert_eq!(app.preview_state.scroll_offset, 0);
+    }
+
+    #[test]
+    fn update_preview_skips_if_same_selection() {
+        let (dir, mut app) = setup_app();
+        std::fs::write(dir.path().join("file_a.txt"), "hello\n").unwrap();
+        app.tree_state.selected_index = 3;
+        app.update_preview();
+        let first_path = app.preview_state.current_path.clone();
+        // Call again without changing selection
+        app.preview_state.scroll_offset = 5;
+        app.update_preview();
+        // Should not reset scroll
+        assert_eq!(app.preview_state.scroll_offset, 5);
+        assert_eq!(app.preview_state.current_path, first_path);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn fifo_preview_is_a_metadata_card_and_editing_is_refused() {
+        let (dir, mut app) = setup_app();
+        let fifo = dir.path().join("pipe");
+        if !crate::fs::tree::mkfifo(&fifo) {
+            return;
+        }
+        app.tree_state.reload_dir(dir.path());
+        select_named(&mut app, "pipe");
+
+        // Opening the FIFO would block with no writer
+        let started = Instant::now();
+        app.update_preview();
+        assert!(started.elapsed() < Duration::from_secs(1));
+        let text = preview_text(&app);
+        assert!(
+            text.contains("named pipe — contents not previewable"),
+            "{text}"
+        );
+        assert!(!app.preview_load.is_loading());
+
+        app.focused_panel = FocusedPanel::Preview;
+        assert!(!app.enter_edit_mode(None));
+        #[cfg(feature = "editor")]
+        {
+            assert!(app.editor_state.is_none());
+            let (msg, _) = app.status_message.as_ref().unwrap();
+            assert_eq!(msg, "Cannot edit pipe: named pipe");
+        }
+    }
+
+    /// Never finishes on its own, like a read from a dead network mount;
+    /// returns once cancelled.
+    struct StuckLoader;
+
+    impl PreviewLoader for StuckLoader {
+        fn load(
+            &self,
+            _: &LoadRequest,
+            cancel: &AtomicBool,
+        ) -> Option<(Loaded, Option<PendingHighlight>)> {
+            while !cancel.load(Ordering::SeqCst) {
+                std::thread::sleep(Duration::from_millis(5));
+            }
+            None
+        }
+    }
+
+    /// Finishes once released, whether or not it was cancelled.
+    struct GateLoader(std::sync::Mutex<std::sync::mpsc::Receiver<()>>);
+
+    impl PreviewLoader for GateLoader {
+        fn load(
+            &self,
+            request: &LoadRequest,
+            _: &AtomicBool,
+        ) -> Option<(Loaded, Option<PendingHighlight>)> {
+            let _ = self.0.lock().unwrap().recv();
+            SystemLoader.load(request, &AtomicBool::new(false))
+        }
+    }
+
+    fn preview_text(app: &App) -> String {
+        app.preview_state
+            .content_lines
+            .iter()
+            .map(crate::wrap::line_text)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// App previewing file_a.txt ("hello") through `loader`, without the
+    /// inline wait.
+    fn loading_app(loader: Arc<dyn PreviewLoader>, row: usize) -> (TempDir, App) {
+        let (dir, mut app) = setup_app();
+        fs::write(dir.path().join("file_a.txt"), "hello\n").unwrap();
+        app.preview_loader = loader;
+        app.preview_load.inline_wait = Duration::ZERO;
+        app.tree_state.selected_index = row;
+        app.update_preview();
+        (dir, app)
+    }
+
+    #[test]
+    fn stuck_preview_load_can_be_cancelled_and_retried() {
+        let (dir, mut app) = loading_app(Arc::new(StuckLoader), 3);
+        assert!(app.preview_load.is_loading());
+        assert_eq!(
+            app.preview_state.current_path,
+            Some(dir.path().join("file_a.txt"))
+        );
+        assert_eq!(preview_text(&app), "Loading…");
+
+        app.poll_preview_load(Instant::now() + Duration::from_secs(3));
+        assert_eq!(
+            preview_text(&app),
+            "Loading… 3s — Esc in the preview cancels"
+        );
+
+        app.cancel_preview_load();
+        assert!(!app.preview_load.is_loading());
+        assert_eq!(preview_text(&app), "Load cancelled — press r to retry");
+        // Polling a stopped load changes nothing
+        app.poll_preview_load(Instant::now() + Duration::from_secs(60));
+        assert_eq!(preview_text(&app), "Load cancelled — press r to retry");
+
+        // The mount came back
+        app.preview_loader = Arc::new(SystemLoader);
+        app.preview_load.inline_wait = Duration::from_secs(10);
+        app.retry_preview_load();
+        assert!(!app.preview_load.is_loading() && !app.preview_load.is_stopped());
+        assert!(preview_text(&app).contains("hello"));
+    }
+
+    #[test]
+    fn stuck_preview_load_times_out() {
+        let (_dir, mut app) = loading_app(Arc::new(StuckLoader), 3);
+        app.config.preview.load_timeout_secs = Some(1);
+        let start = Instant::now();
+        app.poll_preview_load(start + Duration::from_millis(500));
+        assert!(app.preview_load.is_loading());
+
+        app.poll_preview_load(start + Duration::from_secs(2));
+        assert!(app.preview_load.is_stopped());
+        assert_eq!(
+            preview_text(&app),
+            "Load timed out after 1s — press r to retry"
+        );
+
+        // A retry gets a fresh clock
+        app.retry_preview_load();
+        assert!(app.preview_load.is_loading());
+        assert_eq!(preview_text(&app), "Loading…");
+    }
+
+    #[test]
+    fn zero_timeout_waits_forever() {
+        let (_dir, mut app) = loading_app(Arc::new(StuckLoader), 3);
+        app.config.preview.load_timeout_secs = Some(0);
+        app.poll_preview_load(Instant::now() + Duration::from_secs(3600));
+        assert!(app.preview_load.is_loading());
+        app.cancel_preview_load();
+    }
+
+    #[test]
+    fn late_result_cannot_bring_back_a_cancelled_load() {
+        let (release, gate) = std::sync::mpsc::channel();
+        let (_dir, mut app) = loading_app(Arc::new(GateLoader(std::sync::Mutex::new(gate))), 3);
+        app.cancel_preview_load();
+        release.send(()).unwrap();
+        std::thread::sleep(Duration::from_millis(50));
+        app.poll_preview_load(Instant::now());
+        assert_eq!(preview_text(&app), "Load cancelled — press r to retry");
+    }
+
+    #[test]
+    fn late_result_cannot_replace_the_next_selection() {
+        let (release, gate) = std::sync::mpsc::channel();
+        let (dir, mut app) = loading_app(Arc::new(GateLoader(std::sync::Mutex::new(gate))), 3);
+        fs::write(dir.path().join("file_b.rs"), "fn b() {}\n").unwrap();
+        app.preview_loader = Arc::new(SystemLoader);
+        app.preview_load.inline_wait = Duration::from_secs(10);
+        app.tree_state.selected_index = 4;
+        app.update_preview();
+        assert!(preview_text(&app).contains("fn b()"));
+
+        release.send(()).unwrap();
+        std::thread::sleep(Duration::from_millis(50));
+        app.poll_preview_load(Instant::now());
+        assert_eq!(
+            app.preview_state.current_path,
+            Some(dir.path().join("file_b.rs"))
+        );
+        assert!(preview_text(&app).contains("fn b()"));
+    }
+
+    #[test]
+    fn rapid_selection_changes_load_only_where_the_selection_settles() {
+        let (dir, mut app) = setup_app();
+        fs::write(dir.path().join("file_a.txt"), "hello\n").unwrap();
+        fs::write(dir.path().join("file_b.rs"), "fn b() {}\n").unwrap();
+        app.preview_load.debounce = Duration::from_secs(3600);
+        app.tree_state.selected_index = 3;
+        app.update_preview();
+        assert!(preview_text(&app).contains("hello"));
+
+        // Within the debounce of the last load: placeholder, nothing started
+        app.tree_state.selected_index = 4;
+        app.update_preview();
+        assert!(app.preview_load.is_loading());
+        assert!(!app.preview_load.is_running(app.preview_load.generation()));
+        assert_eq!(preview_text(&app), "Loading…");
+        app.poll_preview_load(Instant::now());
+        assert_eq!(preview_text(&app), "Loading…");
+
+        // Settled: the tick starts it and the ready event shows it
+        app.preview_load.debounce = Duration::ZERO;
+        app.poll_preview_load(Instant::now());
+        let generation = app.preview_load.generation();
+        assert!(app.preview_load.is_running(generation));
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while app.preview_load.is_loading() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(5));
+            app.handle_preview_ready(generation);
+        }
+        assert!(preview_text(&app).contains("fn b()"));
+        // A stale generation is ignored
+        app.handle_preview_ready(generation - 1);
+        assert!(preview_text(&app).contains("fn b()"));
+    }
+
+    #[test]
+    #[cfg(feature = "syntax-highlighting")]
+    fn long_file_previews_plain_past_the_first_lines_until_highlighted() {
+        let (dir, mut app) = setup_app();
+        let path = dir.path().join("file_b.rs");
+        let source: String = (0..crate::preview_load::FIRST_HIGHLIGHT_LINES + 50)
+            .map(|i| format!("let x{} = {};\n", i, i))
+            .collect();
+        fs::write(&path, source).unwrap();
+        app.tree_state.selected_index = 4;
+        app.update_preview();
+        assert_eq!(
+            app.preview_state.content_lines.len(),
+            crate::preview_load::FIRST_HIGHLIGHT_LINES + 50
+        );
+        assert_eq!(
+            app.preview_state.total_lines,
+            crate::preview_load::FIRST_HIGHLIGHT_LINES + 50
+        );
+        // Cached only once whole
+        assert!(app.preview_cache.key_for(&path, 4, false).is_none());
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while app.preview_load.highlighting_path().is_some() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(5));
+            app.poll_preview_load(Instant::now());
+        }
+        let last = app.preview_state.content_lines.last().unwrap();
+        // Gutter plus highlighted pieces, not one plain span
+        assert!(last.spans.len() > 2);
+        let key = app.preview_cache.key_for(&path, 4, false).unwrap();
+        assert!(app.preview_cache.get(&key).is_some());
+    }
+
+    #[test]
+    #[cfg(feature = "syntax-highlighting")]
+    fn syntax_is_loaded_only_once_something_is_highlighted() {
+        let (dir, mut app) = setup_app();
+        fs::write(dir.path().join("file_b.rs"), "fn main() {}\n").unwrap();
+        // Root, alpha/, beta/: directory summaries only
+        for row in 0..3 {
+            app.tree_state.selected_index = row;
+            app.update_preview();
+        }
+        assert!(!app.syntax.is_loaded());
+
+        app.tree_state.selected_index = 4;
+        app.update_preview();
+        assert!(app.syntax.is_loaded());
+        assert!(preview_text(&app).contains("fn main()"));
+    }
+
+    #[test]
+    fn directory_summaries_load_through_the_same_controls() {
+        let (dir, mut app) = loading_app(Arc::new(StuckLoader), 1);
+        assert!(app.preview_load.is_loading());
+        assert_eq!(
+            app.preview_state.current_path,
+            Some(dir.path().join("alpha"))
+        );
+        app.cancel_preview_load();
+        assert!(app.preview_load.is_stopped());
+
+        app.preview_loader = Arc::new(SystemLoader);
+        app.preview_load.inline_wait = Duration::from_secs(10);
+        app.retry_preview_load();
+        assert!(preview_text(&app).contains("alpha"));
+    }
+
+    // === Search (Ctrl+P) tests ===
+
+    #[test]
+    fn open_search_sets_mode() {
+        let (_dir, mut app) = setup_app();
+        app.open_search();
+        assert_eq!(app.mode, AppMode::Search);
+        assert!(app.search_state.cached_paths.is_some());
+    }
+
+    #[test]
+    fn close_search_returns_to_normal() {
+        let (_dir, mut app) = setup_app();
+        app.open_search();
+        app.close_search();
+        assert_eq!(app.mode, AppMode::Normal);
+    }
+
+    #[test]
+    fn search_input_updates_query() {
+        let (_dir, mut app) = setup_app();
+        app.open_search();
+        app.search_input_char('f');
+        assert_eq!(app.search_state.query, "f");
+        app.search_input_char('i');
+        assert_eq!(app.search_state.query, "fi");
+    }
+
+    #[test]
+    fn search_delete_char_removes() {
+        let (_dir, mut app) = setup_app();
+        app.open_search();
+        app.search_input_char('a');
+        app.search_input_char('b');
+        app.search_delete_char();
+        assert_eq!(app.search_state.query, "a");
+    }
+
+    #[test]
+    fn search_delete_at_empty_is_noop() {
+        let (_dir, mut app) = setup_app();
+        app.open_search();
+        app.search_delete_char();
+        assert_eq!(app.search_state.query, "");
+    }
+
+    #[test]
+    fn search_results_update_on_input() {
+        let (_dir, mut app) = setup_app();
+        app.open_search();
+        app.search_input_char('f');
+        app.search_input_char('i');
+        app.search_input_char('l');
+        app.search_input_char('e');
+        // Should find file_a.txt and file_b.rs
+        assert!(app.search_state.results.len() >= 2);
+    }
+
+    #[test]
+    fn search_empty_query_clears_results() {
+        let (_dir, mut app) = setup_app();
+        app.open_search();
+        app.search_input_char('a');
+        assert!(!app.search_state.results.is_empty());
+        app.search_delete_char();
+        assert!(app.search_state.results.is_empty());
+    }
+
+    #[test]
+    fn search_no_matches_empty_results() {
+        let (_dir, mut app) = setup_app();
+        app.open_search();
+        app.search_input_char('z');
+        app.search_input_char('z');
+        app.search_input_char('z');
+        assert!(app.search_state.results.is_empty());
+    }
+
+    #[test]
+    fn search_select_navigation() {
+        let (_dir, mut app) = setup_app();
+        app.open_search();
+        app.search_input_char('f');
+        assert_eq!(app.search_state.selected_index, 0);
+        app.search_select_next();
+        assert_eq!(app.search_state.selected_index, 1);
+        app.search_select_previous();
+        assert_eq!(app.search_state.selected_index, 0);
+    }
+
+    #[test]
+    fn search_select_clamps() {
+        let (_dir, mut app) = setup_app();
+        app.open_search();
+        app.search_select_previous(); // at 0, should stay
+        assert_eq!(app.search_state.selected_index, 0);
+    }
+
+    #[test]
+    fn search_confirm_navigates_to_file() {
+        let (dir, mut app) = setup_app();
+        // Create a nested file for navigation test
+        fs::create_dir_all(dir.path().join("alpha").join("nested")).unwrap();
+        File::create(dir.path().join("alpha").join("nested").join("deep.txt")).unwrap();
+        app.tree_state.reload_dir(dir.path());
+        app.invalidate_search_cache();
+
+        app.open_search();
+        app.search_input_char('d');
+        app.search_input_char('e');
+        app.search_input_char('e');
+        app.search_input_char('p');
+
+        assert!(!app.search_state.results.is_empty());
+        app.search_confirm();
+        assert_eq!(app.mode, AppMode::SearchAction);
+        assert!(app.search_action_state.is_some());
+
+        // Navigate from action menu
+        app.search_action_navigate();
+        assert_eq!(app.mode, AppMode::Normal);
+
+        // Should have navigated to the deep.txt file
+        let selected = &app.tree_state.flat_items[app.tree_state.selected_index];
+        assert_eq!(selected.name, "deep.txt");
+    }
+
+    #[test]
+    fn build_path_index_finds_files() {
+        let (_dir, app) = setup_app();
+        let index = app.build_path_index();
+        // Should find file_a.txt, file_b.rs, .hidden
+        assert!(index.paths.len() >= 2);
+    }
+
+    #[test]
+    fn invalidate_search_cache_clears() {
+        let (_dir, mut app) = setup_app();
+        app.open_search();
+        assert!(app.search_state.cached_paths.is_some());
+        app.invalidate_search_cache();
+        assert!(app.search_state.cached_paths.is_none());
+    }
+
+    // === Filter (/) tests ===
+
+    #[test]
+    fn start_filter_sets_mode() {
+        let (_dir, mut app) = setup_app();
+        app.start_filter();
+        assert_eq!(app.mode, AppMode::Filter);
+    }
+
+    #[test]
+    fn filter_input_filters_tree() {
+        let (_dir, mut app) = setup_app();
+        app.start_filter();
+        let total_before = app.tree_state.flat_items.len();
+        app.filter_input_char('a');
+        // Should show fewer items (only matching + ancestors)
+        assert!(app.tree_state.flat_items.len() <= total_before);
+        assert!(app.tree_state.is_filtering);
+    }
+
+    #[test]
+    fn filter_preserves_parent_dirs() {
+        let (dir, mut app) = setup_app();
+        // Create inner.txt inside alpha
+        File::create(dir.path().join("alpha").join("inner.txt")).unwrap();
+        // Expand alpha so its children are loaded
+        app.tree_state.selected_index = 1; // alpha dir
+        app.expand_selected();
+
+        app.start_filter();
+        app.filter_input_char('i');
+        app.filter_input_char('n');
+        app.filter_input_char('n');
+        app.filter_input_char('e');
+        app.filter_input_char('r');
+
+        // "alpha" directory should be preserved as parent of "inner.txt"
+        let names: Vec<&str> = app
+            .tree_state
+            .flat_items
+            .iter()
+            .map(|i| i.name.as_str())
+            .collect();
+        assert!(names.contains(&"alpha"));
+        assert!(names.contains(&"inner.txt"));
+    }
+
+    #[test]
+    fn clear_filter_restores_tree() {
+        let (_dir, mut app) = setup_app();
+        let original_count = app.tree_state.flat_items.len();
+        app.start_filter();
+        app.filter_input_char('x');
+        app.clear_filter();
+        assert_eq!(app.mode, AppMode::Normal);
+        assert!(!app.tree_state.is_filtering);
+        assert_eq!(app.tree_state.flat_items.len(), original_count);
+    }
+
+    /// Names in the tree as currently flattened.
+    fn flat_names(app: &App) -> Vec<String> {
+        app.tree_state
+            .flat_items
+            .iter()
+            .map(|item| item.name.clone())
+            .collect()
+    }
+
+    #[test]
+    fn filter_class_chip_cycles_and_clears() {
+        let (_dir, mut app) = setup_app();
+        let original_count = app.tree_state.flat_items.len();
+        app.cycle_filter_class(false); // Code
+        assert_eq!(app.tree_state.filter_label(), "[Code]");
+        let names = flat_names(&app);
+        assert!(names.contains(&"file_b.rs".to_string()));
+        assert!(!names.contains(&"file_a.txt".to_string()));
+
+        app.cycle_filter_class(false); // Docs
+        let names = flat_names(&app);
+        assert!(names.contains(&"file_a.txt".to_string()));
+        assert!(!names.contains(&"file_b.rs".to_string()));
+
+        // Back past the first class turns the chip off
+        app.cycle_filter_class(true);
+        app.cycle_filter_class(true);
+        assert!(app.tree_state.filter_class.is_none());
+        assert!(!app.tree_state.is_filtering);
+        assert_eq!(app.tree_state.flat_items.len(), original_count);
+    }
+
+    #[test]
+    fn filter_query_composes_with_chip_and_esc_clears_both() {
+        let (_dir, mut app) = setup_app();
+        let original_count = app.tree_state.flat_items.len();
+        app.cycle_filter_class(false); // Code
+        app.start_filter();
+        // The chip survives starting a text filter
+        assert!(app.tree_state.is_filtering);
+        assert!(flat_names(&app).contains(&"file_b.rs".to_string()));
+
+        app.filter_input_char('a');
+        assert_eq!(app.tree_state.filter_label(), "[Code] + 'a'");
+        assert!(!flat_names(&app).contains(&"file_b.rs".to_string()));
+        app.filter_delete_char();
+        assert!(flat_names(&app).contains(&"file_b.rs".to_string()));
+
+        app.clear_filter();
+        assert!(app.tree_state.filter_class.is_none());
+        assert_eq!(app.tree_state.flat_items.len(), original_count);
+    }
+
+    #[test]
+    fn finder_honors_chip_when_configured() {
+        let (_dir, mut app) = setup_app();
+        app.cycle_filter_class(false); // Code
+        let search = |app: &mut App| {
+            app.open_search();
+            for c in "file".chars() {
+                app.search_input_char(c);
+            }
+            app.search_state
+                .results
+                .iter()
+                .map(|r| r.display.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(search(&mut app).len(), 2);
+
+        app.config.filter.in_finder = Some(true);
+        assert_eq!(search(&mut app), ["file_b.rs"]);
+    }
+
+    #[test]
+    fn accept_filter_keeps_filtered_view() {
+        let (_dir, mut app) = setup_app();
+        app.start_filter();
+        app.filter_input_char('f');
+        let filtered_count = app.tree_state.flat_items.len();
+        app.accept_filter();
+        assert_eq!(app.mode, AppMode::Normal);
+        // Filtered view should persist
+        assert_eq!(app.tree_state.flat_items.len(), filtered_count);
+    }
+
+    #[test]
+    fn filter_backspace_updates_filter() {
+        let (_dir, mut app) = setup_app();
+        let original_count = app.tree_state.flat_items.len();
+        app.start_filter();
+        app.filter_input_char('z');
+        app.filter_input_char('z');
+        app.filter_delete_char();
+        app.filter_delete_char();
+        // Should restore full tree when filter query becomes empty
+        assert!(!app.tree_state.is_filtering);
+        assert_eq!(app.tree_state.flat_items.len(), original_count);
+    }
+
+    #[test]
+    fn filter_case_insensitive() {
+        let (_dir, mut app) = setup_app();
+        app.start_filter();
+        app.filter_input_char('F');
+        app.filter_input_char('I');
+        app.filter_input_char('L');
+        app.filter_input_char('E');
+        // Should match "file_a.txt" and "file_b.rs" despite uppercase query
+        let names: Vec<&str> = app
+            .tree_state
+            .flat_items
+            .iter()
+            .map(|i| i.name.as_str())
+            .collect();
+        assert!(names.contains(&"file_a.txt"));
+        assert!(names.contains(&"file_b.rs"));
+    }
+
+    #[test]
+    fn navigate_to_path_expands_ancestors() {
+        let (dir, mut app) = setup_app();
+        // Create nested structure
+        fs::create_dir_all(dir.path().join("alpha").join("nested")).unwrap();
+        File::create(dir.path().join("alpha").join("nested").join("target.txt")).unwrap();
+        app.tree_state.reload_dir(dir.path());
+
+        let target = dir.path().join("alpha").join("nested").join("target.txt");
+        app.navigate_to_path(&target);
+
+        let selected = &app.tree_state.flat_items[app.tree_state.selected_index];
+        assert_eq!(selected.name, "target.txt");
+    }
+
+    #[test]
+    fn navigate_to_path_stops_at_depth_limit() {
+        let (dir, mut app) = setup_app();
+        let deep = dir.path().join("alpha").join("b").join("c");
+        fs::create_dir_all(&deep).unwrap();
+        File::create(deep.join("target.txt")).unwrap();
+        app.tree_state.max_depth = 2;
+
+        app.navigate_to_path(&deep.join("target.txt"));
+        let selected = &app.tree_state.flat_items[app.tree_state.selected_index];
+        assert_eq!(selected.path, dir.path().join("alpha").join("b"));
+        assert!(app
+            .status_message
+            .as_ref()
+            .unwrap()
+            .0
+            .contains("Depth limit reached"));
+
+        // The directory at the limit doesn't expand, and the path index
+        // stays above it too
+        app.expand_selected();
+        assert!(!app.tree_state.flat_items[app.tree_state.selected_index].is_expanded);
+        let index = app.build_path_index();
+        assert!(index.paths.iter().all(|p| !p.starts_with(&deep)));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_expand_and_preview_their_target() {
+        let (dir, mut app) = setup_app();
+        File::create(dir.path().join("alpha/inner.txt")).unwrap();
+        std::os::unix::fs::symlink("alpha", dir.path().join("link")).unwrap();
+        std::os::unix::fs::symlink("..", dir.path().join("alpha/up")).unwrap();
+        app.tree_state.reload_dir(dir.path());
+
+        let link = dir.path().join("link");
+        app.navigate_to_path(&link);
+        app.update_preview();
+        let preview: Vec<String> = app
+            .preview_state
+            .content_lines
+            .iter()
+            .map(|line| line.to_string())
+            .collect();
+        assert!(preview.contains(&"  Status: valid (directory)".to_string()));
+        app.expand_selected();
+        assert!(app
+            .tree_state
+            .find_index_by_path(&link.join("inner.txt"))
+            .is_some());
+
+        // link/up resolves to the root, an ancestor: refused with a note
+        app.navigate_to_path(&link.join("up"));
+        app.expand_selected();
+        assert!(!app.tree_state.flat_items[app.tree_state.selected_index].is_expanded);
+        assert_eq!(
+            app.status_message.as_ref().unwrap().0,
+            "Not expanding up: it links back to ."
+        );
+    }
+
+    // === Filesystem watcher tests ===
+
+    #[test]
+    fn handle_fs_change_detects_new_file() {
+        let (dir, mut app) = setup_app();
+        let original_count = app.tree_state.flat_items.len();
+        // Create a new file externally
+        File::create(dir.path().join("new_file.txt")).unwrap();
+        // Simulate watcher event
+        app.handle_fs_change(vec![dir.path().join("new_file.txt")]);
+        assert!(app.tree_state.flat_items.len() > original_count);
+        let names: Vec<&str> = app
+            .tree_state
+            .flat_items
+            .iter()
+            .map(|i| i.name.as_str())
+            .collect();
+        assert!(names.contains(&"new_file.txt"));
+    }
+
+    fn names(app: &App) -> Vec<String> {
+        app.tree_state
+            .flat_items
+            .iter()
+            .map(|i| i.name.clone())
+            .collect()
+    }
+
+    /// Stream build-sized output from a shell in `cwd` for three seconds
+    /// of synthetic time, starting at `start`; returns the time reached.
+    fn stream_output(app: &mut App, cwd: &Path, start: Instant, rate: usize) -> Instant {
+        app.terminal_state.started_in = Some(cwd.to_path_buf());
+        let mut now = start;
+        for _ in 0..30 {
+            now += Duration::from_millis(100);
+            if rate > 0 {
+                app.refresh_defer.record_output(now, rate / 10);
+            }
+            app.poll_refresh_defer(now);
+        }
+        now
+    }
+
+    fn expand(app: &mut App, name: &str) {
+        app.tree_state.selected_index = app
+            .tree_state
+            .flat_items
+            .iter()
+            .position(|i| i.name == name)
+            .unwrap();
+        app.expand_selected();
+    }
+
+    #[test]
+    fn busy_terminal_defers_only_its_subtree() {
+        let (dir, mut app) = setup_app();
+        expand(&mut app, "alpha");
+        expand(&mut app, "beta");
+        stream_output(&mut app, &dir.path().join("alpha"), Instant::now(), 20_000);
+        let (msg, _) = app.status_message.as_ref().unwrap();
+        assert_eq!(msg, "Auto-refresh deferred for alpha/ (build running)");
+        assert!(app.deferred_refresh_note().is_some());
+
+        File::create(dir.path().join("alpha/out.o")).unwrap();
+        File::create(dir.path().join("beta/notes.md")).unwrap();
+        app.handle_fs_change(vec![
+            dir.path().join("alpha/out.o"),
+            dir.path().join("beta/notes.md"),
+        ]);
+        let shown = names(&app);
+        assert!(!shown.contains(&"out.o".to_string()));
+        assert!(shown.contains(&"notes.md".to_string()));
+        let alpha =
+            TreeState::find_node_mut_pub(&mut app.tree_state.root, &dir.path().join("alpha"))
+                .unwrap();
+        assert!(alpha.is_stale);
+    }
+
+    #[test]
+    fn quiet_terminal_lifts_deferral_with_one_refresh() {
+        let (dir, mut app) = setup_app();
+        expand(&mut app, "alpha");
+        let alpha = dir.path().join("alpha");
+        let t = stream_output(&mut app, &alpha, Instant::now(), 20_000);
+        for name in ["a.o", "b.o", "c.o"] {
+            File::create(alpha.join(name)).unwrap();
+            app.handle_fs_change(vec![alpha.join(name)]);
+        }
+        assert!(!names(&app).contains(&"a.o".to_string()));
+
+        stream_output(&mut app, &alpha, t, 0);
+        assert!(app.refresh_defer.dir().is_none());
+        let shown = names(&app);
+        for name in ["a.o", "b.o", "c.o"] {
+            assert!(shown.contains(&name.to_string()), "{name} missing");
+        }
+        let (msg, _) = app.status_message.as_ref().unwrap();
+        assert_eq!(msg, "Auto-refresh resumed for alpha/");
+    }
+
+    #[test]
+    fn f5_overrides_a_deferral() {
+        let (dir, mut app) = setup_app();
+        expand(&mut app, "alpha");
+        let alpha = dir.path().join("alpha");
+        stream_output(&mut app, &alpha, Instant::now(), 20_000);
+        File::create(alpha.join("out.o")).unwrap();
+        app.handle_fs_change(vec![alpha.join("out.o")]);
+
+        app.full_refresh();
+        assert!(app.refresh_defer.dir().is_none());
+        assert!(names(&app).contains(&"out.o".to_string()));
+    }
+
+    #[test]
+    fn deferral_can_be_disabled() {
+        let (dir, _) = setup_app();
+        let config = AppConfig {
+            watcher: crate::config::WatcherConfig {
+                defer_during_builds: Some(false),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut app = App::new(dir.path(), config).unwrap();
+        stream_output(&mut app, dir.path(), Instant::now(), 20_000);
+        assert!(app.refresh_defer.dir().is_none());
+    }
+
+    #[test]
+    fn handle_fs_change_preserves_selection() {
+        let (dir, mut app) = setup_app();
+        // Select "file_a.txt"
+        let file_a_idx = app
+            .tree_state
+            .flat_items
+            .iter()
+            .position(|i| i.name == "file_a.txt")
+            .unwrap();
+        app.tree_state.selected_index = file_a_idx;
+
+        // Create a new file externally, trigger refresh
+        File::create(dir.path().join("zzz_newfile.txt")).unwrap();
+        app.handle_fs_change(vec![dir.path().join("zzz_newfile.txt")]);
+
+        // Selection should still point to file_a.txt
+        let selected = &app.tree_state.flat_items[app.tree_state.selected_index];
+        assert_eq!(selected.name, "file_a.txt");
+    }
+
+    #[test]
+    fn handle_fs_change_selection_fallback_on_delete() {
+        let (dir, mut app) = setup_app();
+        // Select "file_a.txt"
+        let file_a_idx = app
+            .tree_state
+            .flat_items
+            .iter()
+            .position(|i| i.name == "file_a.txt")
+            .unwrap();
+        app.tree_state.selected_index = file_a_idx;
+
+        // Delete file_a.txt externally
+        fs::remove_file(dir.path().join("file_a.txt")).unwrap();
+        app.handle_fs_change(vec![dir.path().join("file_a.txt")]);
+
+        // Selection should have moved to a valid index
+        assert!(app.tree_state.selected_index < app.tree_state.flat_items.len());
+    }
+
+    #[test]
+    fn handle_fs_change_preserves_expanded_dirs() {
+        let (dir, mut app) = setup_app();
+        // Expand "alpha" directory
+        let alpha_idx = app
+            .tree_state
+            .flat_items
+            .iter()
+            .position(|i| i.name == "alpha")
+            .unwrap();
+        app.tree_state.selected_index = alpha_idx;
+        app.expand_selected();
+        let count_after_expand = app.tree_state.flat_items.len();
+
+        // Create a file in root, trigger refresh
+        File::create(dir.path().join("extra.txt")).unwrap();
+        app.handle_fs_change(vec![dir.path().join("extra.txt")]);
+
+        // alpha should still be expanded (count increased by 1 for new file)
+        assert!(app.tree_state.flat_items.len() > count_after_expand);
+        let alpha_item = app
+            .tree_state
+            .flat_items
+            .iter()
+            .find(|i| i.name == "alpha")
+            .unwrap();
+        assert!(alpha_item.is_expanded);
+    }
+
+    #[test]
+    fn handle_fs_events_applies_a_rename_in_place() {
+        let (dir, mut app) = setup_app();
+        fs::create_dir(dir.path().join("alpha/inner")).unwrap();
+        File::create(dir.path().join("alpha/inner/deep.txt")).unwrap();
+        expand(&mut app, "alpha");
+        expand(&mut app, "inner");
+        let deep = app
+            .tree_state
+            .find_index_by_path(&dir.path().join("alpha/inner/deep.txt"))
+            .unwrap();
+        app.tree_state.selected_index = deep;
+
+        fs::rename(dir.path().join("alpha"), dir.path().join("omega")).unwrap();
+        File::create(dir.path().join("made.txt")).unwrap();
+        app.handle_fs_events(vec![
+            FsEvent::Renamed {
+                from: dir.path().join("alpha"),
+                to: dir.path().join("omega"),
+            },
+            FsEvent::Created(dir.path().join("made.txt")),
+        ]);
+        let selected = &app.tree_state.flat_items[app.tree_state.selected_index];
+        assert_eq!(selected.path, dir.path().join("omega/inner/deep.txt"));
+        let inner = app
+            .tree_state
+            .find_index_by_path(&dir.path().join("omega/inner"))
+            .unwrap();
+        assert!(app.tree_state.flat_items[inner].is_expanded);
+        assert!(names(&app).contains(&"made.txt".to_string()));
+        assert!(!names(&app).contains(&"alpha".to_string()));
+    }
+
+    #[test]
+    fn queued_fs_events_are_applied_together_each_frame() {
+        let (dir, mut app) = setup_app();
+        File::create(dir.path().join("one.txt")).unwrap();
+        File::create(dir.path().join("two.txt")).unwrap();
+        app.queue_fs_events(vec![FsEvent::Created(dir.path().join("one.txt"))]);
+        app.queue_fs_events(vec![
+            FsEvent::Created(dir.path().join("two.txt")),
+            FsEvent::Changed(dir.path().join("one.txt")),
+        ]);
+        assert!(!names(&app).contains(&"one.txt".to_string()));
+        app.poll_fs_events(Instant::now());
+        assert!(app.fs_pending.is_empty());
+        let shown = names(&app);
+        assert!(shown.contains(&"one.txt".to_string()));
+        assert!(shown.contains(&"two.txt".to_string()));
+    }
+
+    #[test]
+    fn changes_in_collapsed_directories_only_mark_them_stale() {
+        let (dir, mut app) = setup_app();
+        expand(&mut app, "alpha");
+        app.collapse_selected();
+        File::create(dir.path().join("alpha/late.txt")).unwrap();
+        app.handle_fs_change(vec![dir.path().join("alpha/late.txt")]);
+        let alpha =
+            TreeState::find_node_mut_pub(&mut app.tree_state.root, &dir.path().join("alpha"))
+                .unwrap();
+        assert!(alpha.is_stale);
+        assert!(alpha.children.as_ref().unwrap().is_empty());
+
+        expand(&mut app, "alpha");
+        assert!(names(&app).contains(&"late.txt".to_string()));
+    }
+
+    #[test]
+    fn changes_across_many_directories_refresh_once_they_settle() {
+        let (dir, mut app) = setup_app();
+        let count = MAX_DIRS_RELOADED_PER_FRAME + 1;
+        for i in 0..count {
+            fs::create_dir(dir.path().join(format!("d{i:02}"))).unwrap();
+        }
+        app.full_refresh();
+        for i in 0..count {
+            expand(&mut app, &format!("d{i:02}"));
+        }
+        let changed: Vec<PathBuf> = (0..count)
+            .map(|i| dir.path().join(format!("d{i:02}/out.o")))
+            .collect();
+        for path in &changed {
+            File::create(path).unwrap();
+        }
+        let start = Instant::now();
+        app.handle_fs_change(changed);
+        assert!(app.fs_storm.is_some());
+        assert!(app.tree_state.root.is_stale);
+        assert!(!names(&app).contains(&"out.o".to_string()));
+
+        // Not yet quiet for a debounce interval
+        app.poll_fs_events(start);
+        assert!(app.fs_storm.is_some());
+        app.poll_fs_events(start + Duration::from_secs(1));
+        assert!(app.fs_storm.is_none());
+        let shown = names(&app);
+        assert_eq!(shown.iter().filter(|n| *n == "out.o").count(), count);
+    }
+
+    #[test]
+    fn handle_fs_change_updates_search_cache_in_place() {
+        let (dir, mut app) = setup_app();
+        app.open_search();
+        app.close_search();
+        assert!(app.search_state.cached_paths.is_some());
+
+        // Trigger fs change in Normal mode
+        File::create(dir.path().join("cache_buster.txt")).unwrap();
+        fs::remove_file(dir.path().join("file_a.txt")).unwrap();
+        app.handle_fs_change(vec![
+            dir.path().join("cache_buster.txt"),
+            dir.path().join("file_a.txt"),
+        ]);
+        let paths = app.search_state.cached_paths.as_ref().unwrap();
+        assert!(paths.contains(&dir.path().join("cache_buster.txt")));
+        assert!(!paths.contains(&dir.path().join("file_a.txt")));
+
+        // A flood collapsed to the root re-indexes
+        app.handle_fs_change(vec![dir.path().to_path_buf()]);
+        assert!(app.search_state.cached_paths.is_none());
+    }
+
+    #[tokio::test]
+    async fn background_index_streams_into_the_finder() {
+        let (dir, mut app) = setup_app();
+        fs::create_dir(dir.path().join("node_modules")).unwrap();
+        fs::write(dir.path().join("node_modules/dep.js"), "").unwrap();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        app.poll_path_index(&tx);
+        let job = app.search_state.indexing.as_ref().unwrap().job;
+        // Opening the finder now doesn't index on the spot
+        app.open_search();
+        assert_eq!(app.search_state.cached_paths.as_deref(), Some(&[][..]));
+        app.search_input_char('f');
+
+        while app.search_state.indexing.is_some() {
+            match rx.recv().await {
+                Some(crate::event::Event::IndexBatch(batch)) => app.handle_index_batch(batch),
+                Some(_) => {}
+                None => panic!("index stopped without a last batch"),
+            }
+        }
+        let paths = app.search_state.cached_paths.as_ref().unwrap();
+        assert!(paths.contains(&dir.path().join("file_a.txt")));
+        assert!(!paths
+            .iter()
+            .any(|p| p.starts_with(dir.path().join("node_modules"))));
+        assert!(!app.search_state.results.is_empty());
+
+        // Nothing more to do until something goes stale
+        app.poll_path_index(&tx);
+        assert!(app.search_state.indexing.is_none());
+        // A batch from a superseded job is dropped
+        app.handle_index_batch(IndexBatch {
+            job,
+            root: dir.path().to_path_buf(),
+            paths: vec![dir.path().join("ghost")],
+            seen: 1,
+            coverage: None,
+        });
+        let paths = app.search_state.cached_paths.as_ref().unwrap();
+        assert!(!paths.contains(&dir.path().join("ghost")));
+    }
+
+    #[test]
+    fn fs_change_skipped_during_search_mode() {
+        let (_dir, mut app) = setup_app();
+        // Open search — builds path cache and sets mode to Search
+        app.open_search();
+        assert!(app.search_state.cached_paths.is_some());
+        assert_eq!(app.mode, AppMode::Search);
+
+        // While in Search mode, fs change should be silently ignored
+        app.handle_fs_change(vec![app.tree_state.root.path.clone()]);
+        // Cache must NOT be invalidated while searching
+        assert!(
+            app.search_state.cached_paths.is_some(),
+            "search cache should survive fs events during search mode"
+        );
+    }
+
+    #[test]
+    fn fs_change_skipped_during_filter_mode() {
+        let (_dir, mut app) = setup_app();
+        app.start_filter();
+        app.filter_input_char('f');
+        let filtered_count = app.tree_state.flat_items.len();
+        assert_eq!(app.mode, AppMode::Filter);
+
+        // While in Filter mode, fs change should be silently ignored
+        app.handle_fs_change(vec![app.tree_state.root.path.clone()]);
+        // flat_items should still be the filtered set, not the full tree
+        assert_eq!(
+            app.tree_state.flat_items.len(),
+            filtered_count,
+            "filtered view should survive fs events during filter mode"
+        );
+    }
+
+    #[test]
+    fn fs_change_works_after_closing_search() {
+        let (dir, mut app) = setup_app();
+        // Open and close search to return to Normal mode
+        app.open_search();
+        app.close_search();
+        assert_eq!(app.mode, AppMode::Normal);
+
+        // Now fs change should process normally
+        let original_count = app.tree_state.flat_items.len();
+        File::create(dir.path().join("new_file.txt")).unwrap();
+        app.handle_fs_change(vec![dir.path().join("new_file.txt")]);
+        assert!(app.tree_state.flat_items.len() > original_count);
+    }
+
+    #[test]
+    fn full_refresh_reloads_tree() {
+        let (dir, mut app) = setup_app();
+        let original_count = app.tree_state.flat_items.len();
+        // Create a file externally
+        File::create(dir.path().join("f5_file.txt")).unwrap();
+        app.full_refresh();
+        assert!(app.tree_state.flat_items.len() > original_count);
+        assert!(app.status_message.is_some());
+    }
+
+    #[test]
+    fn changes_and_refresh_ask_for_git_status() {
+        let (dir, mut app) = setup_app();
+        let root = dir.path().to_path_buf();
+        let t0 = Instant::now();
+        let later = t0 + git_status::MIN_INTERVAL;
+        assert!(app.git_status.start(&root, t0));
+        app.handle_git_status_complete(GitStatusResult {
+            root: root.clone(),
+            statuses: Some(git_status::parse_porcelain(b"?? file_a.txt\0", "", &root)),
+        });
+        assert_eq!(
+            app.git_status.statuses.get(&root.join("file_a.txt")),
+            Some(git_status::GitStatus::Untracked)
+        );
+        assert!(!app.git_status.start(&root, later));
+
+        app.handle_fs_change(vec![root.join("file_a.txt")]);
+        assert!(app.git_status.start(&root, later));
+        app.handle_git_status_complete(GitStatusResult {
+            root: root.clone(),
+            statuses: Some(Default::default()),
+        });
+        app.full_refresh();
+        assert!(app
+            .git_status
+            .start(&root, later + git_status::MIN_INTERVAL));
+
+        let mut config = crate::config::AppConfig::default();
+        config.tree.git_status = Some(false);
+        let mut off = App::new(dir.path(), config).unwrap();
+        assert!(!off.git_status.start(&root, t0));
+    }
+
+    #[test]
+    fn toggle_watcher_flips_state() {
+        let (_dir, mut app) = setup_app();
+        assert!(app.watcher_active); // default on
+        let result = app.toggle_watcher();
+        assert!(!result);
+        assert!(!app.watcher_active);
+        let result2 = app.toggle_watcher();
+        assert!(result2);
+        assert!(app.watcher_active);
+    }
+
+    #[test]
+    fn toggle_watcher_sets_status_message() {
+        let (_dir, mut app) = setup_app();
+        app.toggle_watcher();
+        let (msg, _) = app.status_message.as_ref().unwrap();
+        assert!(msg.contains("paused") || msg.contains("⏸"));
+        app.toggle_watcher();
+        let (msg, _) = app.status_message.as_ref().unwrap();
+        assert!(msg.contains("resumed") || msg.contains("👁"));
+    }
+
+    /// Fails with an inotify-limit error `failures` times, then starts
+    /// real watchers; records the modes it was asked for.
+    struct FlakyFactory {
+        failures: std::sync::atomic::AtomicUsize,
+        modes: Arc<std::sync::Mutex<Vec<WatchMode>>>,
+    }
+
+    impl crate::fs::watcher::WatcherFactory for FlakyFactory {
+        fn start(
+            &self,
+            root: &Path,
+            config: &AppConfig,
+            mode: WatchMode,
+            event_tx: mpsc::UnboundedSender<crate::event::Event>,
+        ) -> std::result::Result<crate::fs::watcher::FsWatcher, crate::error::WatcherError>
+        {
+            self.modes.lock().unwrap().push(mode);
+            let left = self.failures.load(Ordering::SeqCst);
+            if left > 0 {
+                self.failures.store(left - 1, Ordering::SeqCst);
+                return Err(crate::error::WatcherError::InotifyLimit {
+                    current: Some(8192),
+                    needed: Some(20_000),
+                });
+            }
+            SystemWatcherFactory.start(root, config, mode, event_tx)
+        }
+    }
+
+    fn flaky_watcher(app: &mut App, failures: usize) -> Arc<std::sync::Mutex<Vec<WatchMode>>> {
+        let modes = Arc::new(std::sync::Mutex::new(Vec::new()));
+        app.watcher = WatcherSupervisor::new(Box::new(FlakyFactory {
+            failures: std::sync::atomic::AtomicUsize::new(failures),
+            modes: Arc::clone(&modes),
+        }));
+        modes
+    }
+
+    #[tokio::test]
+    async fn failed_watcher_can_be_retried() {
+        let (_dir, mut app) = setup_app();
+        let modes = flaky_watcher(&mut app, 1);
+        let (tx, _rx) = mpsc::unbounded_channel();
+
+        app.start_watcher(&tx);
+        assert!(!app.watcher_active);
+        assert!(!app.watcher.is_running());
+        let (msg, _) = app.status_message.as_ref().unwrap();
+        assert!(msg.contains("inotify watch limit reached"), "{msg}");
+        assert!(msg.contains("W for details"));
+
+        app.open_watcher_details();
+        match &app.mode {
+            AppMode::Dialog(DialogKind::WatcherDetails { lines }) => {
+                assert!(lines.iter().any(|l| l == "Current limit: 8192"));
+                assert!(lines
+                    .iter()
+                    .any(|l| l.contains("sudo sysctl fs.inotify.max_user_watches=524288")));
+            }
+            other => panic!("expected watcher details, got {:?}", other),
+        }
+
+        app.retry_watcher(WatchMode::Native, &tx);
+        assert_eq!(app.mode, AppMode::Normal);
+        assert!(app.watcher.is_running());
+        assert!(app.watcher.error.is_none());
+        assert!(app.watcher_active);
+        assert_eq!(*modes.lock().unwrap(), vec![WatchMode::Native; 2]);
+    }
+
+    /// Wait for the watcher's next batch of events and apply it.
+    async fn apply_next_fs_events(
+        app: &mut App,
+        rx: &mut mpsc::UnboundedReceiver<crate::event::Event>,
+    ) {
+        loop {
+            let event = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+                .await
+                .expect("no watcher event")
+                .expect("watcher gone");
+            if let crate::event::Event::FsEvents(events) = event {
+                return app.handle_fs_events(events);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn expanded_directories_get_their_own_watches() {
+        let (dir, mut app) = setup_app();
+        app.config.watcher.recursive = Some(false);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        app.start_watcher(&tx);
+        assert_eq!(app.watcher.watch_count(), Some(1));
+
+        expand(&mut app, "alpha");
+        assert_eq!(app.watcher.watch_count(), Some(2));
+        File::create(dir.path().join("alpha/outside.txt")).unwrap();
+        apply_next_fs_events(&mut app, &mut rx).await;
+        assert!(app
+            .tree_state
+            .find_index_by_path(&dir.path().join("alpha/outside.txt"))
+            .is_some());
+
+        app.tree_state.selected_index = app
+            .tree_state
+            .find_index_by_path(&dir.path().join("alpha"))
+            .unwrap();
+        app.collapse_selected();
+        assert_eq!(app.watcher.watch_count(), Some(1));
+    }
+
+    #[tokio::test]
+    async fn failed_watcher_can_fall_back_to_polling() {
+        let (_dir, mut app) = setup_app();
+        let modes = flaky_watcher(&mut app, 1);
+        let (tx, _rx) = mpsc::unbounded_channel();
+
+        app.start_watcher(&tx);
+        app.open_watcher_details();
+        app.retry_watcher(WatchMode::Poll, &tx);
+        assert!(app.watcher.is_running());
+        assert_eq!(app.watcher.mode, WatchMode::Poll);
+        assert_eq!(
+            *modes.lock().unwrap(),
+            vec![WatchMode::Native, WatchMode::Poll]
+        );
+        let (msg, _) = app.status_message.as_ref().unwrap();
+        assert!(msg.contains("polling"));
+    }
+
+    #[tokio::test]
+    async fn watcher_details_without_a_problem_only_sets_status() {
+        let (_dir, mut app) = setup_app();
+        flaky_watcher(&mut app, 0);
+        let (tx, _rx) = mpsc::unbounded_channel();
+        app.start_watcher(&tx);
+        app.open_watcher_details();
+        assert_eq!(app.mode, AppMode::Normal);
+        let (msg, _) = app.status_message.as_ref().unwrap();
+        assert!(msg.contains("running normally"));
+    }
+
+    #[tokio::test]
+    async fn watcher_state_tells_paused_from_unavailable() {
+        let (_dir, mut app) = setup_app();
+        assert_eq!(app.watcher_state(), WatcherState::Live);
+        app.toggle_watcher();
+        assert_eq!(app.watcher_state(), WatcherState::Paused);
+        app.toggle_watcher();
+
+        flaky_watcher(&mut app, 1);
+        let (tx, _rx) = mpsc::unbounded_channel();
+        app.start_watcher(&tx);
+        assert_eq!(app.watcher_state(), WatcherState::Unavailable);
+        assert!(app
+            .watcher_state_note(Instant::now())
+            .starts_with("✕ Watcher unavailable (W for details)"));
+    }
+
+    #[test]
+    fn watcher_state_shows_a_flood() {
+        let (dir, mut app) = setup_app();
+        stream_output(&mut app, &dir.path().join("alpha"), Instant::now(), 20_000);
+        assert_eq!(app.watcher_state(), WatcherState::Flooded);
+        let note = app.watcher_state_note(Instant::now());
+        assert!(
+            note.starts_with("⚠ auto-refresh deferred for alpha/"),
+            "{}",
+            note
+        );
+    }
+
+    #[test]
+    fn watcher_state_note_gives_the_time_since_the_last_refresh() {
+        let (dir, mut app) = setup_app();
+        let later = app.last_refresh + Duration::from_secs(3 * 3_600);
+        assert_eq!(
+            app.watcher_state_note(later),
+            "👁 Watcher running normally · last refresh 3h ago"
+        );
+
+        File::create(dir.path().join("new.txt")).unwrap();
+        let before = app.last_refresh;
+        app.handle_fs_change(vec![dir.path().join("new.txt")]);
+        assert!(app.last_refresh > before);
+        assert!(app
+            .watcher_state_note(app.last_refresh)
+            .ends_with("· last refresh just now"));
+    }
+
+    #[test]
+    fn handle_fs_change_preserves_sort_order() {
+        let (dir, mut app) = setup_app();
+
+        // Verify initial sort order: dirs first (alpha, beta), then files (file_a.txt, file_b.rs)
+        // flat_items[0] = root, [1] = alpha, [2] = beta, [3] = file_a.txt, [4] = file_b.rs
+        assert_eq!(app.tree_state.flat_items[1].name, "alpha");
+        assert_eq!(app.tree_state.flat_items[2].name, "beta");
+        assert_eq!(app.tree_state.flat_items[3].name, "file_a.txt");
+        assert_eq!(app.tree_state.flat_items[4].name, "file_b.rs");
+
+        // Create a new file externally to trigger a change
+        File::create(dir.path().join("aaa_new.txt")).unwrap();
+        fs::create_dir(dir.path().join("gamma")).unwrap();
+        app.handle_fs_change(vec![
+            dir.path().join("aaa_new.txt"),
+            dir.path().join("gamma"),
+        ]);
+
+        // After fs change, dirs must still appear first, alphabetically sorted
+        let names: Vec<&str> = app
+            .tree_state
+            .flat_items
+            .iter()
+            .skip(1) // skip root
+            .map(|item| item.name.as_str())
+            .collect();
+
+        // Find the boundary between dirs and files
+        let dir_count = names
+            .iter()
+            .take_while(|n| {
+                app.tree_state
+                    .flat_items
+                    .iter()
+                    .find(|i| i.name == **n)
+                    .map(|i| i.node_type == crate::fs::tree::NodeType::Directory)
+                    .unwrap_or(false)
+            })
+            .count();
+
+        // All directories should come first
+        assert!(
+            dir_count >= 3,
+            "Expected at least 3 dirs (alpha, beta, gamma), got {dir_count}"
+        );
+
+        // Directories should be alphabetically sorted
+        let dir_names: Vec<&str> = names[..dir_count].to_vec();
+        assert_eq!(dir_names, vec!["alpha", "beta", "gamma"]);
+
+        // Files should be alphabetically sorted
+        let file_names: Vec<&str> = names[dir_count..].to_vec();
+        let mut expected_files = file_names.clone();
+        expected_files.sort_by(|a, b| a.to_lowercase().cmp(&b.to_lowercase()));
+        assert_eq!(
+            file_names, expected_files,
+            "Files should be alphabetically sorted"
+        );
+    }
+
+    #[test]
+    fn navigate_to_path_preserves_sort_order() {
+        let (dir, mut app) = setup_app();
+
+        // Create nested structure: alpha/z_file.txt, alpha/a_file.txt, alpha/nested_dir/
+        fs::create_dir_all(dir.path().join("alpha").join("nested_dir")).unwrap();
+        File::create(dir.path().join("alpha").join("z_file.txt")).unwrap();
+        File::create(dir.path().join("alpha").join("a_file.txt")).unwrap();
+
+        // Navigate to a nested file — this forces alpha to expand
+        let target = dir.path().join("alpha").join("a_file.txt");
+        app.navigate_to_path(&target);
+
+        // Find alpha's children in the flat list
+        let alpha_children: Vec<&str> = app
+            .tree_state
+            .flat_items
+            .iter()
+            .filter(|i| i.depth == 2) // alpha's children are at depth 2
+            .map(|i| i.name.as_str())
+            .collect();
+
+        // nested_dir (directory) should come first, then a_file, z_file (alphabetical)
+        assert!(
+            !alpha_children.is_empty(),
+            "Alpha should have children after navigate_to_path"
+        );
+        assert_eq!(
+            alpha_children[0], "nested_dir",
+            "Directory should come first"
+        );
+    }
+
+    #[test]
+    fn preview_scroll_with_content_and_area() {
+        let (dir, mut app) = setup_app();
+        // Write a file with many lines
+        let content: String = (0..200).map(|i| format!("line {}\n", i)).collect();
+        std::fs::write(dir.path().join("file_a.txt"), &content).unwrap();
+
+        // Simulate a real terminal preview area (height=30, visible=28)
+        app.preview_area = Rect::new(40, 0, 80, 30);
+
+        // Select file_a.txt
+        app.tree_state.selected_index = 3;
+        app.update_preview();
+
+        let content_lines_len = app.preview_state.content_lines.len();
+        let visible_height = app.preview_area.height.saturating_sub(2) as usize;
+        let expected_max = content_lines_len.saturating_sub(visible_height);
+
+        // Scroll down 50 times, simulating render cycle each time
+        for _ in 0..50 {
+            app.preview_scroll_down();
+            app.update_preview();
+            app.clamp_preview_scroll();
+        }
+
+        assert_eq!(
+            app.preview_state.scroll_offset,
+            50.min(expected_max),
+            "After scrolling 50 times, offset should be 50 (or max if less)"
+        );
+    }
+
+    #[test]
+    fn preview_scroll_preserved_after_fs_change() {
+        let (dir, mut app) = setup_app();
+        // Write a file with many lines
+        let content: String = (0..200).map(|i| format!("line {}\n", i)).collect();
+        std::fs::write(dir.path().join("file_a.txt"), &content).unwrap();
+
+        // Simulate a real terminal preview area
+        app.preview_area = Rect::new(40, 0, 80, 30);
+
+        // Select file_a.txt and update preview
+        app.tree_state.selected_index = 3;
+        app.update_preview();
+
+        // Scroll down to position 25
+        for _ in 0..25 {
+            app.preview_scroll_down();
+        }
+        assert_eq!(app.preview_state.scroll_offset, 25);
+
+        // Simulate a file watcher event (unrelated file change)
+        app.handle_fs_change(vec![dir.path().join("file_b.rs")]);
+
+        // The next render cycle calls update_preview + clamp
+        app.update_preview();
+        app.clamp_preview_scroll();
+
+        // Scroll offset should be preserved since we're still viewing the same file
+        assert_eq!(
+            app.preview_state.scroll_offset, 25,
+            "Scroll offset should be preserved after FS change event for same file"
+        );
+    }
+
+    #[test]
+    fn preview_scroll_resets_on_different_file() {
+        let (dir, mut app) = setup_app();
+        let content: String = (0..200).map(|i| format!("line {}\n", i)).collect();
+        std::fs::write(dir.path().join("file_a.txt"), &content).unwrap();
+        std::fs::write(dir.path().join("file_b.rs"), "fn main() {}\n").unwrap();
+
+        app.preview_area = Rect::new(40, 0, 80, 30);
+        app.tree_state.selected_index = 3; // file_a.txt
+        app.update_preview();
+
+        // Scroll down
+        for _ in 0..25 {
+            app.preview_scroll_down();
+        }
+        assert_eq!(app.preview_state.scroll_offset, 25);
+
+        // Switch to a different file
+        app.tree_state.selected_index = 4; // file_b.rs
+        app.last_previewed_index = None;
+        app.update_preview();
+
+        // Scroll should reset to 0 for different file
+        assert_eq!(
+            app.preview_state.scroll_offset, 0,
+            "Scroll should reset when switching to a different file"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "editor")]
+    fn preview_tab_width_cycles_per_file() {
+        let (dir, mut app) = setup_app();
+        let mut content = String::from("\tx\n");
+        content.extend((0..100).map(|i| format!("line {}\n", i)));
+        std::fs::write(dir.path().join("file_a.txt"), &content).unwrap();
+        std::fs::write(dir.path().join("file_b.rs"), "\tfn main() {}\n").unwrap();
+        app.preview_area = Rect::new(40, 0, 80, 30);
+
+        let code = |app: &App| -> String {
+            app.preview_state.content_lines[0].spans[1..]
+                .iter()
+                .map(|s| s.content.as_ref())
+                .collect()
+        };
+
+        app.tree_state.selected_index = 3; // file_a.txt
+        app.update_preview();
+        assert_eq!(app.preview_tab_width(), 4);
+        assert_eq!(code(&app), "    x");
+        for _ in 0..10 {
+            app.preview_scroll_down();
+        }
+
+        app.cycle_preview_tab_width();
+        assert_eq!(app.preview_state.tab_width, Some(8));
+        assert_eq!(app.preview_state.scroll_offset, 10);
+        app.preview_jump_top();
+        assert_eq!(code(&app), format!("{}x", " ".repeat(8)));
+        app.cycle_preview_tab_width();
+        assert_eq!(code(&app), "  x");
+
+        // Watcher reloads keep the override; editing uses it
+        app.last_previewed_index = None;
+        app.update_preview();
+        assert_eq!(app.preview_tab_width(), 2);
+        app.focused_panel = FocusedPanel::Preview;
+        assert!(app.enter_edit_mode(None));
+        assert_eq!(app.editor_state.as_ref().unwrap().tab_width, 2);
+        assert_eq!(app.editor_state.as_ref().unwrap().buffer[0], "\tx");
+        app.exit_edit_mode();
+
+        // Another file starts from the configured width
+        app.tree_state.selected_index = 4; // file_b.rs
+        app.last_previewed_index = None;
+        app.update_preview();
+        assert_eq!(app.preview_state.tab_width, None);
+        assert_eq!(app.preview_tab_width(), 4);
+    }
+
+    #[test]
+    fn preview_reports_missing_newline_without_touching_the_file() {
+        let (dir, mut app) = setup_app();
+        let path = dir.path().join("file_b.rs");
+        fs::write(&path, "fn x() {}  ").unwrap();
+        app.tree_state.selected_index = 4; // file_b.rs
+        app.last_previewed_index = None;
+        app.update_preview();
+        assert_eq!(app.preview_state.final_newline, Some(FinalNewline::Missing));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "fn x() {}  ");
+
+        app.tree_state.selected_index = 1; // alpha/
+        app.update_preview();
+        assert_eq!(app.preview_state.final_newline, None);
+    }
+
+    #[test]
+    #[cfg(feature = "editor")]
+    fn save_applies_editor_whitespace_options() {
+        let (dir, mut app) = setup_app();
+        let path = dir.path().join("file_b.rs");
+        fs::write(&path, "fn x() {}  ").unwrap();
+        app.tree_state.selected_index = 4; // file_b.rs
+        app.update_preview();
+        app.focused_panel = FocusedPanel::Preview;
+        assert!(app.enter_edit_mode(None));
+
+        // Off by default: saved as is
+        app.save_editor_buffer().unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "fn x() {}  ");
+
+        app.config.editor.trim_trailing_whitespace = Some(true);
+        app.config.editor.ensure_final_newline = Some(true);
+        app.save_editor_buffer().unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "fn x() {}\n");
+
+        let editor = app.editor_state.as_mut().unwrap();
+        editor.undo();
+        assert_eq!(editor.buffer, vec!["fn x() {}  "]);
+        assert!(editor.modified);
+    }
+
+    #[test]
+    fn path_style_cycles_and_applies_to_search_results() {
+        let (_dir, mut app) = setup_app();
+        let root = app.tree_state.root.path.clone();
+        let alpha = root.join("alpha");
+        assert_eq!(app.path_style, PathStyle::Relative);
+        assert_eq!(app.show_path(&alpha), "alpha");
+
+        app.cycle_path_style();
+        assert_eq!(app.path_style, PathStyle::Absolute);
+        assert_eq!(app.status_message.as_ref().unwrap().0, "Paths: Absolute");
+        assert_eq!(app.show_path(&alpha), alpha.display().to_string());
+
+        app.search_state.results = vec![SearchResult {
+            path: alpha.clone(),
+            display: "alpha".to_string(),
+            score: 0,
+            match_indices: Vec::new(),
+        }];
+        app.search_confirm();
+        let state = app.search_action_state.as_ref().unwrap();
+        assert_eq!(state.display, alpha.display().to_string());
+
+        app.cycle_path_style();
+        app.cycle_path_style();
+        assert_eq!(app.path_style, PathStyle::Relative);
+        assert_eq!(app.show_path(&root), ".");
+    }
+
+    #[test]
+    fn window_title_follows_selection_and_config() {
+        let (_dir, mut app) = setup_app();
+        let alpha = app.tree_state.root.path.join("alpha");
+        app.config.general.title_template = Some("{mode}: {path}".to_string());
+        app.tree_state.selected_index = 1; // alpha
+        let now = Instant::now();
+        let expected = format!(
+            "normal: {}",
+            path_display::home_relative(&alpha, &path_display::homes())
+        );
+        assert_eq!(app.window_title().as_deref(), Some(expected.as_str()));
+        assert_eq!(app.poll_title(now), Some(expected));
+
+        // Changes wait for the selection to settle
+        app.tree_state.selected_index = 2; // beta
+        assert_eq!(app.poll_title(now), None);
+        let settled = app.poll_title(now + title::DEBOUNCE).unwrap();
+        assert!(settled.ends_with("beta"));
+
+        app.config.general.set_title = Some(false);
+        assert_eq!(app.window_title(), None);
+        assert_eq!(app.poll_title(now + title::DEBOUNCE * 3), None);
+    }
+
+    #[test]
+    fn external_edit_takes_files_and_reloads_after() {
+        let (dir, mut app) = setup_app();
+        app.tree_state.selected_index = 1; // alpha
+        app.request_external_edit();
+        assert_eq!(app.external_edit, None);
+        assert_eq!(app.status_message.as_ref().unwrap().0, "Not a file: alpha");
 
-    /// Handle an async directory scan completion.
-    ///
-    /// Installs the snapshot into the tree node and loads the first page.
-    #[allow(dead_code)]
-    pub fn handle_dir_scan_complete(
-        &mut self,
-        path: &std::path::Path,
-        mut snapshot: crate::fs::tree::DirSnapshot,
-    ) {
-        let sort_by = self.tree_state.sort_by.clone();
-        let dirs_first = self.tree_state.dirs_first;
-        let page_size = self.tree_state.page_size;
+        app.tree_state.selected_index = 3; // file_a.txt
+        app.update_preview();
+        app.request_external_edit();
+        let path = app.external_edit.take().unwrap();
+        assert_eq!(path, dir.path().join("file_a.txt"));
+
+        // The watcher was paused: the finish picks up the change itself
+        fs::write(&path, "edited").unwrap();
+        app.finish_external_edit(&path, Ok(()));
+        assert_eq!(app.status_message.as_ref().unwrap().0, "Edited: file_a.txt");
+        assert_eq!(app.last_previewed_index, None);
+        let node = TreeState::find_node_mut_pub(&mut app.tree_state.root, &path).unwrap();
+        assert_eq!(node.meta.size, 6);
+
+        app.finish_external_edit(&path, Err("Editor `nope` not found".to_string()));
+        assert_eq!(
+            app.status_message.as_ref().unwrap().0,
+            "Editor `nope` not found"
+        );
+    }
 
-        if let Some(node) =
-            crate::fs::tree::TreeState::find_node_mut_pub(&mut self.tree_state.root, path)
-        {
-            // Sort the snapshot with current settings
-            snapshot.sort(&sort_by, dirs_first);
+    #[test]
+    #[cfg(feature = "editor")]
+    fn edit_mode_restores_the_last_position_clamped() {
+        let (dir, mut app) = setup_app();
+        let path = dir.path().join("file_b.rs");
+        let long: String = (0..500).map(|i| format!("line {}\n", i)).collect();
+        fs::write(&path, &long).unwrap();
+        app.tree_state.selected_index = 4; // file_b.rs
+        app.update_preview();
+        app.focused_panel = FocusedPanel::Preview;
+        assert!(app.enter_edit_mode(None));
+        assert!(app
+            .status_message
+            .as_ref()
+            .is_none_or(|(msg, _)| !msg.starts_with("Restored")));
+        app.editor_state
+            .as_mut()
+            .unwrap()
+            .set_cursor_position(411, 4);
+        app.exit_edit_mode();
+
+        assert!(app.enter_edit_mode(None));
+        let editor = app.editor_state.as_ref().unwrap();
+        assert_eq!((editor.cursor_line, editor.cursor_col), (411, 4));
+        assert!(editor.scroll_offset <= 411);
+        assert!(411 < editor.scroll_offset + editor.visible_height);
+        assert_eq!(
+            app.status_message.as_ref().unwrap().0,
+            "Restored position (line 412)"
+        );
+        app.exit_edit_mode();
 
-            let total = snapshot.len();
-            node.total_child_count = Some(total);
+        // Truncated since: the position is clamped to what is left
+        fs::write(&path, "short\nfile").unwrap();
+        assert!(app.enter_edit_mode(None));
+        let editor = app.editor_state.as_ref().unwrap();
+        assert_eq!((editor.cursor_line, editor.cursor_col), (1, 4));
+        assert!(editor.scroll_offset <= 1);
 
-            if total <= page_size {
-                // Small enough → just load all (no snapshot needed)
-                let _ = node.load_children_paged_with_sort(page_size, &sort_by, dirs_first);
-            } else {
-                // Install snapshot and load first page
-                let page_entries = snapshot.page(0, page_size);
-                let children = crate::fs::tree::TreeNode::load_nodes_from_snapshot(
-                    page_entries,
-                    &node.path,
-                    node.depth + 1,
-                );
-                let loaded = children.len();
-                node.children = Some(children);
-                node.loaded_child_count = loaded;
-                node.loaded_offset = loaded;
-                node.has_more_children = loaded < total;
-                node.snapshot = Some(snapshot);
-            }
+        // A jump to a line takes precedence over the remembered position
+        app.exit_edit_mode();
+        assert!(app.enter_edit_mode(Some(0)));
+        assert_eq!(app.editor_state.as_ref().unwrap().cursor_line, 0);
+    }
 
-            TreeState::sort_children_of_pub(node, &sort_by, dirs_first);
-            node.is_expanded = true;
-            self.tree_state.flatten();
-        }
+    /// `alpha/numbers.txt` with lines "line 1" ... "line 100", and a
+    /// preview 10 lines high.
+    fn setup_numbered_file() -> (TempDir, App, PathBuf) {
+        let (dir, mut app) = setup_app();
+        let path = dir.path().join("alpha").join("numbers.txt");
+        let body: String = (1..=100).map(|n| format!("line {}\n", n)).collect();
+        fs::write(&path, body).unwrap();
+        app.preview_area = Rect::new(0, 0, 80, 12);
+        (dir, app, path)
     }
 
-    /// Handle an async child count completion.
-    ///
-    /// Updates the node's total_child_count cache for badge display.
-    #[allow(dead_code)]
-    pub fn handle_dir_count_complete(&mut self, path: &std::path::Path, count: usize) {
-        if let Some(node) =
-            crate::fs::tree::TreeState::find_node_mut_pub(&mut self.tree_state.root, path)
-        {
-            node.total_child_count = Some(count);
-        }
-        // No flatten needed — badge display reads from TreeNode directly
+    #[test]
+    fn navigate_to_location_centers_and_flashes_line() {
+        let (_dir, mut app, path) = setup_numbered_file();
+        app.navigate_to_location(&path, 50);
+        assert_eq!(app.selected_file_path(), Some(path.clone()));
+        assert_eq!(app.preview_state.scroll_offset, 44);
+        let (row, step) = app.line_flash_step().unwrap();
+        assert_eq!(row, 49);
+        assert_eq!(step, FlashStep::Strong);
+
+        // Near the top the view can't center
+        app.navigate_to_location(&path, 3);
+        assert_eq!(app.preview_state.scroll_offset, 0);
+        assert_eq!(app.line_flash_step().unwrap().0, 2);
     }
 
-    /// Handle an async directory summary update.
-    ///
-    /// Updates the preview panel with directory statistics.
-    #[allow(dead_code)]
-    pub fn handle_dir_summary_update(
-        &mut self,
-        path: &std::path::Path,
-        files: u64,
-        dirs: u64,
-        size: u64,
-        done: bool,
-    ) {
-        // Only update if the preview is showing this directory
-        if self.preview_state.current_path.as_deref() != Some(path) {
-            return;
-        }
+    #[test]
+    #[cfg(feature = "embedded-terminal")]
+    fn terminal_references_lead_to_the_file_and_line() {
+        let (dir, mut app, path) = setup_numbered_file();
+        app.terminal_state.started_in = Some(dir.path().join("alpha"));
+        app.terminal_area = Rect::new(0, 20, 60, 6);
+        app.terminal_state
+            .process(b"warning --> numbers.txt:50:3\r\nsee ../file_b.rs and gone.rs:4");
+
+        // From the bottom up, skipping what names nothing
+        app.follow_next_terminal_link();
+        assert_eq!(app.selected_file_path(), Some(dir.path().join("file_b.rs")));
+        app.follow_next_terminal_link();
+        assert_eq!(app.selected_file_path(), Some(path.clone()));
+        assert_eq!(app.preview_state.scroll_offset, 44);
+
+        // Ctrl+Click: inside the border, on the reference or beside it
+        app.follow_terminal_link_at(13, 22);
+        assert_eq!(app.selected_file_path(), Some(dir.path().join("file_b.rs")));
+        app.follow_terminal_link_at(3, 21);
+        assert_eq!(app.selected_file_path(), Some(dir.path().join("file_b.rs")));
+        app.follow_terminal_link_at(15, 21);
+        assert_eq!(app.selected_file_path(), Some(path));
+    }
 
-        let status = if done { "Complete" } else { "Scanning..." };
-        let size_str = format_size_bytes(size);
-        let summary = format!(
-            "📁 Directory Summary ({})\n\n  Files: {}\n  Directories: {}\n  Total size: {}",
-            status, files, dirs, size_str
-        );
+    #[test]
+    fn navigate_to_location_clamps_past_end() {
+        let (_dir, mut app, path) = setup_numbered_file();
+        app.navigate_to_location(&path, 500);
+        assert_eq!(app.line_flash_step().unwrap().0, 99);
+        assert_eq!(app.preview_state.scroll_offset, 90);
+        let (msg, _) = app.status_message.as_ref().unwrap();
+        assert!(msg.contains("past the end"), "{}", msg);
+    }
 
-        self.preview_state.content_lines = summary
-            .lines()
-            .map(|l| ratatui::text::Line::raw(l.to_string()))
-            .collect();
-        self.preview_state.total_lines = self.preview_state.content_lines.len();
+    #[test]
+    fn front_matter_card_shifts_line_jumps() {
+        let (dir, mut app) = setup_app();
+        let path = dir.path().join("alpha").join("notes.md");
+        // A 6-line block shown as a 4-row card, then "body 7" ... "body 46"
+        let mut doc = "---\ntitle: Notes\ntags: [a, b]\nauthor:\n  name: Sam\n---\n".to_string();
+        doc.extend((7..=46).map(|n| format!("body {}\n", n)));
+        fs::write(&path, doc).unwrap();
+        app.preview_area = Rect::new(0, 0, 80, 12);
+        let row_text = |app: &App, row: usize| -> String {
+            app.preview_state.content_lines[row]
+                .spans
+                .iter()
+                .map(|s| s.content.as_ref())
+                .collect()
+        };
+
+        app.navigate_to_location(&path, 20);
+        assert_eq!(app.preview_state.total_lines, 46);
+        assert_eq!(app.preview_state.content_lines.len(), 44);
+        assert!(row_text(&app, 1).contains("title"));
+        assert_eq!(app.line_flash_step().unwrap().0, 17);
+        assert!(row_text(&app, 17).ends_with("20 │ body 20"));
+        // Lines of the block land on the card
+        app.navigate_to_location(&path, 3);
+        assert_eq!(app.line_flash_step().unwrap().0, 0);
+
+        app.toggle_front_matter();
+        assert_eq!(app.preview_state.content_lines.len(), 46);
+        assert!(row_text(&app, 0).ends_with("1 │ ---"));
+        app.navigate_to_location(&path, 20);
+        assert_eq!(app.line_flash_step().unwrap().0, 19);
     }
-}
 
-/// Format a byte size into a human-readable string.
-#[allow(dead_code)]
-fn format_size_bytes(bytes: u64) -> String {
-    const KB: u64 = 1024;
-    const MB: u64 = KB * 1024;
-    const GB: u64 = MB * 1024;
-    const TB: u64 = GB * 1024;
-
-    if bytes >= TB {
-        format!("{:.2} TB", bytes as f64 / TB as f64)
-    } else if bytes >= GB {
-        format!("{:.2} GB", bytes as f64 / GB as f64)
-    } else if bytes >= MB {
-        format!("{:.2} MB", bytes as f64 / MB as f64)
-    } else if bytes >= KB {
-        format!("{:.2} KB", bytes as f64 / KB as f64)
-    } else {
-        format!("{} B", bytes)
+    #[test]
+    fn goto_location_parses_line_suffix() {
+        let (dir, mut app, path) = setup_numbered_file();
+        let root = dir.path().canonicalize().unwrap();
+        app.goto_location("alpha/numbers.txt:30:4", &root);
+        assert_eq!(app.selected_file_path(), Some(path.canonicalize().unwrap()));
+        assert_eq!(app.preview_state.scroll_offset, 24);
+        assert_eq!(app.line_flash_step().unwrap().0, 29);
+
+        // Without a line the file is just selected
+        app.goto_location("file_b.rs", &root);
+        assert_eq!(app.selected_file_path(), Some(root.join("file_b.rs")));
+
+        let outside = TempDir::new().unwrap();
+        app.goto_location(&outside.path().to_string_lossy(), &root);
+        let (msg, _) = app.status_message.as_ref().unwrap();
+        assert!(msg.contains("outside the tree root"), "{}", msg);
+        assert_eq!(app.selected_file_path(), Some(root.join("file_b.rs")));
     }
-}
-/// Copy text to the system clipboard using platform-native commands.
-/// Tries (in order): xclip, xsel, wl-copy (Linux/BSD), pbcopy (macOS).
-/// Returns Ok(()) on success, Err(message) on failure.
-fn copy_to_system_clipboard(text: &str) -> std::result::Result<(), String> {
-    use std::io::Write;
-    use std::process::{Command, Stdio};
-
-    // List of clipboard commands to try, with args
-    let commands: &[(&str, &[&str])] = &[
-        ("xclip", &["-selection", "clipboard"]),
-        ("xsel", &["--clipboard", "--input"]),
-        ("wl-copy", &[]),
-        ("pbcopy", &[]),
-    ];
-
-    for (cmd, args) in commands {
-        if let Ok(mut child) = Command::new(cmd)
-            .args(*args)
-            .stdin(Stdio::piped())
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .spawn()
-        {
-            if let Some(ref mut stdin) = child.stdin {
-                if stdin.write_all(text.as_bytes()).is_ok() {
-                    drop(child.stdin.take()); // close stdin to signal EOF
-                    if let Ok(status) = child.wait() {
-                        if status.success() {
-                            return Ok(());
-                        }
-                    }
-                }
-            }
+
+    #[test]
+    #[cfg(feature = "editor")]
+    fn search_hit_with_line_opens_editor_there() {
+        let (_dir, mut app, _path) = setup_numbered_file();
+        app.open_search();
+        for c in "numbers:42:3".chars() {
+            app.search_input_char(c);
         }
+        assert!(!app.search_state.results.is_empty());
+        app.search_confirm();
+        let state = app.search_action_state.as_ref().unwrap();
+        assert_eq!((state.line, state.col), (Some(42), Some(3)));
+
+        app.search_action_edit();
+        assert_eq!(app.mode, AppMode::Edit);
+        let editor = app.editor_state.as_ref().unwrap();
+        assert_eq!((editor.cursor_line, editor.cursor_col), (41, 2));
+        assert_eq!(editor.scroll_offset, 36);
     }
 
-    Err("No clipboard tool found (install xclip or xsel)".to_string())
-}
+    // === Directional focus navigation tests ===
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::fs::{self, File};
-    use tempfile::TempDir;
+    #[test]
+    fn focus_left_from_preview_goes_to_tree() {
+        let (_dir, mut app) = setup_app();
+        app.focused_panel = FocusedPanel::Preview;
+        app.focus_left();
+        assert_eq!(app.focused_panel, FocusedPanel::Tree);
+    }
 
-    fn setup_app() -> (TempDir, App) {
-        let dir = TempDir::new().unwrap();
-        fs::create_dir(dir.path().join("alpha")).unwrap();
-        fs::create_dir(dir.path().join("beta")).unwrap();
-        File::create(dir.path().join("file_a.txt")).unwrap();
-        File::create(dir.path().join("file_b.rs")).unwrap();
-        File::create(dir.path().join(".hidden")).unwrap();
-        let app = App::new(dir.path(), crate::config::AppConfig::default()).unwrap();
-        (dir, app)
+    #[test]
+    fn focus_left_from_terminal_goes_to_tree() {
+        let (_dir, mut app) = setup_app();
+        app.focused_panel = FocusedPanel::Terminal;
+        app.focus_left();
+        assert_eq!(app.focused_panel, FocusedPanel::Tree);
     }
 
     #[test]
-    fn select_next_moves_down() {
+    fn focus_left_from_tree_is_noop() {
         let (_dir, mut app) = setup_app();
-        assert_eq!(app.tree_state.selected_index, 0);
-        app.select_next();
-        assert_eq!(app.tree_state.selected_index, 1);
+        app.focused_panel = FocusedPanel::Tree;
+        app.focus_left();
+        assert_eq!(app.focused_panel, FocusedPanel::Tree);
     }
 
     #[test]
-    fn select_next_clamps_at_end() {
+    fn focus_right_from_tree_goes_to_preview() {
         let (_dir, mut app) = setup_app();
-        let last = app.tree_state.flat_items.len() - 1;
-        app.tree_state.selected_index = last;
-        app.select_next();
-        assert_eq!(app.tree_state.selected_index, last);
+        app.focused_panel = FocusedPanel::Tree;
+        app.focus_right();
+        assert_eq!(app.focused_panel, FocusedPanel::Preview);
     }
 
     #[test]
-    fn select_previous_moves_up() {
+    fn focus_right_from_terminal_goes_to_preview() {
         let (_dir, mut app) = setup_app();
-        app.tree_state.selected_index = 2;
-        app.select_previous();
-        assert_eq!(app.tree_state.selected_index, 1);
+        app.focused_panel = FocusedPanel::Terminal;
+        app.focus_right();
+        assert_eq!(app.focused_panel, FocusedPanel::Preview);
     }
 
     #[test]
-    fn select_previous_clamps_at_start() {
+    fn focus_right_from_preview_is_noop() {
         let (_dir, mut app) = setup_app();
-        app.select_previous();
-        assert_eq!(app.tree_state.selected_index, 0);
+        app.focused_panel = FocusedPanel::Preview;
+        app.focus_right();
+        assert_eq!(app.focused_panel, FocusedPanel::Preview);
     }
 
     #[test]
-    fn select_first_and_last() {
+    fn focus_up_from_terminal_goes_to_tree() {
         let (_dir, mut app) = setup_app();
-        app.select_last();
-        assert_eq!(
-            app.tree_state.selected_index,
-            app.tree_state.flat_items.len() - 1
-        );
-        app.select_first();
-        assert_eq!(app.tree_state.selected_index, 0);
+        app.focused_panel = FocusedPanel::Terminal;
+        app.focus_up();
+        assert_eq!(app.focused_panel, FocusedPanel::Tree);
     }
 
     #[test]
-    fn toggle_hidden_changes_count() {
+    fn focus_up_from_tree_is_noop() {
         let (_dir, mut app) = setup_app();
-        let without_hidden = app.tree_state.flat_items.len();
-        app.toggle_hidden();
-        let with_hidden = app.tree_state.flat_items.len();
-        assert!(with_hidden > without_hidden);
+        app.focused_panel = FocusedPanel::Tree;
+        app.focus_up();
+        assert_eq!(app.focused_panel, FocusedPanel::Tree);
     }
 
     #[test]
-    fn expand_directory() {
+    fn focus_up_from_preview_is_noop() {
         let (_dir, mut app) = setup_app();
-        // Select first child (should be a directory: "alpha")
-        app.select_next();
-        assert_eq!(app.tree_state.flat_items[1].name, "alpha");
-        app.expand_selected();
-        // alpha is empty so flat items count stays same, but it's now expanded
-        assert!(app.tree_state.flat_items[1].is_expanded);
+        app.focused_panel = FocusedPanel::Preview;
+        app.focus_up();
+        assert_eq!(app.focused_panel, FocusedPanel::Preview);
     }
 
     #[test]
-    fn quit_sets_flag() {
+    fn focus_down_from_tree_goes_to_terminal_when_visible() {
         let (_dir, mut app) = setup_app();
-        assert!(!app.should_quit);
-        app.quit();
-        assert!(app.should_quit);
+        app.terminal_state.visible = true;
+        app.focused_panel = FocusedPanel::Tree;
+        app.focus_down();
+        assert_eq!(app.focused_panel, FocusedPanel::Terminal);
     }
 
     #[test]
-    fn open_dialog_sets_mode() {
+    fn focus_down_from_preview_goes_to_terminal_when_visible() {
         let (_dir, mut app) = setup_app();
-        app.open_dialog(DialogKind::CreateFile);
-        assert_eq!(app.mode, AppMode::Dialog(DialogKind::CreateFile));
+        app.terminal_state.visible = true;
+        app.focused_panel = FocusedPanel::Preview;
+        app.focus_down();
+        assert_eq!(app.focused_panel, FocusedPanel::Terminal);
     }
 
     #[test]
-    fn close_dialog_returns_to_normal() {
+    fn focus_down_is_noop_when_terminal_hidden() {
         let (_dir, mut app) = setup_app();
-        app.open_dialog(DialogKind::CreateDirectory);
-        app.close_dialog();
-        assert_eq!(app.mode, AppMode::Normal);
-        assert!(app.dialog_state.input.is_empty());
-        assert_eq!(app.dialog_state.cursor_position, 0);
+        app.terminal_state.visible = false;
+        app.focused_panel = FocusedPanel::Tree;
+        app.focus_down();
+        assert_eq!(app.focused_panel, FocusedPanel::Tree);
+    }
+
+    #[test]
+    fn focus_down_is_noop_when_already_on_terminal() {
+        let (_dir, mut app) = setup_app();
+        app.terminal_state.visible = true;
+        app.focused_panel = FocusedPanel::Terminal;
+        app.focus_down();
+        assert_eq!(app.focused_panel, FocusedPanel::Terminal);
+    }
+
+    #[test]
+    fn preview_populates_cache_and_memory_stats() {
+        let (dir, mut app) = setup_app();
+        fs::write(dir.path().join("file_b.rs"), "fn main() {}\n").unwrap();
+        let idx = app
+            .tree_state
+            .find_index_by_path(&dir.path().join("file_b.rs"))
+            .unwrap();
+        app.tree_state.selected_index = idx;
+        app.update_preview();
+
+        let stats = app.memory_stats();
+        assert_eq!(stats.preview_cache_entries, 1);
+        assert!(stats.preview_bytes > 0);
+        assert!(stats.preview_cache_bytes > 0);
+    }
+
+    #[test]
+    fn preview_cache_respects_configured_budget() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("a.txt"), "a\n".repeat(200)).unwrap();
+        let mut config = crate::config::AppConfig::default();
+        config.preview.cache_max_bytes = Some(64);
+        let mut app = App::new(dir.path(), config).unwrap();
+        app.tree_state.selected_index = app
+            .tree_state
+            .find_index_by_path(&dir.path().join("a.txt"))
+            .unwrap();
+        app.update_preview();
+        assert_eq!(app.preview_state.total_lines, 200);
+        assert_eq!(app.memory_stats().preview_cache_entries, 0);
     }
 
     #[test]
-    fn dialog_input_char_inserts() {
-        let (_dir, mut app) = setup_app();
-        app.open_dialog(DialogKind::CreateFile);
-        app.dialog_input_char('a');
-        app.dialog_input_char('b');
-        app.dialog_input_char('c');
-        assert_eq!(app.dialog_state.input, "abc");
-        assert_eq!(app.dialog_state.cursor_position, 3);
+    fn path_maps_stay_bounded_over_100k_paths() {
+        let dir = TempDir::new().unwrap();
+        let mut config = crate::config::AppConfig::default();
+        config.memory.flash_entries = Some(100);
+        let caps = config.map_capacities();
+        let mut app = App::new(dir.path(), config).unwrap();
+        let now = Instant::now();
+        const PATHS: usize = 100_000;
+        for i in 0..PATHS {
+            let path = PathBuf::from(format!("/soak/{}/{}", i % 97, i));
+            app.flash.mark(path.clone(), now);
+            app.tree_state
+                .set_sort_override(&path, crate::fs::tree::SortBy::Size, true);
+            app.prefetch.mark_warmed(path.clone());
+            let job = app.dir_sizes.next_job();
+            app.dir_sizes
+                .start(path.clone(), job, Arc::new(AtomicBool::new(false)));
+            app.dir_sizes.update(
+                &path,
+                job,
+                DirSize {
+                    bytes: 1,
+                    unique_bytes: 1,
+                    files: 1,
+                    dirs: 0,
+                    progress: dir_size::Progress::Done,
+                },
+            );
+            app.blame.heads.insert(path, None);
+        }
+
+        let bound =
+            caps.flash + caps.sort_overrides + caps.prefetch + caps.dir_sizes + caps.blame_heads;
+        let maps = app.memory_stats().maps;
+        assert_eq!(maps.entries, bound);
+        assert_eq!(maps.evictions, (5 * PATHS - bound) as u64);
+        assert_eq!(app.flash.len(), 100);
+        // The newest paths are the ones kept
+        let last = PathBuf::from(format!("/soak/{}/{}", (PATHS - 1) % 97, PATHS - 1));
+        assert!(app.flash.step_for(&last, now).is_some());
+        assert!(app.dir_sizes.get(&last).is_some());
+        assert!(app.dir_sizes.get(Path::new("/soak/0/0")).is_none());
     }
 
     #[test]
-    fn dialog_delete_char_removes() {
-        let (_dir, mut app) = setup_app();
-        app.open_dialog(DialogKind::CreateFile);
-        app.dialog_input_char('a');
-        app.dialog_input_char('b');
-        app.dialog_delete_char();
-        assert_eq!(app.dialog_state.input, "a");
-        assert_eq!(app.dialog_state.cursor_position, 1);
+    fn terminal_scrollback_limit_comes_from_config() {
+        let dir = TempDir::new().unwrap();
+        let mut config = crate::config::AppConfig::default();
+        config.terminal.scrollback_lines = Some(42);
+        let app = App::new(dir.path(), config).unwrap();
+        assert_eq!(app.terminal_state.emulator.max_scrollback(), 42);
     }
 
     #[test]
-    fn dialog_delete_char_at_start_is_noop() {
-        let (_dir, mut app) = setup_app();
-        app.open_dialog(DialogKind::CreateFile);
-        app.dialog_delete_char();
-        assert!(app.dialog_state.input.is_empty());
-        assert_eq!(app.dialog_state.cursor_position, 0);
+    fn terminal_here_targets_selected_dir_or_file_parent() {
+        let (dir, mut app) = setup_app();
+        app.tree_state.selected_index = 1; // alpha/
+        assert_eq!(app.terminal_here_dir(), Some(dir.path().join("alpha")));
+        app.tree_state.selected_index = 3; // file_a.txt
+        assert_eq!(app.terminal_here_dir(), Some(dir.path().to_path_buf()));
     }
 
     #[test]
-    fn dialog_cursor_left_right() {
-        let (_dir, mut app) = setup_app();
-        app.open_dialog(DialogKind::CreateFile);
-        app.dialog_input_char('a');
-        app.dialog_input_char('b');
-        app.dialog_move_cursor_left();
-        assert_eq!(app.dialog_state.cursor_position, 1);
-        app.dialog_move_cursor_right();
-        assert_eq!(app.dialog_state.cursor_position, 2);
+    #[cfg(feature = "embedded-terminal")]
+    fn terminal_here_reports_vanished_directory() {
+        let (dir, mut app) = setup_app();
+        let (tx, _rx) = mpsc::unbounded_channel();
+        app.tree_state.selected_index = 1; // alpha/
+        std::fs::remove_dir(dir.path().join("alpha")).unwrap();
+
+        app.open_terminal_here(&tx);
+        assert!(app.terminal_state.pty.is_none());
+        assert!(!app.terminal_state.visible);
+        assert_eq!(app.mode, AppMode::Normal);
+        assert!(app
+            .status_message
+            .as_ref()
+            .is_some_and(|(msg, _)| msg.contains("no longer exists")));
     }
 
-    #[test]
-    fn dialog_cursor_boundaries() {
-        let (_dir, mut app) = setup_app();
-        app.open_dialog(DialogKind::CreateFile);
-        app.dialog_move_cursor_left();
-        assert_eq!(app.dialog_state.cursor_position, 0);
-        app.dialog_input_char('x');
-        app.dialog_move_cursor_right();
-        assert_eq!(app.dialog_state.cursor_position, 1);
+    #[tokio::test]
+    #[cfg(feature = "embedded-terminal")]
+    async fn terminal_here_spawns_then_asks_before_replacing_shell() {
+        let (dir, mut app) = setup_app();
+        app.config.terminal.default_shell = Some("/bin/sh".to_string());
+        let (tx, _rx) = mpsc::unbounded_channel();
+
+        // No shell yet: the panel opens with a session in alpha/
+        app.tree_state.selected_index = 1;
+        app.open_terminal_here(&tx);
+        assert!(app.terminal_state.visible);
+        assert!(app.terminal_state.pty.is_some());
+        assert_eq!(app.terminal_state.title.as_deref(), Some("alpha"));
+        assert_eq!(app.focused_panel, FocusedPanel::Terminal);
+
+        // A live shell is never replaced without asking
+        app.focused_panel = FocusedPanel::Tree;
+        app.tree_state.selected_index = 2; // beta/
+        app.open_terminal_here(&tx);
+        let beta = dir.path().join("beta");
+        assert_eq!(
+            app.mode,
+            AppMode::Dialog(DialogKind::TerminalHere { dir: beta.clone() })
+        );
+        app.close_dialog();
+
+        app.restart_terminal_in(&beta, &tx);
+        assert_eq!(app.terminal_state.title.as_deref(), Some("beta"));
+        assert!(app
+            .terminal_state
+            .pty
+            .as_ref()
+            .is_some_and(|p| p.is_alive()));
+        app.shutdown_terminal();
     }
 
-    #[test]
-    fn dialog_cursor_home_end() {
+    #[tokio::test]
+    #[cfg(not(feature = "embedded-terminal"))]
+    async fn terminal_left_out_of_the_build_says_so() {
         let (_dir, mut app) = setup_app();
-        app.open_dialog(DialogKind::CreateFile);
-        app.dialog_input_char('a');
-        app.dialog_input_char('b');
-        app.dialog_input_char('c');
-        app.dialog_cursor_home();
-        assert_eq!(app.dialog_state.cursor_position, 0);
-        app.dialog_cursor_end();
-        assert_eq!(app.dialog_state.cursor_position, 3);
+        let (tx, _rx) = mpsc::unbounded_channel();
+        app.toggle_terminal(&tx);
+        assert!(!app.terminal_state.visible);
+        assert_eq!(app.focused_panel, FocusedPanel::Tree);
+        let (msg, _) = app.status_message.as_ref().unwrap();
+        assert_eq!(msg, "⚠ Terminal: built without terminal support");
+
+        app.tree_state.selected_index = 1; // alpha/
+        app.open_terminal_here(&tx);
+        assert!(!app.terminal_state.visible);
+        assert_eq!(app.mode, AppMode::Normal);
     }
 
     #[test]
-    fn rename_prefills_input() {
+    #[cfg(not(feature = "editor"))]
+    fn editor_left_out_of_the_build_says_so() {
         let (_dir, mut app) = setup_app();
-        let path = PathBuf::from("/some/dir/hello.txt");
-        app.open_dialog(DialogKind::Rename { original: path });
-        assert_eq!(app.dialog_state.input, "hello.txt");
-        assert_eq!(app.dialog_state.cursor_position, 9);
+        app.tree_state.selected_index = 3; // file_a.txt
+        app.update_preview();
+        app.focused_panel = FocusedPanel::Preview;
+        assert!(!app.enter_edit_mode(None));
+        assert_eq!(app.mode, AppMode::Normal);
+        let (msg, _) = app.status_message.as_ref().unwrap();
+        assert_eq!(msg, "Cannot edit: built without editor support");
     }
 
     #[test]
-    fn set_status_message_stores_message() {
-        let (_dir, mut app) = setup_app();
-        app.set_status_message("test message".to_string());
-        assert!(app.status_message.is_some());
+    #[cfg(not(feature = "archives"))]
+    fn archives_left_out_of_the_build_say_so() {
+        let (dir, mut app) = setup_app();
+        fs::write(dir.path().join("alpha.zip"), b"PK").unwrap();
+        app.tree_state.reload_dir(dir.path());
+        app.tree_state.flatten();
+        let (tx, _rx) = mpsc::unbounded_channel();
+        app.extract_selected(tx);
+        assert_eq!(app.mode, AppMode::Normal);
         let (msg, _) = app.status_message.as_ref().unwrap();
-        assert_eq!(msg, "test message");
+        assert_eq!(msg, "Cannot extract: built without archive support");
     }
 
-    #[test]
-    fn clear_expired_status_keeps_recent() {
-        let (_dir, mut app) = setup_app();
-        app.set_status_message("fresh".to_string());
-        app.clear_expired_status();
-        assert!(app.status_message.is_some());
+    #[tokio::test]
+    async fn portability_check_renames_and_undoes() {
+        let (dir, mut app) = setup_app();
+        let beta = dir.path().join("beta");
+        File::create(beta.join("aux.txt")).unwrap();
+        File::create(beta.join("notes:final.txt")).unwrap();
+        File::create(beta.join("ok.txt")).unwrap();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+
+        app.tree_state.selected_index = 2;
+        app.start_portability_check(&tx);
+        match rx.recv().await {
+            Some(crate::event::Event::PortabilityComplete { id, report }) => {
+                app.handle_portability_complete(id, report)
+            }
+            other => panic!("expected a portability result, got {:?}", other),
+        }
+        match &app.mode {
+            AppMode::Dialog(DialogKind::Portability { report, .. }) => {
+                assert_eq!(report.entries, 3);
+                assert_eq!(report.findings.len(), 2);
+            }
+            other => panic!("expected portability results, got {:?}", other),
+        }
+
+        app.portability_rename_all();
+        assert_eq!(app.mode, AppMode::Normal);
+        assert!(beta.join("aux_.txt").exists());
+        assert!(beta.join("notes_final.txt").exists());
+        assert!(!beta.join("aux.txt").exists());
+
+        app.undo();
+        assert!(beta.join("aux.txt").exists());
+        assert!(beta.join("notes:final.txt").exists());
+        assert!(!beta.join("aux_.txt").exists());
     }
 
-    #[test]
-    fn clear_expired_status_removes_old() {
-        let (_dir, mut app) = setup_app();
-        app.status_message = Some((
-            "old".to_string(),
-            Instant::now() - std::time::Duration::from_secs(5),
+    #[tokio::test]
+    async fn truncated_search_index_reindexes_with_a_raised_cap() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("vendor/pkg")).unwrap();
+        for i in 0..5 {
+            File::create(dir.path().join(format!("vendor/pkg/dep{}.rs", i))).unwrap();
+        }
+        File::create(dir.path().join("main.rs")).unwrap();
+        let mut config = crate::config::AppConfig::default();
+        config.general.search_max_entries = Some(3);
+        let mut app = App::new(dir.path(), config).unwrap();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+
+        app.open_search();
+        let coverage = app.search_state.coverage.clone();
+        assert!(coverage.truncated);
+        assert_eq!((coverage.cap, coverage.seen), (3, 8));
+        assert_eq!(coverage.partial, ["vendor"]);
+        for c in "dep4".chars() {
+            app.search_input_char(c);
+        }
+        assert!(app.search_state.results.is_empty());
+
+        app.reindex_search(&tx);
+        assert!(app.search_state.reindex.is_some());
+        // A second Ctrl+R while it runs starts nothing
+        app.reindex_search(&tx);
+        assert_eq!(app.tasks.counters().running, 1);
+        match rx.recv().await {
+            Some(crate::event::Event::IndexComplete(root, index)) => {
+                app.handle_index_complete(root, index)
+            }
+            other => panic!("expected an index, got {:?}", other),
+        }
+        assert!(app.search_state.reindex.is_none());
+        assert!(!app.search_state.coverage.truncated);
+        assert_eq!(app.search_state.cached_paths.as_ref().unwrap().len(), 8);
+        assert!(app.search_state.results[0]
+            .path
+            .ends_with("vendor/pkg/dep4.rs"));
+        // Nothing more to raise
+        app.reindex_search(&tx);
+        assert!(app.search_state.reindex.is_none());
+    }
+
+    /// Select the root-level entry called `name`.
+    fn select_named(app: &mut App, name: &str) {
+        app.tree_state.selected_index = app
+            .tree_state
+            .flat_items
+            .iter()
+            .position(|i| i.name == name)
+            .unwrap();
+    }
+
+    #[cfg(feature = "archives")]
+    #[tokio::test]
+    async fn extract_creates_a_sibling_directory_and_undoes() {
+        let (dir, mut app) = setup_app();
+        let zip = crate::archive::tests::zip_bytes(&[("docs/readme.md", b"# hi")]);
+        fs::write(dir.path().join("bundle.zip"), zip).unwrap();
+        app.tree_state.reload_dir(dir.path());
+        select_named(&mut app, "bundle.zip");
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        app.extract_selected(tx);
+        assert!(matches!(
+            app.mode,
+            AppMode::Dialog(DialogKind::Progress { .. })
         ));
-        app.clear_expired_status();
-        assert!(app.status_message.is_none());
+        while let Some(event) = rx.recv().await {
+            match event {
+                crate::event::Event::Progress(update) => app.handle_progress(update),
+                crate::event::Event::ExtractComplete(result) => {
+                    app.handle_extract_complete(result);
+                    break;
+                }
+                _ => {}
+            }
+        }
+        assert_eq!(app.mode, AppMode::Normal);
+        let target = dir.path().join("bundle");
+        assert_eq!(
+            fs::read_to_string(target.join("docs/readme.md")).unwrap(),
+            "# hi"
+        );
+        let (msg, _) = app.status_message.as_ref().unwrap();
+        assert_eq!(msg, "Extracted 1 entry to bundle/");
+
+        app.undo();
+        assert!(!target.exists());
     }
 
-    #[test]
-    fn current_dir_returns_root_for_directory() {
-        let (dir, app) = setup_app();
-        // selected_index 0 is root, which is a directory
-        assert_eq!(app.current_dir(), dir.path().to_path_buf());
+    /// Run the duplicate task for the current selection to completion.
+    async fn duplicate(app: &mut App) {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        app.duplicate_selected(tx);
+        while let Some(event) = rx.recv().await {
+            match event {
+                crate::event::Event::Progress(update) => app.handle_progress(update),
+                crate::event::Event::DuplicateComplete(result) => {
+                    app.handle_duplicate_complete(result);
+                    break;
+                }
+                _ => {}
+            }
+        }
     }
 
-    #[test]
-    fn current_dir_returns_parent_for_file() {
+    #[tokio::test]
+    async fn duplicate_selects_the_copy_and_undoes() {
         let (dir, mut app) = setup_app();
-        // Navigate to a file (files come after directories in flat_items)
-        // flat_items: root(dir), alpha(dir), beta(dir), file_a.txt, file_b.rs
-        app.tree_state.selected_index = 3; // file_a.txt
-        assert_eq!(app.current_dir(), dir.path().to_path_buf());
+        fs::write(dir.path().join("file_a.txt"), "a").unwrap();
+        select_named(&mut app, "file_a.txt");
+
+        duplicate(&mut app).await;
+        assert_eq!(app.mode, AppMode::Normal);
+        let copy = dir.path().join("file_a (copy).txt");
+        assert_eq!(fs::read_to_string(&copy).unwrap(), "a");
+        let selected = &app.tree_state.flat_items[app.tree_state.selected_index];
+        assert_eq!(selected.path, copy);
+        let (msg, _) = app.status_message.as_ref().unwrap();
+        assert_eq!(msg, "Duplicated as file_a (copy).txt");
+
+        app.undo();
+        assert!(!copy.exists());
+        assert!(dir.path().join("file_a.txt").exists());
+
+        // Duplicating a copy counts up rather than nesting suffixes
+        duplicate(&mut app).await;
+        duplicate(&mut app).await;
+        assert!(dir.path().join("file_a (copy 2).txt").exists());
     }
 
-    // === Preview state tests ===
+    #[tokio::test]
+    async fn duplicate_copies_each_selected_item() {
+        let (dir, mut app) = setup_app();
+        fs::write(dir.path().join("alpha/inner.txt"), "x").unwrap();
+        for name in ["alpha", "file_b.rs"] {
+            select_named(&mut app, name);
+            app.tree_state.toggle_multi_select();
+        }
 
-    #[test]
-    fn default_focused_panel_is_tree() {
-        let (_dir, app) = setup_app();
-        assert_eq!(app.focused_panel, FocusedPanel::Tree);
+        duplicate(&mut app).await;
+        assert_eq!(
+            fs::read_to_string(dir.path().join("alpha (copy)/inner.txt")).unwrap(),
+            "x"
+        );
+        assert!(dir.path().join("file_b (copy).rs").exists());
+        assert!(app.tree_state.multi_selected.is_empty());
+        let (msg, _) = app.status_message.as_ref().unwrap();
+        assert_eq!(msg, "Duplicated 2 items");
+
+        app.undo();
+        assert!(!dir.path().join("alpha (copy)").exists());
+        assert!(!dir.path().join("file_b (copy).rs").exists());
+    }
+
+    /// Handle size updates, as the event loop would, until the running
+    /// walk sends its final total.
+    async fn finish_dir_size(
+        app: &mut App,
+        rx: &mut mpsc::UnboundedReceiver<crate::event::Event>,
+    ) -> DirSize {
+        while let Some(event) = rx.recv().await {
+            if let crate::event::Event::DirSizeUpdate { path, job, size } = event {
+                app.handle_dir_size_update(&path, job, size);
+                if size.done() {
+                    return size;
+                }
+            }
+        }
+        unreachable!("walk ended without a final total");
+    }
+
+    #[tokio::test]
+    async fn settled_directory_selection_is_sized_until_it_changes() {
+        let (dir, mut app) = setup_app();
+        fs::write(dir.path().join("alpha/a.bin"), vec![0u8; 1000]).unwrap();
+        fs::create_dir(dir.path().join("alpha/inner")).unwrap();
+        fs::write(dir.path().join("alpha/inner/b.bin"), vec![0u8; 24]).unwrap();
+        select_named(&mut app, "alpha");
+        let alpha = dir.path().join("alpha");
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        app.idle_refresh.note_input(Instant::now());
+        app.poll_dir_size(&tx);
+        assert_eq!(app.dir_sizes.running(), None);
+
+        app.idle_refresh.last_input = Instant::now().checked_sub(Duration::from_secs(10)).unwrap();
+        app.poll_dir_size(&tx);
+        assert_eq!(app.dir_sizes.running(), Some(alpha.as_path()));
+        let size = finish_dir_size(&mut app, &mut rx).await;
+        assert_eq!((size.bytes, size.files, size.dirs), (1024, 2, 1));
+        assert_eq!(app.dir_sizes.get(&alpha), Some(&size));
+
+        // A change inside drops the size; the next settled tick redoes it
+        app.handle_fs_change(vec![alpha.join("inner/c.bin")]);
+        assert_eq!(app.dir_sizes.get(&alpha), None);
+        app.poll_dir_size(&tx);
+        assert_eq!(app.dir_sizes.running(), Some(alpha.as_path()));
+
+        select_named(&mut app, "file_a.txt");
+        app.poll_dir_size(&tx);
+        assert_eq!(app.dir_sizes.running(), None);
+        assert_eq!(app.dir_sizes.get(&alpha), None);
     }
 
     #[test]
-    fn toggle_focus_switches_panel() {
-        let (_dir, mut app) = setup_app();
-        assert_eq!(app.focused_panel, FocusedPanel::Tree);
-        app.toggle_focus();
-        assert_eq!(app.focused_panel, FocusedPanel::Preview);
-        app.toggle_focus();
-        assert_eq!(app.focused_panel, FocusedPanel::Tree);
+    fn bulk_rename_reports_failures_and_undo_restores_the_rest() {
+        let (dir, mut app) = setup_app();
+        let a = dir.path().join("file_a.txt");
+        let b = dir.path().join("file_b.rs");
+        select_named(&mut app, "file_a.txt");
+        app.tree_state.toggle_multi_select();
+        select_named(&mut app, "file_b.rs");
+        app.tree_state.toggle_multi_select();
+
+        app.open_bulk_rename();
+        let form = app.bulk_rename_form_mut().unwrap();
+        assert_eq!(form.sources, [a.clone(), b.clone()]);
+        for c in "s/file/doc/".chars() {
+            form.input_char(c);
+        }
+        // One source goes away while the dialog is open
+        fs::remove_file(&b).unwrap();
+        app.bulk_rename_confirm();
+
+        assert_eq!(app.mode, AppMode::Normal);
+        assert!(dir.path().join("doc_a.txt").exists());
+        let (msg, _) = app.status_message.clone().unwrap();
+        assert!(msg.starts_with("Renamed 1 of 2: "), "{msg}");
+        assert!(msg.contains("file_b.rs"), "{msg}");
+
+        app.undo();
+        assert!(a.exists());
+        assert!(!dir.path().join("doc_a.txt").exists());
     }
 
     #[test]
-    fn preview_state_defaults() {
-        let (_dir, app) = setup_app();
-        assert!(app.preview_state.current_path.is_none());
-        assert!(app.preview_state.content_lines.is_empty());
-        assert_eq!(app.preview_state.scroll_offset, 0);
-        assert_eq!(app.preview_state.view_mode, ViewMode::HeadAndTail);
-        assert!(!app.preview_state.line_wrap);
-        assert_eq!(app.preview_state.total_lines, 0);
+    fn bulk_rename_refuses_duplicate_targets() {
+        let (dir, mut app) = setup_app();
+        select_named(&mut app, "file_a.txt");
+        app.tree_state.toggle_multi_select();
+        select_named(&mut app, "file_b.rs");
+        app.tree_state.toggle_multi_select();
+
+        app.open_bulk_rename();
+        for c in "notes".chars() {
+            app.bulk_rename_form_mut().unwrap().input_char(c);
+        }
+        app.bulk_rename_confirm();
+        // The dialog stays open with nothing renamed
+        assert!(app.bulk_rename_form_mut().is_some());
+        let (msg, _) = app.status_message.clone().unwrap();
+        assert_eq!(
+            msg,
+            "Error: file_a.txt and file_b.rs would both become notes"
+        );
+        assert!(dir.path().join("file_a.txt").exists());
+        assert!(!dir.path().join("notes").exists());
     }
 
+    #[cfg(unix)]
     #[test]
-    fn preview_scroll_down_up() {
-        let (_dir, mut app) = setup_app();
-        app.preview_state.total_lines = 100;
-        app.preview_scroll_down();
-        assert_eq!(app.preview_state.scroll_offset, 1);
-        app.preview_scroll_down();
-        assert_eq!(app.preview_state.scroll_offset, 2);
-        app.preview_scroll_up();
-        assert_eq!(app.preview_state.scroll_offset, 1);
+    fn permissions_apply_to_every_target_and_undo_restores_them() {
+        let (dir, mut app) = setup_app();
+        let a = dir.path().join("file_a.txt");
+        let b = dir.path().join("file_b.rs");
+        operations::set_permissions(&a, 0o644).unwrap();
+        operations::set_permissions(&b, 0o640).unwrap();
+        select_named(&mut app, "file_a.txt");
+        app.tree_state.toggle_multi_select();
+        select_named(&mut app, "file_b.rs");
+        app.tree_state.toggle_multi_select();
+
+        app.open_permissions();
+        let form = app.permissions_form_mut().unwrap();
+        assert_eq!(form.paths, [a.clone(), b.clone()]);
+        assert!(form.mixed);
+        for c in "600".chars() {
+            form.input_char(c);
+        }
+        if let AppMode::Dialog(kind) = &app.mode {
+            assert_eq!(kind.title(), "Permissions (2 items) — octal 2/2");
+        }
+        // One target goes away while the dialog is open
+        fs::remove_file(&b).unwrap();
+        app.permissions_confirm();
+
+        assert_eq!(app.mode, AppMode::Normal);
+        assert_eq!(operations::file_mode(&a).unwrap(), 0o600);
+        let (msg, _) = app.status_message.clone().unwrap();
+        assert!(msg.starts_with("Permissions set on 1 of 2: "), "{msg}");
+        assert!(msg.contains("file_b.rs not found"), "{msg}");
+
+        app.undo();
+        assert_eq!(operations::file_mode(&a).unwrap(), 0o644);
+        let (msg, _) = app.status_message.clone().unwrap();
+        assert_eq!(msg, "Undo: restored permissions of 1 item");
     }
 
+    #[cfg(unix)]
     #[test]
-    fn preview_scroll_clamps_at_boundaries() {
-        let (_dir, mut app) = setup_app();
-        app.preview_state.total_lines = 3;
-        // Can't scroll past end
-        app.preview_state.scroll_offset = 2;
-        app.preview_scroll_down();
-        assert_eq!(app.preview_state.scroll_offset, 2);
-        // Can't scroll before start
-        app.preview_state.scroll_offset = 0;
-        app.preview_scroll_up();
-        assert_eq!(app.preview_state.scroll_offset, 0);
+    fn permissions_undo_asks_when_the_mode_changed_since() {
+        let (dir, mut app) = setup_app();
+        let a = dir.path().join("file_a.txt");
+        operations::set_permissions(&a, 0o644).unwrap();
+        select_named(&mut app, "file_a.txt");
+        app.open_permissions();
+        app.permissions_form_mut().unwrap().toggle(); // owner read off
+        app.permissions_confirm();
+        assert_eq!(operations::file_mode(&a).unwrap(), 0o244);
+
+        operations::set_permissions(&a, 0o600).unwrap();
+        app.undo();
+        match &app.mode {
+            AppMode::Dialog(DialogKind::UndoConfirm { conflicts, .. }) => {
+                assert_eq!(conflicts.len(), 1);
+                assert_eq!(
+                    conflicts[0].message,
+                    "file_a.txt is 600 now — restore 644 anyway?"
+                );
+            }
+            other => panic!("expected the undo review, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn file_info_sizes_a_directory_while_open() {
+        let (dir, mut app) = setup_app();
+        fs::write(dir.path().join("alpha/a.bin"), vec![0u8; 2048]).unwrap();
+        let alpha = dir.path().join("alpha");
+        select_named(&mut app, "alpha");
+        let (tx, mut rx) = mpsc::unbounded_channel();
+
+        app.open_file_info(&tx);
+        assert_eq!(app.dir_sizes.running(), Some(alpha.as_path()));
+        let info = |app: &App| match &app.mode {
+            AppMode::Dialog(DialogKind::Info { path, info }) => {
+                assert_eq!(path, &alpha);
+                info.as_ref().clone()
+            }
+            other => panic!("expected the info dialog, got {:?}", other),
+        };
+        assert_eq!(info(&app).get("Size"), Some(crate::file_info::CALCULATING));
+        assert_eq!(info(&app).get("Children"), Some("1"));
+
+        finish_dir_size(&mut app, &mut rx).await;
+        assert_eq!(info(&app).get("Size"), Some("2.00 KB"));
+        if let AppMode::Dialog(kind) = &app.mode {
+            assert_eq!(kind.title(), "Info: alpha");
+        }
+
+        // Known sizes show at once, without another walk
+        app.close_dialog();
+        app.open_file_info(&tx);
+        assert_eq!(app.dir_sizes.running(), None);
+        assert_eq!(info(&app).get("Size"), Some("2.00 KB"));
+
+        app.close_dialog();
+        select_named(&mut app, "file_a.txt");
+        app.open_file_info(&tx);
+        match &app.mode {
+            AppMode::Dialog(DialogKind::Info { info, .. }) => {
+                assert_eq!(info.get("Size"), Some("0 bytes"))
+            }
+            other => panic!("expected the info dialog, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn z_sizes_a_directory_with_automatic_sizes_off() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir(dir.path().join("data")).unwrap();
+        fs::write(dir.path().join("data/x"), b"12345").unwrap();
+        let mut config = crate::config::AppConfig::default();
+        config.tree.dir_sizes = Some(false);
+        let mut app = App::new(dir.path(), config).unwrap();
+        select_named(&mut app, "data");
+        app.idle_refresh.last_input = Instant::now().checked_sub(Duration::from_secs(10)).unwrap();
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        app.poll_dir_size(&tx);
+        assert_eq!(app.dir_sizes.running(), None);
+
+        app.measure_dir_size(&tx);
+        assert_eq!(finish_dir_size(&mut app, &mut rx).await.bytes, 5);
+        assert_eq!(
+            app.dir_sizes
+                .get(&dir.path().join("data"))
+                .unwrap()
+                .label(false),
+            "5 B"
+        );
     }
 
-    #[test]
-    fn preview_scroll_down_noop_when_empty() {
-        let (_dir, mut app) = setup_app();
-        app.preview_state.total_lines = 0;
-        app.preview_scroll_down();
-        assert_eq!(app.preview_state.scroll_offset, 0);
+    /// Handle content search batches, as the event loop would, until the
+    /// running search finishes.
+    async fn finish_grep(app: &mut App, rx: &mut mpsc::UnboundedReceiver<crate::event::Event>) {
+        while let Some(event) = rx.recv().await {
+            if let crate::event::Event::GrepResults(batch) = event {
+                let done = batch.done;
+                app.handle_grep_results(batch);
+                if done && !app.grep_state.is_running() {
+                    return;
+                }
+            }
+        }
+        unreachable!("search ended without a final batch");
     }
 
-    #[test]
-    fn preview_jump_top_bottom() {
-        let (_dir, mut app) = setup_app();
-        app.preview_state.total_lines = 100;
-        app.preview_jump_bottom();
-        assert_eq!(app.preview_state.scroll_offset, 99);
-        app.preview_jump_top();
-        assert_eq!(app.preview_state.scroll_offset, 0);
-    }
+    #[tokio::test]
+    async fn content_search_settles_then_goes_to_the_line() {
+        let (_dir, mut app, path) = setup_numbered_file();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        app.open_grep();
+        for c in "line 5".chars() {
+            app.grep_input_char(c);
+        }
+        app.idle_refresh.note_input(Instant::now());
+        app.poll_grep(&tx);
+        assert!(!app.grep_state.is_running());
+
+        app.idle_refresh.last_input = Instant::now().checked_sub(Duration::from_secs(10)).unwrap();
+        app.poll_grep(&tx);
+        assert!(app.grep_state.is_running());
+        finish_grep(&mut app, &mut rx).await;
+        // "line 5" and "line 50" to "line 59"
+        assert_eq!(app.grep_state.results.len(), 11);
+
+        // Switching to regex makes the results stale; Enter searches again
+        app.grep_toggle_regex();
+        for c in "0$".chars() {
+            app.grep_input_char(c);
+        }
+        assert!(app.grep_state.is_stale());
+        app.grep_confirm(&tx);
+        finish_grep(&mut app, &mut rx).await;
+        let lines: Vec<usize> = app.grep_state.results.iter().map(|m| m.line).collect();
+        assert_eq!(lines, [50]);
 
-    #[test]
-    fn preview_half_page_scroll() {
-        let (_dir, mut app) = setup_app();
-        app.preview_state.total_lines = 100;
-        app.preview_half_page_down(20);
-        assert_eq!(app.preview_state.scroll_offset, 10);
-        app.preview_half_page_down(20);
-        assert_eq!(app.preview_state.scroll_offset, 20);
-        app.preview_half_page_up(20);
-        assert_eq!(app.preview_state.scroll_offset, 10);
+        app.grep_confirm(&tx);
+        assert_eq!(app.mode, AppMode::Normal);
+        assert_eq!(app.selected_file_path(), Some(path));
+        assert_eq!(app.preview_state.scroll_offset, 44);
     }
 
     #[test]
-    fn preview_half_page_clamps() {
+    fn invalid_regex_is_reported_without_searching() {
         let (_dir, mut app) = setup_app();
-        app.preview_state.total_lines = 10;
-        app.preview_half_page_down(100);
-        assert_eq!(app.preview_state.scroll_offset, 9);
-        app.preview_half_page_up(100);
-        assert_eq!(app.preview_state.scroll_offset, 0);
+        let (tx, _rx) = mpsc::unbounded_channel();
+        app.open_grep();
+        app.grep_toggle_regex();
+        app.grep_paste("(unclosed\n");
+        assert_eq!(app.grep_state.query, "(unclosed");
+        app.grep_confirm(&tx);
+        assert!(!app.grep_state.is_running());
+        assert_eq!(app.grep_state.error.as_deref(), Some("unclosed ("));
+
+        // Esc keeps the query for the next Ctrl+Shift+F
+        app.close_grep();
+        app.open_grep();
+        assert_eq!(app.grep_state.query, "(unclosed");
+    }
+
+    fn in_use(path: &Path) -> OperationError {
+        OperationError::SharingViolation {
+            path: path.to_path_buf(),
+        }
     }
 
-    #[test]
-    fn preview_jump_bottom_respects_viewport_height() {
-        let (_dir, mut app) = setup_app();
-        app.preview_state.content_lines =
-            (0..100).map(|i| Line::from(format!("line {i}"))).collect();
-        app.preview_state.total_lines = 100;
-        app.preview_area = Rect::new(0, 0, 80, 12); // inner height = 10
-        app.preview_jump_bottom();
-        assert_eq!(app.preview_state.scroll_offset, 90);
+    /// Handle retry events, as the event loop would, until the retry
+    /// reports its outcome.
+    async fn finish_retry(app: &mut App, rx: &mut mpsc::UnboundedReceiver<crate::event::Event>) {
+        while let Some(event) = rx.recv().await {
+            match event {
+                crate::event::Event::RetryAttempt { job, attempt } => {
+                    app.handle_retry_attempt(job, attempt);
+                }
+                crate::event::Event::RetryComplete(result) => {
+                    app.handle_retry_complete(result);
+                    return;
+                }
+                _ => {}
+            }
+        }
+        unreachable!("retry ended without a result");
     }
 
-    #[test]
-    fn clamp_preview_scroll_after_resize() {
-        let (_dir, mut app) = setup_app();
-        app.preview_state.content_lines =
-            (0..100).map(|i| Line::from(format!("line {i}"))).collect();
-        app.preview_state.total_lines = 100;
-        app.preview_area = Rect::new(0, 0, 80, 12); // inner height = 10
-        app.preview_jump_bottom();
-        assert_eq!(app.preview_state.scroll_offset, 90);
+    #[tokio::test]
+    async fn retried_rename_goes_through_and_can_be_undone() {
+        let (dir, mut app) = setup_app();
+        let from = dir.path().join("file_a.txt");
+        let to = dir.path().join("renamed.txt");
+        app.offer_retry(
+            RetryOp::Rename {
+                from: from.clone(),
+                to: to.clone(),
+            },
+            &in_use(&from),
+        );
+        assert!(matches!(
+            app.mode,
+            AppMode::Dialog(DialogKind::BusyRetry { attempt: None, .. })
+        ));
 
-        app.preview_area = Rect::new(0, 0, 80, 22); // inner height = 20
-        app.clamp_preview_scroll();
-        assert_eq!(app.preview_state.scroll_offset, 80);
-    }
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        app.retry_busy(false, &tx);
+        finish_retry(&mut app, &mut rx).await;
+        assert_eq!(app.mode, AppMode::Normal);
+        assert!(to.exists() && !from.exists());
+        assert_eq!(
+            app.status_message.as_ref().unwrap().0,
+            "Renamed to: renamed.txt"
+        );
 
-    // === Integration tests: preview update flow ===
+        app.undo();
+        assert!(from.exists());
+    }
 
-    #[test]
-    fn update_preview_loads_file_content() {
+    #[tokio::test]
+    async fn busy_delete_offers_a_retry_of_what_is_left() {
         let (dir, mut app) = setup_app();
-        // Write content to a file
-        std::fs::write(dir.path().join("file_a.txt"), "hello world\n").unwrap();
-        // Select file_a.txt (index 3)
-        app.tree_state.selected_index = 3;
-        app.update_preview();
-        assert!(app.preview_state.current_path.is_some());
-        assert!(!app.preview_state.content_lines.is_empty());
-        assert!(app.preview_state.total_lines >= 1);
-    }
+        let alpha = dir.path().join("alpha");
+        let file_a = dir.path().join("file_a.txt");
+        app.handle_delete_complete(crate::event::DeleteResult {
+            targets: vec![alpha.clone(), file_a.clone()],
+            deleted: Vec::new(),
+            trashed: Vec::new(),
+            errors: vec![in_use(&alpha)],
+            to_trash: false,
+        });
+        let AppMode::Dialog(DialogKind::BusyRetry { op, message, .. }) = &app.mode else {
+            panic!("no retry offered: {:?}", app.mode);
+        };
+        assert_eq!(op.describe(), "Delete 2 items");
+        assert!(message.contains("in use by another program"));
 
-    #[test]
-    fn update_preview_directory_shows_summary() {
-        let (_dir, mut app) = setup_app();
-        // Select alpha directory (index 1)
-        app.tree_state.selected_index = 1;
-        app.update_preview();
-        assert!(app.preview_state.current_path.is_some());
-        let all_text: String = app
-            .preview_state
-            .content_lines
-            .iter()
-            .flat_map(|l| l.spans.iter().map(|s| s.content.as_ref()))
-            .collect();
-        assert!(all_text.contains("Directory:"));
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        app.retry_busy(false, &tx);
+        finish_retry(&mut app, &mut rx).await;
+        assert!(!alpha.exists() && !file_a.exists());
+        assert_eq!(app.mode, AppMode::Normal);
+        assert_eq!(
+            app.status_message.as_ref().unwrap().0,
+            "Deleted after retrying"
+        );
+        assert!(app.tree_state.find_index_by_path(&alpha).is_none());
     }
 
-    #[test]
-    fn update_preview_binary_file_shows_metadata() {
+    #[tokio::test]
+    async fn stopped_auto_retry_leaves_the_choices() {
         let (dir, mut app) = setup_app();
-        // Create a binary file
-        let bin_path = dir.path().join("model.pt");
-        std::fs::write(&bin_path, &[0u8; 100]).unwrap();
-        app.tree_state.reload_dir(dir.path());
+        let from = dir.path().join("file_a.txt");
+        let op = RetryOp::Rename {
+            from: from.clone(),
+            to: dir.path().join("renamed.txt"),
+        };
+        app.offer_retry(op, &in_use(&from));
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        app.retry_busy(true, &tx);
+        assert!(matches!(
+            app.mode,
+            AppMode::Dialog(DialogKind::BusyRetry {
+                attempt: Some((0, retry::MAX_AUTO_ATTEMPTS)),
+                ..
+            })
+        ));
 
-        // Find the .pt file in flat_items
-        let idx = app
-            .tree_state
-            .flat_items
-            .iter()
-            .position(|item| item.name == "model.pt")
-            .unwrap();
-        app.tree_state.selected_index = idx;
-        app.update_preview();
+        app.stop_retry();
+        finish_retry(&mut app, &mut rx).await;
+        assert!(matches!(
+            app.mode,
+            AppMode::Dialog(DialogKind::BusyRetry { attempt: None, .. })
+        ));
+        assert!(from.exists());
 
-        let all_text: String = app
-            .preview_state
-            .content_lines
-            .iter()
-            .flat_map(|l| l.spans.iter().map(|s| s.content.as_ref()))
-            .collect();
-        assert!(all_text.contains("Binary file"));
-        assert!(all_text.contains("model.pt"));
+        app.skip_retry();
+        assert_eq!(app.mode, AppMode::Normal);
+        assert_eq!(
+            app.status_message.as_ref().unwrap().0,
+            "Skipped: Rename file_a.txt"
+        );
     }
 
+    #[cfg(feature = "archives")]
     #[test]
-    fn update_preview_notebook_file() {
+    fn extract_refuses_an_existing_target() {
         let (dir, mut app) = setup_app();
-        let nb_path = dir.path().join("test.ipynb");
-        std::fs::write(
-            &nb_path,
-            r#"{"cells":[{"cell_type":"code","source":["x=1"],"outputs":[]}],"metadata":{}}"#,
-        )
-        .unwrap();
+        let zip = crate::archive::tests::zip_bytes(&[("a.txt", b"1")]);
+        fs::write(dir.path().join("alpha.zip"), zip).unwrap();
         app.tree_state.reload_dir(dir.path());
+        select_named(&mut app, "alpha.zip");
 
-        let idx = app
-            .tree_state
-            .flat_items
-            .iter()
-            .position(|item| item.name == "test.ipynb")
-            .unwrap();
-        app.tree_state.selected_index = idx;
-        app.update_preview();
+        let (tx, _rx) = mpsc::unbounded_channel();
+        app.extract_selected(tx);
+        assert_eq!(app.mode, AppMode::Normal);
+        let (msg, _) = app.status_message.as_ref().unwrap();
+        assert_eq!(msg, "Cannot extract: alpha/ already exists");
 
-        let all_text: String = app
-            .preview_state
-            .content_lines
-            .iter()
-            .flat_map(|l| l.spans.iter().map(|s| s.content.as_ref()))
-            .collect();
-        assert!(all_text.contains("Cell 1"));
-        assert!(all_text.contains("code"));
+        select_named(&mut app, "file_a.txt");
+        let (tx, _rx) = mpsc::unbounded_channel();
+        app.extract_selected(tx);
+        let (msg, _) = app.status_message.as_ref().unwrap();
+        assert_eq!(msg, "Not an archive: file_a.txt");
     }
 
-    #[test]
-    fn update_preview_resets_scroll_on_selection_change() {
-        let (dir, mut app) = setup_app();
-        std::fs::write(dir.path().join("file_a.txt"), "line1\nline2\nline3\n").unwrap();
-        // Select file
-        app.tree_state.selected_index = 3;
-        app.update_preview();
-        app.preview_state.scroll_offset = 2;
-        // Change selection
-        app.tree_state.selected_index = 1; // directory
-        app.last_previewed_index = None; // force update
-        app.update_preview();
-        assert_eq!(app.preview_state.scroll_offset, 0);
+    /// A config directory holding the scaffold `lib`: `manifest` and the
+    /// `(path, contents)` files.
+    fn setup_scaffold(app: &mut App, manifest: &str, files: &[(&str, &str)]) -> TempDir {
+        let config = TempDir::new().unwrap();
+        let root = config.path().join(scaffold::SCAFFOLDS_DIR).join("lib");
+        fs::create_dir_all(&root).unwrap();
+        if !manifest.is_empty() {
+            fs::write(root.join(scaffold::MANIFEST), manifest).unwrap();
+        }
+        for (path, contents) in files {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+        app.config_dir = Some(config.path().to_path_buf());
+        config
     }
 
-    #[test]
-    fn update_preview_skips_if_same_selection() {
+    #[tokio::test]
+    async fn scaffold_creates_the_rendered_tree_and_undoes() {
         let (dir, mut app) = setup_app();
-        std::fs::write(dir.path().join("file_a.txt"), "hello\n").unwrap();
-        app.tree_state.selected_index = 3;
-        app.update_preview();
-        let first_path = app.preview_state.current_path.clone();
-        // Call again without changing selection
-        app.preview_state.scroll_offset = 5;
-        app.update_preview();
-        // Should not reset scroll
-        assert_eq!(app.preview_state.scroll_offset, 5);
-        assert_eq!(app.preview_state.current_path, first_path);
-    }
-
-    // === Search (Ctrl+P) tests ===
+        let _config = setup_scaffold(
+            &mut app,
+            "[[variables]]\nname = \"project_name\"\ndefault = \"demo\"\n",
+            &[("{{project_name}}/src/lib.rs", "//! {{project_name}}\n")],
+        );
+        app.tree_state.selected_index = 1; // alpha/
+
+        app.open_scaffold_picker();
+        app.scaffold_pick();
+        let form = app.scaffold_form_mut().expect("form open");
+        assert_eq!(form.values, ["demo"]);
+        while !form.values[0].is_empty() {
+            form.delete_char();
+        }
+        "core".chars().for_each(|c| form.input_char(c));
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        app.scaffold_confirm(false, tx);
+        while let Some(event) = rx.recv().await {
+            match event {
+                crate::event::Event::Progress(update) => app.handle_progress(update),
+                crate::event::Event::ScaffoldComplete(result) => {
+                    app.handle_scaffold_complete(result);
+                    break;
+                }
+                _ => {}
+            }
+        }
+        let alpha = dir.path().join("alpha");
+        assert_eq!(
+            fs::read_to_string(alpha.join("core/src/lib.rs")).unwrap(),
+            "//! core\n"
+        );
+        let (msg, _) = app.status_message.as_ref().unwrap();
+        assert_eq!(msg, "Created 3 entries from lib");
 
-    #[test]
-    fn open_search_sets_mode() {
-        let (_dir, mut app) = setup_app();
-        app.open_search();
-        assert_eq!(app.mode, AppMode::Search);
-        assert!(app.search_state.cached_paths.is_some());
+        app.undo();
+        assert!(!alpha.join("core").exists());
+        assert!(alpha.exists());
     }
 
     #[test]
-    fn close_search_returns_to_normal() {
-        let (_dir, mut app) = setup_app();
-        app.open_search();
-        app.close_search();
-        assert_eq!(app.mode, AppMode::Normal);
+    fn scaffold_conflicts_stop_in_the_form() {
+        let (dir, mut app) = setup_app();
+        let _config = setup_scaffold(&mut app, "", &[("new.txt", "1"), ("file_a.txt", "2")]);
+        app.tree_state.selected_index = 0; // root
+
+        app.open_scaffold_picker();
+        app.scaffold_pick();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        app.scaffold_confirm(false, tx);
+        let form = app.scaffold_form_mut().expect("still in the form");
+        assert_eq!(form.conflicts, vec![dir.path().join("file_a.txt")]);
+        assert!(rx.try_recv().is_err(), "nothing was started");
+        assert!(!dir.path().join("new.txt").exists());
+        assert_eq!(
+            fs::read_to_string(dir.path().join("file_a.txt")).unwrap(),
+            ""
+        );
     }
 
     #[test]
-    fn search_input_updates_query() {
-        let (_dir, mut app) = setup_app();
-        app.open_search();
-        app.search_input_char('f');
-        assert_eq!(app.search_state.query, "f");
-        app.search_input_char('i');
-        assert_eq!(app.search_state.query, "fi");
+    fn broken_scaffold_manifest_is_reported_before_writing() {
+        let (dir, mut app) = setup_app();
+        let _config = setup_scaffold(&mut app, "[[variables]]\nname = 3\n", &[("out.txt", "x")]);
+        app.open_scaffold_picker();
+        app.scaffold_pick();
+        match &app.mode {
+            AppMode::Dialog(DialogKind::Error { message }) => {
+                assert!(
+                    message.starts_with("Scaffold lib: scaffold.toml: line 2"),
+                    "{}",
+                    message
+                );
+            }
+            other => panic!("expected an error, got {:?}", other),
+        }
+        assert!(!dir.path().join("out.txt").exists());
+
+        app.close_dialog();
+        app.config_dir = Some(dir.path().to_path_buf());
+        app.open_scaffold_picker();
+        assert_eq!(app.mode, AppMode::Normal);
+        let (msg, _) = app.status_message.as_ref().unwrap();
+        assert!(msg.starts_with("No scaffolds in "));
     }
 
     #[test]
-    fn search_delete_char_removes() {
-        let (_dir, mut app) = setup_app();
-        app.open_search();
-        app.search_input_char('a');
-        app.search_input_char('b');
-        app.search_delete_char();
-        assert_eq!(app.search_state.query, "a");
+    fn stale_portability_results_are_dropped() {
+        let (dir, mut app) = setup_app();
+        app.portability_id = 2;
+        let report = portability::Report {
+            root: dir.path().to_path_buf(),
+            findings: portability::check_entries(&[dir.path().join("con")]),
+            entries: 1,
+            complete: true,
+        };
+        app.handle_portability_complete(1, report.clone());
+        assert_eq!(app.mode, AppMode::Normal);
+        app.handle_portability_complete(2, report);
+        assert!(matches!(
+            app.mode,
+            AppMode::Dialog(DialogKind::Portability { .. })
+        ));
     }
 
     #[test]
-    fn search_delete_at_empty_is_noop() {
+    fn portability_check_needs_a_directory() {
         let (_dir, mut app) = setup_app();
-        app.open_search();
-        app.search_delete_char();
-        assert_eq!(app.search_state.query, "");
+        let (tx, _rx) = mpsc::unbounded_channel();
+        app.tree_state.selected_index = 3;
+        app.start_portability_check(&tx);
+        assert_eq!(app.portability_id, 0);
+        let (msg, _) = app.status_message.as_ref().unwrap();
+        assert!(msg.contains("Select a directory"));
+    }
+
+    fn transfer_result(
+        sources: Vec<PathBuf>,
+        created: Vec<PathBuf>,
+        errors: Vec<crate::error::OperationError>,
+        dest_dir: &Path,
+    ) -> crate::event::OperationResult {
+        let failed_sources = sources[created.len()..created.len() + errors.len()].to_vec();
+        crate::event::OperationResult {
+            success_count: created.len(),
+            errors,
+            created_paths: created,
+            replaced: Vec::new(),
+            backups: Vec::new(),
+            failed_sources,
+            source_paths: sources,
+            dest_dir: dest_dir.to_path_buf(),
+            was_cut: false,
+            send_to: None,
+            elapsed: Duration::from_millis(20),
+        }
     }
 
     #[test]
-    fn search_results_update_on_input() {
-        let (_dir, mut app) = setup_app();
-        app.open_search();
-        app.search_input_char('f');
-        app.search_input_char('i');
-        app.search_input_char('l');
-        app.search_input_char('e');
-        // Should find file_a.txt and file_b.rs
-        assert!(app.search_state.results.len() >= 2);
+    fn clipboard_marks_clear_after_the_cut_is_pasted() {
+        let (dir, mut app) = setup_app();
+        app.tree_state.selected_index = 3;
+        assert_eq!(app.selected_clipboard_note(), None);
+        app.tree_state.toggle_multi_select();
+        app.tree_state.selected_index = 4;
+        app.tree_state.toggle_multi_select();
+        app.cut_to_clipboard();
+        assert_eq!(
+            app.selected_clipboard_note().as_deref(),
+            Some("on clipboard (cut, 2 items total)")
+        );
+        // Marks are by path, so a refresh keeps them
+        app.tree_state.flatten();
+        let marked = app.tree_state.flat_items[3].path.clone();
+        assert_eq!(app.clipboard.mark(&marked), Some(ClipboardOp::Cut));
+
+        let sources = app.clipboard.paths.clone();
+        let dest = dir.path().join("alpha");
+        let created: Vec<PathBuf> = sources
+            .iter()
+            .map(|p| dest.join(p.file_name().unwrap()))
+            .collect();
+        for (src, moved) in sources.iter().zip(&created) {
+            std::fs::rename(src, moved).unwrap();
+        }
+        let mut result = transfer_result(sources, created, vec![], &dest);
+        result.was_cut = true;
+        app.handle_operation_complete(result);
+        assert!(app.clipboard.is_empty());
+        assert_eq!(app.clipboard.mark(&marked), None);
+        assert_eq!(app.selected_clipboard_note(), None);
     }
 
     #[test]
-    fn search_empty_query_clears_results() {
+    fn clear_clipboard_drops_the_marks() {
         let (_dir, mut app) = setup_app();
-        app.open_search();
-        app.search_input_char('a');
-        assert!(!app.search_state.results.is_empty());
-        app.search_delete_char();
-        assert!(app.search_state.results.is_empty());
+        app.tree_state.selected_index = 3;
+        app.copy_to_clipboard();
+        assert_eq!(
+            app.selected_clipboard_note().as_deref(),
+            Some("on clipboard (copy, 1 item total)")
+        );
+        app.clear_clipboard();
+        assert!(app.clipboard.is_empty());
+        assert_eq!(app.selected_clipboard_note(), None);
+        assert_eq!(
+            app.status_message.as_ref().map(|(m, _)| m.as_str()),
+            Some("Clipboard cleared")
+        );
     }
 
     #[test]
-    fn search_no_matches_empty_results() {
-        let (_dir, mut app) = setup_app();
-        app.open_search();
-        app.search_input_char('z');
-        app.search_input_char('z');
-        app.search_input_char('z');
-        assert!(app.search_state.results.is_empty());
+    fn recent_operations_list_newest_first_and_link_undo() {
+        let (dir, mut app) = setup_app();
+        let copied = dir.path().join("alpha/file_a.txt");
+        File::create(&copied).unwrap();
+        app.handle_operation_complete(transfer_result(
+            vec![dir.path().join("file_a.txt")],
+            vec![copied.clone()],
+            vec![],
+            &dir.path().join("alpha"),
+        ));
+        // A failed paste records no undo, so the first stays undoable
+        app.handle_operation_complete(transfer_result(
+            vec![dir.path().join("file_b.rs")],
+            vec![],
+            vec![crate::error::OperationError::PermissionDenied {
+                path: dir.path().join("beta/file_b.rs"),
+            }],
+            &dir.path().join("beta"),
+        ));
+
+        app.open_recent_operations();
+        let (ops, undoable) = match &app.mode {
+            AppMode::Dialog(DialogKind::RecentOperations { ops, undoable, .. }) => {
+                (ops.clone(), *undoable)
+            }
+            other => panic!("expected the recent operations list, got {:?}", other),
+        };
+        assert_eq!(ops.len(), 2);
+        assert!(ops[0].summary().starts_with("Pasted 0 of 1 item to "));
+        assert!(ops[0].summary().contains("1 failed"));
+        assert_eq!(ops[0].retry, vec![dir.path().join("file_b.rs")]);
+        assert!(ops[1].summary().starts_with("Pasted 1 item to "));
+        assert_eq!(undoable, Some(ops[1].id));
+
+        // The failed one cannot be undone; the list stays open
+        app.recent_ops_undo();
+        assert!(matches!(
+            app.mode,
+            AppMode::Dialog(DialogKind::RecentOperations { .. })
+        ));
+        assert!(app
+            .status_message
+            .as_ref()
+            .unwrap()
+            .0
+            .contains("no longer be undone"));
+
+        app.recent_ops_select(true);
+        app.recent_ops_undo();
+        assert!(!copied.exists());
+        assert_eq!(app.undoable_recent_op(), None);
     }
 
     #[test]
-    fn search_select_navigation() {
-        let (_dir, mut app) = setup_app();
-        app.open_search();
-        app.search_input_char('f');
-        assert_eq!(app.search_state.selected_index, 0);
-        app.search_select_next();
-        assert_eq!(app.search_state.selected_index, 1);
-        app.search_select_previous();
-        assert_eq!(app.search_state.selected_index, 0);
+    fn other_undo_unlinks_recent_operation() {
+        let (dir, mut app) = setup_app();
+        let copied = dir.path().join("alpha/file_a.txt");
+        File::create(&copied).unwrap();
+        app.handle_operation_complete(transfer_result(
+            vec![dir.path().join("file_a.txt")],
+            vec![copied.clone()],
+            vec![],
+            &dir.path().join("alpha"),
+        ));
+        assert!(app.undoable_recent_op().is_some());
+
+        app.record_undo(UndoAction::Rename {
+            from: dir.path().join("beta"),
+            to: StampedPath::capture(dir.path().join("gamma")),
+        });
+        assert_eq!(app.undoable_recent_op(), None);
+        app.open_recent_operations();
+        assert!(matches!(
+            app.mode,
+            AppMode::Dialog(DialogKind::RecentOperations { undoable: None, .. })
+        ));
     }
 
     #[test]
-    fn search_select_clamps() {
+    fn recent_operations_empty_says_so() {
         let (_dir, mut app) = setup_app();
-        app.open_search();
-        app.search_select_previous(); // at 0, should stay
-        assert_eq!(app.search_state.selected_index, 0);
+        app.open_recent_operations();
+        assert_eq!(app.mode, AppMode::Normal);
+        assert!(app
+            .status_message
+            .as_ref()
+            .unwrap()
+            .0
+            .contains("No completed operations"));
+    }
+
+    // === Tasks ===
+
+    /// A task of `kind` that runs until the returned sender fires or is
+    /// dropped.
+    fn gated_task(
+        app: &App,
+        kind: TaskKind,
+    ) -> (crate::tasks::TaskToken, tokio::sync::oneshot::Sender<()>) {
+        let (open, gate) = tokio::sync::oneshot::channel::<()>();
+        let token = app
+            .tasks
+            .spawn(kind, "gated", Priority::User, |_| async move {
+                let _ = gate.await;
+            });
+        (token, open)
     }
 
-    #[test]
-    fn search_confirm_navigates_to_file() {
-        let (dir, mut app) = setup_app();
-        // Create a nested file for navigation test
-        fs::create_dir_all(dir.path().join("alpha").join("nested")).unwrap();
-        File::create(dir.path().join("alpha").join("nested").join("deep.txt")).unwrap();
-        app.tree_state.reload_dir(dir.path());
-        app.invalidate_search_cache();
+    #[tokio::test]
+    async fn quit_asks_twice_while_a_copy_runs() {
+        let (_dir, mut app) = setup_app();
+        let (copy, _open) = gated_task(&app, TaskKind::Transfer);
+        let (blame, _open_blame) = gated_task(&app, TaskKind::Blame);
 
-        app.open_search();
-        app.search_input_char('d');
-        app.search_input_char('e');
-        app.search_input_char('e');
-        app.search_input_char('p');
+        app.quit();
+        assert!(!app.should_quit);
+        assert!(app.status_message.as_ref().unwrap().0.contains("q again"));
 
-        assert!(!app.search_state.results.is_empty());
-        app.search_confirm();
-        assert_eq!(app.mode, AppMode::SearchAction);
-        assert!(app.search_action_state.is_some());
+        app.quit();
+        assert!(app.should_quit);
+        assert!(copy.is_cancelled());
+        assert!(blame.is_cancelled());
+    }
 
-        // Navigate from action menu
-        app.search_action_navigate();
+    #[tokio::test]
+    async fn quit_is_immediate_with_only_background_tasks() {
+        let (_dir, mut app) = setup_app();
+        let (autosave, _open) = gated_task(&app, TaskKind::Autosave);
+        app.quit();
+        assert!(app.should_quit);
+        assert!(autosave.is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn tasks_overlay_cancels_the_selected_task() {
+        let (_dir, mut app) = setup_app();
+        app.open_tasks();
         assert_eq!(app.mode, AppMode::Normal);
+        assert!(app
+            .status_message
+            .as_ref()
+            .unwrap()
+            .0
+            .contains("No background tasks"));
+
+        let (first, _open_first) = gated_task(&app, TaskKind::Blame);
+        let (second, _open_second) = gated_task(&app, TaskKind::Blame);
+        app.open_tasks();
+        match &app.mode {
+            AppMode::Dialog(DialogKind::Tasks { tasks, selected: 0 }) => {
+                assert_eq!(tasks.len(), 2);
+                // Over the blame limit: the second waits
+                assert!(tasks[1].started.is_none());
+            }
+            other => panic!("expected the tasks overlay, got {:?}", other),
+        }
 
-        // Should have navigated to the deep.txt file
-        let selected = &app.tree_state.flat_items[app.tree_state.selected_index];
-        assert_eq!(selected.name, "deep.txt");
-    }
+        app.tasks_select(true);
+        app.tasks_cancel_selected();
+        assert!(second.is_cancelled());
+        assert!(!first.is_cancelled());
 
-    #[test]
-    fn build_path_index_finds_files() {
-        let (_dir, app) = setup_app();
-        let index = app.build_path_index();
-        // Should find file_a.txt, file_b.rs, .hidden
-        assert!(index.len() >= 2);
+        app.tasks_cancel_all();
+        assert!(first.is_cancelled());
     }
 
-    #[test]
-    fn invalidate_search_cache_clears() {
+    #[tokio::test]
+    async fn cancel_operation_reaches_the_running_delete() {
         let (_dir, mut app) = setup_app();
-        app.open_search();
-        assert!(app.search_state.cached_paths.is_some());
-        app.invalidate_search_cache();
-        assert!(app.search_state.cached_paths.is_none());
+        let (delete, _open) = gated_task(&app, TaskKind::Delete);
+        let (prefetch, _open_prefetch) = gated_task(&app, TaskKind::Prefetch);
+        app.cancel_operation();
+        assert!(delete.is_cancelled());
+        assert!(!prefetch.is_cancelled());
     }
 
-    // === Filter (/) tests ===
+    #[tokio::test]
+    async fn recent_operation_retries_failed_items() {
+        let (dir, mut app) = setup_app();
+        app.handle_operation_complete(transfer_result(
+            vec![dir.path().join("file_b.rs")],
+            vec![],
+            vec![crate::error::OperationError::NoSpace {
+                path: dir.path().join("beta/file_b.rs"),
+            }],
+            &dir.path().join("beta"),
+        ));
+        app.open_recent_operations();
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        app.recent_ops_retry(tx);
+        loop {
+            if let Some(crate::event::Event::OperationComplete(result)) = rx.recv().await {
+                app.handle_operation_complete(result);
+                break;
+            }
+        }
+        assert!(dir.path().join("beta/file_b.rs").exists());
+        assert_eq!(app.recent_ops.entries().count(), 2);
+        let newest = app.recent_ops.entries().next().unwrap();
+        assert!(newest.summary().starts_with("Pasted 1 item to "));
+        assert_eq!(app.undoable_recent_op(), Some(newest.id));
+    }
 
     #[test]
-    fn start_filter_sets_mode() {
+    fn toggle_debug_overlay_flips_flag() {
         let (_dir, mut app) = setup_app();
-        app.start_filter();
-        assert_eq!(app.mode, AppMode::Filter);
+        assert!(!app.show_debug_overlay);
+        app.toggle_debug_overlay();
+        assert!(app.show_debug_overlay);
     }
 
     #[test]
-    fn filter_input_filters_tree() {
+    fn open_send_to_resolves_configured_targets() {
+        let (dir, mut app) = setup_app();
+        let backup = TempDir::new().unwrap();
+        app.config.send_to.insert(
+            "backup".to_string(),
+            backup.path().to_string_lossy().to_string(),
+        );
+        app.config.send_to.insert(
+            "gone".to_string(),
+            dir.path().join("nope").to_string_lossy().to_string(),
+        );
+        app.open_send_to();
+        assert_eq!(app.mode, AppMode::SendTo);
+        assert_eq!(app.send_to_state.targets.len(), 2);
+        assert!(app.send_to_state.targets[0].status.is_usable());
+        assert!(!app.send_to_state.targets[1].status.is_usable());
+    }
+
+    #[test]
+    fn send_to_selection_wraps_through_add_entry() {
         let (_dir, mut app) = setup_app();
-        app.start_filter();
-        let total_before = app.tree_state.flat_items.len();
-        app.filter_input_char('a');
-        // Should show fewer items (only matching + ancestors)
-        assert!(app.tree_state.flat_items.len() <= total_before);
-        assert!(app.tree_state.is_filtering);
+        app.config
+            .send_to
+            .insert("t".to_string(), "/tmp".to_string());
+        app.open_send_to();
+        app.send_to_select_next();
+        assert!(app.send_to_state.add_entry_selected());
+        app.send_to_select_next();
+        assert_eq!(app.send_to_state.selected_index, 0);
+        app.send_to_select_previous();
+        assert!(app.send_to_state.add_entry_selected());
+    }
+
+    #[tokio::test]
+    async fn send_to_dead_target_reports_error_and_stays_open() {
+        let (dir, mut app) = setup_app();
+        app.config.send_to.insert(
+            "usb".to_string(),
+            dir.path().join("unmounted").to_string_lossy().to_string(),
+        );
+        app.tree_state.selected_index = app
+            .tree_state
+            .find_index_by_path(&dir.path().join("file_a.txt"))
+            .unwrap();
+        app.open_send_to();
+        let (tx, _rx) = mpsc::unbounded_channel();
+        app.send_to_confirm(false, tx);
+        assert_eq!(app.mode, AppMode::SendTo);
+        let msg = &app.status_message.as_ref().unwrap().0;
+        assert!(msg.starts_with("Error"));
+        assert!(msg.contains("missing"));
+        assert!(dir.path().join("file_a.txt").exists());
+    }
+
+    #[tokio::test]
+    async fn send_to_copies_without_touching_clipboard() {
+        let (dir, mut app) = setup_app();
+        let backup = TempDir::new().unwrap();
+        app.config.send_to.insert(
+            "backup".to_string(),
+            backup.path().to_string_lossy().to_string(),
+        );
+        app.tree_state.selected_index = app
+            .tree_state
+            .find_index_by_path(&dir.path().join("beta"))
+            .unwrap();
+        app.cut_to_clipboard();
+        app.tree_state.selected_index = app
+            .tree_state
+            .find_index_by_path(&dir.path().join("file_a.txt"))
+            .unwrap();
+        app.open_send_to();
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        app.send_to_confirm(false, tx);
+        loop {
+            if let Some(crate::event::Event::OperationComplete(result)) = rx.recv().await {
+                assert_eq!(result.send_to.as_deref(), Some("backup"));
+                app.handle_operation_complete(result);
+                break;
+            }
+        }
+        assert!(backup.path().join("file_a.txt").exists());
+        assert!(dir.path().join("file_a.txt").exists());
+        // Clipboard contents from before are untouched
+        assert_eq!(app.clipboard.len(), 1);
+        let msg = &app.status_message.as_ref().unwrap().0;
+        assert!(msg.starts_with("Sent 1 item to backup"));
     }
 
     #[test]
-    fn filter_preserves_parent_dirs() {
+    fn add_current_dir_as_send_to_target_writes_config() {
         let (dir, mut app) = setup_app();
-        // Create inner.txt inside alpha
-        File::create(dir.path().join("alpha").join("inner.txt")).unwrap();
-        // Expand alpha so its children are loaded
-        app.tree_state.selected_index = 1; // alpha dir
-        app.expand_selected();
+        let cfg_dir = TempDir::new().unwrap();
+        let cfg_path = cfg_dir.path().join("config.toml");
+        app.tree_state.selected_index = app
+            .tree_state
+            .find_index_by_path(&dir.path().join("alpha"))
+            .unwrap();
+        app.open_send_to();
+        app.add_current_dir_as_send_to_target(&cfg_path);
 
-        app.start_filter();
-        app.filter_input_char('i');
-        app.filter_input_char('n');
-        app.filter_input_char('n');
-        app.filter_input_char('e');
-        app.filter_input_char('r');
+        let alpha = dir.path().join("alpha");
+        assert_eq!(
+            app.config.send_to.get("alpha").map(String::as_str),
+            Some(alpha.to_string_lossy().as_ref())
+        );
+        assert_eq!(app.send_to_state.targets.len(), 1);
+        let written = std::fs::read_to_string(&cfg_path).unwrap();
+        assert!(written.contains("[send_to]"));
+        assert!(written.contains("alpha"));
 
-        // "alpha" directory should be preserved as parent of "inner.txt"
-        let names: Vec<&str> = app
-            .tree_state
-            .flat_items
-            .iter()
-            .map(|i| i.name.as_str())
-            .collect();
-        assert!(names.contains(&"alpha"));
-        assert!(names.contains(&"inner.txt"));
+        // Adding the same directory again is a no-op
+        app.add_current_dir_as_send_to_target(&cfg_path);
+        assert_eq!(app.config.send_to.len(), 1);
     }
 
-    #[test]
-    fn clear_filter_restores_tree() {
-        let (_dir, mut app) = setup_app();
-        let original_count = app.tree_state.flat_items.len();
-        app.start_filter();
-        app.filter_input_char('x');
-        app.clear_filter();
-        assert_eq!(app.mode, AppMode::Normal);
-        assert!(!app.tree_state.is_filtering);
-        assert_eq!(app.tree_state.flat_items.len(), original_count);
+    /// Mount source returning a fixed list, with no free-space data.
+    struct FixtureMounts(Vec<PathBuf>);
+
+    impl MountSource for FixtureMounts {
+        fn mounts(&self) -> Vec<places::Mount> {
+            self.0
+                .iter()
+                .map(|path| places::Mount {
+                    device: "/dev/sdz1".to_string(),
+                    path: path.clone(),
+                    fs_type: "ext4".to_string(),
+                })
+                .collect()
+        }
+
+        fn disk_space(
+            &self,
+            _paths: &[PathBuf],
+        ) -> std::collections::BTreeMap<PathBuf, places::DiskSpace> {
+            Default::default()
+        }
+    }
+
+    fn open_places_at(app: &mut App, path: &Path) {
+        app.places_state = PlacesState {
+            places: vec![Place::new(
+                "target",
+                path.to_path_buf(),
+                places::PlaceKind::Bookmark,
+            )],
+            selected_index: 0,
+        };
+        app.mode = AppMode::Places;
     }
 
     #[test]
-    fn accept_filter_keeps_filtered_view() {
-        let (_dir, mut app) = setup_app();
-        app.start_filter();
-        app.filter_input_char('f');
-        let filtered_count = app.tree_state.flat_items.len();
-        app.accept_filter();
-        assert_eq!(app.mode, AppMode::Normal);
-        // Filtered view should persist
-        assert_eq!(app.tree_state.flat_items.len(), filtered_count);
+    fn open_places_lists_mounts_and_bookmarks() {
+        let (dir, mut app) = setup_app();
+        let drive = TempDir::new().unwrap();
+        app.mount_source = Box::new(FixtureMounts(vec![drive.path().to_path_buf()]));
+        app.config
+            .bookmarks
+            .insert("here".to_string(), dir.path().to_string_lossy().to_string());
+
+        app.open_places();
+        assert_eq!(app.mode, AppMode::Places);
+        let places = &app.places_state.places;
+        assert!(places.iter().any(|p| p.path == drive.path()));
+        // The entry for the current root starts out selected; bookmarks
+        // come last, so moving down wraps to the top
+        let last = places.len() - 1;
+        assert_eq!(app.places_state.selected_index, last);
+        assert_eq!(places[last].label, "here");
+        app.places_select_next();
+        assert_eq!(app.places_state.selected_index, 0);
+        app.places_select_previous();
+        assert_eq!(app.places_state.selected_index, last);
     }
 
     #[test]
-    fn filter_backspace_updates_filter() {
-        let (_dir, mut app) = setup_app();
-        let original_count = app.tree_state.flat_items.len();
-        app.start_filter();
-        app.filter_input_char('z');
-        app.filter_input_char('z');
-        app.filter_delete_char();
-        app.filter_delete_char();
-        // Should restore full tree when filter query becomes empty
-        assert!(!app.tree_state.is_filtering);
-        assert_eq!(app.tree_state.flat_items.len(), original_count);
+    fn places_confirm_navigates_within_root() {
+        let (dir, mut app) = setup_app();
+        let alpha = dir.path().join("alpha");
+        open_places_at(&mut app, &alpha);
+        app.places_confirm();
+
+        assert_eq!(app.mode, AppMode::Normal);
+        assert_eq!(app.tree_state.root.path, dir.path());
+        let selected = &app.tree_state.flat_items[app.tree_state.selected_index];
+        assert_eq!(selected.path, alpha);
     }
 
     #[test]
-    fn filter_case_insensitive() {
+    fn places_confirm_reroots_outside_root() {
         let (_dir, mut app) = setup_app();
-        app.start_filter();
-        app.filter_input_char('F');
-        app.filter_input_char('I');
-        app.filter_input_char('L');
-        app.filter_input_char('E');
-        // Should match "file_a.txt" and "file_b.rs" despite uppercase query
+        app.tree_state.show_hidden = true;
+        app.tree_state.flatten();
+        let other = TempDir::new().unwrap();
+        File::create(other.path().join(".profile")).unwrap();
+        File::create(other.path().join("notes.md")).unwrap();
+
+        open_places_at(&mut app, other.path());
+        app.places_confirm();
+
+        assert_eq!(app.mode, AppMode::Normal);
+        assert_eq!(app.tree_state.root.path, other.path());
+        assert_eq!(app.tree_state.selected_index, 0);
         let names: Vec<&str> = app
             .tree_state
             .flat_items
             .iter()
             .map(|i| i.name.as_str())
             .collect();
-        assert!(names.contains(&"file_a.txt"));
-        assert!(names.contains(&"file_b.rs"));
+        assert!(names.contains(&"notes.md"));
+        assert!(names.contains(&".profile"));
+        assert!(app.tree_state.show_hidden);
     }
 
     #[test]
-    fn navigate_to_path_expands_ancestors() {
+    fn places_confirm_rejects_missing_place() {
         let (dir, mut app) = setup_app();
-        // Create nested structure
-        fs::create_dir_all(dir.path().join("alpha").join("nested")).unwrap();
-        File::create(dir.path().join("alpha").join("nested").join("target.txt")).unwrap();
-        app.tree_state.reload_dir(dir.path());
+        open_places_at(&mut app, &dir.path().join("unplugged"));
+        app.places_confirm();
 
-        let target = dir.path().join("alpha").join("nested").join("target.txt");
-        app.navigate_to_path(&target);
-
-        let selected = &app.tree_state.flat_items[app.tree_state.selected_index];
-        assert_eq!(selected.name, "target.txt");
+        assert_eq!(app.mode, AppMode::Places);
+        assert_eq!(app.tree_state.root.path, dir.path());
+        let msg = &app.status_message.as_ref().unwrap().0;
+        assert!(msg.starts_with("Error"));
+        assert!(msg.contains("missing"));
     }
 
-    // === Filesystem watcher tests ===
-
     #[test]
-    fn handle_fs_change_detects_new_file() {
+    fn bookmarks_are_saved_and_jumped_to() {
         let (dir, mut app) = setup_app();
-        let original_count = app.tree_state.flat_items.len();
-        // Create a new file externally
-        File::create(dir.path().join("new_file.txt")).unwrap();
-        // Simulate watcher event
-        app.handle_fs_change(vec![dir.path().join("new_file.txt")]);
-        assert!(app.tree_state.flat_items.len() > original_count);
-        let names: Vec<&str> = app
-            .tree_state
-            .flat_items
-            .iter()
-            .map(|i| i.name.as_str())
-            .collect();
-        assert!(names.contains(&"new_file.txt"));
+        let config = TempDir::new().unwrap();
+        app.config_dir = Some(config.path().to_path_buf());
+
+        // Files can't be bookmarked
+        app.tree_state.selected_index = 3;
+        app.add_bookmark();
+        assert!(app.bookmarks.paths().is_empty());
+
+        app.tree_state.selected_index = 2;
+        app.add_bookmark();
+        app.add_bookmark();
+        let beta = dir.path().join("beta").canonicalize().unwrap();
+        assert_eq!(app.bookmarks.paths(), std::slice::from_ref(&beta));
+        assert!(app
+            .status_message
+            .as_ref()
+            .unwrap()
+            .0
+            .starts_with("Already bookmarked"));
+        // Written right away, so a restart sees it
+        assert_eq!(
+            crate::bookmarks::Bookmarks::load(config.path()),
+            app.bookmarks
+        );
+
+        app.tree_state.selected_index = 0;
+        app.open_bookmarks();
+        assert_eq!(app.mode, AppMode::Bookmarks);
+        app.bookmarks_confirm();
+        assert_eq!(app.mode, AppMode::Normal);
+        let selected = &app.tree_state.flat_items[app.tree_state.selected_index];
+        assert_eq!(selected.path, dir.path().join("beta"));
     }
 
     #[test]
-    fn handle_fs_change_preserves_selection() {
+    fn export_profile_packs_the_running_setup() {
         let (dir, mut app) = setup_app();
-        // Select "file_a.txt"
-        let file_a_idx = app
+        let config = TempDir::new().unwrap();
+        app.config_dir = Some(config.path().to_path_buf());
+        // Runs on the defaults, so this machine's config files stay out
+        app.safe_mode = true;
+        app.tree_state.selected_index = 1;
+        app.add_bookmark();
+
+        app.open_export_profile();
+        assert_eq!(app.mode, AppMode::Dialog(DialogKind::ExportProfile));
+        assert_eq!(app.dialog_state.input, profile::DEFAULT_FILE);
+        app.export_profile(profile::DEFAULT_FILE);
+
+        assert!(dir.path().join(profile::DEFAULT_FILE).is_file());
+        let msg = &app.status_message.as_ref().unwrap().0;
+        assert!(msg.starts_with("Exported profile (2 files)"), "{}", msg);
+        assert!(app
             .tree_state
             .flat_items
             .iter()
-            .position(|i| i.name == "file_a.txt")
-            .unwrap();
-        app.tree_state.selected_index = file_a_idx;
-
-        // Create a new file externally, trigger refresh
-        File::create(dir.path().join("zzz_newfile.txt")).unwrap();
-        app.handle_fs_change(vec![dir.path().join("zzz_newfile.txt")]);
-
-        // Selection should still point to file_a.txt
-        let selected = &app.tree_state.flat_items[app.tree_state.selected_index];
-        assert_eq!(selected.name, "file_a.txt");
+            .any(|item| item.name == profile::DEFAULT_FILE));
     }
 
     #[test]
-    fn handle_fs_change_selection_fallback_on_delete() {
+    fn missing_bookmarks_can_be_deleted() {
         let (dir, mut app) = setup_app();
-        // Select "file_a.txt"
-        let file_a_idx = app
-            .tree_state
-            .flat_items
+        let config = TempDir::new().unwrap();
+        app.config_dir = Some(config.path().to_path_buf());
+        let gone = dir.path().join("unplugged");
+        app.bookmarks.add(&gone);
+        app.bookmarks.add(&dir.path().join("alpha"));
+
+        app.open_bookmarks();
+        let statuses: Vec<PlaceStatus> = app
+            .bookmarks_state
+            .entries
             .iter()
-            .position(|i| i.name == "file_a.txt")
-            .unwrap();
-        app.tree_state.selected_index = file_a_idx;
+            .map(|e| e.status)
+            .collect();
+        assert_eq!(statuses, vec![PlaceStatus::Missing, PlaceStatus::Ready]);
 
-        // Delete file_a.txt externally
-        fs::remove_file(dir.path().join("file_a.txt")).unwrap();
-        app.handle_fs_change(vec![dir.path().join("file_a.txt")]);
+        // Enter on a missing bookmark stays in the overlay
+        app.bookmarks_confirm();
+        assert_eq!(app.mode, AppMode::Bookmarks);
+        assert!(app.status_message.as_ref().unwrap().0.contains("missing"));
 
-        // Selection should have moved to a valid index
-        assert!(app.tree_state.selected_index < app.tree_state.flat_items.len());
+        app.bookmarks_delete_selected();
+        assert_eq!(app.bookmarks_state.entries.len(), 1);
+        assert_eq!(app.bookmarks_state.selected_index, 0);
+        assert_eq!(app.bookmarks.paths(), &[dir.path().join("alpha")]);
+        assert_eq!(
+            crate::bookmarks::Bookmarks::load(config.path()).paths(),
+            &[dir.path().join("alpha")]
+        );
     }
 
     #[test]
-    fn handle_fs_change_preserves_expanded_dirs() {
-        let (dir, mut app) = setup_app();
-        // Expand "alpha" directory
-        let alpha_idx = app
+    fn unknown_open_action_warns_at_startup() {
+        let dir = TempDir::new().unwrap();
+        File::create(dir.path().join("main.rs")).unwrap();
+        let mut config = crate::config::AppConfig::default();
+        config
+            .open
+            .rules
+            .insert("rs".to_string(), "launch".to_string());
+        let mut app = App::new(dir.path(), config).unwrap();
+        let msg = &app.status_message.as_ref().unwrap().0;
+        assert!(msg.contains("unknown open action 'launch'"));
+
+        // Falls back to the default action
+        app.tree_state.selected_index = app
             .tree_state
-            .flat_items
-            .iter()
-            .position(|i| i.name == "alpha")
+            .find_index_by_path(&dir.path().join("main.rs"))
             .unwrap();
-        app.tree_state.selected_index = alpha_idx;
-        app.expand_selected();
-        let count_after_expand = app.tree_state.flat_items.len();
-
-        // Create a file in root, trigger refresh
-        File::create(dir.path().join("extra.txt")).unwrap();
-        app.handle_fs_change(vec![dir.path().join("extra.txt")]);
+        let (tx, _rx) = mpsc::unbounded_channel();
+        assert_eq!(app.open_selected_file(&tx), Some(OpenAction::PreviewFocus));
+        assert_eq!(app.focused_panel, FocusedPanel::Preview);
+    }
 
-        // alpha should still be expanded (count increased by 1 for new file)
-        assert!(app.tree_state.flat_items.len() > count_after_expand);
-        let alpha_item = app
+    #[test]
+    fn open_selected_file_ignores_directories() {
+        let (dir, mut app) = setup_app();
+        app.tree_state.selected_index = app
             .tree_state
-            .flat_items
-            .iter()
-            .find(|i| i.name == "alpha")
+            .find_index_by_path(&dir.path().join("alpha"))
             .unwrap();
-        assert!(alpha_item.is_expanded);
+        let (tx, _rx) = mpsc::unbounded_channel();
+        assert_eq!(app.open_selected_file(&tx), None);
+        assert_eq!(app.focused_panel, FocusedPanel::Tree);
     }
 
     #[test]
-    fn handle_fs_change_invalidates_search_cache() {
+    fn fs_change_flashes_added_and_modified_rows() {
         let (dir, mut app) = setup_app();
-        // Build search cache by opening and closing,
-        // then rebuild it manually since close_search invalidates.
-        app.open_search();
-        app.close_search();
-        // Rebuild the cache after close.
-        app.search_state.cached_paths = Some(app.build_path_index());
-        assert!(app.search_state.cached_paths.is_some());
+        let now = Instant::now();
+        fs::write(dir.path().join("file_b.rs"), "fn main() {}").unwrap();
+        fs::write(dir.path().join("new.txt"), "").unwrap();
+        app.handle_fs_change(vec![
+            dir.path().join("file_b.rs"),
+            dir.path().join("new.txt"),
+        ]);
 
-        // Trigger fs change in Normal mode
-        File::create(dir.path().join("cache_buster.txt")).unwrap();
-        app.handle_fs_change(vec![dir.path().join("cache_buster.txt")]);
-        assert!(app.search_state.cached_paths.is_none());
+        assert!(app
+            .flash
+            .step_for(&dir.path().join("new.txt"), now)
+            .is_some());
+        assert!(app
+            .flash
+            .step_for(&dir.path().join("file_b.rs"), now)
+            .is_some());
+        assert!(app
+            .flash
+            .step_for(&dir.path().join("file_a.txt"), now)
+            .is_none());
     }
 
     #[test]
-    fn fs_change_skipped_during_search_mode() {
-        let (_dir, mut app) = setup_app();
-        // Open search — builds path cache and sets mode to Search
-        app.open_search();
-        assert!(app.search_state.cached_paths.is_some());
-        assert_eq!(app.mode, AppMode::Search);
+    fn fs_change_removal_flashes_parent() {
+        let (dir, mut app) = setup_app();
+        let alpha = dir.path().join("alpha");
+        fs::write(alpha.join("gone.txt"), "").unwrap();
+        app.tree_state.selected_index = app.tree_state.find_index_by_path(&alpha).unwrap();
+        app.expand_selected();
+        fs::remove_file(alpha.join("gone.txt")).unwrap();
+        app.handle_fs_change(vec![alpha.join("gone.txt")]);
+        assert!(app.flash.step_for(&alpha, Instant::now()).is_some());
+    }
 
-        // While in Search mode, fs change should be silently ignored
-        app.handle_fs_change(vec![app.tree_state.root.path.clone()]);
-        // Cache must NOT be invalidated while searching
-        assert!(
-            app.search_state.cached_paths.is_some(),
-            "search cache should survive fs events during search mode"
-        );
+    #[test]
+    fn fs_change_highlight_can_be_disabled() {
+        let (dir, mut app) = setup_app();
+        app.config.watcher.highlight_changes = Some(false);
+        fs::write(dir.path().join("new.txt"), "").unwrap();
+        app.handle_fs_change(vec![dir.path().join("new.txt")]);
+        assert!(app.flash.is_empty());
     }
 
     #[test]
-    fn fs_change_skipped_during_filter_mode() {
-        let (_dir, mut app) = setup_app();
-        app.start_filter();
-        app.filter_input_char('f');
-        let filtered_count = app.tree_state.flat_items.len();
-        assert_eq!(app.mode, AppMode::Filter);
+    fn jump_to_changed_cycles_most_recent_first() {
+        let (dir, mut app) = setup_app();
+        let inner = dir.path().join("alpha").join("inner.txt");
+        fs::write(&inner, "x").unwrap();
+        let now = Instant::now();
+        let ago = |secs| now.checked_sub(Duration::from_secs(secs)).unwrap();
+        app.flash.mark(dir.path().join("file_b.rs"), ago(9));
+        app.flash.mark(inner.clone(), ago(4));
+
+        app.jump_to_changed();
+        // alpha was collapsed: expanded to reach the file
+        assert_eq!(
+            app.tree_state.flat_items[app.tree_state.selected_index].path,
+            inner
+        );
+        let msg = &app.status_message.as_ref().unwrap().0;
+        assert_eq!(msg, "changed 1/2 (4s ago)");
 
-        // While in Filter mode, fs change should be silently ignored
-        app.handle_fs_change(vec![app.tree_state.root.path.clone()]);
-        // flat_items should still be the filtered set, not the full tree
+        app.jump_to_changed();
+        let selected = &app.tree_state.flat_items[app.tree_state.selected_index];
+        assert_eq!(selected.path, dir.path().join("file_b.rs"));
+        assert!(app
+            .status_message
+            .as_ref()
+            .unwrap()
+            .0
+            .starts_with("changed 2/2"));
+
+        app.jump_to_changed();
         assert_eq!(
-            app.tree_state.flat_items.len(),
-            filtered_count,
-            "filtered view should survive fs events during filter mode"
+            app.tree_state.flat_items[app.tree_state.selected_index].path,
+            inner
         );
     }
 
     #[test]
-    fn fs_change_works_after_closing_search() {
+    fn jump_to_changed_lands_on_parent_of_deleted_path() {
         let (dir, mut app) = setup_app();
-        // Open and close search to return to Normal mode
-        app.open_search();
-        app.close_search();
-        assert_eq!(app.mode, AppMode::Normal);
-
-        // Now fs change should process normally
-        let original_count = app.tree_state.flat_items.len();
-        File::create(dir.path().join("new_file.txt")).unwrap();
-        app.handle_fs_change(vec![dir.path().join("new_file.txt")]);
-        assert!(app.tree_state.flat_items.len() > original_count);
+        app.flash
+            .mark(dir.path().join("alpha").join("gone.txt"), Instant::now());
+        app.jump_to_changed();
+        let selected = &app.tree_state.flat_items[app.tree_state.selected_index];
+        assert_eq!(selected.path, dir.path().join("alpha"));
+        let msg = &app.status_message.as_ref().unwrap().0;
+        assert!(msg.contains("deleted, showing its parent"), "{}", msg);
     }
 
     #[test]
-    fn full_refresh_reloads_tree() {
+    fn jump_to_changed_explains_filtered_out_targets() {
         let (dir, mut app) = setup_app();
-        let original_count = app.tree_state.flat_items.len();
-        // Create a file externally
-        File::create(dir.path().join("f5_file.txt")).unwrap();
-        app.full_refresh();
-        assert!(app.tree_state.flat_items.len() > original_count);
-        assert!(app.status_message.is_some());
+        app.tree_state.filter_query = "file_a".to_string();
+        app.tree_state.apply_filter();
+        let before = app.tree_state.selected_index;
+        app.flash.mark(dir.path().join("file_b.rs"), Instant::now());
+
+        app.jump_to_changed();
+        assert_eq!(app.tree_state.selected_index, before);
+        assert!(app.tree_state.is_filtering);
+        let msg = &app.status_message.as_ref().unwrap().0;
+        assert!(
+            msg.contains("file_b.rs is hidden by the filter 'file_a'"),
+            "{}",
+            msg
+        );
+
+        // A target the filter shows is selected in place
+        app.flash
+            .mark(dir.path().join("file_a.txt"), Instant::now());
+        app.changed_anchor = None;
+        app.jump_to_changed();
+        let selected = &app.tree_state.flat_items[app.tree_state.selected_index];
+        assert_eq!(selected.path, dir.path().join("file_a.txt"));
+        assert!(app.tree_state.is_filtering);
     }
 
     #[test]
-    fn toggle_watcher_flips_state() {
+    fn jump_to_changed_without_changes_says_so() {
         let (_dir, mut app) = setup_app();
-        assert!(app.watcher_active); // default on
-        let result = app.toggle_watcher();
-        assert!(!result);
-        assert!(!app.watcher_active);
-        let result2 = app.toggle_watcher();
-        assert!(result2);
-        assert!(app.watcher_active);
+        app.jump_to_changed();
+        assert_eq!(
+            app.status_message.as_ref().unwrap().0,
+            "No changes in the last 2m"
+        );
     }
 
-    #[test]
-    fn toggle_watcher_sets_status_message() {
-        let (_dir, mut app) = setup_app();
-        app.toggle_watcher();
-        let (msg, _) = app.status_message.as_ref().unwrap();
-        assert!(msg.contains("paused") || msg.contains("⏸"));
-        app.toggle_watcher();
-        let (msg, _) = app.status_message.as_ref().unwrap();
-        assert!(msg.contains("resumed") || msg.contains("👁"));
+    // === Blame gutter ===
+
+    struct CannedGit {
+        head: Option<&'static str>,
+        calls: Arc<std::sync::Mutex<Vec<(usize, usize)>>>,
     }
 
-    #[test]
-    fn handle_fs_change_preserves_sort_order() {
-        let (dir, mut app) = setup_app();
+    impl blame::GitRunner for CannedGit {
+        fn tracked_head(&self, _path: &Path) -> Option<String> {
+            self.head.map(String::from)
+        }
 
-        // Verify initial sort order: dirs first (alpha, beta), then files (file_a.txt, file_b.rs)
-        // flat_items[0] = root, [1] = alpha, [2] = beta, [3] = file_a.txt, [4] = file_b.rs
-        assert_eq!(app.tree_state.flat_items[1].name, "alpha");
-        assert_eq!(app.tree_state.flat_items[2].name, "beta");
-        assert_eq!(app.tree_state.flat_items[3].name, "file_a.txt");
-        assert_eq!(app.tree_state.flat_items[4].name, "file_b.rs");
+        fn blame_porcelain(&self, _path: &Path, start: usize, end: usize) -> Option<String> {
+            self.calls.lock().unwrap().push((start, end));
+            let mut out = String::new();
+            for line in start..=end {
+                out.push_str(&format!(
+                    "1234567890abcdef1234567890abcdef12345678 {} {} 1\nauthor-time 0\n\tx\n",
+                    line, line
+                ));
+            }
+            Some(out)
+        }
+    }
 
-        // Create a new file externally to trigger a change
-        File::create(dir.path().join("aaa_new.txt")).unwrap();
-        fs::create_dir(dir.path().join("gamma")).unwrap();
-        app.handle_fs_change(vec![
-            dir.path().join("aaa_new.txt"),
-            dir.path().join("gamma"),
-        ]);
+    /// Line ranges the canned git runner was asked to blame.
+    type BlameCalls = Arc<std::sync::Mutex<Vec<(usize, usize)>>>;
 
-        // After fs change, dirs must still appear first, alphabetically sorted
-        let names: Vec<&str> = app
+    fn setup_blame_app(head: Option<&'static str>) -> (TempDir, App, BlameCalls) {
+        let (dir, mut app) = setup_app();
+        let content: String = (1..=30).map(|i| format!("line {}\n", i)).collect();
+        fs::write(dir.path().join("file_b.rs"), content).unwrap();
+        app.tree_state.selected_index = app
             .tree_state
-            .flat_items
-            .iter()
-            .skip(1) // skip root
-            .map(|item| item.name.as_str())
-            .collect();
-
-        // Find the boundary between dirs and files
-        let dir_count = names
-            .iter()
-            .take_while(|n| {
-                app.tree_state
-                    .flat_items
-                    .iter()
-                    .find(|i| i.name == **n)
-                    .map(|i| i.node_type == crate::fs::tree::NodeType::Directory)
-                    .unwrap_or(false)
-            })
-            .count();
+            .find_index_by_path(&dir.path().join("file_b.rs"))
+            .unwrap();
+        app.preview_area = Rect::new(0, 0, 80, 12); // 10 visible lines
+        app.update_preview();
+        let calls = Arc::new(std::sync::Mutex::new(Vec::new()));
+        app.blame.runner = Arc::new(CannedGit {
+            head,
+            calls: Arc::clone(&calls),
+        });
+        app.toggle_blame();
+        (dir, app, calls)
+    }
 
-        // All directories should come first
-        assert!(
-            dir_count >= 3,
-            "Expected at least 3 dirs (alpha, beta, gamma), got {dir_count}"
-        );
+    /// Poll until the viewport counts as settled, then run one fetch.
+    async fn settle_and_fetch(app: &mut App) {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        app.poll_blame(&tx);
+        if let Some((_, since)) = app.blame.settling.as_mut() {
+            *since -= blame::BLAME_DEBOUNCE;
+        }
+        app.poll_blame(&tx);
+        drop(tx);
+        if app.blame.pending.is_some() {
+            if let Some(crate::event::Event::BlameComplete(result)) = rx.recv().await {
+                app.handle_blame_complete(result);
+            }
+        }
+    }
 
-        // Directories should be alphabetically sorted
-        let dir_names: Vec<&str> = names[..dir_count].to_vec();
-        assert_eq!(dir_names, vec!["alpha", "beta", "gamma"]);
+    #[tokio::test]
+    async fn blame_fetches_visible_range_after_settling() {
+        let (_dir, mut app, calls) = setup_blame_app(Some("head1"));
+        let (tx, _rx) = mpsc::unbounded_channel();
+        // First poll only starts the debounce
+        app.poll_blame(&tx);
+        assert!(app.blame.pending.is_none());
 
-        // Files should be alphabetically sorted
-        let file_names: Vec<&str> = names[dir_count..].to_vec();
-        let mut expected_files = file_names.clone();
-        expected_files.sort_by(|a, b| a.to_lowercase().cmp(&b.to_lowercase()));
-        assert_eq!(
-            file_names, expected_files,
-            "Files should be alphabetically sorted"
-        );
+        settle_and_fetch(&mut app).await;
+        assert_eq!(*calls.lock().unwrap(), vec![(1, 10)]);
+        let gutter = app.blame_gutter().expect("gutter shown");
+        assert_eq!(gutter.start, 1);
+        assert!(gutter.cell(10).unwrap().text.starts_with("12345678"));
+        assert_eq!(app.blame_label(), Some("blame"));
     }
 
-    #[test]
-    fn navigate_to_path_preserves_sort_order() {
-        let (dir, mut app) = setup_app();
-
-        // Create nested structure: alpha/z_file.txt, alpha/a_file.txt, alpha/nested_dir/
-        fs::create_dir_all(dir.path().join("alpha").join("nested_dir")).unwrap();
-        File::create(dir.path().join("alpha").join("z_file.txt")).unwrap();
-        File::create(dir.path().join("alpha").join("a_file.txt")).unwrap();
+    #[tokio::test]
+    async fn blame_scroll_back_uses_cache() {
+        let (_dir, mut app, calls) = setup_blame_app(Some("head1"));
+        settle_and_fetch(&mut app).await;
+        app.preview_state.scroll_offset = 5;
+        settle_and_fetch(&mut app).await;
+        app.preview_state.scroll_offset = 0;
+        settle_and_fetch(&mut app).await;
+        // The first range was served from the cache the second time
+        assert_eq!(*calls.lock().unwrap(), vec![(1, 10), (6, 15)]);
+        assert_eq!(app.blame_gutter().unwrap().start, 1);
+    }
 
-        // Navigate to a nested file — this forces alpha to expand
-        let target = dir.path().join("alpha").join("a_file.txt");
-        app.navigate_to_path(&target);
+    #[tokio::test]
+    async fn blame_untracked_file_shows_not_tracked() {
+        let (_dir, mut app, calls) = setup_blame_app(None);
+        settle_and_fetch(&mut app).await;
+        assert!(calls.lock().unwrap().is_empty());
+        assert!(app.blame_gutter().is_none());
+        assert_eq!(app.blame_label(), Some("not tracked"));
+        assert_eq!(app.status_message.as_ref().unwrap().0, "Blame: not tracked");
+    }
 
-        // Find alpha's children in the flat list
-        let alpha_children: Vec<&str> = app
-            .tree_state
-            .flat_items
-            .iter()
-            .filter(|i| i.depth == 2) // alpha's children are at depth 2
-            .map(|i| i.name.as_str())
-            .collect();
+    #[test]
+    fn blame_toggle_off_clears_gutter() {
+        let (_dir, mut app, _calls) = setup_blame_app(Some("head1"));
+        app.blame.gutter = Some(BlameGutter::default());
+        app.toggle_blame();
+        assert!(!app.blame.enabled);
+        assert!(app.blame_gutter().is_none());
+        assert_eq!(app.blame_label(), None);
+    }
 
-        // nested_dir (directory) should come first, then a_file, z_file (alphabetical)
-        assert!(
-            !alpha_children.is_empty(),
-            "Alpha should have children after navigate_to_path"
-        );
-        assert_eq!(
-            alpha_children[0], "nested_dir",
-            "Directory should come first"
-        );
+    /// App with a paginated `big/` (8 files, page size 3) loaded two pages deep.
+    fn setup_paginated_app() -> (TempDir, App) {
+        let dir = TempDir::new().unwrap();
+        let big = dir.path().join("big");
+        fs::create_dir(&big).unwrap();
+        for i in 0..8 {
+            File::create(big.join(format!("b{}.txt", i))).unwrap();
+        }
+        File::create(dir.path().join("file_a.txt")).unwrap();
+        let mut app = App::new(dir.path(), crate::config::AppConfig::default()).unwrap();
+        app.tree_state.page_size = 3;
+        app.tree_area = Rect::new(0, 0, 40, 30);
+        app.tree_state.selected_index = app.tree_state.find_index_by_path(&big).unwrap();
+        app.tree_state.expand_selected();
+        assert_eq!(app.tree_state.load_next_page(&big), 3);
+        (dir, app)
     }
 
-    #[test]
-    fn preview_scroll_with_content_and_area() {
-        let (dir, mut app) = setup_app();
-        // Write a file with many lines
-        let content: String = (0..200).map(|i| format!("line {}\n", i)).collect();
-        std::fs::write(dir.path().join("file_a.txt"), &content).unwrap();
+    fn node_at<'a>(app: &'a mut App, path: &Path) -> &'a mut crate::fs::tree::TreeNode {
+        TreeState::find_node_mut_pub(&mut app.tree_state.root, path).unwrap()
+    }
 
-        // Simulate a real terminal preview area (height=30, visible=28)
-        app.preview_area = Rect::new(40, 0, 80, 30);
+    /// Add a file to `big/` and let the watcher mark it stale.
+    fn make_big_stale(dir: &TempDir, app: &mut App) {
+        let added = dir.path().join("big").join("a_new.txt");
+        File::create(&added).unwrap();
+        app.handle_fs_change(vec![added]);
+        assert!(node_at(app, &dir.path().join("big")).is_stale);
+    }
 
-        // Select file_a.txt
-        app.tree_state.selected_index = 3;
-        app.update_preview();
+    fn go_idle(app: &mut App) {
+        app.idle_refresh.last_input = Instant::now().checked_sub(Duration::from_secs(10)).unwrap();
+    }
 
-        let content_lines_len = app.preview_state.content_lines.len();
-        let visible_height = app.preview_area.height.saturating_sub(2) as usize;
-        let expected_max = content_lines_len.saturating_sub(visible_height);
+    #[tokio::test]
+    async fn idle_refresh_reloads_stale_dir_keeping_loaded_depth() {
+        let (dir, mut app) = setup_paginated_app();
+        let big = dir.path().join("big");
+        let selected = big.join("b4.txt");
+        app.tree_state.selected_index = app.tree_state.find_index_by_path(&selected).unwrap();
+        make_big_stale(&dir, &mut app);
 
-        // Scroll down 50 times, simulating render cycle each time
-        for _ in 0..50 {
-            app.preview_scroll_down();
-            app.update_preview();
-            app.clamp_preview_scroll();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        // Input just happened: not idle yet
+        app.poll_idle_refresh(&tx);
+        assert!(!app.idle_refresh.is_busy());
+
+        go_idle(&mut app);
+        app.poll_idle_refresh(&tx);
+        assert!(app.idle_refresh.is_busy());
+        match rx.recv().await {
+            Some(crate::event::Event::IdleRefreshComplete(result)) => {
+                app.handle_idle_refresh_complete(result)
+            }
+            other => panic!("unexpected event: {:?}", other),
         }
 
-        assert_eq!(
-            app.preview_state.scroll_offset,
-            50.min(expected_max),
-            "After scrolling 50 times, offset should be 50 (or max if less)"
-        );
+        assert!(!app.idle_refresh.is_busy());
+        let node = node_at(&mut app, &big);
+        assert!(!node.is_stale);
+        // Both loaded pages came back, not just the first
+        assert_eq!(node.loaded_offset, 6);
+        assert_eq!(node.children.as_ref().unwrap().len(), 6);
+        assert_eq!(node.total_child_count, Some(9));
+        assert!(node.has_more_children);
+        assert!(app
+            .tree_state
+            .find_index_by_path(&big.join("a_new.txt"))
+            .is_some());
+        let current = &app.tree_state.flat_items[app.tree_state.selected_index];
+        assert_eq!(current.path, selected);
+    }
+
+    #[tokio::test]
+    async fn idle_refresh_aborted_by_input_leaves_dir_stale() {
+        let (dir, mut app) = setup_paginated_app();
+        make_big_stale(&dir, &mut app);
+        go_idle(&mut app);
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        app.poll_idle_refresh(&tx);
+        assert!(app.idle_refresh.is_busy());
+        app.note_input();
+        assert!(!app.idle_refresh.is_busy());
+
+        drop(tx);
+        while let Some(event) = rx.recv().await {
+            if let crate::event::Event::IdleRefreshComplete(result) = event {
+                app.handle_idle_refresh_complete(result);
+            }
+        }
+        assert!(node_at(&mut app, &dir.path().join("big")).is_stale);
+
+        // The clock restarted, so the next tick doesn't retry right away
+        let (tx, _rx) = mpsc::unbounded_channel();
+        app.poll_idle_refresh(&tx);
+        assert!(!app.idle_refresh.is_busy());
+    }
+
+    #[tokio::test]
+    async fn idle_refresh_result_dropped_after_newer_change() {
+        let (dir, mut app) = setup_paginated_app();
+        let big = dir.path().join("big");
+        make_big_stale(&dir, &mut app);
+        go_idle(&mut app);
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        app.poll_idle_refresh(&tx);
+        // Another change lands while the scan runs
+        app.handle_fs_change(vec![big.join("b0.txt")]);
+        drop(tx);
+        while let Some(event) = rx.recv().await {
+            if let crate::event::Event::IdleRefreshComplete(result) = event {
+                app.handle_idle_refresh_complete(result);
+            }
+        }
+        assert!(node_at(&mut app, &big).is_stale);
+        assert!(!app.idle_refresh.is_busy());
+    }
+
+    /// Clipboard provider that records every write.
+    #[derive(Default)]
+    struct RecordingClipboard {
+        writes: std::sync::Mutex<Vec<(Selection, String)>>,
+    }
+
+    impl ClipboardProvider for RecordingClipboard {
+        fn set_text(
+            &self,
+            selection: Selection,
+            text: &str,
+            _timeout: std::time::Duration,
+        ) -> std::result::Result<&'static str, String> {
+            self.writes
+                .lock()
+                .unwrap()
+                .push((selection, text.to_string()));
+            Ok("mock")
+        }
     }
 
-    #[test]
-    fn preview_scroll_preserved_after_fs_change() {
+    /// Open the action menu on `file_a.txt` with a recording clipboard.
+    fn setup_copy_path(primary_selection: bool) -> (TempDir, App, Arc<RecordingClipboard>, String) {
         let (dir, mut app) = setup_app();
-        // Write a file with many lines
-        let content: String = (0..200).map(|i| format!("line {}\n", i)).collect();
-        std::fs::write(dir.path().join("file_a.txt"), &content).unwrap();
+        let clipboard = Arc::new(RecordingClipboard::default());
+        app.system_clipboard = clipboard.clone();
+        app.config.general.primary_selection = Some(primary_selection);
+        app.path_style = PathStyle::Absolute;
+        let path = dir.path().join("file_a.txt");
+        app.search_action_state = Some(SearchActionState {
+            path: path.clone(),
+            display: "file_a.txt".to_string(),
+            is_directory: false,
+            is_binary: false,
+            line: None,
+            col: None,
+        });
+        app.mode = AppMode::SearchAction;
+        (dir, app, clipboard, path.to_string_lossy().to_string())
+    }
 
-        // Simulate a real terminal preview area
-        app.preview_area = Rect::new(40, 0, 80, 30);
+    async fn finish_clipboard_write(
+        app: &mut App,
+        rx: &mut mpsc::UnboundedReceiver<crate::event::Event>,
+    ) {
+        match rx.recv().await {
+            Some(crate::event::Event::ClipboardComplete(report)) => {
+                app.handle_clipboard_complete(report)
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+    }
 
-        // Select file_a.txt and update preview
-        app.tree_state.selected_index = 3;
-        app.update_preview();
+    #[tokio::test]
+    async fn copy_path_follows_the_path_style_unless_a_form_is_given() {
+        let (_dir, mut app, clipboard, text) = setup_copy_path(false);
+        let state = app.search_action_state.clone();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        app.path_style = PathStyle::Relative;
+        app.search_action_copy_path(&tx);
+        finish_clipboard_write(&mut app, &mut rx).await;
 
-        // Scroll down to position 25
-        for _ in 0..25 {
-            app.preview_scroll_down();
-        }
-        assert_eq!(app.preview_state.scroll_offset, 25);
+        app.search_action_state = state;
+        app.search_action_copy_path_as(PathStyle::Absolute, &tx);
+        finish_clipboard_write(&mut app, &mut rx).await;
+        assert_eq!(
+            *clipboard.writes.lock().unwrap(),
+            vec![
+                (Selection::Clipboard, "file_a.txt".to_string()),
+                (Selection::Clipboard, text)
+            ]
+        );
+    }
 
-        // Simulate a file watcher event (unrelated file change)
-        app.handle_fs_change(vec![dir.path().join("file_b.rs")]);
+    #[tokio::test]
+    async fn copy_path_uses_clipboard_only_by_default() {
+        let (_dir, mut app, clipboard, text) = setup_copy_path(false);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        app.search_action_copy_path(&tx);
+        assert_eq!(app.mode, AppMode::Normal);
+        finish_clipboard_write(&mut app, &mut rx).await;
+        assert_eq!(
+            *clipboard.writes.lock().unwrap(),
+            vec![(Selection::Clipboard, text.clone())]
+        );
+        assert_eq!(
+            app.status_message.as_ref().unwrap().0,
+            format!("📋 Path copied via OSC 52 + mock: {}", text)
+        );
+        assert_eq!(
+            app.pending_osc52.as_deref(),
+            system_clipboard::osc52(&text, &[Selection::Clipboard]).as_deref()
+        );
+    }
 
-        // The next render cycle calls update_preview + clamp
-        app.update_preview();
-        app.clamp_preview_scroll();
+    #[tokio::test]
+    async fn copy_selected_paths_joins_the_selection() {
+        let (dir, mut app, clipboard, _) = setup_copy_path(false);
+        app.mode = AppMode::Normal;
+        app.config.general.osc52 = Some(false);
+        for name in ["alpha", "file_a.txt"] {
+            select_named(&mut app, name);
+            app.tree_state.toggle_multi_select();
+        }
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        app.copy_selected_paths(PathStyle::Relative, &tx);
+        finish_clipboard_write(&mut app, &mut rx).await;
+        app.copy_selected_paths(PathStyle::Absolute, &tx);
+        finish_clipboard_write(&mut app, &mut rx).await;
+
+        let absolute = format!(
+            "{}\n{}",
+            dir.path().join("alpha").display(),
+            dir.path().join("file_a.txt").display()
+        );
+        assert_eq!(
+            *clipboard.writes.lock().unwrap(),
+            vec![
+                (Selection::Clipboard, "alpha\nfile_a.txt".to_string()),
+                (Selection::Clipboard, absolute)
+            ]
+        );
+        assert_eq!(app.pending_osc52, None);
+        assert_eq!(
+            app.status_message.as_ref().unwrap().0,
+            "📋 2 paths copied via mock"
+        );
+    }
 
-        // Scroll offset should be preserved since we're still viewing the same file
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn copy_path_writes_both_selections_when_enabled() {
+        let (_dir, mut app, clipboard, text) = setup_copy_path(true);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        app.search_action_copy_path(&tx);
+        finish_clipboard_write(&mut app, &mut rx).await;
         assert_eq!(
-            app.preview_state.scroll_offset, 25,
-            "Scroll offset should be preserved after FS change event for same file"
+            *clipboard.writes.lock().unwrap(),
+            vec![
+                (Selection::Clipboard, text.clone()),
+                (Selection::Primary, text.clone())
+            ]
+        );
+        assert_eq!(
+            app.status_message.as_ref().unwrap().0,
+            format!(
+                "📋 Path copied (clipboard + primary) via OSC 52 + mock: {}",
+                text
+            )
         );
     }
 
-    #[test]
-    fn preview_scroll_resets_on_different_file() {
-        let (dir, mut app) = setup_app();
-        let content: String = (0..200).map(|i| format!("line {}\n", i)).collect();
-        std::fs::write(dir.path().join("file_a.txt"), &content).unwrap();
-        std::fs::write(dir.path().join("file_b.rs"), "fn main() {}\n").unwrap();
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn copy_path_primary_only_skips_clipboard() {
+        let (_dir, mut app, clipboard, text) = setup_copy_path(false);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        app.search_action_copy_path_primary(&tx);
+        finish_clipboard_write(&mut app, &mut rx).await;
+        assert_eq!(
+            *clipboard.writes.lock().unwrap(),
+            vec![(Selection::Primary, text)]
+        );
+    }
 
-        app.preview_area = Rect::new(40, 0, 80, 30);
-        app.tree_state.selected_index = 3; // file_a.txt
-        app.update_preview();
+    // === Preview prefetch ===
 
-        // Scroll down
-        for _ in 0..25 {
-            app.preview_scroll_down();
+    /// App over `n` small source files `f1.rs` .. `fn.rs`, `f1.rs` selected.
+    fn setup_prefetch_app(n: usize) -> (TempDir, App) {
+        let dir = TempDir::new().unwrap();
+        for i in 1..=n {
+            fs::write(
+                dir.path().join(format!("f{}.rs", i)),
+                format!("fn f{}() {{}}\n", i),
+            )
+            .unwrap();
         }
-        assert_eq!(app.preview_state.scroll_offset, 25);
+        let mut app = App::new(dir.path(), crate::config::AppConfig::default()).unwrap();
+        app.tree_state.selected_index = 1;
+        (dir, app)
+    }
 
-        // Switch to a different file
-        app.tree_state.selected_index = 4; // file_b.rs
-        app.last_previewed_index = None;
+    /// Show the selected preview, let prefetch run and wait for its loads.
+    async fn settle_prefetch(app: &mut App) {
+        let (tx, mut rx) = mpsc::unbounded_channel();
         app.update_preview();
+        go_idle(app);
+        app.poll_prefetch(&tx);
+        while app.prefetch.running() > 0 {
+            match rx.recv().await {
+                Some(crate::event::Event::PrefetchComplete(result)) => {
+                    app.handle_prefetch_complete(result)
+                }
+                other => panic!("unexpected event: {:?}", other),
+            }
+        }
+    }
 
-        // Scroll should reset to 0 for different file
-        assert_eq!(
-            app.preview_state.scroll_offset, 0,
-            "Scroll should reset when switching to a different file"
-        );
+    #[tokio::test]
+    async fn prefetch_serves_sequential_navigation_from_cache() {
+        let (_dir, mut app) = setup_prefetch_app(5);
+        settle_prefetch(&mut app).await;
+        for _ in 0..4 {
+            app.select_next();
+            settle_prefetch(&mut app).await;
+        }
+        // Every file after the first was prefetched before it was shown
+        assert_eq!(app.prefetch.hits, 4);
+        assert_eq!(app.prefetch.misses, 0);
+        let stats = app.memory_stats();
+        assert_eq!((stats.prefetch_hits, stats.prefetch_misses), (4, 0));
     }
 
-    // === Directional focus navigation tests ===
+    #[tokio::test]
+    async fn prefetch_moving_far_drops_results() {
+        let (dir, mut app) = setup_prefetch_app(8);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        app.update_preview();
+        go_idle(&mut app);
+        app.poll_prefetch(&tx);
+        assert_eq!(app.prefetch.running(), 1);
+
+        // Jump away before the load reports back
+        app.tree_state.selected_index = 6;
+        app.prefetch.cancel_if_far(app.tree_state.selected_index);
+        assert_eq!(app.prefetch.running(), 0);
+        drop(tx);
+        while let Some(event) = rx.recv().await {
+            if let crate::event::Event::PrefetchComplete(result) = event {
+                app.handle_prefetch_complete(result);
+            }
+        }
+        let key =
+            CacheKey::for_path(&dir.path().join("f2.rs"), app.config.tab_width(), false).unwrap();
+        assert!(!app.preview_cache.contains(&key));
+    }
 
     #[test]
-    fn focus_left_from_preview_goes_to_tree() {
-        let (_dir, mut app) = setup_app();
-        app.focused_panel = FocusedPanel::Preview;
-        app.focus_left();
-        assert_eq!(app.focused_panel, FocusedPanel::Tree);
+    fn prefetch_respects_config_and_running_operations() {
+        let (_dir, mut app) = setup_prefetch_app(3);
+        let (tx, _rx) = mpsc::unbounded_channel();
+        app.update_preview();
+        go_idle(&mut app);
+
+        app.config.preview.prefetch = Some(false);
+        app.poll_prefetch(&tx);
+        assert_eq!(app.prefetch.running(), 0);
+
+        app.config.preview.prefetch = Some(true);
+        let (event_tx, _events) = mpsc::unbounded_channel();
+        app.in_flight.admit(
+            PendingTransfer {
+                paths: Vec::new(),
+                dest_dir: PathBuf::from("/"),
+                was_cut: false,
+                send_to: None,
+                entries: None,
+                bytes: None,
+                overwrite: Vec::new(),
+                backup: None,
+                event_tx,
+            },
+            WhenBusy::Reject,
+        );
+        app.poll_prefetch(&tx);
+        assert_eq!(app.prefetch.running(), 0);
     }
 
-    #[test]
-    fn focus_left_from_terminal_goes_to_tree() {
-        let (_dir, mut app) = setup_app();
-        app.focused_panel = FocusedPanel::Terminal;
-        app.focus_left();
-        assert_eq!(app.focused_panel, FocusedPanel::Tree);
+    // === Session autosave ===
+
+    fn make_autosave_check_due(app: &mut App) {
+        app.autosave.last_check = Instant::now().checked_sub(session::CHECK_INTERVAL).unwrap();
     }
 
-    #[test]
-    fn focus_left_from_tree_is_noop() {
-        let (_dir, mut app) = setup_app();
-        app.focused_panel = FocusedPanel::Tree;
-        app.focus_left();
-        assert_eq!(app.focused_panel, FocusedPanel::Tree);
+    async fn finish_autosave(app: &mut App, rx: &mut mpsc::UnboundedReceiver<crate::event::Event>) {
+        match rx.recv().await {
+            Some(crate::event::Event::AutosaveComplete(result)) => {
+                app.handle_autosave_complete(result)
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
     }
 
-    #[test]
-    fn focus_right_from_tree_goes_to_preview() {
-        let (_dir, mut app) = setup_app();
-        app.focused_panel = FocusedPanel::Tree;
-        app.focus_right();
-        assert_eq!(app.focused_panel, FocusedPanel::Preview);
+    #[tokio::test]
+    async fn autosave_saves_on_start_and_after_expansion() {
+        let (dir, mut app) = setup_app();
+        let session_dir = TempDir::new().unwrap();
+        app.autosave.dir = Some(session_dir.path().to_path_buf());
+        let (tx, mut rx) = mpsc::unbounded_channel();
+
+        // Not checked more than once per interval
+        app.poll_autosave(&tx);
+        assert!(!app.autosave.in_flight);
+
+        make_autosave_check_due(&mut app);
+        app.poll_autosave(&tx);
+        assert!(app.autosave.in_flight);
+        finish_autosave(&mut app, &mut rx).await;
+        let saved = session::load_autosave(session_dir.path()).unwrap();
+        assert_eq!(saved.root, app.tree_state.root.path);
+        assert!(saved.expanded.is_empty());
+
+        // Nothing changed: nothing to write
+        make_autosave_check_due(&mut app);
+        app.poll_autosave(&tx);
+        assert!(!app.autosave.in_flight);
+
+        // Expanding saves without waiting for the activity interval
+        let alpha = dir.path().join("alpha");
+        app.tree_state.selected_index = app.tree_state.find_index_by_path(&alpha).unwrap();
+        app.tree_state.expand_selected();
+        make_autosave_check_due(&mut app);
+        app.poll_autosave(&tx);
+        assert!(app.autosave.in_flight);
+        finish_autosave(&mut app, &mut rx).await;
+        let saved = session::load_autosave(session_dir.path()).unwrap();
+        assert_eq!(saved.expanded, vec![alpha.clone()]);
+        assert_eq!(saved.selected, Some(alpha));
+        // No clean-exit marker yet, so this run would be offered for recovery
+        assert!(session::recovery_offer(session_dir.path()).is_some());
     }
 
     #[test]
-    fn focus_right_from_terminal_goes_to_preview() {
-        let (_dir, mut app) = setup_app();
-        app.focused_panel = FocusedPanel::Terminal;
-        app.focus_right();
-        assert_eq!(app.focused_panel, FocusedPanel::Preview);
+    fn restore_session_applies_layout_and_selection() {
+        let (dir, mut app) = setup_app();
+        let inner = dir.path().join("alpha").join("inner.txt");
+        fs::write(&inner, "x").unwrap();
+        let snapshot = SessionSnapshot {
+            root: app.tree_state.root.path.clone(),
+            expanded: vec![dir.path().join("alpha")],
+            selected: Some(inner.clone()),
+            scroll_offset: 0,
+            sort_by: "size".to_string(),
+            dirs_first: false,
+            show_hidden: true,
+            sort_overrides: vec![session::SortOverride {
+                dir: dir.path().join("alpha"),
+                sort_by: "name".to_string(),
+                dirs_first: true,
+            }],
+            saved_at_ms: 1,
+        };
+        app.restore_session(&snapshot);
+
+        assert_eq!(app.tree_state.sort_by, crate::fs::tree::SortBy::Size);
+        assert!(!app.tree_state.dirs_first);
+        assert_eq!(
+            app.tree_state.sort_for(&dir.path().join("alpha")),
+            (crate::fs::tree::SortBy::Name, true)
+        );
+        assert!(app
+            .tree_state
+            .find_index_by_path(&dir.path().join(".hidden"))
+            .is_some());
+        let selected = &app.tree_state.flat_items[app.tree_state.selected_index];
+        assert_eq!(selected.path, inner);
+        assert!(!app.session_snapshot().differs(&snapshot));
     }
 
-    #[test]
-    fn focus_right_from_preview_is_noop() {
-        let (_dir, mut app) = setup_app();
-        app.focused_panel = FocusedPanel::Preview;
-        app.focus_right();
-        assert_eq!(app.focused_panel, FocusedPanel::Preview);
+    // === Deferred stats ===
+
+    /// Stats from disk after a delay, recording the order they were asked
+    /// for, like a slow network mount.
+    struct SlowStat {
+        delay: Duration,
+        calls: std::sync::Mutex<Vec<PathBuf>>,
     }
 
-    #[test]
-    fn focus_up_from_terminal_goes_to_tree() {
-        let (_dir, mut app) = setup_app();
-        app.focused_panel = FocusedPanel::Terminal;
-        app.focus_up();
-        assert_eq!(app.focused_panel, FocusedPanel::Tree);
+    impl StatSource for SlowStat {
+        fn stat(&self, path: &Path) -> Option<crate::fs::tree::FileMeta> {
+            std::thread::sleep(self.delay);
+            self.calls.lock().unwrap().push(path.to_path_buf());
+            SystemStat.stat(path)
+        }
     }
 
-    #[test]
-    fn focus_up_from_tree_is_noop() {
-        let (_dir, mut app) = setup_app();
-        app.focused_panel = FocusedPanel::Tree;
-        app.focus_up();
-        assert_eq!(app.focused_panel, FocusedPanel::Tree);
+    /// App sorted by size over `n` files `f000` .. whose size is their
+    /// number, with stats deferred to a slow source and 10 rows in view.
+    fn setup_deferred_app(n: usize) -> (TempDir, App, Arc<SlowStat>) {
+        let dir = TempDir::new().unwrap();
+        for i in 0..n {
+            fs::write(dir.path().join(format!("f{:03}", i)), vec![b'x'; i]).unwrap();
+        }
+        let mut config = crate::config::AppConfig::default();
+        config.tree.deferred_stats = Some("always".to_string());
+        config.tree.sort_by = Some("size".to_string());
+        let mut app = App::new(dir.path(), config).unwrap();
+        let source = Arc::new(SlowStat {
+            delay: Duration::from_millis(1),
+            calls: std::sync::Mutex::new(Vec::new()),
+        });
+        app.stat_source = source.clone();
+        app.tree_area = Rect::new(0, 0, 40, 12);
+        (dir, app, source)
     }
 
-    #[test]
-    fn focus_up_from_preview_is_noop() {
-        let (_dir, mut app) = setup_app();
-        app.focused_panel = FocusedPanel::Preview;
-        app.focus_up();
-        assert_eq!(app.focused_panel, FocusedPanel::Preview);
+    fn pending_rows(app: &App) -> usize {
+        app.tree_state
+            .flat_items
+            .iter()
+            .filter(|item| item.stat_pending)
+            .count()
     }
 
     #[test]
-    fn focus_down_from_tree_goes_to_terminal_when_visible() {
-        let (_dir, mut app) = setup_app();
-        app.terminal_state.visible = true;
-        app.focused_panel = FocusedPanel::Tree;
-        app.focus_down();
-        assert_eq!(app.focused_panel, FocusedPanel::Terminal);
+    fn deferred_load_leaves_stats_pending() {
+        let (_dir, app, source) = setup_deferred_app(5);
+        assert!(app.tree_state.defer_stats);
+        assert_eq!(pending_rows(&app), 5);
+        assert!(source.calls.lock().unwrap().is_empty());
+        assert!(app.sort_is_provisional());
+    }
+
+    #[tokio::test]
+    async fn deferred_stats_fill_the_viewport_first() {
+        let (_dir, mut app, source) = setup_deferred_app(200);
+        app.tree_state.scroll_offset = 100;
+        let visible: Vec<PathBuf> = app.tree_state.flat_items[100..110]
+            .iter()
+            .map(|item| item.path.clone())
+            .collect();
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        app.poll_stats(&tx);
+        assert_eq!(
+            app.stat_state.batches_in_flight(),
+            stat_service::MAX_IN_FLIGHT
+        );
+        let mut batches = Vec::new();
+        while batches.len() < stat_service::MAX_IN_FLIGHT {
+            match rx.recv().await {
+                Some(crate::event::Event::StatsReady(batch)) => batches.push(batch),
+                other => panic!("unexpected event: {:?}", other),
+            }
+        }
+        assert!(batches.iter().any(|b| b.paths[..10] == visible[..]));
+        let calls = source.calls.lock().unwrap().len();
+        assert_eq!(
+            calls,
+            stat_service::BATCH_SIZE * stat_service::MAX_IN_FLIGHT
+        );
+
+        for batch in batches {
+            app.handle_stats_ready(batch);
+        }
+        // Rows in view are filled while the rest of the directory waits
+        assert!(app.tree_state.flat_items[100..110]
+            .iter()
+            .all(|item| !item.stat_pending));
+        assert!(pending_rows(&app) > 0);
     }
 
-    #[test]
-    fn focus_down_from_preview_goes_to_terminal_when_visible() {
-        let (_dir, mut app) = setup_app();
-        app.terminal_state.visible = true;
-        app.focused_panel = FocusedPanel::Preview;
-        app.focus_down();
-        assert_eq!(app.focused_panel, FocusedPanel::Terminal);
+    #[tokio::test]
+    async fn size_sort_settles_once_stats_arrive() {
+        let (dir, mut app, _source) = setup_deferred_app(150);
+        let selected = dir.path().join("f042");
+        app.tree_state.selected_index = app.tree_state.find_index_by_path(&selected).unwrap();
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        loop {
+            app.poll_stats(&tx);
+            if app.stat_state.batches_in_flight() == 0 {
+                break;
+            }
+            match rx.recv().await {
+                Some(crate::event::Event::StatsReady(batch)) => app.handle_stats_ready(batch),
+                other => panic!("unexpected event: {:?}", other),
+            }
+        }
+
+        assert_eq!(pending_rows(&app), 0);
+        assert!(!app.sort_is_provisional());
+        let names: Vec<&str> = app.tree_state.flat_items[1..]
+            .iter()
+            .map(|item| item.name.as_str())
+            .collect();
+        let expected: Vec<String> = (0..150).rev().map(|i| format!("f{:03}", i)).collect();
+        assert_eq!(names, expected);
+        let current = &app.tree_state.flat_items[app.tree_state.selected_index];
+        assert_eq!(current.path, selected);
+    }
+
+    #[tokio::test]
+    async fn stats_from_before_a_reroot_are_dropped() {
+        let (dir, mut app, _source) = setup_deferred_app(3);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        app.poll_stats(&tx);
+        app.reroot(dir.path()).unwrap();
+        let Some(crate::event::Event::StatsReady(batch)) = rx.recv().await else {
+            panic!("expected a stat batch");
+        };
+        app.handle_stats_ready(batch);
+        assert_eq!(pending_rows(&app), 3);
+        assert_eq!(app.stat_state.batches_in_flight(), 0);
     }
 
-    #[test]
-    fn focus_down_is_noop_when_terminal_hidden() {
-        let (_dir, mut app) = setup_app();
-        app.terminal_state.visible = false;
-        app.focused_panel = FocusedPanel::Tree;
-        app.focus_down();
-        assert_eq!(app.focused_panel, FocusedPanel::Tree);
+    // === Tab tests ===
+
+    fn index_of(app: &App, name: &str) -> usize {
+        app.tree_state
+            .flat_items
+            .iter()
+            .position(|item| item.name == name)
+            .unwrap()
     }
 
     #[test]
-    fn focus_down_is_noop_when_already_on_terminal() {
-        let (_dir, mut app) = setup_app();
-        app.terminal_state.visible = true;
-        app.focused_panel = FocusedPanel::Terminal;
-        app.focus_down();
-        assert_eq!(app.focused_panel, FocusedPanel::Terminal);
+    fn tabs_keep_their_own_tree_preview_and_focus() {
+        let (dir, mut app) = setup_app();
+        let root_name = app.tree_state.root.name.clone();
+        app.toggle_hidden();
+        let alpha = index_of(&app, "alpha");
+        app.tree_state.selected_index = alpha;
+        app.focused_panel = FocusedPanel::Preview;
+        app.preview_state.line_wrap = true;
+
+        app.new_tab();
+        assert_eq!((app.tabs.active(), app.tabs.len()), (1, 2));
+        assert_eq!(app.tree_state.root.path, dir.path().join("alpha"));
+        assert_eq!(app.focused_panel, FocusedPanel::Tree);
+        assert!(!app.preview_state.line_wrap);
+        // View settings carry over to the new tab
+        assert!(app.tree_state.show_hidden);
+        assert_eq!(app.tabs.names("alpha"), vec![root_name.as_str(), "alpha"]);
+
+        // Changes under a parked tab show once it is switched back to
+        File::create(dir.path().join("gamma.txt")).unwrap();
+        app.cycle_tab(1);
+        app.finish_work();
+        assert_eq!(app.tabs.active(), 0);
+        assert_eq!(app.tree_state.root.path, dir.path());
+        assert_eq!(app.tree_state.selected_index, alpha);
+        assert_eq!(app.focused_panel, FocusedPanel::Preview);
+        assert!(app.preview_state.line_wrap);
+        assert!(app
+            .tree_state
+            .find_index_by_path(&dir.path().join("gamma.txt"))
+            .is_some());
+
+        app.close_tab();
+        assert_eq!((app.tabs.active(), app.tabs.len()), (0, 1));
+        assert_eq!(app.tree_state.root.path, dir.path().join("alpha"));
+        app.close_tab();
+        assert_eq!(app.tabs.len(), 1);
+        assert_eq!(
+            app.status_message.as_ref().map(|(msg, _)| msg.as_str()),
+            Some("Only one tab open")
+        );
     }
 }
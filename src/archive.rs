@@ -0,0 +1,1320 @@
+//! Zip and tar archives: the entry list for the preview panel, and
+//! extraction into a new directory.
+//!
+//! Listing reads only metadata: a zip's central directory, a tar's headers.
+//! File data is skipped, by seeking in plain files and by decompressing into
+//! the void for `.tar.gz`, where a byte budget caps the work on huge
+//! archives.
+//!
+//! Extraction refuses entries that would land outside the target: `..`
+//! components, and paths through a symlink the archive created earlier.
+//! Leading slashes are dropped, as tar does.
+
+use std::cell::Cell;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, Read, Seek, SeekFrom, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use flate2::read::{DeflateDecoder, MultiGzDecoder};
+use thiserror::Error;
+
+/// Entries listed before the rest is only counted (zip) or cut off (tar).
+pub const MAX_ENTRIES: usize = 5_000;
+
+/// Decompressed bytes a `.tar.gz` is read through before the listing stops.
+pub const MAX_DECOMPRESSED_BYTES: u64 = 256 * 1024 * 1024;
+
+/// Archive formats with a contents listing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+impl Format {
+    /// The format of `path` by its extension, if it is a listable archive.
+    pub fn detect(path: &Path) -> Option<Format> {
+        let name = path.file_name()?.to_string_lossy().to_lowercase();
+        [Format::Zip, Format::TarGz, Format::Tar]
+            .into_iter()
+            .find(|format| format.extensions().iter().any(|ext| name.ends_with(ext)))
+    }
+
+    /// Extension the format was detected by.
+    fn extensions(self) -> &'static [&'static str] {
+        match self {
+            Format::Zip => &[".zip", ".jar"],
+            Format::Tar => &[".tar"],
+            Format::TarGz => &[".tar.gz", ".tgz"],
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Format::Zip => "zip",
+            Format::Tar => "tar",
+            Format::TarGz => "tar.gz",
+        }
+    }
+}
+
+/// Directory an archive extracts into: next to it, named after it without
+/// the archive extension (`site.tar.gz` → `site`).
+pub fn extract_target(archive: &Path, format: Format) -> Option<PathBuf> {
+    let name = archive.file_name()?.to_string_lossy();
+    let stem_len = format
+        .extensions()
+        .iter()
+        .find(|ext| {
+            name.len() >= ext.len()
+                && name.as_bytes()[name.len() - ext.len()..].eq_ignore_ascii_case(ext.as_bytes())
+        })
+        .map_or(name.len(), |ext| name.len() - ext.len());
+    let stem = match &name[..stem_len] {
+        "" => format!("{}_extracted", name),
+        stem => stem.to_string(),
+    };
+    Some(archive.with_file_name(stem))
+}
+
+/// One member of an archive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// Path inside the archive, `/`-separated, without a trailing slash.
+    pub path: String,
+    pub is_dir: bool,
+    pub size: u64,
+    /// Stored size; zip only.
+    pub compressed: Option<u64>,
+}
+
+/// Entries past the listed ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Remaining {
+    /// The listing is complete.
+    None,
+    /// This many more, known from the zip central directory.
+    Counted(u64),
+    /// The listing stopped at the entry cap or the decompression budget.
+    Unknown,
+}
+
+/// The entries read from an archive.
+#[derive(Debug)]
+pub struct Listing {
+    pub entries: Vec<Entry>,
+    pub remaining: Remaining,
+}
+
+/// Why an archive couldn't be listed.
+#[derive(Debug, Error)]
+pub enum ArchiveError {
+    #[error("{0}")]
+    Io(#[from] io::Error),
+
+    #[error("corrupt archive: {0}")]
+    Corrupt(&'static str),
+
+    #[error("unsupported: {0}")]
+    Unsupported(String),
+
+    #[error("unsafe entry path: {0}")]
+    UnsafePath(String),
+
+    #[error("cancelled")]
+    Cancelled,
+}
+
+/// List the entries of the archive at `path`, at most `max_entries` of them.
+pub fn list(path: &Path, format: Format, max_entries: usize) -> Result<Listing, ArchiveError> {
+    let file = File::open(path)?;
+    let (entries, remaining) = match format {
+        Format::Zip => read_zip(file, max_entries)?,
+        Format::Tar => read_tar(&mut SeekSkip(BufReader::new(file)), max_entries)?,
+        Format::TarGz => {
+            let decoder = MultiGzDecoder::new(BufReader::new(file));
+            let mut reader = ReadSkip {
+                inner: decoder.take(MAX_DECOMPRESSED_BYTES),
+            };
+            read_tar(&mut reader, max_entries)?
+        }
+    };
+    Ok(Listing { entries, remaining })
+}
+
+// --- zip ---
+
+const EOCD_SIGNATURE: u32 = 0x0605_4b50;
+const EOCD_LEN: usize = 22;
+const ZIP64_LOCATOR_SIGNATURE: u32 = 0x0706_4b50;
+const ZIP64_LOCATOR_LEN: u64 = 20;
+const ZIP64_EOCD_SIGNATURE: u32 = 0x0606_4b50;
+const CENTRAL_HEADER_SIGNATURE: u32 = 0x0201_4b50;
+const CENTRAL_HEADER_LEN: usize = 46;
+const ZIP64_EXTRA_ID: u16 = 0x0001;
+
+fn le16(bytes: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([bytes[at], bytes[at + 1]])
+}
+
+fn le32(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+}
+
+fn le64(bytes: &[u8], at: usize) -> u64 {
+    u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap())
+}
+
+/// Where the central directory is and how many entries it holds.
+struct CentralDirectory {
+    offset: u64,
+    entries: u64,
+}
+
+/// A central directory entry, with what extraction needs to find and
+/// decode its data.
+struct ZipMember {
+    entry: Entry,
+    method: u16,
+    flags: u16,
+    /// Offset of the member's local header.
+    offset: u64,
+    /// Unix mode, for archives made on Unix.
+    mode: Option<u32>,
+}
+
+fn read_zip(mut file: File, max_entries: usize) -> Result<(Vec<Entry>, Remaining), ArchiveError> {
+    let (members, total) = read_zip_directory(&mut file, max_entries)?;
+    let remaining = match total - members.len() as u64 {
+        0 => Remaining::None,
+        more => Remaining::Counted(more),
+    };
+    Ok((members.into_iter().map(|m| m.entry).collect(), remaining))
+}
+
+/// Read up to `max_entries` central directory entries, and the number the
+/// directory holds.
+fn read_zip_directory(
+    file: &mut File,
+    max_entries: usize,
+) -> Result<(Vec<ZipMember>, u64), ArchiveError> {
+    let directory = find_central_directory(file)?;
+    file.seek(SeekFrom::Start(directory.offset))?;
+    let mut reader = BufReader::new(file);
+
+    let listed = directory.entries.min(max_entries as u64);
+    let mut members = Vec::with_capacity(listed.min(MAX_ENTRIES as u64) as usize);
+    let mut header = [0u8; CENTRAL_HEADER_LEN];
+    for _ in 0..listed {
+        reader
+            .read_exact(&mut header)
+            .map_err(|_| ArchiveError::Corrupt("central directory is truncated"))?;
+        if le32(&header, 0) != CENTRAL_HEADER_SIGNATURE {
+            return Err(ArchiveError::Corrupt("bad central directory entry"));
+        }
+        let mut compressed = u64::from(le32(&header, 20));
+        let mut size = u64::from(le32(&header, 24));
+        let mut offset = u64::from(le32(&header, 42));
+        let name_len = usize::from(le16(&header, 28));
+        let extra_len = usize::from(le16(&header, 30));
+        let comment_len = usize::from(le16(&header, 32));
+
+        let mut variable = vec![0u8; name_len + extra_len + comment_len];
+        reader
+            .read_exact(&mut variable)
+            .map_err(|_| ArchiveError::Corrupt("central directory is truncated"))?;
+        let name = String::from_utf8_lossy(&variable[..name_len]).into_owned();
+        zip64_fields(
+            &variable[name_len..name_len + extra_len],
+            &mut size,
+            &mut compressed,
+            &mut offset,
+        );
+
+        let is_dir = name.ends_with('/');
+        let made_on_unix = le16(&header, 4) >> 8 == 3;
+        members.push(ZipMember {
+            entry: Entry {
+                path: name.trim_end_matches('/').to_string(),
+                is_dir,
+                size,
+                compressed: Some(compressed),
+            },
+            method: le16(&header, 10),
+            flags: le16(&header, 8),
+            offset,
+            mode: made_on_unix.then(|| le32(&header, 38) >> 16),
+        });
+    }
+    Ok((members, directory.entries))
+}
+
+/// Locate the central directory from the end-of-central-directory record,
+/// following the zip64 locator when the classic fields overflowed.
+fn find_central_directory(file: &mut File) -> Result<CentralDirectory, ArchiveError> {
+    let len = file.metadata()?.len();
+    // The record is followed by a comment of at most u16::MAX bytes
+    let tail_len = len.min((EOCD_LEN + u16::MAX as usize) as u64);
+    if tail_len < EOCD_LEN as u64 {
+        return Err(ArchiveError::Corrupt("too short for a zip file"));
+    }
+    let tail_start = len - tail_len;
+    file.seek(SeekFrom::Start(tail_start))?;
+    let mut tail = vec![0u8; tail_len as usize];
+    file.read_exact(&mut tail)?;
+
+    let eocd = (0..=tail.len() - EOCD_LEN)
+        .rev()
+        .find(|&at| le32(&tail, at) == EOCD_SIGNATURE)
+        .ok_or(ArchiveError::Corrupt("no end of central directory record"))?;
+    let record = &tail[eocd..];
+    let entries = le16(record, 10);
+    let offset = le32(record, 16);
+
+    if entries != u16::MAX && offset != u32::MAX {
+        if u64::from(offset) > len {
+            return Err(ArchiveError::Corrupt(
+                "central directory offset past the end",
+            ));
+        }
+        return Ok(CentralDirectory {
+            offset: u64::from(offset),
+            entries: u64::from(entries),
+        });
+    }
+
+    let eocd_pos = tail_start + eocd as u64;
+    let locator_pos = eocd_pos
+        .checked_sub(ZIP64_LOCATOR_LEN)
+        .ok_or(ArchiveError::Corrupt("missing zip64 locator"))?;
+    let mut locator = [0u8; ZIP64_LOCATOR_LEN as usize];
+    file.seek(SeekFrom::Start(locator_pos))?;
+    file.read_exact(&mut locator)?;
+    if le32(&locator, 0) != ZIP64_LOCATOR_SIGNATURE {
+        return Err(ArchiveError::Corrupt("missing zip64 locator"));
+    }
+    let mut record = [0u8; 56];
+    file.seek(SeekFrom::Start(le64(&locator, 8)))?;
+    file.read_exact(&mut record)
+        .map_err(|_| ArchiveError::Corrupt("zip64 record is truncated"))?;
+    if le32(&record, 0) != ZIP64_EOCD_SIGNATURE {
+        return Err(ArchiveError::Corrupt("bad zip64 record"));
+    }
+    let offset = le64(&record, 48);
+    if offset > len {
+        return Err(ArchiveError::Corrupt(
+            "central directory offset past the end",
+        ));
+    }
+    Ok(CentralDirectory {
+        offset,
+        entries: le64(&record, 32),
+    })
+}
+
+/// Replace the 32-bit fields that overflowed with those of the zip64 extra
+/// field, which holds only the overflowed ones: uncompressed size,
+/// compressed size, then local header offset.
+fn zip64_fields(mut extra: &[u8], size: &mut u64, compressed: &mut u64, offset: &mut u64) {
+    while extra.len() >= 4 {
+        let id = le16(extra, 0);
+        let len = usize::from(le16(extra, 2));
+        let data = &extra[4..(4 + len).min(extra.len())];
+        if id == ZIP64_EXTRA_ID {
+            let mut fields = data.chunks_exact(8).map(|field| le64(field, 0));
+            for value in [size, compressed, offset] {
+                if *value == u64::from(u32::MAX) {
+                    if let Some(field) = fields.next() {
+                        *value = field;
+                    }
+                }
+            }
+            return;
+        }
+        extra = &extra[(4 + len).min(extra.len())..];
+    }
+}
+
+// --- tar ---
+
+const BLOCK: usize = 512;
+
+/// A tar stream that can pass over member data.
+trait TarSource {
+    /// Fill `block`; `Ok(false)` at a clean end of stream.
+    fn block(&mut self, block: &mut [u8; BLOCK]) -> io::Result<bool>;
+    fn skip(&mut self, bytes: u64) -> io::Result<()>;
+    /// Whether the stream was cut short by the decompression budget.
+    fn exhausted(&self) -> bool {
+        false
+    }
+}
+
+fn read_block(reader: &mut impl Read, block: &mut [u8; BLOCK]) -> io::Result<bool> {
+    let mut filled = 0;
+    while filled < BLOCK {
+        match reader.read(&mut block[filled..]) {
+            Ok(0) if filled == 0 => return Ok(false),
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(true)
+}
+
+/// Plain tar: data is skipped by seeking.
+struct SeekSkip<R>(R);
+
+impl<R: Read + Seek> TarSource for SeekSkip<R> {
+    fn block(&mut self, block: &mut [u8; BLOCK]) -> io::Result<bool> {
+        read_block(&mut self.0, block)
+    }
+
+    fn skip(&mut self, bytes: u64) -> io::Result<()> {
+        let bytes =
+            i64::try_from(bytes).map_err(|_| io::Error::from(io::ErrorKind::InvalidData))?;
+        self.0.seek(SeekFrom::Current(bytes)).map(|_| ())
+    }
+}
+
+/// Compressed tar: data is decompressed and dropped, within the budget.
+struct ReadSkip<R> {
+    inner: io::Take<R>,
+}
+
+impl<R: Read> TarSource for ReadSkip<R> {
+    fn block(&mut self, block: &mut [u8; BLOCK]) -> io::Result<bool> {
+        read_block(&mut self.inner, block)
+    }
+
+    fn skip(&mut self, bytes: u64) -> io::Result<()> {
+        let skipped = io::copy(&mut (&mut self.inner).take(bytes), &mut io::sink())?;
+        if skipped < bytes && !self.exhausted() {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        Ok(())
+    }
+
+    fn exhausted(&self) -> bool {
+        self.inner.limit() == 0
+    }
+}
+
+/// A tar member, with GNU long names and pax paths applied.
+struct TarMember {
+    /// Path as stored, less a leading `./` and trailing slashes.
+    path: String,
+    kind: u8,
+    is_dir: bool,
+    size: u64,
+    mode: u32,
+    /// Target of a symlink or hard link.
+    link: String,
+    /// Bytes of data (padded to whole blocks) after the header.
+    data: u64,
+}
+
+fn truncated(e: io::Error) -> ArchiveError {
+    match e.kind() {
+        io::ErrorKind::UnexpectedEof => ArchiveError::Corrupt("tar stream is truncated"),
+        _ => ArchiveError::Io(e),
+    }
+}
+
+fn read_tar(
+    source: &mut impl TarSource,
+    max_entries: usize,
+) -> Result<(Vec<Entry>, Remaining), ArchiveError> {
+    let mut entries = Vec::new();
+    let mut remaining = Remaining::None;
+    let walked = walk_tar(source, |member, source| {
+        if entries.len() == max_entries {
+            remaining = Remaining::Unknown;
+            return Ok(false);
+        }
+        if !member.path.is_empty() {
+            entries.push(Entry {
+                size: if member.is_dir { 0 } else { member.size },
+                path: member.path,
+                is_dir: member.is_dir,
+                compressed: None,
+            });
+        }
+        source.skip(member.data).map_err(truncated)?;
+        Ok(true)
+    });
+    match walked {
+        // Running out of budget reads as a truncated stream; it isn't one
+        _ if source.exhausted() => Ok((entries, Remaining::Unknown)),
+        Ok(()) => Ok((entries, remaining)),
+        Err(e) => Err(e),
+    }
+}
+
+/// Call `visit` with each member until the end of the archive or until it
+/// returns `false`. `visit` must consume the member's `data` bytes.
+fn walk_tar<S: TarSource>(
+    source: &mut S,
+    mut visit: impl FnMut(TarMember, &mut S) -> Result<bool, ArchiveError>,
+) -> Result<(), ArchiveError> {
+    let mut block = [0u8; BLOCK];
+    // Name and link target from GNU long-name or pax headers, for the next
+    // member
+    let mut long_name: Option<String> = None;
+    let mut long_link: Option<String> = None;
+
+    while source.block(&mut block).map_err(truncated)? {
+        if block.iter().all(|&b| b == 0) {
+            break;
+        }
+        if !checksum_ok(&block) {
+            return Err(ArchiveError::Corrupt("bad tar header checksum"));
+        }
+        let size = parse_size(&block[124..136]).ok_or(ArchiveError::Corrupt("bad tar size"))?;
+        let padded = size.div_ceil(BLOCK as u64) * BLOCK as u64;
+        let kind = block[156];
+
+        match kind {
+            b'L' | b'K' | b'x' => {
+                let mut data = vec![0u8; size.min(64 * 1024) as usize];
+                read_exact_source(source, &mut data).map_err(truncated)?;
+                let read = data.len().div_ceil(BLOCK) * BLOCK;
+                source.skip(padded - read as u64).map_err(truncated)?;
+                let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+                let text = String::from_utf8_lossy(&data[..end]).into_owned();
+                match kind {
+                    b'L' => long_name = Some(text),
+                    b'K' => long_link = Some(text),
+                    _ => {
+                        long_name = pax_record(&data, "path").or(long_name);
+                        long_link = pax_record(&data, "linkpath").or(long_link);
+                    }
+                }
+                continue;
+            }
+            b'g' => {
+                source.skip(padded).map_err(truncated)?;
+                continue;
+            }
+            _ => {}
+        }
+
+        let name = long_name
+            .take()
+            .unwrap_or_else(|| field_string(&block, 0..100, true));
+        let link = long_link
+            .take()
+            .unwrap_or_else(|| field_string(&block, 157..257, false));
+        let is_dir = kind == b'5' || name.ends_with('/');
+        let member = TarMember {
+            path: name
+                .trim_start_matches("./")
+                .trim_end_matches('/')
+                .to_string(),
+            kind,
+            is_dir,
+            size,
+            mode: parse_octal(&block[100..108]).map_or(0o644, |mode| mode as u32 & 0o777),
+            link,
+            // Links and directories carry no data even when a size is set
+            data: if matches!(kind, b'1' | b'2' | b'5') {
+                0
+            } else {
+                padded
+            },
+        };
+        if !visit(member, source)? {
+            break;
+        }
+    }
+    Ok(())
+}
+
+fn read_exact_source(source: &mut impl TarSource, data: &mut [u8]) -> io::Result<()> {
+    let mut block = [0u8; BLOCK];
+    for chunk in data.chunks_mut(BLOCK) {
+        if !source.block(&mut block)? {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        chunk.copy_from_slice(&block[..chunk.len()]);
+    }
+    Ok(())
+}
+
+/// A NUL-terminated header field; with `prefixed`, joined to the ustar
+/// prefix when there is one.
+fn field_string(block: &[u8; BLOCK], range: std::ops::Range<usize>, prefixed: bool) -> String {
+    let field = |bytes: &[u8]| {
+        let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+        String::from_utf8_lossy(&bytes[..end]).into_owned()
+    };
+    let value = field(&block[range]);
+    if prefixed && &block[257..262] == b"ustar" {
+        let prefix = field(&block[345..500]);
+        if !prefix.is_empty() {
+            return format!("{prefix}/{value}");
+        }
+    }
+    value
+}
+
+/// The header checksum: the byte sum with the checksum field read as spaces.
+fn checksum_ok(block: &[u8; BLOCK]) -> bool {
+    let Some(stored) = parse_octal(&block[148..156]) else {
+        return false;
+    };
+    let sum: u64 = block
+        .iter()
+        .enumerate()
+        .map(|(i, &b)| {
+            if (148..156).contains(&i) {
+                u64::from(b' ')
+            } else {
+                u64::from(b)
+            }
+        })
+        .sum();
+    sum == stored
+}
+
+/// A size field: octal, or big-endian base-256 when the high bit is set.
+fn parse_size(field: &[u8]) -> Option<u64> {
+    if field[0] & 0x80 != 0 {
+        return field[1..]
+            .iter()
+            .try_fold(u64::from(field[0] & 0x7f), |acc, &b| {
+                acc.checked_mul(256).map(|acc| acc | u64::from(b))
+            });
+    }
+    parse_octal(field)
+}
+
+fn parse_octal(field: &[u8]) -> Option<u64> {
+    let text = std::str::from_utf8(field).ok()?;
+    let text = text.trim_matches(|c: char| c == '\0' || c == ' ');
+    if text.is_empty() {
+        return Some(0);
+    }
+    u64::from_str_radix(text, 8).ok()
+}
+
+/// The `key` record of pax extended header data.
+fn pax_record(data: &[u8], key: &str) -> Option<String> {
+    let text = String::from_utf8_lossy(data);
+    let prefix = format!("{key}=");
+    text.lines().find_map(|record| {
+        let (_, keyword_value) = record.split_once(' ')?;
+        keyword_value.strip_prefix(&prefix).map(str::to_string)
+    })
+}
+
+// --- extraction ---
+
+/// Where an extraction is, for its progress report.
+pub struct Extracting<'a> {
+    /// Entry just written.
+    pub entry: &'a str,
+    /// Entries written so far.
+    pub done: usize,
+    /// Entries in the archive, when known up front (zip).
+    pub total: Option<usize>,
+    /// Bytes of the archive file read so far.
+    pub read: u64,
+}
+
+/// Extract the archive at `path` into the new directory `dest`, calling
+/// `progress` after each entry. Returns the number of entries written.
+///
+/// `dest` must not exist. On failure or cancellation the partial tree is
+/// removed again.
+pub fn extract(
+    path: &Path,
+    format: Format,
+    dest: &Path,
+    progress: &mut dyn FnMut(&Extracting),
+    cancel: &AtomicBool,
+) -> Result<usize, ArchiveError> {
+    let file = File::open(path)?;
+    fs::create_dir(dest)?;
+    let result = match format {
+        Format::Zip => extract_zip(file, dest, progress, cancel),
+        Format::Tar | Format::TarGz => {
+            let read = Rc::new(Cell::new(0));
+            let counted = BufReader::new(Counted {
+                inner: file,
+                read: Rc::clone(&read),
+            });
+            if format == Format::Tar {
+                extract_tar(&mut SeekSkip(counted), dest, &read, progress, cancel)
+            } else {
+                let mut source = ReadSkip {
+                    inner: MultiGzDecoder::new(counted).take(u64::MAX),
+                };
+                extract_tar(&mut source, dest, &read, progress, cancel)
+            }
+        }
+    };
+    if result.is_err() {
+        let _ = fs::remove_dir_all(dest);
+    }
+    result
+}
+
+/// A reader that keeps its position in `read`, for progress.
+struct Counted<R> {
+    inner: R,
+    read: Rc<Cell<u64>>,
+}
+
+impl<R: Read> Read for Counted<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.read.set(self.read.get() + n as u64);
+        Ok(n)
+    }
+}
+
+impl<R: Seek> Seek for Counted<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let position = self.inner.seek(pos)?;
+        self.read.set(position);
+        Ok(position)
+    }
+}
+
+const LOCAL_HEADER_SIGNATURE: u32 = 0x0403_4b50;
+const LOCAL_HEADER_LEN: usize = 30;
+const S_IFMT: u32 = 0o170_000;
+const S_IFLNK: u32 = 0o120_000;
+/// Longest symlink target read from an archive.
+const MAX_LINK_LEN: u64 = 4096;
+
+fn extract_zip(
+    mut file: File,
+    dest: &Path,
+    progress: &mut dyn FnMut(&Extracting),
+    cancel: &AtomicBool,
+) -> Result<usize, ArchiveError> {
+    let (members, _) = read_zip_directory(&mut file, usize::MAX)?;
+    let total = members.len();
+    let mut reader = BufReader::new(file);
+    for (done, member) in members.iter().enumerate() {
+        if cancel.load(Ordering::Relaxed) {
+            return Err(ArchiveError::Cancelled);
+        }
+        let entry = &member.entry;
+        if let Some(target) = entry_target(dest, &entry.path)? {
+            if entry.is_dir {
+                create_parents(dest, &target)?;
+                ensure_dir(&target)?;
+            } else {
+                extract_zip_member(&mut reader, member, dest, &target, cancel)?;
+            }
+        }
+        progress(&Extracting {
+            entry: &entry.path,
+            done: done + 1,
+            total: Some(total),
+            read: member.offset + entry.compressed.unwrap_or(0),
+        });
+    }
+    Ok(total)
+}
+
+fn extract_zip_member(
+    reader: &mut BufReader<File>,
+    member: &ZipMember,
+    dest: &Path,
+    target: &Path,
+    cancel: &AtomicBool,
+) -> Result<(), ArchiveError> {
+    if member.flags & 1 != 0 {
+        return Err(ArchiveError::Unsupported(format!(
+            "{} is encrypted",
+            member.entry.path
+        )));
+    }
+    reader.seek(SeekFrom::Start(member.offset))?;
+    let mut header = [0u8; LOCAL_HEADER_LEN];
+    reader
+        .read_exact(&mut header)
+        .map_err(|_| ArchiveError::Corrupt("local header is truncated"))?;
+    if le32(&header, 0) != LOCAL_HEADER_SIGNATURE {
+        return Err(ArchiveError::Corrupt("bad local header"));
+    }
+    let skip = i64::from(le16(&header, 26)) + i64::from(le16(&header, 28));
+    reader.seek(SeekFrom::Current(skip))?;
+
+    let stored = reader.take(member.entry.compressed.unwrap_or(0));
+    let mut data: Box<dyn Read + '_> = match member.method {
+        0 => Box::new(stored),
+        8 => Box::new(DeflateDecoder::new(stored)),
+        method => {
+            return Err(ArchiveError::Unsupported(format!(
+                "compression method {} ({})",
+                method, member.entry.path
+            )))
+        }
+    };
+
+    create_parents(dest, target)?;
+    clear_existing(target)?;
+    let mode = member.mode.unwrap_or(0);
+    if mode & S_IFMT == S_IFLNK {
+        let mut link = String::new();
+        (&mut data)
+            .take(MAX_LINK_LEN)
+            .read_to_string(&mut link)
+            .map_err(|_| ArchiveError::Corrupt("bad symlink entry"))?;
+        std::os::unix::fs::symlink(link, target)?;
+        return Ok(());
+    }
+    let mode = match mode & 0o777 {
+        0 => 0o644,
+        mode => mode,
+    };
+    let written = write_file(target, mode, &mut data, cancel)?;
+    if written != member.entry.size {
+        return Err(ArchiveError::Corrupt("entry size mismatch"));
+    }
+    Ok(())
+}
+
+fn extract_tar<S: TarSource>(
+    source: &mut S,
+    dest: &Path,
+    read: &Cell<u64>,
+    progress: &mut dyn FnMut(&Extracting),
+    cancel: &AtomicBool,
+) -> Result<usize, ArchiveError> {
+    let mut done = 0;
+    walk_tar(source, |member, source| {
+        if cancel.load(Ordering::Relaxed) {
+            return Err(ArchiveError::Cancelled);
+        }
+        let Some(target) = entry_target(dest, &member.path)? else {
+            source.skip(member.data).map_err(truncated)?;
+            return Ok(true);
+        };
+        create_parents(dest, &target)?;
+        // Data bytes read while writing the entry
+        let mut consumed = 0;
+        match member.kind {
+            _ if member.is_dir => ensure_dir(&target)?,
+            b'2' => {
+                clear_existing(&target)?;
+                std::os::unix::fs::symlink(&member.link, &target)?;
+            }
+            b'1' => {
+                let original = entry_target(dest, &member.link)?
+                    .ok_or_else(|| ArchiveError::UnsafePath(member.link.clone()))?;
+                create_parents(dest, &original)?;
+                clear_existing(&target)?;
+                fs::hard_link(original, &target)?;
+            }
+            b'0' | b'\0' | b'7' => {
+                clear_existing(&target)?;
+                let mut data = TarData {
+                    source: &mut *source,
+                    left: member.size,
+                    block: [0; BLOCK],
+                    at: BLOCK,
+                };
+                write_file(&target, member.mode, &mut data, cancel).map_err(|e| match e {
+                    ArchiveError::Io(e) => truncated(e),
+                    e => e,
+                })?;
+                // Whole blocks were read, padding included
+                consumed = member.data;
+            }
+            // Devices and fifos aren't recreated
+            _ => {}
+        }
+        source.skip(member.data - consumed).map_err(truncated)?;
+        done += 1;
+        progress(&Extracting {
+            entry: &member.path,
+            done,
+            total: None,
+            read: read.get(),
+        });
+        Ok(true)
+    })?;
+    Ok(done)
+}
+
+/// The data of a tar member, read block by block from its source.
+struct TarData<'a, S> {
+    source: &'a mut S,
+    /// Data bytes not yet returned.
+    left: u64,
+    block: [u8; BLOCK],
+    /// Read position in `block`.
+    at: usize,
+}
+
+impl<S: TarSource> Read for TarData<'_, S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.left == 0 || buf.is_empty() {
+            return Ok(0);
+        }
+        if self.at == BLOCK {
+            if !self.source.block(&mut self.block)? {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            self.at = 0;
+        }
+        let n = buf.len().min(BLOCK - self.at).min(self.left as usize);
+        buf[..n].copy_from_slice(&self.block[self.at..self.at + n]);
+        self.at += n;
+        self.left -= n as u64;
+        Ok(n)
+    }
+}
+
+/// Where entry `name` goes under `dest`; `None` for the archive root
+/// itself. `..` is refused; leading slashes are dropped.
+fn entry_target(dest: &Path, name: &str) -> Result<Option<PathBuf>, ArchiveError> {
+    let mut target = dest.to_path_buf();
+    let mut inside = false;
+    for part in name.split(['/', '\\']) {
+        match part {
+            "" | "." => {}
+            ".." => return Err(ArchiveError::UnsafePath(name.to_string())),
+            part => {
+                target.push(part);
+                inside = true;
+            }
+        }
+    }
+    Ok(inside.then_some(target))
+}
+
+/// Create the directories between `dest` and `target`, refusing to go
+/// through anything that isn't a real directory, such as a symlink the
+/// archive made.
+fn create_parents(dest: &Path, target: &Path) -> Result<(), ArchiveError> {
+    let Some(relative) = target.parent().and_then(|p| p.strip_prefix(dest).ok()) else {
+        return Ok(());
+    };
+    let mut dir = dest.to_path_buf();
+    for part in relative.components() {
+        dir.push(part);
+        ensure_dir(&dir)?;
+    }
+    Ok(())
+}
+
+/// Make `dir` a directory unless it is one already.
+fn ensure_dir(dir: &Path) -> Result<(), ArchiveError> {
+    match fs::symlink_metadata(dir) {
+        Ok(meta) if meta.is_dir() => Ok(()),
+        Ok(_) => Err(ArchiveError::UnsafePath(dir.display().to_string())),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(fs::create_dir(dir)?),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Remove a file or link at `target` left by an earlier entry of the same
+/// name, so that writing never follows a link.
+fn clear_existing(target: &Path) -> Result<(), ArchiveError> {
+    match fs::symlink_metadata(target) {
+        Ok(meta) if meta.is_dir() => Err(ArchiveError::UnsafePath(target.display().to_string())),
+        Ok(_) => Ok(fs::remove_file(target)?),
+        Err(_) => Ok(()),
+    }
+}
+
+/// Write `data` to the new file `target`, checking `cancel` between
+/// chunks. Returns the bytes written.
+fn write_file(
+    target: &Path,
+    mode: u32,
+    data: &mut dyn Read,
+    cancel: &AtomicBool,
+) -> Result<u64, ArchiveError> {
+    let mut out = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(mode)
+        .open(target)?;
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut written = 0;
+    loop {
+        if cancel.load(Ordering::Relaxed) {
+            return Err(ArchiveError::Cancelled);
+        }
+        let n = match data.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        };
+        out.write_all(&buf[..n])?;
+        written += n as u64;
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    /// A tar archive of `(name, data)` members; names ending in `/` are
+    /// directories, and `link -> target` names symlinks.
+    pub(crate) fn tar_bytes(members: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut out = Vec::new();
+        for (name, data) in members {
+            let mut header = [0u8; BLOCK];
+            // `link -> target` members are symlinks
+            let (name, kind) = match name.split_once(" -> ") {
+                Some((name, target)) => {
+                    header[157..157 + target.len()].copy_from_slice(target.as_bytes());
+                    (name, b'2')
+                }
+                None if name.ends_with('/') => (*name, b'5'),
+                None => (*name, b'0'),
+            };
+            header[..name.len()].copy_from_slice(name.as_bytes());
+            header[100..107].copy_from_slice(b"0000644");
+            header[124..135].copy_from_slice(format!("{:011o}", data.len()).as_bytes());
+            header[136..147].copy_from_slice(b"00000000000");
+            header[156] = kind;
+            header[257..263].copy_from_slice(b"ustar\0");
+            header[263..265].copy_from_slice(b"00");
+            header[148..156].copy_from_slice(b"        ");
+            let sum: u32 = header.iter().map(|&b| u32::from(b)).sum();
+            header[148..155].copy_from_slice(format!("{:06o}\0", sum).as_bytes());
+            out.extend_from_slice(&header);
+            out.extend_from_slice(data);
+            out.resize(out.len().div_ceil(BLOCK) * BLOCK, 0);
+        }
+        out.resize(out.len() + 2 * BLOCK, 0);
+        out
+    }
+
+    /// A zip archive of stored `(name, data)` members.
+    pub(crate) fn zip_bytes(members: &[(&str, &[u8])]) -> Vec<u8> {
+        zip_bytes_with(members, false)
+    }
+
+    /// A zip archive made on Unix with mode 0755, its members deflated when
+    /// `deflate`.
+    fn zip_bytes_with(members: &[(&str, &[u8])], deflate: bool) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut central = Vec::new();
+        for (name, data) in members {
+            let offset = out.len() as u32;
+            let len = data.len() as u32;
+            let (method, stored) = if deflate {
+                let mut encoder =
+                    flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(data).unwrap();
+                (8u16, encoder.finish().unwrap())
+            } else {
+                (0u16, data.to_vec())
+            };
+            let packed = stored.len() as u32;
+            out.extend_from_slice(&LOCAL_HEADER_SIGNATURE.to_le_bytes());
+            out.extend_from_slice(&[20, 0, 0, 0]);
+            out.extend_from_slice(&method.to_le_bytes());
+            out.extend_from_slice(&[0; 8]);
+            out.extend_from_slice(&packed.to_le_bytes());
+            out.extend_from_slice(&len.to_le_bytes());
+            out.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            out.extend_from_slice(&0u16.to_le_bytes());
+            out.extend_from_slice(name.as_bytes());
+            out.extend_from_slice(&stored);
+
+            central.extend_from_slice(&CENTRAL_HEADER_SIGNATURE.to_le_bytes());
+            // Made by Unix (3), version 2.0
+            central.extend_from_slice(&[20, 3, 20, 0, 0, 0]);
+            central.extend_from_slice(&method.to_le_bytes());
+            central.extend_from_slice(&[0; 8]);
+            central.extend_from_slice(&packed.to_le_bytes());
+            central.extend_from_slice(&len.to_le_bytes());
+            central.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            central.extend_from_slice(&[0; 8]);
+            let mode: u32 = if name.ends_with('/') {
+                0o040755
+            } else {
+                0o100755
+            };
+            central.extend_from_slice(&(mode << 16).to_le_bytes());
+            central.extend_from_slice(&offset.to_le_bytes());
+            central.extend_from_slice(name.as_bytes());
+        }
+        let central_offset = out.len() as u32;
+        out.extend_from_slice(&central);
+        out.extend_from_slice(&EOCD_SIGNATURE.to_le_bytes());
+        out.extend_from_slice(&[0; 4]);
+        out.extend_from_slice(&(members.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(members.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(central.len() as u32).to_le_bytes());
+        out.extend_from_slice(&central_offset.to_le_bytes());
+        out.extend_from_slice(&0u16.to_le_bytes());
+        out
+    }
+
+    pub(crate) fn write(dir: &TempDir, name: &str, bytes: &[u8]) -> std::path::PathBuf {
+        let path = dir.path().join(name);
+        File::create(&path).unwrap().write_all(bytes).unwrap();
+        path
+    }
+
+    fn gzip(bytes: &[u8]) -> Vec<u8> {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(bytes).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn detects_formats_by_extension() {
+        assert_eq!(Format::detect(Path::new("a.ZIP")), Some(Format::Zip));
+        assert_eq!(Format::detect(Path::new("a.tar")), Some(Format::Tar));
+        assert_eq!(Format::detect(Path::new("a.tar.gz")), Some(Format::TarGz));
+        assert_eq!(Format::detect(Path::new("a.tgz")), Some(Format::TarGz));
+        assert_eq!(Format::detect(Path::new("a.gz")), None);
+    }
+
+    #[test]
+    fn lists_zip_central_directory() {
+        let dir = TempDir::new().unwrap();
+        let path = write(
+            &dir,
+            "a.zip",
+            &zip_bytes(&[("src/", b""), ("src/main.rs", b"fn main() {}")]),
+        );
+        let listing = list(&path, Format::Zip, MAX_ENTRIES).unwrap();
+        assert_eq!(listing.remaining, Remaining::None);
+        assert_eq!(
+            listing.entries,
+            vec![
+                Entry {
+                    path: "src".into(),
+                    is_dir: true,
+                    size: 0,
+                    compressed: Some(0),
+                },
+                Entry {
+                    path: "src/main.rs".into(),
+                    is_dir: false,
+                    size: 12,
+                    compressed: Some(12),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn zip_listing_counts_entries_past_the_cap() {
+        let dir = TempDir::new().unwrap();
+        let path = write(
+            &dir,
+            "a.zip",
+            &zip_bytes(&[("a", b"1"), ("b", b"2"), ("c", b"3")]),
+        );
+        let listing = list(&path, Format::Zip, 1).unwrap();
+        assert_eq!(listing.entries.len(), 1);
+        assert_eq!(listing.remaining, Remaining::Counted(2));
+    }
+
+    #[test]
+    fn lists_tar_and_tar_gz_headers() {
+        let dir = TempDir::new().unwrap();
+        let tar = tar_bytes(&[("docs/", b""), ("docs/a.txt", &[7u8; 700])]);
+        for (name, bytes, format) in [
+            ("a.tar", tar.clone(), Format::Tar),
+            ("a.tar.gz", gzip(&tar), Format::TarGz),
+        ] {
+            let path = write(&dir, name, &bytes);
+            let listing = list(&path, format, MAX_ENTRIES).unwrap();
+            assert_eq!(listing.remaining, Remaining::None);
+            let paths: Vec<_> = listing.entries.iter().map(|e| e.path.as_str()).collect();
+            assert_eq!(paths, ["docs", "docs/a.txt"]);
+            assert_eq!(listing.entries[1].size, 700);
+            assert!(listing.entries[0].is_dir);
+        }
+    }
+
+    #[test]
+    fn tar_listing_stops_at_the_cap() {
+        let dir = TempDir::new().unwrap();
+        let path = write(&dir, "a.tar", &tar_bytes(&[("a", b"1"), ("b", b"2")]));
+        let listing = list(&path, Format::Tar, 1).unwrap();
+        assert_eq!(listing.entries.len(), 1);
+        assert_eq!(listing.remaining, Remaining::Unknown);
+    }
+
+    #[test]
+    fn corrupt_archives_are_errors() {
+        let dir = TempDir::new().unwrap();
+        let garbage = write(&dir, "bad.zip", b"definitely not a zip file at all");
+        assert!(list(&garbage, Format::Zip, MAX_ENTRIES).is_err());
+
+        let mut tar = tar_bytes(&[("a.txt", b"hello")]);
+        tar[0] = b'b'; // invalidates the checksum
+        let bad_tar = write(&dir, "bad.tar", &tar);
+        assert!(matches!(
+            list(&bad_tar, Format::Tar, MAX_ENTRIES),
+            Err(ArchiveError::Corrupt(_))
+        ));
+
+        let truncated = tar_bytes(&[("a.txt", &[1u8; 2000])]);
+        let cut = write(&dir, "cut.tar.gz", &gzip(&truncated[..1024]));
+        assert!(list(&cut, Format::TarGz, MAX_ENTRIES).is_err());
+
+        let not_gzip = write(&dir, "bad.tgz", b"plain text");
+        assert!(list(&not_gzip, Format::TarGz, MAX_ENTRIES).is_err());
+    }
+
+    fn extract_all(path: &Path, format: Format, dest: &Path) -> Result<usize, ArchiveError> {
+        extract(path, format, dest, &mut |_| {}, &AtomicBool::new(false))
+    }
+
+    #[test]
+    fn extract_target_drops_the_archive_extension() {
+        let target = |name: &str| {
+            let path = Path::new("/data").join(name);
+            extract_target(&path, Format::detect(&path).unwrap()).unwrap()
+        };
+        assert_eq!(target("site.tar.gz"), Path::new("/data/site"));
+        assert_eq!(target("Site.TGZ"), Path::new("/data/Site"));
+        assert_eq!(target("lib.v2.zip"), Path::new("/data/lib.v2"));
+        assert_eq!(target(".zip"), Path::new("/data/.zip_extracted"));
+    }
+
+    #[test]
+    fn extracts_zip_members_with_their_modes() {
+        let dir = TempDir::new().unwrap();
+        let body = b"fn main() { println!(\"hi\"); }\n".repeat(50);
+        for (name, deflate) in [("stored.zip", false), ("deflated.zip", true)] {
+            let path = write(
+                &dir,
+                name,
+                &zip_bytes_with(&[("app/", b""), ("app/src/main.rs", &body)], deflate),
+            );
+            let dest = dir.path().join(name.trim_end_matches(".zip"));
+            let mut seen = Vec::new();
+            let entries = extract(
+                &path,
+                Format::Zip,
+                &dest,
+                &mut |at| seen.push((at.done, at.total)),
+                &AtomicBool::new(false),
+            )
+            .unwrap();
+            assert_eq!(entries, 2);
+            assert_eq!(seen, [(1, Some(2)), (2, Some(2))]);
+            let main = dest.join("app/src/main.rs");
+            assert_eq!(fs::read(&main).unwrap(), body);
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&main).unwrap().permissions().mode();
+            assert_eq!(mode & 0o100, 0o100, "executable bit kept");
+        }
+    }
+
+    #[test]
+    fn extracts_tar_gz_with_symlinks() {
+        let dir = TempDir::new().unwrap();
+        let tar = tar_bytes(&[
+            ("./site/", b""),
+            ("site/index.html", b"<h1>hi</h1>"),
+            ("site/home.html -> index.html", b""),
+        ]);
+        let path = write(&dir, "site.tar.gz", &gzip(&tar));
+        let dest = dir.path().join("site");
+        assert_eq!(extract_all(&path, Format::TarGz, &dest).unwrap(), 3);
+        assert_eq!(
+            fs::read_to_string(dest.join("site/home.html")).unwrap(),
+            "<h1>hi</h1>"
+        );
+        assert!(fs::symlink_metadata(dest.join("site/home.html"))
+            .unwrap()
+            .file_type()
+            .is_symlink());
+    }
+
+    #[test]
+    fn extraction_refuses_paths_outside_the_target() {
+        let dir = TempDir::new().unwrap();
+        let outside = dir.path().join("outside");
+        fs::create_dir(&outside).unwrap();
+
+        let dotdot = write(
+            &dir,
+            "dotdot.tar",
+            &tar_bytes(&[("ok.txt", b"1"), ("../outside/evil", b"x")]),
+        );
+        let dest = dir.path().join("dotdot");
+        assert!(matches!(
+            extract_all(&dotdot, Format::Tar, &dest),
+            Err(ArchiveError::UnsafePath(_))
+        ));
+        assert!(!dest.exists(), "the partial tree is removed");
+        assert!(!outside.join("evil").exists());
+
+        // A symlink to outside, then a file written through it
+        let through = write(
+            &dir,
+            "through.tar",
+            &tar_bytes(&[("link -> ../outside", b""), ("link/evil", b"x")]),
+        );
+        let dest = dir.path().join("through");
+        assert!(matches!(
+            extract_all(&through, Format::Tar, &dest),
+            Err(ArchiveError::UnsafePath(_))
+        ));
+        assert!(!outside.join("evil").exists());
+
+        // Leading slashes are dropped
+        let absolute = write(&dir, "abs.tar", &tar_bytes(&[("/etc/x.conf", b"x")]));
+        let dest = dir.path().join("abs");
+        extract_all(&absolute, Format::Tar, &dest).unwrap();
+        assert!(dest.join("etc/x.conf").is_file());
+    }
+
+    #[test]
+    fn cancelled_extraction_removes_the_partial_tree() {
+        let dir = TempDir::new().unwrap();
+        let path = write(
+            &dir,
+            "a.tar",
+            &tar_bytes(&[("a.txt", b"1"), ("b.txt", b"2"), ("c.txt", b"3")]),
+        );
+        let dest = dir.path().join("a");
+        let cancel = AtomicBool::new(false);
+        let result = extract(
+            &path,
+            Format::Tar,
+            &dest,
+            &mut |at| {
+                if at.done == 1 {
+                    cancel.store(true, Ordering::Relaxed);
+                }
+            },
+            &cancel,
+        );
+        assert!(matches!(result, Err(ArchiveError::Cancelled)));
+        assert!(!dest.exists());
+    }
+
+    #[test]
+    fn extraction_into_an_existing_directory_is_refused() {
+        let dir = TempDir::new().unwrap();
+        let path = write(&dir, "a.zip", &zip_bytes(&[("a.txt", b"1")]));
+        let dest = dir.path().join("a");
+        fs::create_dir(&dest).unwrap();
+        fs::write(dest.join("keep"), "mine").unwrap();
+        assert!(extract_all(&path, Format::Zip, &dest).is_err());
+        assert_eq!(fs::read_to_string(dest.join("keep")).unwrap(), "mine");
+    }
+}
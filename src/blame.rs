@@ -0,0 +1,432 @@
+//! Git blame gutter for the preview panel.
+//!
+//! When enabled, the app waits for the preview scroll position to settle
+//! ([`BLAME_DEBOUNCE`]) and then runs `git blame --porcelain -L start,end`
+//! for just the visible lines in a background task. Results are cached per
+//! `(path, HEAD commit, range)`, and the last seen HEAD of up to
+//! `memory.blame_head_entries` files is remembered. Git is reached through
+//! [`GitRunner`] so tests can feed canned porcelain output.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use crate::bounded_map::{BoundedMap, MapStats, DEFAULT_BLAME_HEAD_CAPACITY};
+
+/// How long the preview viewport must stay put before blame is fetched.
+pub const BLAME_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Width of the gutter in columns, including the trailing space.
+pub const GUTTER_WIDTH: u16 = 14;
+
+/// Upper bound on cached blame ranges.
+const MAX_CACHE_ENTRIES: usize = 64;
+
+/// Access to the `git` commands blame needs.
+pub trait GitRunner: Send + Sync {
+    /// HEAD commit of the repository containing `path`, or `None` if the
+    /// file is outside a repository or not tracked.
+    fn tracked_head(&self, path: &Path) -> Option<String>;
+    /// Raw `git blame --porcelain -L start,end` output (1-based, inclusive).
+    fn blame_porcelain(&self, path: &Path, start: usize, end: usize) -> Option<String>;
+}
+
+/// Runs the real `git` binary.
+pub struct SystemGit;
+
+impl SystemGit {
+    fn run(dir: &Path, args: &[&str]) -> Option<String> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(args)
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        String::from_utf8(output.stdout).ok()
+    }
+}
+
+impl GitRunner for SystemGit {
+    fn tracked_head(&self, path: &Path) -> Option<String> {
+        let dir = path.parent()?;
+        let name = path.file_name()?.to_str()?;
+        Self::run(dir, &["ls-files", "--error-unmatch", "--", name])?;
+        let head = Self::run(dir, &["rev-parse", "HEAD"])?;
+        Some(head.trim().to_string())
+    }
+
+    fn blame_porcelain(&self, path: &Path, start: usize, end: usize) -> Option<String> {
+        let dir = path.parent()?;
+        let name = path.file_name()?.to_str()?;
+        let range = format!("{},{}", start, end);
+        Self::run(dir, &["blame", "--porcelain", "-L", &range, "--", name])
+    }
+}
+
+/// Blame information for one line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlameLine {
+    /// Abbreviated commit hash.
+    pub hash: String,
+    /// Author time (seconds since the epoch).
+    pub author_time: i64,
+}
+
+/// Parse porcelain output into `(final line number, blame)` pairs.
+///
+/// Commit metadata (`author-time`) only appears the first time a commit is
+/// mentioned, so it is remembered per hash.
+pub fn parse_porcelain(text: &str) -> Vec<(usize, BlameLine)> {
+    let mut times: HashMap<&str, i64> = HashMap::new();
+    let mut result = Vec::new();
+    let mut current: Option<(&str, usize)> = None;
+
+    for line in text.lines() {
+        if line.starts_with('\t') {
+            if let Some((hash, final_line)) = current.take() {
+                result.push((
+                    final_line,
+                    BlameLine {
+                        hash: hash.chars().take(8).collect(),
+                        author_time: times.get(hash).copied().unwrap_or(0),
+                    },
+                ));
+            }
+            continue;
+        }
+        if let Some(value) = line.strip_prefix("author-time ") {
+            if let (Some((hash, _)), Ok(time)) = (current, value.trim().parse::<i64>()) {
+                times.insert(hash, time);
+            }
+            continue;
+        }
+        let mut parts = line.split(' ');
+        let hash = parts.next().unwrap_or_default();
+        if hash.len() == 40 && hash.bytes().all(|b| b.is_ascii_hexdigit()) {
+            let final_line = parts.nth(1).and_then(|n| n.parse::<usize>().ok());
+            current = final_line.map(|n| (hash, n));
+        }
+    }
+    result
+}
+
+/// How recent a line's last change is; drives the gutter color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Recency {
+    /// Less than a week old.
+    Fresh,
+    /// Less than three months old.
+    Recent,
+    /// Less than a year old.
+    Old,
+    /// A year or older.
+    Ancient,
+}
+
+impl Recency {
+    pub fn from_age(age_secs: i64) -> Self {
+        const DAY: i64 = 86_400;
+        if age_secs < 7 * DAY {
+            Recency::Fresh
+        } else if age_secs < 90 * DAY {
+            Recency::Recent
+        } else if age_secs < 365 * DAY {
+            Recency::Old
+        } else {
+            Recency::Ancient
+        }
+    }
+}
+
+/// Compact age label (`now`, `5m`, `3h`, `4d`, `2w`, `7mo`, `3y`).
+pub fn format_age(age_secs: i64) -> String {
+    let secs = age_secs.max(0);
+    match secs {
+        s if s < 60 => "now".to_string(),
+        s if s < 3_600 => format!("{}m", s / 60),
+        s if s < 86_400 => format!("{}h", s / 3_600),
+        s if s < 14 * 86_400 => format!("{}d", s / 86_400),
+        s if s < 60 * 86_400 => format!("{}w", s / (7 * 86_400)),
+        s if s < 365 * 86_400 => format!("{}mo", s / (30 * 86_400)),
+        s => format!("{}y", s / (365 * 86_400)),
+    }
+}
+
+/// One rendered gutter cell.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GutterCell {
+    pub text: String,
+    pub recency: Recency,
+}
+
+/// Gutter cells for a contiguous range of file lines.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlameGutter {
+    /// First file line covered (1-based).
+    pub start: usize,
+    /// One entry per line from `start`; `None` where blame is unknown.
+    pub cells: Vec<Option<GutterCell>>,
+}
+
+impl BlameGutter {
+    /// Build gutter cells for `start..=end` relative to `now` (epoch secs).
+    pub fn build(lines: &[(usize, BlameLine)], start: usize, end: usize, now: i64) -> Self {
+        let mut cells = vec![None; end.saturating_sub(start) + 1];
+        for (line_no, blame) in lines {
+            if *line_no < start || *line_no > end {
+                continue;
+            }
+            let age = now - blame.author_time;
+            cells[line_no - start] = Some(GutterCell {
+                text: format!("{:<8} {:>4}", blame.hash, format_age(age)),
+                recency: Recency::from_age(age),
+            });
+        }
+        Self { start, cells }
+    }
+
+    /// Cell for a 1-based file line, if covered and known.
+    pub fn cell(&self, line_no: usize) -> Option<&GutterCell> {
+        line_no
+            .checked_sub(self.start)
+            .and_then(|i| self.cells.get(i))
+            .and_then(|c| c.as_ref())
+    }
+}
+
+/// Visible file range of the preview (1-based, inclusive).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BlameRange {
+    pub path: PathBuf,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Result of a background blame fetch.
+#[derive(Debug)]
+pub struct BlameResult {
+    pub range: BlameRange,
+    /// `None` when the file is not tracked by git.
+    pub head: Option<String>,
+    /// Parsed lines; empty if blame failed.
+    pub lines: Vec<(usize, BlameLine)>,
+}
+
+/// Run the git side of a fetch (blocking; call from `spawn_blocking`).
+pub fn fetch(runner: &dyn GitRunner, range: BlameRange) -> BlameResult {
+    let head = runner.tracked_head(&range.path);
+    let lines = match head {
+        Some(_) => runner
+            .blame_porcelain(&range.path, range.start, range.end)
+            .map(|text| parse_porcelain(&text))
+            .unwrap_or_default(),
+        None => Vec::new(),
+    };
+    BlameResult { range, head, lines }
+}
+
+/// Blame gutter state held by the app.
+pub struct BlameState {
+    /// Whether the gutter is shown.
+    pub enabled: bool,
+    /// Gutter for the most recently displayed range.
+    pub gutter: Option<BlameGutter>,
+    /// Range the current gutter was built for.
+    pub shown: Option<BlameRange>,
+    /// Last observed viewport and when it last changed.
+    pub settling: Option<(BlameRange, std::time::Instant)>,
+    /// Range of the fetch in flight, if any.
+    pub pending: Option<BlameRange>,
+    /// Last known HEAD per path (`None` = not tracked).
+    pub heads: BoundedMap<PathBuf, Option<String>>,
+    /// Cached results keyed by (range, HEAD).
+    pub cache: BoundedMap<(BlameRange, String), Vec<(usize, BlameLine)>>,
+    pub runner: std::sync::Arc<dyn GitRunner>,
+}
+
+impl Default for BlameState {
+    fn default() -> Self {
+        Self::with_capacity(DEFAULT_BLAME_HEAD_CAPACITY)
+    }
+}
+
+impl BlameState {
+    /// Remember the HEAD of at most `head_capacity` files.
+    pub fn with_capacity(head_capacity: usize) -> Self {
+        Self {
+            enabled: false,
+            gutter: None,
+            shown: None,
+            settling: None,
+            pending: None,
+            heads: BoundedMap::new(head_capacity),
+            cache: BoundedMap::new(MAX_CACHE_ENTRIES),
+            runner: std::sync::Arc::new(SystemGit),
+        }
+    }
+
+    /// Store a fetched range, evicting the least recently stored when full.
+    pub fn insert_cache(&mut self, key: (BlameRange, String), lines: Vec<(usize, BlameLine)>) {
+        self.cache.insert(key, lines);
+    }
+
+    pub fn stats(&self) -> MapStats {
+        self.heads.stats().add(self.cache.stats())
+    }
+
+    /// Cached lines for `range` at the last known HEAD of its file.
+    pub fn cached(&self, range: &BlameRange) -> Option<&Vec<(usize, BlameLine)>> {
+        let head = self.heads.get(range.path.as_path())?.as_ref()?;
+        self.cache.get(&(range.clone(), head.clone()))
+    }
+
+    /// Whether `path` is known to be untracked.
+    pub fn is_untracked(&self, path: &Path) -> bool {
+        matches!(self.heads.get(path), Some(None))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PORCELAIN: &str = "\
+1234567890abcdef1234567890abcdef12345678 10 10 2
+author Alice
+author-time 1700000000
+summary first
+filename src/main.rs
+\tfn main() {
+1234567890abcdef1234567890abcdef12345678 11 11
+\t    run();
+fedcba0987654321fedcba0987654321fedcba09 3 12 1
+author Bob
+author-time 1690000000
+summary second
+filename src/main.rs
+\t}
+";
+
+    #[test]
+    fn parses_porcelain_with_repeated_commits() {
+        let lines = parse_porcelain(PORCELAIN);
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0].0, 10);
+        assert_eq!(lines[0].1.hash, "12345678");
+        assert_eq!(lines[0].1.author_time, 1_700_000_000);
+        // Second mention of the same commit reuses its author-time
+        assert_eq!(lines[1].0, 11);
+        assert_eq!(lines[1].1.author_time, 1_700_000_000);
+        assert_eq!(lines[2].0, 12);
+        assert_eq!(lines[2].1.hash, "fedcba09");
+        assert_eq!(lines[2].1.author_time, 1_690_000_000);
+    }
+
+    #[test]
+    fn garbage_parses_to_nothing() {
+        assert!(parse_porcelain("fatal: no such path\n").is_empty());
+    }
+
+    #[test]
+    fn age_labels_and_recency() {
+        assert_eq!(format_age(5), "now");
+        assert_eq!(format_age(300), "5m");
+        assert_eq!(format_age(3 * 3_600), "3h");
+        assert_eq!(format_age(4 * 86_400), "4d");
+        assert_eq!(format_age(21 * 86_400), "3w");
+        assert_eq!(format_age(200 * 86_400), "6mo");
+        assert_eq!(format_age(800 * 86_400), "2y");
+        assert_eq!(Recency::from_age(86_400), Recency::Fresh);
+        assert_eq!(Recency::from_age(30 * 86_400), Recency::Recent);
+        assert_eq!(Recency::from_age(200 * 86_400), Recency::Old);
+        assert_eq!(Recency::from_age(400 * 86_400), Recency::Ancient);
+    }
+
+    #[test]
+    fn gutter_maps_lines_into_range() {
+        let lines = parse_porcelain(PORCELAIN);
+        let gutter = BlameGutter::build(&lines, 10, 13, 1_700_000_000 + 3_600);
+        assert_eq!(gutter.cells.len(), 4);
+        assert_eq!(gutter.cell(10).unwrap().text, "12345678   1h");
+        assert_eq!(gutter.cell(10).unwrap().recency, Recency::Fresh);
+        assert_eq!(gutter.cell(12).unwrap().recency, Recency::Old);
+        assert!(gutter.cell(13).is_none());
+        assert!(gutter.cell(9).is_none());
+    }
+
+    struct CannedGit {
+        head: Option<&'static str>,
+        porcelain: Option<&'static str>,
+    }
+
+    impl GitRunner for CannedGit {
+        fn tracked_head(&self, _path: &Path) -> Option<String> {
+            self.head.map(String::from)
+        }
+        fn blame_porcelain(&self, _path: &Path, _start: usize, _end: usize) -> Option<String> {
+            self.porcelain.map(String::from)
+        }
+    }
+
+    fn range() -> BlameRange {
+        BlameRange {
+            path: PathBuf::from("/repo/src/main.rs"),
+            start: 10,
+            end: 12,
+        }
+    }
+
+    #[test]
+    fn fetch_untracked_and_failed_blame() {
+        let untracked = CannedGit {
+            head: None,
+            porcelain: Some(PORCELAIN),
+        };
+        let result = fetch(&untracked, range());
+        assert!(result.head.is_none());
+        assert!(result.lines.is_empty());
+
+        let failing = CannedGit {
+            head: Some("abc"),
+            porcelain: None,
+        };
+        let result = fetch(&failing, range());
+        assert_eq!(result.head.as_deref(), Some("abc"));
+        assert!(result.lines.is_empty());
+    }
+
+    #[test]
+    fn cache_lookup_uses_known_head() {
+        let mut state = BlameState::default();
+        let lines = parse_porcelain(PORCELAIN);
+        state.insert_cache((range(), "abc".to_string()), lines);
+        assert!(state.cached(&range()).is_none());
+        state.heads.insert(range().path, Some("abc".to_string()));
+        assert_eq!(state.cached(&range()).unwrap().len(), 3);
+        state.heads.insert(range().path, Some("def".to_string()));
+        assert!(state.cached(&range()).is_none());
+    }
+
+    #[test]
+    fn heads_beyond_capacity_are_fetched_again() {
+        let mut state = BlameState::with_capacity(1);
+        let lines = parse_porcelain(PORCELAIN);
+        state.insert_cache((range(), "abc".to_string()), lines);
+        state.heads.insert(range().path, Some("abc".to_string()));
+        assert!(state.cached(&range()).is_some());
+
+        // Another file's HEAD pushes this one out; its cached lines are
+        // unreachable until HEAD is looked up again
+        state.heads.insert(PathBuf::from("src/other.rs"), None);
+        assert!(state.cached(&range()).is_none());
+        assert!(!state.is_untracked(&range().path));
+        assert_eq!(state.stats().entries, 2);
+        assert_eq!(state.stats().evictions, 1);
+    }
+}
@@ -0,0 +1,112 @@
+//! Bookmarked directories.
+//!
+//! `b` on a directory bookmarks it and `B` lists the bookmarks in an
+//! overlay that jumps to the chosen one. Unlike the `[bookmarks]` config
+//! table shown under places, this list is edited from inside fm: it lives
+//! in `bookmarks.toml` in the config directory and is rewritten on every
+//! change, so it survives restarts and crashes alike.
+//!
+//! Bookmarked directories may have been removed or unmounted since. They
+//! stay in the list, shown as missing, until deleted with `d`.
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::schema::{self, Artifact};
+
+/// Bookmarks file name inside the config directory.
+pub const BOOKMARKS_FILE: &str = "bookmarks.toml";
+
+/// Bookmarked directories in the order they were added.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Bookmarks {
+    #[serde(default)]
+    paths: Vec<PathBuf>,
+}
+
+impl Bookmarks {
+    /// Bookmark `path`. Returns false when it already is.
+    pub fn add(&mut self, path: &Path) -> bool {
+        if self.contains(path) {
+            return false;
+        }
+        self.paths.push(path.to_path_buf());
+        true
+    }
+
+    /// Whether `path` is bookmarked.
+    pub fn contains(&self, path: &Path) -> bool {
+        self.paths.iter().any(|p| p == path)
+    }
+
+    /// Drop the bookmark at `index`, returning its path.
+    pub fn remove(&mut self, index: usize) -> Option<PathBuf> {
+        if index < self.paths.len() {
+            Some(self.paths.remove(index))
+        } else {
+            None
+        }
+    }
+
+    /// Bookmarked paths, oldest first.
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    /// Atomically write the bookmarks to `dir`, creating it if needed.
+    pub fn save(&self, dir: &Path) -> io::Result<()> {
+        schema::save(Artifact::Bookmarks, dir, self)
+    }
+
+    /// Read the bookmarks saved in `dir`; empty when missing or unreadable.
+    pub fn load(dir: &Path) -> Self {
+        schema::load(Artifact::Bookmarks, dir).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn add_skips_duplicates_and_keeps_order() {
+        let mut bookmarks = Bookmarks::default();
+        assert!(bookmarks.add(Path::new("/srv/b")));
+        assert!(bookmarks.add(Path::new("/srv/a")));
+        assert!(!bookmarks.add(Path::new("/srv/b")));
+        assert_eq!(
+            bookmarks.paths(),
+            &[PathBuf::from("/srv/b"), PathBuf::from("/srv/a")]
+        );
+        assert_eq!(bookmarks.remove(0), Some(PathBuf::from("/srv/b")));
+        assert_eq!(bookmarks.remove(5), None);
+        assert!(!bookmarks.contains(Path::new("/srv/b")));
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let config = TempDir::new().unwrap();
+        // The config directory may not exist yet
+        let dir = config.path().join("fm-tui");
+        let mut bookmarks = Bookmarks::default();
+        bookmarks.add(Path::new("/nonexistent/projects"));
+        bookmarks.add(Path::new("/nonexistent/notes"));
+        bookmarks.save(&dir).unwrap();
+
+        let text = fs::read_to_string(dir.join(BOOKMARKS_FILE)).unwrap();
+        assert!(text.contains("/nonexistent/projects"));
+        assert_eq!(Bookmarks::load(&dir), bookmarks);
+    }
+
+    #[test]
+    fn missing_or_corrupt_file_loads_empty() {
+        let dir = TempDir::new().unwrap();
+        assert!(Bookmarks::load(dir.path()).paths().is_empty());
+        fs::write(dir.path().join(BOOKMARKS_FILE), "paths = [not toml").unwrap();
+        assert!(Bookmarks::load(dir.path()).paths().is_empty());
+    }
+}
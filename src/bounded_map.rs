@@ -0,0 +1,312 @@
+//! A size-capped map for the small per-path caches hung off the app.
+//!
+//! Each cache (changed-path flashes, per-directory sort overrides,
+//! prefetch bookkeeping, directory sizes, blame HEADs) is tiny on its own
+//! but is keyed by paths the user has visited, so a long session that
+//! browses many directories would grow it without bound. [`BoundedMap`]
+//! keeps at most `capacity` entries, dropping the least recently used one
+//! when a new key arrives, and counts how many it dropped so the debug
+//! overlay can show it.
+
+use std::borrow::Borrow;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+
+/// Default flashes kept for the "next changed" key.
+pub const DEFAULT_FLASH_CAPACITY: usize = 256;
+/// Default directories with a remembered sort override.
+pub const DEFAULT_SORT_OVERRIDE_CAPACITY: usize = 512;
+/// Default prefetched paths waiting for their preview lookup.
+pub const DEFAULT_PREFETCH_CAPACITY: usize = 64;
+/// Default directories with a computed size.
+pub const DEFAULT_DIR_SIZE_CAPACITY: usize = 1024;
+/// Default files with a remembered git HEAD for the blame gutter.
+pub const DEFAULT_BLAME_HEAD_CAPACITY: usize = 256;
+
+/// Capacity of each bounded cache (`[memory]` in the config).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MapCapacities {
+    pub flash: usize,
+    pub sort_overrides: usize,
+    pub prefetch: usize,
+    pub dir_sizes: usize,
+    pub blame_heads: usize,
+}
+
+impl Default for MapCapacities {
+    fn default() -> Self {
+        Self {
+            flash: DEFAULT_FLASH_CAPACITY,
+            sort_overrides: DEFAULT_SORT_OVERRIDE_CAPACITY,
+            prefetch: DEFAULT_PREFETCH_CAPACITY,
+            dir_sizes: DEFAULT_DIR_SIZE_CAPACITY,
+            blame_heads: DEFAULT_BLAME_HEAD_CAPACITY,
+        }
+    }
+}
+
+/// Entry and eviction counts of one or more bounded maps.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MapStats {
+    pub entries: usize,
+    pub evictions: u64,
+}
+
+impl MapStats {
+    /// Sum of `self` and `other`.
+    pub fn add(self, other: MapStats) -> MapStats {
+        MapStats {
+            entries: self.entries + other.entries,
+            evictions: self.evictions + other.evictions,
+        }
+    }
+}
+
+/// A map holding at most `capacity` entries, evicting the least recently
+/// used.
+///
+/// Inserting a key or calling [`touch`](Self::touch) on it makes it the
+/// most recently used; plain lookups leave the order alone, so read-only
+/// callers (rendering, sorting) can share it.
+#[derive(Debug, Clone)]
+pub struct BoundedMap<K, V> {
+    entries: HashMap<K, (V, u64)>,
+    /// Keys by the tick they were last used at, oldest first.
+    order: BTreeMap<u64, K>,
+    tick: u64,
+    capacity: usize,
+    evictions: u64,
+}
+
+impl<K: Eq + Hash + Clone, V> BoundedMap<K, V> {
+    /// An empty map holding at most `capacity` entries (at least one).
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+            tick: 0,
+            capacity: capacity.max(1),
+            evictions: 0,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Change the capacity, evicting the oldest entries that no longer fit.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity.max(1);
+        while self.entries.len() > self.capacity {
+            self.evict_oldest();
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Entries dropped to make room since the map was created.
+    pub fn evictions(&self) -> u64 {
+        self.evictions
+    }
+
+    pub fn stats(&self) -> MapStats {
+        MapStats {
+            entries: self.len(),
+            evictions: self.evictions(),
+        }
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.entries.get(key).map(|(value, _)| value)
+    }
+
+    /// Store `value` under `key` as the most recently used entry. Returns
+    /// the entry evicted to make room, if any.
+    pub fn insert(&mut self, key: K, value: V) -> Option<(K, V)> {
+        let tick = self.next_tick();
+        if let Some((old, used)) = self.entries.get_mut(&key) {
+            *old = value;
+            let prev = std::mem::replace(used, tick);
+            self.order.remove(&prev);
+            self.order.insert(tick, key);
+            return None;
+        }
+        let evicted = if self.entries.len() >= self.capacity {
+            self.evict_oldest()
+        } else {
+            None
+        };
+        self.order.insert(tick, key.clone());
+        self.entries.insert(key, (value, tick));
+        evicted
+    }
+
+    /// Mark `key` as the most recently used. Returns false if absent.
+    pub fn touch<Q>(&mut self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let tick = self.next_tick();
+        match self.entries.get_mut(key) {
+            Some((_, used)) => {
+                let prev = std::mem::replace(used, tick);
+                if let Some(key) = self.order.remove(&prev) {
+                    self.order.insert(tick, key);
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let (value, used) = self.entries.remove(key)?;
+        self.order.remove(&used);
+        Some(value)
+    }
+
+    /// Keep only the entries for which `keep` returns true. Dropped
+    /// entries are not counted as evictions.
+    pub fn retain(&mut self, mut keep: impl FnMut(&K, &V) -> bool) {
+        let order = &mut self.order;
+        self.entries.retain(|key, (value, used)| {
+            let kept = keep(key, value);
+            if !kept {
+                order.remove(used);
+            }
+            kept
+        });
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+
+    /// Entries in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.entries.iter().map(|(key, (value, _))| (key, value))
+    }
+
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+
+    fn evict_oldest(&mut self) -> Option<(K, V)> {
+        let (_, key) = self.order.pop_first()?;
+        let (value, _) = self.entries.remove(&key)?;
+        self.evictions += 1;
+        Some((key, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evicts_least_recently_used_beyond_capacity() {
+        let mut map = BoundedMap::new(3);
+        assert_eq!(map.insert("a", 1), None);
+        assert_eq!(map.insert("b", 2), None);
+        assert_eq!(map.insert("c", 3), None);
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.evictions(), 0);
+
+        assert_eq!(map.insert("d", 4), Some(("a", 1)));
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.evictions(), 1);
+        assert_eq!(map.get(&"a"), None);
+    }
+
+    #[test]
+    fn insert_and_touch_refresh_recency() {
+        let mut map = BoundedMap::new(2);
+        map.insert("a", 1);
+        map.insert("b", 2);
+        // Replacing a value is a use, not an eviction
+        assert_eq!(map.insert("a", 10), None);
+        assert_eq!(map.insert("c", 3), Some(("b", 2)));
+
+        assert!(map.touch(&"a"));
+        assert!(!map.touch(&"zz"));
+        assert_eq!(map.insert("d", 4), Some(("c", 3)));
+        assert_eq!(map.get(&"a"), Some(&10));
+    }
+
+    #[test]
+    fn get_does_not_refresh_recency() {
+        let mut map = BoundedMap::new(2);
+        map.insert("a", 1);
+        map.insert("b", 2);
+        assert_eq!(map.get(&"a"), Some(&1));
+        assert_eq!(map.insert("c", 3), Some(("a", 1)));
+    }
+
+    #[test]
+    fn remove_and_retain_are_not_evictions() {
+        let mut map = BoundedMap::new(4);
+        for (i, key) in ["a", "b", "c", "d"].into_iter().enumerate() {
+            map.insert(key, i);
+        }
+        assert_eq!(map.remove(&"b"), Some(1));
+        map.retain(|_, value| *value != 2);
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.evictions(), 0);
+
+        // The freed slots are reused before anything is evicted
+        map.insert("e", 4);
+        map.insert("f", 5);
+        assert_eq!(map.evictions(), 0);
+        assert_eq!(map.insert("g", 6), Some(("a", 0)));
+    }
+
+    #[test]
+    fn shrinking_capacity_evicts_oldest() {
+        let mut map = BoundedMap::new(4);
+        for key in ["a", "b", "c", "d"] {
+            map.insert(key, ());
+        }
+        map.set_capacity(2);
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.evictions(), 2);
+        assert!(map.get(&"c").is_some() && map.get(&"d").is_some());
+
+        map.set_capacity(0);
+        assert_eq!(map.capacity(), 1);
+        assert!(map.get(&"d").is_some());
+    }
+
+    #[test]
+    fn stats_add_up() {
+        let mut a = BoundedMap::new(1);
+        a.insert(1, ());
+        a.insert(2, ());
+        let mut b = BoundedMap::new(5);
+        b.insert(1, ());
+        assert_eq!(
+            a.stats().add(b.stats()),
+            MapStats {
+                entries: 2,
+                evictions: 1
+            }
+        );
+    }
+}
@@ -0,0 +1,198 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Padding, Widget},
+};
+
+use crate::app::BookmarksState;
+use crate::text;
+use crate::theme::ThemeColors;
+
+/// Bookmarks overlay widget (`B`), styled like the fuzzy finder.
+pub struct BookmarksWidget<'a> {
+    state: &'a BookmarksState,
+    theme: &'a ThemeColors,
+}
+
+impl<'a> BookmarksWidget<'a> {
+    pub fn new(state: &'a BookmarksState, theme: &'a ThemeColors) -> Self {
+        Self { state, theme }
+    }
+
+    fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+        let x = area.x + area.width.saturating_sub(width) / 2;
+        let y = area.y + area.height.saturating_sub(height) / 2;
+        let w = width.min(area.width);
+        let h = height.min(area.height);
+        Rect::new(x, y, w, h)
+    }
+
+    /// One line per bookmark; missing ones are greyed out and tagged.
+    fn build_row(&self, index: usize, width: usize) -> Line<'static> {
+        let entry = &self.state.entries[index];
+        let is_selected = index == self.state.selected_index;
+        let usable = entry.status.is_usable();
+
+        let indicator = if is_selected {
+            Span::styled(
+                "▸ ",
+                Style::default()
+                    .fg(self.theme.info_fg)
+                    .add_modifier(Modifier::BOLD),
+            )
+        } else {
+            Span::raw("  ")
+        };
+        let mut style = if is_selected {
+            Style::default().fg(self.theme.status_fg)
+        } else {
+            Style::default().fg(self.theme.dim_fg)
+        };
+        let status = if usable {
+            String::new()
+        } else {
+            style = Style::default()
+                .fg(self.theme.dim_fg)
+                .add_modifier(Modifier::DIM);
+            format!(" [{}]", entry.status.label())
+        };
+        let room = width.saturating_sub(2).saturating_sub(text::width(&status));
+        let path = text::truncate_middle(&entry.path.to_string_lossy(), room);
+        Line::from(vec![
+            indicator,
+            Span::styled(path, style),
+            Span::styled(status, Style::default().fg(self.theme.warning_fg)),
+        ])
+    }
+}
+
+impl<'a> Widget for BookmarksWidget<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.height < 5 || area.width < 20 {
+            return;
+        }
+
+        // Same footprint as the fuzzy finder
+        let dialog_width = (area.width * 60 / 100).clamp(30, 80);
+        let dialog_height = (area.height * 60 / 100).clamp(8, 30);
+        let rect = Self::centered_rect(dialog_width, dialog_height, area);
+
+        Clear.render(rect, buf);
+
+        let block = Block::default()
+            .title(" Bookmarks (B) ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(self.theme.dialog_border_fg))
+            .padding(Padding::horizontal(1));
+        let inner = block.inner(rect);
+        block.render(rect, buf);
+
+        if inner.height == 0 || inner.width == 0 {
+            return;
+        }
+
+        // Row 0: count, or how to add the first bookmark
+        let count = self.state.entries.len();
+        let heading = if count == 0 {
+            "No bookmarks yet: press b on a directory".to_string()
+        } else {
+            format!("{} bookmark{}", count, if count == 1 { "" } else { "s" })
+        };
+        let heading = Line::from(Span::styled(
+            format!("─── {} ", heading),
+            Style::default().fg(self.theme.dim_fg),
+        ));
+        buf.set_line(inner.x, inner.y, &heading, inner.width);
+
+        // Row 1+: bookmarks, scrolled to keep the selection visible
+        let list_start = 1u16;
+        let visible = inner.height.saturating_sub(list_start + 1) as usize;
+        let scroll = if self.state.selected_index >= visible {
+            self.state.selected_index - visible + 1
+        } else {
+            0
+        };
+        for (i, index) in (scroll..count).take(visible).enumerate() {
+            let line = self.build_row(index, inner.width as usize);
+            buf.set_line(inner.x, inner.y + list_start + i as u16, &line, inner.width);
+        }
+
+        if inner.height > 2 {
+            let hint = "[Enter] Open  [d] Delete  [Esc] Close  [↑↓] Navigate";
+            let hint_style = Style::default()
+                .fg(self.theme.dim_fg)
+                .add_modifier(Modifier::DIM);
+            let hint_line = Line::from(Span::styled(hint, hint_style));
+            buf.set_line(inner.x, inner.y + inner.height - 1, &hint_line, inner.width);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::BookmarkEntry;
+    use crate::fs::places::PlaceStatus;
+    use crate::theme;
+    use std::path::PathBuf;
+
+    fn buffer_to_string(buf: &Buffer, area: Rect) -> String {
+        let mut s = String::new();
+        for y in area.y..area.y + area.height {
+            for x in area.x..area.x + area.width {
+                s.push_str(buf.cell((x, y)).unwrap().symbol());
+            }
+            s.push('\n');
+        }
+        s
+    }
+
+    fn render(state: &BookmarksState) -> (Buffer, Rect) {
+        let theme = theme::dark_theme();
+        let area = Rect::new(0, 0, 100, 20);
+        let mut buf = Buffer::empty(area);
+        BookmarksWidget::new(state, &theme).render(area, &mut buf);
+        (buf, area)
+    }
+
+    #[test]
+    fn lists_bookmarks_and_marks_missing_ones() {
+        let state = BookmarksState {
+            entries: vec![
+                BookmarkEntry {
+                    path: PathBuf::from("/home/alice/src/project"),
+                    status: PlaceStatus::Ready,
+                },
+                BookmarkEntry {
+                    path: PathBuf::from("/media/usb/photos"),
+                    status: PlaceStatus::Missing,
+                },
+            ],
+            selected_index: 0,
+        };
+        let (buf, area) = render(&state);
+        let content = buffer_to_string(&buf, area);
+        assert!(content.contains("Bookmarks (B)"));
+        assert!(content.contains("2 bookmarks"));
+        assert!(content.contains("▸ /home/alice/src/project"));
+        assert!(content.contains("/media/usb/photos [missing]"));
+
+        // The missing bookmark's path is greyed out
+        let symbol = |x: u16, y: u16| buf.cell((x, y)).unwrap().symbol().to_string();
+        let (x, y) = (0..area.height)
+            .flat_map(|y| (0..area.width - 1).map(move |x| (x, y)))
+            .find(|&(x, y)| symbol(x, y) == "/" && symbol(x + 1, y) == "m")
+            .unwrap();
+        let cell = buf.cell((x, y)).unwrap();
+        assert!(cell.modifier.contains(Modifier::DIM));
+    }
+
+    #[test]
+    fn empty_list_explains_how_to_add() {
+        let (buf, area) = render(&BookmarksState::default());
+        let content = buffer_to_string(&buf, area);
+        assert!(content.contains("press b on a directory"));
+    }
+}
@@ -0,0 +1,181 @@
+//! Debug overlay: a small corner panel with internal runtime numbers.
+
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Widget},
+};
+
+use crate::app::MemoryStats;
+use crate::preview_content::format_size;
+use crate::theme::ThemeColors;
+
+/// Overlay widget showing approximate memory usage and background task
+/// counts (toggled with F12).
+pub struct DebugOverlay<'a> {
+    stats: &'a MemoryStats,
+    theme: &'a ThemeColors,
+}
+
+impl<'a> DebugOverlay<'a> {
+    pub fn new(stats: &'a MemoryStats, theme: &'a ThemeColors) -> Self {
+        Self { stats, theme }
+    }
+
+    /// Build the label/value rows shown in the overlay.
+    fn build_lines(&self) -> Vec<Line<'static>> {
+        let label_style = Style::default()
+            .fg(self.theme.accent_fg)
+            .add_modifier(Modifier::BOLD);
+        let value_style = Style::default().fg(self.theme.tree_file_fg);
+        let row = |label: &str, value: String| {
+            Line::from(vec![
+                Span::styled(format!("{:<12}", label), label_style),
+                Span::styled(value, value_style),
+            ])
+        };
+
+        vec![
+            row("Preview", format_size(self.stats.preview_bytes as u64)),
+            row(
+                "Cache",
+                format!(
+                    "{} ({} files)",
+                    format_size(self.stats.preview_cache_bytes as u64),
+                    self.stats.preview_cache_entries
+                ),
+            ),
+            row(
+                "Prefetch",
+                format!(
+                    "{} hits / {} misses",
+                    self.stats.prefetch_hits, self.stats.prefetch_misses
+                ),
+            ),
+            row(
+                "Scrollback",
+                format!(
+                    "{} ({} lines)",
+                    format_size(self.stats.scrollback_bytes as u64),
+                    self.stats.scrollback_lines
+                ),
+            ),
+            row(
+                "Tasks",
+                format!(
+                    "{} run / {} wait / {} held",
+                    self.stats.tasks.running, self.stats.tasks.waiting, self.stats.tasks.deferred
+                ),
+            ),
+            row(
+                "Tasks done",
+                format!(
+                    "{} ({} cancelled)",
+                    self.stats.tasks.finished + self.stats.tasks.cancelled,
+                    self.stats.tasks.cancelled
+                ),
+            ),
+            row(
+                "Path maps",
+                format!(
+                    "{} ({} evicted)",
+                    self.stats.maps.entries, self.stats.maps.evictions
+                ),
+            ),
+        ]
+    }
+}
+
+impl<'a> Widget for DebugOverlay<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let lines = self.build_lines();
+        let width = 40u16.min(area.width);
+        let height = (lines.len() as u16 + 2).min(area.height);
+        if width < 4 || height < 3 {
+            return;
+        }
+
+        // Anchor to the top-right corner so the tree stays readable.
+        let rect = Rect::new(area.x + area.width - width, area.y, width, height);
+        Clear.render(rect, buf);
+
+        let block = Block::default()
+            .title(" Debug ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(self.theme.dim_fg))
+            .style(Style::default().bg(self.theme.dialog_bg));
+        let inner = block.inner(rect);
+        block.render(rect, buf);
+
+        for (i, line) in lines.iter().take(inner.height as usize).enumerate() {
+            buf.set_line(inner.x + 1, inner.y + i as u16, line, inner.width - 1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bounded_map::MapStats;
+    use crate::tasks::TaskCounters;
+    use crate::theme::dark_theme;
+
+    fn buffer_to_string(buf: &Buffer, area: Rect) -> String {
+        let mut s = String::new();
+        for y in area.y..area.y + area.height {
+            for x in area.x..area.x + area.width {
+                s.push_str(buf.cell((x, y)).unwrap().symbol());
+            }
+            s.push('\n');
+        }
+        s
+    }
+
+    #[test]
+    fn renders_memory_numbers() {
+        let stats = MemoryStats {
+            preview_bytes: 2048,
+            preview_cache_bytes: 1024 * 1024,
+            preview_cache_entries: 3,
+            prefetch_hits: 5,
+            prefetch_misses: 1,
+            scrollback_bytes: 512,
+            scrollback_lines: 7,
+            tasks: TaskCounters {
+                running: 2,
+                waiting: 1,
+                finished: 37,
+                cancelled: 3,
+                deferred: 4,
+            },
+            maps: MapStats {
+                entries: 120,
+                evictions: 9,
+            },
+        };
+        let theme = dark_theme();
+        let area = Rect::new(0, 0, 60, 10);
+        let mut buf = Buffer::empty(area);
+        DebugOverlay::new(&stats, &theme).render(area, &mut buf);
+        let text = buffer_to_string(&buf, area);
+        assert!(text.contains("Debug"));
+        assert!(text.contains("2.00 KB"));
+        assert!(text.contains("1.00 MB (3 files)"));
+        assert!(text.contains("5 hits / 1 misses"));
+        assert!(text.contains("512 B (7 lines)"));
+        assert!(text.contains("2 run / 1 wait / 4 held"));
+        assert!(text.contains("40 (3 cancelled)"));
+        assert!(text.contains("Path maps   120 (9 evicted)"));
+    }
+
+    #[test]
+    fn tiny_area_does_not_panic() {
+        let stats = MemoryStats::default();
+        let theme = dark_theme();
+        let area = Rect::new(0, 0, 3, 2);
+        let mut buf = Buffer::empty(area);
+        DebugOverlay::new(&stats, &theme).render(area, &mut buf);
+    }
+}
@@ -1,3 +1,7 @@
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
 use ratatui::{
     buffer::Buffer,
     layout::Rect,
@@ -6,7 +10,23 @@ use ratatui::{
     widgets::{Block, Borders, Clear, Padding, Widget},
 };
 
-use crate::app::{AppMode, DialogKind, DialogState};
+use crate::app::{
+    AppMode, DialogKind, DialogState, ExportField, ExportForm, ScaffoldForm, SequenceField,
+    SequenceForm, Sizing, DELETE_LIST_ROWS,
+};
+use crate::file_info::{self, FileInfo};
+use crate::fs::bulk_rename::BulkRenameForm;
+use crate::fs::operations::DiskUsage;
+use crate::fs::overwrite::{BackupTo, Collision};
+use crate::fs::portability::Report as PortabilityReport;
+use crate::fs::scaffold::{Scaffold, MANIFEST};
+use crate::fs::undo_check::UndoConflict;
+use crate::paste_guard;
+use crate::permissions::{self, PermissionsField, PermissionsForm};
+use crate::preview_content;
+use crate::recent_ops::CompletedOp;
+use crate::tasks::TaskInfo;
+use crate::text;
 use crate::theme::ThemeColors;
 
 /// Dialog widget that renders a centered modal overlay.
@@ -42,37 +62,166 @@ impl<'a> Widget for DialogWidget<'a> {
             _ => return,
         };
 
+        let title = kind.title();
         match kind {
-            DialogKind::CreateFile => {
-                render_input_dialog("Create New File", self.dialog_state, self.theme, area, buf);
-            }
-            DialogKind::CreateDirectory => {
+            DialogKind::CreateFile
+            | DialogKind::CreateDirectory
+            | DialogKind::Rename { .. }
+            | DialogKind::GotoPath
+            | DialogKind::ExportProfile => {
                 render_input_dialog(
-                    "Create New Directory",
+                    &title,
                     self.dialog_state,
+                    kind.takes_name(),
                     self.theme,
                     area,
                     buf,
                 );
             }
-            DialogKind::Rename { .. } => {
-                render_input_dialog("Rename", self.dialog_state, self.theme, area, buf);
+            DialogKind::DeleteConfirm {
+                targets,
+                sizing,
+                scroll,
+                to_trash,
+            } => {
+                let verb = if *to_trash { "trash" } else { "delete" };
+                render_confirm_dialog(
+                    &title, verb, targets, *sizing, *scroll, self.theme, area, buf,
+                );
             }
-            DialogKind::DeleteConfirm { targets } => {
-                render_confirm_dialog(targets, self.theme, area, buf);
+            DialogKind::TransferConfirm {
+                items,
+                was_cut,
+                sizing,
+            } => {
+                let verb = if *was_cut { "move" } else { "copy" };
+                render_transfer_confirm_dialog(
+                    &title, verb, *items, *sizing, self.theme, area, buf,
+                );
             }
             DialogKind::Error { message } => {
-                render_error_dialog(message, self.theme, area, buf);
+                render_error_dialog(&title, message, self.theme, area, buf);
             }
             DialogKind::Progress {
                 message,
                 current,
                 total,
+                bytes_done,
+                bytes_total,
+                started,
+                rate,
+                spin,
             } => {
-                render_progress_dialog(message, *current, *total, self.theme, area, buf);
+                let meter = match total {
+                    Some(total) => {
+                        let fraction = if *bytes_total > 0 {
+                            (*bytes_done as f64 / *bytes_total as f64).min(1.0)
+                        } else if *total > 0 {
+                            *current as f64 / *total as f64
+                        } else {
+                            0.0
+                        };
+                        Meter::Bar(fraction)
+                    }
+                    None => Meter::Spinner(format!(
+                        "{} {} done · {}",
+                        SPINNER[spin % SPINNER.len()],
+                        group_thousands(*current),
+                        elapsed(started.elapsed())
+                    )),
+                };
+                let stats = progress_stats(*bytes_done, *bytes_total, *rate);
+                render_progress_dialog(&title, message, &meter, &stats, self.theme, area, buf);
             }
             DialogKind::SaveConfirm => {
-                render_save_confirm_dialog(self.theme, area, buf);
+                render_save_confirm_dialog(&title, self.theme, area, buf);
+            }
+            DialogKind::UndoConfirm {
+                conflicts,
+                selected,
+                from_previous_session,
+            } => {
+                render_undo_confirm_dialog(
+                    &title,
+                    conflicts,
+                    *selected,
+                    *from_previous_session,
+                    self.theme,
+                    area,
+                    buf,
+                );
+            }
+            DialogKind::SequenceRename { form } => {
+                render_sequence_rename_dialog(&title, form, self.theme, area, buf);
+            }
+            DialogKind::BulkRename { form } => {
+                render_bulk_rename_dialog(&title, form, self.theme, area, buf);
+            }
+            DialogKind::PasteConflict {
+                collisions,
+                current,
+                backup,
+            } => {
+                if let Some(collision) = collisions.get(*current) {
+                    let left = collisions.len() - current;
+                    render_paste_conflict_dialog(
+                        &title,
+                        collision,
+                        left,
+                        backup.as_ref(),
+                        self.theme,
+                        area,
+                        buf,
+                    );
+                }
+            }
+            DialogKind::TerminalHere { dir } => {
+                render_terminal_here_dialog(&title, dir, self.theme, area, buf);
+            }
+            DialogKind::ExportTree { form } => {
+                render_export_dialog(&title, form, self.theme, area, buf);
+            }
+            DialogKind::WatcherDetails { lines } => {
+                render_watcher_details_dialog(&title, lines, self.theme, area, buf);
+            }
+            DialogKind::Portability { report, selected } => {
+                render_portability_dialog(&title, report, *selected, self.theme, area, buf);
+            }
+            DialogKind::RecentOperations {
+                ops,
+                undoable,
+                selected,
+                expanded,
+            } => {
+                render_recent_operations_dialog(
+                    &title, ops, *undoable, *selected, *expanded, self.theme, area, buf,
+                );
+            }
+            DialogKind::Tasks { tasks, selected } => {
+                render_tasks_dialog(&title, tasks, *selected, self.theme, area, buf);
+            }
+            DialogKind::ScaffoldPicker {
+                scaffolds,
+                selected,
+                dest,
+            } => {
+                render_scaffold_picker_dialog(
+                    &title, scaffolds, *selected, dest, self.theme, area, buf,
+                );
+            }
+            DialogKind::ScaffoldForm { form } => {
+                render_scaffold_form_dialog(&title, form, self.theme, area, buf);
+            }
+            DialogKind::BusyRetry {
+                message, attempt, ..
+            } => {
+                render_busy_retry_dialog(&title, message, *attempt, self.theme, area, buf);
+            }
+            DialogKind::Info { info, .. } => {
+                render_info_dialog(&title, info, self.theme, area, buf);
+            }
+            DialogKind::Permissions { form } => {
+                render_permissions_dialog(&title, form, self.theme, area, buf);
             }
         }
     }
@@ -81,12 +230,32 @@ impl<'a> Widget for DialogWidget<'a> {
 fn render_input_dialog(
     title: &str,
     state: &DialogState,
+    is_name: bool,
     theme: &ThemeColors,
     area: Rect,
     buf: &mut Buffer,
 ) {
+    // What a paste lost, how close a name is to the length limit, and
+    // what is wrong with it, one per line above the input
+    let length = is_name
+        .then(|| paste_guard::name_feedback(state.input.len()))
+        .flatten();
+    let check = state.name_check.as_ref();
+    let problems = check.and_then(|check| check.summary());
+    let suggestion = check
+        .and_then(|check| check.suggestion.as_ref())
+        .map(|name| format!("did you mean '{}'? [Tab] accept", name));
+    let notes: Vec<&str> = state
+        .warning
+        .as_deref()
+        .into_iter()
+        .chain(length.as_deref())
+        .chain(problems.as_deref())
+        .chain(suggestion.as_deref())
+        .collect();
+
     let dialog_width = 50.min(area.width.saturating_sub(4));
-    let dialog_height = 5;
+    let dialog_height = 5 + notes.len().saturating_sub(1) as u16;
     let rect = DialogWidget::centered_rect(dialog_width, dialog_height, area);
 
     Clear.render(rect, buf);
@@ -106,24 +275,22 @@ fn render_input_dialog(
 
     // Render input line with cursor
     let input = &state.input;
-    let cursor_pos = state.cursor_position;
+    let cursor_pos = state.cursor_position.min(input.len());
     let max_width = inner.width as usize;
 
-    let (before, cursor_char, after) = if cursor_pos < input.len() {
-        let ch = &input[cursor_pos..cursor_pos + 1];
-        (&input[..cursor_pos], ch, &input[cursor_pos + 1..])
-    } else {
-        (input.as_str(), " ", "")
+    let before = &input[..cursor_pos];
+    let (cursor_char, after) = match text::grapheme_at(input, cursor_pos) {
+        Some(g) => (g, &input[cursor_pos + g.len()..]),
+        None => (" ", ""),
     };
 
-    // Truncate from left if input is too long
-    let total_len = before.len() + 1 + after.len();
-    let before_display = if total_len > max_width && before.len() > max_width.saturating_sub(2) {
-        let skip = before.len().saturating_sub(max_width.saturating_sub(2));
-        &before[skip..]
-    } else {
-        before
-    };
+    // Drop whole graphemes from the left until the cursor fits
+    let room = max_width.saturating_sub(text::width(cursor_char).max(1) + 1);
+    let mut skip = 0;
+    while text::width(&before[skip..]) > room {
+        skip = text::next_boundary(before, skip);
+    }
+    let before_display = &before[skip..];
 
     let input_style = Style::default().fg(theme.status_fg);
     let cursor_style = Style::default()
@@ -140,6 +307,15 @@ fn render_input_dialog(
     let line = Line::from(spans);
     buf.set_line(inner.x, inner.y + inner.height / 2, &line, inner.width);
 
+    let note_style = Style::default().fg(theme.warning_fg);
+    for (row, note) in notes.iter().enumerate() {
+        if row as u16 >= inner.height / 2 {
+            break;
+        }
+        let note = Line::from(Span::styled(*note, note_style));
+        buf.set_line(inner.x, inner.y + row as u16, &note, inner.width);
+    }
+
     // Render hint at bottom
     let hint = "[Enter] Confirm  [Esc] Cancel";
     let hint_style = Style::default()
@@ -151,8 +327,102 @@ fn render_input_dialog(
     }
 }
 
+/// Braille spinner frames for background counts.
+const SPINNER: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+/// Spinner frame for a count running since `started`.
+pub fn spinner_frame(started: Instant) -> &'static str {
+    SPINNER[(started.elapsed().as_millis() / 100) as usize % SPINNER.len()]
+}
+
+/// `1234567` as "1,234,567".
+pub fn group_thousands(n: usize) -> String {
+    let digits = n.to_string();
+    let mut out = String::new();
+    for (i, ch) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(ch);
+    }
+    out
+}
+
+/// Size of `usage`, "at least …" when the walk stopped early.
+fn usage_size(usage: DiskUsage) -> String {
+    let size = preview_content::format_size(usage.bytes);
+    if usage.complete {
+        size
+    } else {
+        format!("at least {}", size)
+    }
+}
+
+/// Headline and hint for a confirmation sized by a pre-flight count:
+/// "Delete 3 items (1.20 KB)?", a spinner while counting, or the scale of
+/// a large operation. `verb` is lowercase ("delete", "copy", "move").
+fn sized_prompt(verb: &str, items: usize, sizing: Sizing) -> (String, &'static str) {
+    let mut verb_title = verb.to_string();
+    verb_title[..1].make_ascii_uppercase();
+    let what = format!("{} item{}", items, if items == 1 { "" } else { "s" });
+    match sizing {
+        Sizing::Counting { started } => (
+            format!(
+                "{} {} — counting {}",
+                verb_title,
+                what,
+                spinner_frame(started)
+            ),
+            "[n/Esc] Cancel",
+        ),
+        Sizing::Counted {
+            usage,
+            large: false,
+            ..
+        } => (
+            format!("{} {} ({})?", verb_title, what, usage_size(usage)),
+            "[y] Yes  [n/Esc] Cancel",
+        ),
+        Sizing::Counted { usage, armed, .. } => {
+            let files = if usage.complete {
+                format!("~{}", group_thousands(usage.files))
+            } else {
+                format!("more than {}", group_thousands(usage.files))
+            };
+            let hint = if armed {
+                "[Enter] Proceed  [n/Esc] Cancel"
+            } else {
+                "[y] then [Enter] Proceed  [n/Esc] Cancel"
+            };
+            (
+                format!(
+                    "This will {} {} files ({}). Proceed?",
+                    verb,
+                    files,
+                    usage_size(usage)
+                ),
+                hint,
+            )
+        }
+    }
+}
+
+/// Style of a sized headline: large operations stand out.
+fn sized_style(sizing: Sizing, theme: &ThemeColors) -> Style {
+    let color = match sizing {
+        Sizing::Counted { large: true, .. } => theme.error_fg,
+        _ => theme.warning_fg,
+    };
+    Style::default().fg(color).add_modifier(Modifier::BOLD)
+}
+
+#[allow(clippy::too_many_arguments)]
 fn render_confirm_dialog(
+    title: &str,
+    verb: &str,
     targets: &[std::path::PathBuf],
+    sizing: Sizing,
+    scroll: usize,
     theme: &ThemeColors,
     area: Rect,
     buf: &mut Buffer,
@@ -163,17 +433,34 @@ fn render_confirm_dialog(
         .map(|n| n.to_string_lossy().len())
         .max()
         .unwrap_or(10);
+    // Links on the first page widen the dialog to fit their note
+    let max_line_len = targets
+        .iter()
+        .skip(scroll)
+        .take(DELETE_LIST_ROWS)
+        .filter_map(|p| {
+            let note = link_note(p)?;
+            let name = p.file_name()?.to_string_lossy().len();
+            Some(name + text::width(&note))
+        })
+        .fold(max_name_len, usize::max);
+
+    let (header, hint) = sized_prompt(verb, targets.len(), sizing);
 
-    let dialog_width = (max_name_len as u16 + 10)
-        .max(40)
+    // Long lists scroll, with a position line under them
+    let scrollable = targets.len() > DELETE_LIST_ROWS;
+    let list_rows = targets.len().min(DELETE_LIST_ROWS) + usize::from(scrollable);
+    let dialog_width = (max_line_len as u16 + 10)
+        .max(44)
+        .max(text::width(&header) as u16 + 4)
         .min(area.width.saturating_sub(4));
-    let dialog_height = (targets.len() as u16 + 6).min(area.height.saturating_sub(2));
+    let dialog_height = (list_rows as u16 + 6).min(area.height.saturating_sub(2));
     let rect = DialogWidget::centered_rect(dialog_width, dialog_height, area);
 
     Clear.render(rect, buf);
 
     let block = Block::default()
-        .title(" Delete Confirmation ")
+        .title(format!(" {} ", title))
         .borders(Borders::ALL)
         .border_style(Style::default().fg(theme.error_fg))
         .padding(Padding::horizontal(1));
@@ -185,31 +472,58 @@ fn render_confirm_dialog(
         return;
     }
 
-    // "Delete the following?" header
-    let header = Line::from(Span::styled(
-        "Delete the following?",
-        Style::default()
-            .fg(theme.warning_fg)
-            .add_modifier(Modifier::BOLD),
-    ));
+    // "Delete 3 items (1.20 KB)?" header
+    let header = Line::from(Span::styled(header, sized_style(sizing, theme)));
     buf.set_line(inner.x, inner.y, &header, inner.width);
 
-    // List targets
-    let max_items = (inner.height.saturating_sub(3)) as usize;
-    for (i, target) in targets.iter().take(max_items).enumerate() {
+    // List targets from `scroll`, keeping the last page full
+    let max_items = (inner.height.saturating_sub(3) as usize)
+        .saturating_sub(usize::from(scrollable))
+        .min(DELETE_LIST_ROWS);
+    let first = scroll.min(targets.len().saturating_sub(max_items));
+    for (i, target) in targets.iter().skip(first).take(max_items).enumerate() {
         let name = target
             .file_name()
             .map(|n| n.to_string_lossy().to_string())
             .unwrap_or_else(|| target.to_string_lossy().to_string());
-        let line = Line::from(Span::styled(
+        let mut spans = vec![Span::styled(
             format!("  • {}", name),
             Style::default().fg(theme.status_fg),
+        )];
+        if let Some(note) = link_note(target) {
+            spans.push(Span::styled(note, Style::default().fg(theme.dim_fg)));
+        }
+        buf.set_line(
+            inner.x,
+            inner.y + 2 + i as u16,
+            &Line::from(spans),
+            inner.width,
+        );
+    }
+    if scrollable && max_items > 0 {
+        let position = Line::from(Span::styled(
+            format!(
+                "  {}–{} of {}",
+                first + 1,
+                (first + max_items).min(targets.len()),
+                targets.len()
+            ),
+            Style::default().fg(theme.dim_fg),
         ));
-        buf.set_line(inner.x, inner.y + 2 + i as u16, &line, inner.width);
+        buf.set_line(
+            inner.x,
+            inner.y + 2 + max_items as u16,
+            &position,
+            inner.width,
+        );
     }
 
     // Render hint at bottom
-    let hint = "[y] Yes  [n/Esc] Cancel";
+    let hint = if scrollable {
+        format!("{}  [↑↓] Scroll", hint)
+    } else {
+        hint.to_string()
+    };
     let hint_style = Style::default()
         .fg(theme.dim_fg)
         .add_modifier(Modifier::DIM);
@@ -217,7 +531,65 @@ fn render_confirm_dialog(
     buf.set_line(inner.x, inner.y + inner.height - 1, &hint_line, inner.width);
 }
 
-fn render_error_dialog(message: &str, theme: &ThemeColors, area: Rect, buf: &mut Buffer) {
+/// What a delete target that is a symlink points to, noting that only the
+/// link is removed.
+fn link_note(target: &Path) -> Option<String> {
+    if !fs::symlink_metadata(target).is_ok_and(|m| m.is_symlink()) {
+        return None;
+    }
+    let link = fs::read_link(target).ok()?;
+    Some(format!(" → {} (link only, target kept)", link.display()))
+}
+
+fn render_transfer_confirm_dialog(
+    title: &str,
+    verb: &str,
+    items: usize,
+    sizing: Sizing,
+    theme: &ThemeColors,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let (header, hint) = sized_prompt(verb, items, sizing);
+    let dialog_width = (text::width(&header) as u16 + 4)
+        .max(44)
+        .min(area.width.saturating_sub(4));
+    let rect = DialogWidget::centered_rect(dialog_width, 5, area);
+
+    Clear.render(rect, buf);
+
+    let block = Block::default()
+        .title(format!(" {} ", title))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.warning_fg))
+        .padding(Padding::horizontal(1));
+
+    let inner = block.inner(rect);
+    block.render(rect, buf);
+
+    if inner.height == 0 || inner.width == 0 {
+        return;
+    }
+
+    let header = Line::from(Span::styled(header, sized_style(sizing, theme)));
+    buf.set_line(inner.x, inner.y, &header, inner.width);
+
+    let hint_style = Style::default()
+        .fg(theme.dim_fg)
+        .add_modifier(Modifier::DIM);
+    let hint_line = Line::from(Span::styled(hint, hint_style));
+    if inner.height > 1 {
+        buf.set_line(inner.x, inner.y + inner.height - 1, &hint_line, inner.width);
+    }
+}
+
+fn render_error_dialog(
+    title: &str,
+    message: &str,
+    theme: &ThemeColors,
+    area: Rect,
+    buf: &mut Buffer,
+) {
     let dialog_width = (message.len() as u16 + 6)
         .max(30)
         .min(area.width.saturating_sub(4));
@@ -227,7 +599,7 @@ fn render_error_dialog(message: &str, theme: &ThemeColors, area: Rect, buf: &mut
     Clear.render(rect, buf);
 
     let block = Block::default()
-        .title(" Error ")
+        .title(format!(" {} ", title))
         .borders(Borders::ALL)
         .border_style(Style::default().fg(theme.error_fg))
         .padding(Padding::horizontal(1));
@@ -254,10 +626,49 @@ fn render_error_dialog(message: &str, theme: &ThemeColors, area: Rect, buf: &mut
     }
 }
 
+/// Percentage, bytes and throughput under the progress bar, as far as
+/// they are known: "42% · 4.20 GB of 10.00 GB · 85.30 MB/s".
+fn progress_stats(bytes_done: u64, bytes_total: u64, rate: Option<u64>) -> String {
+    let mut parts = Vec::new();
+    if let Some(percent) = (bytes_done.min(bytes_total) * 100).checked_div(bytes_total) {
+        parts.push(format!("{}%", percent));
+        parts.push(format!(
+            "{} of {}",
+            preview_content::format_size(bytes_done),
+            preview_content::format_size(bytes_total)
+        ));
+    } else if bytes_done > 0 {
+        parts.push(preview_content::format_size(bytes_done));
+    }
+    if let Some(rate) = rate {
+        parts.push(format!("{}/s", preview_content::format_size(rate)));
+    }
+    parts.join(" · ")
+}
+
+/// Second line of the progress dialog.
+enum Meter {
+    /// Fraction of the bar filled.
+    Bar(f64),
+    /// Spinner, running count and elapsed time, while the total is unknown.
+    Spinner(String),
+}
+
+/// Time since an operation started: "42s", "3m 07s", "1h 02m".
+fn elapsed(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m {:02}s", secs / 60, secs % 60),
+        _ => format!("{}h {:02}m", secs / 3600, secs / 60 % 60),
+    }
+}
+
 fn render_progress_dialog(
+    title: &str,
     current_file: &str,
-    current: usize,
-    total: usize,
+    meter: &Meter,
+    stats: &str,
     theme: &ThemeColors,
     area: Rect,
     buf: &mut Buffer,
@@ -268,9 +679,8 @@ fn render_progress_dialog(
 
     Clear.render(rect, buf);
 
-    let title = format!(" Processing {}/{} ", current, total);
     let block = Block::default()
-        .title(title)
+        .title(format!(" {} ", title))
         .borders(Borders::ALL)
         .border_style(Style::default().fg(theme.warning_fg))
         .padding(Padding::horizontal(1));
@@ -289,13 +699,30 @@ fn render_progress_dialog(
     ));
     buf.set_line(inner.x, inner.y, &file_line, inner.width);
 
-    // Simple progress bar
-    if inner.height > 1 && total > 0 {
-        let bar_width = inner.width as usize;
-        let filled = (current * bar_width) / total;
-        let bar: String = "█".repeat(filled) + &"░".repeat(bar_width.saturating_sub(filled));
-        let bar_line = Line::from(Span::styled(bar, Style::default().fg(theme.info_fg)));
-        buf.set_line(inner.x, inner.y + 1, &bar_line, inner.width);
+    // Simple progress bar, or a spinner while the total is unknown
+    if inner.height > 1 {
+        let meter_line = match meter {
+            Meter::Bar(fraction) => {
+                let bar_width = inner.width as usize;
+                let filled = ((fraction * bar_width as f64) as usize).min(bar_width);
+                let bar: String =
+                    "█".repeat(filled) + &"░".repeat(bar_width.saturating_sub(filled));
+                Line::from(Span::styled(bar, Style::default().fg(theme.info_fg)))
+            }
+            Meter::Spinner(text) => Line::from(Span::styled(
+                text.clone(),
+                Style::default().fg(theme.info_fg),
+            )),
+        };
+        buf.set_line(inner.x, inner.y + 1, &meter_line, inner.width);
+    }
+
+    if inner.height > 3 && !stats.is_empty() {
+        let stats_line = Line::from(Span::styled(
+            stats.to_string(),
+            Style::default().fg(theme.status_fg),
+        ));
+        buf.set_line(inner.x, inner.y + 2, &stats_line, inner.width);
     }
 
     // Hint at bottom
@@ -309,7 +736,7 @@ fn render_progress_dialog(
     }
 }
 
-fn render_save_confirm_dialog(theme: &ThemeColors, area: Rect, buf: &mut Buffer) {
+fn render_save_confirm_dialog(title: &str, theme: &ThemeColors, area: Rect, buf: &mut Buffer) {
     let dialog_width = 50u16.min(area.width.saturating_sub(4));
     let dialog_height = 6;
     let rect = DialogWidget::centered_rect(dialog_width, dialog_height, area);
@@ -317,7 +744,7 @@ fn render_save_confirm_dialog(theme: &ThemeColors, area: Rect, buf: &mut Buffer)
     Clear.render(rect, buf);
 
     let block = Block::default()
-        .title(" Unsaved Changes ")
+        .title(format!(" {} ", title))
         .borders(Borders::ALL)
         .border_style(Style::default().fg(theme.warning_fg))
         .padding(Padding::horizontal(1));
@@ -349,93 +776,2034 @@ fn render_save_confirm_dialog(theme: &ThemeColors, area: Rect, buf: &mut Buffer)
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::theme;
-    use std::path::PathBuf;
+fn render_paste_conflict_dialog(
+    title: &str,
+    collision: &Collision,
+    left: usize,
+    backup: Option<&BackupTo>,
+    theme: &ThemeColors,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let dialog_width = 64u16.min(area.width.saturating_sub(4));
+    let dialog_height = 6;
+    let rect = DialogWidget::centered_rect(dialog_width, dialog_height, area);
 
-    fn test_theme() -> ThemeColors {
-        theme::dark_theme()
-    }
+    Clear.render(rect, buf);
 
-    #[test]
-    fn test_input_dialog_renders() {
-        let mode = AppMode::Dialog(DialogKind::CreateFile);
-        let state = DialogState {
-            input: "test.txt".to_string(),
-            cursor_position: 8,
-        };
-        let tc = test_theme();
-        let widget = DialogWidget::new(&mode, &state, &tc);
-        let area = Rect::new(0, 0, 80, 24);
-        let mut buf = Buffer::empty(area);
-        widget.render(area, &mut buf);
+    let block = Block::default()
+        .title(format!(" {} ", title))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.dialog_border_fg))
+        .padding(Padding::horizontal(1));
 
-        // Check that the dialog title appears
-        let content = buffer_to_string(&buf, area);
-        assert!(content.contains("Create New File"));
-        assert!(content.contains("test.txt"));
+    let inner = block.inner(rect);
+    block.render(rect, buf);
+
+    if inner.height == 0 || inner.width == 0 {
+        return;
     }
 
-    #[test]
-    fn test_rename_dialog_renders() {
-        let mode = AppMode::Dialog(DialogKind::Rename {
-            original: PathBuf::from("/tmp/old_name.txt"),
-        });
-        let state = DialogState {
-            input: "old_name.txt".to_string(),
-            cursor_position: 12,
-        };
-        let tc = test_theme();
-        let widget = DialogWidget::new(&mode, &state, &tc);
-        let area = Rect::new(0, 0, 80, 24);
-        let mut buf = Buffer::empty(area);
-        widget.render(area, &mut buf);
+    let kind = if collision.dest_is_dir {
+        "Folder"
+    } else {
+        "File"
+    };
+    let name = collision
+        .dest
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let msg = Line::from(Span::styled(
+        text::truncate(
+            &format!("{} '{}' is already here.", kind, name),
+            inner.width as usize,
+        ),
+        Style::default()
+            .fg(theme.status_fg)
+            .add_modifier(Modifier::BOLD),
+    ));
+    buf.set_line(inner.x, inner.y, &msg, inner.width);
 
-        let content = buffer_to_string(&buf, area);
-        assert!(content.contains("Rename"));
-        assert!(content.contains("old_name.txt"));
+    // What overwriting does to the existing item
+    let note = match (collision.dest_is_dir, backup) {
+        (true, _) => "Overwrite merges into it; files replaced inside aren't kept.",
+        (false, Some(BackupTo::Rename)) => "Overwrite keeps it as a .fm-bak- copy first.",
+        (false, Some(BackupTo::Trash(_))) => "Overwrite moves it to the trash first.",
+        (false, None) => "Overwrite replaces it for good.",
+    };
+    let note_line = Line::from(Span::styled(
+        text::truncate(note, inner.width as usize),
+        Style::default().fg(theme.status_fg),
+    ));
+    if inner.height > 2 {
+        buf.set_line(inner.x, inner.y + 1, &note_line, inner.width);
     }
 
-    #[test]
-    fn test_confirm_dialog_renders() {
-        let targets = vec![
-            PathBuf::from("/tmp/file1.txt"),
-            PathBuf::from("/tmp/file2.txt"),
-        ];
-        let mode = AppMode::Dialog(DialogKind::DeleteConfirm {
-            targets: targets.clone(),
-        });
-        let state = DialogState::default();
-        let tc = test_theme();
-        let widget = DialogWidget::new(&mode, &state, &tc);
-        let area = Rect::new(0, 0, 80, 24);
-        let mut buf = Buffer::empty(area);
-        widget.render(area, &mut buf);
-
-        let content = buffer_to_string(&buf, area);
-        assert!(content.contains("Delete"));
-        assert!(content.contains("file1.txt"));
-        assert!(content.contains("file2.txt"));
+    let hint = if left > 1 {
+        "[o] Overwrite  [a] All  [k] Keep both  [s] Skip  [Esc] Cancel"
+    } else {
+        "[o] Overwrite  [k] Keep both  [s] Skip  [Esc] Cancel"
+    };
+    let hint_style = Style::default()
+        .fg(theme.dim_fg)
+        .add_modifier(Modifier::DIM);
+    let hint_line = Line::from(Span::styled(hint, hint_style));
+    if inner.height > 1 {
+        buf.set_line(inner.x, inner.y + inner.height - 1, &hint_line, inner.width);
     }
+}
 
-    #[test]
-    fn test_error_dialog_renders() {
-        let mode = AppMode::Dialog(DialogKind::Error {
-            message: "Permission denied".to_string(),
-        });
-        let state = DialogState::default();
-        let tc = test_theme();
-        let widget = DialogWidget::new(&mode, &state, &tc);
-        let area = Rect::new(0, 0, 80, 24);
-        let mut buf = Buffer::empty(area);
-        widget.render(area, &mut buf);
+fn render_terminal_here_dialog(
+    title: &str,
+    dir: &Path,
+    theme: &ThemeColors,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let dialog_width = 56u16.min(area.width.saturating_sub(4));
+    let dialog_height = 6;
+    let rect = DialogWidget::centered_rect(dialog_width, dialog_height, area);
 
-        let content = buffer_to_string(&buf, area);
-        assert!(content.contains("Error"));
-        assert!(content.contains("Permission denied"));
-    }
+    Clear.render(rect, buf);
+
+    let block = Block::default()
+        .title(format!(" {} ", title))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.dialog_border_fg))
+        .padding(Padding::horizontal(1));
+
+    let inner = block.inner(rect);
+    block.render(rect, buf);
+
+    if inner.height == 0 || inner.width == 0 {
+        return;
+    }
+
+    let msg = Line::from(Span::styled(
+        "A shell is already running in the terminal.",
+        Style::default()
+            .fg(theme.status_fg)
+            .add_modifier(Modifier::BOLD),
+    ));
+    buf.set_line(inner.x, inner.y, &msg, inner.width);
+
+    let target = text::truncate(&dir.to_string_lossy(), inner.width as usize);
+    let target_line = Line::from(Span::styled(target, Style::default().fg(theme.status_fg)));
+    if inner.height > 2 {
+        buf.set_line(inner.x, inner.y + 1, &target_line, inner.width);
+    }
+
+    let hint = "[c] cd in current shell  [r] Restart here  [Esc] Cancel";
+    let hint_style = Style::default()
+        .fg(theme.dim_fg)
+        .add_modifier(Modifier::DIM);
+    let hint_line = Line::from(Span::styled(hint, hint_style));
+    if inner.height > 1 {
+        buf.set_line(inner.x, inner.y + inner.height - 1, &hint_line, inner.width);
+    }
+}
+
+fn render_busy_retry_dialog(
+    title: &str,
+    message: &str,
+    attempt: Option<(usize, usize)>,
+    theme: &ThemeColors,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let dialog_width = 64u16.min(area.width.saturating_sub(4));
+    let dialog_height = 7;
+    let rect = DialogWidget::centered_rect(dialog_width, dialog_height, area);
+
+    Clear.render(rect, buf);
+
+    let block = Block::default()
+        .title(format!(" {} ", title))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.dialog_border_fg))
+        .padding(Padding::horizontal(1));
+
+    let inner = block.inner(rect);
+    block.render(rect, buf);
+
+    if inner.height == 0 || inner.width == 0 {
+        return;
+    }
+
+    // The error and its hint, over two lines when long
+    let width = inner.width as usize;
+    let (first, rest) = match message.split_once(" — ") {
+        Some((error, hint)) if text::width(message) > width => (error, hint),
+        _ => (message, ""),
+    };
+    for (i, line) in [first, rest].into_iter().enumerate() {
+        let line = Line::from(Span::styled(
+            text::truncate(line, width),
+            Style::default().fg(theme.status_fg),
+        ));
+        buf.set_line(inner.x, inner.y + i as u16, &line, inner.width);
+    }
+
+    let delay = crate::fs::retry::RETRY_DELAY.as_secs();
+    let stop = "[Esc] Stop".to_string();
+    let (status, hint) = match attempt {
+        Some((0, _)) => ("Retrying…".to_string(), stop),
+        Some((current, 1)) => (format!("Retrying… (attempt {})", current), stop),
+        Some((current, total)) => (
+            format!(
+                "Still in use; attempt {}/{}, every {}s",
+                current, total, delay
+            ),
+            stop,
+        ),
+        None => (
+            String::new(),
+            format!(
+                "[r] Retry  [a] Retry every {}s ({}×)  [s] Skip",
+                delay,
+                crate::fs::retry::MAX_AUTO_ATTEMPTS
+            ),
+        ),
+    };
+    if inner.height > 3 {
+        let status_line = Line::from(Span::styled(status, Style::default().fg(theme.info_fg)));
+        buf.set_line(inner.x, inner.y + 2, &status_line, inner.width);
+    }
+
+    let hint_style = Style::default()
+        .fg(theme.dim_fg)
+        .add_modifier(Modifier::DIM);
+    let hint_line = Line::from(Span::styled(hint, hint_style));
+    if inner.height > 1 {
+        buf.set_line(inner.x, inner.y + inner.height - 1, &hint_line, inner.width);
+    }
+}
+
+/// Labels in a column, their values beside them; a size still being
+/// computed is dimmed.
+fn render_permissions_dialog(
+    title: &str,
+    form: &PermissionsForm,
+    theme: &ThemeColors,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let dialog_width = 60.min(area.width.saturating_sub(4));
+    // Header + 3 classes + blank + octal + note + blank + hint + borders
+    let dialog_height = 11.min(area.height.saturating_sub(2));
+    let rect = DialogWidget::centered_rect(dialog_width, dialog_height, area);
+
+    Clear.render(rect, buf);
+
+    let block = Block::default()
+        .title(format!(" {} ", title))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.dialog_border_fg))
+        .padding(Padding::horizontal(1));
+
+    let inner = block.inner(rect);
+    block.render(rect, buf);
+
+    if inner.height < 9 || inner.width == 0 {
+        return;
+    }
+
+    let label_style = Style::default()
+        .fg(theme.info_fg)
+        .add_modifier(Modifier::BOLD);
+    let value_style = Style::default().fg(theme.status_fg);
+    let focused_style = Style::default()
+        .fg(theme.status_fg)
+        .bg(theme.tree_selected_bg)
+        .add_modifier(Modifier::BOLD);
+
+    let mut header = vec![Span::raw("        ")];
+    header.extend(
+        permissions::BITS
+            .iter()
+            .map(|bit| Span::styled(format!("{:<7}", bit), label_style)),
+    );
+    buf.set_line(inner.x, inner.y, &Line::from(header), inner.width);
+    for (row, class) in permissions::CLASSES.iter().enumerate() {
+        let mut spans = vec![Span::styled(format!("{:<8}", class), label_style)];
+        for col in 0..permissions::BITS.len() {
+            let mark = if form.is_set(row, col) { "[x]" } else { "[ ]" };
+            let on_cursor =
+                form.field == PermissionsField::Grid && (form.row, form.col) == (row, col);
+            let style = if on_cursor {
+                focused_style
+            } else {
+                value_style
+            };
+            spans.push(Span::styled(mark, style));
+            spans.push(Span::raw("    "));
+        }
+        let line = Line::from(spans);
+        buf.set_line(inner.x, inner.y + 1 + row as u16, &line, inner.width);
+    }
+
+    let octal_line = if form.field == PermissionsField::Octal {
+        Span::styled(format!("{}_", form.octal), focused_style)
+    } else {
+        Span::styled(form.octal.clone(), value_style)
+    };
+    let line = Line::from(vec![
+        Span::styled("Octal   ", label_style),
+        octal_line,
+        Span::styled(
+            format!("   {}", form.describe()),
+            Style::default().fg(theme.dim_fg),
+        ),
+    ]);
+    buf.set_line(inner.x, inner.y + 5, &line, inner.width);
+
+    // Why Enter won't apply, or that the targets start out different
+    let note = match form.parsed() {
+        Err(msg) => Some(format!("⚠ {}", msg)),
+        Ok(_) if form.mixed => Some(format!(
+            "⚠ Modes differ; all {} items get this one",
+            form.paths.len()
+        )),
+        Ok(_) => None,
+    };
+    if let Some(note) = note {
+        let line = Line::from(Span::styled(note, Style::default().fg(theme.warning_fg)));
+        buf.set_line(inner.x, inner.y + 6, &line, inner.width);
+    }
+
+    let hint = "[Space] Toggle  [Tab] Octal  [Enter] Apply  [Esc] Cancel";
+    let hint_style = Style::default()
+        .fg(theme.dim_fg)
+        .add_modifier(Modifier::DIM);
+    let hint_line = Line::from(Span::styled(hint, hint_style));
+    buf.set_line(inner.x, inner.y + inner.height - 1, &hint_line, inner.width);
+}
+
+fn render_info_dialog(
+    title: &str,
+    info: &FileInfo,
+    theme: &ThemeColors,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let hint = "[Esc] Close";
+    let label_width = info
+        .rows
+        .iter()
+        .map(|(label, _)| label.len())
+        .max()
+        .unwrap_or(0)
+        + 2;
+    let longest = info
+        .rows
+        .iter()
+        .map(|(_, value)| label_width + text::width(value))
+        .max()
+        .unwrap_or(0)
+        .max(text::width(title) + 2);
+    let dialog_width = (longest as u16 + 4).min(area.width.saturating_sub(4));
+    let dialog_height = (info.rows.len() as u16 + 4).min(area.height.saturating_sub(2));
+    let rect = DialogWidget::centered_rect(dialog_width, dialog_height, area);
+
+    Clear.render(rect, buf);
+
+    let block = Block::default()
+        .title(format!(" {} ", title))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.dialog_border_fg))
+        .padding(Padding::horizontal(1));
+
+    let inner = block.inner(rect);
+    block.render(rect, buf);
+
+    if inner.height == 0 || inner.width == 0 {
+        return;
+    }
+
+    // Keep the last row for the hint
+    let rows = inner.height.saturating_sub(2) as usize;
+    let room = (inner.width as usize).saturating_sub(label_width);
+    let label_style = Style::default()
+        .fg(theme.info_fg)
+        .add_modifier(Modifier::BOLD);
+    for (i, (label, value)) in info.rows.iter().take(rows).enumerate() {
+        let value_style = if value == file_info::CALCULATING {
+            Style::default().fg(theme.dim_fg)
+        } else {
+            Style::default().fg(theme.status_fg)
+        };
+        let line = Line::from(vec![
+            Span::styled(
+                format!("{:<width$}", label, width = label_width),
+                label_style,
+            ),
+            Span::styled(text::truncate(value, room), value_style),
+        ]);
+        buf.set_line(inner.x, inner.y + i as u16, &line, inner.width);
+    }
+
+    let hint_style = Style::default()
+        .fg(theme.dim_fg)
+        .add_modifier(Modifier::DIM);
+    let hint_line = Line::from(Span::styled(hint, hint_style));
+    if inner.height > 1 {
+        buf.set_line(inner.x, inner.y + inner.height - 1, &hint_line, inner.width);
+    }
+}
+
+fn render_watcher_details_dialog(
+    title: &str,
+    lines: &[String],
+    theme: &ThemeColors,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let hint = "[r] Retry  [p] Use polling  [Esc] Close";
+    let longest = lines
+        .iter()
+        .map(|l| l.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max(hint.len());
+    let dialog_width = (longest as u16 + 4).min(area.width.saturating_sub(4));
+    let dialog_height = (lines.len() as u16 + 4).min(area.height.saturating_sub(2));
+    let rect = DialogWidget::centered_rect(dialog_width, dialog_height, area);
+
+    Clear.render(rect, buf);
+
+    let block = Block::default()
+        .title(format!(" {} ", title))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.error_fg))
+        .padding(Padding::horizontal(1));
+
+    let inner = block.inner(rect);
+    block.render(rect, buf);
+
+    if inner.height == 0 || inner.width == 0 {
+        return;
+    }
+
+    // Keep the last row for the hint
+    let rows = inner.height.saturating_sub(2) as usize;
+    for (i, line) in lines.iter().take(rows).enumerate() {
+        let style = if i == 0 {
+            Style::default()
+                .fg(theme.error_fg)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(theme.status_fg)
+        };
+        let row = Line::from(Span::styled(
+            text::truncate(line, inner.width as usize),
+            style,
+        ));
+        buf.set_line(inner.x, inner.y + i as u16, &row, inner.width);
+    }
+
+    let hint_style = Style::default()
+        .fg(theme.dim_fg)
+        .add_modifier(Modifier::DIM);
+    let hint_line = Line::from(Span::styled(hint, hint_style));
+    if inner.height > 1 {
+        buf.set_line(inner.x, inner.y + inner.height - 1, &hint_line, inner.width);
+    }
+}
+
+/// One row per finding: its path below the checked directory and the
+/// suggested name. The selected finding's issues sit above the hint.
+fn render_portability_dialog(
+    title: &str,
+    report: &PortabilityReport,
+    selected: usize,
+    theme: &ThemeColors,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let rows: Vec<String> = report
+        .findings
+        .iter()
+        .map(|finding| {
+            let shown = finding
+                .path
+                .strip_prefix(&report.root)
+                .unwrap_or(&finding.path)
+                .display()
+                .to_string();
+            match &finding.suggestion {
+                Some(name) => format!("{} → {}", shown, name),
+                None => format!("{} (no rename)", shown),
+            }
+        })
+        .collect();
+    let hint = "[j/k] Move  [Enter] Go to  [R] Rename all  [Esc] Close";
+    let longest = rows
+        .iter()
+        .map(|r| text::width(r))
+        .max()
+        .unwrap_or(0)
+        .max(hint.len());
+    let dialog_width = (longest as u16 + 4)
+        .max(50)
+        .min(area.width.saturating_sub(4));
+    // header + blank + rows + blank + issues + hint + borders
+    let dialog_height = (rows.len() as u16 + 7).min(area.height.saturating_sub(2));
+    let rect = DialogWidget::centered_rect(dialog_width, dialog_height, area);
+
+    Clear.render(rect, buf);
+
+    let block = Block::default()
+        .title(format!(" {} ", title))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.warning_fg))
+        .padding(Padding::horizontal(1));
+
+    let inner = block.inner(rect);
+    block.render(rect, buf);
+
+    if inner.height < 5 || inner.width == 0 {
+        return;
+    }
+    let width = inner.width as usize;
+
+    let header = if report.complete {
+        format!(
+            "{} entries checked; invalid or risky on Windows/macOS:",
+            report.entries
+        )
+    } else {
+        format!(
+            "Stopped after {} entries; problems found so far:",
+            report.entries
+        )
+    };
+    let header = Line::from(Span::styled(
+        text::truncate(&header, width),
+        Style::default()
+            .fg(theme.warning_fg)
+            .add_modifier(Modifier::BOLD),
+    ));
+    buf.set_line(inner.x, inner.y, &header, inner.width);
+
+    let max_items = inner.height.saturating_sub(5) as usize;
+    render_selectable_rows(&rows, selected, 2, max_items, theme, inner, buf);
+
+    if let Some(finding) = report.findings.get(selected) {
+        let issues = Line::from(Span::styled(
+            text::truncate(&finding.summary(), width),
+            Style::default().fg(theme.warning_fg),
+        ));
+        buf.set_line(inner.x, inner.y + inner.height - 2, &issues, inner.width);
+    }
+
+    let hint_style = Style::default()
+        .fg(theme.dim_fg)
+        .add_modifier(Modifier::DIM);
+    let hint_line = Line::from(Span::styled(hint, hint_style));
+    buf.set_line(inner.x, inner.y + inner.height - 1, &hint_line, inner.width);
+}
+
+/// Draw `rows` from `top` lines into `inner`, at most `max_items` of them,
+/// highlighting `selected` and scrolling to keep it visible.
+fn render_selectable_rows(
+    rows: &[String],
+    selected: usize,
+    top: u16,
+    max_items: usize,
+    theme: &ThemeColors,
+    inner: Rect,
+    buf: &mut Buffer,
+) {
+    let width = inner.width as usize;
+    let start = selected.saturating_sub(max_items.saturating_sub(1));
+    for (i, row) in rows.iter().enumerate().skip(start).take(max_items) {
+        let mut style = Style::default().fg(theme.status_fg);
+        if i == selected {
+            style = style
+                .bg(theme.tree_selected_bg)
+                .add_modifier(Modifier::BOLD);
+        }
+        let line = Line::from(Span::styled(text::truncate(row, width), style));
+        buf.set_line(
+            inner.x,
+            inner.y + top + (i - start) as u16,
+            &line,
+            inner.width,
+        );
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn render_recent_operations_dialog(
+    title: &str,
+    ops: &[CompletedOp],
+    undoable: Option<u64>,
+    selected: usize,
+    expanded: bool,
+    theme: &ThemeColors,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    // ↶ marks the operation Ctrl+Z would reverse
+    let rows: Vec<String> = ops
+        .iter()
+        .map(|op| {
+            let mark = if undoable == Some(op.id) { "↶" } else { " " };
+            format!("{} {}", mark, op.summary())
+        })
+        .collect();
+    let details: Vec<String> = match ops.get(selected) {
+        Some(op) if expanded => {
+            let mut details = op.details();
+            details.push(if undoable == Some(op.id) {
+                "Undo: available".to_string()
+            } else {
+                "Undo: no longer available".to_string()
+            });
+            if !op.retry.is_empty() {
+                details.push(format!("Retry: {} item(s)", op.retry.len()));
+            }
+            details
+        }
+        _ => Vec::new(),
+    };
+    let hint = "[j/k] Move  [Enter] Details  [g] Go to  [u] Undo  [r] Retry  [Esc] Close";
+    let longest = rows
+        .iter()
+        .chain(&details)
+        .map(|r| text::width(r))
+        .max()
+        .unwrap_or(0)
+        .max(hint.len());
+    let dialog_width = (longest as u16 + 4)
+        .max(50)
+        .min(area.width.saturating_sub(4));
+    let details_height = if details.is_empty() {
+        0
+    } else {
+        details.len() + 1
+    };
+    // rows + details + blank + hint + borders
+    let dialog_height =
+        ((rows.len() + details_height) as u16 + 4).min(area.height.saturating_sub(2));
+    let rect = DialogWidget::centered_rect(dialog_width, dialog_height, area);
+
+    Clear.render(rect, buf);
+
+    let block = Block::default()
+        .title(format!(" {} ", title))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.info_fg))
+        .padding(Padding::horizontal(1));
+
+    let inner = block.inner(rect);
+    block.render(rect, buf);
+
+    if inner.height < 3 || inner.width == 0 {
+        return;
+    }
+    let width = inner.width as usize;
+
+    // The list yields room to the details, keeping a few rows
+    let available = inner.height.saturating_sub(2) as usize;
+    let list_height = if details.is_empty() {
+        available
+    } else {
+        rows.len().min(5).min(available)
+    };
+    render_selectable_rows(&rows, selected, 0, list_height, theme, inner, buf);
+
+    let detail_style = Style::default().fg(theme.info_fg);
+    for (i, detail) in details
+        .iter()
+        .enumerate()
+        .take(available.saturating_sub(list_height + 1))
+    {
+        let line = Line::from(Span::styled(text::truncate(detail, width), detail_style));
+        let y = inner.y + (list_height + 1 + i) as u16;
+        buf.set_line(inner.x, y, &line, inner.width);
+    }
+
+    let hint_style = Style::default()
+        .fg(theme.dim_fg)
+        .add_modifier(Modifier::DIM);
+    let hint_line = Line::from(Span::styled(hint, hint_style));
+    buf.set_line(inner.x, inner.y + inner.height - 1, &hint_line, inner.width);
+}
+
+fn render_tasks_dialog(
+    title: &str,
+    tasks: &[TaskInfo],
+    selected: usize,
+    theme: &ThemeColors,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let now = Instant::now();
+    let mut rows: Vec<String> = tasks.iter().map(|task| task.summary(now)).collect();
+    if rows.is_empty() {
+        rows.push("No background tasks running".to_string());
+    }
+    let hint = "[j/k] Move  [x] Cancel  [X] Cancel all  [Esc] Close";
+    let longest = rows
+        .iter()
+        .map(|r| text::width(r))
+        .max()
+        .unwrap_or(0)
+        .max(hint.len());
+    let dialog_width = (longest as u16 + 4)
+        .max(50)
+        .min(area.width.saturating_sub(4));
+    // rows + blank + hint + borders
+    let dialog_height = (rows.len() as u16 + 4).min(area.height.saturating_sub(2));
+    let rect = DialogWidget::centered_rect(dialog_width, dialog_height, area);
+
+    Clear.render(rect, buf);
+
+    let block = Block::default()
+        .title(format!(" {} ", title))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.info_fg))
+        .padding(Padding::horizontal(1));
+
+    let inner = block.inner(rect);
+    block.render(rect, buf);
+
+    if inner.height < 3 || inner.width == 0 {
+        return;
+    }
+
+    let available = inner.height.saturating_sub(2) as usize;
+    render_selectable_rows(&rows, selected, 0, available, theme, inner, buf);
+
+    let hint_style = Style::default()
+        .fg(theme.dim_fg)
+        .add_modifier(Modifier::DIM);
+    let hint_line = Line::from(Span::styled(hint, hint_style));
+    buf.set_line(inner.x, inner.y + inner.height - 1, &hint_line, inner.width);
+}
+
+/// Existing targets listed under the scaffold form before the rest are
+/// counted.
+const SCAFFOLD_CONFLICT_ROWS: usize = 4;
+
+fn render_scaffold_picker_dialog(
+    title: &str,
+    scaffolds: &[Scaffold],
+    selected: usize,
+    dest: &Path,
+    theme: &ThemeColors,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let rows: Vec<String> = scaffolds
+        .iter()
+        .map(
+            |scaffold| match (&scaffold.manifest, scaffold.description()) {
+                (Err(msg), _) => format!("{}  ⚠ {}: {}", scaffold.name(), MANIFEST, msg),
+                (Ok(_), Some(description)) => format!("{} — {}", scaffold.name(), description),
+                (Ok(_), None) => scaffold.name(),
+            },
+        )
+        .collect();
+    let into = format!("Into: {}", dest.display());
+    let hint = "[j/k] Move  [Enter] Choose  [Esc] Cancel";
+    let longest = rows
+        .iter()
+        .map(|r| text::width(r))
+        .chain([text::width(&into), hint.len()])
+        .max()
+        .unwrap_or(0);
+    let dialog_width = (longest as u16 + 4)
+        .max(50)
+        .min(area.width.saturating_sub(4));
+    // into + blank + rows + blank + hint + borders
+    let dialog_height = (rows.len() as u16 + 6).min(area.height.saturating_sub(2));
+    let rect = DialogWidget::centered_rect(dialog_width, dialog_height, area);
+
+    Clear.render(rect, buf);
+
+    let block = Block::default()
+        .title(format!(" {} ", title))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.dialog_border_fg))
+        .padding(Padding::horizontal(1));
+
+    let inner = block.inner(rect);
+    block.render(rect, buf);
+
+    if inner.height < 5 || inner.width == 0 {
+        return;
+    }
+
+    let into = Line::from(Span::styled(
+        text::truncate(&into, inner.width as usize),
+        Style::default().fg(theme.dim_fg),
+    ));
+    buf.set_line(inner.x, inner.y, &into, inner.width);
+
+    let available = inner.height.saturating_sub(4) as usize;
+    render_selectable_rows(&rows, selected, 2, available, theme, inner, buf);
+
+    let hint_style = Style::default()
+        .fg(theme.dim_fg)
+        .add_modifier(Modifier::DIM);
+    let hint_line = Line::from(Span::styled(hint, hint_style));
+    buf.set_line(inner.x, inner.y + inner.height - 1, &hint_line, inner.width);
+}
+
+fn render_scaffold_form_dialog(
+    title: &str,
+    form: &ScaffoldForm,
+    theme: &ThemeColors,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let shown_conflicts = form.conflicts.len().min(SCAFFOLD_CONFLICT_ROWS);
+    let more_conflicts = form.conflicts.len() > SCAFFOLD_CONFLICT_ROWS;
+    let fields = form.variables.len().max(1);
+    let notes = if form.error.is_some() {
+        1
+    } else if !form.conflicts.is_empty() {
+        1 + shown_conflicts + usize::from(more_conflicts)
+    } else {
+        0
+    };
+    let dialog_width = 64.min(area.width.saturating_sub(4));
+    // into + blank + fields + blank + notes + blank + hint + borders
+    let dialog_height =
+        ((fields + notes + usize::from(notes > 0)) as u16 + 6).min(area.height.saturating_sub(2));
+    let rect = DialogWidget::centered_rect(dialog_width, dialog_height, area);
+
+    Clear.render(rect, buf);
+
+    let block = Block::default()
+        .title(format!(" {} ", title))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.dialog_border_fg))
+        .padding(Padding::horizontal(1));
+
+    let inner = block.inner(rect);
+    block.render(rect, buf);
+
+    if inner.height < 4 || inner.width == 0 {
+        return;
+    }
+    let width = inner.width as usize;
+    let mut row = inner.y;
+    let last_row = inner.y + inner.height - 1;
+    let mut put = |row: &mut u16, line: Line| {
+        if *row < last_row {
+            buf.set_line(inner.x, *row, &line, inner.width);
+            *row += 1;
+        }
+    };
+
+    let into = format!("Into: {}", form.dest.display());
+    put(
+        &mut row,
+        Line::from(Span::styled(
+            text::truncate(&into, width),
+            Style::default().fg(theme.dim_fg),
+        )),
+    );
+    row += 1;
+
+    if form.variables.is_empty() {
+        put(
+            &mut row,
+            Line::from(Span::styled(
+                "No variables to fill in",
+                Style::default().fg(theme.dim_fg),
+            )),
+        );
+    }
+    let label_width = form
+        .variables
+        .iter()
+        .map(|v| text::width(&v.name))
+        .max()
+        .unwrap_or(0);
+    for (i, (variable, value)) in form.variables.iter().zip(&form.values).enumerate() {
+        let focused = i == form.field;
+        let value_style = if focused {
+            Style::default()
+                .fg(theme.status_fg)
+                .bg(theme.tree_selected_bg)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(theme.status_fg)
+        };
+        let mut spans = vec![
+            Span::styled(
+                format!("{:<width$}: ", variable.name, width = label_width),
+                Style::default().fg(theme.dim_fg),
+            ),
+            Span::styled(
+                format!("{}{}", value, if focused { "_" } else { "" }),
+                value_style,
+            ),
+        ];
+        if let Some(description) = &variable.description {
+            spans.push(Span::styled(
+                format!("  {}", description),
+                Style::default()
+                    .fg(theme.dim_fg)
+                    .add_modifier(Modifier::DIM),
+            ));
+        }
+        put(&mut row, Line::from(spans));
+    }
+
+    let warning = Style::default().fg(theme.warning_fg);
+    if let Some(error) = &form.error {
+        row += 1;
+        put(
+            &mut row,
+            Line::from(Span::styled(format!("⚠ {}", error), warning)),
+        );
+    } else if !form.conflicts.is_empty() {
+        row += 1;
+        let count = form.conflicts.len();
+        put(
+            &mut row,
+            Line::from(Span::styled(
+                format!(
+                    "⚠ {} target{} already exist{}:",
+                    count,
+                    if count == 1 { "" } else { "s" },
+                    if count == 1 { "s" } else { "" }
+                ),
+                warning,
+            )),
+        );
+        for path in form.conflicts.iter().take(SCAFFOLD_CONFLICT_ROWS) {
+            let shown = path.strip_prefix(&form.dest).unwrap_or(path);
+            put(
+                &mut row,
+                Line::from(Span::styled(
+                    text::truncate(&format!("  {}", shown.display()), width),
+                    warning,
+                )),
+            );
+        }
+        if more_conflicts {
+            put(
+                &mut row,
+                Line::from(Span::styled(
+                    format!("  … and {} more", count - SCAFFOLD_CONFLICT_ROWS),
+                    warning,
+                )),
+            );
+        }
+    }
+
+    let hint = if form.conflicts.is_empty() {
+        "[Tab] Next field  [Enter] Create  [Esc] Cancel"
+    } else {
+        "[Ctrl+F] Overwrite  [Esc] Cancel  (edit a value to recheck)"
+    };
+    let hint_style = Style::default()
+        .fg(theme.dim_fg)
+        .add_modifier(Modifier::DIM);
+    let hint_line = Line::from(Span::styled(hint, hint_style));
+    buf.set_line(inner.x, last_row, &hint_line, inner.width);
+}
+
+fn render_undo_confirm_dialog(
+    title: &str,
+    conflicts: &[UndoConflict],
+    selected: usize,
+    from_previous_session: bool,
+    theme: &ThemeColors,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let max_msg_len = conflicts
+        .iter()
+        .map(|c| c.message.chars().count())
+        .max()
+        .unwrap_or(10);
+
+    let dialog_width = (max_msg_len as u16 + 12)
+        .max(50)
+        .min(area.width.saturating_sub(4));
+    let dialog_height = (conflicts.len() as u16 + 6).min(area.height.saturating_sub(2));
+    let rect = DialogWidget::centered_rect(dialog_width, dialog_height, area);
+
+    Clear.render(rect, buf);
+
+    let block = Block::default()
+        .title(format!(" {} ", title))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.warning_fg))
+        .padding(Padding::horizontal(1));
+
+    let inner = block.inner(rect);
+    block.render(rect, buf);
+
+    if inner.height < 3 || inner.width == 0 {
+        return;
+    }
+
+    let header = Line::from(Span::styled(
+        if from_previous_session {
+            "From the previous session; changed since:"
+        } else {
+            "Changed since the operation:"
+        },
+        Style::default()
+            .fg(theme.warning_fg)
+            .add_modifier(Modifier::BOLD),
+    ));
+    buf.set_line(inner.x, inner.y, &header, inner.width);
+
+    // Keep the selected row visible when the list is taller than the box
+    let max_items = inner.height.saturating_sub(3) as usize;
+    let start = selected.saturating_sub(max_items.saturating_sub(1));
+    for (i, conflict) in conflicts.iter().enumerate().skip(start).take(max_items) {
+        let marker = if !conflict.can_apply() {
+            " - "
+        } else if conflict.apply {
+            "[x]"
+        } else {
+            "[ ]"
+        };
+        let mut style = if conflict.can_apply() {
+            Style::default().fg(theme.status_fg)
+        } else {
+            Style::default()
+                .fg(theme.dim_fg)
+                .add_modifier(Modifier::DIM)
+        };
+        if i == selected {
+            style = style
+                .bg(theme.tree_selected_bg)
+                .add_modifier(Modifier::BOLD);
+        }
+        let line = Line::from(Span::styled(
+            format!("{} {}", marker, conflict.message),
+            style,
+        ));
+        buf.set_line(
+            inner.x,
+            inner.y + 2 + (i - start) as u16,
+            &line,
+            inner.width,
+        );
+    }
+
+    let hint = "[Space] Toggle  [a] All  [s] None  [Enter] Undo  [Esc] Cancel";
+    let hint_style = Style::default()
+        .fg(theme.dim_fg)
+        .add_modifier(Modifier::DIM);
+    let hint_line = Line::from(Span::styled(hint, hint_style));
+    buf.set_line(inner.x, inner.y + inner.height - 1, &hint_line, inner.width);
+}
+
+fn render_sequence_rename_dialog(
+    title: &str,
+    form: &SequenceForm,
+    theme: &ThemeColors,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let preview_rows = match &form.preview {
+        Ok(lines) => lines.len().max(1),
+        Err(_) => 1,
+    };
+    let dialog_width = 60.min(area.width.saturating_sub(4));
+    // 4 fields + blank + preview + blank + hint + borders
+    let dialog_height = (preview_rows as u16 + 9).min(area.height.saturating_sub(2));
+    let rect = DialogWidget::centered_rect(dialog_width, dialog_height, area);
+
+    Clear.render(rect, buf);
+
+    let block = Block::default()
+        .title(format!(" {} ", title))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.dialog_border_fg))
+        .padding(Padding::horizontal(1));
+
+    let inner = block.inner(rect);
+    block.render(rect, buf);
+
+    if inner.height < 6 || inner.width == 0 {
+        return;
+    }
+
+    let sort_label = form.sort.label();
+    let fields = [
+        (SequenceField::Base, "Base name", form.base.as_str()),
+        (SequenceField::Start, "Start at ", form.start.as_str()),
+        (SequenceField::Width, "Padding  ", form.width.as_str()),
+        (SequenceField::Sort, "Sort by  ", sort_label),
+    ];
+    for (i, (field, label, value)) in fields.iter().enumerate() {
+        let focused = *field == form.field;
+        let value_style = if focused {
+            Style::default()
+                .fg(theme.status_fg)
+                .bg(theme.tree_selected_bg)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(theme.status_fg)
+        };
+        let cursor = if focused && *field != SequenceField::Sort {
+            "_"
+        } else {
+            ""
+        };
+        let line = Line::from(vec![
+            Span::styled(format!("{}: ", label), Style::default().fg(theme.dim_fg)),
+            Span::styled(format!("{}{}", value, cursor), value_style),
+        ]);
+        buf.set_line(inner.x, inner.y + i as u16, &line, inner.width);
+    }
+
+    let preview_top = inner.y + 5;
+    let max_rows = inner.height.saturating_sub(7) as usize;
+    match &form.preview {
+        Ok(lines) if lines.is_empty() => {}
+        Ok(lines) => {
+            for (i, text) in lines.iter().take(max_rows.max(1)).enumerate() {
+                let line = Line::from(Span::styled(
+                    text.as_str(),
+                    Style::default().fg(theme.info_fg),
+                ));
+                buf.set_line(inner.x, preview_top + i as u16, &line, inner.width);
+            }
+        }
+        Err(msg) => {
+            let line = Line::from(Span::styled(
+                format!("⚠ {}", msg),
+                Style::default().fg(theme.warning_fg),
+            ));
+            buf.set_line(inner.x, preview_top, &line, inner.width);
+        }
+    }
+
+    // Space only toggles the sort field; elsewhere it is typed
+    let hint = if form.field == SequenceField::Sort {
+        "[Tab] Next field  [Space] Sort  [Enter] Rename  [Esc] Cancel"
+    } else {
+        "[Tab] Next field  [Enter] Rename  [Esc] Cancel"
+    };
+    let hint_style = Style::default()
+        .fg(theme.dim_fg)
+        .add_modifier(Modifier::DIM);
+    let hint_line = Line::from(Span::styled(hint, hint_style));
+    buf.set_line(inner.x, inner.y + inner.height - 1, &hint_line, inner.width);
+}
+
+fn render_bulk_rename_dialog(
+    title: &str,
+    form: &BulkRenameForm,
+    theme: &ThemeColors,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let preview_rows = match &form.preview {
+        Ok(lines) => lines.len().max(1),
+        Err(_) => 1,
+    };
+    let dialog_width = 60.min(area.width.saturating_sub(4));
+    // Pattern + placeholders + blank + preview + blank + hint + borders
+    let dialog_height = (preview_rows as u16 + 7).min(area.height.saturating_sub(2));
+    let rect = DialogWidget::centered_rect(dialog_width, dialog_height, area);
+
+    Clear.render(rect, buf);
+
+    let block = Block::default()
+        .title(format!(" {} ", title))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.dialog_border_fg))
+        .padding(Padding::horizontal(1));
+
+    let inner = block.inner(rect);
+    block.render(rect, buf);
+
+    if inner.height < 5 || inner.width == 0 {
+        return;
+    }
+
+    let dim = Style::default().fg(theme.dim_fg);
+    let pattern = Line::from(vec![
+        Span::styled("Pattern: ", dim),
+        Span::styled(
+            format!("{}_", form.pattern),
+            Style::default()
+                .fg(theme.status_fg)
+                .bg(theme.tree_selected_bg)
+                .add_modifier(Modifier::BOLD),
+        ),
+    ]);
+    buf.set_line(inner.x, inner.y, &pattern, inner.width);
+    let placeholders = match &form.suggestion {
+        Some(pattern) => Line::from(Span::styled(
+            format!(
+                "drops extensions — did you mean '{}'? [Tab] accept",
+                pattern
+            ),
+            Style::default().fg(theme.warning_fg),
+        )),
+        None => Line::from(Span::styled("{name} {ext} {n}  or  s/old/new/[g]", dim)),
+    };
+    buf.set_line(inner.x, inner.y + 1, &placeholders, inner.width);
+
+    let preview_top = inner.y + 3;
+    let max_rows = inner.height.saturating_sub(5) as usize;
+    match &form.preview {
+        Ok(lines) => {
+            for (i, text) in lines.iter().take(max_rows.max(1)).enumerate() {
+                let line = Line::from(Span::styled(
+                    text.as_str(),
+                    Style::default().fg(theme.info_fg),
+                ));
+                buf.set_line(inner.x, preview_top + i as u16, &line, inner.width);
+            }
+        }
+        Err(msg) => {
+            let line = Line::from(Span::styled(
+                format!("⚠ {}", msg),
+                Style::default().fg(theme.warning_fg),
+            ));
+            buf.set_line(inner.x, preview_top, &line, inner.width);
+        }
+    }
+
+    let hint = "[Enter] Rename all  [Esc] Cancel";
+    let hint_style = Style::default()
+        .fg(theme.dim_fg)
+        .add_modifier(Modifier::DIM);
+    let hint_line = Line::from(Span::styled(hint, hint_style));
+    buf.set_line(inner.x, inner.y + inner.height - 1, &hint_line, inner.width);
+}
+
+fn render_export_dialog(
+    title: &str,
+    form: &ExportForm,
+    theme: &ThemeColors,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let dialog_width = 64.min(area.width.saturating_sub(4));
+    // 6 fields + blank + hint + borders
+    let dialog_height = 10.min(area.height.saturating_sub(2));
+    let rect = DialogWidget::centered_rect(dialog_width, dialog_height, area);
+
+    Clear.render(rect, buf);
+
+    let block = Block::default()
+        .title(format!(" {} ", title))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.dialog_border_fg))
+        .padding(Padding::horizontal(1));
+
+    let inner = block.inner(rect);
+    block.render(rect, buf);
+
+    if inner.height < 7 || inner.width == 0 {
+        return;
+    }
+
+    let on_off = |on: bool| if on { "on" } else { "off" };
+    let options = &form.options;
+    // Keep the end of a long path in view
+    let path_room = (inner.width as usize).saturating_sub(12);
+    let path = if form.path.is_empty() {
+        "(clipboard)".to_string()
+    } else if form.path.chars().count() > path_room {
+        let tail: String = form
+            .path
+            .chars()
+            .rev()
+            .take(path_room.saturating_sub(1))
+            .collect::<Vec<_>>()
+            .into_iter()
+            .rev()
+            .collect();
+        format!("…{}", tail)
+    } else {
+        form.path.clone()
+    };
+    let fields = [
+        (ExportField::Path, "Save to  ", path),
+        (
+            ExportField::Format,
+            "Format   ",
+            options.format.label().to_string(),
+        ),
+        (
+            ExportField::Scope,
+            "Rows     ",
+            options.scope.label().to_string(),
+        ),
+        (
+            ExportField::Sizes,
+            "Sizes    ",
+            on_off(options.sizes).to_string(),
+        ),
+        (
+            ExportField::Hidden,
+            "Hidden   ",
+            on_off(options.hidden_markers).to_string(),
+        ),
+        (
+            ExportField::LoadMore,
+            "Load more",
+            on_off(options.load_more).to_string(),
+        ),
+    ];
+    for (i, (field, label, value)) in fields.iter().enumerate() {
+        let focused = *field == form.field;
+        let value_style = if focused {
+            Style::default()
+                .fg(theme.status_fg)
+                .bg(theme.tree_selected_bg)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(theme.status_fg)
+        };
+        let cursor = if focused && *field == ExportField::Path {
+            "_"
+        } else {
+            ""
+        };
+        let line = Line::from(vec![
+            Span::styled(format!("{}: ", label), Style::default().fg(theme.dim_fg)),
+            Span::styled(format!("{}{}", value, cursor), value_style),
+        ]);
+        buf.set_line(inner.x, inner.y + i as u16, &line, inner.width);
+    }
+
+    let hint = "[Tab] Next field  [Space] Toggle  [Enter] Export  [Esc] Cancel";
+    let hint_style = Style::default()
+        .fg(theme.dim_fg)
+        .add_modifier(Modifier::DIM);
+    let hint_line = Line::from(Span::styled(hint, hint_style));
+    buf.set_line(inner.x, inner.y + inner.height - 1, &hint_line, inner.width);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tasks::{Priority, TaskKind};
+    use crate::theme;
+    use std::path::{Path, PathBuf};
+
+    fn test_theme() -> ThemeColors {
+        theme::dark_theme()
+    }
+
+    #[test]
+    fn test_input_dialog_renders() {
+        let mode = AppMode::Dialog(DialogKind::CreateFile);
+        let state = DialogState {
+            input: "test.txt".to_string(),
+            cursor_position: 8,
+            warning: None,
+            ..Default::default()
+        };
+        let tc = test_theme();
+        let widget = DialogWidget::new(&mode, &state, &tc);
+        let area = Rect::new(0, 0, 80, 24);
+        let mut buf = Buffer::empty(area);
+        widget.render(area, &mut buf);
+
+        // Check that the dialog title appears
+        let content = buffer_to_string(&buf, area);
+        assert!(content.contains("Create New File — name 1/1"));
+        assert!(content.contains("test.txt"));
+    }
+
+    #[test]
+    fn test_input_dialog_shows_paste_warning_and_name_limit() {
+        let mode = AppMode::Dialog(DialogKind::CreateFile);
+        let name = "n".repeat(paste_guard::NAME_MAX - 3);
+        let state = DialogState {
+            cursor_position: name.len(),
+            input: name,
+            warning: Some("pasted text contained 1 line break — removed".to_string()),
+            ..Default::default()
+        };
+        let tc = test_theme();
+        let area = Rect::new(0, 0, 120, 24);
+        let mut buf = Buffer::empty(area);
+        DialogWidget::new(&mode, &state, &tc).render(area, &mut buf);
+
+        let content = buffer_to_string(&buf, area);
+        assert!(content.contains("pasted text contained 1 line break — removed"));
+        assert!(content.contains("3 of 255 bytes left"));
+
+        // Paths aren't names: no countdown
+        let mode = AppMode::Dialog(DialogKind::GotoPath);
+        let state = DialogState {
+            input: "x".repeat(paste_guard::NAME_MAX),
+            cursor_position: 0,
+            warning: None,
+            ..Default::default()
+        };
+        let mut buf = Buffer::empty(area);
+        DialogWidget::new(&mode, &state, &tc).render(area, &mut buf);
+        assert!(!buffer_to_string(&buf, area).contains("bytes left"));
+    }
+
+    #[test]
+    fn test_input_dialog_cursor_on_wide_char() {
+        let mode = AppMode::Dialog(DialogKind::CreateFile);
+        let state = DialogState {
+            input: "日本語.txt".to_string(),
+            cursor_position: "日".len(),
+            warning: None,
+            ..Default::default()
+        };
+        let tc = test_theme();
+        let widget = DialogWidget::new(&mode, &state, &tc);
+        let area = Rect::new(0, 0, 80, 24);
+        let mut buf = Buffer::empty(area);
+        widget.render(area, &mut buf);
+
+        let cursor_cell = buf
+            .content()
+            .iter()
+            .find(|cell| cell.symbol() == "本")
+            .expect("cursor glyph rendered whole");
+        assert_eq!(cursor_cell.bg, tc.status_fg);
+        assert!(buf.content().iter().any(|cell| cell.symbol() == "語"));
+    }
+
+    #[test]
+    fn test_rename_dialog_renders() {
+        let mode = AppMode::Dialog(DialogKind::Rename {
+            original: PathBuf::from("/tmp/old_name.txt"),
+        });
+        let state = DialogState {
+            input: "old_name.txt".to_string(),
+            cursor_position: 12,
+            warning: None,
+            ..Default::default()
+        };
+        let tc = test_theme();
+        let widget = DialogWidget::new(&mode, &state, &tc);
+        let area = Rect::new(0, 0, 80, 24);
+        let mut buf = Buffer::empty(area);
+        widget.render(area, &mut buf);
+
+        let content = buffer_to_string(&buf, area);
+        assert!(content.contains("Rename"));
+        assert!(content.contains("old_name.txt"));
+    }
+
+    #[test]
+    fn test_confirm_dialog_renders() {
+        let targets = vec![
+            PathBuf::from("/tmp/file1.txt"),
+            PathBuf::from("/tmp/file2.txt"),
+        ];
+        let mode = AppMode::Dialog(DialogKind::DeleteConfirm {
+            targets: targets.clone(),
+            sizing: Sizing::Counted {
+                usage: DiskUsage {
+                    bytes: 2048,
+                    entries: 2,
+                    files: 2,
+                    complete: true,
+                },
+                large: false,
+                armed: false,
+            },
+            scroll: 0,
+            to_trash: false,
+        });
+        let state = DialogState::default();
+        let tc = test_theme();
+        let widget = DialogWidget::new(&mode, &state, &tc);
+        let area = Rect::new(0, 0, 80, 24);
+        let mut buf = Buffer::empty(area);
+        widget.render(area, &mut buf);
+
+        let content = buffer_to_string(&buf, area);
+        assert!(content.contains(" Delete 2 items permanently — confirm 1/1 "));
+        assert!(content.contains("Delete 2 items (2.00 KB)?"));
+        assert!(content.contains("file1.txt"));
+        assert!(content.contains("file2.txt"));
+        assert!(!content.contains("Scroll"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_confirm_dialog_says_links_keep_their_target() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let file = dir.path().join("plain.txt");
+        std::fs::write(&file, "x").unwrap();
+        let link = dir.path().join("shortcut");
+        std::os::unix::fs::symlink("plain.txt", &link).unwrap();
+        let mode = AppMode::Dialog(DialogKind::DeleteConfirm {
+            targets: vec![file, link],
+            sizing: Sizing::Counted {
+                usage: DiskUsage {
+                    bytes: 1,
+                    entries: 2,
+                    files: 2,
+                    complete: true,
+                },
+                large: false,
+                armed: false,
+            },
+            scroll: 0,
+            to_trash: false,
+        });
+        let state = DialogState::default();
+        let tc = test_theme();
+        let area = Rect::new(0, 0, 80, 24);
+        let mut buf = Buffer::empty(area);
+        DialogWidget::new(&mode, &state, &tc).render(area, &mut buf);
+
+        let content = buffer_to_string(&buf, area);
+        assert!(content.contains("• shortcut → plain.txt (link only, target kept)"));
+        assert!(!content.contains("• plain.txt →"));
+    }
+
+    #[test]
+    fn test_confirm_dialog_pages_long_lists() {
+        let targets: Vec<PathBuf> = (0..25)
+            .map(|i| PathBuf::from(format!("/tmp/file{:02}.txt", i)))
+            .collect();
+        let mode = AppMode::Dialog(DialogKind::DeleteConfirm {
+            targets,
+            sizing: Sizing::Counted {
+                usage: DiskUsage {
+                    bytes: 512,
+                    complete: false,
+                    ..Default::default()
+                },
+                large: false,
+                armed: false,
+            },
+            scroll: 12,
+            to_trash: false,
+        });
+        let state = DialogState::default();
+        let tc = test_theme();
+        let area = Rect::new(0, 0, 80, 30);
+        let mut buf = Buffer::empty(area);
+        DialogWidget::new(&mode, &state, &tc).render(area, &mut buf);
+
+        let content = buffer_to_string(&buf, area);
+        assert!(content.contains("Delete 25 items (at least 512 B)?"));
+        assert!(!content.contains("file11.txt"));
+        assert!(content.contains("file12.txt"));
+        assert!(content.contains("file21.txt"));
+        assert!(!content.contains("file22.txt"));
+        assert!(content.contains("13–22 of 25"));
+        assert!(content.contains("[↑↓] Scroll"));
+    }
+
+    #[test]
+    fn test_large_operation_states_its_scale() {
+        let usage = DiskUsage {
+            bytes: 21 * 1_073_741_824,
+            entries: 150_000,
+            files: 143_000,
+            complete: true,
+        };
+        let mode = AppMode::Dialog(DialogKind::TransferConfirm {
+            items: 1,
+            was_cut: false,
+            sizing: Sizing::Counted {
+                usage,
+                large: true,
+                armed: false,
+            },
+        });
+        let state = DialogState::default();
+        let tc = test_theme();
+        let area = Rect::new(0, 0, 100, 20);
+        let mut buf = Buffer::empty(area);
+        DialogWidget::new(&mode, &state, &tc).render(area, &mut buf);
+
+        let content = buffer_to_string(&buf, area);
+        assert!(content.contains("This will copy ~143,000 files (21.00 GB). Proceed?"));
+        assert!(content.contains("[y] then [Enter] Proceed"));
+    }
+
+    fn render_progress(total: Option<usize>, spin: usize) -> String {
+        let mode = AppMode::Dialog(DialogKind::Progress {
+            message: "data/part-0042.csv".to_string(),
+            current: 1234,
+            total,
+            bytes_done: 0,
+            bytes_total: 0,
+            started: std::time::Instant::now(),
+            rate: None,
+            spin,
+        });
+        let state = DialogState::default();
+        let tc = test_theme();
+        let area = Rect::new(0, 0, 80, 24);
+        let mut buf = Buffer::empty(area);
+        DialogWidget::new(&mode, &state, &tc).render(area, &mut buf);
+        buffer_to_string(&buf, area)
+    }
+
+    #[test]
+    fn test_progress_without_total_shows_spinner_then_bar() {
+        let content = render_progress(None, 3);
+        assert!(content.contains("Processing 1234"));
+        assert!(content.contains(&format!("{} 1,234 done · 0s", SPINNER[3])));
+        assert!(!content.contains('█') && !content.contains('░'));
+        assert!(content.contains("[Esc] Cancel"));
+
+        // Each tick shows the next frame, wrapping around
+        let next = render_progress(None, 4);
+        assert!(next.contains(SPINNER[4]) && !next.contains(SPINNER[3]));
+        assert!(render_progress(None, SPINNER.len() + 3).contains(SPINNER[3]));
+
+        // Once a total is known the bar takes over
+        let content = render_progress(Some(2468), 3);
+        assert!(content.contains("Processing 1234/2468"));
+        assert!(!content.contains(SPINNER[3]));
+        let bar = content.lines().find(|l| l.contains('█')).unwrap();
+        let filled = bar.matches('█').count();
+        let empty = bar.matches('░').count();
+        assert_eq!(filled, (filled + empty) / 2);
+    }
+
+    #[test]
+    fn test_elapsed_formats() {
+        assert_eq!(elapsed(Duration::from_secs(42)), "42s");
+        assert_eq!(elapsed(Duration::from_secs(187)), "3m 07s");
+        assert_eq!(elapsed(Duration::from_secs(3720)), "1h 02m");
+    }
+
+    #[test]
+    fn test_counting_shows_spinner() {
+        let mode = AppMode::Dialog(DialogKind::DeleteConfirm {
+            targets: vec![PathBuf::from("/tmp/big")],
+            sizing: Sizing::Counting {
+                started: Instant::now(),
+            },
+            scroll: 0,
+            to_trash: false,
+        });
+        let state = DialogState::default();
+        let tc = test_theme();
+        let area = Rect::new(0, 0, 80, 24);
+        let mut buf = Buffer::empty(area);
+        DialogWidget::new(&mode, &state, &tc).render(area, &mut buf);
+
+        let content = buffer_to_string(&buf, area);
+        assert!(content.contains("Delete 1 item — counting "));
+        assert!(SPINNER.iter().any(|frame| content.contains(frame)));
+        assert!(!content.contains("[y]"));
+    }
+
+    #[test]
+    fn test_trash_confirm_says_where_items_go() {
+        let mode = AppMode::Dialog(DialogKind::DeleteConfirm {
+            targets: vec![PathBuf::from("/tmp/a.txt")],
+            sizing: Sizing::Counted {
+                usage: DiskUsage {
+                    bytes: 10,
+                    entries: 1,
+                    files: 1,
+                    complete: true,
+                },
+                large: false,
+                armed: false,
+            },
+            scroll: 0,
+            to_trash: true,
+        });
+        let state = DialogState::default();
+        let tc = test_theme();
+        let area = Rect::new(0, 0, 80, 24);
+        let mut buf = Buffer::empty(area);
+        DialogWidget::new(&mode, &state, &tc).render(area, &mut buf);
+
+        let content = buffer_to_string(&buf, area);
+        assert!(content.contains(" Move 1 item to Trash — confirm 1/1 "));
+        assert!(content.contains("Trash 1 item (10 B)?"));
+    }
+
+    #[test]
+    fn test_paste_conflict_says_what_overwrite_does() {
+        use crate::fs::overwrite::{BackupTo, Collision};
+
+        let collision = |name: &str, dest_is_dir: bool| Collision {
+            source: PathBuf::from("/src").join(name),
+            dest: PathBuf::from("/dest").join(name),
+            dest_is_dir,
+        };
+        let render = |current: usize| {
+            let mode = AppMode::Dialog(DialogKind::PasteConflict {
+                collisions: vec![collision("a.txt", false), collision("docs", true)],
+                current,
+                backup: Some(BackupTo::Rename),
+            });
+            let state = DialogState::default();
+            let tc = test_theme();
+            let area = Rect::new(0, 0, 80, 24);
+            let mut buf = Buffer::empty(area);
+            DialogWidget::new(&mode, &state, &tc).render(area, &mut buf);
+            buffer_to_string(&buf, area)
+        };
+
+        let first = render(0);
+        assert!(first.contains(" Already Exists — choose 1/2 "));
+        assert!(first.contains("File 'a.txt' is already here."));
+        assert!(first.contains("Overwrite keeps it as a .fm-bak- copy first."));
+        assert!(first.contains("[a] All"));
+
+        let last = render(1);
+        assert!(last.contains("Folder 'docs' is already here."));
+        assert!(last.contains("files replaced inside aren't kept"));
+        assert!(!last.contains("[a] All"));
+    }
+
+    #[test]
+    fn test_group_thousands() {
+        assert_eq!(group_thousands(0), "0");
+        assert_eq!(group_thousands(999), "999");
+        assert_eq!(group_thousands(1000), "1,000");
+        assert_eq!(group_thousands(1_234_567), "1,234,567");
+    }
+
+    #[test]
+    fn test_error_dialog_renders() {
+        let mode = AppMode::Dialog(DialogKind::Error {
+            message: "Permission denied".to_string(),
+        });
+        let state = DialogState::default();
+        let tc = test_theme();
+        let widget = DialogWidget::new(&mode, &state, &tc);
+        let area = Rect::new(0, 0, 80, 24);
+        let mut buf = Buffer::empty(area);
+        widget.render(area, &mut buf);
+
+        let content = buffer_to_string(&buf, area);
+        assert!(content.contains("Error"));
+        assert!(content.contains("Permission denied"));
+    }
+
+    #[test]
+    fn test_undo_confirm_dialog_renders() {
+        use crate::fs::undo_check::Drift;
+
+        let mut modified = UndoConflict::new(
+            0,
+            Drift::Modified,
+            "beta/file_a.txt was modified after the copy — delete anyway?".to_string(),
+        );
+        modified.apply = true;
+        let missing = UndoConflict::new(
+            1,
+            Drift::Missing,
+            "beta/file_b.rs no longer exists — skipped".to_string(),
+        );
+        let mode = AppMode::Dialog(DialogKind::UndoConfirm {
+            conflicts: vec![modified, missing],
+            selected: 0,
+            from_previous_session: false,
+        });
+        let state = DialogState::default();
+        let tc = test_theme();
+        let widget = DialogWidget::new(&mode, &state, &tc);
+        let area = Rect::new(0, 0, 100, 24);
+        let mut buf = Buffer::empty(area);
+        widget.render(area, &mut buf);
+
+        let content = buffer_to_string(&buf, area);
+        assert!(content.contains("Undo: Changes Detected — review 1/2"));
+        assert!(content.contains("[x] beta/file_a.txt was modified"));
+        assert!(content.contains(" -  beta/file_b.rs no longer exists"));
+    }
+
+    #[test]
+    fn test_sequence_rename_dialog_renders() {
+        let form = SequenceForm {
+            sources: vec![
+                PathBuf::from("/d/IMG_9.jpg"),
+                PathBuf::from("/d/IMG_10.jpg"),
+            ],
+            base: "IMG_".to_string(),
+            start: "1".to_string(),
+            width: "3".to_string(),
+            sort: crate::fs::sequence::SortKey::Name,
+            field: SequenceField::Start,
+            preview: Ok(vec![
+                "IMG_10.jpg → IMG_001.jpg".to_string(),
+                "IMG_9.jpg → IMG_002.jpg".to_string(),
+            ]),
+        };
+        let mode = AppMode::Dialog(DialogKind::SequenceRename { form: form.clone() });
+        let state = DialogState::default();
+        let theme = test_theme();
+        let area = Rect::new(0, 0, 80, 24);
+        let mut buf = Buffer::empty(area);
+        DialogWidget::new(&mode, &state, &theme).render(area, &mut buf);
+        let content = buffer_to_string(&buf, area);
+        assert!(content.contains("Sequence Rename (2 items) — start 2/4"));
+        assert!(content.contains("Base name: IMG_"));
+        assert!(content.contains("Start at : 1_"));
+        assert!(content.contains("IMG_9.jpg → IMG_002.jpg"));
+        // Space only sorts once the sort field has focus
+        assert!(!content.contains("[Space] Sort"));
+        let form = SequenceForm {
+            field: SequenceField::Sort,
+            ..form
+        };
+        let mode = AppMode::Dialog(DialogKind::SequenceRename { form });
+        let mut buf = Buffer::empty(area);
+        DialogWidget::new(&mode, &state, &theme).render(area, &mut buf);
+        assert!(buffer_to_string(&buf, area).contains("[Space] Sort"));
+    }
+
+    #[test]
+    fn test_export_dialog_renders() {
+        let mut form = ExportForm::new(Path::new("/proj"));
+        form.field = ExportField::Format;
+        form.input_char(' ');
+        let mode = AppMode::Dialog(DialogKind::ExportTree { form });
+        let state = DialogState::default();
+        let theme = test_theme();
+        let area = Rect::new(0, 0, 80, 24);
+        let mut buf = Buffer::empty(area);
+        DialogWidget::new(&mode, &state, &theme).render(area, &mut buf);
+        let content = buffer_to_string(&buf, area);
+        assert!(content.contains("Export Tree — format 2/6"));
+        assert!(content.contains("Save to  : /proj/tree.json"));
+        assert!(content.contains("Format   : JSON"));
+        assert!(content.contains("Rows     : visible view"));
+        assert!(content.contains("Load more: off"));
+    }
+
+    #[test]
+    fn test_scaffold_form_renders_conflicts() {
+        use crate::fs::scaffold::{Scaffold, Variable};
+
+        let scaffold = Scaffold {
+            root: PathBuf::from("/cfg/scaffolds/cli"),
+            manifest: Ok(None),
+        };
+        let variables = vec![
+            Variable {
+                name: "project_name".to_string(),
+                description: Some("Crate name".to_string()),
+                default: "tool".to_string(),
+            },
+            Variable {
+                name: "author".to_string(),
+                description: None,
+                default: String::new(),
+            },
+        ];
+        let mut form = ScaffoldForm::new(scaffold, PathBuf::from("/proj"), variables);
+        form.conflicts = vec![
+            PathBuf::from("/proj/tool"),
+            PathBuf::from("/proj/tool/README.md"),
+        ];
+        let mode = AppMode::Dialog(DialogKind::ScaffoldForm {
+            form: Box::new(form),
+        });
+        let state = DialogState::default();
+        let theme = test_theme();
+        let area = Rect::new(0, 0, 80, 24);
+        let mut buf = Buffer::empty(area);
+        DialogWidget::new(&mode, &state, &theme).render(area, &mut buf);
+        let content = buffer_to_string(&buf, area);
+        assert!(content.contains("Scaffold: cli — project_name 1/2"));
+        assert!(content.contains("Into: /proj"));
+        assert!(content.contains("project_name: tool_  Crate name"));
+        assert!(content.contains("author      : "));
+        assert!(content.contains("⚠ 2 targets already exist:"));
+        assert!(content.contains("tool/README.md"));
+        assert!(content.contains("[Ctrl+F] Overwrite"));
+    }
+
+    #[test]
+    fn test_portability_dialog_renders() {
+        use crate::fs::portability::{Finding, Issue};
+
+        let report = PortabilityReport {
+            root: PathBuf::from("/proj/pkg"),
+            findings: vec![
+                Finding {
+                    path: PathBuf::from("/proj/pkg/aux.txt"),
+                    issues: vec![Issue::Reserved],
+                    suggestion: Some("aux_.txt".to_string()),
+                },
+                Finding {
+                    path: PathBuf::from("/proj/pkg/docs/notes:final.txt"),
+                    issues: vec![Issue::ForbiddenChars(vec![':'])],
+                    suggestion: Some("notes_final.txt".to_string()),
+                },
+            ],
+            entries: 12,
+            complete: true,
+        };
+        let mode = AppMode::Dialog(DialogKind::Portability {
+            report,
+            selected: 1,
+        });
+        let state = DialogState::default();
+        let theme = test_theme();
+        let area = Rect::new(0, 0, 100, 24);
+        let mut buf = Buffer::empty(area);
+        DialogWidget::new(&mode, &state, &theme).render(area, &mut buf);
+        let content = buffer_to_string(&buf, area);
+        assert!(content.contains("Portability: pkg (2 problems) — review 2/2"));
+        assert!(content.contains("12 entries checked"));
+        assert!(content.contains("aux.txt → aux_.txt"));
+        assert!(content.contains("docs/notes:final.txt → notes_final.txt"));
+        // Issues of the selected entry
+        assert!(content.contains("not allowed on Windows: :"));
+    }
+
+    #[test]
+    fn test_recent_operations_dialog_renders() {
+        use crate::error::OperationError;
+        use crate::event::OperationResult;
+        use std::time::Duration;
+
+        let pasted = OperationResult {
+            success_count: 1,
+            errors: vec![OperationError::DestinationExists {
+                path: PathBuf::from("/proj/out/b.txt"),
+            }],
+            created_paths: vec![PathBuf::from("/proj/out/a.txt")],
+            replaced: Vec::new(),
+            backups: Vec::new(),
+            failed_sources: vec![PathBuf::from("/proj/b.txt")],
+            source_paths: vec![PathBuf::from("/proj/a.txt"), PathBuf::from("/proj/b.txt")],
+            dest_dir: PathBuf::from("/proj/out"),
+            was_cut: false,
+            send_to: None,
+            elapsed: Duration::from_millis(250),
+        };
+        let moved = OperationResult {
+            success_count: 1,
+            errors: vec![],
+            created_paths: vec![PathBuf::from("/proj/old/c.txt")],
+            replaced: Vec::new(),
+            backups: Vec::new(),
+            failed_sources: vec![],
+            source_paths: vec![PathBuf::from("/proj/c.txt")],
+            dest_dir: PathBuf::from("/proj/old"),
+            was_cut: true,
+            send_to: None,
+            elapsed: Duration::from_millis(2400),
+        };
+        let ops = vec![
+            CompletedOp::from_result(2, &moved),
+            CompletedOp::from_result(1, &pasted),
+        ];
+        let mode = AppMode::Dialog(DialogKind::RecentOperations {
+            ops,
+            undoable: Some(2),
+            selected: 1,
+            expanded: true,
+        });
+        let state = DialogState::default();
+        let theme = test_theme();
+        let area = Rect::new(0, 0, 100, 30);
+        let mut buf = Buffer::empty(area);
+        DialogWidget::new(&mode, &state, &theme).render(area, &mut buf);
+        let content = buffer_to_string(&buf, area);
+        assert!(content.contains("Recent Operations — review 2/2"));
+        assert!(content.contains("↶ Moved 1 item to /proj/old · 2.4s"));
+        assert!(content.contains("Pasted 1 of 2 items to /proj/out · 1 failed · 250ms"));
+        // Details of the selected (older) operation
+        assert!(content.contains("Destination: /proj/out"));
+        assert!(content.contains("/proj/out/a.txt"));
+        assert!(content.contains("Skipped (already there) (1):"));
+        assert!(content.contains("/proj/out/b.txt already exists"));
+        assert!(content.contains("Undo: no longer available"));
+        assert!(content.contains("Retry: 1 item(s)"));
+    }
+
+    #[test]
+    fn test_tasks_dialog_renders() {
+        let started = Instant::now();
+        let tasks = vec![
+            TaskInfo {
+                id: 1,
+                kind: TaskKind::Transfer,
+                description: "copy 3 items to /proj/out".to_string(),
+                priority: Priority::User,
+                started: Some(started),
+                progress: Some((12, 40)),
+                cancelled: false,
+            },
+            TaskInfo {
+                id: 2,
+                kind: TaskKind::Blame,
+                description: "blame main.rs".to_string(),
+                priority: Priority::Normal,
+                started: None,
+                progress: None,
+                cancelled: true,
+            },
+        ];
+        let mode = AppMode::Dialog(DialogKind::Tasks { tasks, selected: 1 });
+        let state = DialogState::default();
+        let theme = test_theme();
+        let area = Rect::new(0, 0, 100, 20);
+        let mut buf = Buffer::empty(area);
+        DialogWidget::new(&mode, &state, &theme).render(area, &mut buf);
+        let content = buffer_to_string(&buf, area);
+        assert!(content.contains("Tasks (2) — review 2/2"));
+        assert!(content.contains("transfer    copy 3 items to /proj/out · 12/40 · 0s"));
+        assert!(content.contains("blame       blame main.rs · waiting · cancelling"));
+        assert!(content.contains("[X] Cancel all"));
+    }
+
+    #[test]
+    fn test_title_tracks_the_step() {
+        let mut form = ExportForm::new(Path::new("/proj"));
+        assert_eq!(
+            DialogKind::ExportTree { form: form.clone() }.title(),
+            "Export Tree — path 1/6"
+        );
+        form.next_field();
+        form.next_field();
+        assert_eq!(
+            DialogKind::ExportTree { form }.title(),
+            "Export Tree — rows 3/6"
+        );
+        let single = |to_trash| DialogKind::DeleteConfirm {
+            targets: vec![PathBuf::from("/tmp/a")],
+            sizing: Sizing::Counted {
+                usage: DiskUsage::default(),
+                large: false,
+                armed: false,
+            },
+            scroll: 0,
+            to_trash,
+        };
+        assert_eq!(
+            single(false).title(),
+            "Delete 1 item permanently — confirm 1/1"
+        );
+        assert_eq!(single(true).title(), "Move 1 item to Trash — confirm 1/1");
+        let large = |armed| DialogKind::TransferConfirm {
+            items: 2,
+            was_cut: true,
+            sizing: Sizing::Counted {
+                usage: DiskUsage::default(),
+                large: true,
+                armed,
+            },
+        };
+        assert_eq!(large(false).title(), "Move 2 items — confirm 1/2");
+        assert_eq!(large(true).title(), "Move 2 items — confirm 2/2");
+        let error = DialogKind::Error {
+            message: "boom".to_string(),
+        };
+        assert_eq!(error.title(), "Error");
+    }
 
     #[test]
     fn test_no_dialog_mode_noop() {
@@ -452,6 +2820,164 @@ mod tests {
         assert!(content.trim().is_empty());
     }
 
+    #[test]
+    fn test_progress_dialog_shows_bytes_and_throughput() {
+        const MB: u64 = 1024 * 1024;
+        let mode = AppMode::Dialog(DialogKind::Progress {
+            message: "big.iso".to_string(),
+            current: 1,
+            total: Some(1),
+            bytes_done: 25 * MB,
+            bytes_total: 100 * MB,
+            started: std::time::Instant::now(),
+            rate: Some(50 * MB),
+            spin: 0,
+        });
+        let state = DialogState::default();
+        let tc = test_theme();
+        let widget = DialogWidget::new(&mode, &state, &tc);
+        let area = Rect::new(0, 0, 80, 24);
+        let mut buf = Buffer::empty(area);
+        widget.render(area, &mut buf);
+
+        let content = buffer_to_string(&buf, area);
+        assert!(content.contains("Processing 1/1"));
+        assert!(content.contains("25% · 25.00 MB of 100.00 MB · 50.00 MB/s"));
+        // A quarter of the bar is filled
+        let bar = content.lines().find(|l| l.contains('█')).unwrap();
+        let filled = bar.matches('█').count();
+        let empty = bar.matches('░').count();
+        assert_eq!(filled, (filled + empty) / 4);
+
+        // Deletes have no bytes to show
+        assert_eq!(progress_stats(0, 0, None), "");
+        assert_eq!(progress_stats(3 * MB, 0, None), "3.00 MB");
+    }
+
+    #[test]
+    fn info_dialog_lines_up_labels_and_dims_a_pending_size() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let info = FileInfo::read(dir.path(), None, None, false);
+        let mode = AppMode::Dialog(DialogKind::Info {
+            path: dir.path().to_path_buf(),
+            info: Box::new(info),
+        });
+        let state = DialogState::default();
+        let tc = test_theme();
+        let area = Rect::new(0, 0, 120, 30);
+        let mut buf = Buffer::empty(area);
+        DialogWidget::new(&mode, &state, &tc).render(area, &mut buf);
+
+        let content = buffer_to_string(&buf, area);
+        assert!(content.contains("[Esc] Close"));
+        let row = |label: &str| {
+            content
+                .lines()
+                .find(|l| l.contains(&format!("{} ", label)))
+                .unwrap_or_else(|| panic!("no {} row", label))
+                .to_string()
+        };
+        assert!(row("Children").contains("Children     0"));
+        assert!(row("MIME").contains("MIME         inode/directory"));
+        let size = row("Size");
+        let x = size.find(file_info::CALCULATING).unwrap();
+        let y = content.lines().position(|l| l == size).unwrap() as u16;
+        let x = size[..x].chars().count() as u16;
+        assert_eq!(buf.cell((x, y)).unwrap().fg, tc.dim_fg);
+    }
+
+    #[test]
+    fn rename_dialog_shows_the_name_check_and_suggestion() {
+        use crate::fs::name_check::Siblings;
+        let original = PathBuf::from("/r/IMG_2031.jpg");
+        let siblings = Siblings::new(Path::new("/r"), [("IMG_2031.jpg".to_string(), false)]);
+        let state = DialogState {
+            input: "photo_01".to_string(),
+            cursor_position: 8,
+            name_check: Some(siblings.check("photo_01", Some(&original))),
+            ..Default::default()
+        };
+        let mode = AppMode::Dialog(DialogKind::Rename { original });
+        let tc = test_theme();
+        let area = Rect::new(0, 0, 80, 24);
+        let mut buf = Buffer::empty(area);
+        DialogWidget::new(&mode, &state, &tc).render(area, &mut buf);
+
+        let content = buffer_to_string(&buf, area);
+        let lines: Vec<&str> = content.lines().collect();
+        let row = |text: &str| lines.iter().position(|l| l.contains(text)).unwrap();
+        // Both notes above the input
+        assert!(row("drops the .jpg extension") < row("photo_01 "));
+        assert!(row("did you mean 'photo_01.jpg'? [Tab] accept") < row("photo_01 "));
+    }
+
+    #[test]
+    fn bulk_rename_dialog_shows_pattern_and_problems() {
+        let mut form = BulkRenameForm::new(vec![
+            PathBuf::from("/nonexistent/a.txt"),
+            PathBuf::from("/nonexistent/b.txt"),
+        ]);
+        for c in "x{n}.md".chars() {
+            form.input_char(c);
+        }
+        let state = DialogState::default();
+        let tc = test_theme();
+        let area = Rect::new(0, 0, 80, 20);
+        let render = |form: &BulkRenameForm| {
+            let mode = AppMode::Dialog(DialogKind::BulkRename { form: form.clone() });
+            let mut buf = Buffer::empty(area);
+            DialogWidget::new(&mode, &state, &tc).render(area, &mut buf);
+            buffer_to_string(&buf, area)
+        };
+
+        let content = render(&form);
+        assert!(content.contains("Bulk Rename (2 items) — pattern 1/1"));
+        assert!(content.contains("Pattern: x{n}.md_"));
+        assert!(content.contains("a.txt → x1.md"));
+        assert!(content.contains("b.txt → x2.md"));
+        assert!(content.contains("[Enter] Rename all"));
+
+        for _ in 0..6 {
+            form.delete_char();
+        }
+        let content = render(&form);
+        assert!(content.contains("⚠ a.txt and b.txt would both become x"));
+    }
+
+    #[test]
+    fn permissions_dialog_shows_the_grid_and_octal_mode() {
+        let paths = vec![PathBuf::from("/r/a.sh"), PathBuf::from("/r/b.sh")];
+        let mut form = PermissionsForm::new(paths, &[0o754, 0o644]);
+        form.move_cursor(1, 2);
+        let mode = AppMode::Dialog(DialogKind::Permissions { form });
+        let state = DialogState::default();
+        let tc = test_theme();
+        let area = Rect::new(0, 0, 80, 20);
+        let mut buf = Buffer::empty(area);
+        DialogWidget::new(&mode, &state, &tc).render(area, &mut buf);
+
+        let content = buffer_to_string(&buf, area);
+        assert!(content.contains("Permissions (2 items) — bits 1/2"));
+        let row = |label: &str| {
+            content
+                .lines()
+                .find(|l| l.contains(label))
+                .unwrap_or_else(|| panic!("no {} row", label))
+                .to_string()
+        };
+        assert!(row("Owner").contains("Owner   [x]    [x]    [x]"));
+        assert!(row("Group").contains("Group   [x]    [ ]    [x]"));
+        assert!(row("Others").contains("Others  [x]    [ ]    [ ]"));
+        assert!(row("Octal").contains("Octal   754   rwxr-xr-- (0754)"));
+        assert!(content.contains("Modes differ; all 2 items get this one"));
+        // The cursor cell: group exec
+        let group = row("Group");
+        let y = content.lines().position(|l| l == group).unwrap() as u16;
+        let x = group.rfind("[x]").unwrap();
+        let x = group[..x].chars().count() as u16;
+        assert_eq!(buf.cell((x, y)).unwrap().bg, tc.tree_selected_bg);
+    }
+
     fn buffer_to_string(buf: &Buffer, area: Rect) -> String {
         let mut s = String::new();
         for y in area.y..area.y + area.height {
@@ -1,22 +1,23 @@
 use ratatui::{
     buffer::Buffer,
     layout::Rect,
-    style::{Modifier, Style},
+    style::{Color, Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Widget},
 };
-use syntect::highlighting::Theme;
-use syntect::parsing::SyntaxSet;
+use unicode_segmentation::UnicodeSegmentation;
 
 use crate::editor::EditorState;
+use crate::syntax::SyntaxResources;
+use crate::text;
 use crate::theme::ThemeColors;
+use crate::whitespace;
 
 /// Widget for rendering the editor view with line numbers, syntax highlighting, and cursor.
 pub struct EditorWidget<'a> {
     editor: &'a EditorState,
     theme: &'a ThemeColors,
-    syntax_set: &'a SyntaxSet,
-    syntax_theme: &'a Theme,
+    syntax: &'a SyntaxResources,
     block: Option<Block<'a>>,
 }
 
@@ -24,14 +25,12 @@ impl<'a> EditorWidget<'a> {
     pub fn new(
         editor: &'a EditorState,
         theme: &'a ThemeColors,
-        syntax_set: &'a SyntaxSet,
-        syntax_theme: &'a Theme,
+        syntax: &'a SyntaxResources,
     ) -> Self {
         Self {
             editor,
             theme,
-            syntax_set,
-            syntax_theme,
+            syntax,
             block: None,
         }
     }
@@ -90,19 +89,15 @@ impl<'a> Widget for EditorWidget<'a> {
 
         // Prepare syntax highlighter for visible lines
         let file_path = &self.editor.file_path;
-        let syntax = self
-            .syntax_set
-            .find_syntax_for_file(file_path)
-            .ok()
-            .flatten()
-            .unwrap_or_else(|| self.syntax_set.find_syntax_plain_text());
-        let mut highlight_state = syntect::easy::HighlightLines::new(syntax, self.syntax_theme);
+        let mut highlighter = self.syntax.highlighter(file_path);
+        // Trailing whitespace is marked unless it means something here.
+        let mark_trailing_ws = !whitespace::trailing_ws_significant(file_path);
 
         // If we're scrolling, we need to process lines before the viewport
         // to get correct syntax state. Process up to scroll offset.
         for i in 0..scroll.min(self.editor.buffer.len()) {
             let line = &self.editor.buffer[i];
-            let _ = highlight_state.highlight_line(line, self.syntax_set);
+            let _ = highlighter.line(line);
         }
 
         // Render visible lines
@@ -135,37 +130,44 @@ impl<'a> Widget for EditorWidget<'a> {
                 let code_x = inner.x + gutter_w;
 
                 // Apply syntax highlighting
-                let highlighted = highlight_state
-                    .highlight_line(line_content, self.syntax_set)
-                    .unwrap_or_default();
+                let highlighted = highlighter
+                    .line(line_content)
+                    .unwrap_or_else(|| vec![(Color::Reset, line_content.as_str())]);
 
+                let trailing_ws = if mark_trailing_ws {
+                    whitespace::trailing_ws_start(line_content)
+                } else {
+                    None
+                };
+
+                // `col_offset` counts display cells, `char_col` buffer chars;
+                // they diverge on wide glyphs, combining marks and tabs.
                 let mut col_offset = 0u16;
-                for (style, text) in &highlighted {
-                    for ch in text.chars() {
-                        if col_offset >= code_width {
-                            break;
+                let mut char_col = 0usize;
+                'segments: for &(fg, segment) in &highlighted {
+                    for grapheme in segment.graphemes(true) {
+                        let cells =
+                            text::cell_width(grapheme, col_offset as usize, self.editor.tab_width)
+                                as u16;
+                        if col_offset + cells > code_width {
+                            break 'segments;
                         }
-                        let char_col = col_offset as usize;
                         let is_cursor = is_current_line && char_col == self.editor.cursor_col;
                         let is_find_match = self.is_find_match(line_idx, char_col);
                         let is_sel = self.editor.is_selected(line_idx, char_col);
 
-                        let fg = ratatui::style::Color::Rgb(
-                            style.foreground.r,
-                            style.foreground.g,
-                            style.foreground.b,
-                        );
-
                         let cell_style = if is_cursor {
                             Style::default()
                                 .fg(self.theme.editor_cursor_fg)
                                 .bg(self.theme.editor_cursor_bg)
                         } else if is_find_match {
                             Style::default()
-                                .fg(ratatui::style::Color::Black)
+                                .fg(Color::Black)
                                 .bg(self.theme.editor_find_match_bg)
                         } else if is_sel {
                             Style::default().fg(fg).bg(self.theme.editor_selection_bg)
+                        } else if trailing_ws.is_some_and(|start| char_col >= start) {
+                            Style::default().fg(fg).bg(self.theme.editor_trailing_ws_bg)
                         } else if is_current_line {
                             Style::default()
                                 .fg(fg)
@@ -174,16 +176,22 @@ impl<'a> Widget for EditorWidget<'a> {
                             Style::default().fg(fg)
                         };
 
-                        let mut s = String::new();
-                        s.push(ch);
-                        buf.set_string(code_x + col_offset, y, &s, cell_style);
-                        col_offset += 1;
+                        if grapheme == "\t" {
+                            let blank = " ".repeat(cells as usize);
+                            buf.set_string(code_x + col_offset, y, blank, cell_style);
+                        } else if cells > 0 {
+                            buf.set_string(code_x + col_offset, y, grapheme, cell_style);
+                        }
+                        col_offset += cells;
+                        char_col += grapheme.chars().count();
                     }
                 }
 
                 // Show cursor at end of line if needed
-                if is_current_line && self.editor.cursor_col >= line_content.len() {
-                    let cursor_x = code_x + line_content.len() as u16;
+                let cursor_at_eol =
+                    is_current_line && self.editor.cursor_col >= line_content.chars().count();
+                if cursor_at_eol {
+                    let cursor_x = code_x + col_offset;
                     if cursor_x < inner.x + inner.width {
                         buf.set_string(
                             cursor_x,
@@ -198,7 +206,7 @@ impl<'a> Widget for EditorWidget<'a> {
 
                 // Fill rest of current line with highlight
                 if is_current_line {
-                    let start_fill = code_x + col_offset.max(self.editor.cursor_col as u16 + 1);
+                    let start_fill = code_x + col_offset + u16::from(cursor_at_eol);
                     for fill_x in start_fill..inner.x + inner.width {
                         buf.set_string(
                             fill_x,
@@ -324,25 +332,21 @@ mod tests {
     use super::*;
     use ratatui::buffer::Buffer;
     use std::path::PathBuf;
-    use syntect::highlighting::ThemeSet;
 
     fn test_theme() -> ThemeColors {
         crate::theme::dark_theme()
     }
 
-    fn test_syntax() -> (SyntaxSet, Theme) {
-        let ss = SyntaxSet::load_defaults_nonewlines();
-        let ts = ThemeSet::load_defaults();
-        let theme = ts.themes["base16-ocean.dark"].clone();
-        (ss, theme)
+    fn test_syntax() -> SyntaxResources {
+        SyntaxResources::load("base16-ocean.dark", None)
     }
 
     #[test]
     fn test_editor_widget_renders_lines() {
         let editor = EditorState::new("line1\nline2\nline3", PathBuf::from("test.txt"));
         let theme = test_theme();
-        let (ss, st) = test_syntax();
-        let widget = EditorWidget::new(&editor, &theme, &ss, &st);
+        let syntax = test_syntax();
+        let widget = EditorWidget::new(&editor, &theme, &syntax);
 
         let area = Rect::new(0, 0, 40, 5);
         let mut buf = Buffer::empty(area);
@@ -358,11 +362,11 @@ mod tests {
     fn test_editor_widget_with_block() {
         let editor = EditorState::new("hello", PathBuf::from("test.txt"));
         let theme = test_theme();
-        let (ss, st) = test_syntax();
+        let syntax = test_syntax();
         let block = Block::default()
             .title(" Test ")
             .borders(ratatui::widgets::Borders::ALL);
-        let widget = EditorWidget::new(&editor, &theme, &ss, &st).block(block);
+        let widget = EditorWidget::new(&editor, &theme, &syntax).block(block);
 
         let area = Rect::new(0, 0, 40, 5);
         let mut buf = Buffer::empty(area);
@@ -376,8 +380,8 @@ mod tests {
     fn test_editor_widget_tilde_beyond_buffer() {
         let editor = EditorState::new("line1", PathBuf::from("test.txt"));
         let theme = test_theme();
-        let (ss, st) = test_syntax();
-        let widget = EditorWidget::new(&editor, &theme, &ss, &st);
+        let syntax = test_syntax();
+        let widget = EditorWidget::new(&editor, &theme, &syntax);
 
         let area = Rect::new(0, 0, 40, 5);
         let mut buf = Buffer::empty(area);
@@ -387,12 +391,147 @@ mod tests {
         assert!(content.contains('~')); // tilde on empty lines
     }
 
+    #[test]
+    fn test_cursor_follows_display_width() {
+        let mut editor = EditorState::new("日本x", PathBuf::from("test.txt"));
+        editor.cursor_col = 1;
+        let theme = test_theme();
+        let syntax = test_syntax();
+        let widget = EditorWidget::new(&editor, &theme, &syntax);
+
+        let area = Rect::new(0, 0, 20, 2);
+        let mut buf = Buffer::empty(area);
+        widget.render(area, &mut buf);
+
+        // Gutter is 3 cells, then 日 takes two, so 本 starts at x = 5.
+        let cursor = buf.cell((5, 0)).unwrap();
+        assert_eq!(cursor.symbol(), "本");
+        assert_eq!(cursor.bg, theme.editor_cursor_bg);
+        assert_eq!(buf.cell((7, 0)).unwrap().symbol(), "x");
+    }
+
+    #[test]
+    fn test_cursor_over_tabs_matches_preview() {
+        let line = "日\tx\ty";
+        let mut editor = EditorState::new(line, PathBuf::from("test.txt"));
+        let theme = test_theme();
+        let syntax = test_syntax();
+
+        for tab_width in [2, 4, 8] {
+            editor.tab_width = tab_width;
+            let (expanded, _) = text::expand_tabs(line, 0, tab_width);
+            for (col, ch) in [(2, "x"), (4, "y")] {
+                editor.cursor_col = col;
+                let widget = EditorWidget::new(&editor, &theme, &syntax);
+                let area = Rect::new(0, 0, 30, 2);
+                let mut buf = Buffer::empty(area);
+                widget.render(area, &mut buf);
+
+                // Where the preview draws the char, after expansion
+                let byte = expanded.find(ch).unwrap();
+                let preview_col = text::width(&expanded[..byte]);
+                assert_eq!(editor.display_col(0, col), preview_col);
+                assert_eq!(editor.col_at_display(0, preview_col), col);
+
+                // Gutter is 3 cells
+                let cursor = buf.cell((3 + preview_col as u16, 0)).unwrap();
+                assert_eq!(cursor.symbol(), ch, "tab width {}", tab_width);
+                assert_eq!(cursor.bg, theme.editor_cursor_bg);
+            }
+        }
+    }
+
+    #[test]
+    fn test_soft_hyphen_is_not_drawn() {
+        let line = "hy\u{AD}phen";
+        let mut editor = EditorState::new(line, PathBuf::from("test.txt"));
+        editor.cursor_col = 3; // the `p`
+        let theme = test_theme();
+        let syntax = test_syntax();
+        let area = Rect::new(0, 0, 30, 2);
+        let mut buf = Buffer::empty(area);
+        EditorWidget::new(&editor, &theme, &syntax).render(area, &mut buf);
+
+        // Same columns as the preview, which drops it
+        let drawn: String = (3..9)
+            .map(|x| buf.cell((x, 0)).unwrap().symbol().to_string())
+            .collect();
+        assert_eq!(drawn, text::expand_tabs(line, 0, 4).0);
+        assert_eq!(editor.display_col(0, 3), 2);
+        assert_eq!(buf.cell((5, 0)).unwrap().bg, theme.editor_cursor_bg);
+    }
+
+    #[test]
+    #[cfg(feature = "syntax-highlighting")]
+    fn test_highlighting_matches_preview() {
+        // Extensionless, so only the shebang says it is Python
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("deploy");
+        let source = "#!/usr/bin/env python3\nprint('hi')\n";
+        std::fs::write(&path, source).unwrap();
+        let syntax = test_syntax();
+
+        let (preview, _) =
+            crate::preview_content::load_highlighted_content(&path, &syntax, 4, false);
+        // Colour of each char of `print('hi')`, skipping the number gutter
+        let preview_fg: Vec<ratatui::style::Color> = preview[1].spans[1..]
+            .iter()
+            .flat_map(|span| span.content.chars().map(move |_| span.style.fg.unwrap()))
+            .collect();
+        assert!(preview_fg.windows(2).any(|w| w[0] != w[1]));
+
+        let editor = EditorState::new(source, path);
+        let theme = test_theme();
+        let widget = EditorWidget::new(&editor, &theme, &syntax);
+        let area = Rect::new(0, 0, 40, 3);
+        let mut buf = Buffer::empty(area);
+        widget.render(area, &mut buf);
+
+        // Gutter is 3 cells; the cursor sits on line 0, not this one
+        let editor_fg: Vec<ratatui::style::Color> = (0..preview_fg.len() as u16)
+            .map(|x| buf.cell((3 + x, 1)).unwrap().fg)
+            .collect();
+        assert_eq!(editor_fg, preview_fg);
+    }
+
+    #[test]
+    fn test_trailing_whitespace_is_marked() {
+        let mut editor = EditorState::new("ab  \ncd", PathBuf::from("test.txt"));
+        editor.cursor_line = 1;
+        let theme = test_theme();
+        let syntax = test_syntax();
+        let widget = EditorWidget::new(&editor, &theme, &syntax);
+        let area = Rect::new(0, 0, 20, 3);
+        let mut buf = Buffer::empty(area);
+        widget.render(area, &mut buf);
+
+        // Gutter is 3 cells: "ab" at x = 3..5, the two spaces at 5..7.
+        assert_ne!(buf.cell((4, 0)).unwrap().bg, theme.editor_trailing_ws_bg);
+        assert_eq!(buf.cell((5, 0)).unwrap().bg, theme.editor_trailing_ws_bg);
+        assert_eq!(buf.cell((6, 0)).unwrap().bg, theme.editor_trailing_ws_bg);
+        assert_ne!(buf.cell((7, 0)).unwrap().bg, theme.editor_trailing_ws_bg);
+    }
+
+    #[test]
+    fn test_markdown_trailing_whitespace_is_not_marked() {
+        let mut editor = EditorState::new("ab  \ncd", PathBuf::from("notes.md"));
+        editor.cursor_line = 1;
+        let theme = test_theme();
+        let syntax = test_syntax();
+        let widget = EditorWidget::new(&editor, &theme, &syntax);
+        let area = Rect::new(0, 0, 20, 3);
+        let mut buf = Buffer::empty(area);
+        widget.render(area, &mut buf);
+
+        assert_ne!(buf.cell((5, 0)).unwrap().bg, theme.editor_trailing_ws_bg);
+    }
+
     #[test]
     fn test_gutter_width() {
         let editor = EditorState::new("a", PathBuf::from("test.txt"));
         let theme = test_theme();
-        let (ss, st) = test_syntax();
-        let widget = EditorWidget::new(&editor, &theme, &ss, &st);
+        let syntax = test_syntax();
+        let widget = EditorWidget::new(&editor, &theme, &syntax);
         assert_eq!(widget.gutter_width(), 3); // 1 digit + 1 space + 1 separator
 
         let many_lines = (0..100)
@@ -400,7 +539,7 @@ mod tests {
             .collect::<Vec<_>>()
             .join("\n");
         let editor2 = EditorState::new(&many_lines, PathBuf::from("test.txt"));
-        let widget2 = EditorWidget::new(&editor2, &theme, &ss, &st);
+        let widget2 = EditorWidget::new(&editor2, &theme, &syntax);
         assert_eq!(widget2.gutter_width(), 5); // 3 digits + 1 space + 1 separator
     }
 
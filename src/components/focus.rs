@@ -0,0 +1,143 @@
+//! Focus and mode spelled out as text.
+//!
+//! Border colours are the only other focus cue, and screen readers and
+//! Braille displays can't see them. Panel titles therefore carry the panel
+//! name, wrapped in the configured markers when focused (`[Tree]` vs
+//! `Tree`), and the status bar starts with the focused panel and mode
+//! (`TREE · NORMAL`). Both are rebuilt from the app state on every draw, so
+//! a mode change shows up in the same frame.
+
+use crate::app::{AppMode, FocusedPanel};
+
+/// Panel name for the title of `panel`, wrapped in `markers` (prefix,
+/// suffix) when it is the `focused` one.
+pub fn panel_label(panel: FocusedPanel, focused: FocusedPanel, markers: (&str, &str)) -> String {
+    if panel == focused {
+        format!("{}{}{}", markers.0, panel.label(), markers.1)
+    } else {
+        panel.label().to_string()
+    }
+}
+
+/// Status bar text naming the focused panel and the mode, e.g.
+/// `PREVIEW · SEARCH`.
+pub fn state_label(focused: FocusedPanel, mode: &AppMode, separator: &str) -> String {
+    format!(
+        "{}{}{}",
+        focused.label().to_uppercase(),
+        separator,
+        mode.label()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::DialogKind;
+    use crate::components::status_bar::StatusBarWidget;
+    use crate::theme;
+    use ratatui::{buffer::Buffer, layout::Rect, widgets::Widget};
+
+    const PANELS: [FocusedPanel; 3] = [
+        FocusedPanel::Tree,
+        FocusedPanel::Preview,
+        FocusedPanel::Terminal,
+    ];
+
+    fn modes() -> Vec<AppMode> {
+        vec![
+            AppMode::Normal,
+            AppMode::Dialog(DialogKind::CreateFile),
+            AppMode::Search,
+            AppMode::SearchAction,
+            AppMode::Grep,
+            AppMode::Filter,
+            AppMode::Help,
+            AppMode::Edit,
+            AppMode::SendTo,
+            AppMode::Places,
+            AppMode::Bookmarks,
+            AppMode::PreviewSearch,
+        ]
+    }
+
+    fn render_status(state: &str, message: Option<&str>) -> String {
+        let tc = theme::dark_theme();
+        let mut widget = StatusBarWidget::new("/proj/src/main.rs", "File", &tc).state(state);
+        if let Some(msg) = message {
+            widget = widget.status_message(msg, false);
+        }
+        let area = Rect::new(0, 0, 100, 1);
+        let mut buf = Buffer::empty(area);
+        widget.render(area, &mut buf);
+        (0..area.width)
+            .map(|x| buf.cell((x, 0)).unwrap().symbol().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn status_bar_names_every_panel_and_mode() {
+        let expected_modes = [
+            "NORMAL",
+            "DIALOG",
+            "SEARCH",
+            "SEARCH ACTIONS",
+            "GREP",
+            "FILTER",
+            "HELP",
+            "EDIT",
+            "SEND TO",
+            "PLACES",
+            "BOOKMARKS",
+            "FIND",
+        ];
+        for (panel, panel_text) in PANELS.iter().zip(["TREE", "PREVIEW", "TERMINAL"]) {
+            for (mode, mode_text) in modes().iter().zip(expected_modes) {
+                let label = state_label(*panel, mode, " · ");
+                let text = render_status(&label, None);
+                let want = format!("{} · {} ", panel_text, mode_text);
+                assert!(
+                    text.starts_with(&want),
+                    "{:?} in {:?}: {:?}",
+                    panel,
+                    mode,
+                    text
+                );
+                assert!(text.contains("/proj/src/main.rs"));
+            }
+        }
+    }
+
+    #[test]
+    fn status_messages_keep_the_state() {
+        let label = state_label(FocusedPanel::Tree, &AppMode::Filter, " · ");
+        let text = render_status(&label, Some("Filter: ma_"));
+        assert!(text.starts_with("TREE · FILTER Filter: ma_"), "{:?}", text);
+    }
+
+    #[test]
+    fn only_the_focused_title_gets_markers() {
+        for focused in PANELS {
+            for panel in PANELS {
+                let label = panel_label(panel, focused, ("[", "]"));
+                if panel == focused {
+                    assert_eq!(label, format!("[{}]", panel.label()));
+                } else {
+                    assert_eq!(label, panel.label());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn markers_and_separator_are_configurable() {
+        assert_eq!(
+            panel_label(FocusedPanel::Preview, FocusedPanel::Preview, ("focus ", "")),
+            "focus Preview"
+        );
+        assert_eq!(
+            state_label(FocusedPanel::Terminal, &AppMode::Help, ", "),
+            "TERMINAL, HELP"
+        );
+    }
+}
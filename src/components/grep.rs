@@ -0,0 +1,281 @@
+use std::path::Path;
+
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Padding, Widget},
+};
+
+use crate::grep::{GrepMatch, GrepState};
+use crate::path_display;
+use crate::text;
+use crate::theme::ThemeColors;
+
+/// Content search overlay widget (Ctrl+Shift+F), styled like the fuzzy
+/// finder. Matches are listed as `path:line: text`, paths relative to
+/// `root`.
+pub struct GrepWidget<'a> {
+    state: &'a GrepState,
+    root: &'a Path,
+    theme: &'a ThemeColors,
+}
+
+impl<'a> GrepWidget<'a> {
+    pub fn new(state: &'a GrepState, root: &'a Path, theme: &'a ThemeColors) -> Self {
+        Self { state, root, theme }
+    }
+
+    fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+        let x = area.x + area.width.saturating_sub(width) / 2;
+        let y = area.y + area.height.saturating_sub(height) / 2;
+        let w = width.min(area.width);
+        let h = height.min(area.height);
+        Rect::new(x, y, w, h)
+    }
+
+    /// Query with its cursor, and the match mode on the right.
+    fn input_line(&self, width: usize) -> Line<'a> {
+        let query = self.state.query.as_str();
+        let cursor = self.state.cursor_position;
+        let (before, at, after) = if cursor < query.len() {
+            let end = text::next_boundary(query, cursor);
+            (&query[..cursor], &query[cursor..end], &query[end..])
+        } else {
+            (query, " ", "")
+        };
+        let input_style = Style::default().fg(self.theme.status_fg);
+        let cursor_style = Style::default()
+            .bg(self.theme.status_fg)
+            .fg(self.theme.dialog_bg)
+            .add_modifier(Modifier::BOLD);
+        let prompt_style = Style::default()
+            .fg(self.theme.info_fg)
+            .add_modifier(Modifier::BOLD);
+
+        let mode = if self.state.regex {
+            "[regex]"
+        } else {
+            "[text]"
+        };
+        let used = 2 + text::width(query) + 1;
+        let gap = width.saturating_sub(used + text::width(mode)).max(1);
+        Line::from(vec![
+            Span::styled("> ", prompt_style),
+            Span::styled(before, input_style),
+            Span::styled(at, cursor_style),
+            Span::styled(after, input_style),
+            Span::raw(" ".repeat(gap)),
+            Span::styled(mode, Style::default().fg(self.theme.dim_fg)),
+        ])
+    }
+
+    /// Error, progress or result count.
+    fn status(&self) -> String {
+        let state = &self.state;
+        if let Some(ref error) = state.error {
+            return format!("Invalid pattern: {}", error);
+        }
+        if state.query.is_empty() {
+            return "Type to search file contents...".to_string();
+        }
+        let count = state.results.len();
+        let mut status = format!("{} match{}", count, if count == 1 { "" } else { "es" });
+        if state.stats.capped {
+            status.push_str(" (limit reached)");
+        }
+        status.push_str(&format!(" in {} files", state.stats.files));
+        if state.stats.skipped > 0 {
+            status.push_str(&format!(", {} skipped", state.stats.skipped));
+        }
+        if state.is_running() {
+            status.push_str(", searching…");
+        } else if state.is_stale() {
+            status.push_str(" [Enter] Search");
+        }
+        status
+    }
+
+    /// `path:line: text`, with the match highlighted.
+    fn build_row(&self, found: &GrepMatch, is_selected: bool) -> Line<'a> {
+        let indicator = if is_selected {
+            Span::styled(
+                "▸ ",
+                Style::default()
+                    .fg(self.theme.info_fg)
+                    .add_modifier(Modifier::BOLD),
+            )
+        } else {
+            Span::raw("  ")
+        };
+        let base_style = if is_selected {
+            Style::default().fg(self.theme.status_fg)
+        } else {
+            Style::default().fg(self.theme.dim_fg)
+        };
+        let highlight_style = Style::default()
+            .fg(self.theme.warning_fg)
+            .add_modifier(Modifier::BOLD);
+        let path = path_display::relative(&found.path, self.root);
+        let text = found.text.as_str();
+        let range = found.range.clone();
+        Line::from(vec![
+            indicator,
+            Span::styled(
+                format!("{}:{}: ", path, found.line),
+                Style::default().fg(self.theme.info_fg),
+            ),
+            Span::styled(text[..range.start].to_string(), base_style),
+            Span::styled(text[range.clone()].to_string(), highlight_style),
+            Span::styled(text[range.end..].to_string(), base_style),
+        ])
+    }
+}
+
+impl<'a> Widget for GrepWidget<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.height < 5 || area.width < 20 {
+            return;
+        }
+
+        // Wider than the fuzzy finder: rows carry line text
+        let dialog_width = (area.width * 80 / 100).clamp(30, 120);
+        let dialog_height = (area.height * 60 / 100).clamp(8, 30);
+        let rect = Self::centered_rect(dialog_width, dialog_height, area);
+
+        Clear.render(rect, buf);
+
+        let block = Block::default()
+            .title(" Content Search (Ctrl+Shift+F) ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(self.theme.dialog_border_fg))
+            .padding(Padding::horizontal(1));
+        let inner = block.inner(rect);
+        block.render(rect, buf);
+
+        if inner.height == 0 || inner.width == 0 {
+            return;
+        }
+
+        // Row 0: query input
+        let input = self.input_line(inner.width as usize);
+        buf.set_line(inner.x, inner.y, &input, inner.width);
+
+        // Row 1: separator + status
+        if inner.height > 1 {
+            let style = if self.state.error.is_some() {
+                Style::default().fg(self.theme.warning_fg)
+            } else {
+                Style::default().fg(self.theme.dim_fg)
+            };
+            let status = Line::from(Span::styled(format!("─── {} ", self.status()), style));
+            buf.set_line(inner.x, inner.y + 1, &status, inner.width);
+        }
+
+        // Row 2+: matches, scrolled to keep the selection visible
+        let results_start = 2u16;
+        let visible = inner.height.saturating_sub(results_start + 1) as usize;
+        let selected = self.state.selected_index;
+        let scroll = if selected >= visible {
+            selected - visible + 1
+        } else {
+            0
+        };
+        for (i, found) in self
+            .state
+            .results
+            .iter()
+            .enumerate()
+            .skip(scroll)
+            .take(visible)
+        {
+            let row = inner.y + results_start + (i - scroll) as u16;
+            let line = self.build_row(found, i == selected);
+            buf.set_line(inner.x, row, &line, inner.width);
+        }
+
+        if inner.height > 3 {
+            let hint = "[Enter] Go to  [Ctrl+R] Regex  [Esc] Close  [↑↓] Navigate";
+            let hint_style = Style::default()
+                .fg(self.theme.dim_fg)
+                .add_modifier(Modifier::DIM);
+            let hint_line = Line::from(Span::styled(hint, hint_style));
+            buf.set_line(inner.x, inner.y + inner.height - 1, &hint_line, inner.width);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grep::GrepStats;
+    use crate::theme;
+    use std::path::PathBuf;
+
+    fn buffer_to_string(buf: &Buffer, area: Rect) -> String {
+        let mut s = String::new();
+        for y in area.y..area.y + area.height {
+            for x in area.x..area.x + area.width {
+                s.push_str(buf.cell((x, y)).unwrap().symbol());
+            }
+            s.push('\n');
+        }
+        s
+    }
+
+    fn render(state: &GrepState) -> (Buffer, Rect) {
+        let theme = theme::dark_theme();
+        let area = Rect::new(0, 0, 100, 20);
+        let mut buf = Buffer::empty(area);
+        GrepWidget::new(state, Path::new("/root"), &theme).render(area, &mut buf);
+        (buf, area)
+    }
+
+    #[test]
+    fn lists_matches_relative_to_the_root() {
+        let mut state = GrepState::default();
+        state.query = "todo".to_string();
+        state.cursor_position = 4;
+        state.results = vec![GrepMatch {
+            path: PathBuf::from("/root/src/main.rs"),
+            line: 12,
+            text: "// todo: tidy".to_string(),
+            range: 3..7,
+        }];
+        state.searched = Some(("todo".to_string(), false));
+        state.stats = GrepStats {
+            files: 3,
+            skipped: 1,
+            capped: false,
+        };
+        let (buf, area) = render(&state);
+        let content = buffer_to_string(&buf, area);
+        assert!(content.contains("Content Search"));
+        assert!(content.contains("[text]"));
+        assert!(content.contains("1 match in 3 files, 1 skipped"));
+        assert!(content.contains("▸ src/main.rs:12: // todo: tidy"));
+
+        // The match itself is highlighted
+        let symbol = |x: u16, y: u16| buf.cell((x, y)).unwrap().symbol().to_string();
+        let (x, y) = (0..area.height)
+            .flat_map(|y| (0..area.width - 1).map(move |x| (x, y)))
+            .find(|&(x, y)| symbol(x, y) == "/" && symbol(x + 1, y) == " ")
+            .unwrap();
+        assert!(!buf.cell((x, y)).unwrap().modifier.contains(Modifier::BOLD));
+        let cell = buf.cell((x + 2, y)).unwrap();
+        assert_eq!(cell.symbol(), "t");
+        assert!(cell.modifier.contains(Modifier::BOLD));
+    }
+
+    #[test]
+    fn shows_pattern_errors() {
+        let mut state = GrepState::default();
+        state.query = "(".to_string();
+        state.regex = true;
+        state.error = Some("unclosed (".to_string());
+        let content = buffer_to_string(&render(&state).0, Rect::new(0, 0, 100, 20));
+        assert!(content.contains("[regex]"));
+        assert!(content.contains("Invalid pattern: unclosed ("));
+    }
+}
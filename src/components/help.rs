@@ -6,6 +6,8 @@ use ratatui::{
     widgets::{Block, Borders, Clear, Widget},
 };
 
+use crate::app::AppMode;
+use crate::text;
 use crate::theme::ThemeColors;
 
 /// State for the help overlay.
@@ -13,6 +15,38 @@ use crate::theme::ThemeColors;
 pub struct HelpState {
     /// Scroll offset for the help content.
     pub scroll_offset: usize,
+    /// Filter typed after `/`; only matching entries are listed.
+    pub query: String,
+    /// Whether keys currently go into `query`.
+    pub searching: bool,
+    /// Mode to return to when the overlay closes.
+    pub return_mode: AppMode,
+}
+
+/// Part of the UI a help section documents, used to open help at the
+/// section for whatever had focus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HelpSection {
+    Tree,
+    FileOps,
+    Search,
+    SearchAction,
+    Preview,
+    Editor,
+    Terminal,
+    General,
+}
+
+impl HelpSection {
+    /// Whether this build has what the section documents; a build without
+    /// the editor or the terminal leaves their keys out of the overlay.
+    fn is_built(self) -> bool {
+        match self {
+            HelpSection::Editor => cfg!(feature = "editor"),
+            HelpSection::Terminal => cfg!(feature = "embedded-terminal"),
+            _ => true,
+        }
+    }
 }
 
 /// A single keybinding entry for display.
@@ -24,6 +58,7 @@ struct KeyEntry {
 /// A category of keybindings.
 struct KeyCategory {
     name: &'static str,
+    section: HelpSection,
     entries: &'static [KeyEntry],
 }
 
@@ -44,10 +79,26 @@ const NAVIGATION_KEYS: &[KeyEntry] = &[
         key: "G / End",
         description: "Jump to last item",
     },
+    KeyEntry {
+        key: "1-9 …",
+        description: "Count for the next motion (5j, 3])",
+    },
     KeyEntry {
         key: "Enter / l / →",
         description: "Expand dir / Load more entries",
     },
+    KeyEntry {
+        key: "Enter (file)",
+        description: "Run [open] action for the file type",
+    },
+    KeyEntry {
+        key: "Shift+Enter / Alt+O",
+        description: "Open file with system opener",
+    },
+    KeyEntry {
+        key: "o / Ctrl+O",
+        description: "Edit file in $EDITOR (suspends the TUI)",
+    },
     KeyEntry {
         key: "Backspace / h / ←",
         description: "Collapse directory",
@@ -64,10 +115,30 @@ const NAVIGATION_KEYS: &[KeyEntry] = &[
         key: "Ctrl+↑/↓",
         description: "Focus up/down (terminal)",
     },
+    KeyEntry {
+        key: "Ctrl+N",
+        description: "New tab at the selected directory",
+    },
+    KeyEntry {
+        key: "Ctrl+PgDn / gt",
+        description: "Next tab",
+    },
+    KeyEntry {
+        key: "Ctrl+PgUp / gT",
+        description: "Previous tab",
+    },
+    KeyEntry {
+        key: "Ctrl+W",
+        description: "Close tab",
+    },
     KeyEntry {
         key: ".",
         description: "Toggle hidden files",
     },
+    KeyEntry {
+        key: "I",
+        description: "Toggle gitignored files",
+    },
     KeyEntry {
         key: "Space",
         description: "Toggle multi-select",
@@ -84,12 +155,60 @@ const NAVIGATION_KEYS: &[KeyEntry] = &[
         key: "S",
         description: "Toggle dirs first",
     },
+    KeyEntry {
+        key: "Alt+s / Alt+S",
+        description: "Sort / dirs first for this directory only",
+    },
+    KeyEntry {
+        key: "Alt+r",
+        description: "Clear this directory's sort override",
+    },
+    KeyEntry {
+        key: "Alt+a",
+        description: "Cycle path display (relative → absolute → ~/)",
+    },
+    KeyEntry {
+        key: "Alt+c / Alt+C",
+        description: "Copy relative / absolute path to system clipboard",
+    },
+    KeyEntry {
+        key: "P",
+        description: "Places: home, drives, bookmarks",
+    },
+    KeyEntry {
+        key: "b",
+        description: "Bookmark the selected directory",
+    },
+    KeyEntry {
+        key: "B",
+        description: "Bookmarks (Enter: jump, d: delete)",
+    },
+    KeyEntry {
+        key: "Alt+p",
+        description: "Export settings profile (.tar.gz)",
+    },
+    KeyEntry {
+        key: ":",
+        description: "Go to path[:line[:col]]",
+    },
+    KeyEntry {
+        key: "c",
+        description: "Jump to next changed path (most recent first)",
+    },
+    KeyEntry {
+        key: "z",
+        description: "Compute selected directory's total size",
+    },
+    KeyEntry {
+        key: "i",
+        description: "File info (permissions, owner, times, MIME)",
+    },
 ];
 
 const FILE_OPS_KEYS: &[KeyEntry] = &[
     KeyEntry {
         key: "a",
-        description: "Create new file",
+        description: "Create file (nested path ok, trailing / = dir)",
     },
     KeyEntry {
         key: "A",
@@ -97,11 +216,47 @@ const FILE_OPS_KEYS: &[KeyEntry] = &[
     },
     KeyEntry {
         key: "r",
-        description: "Rename item",
+        description: "Rename item (bulk rename the selection)",
+    },
+    KeyEntry {
+        key: "R",
+        description: "Sequence rename (selection / dir files)",
+    },
+    KeyEntry {
+        key: "T",
+        description: "Open terminal in selected directory",
+    },
+    KeyEntry {
+        key: "E",
+        description: "Export tree as text / JSON",
+    },
+    KeyEntry {
+        key: "U",
+        description: "Extract archive into a new directory",
+    },
+    KeyEntry {
+        key: "Y",
+        description: "Duplicate selected items in place",
+    },
+    KeyEntry {
+        key: "N",
+        description: "New from scaffold template",
+    },
+    KeyEntry {
+        key: "C",
+        description: "Check directory names for Windows / macOS",
+    },
+    KeyEntry {
+        key: "M",
+        description: "Change permissions of selected items (chmod)",
     },
     KeyEntry {
         key: "d",
-        description: "Delete item",
+        description: "Delete selected / focused items (to trash with use_trash)",
+    },
+    KeyEntry {
+        key: "D",
+        description: "Delete permanently, skipping the trash",
     },
     KeyEntry {
         key: "y",
@@ -115,9 +270,17 @@ const FILE_OPS_KEYS: &[KeyEntry] = &[
         key: "p",
         description: "Paste from clipboard",
     },
+    KeyEntry {
+        key: "Ctrl+Shift+X",
+        description: "Clear clipboard",
+    },
+    KeyEntry {
+        key: ">",
+        description: "Send to target (Enter: copy, m/Shift+Enter: move)",
+    },
     KeyEntry {
         key: "Ctrl+Z",
-        description: "Undo last operation",
+        description: "Undo last operation (confirms if files changed)",
     },
 ];
 
@@ -126,10 +289,22 @@ const SEARCH_FILTER_KEYS: &[KeyEntry] = &[
         key: "Ctrl+P",
         description: "Open fuzzy finder",
     },
+    KeyEntry {
+        key: "Ctrl+Shift+F",
+        description: "Search file contents",
+    },
+    KeyEntry {
+        key: "Ctrl+R",
+        description: "Toggle regex (content search) / raise index cap (fuzzy finder)",
+    },
     KeyEntry {
         key: "/",
         description: "Start inline filter",
     },
+    KeyEntry {
+        key: "F",
+        description: "Cycle filter class chip (Tab / Shift+Tab while filtering)",
+    },
     KeyEntry {
         key: "Esc",
         description: "Cancel / clear filter",
@@ -155,7 +330,15 @@ const SEARCH_ACTION_KEYS: &[KeyEntry] = &[
     },
     KeyEntry {
         key: "y",
-        description: "Copy path to status bar",
+        description: "Copy path (as displayed) to system clipboard",
+    },
+    KeyEntry {
+        key: "Y",
+        description: "Copy path to primary selection (Linux)",
+    },
+    KeyEntry {
+        key: "A / L / ~",
+        description: "Copy absolute / relative / ~ path",
     },
     KeyEntry {
         key: "r",
@@ -177,6 +360,10 @@ const SEARCH_ACTION_KEYS: &[KeyEntry] = &[
         key: "t",
         description: "Open parent dir in terminal",
     },
+    KeyEntry {
+        key: "o",
+        description: "Open with system opener",
+    },
     KeyEntry {
         key: "Esc",
         description: "Back to search results",
@@ -192,6 +379,14 @@ const PREVIEW_KEYS: &[KeyEntry] = &[
         key: "k / ↑",
         description: "Scroll up",
     },
+    KeyEntry {
+        key: "h / ←",
+        description: "Scroll left",
+    },
+    KeyEntry {
+        key: "l / →",
+        description: "Scroll right",
+    },
     KeyEntry {
         key: "g / Home",
         description: "Jump to top",
@@ -212,10 +407,42 @@ const PREVIEW_KEYS: &[KeyEntry] = &[
         key: "Ctrl+W",
         description: "Toggle line wrap",
     },
+    KeyEntry {
+        key: "b",
+        description: "Toggle git blame gutter",
+    },
+    KeyEntry {
+        key: "t",
+        description: "Cycle tab width (2/4/8)",
+    },
+    KeyEntry {
+        key: "m",
+        description: "Markdown front matter: card / raw YAML",
+    },
+    KeyEntry {
+        key: "/",
+        description: "Search the preview text",
+    },
+    KeyEntry {
+        key: "n / N",
+        description: "Next / previous match (Esc clears)",
+    },
+    KeyEntry {
+        key: "[ / ]",
+        description: "Previous / next file in directory",
+    },
     KeyEntry {
         key: "+ / -",
         description: "Adjust head/tail lines",
     },
+    KeyEntry {
+        key: "Esc / x",
+        description: "Cancel a slow preview load",
+    },
+    KeyEntry {
+        key: "r",
+        description: "Retry a cancelled preview load",
+    },
     KeyEntry {
         key: "e",
         description: "Enter edit mode",
@@ -342,6 +569,18 @@ const TERMINAL_KEYS: &[KeyEntry] = &[
         key: "Shift+PgUp/PgDn",
         description: "Fast scroll terminal history",
     },
+    KeyEntry {
+        key: "G / End",
+        description: "Back to live output (while scrolled back)",
+    },
+    KeyEntry {
+        key: "Alt+G",
+        description: "Open the next path:line in the output (bottom up)",
+    },
+    KeyEntry {
+        key: "Ctrl+Click",
+        description: "Open the path:line under the pointer",
+    },
 ];
 
 const GENERAL_KEYS: &[KeyEntry] = &[
@@ -349,6 +588,14 @@ const GENERAL_KEYS: &[KeyEntry] = &[
         key: "?",
         description: "Toggle this help overlay",
     },
+    KeyEntry {
+        key: "F1",
+        description: "Help for the focused panel (also in editor/dialogs)",
+    },
+    KeyEntry {
+        key: "/ (in help)",
+        description: "Search keybindings",
+    },
     KeyEntry {
         key: "q",
         description: "Quit",
@@ -365,47 +612,121 @@ const GENERAL_KEYS: &[KeyEntry] = &[
         key: "Ctrl+R",
         description: "Toggle file watcher",
     },
+    KeyEntry {
+        key: "W",
+        description: "Watcher state; problems: details, retry, polling",
+    },
+    KeyEntry {
+        key: "O",
+        description: "Recent operations: details, go to, undo, retry",
+    },
+    KeyEntry {
+        key: "J",
+        description: "Background tasks: cancel one or all",
+    },
+    KeyEntry {
+        key: "F12",
+        description: "Toggle debug overlay (memory usage)",
+    },
 ];
 
 const CATEGORIES: &[KeyCategory] = &[
     KeyCategory {
         name: "Navigation (Tree Panel)",
+        section: HelpSection::Tree,
         entries: NAVIGATION_KEYS,
     },
     KeyCategory {
         name: "File Operations",
+        section: HelpSection::FileOps,
         entries: FILE_OPS_KEYS,
     },
     KeyCategory {
         name: "Search & Filter",
+        section: HelpSection::Search,
         entries: SEARCH_FILTER_KEYS,
     },
     KeyCategory {
         name: "Search Action Menu",
+        section: HelpSection::SearchAction,
         entries: SEARCH_ACTION_KEYS,
     },
     KeyCategory {
         name: "Preview Panel",
+        section: HelpSection::Preview,
         entries: PREVIEW_KEYS,
     },
     KeyCategory {
         name: "Editor Mode (Preview)",
+        section: HelpSection::Editor,
         entries: EDITOR_KEYS,
     },
     KeyCategory {
         name: "Terminal Panel",
+        section: HelpSection::Terminal,
         entries: TERMINAL_KEYS,
     },
     KeyCategory {
         name: "General",
+        section: HelpSection::General,
         entries: GENERAL_KEYS,
     },
 ];
 
+/// Width of the key column.
+const KEY_WIDTH: usize = 24;
+
+/// Lines above the first category (title, `[open]` default, blank).
+const PREAMBLE_LINES: usize = 3;
+
+/// Categories documenting something in this build.
+fn built_categories() -> impl Iterator<Item = &'static KeyCategory> {
+    CATEGORIES.iter().filter(|c| c.section.is_built())
+}
+
+/// Categories with at least one entry matching `query`, keeping only the
+/// matching entries. An empty query keeps everything.
+fn visible_categories(query: &str) -> Vec<(&'static KeyCategory, Vec<&'static KeyEntry>)> {
+    built_categories()
+        .filter_map(|category| {
+            let entries: Vec<&KeyEntry> = category
+                .entries
+                .iter()
+                .filter(|e| {
+                    query.is_empty()
+                        || text::find_ignore_case(e.key, query).is_some()
+                        || text::find_ignore_case(e.description, query).is_some()
+                })
+                .collect();
+            (!entries.is_empty()).then_some((category, entries))
+        })
+        .collect()
+}
+
+/// Split `text` into spans with every match of `query` highlighted.
+fn highlighted(text: &str, query: &str, style: Style, hit: Style) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    let mut rest = text;
+    while let Some((start, end)) = text::find_ignore_case(rest, query) {
+        if start > 0 {
+            spans.push(Span::styled(rest[..start].to_string(), style));
+        }
+        spans.push(Span::styled(rest[start..end].to_string(), hit));
+        rest = &rest[end..];
+    }
+    if !rest.is_empty() || spans.is_empty() {
+        spans.push(Span::styled(rest.to_string(), style));
+    }
+    spans
+}
+
 /// Help overlay widget showing all keybindings.
 pub struct HelpOverlay<'a> {
     theme: &'a ThemeColors,
     scroll_offset: usize,
+    open_default: &'a str,
+    query: &'a str,
+    searching: bool,
 }
 
 impl<'a> HelpOverlay<'a> {
@@ -413,9 +734,25 @@ impl<'a> HelpOverlay<'a> {
         Self {
             theme,
             scroll_offset,
+            open_default: "preview_focus",
+            query: "",
+            searching: false,
         }
     }
 
+    /// Effective `[open]` default action shown under the title.
+    pub fn open_default(mut self, label: &'a str) -> Self {
+        self.open_default = label;
+        self
+    }
+
+    /// Filter the entries by `query`; `searching` shows the input cursor.
+    pub fn query(mut self, query: &'a str, searching: bool) -> Self {
+        self.query = query;
+        self.searching = searching;
+        self
+    }
+
     /// Build all the lines for the help content.
     fn build_content_lines(&self) -> Vec<Line<'static>> {
         let mut lines: Vec<Line<'static>> = Vec::new();
@@ -427,9 +764,37 @@ impl<'a> HelpOverlay<'a> {
                 .fg(self.theme.accent_fg)
                 .add_modifier(Modifier::BOLD),
         )]));
+        lines.push(Line::from(vec![
+            Span::styled(
+                " Enter on files (default): ",
+                Style::default().fg(self.theme.dim_fg),
+            ),
+            Span::styled(
+                self.open_default.to_string(),
+                Style::default().fg(self.theme.info_fg),
+            ),
+        ]));
         lines.push(Line::from(""));
 
-        for category in CATEGORIES {
+        let key_style = Style::default()
+            .fg(self.theme.warning_fg)
+            .add_modifier(Modifier::BOLD);
+        let desc_style = Style::default().fg(self.theme.tree_file_fg);
+        let hit_style = Style::default()
+            .fg(self.theme.dialog_bg)
+            .bg(self.theme.warning_fg)
+            .add_modifier(Modifier::BOLD);
+
+        let categories = visible_categories(self.query);
+        if categories.is_empty() {
+            lines.push(Line::from(Span::styled(
+                format!("  No keys match \"{}\"", self.query),
+                Style::default().fg(self.theme.dim_fg),
+            )));
+            lines.push(Line::from(""));
+        }
+
+        for (category, entries) in categories {
             // Category header
             lines.push(Line::from(vec![
                 Span::styled(
@@ -441,46 +806,66 @@ impl<'a> HelpOverlay<'a> {
                 Span::styled("─".repeat(40), Style::default().fg(self.theme.dim_fg)),
             ]));
 
-            for entry in category.entries {
-                let key_width = 24;
-                let key_padded = format!("  {:<width$}", entry.key, width = key_width);
-                lines.push(Line::from(vec![
-                    Span::styled(
-                        key_padded,
-                        Style::default()
-                            .fg(self.theme.warning_fg)
-                            .add_modifier(Modifier::BOLD),
-                    ),
-                    Span::styled(
-                        entry.description.to_string(),
-                        Style::default().fg(self.theme.tree_file_fg),
-                    ),
-                ]));
+            for entry in entries {
+                let mut spans = vec![Span::raw("  ")];
+                spans.extend(highlighted(entry.key, self.query, key_style, hit_style));
+                let pad = KEY_WIDTH.saturating_sub(text::width(entry.key));
+                spans.push(Span::styled(" ".repeat(pad), key_style));
+                spans.extend(highlighted(
+                    entry.description,
+                    self.query,
+                    desc_style,
+                    hit_style,
+                ));
+                lines.push(Line::from(spans));
             }
 
             lines.push(Line::from(""));
         }
 
         // Footer
+        let footer = if self.searching {
+            " Type to filter · Enter keep · Esc clear "
+        } else if self.query.is_empty() {
+            " / search · ? or Esc to close "
+        } else {
+            " / edit search · Esc clear · ? close "
+        };
         lines.push(Line::from(vec![Span::styled(
-            " Press ? or Esc to close ",
+            footer,
             Style::default().fg(self.theme.dim_fg),
         )]));
 
         lines
     }
 
-    /// Get total number of content lines (for scroll bounds).
-    pub fn total_lines() -> usize {
-        let mut count = 2; // title + blank
-        for category in CATEGORIES {
+    /// Number of content lines for `query` (for scroll bounds).
+    pub fn total_lines(query: &str) -> usize {
+        let categories = visible_categories(query);
+        let mut count = PREAMBLE_LINES;
+        if categories.is_empty() {
+            count += 2; // "no match" + blank
+        }
+        for (_, entries) in &categories {
             count += 1; // header
-            count += category.entries.len();
+            count += entries.len();
             count += 1; // blank separator
         }
         count += 1; // footer
         count
     }
+
+    /// Line of the unfiltered content where `section`'s header sits.
+    pub fn section_start(section: HelpSection) -> usize {
+        let mut line = PREAMBLE_LINES;
+        for category in built_categories() {
+            if category.section == section {
+                break;
+            }
+            line += category.entries.len() + 2;
+        }
+        line
+    }
 }
 
 impl<'a> Widget for HelpOverlay<'a> {
@@ -497,8 +882,14 @@ impl<'a> Widget for HelpOverlay<'a> {
         Clear.render(overlay_area, buf);
 
         // Draw the block
+        let title = if self.searching || !self.query.is_empty() {
+            let cursor = if self.searching { "█" } else { "" };
+            format!(" Help /{}{} ", self.query, cursor)
+        } else {
+            " Help ".to_string()
+        };
         let block = Block::default()
-            .title(" Help ")
+            .title(title)
             .borders(Borders::ALL)
             .border_style(Style::default().fg(self.theme.border_focused_fg))
             .style(Style::default().bg(self.theme.dialog_bg));
@@ -546,7 +937,7 @@ mod tests {
 
     #[test]
     fn total_lines_is_nonzero() {
-        assert!(HelpOverlay::total_lines() > 0);
+        assert!(HelpOverlay::total_lines("") > 0);
     }
 
     #[test]
@@ -565,6 +956,106 @@ mod tests {
         let theme = crate::theme::dark_theme();
         let overlay = HelpOverlay::new(&theme, 0);
         let lines = overlay.build_content_lines();
-        assert_eq!(lines.len(), HelpOverlay::total_lines());
+        assert_eq!(lines.len(), HelpOverlay::total_lines(""));
+
+        for query in ["blame", "zzz-no-such-key", "ctrl+"] {
+            let overlay = HelpOverlay::new(&theme, 0).query(query, false);
+            let lines = overlay.build_content_lines();
+            assert_eq!(lines.len(), HelpOverlay::total_lines(query), "{}", query);
+        }
+    }
+
+    fn line_text(line: &Line<'_>) -> String {
+        line.spans.iter().map(|s| s.content.as_ref()).collect()
+    }
+
+    #[test]
+    fn every_entry_listed_exactly_once_in_its_section() {
+        let theme = crate::theme::dark_theme();
+        let lines = HelpOverlay::new(&theme, 0).build_content_lines();
+        // (section header, row text) for every row
+        let mut header = String::new();
+        let mut rows: Vec<(String, String)> = Vec::new();
+        for text in lines.iter().map(line_text) {
+            if text.starts_with("── ") {
+                header = text;
+            } else {
+                rows.push((header.clone(), text));
+            }
+        }
+        for category in built_categories() {
+            for entry in category.entries {
+                let expected = format!(
+                    "  {}{}{}",
+                    entry.key,
+                    " ".repeat(KEY_WIDTH.saturating_sub(text::width(entry.key))),
+                    entry.description
+                );
+                let count = rows
+                    .iter()
+                    .filter(|(h, t)| h.contains(category.name) && *t == expected)
+                    .count();
+                assert_eq!(count, 1, "{:?} in {}", entry.key, category.name);
+            }
+        }
+    }
+
+    #[test]
+    fn filter_keeps_headers_of_matching_sections_only() {
+        let theme = crate::theme::dark_theme();
+        let overlay = HelpOverlay::new(&theme, 0).query("BLAME", false);
+        let lines = overlay.build_content_lines();
+        let texts: Vec<String> = lines.iter().map(line_text).collect();
+
+        let headers: Vec<&String> = texts.iter().filter(|t| t.starts_with("── ")).collect();
+        assert_eq!(headers.len(), 1);
+        assert!(headers[0].contains("Preview Panel"));
+        assert!(texts.iter().any(|t| t.contains("Toggle git blame gutter")));
+        assert!(!texts.iter().any(|t| t.contains("Move down")));
+
+        // The match itself is styled differently from the rest of the line
+        let row = lines
+            .iter()
+            .find(|l| line_text(l).contains("git blame"))
+            .unwrap();
+        let hit = row.spans.iter().find(|s| s.content == "blame").unwrap();
+        assert_eq!(hit.style.bg, Some(theme.warning_fg));
+    }
+
+    #[test]
+    fn section_start_points_at_its_header() {
+        let theme = crate::theme::dark_theme();
+        let lines = HelpOverlay::new(&theme, 0).build_content_lines();
+        for (section, name) in [
+            (HelpSection::Tree, "Navigation"),
+            (HelpSection::Preview, "Preview Panel"),
+            (HelpSection::Editor, "Editor Mode"),
+            (HelpSection::Terminal, "Terminal Panel"),
+        ] {
+            if !section.is_built() {
+                continue;
+            }
+            let text = line_text(&lines[HelpOverlay::section_start(section)]);
+            assert!(text.contains(name), "{:?}: {}", section, text);
+        }
+    }
+
+    #[test]
+    fn sections_left_out_of_the_build_are_not_shown() {
+        let theme = crate::theme::dark_theme();
+        let lines = HelpOverlay::new(&theme, 0).build_content_lines();
+        let shown = |name: &str| lines.iter().any(|l| line_text(l).contains(name));
+        assert_eq!(shown("Editor Mode"), cfg!(feature = "editor"));
+        assert_eq!(shown("Terminal Panel"), cfg!(feature = "embedded-terminal"));
+        assert!(shown("Preview Panel"));
+    }
+
+    #[test]
+    fn shows_effective_open_default() {
+        let theme = crate::theme::dark_theme();
+        let overlay = HelpOverlay::new(&theme, 0).open_default("edit");
+        let lines = overlay.build_content_lines();
+        let text: String = lines[1].spans.iter().map(|s| s.content.as_ref()).collect();
+        assert!(text.contains("edit"));
     }
 }
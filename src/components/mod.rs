@@ -1,9 +1,18 @@
+pub mod bookmarks;
+pub mod debug;
 pub mod dialog;
+#[cfg(feature = "editor")]
 pub mod editor;
+pub mod focus;
+pub mod grep;
 pub mod help;
+pub mod places;
 pub mod preview;
 pub mod search;
 pub mod search_action;
+pub mod send_to;
 pub mod status_bar;
+pub mod tab_bar;
 pub mod terminal;
 pub mod tree;
+pub mod watch_indicator;
@@ -0,0 +1,236 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Padding, Widget},
+};
+
+use crate::app::PlacesState;
+use crate::fs::places::Place;
+use crate::preview_content::format_size;
+use crate::text;
+use crate::theme::ThemeColors;
+
+/// Overlay widget listing home, user directories, drives and bookmarks.
+pub struct PlacesWidget<'a> {
+    state: &'a PlacesState,
+    theme: &'a ThemeColors,
+}
+
+impl<'a> PlacesWidget<'a> {
+    pub fn new(state: &'a PlacesState, theme: &'a ThemeColors) -> Self {
+        Self { state, theme }
+    }
+
+    /// Free space column for drives, e.g. "12.00 GB free of 64.00 GB".
+    fn space_label(place: &Place) -> String {
+        match place.space {
+            Some(space) => format!(
+                "  {} free of {}",
+                format_size(space.free),
+                format_size(space.total)
+            ),
+            None => String::new(),
+        }
+    }
+
+    /// Build one line per place.
+    fn build_rows(&self, width: usize) -> Vec<Line<'static>> {
+        let label_width = self
+            .state
+            .places
+            .iter()
+            .map(|p| text::width(&p.label))
+            .max()
+            .unwrap_or(0)
+            .min(16);
+
+        let mut rows: Vec<Line<'static>> = Vec::new();
+        for (i, place) in self.state.places.iter().enumerate() {
+            let selected = i == self.state.selected_index;
+            let usable = place.status.is_usable();
+
+            let mut style = if usable {
+                Style::default().fg(self.theme.status_fg)
+            } else {
+                Style::default()
+                    .fg(self.theme.dim_fg)
+                    .add_modifier(Modifier::DIM)
+            };
+            if selected {
+                style = style
+                    .bg(self.theme.tree_selected_bg)
+                    .add_modifier(Modifier::BOLD);
+            }
+
+            let label = text::truncate(&place.label, label_width);
+            let status = if usable {
+                String::new()
+            } else {
+                format!(" [{}]", place.status.label())
+            };
+            let space = Self::space_label(place);
+            let path_room = width
+                .saturating_sub(label_width + 4)
+                .saturating_sub(status.chars().count())
+                .saturating_sub(space.chars().count());
+            let path = text::truncate(&place.path.to_string_lossy(), path_room);
+
+            rows.push(Line::from(vec![
+                Span::styled(
+                    format!(
+                        " {}{}  ",
+                        label,
+                        " ".repeat(label_width.saturating_sub(text::width(&label)))
+                    ),
+                    style.fg(if usable {
+                        self.theme.accent_fg
+                    } else {
+                        self.theme.dim_fg
+                    }),
+                ),
+                Span::styled(path, style),
+                Span::styled(status, style.fg(self.theme.warning_fg)),
+                Span::styled(space, style.fg(self.theme.info_fg)),
+            ]));
+        }
+        rows
+    }
+}
+
+impl<'a> Widget for PlacesWidget<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.height < 5 || area.width < 20 {
+            return;
+        }
+
+        let width = 72u16.min(area.width);
+        let inner_width = width.saturating_sub(4) as usize;
+        let rows = self.build_rows(inner_width);
+
+        // rows + blank + hint + borders
+        let height = (rows.len().max(1) as u16 + 4).min(area.height);
+        let x = area.x + area.width.saturating_sub(width) / 2;
+        let y = area.y + area.height.saturating_sub(height) / 2;
+        let rect = Rect::new(x, y, width, height);
+
+        Clear.render(rect, buf);
+
+        let block = Block::default()
+            .title(" Places ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(self.theme.dialog_border_fg))
+            .style(Style::default().bg(self.theme.dialog_bg))
+            .padding(Padding::horizontal(1));
+        let inner = block.inner(rect);
+        block.render(rect, buf);
+
+        if inner.height < 2 {
+            return;
+        }
+
+        // Keep the selected row visible when the list is taller than the box.
+        let visible = inner.height.saturating_sub(2) as usize;
+        let start = self
+            .state
+            .selected_index
+            .saturating_sub(visible.saturating_sub(1));
+        for (i, line) in rows.iter().skip(start).take(visible).enumerate() {
+            buf.set_line(inner.x, inner.y + i as u16, line, inner.width);
+        }
+
+        let hint = Line::from(Span::styled(
+            "[Enter] open  [Esc] close",
+            Style::default()
+                .fg(self.theme.dim_fg)
+                .add_modifier(Modifier::DIM),
+        ));
+        buf.set_line(inner.x, inner.y + inner.height - 1, &hint, inner.width);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::places::{DiskSpace, PlaceKind, PlaceStatus};
+    use crate::theme;
+    use std::path::PathBuf;
+
+    fn buffer_to_string(buf: &Buffer, area: Rect) -> String {
+        let mut s = String::new();
+        for y in area.y..area.y + area.height {
+            for x in area.x..area.x + area.width {
+                s.push_str(buf.cell((x, y)).unwrap().symbol());
+            }
+            s.push('\n');
+        }
+        s
+    }
+
+    fn make_state() -> PlacesState {
+        PlacesState {
+            places: vec![
+                Place {
+                    label: "Home".to_string(),
+                    path: PathBuf::from("/home/alice"),
+                    kind: PlaceKind::Home,
+                    space: None,
+                    status: PlaceStatus::Ready,
+                },
+                Place {
+                    label: "USB Stick".to_string(),
+                    path: PathBuf::from("/media/alice/USB Stick"),
+                    kind: PlaceKind::Volume,
+                    space: Some(DiskSpace {
+                        total: 16 * 1024 * 1024 * 1024,
+                        free: 4 * 1024 * 1024 * 1024,
+                    }),
+                    status: PlaceStatus::Ready,
+                },
+                Place {
+                    label: "vault".to_string(),
+                    path: PathBuf::from("/srv/vault"),
+                    kind: PlaceKind::Bookmark,
+                    space: None,
+                    status: PlaceStatus::Unreadable,
+                },
+            ],
+            selected_index: 0,
+        }
+    }
+
+    #[test]
+    fn lists_places_with_free_space() {
+        let state = make_state();
+        let tc = theme::dark_theme();
+        let area = Rect::new(0, 0, 80, 20);
+        let mut buf = Buffer::empty(area);
+        PlacesWidget::new(&state, &tc).render(area, &mut buf);
+        let text = buffer_to_string(&buf, area);
+        assert!(text.contains("Places"));
+        assert!(text.contains("/home/alice"));
+        assert!(text.contains("4.00 GB free of 16.00 GB"));
+        assert!(text.contains("[unreadable]"));
+    }
+
+    #[test]
+    fn unavailable_place_is_dimmed() {
+        let state = make_state();
+        let tc = theme::dark_theme();
+        let widget = PlacesWidget::new(&state, &tc);
+        let rows = widget.build_rows(68);
+        assert_eq!(rows.len(), 3);
+        assert!(rows[2].spans[1].style.add_modifier.contains(Modifier::DIM));
+        assert!(!rows[1].spans[1].style.add_modifier.contains(Modifier::DIM));
+    }
+
+    #[test]
+    fn tiny_area_does_not_panic() {
+        let state = make_state();
+        let tc = theme::dark_theme();
+        let area = Rect::new(0, 0, 10, 3);
+        let mut buf = Buffer::empty(area);
+        PlacesWidget::new(&state, &tc).render(area, &mut buf);
+    }
+}
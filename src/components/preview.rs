@@ -1,13 +1,22 @@
 use ratatui::{
     buffer::Buffer,
     layout::Rect,
-    style::Style,
+    style::{Color, Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Widget},
 };
 
 use crate::app::PreviewState;
+use crate::blame::{BlameGutter, Recency, GUTTER_WIDTH};
+use crate::config::DEFAULT_WRAP_MARKER;
+use crate::flash::FlashStep;
+use crate::preview_search::{self, PreviewSearch};
+use crate::text;
 use crate::theme::ThemeColors;
+use crate::wrap::{self, WrapRow, WrapStyle};
+
+/// Minimum content width kept next to the blame gutter.
+const MIN_CONTENT_WIDTH: u16 = 10;
 
 /// Preview widget that renders file content in the preview panel.
 #[allow(dead_code)]
@@ -15,6 +24,10 @@ pub struct PreviewWidget<'a> {
     preview_state: &'a PreviewState,
     theme: &'a ThemeColors,
     block: Option<Block<'a>>,
+    blame: Option<&'a BlameGutter>,
+    highlight: Option<(usize, FlashStep)>,
+    search: Option<&'a PreviewSearch>,
+    wrap: WrapStyle<'a>,
 }
 
 impl<'a> PreviewWidget<'a> {
@@ -24,6 +37,13 @@ impl<'a> PreviewWidget<'a> {
             preview_state,
             theme,
             block: None,
+            blame: None,
+            highlight: None,
+            search: None,
+            wrap: WrapStyle {
+                marker: DEFAULT_WRAP_MARKER,
+                hang_indent: true,
+            },
         }
     }
 
@@ -32,12 +52,79 @@ impl<'a> PreviewWidget<'a> {
         self.block = block.into();
         self
     }
+
+    /// Draw a git blame gutter to the left of the content.
+    pub fn blame(mut self, gutter: &'a BlameGutter) -> Self {
+        self.blame = Some(gutter);
+        self
+    }
+
+    /// Flash content line `row` (a `path:line` jump target).
+    pub fn highlight(mut self, row: usize, step: FlashStep) -> Self {
+        self.highlight = Some((row, step));
+        self
+    }
+
+    /// Highlight the matches of a preview search and show its query bar
+    /// on the bottom row.
+    pub fn search(mut self, search: &'a PreviewSearch) -> Self {
+        self.search = search.is_active().then_some(search);
+        self
+    }
+
+    /// How continuation rows look when the preview wraps.
+    pub fn wrap(mut self, style: WrapStyle<'a>) -> Self {
+        self.wrap = style;
+        self
+    }
+
+    /// Style patched onto the flashed line; background only, so syntax
+    /// colors stay readable.
+    fn highlight_style(&self, step: FlashStep) -> Style {
+        match step {
+            FlashStep::Strong => Style::default()
+                .bg(self.theme.tree_selected_bg)
+                .add_modifier(Modifier::BOLD),
+            FlashStep::Medium => Style::default().bg(self.theme.tree_selected_bg),
+            FlashStep::Faint => Style::default().add_modifier(Modifier::UNDERLINED),
+        }
+    }
+
+    /// Draw the search query and match counter across `area`.
+    fn render_search_bar(&self, search: &PreviewSearch, area: Rect, buf: &mut Buffer) {
+        let bar = Style::default()
+            .fg(self.theme.status_fg)
+            .bg(self.theme.editor_find_bar_bg);
+        buf.set_style(area, bar);
+        let cursor = if search.typing { "_" } else { "" };
+        let line = Line::from(vec![
+            Span::styled(
+                format!("/{}{}", search.query, cursor),
+                bar.add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(format!(" {}", search.counter()), bar.fg(self.theme.dim_fg)),
+        ]);
+        buf.set_line(area.x, area.y, &line, area.width);
+    }
+
+    fn gutter_style(&self, recency: Recency) -> Style {
+        match recency {
+            Recency::Fresh => Style::default()
+                .fg(self.theme.accent_fg)
+                .add_modifier(Modifier::BOLD),
+            Recency::Recent => Style::default().fg(self.theme.status_fg),
+            Recency::Old => Style::default().fg(self.theme.dim_fg),
+            Recency::Ancient => Style::default()
+                .fg(self.theme.dim_fg)
+                .add_modifier(Modifier::DIM),
+        }
+    }
 }
 
 impl<'a> Widget for PreviewWidget<'a> {
-    fn render(self, area: Rect, buf: &mut Buffer) {
+    fn render(mut self, area: Rect, buf: &mut Buffer) {
         // Render block (border) first, get inner area
-        let inner = if let Some(block) = self.block {
+        let inner = if let Some(block) = self.block.take() {
             let inner = block.inner(area);
             block.render(area, buf);
             inner
@@ -57,24 +144,141 @@ impl<'a> Widget for PreviewWidget<'a> {
             return;
         }
 
+        // The search bar takes the bottom row
+        let inner = match self.search {
+            Some(search) if inner.height > 1 => {
+                let bar = Rect {
+                    y: inner.bottom() - 1,
+                    height: 1,
+                    ..inner
+                };
+                self.render_search_bar(search, bar, buf);
+                Rect {
+                    height: inner.height - 1,
+                    ..inner
+                }
+            }
+            _ => inner,
+        };
+        let hit_style = Style::default()
+            .fg(Color::Black)
+            .bg(self.theme.editor_find_match_bg);
+        let current_style = hit_style.add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+
         // Render visible lines starting from scroll_offset
         let visible_height = inner.height as usize;
-        let max_start = self
-            .preview_state
-            .content_lines
-            .len()
-            .saturating_sub(visible_height);
+        let lines = &self.preview_state.content_lines;
+        let max_start = lines.len().saturating_sub(visible_height);
         let start = self.preview_state.scroll_offset.min(max_start);
-        let end = (start + visible_height).min(self.preview_state.content_lines.len());
 
-        for (i, line) in self.preview_state.content_lines[start..end]
-            .iter()
-            .enumerate()
-        {
+        // The gutter only narrows the content area; row math is unchanged.
+        let (gutter, content) = match self.blame {
+            Some(gutter) if inner.width >= GUTTER_WIDTH + MIN_CONTENT_WIDTH => (
+                Some(gutter),
+                Rect {
+                    x: inner.x + GUTTER_WIDTH,
+                    width: inner.width - GUTTER_WIDTH,
+                    ..inner
+                },
+            ),
+            _ => (None, inner),
+        };
+
+        let rows = if self.preview_state.line_wrap {
+            wrap::wrap_rows(
+                lines,
+                start,
+                content.width as usize,
+                visible_height,
+                self.wrap,
+            )
+        } else {
+            let end = (start + visible_height).min(lines.len());
+            (start..end)
+                .map(|line| WrapRow {
+                    line,
+                    segment: 0,
+                    start: self.preview_state.h_offset,
+                    len: usize::MAX,
+                    lead: 0,
+                    marker: false,
+                })
+                .collect()
+        };
+        let marker_style = Style::default().fg(self.theme.dim_fg);
+
+        for (i, row) in rows.iter().enumerate() {
             let y = inner.y + i as u16;
-            buf.set_line(inner.x, y, line, inner.width);
+            let marked;
+            let line = match self.search.map(|search| search.on_row(row.line)) {
+                Some(ranges) if !ranges.is_empty() => {
+                    marked =
+                        preview_search::mark(&lines[row.line], &ranges, hit_style, current_style);
+                    &marked
+                }
+                _ => &lines[row.line],
+            };
+            if row.segment == 0 {
+                if let Some(cell) = gutter.and_then(|g| g.cell(row.line + 1)) {
+                    let span = Span::styled(cell.text.as_str(), self.gutter_style(cell.recency));
+                    buf.set_span(inner.x, y, &span, GUTTER_WIDTH - 1);
+                }
+            }
+            let flashed = match self.highlight {
+                Some((target, step)) if target == row.line => Some(self.highlight_style(step)),
+                _ => None,
+            };
+            if let Some(style) = flashed {
+                // Fill the row so the highlight spans the panel width
+                buf.set_style(Rect::new(content.x, y, content.width, 1), style);
+            }
+
+            // Continuation rows: gutter and indent blanks, then the marker.
+            // Neither is part of the line, so search and copy never see them.
+            let mut x = content.x + (row.lead as u16).min(content.width);
+            if row.marker {
+                let span = Span::styled(self.wrap.marker, marker_style);
+                x = buf
+                    .set_span(x, y, &span, content.right().saturating_sub(x))
+                    .0;
+            }
+            let width = content.right().saturating_sub(x);
+
+            if row.start == 0 && row.len >= line.width() && flashed.is_none() {
+                buf.set_line(x, y, line, width);
+            } else {
+                let mut shown = slice_line(line, row.start, row.len);
+                if let Some(style) = flashed {
+                    shown = shown.patch_style(style);
+                }
+                buf.set_line(x, y, &shown, width);
+            }
+        }
+    }
+}
+
+/// Columns `start..start + len` of `line`, keeping span styles. A wide
+/// glyph cut in half at either edge is blanked rather than split.
+fn slice_line(line: &Line<'_>, start: usize, len: usize) -> Line<'static> {
+    let end = start.saturating_add(len);
+    let mut col = 0;
+    let mut spans = Vec::new();
+    for span in &line.spans {
+        let span_start = col;
+        let span_end = col + text::width(&span.content);
+        col = span_end;
+        if span_end <= start {
+            continue;
+        }
+        if span_start >= end {
+            break;
         }
+        let from = start.saturating_sub(span_start);
+        let to = end.min(span_end) - span_start;
+        let visible = text::slice_columns(&span.content, from, to - from);
+        spans.push(Span::styled(visible, span.style));
     }
+    Line::from(spans).style(line.style)
 }
 
 #[cfg(test)]
@@ -195,6 +399,235 @@ mod tests {
         assert!(row0.contains("line 3"));
     }
 
+    #[test]
+    fn test_h_offset_blanks_split_wide_glyph() {
+        let state = PreviewState {
+            content_lines: vec![Line::from(vec![
+                Span::raw("日本"),
+                Span::styled("語abc", Style::default().fg(ratatui::style::Color::Red)),
+            ])],
+            total_lines: 1,
+            h_offset: 1,
+            ..Default::default()
+        };
+        let tc = test_theme();
+        let area = Rect::new(0, 0, 12, 1);
+        let mut buf = Buffer::empty(area);
+        PreviewWidget::new(&state, &tc).render(area, &mut buf);
+
+        let row = row_text(&buf, 0, 12);
+        assert!(row.starts_with(' '), "{:?}", row);
+        assert!(!row.contains('日'));
+        assert!(row.contains('本') && row.contains("abc"));
+        let styled = buf
+            .content()
+            .iter()
+            .find(|cell| cell.symbol() == "語")
+            .expect("span after the cut is drawn");
+        assert_eq!(styled.fg, ratatui::style::Color::Red);
+    }
+
+    #[test]
+    fn test_slice_line_skips_whole_spans() {
+        let line = Line::from(vec![Span::raw("ab"), Span::raw("cd")]);
+        let scrolled = slice_line(&line, 3, usize::MAX);
+        assert_eq!(scrolled.spans.len(), 1);
+        assert_eq!(scrolled.spans[0].content, "d");
+        let middle = slice_line(&line, 1, 2);
+        assert_eq!(wrap::line_text(&middle), "bc");
+    }
+
+    #[test]
+    fn test_wrapped_rows_get_dim_marker_and_hang_indent() {
+        let mut state = PreviewState::default();
+        let code = format!("  {}", "x".repeat(20));
+        state.content_lines = vec![
+            Line::from(vec![Span::raw("1 │ "), Span::raw(code.clone())]),
+            Line::from(vec![Span::raw("2 │ "), Span::raw("end")]),
+        ];
+        state.total_lines = 2;
+        state.line_wrap = true;
+        let tc = test_theme();
+        let area = Rect::new(0, 0, 16, 4);
+        let mut buf = Buffer::empty(area);
+        PreviewWidget::new(&state, &tc).render(area, &mut buf);
+
+        assert_eq!(row_text(&buf, 0, 16), format!("1 │ {}", &code[..12]));
+        // Gutter (4) and indent (2) blanks, the marker, then the rest.
+        assert_eq!(row_text(&buf, 1, 16), "      ↪ xxxxxxxx");
+        assert_eq!(buf.cell((6, 1)).unwrap().fg, tc.dim_fg);
+        assert_eq!(row_text(&buf, 2, 16).trim_end(), "      ↪ xx");
+        assert_eq!(row_text(&buf, 3, 16).trim_end(), "2 │ end");
+
+        // The marker lives only in the buffer; the lines search and copy
+        // read are untouched.
+        assert_eq!(
+            wrap::line_text(&state.content_lines[0]),
+            format!("1 │ {}", code)
+        );
+    }
+
+    #[test]
+    fn test_wrap_uses_configured_marker() {
+        let state = PreviewState {
+            content_lines: vec![Line::from("y".repeat(30))],
+            total_lines: 1,
+            line_wrap: true,
+            ..Default::default()
+        };
+        let tc = test_theme();
+        let area = Rect::new(0, 0, 20, 2);
+        let mut buf = Buffer::empty(area);
+        PreviewWidget::new(&state, &tc)
+            .wrap(WrapStyle {
+                marker: "",
+                hang_indent: false,
+            })
+            .render(area, &mut buf);
+        assert_eq!(row_text(&buf, 1, 20).trim_end(), "y".repeat(10));
+    }
+
+    #[test]
+    fn test_blame_only_on_first_wrapped_row() {
+        let (mut state, gutter) = gutter_state();
+        state.content_lines[1] = Line::from(format!("line 2 {}", "z".repeat(30)));
+        state.line_wrap = true;
+        let tc = test_theme();
+        let area = Rect::new(0, 0, 40, 2);
+        let mut buf = Buffer::empty(area);
+        PreviewWidget::new(&state, &tc)
+            .blame(&gutter)
+            .render(area, &mut buf);
+        assert!(row_text(&buf, 0, 40).starts_with("abcdef12"));
+        let row1 = row_text(&buf, 1, 40);
+        assert!(row1.starts_with("              ↪ zz"), "{:?}", row1);
+    }
+
+    #[test]
+    fn test_search_highlights_matches_above_its_bar() {
+        let mut state = PreviewState::default();
+        state.content_lines = vec![
+            Line::from("find me, find"),
+            Line::from("nothing"),
+            Line::from("hidden by the bar: find"),
+        ];
+        state.total_lines = 3;
+        state.text = state
+            .content_lines
+            .iter()
+            .map(preview_search::row_text)
+            .collect();
+        let mut search = PreviewSearch {
+            query: "FIND".to_string(),
+            typing: true,
+            ..Default::default()
+        };
+        search.find(&state.text);
+        search.step(true, 1);
+        let tc = test_theme();
+        let area = Rect::new(0, 0, 20, 3);
+        let mut buf = Buffer::empty(area);
+        PreviewWidget::new(&state, &tc)
+            .search(&search)
+            .render(area, &mut buf);
+
+        assert_eq!(row_text(&buf, 0, 20).trim_end(), "find me, find");
+        let other = buf.cell((0, 0)).unwrap().style();
+        assert_eq!(other.bg, Some(tc.editor_find_match_bg));
+        assert!(!other.add_modifier.contains(Modifier::BOLD));
+        let current = buf.cell((9, 0)).unwrap().style();
+        assert!(current.add_modifier.contains(Modifier::BOLD));
+        assert_ne!(
+            buf.cell((4, 0)).unwrap().style().bg,
+            Some(tc.editor_find_match_bg)
+        );
+        assert_eq!(row_text(&buf, 2, 20).trim_end(), "/FIND_ (2/3)");
+    }
+
+    fn row_text(buf: &Buffer, y: u16, width: u16) -> String {
+        (0..width)
+            .map(|x| buf.cell((x, y)).unwrap().symbol().to_string())
+            .collect()
+    }
+
+    fn gutter_state() -> (PreviewState, BlameGutter) {
+        let state = PreviewState {
+            content_lines: (1..=4).map(|i| Line::from(format!("line {}", i))).collect(),
+            total_lines: 4,
+            scroll_offset: 1,
+            ..Default::default()
+        };
+        let lines = vec![
+            (
+                2,
+                crate::blame::BlameLine {
+                    hash: "abcdef12".to_string(),
+                    author_time: 1_000,
+                },
+            ),
+            (
+                3,
+                crate::blame::BlameLine {
+                    hash: "0123abcd".to_string(),
+                    author_time: 0,
+                },
+            ),
+        ];
+        let gutter = BlameGutter::build(&lines, 2, 3, 1_000 + 7_200);
+        (state, gutter)
+    }
+
+    #[test]
+    fn test_blame_gutter_shifts_content() {
+        let (state, gutter) = gutter_state();
+        let tc = test_theme();
+        let area = Rect::new(0, 0, 40, 2);
+        let mut buf = Buffer::empty(area);
+        PreviewWidget::new(&state, &tc)
+            .blame(&gutter)
+            .render(area, &mut buf);
+        let row0 = row_text(&buf, 0, 40);
+        assert!(row0.starts_with("abcdef12   2h"));
+        assert_eq!(&row0[14..20], "line 2");
+        let fresh = buf.cell((0, 0)).unwrap().style();
+        assert!(fresh.add_modifier.contains(Modifier::BOLD));
+        let row1 = row_text(&buf, 1, 40);
+        assert!(row1.starts_with("0123abcd"));
+        assert_eq!(&row1[14..20], "line 3");
+    }
+
+    #[test]
+    fn test_blame_gutter_dropped_when_too_narrow() {
+        let (state, gutter) = gutter_state();
+        let tc = test_theme();
+        let area = Rect::new(0, 0, 20, 2);
+        let mut buf = Buffer::empty(area);
+        PreviewWidget::new(&state, &tc)
+            .blame(&gutter)
+            .render(area, &mut buf);
+        assert!(row_text(&buf, 0, 20).starts_with("line 2"));
+    }
+
+    #[test]
+    fn test_highlighted_line_gets_background() {
+        let state = PreviewState {
+            content_lines: vec![Line::from("a"), Line::from("b"), Line::from("c")],
+            total_lines: 3,
+            ..Default::default()
+        };
+        let tc = test_theme();
+        let area = Rect::new(0, 0, 10, 3);
+        let mut buf = Buffer::empty(area);
+        PreviewWidget::new(&state, &tc)
+            .highlight(1, FlashStep::Strong)
+            .render(area, &mut buf);
+        assert_eq!(row_text(&buf, 1, 10).trim_end(), "b");
+        assert_eq!(buf.cell((0, 1)).unwrap().bg, tc.tree_selected_bg);
+        // The fill covers the whole row, not just the text
+        assert_eq!(buf.cell((9, 1)).unwrap().bg, tc.tree_selected_bg);
+        assert_ne!(buf.cell((0, 0)).unwrap().bg, tc.tree_selected_bg);
+    }
+
     #[test]
     fn test_zero_area_no_panic() {
         let state = PreviewState::default();
@@ -7,6 +7,8 @@ use ratatui::{
 };
 
 use crate::app::SearchState;
+use crate::components::dialog;
+use crate::path_index;
 use crate::theme::ThemeColors;
 
 /// Fuzzy finder overlay widget (Ctrl+P).
@@ -14,6 +16,8 @@ pub struct SearchWidget<'a> {
     state: &'a SearchState,
     theme: &'a ThemeColors,
     block: Option<Block<'a>>,
+    /// Shown before each (root-relative) result, per the path style.
+    path_prefix: &'a str,
 }
 
 impl<'a> SearchWidget<'a> {
@@ -22,9 +26,16 @@ impl<'a> SearchWidget<'a> {
             state,
             theme,
             block: None,
+            path_prefix: "",
         }
     }
 
+    /// Show results with `prefix` (the root, absolute or `~/`) in front.
+    pub fn path_prefix(mut self, prefix: &'a str) -> Self {
+        self.path_prefix = prefix;
+        self
+    }
+
     #[allow(dead_code)]
     pub fn block(mut self, block: Block<'a>) -> Self {
         self.block = Some(block);
@@ -119,8 +130,42 @@ impl<'a> Widget for SearchWidget<'a> {
             buf.set_line(inner.x, inner.y + 1, &sep_line, inner.width);
         }
 
-        // Row 2+: Results list
-        let results_start = 2u16;
+        // Rows 2-3 when the index is incomplete: the background index
+        // still filling it, what it misses, or the re-index with the
+        // raised cap under way
+        let coverage = &self.state.coverage;
+        let mut warnings = Vec::new();
+        if let Some(indexing) = &self.state.indexing {
+            warnings.push(format!(
+                "{} indexing… {} files",
+                dialog::spinner_frame(indexing.started),
+                dialog::group_thousands(indexing.seen)
+            ));
+        } else if coverage.truncated {
+            match &self.state.reindex {
+                Some(reindex) => warnings.push(format!(
+                    "{} re-indexing up to {} entries…",
+                    dialog::spinner_frame(reindex.started),
+                    dialog::group_thousands(coverage.cap.saturating_mul(path_index::RAISE_FACTOR))
+                )),
+                None => {
+                    warnings.push(format!("⚠ {}", coverage.summary()));
+                    warnings.extend(coverage.missing().map(|m| format!("  {}", m)));
+                }
+            }
+        }
+        let warning_style = Style::default().fg(self.theme.warning_fg);
+        let mut results_start = 2u16;
+        for warning in warnings {
+            if results_start >= inner.height {
+                break;
+            }
+            let line = Line::from(Span::styled(warning, warning_style));
+            buf.set_line(inner.x, inner.y + results_start, &line, inner.width);
+            results_start += 1;
+        }
+
+        // Results list
         let visible_results = (inner.height.saturating_sub(results_start)) as usize;
 
         // Calculate scroll offset for results to keep selected visible
@@ -146,9 +191,10 @@ impl<'a> Widget for SearchWidget<'a> {
             let is_selected = (i + scroll) == self.state.selected_index;
 
             // Build display with highlighted match characters
-            let display = &result.display;
+            let display = format!("{}{}", self.path_prefix, result.display);
+            let offset = self.path_prefix.chars().count();
             let match_set: std::collections::HashSet<usize> =
-                result.match_indices.iter().copied().collect();
+                result.match_indices.iter().map(|i| i + offset).collect();
 
             let mut spans = Vec::new();
 
@@ -213,7 +259,11 @@ impl<'a> Widget for SearchWidget<'a> {
 
         // Hint at bottom
         if inner.height > 3 {
-            let hint = "[Enter] Open  [Esc] Close  [↑↓] Navigate";
+            let hint = if coverage.truncated && self.state.reindex.is_none() {
+                "[Enter] Open  [Esc] Close  [Ctrl+R] Raise cap"
+            } else {
+                "[Enter] Open  [Esc] Close  [↑↓] Navigate"
+            };
             let hint_style = Style::default()
                 .fg(self.theme.dim_fg)
                 .add_modifier(Modifier::DIM);
@@ -322,6 +372,111 @@ mod tests {
         assert!(content.contains("▸"));
     }
 
+    #[test]
+    fn test_path_prefix_shifts_highlights() {
+        let state = SearchState {
+            query: "t".to_string(),
+            cursor_position: 1,
+            results: vec![SearchResult {
+                path: PathBuf::from("/home/me/src/a.txt"),
+                display: "a.txt".to_string(),
+                score: 50,
+                match_indices: vec![2],
+            }],
+            ..Default::default()
+        };
+
+        let tc = test_theme();
+        let widget = SearchWidget::new(&state, &tc).path_prefix("~/src/");
+        let area = Rect::new(0, 0, 80, 24);
+        let mut buf = Buffer::empty(area);
+        widget.render(area, &mut buf);
+
+        let (x, y) = (0..area.height)
+            .find_map(|y| {
+                let row: String = (0..area.width)
+                    .map(|x| buf.cell((x, y)).unwrap().symbol().to_string())
+                    .collect();
+                let i = row.find("~/src/a.txt")?;
+                Some((row[..i].chars().count() as u16, y))
+            })
+            .expect("prefixed result rendered");
+        // The match on "t" moved past the prefix; the prefix isn't lit
+        assert_eq!(buf.cell((x + 8, y)).unwrap().fg, tc.warning_fg);
+        assert_ne!(buf.cell((x + 2, y)).unwrap().fg, tc.warning_fg);
+    }
+
+    #[test]
+    fn truncated_index_warns_above_the_results() {
+        let mut state = SearchState {
+            query: "a".to_string(),
+            cursor_position: 1,
+            results: vec![SearchResult {
+                path: PathBuf::from("/r/a.txt"),
+                display: "a.txt".to_string(),
+                score: 50,
+                match_indices: vec![0],
+            }],
+            coverage: path_index::Coverage {
+                truncated: true,
+                cap: 10_000,
+                seen: 87_000,
+                counted_all: true,
+                partial: vec!["target".into(), "vendor".into()],
+            },
+            ..Default::default()
+        };
+        let tc = test_theme();
+        let area = Rect::new(0, 0, 120, 24);
+        let render = |state: &SearchState| {
+            let mut buf = Buffer::empty(area);
+            SearchWidget::new(state, &tc).render(area, &mut buf);
+            buffer_to_string(&buf, area)
+        };
+
+        let content = render(&state);
+        let lines: Vec<&str> = content.lines().collect();
+        let warning = lines
+            .iter()
+            .position(|l| l.contains("10,000 of 87,000 entries"))
+            .expect("warning line");
+        assert!(content.contains("[Ctrl+R] Raise cap"));
+        assert!(lines[warning + 1].contains("target/ and vendor/ not fully indexed"));
+        assert!(lines[warning + 2].contains("a.txt"));
+
+        state.reindex = Some(crate::app::Reindex {
+            started: std::time::Instant::now(),
+            cancel: Default::default(),
+        });
+        let content = render(&state);
+        assert!(content.contains("re-indexing up to 100,000 entries"));
+        assert!(!content.contains("Raise cap"));
+
+        state.coverage = Default::default();
+        let content = render(&state);
+        assert!(!content.contains("index truncated"));
+        assert!(!content.contains("re-indexing"));
+    }
+
+    #[test]
+    fn background_index_in_progress_is_shown() {
+        let state = SearchState {
+            indexing: Some(crate::app::Indexing {
+                job: 1,
+                root: PathBuf::from("/r"),
+                started: std::time::Instant::now(),
+                seen: 42_000,
+                cancel: Default::default(),
+            }),
+            ..Default::default()
+        };
+        let tc = test_theme();
+        let area = Rect::new(0, 0, 120, 24);
+        let mut buf = Buffer::empty(area);
+        SearchWidget::new(&state, &tc).render(area, &mut buf);
+        assert!(buffer_to_string(&buf, area).contains("indexing… 42,000 files"));
+    }
+
     #[test]
     fn test_small_area_no_panic() {
         let state = SearchState::default();
@@ -7,6 +7,7 @@ use ratatui::{
 };
 
 use crate::app::SearchActionState;
+use crate::text;
 use crate::theme::ThemeColors;
 
 /// A single action entry in the search action menu.
@@ -63,6 +64,16 @@ impl<'a> SearchActionWidget<'a> {
             key: "y",
             label: "Copy path",
         });
+        if cfg!(target_os = "linux") {
+            actions.push(ActionEntry {
+                key: "Y",
+                label: "Copy path (primary)",
+            });
+        }
+        actions.push(ActionEntry {
+            key: "A/L/~",
+            label: "Copy absolute/relative/~ path",
+        });
         actions.push(ActionEntry {
             key: "r",
             label: "Rename",
@@ -83,6 +94,10 @@ impl<'a> SearchActionWidget<'a> {
             key: "t",
             label: "Open in terminal",
         });
+        actions.push(ActionEntry {
+            key: "o",
+            label: "Open with system opener",
+        });
 
         actions
     }
@@ -147,7 +162,7 @@ impl<'a> Widget for SearchActionWidget<'a> {
         let header_line = Line::from(vec![
             Span::styled(type_indicator, Style::default().fg(self.theme.info_fg)),
             Span::styled(
-                truncate_str(&name, inner.width as usize - 4),
+                text::truncate(&name, inner.width as usize - 4),
                 Style::default()
                     .fg(self.theme.status_fg)
                     .add_modifier(Modifier::BOLD),
@@ -159,7 +174,7 @@ impl<'a> Widget for SearchActionWidget<'a> {
         // Row 1: relative path (dimmed)
         if row < inner.y + inner.height {
             let path_line = Line::from(Span::styled(
-                truncate_str(&self.state.display, inner.width as usize),
+                text::truncate(&self.state.display, inner.width as usize),
                 Style::default().fg(self.theme.dim_fg),
             ));
             buf.set_line(inner.x, row, &path_line, inner.width);
@@ -210,16 +225,6 @@ impl<'a> Widget for SearchActionWidget<'a> {
     }
 }
 
-/// Truncate a string to fit within `max_len` characters, adding "…" if needed.
-fn truncate_str(s: &str, max_len: usize) -> String {
-    if s.chars().count() <= max_len {
-        s.to_string()
-    } else {
-        let truncated: String = s.chars().take(max_len.saturating_sub(1)).collect();
-        format!("{}…", truncated)
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -236,6 +241,8 @@ mod tests {
             display: "test.txt".to_string(),
             is_directory,
             is_binary,
+            line: None,
+            col: None,
         }
     }
 
@@ -250,11 +257,16 @@ mod tests {
         assert!(labels.contains(&"Preview"));
         assert!(labels.contains(&"Edit"));
         assert!(labels.contains(&"Copy path"));
+        assert_eq!(
+            labels.contains(&"Copy path (primary)"),
+            cfg!(target_os = "linux")
+        );
         assert!(labels.contains(&"Rename"));
         assert!(labels.contains(&"Delete"));
         assert!(labels.contains(&"Copy (clipboard)"));
         assert!(labels.contains(&"Cut (clipboard)"));
         assert!(labels.contains(&"Open in terminal"));
+        assert!(labels.contains(&"Open with system opener"));
     }
 
     #[test]
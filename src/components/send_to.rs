@@ -0,0 +1,222 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Padding, Widget},
+};
+
+use crate::app::SendToState;
+use crate::text;
+use crate::theme::ThemeColors;
+
+/// Label of the trailing row that registers the current directory.
+const ADD_ENTRY_LABEL: &str = "+ Add current directory as target…";
+
+/// Overlay widget listing the configured "send to" targets.
+pub struct SendToWidget<'a> {
+    state: &'a SendToState,
+    theme: &'a ThemeColors,
+}
+
+impl<'a> SendToWidget<'a> {
+    pub fn new(state: &'a SendToState, theme: &'a ThemeColors) -> Self {
+        Self { state, theme }
+    }
+
+    /// Build one line per target plus the "add" entry.
+    fn build_rows(&self, width: usize) -> Vec<Line<'static>> {
+        let name_width = self
+            .state
+            .targets
+            .iter()
+            .map(|t| text::width(&t.name))
+            .max()
+            .unwrap_or(0)
+            .min(20);
+
+        let mut rows: Vec<Line<'static>> = Vec::new();
+        for (i, target) in self.state.targets.iter().enumerate() {
+            let selected = i == self.state.selected_index;
+            let usable = target.status.is_usable();
+
+            let mut style = if usable {
+                Style::default().fg(self.theme.status_fg)
+            } else {
+                Style::default()
+                    .fg(self.theme.dim_fg)
+                    .add_modifier(Modifier::DIM)
+            };
+            if selected {
+                style = style
+                    .bg(self.theme.tree_selected_bg)
+                    .add_modifier(Modifier::BOLD);
+            }
+
+            let name = text::truncate(&target.name, name_width);
+            let status = if usable {
+                String::new()
+            } else {
+                format!(" [{}]", target.status.label())
+            };
+            let path_room = width
+                .saturating_sub(name_width + 4)
+                .saturating_sub(status.chars().count());
+            let path = text::truncate(&target.path.to_string_lossy(), path_room);
+
+            rows.push(Line::from(vec![
+                Span::styled(
+                    format!(
+                        " {}{}  ",
+                        name,
+                        " ".repeat(name_width.saturating_sub(text::width(&name)))
+                    ),
+                    style.fg(if usable {
+                        self.theme.accent_fg
+                    } else {
+                        self.theme.dim_fg
+                    }),
+                ),
+                Span::styled(path, style),
+                Span::styled(status, style.fg(self.theme.warning_fg)),
+            ]));
+        }
+
+        let mut add_style = Style::default().fg(self.theme.info_fg);
+        if self.state.add_entry_selected() {
+            add_style = add_style
+                .bg(self.theme.tree_selected_bg)
+                .add_modifier(Modifier::BOLD);
+        }
+        rows.push(Line::from(Span::styled(
+            format!(" {}", ADD_ENTRY_LABEL),
+            add_style,
+        )));
+        rows
+    }
+}
+
+impl<'a> Widget for SendToWidget<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.height < 5 || area.width < 20 {
+            return;
+        }
+
+        let width = 64u16.min(area.width);
+        let inner_width = width.saturating_sub(4) as usize;
+        let rows = self.build_rows(inner_width);
+
+        // rows + blank + hint + borders
+        let height = (rows.len() as u16 + 4).min(area.height);
+        let x = area.x + area.width.saturating_sub(width) / 2;
+        let y = area.y + area.height.saturating_sub(height) / 2;
+        let rect = Rect::new(x, y, width, height);
+
+        Clear.render(rect, buf);
+
+        let block = Block::default()
+            .title(" Send to ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(self.theme.dialog_border_fg))
+            .style(Style::default().bg(self.theme.dialog_bg))
+            .padding(Padding::horizontal(1));
+        let inner = block.inner(rect);
+        block.render(rect, buf);
+
+        if inner.height < 2 {
+            return;
+        }
+
+        // Keep the selected row visible when the list is taller than the box.
+        let visible = inner.height.saturating_sub(2) as usize;
+        let start = self
+            .state
+            .selected_index
+            .saturating_sub(visible.saturating_sub(1));
+        for (i, line) in rows.iter().skip(start).take(visible).enumerate() {
+            buf.set_line(inner.x, inner.y + i as u16, line, inner.width);
+        }
+
+        let hint = Line::from(Span::styled(
+            "[Enter] copy  [m/Shift+Enter] move  [Esc] close",
+            Style::default()
+                .fg(self.theme.dim_fg)
+                .add_modifier(Modifier::DIM),
+        ));
+        buf.set_line(inner.x, inner.y + inner.height - 1, &hint, inner.width);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::send_to::{SendToTarget, TargetStatus};
+    use crate::theme;
+    use std::path::PathBuf;
+
+    fn buffer_to_string(buf: &Buffer, area: Rect) -> String {
+        let mut s = String::new();
+        for y in area.y..area.y + area.height {
+            for x in area.x..area.x + area.width {
+                s.push_str(buf.cell((x, y)).unwrap().symbol());
+            }
+            s.push('\n');
+        }
+        s
+    }
+
+    fn make_state() -> SendToState {
+        SendToState {
+            targets: vec![
+                SendToTarget {
+                    name: "backups".to_string(),
+                    path: PathBuf::from("/mnt/backup"),
+                    status: TargetStatus::Ready,
+                },
+                SendToTarget {
+                    name: "usb".to_string(),
+                    path: PathBuf::from("/media/usb"),
+                    status: TargetStatus::Missing,
+                },
+            ],
+            selected_index: 0,
+        }
+    }
+
+    #[test]
+    fn lists_targets_and_add_entry() {
+        let state = make_state();
+        let tc = theme::dark_theme();
+        let area = Rect::new(0, 0, 80, 20);
+        let mut buf = Buffer::empty(area);
+        SendToWidget::new(&state, &tc).render(area, &mut buf);
+        let text = buffer_to_string(&buf, area);
+        assert!(text.contains("Send to"));
+        assert!(text.contains("backups"));
+        assert!(text.contains("/mnt/backup"));
+        assert!(text.contains("[missing]"));
+        assert!(text.contains("Add current directory"));
+    }
+
+    #[test]
+    fn dead_target_is_dimmed() {
+        let state = make_state();
+        let tc = theme::dark_theme();
+        let widget = SendToWidget::new(&state, &tc);
+        let rows = widget.build_rows(60);
+        assert_eq!(rows.len(), 3);
+        let dead_style = rows[1].spans[1].style;
+        assert!(dead_style.add_modifier.contains(Modifier::DIM));
+        let live_style = rows[0].spans[1].style;
+        assert!(!live_style.add_modifier.contains(Modifier::DIM));
+    }
+
+    #[test]
+    fn tiny_area_does_not_panic() {
+        let state = make_state();
+        let tc = theme::dark_theme();
+        let area = Rect::new(0, 0, 10, 3);
+        let mut buf = Buffer::empty(area);
+        SendToWidget::new(&state, &tc).render(area, &mut buf);
+    }
+}
@@ -6,6 +6,8 @@ use ratatui::{
     widgets::Widget,
 };
 
+use unicode_width::UnicodeWidthStr;
+
 use crate::theme::ThemeColors;
 
 /// Status bar widget that displays file path, info, key hints, or status messages.
@@ -17,6 +19,69 @@ pub struct StatusBarWidget<'a> {
     is_error: bool,
     clipboard_info: Option<&'a str>,
     watcher_status: Option<&'a str>,
+    full_path: bool,
+    state: Option<&'a str>,
+    badge: Option<&'a str>,
+    cache: Option<&'a mut StatusBarCache>,
+}
+
+/// The bar drawn by the last render and the inputs it was drawn from. A
+/// render with the same inputs copies the line instead of formatting it
+/// again.
+#[derive(Debug, Default)]
+pub struct StatusBarCache {
+    inputs: Option<Inputs>,
+    line: Line<'static>,
+    /// Lines built so far; a render that reuses the cache adds none.
+    pub built: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Inputs {
+    path: String,
+    file_info: String,
+    status_message: Option<String>,
+    is_error: bool,
+    clipboard_info: Option<String>,
+    watcher_status: Option<String>,
+    full_path: bool,
+    state: Option<String>,
+    badge: Option<String>,
+    width: usize,
+    theme: ThemeColors,
+}
+
+impl Inputs {
+    fn of(widget: &StatusBarWidget<'_>, width: usize) -> Self {
+        Self {
+            path: widget.path_str.to_string(),
+            file_info: widget.file_info.to_string(),
+            status_message: widget.status_message.map(str::to_string),
+            is_error: widget.is_error,
+            clipboard_info: widget.clipboard_info.map(str::to_string),
+            watcher_status: widget.watcher_status.map(str::to_string),
+            full_path: widget.full_path,
+            state: widget.state.map(str::to_string),
+            badge: widget.badge.map(str::to_string),
+            width,
+            theme: widget.theme.clone(),
+        }
+    }
+
+    /// Same as [`Inputs::of`] would give, compared without allocating.
+    fn matches(&self, widget: &StatusBarWidget<'_>, width: usize) -> bool {
+        self.path == widget.path_str
+            && self.file_info == widget.file_info
+            && self.status_message.as_deref() == widget.status_message
+            && self.is_error == widget.is_error
+            && self.clipboard_info.as_deref() == widget.clipboard_info
+            && self.watcher_status.as_deref() == widget.watcher_status
+            && self.full_path == widget.full_path
+            && self.state.as_deref() == widget.state
+            && self.badge.as_deref() == widget.badge
+            && self.width == width
+            && self.theme == *widget.theme
+    }
 }
 
 impl<'a> StatusBarWidget<'a> {
@@ -29,9 +94,19 @@ impl<'a> StatusBarWidget<'a> {
             is_error: false,
             clipboard_info: None,
             watcher_status: None,
+            full_path: false,
+            state: None,
+            badge: None,
+            cache: None,
         }
     }
 
+    /// Reuse the line from, and store the line in, `cache`.
+    pub fn cache(mut self, cache: &'a mut StatusBarCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
     pub fn status_message(mut self, msg: &'a str, is_error: bool) -> Self {
         self.status_message = Some(msg);
         self.is_error = is_error;
@@ -47,15 +122,58 @@ impl<'a> StatusBarWidget<'a> {
         self.watcher_status = Some(status);
         self
     }
+
+    /// Give the path the room of the file info and key hints, for rows
+    /// the tree had to shorten.
+    pub fn full_path(mut self) -> Self {
+        self.full_path = true;
+        self
+    }
+
+    /// Lead with the focused panel and mode ("TREE · NORMAL"), shown even
+    /// while a status message is up.
+    pub fn state(mut self, state: &'a str) -> Self {
+        self.state = Some(state);
+        self
+    }
+
+    /// Warning badge after the state label ("SAFE MODE"), shown even while
+    /// a status message is up.
+    pub fn badge(mut self, badge: &'a str) -> Self {
+        self.badge = Some(badge);
+        self
+    }
 }
 
-impl<'a> Widget for StatusBarWidget<'a> {
-    fn render(self, area: Rect, buf: &mut Buffer) {
-        if area.height == 0 || area.width == 0 {
-            return;
+impl StatusBarWidget<'_> {
+    /// The whole bar as one line `width` columns wide.
+    fn build_line(&self, width: usize) -> Line<'static> {
+        let mut spans: Vec<Span<'static>> = Vec::new();
+        let mut width = width;
+        if let Some(state) = self.state {
+            let label = format!("{} ", state);
+            width -= label.width().min(width);
+            let style = Style::default()
+                .fg(self.theme.accent_fg)
+                .add_modifier(Modifier::BOLD);
+            spans.push(Span::styled(label, style));
+        }
+        if let Some(badge) = self.badge {
+            let label = format!(" {} ", badge);
+            width -= label.width().min(width);
+            let style = Style::default()
+                .bg(self.theme.warning_fg)
+                .fg(self.theme.status_bg)
+                .add_modifier(Modifier::BOLD);
+            spans.push(Span::styled(label, style));
+            if width > 0 {
+                width -= 1;
+                spans.push(Span::raw(" "));
+            }
+        }
+        if width == 0 {
+            return Line::from(spans);
         }
-
-        let width = area.width as usize;
 
         if let Some(msg) = self.status_message {
             let style = if self.is_error {
@@ -72,21 +190,24 @@ impl<'a> Widget for StatusBarWidget<'a> {
             } else {
                 format!("{:<width$}", msg, width = width)
             };
-
-            let line = Line::from(Span::styled(display, style));
-            buf.set_line(area.x, area.y, &line, area.width);
-            return;
+            spans.push(Span::styled(display, style));
+            return Line::from(spans);
         }
 
         // Normal bar: [path] [file_info] [key_hints]
-        let key_hints = " a:new  A:dir  r:ren  d:del ";
+        let key_hints = if self.full_path {
+            ""
+        } else {
+            " a:new  A:dir  r:ren  d:del "
+        };
         let hints_len = key_hints.len();
+        let file_info = if self.full_path { "" } else { self.file_info };
 
         // Reserve space for hints on the right
         let remaining = width.saturating_sub(hints_len);
 
         // Split remaining between path (left) and file_info (center-right)
-        let info_len = self.file_info.len();
+        let info_len = file_info.len();
         let path_budget = remaining.saturating_sub(info_len).saturating_sub(1); // 1 for separator space
 
         let path_display = if self.path_str.len() > path_budget {
@@ -102,15 +223,15 @@ impl<'a> Widget for StatusBarWidget<'a> {
             self.path_str.to_string()
         };
 
-        let info_display = if self.file_info.len() > remaining.saturating_sub(path_display.len()) {
+        let info_display = if file_info.len() > remaining.saturating_sub(path_display.len()) {
             let budget = remaining.saturating_sub(path_display.len());
             if budget > 0 {
-                self.file_info[..budget].to_string()
+                file_info[..budget].to_string()
             } else {
                 String::new()
             }
         } else {
-            self.file_info.to_string()
+            file_info.to_string()
         };
 
         // Calculate gap between path and info to push info toward center-right
@@ -124,11 +245,12 @@ impl<'a> Widget for StatusBarWidget<'a> {
             .fg(self.theme.dim_fg)
             .add_modifier(Modifier::DIM);
 
-        let mut spans = vec![
+        let lead = spans.len();
+        spans.extend([
             Span::styled(path_display, path_style),
             Span::raw(" ".repeat(gap)),
             Span::styled(info_display, info_style),
-        ];
+        ]);
 
         // Add clipboard info if present
         let clipboard_display = self.clipboard_info.unwrap_or("");
@@ -151,15 +273,40 @@ impl<'a> Widget for StatusBarWidget<'a> {
         }
 
         // Pad to fill remaining width if needed, then add hints
-        let used: usize = spans.iter().map(|s| s.content.len()).sum();
+        let used: usize = spans[lead..].iter().map(|s| s.content.len()).sum();
         let pad = width.saturating_sub(used).saturating_sub(hints_len);
         if pad > 0 {
             spans.push(Span::raw(" ".repeat(pad)));
         }
         spans.push(Span::styled(key_hints, hints_style));
 
-        let line = Line::from(spans);
-        buf.set_line(area.x, area.y, &line, area.width);
+        Line::from(spans)
+    }
+}
+
+impl<'a> Widget for StatusBarWidget<'a> {
+    fn render(mut self, area: Rect, buf: &mut Buffer) {
+        if area.height == 0 || area.width == 0 {
+            return;
+        }
+        let width = area.width as usize;
+        match self.cache.take() {
+            Some(cache) => {
+                if !cache
+                    .inputs
+                    .as_ref()
+                    .is_some_and(|inputs| inputs.matches(&self, width))
+                {
+                    cache.line = self.build_line(width);
+                    cache.inputs = Some(Inputs::of(&self, width));
+                    cache.built += 1;
+                }
+                buf.set_line(area.x, area.y, &cache.line, area.width);
+            }
+            None => {
+                buf.set_line(area.x, area.y, &self.build_line(width), area.width);
+            }
+        }
     }
 }
 
@@ -183,6 +330,23 @@ mod tests {
         assert!(!widget.is_error);
     }
 
+    #[test]
+    fn test_full_path_drops_info_and_hints() {
+        let tc = test_theme();
+        let path = "/home/user/projects/app/src/deeply/nested/module/with/a/long/name.rs";
+        let widget = StatusBarWidget::new(path, "File", &tc).full_path();
+
+        let area = Rect::new(0, 0, 80, 1);
+        let mut buf = Buffer::empty(area);
+        widget.render(area, &mut buf);
+
+        let content: String = (0..80)
+            .map(|x| buf.cell((x, 0)).unwrap().symbol().to_string())
+            .collect();
+        assert!(content.contains(path));
+        assert!(!content.contains("a:new"));
+    }
+
     #[test]
     fn test_status_message_success() {
         let tc = test_theme();
@@ -265,4 +429,51 @@ mod tests {
             .collect();
         assert!(content.contains("2 items"));
     }
+
+    #[test]
+    fn test_cache_reused_until_inputs_change() {
+        let tc = test_theme();
+        let area = Rect::new(0, 0, 80, 1);
+        let mut cache = StatusBarCache::default();
+        let render = |message: Option<&str>, cache: &mut StatusBarCache| {
+            let mut widget = StatusBarWidget::new("/path", "info", &tc).state("TREE · NORMAL");
+            if let Some(msg) = message {
+                widget = widget.status_message(msg, false);
+            }
+            let mut buf = Buffer::empty(area);
+            widget.cache(cache).render(area, &mut buf);
+            (0..80)
+                .map(|x| buf.cell((x, 0)).unwrap().symbol().to_string())
+                .collect::<String>()
+        };
+
+        let first = render(None, &mut cache);
+        assert_eq!(render(None, &mut cache), first);
+        assert_eq!(cache.built, 1);
+        assert!(first.starts_with("TREE · NORMAL /path"));
+
+        let shown = render(Some("Copied"), &mut cache);
+        assert_eq!(cache.built, 2);
+        assert!(shown.contains("Copied"));
+        render(Some("Copied"), &mut cache);
+        assert_eq!(cache.built, 2);
+    }
+
+    #[test]
+    fn test_badge_stays_with_a_status_message() {
+        let tc = test_theme();
+        let area = Rect::new(0, 0, 80, 1);
+        let widget = StatusBarWidget::new("/path", "info", &tc)
+            .state("TREE · NORMAL")
+            .badge("SAFE MODE")
+            .status_message("Copied", false);
+        let mut buf = Buffer::empty(area);
+        widget.render(area, &mut buf);
+        let content: String = (0..80)
+            .map(|x| buf.cell((x, 0)).unwrap().symbol().to_string())
+            .collect();
+        assert!(content.starts_with("TREE · NORMAL  SAFE MODE  Copied"));
+        let badge = buf.cell((15, 0)).unwrap();
+        assert_eq!(badge.bg, tc.warning_fg);
+    }
 }
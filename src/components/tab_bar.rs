@@ -0,0 +1,69 @@
+//! Tab bar above the panels, shown while more than one tab is open.
+//!
+//! Each tab is its number and root directory name; the active one is
+//! bracketed as well as highlighted, so it reads without colour too. Names
+//! shrink to share the width between the tabs.
+
+use ratatui::{
+    style::{Modifier, Style},
+    text::{Line, Span},
+};
+
+use crate::text;
+use crate::theme::ThemeColors;
+
+/// Columns a root name may take when there is room.
+const MAX_NAME_WIDTH: usize = 24;
+
+/// The bar for tabs rooted at `names`, with tab `active` marked, in
+/// `width` columns.
+pub fn tab_bar(names: &[&str], active: usize, width: usize, theme: &ThemeColors) -> Line<'static> {
+    let active_style = Style::default()
+        .fg(theme.tree_selected_fg)
+        .bg(theme.tree_selected_bg)
+        .add_modifier(Modifier::BOLD);
+    let inactive_style = Style::default().fg(theme.dim_fg);
+
+    let share = width / names.len().max(1);
+    let spans = names
+        .iter()
+        .enumerate()
+        .flat_map(|(i, name)| {
+            let number = (i + 1).to_string();
+            // `[`, the space after the number, `]` and the gap before the next
+            let room = share.saturating_sub(number.len() + 4).max(1);
+            let name = text::truncate(name, room.min(MAX_NAME_WIDTH));
+            let (label, style) = if i == active {
+                (format!("[{} {}]", number, name), active_style)
+            } else {
+                (format!(" {} {} ", number, name), inactive_style)
+            };
+            [Span::styled(label, style), Span::raw(" ")]
+        })
+        .collect::<Vec<_>>();
+    Line::from(spans)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plain(line: &Line) -> String {
+        line.spans.iter().map(|s| s.content.as_ref()).collect()
+    }
+
+    #[test]
+    fn marks_the_active_tab_and_shrinks_names_to_fit() {
+        let theme = crate::theme::dark_theme();
+        let line = tab_bar(&["src", "docs"], 1, 80, &theme);
+        assert_eq!(plain(&line), " 1 src  [2 docs] ");
+        assert!(line.spans[2].style.add_modifier.contains(Modifier::BOLD));
+        assert_eq!(line.spans[0].style.fg, Some(theme.dim_fg));
+
+        let long = "a_very_long_directory_name_indeed";
+        let line = tab_bar(&[long, long], 0, 30, &theme);
+        assert_eq!(plain(&line), "[1 a_very_lo…]  2 a_very_lo…  ");
+        // Never fewer than one column for the name
+        assert!(plain(&tab_bar(&[long], 0, 2, &theme)).starts_with("[1 …]"));
+    }
+}
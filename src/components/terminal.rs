@@ -83,8 +83,21 @@ impl<'a> Widget for TerminalWidget<'a> {
             }
         }
 
-        // Render cursor if focused
-        if self.show_cursor {
+        // Underline path references; Ctrl+Click or Alt+G follows them
+        for (row, link) in self.state.links() {
+            if row >= inner.height as usize {
+                break;
+            }
+            let y = inner.y + row as u16;
+            for col in link.start..link.end.min(inner.width as usize) {
+                if let Some(cell) = buf.cell_mut((inner.x + col as u16, y)) {
+                    cell.set_style(Style::default().add_modifier(Modifier::UNDERLINED));
+                }
+            }
+        }
+
+        // The cursor sits in live output, off screen while scrolled back
+        if self.show_cursor && self.state.scroll_offset == 0 {
             let cursor_y = inner.y + cursor_row as u16;
             let cursor_x = inner.x + cursor_col as u16;
             if cursor_x < inner.x + inner.width && cursor_y < inner.y + inner.height {
@@ -101,6 +114,31 @@ impl<'a> Widget for TerminalWidget<'a> {
                 }
             }
         }
+
+        if self.state.scroll_offset > 0 && self.state.new_lines > 0 {
+            self.render_new_lines_badge(inner, buf);
+        }
+    }
+}
+
+impl<'a> TerminalWidget<'a> {
+    /// "↓ N new lines" at the bottom-right while output arrives below a
+    /// scrolled-back view.
+    fn render_new_lines_badge(&self, inner: Rect, buf: &mut Buffer) {
+        let count = self.state.new_lines;
+        let badge = format!(
+            " ↓ {} new line{} ",
+            count,
+            if count == 1 { "" } else { "s" }
+        );
+        let width = (badge.chars().count() as u16).min(inner.width);
+        let x = inner.x + inner.width - width;
+        let y = inner.y + inner.height - 1;
+        let style = Style::default()
+            .fg(Color::Black)
+            .bg(self.theme.accent_fg)
+            .add_modifier(Modifier::BOLD);
+        buf.set_stringn(x, y, &badge, width as usize, style);
     }
 }
 
@@ -111,6 +149,7 @@ mod tests {
     use crate::theme;
 
     #[test]
+    #[cfg(feature = "embedded-terminal")]
     fn test_terminal_widget_renders() {
         let mut state = TerminalState::default();
         state.emulator.process(b"Hello World");
@@ -132,6 +171,62 @@ mod tests {
         assert_eq!(content, "Hello World");
     }
 
+    #[test]
+    #[cfg(feature = "embedded-terminal")]
+    fn test_terminal_widget_new_lines_badge() {
+        let mut state = TerminalState::default();
+        state.emulator.resize(4, 30);
+        state.process(b"1\r\n2\r\n3\r\n4\r\n5");
+        state.scroll_up(1);
+        state.process(b"\r\n6\r\n7\r\n8");
+        let theme = theme::dark_theme();
+
+        let area = Rect::new(0, 0, 30, 4);
+        let mut buf = Buffer::empty(area);
+        TerminalWidget::new(&state, &theme, true).render(area, &mut buf);
+        let bottom: String = (0..30)
+            .map(|x| {
+                buf.cell((x, 3))
+                    .map(|c| c.symbol().to_string())
+                    .unwrap_or_default()
+            })
+            .collect();
+        assert!(bottom.ends_with(" ↓ 3 new lines "), "{bottom:?}");
+
+        state.jump_to_bottom();
+        let mut buf = Buffer::empty(area);
+        TerminalWidget::new(&state, &theme, true).render(area, &mut buf);
+        let bottom: String = (0..30)
+            .map(|x| {
+                buf.cell((x, 3))
+                    .map(|c| c.symbol().to_string())
+                    .unwrap_or_default()
+            })
+            .collect();
+        assert!(!bottom.contains("new lines"));
+    }
+
+    #[test]
+    #[cfg(feature = "embedded-terminal")]
+    fn test_terminal_widget_underlines_path_references() {
+        let mut state = TerminalState::default();
+        state.process(b"error --> src/app.rs:12:5");
+        let theme = theme::dark_theme();
+
+        let area = Rect::new(0, 0, 40, 3);
+        let mut buf = Buffer::empty(area);
+        TerminalWidget::new(&state, &theme, false).render(area, &mut buf);
+        let underlined: Vec<bool> = (0..30)
+            .map(|x| {
+                buf.cell((x, 0))
+                    .is_some_and(|c| c.modifier.contains(Modifier::UNDERLINED))
+            })
+            .collect();
+        assert!(underlined[10..25].iter().all(|u| *u));
+        assert!(!underlined[..10].iter().any(|u| *u));
+        assert!(!underlined[25..].iter().any(|u| *u));
+    }
+
     #[test]
     fn test_terminal_widget_exited() {
         let mut state = TerminalState::default();
@@ -6,7 +6,15 @@ use ratatui::{
     widgets::{Block, Widget},
 };
 
-use crate::fs::tree::{FlatItem, NodeType, TreeState};
+use std::time::Instant;
+
+use crate::config::DEFAULT_MIN_NAME_WIDTH;
+use crate::dir_size::DirSizes;
+use crate::flash::{FlashMap, FlashStep};
+use crate::fs::clipboard::{ClipboardOp, ClipboardState};
+use crate::fs::tree::{FlatItem, NodeType, SpecialFile, TreeState};
+use crate::git_status::{GitStatus, GitStatuses};
+use crate::text;
 use crate::theme::ThemeColors;
 
 /// Tree widget that renders the file tree with box-drawing characters.
@@ -15,6 +23,58 @@ pub struct TreeWidget<'a> {
     theme: &'a ThemeColors,
     use_icons: bool,
     block: Option<Block<'a>>,
+    flash: Option<&'a FlashMap>,
+    clipboard: Option<&'a ClipboardState>,
+    dir_sizes: Option<&'a DirSizes>,
+    apparent_sizes: bool,
+    git_status: Option<&'a GitStatuses>,
+    min_name_width: usize,
+    cache: Option<&'a mut TreeRowCache>,
+}
+
+/// Rows drawn by the last render and what they were drawn from.
+///
+/// A render with the same inputs (tree generation, scroll, selection,
+/// clipboard, directory sizes, git status, size, theme and options) copies the cached rows into the buffer instead
+/// of rebuilding them. Rows are never cached while a change highlight is
+/// fading, since their style changes every frame.
+#[derive(Debug, Default)]
+pub struct TreeRowCache {
+    key: Option<RowKey>,
+    lines: Vec<Line<'static>>,
+    /// Per cached row: drawn with hidden indent levels or a shortened name.
+    shortened: Vec<bool>,
+    /// Flat index of the first cached row.
+    first: usize,
+    /// Rows built so far; a render that reuses the cache adds none.
+    pub built: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RowKey {
+    generation: u64,
+    clipboard: u64,
+    dir_sizes: u64,
+    apparent_sizes: bool,
+    git_status: u64,
+    scroll: usize,
+    selected: usize,
+    width: usize,
+    height: usize,
+    use_icons: bool,
+    min_name_width: usize,
+    theme: ThemeColors,
+}
+
+impl TreeRowCache {
+    /// Whether the row at `idx` was drawn with hidden indent levels or a
+    /// shortened name by the last render.
+    pub fn is_shortened(&self, idx: usize) -> bool {
+        idx.checked_sub(self.first)
+            .and_then(|row| self.shortened.get(row))
+            .copied()
+            .unwrap_or(false)
+    }
 }
 
 impl<'a> TreeWidget<'a> {
@@ -24,27 +84,119 @@ impl<'a> TreeWidget<'a> {
             theme,
             use_icons,
             block: None,
+            flash: None,
+            clipboard: None,
+            dir_sizes: None,
+            apparent_sizes: false,
+            git_status: None,
+            min_name_width: DEFAULT_MIN_NAME_WIDTH,
+            cache: None,
         }
     }
 
+    /// Reuse rows from, and store rows in, `cache`.
+    pub fn cache(mut self, cache: &'a mut TreeRowCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
     pub fn block(mut self, block: Block<'a>) -> Self {
         self.block = block.into();
         self
     }
 
-    /// Build the prefix string for tree indentation using box-drawing characters.
-    ///
-    /// We need to know the ancestor chain to draw continuation lines correctly.
-    fn build_prefix(item: &FlatItem, items: &[FlatItem], item_index: usize) -> String {
-        if item.depth == 0 {
-            return String::new();
-        }
+    /// Highlight rows changed by the last refresh.
+    pub fn flash(mut self, flash: &'a FlashMap) -> Self {
+        self.flash = Some(flash);
+        self
+    }
+
+    /// Mark the rows whose paths are on the clipboard.
+    pub fn clipboard(mut self, clipboard: &'a ClipboardState) -> Self {
+        self.clipboard = Some(clipboard);
+        self
+    }
+
+    /// Show the computed sizes of directories after their names, counting
+    /// every hard link when `apparent`.
+    pub fn dir_sizes(mut self, dir_sizes: &'a DirSizes, apparent: bool) -> Self {
+        self.dir_sizes = Some(dir_sizes);
+        self.apparent_sizes = apparent;
+        self
+    }
+
+    /// Color entries with a git status and mark them after the name.
+    pub fn git_status(mut self, git_status: &'a GitStatuses) -> Self {
+        self.git_status = Some(git_status);
+        self
+    }
 
-        // Build prefix from left to right for each depth level
-        let mut parts: Vec<&str> = Vec::new();
+    /// Filename columns to keep visible by compacting deep indentation.
+    pub fn min_name_width(mut self, width: usize) -> Self {
+        self.min_name_width = width;
+        self
+    }
+
+    /// Row style, in order of precedence: selection, multi-selection,
+    /// change highlight, hidden, then node type.
+    fn row_style(
+        &self,
+        item: &FlatItem,
+        is_selected: bool,
+        is_multi_selected: bool,
+        flash: Option<FlashStep>,
+    ) -> Style {
+        if is_selected {
+            return Style::default()
+                .bg(self.theme.tree_selected_bg)
+                .fg(self.theme.tree_selected_fg)
+                .add_modifier(Modifier::BOLD);
+        }
+        if is_multi_selected {
+            return Style::default()
+                .bg(self.theme.accent_fg)
+                .fg(self.theme.warning_fg)
+                .add_modifier(Modifier::BOLD);
+        }
+        match flash {
+            Some(FlashStep::Strong) => {
+                return Style::default()
+                    .fg(self.theme.success_fg)
+                    .add_modifier(Modifier::BOLD | Modifier::REVERSED);
+            }
+            Some(FlashStep::Medium) => {
+                return Style::default()
+                    .fg(self.theme.success_fg)
+                    .add_modifier(Modifier::BOLD);
+            }
+            Some(FlashStep::Faint) => return Style::default().fg(self.theme.success_fg),
+            None => {}
+        }
+        if item.is_hidden || item.is_ignored {
+            return Style::default().fg(self.theme.tree_hidden_fg);
+        }
+        match item.node_type {
+            NodeType::Directory => Style::default()
+                .fg(self.theme.tree_dir_fg)
+                .add_modifier(Modifier::BOLD),
+            NodeType::Symlink if item.link.as_ref().is_some_and(|link| link.broken) => {
+                Style::default().fg(self.theme.tree_broken_link_fg)
+            }
+            NodeType::Symlink => Style::default().fg(self.theme.tree_symlink_fg),
+            NodeType::File if item.special.is_some() => {
+                Style::default().fg(self.theme.tree_special_fg)
+            }
+            NodeType::File => Style::default().fg(self.theme.tree_file_fg),
+            NodeType::LoadMore => Style::default()
+                .fg(self.theme.info_fg)
+                .add_modifier(Modifier::ITALIC),
+        }
+    }
 
-        // For each ancestor level (1..depth), determine if it's the last sibling at that level
-        // We need to look backwards through ancestors to figure this out
+    /// Whether each ancestor level (1..depth) of the row is the last sibling
+    /// at that level, which decides its continuation column.
+    fn ancestors_last(item: &FlatItem, items: &[FlatItem], item_index: usize) -> Vec<bool> {
+        let mut ancestors_last = Vec::with_capacity(item.depth);
         for d in 1..item.depth {
             // Find the ancestor at depth d that contains this item
             let mut ancestor_is_last = false;
@@ -58,31 +210,109 @@ impl<'a> TreeWidget<'a> {
                     break;
                 }
             }
-            if ancestor_is_last {
-                parts.push("   ");
-            } else {
-                parts.push("│  ");
-            }
+            ancestors_last.push(ancestor_is_last);
         }
+        ancestors_last
+    }
+
+    /// Clipboard operation the row's path is on the clipboard for.
+    fn clipboard_mark(&self, item: &FlatItem) -> Option<ClipboardOp> {
+        self.clipboard
+            .and_then(|clipboard| clipboard.mark(&item.path))
+    }
 
-        // The connector for this item
-        if item.is_last_sibling {
-            parts.push("└──");
+    /// Multi-selection and clipboard markers and indicator drawn between
+    /// prefix and name.
+    fn decoration(&self, item: &FlatItem, idx: usize) -> String {
+        let marker = if self.tree_state.multi_selected.contains(&idx) {
+            "● "
         } else {
-            parts.push("├──");
-        }
+            ""
+        };
+        let clip = match self.clipboard_mark(item) {
+            Some(ClipboardOp::Cut) => "✂ ",
+            Some(ClipboardOp::Copy) => "📋 ",
+            None => "",
+        };
+        format!("{}{}{}", marker, clip, self.item_indicator(item))
+    }
+
+    /// Indent levels to hide from each visible row, by flat index.
+    ///
+    /// Every row that needs room gets [`levels_to_skip`]; the viewport then
+    /// hides the largest of those counts from every row deep enough, so the
+    /// connector columns left visible line up from row to row. Shallower
+    /// rows keep their own (possibly zero) count.
+    fn skipped_levels(&self, width: usize, height: usize) -> Vec<(usize, usize)> {
+        let items = &self.tree_state.flat_items;
+        let rows: Vec<(usize, usize, usize)> = items
+            .iter()
+            .enumerate()
+            .skip(self.tree_state.scroll_offset)
+            .take(height)
+            .map(|(idx, item)| {
+                let ancestors = item.depth.saturating_sub(1);
+                let own = if item.depth == 0 {
+                    0
+                } else {
+                    let fixed = LEVEL_WIDTH + text::width(&self.decoration(item, idx));
+                    let wanted = self.min_name_width.min(text::width(&item.name));
+                    levels_to_skip(ancestors, fixed, width, wanted)
+                };
+                (idx, ancestors, own)
+            })
+            .collect();
+        let viewport = rows.iter().map(|&(_, _, own)| own).max().unwrap_or(0);
+        rows.into_iter()
+            .map(|(idx, ancestors, own)| {
+                let skip = if ancestors >= viewport { viewport } else { own };
+                (idx, skip)
+            })
+            .collect()
+    }
+
+    /// Lead (prefix and decoration) and name of a row, fitted to `width`.
+    fn row_text(&self, idx: usize, skip: usize, width: usize) -> (String, String) {
+        let items = &self.tree_state.flat_items;
+        let item = &items[idx];
+        let prefix = if item.depth == 0 {
+            String::new()
+        } else {
+            let ancestors_last = Self::ancestors_last(item, items, idx);
+            let marker = if skip > 0 { SKIPPED_LEVELS_MARKER } else { "" };
+            format!(
+                "{}{}",
+                marker,
+                connector_prefix(&ancestors_last[skip..], item.is_last_sibling)
+            )
+        };
+        let lead = format!("{}{}", prefix, self.decoration(item, idx));
+        let name_room = width.saturating_sub(text::width(&lead));
+        let name = text::truncate_middle(&item.name, name_room);
+        (lead, name)
+    }
 
-        parts.join("")
+    fn git_color(&self, status: GitStatus) -> ratatui::style::Color {
+        match status {
+            GitStatus::Modified => self.theme.warning_fg,
+            GitStatus::Added => self.theme.success_fg,
+            GitStatus::Untracked => self.theme.accent_fg,
+            GitStatus::Deleted => self.theme.error_fg,
+        }
     }
 
     /// Get the directory/file indicator.
     fn item_indicator(&self, item: &FlatItem) -> &'static str {
+        if let Some(special) = item.special {
+            return Self::special_indicator(special, self.use_icons);
+        }
         if self.use_icons {
             match item.node_type {
                 NodeType::Directory if item.is_expanded => " ",
                 NodeType::Directory => " ",
                 NodeType::Symlink => " ",
                 NodeType::File => Self::file_icon_by_ext(&item.name),
+                NodeType::LoadMore if item.is_depth_limit() => "⊘ ",
                 NodeType::LoadMore => "▼ ",
             }
         } else {
@@ -91,11 +321,27 @@ impl<'a> TreeWidget<'a> {
                 NodeType::Directory => "[D] ",
                 NodeType::Symlink => "[L] ",
                 NodeType::File => "[F] ",
+                NodeType::LoadMore if item.is_depth_limit() => "[-] ",
                 NodeType::LoadMore => "[+] ",
             }
         }
     }
 
+    /// Icon for a FIFO, socket or device node; without icons, the letters
+    /// `ls -l` uses for them.
+    fn special_indicator(special: SpecialFile, use_icons: bool) -> &'static str {
+        match (special, use_icons) {
+            (SpecialFile::Fifo, true) => "󰟥 ",
+            (SpecialFile::Socket, true) => "󰌘 ",
+            (SpecialFile::BlockDevice, true) => "󰋊 ",
+            (SpecialFile::CharDevice, true) => " ",
+            (SpecialFile::Fifo, false) => "[p] ",
+            (SpecialFile::Socket, false) => "[s] ",
+            (SpecialFile::BlockDevice, false) => "[b] ",
+            (SpecialFile::CharDevice, false) => "[c] ",
+        }
+    }
+
     /// Get a Nerd Font icon for a file based on its extension.
     fn file_icon_by_ext(name: &str) -> &'static str {
         let ext = name.rsplit('.').next().unwrap_or("").to_lowercase();
@@ -136,8 +382,128 @@ impl<'a> TreeWidget<'a> {
     }
 }
 
+/// Marker standing in for indent levels hidden to make room for a name.
+pub const SKIPPED_LEVELS_MARKER: &str = "⋯ ";
+
+/// Columns one ancestor level takes in the prefix.
+const LEVEL_WIDTH: usize = 3;
+
+/// Shallowest ancestor levels to hide from a row so `wanted` filename
+/// columns fit in `width`.
+///
+/// `ancestors` is the row's depth minus one and `fixed` the columns of
+/// everything else before the name (connector, marker, icon). The hidden
+/// levels are replaced by [`SKIPPED_LEVELS_MARKER`], so hiding `k` of them
+/// saves `3k - 2` columns. Returns `ancestors` when even hiding all of them
+/// is not enough.
+pub fn levels_to_skip(ancestors: usize, fixed: usize, width: usize, wanted: usize) -> usize {
+    let needed = ancestors * LEVEL_WIDTH + fixed + wanted;
+    if needed <= width {
+        return 0;
+    }
+    let overflow = needed - width;
+    let marker = text::width(SKIPPED_LEVELS_MARKER);
+    (overflow + marker).div_ceil(LEVEL_WIDTH).min(ancestors)
+}
+
+/// Box-drawing prefix for a row below the root: one continuation column per
+/// ancestor (`ancestors_last` holds whether each ancestor from depth 1 down
+/// is the last sibling), then the row's own connector.
+///
+/// Shared by the tree panel and the text export.
+pub fn connector_prefix(ancestors_last: &[bool], is_last: bool) -> String {
+    let mut prefix = String::with_capacity((ancestors_last.len() + 1) * 9);
+    for &ancestor_is_last in ancestors_last {
+        prefix.push_str(if ancestor_is_last { "   " } else { "│  " });
+    }
+    prefix.push_str(if is_last { "└──" } else { "├──" });
+    prefix
+}
+
+impl TreeWidget<'_> {
+    /// Lines for the visible rows, and whether each is shortened.
+    fn build_rows(&self, width: usize, height: usize) -> (Vec<Line<'static>>, Vec<bool>) {
+        let items = &self.tree_state.flat_items;
+        let selected = self.tree_state.selected_index;
+        let now = Instant::now();
+
+        let skipped = self.skipped_levels(width, height);
+        let mut lines = Vec::with_capacity(skipped.len());
+        let mut shortened = Vec::with_capacity(skipped.len());
+        for (idx, skip) in skipped {
+            let item = &items[idx];
+            let is_selected = idx == selected;
+            let is_multi_selected = self.tree_state.multi_selected.contains(&idx);
+
+            let flash_step = self.flash.and_then(|flash| flash.step_for(&item.path, now));
+            let mut style = self.row_style(item, is_selected, is_multi_selected, flash_step);
+            // Items waiting to be moved fade out; the selection stays legible
+            if self.clipboard_mark(item) == Some(ClipboardOp::Cut) {
+                style = style.add_modifier(Modifier::ITALIC);
+                if !is_selected {
+                    style = style.add_modifier(Modifier::DIM);
+                }
+            }
+
+            // Git status colors the name unless the row is highlighted
+            let git = self.git_status.and_then(|git| git.get(&item.path));
+            let plain = !is_selected && !is_multi_selected && flash_step.is_none();
+            if let Some(status) = git.filter(|_| plain) {
+                style = style.fg(self.git_color(status));
+            }
+
+            let (lead, name) = self.row_text(idx, skip, width);
+            shortened.push(skip > 0 || name != item.name);
+            let line_content = format!("{}{}", lead, name);
+
+            // Build multi-span line: name, a symlink's target, and a badge
+            // with the item count of collapsed dirs and the computed size
+            // of any dir
+            let name_span = Span::styled(line_content, style);
+            let mut spans = vec![name_span];
+            if let Some(status) = git {
+                let marker_style = if is_selected {
+                    style
+                } else {
+                    Style::default().fg(self.git_color(status))
+                };
+                spans.push(Span::styled(format!(" {}", status.marker()), marker_style));
+            }
+            let dim = if is_selected {
+                style
+            } else {
+                Style::default().fg(self.theme.tree_hidden_fg)
+            };
+            if let Some(link) = &item.link {
+                let broken = if link.broken { " (broken)" } else { "" };
+                spans.push(Span::styled(
+                    format!(" → {}{}", link.target.display(), broken),
+                    dim,
+                ));
+            }
+            let mut badge: Vec<String> = Vec::new();
+            if item.is_expandable() {
+                if let Some(count) = item
+                    .child_count
+                    .filter(|_| !item.is_expanded && !is_selected)
+                {
+                    badge.push(format!("{} items", count));
+                }
+                if let Some(size) = self.dir_sizes.and_then(|sizes| sizes.get(&item.path)) {
+                    badge.push(size.label(self.apparent_sizes));
+                }
+            }
+            if !badge.is_empty() {
+                spans.push(Span::styled(format!(" ({})", badge.join(", ")), dim));
+            }
+            lines.push(Line::from(spans));
+        }
+        (lines, shortened)
+    }
+}
+
 impl<'a> Widget for TreeWidget<'a> {
-    fn render(self, area: Rect, buf: &mut Buffer) {
+    fn render(mut self, area: Rect, buf: &mut Buffer) {
         let inner_area = if let Some(block) = &self.block {
             let inner = block.inner(area);
             block.clone().render(area, buf);
@@ -147,75 +513,338 @@ impl<'a> Widget for TreeWidget<'a> {
         };
 
         let items = &self.tree_state.flat_items;
-        let selected = self.tree_state.selected_index;
         let visible_height = inner_area.height as usize;
 
         if items.is_empty() || visible_height == 0 {
             return;
         }
 
-        // Compute scroll offset to keep selected item visible
+        let width = inner_area.width as usize;
         let scroll = self.tree_state.scroll_offset;
+        let built;
+        let lines = match self.cache.take() {
+            Some(cache) => {
+                let key = RowKey {
+                    generation: self.tree_state.generation,
+                    clipboard: self.clipboard.map_or(0, ClipboardState::generation),
+                    dir_sizes: self.dir_sizes.map_or(0, |sizes| sizes.generation),
+                    apparent_sizes: self.apparent_sizes,
+                    git_status: self.git_status.map_or(0, |git| git.generation),
+                    scroll,
+                    selected: self.tree_state.selected_index,
+                    width,
+                    height: visible_height,
+                    use_icons: self.use_icons,
+                    min_name_width: self.min_name_width,
+                    theme: self.theme.clone(),
+                };
+                let fading = self.flash.is_some_and(|flash| !flash.is_empty());
+                if fading || cache.key.as_ref() != Some(&key) {
+                    let (lines, shortened) = self.build_rows(width, visible_height);
+                    cache.built += lines.len();
+                    cache.lines = lines;
+                    cache.shortened = shortened;
+                    cache.first = scroll;
+                    cache.key = if fading { None } else { Some(key) };
+                }
+                &cache.lines
+            }
+            None => {
+                built = self.build_rows(width, visible_height).0;
+                &built
+            }
+        };
 
-        let visible_items = items.iter().enumerate().skip(scroll).take(visible_height);
-
-        for (i, (idx, item)) in visible_items.enumerate() {
+        for (i, line) in lines.iter().enumerate() {
             let y = inner_area.y + i as u16;
             if y >= inner_area.y + inner_area.height {
                 break;
             }
+            buf.set_line(inner_area.x, y, line, inner_area.width);
+        }
+    }
+}
 
-            let prefix = Self::build_prefix(item, items, idx);
-            let indicator = self.item_indicator(item);
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-            let is_selected = idx == selected;
-            let is_multi_selected = self.tree_state.multi_selected.contains(&idx);
+    /// Connector plus a two-column icon.
+    const FIXED: usize = 5;
 
-            let style = if is_selected {
-                Style::default()
-                    .bg(self.theme.tree_selected_bg)
-                    .fg(self.theme.tree_selected_fg)
-                    .add_modifier(Modifier::BOLD)
-            } else if is_multi_selected {
-                Style::default()
-                    .bg(self.theme.accent_fg)
-                    .fg(self.theme.warning_fg)
-                    .add_modifier(Modifier::BOLD)
-            } else if item.is_hidden {
-                Style::default().fg(self.theme.tree_hidden_fg)
-            } else {
-                match item.node_type {
-                    NodeType::Directory => Style::default()
-                        .fg(self.theme.tree_dir_fg)
-                        .add_modifier(Modifier::BOLD),
-                    NodeType::Symlink => Style::default().fg(self.theme.info_fg),
-                    NodeType::File => Style::default().fg(self.theme.tree_file_fg),
-                    NodeType::LoadMore => Style::default()
-                        .fg(self.theme.info_fg)
-                        .add_modifier(Modifier::ITALIC),
+    #[test]
+    fn shallow_rows_are_not_compacted() {
+        assert_eq!(levels_to_skip(0, FIXED, 40, 12), 0);
+        assert_eq!(levels_to_skip(5, FIXED, 40, 12), 0);
+        // Exactly fits: 5 * 3 + 5 + 12 = 32
+        assert_eq!(levels_to_skip(5, FIXED, 32, 12), 0);
+    }
+
+    #[test]
+    fn compaction_hides_just_enough_levels() {
+        for width in [20, 24, 30, 40, 60] {
+            for ancestors in 0..30 {
+                let skip = levels_to_skip(ancestors, FIXED, width, 12);
+                let marker = if skip > 0 { 2 } else { 0 };
+                let used = (ancestors - skip) * LEVEL_WIDTH + marker + FIXED;
+                if skip < ancestors {
+                    assert!(
+                        used + 12 <= width,
+                        "width {width}, {ancestors} levels: name squeezed"
+                    );
                 }
-            };
+                if skip > 0 {
+                    // One level fewer would not have been enough
+                    let fewer = if skip > 1 { 2 } else { 0 };
+                    let used = (ancestors - skip + 1) * LEVEL_WIDTH + fewer + FIXED;
+                    assert!(used + 12 > width, "width {width}, {ancestors} levels");
+                }
+            }
+        }
+    }
 
-            let marker = if is_multi_selected { "● " } else { "" };
-            let line_content = format!("{}{}{}{}", prefix, marker, indicator, item.name);
+    #[test]
+    fn narrow_panels_hide_every_level() {
+        assert_eq!(levels_to_skip(10, FIXED, 15, 12), 10);
+        assert_eq!(levels_to_skip(3, FIXED, 0, 12), 3);
+    }
 
-            // Build multi-span line: name + optional count badge for collapsed dirs
-            let name_span = Span::styled(line_content, style);
-            let line = if item.node_type == NodeType::Directory && !item.is_expanded && !is_selected
-            {
-                if let Some(count) = item.child_count {
-                    let badge = format!(" ({} items)", count);
-                    let badge_style = Style::default().fg(self.theme.tree_hidden_fg);
-                    Line::from(vec![name_span, Span::styled(badge, badge_style)])
-                } else {
-                    Line::from(name_span)
-                }
-            } else {
-                Line::from(name_span)
-            };
+    #[test]
+    fn short_names_need_less_room() {
+        // 10 * 3 + 5 + 12 = 47 columns
+        assert_eq!(levels_to_skip(10, FIXED, 47, 12), 0);
+        // 7 short: hiding 3 levels saves 9 - 2
+        assert_eq!(levels_to_skip(10, FIXED, 40, 12), 3);
+        assert_eq!(levels_to_skip(10, FIXED, 40, 4), 0);
+    }
+
+    fn render_cached(state: &TreeState, flash: &FlashMap, cache: &mut TreeRowCache) {
+        render_with_clipboard(state, flash, &ClipboardState::new(), cache);
+    }
+
+    fn render_with_clipboard(
+        state: &TreeState,
+        flash: &FlashMap,
+        clipboard: &ClipboardState,
+        cache: &mut TreeRowCache,
+    ) -> Buffer {
+        let theme = crate::theme::dark_theme();
+        let area = Rect::new(0, 0, 40, 10);
+        let mut buf = Buffer::empty(area);
+        TreeWidget::new(state, &theme, false)
+            .flash(flash)
+            .clipboard(clipboard)
+            .cache(cache)
+            .render(area, &mut buf);
+        buf
+    }
+
+    fn row(buf: &Buffer, y: u16) -> String {
+        (0..buf.area.width)
+            .map(|x| buf[(x, y)].symbol())
+            .collect::<String>()
+            .trim_end()
+            .to_string()
+    }
+
+    fn sample_state() -> (tempfile::TempDir, TreeState) {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(dir.path().join("alpha")).unwrap();
+        std::fs::write(dir.path().join("a.txt"), "a").unwrap();
+        std::fs::write(dir.path().join("b.txt"), "b").unwrap();
+        let state = TreeState::new(dir.path()).unwrap();
+        (dir, state)
+    }
+
+    #[test]
+    fn unchanged_frames_reuse_rows() {
+        let (_dir, state) = sample_state();
+        let flash = FlashMap::default();
+        let mut cache = TreeRowCache::default();
+        render_cached(&state, &flash, &mut cache);
+        let first = cache.built;
+        assert_eq!(first, state.flat_items.len());
+        render_cached(&state, &flash, &mut cache);
+        render_cached(&state, &flash, &mut cache);
+        assert_eq!(cache.built, first);
+    }
+
+    #[test]
+    fn changes_rebuild_rows() {
+        let (_dir, mut state) = sample_state();
+        let mut flash = FlashMap::default();
+        let mut cache = TreeRowCache::default();
+        render_cached(&state, &flash, &mut cache);
+        let mut built = cache.built;
+
+        state.selected_index = 1;
+        render_cached(&state, &flash, &mut cache);
+        assert!(cache.built > built, "selection change");
+        built = cache.built;
+
+        state.toggle_multi_select();
+        render_cached(&state, &flash, &mut cache);
+        assert!(cache.built > built, "multi-select change");
+        built = cache.built;
+
+        // A fading highlight rebuilds every frame
+        flash.mark(state.flat_items[1].path.clone(), Instant::now());
+        render_cached(&state, &flash, &mut cache);
+        assert!(cache.built > built);
+        built = cache.built;
+        render_cached(&state, &flash, &mut cache);
+        assert!(cache.built > built, "fading highlight");
+    }
+
+    #[test]
+    fn clipboard_items_are_marked() {
+        let (_dir, state) = sample_state();
+        // Rows: root, alpha/, a.txt, b.txt; the root stays selected
+        let flash = FlashMap::default();
+        let mut clipboard = ClipboardState::new();
+        let mut cache = TreeRowCache::default();
 
-            let line_area = Rect::new(inner_area.x, y, inner_area.width, 1);
-            buf.set_line(line_area.x, line_area.y, &line, line_area.width);
+        let buf = render_with_clipboard(&state, &flash, &clipboard, &mut cache);
+        assert!(row(&buf, 2).ends_with("[F] a.txt"));
+        assert!(!buf[(4, 2)].modifier.contains(Modifier::DIM));
+
+        clipboard.set(vec![state.flat_items[2].path.clone()], ClipboardOp::Cut);
+        let buf = render_with_clipboard(&state, &flash, &clipboard, &mut cache);
+        assert!(row(&buf, 2).ends_with("✂ [F] a.txt"), "{}", row(&buf, 2));
+        let cell = &buf[(4, 2)];
+        assert!(cell.modifier.contains(Modifier::DIM | Modifier::ITALIC));
+        assert!(!row(&buf, 3).contains('✂'));
+        assert!(!buf[(4, 3)].modifier.contains(Modifier::DIM));
+
+        clipboard.set(vec![state.flat_items[3].path.clone()], ClipboardOp::Copy);
+        let buf = render_with_clipboard(&state, &flash, &clipboard, &mut cache);
+        // The wide marker's second cell is blank in the buffer
+        assert!(row(&buf, 3).contains('📋'), "{}", row(&buf, 3));
+        assert!(row(&buf, 3).ends_with(" [F] b.txt"));
+        assert!(!buf[(4, 3)].modifier.contains(Modifier::DIM));
+        assert!(!row(&buf, 2).contains('✂'));
+
+        clipboard.clear();
+        let buf = render_with_clipboard(&state, &flash, &clipboard, &mut cache);
+        assert!(row(&buf, 3).ends_with("[F] b.txt"));
+        assert!(!row(&buf, 3).contains('📋'));
+    }
+
+    #[test]
+    fn git_status_colors_and_marks_names() {
+        let (dir, state) = sample_state();
+        // Rows: root, alpha/, a.txt, b.txt; the root stays selected
+        let mut git = crate::git_status::GitStatusState::new(true);
+        assert!(git.start(dir.path(), Instant::now()));
+        git.finish(crate::git_status::GitStatusResult {
+            root: dir.path().to_path_buf(),
+            statuses: Some(crate::git_status::parse_porcelain(
+                b" M a.txt\0?? alpha/\0",
+                "",
+                dir.path(),
+            )),
+        });
+        let theme = crate::theme::dark_theme();
+        let area = Rect::new(0, 0, 40, 10);
+        let mut cache = TreeRowCache::default();
+        let mut buf = Buffer::empty(area);
+        TreeWidget::new(&state, &theme, false)
+            .git_status(&git.statuses)
+            .cache(&mut cache)
+            .render(area, &mut buf);
+
+        assert!(row(&buf, 1).ends_with("[D] alpha ?"), "{}", row(&buf, 1));
+        assert!(row(&buf, 2).ends_with("[F] a.txt M"));
+        assert!(row(&buf, 3).ends_with("[F] b.txt"));
+        assert_eq!(buf[(7, 2)].fg, theme.warning_fg);
+        assert_eq!(buf[(13, 2)].fg, theme.warning_fg);
+        assert_eq!(buf[(7, 1)].fg, theme.accent_fg);
+        assert_eq!(buf[(7, 3)].fg, theme.tree_file_fg);
+    }
+
+    #[test]
+    fn computed_sizes_follow_directory_names() {
+        let (_dir, state) = sample_state();
+        let alpha = state.flat_items[1].path.clone();
+        let theme = crate::theme::dark_theme();
+        let area = Rect::new(0, 0, 40, 10);
+        let mut cache = TreeRowCache::default();
+        let mut sizes = DirSizes::default();
+        let render = |sizes: &DirSizes, cache: &mut TreeRowCache| {
+            let mut buf = Buffer::empty(area);
+            TreeWidget::new(&state, &theme, false)
+                .dir_sizes(sizes, false)
+                .cache(cache)
+                .render(area, &mut buf);
+            buf
+        };
+
+        let buf = render(&sizes, &mut cache);
+        assert!(!row(&buf, 1).contains(" B"), "{}", row(&buf, 1));
+
+        let job = sizes.next_job();
+        let cancel = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+        sizes.start(alpha.clone(), job, cancel);
+        let size = crate::dir_size::DirSize {
+            bytes: 2048,
+            unique_bytes: 2048,
+            files: 2,
+            dirs: 0,
+            progress: crate::dir_size::Progress::Done,
+        };
+        sizes.update(&alpha, job, size);
+        let buf = render(&sizes, &mut cache);
+        assert!(row(&buf, 1).ends_with("2.00 KB)"), "{}", row(&buf, 1));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_show_their_target_and_broken_ones_stand_out() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(dir.path().join("a.txt"), "a").unwrap();
+        std::os::unix::fs::symlink("a.txt", dir.path().join("b")).unwrap();
+        std::os::unix::fs::symlink("gone", dir.path().join("c")).unwrap();
+        let state = TreeState::new(dir.path()).unwrap();
+        let theme = crate::theme::dark_theme();
+        let area = Rect::new(0, 0, 40, 10);
+        let mut buf = Buffer::empty(area);
+        TreeWidget::new(&state, &theme, false).render(area, &mut buf);
+
+        // Rows: root, a.txt, b, c
+        assert!(row(&buf, 2).ends_with("[L] b → a.txt"), "{}", row(&buf, 2));
+        assert!(row(&buf, 3).ends_with("[L] c → gone (broken)"));
+        assert_eq!(buf[(7, 2)].fg, theme.tree_symlink_fg);
+        assert_eq!(buf[(7, 3)].fg, theme.tree_broken_link_fg);
+        assert_eq!(buf[(9, 3)].fg, theme.tree_hidden_fg);
+        assert_ne!(theme.tree_symlink_fg, theme.tree_broken_link_fg);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn special_files_get_their_own_marker_and_color() {
+        let dir = tempfile::TempDir::new().unwrap();
+        if !crate::fs::tree::mkfifo(&dir.path().join("pipe")) {
+            return;
         }
+        std::fs::write(dir.path().join("plain"), "x").unwrap();
+        let state = TreeState::new(dir.path()).unwrap();
+        let mut cache = TreeRowCache::default();
+        let buf = render_with_clipboard(
+            &state,
+            &FlashMap::default(),
+            &ClipboardState::new(),
+            &mut cache,
+        );
+
+        // Rows: root, pipe, plain
+        assert!(row(&buf, 1).ends_with("[p] pipe"), "{}", row(&buf, 1));
+        assert!(row(&buf, 2).ends_with("[F] plain"));
+        let name_at = |y: u16, name: &str| {
+            let chars: Vec<char> = row(&buf, y).chars().collect();
+            (chars.len() - name.chars().count()) as u16
+        };
+        let theme = crate::theme::dark_theme();
+        assert_eq!(buf[(name_at(1, "pipe"), 1)].fg, theme.tree_special_fg);
+        assert_ne!(buf[(name_at(2, "plain"), 2)].fg, theme.tree_special_fg);
     }
 }
@@ -0,0 +1,122 @@
+//! Auto-refresh state in the tree panel title.
+//!
+//! Pausing the watcher used to show only a passing status message, so a
+//! tree left stale for days had nothing on screen to explain it. The tree
+//! title now ends in a glyph for the watcher's state (see
+//! [`WatcherState`]), and a paused watcher also dims the title. `W` or a
+//! click on the glyph says what it means and when the tree last refreshed.
+
+use ratatui::{
+    style::{Modifier, Style},
+    text::{Line, Span},
+};
+
+use crate::fs::watcher::WatcherState;
+use crate::text;
+use crate::theme::ThemeColors;
+
+/// The tree panel title and where its glyph sits.
+pub struct TreeTitle {
+    pub line: Line<'static>,
+    /// Columns from the start of the title to the glyph.
+    pub glyph_offset: u16,
+    /// Columns the glyph takes.
+    pub glyph_width: u16,
+}
+
+/// Title ` {label} {glyph} ` for the watcher in `state`.
+pub fn tree_title(label: &str, state: WatcherState, theme: &ThemeColors) -> TreeTitle {
+    let lead = format!(" {} ", label);
+    let glyph = state.glyph();
+    let glyph_color = match state {
+        WatcherState::Live => theme.success_fg,
+        WatcherState::Paused => theme.dim_fg,
+        WatcherState::Flooded => theme.warning_fg,
+        WatcherState::Unavailable => theme.error_fg,
+    };
+    let glyph_offset = text::width(&lead) as u16;
+    let mut line = Line::from(vec![
+        Span::raw(lead),
+        Span::styled(glyph, Style::default().fg(glyph_color)),
+        Span::raw(" "),
+    ]);
+    if state == WatcherState::Paused {
+        line = line.style(Style::default().add_modifier(Modifier::DIM));
+    }
+    TreeTitle {
+        line,
+        glyph_offset,
+        glyph_width: text::width(glyph) as u16,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::{
+        buffer::Buffer,
+        layout::Rect,
+        widgets::{Block, Borders, Widget},
+    };
+
+    fn render(state: WatcherState) -> (Buffer, TreeTitle) {
+        let theme = crate::theme::dark_theme();
+        let title = tree_title("[Tree] project", state, &theme);
+        let area = Rect::new(0, 0, 40, 3);
+        let mut buf = Buffer::empty(area);
+        Block::default()
+            .borders(Borders::ALL)
+            .title(title.line.clone())
+            .render(area, &mut buf);
+        (buf, title)
+    }
+
+    fn title_text(buf: &Buffer) -> String {
+        (0..buf.area.width)
+            .map(|x| buf.cell((x, 0)).unwrap().symbol())
+            .collect()
+    }
+
+    #[test]
+    fn each_state_has_its_glyph() {
+        for (state, glyph) in [
+            (WatcherState::Live, "●"),
+            (WatcherState::Paused, "⏸"),
+            (WatcherState::Flooded, "⚠"),
+            (WatcherState::Unavailable, "✕"),
+        ] {
+            let (buf, title) = render(state);
+            assert!(title_text(&buf).contains(" [Tree] project "));
+            // The title starts after the corner
+            let cell = buf.cell((1 + title.glyph_offset, 0)).unwrap();
+            assert_eq!(cell.symbol(), glyph, "{:?}", state);
+        }
+    }
+
+    #[test]
+    fn only_paused_dims_the_title() {
+        let dimmed = |state| {
+            let (buf, _) = render(state);
+            buf.cell((2, 0)).unwrap().modifier.contains(Modifier::DIM)
+        };
+        assert!(dimmed(WatcherState::Paused));
+        assert!(!dimmed(WatcherState::Live));
+        assert!(!dimmed(WatcherState::Flooded));
+        assert!(!dimmed(WatcherState::Unavailable));
+    }
+
+    #[test]
+    fn glyph_colour_follows_the_state() {
+        let theme = crate::theme::dark_theme();
+        let (buf, title) = render(WatcherState::Unavailable);
+        assert_eq!(
+            buf.cell((1 + title.glyph_offset, 0)).unwrap().fg,
+            theme.error_fg
+        );
+        let (buf, title) = render(WatcherState::Live);
+        assert_eq!(
+            buf.cell((1 + title.glyph_offset, 0)).unwrap().fg,
+            theme.success_fg
+        );
+    }
+}
@@ -7,10 +7,17 @@
 //! 4. Global `~/.config/fm-tui/config.toml`
 //! 5. Built-in defaults
 
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use serde::Deserialize;
 
+use crate::bounded_map::MapCapacities;
+use crate::fs::filter_class::{self, FilterClass, Matcher};
+use crate::key_repeat::RepeatSettings;
+use crate::stat_service::StatMode;
+
 // ── Section configs ──────────────────────────────────────────────────────────
 
 /// General application settings.
@@ -23,6 +30,12 @@ pub struct GeneralConfig {
     pub show_hidden: Option<bool>,
     /// Confirm before delete operations.
     pub confirm_delete: Option<bool>,
+    /// Move deleted items to the system trash instead of removing them
+    /// (default: false). Shift+D still deletes permanently.
+    pub use_trash: Option<bool>,
+    /// Keep a file a paste overwrites as `<name>.fm-bak-<time>`, or in the
+    /// trash with `use_trash`, so undo can put it back (default: false).
+    pub backup_on_overwrite: Option<bool>,
     /// Enable mouse support.
     pub mouse: Option<bool>,
     /// Maximum entries to load per page when expanding a directory (default: 1000).
@@ -33,6 +46,42 @@ pub struct GeneralConfig {
     /// Maximum entries in a DirSnapshot (default: 500000).
     /// Limits memory usage for very large directories. Clamped to 10000..5000000.
     pub snapshot_max_entries: Option<u32>,
+    /// Also copy paths to the PRIMARY (middle-click) selection on Linux
+    /// (default: false).
+    pub primary_selection: Option<bool>,
+    /// Also send copies to the terminal as an OSC 52 escape, which reaches
+    /// the local clipboard over SSH (default: true).
+    pub osc52: Option<bool>,
+    /// Seconds of activity between session autosaves (default: 30, 0
+    /// disables autosave and crash recovery).
+    pub autosave_secs: Option<u64>,
+    /// Vim-style count prefixes (`5j`) in the tree and preview (default:
+    /// true). Turn off to free the digit keys.
+    pub count_prefix: Option<bool>,
+    /// Count the entries under directories before a paste or delete, to
+    /// size the confirmation and the progress bar (default: true).
+    pub preflight_count: Option<bool>,
+    /// Entry count at which a paste or delete needs an extra confirmation
+    /// (default: 50000).
+    pub large_op_entries: Option<usize>,
+    /// Total size in bytes at which a paste or delete needs an extra
+    /// confirmation (default: 10 GiB).
+    pub large_op_bytes: Option<u64>,
+    /// Keep the outer terminal's window title on the selected path.
+    pub set_title: Option<bool>,
+    /// Window title with `{path}`, `{root}` and `{mode}` placeholders.
+    pub title_template: Option<String>,
+    /// Largest file the content search (`Ctrl+F`) reads, in bytes
+    /// (default: 2 MiB).
+    pub grep_max_file_bytes: Option<u64>,
+    /// Matching lines at which the content search stops (default: 5000).
+    pub grep_max_results: Option<usize>,
+    /// Command that opens a file with its default application, the path
+    /// appended (default: `xdg-open`, `open` on macOS, `start` on Windows).
+    pub opener: Option<String>,
+    /// How paths are shown: "relative" (to the tree root, default),
+    /// "absolute", or "home-tilde".
+    pub path_display: Option<String>,
 }
 
 /// Preview panel settings.
@@ -55,6 +104,23 @@ pub struct PreviewConfig {
     pub syntax_theme: Option<String>,
     /// Whether the preview panel is enabled.
     pub enabled: Option<bool>,
+    /// Memory budget (bytes) for cached preview content (default: 32 MiB).
+    pub cache_max_bytes: Option<u64>,
+    /// Widest preview text area in columns; extra width goes to the tree or
+    /// centers the panel (0 = no cap).
+    pub max_content_width: Option<u16>,
+    /// `[` / `]` wrap around at the first / last file of a directory.
+    pub wrap_file_navigation: Option<bool>,
+    /// Load previews of the neighbouring files in the background while
+    /// idle (turn off for slow disks or network mounts).
+    pub prefetch: Option<bool>,
+    /// Drawn at the start of each wrapped continuation row ("" = none).
+    pub wrap_marker: Option<String>,
+    /// Indent wrapped continuation rows to their line's leading whitespace.
+    pub wrap_hang_indent: Option<bool>,
+    /// Cancel a preview load still running after this many seconds
+    /// (0 = never).
+    pub load_timeout_secs: Option<u64>,
 }
 
 /// Tree panel settings.
@@ -67,6 +133,50 @@ pub struct TreeConfig {
     pub dirs_first: Option<bool>,
     /// Use nerd font icons (false = ASCII fallback).
     pub use_icons: Option<bool>,
+    /// Widest the tree panel gets in columns, borders included (0 = no cap).
+    pub max_width: Option<u16>,
+    /// Deepest level shown below the root; deeper entries are replaced by a
+    /// "depth limit reached" row (default: 128).
+    pub max_depth: Option<usize>,
+    /// Filename columns kept visible in deep rows, by compacting the
+    /// indentation when needed (default: 12).
+    pub min_name_width: Option<usize>,
+    /// Stat directory entries in the background instead of while loading:
+    /// "auto" (network mounts only), "always" or "never" (default: "auto").
+    pub deferred_stats: Option<String>,
+    /// Sum a directory's size in the background once it stays selected
+    /// (default: true). `z` sizes the selected directory either way.
+    pub dir_sizes: Option<bool>,
+    /// Directory sizes count a hard-linked file once per link rather than
+    /// once (default: false). The preview shows both when they differ.
+    pub apparent_sizes: Option<bool>,
+    /// Directory sizes skip mount points below the directory (default:
+    /// true).
+    pub one_file_system: Option<bool>,
+    /// Mark files git reports as modified, added, untracked or deleted
+    /// when the root is inside a repository (default: true).
+    pub git_status: Option<bool>,
+    /// Hide what `.gitignore` leaves out from the tree, the filter and the
+    /// fuzzy finder; shown dimmed with hidden files (default: true). `I`
+    /// toggles it.
+    pub respect_gitignore: Option<bool>,
+}
+
+/// File classes for the tree filter chips. Extension lists replace the
+/// built-in ones (given without the dot; `tar.gz` style entries match the
+/// end of the name).
+#[derive(Debug, Clone, Deserialize, Default)]
+#[serde(default)]
+pub struct FilterConfig {
+    pub code: Option<Vec<String>>,
+    pub docs: Option<Vec<String>>,
+    pub images: Option<Vec<String>>,
+    pub archives: Option<Vec<String>>,
+    /// Hours back the Today class reaches (default: 24).
+    pub today_hours: Option<u64>,
+    /// The fuzzy finder keeps only files of the active class (default:
+    /// false).
+    pub in_finder: Option<bool>,
 }
 
 /// Filesystem watcher settings.
@@ -77,6 +187,22 @@ pub struct WatcherConfig {
     pub enabled: Option<bool>,
     /// Debounce interval in milliseconds.
     pub debounce_ms: Option<u64>,
+    /// Briefly highlight rows that changed after a refresh (default: true).
+    pub highlight_changes: Option<bool>,
+    /// Idle time in milliseconds before stale paginated directories are
+    /// refreshed in the background (0 disables).
+    pub idle_refresh_ms: Option<u64>,
+    /// Max stale directories refreshed per idle cycle.
+    pub idle_refresh_batch: Option<usize>,
+    /// Hold back refreshes under the terminal's working directory while it
+    /// prints a build's worth of output (default: true).
+    pub defer_during_builds: Option<bool>,
+    /// Seconds a changed path stays reachable with the "next changed" key.
+    pub changed_window_secs: Option<u64>,
+    /// Watch the whole tree recursively (default: true). Off, only the
+    /// root and expanded directories are watched, which takes far fewer
+    /// inotify watches on large trees.
+    pub recursive: Option<bool>,
 }
 
 /// Embedded terminal settings.
@@ -102,6 +228,9 @@ pub struct ThemeColorsConfig {
     pub tree_dir_fg: Option<String>,
     pub tree_file_fg: Option<String>,
     pub tree_hidden_fg: Option<String>,
+    pub tree_symlink_fg: Option<String>,
+    pub tree_broken_link_fg: Option<String>,
+    pub tree_special_fg: Option<String>,
     pub preview_bg: Option<String>,
     pub preview_fg: Option<String>,
     pub preview_line_nr_fg: Option<String>,
@@ -122,6 +251,94 @@ pub struct ThemeConfig {
     pub custom: Option<ThemeColorsConfig>,
 }
 
+/// Per-file-type action when pressing Enter on a file.
+///
+/// Keys other than `default` are patterns: a bare extension (`rs`, `.md`)
+/// or a file-name glob (`*.test.js`, `Makefile`). Values are one of
+/// `edit`, `preview_focus`, `external`, or `terminal:<command>`.
+#[derive(Debug, Clone, Deserialize, Default)]
+#[serde(default)]
+pub struct OpenConfig {
+    /// Action for files that match no rule (default: "preview_focus").
+    pub default: Option<String>,
+    /// Pattern → action rules.
+    #[serde(flatten)]
+    pub rules: BTreeMap<String, String>,
+}
+
+/// Accessibility configuration section.
+///
+/// Tunes how focus and state are spelled out in panel titles and the
+/// status bar, for screen readers and Braille displays that cannot see
+/// border colours.
+#[derive(Debug, Clone, Deserialize, Default)]
+#[serde(default)]
+pub struct AccessibilityConfig {
+    /// Text before the focused panel's name (default: "[").
+    pub focus_prefix: Option<String>,
+    /// Text after the focused panel's name (default: "]").
+    pub focus_suffix: Option<String>,
+    /// Separator between panel and mode in the status bar (default: " · ").
+    pub state_separator: Option<String>,
+    /// Show "TREE · NORMAL" at the start of the status bar (default: true).
+    pub announce_state: Option<bool>,
+}
+
+/// Editor configuration section.
+///
+/// Optional clean-ups applied to the buffer when it is saved. Both are
+/// recorded as one undo step, so a single undo brings the old text back.
+#[derive(Debug, Clone, Deserialize, Default)]
+#[serde(default)]
+pub struct EditorConfig {
+    /// Strip trailing spaces and tabs on save, except in Markdown
+    /// (default: false).
+    pub trim_trailing_whitespace: Option<bool>,
+    /// End the file with exactly one newline on save (default: false).
+    pub ensure_final_newline: Option<bool>,
+}
+
+/// Navigation configuration section.
+///
+/// Holding a navigation key (tree and preview `j`/`k`, terminal
+/// scrollback) moves faster the longer it is held; see
+/// [`key_repeat`](crate::key_repeat).
+#[derive(Debug, Clone, Deserialize, Default)]
+#[serde(default)]
+pub struct NavigationConfig {
+    /// Accelerate held navigation keys (default: true).
+    pub key_acceleration: Option<bool>,
+    /// Longest gap in milliseconds between events of a held key
+    /// (default: 100).
+    pub repeat_window_ms: Option<u64>,
+    /// Events of a held key that move one row before it speeds up
+    /// (default: 3).
+    pub accel_after: Option<usize>,
+    /// Most rows a single event moves (default: 8).
+    pub accel_max_step: Option<usize>,
+}
+
+/// Memory configuration section.
+///
+/// Caps on the small per-path maps kept for the life of the session. When
+/// one is full the least recently used entry is dropped; the debug overlay
+/// (F12) shows how many.
+#[derive(Debug, Clone, Deserialize, Default)]
+#[serde(default)]
+pub struct MemoryConfig {
+    /// Changed paths kept for highlights and the "next changed" key
+    /// (default: 256).
+    pub flash_entries: Option<usize>,
+    /// Directories whose sort override is remembered (default: 512).
+    pub sort_override_entries: Option<usize>,
+    /// Prefetched files tracked for the hit/miss counters (default: 64).
+    pub prefetch_entries: Option<usize>,
+    /// Computed directory sizes kept (default: 1024).
+    pub dir_size_entries: Option<usize>,
+    /// Files whose git HEAD the blame gutter remembers (default: 256).
+    pub blame_head_entries: Option<usize>,
+}
+
 // ── Top-level config ─────────────────────────────────────────────────────────
 
 /// Top-level application configuration.
@@ -134,9 +351,19 @@ pub struct AppConfig {
     pub general: GeneralConfig,
     pub preview: PreviewConfig,
     pub tree: TreeConfig,
+    pub filter: FilterConfig,
     pub watcher: WatcherConfig,
     pub terminal: TerminalConfig,
     pub theme: ThemeConfig,
+    /// "Send to" targets: name → absolute destination directory.
+    pub send_to: BTreeMap<String, String>,
+    /// Places overlay bookmarks: name → directory.
+    pub bookmarks: BTreeMap<String, String>,
+    pub open: OpenConfig,
+    pub accessibility: AccessibilityConfig,
+    pub editor: EditorConfig,
+    pub navigation: NavigationConfig,
+    pub memory: MemoryConfig,
 }
 
 // ── Default constants ────────────────────────────────────────────────────────
@@ -147,8 +374,32 @@ pub const DEFAULT_MAX_FULL_PREVIEW_BYTES: u64 = 1_048_576;
 pub const DEFAULT_HEAD_LINES: usize = 50;
 /// Default tail lines for large file preview.
 pub const DEFAULT_TAIL_LINES: usize = 20;
+/// Default tab stop width in preview and editor.
+pub const DEFAULT_TAB_WIDTH: usize = 4;
+/// Maximum allowed value for tab_width.
+pub const MAX_TAB_WIDTH: usize = 16;
+/// Default marker for wrapped continuation rows.
+pub const DEFAULT_WRAP_MARKER: &str = "↪ ";
+/// Default seconds before a stuck preview load is cancelled.
+pub const DEFAULT_PREVIEW_LOAD_TIMEOUT_SECS: u64 = 10;
+/// Default memory budget for the preview cache (32 MiB).
+pub const DEFAULT_PREVIEW_CACHE_MAX_BYTES: u64 = 32 * 1_048_576;
+/// Default window title template.
+pub const DEFAULT_TITLE_TEMPLATE: &str = "fm — {path}";
 /// Default debounce interval in milliseconds.
 pub const DEFAULT_DEBOUNCE_MS: u64 = 300;
+/// Default seconds of activity between session autosaves.
+pub const DEFAULT_AUTOSAVE_SECS: u64 = 30;
+/// Default entry count that makes a paste or delete "large".
+pub const DEFAULT_LARGE_OP_ENTRIES: usize = 50_000;
+/// Default total size that makes a paste or delete "large" (10 GiB).
+pub const DEFAULT_LARGE_OP_BYTES: u64 = 10 * 1_073_741_824;
+/// Default idle time before stale directories are refreshed.
+pub const DEFAULT_IDLE_REFRESH_MS: u64 = 2_000;
+/// Default number of stale directories refreshed per idle cycle.
+pub const DEFAULT_IDLE_REFRESH_BATCH: usize = 4;
+/// Default seconds a changed path stays a "next changed" jump target.
+pub const DEFAULT_CHANGED_WINDOW_SECS: u64 = 120;
 /// Default max entries per page for directory pagination.
 pub const DEFAULT_MAX_ENTRIES_PER_PAGE: u32 = 1_000;
 /// Minimum allowed value for max_entries_per_page.
@@ -157,15 +408,29 @@ pub const MIN_ENTRIES_PER_PAGE: u32 = 100;
 pub const MAX_ENTRIES_PER_PAGE: u32 = 50_000;
 /// Default max entries for deep search walk.
 pub const DEFAULT_SEARCH_MAX_ENTRIES: u32 = 10_000;
+/// Default largest file read by the content search.
+pub const DEFAULT_GREP_MAX_FILE_BYTES: u64 = 2 * 1024 * 1024;
+/// Default matching lines at which the content search stops.
+pub const DEFAULT_GREP_MAX_RESULTS: usize = 5000;
 /// Default max entries for DirSnapshot.
 pub const DEFAULT_SNAPSHOT_MAX_ENTRIES: u32 = 500_000;
 /// Minimum allowed value for snapshot_max_entries.
 pub const MIN_SNAPSHOT_MAX_ENTRIES: u32 = 10_000;
 /// Maximum allowed value for snapshot_max_entries.
 pub const MAX_SNAPSHOT_MAX_ENTRIES: u32 = 5_000_000;
+/// Default deepest tree level shown below the root.
+pub const DEFAULT_MAX_TREE_DEPTH: usize = 128;
+/// Default filename columns kept visible in deep tree rows.
+pub const DEFAULT_MIN_NAME_WIDTH: usize = 12;
 
 // ── Config file locator ──────────────────────────────────────────────────────
 
+/// fm's config directory, `~/.config/fm-tui`: the global config and the
+/// files fm writes itself (bookmarks).
+pub fn config_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("fm-tui"))
+}
+
 /// Return the list of candidate config file paths in priority order.
 ///
 /// Does NOT include the CLI `--config` path — that is handled separately.
@@ -183,33 +448,167 @@ fn candidate_paths() -> Vec<PathBuf> {
     }
 
     // 3. Global `~/.config/fm-tui/config.toml`
-    if let Some(config_dir) = dirs::config_dir() {
-        paths.push(config_dir.join("fm-tui").join("config.toml"));
+    if let Some(config_dir) = config_dir() {
+        paths.push(config_dir.join("config.toml"));
     }
 
     paths
 }
 
-/// Try to read and parse a TOML config file. Returns `None` if the file
-/// doesn't exist or can't be parsed (with a warning printed to stderr).
-fn load_file(path: &Path) -> Option<AppConfig> {
+/// Outcome of reading one config file.
+#[derive(Debug)]
+pub enum FileLoad {
+    /// The file does not exist (or cannot be read).
+    Missing,
+    Parsed(Box<AppConfig>),
+    /// TOML error, with the 1-based line/column it points at when known.
+    Invalid {
+        message: String,
+        line: Option<usize>,
+        column: Option<usize>,
+    },
+}
+
+/// Read and parse a TOML config file.
+pub fn read_file(path: &Path) -> FileLoad {
     let content = match std::fs::read_to_string(path) {
         Ok(c) => c,
-        Err(_) => return None,
+        Err(_) => return FileLoad::Missing,
     };
     match toml::from_str::<AppConfig>(&content) {
-        Ok(cfg) => Some(cfg),
+        Ok(cfg) => FileLoad::Parsed(Box::new(cfg)),
         Err(e) => {
+            let position = e.span().map(|span| line_column(&content, span.start));
+            FileLoad::Invalid {
+                message: e.message().to_string(),
+                line: position.map(|(line, _)| line),
+                column: position.map(|(_, column)| column),
+            }
+        }
+    }
+}
+
+/// 1-based line and column of byte `offset` in `text`.
+pub(crate) fn line_column(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset.min(text.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before
+        .rsplit('\n')
+        .next()
+        .map(|l| l.chars().count())
+        .unwrap_or(0)
+        + 1;
+    (line, column)
+}
+
+/// Every config file that [`AppConfig::load`] would consult, highest
+/// priority first, whether or not it exists.
+pub fn source_paths(cli_config_path: Option<&Path>) -> Vec<PathBuf> {
+    cli_config_path
+        .map(Path::to_path_buf)
+        .into_iter()
+        .chain(candidate_paths())
+        .collect()
+}
+
+/// Every config file that [`AppConfig::load`] would consult, highest
+/// priority first, with its parse status.
+pub fn file_report(cli_config_path: Option<&Path>) -> Vec<(PathBuf, FileLoad)> {
+    source_paths(cli_config_path)
+        .into_iter()
+        .map(|path| {
+            let load = read_file(&path);
+            (path, load)
+        })
+        .collect()
+}
+
+/// Try to read and parse a TOML config file. Returns `None` if the file
+/// doesn't exist or can't be parsed (with a warning printed to stderr).
+fn load_file(path: &Path) -> Option<AppConfig> {
+    match read_file(path) {
+        FileLoad::Parsed(cfg) => Some(*cfg),
+        FileLoad::Missing => None,
+        FileLoad::Invalid { message, line, .. } => {
+            let at = line.map(|l| format!(" (line {})", l)).unwrap_or_default();
             eprintln!(
-                "Warning: failed to parse config file {}: {}",
+                "Warning: failed to parse config file {}{}: {}",
                 path.display(),
-                e
+                at,
+                message
             );
             None
         }
     }
 }
 
+/// Config file that programmatic edits (e.g. new send-to targets) go to:
+/// the highest-priority existing candidate, else the global config path.
+pub fn writable_config_path() -> Option<PathBuf> {
+    let paths = candidate_paths();
+    paths
+        .iter()
+        .find(|p| p.is_file())
+        .cloned()
+        .or_else(|| paths.last().cloned())
+}
+
+/// Append a `name = "path"` entry to the `[send_to]` table of the TOML file
+/// at `config_path`, creating the file (and table) if needed.
+///
+/// Edits the text in place rather than re-serializing so existing comments
+/// and formatting are preserved.
+pub fn append_send_to_target(config_path: &Path, name: &str, target: &Path) -> std::io::Result<()> {
+    let existing = match std::fs::read_to_string(config_path) {
+        Ok(c) => c,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e),
+    };
+    let entry = format!(
+        "{} = {}",
+        toml::Value::String(name.to_string()),
+        toml::Value::String(target.to_string_lossy().to_string())
+    );
+
+    let mut lines: Vec<&str> = existing.lines().collect();
+    let updated = match lines.iter().position(|l| l.trim() == "[send_to]") {
+        Some(header) => {
+            // Insert after the last key line of the table (before the next header).
+            let end = lines[header + 1..]
+                .iter()
+                .position(|l| l.trim_start().starts_with('['))
+                .map(|i| header + 1 + i)
+                .unwrap_or(lines.len());
+            let mut insert_at = end;
+            while insert_at > header + 1 && lines[insert_at - 1].trim().is_empty() {
+                insert_at -= 1;
+            }
+            lines.insert(insert_at, &entry);
+            let mut out = lines.join("\n");
+            out.push('\n');
+            out
+        }
+        None => {
+            let mut out = existing.clone();
+            if !out.is_empty() && !out.ends_with('\n') {
+                out.push('\n');
+            }
+            if !out.is_empty() {
+                out.push('\n');
+            }
+            out.push_str("[send_to]\n");
+            out.push_str(&entry);
+            out.push('\n');
+            out
+        }
+    };
+
+    if let Some(parent) = config_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(config_path, updated)
+}
+
 // ── Merge logic ──────────────────────────────────────────────────────────────
 
 /// Merge helper: `base` provides defaults; `over` overrides `base`.
@@ -240,6 +639,47 @@ impl AppConfig {
                     .general
                     .snapshot_max_entries
                     .or(self.general.snapshot_max_entries),
+                primary_selection: other
+                    .general
+                    .primary_selection
+                    .or(self.general.primary_selection),
+                osc52: other.general.osc52.or(self.general.osc52),
+                autosave_secs: other.general.autosave_secs.or(self.general.autosave_secs),
+                count_prefix: other.general.count_prefix.or(self.general.count_prefix),
+                use_trash: other.general.use_trash.or(self.general.use_trash),
+                backup_on_overwrite: other
+                    .general
+                    .backup_on_overwrite
+                    .or(self.general.backup_on_overwrite),
+                preflight_count: other
+                    .general
+                    .preflight_count
+                    .or(self.general.preflight_count),
+                large_op_entries: other
+                    .general
+                    .large_op_entries
+                    .or(self.general.large_op_entries),
+                large_op_bytes: other.general.large_op_bytes.or(self.general.large_op_bytes),
+                set_title: other.general.set_title.or(self.general.set_title),
+                title_template: other
+                    .general
+                    .title_template
+                    .clone()
+                    .or(self.general.title_template),
+                grep_max_file_bytes: other
+                    .general
+                    .grep_max_file_bytes
+                    .or(self.general.grep_max_file_bytes),
+                grep_max_results: other
+                    .general
+                    .grep_max_results
+                    .or(self.general.grep_max_results),
+                opener: other.general.opener.clone().or(self.general.opener),
+                path_display: other
+                    .general
+                    .path_display
+                    .clone()
+                    .or(self.general.path_display),
             },
             preview: PreviewConfig {
                 max_full_preview_bytes: other
@@ -261,15 +701,75 @@ impl AppConfig {
                     .clone()
                     .or(self.preview.syntax_theme),
                 enabled: other.preview.enabled.or(self.preview.enabled),
+                cache_max_bytes: other
+                    .preview
+                    .cache_max_bytes
+                    .or(self.preview.cache_max_bytes),
+                max_content_width: other
+                    .preview
+                    .max_content_width
+                    .or(self.preview.max_content_width),
+                wrap_file_navigation: other
+                    .preview
+                    .wrap_file_navigation
+                    .or(self.preview.wrap_file_navigation),
+                prefetch: other.preview.prefetch.or(self.preview.prefetch),
+                wrap_marker: other
+                    .preview
+                    .wrap_marker
+                    .clone()
+                    .or(self.preview.wrap_marker),
+                wrap_hang_indent: other
+                    .preview
+                    .wrap_hang_indent
+                    .or(self.preview.wrap_hang_indent),
+                load_timeout_secs: other
+                    .preview
+                    .load_timeout_secs
+                    .or(self.preview.load_timeout_secs),
             },
             tree: TreeConfig {
                 sort_by: other.tree.sort_by.clone().or(self.tree.sort_by),
                 dirs_first: other.tree.dirs_first.or(self.tree.dirs_first),
                 use_icons: other.tree.use_icons.or(self.tree.use_icons),
+                max_width: other.tree.max_width.or(self.tree.max_width),
+                max_depth: other.tree.max_depth.or(self.tree.max_depth),
+                min_name_width: other.tree.min_name_width.or(self.tree.min_name_width),
+                deferred_stats: other
+                    .tree
+                    .deferred_stats
+                    .clone()
+                    .or(self.tree.deferred_stats),
+                dir_sizes: other.tree.dir_sizes.or(self.tree.dir_sizes),
+                apparent_sizes: other.tree.apparent_sizes.or(self.tree.apparent_sizes),
+                one_file_system: other.tree.one_file_system.or(self.tree.one_file_system),
+                git_status: other.tree.git_status.or(self.tree.git_status),
+                respect_gitignore: other.tree.respect_gitignore.or(self.tree.respect_gitignore),
             },
             watcher: WatcherConfig {
                 enabled: other.watcher.enabled.or(self.watcher.enabled),
                 debounce_ms: other.watcher.debounce_ms.or(self.watcher.debounce_ms),
+                highlight_changes: other
+                    .watcher
+                    .highlight_changes
+                    .or(self.watcher.highlight_changes),
+                idle_refresh_ms: other
+                    .watcher
+                    .idle_refresh_ms
+                    .or(self.watcher.idle_refresh_ms),
+                idle_refresh_batch: other
+                    .watcher
+                    .idle_refresh_batch
+                    .or(self.watcher.idle_refresh_batch),
+                defer_during_builds: other
+                    .watcher
+                    .defer_during_builds
+                    .or(self.watcher.defer_during_builds),
+                changed_window_secs: other
+                    .watcher
+                    .changed_window_secs
+                    .or(self.watcher.changed_window_secs),
+                recursive: other.watcher.recursive.or(self.watcher.recursive),
             },
             terminal: TerminalConfig {
                 enabled: other.terminal.enabled.or(self.terminal.enabled),
@@ -291,6 +791,97 @@ impl AppConfig {
                     (None, None) => None,
                 },
             },
+            send_to: {
+                let mut send_to = self.send_to;
+                send_to.extend(other.send_to.clone());
+                send_to
+            },
+            bookmarks: {
+                let mut bookmarks = self.bookmarks;
+                bookmarks.extend(other.bookmarks.clone());
+                bookmarks
+            },
+            open: OpenConfig {
+                default: other.open.default.clone().or(self.open.default),
+                rules: {
+                    let mut rules = self.open.rules;
+                    rules.extend(other.open.rules.clone());
+                    rules
+                },
+            },
+            accessibility: AccessibilityConfig {
+                focus_prefix: other
+                    .accessibility
+                    .focus_prefix
+                    .clone()
+                    .or(self.accessibility.focus_prefix),
+                focus_suffix: other
+                    .accessibility
+                    .focus_suffix
+                    .clone()
+                    .or(self.accessibility.focus_suffix),
+                state_separator: other
+                    .accessibility
+                    .state_separator
+                    .clone()
+                    .or(self.accessibility.state_separator),
+                announce_state: other
+                    .accessibility
+                    .announce_state
+                    .or(self.accessibility.announce_state),
+            },
+            filter: FilterConfig {
+                code: other.filter.code.clone().or(self.filter.code),
+                docs: other.filter.docs.clone().or(self.filter.docs),
+                images: other.filter.images.clone().or(self.filter.images),
+                archives: other.filter.archives.clone().or(self.filter.archives),
+                today_hours: other.filter.today_hours.or(self.filter.today_hours),
+                in_finder: other.filter.in_finder.or(self.filter.in_finder),
+            },
+            editor: EditorConfig {
+                trim_trailing_whitespace: other
+                    .editor
+                    .trim_trailing_whitespace
+                    .or(self.editor.trim_trailing_whitespace),
+                ensure_final_newline: other
+                    .editor
+                    .ensure_final_newline
+                    .or(self.editor.ensure_final_newline),
+            },
+            navigation: NavigationConfig {
+                key_acceleration: other
+                    .navigation
+                    .key_acceleration
+                    .or(self.navigation.key_acceleration),
+                repeat_window_ms: other
+                    .navigation
+                    .repeat_window_ms
+                    .or(self.navigation.repeat_window_ms),
+                accel_after: other.navigation.accel_after.or(self.navigation.accel_after),
+                accel_max_step: other
+                    .navigation
+                    .accel_max_step
+                    .or(self.navigation.accel_max_step),
+            },
+            memory: MemoryConfig {
+                flash_entries: other.memory.flash_entries.or(self.memory.flash_entries),
+                sort_override_entries: other
+                    .memory
+                    .sort_override_entries
+                    .or(self.memory.sort_override_entries),
+                prefetch_entries: other
+                    .memory
+                    .prefetch_entries
+                    .or(self.memory.prefetch_entries),
+                dir_size_entries: other
+                    .memory
+                    .dir_size_entries
+                    .or(self.memory.dir_size_entries),
+                blame_head_entries: other
+                    .memory
+                    .blame_head_entries
+                    .or(self.memory.blame_head_entries),
+            },
         }
     }
 
@@ -338,11 +929,74 @@ impl AppConfig {
         self.general.confirm_delete.unwrap_or(true)
     }
 
+    /// Whether deletes move items to the trash (default: false).
+    pub fn use_trash(&self) -> bool {
+        self.general.use_trash.unwrap_or(false)
+    }
+
+    /// Whether a paste backs up the files it overwrites (default: false).
+    pub fn backup_on_overwrite(&self) -> bool {
+        self.general.backup_on_overwrite.unwrap_or(false)
+    }
+
     /// Whether mouse support is enabled.
     pub fn mouse_enabled(&self) -> bool {
         self.general.mouse.unwrap_or(true)
     }
 
+    /// Whether copies also go to the PRIMARY selection (Linux only).
+    pub fn primary_selection(&self) -> bool {
+        self.general.primary_selection.unwrap_or(false)
+    }
+
+    /// Whether copies also go out as OSC 52 escapes.
+    pub fn osc52(&self) -> bool {
+        self.general.osc52.unwrap_or(true)
+    }
+
+    /// Seconds of activity between session autosaves (0 disables).
+    pub fn autosave_secs(&self) -> u64 {
+        self.general.autosave_secs.unwrap_or(DEFAULT_AUTOSAVE_SECS)
+    }
+
+    /// Whether digits build a count for the next motion (default: true).
+    pub fn count_prefix(&self) -> bool {
+        self.general.count_prefix.unwrap_or(true)
+    }
+
+    /// Whether pastes and deletes of directories are counted first
+    /// (default: true).
+    pub fn preflight_count(&self) -> bool {
+        self.general.preflight_count.unwrap_or(true)
+    }
+
+    /// Entry count that needs an extra confirmation (default: 50000).
+    pub fn large_op_entries(&self) -> usize {
+        self.general
+            .large_op_entries
+            .unwrap_or(DEFAULT_LARGE_OP_ENTRIES)
+    }
+
+    /// Total size that needs an extra confirmation (default: 10 GiB).
+    pub fn large_op_bytes(&self) -> u64 {
+        self.general
+            .large_op_bytes
+            .unwrap_or(DEFAULT_LARGE_OP_BYTES)
+    }
+
+    /// Whether fm sets the terminal window title (default: true).
+    pub fn set_title(&self) -> bool {
+        self.general.set_title.unwrap_or(true)
+    }
+
+    /// Window title template (default: "fm — {path}").
+    pub fn title_template(&self) -> &str {
+        self.general
+            .title_template
+            .as_deref()
+            .unwrap_or(DEFAULT_TITLE_TEMPLATE)
+    }
+
     /// Whether the preview panel is enabled.
     pub fn preview_enabled(&self) -> bool {
         self.preview.enabled.unwrap_or(true)
@@ -365,6 +1019,21 @@ impl AppConfig {
         self.preview.tail_lines.unwrap_or(DEFAULT_TAIL_LINES)
     }
 
+    /// Tab stop width, clamped to 1..=16.
+    pub fn tab_width(&self) -> usize {
+        self.preview
+            .tab_width
+            .unwrap_or(DEFAULT_TAB_WIDTH)
+            .clamp(1, MAX_TAB_WIDTH)
+    }
+
+    /// Memory budget in bytes for cached preview content.
+    pub fn preview_cache_max_bytes(&self) -> usize {
+        self.preview
+            .cache_max_bytes
+            .unwrap_or(DEFAULT_PREVIEW_CACHE_MAX_BYTES) as usize
+    }
+
     /// Syntax highlighting theme name.
     pub fn syntax_theme_name(&self) -> &str {
         self.preview
@@ -383,6 +1052,45 @@ impl AppConfig {
         self.watcher.debounce_ms.unwrap_or(DEFAULT_DEBOUNCE_MS)
     }
 
+    /// Whether rows changed by a refresh are briefly highlighted.
+    pub fn highlight_changes(&self) -> bool {
+        self.watcher.highlight_changes.unwrap_or(true)
+    }
+
+    /// Idle time in milliseconds before stale directories are refreshed
+    /// (0 disables idle refresh).
+    pub fn idle_refresh_ms(&self) -> u64 {
+        self.watcher
+            .idle_refresh_ms
+            .unwrap_or(DEFAULT_IDLE_REFRESH_MS)
+    }
+
+    /// Max stale directories refreshed per idle cycle (at least 1).
+    pub fn idle_refresh_batch(&self) -> usize {
+        self.watcher
+            .idle_refresh_batch
+            .unwrap_or(DEFAULT_IDLE_REFRESH_BATCH)
+            .max(1)
+    }
+
+    /// Whether refreshes under a busy terminal's directory are deferred.
+    pub fn defer_during_builds(&self) -> bool {
+        self.watcher.defer_during_builds.unwrap_or(true)
+    }
+
+    /// Whether the watcher covers the whole tree with one recursive watch
+    /// rather than one watch per expanded directory.
+    pub fn recursive_watch(&self) -> bool {
+        self.watcher.recursive.unwrap_or(true)
+    }
+
+    /// Seconds a changed path stays reachable with the "next changed" key.
+    pub fn changed_window_secs(&self) -> u64 {
+        self.watcher
+            .changed_window_secs
+            .unwrap_or(DEFAULT_CHANGED_WINDOW_SECS)
+    }
+
     /// Sort mode: "name", "size", or "modified".
     pub fn sort_by(&self) -> &str {
         self.tree.sort_by.as_deref().unwrap_or("name")
@@ -398,6 +1106,92 @@ impl AppConfig {
         self.tree.use_icons.unwrap_or(true)
     }
 
+    /// Tree panel width cap, or None when unset or 0.
+    pub fn tree_max_width(&self) -> Option<u16> {
+        self.tree.max_width.filter(|&w| w > 0)
+    }
+
+    /// Deepest tree level shown below the root, at least 1.
+    pub fn tree_max_depth(&self) -> usize {
+        self.tree.max_depth.unwrap_or(DEFAULT_MAX_TREE_DEPTH).max(1)
+    }
+
+    /// Filename columns kept visible in deep tree rows.
+    pub fn tree_min_name_width(&self) -> usize {
+        self.tree.min_name_width.unwrap_or(DEFAULT_MIN_NAME_WIDTH)
+    }
+
+    /// When directory entries are stat-ed in the background.
+    pub fn deferred_stats(&self) -> StatMode {
+        StatMode::from_str(self.tree.deferred_stats.as_deref().unwrap_or("auto"))
+    }
+
+    /// Whether selected directories are sized automatically.
+    pub fn dir_sizes(&self) -> bool {
+        self.tree.dir_sizes.unwrap_or(true)
+    }
+
+    /// Whether directory sizes count every hard link (default: false).
+    pub fn apparent_sizes(&self) -> bool {
+        self.tree.apparent_sizes.unwrap_or(false)
+    }
+
+    /// Whether directory sizes stay on one filesystem (default: true).
+    pub fn one_file_system(&self) -> bool {
+        self.tree.one_file_system.unwrap_or(true)
+    }
+
+    /// Whether the tree shows git status markers (default: true).
+    pub fn git_status(&self) -> bool {
+        self.tree.git_status.unwrap_or(true)
+    }
+
+    /// Whether `.gitignore` hides entries (default: true).
+    pub fn respect_gitignore(&self) -> bool {
+        self.tree.respect_gitignore.unwrap_or(true)
+    }
+
+    /// Preview content width cap, or None when unset or 0.
+    pub fn preview_max_content_width(&self) -> Option<u16> {
+        self.preview.max_content_width.filter(|&w| w > 0)
+    }
+
+    /// Whether file stepping from the preview wraps around (default: false).
+    pub fn wrap_file_navigation(&self) -> bool {
+        self.preview.wrap_file_navigation.unwrap_or(false)
+    }
+
+    /// Whether neighbouring previews are prefetched while idle (default: true).
+    pub fn preview_prefetch(&self) -> bool {
+        self.preview.prefetch.unwrap_or(true)
+    }
+
+    /// Marker drawn before wrapped continuation rows (default: "↪ ").
+    pub fn wrap_marker(&self) -> &str {
+        self.preview
+            .wrap_marker
+            .as_deref()
+            .unwrap_or(DEFAULT_WRAP_MARKER)
+    }
+
+    /// Whether wrapped rows keep their line's indentation (default: true).
+    pub fn wrap_hang_indent(&self) -> bool {
+        self.preview.wrap_hang_indent.unwrap_or(true)
+    }
+
+    /// How long a preview load may run before it is cancelled
+    /// (default: 10s; `None` when set to 0).
+    pub fn preview_load_timeout(&self) -> Option<Duration> {
+        match self
+            .preview
+            .load_timeout_secs
+            .unwrap_or(DEFAULT_PREVIEW_LOAD_TIMEOUT_SECS)
+        {
+            0 => None,
+            secs => Some(Duration::from_secs(secs)),
+        }
+    }
+
     /// Theme scheme: "dark", "light", or "custom".
     pub fn theme_scheme(&self) -> &str {
         self.theme.scheme.as_deref().unwrap_or("dark")
@@ -421,6 +1215,105 @@ impl AppConfig {
         self.terminal.scrollback_lines.unwrap_or(1000)
     }
 
+    /// Text around the focused panel's name (default: "[", "]").
+    pub fn focus_markers(&self) -> (&str, &str) {
+        (
+            self.accessibility.focus_prefix.as_deref().unwrap_or("["),
+            self.accessibility.focus_suffix.as_deref().unwrap_or("]"),
+        )
+    }
+
+    /// Separator between panel and mode in the status bar (default: " · ").
+    pub fn state_separator(&self) -> &str {
+        self.accessibility
+            .state_separator
+            .as_deref()
+            .unwrap_or(" · ")
+    }
+
+    /// Whether the status bar names the focused panel and mode (default: true).
+    pub fn announce_state(&self) -> bool {
+        self.accessibility.announce_state.unwrap_or(true)
+    }
+
+    /// Whether saving strips trailing whitespace (default: false).
+    pub fn trim_trailing_whitespace(&self) -> bool {
+        self.editor.trim_trailing_whitespace.unwrap_or(false)
+    }
+
+    /// Whether saving ends the file with exactly one newline (default: false).
+    pub fn ensure_final_newline(&self) -> bool {
+        self.editor.ensure_final_newline.unwrap_or(false)
+    }
+
+    /// How held navigation keys accelerate, or None when turned off.
+    pub fn key_repeat(&self) -> Option<RepeatSettings> {
+        if !self.navigation.key_acceleration.unwrap_or(true) {
+            return None;
+        }
+        let defaults = RepeatSettings::default();
+        Some(RepeatSettings {
+            window: self
+                .navigation
+                .repeat_window_ms
+                .map_or(defaults.window, Duration::from_millis),
+            after: self.navigation.accel_after.unwrap_or(defaults.after),
+            max_step: self
+                .navigation
+                .accel_max_step
+                .unwrap_or(defaults.max_step)
+                .max(1),
+        })
+    }
+
+    /// Capacity of each bounded per-path map (at least 1).
+    pub fn map_capacities(&self) -> MapCapacities {
+        let defaults = MapCapacities::default();
+        let cap = |configured: Option<usize>, default: usize| configured.unwrap_or(default).max(1);
+        MapCapacities {
+            flash: cap(self.memory.flash_entries, defaults.flash),
+            sort_overrides: cap(self.memory.sort_override_entries, defaults.sort_overrides),
+            prefetch: cap(self.memory.prefetch_entries, defaults.prefetch),
+            dir_sizes: cap(self.memory.dir_size_entries, defaults.dir_sizes),
+            blame_heads: cap(self.memory.blame_head_entries, defaults.blame_heads),
+        }
+    }
+
+    /// The tree filter's file classes in chip order: Code, Docs, Images,
+    /// Archives, Today.
+    pub fn filter_classes(&self) -> Vec<FilterClass> {
+        let list = |configured: &Option<Vec<String>>, defaults: &[&str]| match configured {
+            Some(list) => list.clone(),
+            None => defaults.iter().map(|ext| ext.to_string()).collect(),
+        };
+        let hours = self
+            .filter
+            .today_hours
+            .unwrap_or(filter_class::DEFAULT_TODAY_HOURS);
+        vec![
+            FilterClass::extensions("Code", &list(&self.filter.code, filter_class::DEFAULT_CODE)),
+            FilterClass::extensions("Docs", &list(&self.filter.docs, filter_class::DEFAULT_DOCS)),
+            FilterClass::extensions(
+                "Images",
+                &list(&self.filter.images, filter_class::DEFAULT_IMAGES),
+            ),
+            FilterClass::extensions(
+                "Archives",
+                &list(&self.filter.archives, filter_class::DEFAULT_ARCHIVES),
+            ),
+            FilterClass {
+                name: "Today".to_string(),
+                matcher: Matcher::ModifiedWithin(Duration::from_secs(hours * 3600)),
+            },
+        ]
+    }
+
+    /// Whether the fuzzy finder honors the active filter class (default:
+    /// false).
+    pub fn filter_in_finder(&self) -> bool {
+        self.filter.in_finder.unwrap_or(false)
+    }
+
     /// Max entries to load per page when expanding large directories.
     /// Clamped to [MIN_ENTRIES_PER_PAGE, MAX_ENTRIES_PER_PAGE].
     pub fn max_entries_per_page(&self) -> usize {
@@ -438,6 +1331,34 @@ impl AppConfig {
             .unwrap_or(DEFAULT_SEARCH_MAX_ENTRIES) as usize
     }
 
+    /// Largest file the content search reads.
+    pub fn grep_max_file_bytes(&self) -> u64 {
+        self.general
+            .grep_max_file_bytes
+            .unwrap_or(DEFAULT_GREP_MAX_FILE_BYTES)
+    }
+
+    /// Matching lines at which the content search stops (at least 1).
+    pub fn grep_max_results(&self) -> usize {
+        self.general
+            .grep_max_results
+            .unwrap_or(DEFAULT_GREP_MAX_RESULTS)
+            .max(1)
+    }
+
+    /// Configured opener command, when set and not blank.
+    pub fn opener(&self) -> Option<&str> {
+        self.general
+            .opener
+            .as_deref()
+            .filter(|opener| !opener.trim().is_empty())
+    }
+
+    /// Path display style: "relative", "absolute", or "home-tilde".
+    pub fn path_display(&self) -> &str {
+        self.general.path_display.as_deref().unwrap_or("relative")
+    }
+
     /// Max entries for DirSnapshot.
     /// Clamped to [MIN_SNAPSHOT_MAX_ENTRIES, MAX_SNAPSHOT_MAX_ENTRIES].
     pub fn snapshot_max_entries(&self) -> usize {
@@ -466,15 +1387,44 @@ mod tests {
         assert_eq!(cfg.max_full_preview_bytes(), 1_048_576);
         assert_eq!(cfg.head_lines(), 50);
         assert_eq!(cfg.tail_lines(), 20);
+        assert_eq!(cfg.tab_width(), 4);
         assert_eq!(cfg.syntax_theme_name(), "base16-ocean.dark");
         assert_eq!(cfg.watcher_enabled(), true);
+        assert!(cfg.recursive_watch());
         assert_eq!(cfg.debounce_ms(), 300);
+        assert!(cfg.highlight_changes());
+        assert!(cfg.defer_during_builds());
+        assert_eq!(cfg.changed_window_secs(), 120);
         assert_eq!(cfg.sort_by(), "name");
         assert_eq!(cfg.dirs_first(), true);
         assert_eq!(cfg.use_icons(), true);
         assert_eq!(cfg.theme_scheme(), "dark");
         assert_eq!(cfg.max_entries_per_page(), 1000);
         assert_eq!(cfg.search_max_entries(), 10000);
+        assert_eq!(cfg.grep_max_file_bytes(), 2 * 1_048_576);
+        assert_eq!(cfg.grep_max_results(), 5000);
+        assert_eq!(cfg.preview_cache_max_bytes(), 32 * 1_048_576);
+        assert!(!cfg.wrap_file_navigation());
+        assert!(cfg.preview_prefetch());
+        assert_eq!(cfg.wrap_marker(), "↪ ");
+        assert!(cfg.wrap_hang_indent());
+        assert_eq!(cfg.preview_load_timeout(), Some(Duration::from_secs(10)));
+        assert_eq!(cfg.terminal_scrollback(), 1000);
+        assert!(cfg.count_prefix());
+        assert!(!cfg.use_trash());
+        assert!(!cfg.backup_on_overwrite());
+        assert!(cfg.preflight_count());
+        assert_eq!(cfg.large_op_entries(), 50_000);
+        assert_eq!(cfg.large_op_bytes(), 10 * 1_073_741_824);
+        assert!(cfg.set_title());
+        assert_eq!(cfg.title_template(), "fm — {path}");
+        assert_eq!(cfg.opener(), None);
+        assert_eq!(cfg.path_display(), "relative");
+        assert_eq!(cfg.focus_markers(), ("[", "]"));
+        assert_eq!(cfg.state_separator(), " · ");
+        assert!(cfg.announce_state());
+        assert!(!cfg.trim_trailing_whitespace());
+        assert!(!cfg.ensure_final_newline());
     }
 
     #[test]
@@ -491,6 +1441,7 @@ head_lines = 100
 tail_lines = 40
 syntax_theme = "Solarized (dark)"
 enabled = false
+cache_max_bytes = 4096
 
 [tree]
 sort_by = "size"
@@ -500,6 +1451,8 @@ use_icons = false
 [watcher]
 enabled = false
 debounce_ms = 500
+highlight_changes = false
+recursive = false
 
 [theme]
 scheme = "light"
@@ -513,8 +1466,11 @@ scheme = "light"
         assert_eq!(cfg.head_lines(), 100);
         assert_eq!(cfg.tail_lines(), 40);
         assert_eq!(cfg.syntax_theme_name(), "Solarized (dark)");
+        assert_eq!(cfg.preview_cache_max_bytes(), 4096);
         assert_eq!(cfg.watcher_enabled(), false);
         assert_eq!(cfg.debounce_ms(), 500);
+        assert!(!cfg.highlight_changes());
+        assert!(!cfg.recursive_watch());
         assert_eq!(cfg.sort_by(), "size");
         assert_eq!(cfg.dirs_first(), false);
         assert_eq!(cfg.use_icons(), false);
@@ -542,6 +1498,87 @@ show_hidden = true
         assert_eq!(cfg.confirm_delete(), true);
     }
 
+    #[test]
+    fn test_tree_max_depth() {
+        let cfg = AppConfig::default();
+        assert_eq!(cfg.tree_max_depth(), DEFAULT_MAX_TREE_DEPTH);
+
+        let cfg: AppConfig = toml::from_str("[tree]\nmax_depth = 16\n").expect("parse failed");
+        assert_eq!(cfg.tree_max_depth(), 16);
+
+        // At least the root's children stay visible
+        let cfg: AppConfig = toml::from_str("[tree]\nmax_depth = 0\n").expect("parse failed");
+        assert_eq!(cfg.tree_max_depth(), 1);
+    }
+
+    #[test]
+    fn test_tree_min_name_width() {
+        let cfg = AppConfig::default();
+        assert_eq!(cfg.tree_min_name_width(), DEFAULT_MIN_NAME_WIDTH);
+
+        let cfg: AppConfig = toml::from_str("[tree]\nmin_name_width = 20\n").expect("parse failed");
+        assert_eq!(cfg.tree_min_name_width(), 20);
+    }
+
+    #[test]
+    fn test_deferred_stats() {
+        let cfg = AppConfig::default();
+        assert_eq!(cfg.deferred_stats(), StatMode::Auto);
+
+        let cfg: AppConfig =
+            toml::from_str("[tree]\ndeferred_stats = \"never\"\n").expect("parse failed");
+        assert_eq!(cfg.deferred_stats(), StatMode::Never);
+    }
+
+    #[test]
+    fn test_dir_sizes() {
+        assert!(AppConfig::default().dir_sizes());
+        let cfg: AppConfig = toml::from_str("[tree]\ndir_sizes = false\n").expect("parse failed");
+        assert!(!cfg.dir_sizes());
+        assert!(!AppConfig::default().apparent_sizes());
+        assert!(AppConfig::default().one_file_system());
+        let cfg: AppConfig =
+            toml::from_str("[tree]\napparent_sizes = true\none_file_system = false\n")
+                .expect("parse failed");
+        assert!(cfg.apparent_sizes());
+        assert!(!cfg.one_file_system());
+    }
+
+    #[test]
+    fn test_git_status() {
+        assert!(AppConfig::default().git_status());
+        let cfg: AppConfig = toml::from_str("[tree]\ngit_status = false\n").expect("parse failed");
+        assert!(!cfg.git_status());
+    }
+
+    #[test]
+    fn test_respect_gitignore() {
+        assert!(AppConfig::default().respect_gitignore());
+        let cfg: AppConfig =
+            toml::from_str("[tree]\nrespect_gitignore = false\n").expect("parse failed");
+        assert!(!cfg.respect_gitignore());
+    }
+
+    #[test]
+    fn test_panel_width_caps() {
+        let cfg = AppConfig::default();
+        assert_eq!(cfg.tree_max_width(), None);
+        assert_eq!(cfg.preview_max_content_width(), None);
+
+        let cfg: AppConfig =
+            toml::from_str("[tree]\nmax_width = 60\n\n[preview]\nmax_content_width = 120\n")
+                .expect("parse failed");
+        assert_eq!(cfg.tree_max_width(), Some(60));
+        assert_eq!(cfg.preview_max_content_width(), Some(120));
+
+        // 0 means no cap
+        let cfg: AppConfig =
+            toml::from_str("[tree]\nmax_width = 0\n\n[preview]\nmax_content_width = 0\n")
+                .expect("parse failed");
+        assert_eq!(cfg.tree_max_width(), None);
+        assert_eq!(cfg.preview_max_content_width(), None);
+    }
+
     #[test]
     fn test_merge_overrides() {
         let base = AppConfig {
@@ -585,6 +1622,12 @@ show_hidden = true
             watcher: WatcherConfig {
                 enabled: Some(false),
                 debounce_ms: Some(500),
+                highlight_changes: Some(false),
+                idle_refresh_ms: Some(0),
+                idle_refresh_batch: Some(2),
+                defer_during_builds: Some(false),
+                changed_window_secs: Some(30),
+                recursive: Some(false),
             },
             ..Default::default()
         };
@@ -593,6 +1636,12 @@ show_hidden = true
         let merged = base.merge(&over);
         assert_eq!(merged.watcher_enabled(), false); // base preserved
         assert_eq!(merged.debounce_ms(), 500); // base preserved
+        assert!(!merged.highlight_changes()); // base preserved
+        assert_eq!(merged.idle_refresh_ms(), 0); // base preserved
+        assert_eq!(merged.idle_refresh_batch(), 2); // base preserved
+        assert!(!merged.defer_during_builds()); // base preserved
+        assert_eq!(merged.changed_window_secs(), 30); // base preserved
+        assert!(!merged.recursive_watch()); // base preserved
     }
 
     #[test]
@@ -757,4 +1806,396 @@ search_max_entries = 5000
         assert_eq!(merged.max_entries_per_page(), 3000); // overridden
         assert_eq!(merged.search_max_entries(), 8000); // from base
     }
+
+    #[test]
+    fn test_send_to_table_parsing_and_merge() {
+        let toml = r#"
+[send_to]
+backups = "/mnt/backup"
+usb = "/media/usb"
+"#;
+        let file_cfg: AppConfig = toml::from_str(toml).expect("parse failed");
+        assert_eq!(file_cfg.send_to.len(), 2);
+        assert_eq!(file_cfg.send_to["backups"], "/mnt/backup");
+
+        let mut over = AppConfig::default();
+        over.send_to
+            .insert("usb".to_string(), "/media/usb2".to_string());
+        let merged = file_cfg.merge(&over);
+        assert_eq!(merged.send_to.len(), 2);
+        assert_eq!(merged.send_to["usb"], "/media/usb2"); // overridden
+        assert_eq!(merged.send_to["backups"], "/mnt/backup"); // from base
+    }
+
+    #[test]
+    fn test_bookmarks_table_parsing_and_merge() {
+        let toml = r#"
+[bookmarks]
+projects = "~/src"
+"#;
+        let file_cfg: AppConfig = toml::from_str(toml).expect("parse failed");
+        assert_eq!(file_cfg.bookmarks["projects"], "~/src");
+
+        let mut over = AppConfig::default();
+        over.bookmarks
+            .insert("logs".to_string(), "/var/log".to_string());
+        let merged = file_cfg.merge(&over);
+        assert_eq!(merged.bookmarks.len(), 2);
+        assert_eq!(merged.bookmarks["projects"], "~/src");
+    }
+
+    #[test]
+    fn test_append_send_to_creates_table() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let cfg_path = dir.path().join("nested").join("config.toml");
+        append_send_to_target(&cfg_path, "docs", Path::new("/home/u/docs")).expect("append");
+        let cfg = load_file(&cfg_path).expect("load");
+        assert_eq!(cfg.send_to["docs"], "/home/u/docs");
+    }
+
+    #[test]
+    fn test_append_send_to_preserves_existing_content() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let cfg_path = dir.path().join("config.toml");
+        std::fs::write(
+            &cfg_path,
+            "# my settings\n[general]\nshow_hidden = true\n\n[send_to]\nold = \"/old\"\n\n[tree]\nsort_by = \"size\"\n",
+        )
+        .expect("write");
+        append_send_to_target(&cfg_path, "new dir", Path::new("/new")).expect("append");
+
+        let content = std::fs::read_to_string(&cfg_path).expect("read");
+        assert!(content.starts_with("# my settings"));
+        let cfg = load_file(&cfg_path).expect("load");
+        assert_eq!(cfg.send_to["old"], "/old");
+        assert_eq!(cfg.send_to["new dir"], "/new");
+        assert!(cfg.show_hidden());
+        assert_eq!(cfg.sort_by(), "size");
+    }
+
+    #[test]
+    fn test_open_section_parsing_and_merge() {
+        let toml = r#"
+[open]
+default = "edit"
+md = "preview_focus"
+"*.pdf" = "external"
+"#;
+        let file_cfg: AppConfig = toml::from_str(toml).expect("parse failed");
+        assert_eq!(file_cfg.open.default.as_deref(), Some("edit"));
+        assert_eq!(file_cfg.open.rules.len(), 2);
+        assert_eq!(file_cfg.open.rules["*.pdf"], "external");
+
+        let mut over = AppConfig::default();
+        over.open
+            .rules
+            .insert("md".to_string(), "terminal:glow {path}".to_string());
+        let merged = file_cfg.merge(&over);
+        assert_eq!(merged.open.default.as_deref(), Some("edit")); // from base
+        assert_eq!(merged.open.rules["md"], "terminal:glow {path}"); // overridden
+        assert_eq!(merged.open.rules["*.pdf"], "external");
+    }
+
+    #[test]
+    fn test_accessibility_section_parsing_and_merge() {
+        let toml = r#"
+[accessibility]
+focus_prefix = "focused "
+focus_suffix = ""
+announce_state = false
+"#;
+        let file_cfg: AppConfig = toml::from_str(toml).expect("parse failed");
+        assert_eq!(file_cfg.focus_markers(), ("focused ", ""));
+        assert_eq!(file_cfg.state_separator(), " · "); // default
+        assert!(!file_cfg.announce_state());
+
+        let over = AppConfig {
+            accessibility: AccessibilityConfig {
+                state_separator: Some(" / ".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let merged = file_cfg.merge(&over);
+        assert_eq!(merged.focus_markers(), ("focused ", "")); // from base
+        assert_eq!(merged.state_separator(), " / "); // overridden
+        assert!(!merged.announce_state());
+    }
+
+    #[test]
+    fn test_large_op_thresholds_parsing_and_merge() {
+        let toml = r#"
+[general]
+large_op_entries = 1000
+preflight_count = false
+backup_on_overwrite = true
+"#;
+        let file_cfg: AppConfig = toml::from_str(toml).expect("parse failed");
+        assert_eq!(file_cfg.large_op_entries(), 1000);
+        assert!(file_cfg.backup_on_overwrite());
+        assert_eq!(file_cfg.large_op_bytes(), DEFAULT_LARGE_OP_BYTES);
+        assert!(!file_cfg.preflight_count());
+
+        let over = AppConfig {
+            general: GeneralConfig {
+                large_op_bytes: Some(1_000_000),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let merged = file_cfg.merge(&over);
+        assert_eq!(merged.large_op_entries(), 1000); // from base
+        assert_eq!(merged.large_op_bytes(), 1_000_000); // overridden
+        assert!(!merged.preflight_count());
+        assert!(merged.backup_on_overwrite()); // from base
+    }
+
+    #[test]
+    fn test_title_settings_parsing_and_merge() {
+        let toml = r#"
+[general]
+title_template = "{mode} {root}"
+"#;
+        let file_cfg: AppConfig = toml::from_str(toml).expect("parse failed");
+        assert_eq!(file_cfg.title_template(), "{mode} {root}");
+        assert!(file_cfg.set_title()); // default
+
+        let over = AppConfig {
+            general: GeneralConfig {
+                set_title: Some(false),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let merged = file_cfg.merge(&over);
+        assert_eq!(merged.title_template(), "{mode} {root}"); // from base
+        assert!(!merged.set_title()); // overridden
+    }
+
+    #[test]
+    fn test_opener_parsing_and_merge() {
+        let toml = r#"
+[general]
+opener = "gio open"
+"#;
+        let file_cfg: AppConfig = toml::from_str(toml).expect("parse failed");
+        assert_eq!(file_cfg.opener(), Some("gio open"));
+
+        let blank = AppConfig {
+            general: GeneralConfig {
+                opener: Some("  ".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(blank.opener(), None);
+        let over = AppConfig {
+            general: GeneralConfig {
+                opener: Some("open -a Preview".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(file_cfg.merge(&over).opener(), Some("open -a Preview"));
+    }
+
+    #[test]
+    fn test_path_display_parsing_and_merge() {
+        let toml = r#"
+[general]
+path_display = "home-tilde"
+"#;
+        let file_cfg: AppConfig = toml::from_str(toml).expect("parse failed");
+        assert_eq!(file_cfg.path_display(), "home-tilde");
+
+        let over = AppConfig {
+            general: GeneralConfig {
+                path_display: Some("absolute".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(file_cfg.merge(&over).path_display(), "absolute");
+    }
+
+    #[test]
+    fn test_editor_section_parsing_and_merge() {
+        let toml = r#"
+[editor]
+trim_trailing_whitespace = true
+"#;
+        let file_cfg: AppConfig = toml::from_str(toml).expect("parse failed");
+        assert!(file_cfg.trim_trailing_whitespace());
+        assert!(!file_cfg.ensure_final_newline()); // default
+
+        let over = AppConfig {
+            editor: EditorConfig {
+                ensure_final_newline: Some(true),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let merged = file_cfg.merge(&over);
+        assert!(merged.trim_trailing_whitespace()); // from base
+        assert!(merged.ensure_final_newline()); // overridden
+    }
+
+    #[test]
+    fn test_navigation_section_parsing_and_merge() {
+        assert_eq!(
+            AppConfig::default().key_repeat(),
+            Some(RepeatSettings::default())
+        );
+        let toml = r#"
+[navigation]
+repeat_window_ms = 150
+accel_max_step = 0
+"#;
+        let file_cfg: AppConfig = toml::from_str(toml).expect("parse failed");
+        let settings = file_cfg.key_repeat().unwrap();
+        assert_eq!(settings.window, Duration::from_millis(150));
+        assert_eq!(settings.after, 3); // default
+        assert_eq!(settings.max_step, 1); // clamped
+
+        let over = AppConfig {
+            navigation: NavigationConfig {
+                key_acceleration: Some(false),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(file_cfg.merge(&over).key_repeat(), None);
+    }
+
+    #[test]
+    fn test_memory_section_parsing_and_merge() {
+        assert_eq!(
+            AppConfig::default().map_capacities(),
+            MapCapacities::default()
+        );
+        let toml = r#"
+[memory]
+flash_entries = 32
+dir_size_entries = 0
+"#;
+        let file_cfg: AppConfig = toml::from_str(toml).expect("parse failed");
+        let caps = file_cfg.map_capacities();
+        assert_eq!(caps.flash, 32);
+        assert_eq!(caps.dir_sizes, 1); // clamped
+        assert_eq!(caps.sort_overrides, MapCapacities::default().sort_overrides);
+
+        let over = AppConfig {
+            memory: MemoryConfig {
+                flash_entries: Some(8),
+                sort_override_entries: Some(4),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let merged = file_cfg.merge(&over).map_capacities();
+        assert_eq!((merged.flash, merged.sort_overrides), (8, 4));
+        assert_eq!(merged.dir_sizes, 1); // from base
+    }
+
+    #[test]
+    fn test_filter_classes_defaults_and_overrides() {
+        let now = std::time::SystemTime::now();
+        let defaults = AppConfig::default().filter_classes();
+        let names: Vec<&str> = defaults.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["Code", "Docs", "Images", "Archives", "Today"]);
+        assert!(defaults[0].matches("main.rs", None, now));
+        assert!(!AppConfig::default().filter_in_finder());
+
+        let toml = r#"
+[filter]
+code = ["ex", ".exs"]
+today_hours = 2
+in_finder = true
+"#;
+        let cfg: AppConfig = toml::from_str(toml).expect("parse failed");
+        let classes = AppConfig::default().merge(&cfg).filter_classes();
+        assert!(classes[0].matches("app.exs", None, now));
+        assert!(!classes[0].matches("main.rs", None, now));
+        // Lists not given keep their defaults
+        assert!(classes[2].matches("logo.png", None, now));
+        assert_eq!(
+            classes[4].matcher,
+            Matcher::ModifiedWithin(Duration::from_secs(2 * 3600))
+        );
+        assert!(cfg.filter_in_finder());
+    }
+
+    #[test]
+    fn test_wrap_settings_parsing_and_merge() {
+        let toml = r#"
+[preview]
+wrap_marker = ""
+"#;
+        let file_cfg: AppConfig = toml::from_str(toml).expect("parse failed");
+        assert_eq!(file_cfg.wrap_marker(), "");
+        assert!(file_cfg.wrap_hang_indent()); // default
+
+        let over = AppConfig {
+            preview: PreviewConfig {
+                wrap_hang_indent: Some(false),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let merged = file_cfg.merge(&over);
+        assert_eq!(merged.wrap_marker(), ""); // from base
+        assert!(!merged.wrap_hang_indent()); // overridden
+    }
+
+    #[test]
+    fn test_preview_load_timeout_parsing_and_merge() {
+        let toml = r#"
+[preview]
+load_timeout_secs = 0
+"#;
+        let file_cfg: AppConfig = toml::from_str(toml).expect("parse failed");
+        assert_eq!(file_cfg.preview_load_timeout(), None);
+
+        let over = AppConfig {
+            preview: PreviewConfig {
+                load_timeout_secs: Some(3),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let merged = file_cfg.merge(&over);
+        assert_eq!(merged.preview_load_timeout(), Some(Duration::from_secs(3)));
+    }
+
+    #[test]
+    fn read_file_reports_error_line() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "[general]\nshow_hidden = true\nmouse = \"yes\"\n").unwrap();
+        match read_file(&path) {
+            FileLoad::Invalid { line, column, .. } => {
+                assert_eq!(line, Some(3));
+                assert_eq!(column, Some(9));
+            }
+            other => panic!("expected parse error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn read_file_missing_and_valid() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("config.toml");
+        assert!(matches!(read_file(&path), FileLoad::Missing));
+        std::fs::write(&path, "[general]\nshow_hidden = true\n").unwrap();
+        match read_file(&path) {
+            FileLoad::Parsed(cfg) => assert_eq!(cfg.general.show_hidden, Some(true)),
+            other => panic!("expected parsed config, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn line_column_counts_from_one() {
+        assert_eq!(line_column("abc", 0), (1, 1));
+        assert_eq!(line_column("ab\ncd", 4), (2, 2));
+    }
 }
@@ -0,0 +1,256 @@
+//! Which mutating operations ask first, and how.
+//!
+//! Deletes and pastes consult a [`ConfirmationPolicy`] before showing a
+//! confirmation dialog or starting: given the [`Intent`] and, once the
+//! pre-flight count has run, the [`DiskUsage`] it found, the policy
+//! returns a [`Decision`]. Rules apply in order, the first that matches
+//! deciding:
+//!
+//! 1. Protected: deleting the tree root, or pasting a directory into
+//!    itself, is denied.
+//! 2. Size: a counted operation reaching `large_op_entries` or
+//!    `large_op_bytes` asks twice (`y`, then Enter).
+//! 3. `confirm_delete`: a delete asks once, or goes ahead when the
+//!    setting is off. A paste goes ahead.
+//!
+//! `fm doctor` lists the active policy through [`ConfirmationPolicy::describe`].
+
+use std::path::{Path, PathBuf};
+
+use crate::config::AppConfig;
+use crate::fs::operations::DiskUsage;
+use crate::preview_content::format_size;
+
+/// Entries a pre-flight count walks before settling for "more than".
+pub const PREFLIGHT_MAX_ENTRIES: usize = 1_000_000;
+
+/// An operation about to run.
+#[derive(Debug, Clone, Copy)]
+pub enum Intent<'a> {
+    Delete {
+        targets: &'a [PathBuf],
+    },
+    /// Copy or move `sources` into `dest`.
+    Paste {
+        sources: &'a [PathBuf],
+        dest: &'a Path,
+    },
+}
+
+/// How many steps a confirmation takes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    /// `y` goes ahead.
+    Once,
+    /// `y` arms, Enter goes ahead, so a held `y` can't confirm.
+    Twice,
+}
+
+/// What to do with an [`Intent`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Decision {
+    Proceed,
+    Confirm { level: Level },
+    Deny { reason: String },
+}
+
+/// The confirmation rules in effect, read from the config.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfirmationPolicy {
+    /// Tree root, which is never deleted.
+    pub root: PathBuf,
+    pub confirm_delete: bool,
+    pub large_entries: usize,
+    pub large_bytes: u64,
+}
+
+impl ConfirmationPolicy {
+    pub fn from_config(config: &AppConfig, root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            confirm_delete: config.confirm_delete(),
+            large_entries: config.large_op_entries().min(PREFLIGHT_MAX_ENTRIES),
+            large_bytes: config.large_op_bytes(),
+        }
+    }
+
+    /// Decide `intent`. `usage` is the pre-flight count, when one ran;
+    /// without it the size rule doesn't apply.
+    pub fn decide(&self, intent: &Intent, usage: Option<DiskUsage>) -> Decision {
+        if let Some(reason) = self.protected(intent) {
+            return Decision::Deny { reason };
+        }
+        if usage.is_some_and(|u| self.is_large(u)) {
+            return Decision::Confirm {
+                level: Level::Twice,
+            };
+        }
+        match intent {
+            Intent::Delete { .. } if self.confirm_delete => {
+                Decision::Confirm { level: Level::Once }
+            }
+            _ => Decision::Proceed,
+        }
+    }
+
+    /// Whether `usage` reaches the entry or size threshold.
+    pub fn is_large(&self, usage: DiskUsage) -> bool {
+        usage.entries >= self.large_entries || usage.bytes >= self.large_bytes
+    }
+
+    /// Why `intent` is refused, if it is.
+    fn protected(&self, intent: &Intent) -> Option<String> {
+        match intent {
+            Intent::Delete { targets } => targets
+                .contains(&self.root)
+                .then(|| "Can't delete the tree root".to_string()),
+            Intent::Paste { sources, dest } => sources
+                .iter()
+                .find(|s| dest.starts_with(s))
+                .map(|s| format!("Can't paste {} into itself", name(s))),
+        }
+    }
+
+    /// The rules in order, one line each.
+    pub fn describe(&self) -> Vec<String> {
+        vec![
+            "deleting the tree root or pasting a folder into itself is refused".to_string(),
+            format!(
+                "operations of {}+ entries or {}+ ask twice",
+                self.large_entries,
+                format_size(self.large_bytes)
+            ),
+            if self.confirm_delete {
+                "other deletes ask once".to_string()
+            } else {
+                "other deletes go ahead (confirm_delete = false)".to_string()
+            },
+        ]
+    }
+}
+
+fn name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(confirm_delete: bool) -> ConfirmationPolicy {
+        ConfirmationPolicy {
+            root: PathBuf::from("/r"),
+            confirm_delete,
+            large_entries: 100,
+            large_bytes: 1000,
+        }
+    }
+
+    fn usage(entries: usize, bytes: u64) -> DiskUsage {
+        DiskUsage {
+            bytes,
+            entries,
+            files: entries,
+            complete: true,
+        }
+    }
+
+    const ONCE: Decision = Decision::Confirm { level: Level::Once };
+    const TWICE: Decision = Decision::Confirm {
+        level: Level::Twice,
+    };
+
+    #[test]
+    fn delete_matrix() {
+        let targets = [PathBuf::from("/r/a")];
+        let delete = Intent::Delete { targets: &targets };
+        let small = Some(usage(1, 10));
+        let many = Some(usage(100, 10));
+        let big = Some(usage(1, 1000));
+        // (confirm_delete, usage, expected)
+        let cases = [
+            (true, None, ONCE),
+            (true, small, ONCE),
+            (true, many, TWICE),
+            (true, big, TWICE),
+            (false, None, Decision::Proceed),
+            (false, small, Decision::Proceed),
+            (false, many, TWICE),
+            (false, big, TWICE),
+        ];
+        for (confirm_delete, usage, expected) in cases {
+            assert_eq!(
+                policy(confirm_delete).decide(&delete, usage),
+                expected,
+                "confirm_delete={} usage={:?}",
+                confirm_delete,
+                usage
+            );
+        }
+    }
+
+    #[test]
+    fn paste_matrix() {
+        let sources = [PathBuf::from("/r/a")];
+        let paste = Intent::Paste {
+            sources: &sources,
+            dest: Path::new("/r/b"),
+        };
+        for confirm_delete in [true, false] {
+            let policy = policy(confirm_delete);
+            assert_eq!(policy.decide(&paste, None), Decision::Proceed);
+            assert_eq!(policy.decide(&paste, Some(usage(1, 10))), Decision::Proceed);
+            assert_eq!(policy.decide(&paste, Some(usage(100, 10))), TWICE);
+            assert_eq!(policy.decide(&paste, Some(usage(1, 1000))), TWICE);
+        }
+    }
+
+    #[test]
+    fn protected_paths_are_denied_before_anything_else() {
+        let policy = policy(false);
+        let targets = [PathBuf::from("/r/a"), PathBuf::from("/r")];
+        let delete = Intent::Delete { targets: &targets };
+        for usage in [None, Some(usage(100, 1000))] {
+            assert!(matches!(
+                policy.decide(&delete, usage),
+                Decision::Deny { .. }
+            ));
+        }
+
+        let sources = [PathBuf::from("/r/a")];
+        for dest in ["/r/a", "/r/a/sub"] {
+            let paste = Intent::Paste {
+                sources: &sources,
+                dest: Path::new(dest),
+            };
+            assert_eq!(
+                policy.decide(&paste, Some(usage(100, 1000))),
+                Decision::Deny {
+                    reason: "Can't paste a into itself".to_string()
+                }
+            );
+        }
+        // A sibling sharing the name's prefix is fine
+        let paste = Intent::Paste {
+            sources: &sources,
+            dest: Path::new("/r/ab"),
+        };
+        assert_eq!(policy.decide(&paste, None), Decision::Proceed);
+    }
+
+    #[test]
+    fn thresholds_come_from_the_config() {
+        let mut config = AppConfig::default();
+        config.general.confirm_delete = Some(false);
+        config.general.large_op_entries = Some(usize::MAX);
+        config.general.large_op_bytes = Some(5);
+        let policy = ConfirmationPolicy::from_config(&config, Path::new("/r"));
+        assert!(!policy.confirm_delete);
+        assert_eq!(policy.large_entries, PREFLIGHT_MAX_ENTRIES);
+        assert!(policy.is_large(usage(1, 5)));
+        assert!(!policy.is_large(usage(1, 4)));
+        assert!(policy.describe()[2].contains("go ahead"));
+    }
+}
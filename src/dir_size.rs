@@ -0,0 +1,429 @@
+//! Recursive directory sizes for the tree and the directory preview.
+//!
+//! A directory's own `FileMeta.size` says nothing about what it holds, so
+//! the total is summed by walking the subtree on a `TaskKind::DirSummary`
+//! task. Once the selection has rested on a directory for [`SETTLE`]
+//! (with `tree.dir_sizes` on), or when `z` is pressed, `App::poll_dir_size`
+//! starts a walk that streams running totals as `Event::DirSizeUpdate`
+//! every [`UPDATE_EVERY`] entries. Totals land in [`DirSizes`], which the
+//! tree rows and the directory preview read. Moving the selection off the
+//! directory cancels its walk and drops the partial total. A walk stops
+//! after [`MAX_ENTRIES`] entries or [`TIME_LIMIT`], and its total is then
+//! shown as a lower bound. A filesystem change drops the sizes of the
+//! directories above and below the changed path, and only the
+//! `memory.dir_size_entries` most recently computed sizes are kept.
+//!
+//! The walk is the shared one in `fs::walk`: hard-linked files count once
+//! (`tree.apparent_sizes` shows the sum over every link instead) and mount
+//! points below the directory are skipped unless `tree.one_file_system` is
+//! off.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::bounded_map::{BoundedMap, MapStats, DEFAULT_DIR_SIZE_CAPACITY};
+use crate::fs::walk::{self, WalkOptions};
+use crate::preview_content::format_size;
+
+/// Quiet time on a selected directory before its size is computed.
+pub const SETTLE: Duration = Duration::from_millis(300);
+
+/// Entries walked between running totals.
+pub const UPDATE_EVERY: u64 = 1000;
+
+/// Most entries one walk visits.
+pub const MAX_ENTRIES: u64 = 1_000_000;
+
+/// Longest one walk runs.
+pub const TIME_LIMIT: Duration = Duration::from_secs(30);
+
+/// How far a walk got.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Progress {
+    Scanning,
+    Done,
+    /// Stopped at the entry or time limit.
+    Capped,
+}
+
+/// Totals under a directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DirSize {
+    /// Apparent size: hard-linked files count once per link.
+    pub bytes: u64,
+    /// Hard-linked files counted once.
+    pub unique_bytes: u64,
+    pub files: u64,
+    pub dirs: u64,
+    pub progress: Progress,
+}
+
+impl DirSize {
+    /// Whether the walk has finished, complete or capped.
+    pub fn done(&self) -> bool {
+        self.progress != Progress::Scanning
+    }
+
+    /// Size for display: "1.50 MB", "≥1.50 MB" when capped and "1.50 MB…"
+    /// while scanning. The unique size unless `apparent`.
+    pub fn label(&self, apparent: bool) -> String {
+        let bytes = if apparent {
+            self.bytes
+        } else {
+            self.unique_bytes
+        };
+        let size = format_size(bytes);
+        match self.progress {
+            Progress::Scanning => format!("{}…", size),
+            Progress::Done => size,
+            Progress::Capped => format!("≥{}", size),
+        }
+    }
+
+    /// [`label`](Self::label) followed by the other size when hard links
+    /// make them differ: "1.00 KB (2.00 KB apparent)".
+    pub fn detail(&self, apparent: bool) -> String {
+        let label = self.label(apparent);
+        if self.bytes == self.unique_bytes {
+            return label;
+        }
+        let (other, kind) = if apparent {
+            (self.unique_bytes, "unique")
+        } else {
+            (self.bytes, "apparent")
+        };
+        format!("{} ({} {})", label, format_size(other), kind)
+    }
+}
+
+/// Sum the files under `root` with the shared walk, calling `report`
+/// with the running totals every [`UPDATE_EVERY`] entries. Returns `None`
+/// once `cancel` is raised.
+pub fn walk(
+    root: &Path,
+    options: &WalkOptions,
+    cancel: &AtomicBool,
+    report: &mut dyn FnMut(DirSize),
+) -> Option<DirSize> {
+    let usage = walk::walk(&[root.to_path_buf()], options, cancel, &mut |usage| {
+        if usage.entries.is_multiple_of(UPDATE_EVERY) {
+            report(dir_size(usage, Progress::Scanning));
+        }
+    })?;
+    let progress = if usage.partial {
+        Progress::Capped
+    } else {
+        Progress::Done
+    };
+    Some(dir_size(&usage, progress))
+}
+
+/// Totals under the walked directory, itself not included.
+fn dir_size(usage: &walk::Usage, progress: Progress) -> DirSize {
+    DirSize {
+        bytes: usage.bytes,
+        unique_bytes: usage.unique_bytes,
+        files: usage.files,
+        dirs: usage.dirs.saturating_sub(1),
+        progress,
+    }
+}
+
+#[derive(Debug)]
+struct Running {
+    path: PathBuf,
+    job: u64,
+    cancel: Arc<AtomicBool>,
+}
+
+/// Computed sizes and the walk in progress.
+#[derive(Debug)]
+pub struct DirSizes {
+    sizes: BoundedMap<PathBuf, DirSize>,
+    running: Option<Running>,
+    last_job: u64,
+    /// Bumped whenever a size is added, changed or dropped.
+    pub generation: u64,
+}
+
+impl Default for DirSizes {
+    fn default() -> Self {
+        Self::with_capacity(DEFAULT_DIR_SIZE_CAPACITY)
+    }
+}
+
+impl DirSizes {
+    /// Keep at most `capacity` sizes, dropping the least recently computed.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            sizes: BoundedMap::new(capacity),
+            running: None,
+            last_job: 0,
+            generation: 0,
+        }
+    }
+
+    pub fn stats(&self) -> MapStats {
+        self.sizes.stats()
+    }
+
+    pub fn get(&self, path: &Path) -> Option<&DirSize> {
+        self.sizes.get(path)
+    }
+
+    /// Directory being walked, if any.
+    pub fn running(&self) -> Option<&Path> {
+        self.running.as_ref().map(|r| r.path.as_path())
+    }
+
+    /// Id for the next walk, carried by its updates.
+    pub fn next_job(&mut self) -> u64 {
+        self.last_job += 1;
+        self.last_job
+    }
+
+    /// Track walk `job` of `path`, cancelling the one before.
+    pub fn start(&mut self, path: PathBuf, job: u64, cancel: Arc<AtomicBool>) {
+        self.cancel();
+        self.forget(&path);
+        self.running = Some(Running { path, job, cancel });
+    }
+
+    /// Cancel the walk unless it is of `keep`.
+    pub fn cancel_unless(&mut self, keep: Option<&Path>) {
+        if self.running.is_some() && self.running() != keep {
+            self.cancel();
+        }
+    }
+
+    /// Cancel the walk and drop its partial total.
+    fn cancel(&mut self) {
+        if let Some(running) = self.running.take() {
+            running.cancel.store(true, Ordering::Relaxed);
+            if self.sizes.get(&running.path).is_some_and(|s| !s.done()) {
+                self.forget(&running.path);
+            }
+        }
+    }
+
+    /// Record a total from walk `job`. Returns false, recording nothing,
+    /// for a walk that was cancelled or replaced.
+    pub fn update(&mut self, path: &Path, job: u64, size: DirSize) -> bool {
+        let current = self
+            .running
+            .as_ref()
+            .is_some_and(|r| r.job == job && r.path == path);
+        if !current {
+            return false;
+        }
+        if size.done() {
+            self.running = None;
+        }
+        self.sizes.insert(path.to_path_buf(), size);
+        self.generation += 1;
+        true
+    }
+
+    /// Drop the size of `path`.
+    pub fn forget(&mut self, path: &Path) {
+        if self.sizes.remove(path).is_some() {
+            self.generation += 1;
+        }
+    }
+
+    /// Drop every size that `changed` may have altered: those of the
+    /// directories containing it and of the directories under it. A walk
+    /// of one of them is cancelled.
+    pub fn invalidate(&mut self, changed: &Path) {
+        let affected = |dir: &Path| changed.starts_with(dir) || dir.starts_with(changed);
+        if self.running().is_some_and(affected) {
+            self.cancel();
+        }
+        let before = self.sizes.len();
+        self.sizes.retain(|dir, _| !affected(dir));
+        if self.sizes.len() != before {
+            self.generation += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::time::Instant;
+    use tempfile::TempDir;
+
+    fn options(max_entries: u64, deadline: Instant) -> WalkOptions {
+        WalkOptions {
+            max_entries,
+            deadline: Some(deadline),
+            one_file_system: true,
+        }
+    }
+
+    fn far() -> Instant {
+        Instant::now() + Duration::from_secs(60)
+    }
+
+    fn sized(bytes: u64, progress: Progress) -> DirSize {
+        DirSize {
+            bytes,
+            unique_bytes: bytes,
+            files: 1,
+            dirs: 0,
+            progress,
+        }
+    }
+
+    #[test]
+    fn walk_sums_the_subtree() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("a.txt"), vec![0u8; 100]).unwrap();
+        fs::create_dir_all(dir.path().join("sub/deeper")).unwrap();
+        fs::write(dir.path().join("sub/b.txt"), vec![0u8; 20]).unwrap();
+        fs::write(dir.path().join("sub/deeper/c.txt"), vec![0u8; 3]).unwrap();
+
+        let cancel = AtomicBool::new(false);
+        let size = walk(
+            dir.path(),
+            &options(MAX_ENTRIES, far()),
+            &cancel,
+            &mut |_| {},
+        )
+        .unwrap();
+        assert_eq!(
+            size,
+            DirSize {
+                bytes: 123,
+                unique_bytes: 123,
+                files: 3,
+                dirs: 2,
+                progress: Progress::Done,
+            }
+        );
+        assert_eq!(size.label(false), "123 B");
+        assert_eq!(size.detail(false), "123 B");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn hard_links_count_once_unless_apparent() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("a.bin"), vec![0u8; 1024]).unwrap();
+        fs::hard_link(dir.path().join("a.bin"), dir.path().join("b.bin")).unwrap();
+
+        let cancel = AtomicBool::new(false);
+        let options = options(MAX_ENTRIES, far());
+        let size = walk(dir.path(), &options, &cancel, &mut |_| {}).unwrap();
+        assert_eq!((size.bytes, size.unique_bytes), (2048, 1024));
+        assert_eq!(size.label(false), "1.00 KB");
+        assert_eq!(size.label(true), "2.00 KB");
+        assert_eq!(size.detail(false), "1.00 KB (2.00 KB apparent)");
+        assert_eq!(size.detail(true), "2.00 KB (1.00 KB unique)");
+    }
+
+    #[test]
+    fn walk_stops_at_the_limits_and_on_cancel() {
+        let dir = TempDir::new().unwrap();
+        for i in 0..5 {
+            fs::write(dir.path().join(format!("f{}", i)), b"x").unwrap();
+        }
+        let cancel = AtomicBool::new(false);
+        // The directory itself is the first entry
+        let capped = walk(dir.path(), &options(4, far()), &cancel, &mut |_| {}).unwrap();
+        assert_eq!((capped.files, capped.progress), (3, Progress::Capped));
+        assert_eq!(capped.label(false), "≥3 B");
+
+        let late = walk(
+            dir.path(),
+            &options(MAX_ENTRIES, Instant::now()),
+            &cancel,
+            &mut |_| {},
+        )
+        .unwrap();
+        assert_eq!(late.progress, Progress::Capped);
+
+        cancel.store(true, Ordering::Relaxed);
+        assert_eq!(
+            walk(
+                dir.path(),
+                &options(MAX_ENTRIES, far()),
+                &cancel,
+                &mut |_| {}
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn updates_from_other_walks_are_ignored() {
+        let mut sizes = DirSizes::default();
+        let a = PathBuf::from("/r/a");
+        let b = PathBuf::from("/r/b");
+        let old = sizes.next_job();
+        let old_cancel = Arc::new(AtomicBool::new(false));
+        sizes.start(a.clone(), old, Arc::clone(&old_cancel));
+        assert!(sizes.update(&a, old, sized(5, Progress::Scanning)));
+
+        let job = sizes.next_job();
+        sizes.start(b.clone(), job, Arc::new(AtomicBool::new(false)));
+        assert!(old_cancel.load(Ordering::Relaxed));
+        // The cancelled walk's partial total is gone and stays gone
+        assert_eq!(sizes.get(&a), None);
+        assert!(!sizes.update(&a, old, sized(9, Progress::Done)));
+
+        assert!(sizes.update(&b, job, sized(7, Progress::Done)));
+        assert_eq!(sizes.running(), None);
+        assert_eq!(sizes.get(&b).map(|s| s.bytes), Some(7));
+    }
+
+    #[test]
+    fn changes_drop_sizes_above_and_below() {
+        let mut sizes = DirSizes::default();
+        for path in ["/r", "/r/a", "/r/a/deep", "/r/b"] {
+            let job = sizes.next_job();
+            sizes.start(PathBuf::from(path), job, Arc::new(AtomicBool::new(false)));
+            sizes.update(Path::new(path), job, sized(1, Progress::Done));
+        }
+        let generation = sizes.generation;
+        sizes.invalidate(Path::new("/r/a"));
+        assert!(sizes.generation > generation);
+        let kept: Vec<bool> = ["/r", "/r/a", "/r/a/deep", "/r/b"]
+            .iter()
+            .map(|p| sizes.get(Path::new(p)).is_some())
+            .collect();
+        assert_eq!(kept, [false, false, false, true]);
+    }
+
+    #[test]
+    fn sizes_beyond_capacity_drop_the_oldest() {
+        let mut sizes = DirSizes::with_capacity(2);
+        for path in ["/r/a", "/r/b", "/r/c"] {
+            let job = sizes.next_job();
+            sizes.start(PathBuf::from(path), job, Arc::new(AtomicBool::new(false)));
+            assert!(sizes.update(Path::new(path), job, sized(1, Progress::Done)));
+        }
+        assert_eq!(sizes.get(Path::new("/r/a")), None);
+        assert!(sizes.get(Path::new("/r/b")).is_some());
+        assert!(sizes.get(Path::new("/r/c")).is_some());
+        assert_eq!(sizes.stats().entries, 2);
+        assert_eq!(sizes.stats().evictions, 1);
+    }
+
+    #[test]
+    fn moving_away_cancels_the_walk() {
+        let mut sizes = DirSizes::default();
+        let a = PathBuf::from("/r/a");
+        let cancel = Arc::new(AtomicBool::new(false));
+        let job = sizes.next_job();
+        sizes.start(a.clone(), job, Arc::clone(&cancel));
+        sizes.cancel_unless(Some(&a));
+        assert!(!cancel.load(Ordering::Relaxed));
+        sizes.cancel_unless(None);
+        assert!(cancel.load(Ordering::Relaxed));
+        assert_eq!(sizes.running(), None);
+    }
+}
@@ -0,0 +1,620 @@
+//! `fm doctor`: environment self-diagnostics.
+//!
+//! Each check reuses the code path the TUI takes at startup (config loading,
+//! watcher construction, PTY opening) so a passing report means the real
+//! thing will work too.
+
+use std::path::{Path, PathBuf};
+
+use tokio::sync::mpsc;
+
+use crate::config::{self, AppConfig, FileLoad};
+use crate::confirm::ConfirmationPolicy;
+use crate::error::WatcherError;
+use crate::fs::watcher::{self, FsWatcher};
+use crate::terminal::pty;
+
+/// Outcome of a single check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Pass,
+    /// Works, but degraded or unverifiable.
+    Warn,
+    /// A feature will not work; makes `fm doctor` exit non-zero.
+    Fail,
+}
+
+impl Status {
+    fn marker(self) -> &'static str {
+        match self {
+            Status::Pass => "[PASS]",
+            Status::Warn => "[WARN]",
+            Status::Fail => "[FAIL]",
+        }
+    }
+}
+
+/// One row of the report.
+#[derive(Debug, Clone)]
+pub struct Check {
+    pub name: String,
+    pub status: Status,
+    pub detail: String,
+    /// Suggested fix, shown under non-passing rows.
+    pub fix: Option<String>,
+}
+
+impl Check {
+    fn pass(name: &str, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status: Status::Pass,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    fn warn(name: &str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status: Status::Warn,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+
+    fn fail(name: &str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status: Status::Fail,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+}
+
+/// Terminal-identifying environment variables.
+#[derive(Debug, Clone, Default)]
+pub struct TermEnv {
+    pub colorterm: Option<String>,
+    pub term: Option<String>,
+    pub term_program: Option<String>,
+    pub kitty_window: bool,
+    pub tmux: bool,
+}
+
+impl TermEnv {
+    /// Read the relevant variables from the process environment.
+    pub fn from_env() -> Self {
+        let var = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+        Self {
+            colorterm: var("COLORTERM"),
+            term: var("TERM"),
+            term_program: var("TERM_PROGRAM"),
+            kitty_window: var("KITTY_WINDOW_ID").is_some(),
+            tmux: var("TMUX").is_some(),
+        }
+    }
+
+    fn term_is(&self, names: &[&str]) -> bool {
+        self.term
+            .as_deref()
+            .is_some_and(|t| names.iter().any(|n| t.contains(n)))
+    }
+
+    fn program_is(&self, names: &[&str]) -> bool {
+        self.term_program
+            .as_deref()
+            .is_some_and(|p| names.iter().any(|n| p.eq_ignore_ascii_case(n)))
+    }
+}
+
+/// 24-bit color, as advertised through `$COLORTERM`.
+pub fn check_truecolor(env: &TermEnv) -> Check {
+    const NAME: &str = "truecolor";
+    match env.colorterm.as_deref() {
+        Some("truecolor") | Some("24bit") => Check::pass(NAME, "COLORTERM advertises 24-bit color"),
+        _ => Check::warn(
+            NAME,
+            "COLORTERM does not advertise 24-bit color; themes may look washed out",
+            "export COLORTERM=truecolor if your terminal supports it",
+        ),
+    }
+}
+
+/// Mouse reporting: enabled in config and a terminal that can send it.
+pub fn check_mouse(env: &TermEnv, config: &AppConfig) -> Check {
+    const NAME: &str = "mouse";
+    if !config.mouse_enabled() {
+        return Check::warn(
+            NAME,
+            "disabled by config or --no-mouse",
+            "set `mouse = true` under [general] to enable it",
+        );
+    }
+    match env.term.as_deref() {
+        None | Some("dumb") => Check::warn(
+            NAME,
+            "TERM is unset or dumb; mouse events will not be reported",
+            "run fm inside a terminal emulator with TERM set (e.g. xterm-256color)",
+        ),
+        Some(term) => Check::pass(NAME, format!("enabled (TERM={})", term)),
+    }
+}
+
+/// OSC 52 clipboard escape support (heuristic — it cannot be queried).
+pub fn check_osc52(env: &TermEnv) -> Check {
+    const NAME: &str = "OSC 52 clipboard";
+    if env.tmux {
+        return Check::warn(
+            NAME,
+            "running inside tmux, which filters OSC 52 by default",
+            "add `set -g set-clipboard on` to ~/.tmux.conf",
+        );
+    }
+    if env.kitty_window
+        || env.term_is(&["kitty", "alacritty", "foot", "wezterm"])
+        || env.program_is(&["iTerm.app", "WezTerm", "ghostty", "vscode"])
+    {
+        Check::pass(NAME, "terminal is known to support OSC 52")
+    } else {
+        Check::warn(
+            NAME,
+            "terminal support unknown",
+            "enable clipboard access (OSC 52) in your terminal settings if available",
+        )
+    }
+}
+
+/// Kitty graphics protocol support (heuristic).
+pub fn check_kitty_graphics(env: &TermEnv) -> Check {
+    const NAME: &str = "kitty graphics";
+    if env.kitty_window || env.term_is(&["kitty"]) || env.program_is(&["WezTerm", "ghostty"]) {
+        Check::pass(
+            NAME,
+            "terminal is known to support the kitty graphics protocol",
+        )
+    } else {
+        Check::warn(
+            NAME,
+            "not detected",
+            "use kitty, WezTerm or Ghostty for inline graphics",
+        )
+    }
+}
+
+/// Whether Nerd Font icons are configured.
+pub fn check_icons(config: &AppConfig) -> Check {
+    const NAME: &str = "nerd font icons";
+    if config.use_icons() {
+        Check::pass(
+            NAME,
+            "icons enabled; glyphs render as boxes unless a Nerd Font is active",
+        )
+    } else {
+        Check::pass(NAME, "ASCII icons (use_icons = false or --no-icons)")
+    }
+}
+
+/// Parse status of every config file that would be loaded.
+pub fn check_config_files(cli_config: Option<&Path>, report: &[(PathBuf, FileLoad)]) -> Vec<Check> {
+    const NAME: &str = "config";
+    let mut checks = Vec::new();
+    for (path, load) in report {
+        match load {
+            FileLoad::Missing if Some(path.as_path()) == cli_config => checks.push(Check::fail(
+                NAME,
+                format!("{} (from --config) does not exist", path.display()),
+                "check the path passed to --config",
+            )),
+            FileLoad::Missing => {}
+            FileLoad::Parsed(_) => {
+                checks.push(Check::pass(NAME, format!("{} parsed", path.display())))
+            }
+            FileLoad::Invalid {
+                message,
+                line,
+                column,
+            } => {
+                let at = match (line, column) {
+                    (Some(l), Some(c)) => format!(":{}:{}", l, c),
+                    (Some(l), None) => format!(":{}", l),
+                    _ => String::new(),
+                };
+                checks.push(Check::fail(
+                    NAME,
+                    format!("{}{}: {}", path.display(), at, message.trim()),
+                    "fix the TOML at that location; the whole file is ignored until then",
+                ));
+            }
+        }
+    }
+    if checks.is_empty() {
+        checks.push(Check::pass(NAME, "no config file found; using defaults"));
+    }
+    checks
+}
+
+/// Start the same recursive watcher the TUI uses on `root`.
+pub fn check_watcher(root: &Path, config: &AppConfig) -> Check {
+    const NAME: &str = "watcher";
+    if !config.watcher_enabled() {
+        return Check::pass(NAME, "disabled by config or --no-watcher");
+    }
+    let (tx, _rx) = mpsc::unbounded_channel();
+    match FsWatcher::from_config(root, config, tx) {
+        Ok(w) if !w.unwatched().is_empty() => Check::warn(
+            NAME,
+            format!(
+                "watching {} partially: {} subtrees unwatched",
+                root.display(),
+                w.unwatched().len()
+            ),
+            w.partial_error()
+                .map(|e| e.to_string())
+                .unwrap_or_else(|| "check the permissions of the unwatched directories".into()),
+        ),
+        Ok(_) => Check::pass(NAME, format!("watching {}", root.display())),
+        Err(e @ WatcherError::InotifyLimit { .. }) => Check::fail(
+            NAME,
+            format!("cannot watch {}: {}", root.display(), e),
+            e.details()
+                .iter()
+                .find(|l| l.contains("sysctl fs.inotify"))
+                .map(|l| l.trim().to_string())
+                .unwrap_or_else(|| "raise the inotify limits or run with --no-watcher".into()),
+        ),
+        Err(e) => Check::fail(
+            NAME,
+            format!("cannot watch {}: {}", root.display(), e),
+            "raise the inotify limits or run with --no-watcher",
+        ),
+    }
+}
+
+/// Compare the inotify watch limit against [`watcher::LOW_INOTIFY_WATCHES`].
+pub fn check_inotify_limit(limit: Option<u64>) -> Check {
+    const NAME: &str = "inotify watches";
+    match limit {
+        None => Check::warn(
+            NAME,
+            format!("could not read {}", watcher::INOTIFY_MAX_WATCHES_PATH),
+            "large trees may exhaust the watch limit without notice",
+        ),
+        Some(n) if n < watcher::LOW_INOTIFY_WATCHES => Check::warn(
+            NAME,
+            format!("fs.inotify.max_user_watches = {} is low", n),
+            "sudo sysctl fs.inotify.max_user_watches=524288",
+        ),
+        Some(n) => Check::pass(NAME, format!("fs.inotify.max_user_watches = {}", n)),
+    }
+}
+
+/// Shell lookup and PTY allocation for the embedded terminal.
+pub fn check_shell(config: &AppConfig) -> Check {
+    const NAME: &str = "terminal shell";
+    if !cfg!(feature = "embedded-terminal") {
+        return Check::pass(NAME, pty::UNSUPPORTED);
+    }
+    if !config.terminal_enabled() {
+        return Check::pass(NAME, "embedded terminal disabled");
+    }
+    let shell = config.terminal_shell();
+    let path = match pty::resolve_shell(&shell) {
+        Some(p) => p,
+        None => {
+            return Check::fail(
+                NAME,
+                format!("shell `{}` not found", shell),
+                "set `default_shell` under [terminal] or fix $SHELL",
+            )
+        }
+    };
+    match pty::open_pty(24, 80) {
+        Ok(_) => Check::pass(NAME, format!("{}; PTY available", path.display())),
+        Err(e) => Check::fail(
+            NAME,
+            format!("cannot open a PTY: {}", e),
+            "check that /dev/ptmx is accessible, or run with --no-terminal",
+        ),
+    }
+}
+
+/// The confirmation rules deletes and pastes follow under `root`.
+pub fn check_confirmations(root: &Path, config: &AppConfig) -> Check {
+    let policy = ConfirmationPolicy::from_config(config, root);
+    Check::pass("confirmations", policy.describe().join("; "))
+}
+
+/// Write access to `dir`. A directory that does not exist yet passes if
+/// its nearest existing ancestor is writable, since it can be created.
+pub fn check_writable(name: &str, dir: Option<&Path>) -> Check {
+    let dir = match dir {
+        Some(d) => d,
+        None => {
+            return Check::warn(
+                name,
+                "location unknown on this platform",
+                "set $HOME (and $XDG_CONFIG_HOME / $XDG_DATA_HOME if needed)",
+            )
+        }
+    };
+    let existing = match dir.ancestors().find(|a| a.exists()) {
+        Some(a) => a,
+        None => {
+            return Check::fail(
+                name,
+                format!("{}: no existing parent", dir.display()),
+                "create the directory",
+            )
+        }
+    };
+    let writable = existing.is_dir() && probe_write(existing).is_ok();
+    match (writable, existing == dir) {
+        (true, true) => Check::pass(name, format!("{} writable", dir.display())),
+        (true, false) => Check::pass(
+            name,
+            format!(
+                "{} will be created under {}",
+                dir.display(),
+                existing.display()
+            ),
+        ),
+        (false, _) => Check::fail(
+            name,
+            format!("{} is not writable", existing.display()),
+            format!(
+                "chown/chmod {} or remove what is in the way",
+                existing.display()
+            ),
+        ),
+    }
+}
+
+/// Create and remove a scratch file in `dir`.
+fn probe_write(dir: &Path) -> std::io::Result<()> {
+    let probe = dir.join(format!(".fm-doctor-{}", std::process::id()));
+    std::fs::write(&probe, b"")?;
+    std::fs::remove_file(&probe)
+}
+
+/// Run every check for `root`.
+pub fn collect(
+    root: &Path,
+    cli_config: Option<&Path>,
+    config: &AppConfig,
+    env: &TermEnv,
+) -> Vec<Check> {
+    let mut checks = vec![
+        check_truecolor(env),
+        check_mouse(env, config),
+        check_osc52(env),
+        check_kitty_graphics(env),
+        check_icons(config),
+    ];
+    checks.extend(check_config_files(
+        cli_config,
+        &config::file_report(cli_config),
+    ));
+    checks.push(check_watcher(root, config));
+    if cfg!(target_os = "linux") {
+        checks.push(check_inotify_limit(watcher::inotify_max_user_watches()));
+    }
+    checks.push(check_shell(config));
+    checks.push(check_confirmations(root, config));
+
+    let config_dir = dirs::config_dir().map(|d| d.join("fm-tui"));
+    let data_dir = dirs::data_dir().map(|d| d.join("fm-tui"));
+    let trash_dir = crate::fs::trash::Trash::system().map(|t| t.dir);
+    checks.push(check_writable("config dir", config_dir.as_deref()));
+    checks.push(check_writable("data dir", data_dir.as_deref()));
+    checks.push(check_writable("trash dir", trash_dir.as_deref()));
+    checks
+}
+
+/// Format checks as a plain-text table followed by a summary line.
+pub fn render(checks: &[Check]) -> String {
+    let width = checks
+        .iter()
+        .map(|c| c.name.chars().count())
+        .max()
+        .unwrap_or(0);
+    let mut out = String::new();
+    for check in checks {
+        out.push_str(&format!(
+            "{} {:<width$}  {}\n",
+            check.status.marker(),
+            check.name,
+            check.detail,
+            width = width
+        ));
+        if check.status != Status::Pass {
+            if let Some(ref fix) = check.fix {
+                out.push_str(&format!(
+                    "       {:<width$}  fix: {}\n",
+                    "",
+                    fix,
+                    width = width
+                ));
+            }
+        }
+    }
+    let count = |s: Status| checks.iter().filter(|c| c.status == s).count();
+    out.push_str(&format!(
+        "\n{} passed, {} warnings, {} failed\n",
+        count(Status::Pass),
+        count(Status::Warn),
+        count(Status::Fail)
+    ));
+    out
+}
+
+/// Process exit code for a report: non-zero if any check failed.
+pub fn exit_code(checks: &[Check]) -> i32 {
+    if checks.iter().any(|c| c.status == Status::Fail) {
+        1
+    } else {
+        0
+    }
+}
+
+/// Run `fm doctor`: print the report and return the exit code.
+pub fn run(root: &Path, cli_config: Option<&Path>, config: &AppConfig) -> i32 {
+    let checks = collect(root, cli_config, config, &TermEnv::from_env());
+    print!("{}", render(&checks));
+    exit_code(&checks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GeneralConfig;
+
+    fn term(name: &str) -> TermEnv {
+        TermEnv {
+            term: Some(name.to_string()),
+            ..TermEnv::default()
+        }
+    }
+
+    #[test]
+    fn truecolor_follows_colorterm() {
+        let mut env = term("xterm-256color");
+        assert_eq!(check_truecolor(&env).status, Status::Warn);
+        env.colorterm = Some("truecolor".to_string());
+        assert_eq!(check_truecolor(&env).status, Status::Pass);
+    }
+
+    #[test]
+    fn mouse_warns_on_dumb_terminal_or_when_disabled() {
+        let config = AppConfig::default();
+        assert_eq!(check_mouse(&term("dumb"), &config).status, Status::Warn);
+        assert_eq!(check_mouse(&term("xterm"), &config).status, Status::Pass);
+
+        let disabled = AppConfig {
+            general: GeneralConfig {
+                mouse: Some(false),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(check_mouse(&term("xterm"), &disabled).status, Status::Warn);
+    }
+
+    #[test]
+    fn osc52_and_graphics_detect_kitty_and_tmux() {
+        let kitty = term("xterm-kitty");
+        assert_eq!(check_osc52(&kitty).status, Status::Pass);
+        assert_eq!(check_kitty_graphics(&kitty).status, Status::Pass);
+
+        let tmux = TermEnv {
+            tmux: true,
+            ..term("screen-256color")
+        };
+        let check = check_osc52(&tmux);
+        assert_eq!(check.status, Status::Warn);
+        assert!(check.fix.unwrap().contains("set-clipboard"));
+        assert_eq!(check_kitty_graphics(&tmux).status, Status::Warn);
+    }
+
+    #[test]
+    fn config_errors_carry_line_numbers() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let path = tmp.path().join("config.toml");
+        std::fs::write(&path, "[general]\nmouse = \"yes\"\n").unwrap();
+        let report = vec![(path.clone(), config::read_file(&path))];
+
+        let checks = check_config_files(None, &report);
+        assert_eq!(checks.len(), 1);
+        assert_eq!(checks[0].status, Status::Fail);
+        assert!(
+            checks[0].detail.contains("config.toml:2:"),
+            "{}",
+            checks[0].detail
+        );
+    }
+
+    #[test]
+    fn missing_cli_config_fails_but_missing_candidates_do_not() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let path = tmp.path().join("absent.toml");
+        let report = vec![(path.clone(), FileLoad::Missing)];
+
+        let checks = check_config_files(None, &report);
+        assert_eq!(checks.len(), 1);
+        assert_eq!(checks[0].status, Status::Pass);
+
+        let checks = check_config_files(Some(&path), &report);
+        assert_eq!(checks[0].status, Status::Fail);
+    }
+
+    #[test]
+    fn inotify_limit_thresholds() {
+        assert_eq!(check_inotify_limit(Some(8192)).status, Status::Warn);
+        assert_eq!(check_inotify_limit(Some(524_288)).status, Status::Pass);
+        assert_eq!(check_inotify_limit(None).status, Status::Warn);
+    }
+
+    #[test]
+    fn watcher_check_uses_root() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let check = check_watcher(tmp.path(), &AppConfig::default());
+        assert_eq!(check.status, Status::Pass);
+
+        let check = check_watcher(&tmp.path().join("gone"), &AppConfig::default());
+        assert_eq!(check.status, Status::Fail);
+    }
+
+    #[test]
+    fn confirmation_check_explains_the_policy() {
+        let mut config = AppConfig::default();
+        let check = check_confirmations(Path::new("/r"), &config);
+        assert_eq!(check.status, Status::Pass);
+        assert!(check.detail.contains("other deletes ask once"));
+
+        config.general.confirm_delete = Some(false);
+        let check = check_confirmations(Path::new("/r"), &config);
+        assert!(check.detail.contains("other deletes go ahead"));
+    }
+
+    #[test]
+    fn writable_check_passes_existing_and_creatable_dirs() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        assert_eq!(check_writable("dir", Some(tmp.path())).status, Status::Pass);
+
+        let nested = tmp.path().join("a").join("b");
+        let check = check_writable("dir", Some(&nested));
+        assert_eq!(check.status, Status::Pass);
+        assert!(check.detail.contains("will be created"));
+        assert!(!nested.exists());
+    }
+
+    #[test]
+    fn writable_check_fails_under_a_file() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let blocker = tmp.path().join("blocker");
+        std::fs::write(&blocker, "").unwrap();
+
+        let check = check_writable("dir", Some(&blocker.join("fm-tui")));
+        assert_eq!(check.status, Status::Fail);
+        assert!(check.fix.is_some());
+    }
+
+    #[test]
+    fn render_marks_rows_and_exit_code_tracks_failures() {
+        let checks = vec![
+            Check::pass("alpha", "fine"),
+            Check::warn("beta", "meh", "do this"),
+        ];
+        let out = render(&checks);
+        assert!(out.contains("[PASS] alpha  fine"));
+        assert!(out.contains("[WARN] beta   meh"));
+        assert!(out.contains("fix: do this"));
+        assert!(out.contains("1 passed, 1 warnings, 0 failed"));
+        assert_eq!(exit_code(&checks), 0);
+
+        let mut failing = checks;
+        failing.push(Check::fail("gamma", "broken", "repair"));
+        assert_eq!(exit_code(&failing), 1);
+    }
+}
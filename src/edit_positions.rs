@@ -0,0 +1,174 @@
+//! Remembered editor positions.
+//!
+//! Leaving edit mode records where the cursor and viewport were, keyed by
+//! canonical path; editing the same file again puts them back. Only the
+//! [`MAX_POSITIONS`] most recently left files are kept. The map is written
+//! to `positions.json` in the session directory on exit and read on start,
+//! alongside the persisted undo.
+//!
+//! A file may have shrunk or been rewritten since: positions are clamped to
+//! the buffer when restored (by `EditorState::set_cursor_position`), never
+//! trusted as-is.
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::schema::{self, Artifact};
+
+/// Positions file name inside the session directory.
+pub const POSITIONS_FILE: &str = "positions.json";
+
+/// Files whose position is remembered; the least recently left go first.
+pub const MAX_POSITIONS: usize = 500;
+
+/// Where the cursor and viewport were when a file was last left.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EditPosition {
+    /// 0-based cursor line.
+    pub line: usize,
+    /// 0-based cursor column in chars.
+    pub col: usize,
+    /// First buffer line in view.
+    pub scroll: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Entry {
+    path: PathBuf,
+    #[serde(flatten)]
+    position: EditPosition,
+}
+
+/// Bounded map of positions, oldest first.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EditPositions {
+    entries: Vec<Entry>,
+}
+
+impl EditPositions {
+    /// Remember `position` for `path`, making it the most recent entry and
+    /// evicting the oldest beyond [`MAX_POSITIONS`].
+    #[cfg_attr(not(feature = "editor"), allow(dead_code))]
+    pub fn remember(&mut self, path: &Path, position: EditPosition) {
+        let path = canonical(path);
+        self.entries.retain(|e| e.path != path);
+        self.entries.push(Entry { path, position });
+        let excess = self.entries.len().saturating_sub(MAX_POSITIONS);
+        self.entries.drain(..excess);
+    }
+
+    /// Position last left in `path`, if remembered.
+    #[cfg_attr(not(feature = "editor"), allow(dead_code))]
+    pub fn get(&self, path: &Path) -> Option<EditPosition> {
+        let path = canonical(path);
+        self.entries
+            .iter()
+            .find(|e| e.path == path)
+            .map(|e| e.position)
+    }
+
+    /// Number of remembered files.
+    #[allow(dead_code)]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether no position is remembered.
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Atomically write the positions to `dir`.
+    pub fn save(&self, dir: &Path) -> io::Result<()> {
+        schema::save(Artifact::Positions, dir, self)
+    }
+
+    /// Read the positions saved in `dir`; empty when missing or unreadable.
+    pub fn load(dir: &Path) -> Self {
+        schema::load::<EditPositions>(Artifact::Positions, dir)
+            .map(|mut positions| {
+                // A hand-edited or older file may hold more than fits now
+                let excess = positions.entries.len().saturating_sub(MAX_POSITIONS);
+                positions.entries.drain(..excess);
+                positions
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// `path` with symlinks and `..` resolved, so every route to a file shares
+/// one entry. Falls back to `path` itself when it can't be resolved.
+#[cfg_attr(not(feature = "editor"), allow(dead_code))]
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn at(line: usize) -> EditPosition {
+        EditPosition {
+            line,
+            col: 3,
+            scroll: line.saturating_sub(10),
+        }
+    }
+
+    #[test]
+    fn remember_replaces_and_resolves_paths() {
+        let tmp = TempDir::new().unwrap();
+        std::fs::create_dir(tmp.path().join("src")).unwrap();
+        let file = tmp.path().join("src").join("main.rs");
+        std::fs::write(&file, "fn main() {}\n").unwrap();
+
+        let mut positions = EditPositions::default();
+        positions.remember(&file, at(5));
+        let other_route = tmp.path().join("src").join("..").join("src/main.rs");
+        positions.remember(&other_route, at(40));
+        assert_eq!(positions.len(), 1);
+        assert_eq!(positions.get(&file), Some(at(40)));
+        assert_eq!(positions.get(&tmp.path().join("missing.rs")), None);
+    }
+
+    #[test]
+    fn oldest_entries_are_evicted() {
+        let mut positions = EditPositions::default();
+        for i in 0..MAX_POSITIONS {
+            positions.remember(Path::new(&format!("/nonexistent/{}", i)), at(i));
+        }
+        // Touching the oldest keeps it; the next oldest goes instead.
+        positions.remember(Path::new("/nonexistent/0"), at(1));
+        positions.remember(Path::new("/nonexistent/new"), at(2));
+        assert_eq!(positions.len(), MAX_POSITIONS);
+        assert_eq!(positions.get(Path::new("/nonexistent/0")), Some(at(1)));
+        assert_eq!(positions.get(Path::new("/nonexistent/1")), None);
+        assert_eq!(positions.get(Path::new("/nonexistent/2")), Some(at(2)));
+        assert_eq!(positions.get(Path::new("/nonexistent/new")), Some(at(2)));
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let session = TempDir::new().unwrap();
+        let mut positions = EditPositions::default();
+        positions.remember(Path::new("/nonexistent/a.rs"), at(412));
+        positions.remember(Path::new("/nonexistent/b.rs"), at(0));
+        positions.save(session.path()).unwrap();
+
+        let loaded = EditPositions::load(session.path());
+        assert_eq!(loaded, positions);
+        assert_eq!(loaded.get(Path::new("/nonexistent/a.rs")), Some(at(412)));
+    }
+
+    #[test]
+    fn missing_or_corrupt_file_loads_empty() {
+        let session = TempDir::new().unwrap();
+        assert!(EditPositions::load(session.path()).is_empty());
+        std::fs::write(session.path().join(POSITIONS_FILE), "{not json").unwrap();
+        assert!(EditPositions::load(session.path()).is_empty());
+    }
+}
@@ -1,6 +1,9 @@
 use std::path::PathBuf;
 use std::time::Instant;
 
+use crate::text;
+use crate::whitespace::{self, FinalNewline};
+
 /// A single reversible edit action in the editor.
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
@@ -120,6 +123,9 @@ pub struct EditorState {
     pub group_is_delete: bool,
     /// Active text selection (None if no selection).
     pub selection: Option<Selection>,
+    /// Tab stop width for rendering and cursor columns. The buffer (and
+    /// anything copied from it) keeps the real tab characters.
+    pub tab_width: usize,
 }
 
 /// Maximum entries in the undo stack.
@@ -170,6 +176,7 @@ impl EditorState {
             group_start_col: 0,
             group_is_delete: false,
             selection: None,
+            tab_width: crate::config::DEFAULT_TAB_WIDTH,
         }
     }
 
@@ -201,9 +208,9 @@ impl EditorState {
         let line_len = self
             .buffer
             .get(self.cursor_line)
-            .map(|l| l.len())
+            .map(|l| l.chars().count())
             .unwrap_or(0);
-        self.cursor_col = col.min(line_len);
+        self.cursor_col = self.grapheme_floor(col.min(line_len));
         self.selection = None;
         self.ensure_cursor_visible();
     }
@@ -215,20 +222,74 @@ impl EditorState {
         let line_len = self
             .buffer
             .get(self.cursor_line)
-            .map(|l| l.len())
+            .map(|l| l.chars().count())
             .unwrap_or(0);
-        self.cursor_col = col.min(line_len);
+        self.cursor_col = self.grapheme_floor(col.min(line_len));
         self.ensure_cursor_visible();
     }
 
-    /// Get the length of the current line.
+    /// Get the length of the current line in chars.
     pub fn current_line_len(&self) -> usize {
         self.buffer
             .get(self.cursor_line)
-            .map(|l| l.len())
+            .map(|l| l.chars().count())
             .unwrap_or(0)
     }
 
+    /// Snap `col` on the current line back to the start of its grapheme, so
+    /// the cursor never sits inside a combining or ZWJ sequence.
+    fn grapheme_floor(&self, col: usize) -> usize {
+        match self.buffer.get(self.cursor_line) {
+            Some(line) => {
+                let byte = text::floor_boundary(line, char_to_byte_index(line, col));
+                line[..byte].chars().count()
+            }
+            None => col,
+        }
+    }
+
+    /// Char column of the grapheme boundary after the cursor.
+    fn next_grapheme_col(&self) -> usize {
+        match self.buffer.get(self.cursor_line) {
+            Some(line) => {
+                let byte = char_to_byte_index(line, self.cursor_col);
+                let next = text::next_boundary(line, byte);
+                self.cursor_col + line[byte..next].chars().count()
+            }
+            None => self.cursor_col,
+        }
+    }
+
+    /// Char column of the grapheme boundary before the cursor.
+    fn prev_grapheme_col(&self) -> usize {
+        match self.buffer.get(self.cursor_line) {
+            Some(line) => {
+                let byte = char_to_byte_index(line, self.cursor_col);
+                let prev = text::prev_boundary(line, byte);
+                self.cursor_col - line[prev..byte].chars().count()
+            }
+            None => self.cursor_col,
+        }
+    }
+
+    /// Char column on `line` under display column `display_col`.
+    pub fn col_at_display(&self, line: usize, display_col: usize) -> usize {
+        match self.buffer.get(line) {
+            Some(l) => l[..text::byte_at_column(l, display_col, self.tab_width)]
+                .chars()
+                .count(),
+            None => display_col,
+        }
+    }
+
+    /// Display column where char column `col` of `line` is drawn.
+    pub fn display_col(&self, line: usize, col: usize) -> usize {
+        match self.buffer.get(line) {
+            Some(l) => text::column_at_byte(l, char_to_byte_index(l, col), self.tab_width),
+            None => col,
+        }
+    }
+
     /// Clamp cursor position to valid bounds.
     pub fn clamp_cursor(&mut self) {
         if self.cursor_line >= self.buffer.len() {
@@ -238,6 +299,7 @@ impl EditorState {
         if self.cursor_col > line_len {
             self.cursor_col = line_len;
         }
+        self.cursor_col = self.grapheme_floor(self.cursor_col);
     }
 
     /// Ensure the viewport scrolls to keep the cursor visible.
@@ -371,7 +433,7 @@ impl EditorState {
         }
     }
 
-    /// Delete the character before the cursor (Backspace).
+    /// Delete the grapheme before the cursor (Backspace).
     /// If there is a selection, delete it instead.
     pub fn delete_char_before(&mut self) {
         if self.selection.is_some() {
@@ -379,26 +441,16 @@ impl EditorState {
             return;
         }
         if self.cursor_col > 0 {
-            // Extract char info before mutating
-            let cur_line = self.cursor_line;
-            let cur_col = self.cursor_col;
-            let (prev_byte_idx, deleted_ch) = {
-                let line = &self.buffer[cur_line];
-                let byte_idx = char_to_byte_index(line, cur_col);
-                let prev_byte_idx = char_to_byte_index(line, cur_col - 1);
-                let ch = line[prev_byte_idx..byte_idx].chars().next().unwrap_or(' ');
-                (prev_byte_idx, ch)
-            };
-            self.record_char_delete(cur_line, cur_col - 1, deleted_ch);
-            self.buffer[cur_line].remove(prev_byte_idx);
-            self.cursor_col -= 1;
+            let start_col = self.prev_grapheme_col();
+            self.delete_cols(start_col, self.cursor_col);
+            self.cursor_col = start_col;
             self.modified = true;
         } else if self.cursor_line > 0 {
             // Join with the previous line
             self.flush_group();
             let current_line = self.buffer.remove(self.cursor_line);
             self.cursor_line -= 1;
-            let join_col = self.buffer[self.cursor_line].len();
+            let join_col = self.buffer[self.cursor_line].chars().count();
             self.buffer[self.cursor_line].push_str(&current_line);
             self.cursor_col = join_col;
             self.record_action(EditorAction::JoinLine {
@@ -409,7 +461,7 @@ impl EditorState {
         }
     }
 
-    /// Delete the character at the cursor (Delete key).
+    /// Delete the grapheme at the cursor (Delete key).
     /// If there is a selection, delete it instead.
     pub fn delete_char_at(&mut self) {
         if self.selection.is_some() {
@@ -418,23 +470,14 @@ impl EditorState {
         }
         let line_len = self.current_line_len();
         if self.cursor_col < line_len {
-            // Extract info before mutating
-            let cur_line = self.cursor_line;
-            let cur_col = self.cursor_col;
-            let (byte_idx, ch) = {
-                let line = &self.buffer[cur_line];
-                let bi = char_to_byte_index(line, cur_col);
-                let c = line[bi..].chars().next().unwrap_or(' ');
-                (bi, c)
-            };
-            self.record_char_delete(cur_line, cur_col, ch);
-            self.buffer[cur_line].remove(byte_idx);
+            let end_col = self.next_grapheme_col();
+            self.delete_cols(self.cursor_col, end_col);
             self.modified = true;
         } else if self.cursor_line + 1 < self.buffer.len() {
             // Join next line with current
             self.flush_group();
             let next_line = self.buffer.remove(self.cursor_line + 1);
-            let join_col = self.buffer[self.cursor_line].len();
+            let join_col = self.buffer[self.cursor_line].chars().count();
             self.buffer[self.cursor_line].push_str(&next_line);
             self.record_action(EditorAction::JoinLine {
                 line: self.cursor_line,
@@ -444,6 +487,19 @@ impl EditorState {
         }
     }
 
+    /// Remove chars `start_col..end_col` of the current line, recording them
+    /// as one delete so a whole grapheme is undone together.
+    fn delete_cols(&mut self, start_col: usize, end_col: usize) {
+        let line = &mut self.buffer[self.cursor_line];
+        let start = char_to_byte_index(line, start_col);
+        let end = char_to_byte_index(line, end_col);
+        let removed: String = line.drain(start..end).collect();
+        // Deletes accumulate at the front of the group, so feed them backwards.
+        for ch in removed.chars().rev() {
+            self.record_char_delete(self.cursor_line, start_col, ch);
+        }
+    }
+
     /// Split the current line at the cursor position (Enter).
     /// Implements auto-indent: copies leading whitespace from the current line.
     /// If there is a selection, delete it first.
@@ -470,7 +526,7 @@ impl EditorState {
             });
 
             self.cursor_line += 1;
-            self.cursor_col = indent.len();
+            self.cursor_col = indent.chars().count();
             self.modified = true;
         }
     }
@@ -497,11 +553,11 @@ impl EditorState {
         }
     }
 
-    /// Move cursor left one character (clears selection).
+    /// Move cursor left one grapheme (clears selection).
     pub fn move_left(&mut self) {
         self.selection = None;
         if self.cursor_col > 0 {
-            self.cursor_col -= 1;
+            self.cursor_col = self.prev_grapheme_col();
         } else if self.cursor_line > 0 {
             self.cursor_line -= 1;
             self.cursor_col = self.current_line_len();
@@ -509,12 +565,12 @@ impl EditorState {
         }
     }
 
-    /// Move cursor right one character (clears selection).
+    /// Move cursor right one grapheme (clears selection).
     pub fn move_right(&mut self) {
         self.selection = None;
         let line_len = self.current_line_len();
         if self.cursor_col < line_len {
-            self.cursor_col += 1;
+            self.cursor_col = self.next_grapheme_col();
         } else if self.cursor_line + 1 < self.buffer.len() {
             self.cursor_line += 1;
             self.cursor_col = 0;
@@ -597,11 +653,11 @@ impl EditorState {
         }
     }
 
-    /// Extend selection left one character.
+    /// Extend selection left one grapheme.
     pub fn select_left(&mut self) {
         self.ensure_selection_anchor();
         if self.cursor_col > 0 {
-            self.cursor_col -= 1;
+            self.cursor_col = self.prev_grapheme_col();
         } else if self.cursor_line > 0 {
             self.cursor_line -= 1;
             self.cursor_col = self.current_line_len();
@@ -609,12 +665,12 @@ impl EditorState {
         }
     }
 
-    /// Extend selection right one character.
+    /// Extend selection right one grapheme.
     pub fn select_right(&mut self) {
         self.ensure_selection_anchor();
         let line_len = self.current_line_len();
         if self.cursor_col < line_len {
-            self.cursor_col += 1;
+            self.cursor_col = self.next_grapheme_col();
         } else if self.cursor_line + 1 < self.buffer.len() {
             self.cursor_line += 1;
             self.cursor_col = 0;
@@ -843,6 +899,57 @@ impl EditorState {
         Ok(())
     }
 
+    /// Tidy the buffer before a save: strip trailing spaces and tabs
+    /// (`trim`, skipped where they are significant, see
+    /// [`whitespace::trailing_ws_significant`]) and end it with exactly one
+    /// newline (`final_newline`). The changes form a single undo step.
+    /// Returns whether anything changed.
+    pub fn normalize_for_save(&mut self, trim: bool, final_newline: bool) -> bool {
+        self.flush_group();
+        let mut actions = Vec::new();
+        if trim && !whitespace::trailing_ws_significant(&self.file_path) {
+            for (i, line) in self.buffer.iter_mut().enumerate() {
+                if let Some(start_col) = whitespace::trailing_ws_start(line) {
+                    let byte_idx = char_to_byte_index(line, start_col);
+                    actions.push(EditorAction::DeleteGroup {
+                        line: i,
+                        start_col,
+                        chars: line[byte_idx..].to_string(),
+                    });
+                    line.truncate(byte_idx);
+                }
+            }
+        }
+        if final_newline {
+            match whitespace::lines_final_newline(&self.buffer) {
+                Some(FinalNewline::Missing) => {
+                    actions.push(EditorAction::InsertLine {
+                        line: self.buffer.len(),
+                        content: String::new(),
+                    });
+                    self.buffer.push(String::new());
+                }
+                Some(FinalNewline::Extra(n)) => {
+                    for _ in 0..n {
+                        self.buffer.pop();
+                        actions.push(EditorAction::RemoveLine {
+                            line: self.buffer.len(),
+                            content: String::new(),
+                        });
+                    }
+                }
+                Some(FinalNewline::Single) | None => {}
+            }
+        }
+        if actions.is_empty() {
+            return false;
+        }
+        self.record_action(EditorAction::Compound { actions });
+        self.modified = true;
+        self.clamp_cursor();
+        true
+    }
+
     // ── Undo/Redo ─────────────────────────────────────────────────────
 
     /// Undo the last action.
@@ -987,7 +1094,7 @@ impl EditorState {
                     self.buffer.insert(*line + 1, new_line);
                 }
                 self.cursor_line = *line + 1;
-                self.cursor_col = indent.len();
+                self.cursor_col = indent.chars().count();
             }
             EditorAction::JoinLine { line, col } => {
                 if *line + 1 < self.buffer.len() {
@@ -1447,6 +1554,78 @@ mod tests {
         assert_eq!(state.cursor_col, 5);
     }
 
+    #[test]
+    fn test_move_steps_over_grapheme_clusters() {
+        // é (2 chars), woman-technologist ZWJ sequence (3 chars), 日 (1 char)
+        let mut state = EditorState::new(
+            "e\u{301}\u{1F469}\u{200D}\u{1F4BB}日",
+            PathBuf::from("/tmp/test.txt"),
+        );
+        let mut stops = vec![state.cursor_col];
+        for _ in 0..3 {
+            state.move_right();
+            stops.push(state.cursor_col);
+        }
+        assert_eq!(stops, vec![0, 2, 5, 6]);
+        state.move_left();
+        assert_eq!(state.cursor_col, 5);
+        state.move_left();
+        assert_eq!(state.cursor_col, 2);
+        state.select_left();
+        assert_eq!(state.selected_text(), "e\u{301}");
+    }
+
+    #[test]
+    fn test_delete_removes_whole_cluster_and_undo_restores_it() {
+        let original = "a\u{1F469}\u{200D}\u{1F4BB}";
+        let mut state = EditorState::new(original, PathBuf::from("/tmp/test.txt"));
+        state.cursor_col = 4;
+        state.delete_char_before();
+        assert_eq!(state.buffer[0], "a");
+        assert_eq!(state.cursor_col, 1);
+        state.undo();
+        assert_eq!(state.buffer[0], original);
+
+        let mut state = EditorState::new("e\u{301}x", PathBuf::from("/tmp/test.txt"));
+        state.delete_char_at();
+        assert_eq!(state.buffer[0], "x");
+    }
+
+    #[test]
+    fn test_cursor_snaps_out_of_cluster_and_counts_chars() {
+        let mut state = EditorState::new("ab\ne\u{301}", PathBuf::from("/tmp/test.txt"));
+        state.cursor_col = 1;
+        state.move_down();
+        assert_eq!(state.cursor_col, 0);
+        state.move_end();
+        assert_eq!(state.cursor_col, 2);
+
+        let mut state = EditorState::new("日本\nx", PathBuf::from("/tmp/test.txt"));
+        state.cursor_line = 1;
+        state.delete_char_before();
+        assert_eq!(state.buffer, vec!["日本x"]);
+        assert_eq!(state.cursor_col, 2);
+        assert_eq!(state.col_at_display(0, 3), 1);
+        assert_eq!(state.col_at_display(0, 9), 3);
+    }
+
+    #[test]
+    fn test_tabs_stay_real_in_buffer_and_copies() {
+        let mut state = EditorState::new("a\tb\n\tc", PathBuf::from("/tmp/test.txt"));
+        state.tab_width = 8;
+        // Clicking anywhere inside the tab lands on it
+        assert_eq!(state.col_at_display(0, 1), 1);
+        assert_eq!(state.col_at_display(0, 7), 1);
+        assert_eq!(state.col_at_display(0, 8), 2);
+        assert_eq!(state.display_col(1, 1), 8);
+
+        state.cursor_line = 1;
+        state.copy_line();
+        assert_eq!(state.editor_clipboard, vec!["\tc"]);
+        state.paste();
+        assert_eq!(state.buffer[2], "\tc");
+    }
+
     #[test]
     fn test_delete_char_at() {
         let mut state = EditorState::new("hello", PathBuf::from("/tmp/test.txt"));
@@ -1623,6 +1802,62 @@ mod tests {
         assert!(!state.modified);
     }
 
+    #[test]
+    fn test_normalize_for_save_trims_and_ends_with_newline() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("tidy.rs");
+        std::fs::write(&path, "fn main() {  \n\tlet x = 1;\t\n}").unwrap();
+        let mut state = EditorState::from_file(&path).unwrap();
+        assert!(state.normalize_for_save(true, true));
+        state.save().unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(content, "fn main() {\n\tlet x = 1;\n}\n");
+        assert!(!state.normalize_for_save(true, true)); // already tidy
+    }
+
+    #[test]
+    fn test_normalize_for_save_drops_extra_newlines() {
+        let mut state = EditorState::new("a\n  \n\n", PathBuf::from("/tmp/test.txt"));
+        assert!(state.normalize_for_save(true, true));
+        assert_eq!(state.buffer, vec!["a", ""]);
+        assert!(state.cursor_line < state.buffer.len());
+    }
+
+    #[test]
+    fn test_normalize_for_save_is_one_undo_step() {
+        let mut state = EditorState::new("one  \ntwo\t", PathBuf::from("/tmp/test.txt"));
+        state.cursor_col = 5;
+        state.insert_char('!');
+        assert!(state.normalize_for_save(true, true));
+        assert_eq!(state.buffer, vec!["one  !", "two", ""]);
+
+        state.undo();
+        assert_eq!(state.buffer, vec!["one  !", "two\t"]);
+        state.undo();
+        assert_eq!(state.buffer, vec!["one  ", "two\t"]);
+        state.redo();
+        state.redo();
+        assert_eq!(state.buffer, vec!["one  !", "two", ""]);
+    }
+
+    #[test]
+    fn test_normalize_for_save_keeps_markdown_line_breaks() {
+        let mut state = EditorState::new("hard  \nbreak", PathBuf::from("/tmp/notes.md"));
+        assert!(!state.normalize_for_save(true, false));
+        assert_eq!(state.buffer, vec!["hard  ", "break"]);
+        // The final newline still applies to Markdown.
+        assert!(state.normalize_for_save(true, true));
+        assert_eq!(state.buffer, vec!["hard  ", "break", ""]);
+    }
+
+    #[test]
+    fn test_normalize_for_save_off_by_default() {
+        let mut state = EditorState::new("x  ", PathBuf::from("/tmp/test.txt"));
+        assert!(!state.normalize_for_save(false, false));
+        assert_eq!(state.buffer, vec!["x  "]);
+        assert!(state.undo_stack.is_empty());
+    }
+
     #[test]
     fn test_find_matches() {
         let mut state = EditorState::new("hello world\nhello rust", PathBuf::from("/tmp/test.txt"));
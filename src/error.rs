@@ -1,8 +1,14 @@
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
 use thiserror::Error;
 
 /// Application-wide result type alias.
 pub type Result<T> = std::result::Result<T, AppError>;
 
+/// Result type for filesystem operations (`fs::operations`).
+pub type OpResult<T> = std::result::Result<T, OperationError>;
+
 /// Application error types.
 #[derive(Debug, Error)]
 pub enum AppError {
@@ -19,6 +25,384 @@ pub enum AppError {
     InvalidPath(String),
 }
 
+/// Typed failure of a file operation (create, rename, delete, copy, move).
+///
+/// Each variant carries the path it concerns so the UI can say which item
+/// failed and suggest what to do about it (see [`OperationError::hint`]).
+#[derive(Debug, Error)]
+pub enum OperationError {
+    #[error("{} not found", .path.display())]
+    NotFound { path: PathBuf },
+
+    #[error("permission denied: {}", .path.display())]
+    PermissionDenied { path: PathBuf },
+
+    #[error("{} already exists", .path.display())]
+    DestinationExists { path: PathBuf },
+
+    #[error("cannot move {} across filesystems", .path.display())]
+    CrossDevice { path: PathBuf },
+
+    #[error("directory not empty: {}", .path.display())]
+    DirectoryNotEmpty { path: PathBuf },
+
+    #[error("no space left on the device containing {}", .path.display())]
+    NoSpace { path: PathBuf },
+
+    /// Another program holds the item open (see [`is_sharing_violation`]).
+    #[error("{} is in use by another program", .path.display())]
+    SharingViolation { path: PathBuf },
+
+    /// A FIFO, socket or device node: reading it could block forever.
+    #[error("{} is a {kind}; its contents can't be copied", .path.display())]
+    SpecialFile { path: PathBuf, kind: &'static str },
+
+    #[error("operation cancelled")]
+    Cancelled,
+
+    #[error("{}: {source}", .path.display())]
+    Other {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+}
+
+impl OperationError {
+    /// Classify an I/O error that occurred while operating on `path`.
+    pub fn from_io(err: std::io::Error, path: &Path) -> Self {
+        let path = path.to_path_buf();
+        if is_sharing_violation(&err) {
+            return OperationError::SharingViolation { path };
+        }
+        match err.kind() {
+            ErrorKind::NotFound => OperationError::NotFound { path },
+            ErrorKind::PermissionDenied | ErrorKind::ReadOnlyFilesystem => {
+                OperationError::PermissionDenied { path }
+            }
+            ErrorKind::AlreadyExists => OperationError::DestinationExists { path },
+            ErrorKind::CrossesDevices => OperationError::CrossDevice { path },
+            ErrorKind::DirectoryNotEmpty => OperationError::DirectoryNotEmpty { path },
+            ErrorKind::StorageFull | ErrorKind::QuotaExceeded => OperationError::NoSpace { path },
+            _ => OperationError::Other { path, source: err },
+        }
+    }
+
+    /// Path the error concerns, if any.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            OperationError::NotFound { path }
+            | OperationError::PermissionDenied { path }
+            | OperationError::DestinationExists { path }
+            | OperationError::CrossDevice { path }
+            | OperationError::DirectoryNotEmpty { path }
+            | OperationError::NoSpace { path }
+            | OperationError::SharingViolation { path }
+            | OperationError::SpecialFile { path, .. }
+            | OperationError::Other { path, .. } => Some(path),
+            OperationError::Cancelled => None,
+        }
+    }
+
+    /// Suggested next step for the user, if there is a useful one.
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            OperationError::NotFound { .. } => {
+                Some("it may have been moved or deleted; press F5 to refresh")
+            }
+            OperationError::PermissionDenied { .. } => {
+                Some("check the owner and permissions of the item and its folder")
+            }
+            OperationError::DestinationExists { .. } => {
+                Some("choose a different name or remove the existing item")
+            }
+            OperationError::CrossDevice { .. } => {
+                Some("use cut and paste, which copies across devices")
+            }
+            OperationError::DirectoryNotEmpty { .. } => Some("remove its contents first"),
+            OperationError::NoSpace { .. } => Some("free up space on that device and retry"),
+            OperationError::SharingViolation { .. } => {
+                Some("close it in the other program, or wait and retry")
+            }
+            OperationError::SpecialFile { .. } => {
+                Some("create a new one at the destination instead")
+            }
+            OperationError::Cancelled | OperationError::Other { .. } => None,
+        }
+    }
+
+    /// Whether retrying may succeed once another program lets go.
+    pub fn is_sharing_violation(&self) -> bool {
+        matches!(self, OperationError::SharingViolation { .. })
+    }
+
+    /// Message for the status bar: the error followed by its hint.
+    pub fn user_message(&self) -> String {
+        match self.hint() {
+            Some(hint) => format!("{} — {}", self, hint),
+            None => self.to_string(),
+        }
+    }
+
+    /// What went wrong, without the path, so failures of many items can
+    /// be counted together.
+    pub fn reason(&self) -> String {
+        match self {
+            OperationError::NotFound { .. } => "not found".to_string(),
+            OperationError::PermissionDenied { .. } => "permission denied".to_string(),
+            OperationError::DestinationExists { .. } => "already exists".to_string(),
+            OperationError::CrossDevice { .. } => "cannot move across filesystems".to_string(),
+            OperationError::DirectoryNotEmpty { .. } => "directory not empty".to_string(),
+            OperationError::NoSpace { .. } => "no space left on the device".to_string(),
+            OperationError::SharingViolation { .. } => "in use by another program".to_string(),
+            OperationError::SpecialFile { kind, .. } => {
+                format!("{}; its contents can't be copied", kind)
+            }
+            OperationError::Cancelled => self.to_string(),
+            OperationError::Other { source, .. } => source.to_string(),
+        }
+    }
+}
+
+/// Whether `err` means another process has the item open: a sharing or
+/// lock violation on Windows, and `EBUSY` or `ETXTBSY` elsewhere.
+fn is_sharing_violation(err: &std::io::Error) -> bool {
+    #[cfg(windows)]
+    {
+        // ERROR_SHARING_VIOLATION, ERROR_LOCK_VIOLATION
+        matches!(err.raw_os_error(), Some(32 | 33))
+    }
+    #[cfg(not(windows))]
+    {
+        matches!(
+            err.kind(),
+            ErrorKind::ResourceBusy | ErrorKind::ExecutableFileBusy
+        )
+    }
+}
+
+/// `fs.inotify.max_user_watches` suggested when the limit is hit, unless
+/// the tree needs more.
+pub const SUGGESTED_INOTIFY_WATCHES: u64 = 524_288;
+
+/// Why the filesystem watcher could not start (or only partly started).
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum WatcherError {
+    /// The per-user inotify watch (or instance) limit ran out.
+    #[error("inotify watch limit reached")]
+    InotifyLimit {
+        /// Current `fs.inotify.max_user_watches`, if readable.
+        current: Option<u64>,
+        /// Estimated watches the tree needs (one per directory).
+        needed: Option<u64>,
+    },
+
+    #[error("permission denied watching {}", .path.display())]
+    PermissionDenied { path: PathBuf },
+
+    #[error("the file system of {} cannot be watched", .path.display())]
+    Unsupported { path: PathBuf },
+
+    #[error("{0}")]
+    Other(String),
+}
+
+impl WatcherError {
+    /// Classify a watcher initialization error. Paths missing from the
+    /// error are reported as `root`.
+    pub fn from_notify(err: &notify::Error, root: &Path) -> Self {
+        let path = err
+            .paths
+            .first()
+            .cloned()
+            .unwrap_or_else(|| root.to_path_buf());
+        let limit = WatcherError::InotifyLimit {
+            current: None,
+            needed: None,
+        };
+        match &err.kind {
+            notify::ErrorKind::MaxFilesWatch => limit,
+            notify::ErrorKind::Io(io) => match io.raw_os_error() {
+                // ENOSPC from inotify_add_watch, EMFILE from inotify_init
+                Some(28) | Some(24) => limit,
+                // EOPNOTSUPP, ENOSYS
+                Some(95) | Some(38) => WatcherError::Unsupported { path },
+                _ => match io.kind() {
+                    ErrorKind::PermissionDenied => WatcherError::PermissionDenied { path },
+                    ErrorKind::Unsupported => WatcherError::Unsupported { path },
+                    _ => WatcherError::Other(err.to_string()),
+                },
+            },
+            notify::ErrorKind::Generic(msg) if msg.to_lowercase().contains("not supported") => {
+                WatcherError::Unsupported { path }
+            }
+            _ => WatcherError::Other(err.to_string()),
+        }
+    }
+
+    /// Fill in the current and needed watch counts of an inotify-limit
+    /// error; other errors are returned unchanged.
+    pub fn with_limits(self, current: Option<u64>, needed: Option<u64>) -> Self {
+        match self {
+            WatcherError::InotifyLimit { .. } => WatcherError::InotifyLimit { current, needed },
+            other => other,
+        }
+    }
+
+    /// Explanation and fix, one line per entry, for the details dialog.
+    pub fn details(&self) -> Vec<String> {
+        match self {
+            WatcherError::InotifyLimit { current, needed } => {
+                let unknown = || "unknown".to_string();
+                let suggested = needed
+                    .map(|n| n.next_power_of_two())
+                    .unwrap_or(0)
+                    .max(SUGGESTED_INOTIFY_WATCHES);
+                vec![
+                    "The kernel ran out of inotify watches (one is needed per directory)."
+                        .to_string(),
+                    format!(
+                        "Current limit: {}",
+                        current.map(|n| n.to_string()).unwrap_or_else(unknown)
+                    ),
+                    format!(
+                        "Needed for this tree: about {}",
+                        needed.map(|n| n.to_string()).unwrap_or_else(unknown)
+                    ),
+                    "Raise it now:".to_string(),
+                    format!("  sudo sysctl fs.inotify.max_user_watches={}", suggested),
+                    "Keep it after a reboot:".to_string(),
+                    format!(
+                        "  echo fs.inotify.max_user_watches={} | sudo tee /etc/sysctl.d/90-inotify.conf",
+                        suggested
+                    ),
+                ]
+            }
+            WatcherError::PermissionDenied { path } => vec![
+                format!("{} cannot be read, so it is not watched.", path.display()),
+                "Check its owner and permissions; the rest of the tree is unaffected.".to_string(),
+            ],
+            WatcherError::Unsupported { path } => vec![
+                format!(
+                    "{} is on a file system that does not report changes",
+                    path.display()
+                ),
+                "(common for network and FUSE mounts). Polling checks for changes instead."
+                    .to_string(),
+            ],
+            WatcherError::Other(msg) => vec![msg.clone()],
+        }
+    }
+}
+
+/// `errors` ordered by directory, then name, then message, with pathless
+/// ones (cancellation) last, so the same failures always read the same
+/// whatever order the operation hit them in.
+pub fn sorted_errors<'a>(
+    errors: impl IntoIterator<Item = &'a OperationError>,
+) -> Vec<&'a OperationError> {
+    let mut sorted: Vec<&OperationError> = errors.into_iter().collect();
+    sorted.sort_by_cached_key(|e| {
+        let path = e.path();
+        (
+            path.is_none(),
+            path.and_then(Path::parent).map(Path::to_path_buf),
+            path.and_then(Path::file_name).map(|n| n.to_os_string()),
+            e.to_string(),
+        )
+    });
+    sorted
+}
+
+/// Failures sharing one [`reason`](OperationError::reason).
+#[derive(Debug)]
+pub struct ErrorGroup<'a> {
+    pub reason: String,
+    /// The failures, in [`sorted_errors`] order.
+    pub errors: Vec<&'a OperationError>,
+}
+
+impl ErrorGroup<'_> {
+    /// The failure itself when it is alone, else its reason and count:
+    /// "permission denied ×12 — check the owner…".
+    pub fn message(&self) -> String {
+        match self.errors.as_slice() {
+            [only] => only.user_message(),
+            errors => {
+                let counted = format!("{} ×{}", self.reason, errors.len());
+                match errors[0].hint() {
+                    Some(hint) => format!("{} — {}", counted, hint),
+                    None => counted,
+                }
+            }
+        }
+    }
+}
+
+/// Group `errors` by reason, in the order each reason first appears in
+/// [`sorted_errors`]. Identical failures of many items become one group.
+pub fn group_errors<'a>(
+    errors: impl IntoIterator<Item = &'a OperationError>,
+) -> Vec<ErrorGroup<'a>> {
+    let mut groups: Vec<ErrorGroup> = Vec::new();
+    for err in sorted_errors(errors) {
+        let reason = err.reason();
+        match groups.iter_mut().find(|g| g.reason == reason) {
+            Some(group) => group.errors.push(err),
+            None => groups.push(ErrorGroup {
+                reason,
+                errors: vec![err],
+            }),
+        }
+    }
+    groups
+}
+
+/// Failures of the items in one directory, for the detailed results.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirErrors {
+    /// Directory holding the items; `None` for failures without a path.
+    pub dir: Option<PathBuf>,
+    /// "name: reason — hint" per item, sorted by name.
+    pub items: Vec<String>,
+}
+
+/// `errors` grouped by the directory of their path, directories sorted.
+pub fn errors_by_dir<'a>(errors: impl IntoIterator<Item = &'a OperationError>) -> Vec<DirErrors> {
+    let mut groups: Vec<DirErrors> = Vec::new();
+    for err in sorted_errors(errors) {
+        let dir = err.path().and_then(Path::parent).map(Path::to_path_buf);
+        let item = match err.path().and_then(Path::file_name) {
+            Some(name) => {
+                let reason = match err.hint() {
+                    Some(hint) => format!("{} — {}", err.reason(), hint),
+                    None => err.reason(),
+                };
+                format!("{}: {}", name.to_string_lossy(), reason)
+            }
+            None => err.user_message(),
+        };
+        match groups.last_mut().filter(|g| g.dir == dir) {
+            Some(group) => group.items.push(item),
+            None => groups.push(DirErrors {
+                dir,
+                items: vec![item],
+            }),
+        }
+    }
+    groups
+}
+
+/// Join several operation errors into one status-bar message: sorted,
+/// with identical failures counted rather than repeated.
+pub fn describe_errors(errors: &[OperationError]) -> String {
+    group_errors(errors)
+        .iter()
+        .map(ErrorGroup::message)
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -42,4 +426,251 @@ mod tests {
         let err = AppError::InvalidPath("/nonexistent".into());
         assert_eq!(err.to_string(), "Invalid path: /nonexistent");
     }
+
+    #[test]
+    fn busy_items_are_sharing_violations() {
+        let path = Path::new("/srv/tree/locked.docx");
+        #[cfg(windows)]
+        let busy = std::io::Error::from_raw_os_error(32);
+        #[cfg(not(windows))]
+        let busy = std::io::Error::from(ErrorKind::ResourceBusy);
+        let err = OperationError::from_io(busy, path);
+        assert!(err.is_sharing_violation());
+        assert_eq!(err.path(), Some(path));
+        assert!(err.user_message().contains("in use by another program"));
+
+        let denied =
+            OperationError::from_io(std::io::Error::from(ErrorKind::PermissionDenied), path);
+        assert!(!denied.is_sharing_violation());
+    }
+
+    #[test]
+    fn watcher_errors_are_classified() {
+        let root = Path::new("/srv/tree");
+        let limit = |err: notify::Error| {
+            matches!(
+                WatcherError::from_notify(&err, root),
+                WatcherError::InotifyLimit { .. }
+            )
+        };
+        assert!(limit(notify::Error::new(notify::ErrorKind::MaxFilesWatch)));
+        assert!(limit(notify::Error::io(std::io::Error::from_raw_os_error(
+            28
+        ))));
+        assert!(limit(notify::Error::io(std::io::Error::from_raw_os_error(
+            24
+        ))));
+
+        let denied = notify::Error::io(io(ErrorKind::PermissionDenied))
+            .add_path(PathBuf::from("/srv/tree/private"));
+        assert_eq!(
+            WatcherError::from_notify(&denied, root),
+            WatcherError::PermissionDenied {
+                path: PathBuf::from("/srv/tree/private")
+            }
+        );
+
+        let unsupported = notify::Error::io(std::io::Error::from_raw_os_error(95));
+        assert_eq!(
+            WatcherError::from_notify(&unsupported, root),
+            WatcherError::Unsupported {
+                path: root.to_path_buf()
+            }
+        );
+        let generic = notify::Error::generic("operation not supported on this mount");
+        assert!(matches!(
+            WatcherError::from_notify(&generic, root),
+            WatcherError::Unsupported { .. }
+        ));
+
+        let other = notify::Error::path_not_found();
+        assert!(matches!(
+            WatcherError::from_notify(&other, root),
+            WatcherError::Other(_)
+        ));
+    }
+
+    #[test]
+    fn inotify_limit_details_suggest_a_sysctl() {
+        let err = WatcherError::InotifyLimit {
+            current: None,
+            needed: None,
+        }
+        .with_limits(Some(8192), Some(700_000));
+        let details = err.details();
+        assert!(details.contains(&"Current limit: 8192".to_string()));
+        assert!(details.contains(&"Needed for this tree: about 700000".to_string()));
+        // Rounded up past the tree's needs
+        assert!(details.contains(&"  sudo sysctl fs.inotify.max_user_watches=1048576".to_string()));
+
+        // Small trees get the usual suggestion
+        let small = WatcherError::InotifyLimit {
+            current: Some(8192),
+            needed: Some(9000),
+        };
+        assert!(small
+            .details()
+            .iter()
+            .any(|l| l.ends_with("max_user_watches=524288")));
+        // Other errors keep their shape
+        let other = WatcherError::Other("x".into()).with_limits(Some(1), Some(2));
+        assert_eq!(other, WatcherError::Other("x".into()));
+    }
+
+    fn io(kind: ErrorKind) -> std::io::Error {
+        std::io::Error::new(kind, "boom")
+    }
+
+    #[test]
+    fn io_kind_maps_to_variant() {
+        let p = Path::new("/data/x");
+        let cases = [
+            (ErrorKind::NotFound, "NotFound"),
+            (ErrorKind::PermissionDenied, "PermissionDenied"),
+            (ErrorKind::AlreadyExists, "DestinationExists"),
+            (ErrorKind::CrossesDevices, "CrossDevice"),
+            (ErrorKind::DirectoryNotEmpty, "DirectoryNotEmpty"),
+            (ErrorKind::StorageFull, "NoSpace"),
+            (ErrorKind::InvalidData, "Other"),
+        ];
+        for (kind, expected) in cases {
+            let err = OperationError::from_io(io(kind), p);
+            let debug = format!("{:?}", err);
+            assert!(
+                debug.starts_with(expected),
+                "{:?} mapped to {}",
+                kind,
+                debug
+            );
+            assert_eq!(err.path(), Some(p));
+        }
+    }
+
+    #[test]
+    fn hints_are_rendered_with_path() {
+        let p = Path::new("/mnt/usb/big.iso");
+        let err = OperationError::from_io(io(ErrorKind::StorageFull), p);
+        assert_eq!(
+            err.user_message(),
+            "no space left on the device containing /mnt/usb/big.iso — free up space on that device and retry"
+        );
+
+        let err = OperationError::from_io(io(ErrorKind::AlreadyExists), Path::new("/d/a.txt"));
+        assert_eq!(
+            err.user_message(),
+            "/d/a.txt already exists — choose a different name or remove the existing item"
+        );
+
+        let err = OperationError::from_io(io(ErrorKind::PermissionDenied), Path::new("/root/x"));
+        assert!(err
+            .user_message()
+            .starts_with("permission denied: /root/x — "));
+    }
+
+    #[test]
+    fn other_errors_keep_source_and_have_no_hint() {
+        let err = OperationError::from_io(io(ErrorKind::InvalidData), Path::new("/d/f"));
+        assert_eq!(err.hint(), None);
+        assert_eq!(err.user_message(), "/d/f: boom");
+        assert_eq!(
+            OperationError::Cancelled.user_message(),
+            "operation cancelled"
+        );
+    }
+
+    #[test]
+    fn describe_errors_joins_messages() {
+        let errors = vec![
+            OperationError::NotFound {
+                path: PathBuf::from("/a"),
+            },
+            OperationError::Cancelled,
+        ];
+        let msg = describe_errors(&errors);
+        assert!(msg.starts_with("/a not found — "));
+        assert!(msg.ends_with("; operation cancelled"));
+    }
+
+    fn denied(path: &str) -> OperationError {
+        OperationError::PermissionDenied {
+            path: PathBuf::from(path),
+        }
+    }
+
+    fn missing(path: &str) -> OperationError {
+        OperationError::NotFound {
+            path: PathBuf::from(path),
+        }
+    }
+
+    fn full(path: &str) -> OperationError {
+        OperationError::NoSpace {
+            path: PathBuf::from(path),
+        }
+    }
+
+    const DENIED: &str = "check the owner and permissions of the item and its folder";
+    const MISSING: &str = "it may have been moved or deleted; press F5 to refresh";
+
+    #[test]
+    fn describe_errors_sorts_and_counts_repeats() {
+        let cases: Vec<(Vec<OperationError>, String)> = vec![
+            (vec![], String::new()),
+            (
+                vec![missing("/b"), missing("/a")],
+                format!("not found ×2 — {}", MISSING),
+            ),
+            (
+                vec![OperationError::Cancelled, denied("/x")],
+                format!("permission denied: /x — {}; operation cancelled", DENIED),
+            ),
+            (
+                vec![denied("/d/b"), full("/d/a"), denied("/c/z")],
+                format!(
+                    "permission denied ×2 — {}; no space left on the device containing /d/a — \
+                     free up space on that device and retry",
+                    DENIED
+                ),
+            ),
+        ];
+        for (mut errors, expected) in cases {
+            assert_eq!(describe_errors(&errors), expected);
+            // The order the operation hit them in makes no difference
+            errors.reverse();
+            assert_eq!(describe_errors(&errors), expected);
+        }
+    }
+
+    #[test]
+    fn errors_are_grouped_by_directory() {
+        let errors = vec![
+            denied("/d/b"),
+            OperationError::Cancelled,
+            missing("/c/z"),
+            denied("/d/a"),
+            denied("/d/sub/x"),
+        ];
+        let dir = |d: &str, items: &[String]| DirErrors {
+            dir: Some(PathBuf::from(d)),
+            items: items.to_vec(),
+        };
+        assert_eq!(
+            errors_by_dir(&errors),
+            vec![
+                dir("/c", &[format!("z: not found — {}", MISSING)]),
+                dir(
+                    "/d",
+                    &[
+                        format!("a: permission denied — {}", DENIED),
+                        format!("b: permission denied — {}", DENIED),
+                    ]
+                ),
+                dir("/d/sub", &[format!("x: permission denied — {}", DENIED)]),
+                DirErrors {
+                    dir: None,
+                    items: vec!["operation cancelled".to_string()],
+                },
+            ]
+        );
+    }
 }
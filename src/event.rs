@@ -1,12 +1,33 @@
 use std::path::PathBuf;
-use std::time::Duration;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use crossterm::event::{self, Event as CrosstermEvent, KeyEvent, MouseEvent};
 use tokio::sync::mpsc;
 
-use crate::error::Result;
+#[cfg(feature = "archives")]
+use crate::archive::ArchiveError;
+use crate::blame::BlameResult;
+use crate::dir_size::DirSize;
+use crate::error::{OpResult, OperationError, Result};
+use crate::git_status::GitStatusResult;
+use crate::grep::GrepBatch;
+use crate::idle_refresh::IdleRefreshResult;
+use crate::path_index::{IndexBatch, PathIndex};
+use crate::prefetch::PrefetchResult;
+use crate::stat_service::StatBatch;
+use crate::system_clipboard::ClipboardReport;
+use crate::work_queue::WorkId;
 
+use crate::fs::operations::DiskUsage;
+use crate::fs::overwrite::Backup;
+use crate::fs::portability::Report as PortabilityReport;
+use crate::fs::retry::RetryOp;
+use crate::fs::scaffold::{Applied, ScaffoldError};
+use crate::fs::trash::TrashedEntry;
 use crate::fs::tree::DirSnapshot;
+use crate::fs::watcher::FsEvent;
 
 /// Progress update from an async file operation.
 #[derive(Debug, Clone)]
@@ -15,8 +36,15 @@ pub struct ProgressUpdate {
     pub current_file: String,
     /// Index of current item (1-based).
     pub current: usize,
-    /// Total number of items.
-    pub total: usize,
+    /// Total number of items; `None` while it isn't known, in which case
+    /// the progress dialog shows a spinner and a running count instead of
+    /// a bar.
+    pub total: Option<usize>,
+    /// Bytes written so far; for an extraction, archive bytes read.
+    pub bytes_done: u64,
+    /// Bytes the operation will write; 0 when unknown or nothing is
+    /// copied (deletes).
+    pub bytes_total: u64,
 }
 
 /// Result of a completed async operation.
@@ -24,17 +52,92 @@ pub struct ProgressUpdate {
 pub struct OperationResult {
     /// Number of successfully processed items.
     pub success_count: usize,
-    /// Error messages, if any.
-    pub errors: Vec<String>,
+    /// Per-item failures, if any.
+    pub errors: Vec<OperationError>,
     /// Paths that were created (for undo support).
-    #[allow(dead_code)]
     pub created_paths: Vec<PathBuf>,
+    /// Created paths that replaced or merged into an item already there.
+    pub replaced: Vec<PathBuf>,
+    /// Files moved out of the way before being overwritten.
+    pub backups: Vec<Backup>,
+    /// Sources whose copy or move failed (for retrying them).
+    pub failed_sources: Vec<PathBuf>,
     /// Source paths that were involved (for tree refresh).
     pub source_paths: Vec<PathBuf>,
     /// Destination directory.
     pub dest_dir: PathBuf,
     /// Whether this was a cut (move) operation.
     pub was_cut: bool,
+    /// Name of the "send to" target, if this came from the send-to overlay
+    /// rather than a clipboard paste.
+    pub send_to: Option<String>,
+    /// How long the operation ran.
+    pub elapsed: Duration,
+}
+
+/// Result of a completed async delete.
+#[derive(Debug)]
+pub struct DeleteResult {
+    /// Every target of the delete, in the order they were tried.
+    pub targets: Vec<PathBuf>,
+    /// Targets that were removed.
+    pub deleted: Vec<PathBuf>,
+    /// How to put back the targets that went to the trash rather than
+    /// being removed.
+    pub trashed: Vec<TrashedEntry>,
+    /// Per-item failures, ending with `Cancelled` if the run was stopped.
+    pub errors: Vec<OperationError>,
+    /// The targets were meant for the trash.
+    pub to_trash: bool,
+}
+
+/// Result of retrying a rename or delete that hit a sharing violation.
+#[derive(Debug)]
+pub struct RetryResult {
+    /// Retry the result comes from.
+    pub job: u64,
+    /// The operation, less the delete targets that are gone.
+    pub op: RetryOp,
+    /// Delete targets removed by the retry.
+    pub deleted: Vec<PathBuf>,
+    /// How to put back the targets the retry moved to the trash.
+    pub trashed: Vec<TrashedEntry>,
+    pub result: OpResult<()>,
+}
+
+/// Result of duplicating items next to themselves.
+#[derive(Debug)]
+pub struct DuplicateResult {
+    /// Items to duplicate, in the order they were tried.
+    pub sources: Vec<PathBuf>,
+    /// The copies made.
+    pub created: Vec<PathBuf>,
+    /// Per-item failures, ending with `Cancelled` if the run was stopped.
+    pub errors: Vec<OperationError>,
+}
+
+/// Result of a completed archive extraction.
+#[cfg(feature = "archives")]
+#[derive(Debug)]
+pub struct ExtractResult {
+    pub archive: PathBuf,
+    /// Directory the archive was extracted into.
+    pub target: PathBuf,
+    /// Entries written, or why the extraction stopped; the partial tree is
+    /// removed on failure.
+    pub result: std::result::Result<usize, ArchiveError>,
+}
+
+/// Result of a completed scaffold instantiation.
+#[derive(Debug)]
+pub struct ScaffoldResult {
+    /// The scaffold's display name.
+    pub name: String,
+    /// Directory the scaffold was instantiated into.
+    pub dest: PathBuf,
+    /// What was created, or why it stopped; whatever was created is
+    /// removed on failure.
+    pub result: std::result::Result<Applied, ScaffoldError>,
 }
 
 /// Application events.
@@ -44,6 +147,8 @@ pub enum Event {
     Key(KeyEvent),
     /// A mouse event.
     Mouse(MouseEvent),
+    /// Text pasted into the terminal (bracketed paste).
+    Paste(String),
     /// A periodic tick for rendering.
     Tick,
     /// Terminal resize event.
@@ -53,8 +158,25 @@ pub enum Event {
     Progress(ProgressUpdate),
     /// Async file operation completed.
     OperationComplete(OperationResult),
+    /// Async delete completed.
+    DeleteComplete(DeleteResult),
+    /// A retry of a rename or delete is about to make attempt `attempt`.
+    RetryAttempt { job: u64, attempt: usize },
+    /// A retry of a rename or delete finished.
+    RetryComplete(RetryResult),
+    /// Async duplicate completed.
+    DuplicateComplete(DuplicateResult),
+    /// Async archive extraction completed.
+    #[cfg(feature = "archives")]
+    ExtractComplete(ExtractResult),
+    /// Async scaffold instantiation completed.
+    ScaffoldComplete(ScaffoldResult),
+    /// Matches found by the running content search since its last batch.
+    GrepResults(GrepBatch),
     /// Filesystem change detected by watcher.
     FsChange(Vec<PathBuf>),
+    /// What the watcher saw happen during one debounce window.
+    FsEvents(Vec<FsEvent>),
     /// Raw output from the embedded terminal PTY.
     TerminalOutput(Vec<u8>),
     /// Async directory snapshot collection completed.
@@ -66,21 +188,55 @@ pub enum Event {
     /// Async directory child count completed.
     #[allow(dead_code)]
     DirCountComplete { path: PathBuf, count: usize },
-    /// Async directory summary update (streaming).
-    #[allow(dead_code)]
-    DirSummaryUpdate {
+    /// Running or final total from a directory size walk.
+    DirSizeUpdate {
         path: PathBuf,
-        files: u64,
-        dirs: u64,
-        size: u64,
-        done: bool,
+        /// Walk the total comes from.
+        job: u64,
+        size: DirSize,
     },
+    /// Background git blame fetch for the preview completed.
+    BlameComplete(BlameResult),
+    /// Background `git status` run for the tree markers completed.
+    GitStatusComplete(GitStatusResult),
+    /// Fuzzy finder re-index with the raised cap finished, for the root.
+    IndexComplete(PathBuf, PathIndex),
+    /// Paths found by the fuzzy finder's background index.
+    IndexBatch(IndexBatch),
+    /// Background refresh of a stale paginated directory completed.
+    IdleRefreshComplete(IdleRefreshResult),
+    /// Background system clipboard write finished.
+    ClipboardComplete(ClipboardReport),
+    /// Background session autosave finished.
+    AutosaveComplete(std::result::Result<(), String>),
+    /// Preview load `generation` finished on its thread; stale
+    /// generations are ignored.
+    PreviewReady { generation: u64 },
+    /// Another chunk of preview load `generation` was highlighted.
+    PreviewHighlighted { generation: u64 },
+    /// Background preview prefetch for a neighbouring file finished.
+    PrefetchComplete(PrefetchResult),
+    /// A batch of deferred tree-row stats finished.
+    StatsReady(StatBatch),
+    /// Pre-flight count of a paste or delete finished; `id` tells it apart
+    /// from counts that were superseded.
+    PreflightComplete { id: u64, usage: DiskUsage },
+    /// Portability check of a directory finished; `id` tells it apart from
+    /// checks that were superseded.
+    PortabilityComplete { id: u64, report: PortabilityReport },
+    /// Time for the next slice of a queued tree job.
+    ContinueWork(WorkId),
 }
 
 /// Async event handler that polls crossterm events and forwards them via a channel.
 pub struct EventHandler {
     rx: mpsc::UnboundedReceiver<Event>,
     tx: mpsc::UnboundedSender<Event>,
+    tick_rate: Duration,
+    /// Set while a foreground program owns the terminal.
+    paused: Arc<AtomicBool>,
+    /// Set by the reader once it has stopped reading for `paused`.
+    idle: Arc<AtomicBool>,
 }
 
 impl EventHandler {
@@ -88,27 +244,28 @@ impl EventHandler {
     pub fn new(tick_rate: Duration) -> Self {
         let (tx, rx) = mpsc::unbounded_channel();
         let event_tx = tx.clone();
+        let paused = Arc::new(AtomicBool::new(false));
+        let idle = Arc::new(AtomicBool::new(false));
+        let (reader_paused, reader_idle) = (Arc::clone(&paused), Arc::clone(&idle));
 
         tokio::spawn(async move {
             loop {
+                if reader_paused.load(Ordering::Acquire) {
+                    reader_idle.store(true, Ordering::Release);
+                    std::thread::sleep(tick_rate);
+                    continue;
+                }
+                reader_idle.store(false, Ordering::Release);
                 if event::poll(tick_rate).unwrap_or(false) {
-                    match event::read() {
-                        Ok(CrosstermEvent::Key(key)) => {
-                            if event_tx.send(Event::Key(key)).is_err() {
-                                break;
-                            }
-                        }
-                        Ok(CrosstermEvent::Mouse(mouse)) => {
-                            if event_tx.send(Event::Mouse(mouse)).is_err() {
-                                break;
-                            }
-                        }
-                        Ok(CrosstermEvent::Resize(w, h)) => {
-                            if event_tx.send(Event::Resize(w, h)).is_err() {
-                                break;
-                            }
-                        }
-                        _ => {}
+                    let forwarded = match event::read() {
+                        Ok(CrosstermEvent::Key(key)) => Event::Key(key),
+                        Ok(CrosstermEvent::Mouse(mouse)) => Event::Mouse(mouse),
+                        Ok(CrosstermEvent::Paste(text)) => Event::Paste(text),
+                        Ok(CrosstermEvent::Resize(w, h)) => Event::Resize(w, h),
+                        _ => continue,
+                    };
+                    if event_tx.send(forwarded).is_err() {
+                        break;
                     }
                 } else if event_tx.send(Event::Tick).is_err() {
                     break;
@@ -116,7 +273,28 @@ impl EventHandler {
             }
         });
 
-        Self { rx, tx }
+        Self {
+            rx,
+            tx,
+            tick_rate,
+            paused,
+            idle,
+        }
+    }
+
+    /// Stop reading the terminal so a foreground program gets the
+    /// keystrokes, waiting out a read already in progress.
+    pub fn pause_input(&self) {
+        self.paused.store(true, Ordering::Release);
+        let deadline = Instant::now() + self.tick_rate * 4;
+        while !self.idle.load(Ordering::Acquire) && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    /// Read the terminal again after `pause_input`.
+    pub fn resume_input(&self) {
+        self.paused.store(false, Ordering::Release);
     }
 
     /// Get a sender clone for async tasks to send progress/completion events.
@@ -124,6 +302,11 @@ impl EventHandler {
         self.tx.clone()
     }
 
+    /// Whether more events are already queued.
+    pub fn has_pending(&self) -> bool {
+        !self.rx.is_empty()
+    }
+
     /// Receive the next event (blocks until available).
     pub async fn next(&mut self) -> Result<Event> {
         self.rx
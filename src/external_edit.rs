@@ -0,0 +1,84 @@
+//! Editing the selected file in `$EDITOR` (`o` / Ctrl+O).
+//!
+//! `App::request_external_edit` only records the file. The main loop picks
+//! it up after the key is handled: it pauses the input reader and the
+//! watcher, suspends the TUI, runs the editor in the foreground with
+//! [`run`], then restores everything and hands the outcome to
+//! `App::finish_external_edit`, which reloads the file's preview and
+//! directory.
+
+use std::io;
+use std::path::Path;
+use std::process::Command;
+
+/// Editor used when `$EDITOR` is unset or blank.
+pub const FALLBACK_EDITOR: &str = "vi";
+
+/// Program and arguments for the `$EDITOR` value `editor`: split on
+/// whitespace, so `code --wait` works.
+pub fn editor_command(editor: Option<&str>) -> Vec<String> {
+    let words: Vec<String> = editor
+        .unwrap_or_default()
+        .split_whitespace()
+        .map(str::to_string)
+        .collect();
+    if words.is_empty() {
+        vec![FALLBACK_EDITOR.to_string()]
+    } else {
+        words
+    }
+}
+
+/// Run `$EDITOR` on `path` and wait for it to exit.
+pub fn run(path: &Path) -> Result<(), String> {
+    let editor = std::env::var("EDITOR").ok();
+    run_command(&editor_command(editor.as_deref()), path)
+}
+
+/// Run `command` followed by `path`. Errors read as status messages.
+fn run_command(command: &[String], path: &Path) -> Result<(), String> {
+    let (program, args) = command
+        .split_first()
+        .expect("editor command is never empty");
+    let status = Command::new(program)
+        .args(args)
+        .arg(path)
+        .status()
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => format!("Editor `{}` not found (set $EDITOR)", program),
+            _ => format!("Cannot start `{}`: {}", program, e),
+        })?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("`{}` exited with {}", program, status))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn editor_falls_back_to_vi() {
+        assert_eq!(editor_command(None), ["vi"]);
+        assert_eq!(editor_command(Some("  ")), ["vi"]);
+        assert_eq!(editor_command(Some("code --wait")), ["code", "--wait"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn reports_how_the_editor_ended() {
+        let path = Path::new("/dev/null");
+        assert_eq!(run_command(&["true".to_string()], path), Ok(()));
+
+        let failed = run_command(&["false".to_string()], path).unwrap_err();
+        assert!(failed.starts_with("`false` exited with"), "{}", failed);
+
+        let missing = run_command(&["fm-no-such-editor".to_string()], path).unwrap_err();
+        assert_eq!(
+            missing,
+            "Editor `fm-no-such-editor` not found (set $EDITOR)"
+        );
+    }
+}
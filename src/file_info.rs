@@ -0,0 +1,367 @@
+//! The file info popup (`i`): what the filesystem records about the
+//! selected entry.
+//!
+//! `App::open_file_info` reads a [`FileInfo`] once and shows it as
+//! `DialogKind::Info`. A directory's recursive size comes from the
+//! `dir_size` walk: a known total is shown at once, otherwise the walk is
+//! started and its running totals replace the "(calculating…)" placeholder
+//! through [`FileInfo::set_dir_size`]. Times are shown in UTC, as there is
+//! no timezone database to convert them with.
+
+use std::fs::{self, Metadata};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::dir_size::DirSize;
+use crate::fs::tree::SpecialFile;
+use crate::preview_content::{self, epoch_days_to_date, format_size};
+
+/// Shown in place of a directory size still being computed.
+pub const CALCULATING: &str = "(calculating…)";
+
+/// Label and value rows describing one entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileInfo {
+    pub rows: Vec<(&'static str, String)>,
+    /// Row holding a directory's recursive size.
+    size_row: Option<usize>,
+}
+
+impl FileInfo {
+    /// Describe `path`. `children` is the directory's child count when the
+    /// tree already knows it; `size` its recursive size, if computed.
+    pub fn read(
+        path: &Path,
+        children: Option<usize>,
+        size: Option<&DirSize>,
+        apparent: bool,
+    ) -> FileInfo {
+        let mut rows = vec![("Path", path.display().to_string())];
+        let link = match fs::symlink_metadata(path) {
+            Ok(link) => link,
+            Err(e) => {
+                rows.push(("Error", e.to_string()));
+                return FileInfo {
+                    rows,
+                    size_row: None,
+                };
+            }
+        };
+        // Links are described by what they point at, when it exists
+        let target = link
+            .file_type()
+            .is_symlink()
+            .then(|| fs::read_link(path).ok())
+            .flatten();
+        let followed = fs::metadata(path);
+        let broken = followed.is_err();
+        let meta = followed.unwrap_or(link);
+        let is_dir = meta.is_dir();
+
+        rows.push(("Type", kind(&meta, target.is_some(), broken)));
+        if let Some(target) = &target {
+            let note = if broken { " (broken)" } else { "" };
+            rows.push(("Target", format!("{}{}", target.display(), note)));
+        }
+        let mut size_row = None;
+        if is_dir {
+            let count = children.or_else(|| fs::read_dir(path).ok().map(|rd| rd.count()));
+            rows.push((
+                "Children",
+                count.map_or_else(|| "unreadable".to_string(), |n| n.to_string()),
+            ));
+            size_row = Some(rows.len());
+            rows.push((
+                "Size",
+                size.map_or_else(|| CALCULATING.to_string(), |s| s.detail(apparent)),
+            ));
+        } else {
+            rows.push(("Size", file_size(meta.len())));
+        }
+        rows.push(("Modified", time(meta.modified().ok())));
+        rows.push(("Accessed", time(meta.accessed().ok())));
+        rows.push(("Created", time(meta.created().ok())));
+        platform_rows(&meta, &mut rows);
+        rows.push(("MIME", mime_guess(path, &meta)));
+        FileInfo { rows, size_row }
+    }
+
+    /// Show a directory's running or final recursive size.
+    pub fn set_dir_size(&mut self, size: &DirSize, apparent: bool) {
+        if let Some(row) = self.size_row {
+            self.rows[row].1 = size.detail(apparent);
+        }
+    }
+
+    /// Value of the row labelled `label`.
+    #[cfg(test)]
+    pub fn get(&self, label: &str) -> Option<&str> {
+        self.rows
+            .iter()
+            .find(|(l, _)| *l == label)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// "directory", "file", or "symlink → directory" and the like.
+fn kind(meta: &Metadata, is_link: bool, broken: bool) -> String {
+    if is_link && broken {
+        return "symlink".to_string();
+    }
+    let kind = if meta.is_dir() {
+        "directory"
+    } else if let Some(special) = SpecialFile::of(meta.file_type()) {
+        special.label()
+    } else if meta.is_file() {
+        "file"
+    } else {
+        "special file"
+    };
+    if is_link {
+        format!("symlink → {}", kind)
+    } else {
+        kind.to_string()
+    }
+}
+
+/// "1,234 bytes (1.21 KB)", or just the bytes below a kilobyte.
+fn file_size(bytes: u64) -> String {
+    let raw = format!(
+        "{} byte{}",
+        crate::components::dialog::group_thousands(bytes as usize),
+        if bytes == 1 { "" } else { "s" }
+    );
+    if bytes < 1024 {
+        raw
+    } else {
+        format!("{} ({})", raw, format_size(bytes))
+    }
+}
+
+/// "2024-03-05 14:07:09 UTC", or "unavailable" when the filesystem
+/// doesn't record it.
+fn time(time: Option<SystemTime>) -> String {
+    let Some(secs) = time
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+    else {
+        return "unavailable".to_string();
+    };
+    let (year, month, day) = epoch_days_to_date(secs / 86_400);
+    let rem = secs % 86_400;
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        rem / 3600,
+        (rem % 3600) / 60,
+        rem % 60
+    )
+}
+
+/// Permissions, owner, group, inode and link count.
+#[cfg(unix)]
+fn platform_rows(meta: &Metadata, rows: &mut Vec<(&'static str, String)>) {
+    use std::os::unix::fs::MetadataExt;
+    let mode = meta.mode();
+    rows.push((
+        "Permissions",
+        format!(
+            "{} ({:04o})",
+            preview_content::format_permissions(mode),
+            mode & 0o7777
+        ),
+    ));
+    let passwd = fs::read_to_string("/etc/passwd").unwrap_or_default();
+    let group = fs::read_to_string("/etc/group").unwrap_or_default();
+    rows.push(("Owner", named_id(&passwd, meta.uid())));
+    rows.push(("Group", named_id(&group, meta.gid())));
+    rows.push(("Inode", meta.ino().to_string()));
+    rows.push(("Links", meta.nlink().to_string()));
+}
+
+#[cfg(not(unix))]
+fn platform_rows(meta: &Metadata, rows: &mut Vec<(&'static str, String)>) {
+    let access = if meta.permissions().readonly() {
+        "read-only"
+    } else {
+        "read-write"
+    };
+    rows.push(("Permissions", access.to_string()));
+}
+
+/// "alice (1000)" for id 1000 named in `table` (`/etc/passwd` or
+/// `/etc/group` lines), or just the id.
+#[cfg_attr(not(unix), allow(dead_code))]
+fn named_id(table: &str, id: u32) -> String {
+    let name = table.lines().find_map(|line| {
+        let mut fields = line.split(':');
+        let name = fields.next()?;
+        let id_field = fields.nth(1)?;
+        (id_field.parse::<u32>().ok() == Some(id)).then_some(name)
+    });
+    match name {
+        Some(name) => format!("{} ({})", name, id),
+        None => id.to_string(),
+    }
+}
+
+/// MIME type by extension, falling back to a text/binary sniff.
+fn mime_guess(path: &Path, meta: &Metadata) -> String {
+    if meta.is_dir() {
+        return "inode/directory".to_string();
+    }
+    if let Some(special) = SpecialFile::of(meta.file_type()) {
+        let mime = match special {
+            SpecialFile::Fifo => "inode/fifo",
+            SpecialFile::Socket => "inode/socket",
+            SpecialFile::BlockDevice => "inode/blockdevice",
+            SpecialFile::CharDevice => "inode/chardevice",
+        };
+        return mime.to_string();
+    }
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_ascii_lowercase());
+    let known = ext.as_deref().and_then(|ext| {
+        MIME_TYPES
+            .iter()
+            .find(|(exts, _)| exts.contains(&ext))
+            .map(|(_, mime)| *mime)
+    });
+    let mime = match known {
+        Some(mime) => mime,
+        None if !meta.is_file() => "application/octet-stream",
+        None if preview_content::is_binary_file(path) => "application/octet-stream",
+        None => "text/plain",
+    };
+    mime.to_string()
+}
+
+/// Extensions and their MIME types.
+const MIME_TYPES: &[(&[&str], &str)] = &[
+    (&["txt", "log"], "text/plain"),
+    (&["md", "markdown"], "text/markdown"),
+    (&["html", "htm"], "text/html"),
+    (&["css"], "text/css"),
+    (&["csv"], "text/csv"),
+    (&["js", "mjs"], "text/javascript"),
+    (&["rs"], "text/x-rust"),
+    (&["py"], "text/x-python"),
+    (&["c", "h"], "text/x-c"),
+    (&["sh"], "application/x-sh"),
+    (&["json"], "application/json"),
+    (&["ipynb"], "application/x-ipynb+json"),
+    (&["toml"], "application/toml"),
+    (&["yaml", "yml"], "application/yaml"),
+    (&["xml"], "application/xml"),
+    (&["pdf"], "application/pdf"),
+    (&["zip"], "application/zip"),
+    (&["tar"], "application/x-tar"),
+    (&["gz", "tgz"], "application/gzip"),
+    (&["png"], "image/png"),
+    (&["jpg", "jpeg"], "image/jpeg"),
+    (&["gif"], "image/gif"),
+    (&["svg"], "image/svg+xml"),
+    (&["webp"], "image/webp"),
+    (&["mp3"], "audio/mpeg"),
+    (&["wav"], "audio/wav"),
+    (&["mp4"], "video/mp4"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dir_size::Progress;
+    use tempfile::TempDir;
+
+    #[test]
+    fn describes_a_file() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("notes.md");
+        fs::write(&path, vec![b'x'; 2048]).unwrap();
+        let info = FileInfo::read(&path, None, None, false);
+
+        assert_eq!(info.get("Path"), Some(path.display().to_string().as_str()));
+        assert_eq!(info.get("Type"), Some("file"));
+        assert_eq!(info.get("Size"), Some("2,048 bytes (2.00 KB)"));
+        assert_eq!(info.get("MIME"), Some("text/markdown"));
+        assert_eq!(info.get("Children"), None);
+        assert!(info.get("Modified").unwrap().ends_with(" UTC"));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+            let info = FileInfo::read(&path, None, None, false);
+            assert_eq!(info.get("Permissions"), Some("rw-r----- (0640)"));
+            assert_eq!(info.get("Links"), Some("1"));
+            assert!(info.get("Inode").is_some_and(|i| i.parse::<u64>().is_ok()));
+        }
+    }
+
+    #[test]
+    fn directories_count_children_and_wait_for_their_size() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("a"), "").unwrap();
+        fs::write(dir.path().join("b"), "").unwrap();
+        let mut info = FileInfo::read(dir.path(), None, None, false);
+        assert_eq!(info.get("Type"), Some("directory"));
+        assert_eq!(info.get("Children"), Some("2"));
+        assert_eq!(info.get("Size"), Some(CALCULATING));
+        assert_eq!(info.get("MIME"), Some("inode/directory"));
+
+        let size = DirSize {
+            bytes: 2048,
+            unique_bytes: 2048,
+            files: 2,
+            dirs: 1,
+            progress: Progress::Done,
+        };
+        info.set_dir_size(&size, false);
+        assert_eq!(info.get("Size"), Some("2.00 KB"));
+
+        // The tree's cached count is used as is
+        let cached = FileInfo::read(dir.path(), Some(7), Some(&size), false);
+        assert_eq!(cached.get("Children"), Some("7"));
+        assert_eq!(cached.get("Size"), Some("2.00 KB"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_show_their_target() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir(dir.path().join("real")).unwrap();
+        std::os::unix::fs::symlink("real", dir.path().join("link")).unwrap();
+        std::os::unix::fs::symlink("gone", dir.path().join("dangling")).unwrap();
+
+        let info = FileInfo::read(&dir.path().join("link"), None, None, false);
+        assert_eq!(info.get("Type"), Some("symlink → directory"));
+        assert_eq!(info.get("Target"), Some("real"));
+        let broken = FileInfo::read(&dir.path().join("dangling"), None, None, false);
+        assert_eq!(broken.get("Type"), Some("symlink"));
+        assert_eq!(broken.get("Target"), Some("gone (broken)"));
+
+        let missing = FileInfo::read(&dir.path().join("nothing"), None, None, false);
+        assert_eq!(missing.rows.len(), 2);
+        assert!(missing.get("Error").is_some());
+    }
+
+    #[test]
+    fn ids_are_named_from_the_tables() {
+        let passwd = "root:x:0:0:root:/root:/bin/sh\nalice:x:1000:1000::/home/alice:/bin/sh\n";
+        assert_eq!(named_id(passwd, 1000), "alice (1000)");
+        assert_eq!(named_id(passwd, 0), "root (0)");
+        assert_eq!(named_id(passwd, 42), "42");
+        assert_eq!(named_id("", 7), "7");
+    }
+
+    #[test]
+    fn sizes_and_times_read_plainly() {
+        assert_eq!(file_size(1), "1 byte");
+        assert_eq!(file_size(1023), "1,023 bytes");
+        assert_eq!(time(None), "unavailable");
+        let t = UNIX_EPOCH + std::time::Duration::from_secs(1_709_647_629);
+        assert_eq!(time(Some(t)), "2024-03-05 14:07:09 UTC");
+    }
+}
@@ -0,0 +1,494 @@
+//! Short-lived highlight for tree rows that changed in the last FS refresh.
+//!
+//! `handle_fs_change` diffs the child lists of the directories it reloads
+//! and marks added/modified paths (or the parent, for removals). The tree
+//! widget asks for each row's [`FlashStep`], which fades out over
+//! [`FLASH_DURATION`]; expired entries are pruned on tick. Jumping to a
+//! `path:line` location flashes the target preview line the same way
+//! ([`LineFlash`]).
+//!
+//! Entries outlive their highlight: until `watcher.changed_window_secs`
+//! they stay in the map as jump targets for the "next changed" key, most
+//! recent first ([`jump_targets`]). The map holds at most
+//! `memory.flash_entries` paths, dropping the oldest mark first.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::bounded_map::{BoundedMap, MapStats, DEFAULT_FLASH_CAPACITY};
+use crate::fs::tree::TreeNode;
+
+/// How long a changed row stays highlighted.
+pub const FLASH_DURATION: Duration = Duration::from_millis(2_000);
+
+/// Fade intensity of a highlighted row, strongest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlashStep {
+    Strong,
+    Medium,
+    Faint,
+}
+
+/// Map the time since a change to a fade step (`None` once expired).
+pub fn classify(age: Duration) -> Option<FlashStep> {
+    let third = FLASH_DURATION / 3;
+    if age < third {
+        Some(FlashStep::Strong)
+    } else if age < third * 2 {
+        Some(FlashStep::Medium)
+    } else if age < FLASH_DURATION {
+        Some(FlashStep::Faint)
+    } else {
+        None
+    }
+}
+
+/// Per-child signature used to detect modifications across a reload;
+/// `None` while the child's stat is deferred.
+pub type ChildSignature = Option<(u64, Option<SystemTime>)>;
+
+/// Capture the loaded children of a directory node.
+/// Returns `None` if the children were never loaded (nothing to diff).
+pub fn child_signatures(node: &TreeNode) -> Option<HashMap<PathBuf, ChildSignature>> {
+    node.children.as_ref().map(|children| {
+        children
+            .iter()
+            .map(|c| {
+                let sig = (!c.meta.pending).then_some((c.meta.size, c.meta.modified));
+                (c.path.clone(), sig)
+            })
+            .collect()
+    })
+}
+
+/// Result of diffing one directory's children before/after a reload.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ChildDiff {
+    /// Paths that are new or whose size/mtime changed. A child whose stat
+    /// is pending on either side counts as unchanged.
+    pub changed: Vec<PathBuf>,
+    /// Whether any child disappeared.
+    pub removed: bool,
+}
+
+/// Compare two child signature sets.
+pub fn diff_children(
+    before: &HashMap<PathBuf, ChildSignature>,
+    after: &HashMap<PathBuf, ChildSignature>,
+) -> ChildDiff {
+    let mut changed: Vec<PathBuf> = after
+        .iter()
+        .filter(|(path, sig)| match (before.get(*path), sig) {
+            (None, _) => true,
+            (Some(Some(old)), Some(new)) => old != new,
+            (Some(_), _) => false,
+        })
+        .map(|(path, _)| path.clone())
+        .collect();
+    changed.sort();
+    let removed = before.keys().any(|path| !after.contains_key(path));
+    ChildDiff { changed, removed }
+}
+
+/// Paths currently highlighted, with the time they were marked.
+#[derive(Debug)]
+pub struct FlashMap {
+    entries: BoundedMap<PathBuf, Instant>,
+}
+
+impl Default for FlashMap {
+    fn default() -> Self {
+        Self::with_capacity(DEFAULT_FLASH_CAPACITY)
+    }
+}
+
+impl FlashMap {
+    /// An empty map tracking at most `capacity` paths.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            entries: BoundedMap::new(capacity),
+        }
+    }
+
+    /// Highlight `path` starting at `now`, evicting the oldest entry if full.
+    pub fn mark(&mut self, path: PathBuf, now: Instant) {
+        self.entries.insert(path, now);
+    }
+
+    /// Current fade step for `path`, if it is highlighted.
+    pub fn step_for(&self, path: &Path, now: Instant) -> Option<FlashStep> {
+        self.entries
+            .get(path)
+            .and_then(|at| classify(now.saturating_duration_since(*at)))
+    }
+
+    /// Drop entries older than `keep` (and whose highlight has faded).
+    pub fn prune(&mut self, now: Instant, keep: Duration) {
+        let keep = keep.max(FLASH_DURATION);
+        self.entries
+            .retain(|_, at| now.saturating_duration_since(*at) < keep);
+    }
+
+    /// Paths marked within `window` of `now`, most recent first.
+    pub fn recent(&self, now: Instant, window: Duration) -> Vec<(PathBuf, Instant)> {
+        let mut recent: Vec<(PathBuf, Instant)> = self
+            .entries
+            .iter()
+            .filter(|(_, at)| now.saturating_duration_since(**at) < window)
+            .map(|(path, at)| (path.clone(), *at))
+            .collect();
+        recent.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        recent
+    }
+
+    #[allow(dead_code)]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn stats(&self) -> MapStats {
+        self.entries.stats()
+    }
+}
+
+/// Where the "next changed" key can land.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JumpTarget {
+    pub path: PathBuf,
+    /// When the change was seen.
+    pub at: Instant,
+    /// The changed path is gone and `path` is its nearest existing
+    /// ancestor.
+    pub deleted: bool,
+}
+
+/// Turn `recent` changes (most recent first) into jump targets under
+/// `root`. A path that no longer exists is replaced by its nearest
+/// existing ancestor; a target reached twice keeps its most recent
+/// change; paths outside `root` are dropped.
+pub fn jump_targets(
+    recent: &[(PathBuf, Instant)],
+    root: &Path,
+    exists: impl Fn(&Path) -> bool,
+) -> Vec<JumpTarget> {
+    let mut targets: Vec<JumpTarget> = Vec::new();
+    for (path, at) in recent {
+        if !path.starts_with(root) {
+            continue;
+        }
+        let landing = path
+            .ancestors()
+            .take_while(|p| p.starts_with(root))
+            .find(|p| exists(p));
+        let landing = match landing {
+            Some(landing) => landing,
+            None => continue,
+        };
+        if targets.iter().any(|t| t.path == landing) {
+            continue;
+        }
+        targets.push(JumpTarget {
+            path: landing.to_path_buf(),
+            at: *at,
+            deleted: landing != path,
+        });
+    }
+    targets
+}
+
+/// Index of the target after `current` in `targets`, wrapping around;
+/// the first (most recent) when `current` isn't one of them.
+pub fn next_target(targets: &[JumpTarget], current: Option<&Path>) -> usize {
+    let position = current.and_then(|current| targets.iter().position(|t| t.path == current));
+    match position {
+        Some(i) if !targets.is_empty() => (i + 1) % targets.len(),
+        _ => 0,
+    }
+}
+
+/// Short age for the status bar: `4s`, `3m`.
+pub fn format_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    if secs < 60 {
+        format!("{}s", secs)
+    } else {
+        format!("{}m", secs / 60)
+    }
+}
+
+/// Highlight for the preview line a `path:line` jump landed on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineFlash {
+    /// File whose preview shows the line.
+    pub path: PathBuf,
+    /// Index into the preview's content lines.
+    pub row: usize,
+    pub at: Instant,
+}
+
+impl LineFlash {
+    /// Current fade step (`None` once expired).
+    pub fn step(&self, now: Instant) -> Option<FlashStep> {
+        classify(now.saturating_duration_since(self.at))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sigs(entries: &[(&str, u64, u64)]) -> HashMap<PathBuf, ChildSignature> {
+        entries
+            .iter()
+            .map(|(name, size, secs)| {
+                (
+                    PathBuf::from(name),
+                    Some((
+                        *size,
+                        Some(SystemTime::UNIX_EPOCH + Duration::from_secs(*secs)),
+                    )),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn diff_detects_added_and_modified() {
+        let before = sigs(&[("/d/a", 1, 10), ("/d/b", 2, 10)]);
+        let after = sigs(&[("/d/a", 1, 10), ("/d/b", 5, 11), ("/d/c", 0, 12)]);
+        let diff = diff_children(&before, &after);
+        assert_eq!(
+            diff.changed,
+            vec![PathBuf::from("/d/b"), PathBuf::from("/d/c")]
+        );
+        assert!(!diff.removed);
+    }
+
+    #[test]
+    fn diff_detects_removal() {
+        let before = sigs(&[("/d/a", 1, 10), ("/d/b", 2, 10)]);
+        let after = sigs(&[("/d/a", 1, 10)]);
+        let diff = diff_children(&before, &after);
+        assert!(diff.changed.is_empty());
+        assert!(diff.removed);
+    }
+
+    #[test]
+    fn diff_ignores_pending_stats() {
+        let before = sigs(&[("/d/a", 1, 10)]);
+        let mut after = before.clone();
+        after.insert(PathBuf::from("/d/a"), None);
+        after.insert(PathBuf::from("/d/b"), None);
+        let diff = diff_children(&before, &after);
+        assert_eq!(diff.changed, vec![PathBuf::from("/d/b")]);
+        assert!(diff_children(&after, &before).changed.is_empty());
+    }
+
+    #[test]
+    fn diff_unchanged_is_empty() {
+        let before = sigs(&[("/d/a", 1, 10)]);
+        assert_eq!(
+            diff_children(&before, &before.clone()),
+            ChildDiff::default()
+        );
+    }
+
+    #[test]
+    fn fade_steps_by_age() {
+        assert_eq!(classify(Duration::ZERO), Some(FlashStep::Strong));
+        assert_eq!(
+            classify(Duration::from_millis(500)),
+            Some(FlashStep::Strong)
+        );
+        assert_eq!(
+            classify(Duration::from_millis(1_000)),
+            Some(FlashStep::Medium)
+        );
+        assert_eq!(
+            classify(Duration::from_millis(1_500)),
+            Some(FlashStep::Faint)
+        );
+        assert_eq!(classify(FLASH_DURATION), None);
+    }
+
+    #[test]
+    fn prune_removes_expired_entries() {
+        let start = Instant::now();
+        let mut map = FlashMap::default();
+        map.mark(PathBuf::from("/a"), start);
+        map.mark(PathBuf::from("/b"), start + Duration::from_millis(1_500));
+        let later = start + Duration::from_millis(2_500);
+        assert_eq!(map.step_for(Path::new("/a"), later), None);
+        assert_eq!(
+            map.step_for(Path::new("/b"), later),
+            Some(FlashStep::Medium)
+        );
+        map.prune(later, Duration::ZERO);
+        assert_eq!(map.len(), 1);
+    }
+
+    #[test]
+    fn prune_keeps_entries_for_the_jump_window() {
+        let start = Instant::now();
+        let mut map = FlashMap::default();
+        map.mark(PathBuf::from("/a"), start);
+        map.mark(PathBuf::from("/b"), start + Duration::from_secs(30));
+        let later = start + Duration::from_secs(45);
+        map.prune(later, Duration::from_secs(60));
+        assert_eq!(map.len(), 2);
+        // Long faded, but still a jump target
+        assert_eq!(map.step_for(Path::new("/a"), later), None);
+
+        map.prune(start + Duration::from_secs(75), Duration::from_secs(60));
+        assert_eq!(map.len(), 1);
+        assert_eq!(
+            map.recent(later, Duration::from_secs(60)),
+            vec![(PathBuf::from("/b"), start + Duration::from_secs(30))]
+        );
+    }
+
+    #[test]
+    fn recent_is_newest_first_within_the_window() {
+        let start = Instant::now();
+        let mut map = FlashMap::default();
+        map.mark(PathBuf::from("/old"), start);
+        map.mark(PathBuf::from("/b"), start + Duration::from_secs(20));
+        map.mark(PathBuf::from("/a"), start + Duration::from_secs(20));
+        map.mark(PathBuf::from("/new"), start + Duration::from_secs(25));
+        let now = start + Duration::from_secs(30);
+        let paths: Vec<PathBuf> = map
+            .recent(now, Duration::from_secs(15))
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect();
+        // Same instant: by path
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from("/new"),
+                PathBuf::from("/a"),
+                PathBuf::from("/b")
+            ]
+        );
+    }
+
+    fn newest_first(start: Instant, paths: &[&str]) -> Vec<(PathBuf, Instant)> {
+        // Most recent first, a second apart
+        paths
+            .iter()
+            .enumerate()
+            .map(|(i, p)| {
+                let age = Duration::from_secs((paths.len() - i) as u64);
+                (PathBuf::from(p), start + age)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn deleted_paths_land_on_their_parent() {
+        let start = Instant::now();
+        let changes = newest_first(
+            start,
+            &["/r/src/gone.rs", "/r/src/main.rs", "/r/src", "/elsewhere/x"],
+        );
+        let exists = |p: &Path| p != Path::new("/r/src/gone.rs");
+        let targets = jump_targets(&changes, Path::new("/r"), exists);
+        // The parent stands in for the deleted file, once, at its most
+        // recent change; paths outside the root are dropped
+        assert_eq!(
+            targets,
+            vec![
+                JumpTarget {
+                    path: PathBuf::from("/r/src"),
+                    at: changes[0].1,
+                    deleted: true,
+                },
+                JumpTarget {
+                    path: PathBuf::from("/r/src/main.rs"),
+                    at: changes[1].1,
+                    deleted: false,
+                },
+            ]
+        );
+
+        // A whole deleted subtree falls back as far as it must
+        let changes = newest_first(start, &["/r/a/b/c.txt"]);
+        let targets = jump_targets(&changes, Path::new("/r"), |p| p == Path::new("/r"));
+        assert_eq!(targets[0].path, PathBuf::from("/r"));
+        assert!(jump_targets(&changes, Path::new("/r"), |_| false).is_empty());
+    }
+
+    #[test]
+    fn next_target_cycles_and_restarts() {
+        let start = Instant::now();
+        let changes = newest_first(start, &["/r/a", "/r/b", "/r/c"]);
+        let targets = jump_targets(&changes, Path::new("/r"), |_| true);
+        assert_eq!(next_target(&targets, None), 0);
+        assert_eq!(next_target(&targets, Some(Path::new("/r/a"))), 1);
+        assert_eq!(next_target(&targets, Some(Path::new("/r/c"))), 0);
+        // Not (or no longer) a target: start from the most recent
+        assert_eq!(next_target(&targets, Some(Path::new("/r/zzz"))), 0);
+        assert_eq!(next_target(&[], Some(Path::new("/r/a"))), 0);
+    }
+
+    #[test]
+    fn ages_are_short() {
+        assert_eq!(format_age(Duration::from_millis(4_900)), "4s");
+        assert_eq!(format_age(Duration::from_secs(59)), "59s");
+        assert_eq!(format_age(Duration::from_secs(200)), "3m");
+    }
+
+    #[test]
+    fn line_flash_fades() {
+        let start = Instant::now();
+        let flash = LineFlash {
+            path: PathBuf::from("/src/app.rs"),
+            row: 41,
+            at: start,
+        };
+        assert_eq!(flash.step(start), Some(FlashStep::Strong));
+        assert_eq!(flash.step(start + FLASH_DURATION), None);
+    }
+
+    #[test]
+    fn map_is_bounded() {
+        let start = Instant::now();
+        let mut map = FlashMap::default();
+        for i in 0..DEFAULT_FLASH_CAPACITY + 10 {
+            map.mark(
+                PathBuf::from(format!("/f{}", i)),
+                start + Duration::from_millis(i as u64),
+            );
+        }
+        assert_eq!(map.len(), DEFAULT_FLASH_CAPACITY);
+        // Oldest entries were evicted first
+        assert!(map.step_for(Path::new("/f0"), start).is_none());
+        assert!(map
+            .step_for(
+                Path::new(&format!("/f{}", DEFAULT_FLASH_CAPACITY + 9)),
+                start + Duration::from_millis(300)
+            )
+            .is_some());
+    }
+
+    #[test]
+    fn remarking_a_path_keeps_it_past_capacity() {
+        let start = Instant::now();
+        let mut map = FlashMap::with_capacity(2);
+        map.mark(PathBuf::from("/a"), start);
+        map.mark(PathBuf::from("/b"), start);
+        map.mark(PathBuf::from("/a"), start + Duration::from_millis(10));
+        assert_eq!(map.stats().evictions, 0);
+
+        map.mark(PathBuf::from("/c"), start + Duration::from_millis(20));
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.stats().evictions, 1);
+        assert!(map.step_for(Path::new("/a"), start).is_some());
+        assert!(map.step_for(Path::new("/b"), start).is_none());
+    }
+}
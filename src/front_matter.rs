@@ -0,0 +1,198 @@
+//! YAML front matter at the top of Markdown documents.
+//!
+//! Notes and static-site pages often start with a `---` delimited YAML
+//! block. The preview shows it as a compact card of its scalar keys (title,
+//! date and tags first) in place of the block's lines, with the body
+//! following as usual; `m` in the preview switches back to the raw YAML.
+//! A block that does not parse as a YAML mapping is left as raw text.
+
+use std::path::Path;
+
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use serde_yaml::Value;
+
+use crate::text;
+
+/// Extensions of the documents whose front matter gets a card.
+const EXTENSIONS: &[&str] = &["md", "markdown", "mdown", "mkd", "mdx"];
+
+/// Keys listed first, in this order; the rest keep their document order.
+const LEADING_KEYS: &[&str] = &["title", "date", "tags"];
+
+/// Whether `path` is a document whose front matter gets a card.
+pub fn applies_to(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|ext| EXTENSIONS.iter().any(|e| ext.eq_ignore_ascii_case(e)))
+}
+
+/// Lines taken by the front matter block at the start of `lines`,
+/// delimiters included, or `None` without one. The block opens with a
+/// `---` first line and closes at the next `---` or `...` line.
+pub fn block_len(lines: &[&str]) -> Option<usize> {
+    if lines.first().map(|l| l.trim_end()) != Some("---") {
+        return None;
+    }
+    lines
+        .iter()
+        .skip(1)
+        .position(|l| matches!(l.trim_end(), "---" | "..."))
+        .map(|i| i + 2)
+}
+
+/// Scalar keys of `yaml` with their values, leading keys first. Lists of
+/// scalars are joined with commas; nested mappings are left out. `None`
+/// if `yaml` is not a mapping.
+pub fn fields(yaml: &str) -> Option<Vec<(String, String)>> {
+    let mapping = match serde_yaml::from_str::<Value>(yaml).ok()? {
+        Value::Mapping(mapping) => mapping,
+        _ => return None,
+    };
+    let mut fields = Vec::new();
+    for (key, value) in mapping {
+        let key = match scalar(&key) {
+            Some(key) => key,
+            None => continue,
+        };
+        let shown = match &value {
+            Value::Sequence(items) => match items.iter().map(scalar).collect::<Option<Vec<_>>>() {
+                Some(items) => items.join(", "),
+                None => continue,
+            },
+            other => match scalar(other) {
+                Some(shown) => shown,
+                None => continue,
+            },
+        };
+        fields.push((key, shown));
+    }
+    // Stable, so the other keys stay in document order
+    fields.sort_by_key(|(key, _)| {
+        LEADING_KEYS
+            .iter()
+            .position(|k| k == key)
+            .unwrap_or(LEADING_KEYS.len())
+    });
+    Some(fields)
+}
+
+/// A scalar as shown on the card, with block scalars on one line.
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.split_whitespace().collect::<Vec<_>>().join(" ")),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        Value::Null => Some(String::new()),
+        Value::Tagged(tagged) => scalar(&tagged.value),
+        Value::Sequence(_) | Value::Mapping(_) => None,
+    }
+}
+
+/// Card rows standing in for the front matter block at the start of
+/// `lines`, and the number of lines they replace. `None` when there is no
+/// block or it does not parse, so the document is shown raw.
+pub fn card(lines: &[&str]) -> Option<(Vec<Line<'static>>, usize)> {
+    let len = block_len(lines)?;
+    let fields = fields(&lines[1..len - 1].join("\n"))?;
+
+    let border = Style::default().fg(Color::DarkGray);
+    let key_style = Style::default()
+        .fg(Color::Cyan)
+        .add_modifier(Modifier::BOLD);
+    let key_width = fields
+        .iter()
+        .map(|(k, _)| text::width(k))
+        .max()
+        .unwrap_or(0);
+
+    let mut rows = Vec::with_capacity(fields.len() + 2);
+    rows.push(Line::from(Span::styled(
+        format!("╭─ Front matter ({} lines, m: raw)", len),
+        border,
+    )));
+    for (key, value) in fields {
+        let value_style = if key == "title" {
+            Style::default()
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::White)
+        };
+        let pad = " ".repeat(key_width - text::width(&key) + 2);
+        rows.push(Line::from(vec![
+            Span::styled("│ ", border),
+            Span::styled(key, key_style),
+            Span::raw(pad),
+            Span::styled(value, value_style),
+        ]));
+    }
+    rows.push(Line::from(Span::styled("╰─", border)));
+    Some((rows, len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(doc: &str) -> Vec<&str> {
+        doc.lines().collect()
+    }
+
+    fn row_text(line: &Line) -> String {
+        line.spans.iter().map(|s| s.content.as_ref()).collect()
+    }
+
+    #[test]
+    fn only_markdown_documents() {
+        assert!(applies_to(Path::new("notes/post.md")));
+        assert!(applies_to(Path::new("README.Markdown")));
+        assert!(!applies_to(Path::new("config.yaml")));
+        assert!(!applies_to(Path::new("Makefile")));
+    }
+
+    #[test]
+    fn block_ends_at_the_next_delimiter() {
+        let doc = "---\ntitle: A\n---\nbody\n---\nmore\n";
+        assert_eq!(block_len(&lines(doc)), Some(3));
+        assert_eq!(block_len(&lines("---\ntitle: A\n...\nbody")), Some(3));
+        // Not at the very start, or never closed
+        assert_eq!(block_len(&lines("\n---\ntitle: A\n---\n")), None);
+        assert_eq!(block_len(&lines("---\ntitle: A\n")), None);
+    }
+
+    #[test]
+    fn card_lists_leading_keys_first() {
+        let doc = "---\nlayout: post\ntags: [rust, tui]\ntitle: Hello\ndraft: false\ndate: 2024-05-01\nauthor:\n  name: Sam\n---\n# Hello\n";
+        let (rows, replaced) = card(&lines(doc)).unwrap();
+        assert_eq!(replaced, 9);
+        let texts: Vec<String> = rows.iter().map(row_text).collect();
+        assert_eq!(
+            texts,
+            vec![
+                "╭─ Front matter (9 lines, m: raw)",
+                "│ title   Hello",
+                "│ date    2024-05-01",
+                "│ tags    rust, tui",
+                "│ layout  post",
+                "│ draft   false",
+                "╰─",
+            ]
+        );
+    }
+
+    #[test]
+    fn malformed_yaml_stays_raw() {
+        assert!(card(&lines("---\ntitle: [unclosed\n---\nbody")).is_none());
+        // Valid YAML, but not a mapping
+        assert!(card(&lines("---\n- a\n- b\n---\nbody")).is_none());
+        assert!(card(&lines("---\n---\nbody")).is_none());
+    }
+
+    #[test]
+    fn block_scalars_fit_one_row() {
+        let doc = "---\nsummary: |\n  First line\n  second line\n---\n";
+        let (rows, _) = card(&lines(doc)).unwrap();
+        assert_eq!(row_text(&rows[1]), "│ summary  First line second line");
+    }
+}
@@ -0,0 +1,486 @@
+//! Bulk rename: rename every multi-selected item through one pattern.
+//!
+//! A pattern is either a template such as `{name}_{n}.{ext}` — `{name}`
+//! is the stem, `{ext}` the extension and `{n}` a counter from 1 in
+//! selection order — or a substitution `s/old/new/` on the whole name
+//! (`s/old/new/g` replaces every match). [`plan`] computes the targets and
+//! refuses patterns that would give two items the same name or land on a
+//! file that already exists; the dialog shows [`preview`] as the pattern
+//! is typed. Items are then renamed one by one, so a failure only affects
+//! its own item.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use thiserror::Error;
+
+use crate::fs::name_check::{self, NameProblem, Siblings};
+
+/// Renames listed in the dialog before "… N more".
+pub const PREVIEW_ROWS: usize = 5;
+
+/// A parsed pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Pattern {
+    /// Literal text and placeholders.
+    Template(Vec<Token>),
+    /// `s/old/new/`, replacing the first match or, with `g`, all of them.
+    Substitute {
+        old: String,
+        new: String,
+        global: bool,
+    },
+}
+
+/// Piece of a template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Token {
+    Text(String),
+    Name,
+    Ext,
+    Counter,
+}
+
+/// Why a bulk rename cannot be planned.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum PlanError {
+    #[error("enter a pattern")]
+    Empty,
+    #[error("unknown placeholder {{{0}}}; use {{name}}, {{ext}} or {{n}}")]
+    UnknownPlaceholder(String),
+    #[error("unclosed '{{' in pattern")]
+    Unclosed,
+    #[error("write substitutions as s/old/new/")]
+    BadSubstitution,
+    #[error("{}: {}", file_name(.path), .problem)]
+    Name { path: PathBuf, problem: NameProblem },
+    #[error("{} and {} would both become {}", file_name(.first), file_name(.second), file_name(.target))]
+    Duplicate {
+        first: PathBuf,
+        second: PathBuf,
+        target: PathBuf,
+    },
+    #[error("the pattern leaves every name unchanged")]
+    Unchanged,
+}
+
+/// Last component of `path`, for messages.
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+impl Pattern {
+    /// Parse what was typed in the dialog.
+    pub fn parse(text: &str) -> Result<Self, PlanError> {
+        if text.is_empty() {
+            return Err(PlanError::Empty);
+        }
+        if let Some(rest) = text.strip_prefix("s/") {
+            return parse_substitution(rest);
+        }
+        let mut tokens = Vec::new();
+        let mut literal = String::new();
+        let mut chars = text.chars();
+        while let Some(c) = chars.next() {
+            if c != '{' {
+                literal.push(c);
+                continue;
+            }
+            let mut name = String::new();
+            loop {
+                match chars.next() {
+                    Some('}') => break,
+                    Some(c) => name.push(c),
+                    None => return Err(PlanError::Unclosed),
+                }
+            }
+            let token = match name.as_str() {
+                "name" => Token::Name,
+                "ext" => Token::Ext,
+                "n" => Token::Counter,
+                _ => return Err(PlanError::UnknownPlaceholder(name)),
+            };
+            if !literal.is_empty() {
+                tokens.push(Token::Text(std::mem::take(&mut literal)));
+            }
+            tokens.push(token);
+        }
+        if !literal.is_empty() {
+            tokens.push(Token::Text(literal));
+        }
+        Ok(Pattern::Template(tokens))
+    }
+
+    /// New file name for `src`, the `n`-th item.
+    pub fn apply(&self, src: &Path, n: usize) -> String {
+        let name = file_name(src);
+        match self {
+            Pattern::Substitute { old, new, global } => {
+                if *global {
+                    name.replace(old.as_str(), new)
+                } else {
+                    name.replacen(old.as_str(), new, 1)
+                }
+            }
+            Pattern::Template(tokens) => {
+                let stem = src
+                    .file_stem()
+                    .map(|s| s.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                let ext = src
+                    .extension()
+                    .map(|s| s.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                let mut out = String::new();
+                for token in tokens {
+                    match token {
+                        Token::Text(text) => out.push_str(text),
+                        Token::Name => out.push_str(&stem),
+                        // `.{ext}` drops out for items without an extension
+                        Token::Ext if ext.is_empty() => {
+                            if out.ends_with('.') {
+                                out.pop();
+                            }
+                        }
+                        Token::Ext => out.push_str(&ext),
+                        Token::Counter => out.push_str(&n.to_string()),
+                    }
+                }
+                out
+            }
+        }
+    }
+}
+
+/// `old/new/` or `old/new/g`, after the leading `s/`.
+fn parse_substitution(rest: &str) -> Result<Pattern, PlanError> {
+    let parts: Vec<&str> = rest.split('/').collect();
+    let (old, new, global) = match parts.as_slice() {
+        [old, new, ""] => (old, new, false),
+        [old, new, "g"] => (old, new, true),
+        _ => return Err(PlanError::BadSubstitution),
+    };
+    if old.is_empty() {
+        return Err(PlanError::BadSubstitution);
+    }
+    Ok(Pattern::Substitute {
+        old: old.to_string(),
+        new: new.to_string(),
+        global,
+    })
+}
+
+/// Renames a pattern produces.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Plan {
+    /// `(source, target)` for the items whose name changes.
+    pub pairs: Vec<(PathBuf, PathBuf)>,
+    /// Some target lost the extension its source had.
+    pub drops_extension: bool,
+}
+
+/// Plan the renames, numbering `sources` in the order given. Every target
+/// goes through [`name_check`] against its directory, and two targets
+/// that are one name up to case or normalization count as duplicates.
+pub fn plan(sources: &[PathBuf], pattern: &Pattern) -> Result<Plan, PlanError> {
+    let mut siblings: HashMap<PathBuf, Siblings> = HashMap::new();
+    let mut targets: HashMap<(PathBuf, String), &PathBuf> = HashMap::new();
+    let mut pairs = Vec::new();
+    let mut drops_extension = false;
+    for (i, src) in sources.iter().enumerate() {
+        let name = pattern.apply(src, i + 1);
+        let parent = src.parent().unwrap_or(Path::new("."));
+        let check = siblings
+            .entry(parent.to_path_buf())
+            .or_insert_with(|| Siblings::read(parent))
+            .check(&name, Some(src));
+        if let Some(problem) = check.blocking() {
+            return Err(PlanError::Name {
+                path: src.clone(),
+                problem: problem.clone(),
+            });
+        }
+        drops_extension |= check
+            .problems
+            .iter()
+            .any(|p| matches!(p, NameProblem::ExtensionDropped(_)));
+        let target = parent.join(&name);
+        let key = (parent.to_path_buf(), name_check::fold(&name));
+        if let Some(first) = targets.insert(key, src) {
+            return Err(PlanError::Duplicate {
+                first: first.clone(),
+                second: src.clone(),
+                target,
+            });
+        }
+        if target != *src {
+            pairs.push((src.clone(), target));
+        }
+    }
+    if pairs.is_empty() && !sources.is_empty() {
+        return Err(PlanError::Unchanged);
+    }
+    Ok(Plan {
+        pairs,
+        drops_extension,
+    })
+}
+
+/// The first `rows` renames as `old → new` lines, then how many more.
+pub fn preview(pairs: &[(PathBuf, PathBuf)], rows: usize) -> Vec<String> {
+    let mut lines: Vec<String> = pairs
+        .iter()
+        .take(rows)
+        .map(|(src, dst)| format!("{} → {}", file_name(src), file_name(dst)))
+        .collect();
+    if pairs.len() > rows {
+        lines.push(format!("… {} more", pairs.len() - rows));
+    }
+    lines
+}
+
+/// Input state of the bulk rename dialog.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BulkRenameForm {
+    /// Items to rename, in counter order.
+    pub sources: Vec<PathBuf>,
+    pub pattern: String,
+    /// Preview lines for the current pattern, or why it can't be applied.
+    pub preview: Result<Vec<String>, String>,
+    /// The pattern with `.{ext}` put back, when it drops extensions.
+    pub suggestion: Option<String>,
+}
+
+impl BulkRenameForm {
+    pub fn new(sources: Vec<PathBuf>) -> Self {
+        Self {
+            sources,
+            pattern: String::new(),
+            preview: Ok(Vec::new()),
+            suggestion: None,
+        }
+    }
+
+    /// Plan the renames for the current pattern.
+    pub fn plan(&self) -> Result<Plan, String> {
+        Pattern::parse(&self.pattern)
+            .and_then(|pattern| plan(&self.sources, &pattern))
+            .map_err(|e| e.to_string())
+    }
+
+    /// Recompute the live preview and suggestion; an empty pattern shows
+    /// nothing.
+    pub fn refresh(&mut self) {
+        self.suggestion = None;
+        if self.pattern.is_empty() {
+            self.preview = Ok(Vec::new());
+            return;
+        }
+        let plan = self.plan();
+        let templated = !self.pattern.starts_with("s/");
+        if matches!(&plan, Ok(plan) if plan.drops_extension && templated) {
+            self.suggestion = Some(format!("{}.{{ext}}", self.pattern.trim_end_matches('.')));
+        }
+        self.preview = plan.map(|plan| preview(&plan.pairs, PREVIEW_ROWS));
+    }
+
+    /// Replace the pattern with the suggested one.
+    pub fn accept_suggestion(&mut self) {
+        if let Some(pattern) = self.suggestion.take() {
+            self.pattern = pattern;
+            self.refresh();
+        }
+    }
+
+    pub fn input_char(&mut self, c: char) {
+        if c.is_control() {
+            return;
+        }
+        self.pattern.push(c);
+        self.refresh();
+    }
+
+    pub fn delete_char(&mut self) {
+        self.pattern.pop();
+        self.refresh();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn make_files(dir: &Path, names: &[&str]) -> Vec<PathBuf> {
+        names
+            .iter()
+            .map(|n| {
+                let p = dir.join(n);
+                fs::write(&p, n).unwrap();
+                p
+            })
+            .collect()
+    }
+
+    fn targets(plan: &Plan) -> Vec<String> {
+        plan.pairs
+            .iter()
+            .map(|(_, dst)| dst.file_name().unwrap().to_string_lossy().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn templates_fill_in_name_ext_and_counter() {
+        let tmp = TempDir::new().unwrap();
+        let files = make_files(tmp.path(), &["b.txt", "a.rs", "README"]);
+        let pattern = Pattern::parse("{n}-{name}.{ext}").unwrap();
+        let plan = plan(&files, &pattern).unwrap();
+        // Counted in the order given; `.{ext}` drops out without an extension
+        assert_eq!(targets(&plan), ["1-b.txt", "2-a.rs", "3-README"]);
+        assert!(!plan.drops_extension);
+
+        let upper = Pattern::parse("{name}.bak").unwrap();
+        assert_eq!(upper.apply(Path::new("x/notes.md"), 1), "notes.bak");
+    }
+
+    #[test]
+    fn substitutions_replace_the_first_match_or_all() {
+        let first = Pattern::parse("s/a/o/").unwrap();
+        assert_eq!(first.apply(Path::new("banana.txt"), 1), "bonana.txt");
+        let global = Pattern::parse("s/a/o/g").unwrap();
+        assert_eq!(global.apply(Path::new("banana.txt"), 1), "bonono.txt");
+        let delete = Pattern::parse("s/_draft//").unwrap();
+        assert_eq!(delete.apply(Path::new("essay_draft.md"), 1), "essay.md");
+
+        for bad in ["s/a/b", "s//b/", "s/a/b/x", "s/a/b/c/"] {
+            assert_eq!(
+                Pattern::parse(bad),
+                Err(PlanError::BadSubstitution),
+                "{}",
+                bad
+            );
+        }
+    }
+
+    #[test]
+    fn bad_templates_are_rejected() {
+        assert_eq!(Pattern::parse(""), Err(PlanError::Empty));
+        assert_eq!(
+            Pattern::parse("{stem}.txt"),
+            Err(PlanError::UnknownPlaceholder("stem".to_string()))
+        );
+        assert_eq!(Pattern::parse("{name"), Err(PlanError::Unclosed));
+        let err = Pattern::parse("{x}").unwrap_err().to_string();
+        assert_eq!(err, "unknown placeholder {x}; use {name}, {ext} or {n}");
+
+        let files = [PathBuf::from("/tmp/a.txt")];
+        let slash = Pattern::parse("sub/{name}").unwrap();
+        let err = plan(&files, &slash).unwrap_err();
+        assert_eq!(
+            err,
+            PlanError::Name {
+                path: files[0].clone(),
+                problem: NameProblem::Separator,
+            }
+        );
+        assert_eq!(err.to_string(), "a.txt: names cannot contain '/'");
+        let empty = Pattern::parse("s/a.txt//").unwrap();
+        assert_eq!(
+            plan(&files, &empty).unwrap_err().to_string(),
+            "a.txt: name is empty"
+        );
+    }
+
+    #[test]
+    fn duplicate_targets_are_refused() {
+        let tmp = TempDir::new().unwrap();
+        let files = make_files(tmp.path(), &["a.txt", "b.txt"]);
+        let pattern = Pattern::parse("same.{ext}").unwrap();
+        assert_eq!(
+            plan(&files, &pattern),
+            Err(PlanError::Duplicate {
+                first: files[0].clone(),
+                second: files[1].clone(),
+                target: tmp.path().join("same.txt"),
+            })
+        );
+        // Names that are one file on macOS and Windows are duplicates too
+        let files = make_files(tmp.path(), &["x.txt", "y.TXT"]);
+        let pattern = Pattern::parse("notes.{ext}").unwrap();
+        assert_eq!(
+            plan(&files, &pattern).unwrap_err().to_string(),
+            "x.txt and y.TXT would both become notes.TXT"
+        );
+    }
+
+    #[test]
+    fn existing_files_and_no_op_patterns_are_refused() {
+        let tmp = TempDir::new().unwrap();
+        let files = make_files(tmp.path(), &["a.txt", "b.txt"]);
+        fs::write(tmp.path().join("b.md"), "other").unwrap();
+        let pattern = Pattern::parse("{name}.md").unwrap();
+        assert_eq!(
+            plan(&files, &pattern).unwrap_err().to_string(),
+            "b.txt: b.md already exists"
+        );
+        // Also up to case
+        let upper = Pattern::parse("{name}.MD").unwrap();
+        assert_eq!(
+            plan(&files, &upper).unwrap_err().to_string(),
+            "b.txt: clashes with b.md ignoring case"
+        );
+        let same = Pattern::parse("{name}.{ext}").unwrap();
+        assert_eq!(plan(&files, &same), Err(PlanError::Unchanged));
+        // Items the pattern leaves alone are not renamed
+        let partial = Pattern::parse("s/a/z/").unwrap();
+        assert_eq!(targets(&plan(&files, &partial).unwrap()), ["z.txt"]);
+    }
+
+    #[test]
+    fn form_previews_the_first_few_renames() {
+        let tmp = TempDir::new().unwrap();
+        let names: Vec<String> = (0..8).map(|i| format!("f{}.txt", i)).collect();
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+        let mut form = BulkRenameForm::new(make_files(tmp.path(), &names));
+        assert_eq!(form.preview, Ok(Vec::new()));
+        for c in "g{n}".chars() {
+            form.input_char(c);
+        }
+        let lines = form.preview.clone().unwrap();
+        assert_eq!(lines.len(), PREVIEW_ROWS + 1);
+        assert_eq!(lines[0], "f0.txt → g1");
+        assert_eq!(lines[PREVIEW_ROWS], "… 3 more");
+
+        form.delete_char();
+        form.delete_char();
+        form.delete_char();
+        assert_eq!(
+            form.preview,
+            Err("f0.txt and f1.txt would both become g".to_string())
+        );
+    }
+
+    #[test]
+    fn a_pattern_dropping_extensions_is_offered_them_back() {
+        let tmp = TempDir::new().unwrap();
+        let mut form = BulkRenameForm::new(make_files(tmp.path(), &["a.jpg", "b.jpg"]));
+        for c in "photo_{n}".chars() {
+            form.input_char(c);
+        }
+        assert_eq!(form.suggestion.as_deref(), Some("photo_{n}.{ext}"));
+        assert_eq!(form.preview.clone().unwrap()[0], "a.jpg → photo_1");
+        form.accept_suggestion();
+        assert_eq!(form.pattern, "photo_{n}.{ext}");
+        assert_eq!(form.suggestion, None);
+        assert_eq!(form.preview.clone().unwrap()[0], "a.jpg → photo_1.jpg");
+
+        // Substitutions get no pattern to accept
+        form.pattern = "s/.jpg//".to_string();
+        form.refresh();
+        assert!(form.plan().unwrap().drops_extension);
+        assert_eq!(form.suggestion, None);
+    }
+}
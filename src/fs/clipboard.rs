@@ -1,4 +1,5 @@
-use std::path::PathBuf;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 
 /// The type of clipboard operation.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -8,10 +9,17 @@ pub enum ClipboardOp {
 }
 
 /// Internal clipboard buffer holding file paths and operation type.
+///
+/// Only change it through [`set`](Self::set) and [`clear`](Self::clear),
+/// which keep the path index used by [`mark`](Self::mark) in step.
 #[derive(Debug, Clone)]
 pub struct ClipboardState {
     pub paths: Vec<PathBuf>,
     pub operation: Option<ClipboardOp>,
+    /// `paths` as a set, so each tree row is looked up in O(1).
+    index: HashSet<PathBuf>,
+    /// Bumped on every change; tree rows cached for an older value redraw.
+    generation: u64,
 }
 
 impl Default for ClipboardState {
@@ -26,20 +34,39 @@ impl ClipboardState {
         Self {
             paths: Vec::new(),
             operation: None,
+            index: HashSet::new(),
+            generation: 0,
         }
     }
 
     /// Set the clipboard with paths and operation type.
     pub fn set(&mut self, paths: Vec<PathBuf>, op: ClipboardOp) {
+        self.index = paths.iter().cloned().collect();
         self.paths = paths;
         self.operation = Some(op);
+        self.generation += 1;
     }
 
     /// Clear the clipboard.
-    #[allow(dead_code)]
     pub fn clear(&mut self) {
         self.paths.clear();
+        self.index.clear();
         self.operation = None;
+        self.generation += 1;
+    }
+
+    /// The operation `path` is on the clipboard for, if it is on it.
+    pub fn mark(&self, path: &Path) -> Option<ClipboardOp> {
+        if self.index.contains(path) {
+            self.operation
+        } else {
+            None
+        }
+    }
+
+    /// Changes so far, for telling whether marks drawn earlier are stale.
+    pub fn generation(&self) -> u64 {
+        self.generation
     }
 
     /// Whether the clipboard has content.
@@ -113,6 +140,22 @@ mod tests {
         assert_eq!(cb.paths[0], PathBuf::from("/tmp/new.txt"));
     }
 
+    #[test]
+    fn marks_follow_the_clipboard() {
+        let mut cb = ClipboardState::new();
+        let a = PathBuf::from("/tmp/a.txt");
+        assert_eq!(cb.mark(&a), None);
+        cb.set(vec![a.clone()], ClipboardOp::Cut);
+        assert_eq!(cb.mark(&a), Some(ClipboardOp::Cut));
+        assert_eq!(cb.mark(Path::new("/tmp/b.txt")), None);
+        let before = cb.generation();
+        cb.set(vec![a.clone()], ClipboardOp::Copy);
+        assert_eq!(cb.mark(&a), Some(ClipboardOp::Copy));
+        assert!(cb.generation() > before);
+        cb.clear();
+        assert_eq!(cb.mark(&a), None);
+    }
+
     #[test]
     fn set_with_empty_paths() {
         let mut cb = ClipboardState::new();
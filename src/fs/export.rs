@@ -0,0 +1,420 @@
+//! Export of the tree view as indented text or JSON.
+//!
+//! Rows come from `TreeState::flat_items` (what the panel shows, filter
+//! included) or `TreeState::loaded_items` (everything loaded, collapsed or
+//! not). Text rows use the tree panel's connectors through
+//! [`connector_prefix`]; both formats are written row by row, so a large
+//! view streams straight into the file instead of being built in memory.
+
+use std::borrow::Cow;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use serde::Serialize;
+
+use crate::components::tree::connector_prefix;
+use crate::fs::tree::{FlatItem, NodeType};
+use crate::preview_content::format_size;
+
+/// Output format.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Indented lines with box-drawing connectors.
+    #[default]
+    Text,
+    /// A JSON array with one object per row.
+    Json,
+}
+
+impl ExportFormat {
+    pub fn label(self) -> &'static str {
+        match self {
+            ExportFormat::Text => "text",
+            ExportFormat::Json => "JSON",
+        }
+    }
+
+    /// File extension matching the format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Text => "txt",
+            ExportFormat::Json => "json",
+        }
+    }
+
+    pub fn toggle(self) -> Self {
+        match self {
+            ExportFormat::Text => ExportFormat::Json,
+            ExportFormat::Json => ExportFormat::Text,
+        }
+    }
+}
+
+/// Which rows are exported.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ExportScope {
+    /// The rows the tree panel shows, filtered view included.
+    #[default]
+    Visible,
+    /// Every loaded node, collapsed directories included, ignoring any filter.
+    Loaded,
+}
+
+impl ExportScope {
+    pub fn label(self) -> &'static str {
+        match self {
+            ExportScope::Visible => "visible view",
+            ExportScope::Loaded => "full loaded tree",
+        }
+    }
+
+    pub fn toggle(self) -> Self {
+        match self {
+            ExportScope::Visible => ExportScope::Loaded,
+            ExportScope::Loaded => ExportScope::Visible,
+        }
+    }
+}
+
+/// What goes into an export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExportOptions {
+    pub format: ExportFormat,
+    pub scope: ExportScope,
+    /// Append file sizes to text rows.
+    pub sizes: bool,
+    /// Mark hidden entries (text) or add a `hidden` field (JSON).
+    pub hidden_markers: bool,
+    /// Keep the "Load more..." rows of paginated directories.
+    pub load_more: bool,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            format: ExportFormat::Text,
+            scope: ExportScope::Visible,
+            sizes: false,
+            hidden_markers: true,
+            load_more: false,
+        }
+    }
+}
+
+/// One JSON row.
+#[derive(Serialize)]
+struct JsonRow {
+    path: String,
+    #[serde(rename = "type")]
+    kind: &'static str,
+    depth: usize,
+    /// File size in bytes; null for directories.
+    size: Option<u64>,
+    /// Modification time in Unix seconds.
+    modified: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hidden: Option<bool>,
+    /// For "load_more" rows: entries not loaded yet.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    remaining: Option<usize>,
+}
+
+/// Write `items` to `out` in the chosen format. Returns the number of rows.
+pub fn write<W: Write>(
+    items: &[FlatItem],
+    options: &ExportOptions,
+    out: &mut W,
+) -> io::Result<usize> {
+    let items = if options.load_more {
+        Cow::Borrowed(items)
+    } else {
+        Cow::Owned(without_load_more(items))
+    };
+    match options.format {
+        ExportFormat::Text => write_text(&items, options, out),
+        ExportFormat::Json => write_json(&items, options, out),
+    }
+}
+
+/// Export `items` into the file at `path`, replacing it.
+pub fn write_file(items: &[FlatItem], options: &ExportOptions, path: &Path) -> io::Result<usize> {
+    let mut out = BufWriter::new(File::create(path)?);
+    let rows = write(items, options, &mut out)?;
+    out.flush()?;
+    Ok(rows)
+}
+
+/// Drop "Load more..." rows (but not depth limit rows), making the sibling
+/// before each one the last.
+fn without_load_more(items: &[FlatItem]) -> Vec<FlatItem> {
+    let mut kept: Vec<FlatItem> = Vec::with_capacity(items.len());
+    // Index in `kept` of the latest row at each depth under the current parent
+    let mut latest_at_depth: Vec<Option<usize>> = Vec::new();
+    for item in items {
+        let depth = item.depth;
+        if item.node_type == NodeType::LoadMore && !item.is_depth_limit() {
+            if let Some(Some(sibling)) = latest_at_depth.get(depth) {
+                kept[*sibling].is_last_sibling = true;
+            }
+            continue;
+        }
+        latest_at_depth.truncate(depth);
+        latest_at_depth.resize(depth, None);
+        latest_at_depth.push(Some(kept.len()));
+        kept.push(item.clone());
+    }
+    kept
+}
+
+fn write_text<W: Write>(
+    items: &[FlatItem],
+    options: &ExportOptions,
+    out: &mut W,
+) -> io::Result<usize> {
+    // Whether the latest row at each depth was the last sibling; for the
+    // current row these are its ancestors
+    let mut last_at_depth: Vec<bool> = Vec::new();
+    for item in items {
+        let prefix = if item.depth == 0 {
+            String::new()
+        } else {
+            last_at_depth.resize(item.depth, false);
+            connector_prefix(&last_at_depth[1..], item.is_last_sibling) + " "
+        };
+        last_at_depth.truncate(item.depth);
+        last_at_depth.push(item.is_last_sibling);
+
+        let mut line = prefix;
+        match item.node_type {
+            NodeType::Directory => {
+                line.push_str(&item.name);
+                line.push('/');
+            }
+            NodeType::LoadMore if item.is_depth_limit() => line.push_str(&item.name),
+            NodeType::LoadMore => {
+                line.push_str(&format!(
+                    "... {} more",
+                    item.load_more_remaining.unwrap_or(0)
+                ));
+            }
+            NodeType::File | NodeType::Symlink => {
+                line.push_str(&item.name);
+                if options.sizes {
+                    if let Ok(meta) = fs::metadata(&item.path) {
+                        line.push_str(&format!(" ({})", format_size(meta.len())));
+                    }
+                }
+            }
+        }
+        if options.hidden_markers && item.is_hidden {
+            line.push_str(" (hidden)");
+        }
+        writeln!(out, "{}", line)?;
+    }
+    Ok(items.len())
+}
+
+fn write_json<W: Write>(
+    items: &[FlatItem],
+    options: &ExportOptions,
+    out: &mut W,
+) -> io::Result<usize> {
+    if items.is_empty() {
+        writeln!(out, "[]")?;
+        return Ok(0);
+    }
+    writeln!(out, "[")?;
+    for (i, item) in items.iter().enumerate() {
+        let row = json_row(item, options);
+        let json = serde_json::to_string(&row).map_err(io::Error::other)?;
+        let comma = if i + 1 < items.len() { "," } else { "" };
+        writeln!(out, "  {}{}", json, comma)?;
+    }
+    writeln!(out, "]")?;
+    Ok(items.len())
+}
+
+fn json_row(item: &FlatItem, options: &ExportOptions) -> JsonRow {
+    let kind = match item.node_type {
+        NodeType::Directory => "directory",
+        NodeType::File => "file",
+        NodeType::Symlink => "symlink",
+        NodeType::LoadMore if item.is_depth_limit() => "depth_limit",
+        NodeType::LoadMore => "load_more",
+    };
+    let meta = match item.node_type {
+        NodeType::LoadMore => None,
+        _ => fs::symlink_metadata(&item.path).ok(),
+    };
+    let size = meta.as_ref().filter(|m| !m.is_dir()).map(|m| m.len());
+    let modified = meta
+        .as_ref()
+        .and_then(|m| m.modified().ok())
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs());
+    JsonRow {
+        path: item.path.to_string_lossy().to_string(),
+        kind,
+        depth: item.depth,
+        size,
+        modified,
+        hidden: options.hidden_markers.then_some(item.is_hidden),
+        remaining: item.load_more_remaining,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn row(name: &str, node_type: NodeType, depth: usize, is_last: bool) -> FlatItem {
+        let load_more_parent = (node_type == NodeType::LoadMore).then(|| PathBuf::from("/r"));
+        FlatItem {
+            name: name.to_string(),
+            path: PathBuf::from("/r").join(name),
+            node_type,
+            depth,
+            is_expanded: false,
+            is_last_sibling: is_last,
+            is_hidden: name.starts_with('.'),
+            is_ignored: false,
+            load_more_parent,
+            load_more_remaining: None,
+            child_count: None,
+            stat_pending: false,
+            link: None,
+            special: None,
+        }
+    }
+
+    /// r/ ── src/ (expanded: lib/ (expanded: a.rs), main.rs, Load more),
+    /// .env, README.md
+    fn sample() -> Vec<FlatItem> {
+        let mut more = row("Load more... (remaining: ~7)", NodeType::LoadMore, 2, true);
+        more.load_more_remaining = Some(7);
+        vec![
+            row("r", NodeType::Directory, 0, true),
+            row("src", NodeType::Directory, 1, false),
+            row("lib", NodeType::Directory, 2, false),
+            row("a.rs", NodeType::File, 3, true),
+            row("main.rs", NodeType::File, 2, false),
+            more,
+            row(".env", NodeType::File, 1, false),
+            row("README.md", NodeType::File, 1, true),
+        ]
+    }
+
+    fn export(items: &[FlatItem], options: ExportOptions) -> String {
+        let mut out = Vec::new();
+        write(items, &options, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn text_export_matches_golden() {
+        let options = ExportOptions {
+            load_more: true,
+            ..Default::default()
+        };
+        let golden = "\
+r/
+├── src/
+│  ├── lib/
+│  │  └── a.rs
+│  ├── main.rs
+│  └── ... 7 more
+├── .env (hidden)
+└── README.md
+";
+        assert_eq!(export(&sample(), options), golden);
+    }
+
+    #[test]
+    fn text_export_without_load_more_closes_the_branch() {
+        let options = ExportOptions {
+            hidden_markers: false,
+            ..Default::default()
+        };
+        let golden = "\
+r/
+├── src/
+│  ├── lib/
+│  │  └── a.rs
+│  └── main.rs
+├── .env
+└── README.md
+";
+        assert_eq!(export(&sample(), options), golden);
+    }
+
+    #[test]
+    fn text_export_appends_sizes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.bin");
+        fs::write(&path, vec![0u8; 2048]).unwrap();
+        let mut file = row("data.bin", NodeType::File, 0, true);
+        file.path = path;
+        let options = ExportOptions {
+            sizes: true,
+            ..Default::default()
+        };
+        assert_eq!(export(&[file], options), "data.bin (2.00 KB)\n");
+    }
+
+    #[test]
+    fn json_export_rows() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.txt"), "hello").unwrap();
+        let mut root = row("d", NodeType::Directory, 0, true);
+        root.path = dir.path().to_path_buf();
+        let mut file = row("a.txt", NodeType::File, 1, false);
+        file.path = dir.path().join("a.txt");
+        let mut more = row("Load more...", NodeType::LoadMore, 1, true);
+        more.path = dir.path().to_path_buf();
+        more.load_more_remaining = Some(3);
+        let items = vec![root, file, more];
+
+        let options = ExportOptions {
+            format: ExportFormat::Json,
+            load_more: true,
+            hidden_markers: false,
+            ..Default::default()
+        };
+        let json = export(&items, options);
+        let rows: Vec<serde_json::Value> = serde_json::from_str(&json).unwrap();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0]["type"], "directory");
+        assert!(rows[0]["size"].is_null());
+        assert_eq!(rows[1]["type"], "file");
+        assert_eq!(rows[1]["depth"], 1);
+        assert_eq!(rows[1]["size"], 5);
+        assert!(rows[1]["modified"].as_u64().is_some());
+        assert!(rows[1].get("hidden").is_none());
+        assert_eq!(rows[2]["type"], "load_more");
+        assert_eq!(rows[2]["remaining"], 3);
+
+        // Load more rows dropped, hidden flags added
+        let options = ExportOptions {
+            format: ExportFormat::Json,
+            ..Default::default()
+        };
+        let rows: Vec<serde_json::Value> = serde_json::from_str(&export(&items, options)).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1]["hidden"], false);
+
+        assert_eq!(export(&[], options), "[]\n");
+    }
+
+    #[test]
+    fn write_file_streams_to_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("tree.txt");
+        let rows = write_file(&sample(), &ExportOptions::default(), &target).unwrap();
+        assert_eq!(rows, 7);
+        let written = fs::read_to_string(&target).unwrap();
+        assert!(written.starts_with("r/\n├── src/\n"));
+    }
+}
@@ -0,0 +1,173 @@
+//! File classes for the one-key tree filter chips.
+//!
+//! A class is a predicate on files: an extension set (Code, Docs, Images,
+//! Archives) or a modification window (Today). The active class applies on
+//! top of the inline text filter, so `[Images] + 'logo'` keeps the images
+//! whose name contains "logo". Directories never match a class themselves;
+//! they stay in view as ancestors of matching files.
+
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+/// Default extensions of the Code class.
+pub const DEFAULT_CODE: &[&str] = &[
+    "rs", "py", "js", "ts", "jsx", "tsx", "go", "c", "h", "cc", "cpp", "hpp", "java", "kt",
+    "swift", "rb", "php", "cs", "sh", "bash", "zsh", "lua", "scala", "sql",
+];
+
+/// Default extensions of the Docs class.
+pub const DEFAULT_DOCS: &[&str] = &["md", "txt", "pdf", "rst"];
+
+/// Default extensions of the Images class.
+pub const DEFAULT_IMAGES: &[&str] = &[
+    "png", "jpg", "jpeg", "gif", "bmp", "webp", "svg", "ico", "tif", "tiff", "heic",
+];
+
+/// Default extensions of the Archives class.
+pub const DEFAULT_ARCHIVES: &[&str] = &[
+    "zip", "tar", "gz", "tgz", "bz2", "xz", "zst", "7z", "rar", "jar",
+];
+
+/// Default window of the Today class, in hours.
+pub const DEFAULT_TODAY_HOURS: u64 = 24;
+
+/// What a class matches files by.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Matcher {
+    /// Lowercase extensions without the dot; `tar.gz` style entries match
+    /// the end of the name.
+    Extensions(Vec<String>),
+    /// Modified no longer ago than this.
+    ModifiedWithin(Duration),
+}
+
+/// A named file class, shown as the chip `[name]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilterClass {
+    pub name: String,
+    pub matcher: Matcher,
+}
+
+impl FilterClass {
+    /// Class `name` matching `extensions`, normalised to lowercase without
+    /// a leading dot.
+    pub fn extensions(name: &str, extensions: &[String]) -> Self {
+        let extensions = extensions
+            .iter()
+            .map(|ext| ext.trim_start_matches('.').to_lowercase())
+            .filter(|ext| !ext.is_empty())
+            .collect();
+        Self {
+            name: name.to_string(),
+            matcher: Matcher::Extensions(extensions),
+        }
+    }
+
+    /// Whether a file named `name`, last modified at `modified`, belongs to
+    /// the class as of `now`. A file whose mtime isn't known yet doesn't.
+    pub fn matches(&self, name: &str, modified: Option<SystemTime>, now: SystemTime) -> bool {
+        match &self.matcher {
+            Matcher::Extensions(extensions) => {
+                let name = name.to_lowercase();
+                extensions.iter().any(|ext| {
+                    name.strip_suffix(ext.as_str())
+                        .is_some_and(|stem| stem.len() > 1 && stem.ends_with('.'))
+                })
+            }
+            Matcher::ModifiedWithin(window) => modified.is_some_and(|modified| {
+                // A clock set back can put the mtime in the future; count it
+                now.duration_since(modified)
+                    .map(|age| age <= *window)
+                    .unwrap_or(true)
+            }),
+        }
+    }
+
+    /// [`matches`](Self::matches) for a path off the tree, e.g. a finder
+    /// result. Only the Today class reads metadata, to rule out
+    /// directories and learn the mtime.
+    pub fn matches_path(&self, path: &Path, now: SystemTime) -> bool {
+        let name = match path.file_name() {
+            Some(name) => name.to_string_lossy(),
+            None => return false,
+        };
+        let modified = match self.matcher {
+            Matcher::Extensions(_) => None,
+            Matcher::ModifiedWithin(_) => match std::fs::symlink_metadata(path) {
+                Ok(meta) if !meta.is_dir() => meta.modified().ok(),
+                _ => return false,
+            },
+        };
+        self.matches(&name, modified, now)
+    }
+}
+
+/// The class after `current` in `classes`, or none after the last one.
+pub fn next(classes: &[FilterClass], current: Option<&FilterClass>) -> Option<FilterClass> {
+    let index = current
+        .and_then(|c| classes.iter().position(|other| other == c))
+        .map_or(0, |index| index + 1);
+    classes.get(index).cloned()
+}
+
+/// The class before `current` in `classes`, or none before the first one.
+pub fn previous(classes: &[FilterClass], current: Option<&FilterClass>) -> Option<FilterClass> {
+    match current.and_then(|c| classes.iter().position(|other| other == c)) {
+        Some(0) => None,
+        Some(index) => classes.get(index - 1).cloned(),
+        None => classes.last().cloned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(exts: &[&str]) -> Vec<String> {
+        exts.iter().map(|e| e.to_string()).collect()
+    }
+
+    #[test]
+    fn extension_classes_match_case_insensitively() {
+        let now = SystemTime::now();
+        let images = FilterClass::extensions("Images", &strings(&[".PNG", "jpg"]));
+        assert!(images.matches("logo.png", None, now));
+        assert!(images.matches("Photo.JPG", None, now));
+        assert!(!images.matches("png", None, now));
+        assert!(!images.matches(".png", None, now));
+        assert!(!images.matches("notes.png.txt", None, now));
+
+        let archives = FilterClass::extensions("Archives", &strings(&["tar.gz"]));
+        assert!(archives.matches("backup.tar.gz", None, now));
+        assert!(!archives.matches("backup.gz", None, now));
+    }
+
+    #[test]
+    fn modified_class_matches_within_window() {
+        let now = SystemTime::now();
+        let today = FilterClass {
+            name: "Today".to_string(),
+            matcher: Matcher::ModifiedWithin(Duration::from_secs(3600)),
+        };
+        assert!(today.matches("a", Some(now - Duration::from_secs(60)), now));
+        assert!(!today.matches("a", Some(now - Duration::from_secs(7200)), now));
+        assert!(today.matches("a", Some(now + Duration::from_secs(5)), now));
+        assert!(!today.matches("a", None, now));
+    }
+
+    #[test]
+    fn cycling_wraps_through_none() {
+        let classes = vec![
+            FilterClass::extensions("Code", &strings(&["rs"])),
+            FilterClass::extensions("Docs", &strings(&["md"])),
+        ];
+        let code = next(&classes, None);
+        assert_eq!(code.as_ref().unwrap().name, "Code");
+        let docs = next(&classes, code.as_ref());
+        assert_eq!(docs.as_ref().unwrap().name, "Docs");
+        assert_eq!(next(&classes, docs.as_ref()), None);
+
+        assert_eq!(previous(&classes, None).unwrap().name, "Docs");
+        assert_eq!(previous(&classes, code.as_ref()), None);
+    }
+}
@@ -0,0 +1,182 @@
+//! `.gitignore` rules for the tree, the fuzzy finder and the watcher
+//! (`tree.respect_gitignore`, toggled with `I`).
+//!
+//! A [`GitIgnore`] holds the rules in effect inside one directory: its own
+//! `.gitignore` on top of its parent's, which is shared rather than copied.
+//! Entering a subdirectory reads that one file, so listing deep in a tree
+//! never re-parses the ancestors' files. A chain starts over at each
+//! repository root (a directory holding `.git`), where
+//! `.git/info/exclude` applies as well; `.git` itself is always ignored.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+
+/// Rules in effect inside one directory.
+#[derive(Debug)]
+pub struct GitIgnore {
+    parent: Option<Arc<GitIgnore>>,
+    rules: Gitignore,
+}
+
+impl GitIgnore {
+    /// No rules at all.
+    pub fn empty() -> Arc<Self> {
+        Arc::new(Self {
+            parent: None,
+            rules: Gitignore::empty(),
+        })
+    }
+
+    /// The rules in effect where `path` sits: those of its directory, from
+    /// the repository root down. Empty when `path` is itself a repository
+    /// root or not inside one.
+    pub fn around(path: &Path) -> Arc<Self> {
+        let Some(dir) = path.parent() else {
+            return Self::empty();
+        };
+        if path.join(".git").exists() {
+            return Self::empty();
+        }
+        let Some(repo) = dir.ancestors().find(|a| a.join(".git").exists()) else {
+            return Self::empty();
+        };
+        let mut dirs: Vec<&Path> = dir.ancestors().take_while(|a| *a != repo).collect();
+        dirs.push(repo);
+        dirs.iter()
+            .rev()
+            .fold(Self::empty(), |rules, dir| rules.enter(dir))
+    }
+
+    /// The rules inside `dir`, a directory these rules apply to: its
+    /// `.gitignore` on top of these, or a fresh chain when `dir` is a
+    /// repository root.
+    pub fn enter(self: &Arc<Self>, dir: &Path) -> Arc<Self> {
+        let mut builder = GitignoreBuilder::new(dir);
+        let parent = if dir.join(".git").exists() {
+            builder.add(dir.join(".git/info/exclude"));
+            None
+        } else {
+            Some(Arc::clone(self))
+        };
+        // A missing or unreadable file just adds no rules
+        builder.add(dir.join(".gitignore"));
+        let rules = builder.build().unwrap_or_else(|_| Gitignore::empty());
+        if rules.is_empty() {
+            if let Some(parent) = parent {
+                return parent;
+            }
+        }
+        Arc::new(Self { parent, rules })
+    }
+
+    /// Whether `path`, an entry of the directory these rules belong to, is
+    /// ignored. The nearest `.gitignore` with a matching rule decides.
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        if path.file_name().is_some_and(|name| name == ".git") {
+            return true;
+        }
+        let mut level = Some(self);
+        while let Some(rules) = level {
+            match rules.rules.matched(path, is_dir) {
+                Match::Ignore(_) => return true,
+                Match::Whitelist(_) => return false,
+                Match::None => level = rules.parent.as_deref(),
+            }
+        }
+        false
+    }
+
+    /// Whether `path`, anywhere below `dir` (the directory these rules
+    /// belong to), is ignored itself or sits in an ignored directory.
+    /// Reads the `.gitignore` of every directory in between.
+    pub fn is_ignored_below(self: &Arc<Self>, dir: &Path, path: &Path) -> bool {
+        let Ok(relative) = path.strip_prefix(dir) else {
+            return false;
+        };
+        let mut rules = Arc::clone(self);
+        let mut current = PathBuf::from(dir);
+        let mut parts = relative.components().peekable();
+        while let Some(part) = parts.next() {
+            current.push(part);
+            let is_dir = parts.peek().is_some() || current.is_dir();
+            if rules.is_ignored(&current, is_dir) {
+                return true;
+            }
+            if parts.peek().is_some() {
+                rules = rules.enter(&current);
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    /// A repository ignoring `*.log` and `build/`, with `src/` keeping
+    /// `keep.log` and ignoring `gen/`.
+    fn repo() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join(".git/info")).unwrap();
+        fs::write(root.join(".git/info/exclude"), "secret.txt\n").unwrap();
+        fs::write(root.join(".gitignore"), "*.log\nbuild/\n").unwrap();
+        fs::create_dir_all(root.join("src/gen")).unwrap();
+        fs::write(root.join("src/.gitignore"), "!keep.log\ngen/\n").unwrap();
+        fs::create_dir(root.join("build")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn rules_apply_from_the_repository_root_down() {
+        let dir = repo();
+        let root = dir.path();
+        let top = GitIgnore::around(root).enter(root);
+        assert!(top.is_ignored(&root.join("app.log"), false));
+        assert!(top.is_ignored(&root.join("build"), true));
+        assert!(top.is_ignored(&root.join("secret.txt"), false));
+        assert!(top.is_ignored(&root.join(".git"), true));
+        assert!(!top.is_ignored(&root.join("src"), true));
+        // `build/` only matches directories
+        assert!(!top.is_ignored(&root.join("build"), false));
+
+        let src = top.enter(&root.join("src"));
+        assert!(src.is_ignored(&root.join("src/debug.log"), false));
+        assert!(!src.is_ignored(&root.join("src/keep.log"), false));
+        assert!(src.is_ignored(&root.join("src/gen"), true));
+    }
+
+    #[test]
+    fn rules_around_a_subdirectory_include_its_ancestors() {
+        let dir = repo();
+        let src = dir.path().join("src");
+        let rules = GitIgnore::around(&src).enter(&src);
+        assert!(rules.is_ignored(&src.join("debug.log"), false));
+        assert!(!rules.is_ignored(&src.join("keep.log"), false));
+
+        let outside = TempDir::new().unwrap();
+        fs::write(outside.path().join(".gitignore"), "*.log\n").unwrap();
+        let rules = GitIgnore::around(outside.path());
+        assert!(!rules.is_ignored(&outside.path().join("a.log"), false));
+    }
+
+    #[test]
+    fn entries_below_an_ignored_directory_are_ignored() {
+        let dir = repo();
+        let root = dir.path();
+        fs::write(root.join("src/gen/out.rs"), "").unwrap();
+        fs::write(root.join("src/main.rs"), "").unwrap();
+        let top = GitIgnore::around(root).enter(root);
+        assert!(top.is_ignored_below(root, &root.join("src/gen/out.rs")));
+        assert!(top.is_ignored_below(root, &root.join(".git/index")));
+        assert!(top.is_ignored_below(root, &root.join("build/x/y.o")));
+        assert!(!top.is_ignored_below(root, &root.join("src/main.rs")));
+        assert!(!top.is_ignored_below(root, &root.join("src/keep.log")));
+    }
+}
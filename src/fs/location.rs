@@ -0,0 +1,123 @@
+//! `path:line[:col]` references, as printed by compilers, grep and test
+//! runners, accepted by the go-to prompt, `--select` and the fuzzy finder.
+
+use std::path::PathBuf;
+
+/// A path with an optional 1-based line and column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    pub path: PathBuf,
+    pub line: Option<usize>,
+    pub col: Option<usize>,
+}
+
+/// Split `input` into a path and trailing `:line` / `:line:col` numbers.
+///
+/// A trailing `:` (as in grep's `path:line:text` once the text is cut) is
+/// ignored. A number is only taken when something other than a bare drive
+/// letter precedes it, so `C:\src\app.rs:12` has line 12 but `C:12` is
+/// left as a path.
+pub fn parse(input: &str) -> Location {
+    let input = input.trim();
+    if let Some(trimmed) = input.strip_suffix(':') {
+        let location = split_numbers(trimmed);
+        if location.line.is_some() {
+            return location;
+        }
+    }
+    split_numbers(input)
+}
+
+fn split_numbers(input: &str) -> Location {
+    let mut rest = input;
+    let mut numbers: Vec<usize> = Vec::new();
+    while numbers.len() < 2 {
+        let (prefix, suffix) = match rest.rsplit_once(':') {
+            Some(parts) => parts,
+            None => break,
+        };
+        if prefix.is_empty() || is_drive_letter(prefix) || !is_number(suffix) {
+            break;
+        }
+        match suffix.parse::<usize>() {
+            Ok(n) => numbers.push(n),
+            Err(_) => break,
+        }
+        rest = prefix;
+    }
+
+    let (line, col) = match numbers.as_slice() {
+        [line] => (Some(*line), None),
+        [col, line] => (Some(*line), Some(*col)),
+        _ => (None, None),
+    };
+    Location {
+        path: PathBuf::from(rest),
+        line,
+        col,
+    }
+}
+
+fn is_number(s: &str) -> bool {
+    !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// `C`, `d`, … — what precedes the colon of a Windows drive prefix.
+fn is_drive_letter(s: &str) -> bool {
+    s.len() == 1 && s.bytes().all(|b| b.is_ascii_alphabetic())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn loc(path: &str, line: Option<usize>, col: Option<usize>) -> Location {
+        Location {
+            path: PathBuf::from(path),
+            line,
+            col,
+        }
+    }
+
+    #[test]
+    fn plain_paths_have_no_line() {
+        assert_eq!(parse("src/app.rs"), loc("src/app.rs", None, None));
+        assert_eq!(parse("  notes.md "), loc("notes.md", None, None));
+        assert_eq!(parse("a:b/c.txt"), loc("a:b/c.txt", None, None));
+    }
+
+    #[test]
+    fn line_and_column_suffixes() {
+        assert_eq!(parse("src/app.rs:412"), loc("src/app.rs", Some(412), None));
+        assert_eq!(
+            parse("src/app.rs:412:7"),
+            loc("src/app.rs", Some(412), Some(7))
+        );
+        // grep -n output cut after the line number
+        assert_eq!(parse("src/app.rs:412:"), loc("src/app.rs", Some(412), None));
+        // At most line and column are taken
+        assert_eq!(parse("v1:2:3:4"), loc("v1:2", Some(3), Some(4)));
+        // Not numbers: part of the path
+        assert_eq!(parse("build:release"), loc("build:release", None, None));
+        assert_eq!(parse("file:"), loc("file:", None, None));
+        assert_eq!(parse(":12"), loc(":12", None, None));
+    }
+
+    #[test]
+    fn windows_drive_letters_are_not_line_numbers() {
+        assert_eq!(
+            parse(r"C:\src\app.rs:12:3"),
+            loc(r"C:\src\app.rs", Some(12), Some(3))
+        );
+        assert_eq!(
+            parse(r"C:\src\app.rs:12"),
+            loc(r"C:\src\app.rs", Some(12), None)
+        );
+        assert_eq!(parse(r"D:\data"), loc(r"D:\data", None, None));
+        assert_eq!(parse("C:12"), loc("C:12", None, None));
+        assert_eq!(
+            parse("c:/work/main.rs:5"),
+            loc("c:/work/main.rs", Some(5), None)
+        );
+    }
+}
@@ -1,4 +1,20 @@
+pub mod bulk_rename;
 pub mod clipboard;
+pub mod export;
+pub mod filter_class;
+pub mod gitignore;
+pub mod location;
+pub mod name_check;
 pub mod operations;
+pub mod overwrite;
+pub mod places;
+pub mod portability;
+pub mod retry;
+pub mod scaffold;
+pub mod send_to;
+pub mod sequence;
+pub mod trash;
 pub mod tree;
+pub mod undo_check;
+pub mod walk;
 pub mod watcher;
@@ -0,0 +1,460 @@
+//! Checks on a name about to be given to an item, shared by every dialog
+//! that produces one: Rename, bulk rename and sequence rename.
+//!
+//! [`validate_and_suggest`] looks at the proposed name against what is
+//! already in the target directory. Some problems block the name — empty,
+//! a `/`, an existing entry, or one that differs only by case or Unicode
+//! normalization (the same file on macOS and Windows). Others are
+//! warnings: the portability rules, and an extension the source had that
+//! the new name lost. For those a corrected name is suggested, which the
+//! dialogs offer on Tab ("did you mean 'photo_01.jpg'?").
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use unicode_normalization::UnicodeNormalization;
+
+use crate::fs::portability::{self, Issue};
+
+/// One thing wrong with a proposed name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NameProblem {
+    Empty,
+    /// A `/` or NUL, which no filesystem takes in a name.
+    Separator,
+    /// `.` or `..`.
+    DotName,
+    /// An entry with exactly this name is in the directory.
+    Exists(String),
+    /// An entry with this name up to case is in the directory.
+    CaseTwin(String),
+    /// An entry with this name in another Unicode normalization form.
+    FormTwin(String),
+    /// Breaks a portability rule; see [`portability::name_issues`].
+    Portability(Issue),
+    /// The source's extension, which the name no longer has.
+    ExtensionDropped(String),
+}
+
+impl NameProblem {
+    /// Whether the name can't be used at all, rather than being risky.
+    pub fn is_blocking(&self) -> bool {
+        !matches!(
+            self,
+            NameProblem::Portability(_) | NameProblem::ExtensionDropped(_)
+        )
+    }
+}
+
+impl fmt::Display for NameProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NameProblem::Empty => write!(f, "name is empty"),
+            NameProblem::Separator => write!(f, "names cannot contain '/'"),
+            NameProblem::DotName => write!(f, "'.' and '..' cannot be used as names"),
+            NameProblem::Exists(name) => write!(f, "{} already exists", name),
+            NameProblem::CaseTwin(name) => write!(f, "clashes with {} ignoring case", name),
+            NameProblem::FormTwin(name) => {
+                write!(f, "is {} in another Unicode form", name)
+            }
+            NameProblem::Portability(issue) => write!(f, "{}", issue.describe()),
+            NameProblem::ExtensionDropped(ext) => write!(f, "drops the .{} extension", ext),
+        }
+    }
+}
+
+/// Verdict on a proposed name.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NameCheck {
+    /// Blocking problems first, then warnings.
+    pub problems: Vec<NameProblem>,
+    /// A name without the warnings, to offer instead.
+    pub suggestion: Option<String>,
+}
+
+impl NameCheck {
+    /// The first problem that rules the name out.
+    pub fn blocking(&self) -> Option<&NameProblem> {
+        self.problems.iter().find(|p| p.is_blocking())
+    }
+
+    /// Every problem on one line, or `None` when the name is fine.
+    pub fn summary(&self) -> Option<String> {
+        if self.problems.is_empty() {
+            return None;
+        }
+        Some(
+            self.problems
+                .iter()
+                .map(NameProblem::to_string)
+                .collect::<Vec<_>>()
+                .join("; "),
+        )
+    }
+}
+
+/// Entries of a directory, read once and checked against many times.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Siblings {
+    dir: PathBuf,
+    /// Entry names, with whether each is a directory.
+    entries: HashMap<String, bool>,
+    /// Entry names by [`fold`]ed form.
+    folded: HashMap<String, Vec<String>>,
+}
+
+impl Siblings {
+    /// List `dir`; an unreadable one counts as empty.
+    pub fn read(dir: &Path) -> Self {
+        let entries = fs::read_dir(dir)
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .map(|e| {
+                        let is_dir = e.file_type().is_ok_and(|t| t.is_dir());
+                        (e.file_name().to_string_lossy().to_string(), is_dir)
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        Self::new(dir, entries)
+    }
+
+    /// Siblings from names and whether each is a directory.
+    pub fn new(dir: &Path, entries: impl IntoIterator<Item = (String, bool)>) -> Self {
+        let entries: HashMap<String, bool> = entries.into_iter().collect();
+        let mut folded: HashMap<String, Vec<String>> = HashMap::new();
+        for name in entries.keys() {
+            folded.entry(fold(name)).or_default().push(name.clone());
+        }
+        for names in folded.values_mut() {
+            names.sort();
+        }
+        Self {
+            dir: dir.to_path_buf(),
+            entries,
+            folded,
+        }
+    }
+
+    /// Check `proposed` as a name in this directory for `source`, the item
+    /// being renamed (`None` for a new one). The source itself never
+    /// counts as a clash, so a case-only rename goes through.
+    pub fn check(&self, proposed: &str, source: Option<&Path>) -> NameCheck {
+        let problems = self.problems(proposed, source);
+        let suggestion = self.suggest(proposed, source, &problems);
+        NameCheck {
+            problems,
+            suggestion,
+        }
+    }
+
+    fn problems(&self, proposed: &str, source: Option<&Path>) -> Vec<NameProblem> {
+        if proposed.is_empty() {
+            return vec![NameProblem::Empty];
+        }
+        if proposed.contains('/') || proposed.contains('\0') {
+            return vec![NameProblem::Separator];
+        }
+        if proposed == "." || proposed == ".." {
+            return vec![NameProblem::DotName];
+        }
+        let own = source
+            .filter(|s| s.parent() == Some(self.dir.as_path()))
+            .and_then(|s| s.file_name())
+            .map(|n| n.to_string_lossy().to_string());
+
+        let mut problems = Vec::new();
+        if self.entries.contains_key(proposed) && own.as_deref() != Some(proposed) {
+            problems.push(NameProblem::Exists(proposed.to_string()));
+        } else {
+            let twin = self
+                .folded
+                .get(&fold(proposed))
+                .into_iter()
+                .flatten()
+                .find(|name| *name != proposed && Some(name.as_str()) != own.as_deref());
+            if let Some(twin) = twin {
+                problems.push(if nfc(twin) == nfc(proposed) {
+                    NameProblem::FormTwin(twin.clone())
+                } else {
+                    NameProblem::CaseTwin(twin.clone())
+                });
+            }
+        }
+        problems.extend(
+            portability::name_issues(proposed)
+                .into_iter()
+                .map(NameProblem::Portability),
+        );
+        if let Some(ext) = source.and_then(|s| self.dropped_extension(s, proposed)) {
+            problems.push(NameProblem::ExtensionDropped(ext));
+        }
+        problems
+    }
+
+    /// The extension of `source` when `proposed` has none; directories
+    /// don't count.
+    fn dropped_extension(&self, source: &Path, proposed: &str) -> Option<String> {
+        let name = source.file_name()?.to_string_lossy().to_string();
+        let is_dir = match self.entries.get(&name) {
+            Some(&is_dir) if source.parent() == Some(self.dir.as_path()) => is_dir,
+            _ => source.is_dir(),
+        };
+        if is_dir {
+            return None;
+        }
+        dropped_extension(source, proposed)
+    }
+
+    /// A fix for the warnings that has no blocking problem itself.
+    fn suggest(
+        &self,
+        proposed: &str,
+        source: Option<&Path>,
+        problems: &[NameProblem],
+    ) -> Option<String> {
+        if problems.iter().any(NameProblem::is_blocking) {
+            return None;
+        }
+        let candidate = problems.iter().find_map(|p| match p {
+            NameProblem::ExtensionDropped(ext) => {
+                Some(format!("{}.{}", proposed.trim_end_matches('.'), ext))
+            }
+            _ => None,
+        });
+        let candidate = match candidate {
+            Some(candidate) => candidate,
+            None if !problems.is_empty() => portability::safe_name(proposed),
+            None => return None,
+        };
+        let fixed = self.problems(&candidate, source);
+        (candidate != proposed && !fixed.iter().any(NameProblem::is_blocking)).then_some(candidate)
+    }
+}
+
+/// Check `proposed` as the name of `source` (or of a new item, for
+/// `None`) in `dir`, and suggest a corrected one for warnings.
+pub fn validate_and_suggest(dir: &Path, proposed: &str, source: Option<&Path>) -> NameCheck {
+    Siblings::read(dir).check(proposed, source)
+}
+
+/// The extension of `source` when `proposed` has none, so `photo_01` for
+/// `IMG_2031.jpg` reports `jpg`.
+pub fn dropped_extension(source: &Path, proposed: &str) -> Option<String> {
+    let ext = source.extension()?.to_string_lossy().to_string();
+    let proposed = proposed.trim_end_matches('.');
+    if proposed.is_empty() || Path::new(proposed).extension().is_some() {
+        return None;
+    }
+    Some(ext)
+}
+
+/// Key under which names that are one file on a case-insensitive,
+/// normalizing filesystem meet.
+pub fn fold(name: &str) -> String {
+    nfc(name).to_lowercase()
+}
+
+fn nfc(name: &str) -> String {
+    name.nfc().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn siblings(names: &[&str]) -> Siblings {
+        Siblings::new(
+            Path::new("/d"),
+            names
+                .iter()
+                .map(|n| (n.trim_end_matches('/').to_string(), n.ends_with('/'))),
+        )
+    }
+
+    fn source(name: &str) -> PathBuf {
+        Path::new("/d").join(name)
+    }
+
+    #[test]
+    fn a_fresh_name_passes() {
+        let dir = siblings(&["a.txt", "photo.jpg"]);
+        assert_eq!(dir.check("b.txt", None), NameCheck::default());
+        let src = source("photo.jpg");
+        assert_eq!(dir.check("holiday.jpg", Some(&src)), NameCheck::default());
+        // Keeping the name, or changing only its case, is no clash
+        assert_eq!(dir.check("photo.jpg", Some(&src)), NameCheck::default());
+        assert_eq!(dir.check("Photo.jpg", Some(&src)), NameCheck::default());
+        assert_eq!(
+            dir.check("Photo.jpg", None).summary().unwrap(),
+            "clashes with photo.jpg ignoring case"
+        );
+    }
+
+    #[test]
+    fn malformed_names_block() {
+        let dir = siblings(&[]);
+        for (name, problem) in [
+            ("", NameProblem::Empty),
+            ("a/b", NameProblem::Separator),
+            ("a\0b", NameProblem::Separator),
+            (".", NameProblem::DotName),
+            ("..", NameProblem::DotName),
+        ] {
+            let check = dir.check(name, None);
+            assert_eq!(
+                check.problems.as_slice(),
+                std::slice::from_ref(&problem),
+                "{:?}",
+                name
+            );
+            assert_eq!(check.blocking(), Some(&problem));
+            assert_eq!(check.suggestion, None);
+        }
+    }
+
+    #[test]
+    fn existing_entries_block() {
+        let dir = siblings(&["a.txt", "b.txt"]);
+        let check = dir.check("b.txt", Some(&source("a.txt")));
+        assert_eq!(check.problems, [NameProblem::Exists("b.txt".into())]);
+        assert_eq!(check.summary().unwrap(), "b.txt already exists");
+        assert_eq!(check.suggestion, None);
+        // A source elsewhere isn't excused by sharing the name
+        let other = Path::new("/elsewhere/a.txt");
+        assert_eq!(
+            dir.check("a.txt", Some(other)).blocking(),
+            Some(&NameProblem::Exists("a.txt".into()))
+        );
+    }
+
+    #[test]
+    fn case_and_normalization_twins_block() {
+        // "é" precomposed, and as e + combining acute
+        let dir = siblings(&["README.md", "caf\u{e9}.txt"]);
+        let check = dir.check("readme.md", None);
+        assert_eq!(check.problems, [NameProblem::CaseTwin("README.md".into())]);
+        assert!(check.blocking().is_some());
+
+        let check = dir.check("cafe\u{301}.txt", None);
+        assert_eq!(
+            check.problems,
+            [NameProblem::FormTwin("caf\u{e9}.txt".into())]
+        );
+        assert_eq!(
+            check.summary().unwrap(),
+            "is caf\u{e9}.txt in another Unicode form"
+        );
+        let check = dir.check("CAFE\u{301}.TXT", None);
+        assert_eq!(
+            check.problems,
+            [NameProblem::CaseTwin("caf\u{e9}.txt".into())]
+        );
+    }
+
+    #[test]
+    fn portability_issues_warn_and_suggest_a_safe_name() {
+        let dir = siblings(&[]);
+        let check = dir.check("a:b.txt", None);
+        assert_eq!(
+            check.problems,
+            [NameProblem::Portability(Issue::ForbiddenChars(vec![':']))]
+        );
+        assert_eq!(check.blocking(), None);
+        assert_eq!(check.suggestion.as_deref(), Some("a_b.txt"));
+
+        let check = dir.check("aux.txt", None);
+        assert_eq!(check.problems, [NameProblem::Portability(Issue::Reserved)]);
+        assert_eq!(check.suggestion.as_deref(), Some("aux_.txt"));
+
+        // No suggestion when the safe name is taken
+        let taken = siblings(&["a_b.txt"]);
+        assert_eq!(taken.check("a:b.txt", None).suggestion, None);
+    }
+
+    #[test]
+    fn a_dropped_extension_is_offered_back() {
+        let dir = siblings(&["IMG_2031.jpg", "notes", "photos.d/"]);
+        let src = source("IMG_2031.jpg");
+        let check = dir.check("photo_01", Some(&src));
+        assert_eq!(
+            check.problems,
+            [NameProblem::ExtensionDropped("jpg".into())]
+        );
+        assert_eq!(check.blocking(), None);
+        assert_eq!(check.suggestion.as_deref(), Some("photo_01.jpg"));
+        assert_eq!(check.summary().unwrap(), "drops the .jpg extension");
+        // A trailing dot is replaced rather than doubled
+        let check = dir.check("photo_01.", Some(&src));
+        assert_eq!(check.suggestion.as_deref(), Some("photo_01.jpg"));
+
+        // Another extension is a choice, not a slip
+        assert_eq!(dir.check("photo_01.png", Some(&src)), NameCheck::default());
+        // Sources without an extension, and directories, keep nothing
+        assert_eq!(
+            dir.check("notes2", Some(&source("notes"))),
+            NameCheck::default()
+        );
+        assert_eq!(
+            dir.check("photos", Some(&source("photos.d"))),
+            NameCheck::default()
+        );
+        // Dotfiles have no extension to lose
+        assert_eq!(dropped_extension(Path::new(".bashrc"), "bashrc"), None);
+        assert_eq!(
+            dropped_extension(Path::new("a.tar.gz"), "b"),
+            Some("gz".into())
+        );
+    }
+
+    #[test]
+    fn a_suggestion_that_would_clash_is_withheld() {
+        let dir = siblings(&["IMG_2031.jpg", "photo_01.jpg"]);
+        let check = dir.check("photo_01", Some(&source("IMG_2031.jpg")));
+        assert_eq!(
+            check.problems,
+            [NameProblem::ExtensionDropped("jpg".into())]
+        );
+        assert_eq!(check.suggestion, None);
+    }
+
+    #[test]
+    fn blocking_problems_come_before_warnings() {
+        let dir = siblings(&["a:b", "x.txt"]);
+        let check = dir.check("a:b", Some(&source("x.txt")));
+        assert_eq!(
+            check.problems,
+            [
+                NameProblem::Exists("a:b".into()),
+                NameProblem::Portability(Issue::ForbiddenChars(vec![':'])),
+                NameProblem::ExtensionDropped("txt".into()),
+            ]
+        );
+        assert_eq!(check.blocking(), Some(&NameProblem::Exists("a:b".into())));
+        assert_eq!(check.suggestion, None);
+    }
+
+    #[test]
+    fn reading_a_directory_sees_its_entries() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        fs::write(tmp.path().join("a.txt"), "").unwrap();
+        fs::create_dir(tmp.path().join("sub.d")).unwrap();
+        let check = validate_and_suggest(tmp.path(), "A.TXT", None);
+        assert_eq!(check.problems, [NameProblem::CaseTwin("a.txt".into())]);
+        // Known to be a directory from the listing
+        let sub = tmp.path().join("sub.d");
+        assert_eq!(
+            validate_and_suggest(tmp.path(), "sub", Some(&sub)),
+            NameCheck::default()
+        );
+        // A missing directory has nothing to clash with
+        let gone = tmp.path().join("gone");
+        assert_eq!(
+            validate_and_suggest(&gone, "a.txt", None),
+            NameCheck::default()
+        );
+    }
+}
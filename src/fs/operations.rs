@@ -1,40 +1,150 @@
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 
-use crate::error::Result;
+use crate::error::{OpResult, OperationError};
+use crate::fs::tree::SpecialFile;
+use crate::fs::walk::{self, WalkOptions};
+
+/// Attach the path an I/O call was operating on, classifying the error.
+trait IoContext<T> {
+    fn at(self, path: &Path) -> OpResult<T>;
+}
+
+impl<T> IoContext<T> for std::io::Result<T> {
+    fn at(self, path: &Path) -> OpResult<T> {
+        self.map_err(|e| OperationError::from_io(e, path))
+    }
+}
+
+/// Classify an error from a two-path call (copy/rename): a missing item is
+/// reported against the source, everything else against the destination.
+fn pair_error(err: std::io::Error, src: &Path, dest: &Path) -> OperationError {
+    if err.kind() == std::io::ErrorKind::NotFound && !src.exists() {
+        OperationError::from_io(err, src)
+    } else {
+        OperationError::from_io(err, dest)
+    }
+}
+
+/// File name of `src`, or an error if it has none (e.g. `/`).
+fn source_name(src: &Path) -> OpResult<&std::ffi::OsStr> {
+    src.file_name().ok_or_else(|| OperationError::Other {
+        path: src.to_path_buf(),
+        source: std::io::Error::new(std::io::ErrorKind::InvalidInput, "no filename"),
+    })
+}
 
 /// Create an empty file at the given path.
 #[allow(dead_code)]
-pub fn create_file(path: &Path) -> Result<()> {
-    fs::File::create(path)?;
+pub fn create_file(path: &Path) -> OpResult<()> {
+    fs::File::create(path).at(path)?;
     Ok(())
 }
 
 /// Create a new directory at the given path.
 #[allow(dead_code)]
-pub fn create_dir(path: &Path) -> Result<()> {
-    fs::create_dir(path)?;
+pub fn create_dir(path: &Path) -> OpResult<()> {
+    fs::create_dir(path).at(path)?;
     Ok(())
 }
 
+/// Split what was typed into the create-file dialog into a path relative
+/// to the directory it goes in, and whether it names a directory (a
+/// trailing `/`). Absolute paths and `..` are refused, so nothing lands
+/// outside that directory.
+pub fn parse_new_path(input: &str) -> Result<(PathBuf, bool), String> {
+    let is_dir = input.ends_with('/') || input.ends_with(std::path::MAIN_SEPARATOR);
+    let mut relative = PathBuf::new();
+    for component in Path::new(input).components() {
+        match component {
+            Component::Normal(part) => relative.push(part),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                return Err("'..' is not allowed; create it from the parent directory".to_string())
+            }
+            Component::RootDir | Component::Prefix(_) => {
+                return Err(
+                    "absolute paths are not allowed; give a path below the current directory"
+                        .to_string(),
+                )
+            }
+        }
+    }
+    if relative.as_os_str().is_empty() {
+        return Err("enter a name".to_string());
+    }
+    Ok((relative, is_dir))
+}
+
+/// Create `path` as an empty file, or as a directory when `is_dir`,
+/// making missing parent directories first.
+pub fn create_nested(path: &Path, is_dir: bool) -> OpResult<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).at(parent)?;
+    }
+    if is_dir {
+        create_dir(path)
+    } else {
+        create_file(path)
+    }
+}
+
 /// Rename (move) a file or directory from one path to another.
 #[allow(dead_code)]
-pub fn rename(from: &Path, to: &Path) -> Result<()> {
-    fs::rename(from, to)?;
+pub fn rename(from: &Path, to: &Path) -> OpResult<()> {
+    fs::rename(from, to).map_err(|e| pair_error(e, from, to))?;
     Ok(())
 }
 
-/// Delete a file or directory. Directories are removed recursively.
+/// Delete a file or directory. Directories are removed recursively; a
+/// symlink is removed itself, whatever it points to.
 #[allow(dead_code)]
-pub fn delete(path: &Path) -> Result<()> {
-    if path.is_dir() {
-        fs::remove_dir_all(path)?;
+pub fn delete(path: &Path) -> OpResult<()> {
+    let is_dir = fs::symlink_metadata(path).is_ok_and(|m| m.is_dir());
+    if is_dir {
+        fs::remove_dir_all(path).at(path)?;
     } else {
-        fs::remove_file(path)?;
+        fs::remove_file(path).at(path)?;
     }
     Ok(())
 }
 
+/// Permission bits of `path`, following symlinks: rwx for owner, group
+/// and others, plus setuid, setgid and sticky.
+#[cfg(unix)]
+pub fn file_mode(path: &Path) -> OpResult<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    Ok(fs::metadata(path).at(path)?.permissions().mode() & 0o7777)
+}
+
+#[cfg(not(unix))]
+pub fn file_mode(path: &Path) -> OpResult<u32> {
+    Err(unsupported(path))
+}
+
+/// Set the permission bits of `path` (a chmod), following symlinks.
+#[cfg(unix)]
+pub fn set_permissions(path: &Path, mode: u32) -> OpResult<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode)).at(path)
+}
+
+#[cfg(not(unix))]
+pub fn set_permissions(path: &Path, _mode: u32) -> OpResult<()> {
+    Err(unsupported(path))
+}
+
+#[cfg(not(unix))]
+fn unsupported(path: &Path) -> OperationError {
+    OperationError::Other {
+        path: path.to_path_buf(),
+        source: std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "Unix permissions are not supported here",
+        ),
+    }
+}
+
 /// Progress callback for recursive delete operations.
 pub type DeleteProgressFn = Box<dyn Fn(&str, usize) + Send>;
 
@@ -42,24 +152,27 @@ pub type DeleteProgressFn = Box<dyn Fn(&str, usize) + Send>;
 ///
 /// For files: simply deletes the file.
 /// For directories: walks the tree, collecting all files first, then deletes
-/// bottom-up (files, then empty dirs).
+/// bottom-up (files, then empty dirs). Symlinks are removed, never followed.
 ///
 /// - `progress_fn`: called with `(current_file_name, items_deleted_so_far)`
 /// - `cancel`: checked between each file deletion; if set, stops early
 ///
-/// Returns `(deleted_count, errors)`.
-#[allow(dead_code)]
+/// Returns `(deleted_count, errors)`; a cancelled run ends with
+/// [`OperationError::Cancelled`].
 pub fn delete_recursive_with_progress(
     path: &Path,
     progress_fn: &DeleteProgressFn,
     cancel: &std::sync::atomic::AtomicBool,
-) -> (usize, Vec<String>) {
+) -> (usize, Vec<OperationError>) {
     use std::sync::atomic::Ordering;
 
     let mut deleted = 0;
     let mut errors = Vec::new();
 
-    if !path.is_dir() {
+    let is_dir = fs::symlink_metadata(path)
+        .map(|m| m.is_dir())
+        .unwrap_or(false);
+    if !is_dir {
         // Simple file delete
         let name = path
             .file_name()
@@ -68,7 +181,7 @@ pub fn delete_recursive_with_progress(
         progress_fn(&name, 0);
         match fs::remove_file(path) {
             Ok(()) => deleted += 1,
-            Err(e) => errors.push(format!("{}: {}", path.display(), e)),
+            Err(e) => errors.push(OperationError::from_io(e, path)),
         }
         return (deleted, errors);
     }
@@ -86,7 +199,7 @@ pub fn delete_recursive_with_progress(
         let entries = match fs::read_dir(&dir) {
             Ok(e) => e,
             Err(e) => {
-                errors.push(format!("{}: {}", dir.display(), e));
+                errors.push(OperationError::from_io(e, &dir));
                 continue;
             }
         };
@@ -94,12 +207,14 @@ pub fn delete_recursive_with_progress(
             let entry = match entry {
                 Ok(e) => e,
                 Err(e) => {
-                    errors.push(format!("read_dir entry: {}", e));
+                    errors.push(OperationError::from_io(e, &dir));
                     continue;
                 }
             };
             let entry_path = entry.path();
-            if entry_path.is_dir() {
+            // `file_type` doesn't follow links, so a linked directory is
+            // unlinked rather than emptied
+            if entry.file_type().map(|t| t.is_dir()).unwrap_or(false) {
                 // Skip symlink loops
                 if visited.visit(&entry_path) {
                     stack.push(entry_path);
@@ -113,6 +228,7 @@ pub fn delete_recursive_with_progress(
     // Delete files first
     for file in &files {
         if cancel.load(Ordering::Relaxed) {
+            errors.push(OperationError::Cancelled);
             return (deleted, errors);
         }
         let name = file
@@ -122,7 +238,7 @@ pub fn delete_recursive_with_progress(
         progress_fn(&name, deleted);
         match fs::remove_file(file) {
             Ok(()) => deleted += 1,
-            Err(e) => errors.push(format!("{}: {}", file.display(), e)),
+            Err(e) => errors.push(OperationError::from_io(e, file)),
         }
     }
 
@@ -130,6 +246,7 @@ pub fn delete_recursive_with_progress(
     dirs.reverse();
     for dir in &dirs {
         if cancel.load(Ordering::Relaxed) {
+            errors.push(OperationError::Cancelled);
             return (deleted, errors);
         }
         let name = dir
@@ -139,13 +256,60 @@ pub fn delete_recursive_with_progress(
         progress_fn(&name, deleted);
         match fs::remove_dir(dir) {
             Ok(()) => deleted += 1,
-            Err(e) => errors.push(format!("{}: {}", dir.display(), e)),
+            Err(e) => errors.push(OperationError::from_io(e, dir)),
         }
     }
 
     (deleted, errors)
 }
 
+/// Combined size of a set of paths, as counted before deleting or copying
+/// them.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DiskUsage {
+    pub bytes: u64,
+    /// Every file, directory and link walked, the paths themselves included.
+    pub entries: usize,
+    /// Entries that aren't directories.
+    pub files: usize,
+    /// `false` when the walk stopped at its entry cap, was cancelled or hit
+    /// an unreadable directory, making the counts lower bounds.
+    pub complete: bool,
+}
+
+/// Sum the sizes of `paths`, walking directories without following
+/// symlinks and giving up after `max_entries` entries.
+pub fn disk_usage(paths: &[PathBuf], max_entries: usize) -> DiskUsage {
+    disk_usage_until(
+        paths,
+        max_entries,
+        &std::sync::atomic::AtomicBool::new(false),
+    )
+}
+
+/// [`disk_usage`] that also gives up once `cancel` is set. Counts cross
+/// mount points, as the copy or delete they size does.
+pub fn disk_usage_until(
+    paths: &[PathBuf],
+    max_entries: usize,
+    cancel: &std::sync::atomic::AtomicBool,
+) -> DiskUsage {
+    let options = WalkOptions {
+        max_entries: max_entries as u64,
+        deadline: None,
+        one_file_system: false,
+    };
+    match walk::walk(paths, &options, cancel, &mut |_| {}) {
+        Some(usage) => DiskUsage {
+            bytes: usage.bytes,
+            entries: usage.entries as usize,
+            files: usage.files as usize,
+            complete: !usage.partial,
+        },
+        None => DiskUsage::default(),
+    }
+}
+
 /// Resolve a name collision by appending `_copy`, `_copy2`, etc.
 ///
 /// Returns a path that does not exist yet in the destination directory.
@@ -182,64 +346,224 @@ pub fn resolve_collision(dest: &Path) -> PathBuf {
     dest.to_path_buf()
 }
 
+/// Bytes read and written per round of a file copy; each round is
+/// reported to [`CopyProgress::bytes`].
+pub const COPY_CHUNK: usize = 1024 * 1024;
+
+/// Receives progress from the copying functions.
+pub trait CopyProgress {
+    /// A file or directory is about to be copied.
+    fn entry(&mut self, path: &Path);
+
+    /// Another `n` bytes of the current file were written.
+    fn bytes(&mut self, _n: u64) {}
+
+    /// Whether to stop; checked before every chunk.
+    fn cancelled(&self) -> bool {
+        false
+    }
+}
+
+/// A closure sees entries only.
+impl<F: FnMut(&Path)> CopyProgress for F {
+    fn entry(&mut self, path: &Path) {
+        self(path)
+    }
+}
+
 /// Recursively copy a file or directory from `src` to `dest_dir`.
 ///
 /// Returns the final path of the copied item (with collision resolution).
 #[allow(dead_code)]
-pub fn copy_recursive(src: &Path, dest_dir: &Path) -> Result<PathBuf> {
-    let name = src
-        .file_name()
-        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "no filename"))?;
+pub fn copy_recursive(src: &Path, dest_dir: &Path) -> OpResult<PathBuf> {
+    copy_recursive_with_progress(src, dest_dir, &mut |_: &Path| {})
+}
+
+/// [`copy_recursive`], reporting each file and directory and every chunk
+/// written to `progress`. A cancelled copy fails with
+/// [`OperationError::Cancelled`], leaving no partly written file behind.
+pub fn copy_recursive_with_progress(
+    src: &Path,
+    dest_dir: &Path,
+    progress: &mut dyn CopyProgress,
+) -> OpResult<PathBuf> {
+    let name = source_name(src)?;
     let dest = resolve_collision(&dest_dir.join(name));
+    copy_to(src, &dest, progress)?;
+    Ok(dest)
+}
 
+/// Copy `src` to exactly `dest`, recursing into directories. A file
+/// already at `dest` is replaced, and a directory there gets `src`'s
+/// contents merged into it.
+pub fn copy_to(src: &Path, dest: &Path, progress: &mut dyn CopyProgress) -> OpResult<()> {
     if src.is_dir() {
-        copy_dir_recursive(src, &dest)?;
+        copy_dir_recursive(src, dest, progress)
     } else {
-        fs::copy(src, &dest)?;
+        progress.entry(src);
+        copy_file(src, dest, progress)
+    }
+}
+
+/// Path for a copy of `src` next to it that doesn't exist yet:
+/// `foo.txt` → `foo (copy).txt`, then `foo (copy 2).txt`, and so on.
+/// Directories keep their whole name as the stem, and a copy of a copy
+/// counts on from the original name.
+pub fn duplicate_name(src: &Path) -> OpResult<PathBuf> {
+    let name = source_name(src)?.to_string_lossy().to_string();
+    let parent = src.parent().unwrap_or(Path::new("."));
+    let (stem, ext) = match name.rfind('.') {
+        Some(dot) if dot > 0 && !src.is_dir() => (&name[..dot], &name[dot..]),
+        _ => (name.as_str(), ""),
+    };
+    let stem = strip_copy_suffix(stem);
+    let mut n = 1;
+    loop {
+        let suffix = match n {
+            1 => " (copy)".to_string(),
+            n => format!(" (copy {})", n),
+        };
+        let candidate = parent.join(format!("{}{}{}", stem, suffix, ext));
+        if fs::symlink_metadata(&candidate).is_err() {
+            return Ok(candidate);
+        }
+        n += 1;
+    }
+}
+
+/// `stem` without a trailing " (copy)" or " (copy N)".
+fn strip_copy_suffix(stem: &str) -> &str {
+    if let Some(base) = stem.strip_suffix(" (copy)") {
+        return base;
+    }
+    stem.strip_suffix(')')
+        .and_then(|rest| rest.rsplit_once(" (copy "))
+        .filter(|(_, n)| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+        .map_or(stem, |(base, _)| base)
+}
+
+/// Copy `src` into its own directory under [`duplicate_name`], reporting
+/// to `progress` like [`copy_recursive_with_progress`]. A copy that fails
+/// or is cancelled is removed again.
+pub fn duplicate_with_progress(src: &Path, progress: &mut dyn CopyProgress) -> OpResult<PathBuf> {
+    let dest = duplicate_name(src)?;
+    if let Err(e) = copy_to(src, &dest, progress) {
+        if dest.is_dir() {
+            let _ = fs::remove_dir_all(&dest);
+        }
+        return Err(e);
     }
     Ok(dest)
 }
 
 /// Internal recursive directory copy.
-fn copy_dir_recursive(src: &Path, dest: &Path) -> Result<()> {
-    fs::create_dir_all(dest)?;
-    for entry in fs::read_dir(src)? {
-        let entry = entry?;
+fn copy_dir_recursive(src: &Path, dest: &Path, progress: &mut dyn CopyProgress) -> OpResult<()> {
+    progress.entry(src);
+    fs::create_dir_all(dest).at(dest)?;
+    for entry in fs::read_dir(src).at(src)? {
+        let entry = entry.at(src)?;
         let src_path = entry.path();
         let dest_path = dest.join(entry.file_name());
         if src_path.is_dir() {
-            copy_dir_recursive(&src_path, &dest_path)?;
+            copy_dir_recursive(&src_path, &dest_path, progress)?;
         } else {
-            fs::copy(&src_path, &dest_path)?;
+            progress.entry(&src_path);
+            copy_file(&src_path, &dest_path, progress)?;
         }
     }
     Ok(())
 }
 
+/// Copy one file in [`COPY_CHUNK`] pieces and carry over its permissions.
+/// On any failure, cancellation included, the partly written `dest` is
+/// removed.
+fn copy_file(src: &Path, dest: &Path, progress: &mut dyn CopyProgress) -> OpResult<()> {
+    // Opening a FIFO blocks until a writer shows up
+    if let Some(special) = SpecialFile::at(src) {
+        return Err(OperationError::SpecialFile {
+            path: src.to_path_buf(),
+            kind: special.label(),
+        });
+    }
+    let mut reader = fs::File::open(src).map_err(|e| pair_error(e, src, dest))?;
+    let permissions = reader.metadata().at(src)?.permissions();
+    let mut writer = fs::File::create(dest).at(dest)?;
+    let result = copy_chunks(&mut reader, &mut writer, src, dest, progress)
+        .and_then(|()| fs::set_permissions(dest, permissions).at(dest));
+    if result.is_err() {
+        drop(writer);
+        let _ = fs::remove_file(dest);
+    }
+    result
+}
+
+/// The read/write loop of [`copy_file`].
+fn copy_chunks(
+    reader: &mut fs::File,
+    writer: &mut fs::File,
+    src: &Path,
+    dest: &Path,
+    progress: &mut dyn CopyProgress,
+) -> OpResult<()> {
+    use std::io::{ErrorKind, Read, Write};
+
+    let mut buf = vec![0; COPY_CHUNK];
+    loop {
+        if progress.cancelled() {
+            return Err(OperationError::Cancelled);
+        }
+        let n = match reader.read(&mut buf) {
+            Ok(0) => return Ok(()),
+            Ok(n) => n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(OperationError::from_io(e, src)),
+        };
+        writer.write_all(&buf[..n]).at(dest)?;
+        progress.bytes(n as u64);
+    }
+}
+
 /// Move a file or directory from `src` to `dest_dir`.
 ///
 /// Uses `fs::rename` first (fast, same-device). Falls back to copy+delete
 /// if rename fails (cross-device). Returns the final path.
 #[allow(dead_code)]
-pub fn move_item(src: &Path, dest_dir: &Path) -> Result<PathBuf> {
-    let name = src
-        .file_name()
-        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "no filename"))?;
+pub fn move_item(src: &Path, dest_dir: &Path) -> OpResult<PathBuf> {
+    move_item_with_progress(src, dest_dir, &mut |_: &Path| {})
+}
+
+/// [`move_item`], reporting to `progress` as [`copy_recursive_with_progress`]
+/// does when the move falls back to copy+delete. A rename reports nothing.
+/// The source is only removed once its copy is complete.
+pub fn move_item_with_progress(
+    src: &Path,
+    dest_dir: &Path,
+    progress: &mut dyn CopyProgress,
+) -> OpResult<PathBuf> {
+    let name = source_name(src)?;
     let dest = resolve_collision(&dest_dir.join(name));
+    move_to(src, &dest, progress)?;
+    Ok(dest)
+}
 
+/// Move `src` to exactly `dest`, as [`move_item_with_progress`] does. A
+/// file already at `dest` is replaced, and a non-empty directory there
+/// gets `src`'s contents merged into it.
+pub fn move_to(src: &Path, dest: &Path, progress: &mut dyn CopyProgress) -> OpResult<()> {
     // Try rename first (same filesystem, instant)
-    match fs::rename(src, &dest) {
-        Ok(()) => Ok(dest),
+    match fs::rename(src, dest) {
+        Ok(()) => Ok(()),
         Err(_) => {
             // Fallback: copy then delete (cross-device)
             if src.is_dir() {
-                copy_dir_recursive(src, &dest)?;
-                fs::remove_dir_all(src)?;
+                copy_dir_recursive(src, dest, progress)?;
+                fs::remove_dir_all(src).at(src)?;
             } else {
-                fs::copy(src, &dest)?;
-                fs::remove_file(src)?;
+                progress.entry(src);
+                copy_file(src, dest, progress)?;
+                fs::remove_file(src).at(src)?;
             }
-            Ok(dest)
+            Ok(())
         }
     }
 }
@@ -277,6 +601,46 @@ mod tests {
         assert!(new_path.exists());
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn fifos_rename_and_delete_but_refuse_copying() {
+        let tmp = TempDir::new().unwrap();
+        let fifo = tmp.path().join("pipe");
+        if !crate::fs::tree::mkfifo(&fifo) {
+            return;
+        }
+        let renamed = tmp.path().join("renamed");
+        rename(&fifo, &renamed).unwrap();
+        assert_eq!(SpecialFile::at(&renamed), Some(SpecialFile::Fifo));
+
+        // Copying would block on opening the FIFO; it fails at once instead
+        let dest = tmp.path().join("dest");
+        fs::create_dir(&dest).unwrap();
+        let err = copy_recursive(&renamed, &dest).unwrap_err();
+        assert!(matches!(err, OperationError::SpecialFile { .. }), "{err}");
+        assert!(err.to_string().contains("named pipe"));
+        assert!(!dest.join("renamed").exists());
+
+        delete(&renamed).unwrap();
+        assert!(fs::symlink_metadata(&renamed).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn permissions_are_set_and_read_back() {
+        let tmp = TempDir::new().unwrap();
+        let file = tmp.path().join("script.sh");
+        create_file(&file).unwrap();
+        set_permissions(&file, 0o750).unwrap();
+        assert_eq!(file_mode(&file).unwrap(), 0o750);
+        set_permissions(&file, 0o4644).unwrap();
+        assert_eq!(file_mode(&file).unwrap(), 0o4644);
+
+        let gone = tmp.path().join("gone");
+        let err = set_permissions(&gone, 0o644).unwrap_err();
+        assert!(matches!(err, OperationError::NotFound { .. }));
+    }
+
     #[test]
     fn test_delete_file() {
         let tmp = TempDir::new().unwrap();
@@ -310,6 +674,40 @@ mod tests {
         assert!(create_file(&file_path).is_ok());
     }
 
+    #[test]
+    fn new_paths_stay_below_the_directory() {
+        let parse = |input: &str| parse_new_path(input);
+        assert_eq!(
+            parse("src/utils/helpers.rs"),
+            Ok((PathBuf::from("src/utils/helpers.rs"), false))
+        );
+        assert_eq!(parse("notes.txt"), Ok((PathBuf::from("notes.txt"), false)));
+        assert_eq!(parse("build/out/"), Ok((PathBuf::from("build/out"), true)));
+        assert_eq!(parse("./a//b"), Ok((PathBuf::from("a/b"), false)));
+        assert!(parse("../escape.txt").unwrap_err().contains("'..'"));
+        assert!(parse("a/../../b").unwrap_err().contains("'..'"));
+        assert!(parse("/etc/passwd")
+            .unwrap_err()
+            .starts_with("absolute paths are not allowed"));
+        assert_eq!(parse("./"), Err("enter a name".to_string()));
+    }
+
+    #[test]
+    fn create_nested_makes_missing_parents() {
+        let tmp = TempDir::new().unwrap();
+        let file = tmp.path().join("src/utils/helpers.rs");
+        create_nested(&file, false).unwrap();
+        assert!(file.is_file());
+        let dir = tmp.path().join("src/utils/more");
+        create_nested(&dir, true).unwrap();
+        assert!(dir.is_dir());
+        // The last component must be new for a directory
+        assert!(matches!(
+            create_nested(&dir, true),
+            Err(OperationError::DestinationExists { .. })
+        ));
+    }
+
     #[test]
     fn test_create_dir_already_exists_fails() {
         let tmp = TempDir::new().unwrap();
@@ -318,12 +716,26 @@ mod tests {
         assert!(create_dir(&dir_path).is_err());
     }
 
+    #[test]
+    fn test_create_dir_already_exists_is_destination_exists() {
+        let tmp = TempDir::new().unwrap();
+        let dir_path = tmp.path().join("dup");
+        create_dir(&dir_path).unwrap();
+        match create_dir(&dir_path) {
+            Err(OperationError::DestinationExists { path }) => assert_eq!(path, dir_path),
+            other => panic!("expected DestinationExists, got {:?}", other),
+        }
+    }
+
     #[test]
     fn test_rename_nonexistent_fails() {
         let tmp = TempDir::new().unwrap();
         let from = tmp.path().join("no_such_file.txt");
         let to = tmp.path().join("dest.txt");
-        assert!(rename(&from, &to).is_err());
+        match rename(&from, &to) {
+            Err(OperationError::NotFound { path }) => assert_eq!(path, from),
+            other => panic!("expected NotFound, got {:?}", other),
+        }
     }
 
     #[test]
@@ -373,6 +785,59 @@ mod tests {
         assert_eq!(result, tmp.path().join("file_copy2.txt"));
     }
 
+    #[test]
+    fn duplicate_names_count_up_past_existing_copies() {
+        let tmp = TempDir::new().unwrap();
+        let src = tmp.path().join("foo.txt");
+        fs::write(&src, "x").unwrap();
+        assert_eq!(
+            duplicate_name(&src).unwrap(),
+            tmp.path().join("foo (copy).txt")
+        );
+        fs::write(tmp.path().join("foo (copy).txt"), "x").unwrap();
+        assert_eq!(
+            duplicate_name(&src).unwrap(),
+            tmp.path().join("foo (copy 2).txt")
+        );
+        // A copy of a copy counts on from the original name
+        fs::write(tmp.path().join("foo (copy 2).txt"), "x").unwrap();
+        assert_eq!(
+            duplicate_name(&tmp.path().join("foo (copy 2).txt")).unwrap(),
+            tmp.path().join("foo (copy 3).txt")
+        );
+
+        // Directories and dotfiles keep their whole name
+        let dir = tmp.path().join("site.v2");
+        fs::create_dir(&dir).unwrap();
+        assert_eq!(
+            duplicate_name(&dir).unwrap(),
+            tmp.path().join("site.v2 (copy)")
+        );
+        let dotfile = tmp.path().join(".env");
+        assert_eq!(
+            duplicate_name(&dotfile).unwrap(),
+            tmp.path().join(".env (copy)")
+        );
+        assert_eq!(strip_copy_suffix("notes (copy x)"), "notes (copy x)");
+    }
+
+    #[test]
+    fn duplicate_copies_a_directory_next_to_itself() {
+        let tmp = TempDir::new().unwrap();
+        let src = tmp.path().join("proj");
+        fs::create_dir_all(src.join("sub")).unwrap();
+        fs::write(src.join("sub").join("a.txt"), "aaa").unwrap();
+        let mut seen = 0;
+        let dest = duplicate_with_progress(&src, &mut |_: &Path| seen += 1).unwrap();
+        assert_eq!(dest, tmp.path().join("proj (copy)"));
+        assert_eq!(
+            fs::read_to_string(dest.join("sub").join("a.txt")).unwrap(),
+            "aaa"
+        );
+        assert_eq!(seen, 3);
+        assert!(src.join("sub").join("a.txt").exists());
+    }
+
     #[test]
     fn test_copy_directory_recursive() {
         let tmp = TempDir::new().unwrap();
@@ -527,11 +992,171 @@ mod tests {
 
         // Set cancel immediately
         cancel.store(true, std::sync::atomic::Ordering::SeqCst);
-        let (deleted, _errors) = delete_recursive_with_progress(&dir, &progress, &cancel);
+        let (deleted, errors) = delete_recursive_with_progress(&dir, &progress, &cancel);
 
         // Cancelled before deleting any files
         assert_eq!(deleted, 0);
+        assert!(matches!(errors.as_slice(), [OperationError::Cancelled]));
         // Directory and files still exist
         assert!(dir.exists());
     }
+
+    #[test]
+    fn test_disk_usage_sums_files_and_directories() {
+        let tmp = TempDir::new().unwrap();
+        let dir = tmp.path().join("dir");
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        fs::write(dir.join("a.txt"), "12345").unwrap();
+        fs::write(dir.join("nested").join("b.txt"), "123").unwrap();
+        let file = tmp.path().join("c.txt");
+        fs::write(&file, "12").unwrap();
+
+        let usage = disk_usage(&[dir.clone(), file.clone()], 100);
+        assert_eq!(usage.bytes, 10);
+        assert_eq!(usage.entries, 5); // dir, nested, a.txt, b.txt, c.txt
+        assert_eq!(usage.files, 3);
+        assert!(usage.complete);
+
+        // Capped walks report a lower bound
+        let usage = disk_usage(&[dir, file], 2);
+        assert!(!usage.complete);
+        assert!(usage.bytes < 10);
+
+        let usage = disk_usage(&[tmp.path().join("missing")], 100);
+        assert_eq!(usage.bytes, 0);
+        assert!(!usage.complete);
+    }
+
+    #[test]
+    fn test_disk_usage_until_stops_when_cancelled() {
+        let tmp = TempDir::new().unwrap();
+        fs::write(tmp.path().join("a.txt"), "a").unwrap();
+        let cancel = std::sync::atomic::AtomicBool::new(true);
+        let usage = disk_usage_until(&[tmp.path().to_path_buf()], 100, &cancel);
+        assert_eq!(usage.entries, 0);
+        assert!(!usage.complete);
+    }
+
+    #[test]
+    fn test_copy_progress_matches_disk_usage_entries() {
+        let tmp = TempDir::new().unwrap();
+        let src = tmp.path().join("src");
+        fs::create_dir_all(src.join("nested")).unwrap();
+        fs::write(src.join("a.txt"), "a").unwrap();
+        fs::write(src.join("nested").join("b.txt"), "b").unwrap();
+        let dest = tmp.path().join("dest");
+        fs::create_dir(&dest).unwrap();
+
+        let mut seen = 0;
+        copy_recursive_with_progress(&src, &dest, &mut |_: &Path| seen += 1).unwrap();
+        assert_eq!(seen, disk_usage(&[src], 100).entries);
+        assert!(dest.join("src").join("nested").join("b.txt").exists());
+    }
+
+    /// Counts chunks and cancels after `cancel_after` of them.
+    struct Chunks {
+        written: Vec<u64>,
+        cancel_after: usize,
+    }
+
+    impl CopyProgress for Chunks {
+        fn entry(&mut self, _path: &Path) {}
+
+        fn bytes(&mut self, n: u64) {
+            self.written.push(n);
+        }
+
+        fn cancelled(&self) -> bool {
+            self.written.len() >= self.cancel_after
+        }
+    }
+
+    #[test]
+    fn test_copy_reports_bytes_per_chunk() {
+        let tmp = TempDir::new().unwrap();
+        let src = tmp.path().join("big.bin");
+        let size = COPY_CHUNK * 2 + 10;
+        fs::write(&src, vec![7u8; size]).unwrap();
+        let dest = tmp.path().join("dest");
+        fs::create_dir(&dest).unwrap();
+
+        let mut chunks = Chunks {
+            written: Vec::new(),
+            cancel_after: usize::MAX,
+        };
+        let copied = copy_recursive_with_progress(&src, &dest, &mut chunks).unwrap();
+        assert!(chunks.written.len() >= 3);
+        assert_eq!(chunks.written.iter().sum::<u64>(), size as u64);
+        assert_eq!(fs::read(copied).unwrap().len(), size);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_copy_keeps_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+        let tmp = TempDir::new().unwrap();
+        let src = tmp.path().join("run.sh");
+        fs::write(&src, "#!/bin/sh").unwrap();
+        fs::set_permissions(&src, fs::Permissions::from_mode(0o750)).unwrap();
+        let dest = tmp.path().join("dest");
+        fs::create_dir(&dest).unwrap();
+
+        let copied = copy_recursive(&src, &dest).unwrap();
+        let mode = fs::metadata(copied).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o750);
+    }
+
+    #[test]
+    fn test_cancelled_copy_removes_partial_file() {
+        let tmp = TempDir::new().unwrap();
+        let src = tmp.path().join("big.bin");
+        fs::write(&src, vec![7u8; COPY_CHUNK * 3]).unwrap();
+        let dest = tmp.path().join("dest");
+        fs::create_dir(&dest).unwrap();
+
+        let mut chunks = Chunks {
+            written: Vec::new(),
+            cancel_after: 1,
+        };
+        let result = copy_recursive_with_progress(&src, &dest, &mut chunks);
+        assert!(matches!(result, Err(OperationError::Cancelled)));
+        assert_eq!(chunks.written.len(), 1);
+        assert!(!dest.join("big.bin").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_delete_recursive_unlinks_linked_directories() {
+        let tmp = TempDir::new().unwrap();
+        let outside = tmp.path().join("outside");
+        fs::create_dir(&outside).unwrap();
+        fs::write(outside.join("keep.txt"), "keep").unwrap();
+        let dir = tmp.path().join("dir");
+        fs::create_dir(&dir).unwrap();
+        std::os::unix::fs::symlink(&outside, dir.join("link")).unwrap();
+
+        let cancel = std::sync::atomic::AtomicBool::new(false);
+        let progress: DeleteProgressFn = Box::new(|_, _| {});
+        let (deleted, errors) = delete_recursive_with_progress(&dir, &progress, &cancel);
+
+        assert!(errors.is_empty());
+        assert_eq!(deleted, 2); // the link and dir
+        assert!(!dir.exists());
+        assert!(outside.join("keep.txt").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_delete_removes_the_link_not_its_target() {
+        let tmp = TempDir::new().unwrap();
+        let target = tmp.path().join("target");
+        fs::create_dir(&target).unwrap();
+        fs::write(target.join("keep.txt"), "keep").unwrap();
+        let link = tmp.path().join("link");
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        delete(&link).unwrap();
+        assert!(fs::symlink_metadata(&link).is_err());
+        assert!(target.join("keep.txt").exists());
+    }
 }
@@ -0,0 +1,280 @@
+//! Pastes over items the destination already has.
+//!
+//! A paste whose names are taken asks, item by item, whether to overwrite,
+//! keep both or skip. With `general.backup_on_overwrite` a file about to be
+//! overwritten is kept first: renamed to `<name>.fm-bak-<time>` next to
+//! itself, or moved to the trash when `use_trash` is on, so undo can put
+//! it back. Directories are merged rather than replaced, and nothing
+//! inside them is backed up.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{OpResult, OperationError};
+use crate::fs::operations;
+use crate::fs::trash::{self, Trash, TrashedEntry};
+use crate::fs::undo_check::StampedPath;
+use crate::preview_content::epoch_days_to_date;
+
+/// Marker between a backed-up file's name and its timestamp.
+pub const BACKUP_MARKER: &str = ".fm-bak-";
+
+/// Numbered names tried before giving up on a backup name.
+const MAX_NAME_ATTEMPTS: usize = 10_000;
+
+/// A pasted item whose name is already taken in the destination.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Collision {
+    pub source: PathBuf,
+    /// The existing item, `dest_dir/<name of source>`.
+    pub dest: PathBuf,
+    /// The existing item is a directory, so overwriting merges into it.
+    pub dest_is_dir: bool,
+}
+
+/// The items of `sources` whose name is taken in `dest_dir`. An item
+/// pasted into its own directory isn't one: it can only go next to itself.
+pub fn collisions(sources: &[PathBuf], dest_dir: &Path) -> Vec<Collision> {
+    sources
+        .iter()
+        .filter_map(|source| {
+            let dest = dest_dir.join(source.file_name()?);
+            if &dest == source {
+                return None;
+            }
+            let meta = fs::symlink_metadata(&dest).ok()?;
+            Some(Collision {
+                source: source.clone(),
+                dest,
+                dest_is_dir: meta.is_dir(),
+            })
+        })
+        .collect()
+}
+
+/// What to do with a pasted item whose name is taken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictChoice {
+    /// Replace the existing file, or merge into the existing directory.
+    Overwrite,
+    /// Paste next to it under a `_copy` name.
+    KeepBoth,
+    /// Leave it out of the paste.
+    Skip,
+}
+
+/// Where files a paste overwrites are kept.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BackupTo {
+    /// Renamed next to themselves.
+    Rename,
+    Trash(Trash),
+}
+
+/// A file moved out of the way before a paste overwrote it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Backup {
+    Renamed {
+        original: PathBuf,
+        backup: StampedPath,
+    },
+    Trashed {
+        entry: TrashedEntry,
+    },
+}
+
+impl Backup {
+    /// Where the file was, and goes back to.
+    pub fn original(&self) -> &Path {
+        match self {
+            Backup::Renamed { original, .. } => original,
+            Backup::Trashed { entry } => &entry.original,
+        }
+    }
+
+    /// Where the file is kept.
+    pub fn kept(&self) -> &StampedPath {
+        match self {
+            Backup::Renamed { backup, .. } => backup,
+            Backup::Trashed { entry } => &entry.trashed,
+        }
+    }
+}
+
+/// `<name>.fm-bak-<YYYYMMDD-HHMMSS>` next to `path`, for `secs` since the
+/// epoch (UTC), with `-2`, `-3`, … added while the name is taken.
+pub fn backup_path(path: &Path, secs: u64) -> PathBuf {
+    let (year, month, day) = epoch_days_to_date(secs / 86_400);
+    let rem = secs % 86_400;
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(
+        "{}{:04}{:02}{:02}-{:02}{:02}{:02}",
+        BACKUP_MARKER,
+        year,
+        month,
+        day,
+        rem / 3600,
+        (rem % 3600) / 60,
+        rem % 60
+    ));
+    let first = path.with_file_name(&name);
+    if fs::symlink_metadata(&first).is_err() {
+        return first;
+    }
+    for n in 2..=MAX_NAME_ATTEMPTS {
+        let mut numbered = name.clone();
+        numbered.push(format!("-{}", n));
+        let candidate = path.with_file_name(numbered);
+        if fs::symlink_metadata(&candidate).is_err() {
+            return candidate;
+        }
+    }
+    first
+}
+
+/// Move the file at `path` out of the way before it is overwritten.
+pub fn back_up(path: &Path, to: &BackupTo) -> OpResult<Backup> {
+    match to {
+        BackupTo::Rename => {
+            let secs = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+            let backup = backup_path(path, secs);
+            if fs::symlink_metadata(&backup).is_ok() {
+                return Err(OperationError::DestinationExists { path: backup });
+            }
+            operations::rename(path, &backup)?;
+            Ok(Backup::Renamed {
+                original: path.to_path_buf(),
+                backup: StampedPath::capture(backup),
+            })
+        }
+        BackupTo::Trash(trash) => Ok(Backup::Trashed {
+            entry: trash.trash(path)?,
+        }),
+    }
+}
+
+/// Put a backed-up file back where it was, which must be free again.
+pub fn restore(backup: &Backup) -> OpResult<PathBuf> {
+    let original = backup.original();
+    if fs::symlink_metadata(original).is_ok() {
+        return Err(OperationError::DestinationExists {
+            path: original.to_path_buf(),
+        });
+    }
+    match backup {
+        Backup::Renamed { original, backup } => {
+            operations::rename(&backup.path, original)?;
+            Ok(original.clone())
+        }
+        Backup::Trashed { entry } => trash::restore(entry),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::trash::TrashLayout;
+    use tempfile::TempDir;
+
+    #[test]
+    fn collisions_skip_free_names_and_the_items_own_directory() {
+        let tmp = TempDir::new().unwrap();
+        let src = tmp.path().join("src");
+        let dest = tmp.path().join("dest");
+        fs::create_dir_all(src.join("docs")).unwrap();
+        fs::create_dir_all(dest.join("docs")).unwrap();
+        fs::write(src.join("a.txt"), "new").unwrap();
+        fs::write(src.join("b.txt"), "new").unwrap();
+        fs::write(dest.join("a.txt"), "old").unwrap();
+
+        let sources = vec![src.join("a.txt"), src.join("b.txt"), src.join("docs")];
+        let found = collisions(&sources, &dest);
+        assert_eq!(
+            found,
+            vec![
+                Collision {
+                    source: src.join("a.txt"),
+                    dest: dest.join("a.txt"),
+                    dest_is_dir: false,
+                },
+                Collision {
+                    source: src.join("docs"),
+                    dest: dest.join("docs"),
+                    dest_is_dir: true,
+                },
+            ]
+        );
+        assert!(collisions(&sources, &src).is_empty());
+    }
+
+    #[test]
+    fn backup_names_count_up_while_taken() {
+        let tmp = TempDir::new().unwrap();
+        let file = tmp.path().join("notes.txt");
+        // 2026-10-16 08:38:41 UTC
+        let secs = 1_792_139_921;
+        let first = backup_path(&file, secs);
+        assert_eq!(
+            first.file_name().unwrap(),
+            "notes.txt.fm-bak-20261016-083841"
+        );
+        fs::write(&first, "").unwrap();
+        let second = backup_path(&file, secs);
+        assert_eq!(
+            second.file_name().unwrap(),
+            "notes.txt.fm-bak-20261016-083841-2"
+        );
+        fs::write(&second, "").unwrap();
+        assert!(backup_path(&file, secs).ends_with("notes.txt.fm-bak-20261016-083841-3"));
+    }
+
+    #[test]
+    fn renamed_backup_goes_back_once_its_place_is_free() {
+        let tmp = TempDir::new().unwrap();
+        let file = tmp.path().join("a.txt");
+        fs::write(&file, "old").unwrap();
+        let backup = back_up(&file, &BackupTo::Rename).unwrap();
+        assert!(!file.exists());
+        let kept = &backup.kept().path;
+        assert!(kept
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with("a.txt.fm-bak-"));
+
+        fs::write(&file, "new").unwrap();
+        assert!(matches!(
+            restore(&backup),
+            Err(OperationError::DestinationExists { .. })
+        ));
+        fs::remove_file(&file).unwrap();
+        assert_eq!(restore(&backup).unwrap(), file);
+        assert_eq!(fs::read_to_string(&file).unwrap(), "old");
+        assert!(!kept.exists());
+    }
+
+    #[test]
+    fn trashed_backup_comes_back_from_the_trash() {
+        let tmp = TempDir::new().unwrap();
+        let trash = Trash {
+            dir: tmp.path().join("Trash"),
+            layout: TrashLayout::Freedesktop,
+        };
+        let file = tmp.path().join("a.txt");
+        fs::write(&file, "old").unwrap();
+        let backup = back_up(&file, &BackupTo::Trash(trash)).unwrap();
+        assert!(!file.exists());
+        assert!(tmp.path().join("Trash/files/a.txt").exists());
+
+        restore(&backup).unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "old");
+        assert!(!tmp.path().join("Trash/info/a.txt.trashinfo").exists());
+    }
+}
@@ -0,0 +1,624 @@
+//! Places: entry points for jumping the tree elsewhere — home and the
+//! standard user directories, the filesystem root, mounted volumes and the
+//! `[bookmarks]` config table.
+//!
+//! Mount enumeration differs per OS and sits behind [`MountSource`] so it
+//! can be replaced by fixtures in tests.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::fs::send_to;
+
+/// Where a place comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlaceKind {
+    Home,
+    /// Desktop, Documents, Downloads, Pictures (xdg-user-dirs on Linux).
+    UserDir,
+    Root,
+    Volume,
+    Bookmark,
+}
+
+/// Whether a place can be opened.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlaceStatus {
+    Ready,
+    /// Path does not exist (e.g. unmounted drive, stale bookmark).
+    Missing,
+    /// Path exists but isn't a directory we can list.
+    Unreadable,
+}
+
+impl PlaceStatus {
+    /// Whether the tree can be opened at a place with this status.
+    pub fn is_usable(self) -> bool {
+        self == PlaceStatus::Ready
+    }
+
+    /// Short label shown next to unavailable places.
+    pub fn label(self) -> &'static str {
+        match self {
+            PlaceStatus::Ready => "ok",
+            PlaceStatus::Missing => "missing",
+            PlaceStatus::Unreadable => "unreadable",
+        }
+    }
+}
+
+/// Size and free space of the filesystem holding a place.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiskSpace {
+    pub total: u64,
+    pub free: u64,
+}
+
+/// A single entry in the places overlay.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Place {
+    pub label: String,
+    pub path: PathBuf,
+    pub kind: PlaceKind,
+    /// Filled in for the root and volumes when the platform reports it.
+    pub space: Option<DiskSpace>,
+    pub status: PlaceStatus,
+}
+
+impl Place {
+    /// Build a place and check whether it can be listed.
+    pub fn new(label: &str, path: PathBuf, kind: PlaceKind) -> Self {
+        let status = check_place(&path);
+        Self {
+            label: label.to_string(),
+            path,
+            kind,
+            space: None,
+            status,
+        }
+    }
+}
+
+/// Check whether `path` is a directory the tree can be rooted at.
+pub fn check_place(path: &Path) -> PlaceStatus {
+    if !path.exists() {
+        return PlaceStatus::Missing;
+    }
+    if path.is_dir() && std::fs::read_dir(path).is_ok() {
+        PlaceStatus::Ready
+    } else {
+        PlaceStatus::Unreadable
+    }
+}
+
+/// A mounted filesystem.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mount {
+    pub device: String,
+    pub path: PathBuf,
+    pub fs_type: String,
+}
+
+/// Platform-specific listing of mounted drives.
+pub trait MountSource {
+    /// User-facing mounts, excluding the filesystem root.
+    fn mounts(&self) -> Vec<Mount>;
+    /// Size and free space for each of `paths` the platform can report.
+    fn disk_space(&self, paths: &[PathBuf]) -> BTreeMap<PathBuf, DiskSpace>;
+}
+
+/// Linux: mounts from `/proc/mounts`, free space from `df`.
+pub struct ProcMounts {
+    pub mounts_file: PathBuf,
+}
+
+impl Default for ProcMounts {
+    fn default() -> Self {
+        Self {
+            mounts_file: PathBuf::from("/proc/mounts"),
+        }
+    }
+}
+
+impl MountSource for ProcMounts {
+    fn mounts(&self) -> Vec<Mount> {
+        match std::fs::read_to_string(&self.mounts_file) {
+            Ok(text) => parse_proc_mounts(&text),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    fn disk_space(&self, paths: &[PathBuf]) -> BTreeMap<PathBuf, DiskSpace> {
+        df_disk_space(paths)
+    }
+}
+
+/// macOS: one mount per entry in `/Volumes`, free space from `df`.
+#[allow(dead_code)]
+pub struct VolumesDir {
+    pub dir: PathBuf,
+}
+
+impl Default for VolumesDir {
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::from("/Volumes"),
+        }
+    }
+}
+
+impl MountSource for VolumesDir {
+    fn mounts(&self) -> Vec<Mount> {
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(_) => return Vec::new(),
+        };
+        let mut mounts: Vec<Mount> = entries
+            .flatten()
+            .filter(|entry| {
+                // The boot volume shows up as a symlink back to `/`
+                !matches!(std::fs::read_link(entry.path()), Ok(target) if target == Path::new("/"))
+            })
+            .map(|entry| Mount {
+                device: entry.file_name().to_string_lossy().to_string(),
+                path: entry.path(),
+                fs_type: String::new(),
+            })
+            .collect();
+        mounts.sort_by(|a, b| a.path.cmp(&b.path));
+        mounts
+    }
+
+    fn disk_space(&self, paths: &[PathBuf]) -> BTreeMap<PathBuf, DiskSpace> {
+        df_disk_space(paths)
+    }
+}
+
+/// Windows: every drive letter whose root exists.
+#[allow(dead_code)]
+#[derive(Default)]
+pub struct DriveLetters;
+
+impl MountSource for DriveLetters {
+    fn mounts(&self) -> Vec<Mount> {
+        drive_mounts(|root| root.is_dir())
+    }
+
+    fn disk_space(&self, _paths: &[PathBuf]) -> BTreeMap<PathBuf, DiskSpace> {
+        BTreeMap::new()
+    }
+}
+
+/// Drive letters `A:` through `Z:` whose root passes `present`.
+#[allow(dead_code)]
+pub fn drive_mounts(present: impl Fn(&Path) -> bool) -> Vec<Mount> {
+    (b'A'..=b'Z')
+        .map(|letter| format!("{}:\\", letter as char))
+        .filter(|root| present(Path::new(root)))
+        .map(|root| Mount {
+            device: root.trim_end_matches('\\').to_string(),
+            path: PathBuf::from(root),
+            fs_type: String::new(),
+        })
+        .collect()
+}
+
+/// The mount source for the current platform.
+pub fn system_source() -> Box<dyn MountSource> {
+    #[cfg(target_os = "macos")]
+    {
+        Box::new(VolumesDir::default())
+    }
+    #[cfg(windows)]
+    {
+        Box::new(DriveLetters)
+    }
+    #[cfg(not(any(target_os = "macos", windows)))]
+    {
+        Box::new(ProcMounts::default())
+    }
+}
+
+/// Network filesystems worth listing even though their device isn't a path.
+const NETWORK_FS_TYPES: &[&str] = &[
+    "nfs",
+    "nfs4",
+    "cifs",
+    "smb3",
+    "smbfs",
+    "fuse.sshfs",
+    "fuse.rclone",
+    "9p",
+];
+
+/// Mount points that hold system data rather than user files.
+const SYSTEM_MOUNT_PREFIXES: &[&str] = &["/boot", "/snap", "/var/snap", "/var/lib"];
+
+/// Parse `/proc/mounts`, keeping block devices and network shares.
+///
+/// Pseudo filesystems, loop-mounted snaps, the root and mounts under system
+/// directories are dropped; repeated mount points keep the first entry.
+pub fn parse_proc_mounts(text: &str) -> Vec<Mount> {
+    let mut mounts: Vec<Mount> = Vec::new();
+    for line in text.lines() {
+        let mut fields = line.split_whitespace();
+        let (device, path, fs_type) = match (fields.next(), fields.next(), fields.next()) {
+            (Some(d), Some(p), Some(t)) => (unescape_mount_field(d), unescape_mount_field(p), t),
+            _ => continue,
+        };
+        let is_block = device.starts_with('/') && !device.starts_with("/dev/loop");
+        if !is_block && !NETWORK_FS_TYPES.contains(&fs_type) {
+            continue;
+        }
+        if fs_type == "squashfs" || path == "/" {
+            continue;
+        }
+        if SYSTEM_MOUNT_PREFIXES
+            .iter()
+            .any(|prefix| Path::new(&path).starts_with(prefix))
+        {
+            continue;
+        }
+        if mounts.iter().any(|m| m.path == Path::new(&path)) {
+            continue;
+        }
+        mounts.push(Mount {
+            device,
+            path: PathBuf::from(path),
+            fs_type: fs_type.to_string(),
+        });
+    }
+    mounts
+}
+
+/// Whether `path` lives on a network share, judged by the deepest of
+/// `mounts` containing it.
+pub fn is_network_path(path: &Path, mounts: &[Mount]) -> bool {
+    mounts
+        .iter()
+        .filter(|m| path.starts_with(&m.path))
+        .max_by_key(|m| m.path.components().count())
+        .is_some_and(|m| NETWORK_FS_TYPES.contains(&m.fs_type.as_str()))
+}
+
+/// Undo the octal escapes (`\040` for space, …) used in `/proc/mounts`.
+fn unescape_mount_field(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\' && i + 3 < bytes.len() {
+            let digits = &bytes[i + 1..i + 4];
+            if digits.iter().all(|d| (b'0'..=b'7').contains(d)) {
+                let value = digits
+                    .iter()
+                    .fold(0u32, |acc, d| acc * 8 + (d - b'0') as u32);
+                out.push(value as u8);
+                i += 4;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+/// Run `df -Pk` over `paths` and parse the result.
+fn df_disk_space(paths: &[PathBuf]) -> BTreeMap<PathBuf, DiskSpace> {
+    if paths.is_empty() {
+        return BTreeMap::new();
+    }
+    match Command::new("df").arg("-Pk").args(paths).output() {
+        Ok(output) => parse_df_output(&String::from_utf8_lossy(&output.stdout)),
+        Err(_) => BTreeMap::new(),
+    }
+}
+
+/// Parse POSIX `df -Pk` output into space per mount point.
+///
+/// Columns are located from the capacity (`NN%`) field, so devices and
+/// mount points containing spaces still parse.
+pub fn parse_df_output(text: &str) -> BTreeMap<PathBuf, DiskSpace> {
+    let mut spaces = BTreeMap::new();
+    for line in text.lines().skip(1) {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let capacity = match fields.iter().rposition(|f| f.ends_with('%')) {
+            Some(i) if i >= 4 && i + 1 < fields.len() => i,
+            _ => continue,
+        };
+        let total = fields[capacity - 3].parse::<u64>();
+        let free = fields[capacity - 1].parse::<u64>();
+        if let (Ok(total), Ok(free)) = (total, free) {
+            let mount_point = fields[capacity + 1..].join(" ");
+            spaces.insert(
+                PathBuf::from(mount_point),
+                DiskSpace {
+                    total: total * 1024,
+                    free: free * 1024,
+                },
+            );
+        }
+    }
+    spaces
+}
+
+/// The standard user directories, in display order.
+pub fn user_dirs() -> Vec<(&'static str, Option<PathBuf>)> {
+    vec![
+        ("Desktop", dirs::desktop_dir()),
+        ("Documents", dirs::document_dir()),
+        ("Downloads", dirs::download_dir()),
+        ("Pictures", dirs::picture_dir()),
+    ]
+}
+
+/// The filesystem root, where the platform has a single one.
+fn filesystem_root() -> Option<PathBuf> {
+    if cfg!(windows) {
+        None
+    } else {
+        Some(PathBuf::from("/"))
+    }
+}
+
+/// Gather every place for the overlay, querying `source` for mounts.
+pub fn collect(source: &dyn MountSource, bookmarks: &BTreeMap<String, String>) -> Vec<Place> {
+    collect_with(
+        dirs::home_dir(),
+        &user_dirs(),
+        filesystem_root(),
+        source,
+        bookmarks,
+    )
+}
+
+/// [`collect`] with the home, user directories and root supplied.
+///
+/// User directories that are unset or resolve to home itself (xdg-user-dirs
+/// does that for unconfigured entries) are skipped.
+pub fn collect_with(
+    home: Option<PathBuf>,
+    user_dirs: &[(&str, Option<PathBuf>)],
+    root: Option<PathBuf>,
+    source: &dyn MountSource,
+    bookmarks: &BTreeMap<String, String>,
+) -> Vec<Place> {
+    let mut places = Vec::new();
+    if let Some(ref home) = home {
+        places.push(Place::new("Home", home.clone(), PlaceKind::Home));
+    }
+    for (label, dir) in user_dirs {
+        if let Some(dir) = dir {
+            if home.as_ref() != Some(dir) {
+                places.push(Place::new(label, dir.clone(), PlaceKind::UserDir));
+            }
+        }
+    }
+
+    let first_drive = places.len();
+    if let Some(root) = root {
+        places.push(Place::new("Filesystem", root, PlaceKind::Root));
+    }
+    for mount in source.mounts() {
+        let label = mount
+            .path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| mount.device.clone());
+        places.push(Place::new(&label, mount.path, PlaceKind::Volume));
+    }
+    let drive_paths: Vec<PathBuf> = places[first_drive..]
+        .iter()
+        .filter(|p| p.status.is_usable())
+        .map(|p| p.path.clone())
+        .collect();
+    let spaces = source.disk_space(&drive_paths);
+    for place in &mut places[first_drive..] {
+        place.space = spaces.get(&place.path).copied();
+    }
+
+    for (name, raw) in bookmarks {
+        places.push(Place::new(
+            name,
+            send_to::expand_target_path(raw),
+            PlaceKind::Bookmark,
+        ));
+    }
+    places
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const PROC_MOUNTS: &str = "\
+sysfs /sys sysfs rw,nosuid,nodev,noexec,relatime 0 0
+proc /proc proc rw,nosuid,nodev,noexec,relatime 0 0
+/dev/nvme0n1p2 / ext4 rw,relatime 0 0
+/dev/nvme0n1p1 /boot/efi vfat rw,relatime 0 0
+tmpfs /run tmpfs rw,nosuid,nodev 0 0
+/dev/loop3 /snap/core22/1380 squashfs ro,nodev,relatime 0 0
+/dev/sdb1 /media/alice/USB\\040Stick vfat rw,nosuid,nodev 0 0
+/dev/sdb1 /mnt/again vfat rw,nosuid,nodev 0 0
+nas:/export/share /mnt/nas nfs4 rw,relatime 0 0
+overlay /var/lib/docker/overlay2/abc/merged overlay rw 0 0
+/dev/sdc1 /mnt/data ext4 rw 0 0
+/dev/sdc1 /mnt/data ext4 rw 0 0
+";
+
+    const DF_OUTPUT: &str = "\
+Filesystem     1024-blocks      Used Available Capacity Mounted on
+/dev/nvme0n1p2   490691512 210000000 255691512      46% /
+/dev/sdb1         15000000   5000000  10000000      34% /media/alice/USB Stick
+nas:/export/share 100 50 50 50% /mnt/nas
+";
+
+    /// Fixture mount source returning canned mounts and `df` output.
+    struct FixtureSource {
+        mounts: Vec<Mount>,
+        df: &'static str,
+    }
+
+    impl MountSource for FixtureSource {
+        fn mounts(&self) -> Vec<Mount> {
+            self.mounts.clone()
+        }
+
+        fn disk_space(&self, _paths: &[PathBuf]) -> BTreeMap<PathBuf, DiskSpace> {
+            parse_df_output(self.df)
+        }
+    }
+
+    #[test]
+    fn proc_mounts_keeps_user_drives() {
+        let mounts = parse_proc_mounts(PROC_MOUNTS);
+        let paths: Vec<&Path> = mounts.iter().map(|m| m.path.as_path()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                Path::new("/media/alice/USB Stick"),
+                Path::new("/mnt/again"),
+                Path::new("/mnt/nas"),
+                Path::new("/mnt/data"),
+            ]
+        );
+        assert_eq!(mounts[0].device, "/dev/sdb1");
+        assert_eq!(mounts[2].fs_type, "nfs4");
+    }
+
+    #[test]
+    fn proc_mounts_reads_from_file() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("mounts");
+        std::fs::write(&file, PROC_MOUNTS).unwrap();
+        let source = ProcMounts { mounts_file: file };
+        assert_eq!(source.mounts().len(), 4);
+
+        let missing = ProcMounts {
+            mounts_file: dir.path().join("nope"),
+        };
+        assert!(missing.mounts().is_empty());
+    }
+
+    #[test]
+    fn network_path_uses_deepest_mount() {
+        let mut mounts = parse_proc_mounts(PROC_MOUNTS);
+        assert!(is_network_path(Path::new("/mnt/nas/projects"), &mounts));
+        assert!(!is_network_path(Path::new("/mnt/data/x"), &mounts));
+        assert!(!is_network_path(Path::new("/home/alice"), &mounts));
+
+        // A local disk mounted inside the share wins for paths below it
+        mounts.push(Mount {
+            device: "/dev/sdc1".to_string(),
+            path: PathBuf::from("/mnt/nas/local"),
+            fs_type: "ext4".to_string(),
+        });
+        assert!(!is_network_path(Path::new("/mnt/nas/local/f"), &mounts));
+        assert!(is_network_path(Path::new("/mnt/nas"), &mounts));
+    }
+
+    #[test]
+    fn df_output_parses_spaces_in_mount_points() {
+        let spaces = parse_df_output(DF_OUTPUT);
+        assert_eq!(spaces.len(), 3);
+        assert_eq!(
+            spaces[Path::new("/")],
+            DiskSpace {
+                total: 490691512 * 1024,
+                free: 255691512 * 1024,
+            }
+        );
+        assert_eq!(
+            spaces[Path::new("/media/alice/USB Stick")].free,
+            10000000 * 1024
+        );
+        assert!(parse_df_output("garbage\nnot a df line\n").is_empty());
+    }
+
+    #[test]
+    fn volumes_dir_skips_boot_volume_link() {
+        let dir = TempDir::new().unwrap();
+        std::fs::create_dir(dir.path().join("Backup")).unwrap();
+        std::fs::create_dir(dir.path().join("Camera")).unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink("/", dir.path().join("Macintosh HD")).unwrap();
+
+        let source = VolumesDir {
+            dir: dir.path().to_path_buf(),
+        };
+        let names: Vec<String> = source.mounts().into_iter().map(|m| m.device).collect();
+        assert_eq!(names, vec!["Backup", "Camera"]);
+    }
+
+    #[test]
+    fn drive_letters_lists_present_roots() {
+        let mounts = drive_mounts(|root| root == Path::new("C:\\") || root == Path::new("E:\\"));
+        let devices: Vec<&str> = mounts.iter().map(|m| m.device.as_str()).collect();
+        assert_eq!(devices, vec!["C:", "E:"]);
+        assert_eq!(mounts[0].path, PathBuf::from("C:\\"));
+    }
+
+    #[test]
+    fn collect_orders_and_checks_places() {
+        let home = TempDir::new().unwrap();
+        let docs = home.path().join("Documents");
+        std::fs::create_dir(&docs).unwrap();
+        let stick = TempDir::new().unwrap();
+
+        let source = FixtureSource {
+            mounts: vec![Mount {
+                device: "/dev/sdb1".to_string(),
+                path: stick.path().to_path_buf(),
+                fs_type: "vfat".to_string(),
+            }],
+            df: DF_OUTPUT,
+        };
+        let mut bookmarks = BTreeMap::new();
+        bookmarks.insert("gone".to_string(), "/definitely/not/here".to_string());
+
+        let places = collect_with(
+            Some(home.path().to_path_buf()),
+            &[
+                // Unconfigured xdg-user-dirs entries point at home itself
+                ("Desktop", Some(home.path().to_path_buf())),
+                ("Documents", Some(docs.clone())),
+                ("Downloads", Some(home.path().join("Downloads"))),
+                ("Pictures", None),
+            ],
+            Some(PathBuf::from("/")),
+            &source,
+            &bookmarks,
+        );
+
+        let labels: Vec<&str> = places.iter().map(|p| p.label.as_str()).collect();
+        let stick_label = stick
+            .path()
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .to_string();
+        assert_eq!(
+            labels,
+            vec![
+                "Home",
+                "Documents",
+                "Downloads",
+                "Filesystem",
+                stick_label.as_str(),
+                "gone"
+            ]
+        );
+        assert_eq!(places[0].kind, PlaceKind::Home);
+        assert!(places[1].status.is_usable());
+        assert_eq!(places[2].status, PlaceStatus::Missing);
+        assert_eq!(places[3].kind, PlaceKind::Root);
+        assert_eq!(places[3].space.map(|s| s.total), Some(490691512 * 1024));
+        assert_eq!(places[4].kind, PlaceKind::Volume);
+        assert_eq!(places[4].space, None);
+        assert_eq!(places[5].kind, PlaceKind::Bookmark);
+        assert_eq!(places[5].status, PlaceStatus::Missing);
+    }
+}
@@ -0,0 +1,510 @@
+//! Portability check: names that break when a directory is copied, zipped
+//! or unpacked on Windows or macOS.
+//!
+//! [`check_entries`] applies the per-directory rules to one listing —
+//! reserved device names, forbidden characters, trailing dots or spaces,
+//! names that differ only by case — and [`scan`] walks a tree with them,
+//! adding the Windows path length limit. Every renamable finding carries a
+//! suggested name that is valid and unique (ignoring case) in its
+//! directory; [`rename_plan`] turns those into pairs for a batch rename.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Longest path Windows tools handle without long-path support.
+pub const MAX_PATH: usize = 260;
+
+/// Device names Windows reserves, with or without an extension.
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Characters Windows rejects in names (control characters aside).
+const FORBIDDEN_CHARS: &[char] = &['<', '>', ':', '"', '|', '?', '*', '\\'];
+
+/// One reason a name or path is invalid or risky elsewhere.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Issue {
+    /// A Windows device name such as `CON` or `aux.txt`.
+    Reserved,
+    /// Characters Windows does not allow, in order of appearance.
+    ForbiddenChars(Vec<char>),
+    /// Windows drops trailing dots and spaces, so the name changes.
+    TrailingDotOrSpace,
+    /// An earlier entry in the same directory has this name up to case.
+    CaseCollision(String),
+    /// Characters in the path, counted from the checked directory's name.
+    PathTooLong(usize),
+}
+
+impl Issue {
+    pub fn describe(&self) -> String {
+        match self {
+            Issue::Reserved => "reserved name on Windows".to_string(),
+            Issue::ForbiddenChars(chars) => format!(
+                "not allowed on Windows: {}",
+                chars
+                    .iter()
+                    .map(|c| c.escape_debug().to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            ),
+            Issue::TrailingDotOrSpace => "ends with a dot or space".to_string(),
+            Issue::CaseCollision(other) => format!("clashes with {} ignoring case", other),
+            Issue::PathTooLong(len) => {
+                format!("path is {} characters (limit {})", len, MAX_PATH)
+            }
+        }
+    }
+}
+
+/// An entry with at least one issue.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub path: PathBuf,
+    pub issues: Vec<Issue>,
+    /// Replacement file name, when renaming the entry fixes its issues.
+    pub suggestion: Option<String>,
+}
+
+impl Finding {
+    /// Every issue, joined for a single line.
+    pub fn summary(&self) -> String {
+        self.issues
+            .iter()
+            .map(Issue::describe)
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
+
+/// Outcome of a [`scan`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Report {
+    pub root: PathBuf,
+    /// Findings in walk order: each directory's entries by name, parents
+    /// before their contents.
+    pub findings: Vec<Finding>,
+    /// Entries checked, the root excluded.
+    pub entries: usize,
+    /// `false` when the walk stopped at its entry cap, was cancelled or hit
+    /// an unreadable directory.
+    pub complete: bool,
+}
+
+/// Whether `name` is a reserved device name, extension or not.
+pub fn is_reserved(name: &str) -> bool {
+    let stem = name.split('.').next().unwrap_or(name).trim_end_matches(' ');
+    RESERVED_NAMES
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(stem))
+}
+
+/// Issues of `name` on its own, without looking at its siblings.
+pub fn name_issues(name: &str) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    if is_reserved(name) {
+        issues.push(Issue::Reserved);
+    }
+    let mut forbidden: Vec<char> = Vec::new();
+    for c in name.chars() {
+        if (FORBIDDEN_CHARS.contains(&c) || (c as u32) < 0x20) && !forbidden.contains(&c) {
+            forbidden.push(c);
+        }
+    }
+    if !forbidden.is_empty() {
+        issues.push(Issue::ForbiddenChars(forbidden));
+    }
+    if name.ends_with('.') || name.ends_with(' ') {
+        issues.push(Issue::TrailingDotOrSpace);
+    }
+    issues
+}
+
+/// `name` with forbidden characters replaced by `_`, trailing dots and
+/// spaces dropped, and `_` added to a reserved stem (`aux.txt` →
+/// `aux_.txt`).
+pub fn safe_name(name: &str) -> String {
+    let mut safe: String = name
+        .chars()
+        .map(|c| {
+            if FORBIDDEN_CHARS.contains(&c) || (c as u32) < 0x20 {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect();
+    let kept = safe.trim_end_matches(['.', ' ']).len();
+    safe.truncate(kept);
+    if safe.is_empty() {
+        safe.push('_');
+    }
+    if is_reserved(&safe) {
+        let stem_end = safe.find('.').unwrap_or(safe.len());
+        safe.insert(stem_end, '_');
+    }
+    safe
+}
+
+/// `candidate`, or `candidate` with `_2`, `_3`, … before its extension,
+/// whichever is first not in `taken` (lowercased names).
+fn unique_name(candidate: &str, taken: &HashSet<String>) -> String {
+    if !taken.contains(&candidate.to_lowercase()) {
+        return candidate.to_string();
+    }
+    let (stem, ext) = match candidate.rfind('.') {
+        Some(i) if i > 0 => candidate.split_at(i),
+        _ => (candidate, ""),
+    };
+    let mut n = 2;
+    loop {
+        let name = format!("{}_{}{}", stem, n, ext);
+        if !taken.contains(&name.to_lowercase()) {
+            return name;
+        }
+        n += 1;
+    }
+}
+
+/// Check the entries of one directory, given in listing order. The first
+/// of several names that differ only by case keeps its name; the others
+/// are findings.
+pub fn check_entries(paths: &[PathBuf]) -> Vec<Finding> {
+    let names: Vec<String> = paths
+        .iter()
+        .map(|p| {
+            p.file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default()
+        })
+        .collect();
+    let mut taken: HashSet<String> = names.iter().map(|n| n.to_lowercase()).collect();
+    let mut first_by_case: HashMap<String, &str> = HashMap::new();
+
+    let mut findings = Vec::new();
+    for (path, name) in paths.iter().zip(&names) {
+        let mut issues = name_issues(name);
+        let collides = match first_by_case.get(&name.to_lowercase()) {
+            Some(first) => {
+                issues.push(Issue::CaseCollision(first.to_string()));
+                true
+            }
+            None => {
+                first_by_case.insert(name.to_lowercase(), name);
+                false
+            }
+        };
+        if issues.is_empty() {
+            continue;
+        }
+        let safe = safe_name(name);
+        let suggestion = if collides || safe.to_lowercase() != name.to_lowercase() {
+            unique_name(&safe, &taken)
+        } else {
+            safe
+        };
+        taken.insert(suggestion.to_lowercase());
+        findings.push(Finding {
+            path: path.clone(),
+            issues,
+            suggestion: Some(suggestion),
+        });
+    }
+    findings
+}
+
+/// Walk `root` and check every entry, skipping names in `ignore` (neither
+/// checked nor entered) and stopping after `max_entries` entries or once
+/// `cancel` is set. Symlinks are checked but not followed.
+pub fn scan(root: &Path, ignore: &[String], max_entries: usize, cancel: &AtomicBool) -> Report {
+    let mut report = Report {
+        root: root.to_path_buf(),
+        complete: true,
+        ..Default::default()
+    };
+    // Lengths count from the checked directory's own name, as in an archive of it
+    let base = root.parent().unwrap_or(root);
+
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        if cancel.load(Ordering::Relaxed) {
+            report.complete = false;
+            break;
+        }
+        let mut entries: Vec<(PathBuf, bool)> = match fs::read_dir(&dir) {
+            Ok(entries) => entries
+                .flatten()
+                .filter(|e| !ignore.iter().any(|p| e.file_name().to_string_lossy() == *p))
+                .map(|e| {
+                    let is_dir = e.file_type().map(|t| t.is_dir()).unwrap_or(false);
+                    (e.path(), is_dir)
+                })
+                .collect(),
+            Err(_) => {
+                report.complete = false;
+                continue;
+            }
+        };
+        entries.sort_by(|a, b| a.0.file_name().cmp(&b.0.file_name()));
+        if report.entries + entries.len() > max_entries {
+            entries.truncate(max_entries - report.entries);
+            report.complete = false;
+        }
+        report.entries += entries.len();
+
+        let paths: Vec<PathBuf> = entries.iter().map(|(p, _)| p.clone()).collect();
+        let mut findings = check_entries(&paths);
+        for path in &paths {
+            let len = path
+                .strip_prefix(base)
+                .unwrap_or(path)
+                .to_string_lossy()
+                .chars()
+                .count();
+            if len <= MAX_PATH {
+                continue;
+            }
+            match findings.iter_mut().find(|f| &f.path == path) {
+                Some(finding) => finding.issues.push(Issue::PathTooLong(len)),
+                None => findings.push(Finding {
+                    path: path.clone(),
+                    issues: vec![Issue::PathTooLong(len)],
+                    suggestion: None,
+                }),
+            }
+        }
+        findings.sort_by(|a, b| a.path.file_name().cmp(&b.path.file_name()));
+        report.findings.extend(findings);
+
+        if !report.complete {
+            break;
+        }
+        // Reversed so the stack visits subdirectories in name order
+        pending.extend(
+            entries
+                .into_iter()
+                .rev()
+                .filter(|(_, is_dir)| *is_dir)
+                .map(|(p, _)| p),
+        );
+    }
+    report
+}
+
+/// Suggested renames as `(path, renamed path)` pairs, and the number left
+/// out because a directory above them is renamed too: their paths change
+/// with it, so they wait for another check.
+pub fn rename_plan(findings: &[Finding]) -> (Vec<(PathBuf, PathBuf)>, usize) {
+    let renamed: Vec<&Path> = findings
+        .iter()
+        .filter(|f| f.suggestion.is_some())
+        .map(|f| f.path.as_path())
+        .collect();
+    let mut pairs = Vec::new();
+    let mut deferred = 0;
+    for finding in findings {
+        let suggestion = match &finding.suggestion {
+            Some(s) => s,
+            None => continue,
+        };
+        let nested = renamed
+            .iter()
+            .any(|dir| *dir != finding.path && finding.path.starts_with(dir));
+        if nested {
+            deferred += 1;
+            continue;
+        }
+        let parent = finding.path.parent().unwrap_or(Path::new("."));
+        pairs.push((finding.path.clone(), parent.join(suggestion)));
+    }
+    (pairs, deferred)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn touch(dir: &Path, names: &[&str]) -> Vec<PathBuf> {
+        names
+            .iter()
+            .map(|n| {
+                let p = dir.join(n);
+                fs::write(&p, n).unwrap();
+                p
+            })
+            .collect()
+    }
+
+    fn scan_all(root: &Path) -> Report {
+        scan(root, &[], usize::MAX, &AtomicBool::new(false))
+    }
+
+    fn found<'r>(report: &'r Report, name: &str) -> &'r Finding {
+        report
+            .findings
+            .iter()
+            .find(|f| f.path.file_name().unwrap() == name)
+            .unwrap_or_else(|| panic!("no finding for {}", name))
+    }
+
+    #[test]
+    fn reserved_names_with_and_without_extension() {
+        assert!(is_reserved("CON"));
+        assert!(is_reserved("aux.txt"));
+        assert!(is_reserved("Lpt1.tar.gz"));
+        assert!(!is_reserved("console.log"));
+        assert!(!is_reserved("COM10"));
+        assert_eq!(name_issues("nul.json"), vec![Issue::Reserved]);
+        assert_eq!(safe_name("aux.txt"), "aux_.txt");
+        assert_eq!(safe_name("CON"), "CON_");
+    }
+
+    #[test]
+    fn forbidden_characters_are_listed_once_and_replaced() {
+        assert_eq!(
+            name_issues("notes:final?.txt"),
+            vec![Issue::ForbiddenChars(vec![':', '?'])]
+        );
+        assert_eq!(name_issues("a|b|c"), vec![Issue::ForbiddenChars(vec!['|'])]);
+        assert_eq!(
+            name_issues("tab\there"),
+            vec![Issue::ForbiddenChars(vec!['\t'])]
+        );
+        assert_eq!(safe_name("notes:final?.txt"), "notes_final_.txt");
+        assert_eq!(safe_name("<a*b>\"c\\d"), "_a_b__c_d");
+    }
+
+    #[test]
+    fn trailing_dots_and_spaces_are_trimmed() {
+        assert_eq!(name_issues("draft."), vec![Issue::TrailingDotOrSpace]);
+        assert_eq!(name_issues("draft "), vec![Issue::TrailingDotOrSpace]);
+        assert_eq!(safe_name("draft. ."), "draft");
+        assert_eq!(safe_name("..."), "_");
+        assert!(name_issues(".hidden").is_empty());
+    }
+
+    #[test]
+    fn combined_issues_are_all_fixed() {
+        assert_eq!(
+            name_issues("con.t:xt."),
+            vec![
+                Issue::Reserved,
+                Issue::ForbiddenChars(vec![':']),
+                Issue::TrailingDotOrSpace
+            ]
+        );
+        assert_eq!(safe_name("con.t:xt."), "con_.t_xt");
+        assert!(name_issues(&safe_name("con.t:xt.")).is_empty());
+    }
+
+    #[test]
+    fn case_collisions_keep_the_first_name() {
+        let tmp = TempDir::new().unwrap();
+        let paths = touch(tmp.path(), &["README.md", "Readme.md", "readme.md"]);
+        let findings = check_entries(&paths);
+        assert_eq!(findings.len(), 2);
+        assert_eq!(
+            findings[0].issues,
+            vec![Issue::CaseCollision("README.md".to_string())]
+        );
+        assert_eq!(findings[0].suggestion.as_deref(), Some("Readme_2.md"));
+        assert_eq!(findings[1].suggestion.as_deref(), Some("readme_3.md"));
+    }
+
+    #[test]
+    fn suggestions_avoid_existing_names() {
+        let tmp = TempDir::new().unwrap();
+        // The fix for `a:b` is taken, in another case
+        let paths = touch(tmp.path(), &["A_B", "a:b", "a?b"]);
+        let findings = check_entries(&paths);
+        assert_eq!(findings[0].suggestion.as_deref(), Some("a_b_2"));
+        assert_eq!(findings[1].suggestion.as_deref(), Some("a_b_3"));
+    }
+
+    #[test]
+    fn long_paths_are_reported_without_a_rename() {
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path().join("pkg");
+        let mut dir = root.clone();
+        for _ in 0..6 {
+            dir = dir.join("d".repeat(40));
+        }
+        fs::create_dir_all(&dir).unwrap();
+        let name = format!("{}.txt", "f".repeat(16));
+        fs::write(dir.join(&name), "").unwrap();
+
+        let report = scan_all(&root);
+        let finding = found(&report, &name);
+        // "pkg" + 6 × "/<40 chars>" + "/<20 chars>"; the directories fit
+        assert_eq!(finding.issues, vec![Issue::PathTooLong(3 + 6 * 41 + 21)]);
+        assert_eq!(finding.suggestion, None);
+        assert_eq!(report.findings.len(), 1);
+    }
+
+    #[test]
+    fn scan_walks_in_order_and_skips_ignored() {
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path();
+        fs::create_dir_all(root.join("b/sub")).unwrap();
+        fs::create_dir_all(root.join("node_modules")).unwrap();
+        touch(root, &["z?.txt", "aux"]);
+        touch(&root.join("b"), &["x:y"]);
+        touch(&root.join("b/sub"), &["end."]);
+        touch(&root.join("node_modules"), &["con"]);
+
+        let report = scan(
+            root,
+            &["node_modules".to_string()],
+            usize::MAX,
+            &AtomicBool::new(false),
+        );
+        let names: Vec<String> = report
+            .findings
+            .iter()
+            .map(|f| f.path.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names, vec!["aux", "z?.txt", "x:y", "end."]);
+        assert!(report.complete);
+        // aux, b, z?.txt, sub, x:y, end.
+        assert_eq!(report.entries, 6);
+    }
+
+    #[test]
+    fn scan_stops_at_the_cap_and_on_cancel() {
+        let tmp = TempDir::new().unwrap();
+        touch(tmp.path(), &["a", "b", "c:"]);
+        let capped = scan(tmp.path(), &[], 2, &AtomicBool::new(false));
+        assert!(!capped.complete);
+        assert_eq!(capped.entries, 2);
+        assert!(capped.findings.is_empty());
+
+        let cancelled = scan(tmp.path(), &[], usize::MAX, &AtomicBool::new(true));
+        assert!(!cancelled.complete);
+        assert_eq!(cancelled.entries, 0);
+    }
+
+    #[test]
+    fn rename_plan_defers_entries_inside_renamed_dirs() {
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path();
+        fs::create_dir_all(root.join("aux")).unwrap();
+        touch(&root.join("aux"), &["a:b"]);
+        touch(root, &["x|y"]);
+
+        let report = scan_all(root);
+        let (pairs, deferred) = rename_plan(&report.findings);
+        assert_eq!(
+            pairs,
+            vec![
+                (root.join("aux"), root.join("aux_")),
+                (root.join("x|y"), root.join("x_y")),
+            ]
+        );
+        assert_eq!(deferred, 1);
+    }
+}
@@ -0,0 +1,257 @@
+//! Retrying renames and deletes of items another program has open.
+//!
+//! Windows refuses to rename or delete a file that another process holds
+//! open (a sharing violation, also seen over SMB), and Linux answers
+//! `EBUSY` for a busy mount point or device. Such locks are usually brief:
+//! an editor saving, an antivirus scan. When a rename or delete fails with
+//! [`OperationError::SharingViolation`], the app offers the `BusyRetry`
+//! dialog: retry now, retry every [`RETRY_DELAY`] up to
+//! [`MAX_AUTO_ATTEMPTS`] times, or skip. Either retry runs [`run`] on a
+//! `TaskKind::Retry` task, which reports each attempt as
+//! `Event::RetryAttempt` and stops waiting once its cancel flag is raised.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::error::{OpResult, OperationError};
+
+/// Wait before each automatic retry.
+pub const RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// Attempts made by an automatic retry.
+pub const MAX_AUTO_ATTEMPTS: usize = 5;
+
+/// How often a wait checks its cancel flag.
+const CANCEL_POLL: Duration = Duration::from_millis(50);
+
+/// The operation a `BusyRetry` dialog retries.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RetryOp {
+    Rename {
+        from: PathBuf,
+        to: PathBuf,
+    },
+    /// Targets not yet deleted, in order.
+    Delete {
+        paths: Vec<PathBuf>,
+        to_trash: bool,
+    },
+}
+
+impl RetryOp {
+    /// What is being retried, for the dialog ("Rename notes.txt").
+    pub fn describe(&self) -> String {
+        match self {
+            RetryOp::Rename { from, .. } => format!("Rename {}", name(from)),
+            RetryOp::Delete { paths, to_trash } => {
+                let verb = if *to_trash { "Trash" } else { "Delete" };
+                match paths.as_slice() {
+                    [path] => format!("{} {}", verb, name(path)),
+                    _ => format!("{} {} items", verb, paths.len()),
+                }
+            }
+        }
+    }
+}
+
+fn name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+/// Sleep for `delay` unless `cancel` is raised first. Returns false when
+/// cancelled.
+pub fn wait(delay: Duration, cancel: &AtomicBool) -> bool {
+    let deadline = Instant::now() + delay;
+    loop {
+        if cancel.load(Ordering::Relaxed) {
+            return false;
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            return true;
+        }
+        std::thread::sleep(CANCEL_POLL.min(deadline - now));
+    }
+}
+
+/// Run `op` up to `attempts` times, waiting `delay` before each attempt
+/// with `wait` and calling `report` with its 1-based number. Only a
+/// sharing violation is retried; any other outcome ends the run. A
+/// cancelled wait ends it with [`OperationError::Cancelled`].
+pub fn run(
+    op: &mut dyn FnMut() -> OpResult<()>,
+    attempts: usize,
+    delay: Duration,
+    cancel: &AtomicBool,
+    wait: &mut dyn FnMut(Duration, &AtomicBool) -> bool,
+    report: &mut dyn FnMut(usize),
+) -> OpResult<()> {
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        if !wait(delay, cancel) {
+            return Err(OperationError::Cancelled);
+        }
+        report(attempt);
+        match op() {
+            Err(e) if e.is_sharing_violation() && attempt < attempts.max(1) => {}
+            result => return result,
+        }
+    }
+}
+
+/// The retry running for the open `BusyRetry` dialog, if any.
+#[derive(Debug, Default)]
+pub struct RetryState {
+    running: Option<(u64, Arc<AtomicBool>)>,
+    last_job: u64,
+}
+
+impl RetryState {
+    /// Id for the next retry, carried by its events.
+    pub fn next_job(&mut self) -> u64 {
+        self.last_job += 1;
+        self.last_job
+    }
+
+    /// Track retry `job`, cancelling the one before.
+    pub fn begin(&mut self, job: u64, cancel: Arc<AtomicBool>) {
+        self.cancel();
+        self.running = Some((job, cancel));
+    }
+
+    /// Whether `job` is the running retry.
+    pub fn is_current(&self, job: u64) -> bool {
+        self.running.as_ref().is_some_and(|(j, _)| *j == job)
+    }
+
+    /// Stop tracking `job` once it has finished. Returns false for a retry
+    /// that was cancelled or replaced.
+    pub fn finish(&mut self, job: u64) -> bool {
+        let current = self.is_current(job);
+        if current {
+            self.running = None;
+        }
+        current
+    }
+
+    /// Raise the running retry's cancel flag.
+    pub fn cancel(&mut self) {
+        if let Some((_, cancel)) = self.running.take() {
+            cancel.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Error, ErrorKind};
+
+    fn busy() -> OperationError {
+        OperationError::SharingViolation {
+            path: PathBuf::from("/r/locked.txt"),
+        }
+    }
+
+    /// Run with an operation that fails with `failures` before succeeding,
+    /// and no real waiting. Returns the result and the attempts reported.
+    fn run_failing(failures: Vec<OperationError>, attempts: usize) -> (OpResult<()>, Vec<usize>) {
+        let mut failures = failures.into_iter();
+        let mut reported = Vec::new();
+        let result = run(
+            &mut || failures.next().map_or(Ok(()), Err),
+            attempts,
+            RETRY_DELAY,
+            &AtomicBool::new(false),
+            &mut |delay, _| {
+                assert_eq!(delay, RETRY_DELAY);
+                true
+            },
+            &mut |attempt| reported.push(attempt),
+        );
+        (result, reported)
+    }
+
+    #[test]
+    fn retries_until_the_lock_clears() {
+        let (result, reported) = run_failing(vec![busy(), busy(), busy()], MAX_AUTO_ATTEMPTS);
+        assert!(result.is_ok());
+        assert_eq!(reported, [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn gives_up_after_the_last_attempt() {
+        let (result, reported) = run_failing((0..9).map(|_| busy()).collect(), MAX_AUTO_ATTEMPTS);
+        assert!(matches!(
+            result,
+            Err(OperationError::SharingViolation { .. })
+        ));
+        assert_eq!(reported, [1, 2, 3, 4, 5]);
+
+        // A single retry makes one attempt
+        let (result, reported) = run_failing(vec![busy()], 1);
+        assert!(result.is_err());
+        assert_eq!(reported, [1]);
+    }
+
+    #[test]
+    fn other_errors_are_not_retried() {
+        let gone = OperationError::from_io(Error::from(ErrorKind::NotFound), Path::new("/r/a"));
+        let (result, reported) = run_failing(vec![busy(), gone], MAX_AUTO_ATTEMPTS);
+        assert!(matches!(result, Err(OperationError::NotFound { .. })));
+        assert_eq!(reported, [1, 2]);
+    }
+
+    #[test]
+    fn cancel_stops_the_wait() {
+        let cancel = AtomicBool::new(true);
+        let mut calls = 0;
+        let result = run(
+            &mut || {
+                calls += 1;
+                Err(busy())
+            },
+            MAX_AUTO_ATTEMPTS,
+            RETRY_DELAY,
+            &cancel,
+            &mut wait,
+            &mut |_| {},
+        );
+        assert!(matches!(result, Err(OperationError::Cancelled)));
+        assert_eq!(calls, 0);
+    }
+
+    #[test]
+    fn replaced_retries_are_ignored() {
+        let mut state = RetryState::default();
+        let first = Arc::new(AtomicBool::new(false));
+        let old = state.next_job();
+        state.begin(old, Arc::clone(&first));
+        let job = state.next_job();
+        state.begin(job, Arc::new(AtomicBool::new(false)));
+        assert!(first.load(Ordering::Relaxed));
+        assert!(!state.finish(old));
+        assert!(state.is_current(job));
+        assert!(state.finish(job));
+        assert!(!state.is_current(job));
+    }
+
+    #[test]
+    fn describes_the_operation() {
+        let delete = RetryOp::Delete {
+            paths: vec![PathBuf::from("/r/a.txt"), PathBuf::from("/r/b.txt")],
+            to_trash: false,
+        };
+        assert_eq!(delete.describe(), "Delete 2 items");
+        let rename = RetryOp::Rename {
+            from: PathBuf::from("/r/a.txt"),
+            to: PathBuf::from("/r/b.txt"),
+        };
+        assert_eq!(rename.describe(), "Rename a.txt");
+    }
+}
@@ -0,0 +1,700 @@
+//! Scaffolds: directory templates instantiated into the tree.
+//!
+//! A scaffold is a directory under `~/.config/fm-tui/scaffolds/<name>/`.
+//! Its files and subdirectories are copied into a chosen directory with
+//! `{{placeholders}}` filled in, in file contents and in file and
+//! directory names alike, by the shared [`template`] engine. An optional
+//! `scaffold.toml` at its top names and describes it and declares the
+//! variables to ask for:
+//!
+//! ```toml
+//! description = "Command-line tool"
+//!
+//! [[variables]]
+//! name = "project_name"
+//! description = "Crate name"
+//! default = "my-tool"
+//! ```
+//!
+//! Without a manifest the variables are the placeholders found in the
+//! scaffold. [`plan`] renders every target path and lists the ones that
+//! already exist before anything is written; [`apply`] creates the tree
+//! and removes whatever it created when it fails or is cancelled.
+
+use std::collections::HashSet;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Read, Write};
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::template::{self, Vars};
+
+/// Directory under the config directory holding the scaffolds.
+pub const SCAFFOLDS_DIR: &str = "scaffolds";
+
+/// Manifest file name, read from a scaffold's top directory.
+pub const MANIFEST: &str = "scaffold.toml";
+
+/// Most entries a scaffold may hold.
+pub const MAX_ENTRIES: usize = 10_000;
+
+/// Largest file whose contents are rendered; bigger ones, and files that
+/// aren't UTF-8, are copied as they are.
+const MAX_RENDERED_BYTES: u64 = 4 * 1024 * 1024;
+
+/// `scaffold.toml`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    /// Shown in the picker instead of the directory name.
+    pub name: Option<String>,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub variables: Vec<Variable>,
+}
+
+/// A value asked for before the scaffold is instantiated.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Variable {
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub default: String,
+}
+
+/// A scaffold directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scaffold {
+    pub root: PathBuf,
+    /// `None` without a manifest; the reason when it doesn't parse.
+    pub manifest: Result<Option<Manifest>, String>,
+}
+
+impl Scaffold {
+    /// Read the scaffold at `root`, manifest included.
+    pub fn load(root: PathBuf) -> Self {
+        let manifest = match fs::read_to_string(root.join(MANIFEST)) {
+            Ok(content) => parse_manifest(&content).map(Some),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.to_string()),
+        };
+        Self { root, manifest }
+    }
+
+    /// The manifest's name, else the directory's.
+    pub fn name(&self) -> String {
+        match &self.manifest {
+            Ok(Some(Manifest {
+                name: Some(name), ..
+            })) => name.clone(),
+            _ => self
+                .root
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default(),
+        }
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        match &self.manifest {
+            Ok(Some(manifest)) => manifest.description.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Variables to ask for: the manifest's, or else every placeholder in
+    /// the scaffold's names and contents with an empty default.
+    pub fn variables(&self) -> Result<Vec<Variable>, ScaffoldError> {
+        match &self.manifest {
+            Ok(Some(manifest)) => Ok(manifest.variables.clone()),
+            Ok(None) => {
+                let mut names = Vec::new();
+                let mut entries = 0;
+                collect_placeholders(&self.root, true, &mut names, &mut entries)?;
+                Ok(names
+                    .into_iter()
+                    .map(|name| Variable {
+                        name,
+                        description: None,
+                        default: String::new(),
+                    })
+                    .collect())
+            }
+            Err(msg) => Err(ScaffoldError::Manifest(msg.clone())),
+        }
+    }
+}
+
+/// Why a scaffold can't be instantiated.
+#[derive(Debug, Error)]
+pub enum ScaffoldError {
+    #[error("{MANIFEST}: {0}")]
+    Manifest(String),
+    #[error("{}: {error}", path.display())]
+    Io { path: PathBuf, error: io::Error },
+    #[error("{} renders to the invalid name {name:?}", entry.display())]
+    BadName { entry: PathBuf, name: String },
+    #[error("two entries render to {}", .0.display())]
+    Duplicate(PathBuf),
+    #[error("{} already exists", .0.display())]
+    Conflict(PathBuf),
+    #[error("more than {MAX_ENTRIES} entries")]
+    TooLarge,
+    #[error("cancelled")]
+    Cancelled,
+}
+
+impl ScaffoldError {
+    fn io(path: &Path) -> impl FnOnce(io::Error) -> Self + '_ {
+        move |error| ScaffoldError::Io {
+            path: path.to_path_buf(),
+            error,
+        }
+    }
+}
+
+/// Parse and check a manifest. Messages name the line the problem is on.
+pub fn parse_manifest(content: &str) -> Result<Manifest, String> {
+    let manifest: Manifest = toml::from_str(content).map_err(|e| {
+        match e
+            .span()
+            .map(|span| crate::config::line_column(content, span.start))
+        {
+            Some((line, _)) => format!("line {}: {}", line, e.message()),
+            None => e.message().to_string(),
+        }
+    })?;
+    let mut seen = HashSet::new();
+    for variable in &manifest.variables {
+        if !template::is_valid_name(&variable.name) {
+            return Err(format!(
+                "variable name {:?} may only hold letters, digits, '_' and '-'",
+                variable.name
+            ));
+        }
+        if !seen.insert(variable.name.as_str()) {
+            return Err(format!("variable {:?} is declared twice", variable.name));
+        }
+    }
+    Ok(manifest)
+}
+
+/// Every scaffold directory in `dir`, by name. A missing `dir` has none.
+pub fn discover(dir: &Path) -> Vec<Scaffold> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut roots: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+        .map(|e| e.path())
+        .collect();
+    roots.sort();
+    roots.into_iter().map(Scaffold::load).collect()
+}
+
+/// What a scaffold entry is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    Dir,
+    File,
+    Symlink,
+}
+
+/// One entry of the scaffold and where it goes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Planned {
+    pub source: PathBuf,
+    pub target: PathBuf,
+    pub kind: EntryKind,
+}
+
+/// The rendered tree, parents before their contents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Plan {
+    pub entries: Vec<Planned>,
+    /// Targets that already exist, in entry order.
+    pub conflicts: Vec<PathBuf>,
+}
+
+/// Render every path of the scaffold at `root` into `dest`.
+pub fn plan(root: &Path, dest: &Path, vars: &Vars) -> Result<Plan, ScaffoldError> {
+    let mut entries = Vec::new();
+    plan_dir(root, dest, vars, true, &mut entries)?;
+
+    let mut targets = HashSet::new();
+    for entry in &entries {
+        if !targets.insert(&entry.target) {
+            return Err(ScaffoldError::Duplicate(entry.target.clone()));
+        }
+    }
+    let conflicts = entries
+        .iter()
+        .filter(|entry| fs::symlink_metadata(&entry.target).is_ok())
+        .map(|entry| entry.target.clone())
+        .collect();
+    Ok(Plan { entries, conflicts })
+}
+
+fn plan_dir(
+    dir: &Path,
+    dest: &Path,
+    vars: &Vars,
+    top: bool,
+    entries: &mut Vec<Planned>,
+) -> Result<(), ScaffoldError> {
+    for source in sorted_children(dir)? {
+        let Some(name) = source.file_name() else {
+            continue;
+        };
+        if top && name == MANIFEST {
+            continue;
+        }
+        let rendered = match name.to_str() {
+            Some(name) => template::render(name, vars),
+            None => name.to_string_lossy().to_string(),
+        };
+        if rendered.is_empty()
+            || rendered == "."
+            || rendered == ".."
+            || rendered.contains(['/', '\0'])
+        {
+            return Err(ScaffoldError::BadName {
+                entry: source,
+                name: rendered,
+            });
+        }
+        let target = match name.to_str() {
+            Some(_) => dest.join(&rendered),
+            None => dest.join(name),
+        };
+        let file_type = fs::symlink_metadata(&source)
+            .map_err(ScaffoldError::io(&source))?
+            .file_type();
+        let kind = if file_type.is_dir() {
+            EntryKind::Dir
+        } else if file_type.is_symlink() {
+            EntryKind::Symlink
+        } else if file_type.is_file() {
+            EntryKind::File
+        } else {
+            continue;
+        };
+        if entries.len() == MAX_ENTRIES {
+            return Err(ScaffoldError::TooLarge);
+        }
+        entries.push(Planned {
+            source: source.clone(),
+            target: target.clone(),
+            kind,
+        });
+        if kind == EntryKind::Dir {
+            plan_dir(&source, &target, vars, false, entries)?;
+        }
+    }
+    Ok(())
+}
+
+/// Children of `dir`, by name.
+fn sorted_children(dir: &Path) -> Result<Vec<PathBuf>, ScaffoldError> {
+    let mut children: Vec<PathBuf> = fs::read_dir(dir)
+        .map_err(ScaffoldError::io(dir))?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .collect();
+    children.sort();
+    Ok(children)
+}
+
+fn collect_placeholders(
+    dir: &Path,
+    top: bool,
+    names: &mut Vec<String>,
+    entries: &mut usize,
+) -> Result<(), ScaffoldError> {
+    let mut add = |text: &str| {
+        for name in template::placeholders(text) {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+    };
+    let mut subdirs = Vec::new();
+    for source in sorted_children(dir)? {
+        let Some(name) = source.file_name() else {
+            continue;
+        };
+        if top && name == MANIFEST {
+            continue;
+        }
+        *entries += 1;
+        if *entries > MAX_ENTRIES {
+            return Err(ScaffoldError::TooLarge);
+        }
+        add(&name.to_string_lossy());
+        let meta = fs::symlink_metadata(&source).map_err(ScaffoldError::io(&source))?;
+        if meta.is_dir() {
+            subdirs.push(source);
+        } else if meta.is_file() {
+            if let Some(text) = read_text(&source, meta.len())? {
+                add(&text);
+            }
+        }
+    }
+    for subdir in subdirs {
+        collect_placeholders(&subdir, false, names, entries)?;
+    }
+    Ok(())
+}
+
+/// The contents of a file `len` bytes long, when small enough to render
+/// and valid UTF-8.
+fn read_text(path: &Path, len: u64) -> Result<Option<String>, ScaffoldError> {
+    if len > MAX_RENDERED_BYTES {
+        return Ok(None);
+    }
+    let bytes = fs::read(path).map_err(ScaffoldError::io(path))?;
+    Ok(String::from_utf8(bytes).ok())
+}
+
+/// What [`apply`] did.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Applied {
+    /// Created paths whose parent already existed; removing them removes
+    /// everything the scaffold created.
+    pub created: Vec<PathBuf>,
+    pub entries: usize,
+    /// Existing files replaced under `force`.
+    pub overwritten: usize,
+}
+
+/// Create the planned tree. An existing target aborts the run, unless
+/// `force` is set: then existing directories are filled in and existing
+/// files overwritten. On failure or cancel, everything created so far is
+/// removed again; files overwritten under `force` stay overwritten.
+pub fn apply(
+    plan: &Plan,
+    vars: &Vars,
+    force: bool,
+    progress: &mut dyn FnMut(usize, &Path),
+    cancel: &AtomicBool,
+) -> Result<Applied, ScaffoldError> {
+    let mut created = Vec::new();
+    let mut overwritten = 0;
+    let result = (|| {
+        for (i, entry) in plan.entries.iter().enumerate() {
+            if cancel.load(Ordering::Relaxed) {
+                return Err(ScaffoldError::Cancelled);
+            }
+            match create_entry(entry, vars, force)? {
+                Outcome::Created => created.push(entry.target.clone()),
+                Outcome::Overwritten => overwritten += 1,
+                Outcome::Reused => {}
+            }
+            progress(i + 1, &entry.target);
+        }
+        Ok(())
+    })();
+    if let Err(e) = result {
+        for path in created.iter().rev() {
+            let _ = match fs::symlink_metadata(path) {
+                Ok(meta) if meta.is_dir() => fs::remove_dir_all(path),
+                _ => fs::remove_file(path),
+            };
+        }
+        return Err(e);
+    }
+
+    let new: HashSet<&Path> = created.iter().map(PathBuf::as_path).collect();
+    let top = created
+        .iter()
+        .filter(|path| !path.parent().is_some_and(|parent| new.contains(parent)))
+        .cloned()
+        .collect();
+    Ok(Applied {
+        created: top,
+        entries: plan.entries.len(),
+        overwritten,
+    })
+}
+
+enum Outcome {
+    Created,
+    /// An existing directory, kept under `force`.
+    Reused,
+    /// An existing file, replaced under `force`.
+    Overwritten,
+}
+
+fn create_entry(entry: &Planned, vars: &Vars, force: bool) -> Result<Outcome, ScaffoldError> {
+    let target = &entry.target;
+    let exists = |e: &io::Error| e.kind() == io::ErrorKind::AlreadyExists;
+    match entry.kind {
+        EntryKind::Dir => match fs::create_dir(target) {
+            Ok(()) => Ok(Outcome::Created),
+            Err(e) if exists(&e) && force && is_real(target, |m| m.is_dir()) => Ok(Outcome::Reused),
+            Err(e) if exists(&e) => Err(ScaffoldError::Conflict(target.clone())),
+            Err(e) => Err(ScaffoldError::io(target)(e)),
+        },
+        EntryKind::Symlink => {
+            let link = fs::read_link(&entry.source).map_err(ScaffoldError::io(&entry.source))?;
+            let link = match link.to_str() {
+                Some(text) => PathBuf::from(template::render(text, vars)),
+                None => link,
+            };
+            match std::os::unix::fs::symlink(&link, target) {
+                Ok(()) => Ok(Outcome::Created),
+                Err(e) if exists(&e) => Err(ScaffoldError::Conflict(target.clone())),
+                Err(e) => Err(ScaffoldError::io(target)(e)),
+            }
+        }
+        EntryKind::File => {
+            let meta = fs::metadata(&entry.source).map_err(ScaffoldError::io(&entry.source))?;
+            let mode = meta.permissions().mode() & 0o7777;
+            let contents =
+                read_text(&entry.source, meta.len())?.map(|text| template::render(&text, vars));
+            let mut options = OpenOptions::new();
+            options.write(true).create_new(true).mode(mode);
+            let (file, outcome) = match options.open(target) {
+                Ok(file) => (file, Outcome::Created),
+                Err(e) if exists(&e) && force && is_real(target, |m| m.is_file()) => {
+                    let file = OpenOptions::new()
+                        .write(true)
+                        .truncate(true)
+                        .open(target)
+                        .map_err(ScaffoldError::io(target))?;
+                    file.set_permissions(fs::Permissions::from_mode(mode))
+                        .map_err(ScaffoldError::io(target))?;
+                    (file, Outcome::Overwritten)
+                }
+                Err(e) if exists(&e) => return Err(ScaffoldError::Conflict(target.clone())),
+                Err(e) => return Err(ScaffoldError::io(target)(e)),
+            };
+            write_contents(file, &entry.source, contents).map_err(ScaffoldError::io(target))?;
+            Ok(outcome)
+        }
+    }
+}
+
+/// Whether `path` is itself (not through a symlink) what `check` wants.
+fn is_real(path: &Path, check: impl Fn(&fs::Metadata) -> bool) -> bool {
+    fs::symlink_metadata(path).is_ok_and(|meta| check(&meta))
+}
+
+/// Write the rendered `contents`, or else copy `source` as it is.
+fn write_contents(mut file: fs::File, source: &Path, contents: Option<String>) -> io::Result<()> {
+    match contents {
+        Some(text) => file.write_all(text.as_bytes()),
+        None => {
+            let mut from = fs::File::open(source)?;
+            let mut buf = vec![0; 64 * 1024];
+            loop {
+                let n = from.read(&mut buf)?;
+                if n == 0 {
+                    return Ok(());
+                }
+                file.write_all(&buf[..n])?;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn vars(pairs: &[(&str, &str)]) -> Vars {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    /// A scaffold in `dir/scaffold` of `(path, contents)` files; paths
+    /// ending in `/` are directories.
+    fn scaffold(dir: &TempDir, files: &[(&str, &str)]) -> PathBuf {
+        let root = dir.path().join("scaffold");
+        fs::create_dir(&root).unwrap();
+        for (path, contents) in files {
+            let path = root.join(path);
+            if contents.is_empty() && path.to_string_lossy().ends_with('/') {
+                fs::create_dir_all(&path).unwrap();
+            } else {
+                fs::create_dir_all(path.parent().unwrap()).unwrap();
+                fs::write(&path, contents).unwrap();
+            }
+        }
+        root
+    }
+
+    fn run(plan: &Plan, vars: &Vars, force: bool) -> Result<Applied, ScaffoldError> {
+        apply(plan, vars, force, &mut |_, _| {}, &AtomicBool::new(false))
+    }
+
+    #[test]
+    fn renders_names_and_nested_contents() {
+        let dir = TempDir::new().unwrap();
+        let root = scaffold(
+            &dir,
+            &[
+                (MANIFEST, "[[variables]]\nname = \"project_name\"\n"),
+                (
+                    "{{project_name}}/Cargo.toml",
+                    "name = \"{{project_name}}\"\n",
+                ),
+                (
+                    "{{project_name}}/src/{{ project_name }}.rs",
+                    "// {{project_name}}\n${{ ci }}",
+                ),
+                ("{{project_name}}/empty/", ""),
+            ],
+        );
+        fs::set_permissions(
+            root.join("{{project_name}}/Cargo.toml"),
+            fs::Permissions::from_mode(0o755),
+        )
+        .unwrap();
+        let dest = dir.path().join("out");
+        fs::create_dir(&dest).unwrap();
+        let vars = vars(&[("project_name", "demo")]);
+
+        let plan = plan(&root, &dest, &vars).unwrap();
+        assert!(plan.conflicts.is_empty());
+        let applied = run(&plan, &vars, false).unwrap();
+        assert_eq!(applied.created, vec![dest.join("demo")]);
+        assert_eq!(applied.entries, 5);
+        assert_eq!(
+            fs::read_to_string(dest.join("demo/Cargo.toml")).unwrap(),
+            "name = \"demo\"\n"
+        );
+        assert_eq!(
+            fs::read_to_string(dest.join("demo/src/demo.rs")).unwrap(),
+            "// demo\n${{ ci }}"
+        );
+        assert!(dest.join("demo/empty").is_dir());
+        assert!(!dest.join(MANIFEST).exists());
+        let mode = fs::metadata(dest.join("demo/Cargo.toml"))
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o755);
+    }
+
+    #[test]
+    fn bad_manifest_is_a_readable_error() {
+        let dir = TempDir::new().unwrap();
+        let root = scaffold(
+            &dir,
+            &[(MANIFEST, "description = \"x\"\n[[variables]]\nnam = 1\n")],
+        );
+        let scaffold = Scaffold::load(root);
+        let msg = scaffold.manifest.clone().unwrap_err();
+        assert!(msg.starts_with("line 3: "), "{}", msg);
+        assert!(matches!(
+            scaffold.variables(),
+            Err(ScaffoldError::Manifest(_))
+        ));
+
+        assert!(parse_manifest("[[variables]]\nname = \"a b\"\n").is_err());
+        assert!(
+            parse_manifest("[[variables]]\nname = \"a\"\n[[variables]]\nname = \"a\"\n").is_err()
+        );
+    }
+
+    #[test]
+    fn variables_default_to_the_placeholders_found() {
+        let dir = TempDir::new().unwrap();
+        let root = scaffold(
+            &dir,
+            &[
+                ("{{name}}.md", "by {{author}}"),
+                ("sub/{{name}}.txt", "{{year}}"),
+            ],
+        );
+        let names: Vec<String> = Scaffold::load(root)
+            .variables()
+            .unwrap()
+            .into_iter()
+            .map(|v| v.name)
+            .collect();
+        assert_eq!(names, ["name", "author", "year"]);
+    }
+
+    #[test]
+    fn existing_targets_abort_everything() {
+        let dir = TempDir::new().unwrap();
+        let root = scaffold(
+            &dir,
+            &[("a.txt", "new"), ("b/c.txt", "new"), ("z.txt", "new")],
+        );
+        let dest = dir.path().join("out");
+        fs::create_dir(&dest).unwrap();
+        fs::write(dest.join("z.txt"), "mine").unwrap();
+
+        let plan = plan(&root, &dest, &Vars::new()).unwrap();
+        assert_eq!(plan.conflicts, vec![dest.join("z.txt")]);
+        assert!(matches!(
+            run(&plan, &Vars::new(), false),
+            Err(ScaffoldError::Conflict(_))
+        ));
+        // What was written before the conflict is gone again
+        assert!(!dest.join("a.txt").exists());
+        assert!(!dest.join("b").exists());
+        assert_eq!(fs::read_to_string(dest.join("z.txt")).unwrap(), "mine");
+
+        let applied = run(&plan, &Vars::new(), true).unwrap();
+        assert_eq!(applied.overwritten, 1);
+        assert_eq!(applied.created, vec![dest.join("a.txt"), dest.join("b")]);
+        assert_eq!(fs::read_to_string(dest.join("z.txt")).unwrap(), "new");
+    }
+
+    #[test]
+    fn names_rendering_empty_or_clashing_are_refused() {
+        let dir = TempDir::new().unwrap();
+        let root = scaffold(&dir, &[("{{a}}", "x"), ("{{b}}.txt", "y")]);
+        let dest = dir.path().join("out");
+        let empty = vars(&[("a", ""), ("b", "ok")]);
+        assert!(matches!(
+            plan(&root, &dest, &empty),
+            Err(ScaffoldError::BadName { .. })
+        ));
+        let slash = vars(&[("a", "../up"), ("b", "ok")]);
+        assert!(matches!(
+            plan(&root, &dest, &slash),
+            Err(ScaffoldError::BadName { .. })
+        ));
+        let clash = vars(&[("a", "same.txt"), ("b", "same")]);
+        assert!(matches!(
+            plan(&root, &dest, &clash),
+            Err(ScaffoldError::Duplicate(_))
+        ));
+    }
+
+    #[test]
+    fn cancel_removes_what_was_created() {
+        let dir = TempDir::new().unwrap();
+        let root = scaffold(&dir, &[("a/1.txt", "1"), ("b.txt", "2")]);
+        let dest = dir.path().join("out");
+        fs::create_dir(&dest).unwrap();
+        let plan = plan(&root, &dest, &Vars::new()).unwrap();
+        let cancel = AtomicBool::new(false);
+        let result = apply(
+            &plan,
+            &Vars::new(),
+            false,
+            &mut |done, _| {
+                if done == 2 {
+                    cancel.store(true, Ordering::Relaxed);
+                }
+            },
+            &cancel,
+        );
+        assert!(matches!(result, Err(ScaffoldError::Cancelled)));
+        assert_eq!(fs::read_dir(&dest).unwrap().count(), 0);
+    }
+}
@@ -0,0 +1,226 @@
+//! "Send to" targets: named destination directories from the `[send_to]`
+//! config table, with live existence/writability checks.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Availability of a send-to destination.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetStatus {
+    /// Directory exists and is writable.
+    Ready,
+    /// Path does not exist (e.g. unmounted USB drive).
+    Missing,
+    /// Path exists but is not a directory.
+    NotDirectory,
+    /// Directory exists but is read-only.
+    ReadOnly,
+    /// Configured path is relative; targets must be absolute.
+    NotAbsolute,
+}
+
+impl TargetStatus {
+    /// Whether items can be sent to a target with this status.
+    pub fn is_usable(self) -> bool {
+        self == TargetStatus::Ready
+    }
+
+    /// Short label shown next to unavailable targets.
+    pub fn label(self) -> &'static str {
+        match self {
+            TargetStatus::Ready => "ok",
+            TargetStatus::Missing => "missing",
+            TargetStatus::NotDirectory => "not a directory",
+            TargetStatus::ReadOnly => "read-only",
+            TargetStatus::NotAbsolute => "not absolute",
+        }
+    }
+}
+
+/// A single named destination.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SendToTarget {
+    /// Name from the config table key.
+    pub name: String,
+    /// Destination directory (with `~` expanded).
+    pub path: PathBuf,
+    /// Last checked availability.
+    pub status: TargetStatus,
+}
+
+impl SendToTarget {
+    /// Build a target and check its current status.
+    pub fn new(name: &str, raw_path: &str) -> Self {
+        let path = expand_target_path(raw_path);
+        let status = check_target(&path);
+        Self {
+            name: name.to_string(),
+            path,
+            status,
+        }
+    }
+
+    /// Re-check the target against the filesystem.
+    pub fn refresh(&mut self) {
+        self.status = check_target(&self.path);
+    }
+}
+
+/// Expand a leading `~` to the user's home directory.
+pub fn expand_target_path(raw: &str) -> PathBuf {
+    if raw == "~" {
+        if let Some(home) = dirs::home_dir() {
+            return home;
+        }
+    } else if let Some(rest) = raw.strip_prefix("~/") {
+        if let Some(home) = dirs::home_dir() {
+            return home.join(rest);
+        }
+    }
+    PathBuf::from(raw)
+}
+
+/// Check whether `path` is usable as a send-to destination.
+pub fn check_target(path: &Path) -> TargetStatus {
+    if !path.is_absolute() {
+        return TargetStatus::NotAbsolute;
+    }
+    match std::fs::metadata(path) {
+        Err(_) => TargetStatus::Missing,
+        Ok(meta) if !meta.is_dir() => TargetStatus::NotDirectory,
+        Ok(meta) if meta.permissions().readonly() => TargetStatus::ReadOnly,
+        Ok(_) => TargetStatus::Ready,
+    }
+}
+
+/// Resolve the configured table into targets, sorted by name.
+pub fn resolve_targets(table: &BTreeMap<String, String>) -> Vec<SendToTarget> {
+    table
+        .iter()
+        .map(|(name, path)| SendToTarget::new(name, path))
+        .collect()
+}
+
+/// Pick a target name for `dir` that doesn't clash with existing names.
+///
+/// Uses the directory's basename, adding `_2`, `_3`, … when taken.
+pub fn unique_target_name(dir: &Path, table: &BTreeMap<String, String>) -> String {
+    let base = dir
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "root".to_string());
+    if !table.contains_key(&base) {
+        return base;
+    }
+    let mut n = 2;
+    loop {
+        let candidate = format!("{}_{}", base, n);
+        if !table.contains_key(&candidate) {
+            return candidate;
+        }
+        n += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn existing_dir_is_ready() {
+        let dir = TempDir::new().unwrap();
+        assert_eq!(check_target(dir.path()), TargetStatus::Ready);
+    }
+
+    #[test]
+    fn missing_dir_is_dead() {
+        let dir = TempDir::new().unwrap();
+        let status = check_target(&dir.path().join("unmounted"));
+        assert_eq!(status, TargetStatus::Missing);
+        assert!(!status.is_usable());
+    }
+
+    #[test]
+    fn file_is_not_a_directory() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("f.txt");
+        fs::write(&file, "x").unwrap();
+        assert_eq!(check_target(&file), TargetStatus::NotDirectory);
+    }
+
+    #[test]
+    fn relative_path_rejected() {
+        assert_eq!(
+            check_target(Path::new("relative/dir")),
+            TargetStatus::NotAbsolute
+        );
+    }
+
+    #[test]
+    fn readonly_dir_detected() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = TempDir::new().unwrap();
+        let ro = dir.path().join("ro");
+        fs::create_dir(&ro).unwrap();
+        fs::set_permissions(&ro, fs::Permissions::from_mode(0o555)).unwrap();
+        assert_eq!(check_target(&ro), TargetStatus::ReadOnly);
+        fs::set_permissions(&ro, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    #[test]
+    fn resolve_targets_sorted_with_status() {
+        let dir = TempDir::new().unwrap();
+        let mut table = BTreeMap::new();
+        table.insert(
+            "usb".to_string(),
+            dir.path().join("usb").to_string_lossy().to_string(),
+        );
+        table.insert(
+            "backups".to_string(),
+            dir.path().to_string_lossy().to_string(),
+        );
+        let targets = resolve_targets(&table);
+        assert_eq!(targets.len(), 2);
+        assert_eq!(targets[0].name, "backups");
+        assert_eq!(targets[0].status, TargetStatus::Ready);
+        assert_eq!(targets[1].name, "usb");
+        assert_eq!(targets[1].status, TargetStatus::Missing);
+    }
+
+    #[test]
+    fn refresh_picks_up_new_mount() {
+        let dir = TempDir::new().unwrap();
+        let usb = dir.path().join("usb");
+        let mut target = SendToTarget::new("usb", &usb.to_string_lossy());
+        assert_eq!(target.status, TargetStatus::Missing);
+        fs::create_dir(&usb).unwrap();
+        target.refresh();
+        assert_eq!(target.status, TargetStatus::Ready);
+    }
+
+    #[test]
+    fn tilde_expands_to_home() {
+        if let Some(home) = dirs::home_dir() {
+            assert_eq!(expand_target_path("~/backup"), home.join("backup"));
+            assert_eq!(expand_target_path("~"), home);
+        }
+        assert_eq!(expand_target_path("/abs"), PathBuf::from("/abs"));
+    }
+
+    #[test]
+    fn unique_name_avoids_collisions() {
+        let mut table = BTreeMap::new();
+        assert_eq!(
+            unique_target_name(Path::new("/data/photos"), &table),
+            "photos"
+        );
+        table.insert("photos".to_string(), "/other/photos".to_string());
+        assert_eq!(
+            unique_target_name(Path::new("/data/photos"), &table),
+            "photos_2"
+        );
+        assert_eq!(unique_target_name(Path::new("/"), &table), "root");
+    }
+}
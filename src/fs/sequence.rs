@@ -0,0 +1,456 @@
+//! Sequence rename: renumber a set of files as `<base><NNN>.<ext>`.
+//!
+//! [`plan`] orders the sources and computes target names, rejecting targets
+//! that would clobber files outside the set. [`apply`] then renames in two
+//! phases — every source to a unique temporary name first, then every
+//! temporary to its target — so renumbering in place (`2→1`, `3→2`, …)
+//! never collides mid-way. A failure in either phase rolls back what was
+//! already done.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use thiserror::Error;
+
+use crate::error::{OpResult, OperationError};
+use crate::fs::name_check::{NameProblem, Siblings};
+
+/// Order in which the sources are numbered.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    #[default]
+    Name,
+    Mtime,
+}
+
+impl SortKey {
+    pub fn label(self) -> &'static str {
+        match self {
+            SortKey::Name => "name",
+            SortKey::Mtime => "modified time",
+        }
+    }
+
+    pub fn toggle(self) -> Self {
+        match self {
+            SortKey::Name => SortKey::Mtime,
+            SortKey::Mtime => SortKey::Name,
+        }
+    }
+}
+
+/// Naming parameters entered in the sequence rename dialog.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SequenceSpec {
+    pub base: String,
+    pub start: usize,
+    /// Zero-padding width of the number.
+    pub width: usize,
+    pub sort: SortKey,
+}
+
+/// Why a sequence rename cannot be planned.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum PlanError {
+    #[error("nothing to rename")]
+    Empty,
+    #[error("base name cannot contain '/'")]
+    InvalidBase,
+    #[error("{} already exists", .0.display())]
+    Collision(PathBuf),
+}
+
+/// Target file name for the `n`-th item, keeping the source's extension.
+pub fn target_name(spec: &SequenceSpec, n: usize, src: &Path) -> String {
+    let number = format!("{:0width$}", n, width = spec.width);
+    match src.extension() {
+        Some(ext) => format!("{}{}.{}", spec.base, number, ext.to_string_lossy()),
+        None => format!("{}{}", spec.base, number),
+    }
+}
+
+/// Sort sources by name, or by mtime (oldest first, name as tie-break).
+pub fn order_sources(sources: &[PathBuf], sort: SortKey) -> Vec<PathBuf> {
+    let mut ordered = sources.to_vec();
+    match sort {
+        SortKey::Name => ordered.sort_by(|a, b| a.file_name().cmp(&b.file_name())),
+        SortKey::Mtime => {
+            let mtime = |p: &Path| {
+                std::fs::symlink_metadata(p)
+                    .and_then(|m| m.modified())
+                    .unwrap_or(SystemTime::UNIX_EPOCH)
+            };
+            ordered.sort_by(|a, b| {
+                mtime(a)
+                    .cmp(&mtime(b))
+                    .then_with(|| a.file_name().cmp(&b.file_name()))
+            });
+        }
+    }
+    ordered
+}
+
+/// Compute `(source, target)` pairs in numbering order.
+///
+/// Targets go through [`name_check`](crate::fs::name_check) and may reuse
+/// names currently held by other sources (they are freed in phase one),
+/// but not names of files outside the set, even up to case.
+pub fn plan(
+    sources: &[PathBuf],
+    spec: &SequenceSpec,
+) -> Result<Vec<(PathBuf, PathBuf)>, PlanError> {
+    if sources.is_empty() {
+        return Err(PlanError::Empty);
+    }
+
+    let mut unique: Vec<PathBuf> = Vec::with_capacity(sources.len());
+    let mut source_set: HashSet<&PathBuf> = HashSet::new();
+    for src in sources {
+        if source_set.insert(src) {
+            unique.push(src.clone());
+        }
+    }
+
+    let mut siblings: HashMap<PathBuf, Siblings> = HashMap::new();
+    let mut pairs = Vec::with_capacity(unique.len());
+    for (i, src) in order_sources(&unique, spec.sort).into_iter().enumerate() {
+        let parent = src.parent().unwrap_or(Path::new("."));
+        let name = target_name(spec, spec.start + i, &src);
+        let check = siblings
+            .entry(parent.to_path_buf())
+            .or_insert_with(|| Siblings::read(parent))
+            .check(&name, Some(&src));
+        match check.blocking() {
+            Some(
+                NameProblem::Exists(other)
+                | NameProblem::CaseTwin(other)
+                | NameProblem::FormTwin(other),
+            ) => {
+                let existing = parent.join(other);
+                if !source_set.contains(&existing) {
+                    return Err(PlanError::Collision(existing));
+                }
+            }
+            Some(_) => return Err(PlanError::InvalidBase),
+            None => {}
+        }
+        let target = parent.join(name);
+        pairs.push((src, target));
+    }
+    Ok(pairs)
+}
+
+/// First and last `edge` renames as `old → new` lines, with `…` between.
+pub fn preview(pairs: &[(PathBuf, PathBuf)], edge: usize) -> Vec<String> {
+    let line = |(src, dst): &(PathBuf, PathBuf)| {
+        let name = |p: &Path| {
+            p.file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default()
+        };
+        format!("{} → {}", name(src), name(dst))
+    };
+    if pairs.len() <= edge * 2 {
+        return pairs.iter().map(line).collect();
+    }
+    let mut lines: Vec<String> = pairs[..edge].iter().map(line).collect();
+    lines.push(format!("… {} more …", pairs.len() - edge * 2));
+    lines.extend(pairs[pairs.len() - edge..].iter().map(line));
+    lines
+}
+
+/// Run the planned renames on the real filesystem.
+pub fn apply(pairs: &[(PathBuf, PathBuf)]) -> OpResult<()> {
+    apply_with(pairs, |from, to| std::fs::rename(from, to))
+}
+
+/// Two-phase rename through `rename` (injectable for tests).
+///
+/// On error, completed steps are reversed best-effort so the sources keep
+/// their original names, and the first failure is returned.
+pub fn apply_with<F>(pairs: &[(PathBuf, PathBuf)], mut rename: F) -> OpResult<()>
+where
+    F: FnMut(&Path, &Path) -> std::io::Result<()>,
+{
+    let moves: Vec<&(PathBuf, PathBuf)> = pairs.iter().filter(|(src, dst)| src != dst).collect();
+
+    // Phase 1: sources → temporary names
+    let mut temps: Vec<PathBuf> = Vec::with_capacity(moves.len());
+    for (i, (src, _)) in moves.iter().enumerate() {
+        let tmp = temp_path(src, i);
+        if let Err(e) = rename(src, &tmp) {
+            restore_sources(&moves, &temps, &mut rename);
+            return Err(OperationError::from_io(e, src));
+        }
+        temps.push(tmp);
+    }
+
+    // Phase 2: temporary names → targets
+    for (i, (_, dst)) in moves.iter().enumerate() {
+        let result = if std::fs::symlink_metadata(dst).is_ok() {
+            Err(OperationError::DestinationExists { path: dst.clone() })
+        } else {
+            rename(&temps[i], dst).map_err(|e| OperationError::from_io(e, dst))
+        };
+        if let Err(e) = result {
+            for j in (0..i).rev() {
+                let _ = rename(&moves[j].1, &temps[j]);
+            }
+            restore_sources(&moves, &temps, &mut rename);
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+/// Undo phase one for the first `temps.len()` moves.
+fn restore_sources<F>(moves: &[&(PathBuf, PathBuf)], temps: &[PathBuf], rename: &mut F)
+where
+    F: FnMut(&Path, &Path) -> std::io::Result<()>,
+{
+    for (tmp, (src, _)) in temps.iter().zip(moves.iter()).rev() {
+        let _ = rename(tmp, src);
+    }
+}
+
+/// Unused hidden name next to `src` for phase one.
+fn temp_path(src: &Path, index: usize) -> PathBuf {
+    let parent = src.parent().unwrap_or(Path::new("."));
+    let pid = std::process::id();
+    let mut attempt = 0;
+    loop {
+        let candidate = parent.join(format!(".fm-seq-{}-{}-{}", pid, index, attempt));
+        if std::fs::symlink_metadata(&candidate).is_err() {
+            return candidate;
+        }
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn spec(base: &str, start: usize, width: usize) -> SequenceSpec {
+        SequenceSpec {
+            base: base.to_string(),
+            start,
+            width,
+            sort: SortKey::Name,
+        }
+    }
+
+    fn make_files(dir: &Path, names: &[&str]) -> Vec<PathBuf> {
+        names
+            .iter()
+            .map(|n| {
+                let p = dir.join(n);
+                fs::write(&p, n).unwrap();
+                p
+            })
+            .collect()
+    }
+
+    fn listing(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn target_name_pads_and_keeps_extension() {
+        let s = spec("IMG_", 7, 4);
+        assert_eq!(target_name(&s, 7, Path::new("a.JPG")), "IMG_0007.JPG");
+        assert_eq!(target_name(&s, 12345, Path::new("noext")), "IMG_12345");
+    }
+
+    #[test]
+    fn plan_orders_by_name() {
+        let tmp = TempDir::new().unwrap();
+        let files = make_files(tmp.path(), &["c.jpg", "a.jpg", "b.jpg"]);
+        let pairs = plan(&files, &spec("p", 1, 2)).unwrap();
+        let names: Vec<String> = pairs
+            .iter()
+            .map(|(s, d)| {
+                format!(
+                    "{}>{}",
+                    s.file_name().unwrap().to_string_lossy(),
+                    d.file_name().unwrap().to_string_lossy()
+                )
+            })
+            .collect();
+        assert_eq!(
+            names,
+            vec!["a.jpg>p01.jpg", "b.jpg>p02.jpg", "c.jpg>p03.jpg"]
+        );
+    }
+
+    #[test]
+    fn order_by_mtime_oldest_first() {
+        let tmp = TempDir::new().unwrap();
+        let files = make_files(tmp.path(), &["a.jpg", "b.jpg", "c.jpg"]);
+        let base = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_000_000);
+        for (file, offset) in files.iter().zip([30u64, 10, 20]) {
+            fs::File::options()
+                .write(true)
+                .open(file)
+                .unwrap()
+                .set_modified(base + std::time::Duration::from_secs(offset))
+                .unwrap();
+        }
+        let ordered = order_sources(&files, SortKey::Mtime);
+        assert_eq!(
+            ordered,
+            vec![files[1].clone(), files[2].clone(), files[0].clone()]
+        );
+    }
+
+    #[test]
+    fn plan_rejects_collision_outside_set() {
+        let tmp = TempDir::new().unwrap();
+        let files = make_files(tmp.path(), &["a.jpg", "b.jpg"]);
+        fs::write(tmp.path().join("x2.jpg"), "other").unwrap();
+        assert_eq!(
+            plan(&files, &spec("x", 1, 1)),
+            Err(PlanError::Collision(tmp.path().join("x2.jpg")))
+        );
+    }
+
+    #[test]
+    fn plan_rejects_a_target_differing_only_by_case() {
+        let tmp = TempDir::new().unwrap();
+        let files = make_files(tmp.path(), &["a.jpg", "b.jpg"]);
+        fs::write(tmp.path().join("X1.jpg"), "other").unwrap();
+        assert_eq!(
+            plan(&files, &spec("x", 1, 1)),
+            Err(PlanError::Collision(tmp.path().join("X1.jpg")))
+        );
+    }
+
+    #[test]
+    fn plan_allows_targets_held_by_sources() {
+        let tmp = TempDir::new().unwrap();
+        let files = make_files(tmp.path(), &["f2.txt", "f3.txt", "f4.txt"]);
+        assert!(plan(&files, &spec("f", 1, 1)).is_ok());
+    }
+
+    #[test]
+    fn plan_dedups_sources_and_rejects_bad_base() {
+        let tmp = TempDir::new().unwrap();
+        let files = make_files(tmp.path(), &["a.txt"]);
+        let dup = vec![files[0].clone(), files[0].clone()];
+        assert_eq!(plan(&dup, &spec("n", 1, 1)).unwrap().len(), 1);
+        assert_eq!(
+            plan(&files, &spec("a/b", 1, 1)),
+            Err(PlanError::InvalidBase)
+        );
+        assert_eq!(plan(&[], &spec("a", 1, 1)), Err(PlanError::Empty));
+    }
+
+    #[test]
+    fn shift_by_one_overlap_renames_in_place() {
+        let tmp = TempDir::new().unwrap();
+        // f2→f1, f3→f2, f4→f3: every target but the first is another source
+        let files = make_files(tmp.path(), &["f2.txt", "f3.txt", "f4.txt"]);
+        let pairs = plan(&files, &spec("f", 1, 1)).unwrap();
+        apply(&pairs).unwrap();
+        assert_eq!(listing(tmp.path()), vec!["f1.txt", "f2.txt", "f3.txt"]);
+        // Contents followed their files
+        assert_eq!(
+            fs::read_to_string(tmp.path().join("f1.txt")).unwrap(),
+            "f2.txt"
+        );
+        assert_eq!(
+            fs::read_to_string(tmp.path().join("f3.txt")).unwrap(),
+            "f4.txt"
+        );
+    }
+
+    #[test]
+    fn phase_one_failure_restores_originals() {
+        let tmp = TempDir::new().unwrap();
+        let files = make_files(tmp.path(), &["a.txt", "b.txt", "c.txt"]);
+        let pairs = plan(&files, &spec("n", 1, 1)).unwrap();
+        let mut calls = 0;
+        let result = apply_with(&pairs, |from, to| {
+            calls += 1;
+            if calls == 3 {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::PermissionDenied,
+                    "denied",
+                ));
+            }
+            fs::rename(from, to)
+        });
+        assert!(matches!(
+            result,
+            Err(OperationError::PermissionDenied { .. })
+        ));
+        assert_eq!(listing(tmp.path()), vec!["a.txt", "b.txt", "c.txt"]);
+    }
+
+    #[test]
+    fn phase_two_failure_restores_originals() {
+        let tmp = TempDir::new().unwrap();
+        let files = make_files(tmp.path(), &["f2.txt", "f3.txt", "f4.txt"]);
+        let pairs = plan(&files, &spec("f", 1, 1)).unwrap();
+        let mut calls = 0;
+        // 3 phase-one renames succeed, then the second phase-two rename fails
+        let result = apply_with(&pairs, |from, to| {
+            calls += 1;
+            if calls == 5 {
+                return Err(std::io::Error::other("disk"));
+            }
+            fs::rename(from, to)
+        });
+        assert!(result.is_err());
+        assert_eq!(listing(tmp.path()), vec!["f2.txt", "f3.txt", "f4.txt"]);
+        assert_eq!(
+            fs::read_to_string(tmp.path().join("f2.txt")).unwrap(),
+            "f2.txt"
+        );
+    }
+
+    #[test]
+    fn target_created_after_planning_is_not_clobbered() {
+        let tmp = TempDir::new().unwrap();
+        let files = make_files(tmp.path(), &["a.txt", "b.txt"]);
+        let pairs = plan(&files, &spec("n", 1, 1)).unwrap();
+        fs::write(tmp.path().join("n2.txt"), "late").unwrap();
+        match apply(&pairs) {
+            Err(OperationError::DestinationExists { path }) => {
+                assert_eq!(path, tmp.path().join("n2.txt"))
+            }
+            other => panic!("expected DestinationExists, got {:?}", other),
+        }
+        assert_eq!(listing(tmp.path()), vec!["a.txt", "b.txt", "n2.txt"]);
+        assert_eq!(
+            fs::read_to_string(tmp.path().join("n2.txt")).unwrap(),
+            "late"
+        );
+    }
+
+    #[test]
+    fn preview_elides_middle() {
+        let pairs: Vec<(PathBuf, PathBuf)> = (0..10)
+            .map(|i| {
+                (
+                    PathBuf::from(format!("/d/s{}", i)),
+                    PathBuf::from(format!("/d/t{}", i)),
+                )
+            })
+            .collect();
+        let lines = preview(&pairs, 2);
+        assert_eq!(
+            lines,
+            vec!["s0 → t0", "s1 → t1", "… 6 more …", "s8 → t8", "s9 → t9"]
+        );
+        assert_eq!(preview(&pairs[..3], 2).len(), 3);
+    }
+}
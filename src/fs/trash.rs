@@ -0,0 +1,362 @@
+//! Move-to-trash deletes: the freedesktop.org home trash on Linux and
+//! `~/.Trash` on macOS. Trashed items can be put back by undo.
+
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{OpResult, OperationError};
+use crate::fs::operations;
+use crate::fs::undo_check::StampedPath;
+use crate::preview_content::epoch_days_to_date;
+
+/// Names tried inside the trash before giving up on a collision.
+const MAX_NAME_ATTEMPTS: usize = 10_000;
+
+/// How a trash directory is laid out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrashLayout {
+    /// `files/` plus an `info/NAME.trashinfo` record per item.
+    Freedesktop,
+    /// Items directly in the directory, with no record of where they came
+    /// from.
+    MacOs,
+}
+
+/// A trash directory items can be moved into.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trash {
+    pub dir: PathBuf,
+    pub layout: TrashLayout,
+}
+
+/// An item moved to the trash, with what is needed to put it back.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrashedEntry {
+    /// Where the item was before it was trashed.
+    pub original: PathBuf,
+    /// Where it is now, inside the trash.
+    pub trashed: StampedPath,
+    /// The `.trashinfo` record, removed again on restore.
+    pub info: Option<PathBuf>,
+}
+
+impl Trash {
+    /// The current user's trash, if this platform has one.
+    pub fn system() -> Option<Self> {
+        if cfg!(target_os = "macos") {
+            Some(Self {
+                dir: dirs::home_dir()?.join(".Trash"),
+                layout: TrashLayout::MacOs,
+            })
+        } else if cfg!(all(unix, not(target_os = "android"))) {
+            Some(Self {
+                dir: dirs::data_dir()?.join("Trash"),
+                layout: TrashLayout::Freedesktop,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Directory trashed items are moved into.
+    fn files_dir(&self) -> PathBuf {
+        match self.layout {
+            TrashLayout::Freedesktop => self.dir.join("files"),
+            TrashLayout::MacOs => self.dir.clone(),
+        }
+    }
+
+    /// Move `path` into the trash. The trash must be on the same
+    /// filesystem; nothing is copied.
+    pub fn trash(&self, path: &Path) -> OpResult<TrashedEntry> {
+        let original = std::path::absolute(path).map_err(|e| OperationError::from_io(e, path))?;
+        let name = original.file_name().ok_or_else(|| OperationError::Other {
+            path: original.clone(),
+            source: std::io::Error::new(ErrorKind::InvalidInput, "no filename"),
+        })?;
+        fs::symlink_metadata(&original).map_err(|e| OperationError::from_io(e, &original))?;
+
+        let files = self.files_dir();
+        create_private_dir(&files)?;
+        let info_dir = match self.layout {
+            TrashLayout::Freedesktop => {
+                let info_dir = self.dir.join("info");
+                create_private_dir(&info_dir)?;
+                Some(info_dir)
+            }
+            TrashLayout::MacOs => None,
+        };
+
+        for attempt in 1..=MAX_NAME_ATTEMPTS {
+            let mut candidate = name.to_os_string();
+            if attempt > 1 {
+                candidate.push(format!(".{}", attempt));
+            }
+            let dest = files.join(&candidate);
+            if fs::symlink_metadata(&dest).is_ok() {
+                continue;
+            }
+
+            // Claim the name with the info record first, so two processes
+            // trashing the same name can't both pick it
+            let info = match &info_dir {
+                Some(info_dir) => {
+                    let mut info_name = candidate.clone();
+                    info_name.push(".trashinfo");
+                    let info = info_dir.join(info_name);
+                    match write_info(&info, &original) {
+                        Ok(()) => Some(info),
+                        Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+                        Err(e) => return Err(OperationError::from_io(e, &info)),
+                    }
+                }
+                None => None,
+            };
+
+            if let Err(e) = fs::rename(&original, &dest) {
+                if let Some(info) = &info {
+                    let _ = fs::remove_file(info);
+                }
+                return Err(if e.kind() == ErrorKind::CrossesDevices {
+                    OperationError::Other {
+                        path: original,
+                        source: std::io::Error::new(
+                            ErrorKind::CrossesDevices,
+                            "the trash is on another filesystem (Shift+D deletes permanently)",
+                        ),
+                    }
+                } else {
+                    OperationError::from_io(e, &original)
+                });
+            }
+
+            return Ok(TrashedEntry {
+                original,
+                trashed: StampedPath::capture(dest),
+                info,
+            });
+        }
+
+        Err(OperationError::DestinationExists {
+            path: files.join(name),
+        })
+    }
+}
+
+/// Put a trashed item back. If its original location was taken since, it
+/// is restored next to it under a `_copy` name. Returns where it went.
+pub fn restore(entry: &TrashedEntry) -> OpResult<PathBuf> {
+    let dest = operations::resolve_collision(&entry.original);
+    operations::rename(&entry.trashed.path, &dest)?;
+    if let Some(info) = &entry.info {
+        let _ = fs::remove_file(info);
+    }
+    Ok(dest)
+}
+
+/// Create `dir` (and its parents), readable only by the owner as the trash
+/// spec asks.
+fn create_private_dir(dir: &Path) -> OpResult<()> {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    builder
+        .create(dir)
+        .map_err(|e| OperationError::from_io(e, dir))
+}
+
+/// Write the `.trashinfo` record for `original`, failing if `info` exists.
+fn write_info(info: &Path, original: &Path) -> std::io::Result<()> {
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(info)?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let contents = format!(
+        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+        encode_path(original),
+        format_deletion_date(now)
+    );
+    if let Err(e) = file.write_all(contents.as_bytes()) {
+        let _ = fs::remove_file(info);
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// Percent-encode a path for the `Path=` key, keeping `/` as is.
+fn encode_path(path: &Path) -> String {
+    #[cfg(unix)]
+    let bytes = {
+        use std::os::unix::ffi::OsStrExt;
+        path.as_os_str().as_bytes().to_vec()
+    };
+    #[cfg(not(unix))]
+    let bytes = path.to_string_lossy().into_owned().into_bytes();
+
+    let mut out = String::with_capacity(bytes.len());
+    for b in bytes {
+        if b.is_ascii_alphanumeric() || b"/-_.~".contains(&b) {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{:02X}", b));
+        }
+    }
+    out
+}
+
+/// `DeletionDate` value for `secs` since the epoch. The spec asks for
+/// local time; without a timezone database this writes UTC.
+fn format_deletion_date(secs: u64) -> String {
+    let (year, month, day) = epoch_days_to_date(secs / 86_400);
+    let rem = secs % 86_400;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        rem / 3600,
+        (rem % 3600) / 60,
+        rem % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn freedesktop(dir: &TempDir) -> Trash {
+        Trash {
+            dir: dir.path().join("Trash"),
+            layout: TrashLayout::Freedesktop,
+        }
+    }
+
+    #[test]
+    fn trash_moves_item_and_writes_info() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("my notes.txt");
+        fs::write(&file, "hello").unwrap();
+
+        let entry = freedesktop(&dir).trash(&file).unwrap();
+        assert!(!file.exists());
+        assert_eq!(entry.original, file);
+        assert_eq!(
+            entry.trashed.path,
+            dir.path().join("Trash/files/my notes.txt")
+        );
+        assert_eq!(fs::read_to_string(&entry.trashed.path).unwrap(), "hello");
+
+        let info = entry.info.clone().unwrap();
+        assert_eq!(info, dir.path().join("Trash/info/my notes.txt.trashinfo"));
+        let text = fs::read_to_string(&info).unwrap();
+        assert!(text.starts_with("[Trash Info]\n"));
+        assert!(text.contains(&format!("Path={}\n", encode_path(&file))));
+        assert!(text.contains("my%20notes.txt"));
+        assert!(text.contains("DeletionDate="));
+    }
+
+    #[test]
+    fn same_name_gets_a_numbered_slot() {
+        let dir = TempDir::new().unwrap();
+        let trash = freedesktop(&dir);
+        let a = dir.path().join("a");
+        let b = dir.path().join("b");
+        fs::create_dir_all(&a).unwrap();
+        fs::create_dir_all(&b).unwrap();
+        fs::write(a.join("x.txt"), "first").unwrap();
+        fs::write(b.join("x.txt"), "second").unwrap();
+
+        let first = trash.trash(&a.join("x.txt")).unwrap();
+        let second = trash.trash(&b.join("x.txt")).unwrap();
+        assert_eq!(first.trashed.path, dir.path().join("Trash/files/x.txt"));
+        assert_eq!(second.trashed.path, dir.path().join("Trash/files/x.txt.2"));
+        assert_eq!(
+            second.info.unwrap(),
+            dir.path().join("Trash/info/x.txt.2.trashinfo")
+        );
+    }
+
+    #[test]
+    fn restore_puts_item_back_and_drops_info() {
+        let dir = TempDir::new().unwrap();
+        let sub = dir.path().join("sub");
+        fs::create_dir_all(sub.join("inner")).unwrap();
+        fs::write(sub.join("inner/f.txt"), "x").unwrap();
+
+        let entry = freedesktop(&dir).trash(&sub).unwrap();
+        assert!(!sub.exists());
+        assert_eq!(restore(&entry).unwrap(), sub);
+        assert_eq!(fs::read_to_string(sub.join("inner/f.txt")).unwrap(), "x");
+        assert!(!entry.trashed.path.exists());
+        assert!(!entry.info.unwrap().exists());
+    }
+
+    #[test]
+    fn restore_beside_a_recreated_original() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("a.txt");
+        fs::write(&file, "old").unwrap();
+        let entry = freedesktop(&dir).trash(&file).unwrap();
+        fs::write(&file, "new").unwrap();
+
+        let restored = restore(&entry).unwrap();
+        assert_eq!(restored, dir.path().join("a_copy.txt"));
+        assert_eq!(fs::read_to_string(&file).unwrap(), "new");
+        assert_eq!(fs::read_to_string(&restored).unwrap(), "old");
+    }
+
+    #[test]
+    fn macos_layout_has_no_info() {
+        let dir = TempDir::new().unwrap();
+        let trash = Trash {
+            dir: dir.path().join(".Trash"),
+            layout: TrashLayout::MacOs,
+        };
+        let file = dir.path().join("a.txt");
+        fs::write(&file, "x").unwrap();
+        let entry = trash.trash(&file).unwrap();
+        assert_eq!(entry.trashed.path, dir.path().join(".Trash/a.txt"));
+        assert_eq!(entry.info, None);
+        restore(&entry).unwrap();
+        assert!(file.exists());
+    }
+
+    #[test]
+    fn missing_item_is_not_found() {
+        let dir = TempDir::new().unwrap();
+        let err = freedesktop(&dir)
+            .trash(&dir.path().join("nope"))
+            .unwrap_err();
+        assert!(matches!(err, OperationError::NotFound { .. }));
+        assert_eq!(
+            fs::read_dir(dir.path().join("Trash/info"))
+                .ok()
+                .map(|d| d.count()),
+            None
+        );
+    }
+
+    #[test]
+    fn deletion_date_is_iso_8601() {
+        assert_eq!(format_deletion_date(0), "1970-01-01T00:00:00");
+        assert_eq!(format_deletion_date(1_700_000_000), "2023-11-14T22:13:20");
+    }
+
+    #[test]
+    fn path_encoding_escapes_reserved_bytes() {
+        assert_eq!(encode_path(Path::new("/a b/c%d/é")), "/a%20b/c%25d/%C3%A9");
+    }
+}
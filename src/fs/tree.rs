@@ -1,10 +1,15 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::ffi::OsString;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::SystemTime;
 
+use crate::bounded_map::{BoundedMap, DEFAULT_SORT_OVERRIDE_CAPACITY};
 use crate::error::Result;
+use crate::fs::filter_class::FilterClass;
+use crate::fs::gitignore::GitIgnore;
+use crate::work_queue::Budget;
 
 /// Tracks visited directories by (device, inode) to detect symlink loops.
 ///
@@ -70,12 +75,14 @@ impl VisitedDirs {
 }
 
 /// A lightweight entry in a directory snapshot.
-/// Only stores the name and whether it's a directory — no expensive stat() call.
+/// Only stores the name and file type — no expensive stat() call.
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
 pub struct SnapshotEntry {
     pub name: OsString,
     pub is_dir: bool,
+    pub is_symlink: bool,
+    pub special: Option<SpecialFile>,
 }
 
 /// A snapshot of a directory's contents for efficient paginated access.
@@ -128,8 +135,8 @@ impl DirSnapshot {
                 }
             };
             // Use file_type() which is usually free (no extra stat on most OS)
-            let is_dir = match entry.file_type() {
-                Ok(ft) => ft.is_dir(),
+            let file_type = match entry.file_type() {
+                Ok(ft) => ft,
                 Err(_) => {
                     skipped_count += 1;
                     continue;
@@ -137,7 +144,9 @@ impl DirSnapshot {
             };
             entries.push(SnapshotEntry {
                 name: entry.file_name(),
-                is_dir,
+                is_dir: file_type.is_dir(),
+                is_symlink: file_type.is_symlink(),
+                special: SpecialFile::of(file_type),
             });
         }
 
@@ -215,6 +224,128 @@ pub struct FileMeta {
     pub size: u64,
     pub modified: Option<SystemTime>,
     pub is_hidden: bool,
+    /// Size and mtime are not known yet: the node was built from a
+    /// snapshot entry and its stat is queued (see `stat_service`).
+    pub pending: bool,
+    /// Where the entry points, for a symlink.
+    pub link: Option<LinkTarget>,
+    /// What kind of special file the entry is, if it is one.
+    pub special: Option<SpecialFile>,
+}
+
+/// A file that is neither a regular file, a directory nor a symlink. Its
+/// contents are never read: opening a FIFO blocks until a writer shows up,
+/// and reading a device can stream forever.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(not(unix), allow(dead_code))]
+pub enum SpecialFile {
+    Fifo,
+    Socket,
+    BlockDevice,
+    CharDevice,
+}
+
+impl SpecialFile {
+    /// The special kind of `file_type`, if any.
+    #[cfg(unix)]
+    pub fn of(file_type: fs::FileType) -> Option<Self> {
+        use std::os::unix::fs::FileTypeExt;
+        if file_type.is_fifo() {
+            Some(Self::Fifo)
+        } else if file_type.is_socket() {
+            Some(Self::Socket)
+        } else if file_type.is_block_device() {
+            Some(Self::BlockDevice)
+        } else if file_type.is_char_device() {
+            Some(Self::CharDevice)
+        } else {
+            None
+        }
+    }
+
+    #[cfg(not(unix))]
+    pub fn of(_file_type: fs::FileType) -> Option<Self> {
+        None
+    }
+
+    /// The special kind of what `path` resolves to, following symlinks.
+    /// Only stats `path`, so it is safe to call before opening it.
+    pub fn at(path: &Path) -> Option<Self> {
+        fs::metadata(path)
+            .ok()
+            .and_then(|meta| Self::of(meta.file_type()))
+    }
+
+    /// "named pipe", "socket", "block device" or "character device".
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Fifo => "named pipe",
+            Self::Socket => "socket",
+            Self::BlockDevice => "block device",
+            Self::CharDevice => "character device",
+        }
+    }
+}
+
+/// Make a FIFO at `path` with `mkfifo`, for tests; false where that fails.
+#[cfg(all(test, unix))]
+pub fn mkfifo(path: &Path) -> bool {
+    std::process::Command::new("mkfifo")
+        .arg(path)
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// Where a symlink points.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkTarget {
+    /// The link's contents as written, possibly relative to its directory.
+    pub target: PathBuf,
+    /// The target is a directory, so the link can be expanded.
+    pub is_dir: bool,
+    /// The target is missing or unreachable (`fs::metadata` fails).
+    pub broken: bool,
+}
+
+impl LinkTarget {
+    /// Read the symlink at `path`; `None` if it can't be read.
+    pub fn read(path: &Path) -> Option<Self> {
+        let target = fs::read_link(path).ok()?;
+        let resolved = fs::metadata(path);
+        Some(Self {
+            target,
+            is_dir: resolved.as_ref().is_ok_and(|m| m.is_dir()),
+            broken: resolved.is_err(),
+        })
+    }
+}
+
+impl FileMeta {
+    /// Metadata of the entry at `path`, without following symlinks.
+    pub fn stat(path: &Path) -> std::io::Result<Self> {
+        let metadata = fs::symlink_metadata(path)?;
+        Ok(Self::from_metadata(path, &metadata))
+    }
+
+    fn from_metadata(path: &Path, metadata: &fs::Metadata) -> Self {
+        Self {
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+            is_hidden: is_hidden_name(path),
+            pending: false,
+            link: metadata
+                .is_symlink()
+                .then(|| LinkTarget::read(path))
+                .flatten(),
+            special: SpecialFile::of(metadata.file_type()),
+        }
+    }
+}
+
+/// Dotfiles are hidden.
+fn is_hidden_name(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|n| n.to_string_lossy().starts_with('.'))
 }
 
 /// A node in the filesystem tree.
@@ -242,6 +373,12 @@ pub struct TreeNode {
     /// Whether the directory contents may have changed since last load.
     /// Set by FS watcher for paginated dirs; cleared on re-scan.
     pub is_stale: bool,
+    /// `.gitignore` rules of the directory holding this node, when they
+    /// are respected. Listing the node's children reads only its own
+    /// `.gitignore` on top of these.
+    pub gitignore: Option<Arc<GitIgnore>>,
+    /// Left out by `.gitignore`; shown, dimmed, only with hidden files.
+    pub is_ignored: bool,
 }
 
 impl TreeNode {
@@ -261,13 +398,7 @@ impl TreeNode {
             NodeType::File
         };
 
-        let is_hidden = name.starts_with('.');
-
-        let meta = FileMeta {
-            size: metadata.len(),
-            modified: metadata.modified().ok(),
-            is_hidden,
-        };
+        let meta = FileMeta::from_metadata(path, &metadata);
 
         Ok(Self {
             name,
@@ -283,9 +414,106 @@ impl TreeNode {
             snapshot: None,
             loaded_offset: 0,
             is_stale: false,
+            gitignore: None,
+            is_ignored: false,
         })
     }
 
+    /// Create a node from a snapshot entry without touching the disk. Its
+    /// metadata is pending until a stat fills it in.
+    pub fn from_entry(entry: &SnapshotEntry, parent_path: &Path, depth: usize) -> Self {
+        let path = parent_path.join(&entry.name);
+        let node_type = if entry.is_symlink {
+            NodeType::Symlink
+        } else if entry.is_dir {
+            NodeType::Directory
+        } else {
+            NodeType::File
+        };
+        Self {
+            name: entry.name.to_string_lossy().to_string(),
+            meta: FileMeta {
+                size: 0,
+                modified: None,
+                is_hidden: is_hidden_name(&path),
+                pending: true,
+                link: None,
+                special: entry.special,
+            },
+            path,
+            node_type,
+            children: None,
+            is_expanded: false,
+            depth,
+            total_child_count: None,
+            loaded_child_count: 0,
+            has_more_children: false,
+            snapshot: None,
+            loaded_offset: 0,
+            is_stale: false,
+            gitignore: None,
+            is_ignored: false,
+        }
+    }
+
+    /// Whether the node lists children: a directory, or a symlink to one.
+    pub fn is_expandable(&self) -> bool {
+        self.node_type == NodeType::Directory
+            || self.meta.link.as_ref().is_some_and(|link| link.is_dir)
+    }
+
+    /// Whether the node only shows with hidden files: a dotfile, or left
+    /// out by `.gitignore`.
+    pub fn is_hidden(&self) -> bool {
+        self.meta.is_hidden || self.is_ignored
+    }
+
+    /// Mark the loaded children `.gitignore` leaves out and hand them the
+    /// rules inside this directory. Does nothing unless the rules around
+    /// this node are set.
+    pub fn mark_ignored(&mut self) {
+        let (Some(around), Some(children)) = (&self.gitignore, &mut self.children) else {
+            return;
+        };
+        let inside = around.enter(&self.path);
+        let parent_ignored = self.is_ignored;
+        for child in children {
+            let is_dir = child.node_type == NodeType::Directory;
+            child.is_ignored = parent_ignored || inside.is_ignored(&child.path, is_dir);
+            child.gitignore = Some(Arc::clone(&inside));
+        }
+    }
+
+    /// `mark_ignored` for this node and every loaded node below it.
+    pub fn mark_ignored_below(&mut self) {
+        let mut stack = vec![self];
+        while let Some(node) = stack.pop() {
+            node.mark_ignored();
+            stack.extend(node.children.iter_mut().flatten());
+        }
+    }
+
+    /// Give this node the path `to` at `depth`, after it was renamed or
+    /// moved on disk, and carry everything loaded below it along.
+    fn move_to(&mut self, to: &Path, depth: usize) {
+        let from = std::mem::replace(&mut self.path, to.to_path_buf());
+        self.name = to
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| to.to_string_lossy().to_string());
+        self.meta.is_hidden = is_hidden_name(to);
+        let shift = depth as isize - self.depth as isize;
+        self.depth = depth;
+        let mut stack: Vec<&mut TreeNode> = self.children.iter_mut().flatten().collect();
+        while let Some(node) = stack.pop() {
+            if let Ok(rest) = node.path.strip_prefix(&from) {
+                node.path = to.join(rest);
+            }
+            node.depth = node.depth.saturating_add_signed(shift);
+            stack.extend(node.children.iter_mut().flatten());
+        }
+    }
+
     /// Load ALL children for a directory node (no pagination).
     ///
     /// This is the original unpaginated loading. Used internally when the
@@ -293,7 +521,7 @@ impl TreeNode {
     /// Sorting is applied separately via `TreeState::sort_children_of`.
     /// Permission-denied and broken symlinks are silently skipped.
     fn load_children_all(&mut self) -> Result<()> {
-        if self.node_type != NodeType::Directory {
+        if !self.is_expandable() {
             return Ok(());
         }
 
@@ -316,6 +544,7 @@ impl TreeNode {
         self.total_child_count = Some(count);
         self.loaded_child_count = count;
         self.has_more_children = false;
+        self.mark_ignored();
         Ok(())
     }
 
@@ -328,18 +557,23 @@ impl TreeNode {
     ///    `snapshot.len()` — no separate count pass needed.
     ///
     /// Sorting is applied separately via `TreeState::sort_children_of`.
+    #[allow(dead_code)]
     pub fn load_children_paged(&mut self, page_size: usize) -> Result<()> {
-        self.load_children_paged_with_sort(page_size, &SortBy::Name, true)
+        self.load_children_paged_with_sort(page_size, &SortBy::Name, true, false)
     }
 
     /// Load children with pagination support, using provided sort settings for snapshot.
+    ///
+    /// With `defer_stats`, children are built from the snapshot alone and
+    /// left with pending metadata instead of being stat-ed one by one.
     pub fn load_children_paged_with_sort(
         &mut self,
         page_size: usize,
         sort_by: &SortBy,
         dirs_first: bool,
+        defer_stats: bool,
     ) -> Result<()> {
-        if self.node_type != NodeType::Directory {
+        if !self.is_expandable() {
             return Ok(());
         }
 
@@ -362,7 +596,16 @@ impl TreeNode {
         if total <= page_size {
             self.snapshot = None;
             self.loaded_offset = 0;
-            return self.load_children_all();
+            if !defer_stats {
+                return self.load_children_all();
+            }
+            let children =
+                Self::load_nodes_from_snapshot(&snapshot.entries, &self.path, self.depth + 1, true);
+            self.loaded_child_count = children.len();
+            self.children = Some(children);
+            self.has_more_children = false;
+            self.mark_ignored();
+            return Ok(());
         }
 
         // Sort the snapshot for consistent pagination order
@@ -370,7 +613,8 @@ impl TreeNode {
 
         // Load first page of TreeNodes from snapshot
         let page_entries = snapshot.page(0, page_size);
-        let children = Self::load_nodes_from_snapshot(page_entries, &self.path, self.depth + 1);
+        let children =
+            Self::load_nodes_from_snapshot(page_entries, &self.path, self.depth + 1, defer_stats);
         let loaded = children.len();
 
         self.children = Some(children);
@@ -379,6 +623,7 @@ impl TreeNode {
         self.has_more_children = loaded < total;
         self.snapshot = Some(snapshot);
         self.is_stale = false;
+        self.mark_ignored();
 
         Ok(())
     }
@@ -386,11 +631,20 @@ impl TreeNode {
     /// Create TreeNodes from snapshot entries by stat-ing each one.
     ///
     /// Entries that fail to stat (permission denied, broken symlink) are skipped.
+    /// With `defer_stats`, nothing is stat-ed: every entry becomes a node
+    /// with pending metadata.
     pub fn load_nodes_from_snapshot(
         entries: &[SnapshotEntry],
         parent_path: &Path,
         child_depth: usize,
+        defer_stats: bool,
     ) -> Vec<TreeNode> {
+        if defer_stats {
+            return entries
+                .iter()
+                .map(|entry| TreeNode::from_entry(entry, parent_path, child_depth))
+                .collect();
+        }
         let mut nodes = Vec::with_capacity(entries.len());
         for entry in entries {
             let child_path = parent_path.join(&entry.name);
@@ -425,7 +679,7 @@ impl TreeNode {
         if let Some(count) = self.total_child_count {
             return Some(count);
         }
-        if self.node_type != NodeType::Directory {
+        if !self.is_expandable() {
             return None;
         }
         match fs::read_dir(&self.path) {
@@ -442,7 +696,6 @@ impl TreeNode {
     ///
     /// Returns `None` if the count hasn't been computed yet.
     /// Use `spawn_async_child_count` to populate this value asynchronously.
-    #[allow(dead_code)]
     pub fn child_count_cached(&self) -> Option<usize> {
         self.total_child_count
     }
@@ -453,22 +706,26 @@ impl TreeNode {
     /// O(1) access — no iteration, no HashSet dedup.
     ///
     /// Returns the number of newly loaded entries.
-    pub fn load_next_page(&mut self, page_size: usize) -> Result<usize> {
-        if self.node_type != NodeType::Directory || !self.has_more_children {
+    pub fn load_next_page(&mut self, page_size: usize, defer_stats: bool) -> Result<usize> {
+        if !self.is_expandable() || !self.has_more_children {
             return Ok(0);
         }
 
         // If stale, re-collect snapshot before loading next page
         if self.is_stale && self.snapshot.is_some() {
-            self.load_children_paged_with_sort(page_size, &SortBy::Name, true)?;
+            self.load_children_paged_with_sort(page_size, &SortBy::Name, true, defer_stats)?;
             return Ok(self.loaded_child_count);
         }
 
         // If we have a snapshot, use O(1) index-based access
         if let Some(ref snapshot) = self.snapshot {
             let page_entries = snapshot.page(self.loaded_offset, page_size);
-            let new_nodes =
-                Self::load_nodes_from_snapshot(page_entries, &self.path, self.depth + 1);
+            let new_nodes = Self::load_nodes_from_snapshot(
+                page_entries,
+                &self.path,
+                self.depth + 1,
+                defer_stats,
+            );
             let newly_loaded = new_nodes.len();
 
             let children = self.children.get_or_insert_with(Vec::new);
@@ -478,6 +735,7 @@ impl TreeNode {
             self.loaded_child_count += newly_loaded;
             let total = snapshot.len();
             self.has_more_children = self.loaded_offset < total;
+            self.mark_ignored();
 
             return Ok(newly_loaded);
         }
@@ -517,10 +775,32 @@ impl TreeNode {
         self.loaded_child_count += newly_loaded;
         let total = self.total_child_count.unwrap_or(0);
         self.has_more_children = self.loaded_child_count < total;
+        self.mark_ignored();
         Ok(newly_loaded)
     }
 }
 
+impl Drop for TreeNode {
+    /// Drop descendants one at a time; the default drop glue recurses once
+    /// per level and can overflow the stack on very deep trees.
+    fn drop(&mut self) {
+        let mut pending = self.children.take().unwrap_or_default();
+        while let Some(mut node) = pending.pop() {
+            if let Some(children) = node.children.take() {
+                pending.extend(children);
+            }
+        }
+    }
+}
+
+/// Label of the virtual row shown in place of children beyond
+/// `TreeState::max_depth`.
+pub const DEPTH_LIMIT_LABEL: &str = "… depth limit reached";
+
+/// Most rows `flatten` builds, so a pathological tree can't run out of
+/// memory.
+const MAX_FLAT_ITEMS: usize = 100_000;
+
 /// A flattened representation of a tree node for rendering.
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
@@ -532,12 +812,132 @@ pub struct FlatItem {
     pub is_expanded: bool,
     pub is_last_sibling: bool,
     pub is_hidden: bool,
+    /// Left out by `.gitignore`.
+    pub is_ignored: bool,
     /// For `NodeType::LoadMore`: the parent directory path to load more from.
     pub load_more_parent: Option<PathBuf>,
     /// For `NodeType::LoadMore`: approximate remaining entries.
     pub load_more_remaining: Option<usize>,
     /// For directories: total immediate child count (for count badge).
     pub child_count: Option<usize>,
+    /// Size and mtime are still being fetched.
+    pub stat_pending: bool,
+    /// Where a symlink points, once stat-ed.
+    pub link: Option<LinkTarget>,
+    pub special: Option<SpecialFile>,
+}
+
+impl FlatItem {
+    /// Virtual row standing in for the children of `node`, which sits at
+    /// the depth limit. It has no `load_more_parent`, so activating it does
+    /// nothing.
+    fn depth_limit(node: &TreeNode) -> Self {
+        Self {
+            name: DEPTH_LIMIT_LABEL.to_string(),
+            path: node.path.clone(),
+            node_type: NodeType::LoadMore,
+            depth: node.depth + 1,
+            is_expanded: false,
+            is_last_sibling: true,
+            is_hidden: false,
+            is_ignored: false,
+            load_more_parent: None,
+            load_more_remaining: None,
+            child_count: None,
+            stat_pending: false,
+            link: None,
+            special: None,
+        }
+    }
+
+    /// "Load more..." row after the loaded children of the paginated
+    /// directory `node`.
+    fn load_more(node: &TreeNode) -> Self {
+        let remaining = node
+            .total_child_count
+            .unwrap_or(0)
+            .saturating_sub(node.loaded_child_count);
+        Self {
+            name: format!("Load more... (remaining: ~{})", remaining),
+            path: node.path.clone(), // path points to the parent dir
+            node_type: NodeType::LoadMore,
+            depth: node.depth + 1,
+            is_expanded: false,
+            is_last_sibling: true,
+            is_hidden: false,
+            is_ignored: false,
+            load_more_parent: Some(node.path.clone()),
+            load_more_remaining: Some(remaining),
+            child_count: None,
+            stat_pending: false,
+            link: None,
+            special: None,
+        }
+    }
+
+    /// Whether this is a "depth limit reached" row.
+    pub fn is_depth_limit(&self) -> bool {
+        self.node_type == NodeType::LoadMore && self.load_more_parent.is_none()
+    }
+
+    /// Turn this row into `from_node(node, is_last, is_expanded)`, reusing
+    /// its name and path buffers instead of allocating new ones.
+    fn refill(&mut self, node: &TreeNode, is_last: bool, is_expanded: bool) {
+        self.name.clone_from(&node.name);
+        self.path.clone_from(&node.path);
+        self.node_type = node.node_type.clone();
+        self.depth = node.depth;
+        self.is_expanded = is_expanded;
+        self.is_last_sibling = is_last;
+        self.is_hidden = node.meta.is_hidden;
+        self.is_ignored = node.is_ignored;
+        self.load_more_parent = None;
+        self.load_more_remaining = None;
+        self.child_count = node.total_child_count;
+        self.stat_pending = node.meta.pending;
+        self.link.clone_from(&node.meta.link);
+        self.special = node.meta.special;
+    }
+
+    /// A row for `node`, recycled from `pool` when it holds one.
+    fn recycled(
+        pool: &mut Vec<FlatItem>,
+        node: &TreeNode,
+        is_last: bool,
+        is_expanded: bool,
+    ) -> Self {
+        match pool.pop() {
+            Some(mut row) => {
+                row.refill(node, is_last, is_expanded);
+                row
+            }
+            None => Self::from_node(node, is_last, is_expanded),
+        }
+    }
+
+    fn from_node(node: &TreeNode, is_last: bool, is_expanded: bool) -> Self {
+        Self {
+            name: node.name.clone(),
+            path: node.path.clone(),
+            node_type: node.node_type.clone(),
+            depth: node.depth,
+            is_expanded,
+            is_last_sibling: is_last,
+            is_hidden: node.meta.is_hidden,
+            is_ignored: node.is_ignored,
+            load_more_parent: None,
+            load_more_remaining: None,
+            child_count: node.total_child_count,
+            stat_pending: node.meta.pending,
+            link: node.meta.link.clone(),
+            special: node.meta.special,
+        }
+    }
+
+    /// Whether the row lists children: a directory, or a symlink to one.
+    pub fn is_expandable(&self) -> bool {
+        self.node_type == NodeType::Directory || self.link.as_ref().is_some_and(|link| link.is_dir)
+    }
 }
 
 /// Sort criteria for the tree.
@@ -561,6 +961,15 @@ impl SortBy {
         }
     }
 
+    /// Config string for this sort (inverse of `from_str`).
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SortBy::Name => "name",
+            SortBy::Size => "size",
+            SortBy::Modified => "modified",
+        }
+    }
+
     /// Get the display label for the current sort.
     pub fn label(&self) -> &'static str {
         match self {
@@ -591,14 +1000,50 @@ pub struct TreeState {
     pub multi_selected: HashSet<usize>,
     /// Current inline filter query string.
     pub filter_query: String,
+    /// File class chip applied with the query, if any.
+    pub filter_class: Option<FilterClass>,
     /// Whether the tree is currently being filtered.
     pub is_filtering: bool,
     /// Current sort criteria.
     pub sort_by: SortBy,
     /// Whether directories are shown before files.
     pub dirs_first: bool,
+    /// Per-directory sort for that directory's children, overriding
+    /// `sort_by` / `dirs_first`. Bounded: the least recently set override
+    /// is dropped and its directory follows the global sort again.
+    pub sort_overrides: BoundedMap<PathBuf, (SortBy, bool)>,
     /// Max entries to load per page (pagination threshold).
     pub page_size: usize,
+    /// Deepest level shown below the root. Directories at this depth don't
+    /// expand, and loaded children below it render as a single
+    /// "depth limit reached" row.
+    pub max_depth: usize,
+    /// Bumped whenever `flat_items` or `multi_selected` change, so rows
+    /// drawn from them can be reused until the next bump.
+    pub generation: u64,
+    /// Build loaded nodes without stat-ing them, leaving their metadata
+    /// for the stat service (slow network filesystems).
+    pub defer_stats: bool,
+    /// Entries `.gitignore` leaves out are hidden like dotfiles
+    /// (`tree.respect_gitignore`, `I` toggles).
+    pub respect_gitignore: bool,
+    /// Allocations kept between flattens.
+    buffers: FlattenBuffers,
+}
+
+/// What `flatten` and `apply_filter` keep between calls, so a keystroke in
+/// the filter on a large tree recycles the previous rows rather than
+/// allocating a name and a path for each one again.
+#[derive(Debug, Default)]
+struct FlattenBuffers {
+    /// Emptied rows vector of the previous pass.
+    spare: Vec<FlatItem>,
+    /// Per loaded node in display order: its parent's index.
+    parents: Vec<Option<usize>>,
+    /// Per loaded node: it or a descendant matches the filter.
+    keep: Vec<bool>,
+    /// Per loaded node: a descendant matches the filter.
+    child_matches: Vec<bool>,
 }
 
 impl TreeState {
@@ -610,9 +1055,15 @@ impl TreeState {
 
     /// Create a new TreeState with a specific page size for pagination.
     pub fn with_page_size(path: &Path, page_size: usize) -> Result<Self> {
+        Self::with_options(path, page_size, false)
+    }
+
+    /// Create a new TreeState, deferring the stats of loaded entries when
+    /// `defer_stats` is set.
+    pub fn with_options(path: &Path, page_size: usize, defer_stats: bool) -> Result<Self> {
         let mut root = TreeNode::new(path, 0)?;
-        if root.node_type == NodeType::Directory {
-            root.load_children_paged(page_size)?;
+        if root.is_expandable() {
+            root.load_children_paged_with_sort(page_size, &SortBy::Name, true, defer_stats)?;
             root.is_expanded = true;
         }
 
@@ -624,10 +1075,17 @@ impl TreeState {
             show_hidden: false,
             multi_selected: HashSet::new(),
             filter_query: String::new(),
+            filter_class: None,
             is_filtering: false,
             sort_by: SortBy::Name,
             dirs_first: true,
+            sort_overrides: BoundedMap::new(DEFAULT_SORT_OVERRIDE_CAPACITY),
             page_size,
+            max_depth: crate::config::DEFAULT_MAX_TREE_DEPTH,
+            generation: 0,
+            defer_stats,
+            respect_gitignore: false,
+            buffers: FlattenBuffers::default(),
         };
         state.sort_all_children();
         state.flatten();
@@ -639,92 +1097,161 @@ impl TreeState {
     /// The root node is always included regardless of hidden status.
     /// Multi-selection is cleared since indices change.
     /// Item count is capped at 100K to prevent OOM on pathological trees.
+    /// The previous rows are recycled, so this allocates little beyond what
+    /// the tree grew by.
     pub fn flatten(&mut self) {
-        let prev_len = self.flat_items.len();
-        self.flat_items.clear();
+        self.touch();
         self.multi_selected.clear();
-        // Pre-allocate based on previous size for performance
-        self.flat_items.reserve(prev_len.min(10_000));
+        let mut pool = std::mem::take(&mut self.flat_items);
+        let mut items = std::mem::take(&mut self.buffers.spare);
+        items.clear();
         Self::flatten_node(
             &self.root,
-            &mut self.flat_items,
+            &mut items,
+            &mut pool,
             self.show_hidden,
-            true,
-            true,
+            self.max_depth,
+            false,
         );
-        // Clamp selected index
-        if !self.flat_items.is_empty() && self.selected_index >= self.flat_items.len() {
-            self.selected_index = self.flat_items.len() - 1;
+        pool.clear();
+        self.buffers.spare = pool;
+        self.flat_items = items;
+        self.clamp_selection();
+    }
+
+    /// Rebuild only the rows of `dir` and of what is shown below it, as
+    /// `flatten` would, after that one directory was expanded, collapsed or
+    /// (re)loaded. Every other row stays as it is. Falls back to `flatten`
+    /// while filtering, when `dir` has no row or near the row cap.
+    pub fn flatten_subtree(&mut self, dir: &Path) {
+        if self.is_filtering || self.flat_items.len() >= MAX_FLAT_ITEMS {
+            return self.flatten();
         }
+        let row = match self.flat_items.get(self.selected_index) {
+            Some(item) if item.path == dir && item.node_type != NodeType::LoadMore => {
+                Some(self.selected_index)
+            }
+            _ => self.find_index_by_path(dir),
+        };
+        let Some(row) = row else {
+            return self.flatten();
+        };
+        self.touch();
+        self.multi_selected.clear();
+        let Some(node) = Self::find_node(&self.root, dir) else {
+            return self.flatten();
+        };
+        let depth = self.flat_items[row].depth;
+        let is_last = self.flat_items[row].is_last_sibling;
+        let end = self.flat_items[row + 1..]
+            .iter()
+            .position(|item| item.depth <= depth)
+            .map_or(self.flat_items.len(), |i| row + 1 + i);
+        let mut pool: Vec<FlatItem> = self.flat_items.drain(row..end).collect();
+        let mut rows = Vec::with_capacity(pool.len());
+        Self::flatten_node(
+            node,
+            &mut rows,
+            &mut pool,
+            self.show_hidden,
+            self.max_depth,
+            false,
+        );
+        rows[0].is_last_sibling = is_last;
+        self.flat_items.splice(row..row, rows);
+        self.flat_items.truncate(MAX_FLAT_ITEMS);
+        self.clamp_selection();
+    }
+
+    /// Flatten every loaded node like `flatten`, including the children of
+    /// collapsed directories and ignoring any filter (used by tree export).
+    pub fn loaded_items(&self) -> Vec<FlatItem> {
+        let mut items = Vec::new();
+        Self::flatten_node(
+            &self.root,
+            &mut items,
+            &mut Vec::new(),
+            self.show_hidden,
+            self.max_depth,
+            true,
+        );
+        items
     }
 
+    /// Append `root` and every expanded (or, with `all_loaded`, every
+    /// loaded) descendant in display order, recycling rows from `pool`.
+    ///
+    /// Walks with an explicit stack so deep trees can't overflow the call
+    /// stack; children below `max_depth` become one "depth limit reached" row.
     fn flatten_node(
-        node: &TreeNode,
+        root: &TreeNode,
         items: &mut Vec<FlatItem>,
+        pool: &mut Vec<FlatItem>,
         show_hidden: bool,
-        is_last: bool,
-        is_root: bool,
+        max_depth: usize,
+        all_loaded: bool,
     ) {
-        // Safety cap: prevent OOM on pathological trees
-        const MAX_FLAT_ITEMS: usize = 100_000;
-        if items.len() >= MAX_FLAT_ITEMS {
-            return;
+        enum Row<'a> {
+            Node { node: &'a TreeNode, is_last: bool },
+            LoadMore(&'a TreeNode),
+            DepthLimit(&'a TreeNode),
         }
 
-        if !is_root && !show_hidden && node.meta.is_hidden {
-            return;
-        }
-
-        items.push(FlatItem {
-            name: node.name.clone(),
-            path: node.path.clone(),
-            node_type: node.node_type.clone(),
-            depth: node.depth,
-            is_expanded: node.is_expanded,
-            is_last_sibling: is_last,
-            is_hidden: node.meta.is_hidden,
-            load_more_parent: None,
-            load_more_remaining: None,
-            child_count: node.total_child_count,
-        });
-
-        if node.is_expanded {
-            if let Some(children) = &node.children {
-                let visible_children: Vec<&TreeNode> = if show_hidden {
-                    children.iter().collect()
-                } else {
-                    children.iter().filter(|c| !c.meta.is_hidden).collect()
-                };
-
-                // If paginated, the last real child is NOT the last sibling —
-                // the LoadMore node will be the last sibling instead.
-                let has_load_more = node.has_more_children;
+        let mut stack = vec![Row::Node {
+            node: root,
+            is_last: true,
+        }];
+        while let Some(row) = stack.pop() {
+            if items.len() >= MAX_FLAT_ITEMS {
+                return;
+            }
+            let node = match row {
+                Row::Node { node, is_last } => {
+                    items.push(FlatItem::recycled(pool, node, is_last, node.is_expanded));
+                    node
+                }
+                Row::LoadMore(node) => {
+                    items.push(FlatItem::load_more(node));
+                    continue;
+                }
+                Row::DepthLimit(node) => {
+                    items.push(FlatItem::depth_limit(node));
+                    continue;
+                }
+            };
 
-                for (i, child) in visible_children.iter().enumerate() {
-                    let is_last_child = i == visible_children.len() - 1 && !has_load_more;
-                    Self::flatten_node(child, items, show_hidden, is_last_child, false);
+            if !node.is_expanded && !all_loaded {
+                continue;
+            }
+            let children = match &node.children {
+                Some(children) => children,
+                None => continue,
+            };
+            let shown = |c: &TreeNode| show_hidden || !c.is_hidden();
+            let last_shown = children.iter().rposition(shown);
+            if node.depth >= max_depth {
+                if last_shown.is_some() || node.has_more_children {
+                    stack.push(Row::DepthLimit(node));
                 }
+                continue;
+            }
 
-                // Emit the "Load more..." virtual node
-                if has_load_more {
-                    let remaining = node
-                        .total_child_count
-                        .unwrap_or(0)
-                        .saturating_sub(node.loaded_child_count);
-                    let label = format!("Load more... (remaining: ~{})", remaining);
-                    items.push(FlatItem {
-                        name: label,
-                        path: node.path.clone(), // path points to the parent dir
-                        node_type: NodeType::LoadMore,
-                        depth: node.depth + 1,
-                        is_expanded: false,
-                        is_last_sibling: true,
-                        is_hidden: false,
-                        load_more_parent: Some(node.path.clone()),
-                        load_more_remaining: Some(remaining),
-                        child_count: None,
-                    });
+            // If paginated, the last real child is NOT the last sibling —
+            // the LoadMore node will be the last sibling instead.
+            let has_load_more = node.has_more_children;
+            if has_load_more {
+                stack.push(Row::LoadMore(node));
+            }
+            // Pushed in reverse so they pop in display order
+            for (i, child) in children.iter().enumerate().rev() {
+                if !shown(child) {
+                    continue;
                 }
+                let is_last = Some(i) == last_shown && !has_load_more;
+                stack.push(Row::Node {
+                    node: child,
+                    is_last,
+                });
             }
         }
     }
@@ -737,19 +1264,29 @@ impl TreeState {
             return;
         }
         let selected = &self.flat_items[self.selected_index];
-        if selected.node_type != NodeType::Directory {
+        if !selected.is_expandable() || selected.depth >= self.max_depth {
             return;
         }
         let path = selected.path.clone();
-        let sort_by = self.sort_by.clone();
-        let dirs_first = self.dirs_first;
+        if selected.node_type == NodeType::Symlink && self.link_loops(&path).is_some() {
+            return;
+        }
+        // Opening a directory counts as using its override
+        self.sort_overrides.touch(path.as_path());
+        let (sort_by, dirs_first) = self.sort_for(&path);
         let page_size = self.page_size;
+        let defer_stats = self.defer_stats;
         if let Some(node) = Self::find_node_mut(&mut self.root, &path) {
             if !node.is_expanded || node.is_stale {
-                let _ = node.load_children_paged_with_sort(page_size, &sort_by, dirs_first);
+                let _ = node.load_children_paged_with_sort(
+                    page_size,
+                    &sort_by,
+                    dirs_first,
+                    defer_stats,
+                );
                 Self::sort_children_of(node, &sort_by, dirs_first);
                 node.is_expanded = true;
-                self.flatten();
+                self.flatten_subtree(&path);
             }
         }
     }
@@ -760,12 +1297,12 @@ impl TreeState {
     /// `parent_path` is the directory to load more entries from.
     /// Returns the number of newly loaded entries.
     pub fn load_next_page(&mut self, parent_path: &Path) -> usize {
-        let sort_by = self.sort_by.clone();
-        let dirs_first = self.dirs_first;
+        let (sort_by, dirs_first) = self.sort_for(parent_path);
         let page_size = self.page_size;
+        let defer_stats = self.defer_stats;
 
         let loaded = if let Some(node) = Self::find_node_mut(&mut self.root, parent_path) {
-            let count = node.load_next_page(page_size).unwrap_or(0);
+            let count = node.load_next_page(page_size, defer_stats).unwrap_or(0);
             if count > 0 {
                 Self::sort_children_of(node, &sort_by, dirs_first);
             }
@@ -775,7 +1312,7 @@ impl TreeState {
         };
 
         if loaded > 0 {
-            self.flatten();
+            self.flatten_subtree(parent_path);
         }
         loaded
     }
@@ -789,10 +1326,10 @@ impl TreeState {
         let path = selected.path.clone();
 
         // If it's an expanded directory, collapse it
-        if selected.node_type == NodeType::Directory && selected.is_expanded {
+        if selected.is_expandable() && selected.is_expanded {
             if let Some(node) = Self::find_node_mut(&mut self.root, &path) {
                 node.is_expanded = false;
-                self.flatten();
+                self.flatten_subtree(&path);
             }
             return;
         }
@@ -809,19 +1346,33 @@ impl TreeState {
         }
     }
 
-    /// Find a mutable reference to a node by path.
+    /// Find a mutable reference to a node by path, descending only into
+    /// the child whose path leads to `target`.
     fn find_node_mut<'a>(node: &'a mut TreeNode, target: &Path) -> Option<&'a mut TreeNode> {
-        if node.path == target {
-            return Some(node);
-        }
-        if let Some(children) = &mut node.children {
-            for child in children.iter_mut() {
-                if let Some(found) = Self::find_node_mut(child, target) {
-                    return Some(found);
-                }
+        let mut node = node;
+        loop {
+            if node.path == target {
+                return Some(node);
             }
+            node = node
+                .children
+                .as_mut()?
+                .iter_mut()
+                .find(|child| target.starts_with(&child.path))?;
         }
-        None
+    }
+
+    /// The ancestor of `path` (a symlink) that it resolves to, if any:
+    /// expanding it would list that ancestor again inside itself, forever.
+    /// Ancestors up to the root are compared by canonical path; how deep
+    /// links to elsewhere can nest is capped by `max_depth`.
+    pub fn link_loops(&self, path: &Path) -> Option<PathBuf> {
+        let target = fs::canonicalize(path).ok()?;
+        path.ancestors()
+            .skip(1)
+            .take_while(|ancestor| ancestor.starts_with(&self.root.path))
+            .find(|ancestor| fs::canonicalize(ancestor).is_ok_and(|c| c == target))
+            .map(Path::to_path_buf)
     }
 
     /// Update the scroll offset to ensure the selected item is visible.
@@ -838,14 +1389,19 @@ impl TreeState {
 
     /// Reload a specific directory's children and re-flatten.
     pub fn reload_dir(&mut self, dir_path: &Path) {
-        let sort_by = self.sort_by.clone();
-        let dirs_first = self.dirs_first;
+        let (sort_by, dirs_first) = self.sort_for(dir_path);
         let page_size = self.page_size;
+        let defer_stats = self.defer_stats;
         if let Some(node) = Self::find_node_mut(&mut self.root, dir_path) {
-            if node.node_type == NodeType::Directory {
-                let _ = node.load_children_paged_with_sort(page_size, &sort_by, dirs_first);
+            if node.is_expandable() {
+                let _ = node.load_children_paged_with_sort(
+                    page_size,
+                    &sort_by,
+                    dirs_first,
+                    defer_stats,
+                );
                 Self::sort_children_of(node, &sort_by, dirs_first);
-                self.flatten();
+                self.flatten_subtree(dir_path);
             }
         }
     }
@@ -856,83 +1412,424 @@ impl TreeState {
         self.flatten();
     }
 
-    /// Sort a node's children (non-recursive, just immediate children).
-    fn sort_children_of(node: &mut TreeNode, sort_by: &SortBy, dirs_first: bool) {
-        if let Some(children) = &mut node.children {
-            children.sort_by(|a, b| {
-                let mut cmp = std::cmp::Ordering::Equal;
-
-                if dirs_first {
-                    cmp = matches!(b.node_type, NodeType::Directory)
-                        .cmp(&matches!(a.node_type, NodeType::Directory));
-                }
-
-                cmp.then_with(|| match sort_by {
-                    SortBy::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
-                    SortBy::Size => b.meta.size.cmp(&a.meta.size),
-                    SortBy::Modified => b.meta.modified.cmp(&a.meta.modified),
-                })
-            });
+    /// Start or stop hiding what `.gitignore` leaves out, re-marking every
+    /// loaded node, and re-flatten.
+    pub fn set_respect_gitignore(&mut self, on: bool) {
+        self.respect_gitignore = on;
+        self.root.gitignore = on.then(|| GitIgnore::around(&self.root.path));
+        self.root.is_ignored = false;
+        if on {
+            self.root.mark_ignored_below();
+        } else {
+            let mut stack = vec![&mut self.root];
+            while let Some(node) = stack.pop() {
+                node.gitignore = None;
+                node.is_ignored = false;
+                stack.extend(node.children.iter_mut().flatten());
+            }
+        }
+        if self.is_filtering {
+            self.apply_filter();
+        } else {
+            self.flatten();
         }
     }
 
-    /// Recursively sort all loaded children in the tree.
-    ///
-    /// For paginated directories with snapshots: re-sort the snapshot,
-    /// drop loaded children, and re-load the first page from the new order.
-    fn sort_all_children_recursive(
-        node: &mut TreeNode,
-        sort_by: &SortBy,
-        dirs_first: bool,
-        page_size: usize,
-    ) {
-        // If this node has a snapshot (paginated), re-sort and re-paginate
-        if let Some(ref mut snapshot) = node.snapshot {
-            snapshot.sort(sort_by, dirs_first);
-            // Re-load first page from re-sorted snapshot
-            let page_entries = snapshot.page(0, page_size);
-            let children =
-                TreeNode::load_nodes_from_snapshot(page_entries, &node.path, node.depth + 1);
-            let loaded = children.len();
-            node.children = Some(children);
-            node.loaded_child_count = loaded;
-            node.loaded_offset = loaded;
-            node.has_more_children = loaded < snapshot.len();
+    /// Add the entry `path`, just created on disk, to its loaded parent in
+    /// sorted position instead of reloading the parent. Nothing to do when
+    /// the parent isn't loaded. Returns false when only a reload will do:
+    /// the parent is paginated or stale, or the entry is already listed or
+    /// can't be stat-ed.
+    pub fn insert_path(&mut self, path: &Path) -> bool {
+        let Some(parent_path) = path.parent() else {
+            return false;
+        };
+        let (sort_by, dirs_first) = self.sort_for(parent_path);
+        let Some(parent) = Self::find_node_mut(&mut self.root, parent_path) else {
+            return true;
+        };
+        let Some(children) = &parent.children else {
+            return true;
+        };
+        if parent.snapshot.is_some() || parent.is_stale || children.iter().any(|c| c.path == path) {
+            return false;
         }
+        let Ok(node) = TreeNode::new(path, parent.depth + 1) else {
+            return false;
+        };
+        Self::insert_sorted(parent, node, &sort_by, dirs_first);
+        true
+    }
 
-        // Sort currently loaded children (applies full metadata sort: size/modified/name)
-        Self::sort_children_of(node, sort_by, dirs_first);
+    /// Drop the entry `path`, gone from disk, from its loaded parent
+    /// instead of reloading the parent. Returns false when only a reload
+    /// will do: the parent is paginated or stale, or something is at
+    /// `path` again.
+    pub fn remove_path(&mut self, path: &Path) -> bool {
+        if fs::symlink_metadata(path).is_ok() {
+            return false;
+        }
+        let Some(parent) = path
+            .parent()
+            .and_then(|dir| Self::find_node_mut(&mut self.root, dir))
+        else {
+            return true;
+        };
+        if parent.snapshot.is_some() || parent.is_stale {
+            return false;
+        }
+        if let Some(children) = &mut parent.children {
+            children.retain(|c| c.path != path);
+            parent.total_child_count = Some(children.len());
+            parent.loaded_child_count = children.len();
+        }
+        true
+    }
 
-        // Recurse into children
+    /// Move the node at `from` to `to` after a rename on disk, keeping it
+    /// and everything loaded below it, expanded directories included,
+    /// instead of reloading either parent. Returns false when only a
+    /// reload will do: a parent involved is paginated or stale, or `from`
+    /// isn't listed in its loaded parent.
+    pub fn rename_path(&mut self, from: &Path, to: &Path) -> bool {
+        let (Some(from_dir), Some(to_dir)) = (from.parent(), to.parent()) else {
+            return false;
+        };
+        let dest_loaded = match Self::find_node(&self.root, to_dir) {
+            Some(dest) if dest.snapshot.is_some() || dest.is_stale => return false,
+            Some(dest) => dest.children.is_some(),
+            None => false,
+        };
+        let source = Self::find_node_mut(&mut self.root, from_dir);
+        let Some(source) = source.filter(|source| source.children.is_some()) else {
+            // Moved into view from somewhere not loaded
+            return !dest_loaded || self.insert_path(to);
+        };
+        if source.snapshot.is_some() || source.is_stale {
+            return false;
+        }
+        let children = source.children.get_or_insert_with(Vec::new);
+        let Some(index) = children.iter().position(|c| c.path == from) else {
+            return false;
+        };
+        let mut node = children.remove(index);
+        source.total_child_count = Some(children.len());
+        source.loaded_child_count = children.len();
+        if !dest_loaded {
+            return true;
+        }
+
+        let (sort_by, dirs_first) = self.sort_for(to_dir);
+        let Some(dest) = Self::find_node_mut(&mut self.root, to_dir) else {
+            return true;
+        };
+        node.move_to(to, dest.depth + 1);
+        // Renamed over an existing entry
+        if let Some(children) = &mut dest.children {
+            children.retain(|c| c.path != to);
+        }
+        let index = Self::insert_sorted(dest, node, &sort_by, dirs_first);
+        if let Some(moved) = dest.children.as_mut().and_then(|c| c.get_mut(index)) {
+            moved.mark_ignored_below();
+        }
+        true
+    }
+
+    /// Insert `node` among `parent`'s loaded children where sorting puts
+    /// it, marking whether `.gitignore` leaves it out. Returns its index.
+    fn insert_sorted(
+        parent: &mut TreeNode,
+        node: TreeNode,
+        sort_by: &SortBy,
+        dirs_first: bool,
+    ) -> usize {
+        let children = parent.children.get_or_insert_with(Vec::new);
+        let index = children.partition_point(|c| {
+            compare_nodes(c, &node, sort_by, dirs_first) != std::cmp::Ordering::Greater
+        });
+        children.insert(index, node);
+        parent.total_child_count = Some(children.len());
+        parent.loaded_child_count = children.len();
+        parent.mark_ignored();
+        index
+    }
+
+    /// Sort a node's children (non-recursive, just immediate children).
+    fn sort_children_of(node: &mut TreeNode, sort_by: &SortBy, dirs_first: bool) {
         if let Some(children) = &mut node.children {
-            for child in children.iter_mut() {
-                Self::sort_all_children_recursive(child, sort_by, dirs_first, page_size);
+            children.sort_by(|a, b| compare_nodes(a, b, sort_by, dirs_first));
+        }
+    }
+
+    /// Sort settings for `dir`'s children: its override if one is set,
+    /// otherwise the global `sort_by` / `dirs_first`.
+    pub fn sort_for(&self, dir: &Path) -> (SortBy, bool) {
+        match self.sort_overrides.get(dir) {
+            Some(sort) => sort.clone(),
+            None => (self.sort_by.clone(), self.dirs_first),
+        }
+    }
+
+    /// Re-sort one node's children (non-recursive).
+    ///
+    /// For paginated directories with snapshots: re-sort the snapshot,
+    /// drop loaded children, and re-load the first page from the new order.
+    fn resort_node(
+        node: &mut TreeNode,
+        sort_by: &SortBy,
+        dirs_first: bool,
+        page_size: usize,
+        defer_stats: bool,
+    ) {
+        if let Some(ref mut snapshot) = node.snapshot {
+            snapshot.sort(sort_by, dirs_first);
+            // Re-load first page from re-sorted snapshot
+            let page_entries = snapshot.page(0, page_size);
+            let children = TreeNode::load_nodes_from_snapshot(
+                page_entries,
+                &node.path,
+                node.depth + 1,
+                defer_stats,
+            );
+            let loaded = children.len();
+            node.children = Some(children);
+            node.loaded_child_count = loaded;
+            node.loaded_offset = loaded;
+            node.has_more_children = loaded < snapshot.len();
+        }
+
+        // Sort currently loaded children (applies full metadata sort: size/modified/name)
+        Self::sort_children_of(node, sort_by, dirs_first);
+    }
+
+    /// Sort the loaded children of `root` and all its descendants, honoring
+    /// overrides. Walks with an explicit stack, so depth isn't limited by
+    /// the call stack.
+    ///
+    /// With `skip_overridden`, directories with an override are left as they
+    /// are (only their descendants are visited), so a global sort change
+    /// doesn't re-page them.
+    fn sort_subtree(
+        root: &mut TreeNode,
+        default: &(SortBy, bool),
+        overrides: &BoundedMap<PathBuf, (SortBy, bool)>,
+        skip_overridden: bool,
+        page_size: usize,
+        defer_stats: bool,
+    ) {
+        let mut stack = vec![root];
+        while let Some(node) = stack.pop() {
+            Self::resort_in_subtree(
+                node,
+                default,
+                overrides,
+                skip_overridden,
+                page_size,
+                defer_stats,
+            );
+            if let Some(children) = node.children.as_mut() {
+                stack.extend(children.iter_mut());
             }
         }
     }
 
+    /// Re-sort one node of a subtree being sorted: by its override, or by
+    /// `default` if it has none.
+    fn resort_in_subtree(
+        node: &mut TreeNode,
+        default: &(SortBy, bool),
+        overrides: &BoundedMap<PathBuf, (SortBy, bool)>,
+        skip_overridden: bool,
+        page_size: usize,
+        defer_stats: bool,
+    ) {
+        match overrides.get(&node.path) {
+            Some(_) if skip_overridden => {}
+            Some((sort_by, dirs_first)) => {
+                Self::resort_node(node, sort_by, *dirs_first, page_size, defer_stats);
+            }
+            None => Self::resort_node(node, &default.0, default.1, page_size, defer_stats),
+        }
+    }
+
     /// Sort all children in the entire tree and re-flatten.
     pub fn sort_all_children(&mut self) {
-        let sort_by = self.sort_by.clone();
-        let dirs_first = self.dirs_first;
+        self.sort_all_children_with(false);
+    }
+
+    fn sort_all_children_with(&mut self, skip_overridden: bool) {
+        let default = (self.sort_by.clone(), self.dirs_first);
         let page_size = self.page_size;
-        Self::sort_all_children_recursive(&mut self.root, &sort_by, dirs_first, page_size);
+        Self::sort_subtree(
+            &mut self.root,
+            &default,
+            &self.sort_overrides,
+            skip_overridden,
+            page_size,
+            self.defer_stats,
+        );
     }
 
-    /// Cycle to the next sort mode and re-sort.
+    /// Cycle to the next sort mode and re-sort directories without an override.
+    #[allow(dead_code)]
     pub fn cycle_sort(&mut self) {
         self.sort_by = self.sort_by.next();
-        self.sort_all_children();
+        self.sort_all_children_with(true);
         self.flatten();
     }
 
-    /// Toggle dirs_first and re-sort.
+    /// Toggle dirs_first and re-sort directories without an override.
+    #[allow(dead_code)]
     pub fn toggle_dirs_first(&mut self) {
         self.dirs_first = !self.dirs_first;
-        self.sort_all_children();
+        self.sort_all_children_with(true);
         self.flatten();
     }
 
+    /// Set `dir`'s sort override and re-sort just that directory. A
+    /// directory whose override is evicted to make room goes back to the
+    /// global sort.
+    pub fn set_sort_override(&mut self, dir: &Path, sort_by: SortBy, dirs_first: bool) {
+        let evicted = self
+            .sort_overrides
+            .insert(dir.to_path_buf(), (sort_by.clone(), dirs_first));
+        if let Some((evicted, _)) = evicted {
+            let sort_by = self.sort_by.clone();
+            let dirs_first = self.dirs_first;
+            self.resort_dir(&evicted, &sort_by, dirs_first);
+        }
+        self.resort_dir(dir, &sort_by, dirs_first);
+    }
+
+    /// Cycle the sort mode of `dir`'s override, starting from its current sort.
+    pub fn cycle_dir_sort(&mut self, dir: &Path) {
+        let (sort_by, dirs_first) = self.sort_for(dir);
+        self.set_sort_override(dir, sort_by.next(), dirs_first);
+    }
+
+    /// Toggle dirs_first in `dir`'s override, starting from its current sort.
+    pub fn toggle_dir_dirs_first(&mut self, dir: &Path) {
+        let (sort_by, dirs_first) = self.sort_for(dir);
+        self.set_sort_override(dir, sort_by, !dirs_first);
+    }
+
+    /// Drop `dir`'s override so it follows the global sort again.
+    ///
+    /// Returns false if there was no override.
+    pub fn clear_sort_override(&mut self, dir: &Path) -> bool {
+        if self.sort_overrides.remove(dir).is_none() {
+            return false;
+        }
+        let sort_by = self.sort_by.clone();
+        let dirs_first = self.dirs_first;
+        self.resort_dir(dir, &sort_by, dirs_first);
+        true
+    }
+
+    /// Re-sort one loaded directory and re-flatten.
+    fn resort_dir(&mut self, dir: &Path, sort_by: &SortBy, dirs_first: bool) {
+        let page_size = self.page_size;
+        if let Some(node) = Self::find_node_mut(&mut self.root, dir) {
+            Self::resort_node(node, sort_by, dirs_first, page_size, self.defer_stats);
+            self.flatten();
+        }
+    }
+
+    /// Fill in deferred stats for loaded nodes; `None` marks a path whose
+    /// stat failed, which stops it being pending. A directory sorted by size
+    /// or mtime is re-sorted once none of its loaded children is pending,
+    /// with the selection and marks following their paths.
+    pub fn apply_stats(&mut self, results: Vec<(PathBuf, Option<FileMeta>)>) {
+        let mut patched: HashMap<PathBuf, Option<LinkTarget>> = HashMap::new();
+        let mut parents: Vec<PathBuf> = Vec::new();
+        for (path, meta) in results {
+            let node = match Self::find_node_mut(&mut self.root, &path) {
+                Some(node) if node.meta.pending => node,
+                _ => continue,
+            };
+            match meta {
+                Some(meta) => node.meta = meta,
+                None => node.meta.pending = false,
+            }
+            if let Some(parent) = path.parent() {
+                if !parents.iter().any(|p| p == parent) {
+                    parents.push(parent.to_path_buf());
+                }
+            }
+            patched.insert(path, node.meta.link.clone());
+        }
+        if patched.is_empty() {
+            return;
+        }
+
+        let mut resorted = false;
+        for dir in parents {
+            let (sort_by, dirs_first) = self.sort_for(&dir);
+            if sort_by == SortBy::Name {
+                continue;
+            }
+            if let Some(node) = Self::find_node_mut(&mut self.root, &dir) {
+                let settled = node
+                    .children
+                    .as_ref()
+                    .is_some_and(|c| c.iter().all(|child| !child.meta.pending));
+                if settled {
+                    Self::sort_children_of(node, &sort_by, dirs_first);
+                    resorted = true;
+                }
+            }
+        }
+
+        if resorted {
+            let selected = self
+                .flat_items
+                .get(self.selected_index)
+                .map(|item| item.path.clone());
+            let marked: Vec<PathBuf> = self
+                .multi_selected
+                .iter()
+                .filter_map(|&i| self.flat_items.get(i))
+                .map(|item| item.path.clone())
+                .collect();
+            if self.is_filtering {
+                self.apply_filter();
+            } else {
+                self.flatten();
+            }
+            if let Some(index) = selected.and_then(|p| self.find_index_by_path(&p)) {
+                self.selected_index = index;
+            }
+            self.multi_selected = marked
+                .iter()
+                .filter_map(|p| self.find_index_by_path(p))
+                .collect();
+        } else {
+            for item in &mut self.flat_items {
+                if let Some(link) = patched.get(&item.path) {
+                    item.stat_pending = false;
+                    item.link = link.clone();
+                }
+            }
+            self.touch();
+        }
+    }
+
+    /// Whether a row in `rows` rows from `scroll` is still waiting for its
+    /// stat inside a directory sorted by size or mtime, so the order on
+    /// screen may still change.
+    pub fn order_is_provisional(&self, scroll: usize, rows: usize) -> bool {
+        self.flat_items
+            .iter()
+            .skip(scroll)
+            .take(rows)
+            .filter(|item| item.stat_pending)
+            .any(|item| {
+                item.path
+                    .parent()
+                    .is_some_and(|dir| self.sort_for(dir).0 != SortBy::Name)
+            })
+    }
+
     /// Public accessor to find a mutable node by path (used by navigate_to_path).
     pub fn find_node_mut_pub<'a>(
         node: &'a mut TreeNode,
@@ -947,96 +1844,122 @@ impl TreeState {
     }
 
     /// Apply inline filter: rebuild flat_items showing only matches + ancestor dirs.
-    /// Case-insensitive substring match on filename.
+    /// Case-insensitive substring match on filename, limited to files of
+    /// `filter_class` when one is set.
     pub fn apply_filter(&mut self) {
-        if self.filter_query.is_empty() {
+        if self.filter_query.is_empty() && self.filter_class.is_none() {
             self.is_filtering = false;
             self.flatten();
             return;
         }
 
         self.is_filtering = true;
-        self.flat_items.clear();
+        self.touch();
         self.multi_selected.clear();
 
         let query_lower = self.filter_query.to_lowercase();
+        let mut pool = std::mem::take(&mut self.flat_items);
+        let mut items = std::mem::take(&mut self.buffers.spare);
+        items.clear();
         Self::flatten_node_filtered(
             &self.root,
-            &mut self.flat_items,
+            &mut items,
+            &mut pool,
+            &mut self.buffers,
             self.show_hidden,
-            true,
-            true,
+            self.max_depth,
             &query_lower,
+            self.filter_class.as_ref(),
         );
+        pool.clear();
+        self.buffers.spare = pool;
+        self.flat_items = items;
 
-        // Clamp selected index
-        if !self.flat_items.is_empty() && self.selected_index >= self.flat_items.len() {
-            self.selected_index = self.flat_items.len() - 1;
+        self.clamp_selection();
+    }
+
+    /// The active filter as shown to the user: `[Images] + 'logo'`,
+    /// `[Images]` or `'logo'`.
+    pub fn filter_label(&self) -> String {
+        match (&self.filter_class, self.filter_query.is_empty()) {
+            (Some(class), true) => format!("[{}]", class.name),
+            (Some(class), false) => format!("[{}] + '{}'", class.name, self.filter_query),
+            (None, _) => format!("'{}'", self.filter_query),
         }
     }
 
-    /// Recursively flatten, but only include nodes whose name matches the filter
-    /// or that are ancestors of matching nodes.
-    /// Returns true if this subtree contains any matches.
+    /// Append the loaded nodes under `root` whose name contains `query`,
+    /// plus their ancestors; the root is always included. With a `class`,
+    /// only files of that class match.
+    ///
+    /// Walks the loaded nodes twice in display order: the first pass marks
+    /// the matches, settled bottom-up so every ancestor of a match is kept,
+    /// and the second emits the kept rows, recycled from `pool`. The marks
+    /// live in `buffers`, so neither pass allocates per node. Children
+    /// below `max_depth` aren't searched and show as a "depth limit
+    /// reached" row.
+    #[allow(clippy::too_many_arguments)]
     fn flatten_node_filtered(
-        node: &TreeNode,
+        root: &TreeNode,
         items: &mut Vec<FlatItem>,
+        pool: &mut Vec<FlatItem>,
+        buffers: &mut FlattenBuffers,
         show_hidden: bool,
-        is_last: bool,
-        is_root: bool,
+        max_depth: usize,
         query: &str,
-    ) -> bool {
-        if !is_root && !show_hidden && node.meta.is_hidden {
-            return false;
-        }
-
-        let name_lower = node.name.to_lowercase();
-        let self_matches = name_lower.contains(query);
-
-        // Check if any child subtree matches
-        let mut child_matches = false;
-        let mut child_items = Vec::new();
-
-        if let Some(children) = &node.children {
-            let visible_children: Vec<&TreeNode> = if show_hidden {
-                children.iter().collect()
-            } else {
-                children.iter().filter(|c| !c.meta.is_hidden).collect()
+        class: Option<&FilterClass>,
+    ) {
+        let now = SystemTime::now();
+        let matches = |node: &TreeNode| {
+            let in_class = match class {
+                Some(class) => {
+                    node.node_type != NodeType::Directory
+                        && class.matches(&node.name, node.meta.modified, now)
+                }
+                None => true,
             };
+            in_class && contains_lowercase(&node.name, query)
+        };
+        let FlattenBuffers {
+            parents,
+            keep,
+            child_matches,
+            ..
+        } = buffers;
+        parents.clear();
+        keep.clear();
+        walk_loaded(root, show_hidden, max_depth, |_, node, parent, _, _| {
+            parents.push(parent);
+            keep.push(matches(node));
+        });
 
-            for (i, child) in visible_children.iter().enumerate() {
-                let is_last_child = i == visible_children.len() - 1;
-                if Self::flatten_node_filtered(
-                    child,
-                    &mut child_items,
-                    show_hidden,
-                    is_last_child,
-                    false,
-                    query,
-                ) {
-                    child_matches = true;
-                }
+        // Descendants follow their ancestors, so walking backwards settles
+        // every child before its parent
+        child_matches.clear();
+        child_matches.resize(keep.len(), false);
+        for i in (0..keep.len()).rev() {
+            keep[i] |= child_matches[i];
+            if let (true, Some(parent)) = (keep[i], parents[i]) {
+                child_matches[parent] = true;
             }
         }
 
-        if self_matches || child_matches || is_root {
-            items.push(FlatItem {
-                name: node.name.clone(),
-                path: node.path.clone(),
-                node_type: node.node_type.clone(),
-                depth: node.depth,
-                is_expanded: node.is_expanded || child_matches,
-                is_last_sibling: is_last,
-                is_hidden: node.meta.is_hidden,
-                load_more_parent: None,
-                load_more_remaining: None,
-                child_count: node.total_child_count,
-            });
-            items.extend(child_items);
-            true
-        } else {
-            false
-        }
+        walk_loaded(
+            root,
+            show_hidden,
+            max_depth,
+            |i, node, _, is_last, capped| {
+                // The root (index 0) is always shown
+                if !keep[i] && i > 0 {
+                    return;
+                }
+                let is_expanded = node.is_expanded || child_matches[i];
+                items.push(FlatItem::recycled(pool, node, is_last, is_expanded));
+                if capped {
+                    items.push(FlatItem::depth_limit(node));
+                }
+            },
+        );
     }
 
     /// Toggle multi-selection of the currently focused item.
@@ -1056,11 +1979,19 @@ impl TreeState {
         } else {
             self.multi_selected.insert(idx);
         }
+        self.touch();
     }
 
     /// Clear all multi-selections.
     pub fn clear_multi_select(&mut self) {
         self.multi_selected.clear();
+        self.touch();
+    }
+
+    /// Mark `flat_items` or `multi_selected` as changed. Callers that edit
+    /// either field directly must call this.
+    pub fn touch(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
     }
 
     /// Find the flat_items index of a node by its path.
@@ -1068,11 +1999,194 @@ impl TreeState {
         self.flat_items.iter().position(|item| item.path == path)
     }
 
+    /// Whether a flat item can be stepped to from the preview: a file, or a
+    /// symlink that doesn't point at a directory.
+    fn is_previewable_file(item: &FlatItem) -> bool {
+        match item.node_type {
+            NodeType::File => true,
+            NodeType::Symlink => !item.path.is_dir(),
+            _ => false,
+        }
+    }
+
+    /// Half-open range of `flat_items` holding the siblings of the item at
+    /// `index` (and their expanded descendants).
+    fn sibling_range(&self, index: usize) -> (usize, usize) {
+        let depth = self.flat_items[index].depth;
+        let mut start = index;
+        while start > 0 && self.flat_items[start - 1].depth >= depth {
+            start -= 1;
+        }
+        let mut end = index + 1;
+        while end < self.flat_items.len() && self.flat_items[end].depth >= depth {
+            end += 1;
+        }
+        (start, end)
+    }
+
+    /// Position of the selected file among its parent's files, as
+    /// (1-based position, total).
+    ///
+    /// In a filtered view both count only the visible matches. Otherwise,
+    /// for a directory with unloaded pages, the total comes from its
+    /// snapshot.
+    pub fn sibling_file_position(&self) -> Option<(usize, usize)> {
+        let index = self.selected_index;
+        let item = self.flat_items.get(index)?;
+        if item.depth == 0 || !Self::is_previewable_file(item) {
+            return None;
+        }
+        let (start, end) = self.sibling_range(index);
+        let depth = item.depth;
+        let mut position = 0;
+        let mut loaded = 0;
+        let mut more_pages = None;
+        for (i, sibling) in self.flat_items[start..end].iter().enumerate() {
+            if sibling.depth != depth {
+                continue;
+            }
+            if sibling.node_type == NodeType::LoadMore {
+                more_pages = sibling.load_more_parent.as_deref();
+            } else if Self::is_previewable_file(sibling) {
+                loaded += 1;
+                if start + i == index {
+                    position = loaded;
+                }
+            }
+        }
+        let total = match more_pages.and_then(|dir| self.snapshot_file_count(dir)) {
+            Some(total) => total.max(loaded),
+            None => loaded,
+        };
+        Some((position, total))
+    }
+
+    /// Number of non-directory entries in `dir`'s snapshot, hidden ones
+    /// only when they're shown.
+    fn snapshot_file_count(&self, dir: &Path) -> Option<usize> {
+        let node = Self::find_node(&self.root, dir)?;
+        let snapshot = node.snapshot.as_ref()?;
+        let count = snapshot
+            .entries
+            .iter()
+            .filter(|e| !e.is_dir)
+            .filter(|e| self.show_hidden || !e.name.to_string_lossy().starts_with('.'))
+            .count();
+        Some(count)
+    }
+
+    /// Find a node by path.
+    fn find_node<'a>(node: &'a TreeNode, target: &Path) -> Option<&'a TreeNode> {
+        let mut node = node;
+        loop {
+            if node.path == target {
+                return Some(node);
+            }
+            node = node
+                .children
+                .as_ref()?
+                .iter()
+                .find(|child| target.starts_with(&child.path))?;
+        }
+    }
+
+    /// Move the selection to the next (or previous) file among the selected
+    /// item's siblings, skipping directories and their expanded contents.
+    ///
+    /// Stepping past the last loaded page of a paginated directory loads the
+    /// next one; wrapping backward loads the rest. Filtered views step only
+    /// through the visible matches. Returns false if the selection didn't
+    /// move.
+    pub fn step_sibling_file(&mut self, forward: bool, wrap: bool) -> bool {
+        let current = match self.flat_items.get(self.selected_index) {
+            Some(item) if item.depth > 0 && item.node_type != NodeType::LoadMore => {
+                item.path.clone()
+            }
+            _ => return false,
+        };
+        let depth = self.flat_items[self.selected_index].depth;
+
+        if forward {
+            if let Some(index) = self.next_sibling_file(&current, depth) {
+                self.selected_index = index;
+                return true;
+            }
+            if wrap {
+                let (start, _) = self.sibling_range(self.selected_index);
+                let first = (start..self.selected_index).find(|&i| {
+                    self.flat_items[i].depth == depth
+                        && Self::is_previewable_file(&self.flat_items[i])
+                });
+                if let Some(index) = first {
+                    self.selected_index = index;
+                    return true;
+                }
+            }
+            return false;
+        }
+
+        let (start, _) = self.sibling_range(self.selected_index);
+        let previous = (start..self.selected_index).rev().find(|&i| {
+            self.flat_items[i].depth == depth && Self::is_previewable_file(&self.flat_items[i])
+        });
+        if let Some(index) = previous {
+            self.selected_index = index;
+            return true;
+        }
+        if !wrap {
+            return false;
+        }
+        // The last file may be on a page that isn't loaded yet
+        if !self.is_filtering {
+            if let Some(parent) = current.parent() {
+                while self.load_next_page(parent) > 0 {}
+            }
+        }
+        let index = match self.find_index_by_path(&current) {
+            Some(index) => index,
+            None => return false,
+        };
+        let (_, end) = self.sibling_range(index);
+        let last = (index + 1..end).rev().find(|&i| {
+            self.flat_items[i].depth == depth && Self::is_previewable_file(&self.flat_items[i])
+        });
+        // Loading pages rebuilt the list, so re-select even if nothing follows
+        self.selected_index = last.unwrap_or(index);
+        last.is_some()
+    }
+
+    /// Index of the next file sibling after `current`, loading further pages
+    /// of its directory when the scan reaches the "Load more..." row.
+    fn next_sibling_file(&mut self, current: &Path, depth: usize) -> Option<usize> {
+        let mut i = self.find_index_by_path(current)? + 1;
+        loop {
+            let item = self.flat_items.get(i)?;
+            if item.depth < depth {
+                return None;
+            }
+            if item.depth > depth {
+                i += 1;
+            } else if item.node_type == NodeType::LoadMore {
+                let parent = item.load_more_parent.clone()?;
+                if self.load_next_page(&parent) == 0 {
+                    return None;
+                }
+                // The new page is sorted in with the loaded ones
+                self.selected_index = self.find_index_by_path(current)?;
+                i = self.selected_index + 1;
+            } else if Self::is_previewable_file(item) {
+                return Some(i);
+            } else {
+                i += 1;
+            }
+        }
+    }
+
     /// Collect all currently expanded directory paths.
     pub fn collect_expanded_paths(&self) -> HashSet<PathBuf> {
         self.flat_items
             .iter()
-            .filter(|item| item.node_type == NodeType::Directory && item.is_expanded)
+            .filter(|item| item.is_expandable() && item.is_expanded)
             .map(|item| item.path.clone())
             .collect()
     }
@@ -1080,18 +2194,40 @@ impl TreeState {
     /// Re-expand directories from a saved set of expanded paths.
     ///
     /// After loading children, sorting is applied using current sort settings.
+    #[allow(dead_code)]
     pub fn restore_expanded(&mut self, expanded: &HashSet<PathBuf>) {
-        let sort_by = self.sort_by.clone();
-        let dirs_first = self.dirs_first;
-        let page_size = self.page_size;
         for path in Self::expanded_paths_in_restore_order(expanded) {
-            if let Some(node) = Self::find_node_mut(&mut self.root, path) {
-                if node.node_type == NodeType::Directory && !node.is_expanded {
-                    let _ = node.load_children_paged_with_sort(page_size, &sort_by, dirs_first);
-                    Self::sort_children_of(node, &sort_by, dirs_first);
-                    node.is_expanded = true;
-                }
+            self.restore_path(path);
+        }
+    }
+
+    /// Re-expand the directory at `path` if it is loaded and collapsed.
+    /// Returns how many children were loaded.
+    fn restore_path(&mut self, path: &Path) -> usize {
+        let page_size = self.page_size;
+        let defer_stats = self.defer_stats;
+        let (sort_by, dirs_first) = self.sort_for(path);
+        let is_link = Self::find_node_mut(&mut self.root, path)
+            .is_some_and(|node| node.node_type == NodeType::Symlink);
+        let loops = is_link && self.link_loops(path).is_some();
+        match Self::find_node_mut(&mut self.root, path) {
+            Some(node)
+                if node.is_expandable()
+                    && !loops
+                    && !node.is_expanded
+                    && node.depth < self.max_depth =>
+            {
+                let _ = node.load_children_paged_with_sort(
+                    page_size,
+                    &sort_by,
+                    dirs_first,
+                    defer_stats,
+                );
+                Self::sort_children_of(node, &sort_by, dirs_first);
+                node.is_expanded = true;
+                node.children.as_ref().map_or(0, Vec::len)
             }
+            _ => 0,
         }
     }
 
@@ -1128,6 +2264,525 @@ impl TreeState {
     }
 }
 
+/// A whole-tree transform that runs a [`Budget`] at a time, so the work
+/// queue can spread it over several frames. Built by
+/// [`TreeState::sort_step`], [`TreeState::restore_step`],
+/// [`TreeStep::flatten`] and [`TreeStep::filter`].
+///
+/// Steps hold their place as child indices and paths rather than node
+/// references, so the tree stays free to change between slices; a step
+/// whose tree changed under it starts over (see [`TreeStep::restart`]).
+#[derive(Debug)]
+pub enum TreeStep {
+    /// Re-sort the loaded directories.
+    Sort(SortCursor),
+    /// Re-expand saved directories, parents first.
+    Restore(RestoreCursor),
+    /// Rebuild `flat_items` from the expanded nodes.
+    Flatten(FlattenCursor),
+    /// Rebuild `flat_items` from the filter matches.
+    Filter(FilterCursor),
+}
+
+/// Where a [`TreeStep::Sort`] is: directories still to sort, as child
+/// index paths from the root.
+#[derive(Debug)]
+pub struct SortCursor {
+    skip_overridden: bool,
+    pending: Vec<Vec<usize>>,
+    started: bool,
+}
+
+/// Where a [`TreeStep::Restore`] is: the paths in restore order and the
+/// next one to expand.
+#[derive(Debug)]
+pub struct RestoreCursor {
+    paths: Vec<PathBuf>,
+    next: usize,
+}
+
+/// An open directory of a flatten or filter walk.
+#[derive(Debug)]
+struct WalkFrame {
+    /// Index of the directory in its parent's children (0 for the root).
+    index: usize,
+    /// Next visible child to visit, or the child count once all are.
+    next: usize,
+}
+
+/// Where a [`TreeStep::Flatten`] is. Rows are built on the side and
+/// replace `flat_items` at the end, so the view never shows half a tree.
+#[derive(Debug, Default)]
+pub struct FlattenCursor {
+    frames: Vec<WalkFrame>,
+    items: Vec<FlatItem>,
+    started: bool,
+}
+
+/// An open directory of a filter walk, with the rows of its matches so
+/// far (its own row first).
+#[derive(Debug)]
+struct FilterFrame {
+    walk: WalkFrame,
+    rows: Vec<FlatItem>,
+    child_matches: bool,
+}
+
+/// Where a [`TreeStep::Filter`] is. A directory's rows wait until its
+/// subtree has been searched, since only then is it known whether it is
+/// kept; each finished top-level subtree goes straight into `flat_items`,
+/// so results show up as they are found.
+#[derive(Debug)]
+pub struct FilterCursor {
+    query: String,
+    class: Option<FilterClass>,
+    now: SystemTime,
+    frames: Vec<FilterFrame>,
+    started: bool,
+}
+
+impl Default for FilterCursor {
+    fn default() -> Self {
+        Self {
+            query: String::new(),
+            class: None,
+            now: SystemTime::UNIX_EPOCH,
+            frames: Vec::new(),
+            started: false,
+        }
+    }
+}
+
+/// Order of two siblings under `sort_by`, directories first with
+/// `dirs_first`.
+fn compare_nodes(
+    a: &TreeNode,
+    b: &TreeNode,
+    sort_by: &SortBy,
+    dirs_first: bool,
+) -> std::cmp::Ordering {
+    let mut cmp = std::cmp::Ordering::Equal;
+
+    if dirs_first {
+        cmp = matches!(b.node_type, NodeType::Directory)
+            .cmp(&matches!(a.node_type, NodeType::Directory));
+    }
+
+    cmp.then_with(|| match sort_by {
+        SortBy::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+        SortBy::Size => b.meta.size.cmp(&a.meta.size),
+        SortBy::Modified => b.meta.modified.cmp(&a.meta.modified),
+    })
+}
+
+/// Visit `root` and every loaded node below it in display order, collapsed
+/// or not, with its index in that order, its parent's index, whether it is
+/// the last shown sibling and whether `max_depth` cuts off its children.
+fn walk_loaded<'a>(
+    root: &'a TreeNode,
+    show_hidden: bool,
+    max_depth: usize,
+    mut visit: impl FnMut(usize, &'a TreeNode, Option<usize>, bool, bool),
+) {
+    let shown = |c: &TreeNode| show_hidden || !c.is_hidden();
+    let mut stack = vec![(root, None, true)];
+    let mut index = 0;
+    while let Some((node, parent, is_last)) = stack.pop() {
+        let children = node.children.as_deref().unwrap_or_default();
+        let last_shown = children.iter().rposition(shown);
+        let capped = node.depth >= max_depth && last_shown.is_some();
+        visit(index, node, parent, is_last, capped);
+        if !capped {
+            for (i, child) in children.iter().enumerate().rev() {
+                if shown(child) {
+                    stack.push((child, Some(index), Some(i) == last_shown));
+                }
+            }
+        }
+        index += 1;
+    }
+}
+
+/// Whether `name` contains `query`, which is lowercase, ignoring case.
+/// Only non-ASCII names are lowercased into a new string.
+fn contains_lowercase(name: &str, query: &str) -> bool {
+    if query.is_empty() {
+        return true;
+    }
+    if name.is_ascii() {
+        return name
+            .as_bytes()
+            .windows(query.len())
+            .any(|window| window.eq_ignore_ascii_case(query.as_bytes()));
+    }
+    name.to_lowercase().contains(query)
+}
+
+/// Index of the first child from `from` on that is shown.
+fn next_visible(children: &[TreeNode], from: usize, show_hidden: bool) -> usize {
+    children[from.min(children.len())..]
+        .iter()
+        .position(|c| show_hidden || !c.is_hidden())
+        .map_or(children.len(), |i| from + i)
+}
+
+/// The open directories of a walk, from the root down; `None` if the tree
+/// no longer has them.
+fn walk_chain(root: &TreeNode, indices: impl Iterator<Item = usize>) -> Option<Vec<&TreeNode>> {
+    let mut chain: Vec<&TreeNode> = Vec::new();
+    for index in indices {
+        let node = match chain.last() {
+            Some(parent) => parent.children.as_ref()?.get(index)?,
+            None => root,
+        };
+        chain.push(node);
+    }
+    Some(chain)
+}
+
+impl TreeStep {
+    pub fn flatten() -> Self {
+        TreeStep::Flatten(FlattenCursor::default())
+    }
+
+    /// Re-filter with the query and class set when the step starts; with
+    /// neither, it turns filtering off and flattens instead.
+    pub fn filter() -> Self {
+        TreeStep::Filter(FilterCursor::default())
+    }
+
+    /// Whether the step replaces `flat_items` (so a newer one makes it
+    /// pointless), as opposed to changing the tree itself.
+    pub fn rebuilds_view(&self) -> bool {
+        matches!(self, TreeStep::Flatten(_) | TreeStep::Filter(_))
+    }
+
+    /// Start over on a tree that changed since the last slice.
+    pub fn restart(&mut self) {
+        match self {
+            TreeStep::Sort(cursor) => cursor.started = false,
+            // Expanding is keyed by path and skips what is expanded already
+            TreeStep::Restore(_) => {}
+            TreeStep::Flatten(cursor) => cursor.started = false,
+            TreeStep::Filter(cursor) => cursor.started = false,
+        }
+    }
+
+    /// `(done, total)` for a step that knows its size.
+    pub fn progress(&self) -> Option<(usize, usize)> {
+        match self {
+            TreeStep::Restore(cursor) => Some((cursor.next, cursor.paths.len())),
+            _ => None,
+        }
+    }
+
+    /// Work until the step is done (true) or `budget` runs out (false).
+    pub fn run(&mut self, tree: &mut TreeState, budget: &mut Budget) -> bool {
+        match self {
+            TreeStep::Sort(cursor) => cursor.run(tree, budget),
+            TreeStep::Restore(cursor) => cursor.run(tree, budget),
+            TreeStep::Flatten(cursor) => cursor.run(tree, budget),
+            TreeStep::Filter(cursor)
+                if !cursor.started
+                    && tree.filter_query.is_empty()
+                    && tree.filter_class.is_none() =>
+            {
+                tree.is_filtering = false;
+                *self = TreeStep::flatten();
+                self.run(tree, budget)
+            }
+            TreeStep::Filter(cursor) => cursor.run(tree, budget),
+        }
+    }
+}
+
+impl SortCursor {
+    fn run(&mut self, tree: &mut TreeState, budget: &mut Budget) -> bool {
+        if !self.started {
+            self.pending = vec![Vec::new()];
+            self.started = true;
+        }
+        let default = (tree.sort_by.clone(), tree.dirs_first);
+        let TreeState {
+            root,
+            sort_overrides,
+            page_size,
+            defer_stats,
+            ..
+        } = tree;
+        while let Some(path) = self.pending.pop() {
+            let mut node = Some(&mut *root);
+            for &i in &path {
+                node = node
+                    .and_then(|n| n.children.as_mut())
+                    .and_then(|c| c.get_mut(i));
+            }
+            // Gone since it was queued
+            let Some(node) = node else { continue };
+            TreeState::resort_in_subtree(
+                node,
+                &default,
+                sort_overrides,
+                self.skip_overridden,
+                *page_size,
+                *defer_stats,
+            );
+            let count = node.children.as_ref().map_or(0, Vec::len);
+            self.pending.extend((0..count).map(|i| {
+                let mut child = path.clone();
+                child.push(i);
+                child
+            }));
+            if !budget.spend(count.max(1)) {
+                return self.pending.is_empty();
+            }
+        }
+        true
+    }
+}
+
+impl RestoreCursor {
+    fn run(&mut self, tree: &mut TreeState, budget: &mut Budget) -> bool {
+        while let Some(path) = self.paths.get(self.next) {
+            let loaded = tree.restore_path(path);
+            self.next += 1;
+            if !budget.spend(loaded.max(1)) {
+                break;
+            }
+        }
+        self.next >= self.paths.len()
+    }
+}
+
+impl FlattenCursor {
+    /// Add `item` unless the row cap is reached; false if it was.
+    fn push(&mut self, item: FlatItem) -> bool {
+        if self.items.len() >= MAX_FLAT_ITEMS {
+            return false;
+        }
+        self.items.push(item);
+        true
+    }
+
+    /// Open `node` (child `index` of its parent) if its children are
+    /// shown, or add the depth limit row in their place.
+    fn open(&mut self, node: &TreeNode, index: usize, show_hidden: bool, max_depth: usize) {
+        let children = match &node.children {
+            Some(children) if node.is_expanded => children,
+            _ => return,
+        };
+        let next = next_visible(children, 0, show_hidden);
+        if node.depth >= max_depth {
+            if next < children.len() || node.has_more_children {
+                self.push(FlatItem::depth_limit(node));
+            }
+            return;
+        }
+        self.frames.push(WalkFrame { index, next });
+    }
+
+    fn run(&mut self, tree: &mut TreeState, budget: &mut Budget) -> bool {
+        let (show_hidden, max_depth) = (tree.show_hidden, tree.max_depth);
+        if !self.started {
+            self.started = true;
+            self.frames.clear();
+            self.items = Vec::with_capacity(tree.flat_items.len().min(10_000));
+            self.push(FlatItem::from_node(&tree.root, true, tree.root.is_expanded));
+            self.open(&tree.root, 0, show_hidden, max_depth);
+        }
+        let Some(mut chain) = walk_chain(&tree.root, self.frames.iter().map(|f| f.index)) else {
+            self.started = false;
+            return false;
+        };
+        while let (Some(frame), Some(&node)) = (self.frames.last_mut(), chain.last()) {
+            if self.items.len() >= MAX_FLAT_ITEMS {
+                break;
+            }
+            let children = node.children.as_deref().unwrap_or(&[]);
+            if frame.next < children.len() {
+                let i = frame.next;
+                frame.next = next_visible(children, i + 1, show_hidden);
+                let is_last = frame.next == children.len() && !node.has_more_children;
+                let child = &children[i];
+                self.push(FlatItem::from_node(child, is_last, child.is_expanded));
+                let open = self.frames.len();
+                self.open(child, i, show_hidden, max_depth);
+                if self.frames.len() > open {
+                    chain.push(child);
+                }
+            } else {
+                if node.has_more_children {
+                    self.push(FlatItem::load_more(node));
+                }
+                self.frames.pop();
+                chain.pop();
+            }
+            if !budget.spend(1) {
+                return false;
+            }
+        }
+        tree.touch();
+        tree.flat_items = std::mem::take(&mut self.items);
+        tree.multi_selected.clear();
+        tree.clamp_selection();
+        true
+    }
+}
+
+impl FilterCursor {
+    fn matches(&self, node: &TreeNode) -> bool {
+        let in_class = match &self.class {
+            Some(class) => {
+                node.node_type != NodeType::Directory
+                    && class.matches(&node.name, node.meta.modified, self.now)
+            }
+            None => true,
+        };
+        in_class && node.name.to_lowercase().contains(&self.query)
+    }
+
+    /// Hand the rows of a kept subtree to the open directory above it;
+    /// below the root that is `flat_items` itself.
+    fn emit(&mut self, flat_items: &mut Vec<FlatItem>, mut rows: Vec<FlatItem>) {
+        let below_root = self.frames.len() == 1;
+        let Some(parent) = self.frames.last_mut() else {
+            return;
+        };
+        parent.child_matches = true;
+        if below_root {
+            flat_items.append(&mut rows);
+        } else {
+            parent.rows.append(&mut rows);
+        }
+    }
+
+    fn run(&mut self, tree: &mut TreeState, budget: &mut Budget) -> bool {
+        let (show_hidden, max_depth) = (tree.show_hidden, tree.max_depth);
+        if !self.started {
+            self.started = true;
+            self.query = tree.filter_query.to_lowercase();
+            self.class = tree.filter_class.clone();
+            self.now = SystemTime::now();
+            self.frames.clear();
+            tree.is_filtering = true;
+            tree.touch();
+            tree.flat_items.clear();
+            tree.multi_selected.clear();
+
+            // The root is always shown
+            let root = &tree.root;
+            let children = root.children.as_deref().unwrap_or(&[]);
+            let next = next_visible(children, 0, show_hidden);
+            let mut rows = vec![FlatItem::from_node(root, true, root.is_expanded)];
+            if next < children.len() {
+                if root.depth >= max_depth {
+                    rows.push(FlatItem::depth_limit(root));
+                } else {
+                    self.frames.push(FilterFrame {
+                        walk: WalkFrame { index: 0, next },
+                        rows: Vec::new(),
+                        child_matches: false,
+                    });
+                }
+            }
+            tree.flat_items = rows;
+        }
+        let TreeState {
+            root, flat_items, ..
+        } = &mut *tree;
+        let Some(mut chain) = walk_chain(root, self.frames.iter().map(|f| f.walk.index)) else {
+            self.started = false;
+            return false;
+        };
+        let mut done = self.frames.is_empty();
+        loop {
+            let depth = self.frames.len();
+            let (Some(frame), Some(&node)) = (self.frames.last_mut(), chain.last()) else {
+                break;
+            };
+            let children = node.children.as_deref().unwrap_or(&[]);
+            if frame.walk.next < children.len() {
+                let i = frame.walk.next;
+                frame.walk.next = next_visible(children, i + 1, show_hidden);
+                let is_last = frame.walk.next == children.len();
+                let child = &children[i];
+                let grandchildren = child.children.as_deref().unwrap_or(&[]);
+                let next = next_visible(grandchildren, 0, show_hidden);
+                let capped = child.depth >= max_depth && next < grandchildren.len();
+                if next < grandchildren.len() && !capped {
+                    // Kept or not once its subtree is searched
+                    self.frames.push(FilterFrame {
+                        walk: WalkFrame { index: i, next },
+                        rows: vec![FlatItem::from_node(child, is_last, child.is_expanded)],
+                        child_matches: false,
+                    });
+                    chain.push(child);
+                } else if self.matches(child) {
+                    let mut rows = vec![FlatItem::from_node(child, is_last, child.is_expanded)];
+                    if capped {
+                        rows.push(FlatItem::depth_limit(child));
+                    }
+                    self.emit(flat_items, rows);
+                }
+            } else if depth == 1 {
+                let child_matches = frame.child_matches;
+                if let Some(root) = flat_items.first_mut() {
+                    root.is_expanded |= child_matches;
+                }
+                self.frames.clear();
+                done = true;
+                break;
+            } else {
+                let Some(mut frame) = self.frames.pop() else {
+                    break;
+                };
+                chain.pop();
+                if frame.child_matches || self.matches(node) {
+                    frame.rows[0].is_expanded |= frame.child_matches;
+                    self.emit(flat_items, frame.rows);
+                }
+            }
+            if !budget.spend(1) {
+                break;
+            }
+        }
+        // Rows found in this slice are shown now
+        tree.touch();
+        tree.clamp_selection();
+        done
+    }
+}
+
+impl TreeState {
+    /// Sort every loaded directory a slice at a time; with
+    /// `skip_overridden`, directories with an override keep their order.
+    pub fn sort_step(skip_overridden: bool) -> TreeStep {
+        TreeStep::Sort(SortCursor {
+            skip_overridden,
+            pending: Vec::new(),
+            started: false,
+        })
+    }
+
+    /// Re-expand `expanded` a directory at a time, like `restore_expanded`.
+    pub fn restore_step(expanded: &HashSet<PathBuf>) -> TreeStep {
+        TreeStep::Restore(RestoreCursor {
+            paths: Self::expanded_paths_in_restore_order(expanded)
+                .into_iter()
+                .cloned()
+                .collect(),
+            next: 0,
+        })
+    }
+
+    /// Keep the selection inside `flat_items`.
+    fn clamp_selection(&mut self) {
+        if !self.flat_items.is_empty() && self.selected_index >= self.flat_items.len() {
+            self.selected_index = self.flat_items.len() - 1;
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1174,6 +2829,60 @@ mod tests {
         assert!(node.meta.is_hidden);
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn symlink_nodes_know_their_target() {
+        let dir = setup_test_dir();
+        std::os::unix::fs::symlink("alpha", dir.path().join("to_alpha")).unwrap();
+        std::os::unix::fs::symlink("file_a.txt", dir.path().join("to_file")).unwrap();
+        std::os::unix::fs::symlink("gone", dir.path().join("dangling")).unwrap();
+        let link = |name: &str| {
+            let node = TreeNode::new(&dir.path().join(name), 1).unwrap();
+            assert_eq!(node.node_type, NodeType::Symlink);
+            node.meta.link.clone().unwrap()
+        };
+
+        let to_alpha = link("to_alpha");
+        assert_eq!(to_alpha.target, PathBuf::from("alpha"));
+        assert!(to_alpha.is_dir && !to_alpha.broken);
+        let to_file = link("to_file");
+        assert!(!to_file.is_dir && !to_file.broken);
+        let dangling = link("dangling");
+        assert_eq!(dangling.target, PathBuf::from("gone"));
+        assert!(!dangling.is_dir && dangling.broken);
+        let alpha = TreeNode::new(&dir.path().join("alpha"), 1).unwrap();
+        assert!(alpha.meta.link.is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn directory_symlinks_expand_unless_they_loop() {
+        let dir = setup_test_dir();
+        std::os::unix::fs::symlink("alpha", dir.path().join("to_alpha")).unwrap();
+        std::os::unix::fs::symlink("..", dir.path().join("alpha/up")).unwrap();
+        let mut state = TreeState::new(dir.path()).unwrap();
+
+        let to_alpha = dir.path().join("to_alpha");
+        state.selected_index = state.find_index_by_path(&to_alpha).unwrap();
+        assert!(state.flat_items[state.selected_index].is_expandable());
+        state.expand_selected();
+        assert!(state
+            .find_index_by_path(&to_alpha.join("inner.txt"))
+            .is_some());
+        assert_eq!(state.link_loops(&to_alpha), None);
+
+        // alpha/up leads back to the root
+        let alpha = dir.path().join("alpha");
+        state.selected_index = state.find_index_by_path(&alpha).unwrap();
+        state.expand_selected();
+        let up = alpha.join("up");
+        assert_eq!(state.link_loops(&up), Some(dir.path().to_path_buf()));
+        state.selected_index = state.find_index_by_path(&up).unwrap();
+        state.expand_selected();
+        assert!(!state.flat_items[state.selected_index].is_expanded);
+        assert!(state.find_index_by_path(&up.join("alpha")).is_none());
+    }
+
     #[test]
     fn tree_state_sorts_dirs_first_by_default() {
         let dir = setup_test_dir();
@@ -1198,6 +2907,134 @@ mod tests {
         assert_eq!(state.sort_by, SortBy::Name);
     }
 
+    /// Names of the root's loaded children, in display order.
+    fn root_child_names(state: &TreeState) -> Vec<String> {
+        state
+            .flat_items
+            .iter()
+            .filter(|i| i.depth == 1 && i.node_type != NodeType::LoadMore)
+            .map(|i| i.name.clone())
+            .collect()
+    }
+
+    #[test]
+    fn global_sort_change_keeps_overridden_dirs() {
+        let dir = setup_test_dir();
+        std::fs::write(dir.path().join("file_a.txt"), "small").unwrap();
+        std::fs::write(
+            dir.path().join("file_b.rs"),
+            "this is a much larger file content",
+        )
+        .unwrap();
+        let mut state = TreeState::new(dir.path()).unwrap();
+        let alpha = dir.path().join("alpha");
+        state.selected_index = state.find_index_by_path(&alpha).unwrap();
+        state.expand_selected();
+
+        state.set_sort_override(dir.path(), SortBy::Size, true);
+        let files: Vec<String> = root_child_names(&state)
+            .into_iter()
+            .filter(|n| n.starts_with("file_"))
+            .collect();
+        assert_eq!(files, vec!["file_b.rs", "file_a.txt"]);
+
+        // Global: name order with dirs mixed in
+        state.cycle_sort();
+        state.cycle_sort();
+        state.cycle_sort();
+        state.toggle_dirs_first();
+        assert_eq!(state.sort_by, SortBy::Name);
+        assert!(!state.dirs_first);
+
+        // The root keeps its override...
+        let names = root_child_names(&state);
+        assert_eq!(&names[2..], &["file_b.rs", "file_a.txt"]);
+        assert_eq!(state.sort_for(dir.path()), (SortBy::Size, true));
+        // ...while alpha follows the global sort: inner.txt before nested/
+        let alpha_children: Vec<&str> = state
+            .flat_items
+            .iter()
+            .filter(|i| i.depth == 2)
+            .map(|i| i.name.as_str())
+            .collect();
+        assert_eq!(alpha_children, vec!["inner.txt", "nested"]);
+
+        // Clearing hands the root back to the global sort
+        assert!(state.clear_sort_override(dir.path()));
+        assert!(!state.clear_sort_override(dir.path()));
+        assert_eq!(root_child_names(&state)[0], "alpha");
+        assert_eq!(state.sort_for(dir.path()), (SortBy::Name, false));
+    }
+
+    #[test]
+    fn evicted_sort_override_reverts_to_global() {
+        let dir = setup_test_dir();
+        std::fs::write(dir.path().join("file_a.txt"), "small").unwrap();
+        std::fs::write(
+            dir.path().join("file_b.rs"),
+            "this is a much larger file content",
+        )
+        .unwrap();
+        let mut state = TreeState::new(dir.path()).unwrap();
+        state.sort_overrides.set_capacity(2);
+        let (alpha, beta) = (dir.path().join("alpha"), dir.path().join("beta"));
+
+        state.set_sort_override(dir.path(), SortBy::Size, true);
+        state.set_sort_override(&alpha, SortBy::Modified, true);
+        assert_eq!(root_child_names(&state)[2..], ["file_b.rs", "file_a.txt"]);
+
+        // At capacity: the root's override is the oldest and goes first
+        state.set_sort_override(&beta, SortBy::Size, false);
+        assert_eq!(state.sort_overrides.len(), 2);
+        assert_eq!(state.sort_overrides.stats().evictions, 1);
+        assert_eq!(state.sort_for(dir.path()), (SortBy::Name, true));
+        assert_eq!(state.sort_for(&alpha), (SortBy::Modified, true));
+        // The root is re-sorted with the global sort right away
+        assert_eq!(root_child_names(&state)[2..], ["file_a.txt", "file_b.rs"]);
+    }
+
+    #[test]
+    fn expanding_a_directory_keeps_its_override() {
+        let dir = setup_test_dir();
+        let mut state = TreeState::new(dir.path()).unwrap();
+        state.sort_overrides.set_capacity(2);
+        let (alpha, beta) = (dir.path().join("alpha"), dir.path().join("beta"));
+
+        state.set_sort_override(&alpha, SortBy::Size, true);
+        state.set_sort_override(dir.path(), SortBy::Size, true);
+        state.selected_index = state.find_index_by_path(&alpha).unwrap();
+        state.expand_selected();
+
+        // alpha was used more recently than the root, so the root goes
+        state.set_sort_override(&beta, SortBy::Size, true);
+        assert_eq!(state.sort_for(&alpha), (SortBy::Size, true));
+        assert_eq!(state.sort_for(dir.path()), (SortBy::Name, true));
+    }
+
+    #[test]
+    fn pagination_honors_sort_override() {
+        let dir = TempDir::new().unwrap();
+        for i in 0..6 {
+            File::create(dir.path().join(format!("a{}", i))).unwrap();
+        }
+        for i in 0..4 {
+            fs::create_dir(dir.path().join(format!("z{}", i))).unwrap();
+        }
+        let mut state = TreeState::with_page_size(dir.path(), 5).unwrap();
+        state.flatten();
+        // Global dirs-first: the first page leads with the directories
+        assert_eq!(root_child_names(&state), vec!["z0", "z1", "z2", "z3", "a0"]);
+
+        state.set_sort_override(dir.path(), SortBy::Name, false);
+        assert_eq!(root_child_names(&state), vec!["a0", "a1", "a2", "a3", "a4"]);
+
+        assert_eq!(state.load_next_page(dir.path()), 5);
+        assert_eq!(
+            root_child_names(&state),
+            vec!["a0", "a1", "a2", "a3", "a4", "a5", "z0", "z1", "z2", "z3"]
+        );
+    }
+
     #[test]
     fn toggle_dirs_first() {
         let dir = setup_test_dir();
@@ -1264,6 +3101,23 @@ mod tests {
         assert_eq!(state.flat_items.len(), 6);
     }
 
+    #[test]
+    fn loaded_items_include_collapsed_children() {
+        let dir = setup_test_dir();
+        let mut state = TreeState::new(dir.path()).unwrap();
+        state.selected_index = 1; // alpha
+        state.expand_selected();
+        state.collapse_selected();
+        assert_eq!(state.flat_items.len(), 5);
+
+        let loaded = state.loaded_items();
+        // Root + alpha + (inner.txt, nested) + beta + file_a.txt + file_b.rs
+        assert_eq!(loaded.len(), 7);
+        assert!(loaded
+            .iter()
+            .any(|item| item.name == "inner.txt" && item.depth == 2));
+    }
+
     #[test]
     fn is_last_sibling_correctness() {
         let dir = setup_test_dir();
@@ -1283,6 +3137,46 @@ mod tests {
         assert_eq!(state.flat_items.len(), original_count);
     }
 
+    #[test]
+    fn gitignored_entries_hide_like_dotfiles() {
+        let dir = setup_test_dir();
+        fs::create_dir(dir.path().join(".git")).unwrap();
+        fs::write(dir.path().join(".gitignore"), "beta/\n*.rs\n").unwrap();
+        let mut state = TreeState::new(dir.path()).unwrap();
+        let names = |state: &TreeState| -> Vec<String> {
+            state.flat_items.iter().map(|i| i.name.clone()).collect()
+        };
+        assert!(names(&state).contains(&"beta".to_string()));
+
+        state.set_respect_gitignore(true);
+        let shown = names(&state);
+        assert!(!shown.contains(&"beta".to_string()));
+        assert!(!shown.contains(&"file_b.rs".to_string()));
+        assert!(shown.contains(&"file_a.txt".to_string()));
+
+        // Children loaded later are marked too
+        state.selected_index = state
+            .flat_items
+            .iter()
+            .position(|i| i.name == "alpha")
+            .unwrap();
+        state.expand_selected();
+        fs::write(dir.path().join("alpha/inner.rs"), "").unwrap();
+        state.reload_dir(&dir.path().join("alpha"));
+        assert!(!names(&state).contains(&"inner.rs".to_string()));
+
+        // With hidden files shown, they come back marked as ignored
+        state.toggle_hidden();
+        let beta = state.flat_items.iter().find(|i| i.name == "beta").unwrap();
+        assert!(beta.is_ignored);
+        assert!(!beta.is_hidden);
+        let git = state.flat_items.iter().find(|i| i.name == ".git").unwrap();
+        assert!(git.is_ignored);
+
+        state.set_respect_gitignore(false);
+        assert!(state.flat_items.iter().all(|i| !i.is_ignored));
+    }
+
     #[test]
     fn multi_select_toggle_adds_index() {
         let dir = setup_test_dir();
@@ -1423,6 +3317,74 @@ mod tests {
         assert!(state.multi_selected.is_empty());
     }
 
+    fn class(name: &str, exts: &[&str]) -> FilterClass {
+        let exts: Vec<String> = exts.iter().map(|e| e.to_string()).collect();
+        FilterClass::extensions(name, &exts)
+    }
+
+    #[test]
+    fn filter_class_keeps_matching_files_and_ancestors() {
+        let dir = setup_test_dir();
+        let mut state = TreeState::new(dir.path()).unwrap();
+        state.selected_index = 1; // alpha
+        state.expand_selected();
+        state.filter_class = Some(class("Docs", &["txt"]));
+        state.apply_filter();
+        assert!(state.is_filtering);
+        let names: Vec<&str> = state.flat_items.iter().map(|i| i.name.as_str()).collect();
+        assert!(names.contains(&"file_a.txt"));
+        assert!(names.contains(&"alpha"));
+        assert!(names.contains(&"inner.txt"));
+        assert!(!names.contains(&"file_b.rs"));
+        // Directories never match a class on their own
+        assert!(!names.contains(&"beta"));
+        assert_eq!(state.filter_label(), "[Docs]");
+    }
+
+    #[test]
+    fn filter_class_composes_with_query() {
+        let dir = setup_test_dir();
+        let mut state = TreeState::new(dir.path()).unwrap();
+        state.selected_index = 1; // alpha
+        state.expand_selected();
+        state.filter_class = Some(class("Docs", &["txt"]));
+        state.filter_query = "inner".to_string();
+        state.apply_filter();
+        let names: Vec<&str> = state.flat_items.iter().map(|i| i.name.as_str()).collect();
+        assert!(names.contains(&"inner.txt"));
+        assert!(!names.contains(&"file_a.txt"));
+        assert_eq!(state.filter_label(), "[Docs] + 'inner'");
+
+        // A name that matches the query but not the class stays hidden
+        state.filter_query = "file_b".to_string();
+        state.apply_filter();
+        assert_eq!(state.flat_items.len(), 1); // root only
+    }
+
+    #[test]
+    fn modified_class_uses_node_mtime() {
+        use crate::fs::filter_class::Matcher;
+        use std::time::Duration;
+
+        let dir = setup_test_dir();
+        let old = SystemTime::now() - Duration::from_secs(3 * 86_400);
+        File::options()
+            .write(true)
+            .open(dir.path().join("file_b.rs"))
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+        let mut state = TreeState::new(dir.path()).unwrap();
+        state.filter_class = Some(FilterClass {
+            name: "Today".to_string(),
+            matcher: Matcher::ModifiedWithin(Duration::from_secs(86_400)),
+        });
+        state.apply_filter();
+        let names: Vec<&str> = state.flat_items.iter().map(|i| i.name.as_str()).collect();
+        assert!(names.contains(&"file_a.txt"));
+        assert!(!names.contains(&"file_b.rs"));
+    }
+
     #[test]
     fn find_node_mut_pub_finds_node() {
         let dir = setup_test_dir();
@@ -1586,6 +3548,89 @@ mod tests {
         dir
     }
 
+    #[test]
+    fn step_sibling_file_in_paginated_dir() {
+        let dir = setup_large_dir(12);
+        let mut state = TreeState::with_page_size(dir.path(), 5).unwrap();
+        state.flatten();
+        let last_loaded = dir.path().join("file_00004.txt");
+        state.selected_index = state.find_index_by_path(&last_loaded).unwrap();
+        // Total counts the unloaded pages too
+        assert_eq!(state.sibling_file_position(), Some((5, 12)));
+
+        // Stepping past the loaded boundary loads the next page
+        assert!(state.step_sibling_file(true, false));
+        assert_eq!(
+            state.flat_items[state.selected_index].name,
+            "file_00005.txt"
+        );
+        assert_eq!(state.sibling_file_position(), Some((6, 12)));
+
+        // Wrapping backward from the first file loads the rest
+        state.selected_index = state
+            .find_index_by_path(&dir.path().join("file_00000.txt"))
+            .unwrap();
+        assert!(!state.step_sibling_file(false, false));
+        assert!(state.step_sibling_file(false, true));
+        assert_eq!(
+            state.flat_items[state.selected_index].name,
+            "file_00011.txt"
+        );
+        assert_eq!(state.sibling_file_position(), Some((12, 12)));
+    }
+
+    #[test]
+    fn step_sibling_file_skips_dirs_and_their_contents() {
+        let dir = setup_test_dir();
+        let mut state = TreeState::new(dir.path()).unwrap();
+        state.selected_index = state.find_index_by_path(&dir.path().join("alpha")).unwrap();
+        state.expand_selected();
+        let file_a = state
+            .find_index_by_path(&dir.path().join("file_a.txt"))
+            .unwrap();
+        let file_b = state
+            .find_index_by_path(&dir.path().join("file_b.rs"))
+            .unwrap();
+
+        // From a directory, alpha/inner.txt is not a sibling
+        assert!(state.step_sibling_file(true, false));
+        assert_eq!(state.selected_index, file_a);
+        assert_eq!(state.sibling_file_position(), Some((1, 2)));
+        assert!(!state.step_sibling_file(false, false));
+        assert!(state.step_sibling_file(false, true));
+        assert_eq!(state.selected_index, file_b);
+        assert!(!state.step_sibling_file(true, false));
+        assert!(state.step_sibling_file(true, true));
+        assert_eq!(state.selected_index, file_a);
+
+        // Directories have no file position
+        state.selected_index = 0;
+        assert_eq!(state.sibling_file_position(), None);
+        assert!(!state.step_sibling_file(true, true));
+    }
+
+    #[test]
+    fn step_sibling_file_in_filtered_view() {
+        let dir = setup_test_dir();
+        File::create(dir.path().join("notes.md")).unwrap();
+        File::create(dir.path().join("z.rs")).unwrap();
+        let mut state = TreeState::new(dir.path()).unwrap();
+        state.filter_query = ".rs".to_string();
+        state.apply_filter();
+        state.selected_index = state
+            .find_index_by_path(&dir.path().join("file_b.rs"))
+            .unwrap();
+
+        assert_eq!(state.sibling_file_position(), Some((1, 2)));
+        assert!(state.step_sibling_file(true, false));
+        assert_eq!(state.flat_items[state.selected_index].name, "z.rs");
+        assert_eq!(state.sibling_file_position(), Some((2, 2)));
+        assert!(!state.step_sibling_file(true, false));
+        assert!(state.step_sibling_file(false, true));
+        assert_eq!(state.flat_items[state.selected_index].name, "file_b.rs");
+        assert!(state.is_filtering);
+    }
+
     #[test]
     fn load_children_paged_small_dir_no_pagination() {
         let dir = setup_test_dir();
@@ -1609,6 +3654,505 @@ mod tests {
         assert_eq!(node.children.as_ref().unwrap().len(), 10);
     }
 
+    #[test]
+    fn deferred_load_takes_types_from_snapshot() {
+        let dir = setup_test_dir();
+        fs::write(dir.path().join("file_a.txt"), "hello").unwrap();
+        let mut state = TreeState::with_options(dir.path(), 1000, true).unwrap();
+        let children = state.root.children.as_ref().unwrap();
+        assert_eq!(children.len(), 5);
+        assert!(children.iter().all(|c| c.meta.pending));
+        let alpha = children.iter().find(|c| c.name == "alpha").unwrap();
+        assert_eq!(alpha.node_type, NodeType::Directory);
+        let hidden = children.iter().find(|c| c.name == ".hidden").unwrap();
+        assert!(hidden.meta.is_hidden);
+
+        let file = dir.path().join("file_a.txt");
+        let meta = FileMeta::stat(&file).unwrap();
+        state.apply_stats(vec![(file.clone(), Some(meta))]);
+        let index = state.find_index_by_path(&file).unwrap();
+        assert!(!state.flat_items[index].stat_pending);
+        let node = TreeState::find_node_mut(&mut state.root, &file).unwrap();
+        assert_eq!(node.meta.size, 5);
+        assert!(!node.meta.pending);
+    }
+
+    /// A node built in memory, without touching the filesystem.
+    fn synthetic_node(path: PathBuf, depth: usize, node_type: NodeType) -> TreeNode {
+        TreeNode {
+            name: path.file_name().unwrap().to_string_lossy().to_string(),
+            path,
+            node_type,
+            children: None,
+            is_expanded: false,
+            depth,
+            meta: FileMeta {
+                size: 0,
+                modified: None,
+                is_hidden: false,
+                pending: false,
+                link: None,
+                special: None,
+            },
+            total_child_count: None,
+            loaded_child_count: 0,
+            has_more_children: false,
+            snapshot: None,
+            loaded_offset: 0,
+            is_stale: false,
+            gitignore: None,
+            is_ignored: false,
+        }
+    }
+
+    /// A fully expanded chain `/deep/d/d/...` of `levels` directories below
+    /// the root; each holds a file `f<depth>.txt`, and the last one
+    /// `leaf.txt`.
+    fn synthetic_deep_state(levels: usize) -> (TreeState, PathBuf) {
+        let mut paths = vec![PathBuf::from("/deep")];
+        for _ in 0..levels {
+            let next = paths.last().unwrap().join("d");
+            paths.push(next);
+        }
+        let leaf = paths[levels].join("leaf.txt");
+        let mut node = synthetic_node(paths[levels].clone(), levels, NodeType::Directory);
+        node.children = Some(vec![synthetic_node(
+            leaf.clone(),
+            levels + 1,
+            NodeType::File,
+        )]);
+        node.is_expanded = true;
+        for depth in (0..levels).rev() {
+            let mut parent = synthetic_node(paths[depth].clone(), depth, NodeType::Directory);
+            let file = paths[depth].join(format!("f{}.txt", depth));
+            // File first, so sorting with dirs first has something to do
+            parent.children = Some(vec![synthetic_node(file, depth + 1, NodeType::File), node]);
+            parent.is_expanded = true;
+            node = parent;
+        }
+        (synthetic_state(node), leaf)
+    }
+
+    /// Unbounded tree state over the in-memory tree `root`, before any
+    /// sort or flatten.
+    fn synthetic_state(root: TreeNode) -> TreeState {
+        TreeState {
+            root,
+            flat_items: Vec::new(),
+            selected_index: 0,
+            scroll_offset: 0,
+            show_hidden: false,
+            multi_selected: HashSet::new(),
+            filter_query: String::new(),
+            filter_class: None,
+            is_filtering: false,
+            sort_by: SortBy::Name,
+            dirs_first: true,
+            sort_overrides: BoundedMap::new(DEFAULT_SORT_OVERRIDE_CAPACITY),
+            page_size: usize::MAX,
+            max_depth: usize::MAX,
+            generation: 0,
+            defer_stats: false,
+            respect_gitignore: false,
+            buffers: FlattenBuffers::default(),
+        }
+    }
+
+    /// `dirs` expanded directories of `files` files each under `/wide`.
+    fn synthetic_wide_state(dirs: usize, files: usize) -> TreeState {
+        let mut root = synthetic_node(PathBuf::from("/wide"), 0, NodeType::Directory);
+        root.is_expanded = true;
+        root.children = Some(
+            (0..dirs)
+                .map(|d| {
+                    let path = PathBuf::from(format!("/wide/dir{:03}", d));
+                    let mut dir = synthetic_node(path.clone(), 1, NodeType::Directory);
+                    dir.is_expanded = true;
+                    dir.children = Some(
+                        (0..files)
+                            .map(|f| {
+                                let file = path.join(format!("file{:04}.txt", f));
+                                synthetic_node(file, 2, NodeType::File)
+                            })
+                            .collect(),
+                    );
+                    dir
+                })
+                .collect(),
+        );
+        let mut state = synthetic_state(root);
+        state.flatten();
+        state
+    }
+
+    #[test]
+    fn flattening_one_subtree_leaves_the_other_rows_alone() {
+        // 100 directories of 500 files: 50,101 rows
+        let mut state = synthetic_wide_state(100, 500);
+        assert_eq!(state.flat_items.len(), 50_101);
+        let before: Vec<*const u8> = state.flat_items.iter().map(|i| i.name.as_ptr()).collect();
+
+        let dir = PathBuf::from("/wide/dir050");
+        state.selected_index = state.find_index_by_path(&dir).unwrap();
+        let row = state.selected_index;
+        state.collapse_selected();
+        assert_eq!(state.flat_items.len(), 50_101 - 500);
+        // Rows outside the subtree still own the same buffers: nothing
+        // was cloned for them
+        let after: Vec<*const u8> = state.flat_items.iter().map(|i| i.name.as_ptr()).collect();
+        assert_eq!(after[..row], before[..row]);
+        assert_eq!(after[row + 1..], before[row + 501..]);
+
+        let mut whole = synthetic_state(state.root.clone());
+        whole.flatten();
+        assert_eq!(rows(&state), rows(&whole));
+
+        // Expanding it again (already loaded) only adds its rows back
+        TreeState::find_node_mut(&mut state.root, &dir)
+            .unwrap()
+            .is_expanded = true;
+        state.flatten_subtree(&dir);
+        assert_eq!(state.flat_items.len(), 50_101);
+        let after: Vec<*const u8> = state.flat_items.iter().map(|i| i.name.as_ptr()).collect();
+        assert_eq!(after[row + 501..], before[row + 501..]);
+        let mut whole = synthetic_state(state.root.clone());
+        whole.flatten();
+        assert_eq!(rows(&state), rows(&whole));
+    }
+
+    #[test]
+    fn filtering_a_large_tree_matches_names_case_insensitively() {
+        let mut state = synthetic_wide_state(100, 500);
+        state.filter_query = "FILE0499".to_string();
+        state.apply_filter();
+        // The root, each directory and its one match
+        assert_eq!(state.flat_items.len(), 1 + 2 * 100);
+        state.filter_query = "dir007".to_string();
+        state.apply_filter();
+        assert_eq!(state.flat_items.len(), 1 + 1);
+        state.filter_query.clear();
+        state.apply_filter();
+        assert_eq!(state.flat_items.len(), 50_101);
+
+        assert!(contains_lowercase("Ünïcode.TXT", "code.txt"));
+        assert!(contains_lowercase("README.md", "readme"));
+        assert!(!contains_lowercase("a.rs", "a.rs.bak"));
+    }
+
+    #[test]
+    fn very_deep_tree_does_not_overflow() {
+        let (mut state, leaf) = synthetic_deep_state(5_000);
+        state.sort_all_children();
+        state.flatten();
+        // Root + 5,000 dirs + 5,000 files + leaf.txt
+        assert_eq!(state.flat_items.len(), 10_002);
+        // Dirs first: the chain continues before each level's file
+        assert_eq!(state.flat_items[1].name, "d");
+        assert_eq!(state.flat_items[5_001].name, "leaf.txt");
+        assert_eq!(state.flat_items[5_001].depth, 5_001);
+
+        assert!(TreeState::find_node_mut(&mut state.root, &leaf).is_some());
+        assert!(TreeState::find_node(&state.root, leaf.parent().unwrap()).is_some());
+        assert_eq!(state.loaded_items().len(), 10_002);
+
+        state.filter_query = "leaf".to_string();
+        state.apply_filter();
+        // The whole chain of ancestors plus the match
+        assert_eq!(state.flat_items.len(), 5_002);
+        assert_eq!(state.flat_items.last().unwrap().path, leaf);
+    }
+
+    #[test]
+    fn depth_limit_caps_flatten_filter_and_expansion() {
+        let (mut state, leaf) = synthetic_deep_state(5_000);
+        state.max_depth = 128;
+        state.sort_all_children();
+        state.flatten();
+
+        // Root, then the chain down to depth 128, then the marker in place
+        // of the children of the directory at the limit
+        let marker = &state.flat_items[129];
+        assert!(marker.is_depth_limit());
+        assert_eq!(marker.name, DEPTH_LIMIT_LABEL);
+        assert_eq!(marker.depth, 129);
+        assert!(state.flat_items.iter().all(|item| item.depth <= 129));
+        assert_eq!(
+            state
+                .flat_items
+                .iter()
+                .filter(|i| i.is_depth_limit())
+                .count(),
+            1
+        );
+
+        // A match below the limit isn't found; the marker still shows where
+        // the tree was cut off
+        state.filter_query = "leaf".to_string();
+        state.apply_filter();
+        assert!(state.flat_items.iter().all(|item| item.path != leaf));
+        state.filter_query = "d".to_string();
+        state.apply_filter();
+        assert!(state.flat_items.last().unwrap().is_depth_limit());
+
+        // The directory at the limit doesn't expand
+        state.filter_query.clear();
+        state.apply_filter();
+        let capped = state
+            .flat_items
+            .iter()
+            .position(|item| item.depth == 128 && item.node_type == NodeType::Directory)
+            .unwrap();
+        let node =
+            TreeState::find_node_mut(&mut state.root, &state.flat_items[capped].path.clone())
+                .unwrap();
+        node.is_expanded = false;
+        state.flatten();
+        state.selected_index = capped;
+        state.expand_selected();
+        assert!(!state.flat_items[capped].is_expanded);
+    }
+
+    /// Small seeded generator, so random trees repeat from run to run.
+    struct Lcg(u64);
+
+    impl Lcg {
+        fn below(&mut self, bound: usize) -> usize {
+            self.0 = self
+                .0
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            (self.0 >> 33) as usize % bound
+        }
+    }
+
+    /// A random in-memory tree: few distinct names (so sorts tie and the
+    /// filter matches), some hidden, collapsed or paginated directories.
+    fn random_node(rng: &mut Lcg, path: PathBuf, depth: usize) -> TreeNode {
+        let is_dir = depth == 0 || (depth < 6 && rng.below(3) == 0);
+        let node_type = if is_dir {
+            NodeType::Directory
+        } else {
+            NodeType::File
+        };
+        let mut node = synthetic_node(path.clone(), depth, node_type);
+        node.meta.is_hidden = node.name.starts_with('.');
+        node.meta.size = rng.below(4) as u64;
+        node.meta.modified =
+            Some(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(rng.below(4) as u64));
+        if is_dir {
+            let children: Vec<TreeNode> = (0..rng.below(9))
+                .map(|i| {
+                    let name = format!(
+                        "{}{}{}.{}",
+                        [".", "", "", ""][rng.below(4)],
+                        ["a", "B", "c", "ab"][rng.below(4)],
+                        i % 3,
+                        ["rs", "txt"][rng.below(2)]
+                    );
+                    random_node(rng, path.join(name), depth + 1)
+                })
+                .collect();
+            node.is_expanded = depth == 0 || rng.below(4) > 0;
+            node.has_more_children = rng.below(8) == 0;
+            node.loaded_child_count = children.len();
+            node.total_child_count = Some(children.len() + 3 * node.has_more_children as usize);
+            node.children = Some(children);
+        }
+        node
+    }
+
+    /// The random tree `seed` builds, with a random depth limit, hidden
+    /// files shown or not and an override on one directory.
+    fn random_state(seed: u64) -> TreeState {
+        let mut rng = Lcg(seed);
+        let mut state = synthetic_state(random_node(&mut rng, PathBuf::from("/r"), 0));
+        state.max_depth = rng.below(7);
+        state.show_hidden = rng.below(2) == 0;
+        state.dirs_first = rng.below(2) == 0;
+        let overridden = state
+            .root
+            .children
+            .iter()
+            .flatten()
+            .find(|c| c.children.is_some());
+        if let Some(dir) = overridden.map(|dir| dir.path.clone()) {
+            state.sort_overrides.insert(dir, (SortBy::Size, false));
+        }
+        state
+    }
+
+    /// Run `step` to the end in slices of a few nodes each.
+    fn run_sliced(state: &mut TreeState, mut step: TreeStep, rng: &mut Lcg) -> usize {
+        let mut slices = 1;
+        while !step.run(
+            state,
+            &mut Budget::new(1 + rng.below(5), std::time::Duration::from_secs(60)),
+        ) {
+            slices += 1;
+        }
+        slices
+    }
+
+    fn rows(state: &TreeState) -> Vec<String> {
+        state
+            .flat_items
+            .iter()
+            .map(|item| format!("{:?}", item))
+            .collect()
+    }
+
+    #[test]
+    fn sliced_steps_match_the_whole_tree_versions() {
+        for seed in 0..60 {
+            let mut whole = random_state(seed);
+            let mut sliced = random_state(seed);
+            let mut rng = Lcg(seed);
+            for sort_by in [SortBy::Size, SortBy::Modified, SortBy::Name] {
+                whole.sort_by = sort_by.clone();
+                whole.sort_all_children();
+                whole.flatten();
+                sliced.sort_by = sort_by;
+                run_sliced(&mut sliced, TreeState::sort_step(false), &mut rng);
+                run_sliced(&mut sliced, TreeStep::flatten(), &mut rng);
+                assert_eq!(rows(&sliced), rows(&whole), "seed {}", seed);
+            }
+
+            let filters = [
+                ("a", None),
+                ("B1", None),
+                ("zz", None),
+                ("", Some(class("Rust", &["rs"]))),
+                ("c", Some(class("Rust", &["rs"]))),
+                ("", None),
+            ];
+            for (query, class) in filters {
+                for state in [&mut whole, &mut sliced] {
+                    state.filter_query = query.to_string();
+                    state.filter_class = class.clone();
+                }
+                whole.apply_filter();
+                run_sliced(&mut sliced, TreeStep::filter(), &mut rng);
+                assert_eq!(rows(&sliced), rows(&whole), "seed {} {:?}", seed, query);
+                assert_eq!(sliced.is_filtering, whole.is_filtering);
+            }
+        }
+    }
+
+    #[test]
+    fn sliced_restore_matches_restore_expanded() {
+        let dir = setup_test_dir();
+        let (mut whole, mut sliced) = (
+            TreeState::new(dir.path()).unwrap(),
+            TreeState::new(dir.path()).unwrap(),
+        );
+        let mut expanded = HashSet::new();
+        expanded.insert(dir.path().join("alpha"));
+        expanded.insert(dir.path().join("alpha").join("nested"));
+        expanded.insert(dir.path().join("gone"));
+
+        whole.restore_expanded(&expanded);
+        whole.flatten();
+        let mut rng = Lcg(7);
+        run_sliced(&mut sliced, TreeState::restore_step(&expanded), &mut rng);
+        run_sliced(&mut sliced, TreeStep::flatten(), &mut rng);
+        assert_eq!(rows(&sliced), rows(&whole));
+        assert!(sliced
+            .find_index_by_path(&dir.path().join("alpha").join("inner.txt"))
+            .is_some());
+    }
+
+    /// 100 expanded directories of 1,000 files each, out of order.
+    fn huge_tree_state() -> TreeState {
+        let root_path = PathBuf::from("/huge");
+        let mut root = synthetic_node(root_path.clone(), 0, NodeType::Directory);
+        let dirs = (0..100)
+            .map(|d| {
+                let path = root_path.join(format!("d{:03}", (d * 37) % 100));
+                let mut dir = synthetic_node(path.clone(), 1, NodeType::Directory);
+                let files = (0..1_000)
+                    .map(|f| {
+                        let name = format!("f{:04}.txt", (f * 7_919) % 1_000);
+                        synthetic_node(path.join(name), 2, NodeType::File)
+                    })
+                    .collect();
+                dir.children = Some(files);
+                dir.is_expanded = true;
+                dir
+            })
+            .collect();
+        root.children = Some(dirs);
+        root.is_expanded = true;
+        let mut state = synthetic_state(root);
+        state.filter_query = "f000".to_string();
+        state
+    }
+
+    #[test]
+    fn slices_of_a_huge_tree_fit_in_a_frame() {
+        use crate::work_queue::SLICE_NODES;
+        use std::time::Duration;
+        const NODES: usize = 1 + 100 + 100 * 1_000;
+        let mut state = huge_tree_state();
+
+        for mut step in [
+            TreeState::sort_step(false),
+            TreeStep::flatten(),
+            TreeStep::filter(),
+        ] {
+            let mut slices = 0;
+            loop {
+                // Nodes only: wall-clock time depends on the machine and load
+                let mut budget = Budget::new(SLICE_NODES, Duration::from_secs(3_600));
+                let done = step.run(&mut state, &mut budget);
+                slices += 1;
+                if done {
+                    break;
+                }
+                assert_eq!(budget.nodes_left(), 0, "slice {} stopped early", slices);
+            }
+            // Every node costs at least one unit, so fewer slices would mean
+            // some slice visited more than its share
+            assert!(slices > NODES / SLICE_NODES, "{} slices", slices);
+        }
+        // Every directory, plus the root and its ten matches in each
+        assert_eq!(state.flat_items.len(), 1 + 100 * 11);
+        assert_eq!(state.flat_items[1].name, "d000");
+        assert_eq!(state.flat_items[2].name, "f0000.txt");
+    }
+
+    #[test]
+    fn slices_of_a_huge_tree_return_near_their_deadline() {
+        use crate::work_queue::SLICE_TIME;
+        // Generous so a loaded machine does not fail it; the node count
+        // test above pins down how the work is split
+        const BOUND: std::time::Duration = std::time::Duration::from_millis(500);
+        let mut state = huge_tree_state();
+
+        for mut step in [
+            TreeState::sort_step(false),
+            TreeStep::flatten(),
+            TreeStep::filter(),
+        ] {
+            let mut slices = 0;
+            loop {
+                let started = std::time::Instant::now();
+                let done = step.run(&mut state, &mut Budget::slice());
+                let took = started.elapsed();
+                assert!(
+                    took < SLICE_TIME + BOUND,
+                    "slice {} took {:?}",
+                    slices,
+                    took
+                );
+                slices += 1;
+                if done {
+                    break;
+                }
+            }
+        }
+        assert_eq!(state.flat_items.len(), 1 + 100 * 11);
+    }
+
     #[test]
     fn flatten_emits_load_more_node() {
         let dir = setup_large_dir(20);
@@ -1686,21 +4230,21 @@ mod tests {
         assert!(node.has_more_children);
 
         // Second page: 10 more entries
-        let loaded = node.load_next_page(10).unwrap();
+        let loaded = node.load_next_page(10, false).unwrap();
         assert_eq!(loaded, 10);
         assert_eq!(node.loaded_child_count, 20);
         assert_eq!(node.children.as_ref().unwrap().len(), 20);
         assert!(node.has_more_children);
 
         // Third page: only 5 remaining
-        let loaded = node.load_next_page(10).unwrap();
+        let loaded = node.load_next_page(10, false).unwrap();
         assert_eq!(loaded, 5);
         assert_eq!(node.loaded_child_count, 25);
         assert_eq!(node.children.as_ref().unwrap().len(), 25);
         assert!(!node.has_more_children);
 
         // Fourth page: nothing left
-        let loaded = node.load_next_page(10).unwrap();
+        let loaded = node.load_next_page(10, false).unwrap();
         assert_eq!(loaded, 0);
     }
 
@@ -1957,4 +4501,104 @@ mod tests {
         assert_eq!(state.root.loaded_child_count, 5);
         assert_eq!(state.root.loaded_offset, 5);
     }
+
+    #[cfg(unix)]
+    #[test]
+    fn special_files_are_detected_without_opening() {
+        let dir = TempDir::new().unwrap();
+        let fifo = dir.path().join("pipe");
+        if !mkfifo(&fifo) {
+            return;
+        }
+        let _socket = std::os::unix::net::UnixListener::bind(dir.path().join("sock")).unwrap();
+        File::create(dir.path().join("plain.txt")).unwrap();
+
+        let state = TreeState::new(dir.path()).unwrap();
+        let special = |name: &str| {
+            state
+                .flat_items
+                .iter()
+                .find(|item| item.name == name)
+                .unwrap()
+                .special
+        };
+        assert_eq!(special("pipe"), Some(SpecialFile::Fifo));
+        assert_eq!(special("sock"), Some(SpecialFile::Socket));
+        assert_eq!(special("plain.txt"), None);
+
+        assert_eq!(SpecialFile::at(&fifo), Some(SpecialFile::Fifo));
+        assert_eq!(
+            SpecialFile::at(Path::new("/dev/null")),
+            Some(SpecialFile::CharDevice)
+        );
+        assert_eq!(SpecialFile::at(dir.path()), None);
+    }
+
+    #[test]
+    fn created_and_removed_entries_are_placed_without_a_reload() {
+        let dir = setup_test_dir();
+        let mut state = TreeState::new(dir.path()).unwrap();
+        File::create(dir.path().join("aaa.txt")).unwrap();
+        fs::create_dir(dir.path().join("gamma")).unwrap();
+        fs::remove_file(dir.path().join("file_a.txt")).unwrap();
+        assert!(state.insert_path(&dir.path().join("aaa.txt")));
+        assert!(state.insert_path(&dir.path().join("gamma")));
+        assert!(state.remove_path(&dir.path().join("file_a.txt")));
+        // Already listed: only a reload can tell what changed
+        assert!(!state.insert_path(&dir.path().join("gamma")));
+        state.flatten();
+        assert_eq!(
+            root_child_names(&state),
+            ["alpha", "beta", "gamma", "aaa.txt", "file_b.rs"]
+        );
+        assert_eq!(state.root.total_child_count, Some(6));
+
+        state.root.is_stale = true;
+        File::create(dir.path().join("zzz.txt")).unwrap();
+        assert!(!state.insert_path(&dir.path().join("zzz.txt")));
+    }
+
+    #[test]
+    fn renaming_a_directory_keeps_what_is_expanded_below_it() {
+        let dir = setup_test_dir();
+        let mut state = TreeState::new(dir.path()).unwrap();
+        state.selected_index = state.find_index_by_path(&dir.path().join("alpha")).unwrap();
+        state.expand_selected();
+        state.selected_index = state
+            .find_index_by_path(&dir.path().join("alpha/nested"))
+            .unwrap();
+        state.expand_selected();
+
+        fs::rename(dir.path().join("alpha"), dir.path().join("omega")).unwrap();
+        assert!(state.rename_path(&dir.path().join("alpha"), &dir.path().join("omega")));
+        state.flatten();
+        assert_eq!(
+            root_child_names(&state),
+            ["beta", "omega", "file_a.txt", "file_b.rs"]
+        );
+        let nested = state
+            .find_index_by_path(&dir.path().join("omega/nested"))
+            .unwrap();
+        assert!(state.flat_items[nested].is_expanded);
+        assert_eq!(state.flat_items[nested].depth, 2);
+        assert!(state
+            .find_index_by_path(&dir.path().join("omega/inner.txt"))
+            .is_some());
+        assert!(state
+            .find_index_by_path(&dir.path().join("alpha"))
+            .is_none());
+
+        // Moved into a directory that isn't loaded: just gone from here
+        fs::rename(
+            dir.path().join("file_a.txt"),
+            dir.path().join("beta/file_a.txt"),
+        )
+        .unwrap();
+        assert!(state.rename_path(
+            &dir.path().join("file_a.txt"),
+            &dir.path().join("beta/file_a.txt")
+        ));
+        state.flatten();
+        assert_eq!(root_child_names(&state), ["beta", "omega", "file_b.rs"]);
+    }
 }
@@ -0,0 +1,151 @@
+//! Pre-undo verification: metadata captured when an undo action is
+//! recorded, compared against the filesystem before the undo is applied.
+
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+/// Size and mtime of a path at the time an operation finished.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PathStamp {
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+}
+
+impl PathStamp {
+    /// Stat `path` once. Returns `None` if it cannot be read.
+    pub fn capture(path: &Path) -> Option<Self> {
+        let meta = std::fs::symlink_metadata(path).ok()?;
+        Some(Self {
+            size: meta.len(),
+            modified: meta.modified().ok(),
+        })
+    }
+}
+
+/// A path together with the metadata it had when the action was recorded.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StampedPath {
+    pub path: PathBuf,
+    pub stamp: Option<PathStamp>,
+}
+
+impl StampedPath {
+    pub fn capture(path: PathBuf) -> Self {
+        let stamp = PathStamp::capture(&path);
+        Self { path, stamp }
+    }
+}
+
+/// How a path changed since the undo action was recorded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Drift {
+    /// The path no longer exists; there is nothing to undo for it.
+    Missing,
+    /// Size or mtime differ from the captured stamp.
+    Modified,
+    /// Something now exists where the undo would put the item back.
+    Recreated,
+}
+
+/// Compare a stamped path against its current metadata.
+pub fn check_stamped(stamped: &StampedPath) -> Option<Drift> {
+    match PathStamp::capture(&stamped.path) {
+        None => Some(Drift::Missing),
+        Some(now) if stamped.stamp.is_some_and(|then| then != now) => Some(Drift::Modified),
+        Some(_) => None,
+    }
+}
+
+/// Check that `path` is still free to be restored into.
+pub fn check_vacant(path: &Path) -> Option<Drift> {
+    if std::fs::symlink_metadata(path).is_ok() {
+        Some(Drift::Recreated)
+    } else {
+        None
+    }
+}
+
+/// One changed item in a pending undo, shown in the confirmation dialog.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UndoConflict {
+    /// Index of the affected item within the undo action.
+    pub item: usize,
+    pub drift: Drift,
+    /// Human-readable description, phrased as a question.
+    pub message: String,
+    /// Whether the user chose to apply the undo for this item anyway.
+    pub apply: bool,
+}
+
+impl UndoConflict {
+    pub fn new(item: usize, drift: Drift, message: String) -> Self {
+        Self {
+            item,
+            drift,
+            message,
+            apply: false,
+        }
+    }
+
+    /// Missing items can never be applied.
+    pub fn can_apply(&self) -> bool {
+        self.drift != Drift::Missing
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn unchanged_path_has_no_drift() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("a.txt");
+        fs::write(&file, "one").unwrap();
+        let stamped = StampedPath::capture(file);
+        assert!(stamped.stamp.is_some());
+        assert_eq!(check_stamped(&stamped), None);
+    }
+
+    #[test]
+    fn edited_path_is_modified() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("a.txt");
+        fs::write(&file, "one").unwrap();
+        let stamped = StampedPath::capture(file.clone());
+        fs::write(&file, "one and more").unwrap();
+        assert_eq!(check_stamped(&stamped), Some(Drift::Modified));
+    }
+
+    #[test]
+    fn deleted_path_is_missing() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("a.txt");
+        fs::write(&file, "one").unwrap();
+        let stamped = StampedPath::capture(file.clone());
+        fs::remove_file(&file).unwrap();
+        assert_eq!(check_stamped(&stamped), Some(Drift::Missing));
+    }
+
+    #[test]
+    fn vacant_check_detects_recreation() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("a.txt");
+        assert_eq!(check_vacant(&file), None);
+        fs::write(&file, "new").unwrap();
+        assert_eq!(check_vacant(&file), Some(Drift::Recreated));
+    }
+
+    #[test]
+    fn missing_conflict_cannot_apply() {
+        let c = UndoConflict::new(0, Drift::Missing, "gone".to_string());
+        assert!(!c.can_apply());
+        let c = UndoConflict::new(0, Drift::Modified, "changed".to_string());
+        assert!(c.can_apply());
+        assert!(!c.apply);
+    }
+}
@@ -0,0 +1,228 @@
+//! The recursive walk behind directory sizes and pre-flight counts.
+//!
+//! Both `dir_size::walk` and `operations::disk_usage_until` sum a subtree
+//! through [`walk`], so they agree on what a subtree holds:
+//!
+//! - Symlinks count as themselves and are never followed.
+//! - Each directory is entered once, tracked by [`VisitedDirs`], so bind
+//!   mounts leading back up the tree can't loop.
+//! - A file with several hard links adds its size to `bytes` (the apparent
+//!   size) every time but to `unique_bytes` once.
+//! - With `one_file_system`, directories on another device than their root
+//!   (mount points) are counted but not entered.
+//! - The walk stops at `max_entries` or the deadline and marks the totals
+//!   `partial`, as it does when a directory can't be read.
+
+use std::collections::HashSet;
+use std::fs::{self, Metadata};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+
+use crate::fs::tree::VisitedDirs;
+
+/// Limits and boundaries of a walk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WalkOptions {
+    pub max_entries: u64,
+    pub deadline: Option<Instant>,
+    /// Stay on the device each root is on.
+    pub one_file_system: bool,
+}
+
+/// Totals of a walk.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Usage {
+    /// Every file's size, hard links counted each time.
+    pub bytes: u64,
+    /// Hard-linked content counted once.
+    pub unique_bytes: u64,
+    /// Every file, directory and link walked, the roots included.
+    pub entries: u64,
+    /// Entries that aren't directories.
+    pub files: u64,
+    pub dirs: u64,
+    /// Stopped at a limit or missed an unreadable directory, making the
+    /// totals lower bounds.
+    pub partial: bool,
+}
+
+/// Sum `roots` and everything under them, calling `report` with the
+/// running totals after each entry. Returns `None` once `cancel` is
+/// raised.
+pub fn walk(
+    roots: &[PathBuf],
+    options: &WalkOptions,
+    cancel: &AtomicBool,
+    report: &mut dyn FnMut(&Usage),
+) -> Option<Usage> {
+    let mut usage = Usage::default();
+    let mut visited = VisitedDirs::new();
+    let mut links = HashSet::new();
+    // Each path with the device of its root, once known
+    let mut pending: Vec<(PathBuf, Option<u64>)> = roots
+        .iter()
+        .rev()
+        .map(|root| (root.clone(), None))
+        .collect();
+
+    while let Some((path, root_device)) = pending.pop() {
+        if cancel.load(Ordering::Relaxed) {
+            return None;
+        }
+        if usage.entries >= options.max_entries
+            || options.deadline.is_some_and(|d| Instant::now() >= d)
+        {
+            usage.partial = true;
+            return Some(usage);
+        }
+        usage.entries += 1;
+        let Ok(meta) = fs::symlink_metadata(&path) else {
+            usage.partial = true;
+            continue;
+        };
+        if meta.is_dir() {
+            usage.dirs += 1;
+            let device = device(&meta);
+            let root_device = root_device.or(device);
+            let crosses = options.one_file_system && device != root_device;
+            if !crosses && visited.visit(&path) {
+                match fs::read_dir(&path) {
+                    Ok(entries) => {
+                        pending.extend(entries.flatten().map(|entry| (entry.path(), root_device)))
+                    }
+                    Err(_) => usage.partial = true,
+                }
+            }
+        } else {
+            usage.files += 1;
+            usage.bytes += meta.len();
+            if first_link(&meta, &mut links) {
+                usage.unique_bytes += meta.len();
+            }
+        }
+        report(&usage);
+    }
+    Some(usage)
+}
+
+#[cfg(unix)]
+fn device(meta: &Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(meta.dev())
+}
+
+#[cfg(not(unix))]
+fn device(_meta: &Metadata) -> Option<u64> {
+    None
+}
+
+/// Whether `meta` is the first link seen to its file. Files with a single
+/// link always are.
+#[cfg(unix)]
+fn first_link(meta: &Metadata, links: &mut HashSet<(u64, u64)>) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    meta.nlink() < 2 || links.insert((meta.dev(), meta.ino()))
+}
+
+#[cfg(not(unix))]
+fn first_link(_meta: &Metadata, _links: &mut HashSet<(u64, u64)>) -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn options() -> WalkOptions {
+        WalkOptions {
+            max_entries: 1000,
+            deadline: None,
+            one_file_system: true,
+        }
+    }
+
+    fn walk_all(root: &std::path::Path, options: &WalkOptions) -> Usage {
+        walk(
+            &[root.to_path_buf()],
+            options,
+            &AtomicBool::new(false),
+            &mut |_| {},
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn hard_links_count_once_in_unique_bytes() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("a.bin"), vec![0u8; 100]).unwrap();
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        fs::hard_link(dir.path().join("a.bin"), dir.path().join("sub/b.bin")).unwrap();
+        fs::write(dir.path().join("c.bin"), vec![0u8; 7]).unwrap();
+
+        let usage = walk_all(dir.path(), &options());
+        assert_eq!(usage.bytes, 207);
+        if cfg!(unix) {
+            assert_eq!(usage.unique_bytes, 107);
+        }
+        assert_eq!((usage.entries, usage.files, usage.dirs), (5, 3, 2));
+        assert!(!usage.partial);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlink_cycles_end() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir(dir.path().join("a")).unwrap();
+        std::os::unix::fs::symlink(dir.path(), dir.path().join("a/up")).unwrap();
+        std::os::unix::fs::symlink("loop", dir.path().join("loop")).unwrap();
+
+        let usage = walk_all(dir.path(), &options());
+        // The root, a/, and the two links as themselves
+        assert_eq!((usage.entries, usage.files, usage.dirs), (4, 2, 2));
+        assert!(!usage.partial);
+
+        // The same directory twice is entered once
+        let roots = [dir.path().to_path_buf(), dir.path().join("a/..")];
+        let twice = walk(&roots, &options(), &AtomicBool::new(false), &mut |_| {}).unwrap();
+        assert_eq!(twice.entries, 5);
+    }
+
+    #[test]
+    fn limits_mark_the_totals_partial() {
+        let dir = TempDir::new().unwrap();
+        for i in 0..5 {
+            fs::write(dir.path().join(format!("f{}", i)), b"x").unwrap();
+        }
+        let mut capped = options();
+        capped.max_entries = 3;
+        let usage = walk_all(dir.path(), &capped);
+        assert_eq!((usage.entries, usage.files), (3, 2));
+        assert!(usage.partial);
+
+        let mut late = options();
+        late.deadline = Some(Instant::now());
+        assert!(walk_all(dir.path(), &late).partial);
+
+        let missing = walk_all(&dir.path().join("gone"), &options());
+        assert_eq!(missing.entries, 1);
+        assert!(missing.partial);
+
+        let cancel = AtomicBool::new(true);
+        let roots = [dir.path().to_path_buf()];
+        assert_eq!(walk(&roots, &options(), &cancel, &mut |_| {}), None);
+    }
+
+    #[test]
+    fn reports_running_totals() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("a"), b"xy").unwrap();
+        let mut seen = Vec::new();
+        let roots = [dir.path().to_path_buf()];
+        walk(&roots, &options(), &AtomicBool::new(false), &mut |usage| {
+            seen.push(usage.entries)
+        });
+        assert_eq!(seen, [1, 2]);
+    }
+}
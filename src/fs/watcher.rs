@@ -1,12 +1,17 @@
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
-use notify_debouncer_mini::{new_debouncer, DebouncedEventKind};
+use notify::event::{AccessKind, AccessMode, ModifyKind, RenameMode};
+use notify::{EventKind, RecursiveMode, Watcher};
 use tokio::sync::mpsc;
 
+use crate::config::AppConfig;
+use crate::error::WatcherError;
 use crate::event::Event;
+use crate::fs::gitignore::GitIgnore;
 
 /// Default patterns to ignore when watching the filesystem.
 #[allow(dead_code)]
@@ -30,81 +35,294 @@ pub const DEFAULT_DEBOUNCE_MS: u64 = 300;
 #[allow(dead_code)]
 pub const DEFAULT_FLOOD_THRESHOLD: usize = 100;
 
+/// Below this many inotify watches, recursive watching of a large tree is
+/// likely to run out.
+pub const LOW_INOTIFY_WATCHES: u64 = 65_536;
+
+/// Kernel setting holding the per-user inotify watch limit.
+pub const INOTIFY_MAX_WATCHES_PATH: &str = "/proc/sys/fs/inotify/max_user_watches";
+
+/// Interval between scans when watching by polling.
+pub const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Directories counted at most when estimating the watches a tree needs.
+const WATCH_ESTIMATE_CAP: u64 = 4_000_000;
+
+/// How changes are detected.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WatchMode {
+    /// Kernel notifications (inotify, FSEvents, ReadDirectoryChangesW).
+    #[default]
+    Native,
+    /// Periodic rescans; slower, but needs no kernel watches.
+    Poll,
+}
+
+/// Whether the tree is being kept up to date, as shown in its title.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatcherState {
+    /// Changes refresh the tree as they happen.
+    Live,
+    /// Paused with Ctrl+R, or while editing.
+    Paused,
+    /// Refreshes held back while the terminal floods the tree with build
+    /// output.
+    Flooded,
+    /// Not running: disabled, or it failed to start.
+    Unavailable,
+}
+
+impl WatcherState {
+    pub fn glyph(self) -> &'static str {
+        match self {
+            WatcherState::Live => "●",
+            WatcherState::Paused => "⏸",
+            WatcherState::Flooded => "⚠",
+            WatcherState::Unavailable => "✕",
+        }
+    }
+}
+
+/// What happened to a path, as far as the backend tells.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum FsEvent {
+    Created(PathBuf),
+    Removed(PathBuf),
+    /// Both ends of a rename seen: `from` is gone and `to` is the same
+    /// entry.
+    Renamed {
+        from: PathBuf,
+        to: PathBuf,
+    },
+    /// Modified, or anything the backend didn't say more about.
+    Changed(PathBuf),
+}
+
+impl FsEvent {
+    /// The paths the event is about.
+    pub fn paths(&self) -> impl Iterator<Item = &PathBuf> {
+        let (first, second) = match self {
+            FsEvent::Renamed { from, to } => (from, Some(to)),
+            FsEvent::Created(path) | FsEvent::Removed(path) | FsEvent::Changed(path) => {
+                (path, None)
+            }
+        };
+        std::iter::once(first).chain(second)
+    }
+}
+
+/// Events gathered over a debounce window, or between two frames, each
+/// kept once and in arrival order.
+#[derive(Debug, Default)]
+pub struct EventBatch {
+    events: Vec<FsEvent>,
+    seen: HashSet<FsEvent>,
+    /// Paths created or renamed to in this batch.
+    fresh: HashSet<PathBuf>,
+}
+
+impl EventBatch {
+    /// Add `event` unless it is already in the batch or only says a path
+    /// created in it changed: a new entry is read whole when it is added.
+    pub fn push(&mut self, event: FsEvent) {
+        if matches!(&event, FsEvent::Changed(path) if self.fresh.contains(path)) {
+            return;
+        }
+        if !self.seen.insert(event.clone()) {
+            return;
+        }
+        if let FsEvent::Created(path) | FsEvent::Renamed { to: path, .. } = &event {
+            self.fresh.insert(path.clone());
+        }
+        self.events.push(event);
+    }
+
+    /// Drop `event` if it is in the batch.
+    fn remove(&mut self, event: &FsEvent) {
+        if self.seen.remove(event) {
+            self.events.retain(|e| e != event);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// The events so far, leaving the batch empty.
+    pub fn take(&mut self) -> Vec<FsEvent> {
+        self.seen.clear();
+        self.fresh.clear();
+        std::mem::take(&mut self.events)
+    }
+}
+
+impl Extend<FsEvent> for EventBatch {
+    fn extend<I: IntoIterator<Item = FsEvent>>(&mut self, events: I) {
+        for event in events {
+            self.push(event);
+        }
+    }
+}
+
+/// Whichever backend is in use (dropped to stop watching).
+enum Backend {
+    Native(notify::RecommendedWatcher),
+    Poll(notify::PollWatcher),
+}
+
+impl Backend {
+    fn watcher(&mut self) -> &mut dyn Watcher {
+        match self {
+            Backend::Native(watcher) => watcher,
+            Backend::Poll(watcher) => watcher,
+        }
+    }
+}
+
 /// Filesystem watcher that monitors a root directory and sends change events.
 #[allow(dead_code)]
 pub struct FsWatcher {
     /// Whether the watcher is currently forwarding events.
     active: Arc<AtomicBool>,
-    /// Handle to the debouncer (dropped to stop watching).
-    _debouncer: notify_debouncer_mini::Debouncer<notify::RecommendedWatcher>,
+    /// Drop what `.gitignore` leaves out instead of the ignore patterns.
+    respect_gitignore: Arc<AtomicBool>,
+    /// Tells the flushing thread to stop.
+    stopped: Arc<AtomicBool>,
+    backend: Backend,
+    root: PathBuf,
+    /// The root is watched recursively, so directories below it need no
+    /// watch of their own unless they sit in an unwatched subtree.
+    recursive: bool,
+    /// Directories watched on their own, non-recursively.
+    watched: HashSet<PathBuf>,
+    /// Subtrees that could not be watched when the root could only be
+    /// watched partially.
+    unwatched: Vec<PathBuf>,
+    /// Why those subtrees are unwatched.
+    partial_error: Option<WatcherError>,
 }
 
 #[allow(dead_code)]
 impl FsWatcher {
-    /// Create a new FsWatcher that watches `root` recursively.
+    /// Create a new FsWatcher that watches `root`: recursively, or with
+    /// `recursive` off the root alone, leaving the directories below it to
+    /// [`watch_path`](Self::watch_path).
     ///
-    /// Events are debounced by `debounce_duration` and sent via `event_tx`.
-    /// Paths matching any of `ignore_patterns` are silently dropped.
+    /// Events are gathered over windows of `debounce_duration` and sent
+    /// via `event_tx` as one `Event::FsEvents` per window.
+    /// Paths matching any of `ignore_patterns` are silently dropped, or,
+    /// once [`set_respect_gitignore`](Self::set_respect_gitignore) turns it
+    /// on, paths `.gitignore` leaves out.
     /// If more than `flood_threshold` events arrive in a single debounce window,
     /// they are collapsed into a single full-refresh event (root path only).
+    /// If the recursive watch fails, the root and each subdirectory are
+    /// watched separately and the ones that fail are left out.
     pub fn new(
         root: &Path,
         debounce_duration: Duration,
         ignore_patterns: Vec<String>,
         flood_threshold: usize,
+        mode: WatchMode,
+        recursive: bool,
         event_tx: mpsc::UnboundedSender<Event>,
     ) -> notify::Result<Self> {
         let active = Arc::new(AtomicBool::new(true));
-        let active_clone = active.clone();
-        let root_path = root.to_path_buf();
-
-        let mut debouncer = new_debouncer(
-            debounce_duration,
-            move |result: Result<Vec<notify_debouncer_mini::DebouncedEvent>, notify::Error>| {
+        let respect_gitignore = Arc::new(AtomicBool::new(false));
+        let stopped = Arc::new(AtomicBool::new(false));
+        let window: Arc<Mutex<EventBatch>> = Arc::default();
+        let handler = {
+            let active = active.clone();
+            let window = window.clone();
+            let root = root.to_path_buf();
+            move |result: notify::Result<notify::Event>| {
                 // If paused, silently drop events
-                if !active_clone.load(Ordering::Relaxed) {
-                    return;
-                }
-
-                match result {
-                    Ok(events) => {
-                        let paths: Vec<PathBuf> = events
-                            .iter()
-                            .filter(|e| e.kind == DebouncedEventKind::Any)
-                            .map(|e| e.path.clone())
-                            .filter(|p| !should_ignore(p, &ignore_patterns))
-                            .collect();
-
-                        if paths.is_empty() {
-                            return;
-                        }
-
-                        // Flood protection: if too many events, collapse to root refresh
-                        let final_paths = if paths.len() > flood_threshold {
-                            vec![root_path.clone()]
-                        } else {
-                            paths
-                        };
-
-                        let _ = event_tx.send(Event::FsChange(final_paths));
-                    }
-                    Err(_errors) => {
-                        // Watcher errors are non-fatal; silently ignore
+                if let (true, Ok(event)) = (active.load(Ordering::Relaxed), result) {
+                    if let Ok(mut batch) = window.lock() {
+                        record(&mut batch, event, &root);
                     }
                 }
-            },
-        )?;
+            }
+        };
 
-        debouncer
-            .watcher()
-            .watch(root, notify::RecursiveMode::Recursive)?;
+        let mut backend = match mode {
+            WatchMode::Native => Backend::Native(notify::recommended_watcher(handler)?),
+            WatchMode::Poll => {
+                let config = notify::Config::default().with_poll_interval(POLL_INTERVAL);
+                Backend::Poll(notify::PollWatcher::new(handler, config)?)
+            }
+        };
+        let (unwatched, partial_error) = if recursive {
+            watch_tree(backend.watcher(), root)?
+        } else {
+            backend.watcher().watch(root, RecursiveMode::NonRecursive)?;
+            (Vec::new(), None)
+        };
+
+        let mut forward = event_forwarder(
+            active.clone(),
+            respect_gitignore.clone(),
+            root.to_path_buf(),
+            ignore_patterns,
+            flood_threshold,
+            event_tx,
+        );
+        let flusher_stopped = stopped.clone();
+        std::thread::spawn(move || {
+            while !flusher_stopped.load(Ordering::Relaxed) {
+                std::thread::sleep(debounce_duration);
+                let events = match window.lock() {
+                    Ok(mut batch) => batch.take(),
+                    Err(_) => return,
+                };
+                if !events.is_empty() && !forward(events) {
+                    return;
+                }
+            }
+        });
 
         Ok(Self {
             active,
-            _debouncer: debouncer,
+            respect_gitignore,
+            stopped,
+            backend,
+            root: root.to_path_buf(),
+            recursive,
+            watched: HashSet::new(),
+            unwatched,
+            partial_error: partial_error.map(|e| classify(&e, root)),
         })
     }
 
+    /// Create a native watcher for `root` using the debounce interval from
+    /// `config` and the default ignore patterns and flood threshold.
+    pub fn from_config(
+        root: &Path,
+        config: &AppConfig,
+        event_tx: mpsc::UnboundedSender<Event>,
+    ) -> Result<Self, WatcherError> {
+        Self::start(root, config, WatchMode::Native, event_tx)
+    }
+
+    /// Like [`from_config`](Self::from_config) with an explicit mode.
+    /// Failures are classified, with inotify limits filled in.
+    pub fn start(
+        root: &Path,
+        config: &AppConfig,
+        mode: WatchMode,
+        event_tx: mpsc::UnboundedSender<Event>,
+    ) -> Result<Self, WatcherError> {
+        Self::new(
+            root,
+            Duration::from_millis(config.debounce_ms()),
+            default_ignore_patterns(),
+            DEFAULT_FLOOD_THRESHOLD,
+            mode,
+            config.recursive_watch(),
+            event_tx,
+        )
+        .map_err(|e| classify(&e, root))
+    }
+
     /// Pause event forwarding (watcher stays alive to avoid re-creating inotify watches).
     pub fn pause(&self) {
         self.active.store(false, Ordering::Relaxed);
@@ -119,6 +337,390 @@ impl FsWatcher {
     pub fn is_active(&self) -> bool {
         self.active.load(Ordering::Relaxed)
     }
+
+    /// Filter events by `.gitignore` rather than the ignore patterns.
+    pub fn set_respect_gitignore(&self, on: bool) {
+        self.respect_gitignore.store(on, Ordering::Relaxed);
+    }
+
+    /// Subtrees left unwatched by a partial initialization.
+    pub fn unwatched(&self) -> &[PathBuf] {
+        &self.unwatched
+    }
+
+    /// Why some subtrees are unwatched, if any are.
+    pub fn partial_error(&self) -> Option<&WatcherError> {
+        self.partial_error.as_ref()
+    }
+
+    /// Whether changes in `dir` are reported without a watch of its own.
+    fn covers(&self, dir: &Path) -> bool {
+        dir == self.root
+            || self.recursive
+                && dir.starts_with(&self.root)
+                && !self.unwatched.iter().any(|u| dir.starts_with(u))
+    }
+
+    /// Watch the entries of `dir`, an expanded directory, unless the root
+    /// watch already covers it.
+    pub fn watch_path(&mut self, dir: &Path) -> notify::Result<()> {
+        if self.covers(dir) || self.watched.contains(dir) {
+            return Ok(());
+        }
+        self.backend
+            .watcher()
+            .watch(dir, RecursiveMode::NonRecursive)?;
+        self.watched.insert(dir.to_path_buf());
+        Ok(())
+    }
+
+    /// Drop the watches of `dir` and of the directories below it.
+    pub fn unwatch_path(&mut self, dir: &Path) {
+        let below: Vec<PathBuf> = self
+            .watched
+            .iter()
+            .filter(|path| path.starts_with(dir))
+            .cloned()
+            .collect();
+        for path in below {
+            // Gone from disk, the watch went with it
+            let _ = self.backend.watcher().unwatch(&path);
+            self.watched.remove(&path);
+        }
+    }
+
+    /// Directories watched on their own.
+    pub fn watched_paths(&self) -> impl Iterator<Item = &PathBuf> {
+        self.watched.iter()
+    }
+
+    /// Watches handed to the backend: the root's plus one per directory
+    /// watched on its own.
+    pub fn watch_count(&self) -> usize {
+        1 + self.watched.len()
+    }
+
+    /// Whether only some expanded directories need watches of their own:
+    /// watching non-recursively, or partially.
+    pub fn needs_dir_watches(&self) -> bool {
+        !self.recursive || !self.unwatched.is_empty()
+    }
+}
+
+impl Drop for FsWatcher {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Fold one backend event into the events of the current window. A rename
+/// seen from both ends replaces the removal and creation recorded for its
+/// halves; a half seen alone (moved in or out of the tree) stays one.
+fn record(batch: &mut EventBatch, event: notify::Event, root: &Path) {
+    let no_paths = event.paths.is_empty();
+    let mut paths = event.paths.into_iter();
+    let recorded: Vec<FsEvent> = match event.kind {
+        EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(RenameMode::To)) => {
+            paths.map(FsEvent::Created).collect()
+        }
+        EventKind::Remove(_) | EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+            paths.map(FsEvent::Removed).collect()
+        }
+        EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => {
+            match (paths.next(), paths.next()) {
+                (Some(from), Some(to)) => {
+                    batch.remove(&FsEvent::Removed(from.clone()));
+                    batch.remove(&FsEvent::Created(to.clone()));
+                    vec![FsEvent::Renamed { from, to }]
+                }
+                (from, to) => from.into_iter().chain(to).map(FsEvent::Changed).collect(),
+            }
+        }
+        // Reads and opens change nothing; a close after writing does
+        EventKind::Access(AccessKind::Close(AccessMode::Write)) => {
+            paths.map(FsEvent::Changed).collect()
+        }
+        EventKind::Access(_) => Vec::new(),
+        // Without paths (a rescan), anything may have changed
+        _ if no_paths => vec![FsEvent::Changed(root.to_path_buf())],
+        _ => paths.map(FsEvent::Changed).collect(),
+    };
+    batch.extend(recorded);
+}
+
+/// Sends each window's events to `event_tx`, filtered; returns false once
+/// the receiver is gone.
+fn event_forwarder(
+    active: Arc<AtomicBool>,
+    respect_gitignore: Arc<AtomicBool>,
+    root_path: PathBuf,
+    ignore_patterns: Vec<String>,
+    flood_threshold: usize,
+    event_tx: mpsc::UnboundedSender<Event>,
+) -> impl FnMut(Vec<FsEvent>) -> bool + Send + 'static {
+    move |events: Vec<FsEvent>| {
+        // If paused, silently drop events
+        if !active.load(Ordering::Relaxed) {
+            return true;
+        }
+        // Read afresh each time, so edits to `.gitignore` apply
+        let gitignore = respect_gitignore
+            .load(Ordering::Relaxed)
+            .then(|| GitIgnore::around(&root_path).enter(&root_path));
+        let ignored = |p: &Path| match &gitignore {
+            Some(rules) => rules.is_ignored_below(&root_path, p),
+            None => should_ignore(p, &ignore_patterns),
+        };
+        let events: Vec<FsEvent> = events
+            .into_iter()
+            .filter_map(|event| match event {
+                FsEvent::Renamed { from, to } => match (ignored(&from), ignored(&to)) {
+                    (false, false) => Some(FsEvent::Renamed { from, to }),
+                    (false, true) => Some(FsEvent::Removed(from)),
+                    (true, false) => Some(FsEvent::Created(to)),
+                    (true, true) => None,
+                },
+                FsEvent::Created(ref p) | FsEvent::Removed(ref p) | FsEvent::Changed(ref p) => {
+                    (!ignored(p)).then_some(event)
+                }
+            })
+            .collect();
+
+        if events.is_empty() {
+            return true;
+        }
+
+        // Flood protection: if too many events, collapse to root refresh
+        let event = if events.len() > flood_threshold {
+            Event::FsChange(vec![root_path.clone()])
+        } else {
+            Event::FsEvents(events)
+        };
+        event_tx.send(event).is_ok()
+    }
+}
+
+/// Watch `root` recursively, falling back to the root alone plus each
+/// subdirectory separately. Returns the subdirectories that could not be
+/// watched and the first error among them, none when every one was; fails
+/// only if the root itself cannot be watched.
+pub fn watch_tree<W: Watcher + ?Sized>(
+    watcher: &mut W,
+    root: &Path,
+) -> notify::Result<(Vec<PathBuf>, Option<notify::Error>)> {
+    let whole = match watcher.watch(root, RecursiveMode::Recursive) {
+        Ok(()) => return Ok((Vec::new(), None)),
+        Err(e) => e,
+    };
+    // Drop whatever the failed recursive watch managed to add
+    let _ = watcher.unwatch(root);
+    if watcher.watch(root, RecursiveMode::NonRecursive).is_err() {
+        return Err(whole);
+    }
+
+    let mut subdirs: Vec<PathBuf> = match std::fs::read_dir(root) {
+        Ok(entries) => entries
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().map(|t| t.is_dir()).unwrap_or(false))
+            .map(|e| e.path())
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    subdirs.sort();
+
+    let mut unwatched = Vec::new();
+    let mut first_error = None;
+    for dir in subdirs {
+        if let Err(e) = watcher.watch(&dir, RecursiveMode::Recursive) {
+            let _ = watcher.unwatch(&dir);
+            first_error.get_or_insert(e);
+            unwatched.push(dir);
+        }
+    }
+    Ok((unwatched, first_error))
+}
+
+/// Classify `err`, adding the current and estimated inotify limits when
+/// it is a watch-limit error.
+fn classify(err: &notify::Error, root: &Path) -> WatcherError {
+    match WatcherError::from_notify(err, root) {
+        limit @ WatcherError::InotifyLimit { .. } => limit.with_limits(
+            inotify_max_user_watches(),
+            Some(count_directories(root, WATCH_ESTIMATE_CAP)),
+        ),
+        other => other,
+    }
+}
+
+/// Number of directories under `root` (itself included), up to `cap`.
+/// Recursive inotify watching needs one watch per directory.
+pub fn count_directories(root: &Path, cap: u64) -> u64 {
+    let mut count = 0;
+    let mut stack = vec![root.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        count += 1;
+        if count >= cap {
+            break;
+        }
+        if let Ok(entries) = std::fs::read_dir(&dir) {
+            stack.extend(
+                entries
+                    .filter_map(|e| e.ok())
+                    .filter(|e| e.file_type().map(|t| t.is_dir()).unwrap_or(false))
+                    .map(|e| e.path()),
+            );
+        }
+    }
+    count
+}
+
+/// Makes watchers; swapped out in tests to simulate failures.
+pub trait WatcherFactory {
+    fn start(
+        &self,
+        root: &Path,
+        config: &AppConfig,
+        mode: WatchMode,
+        event_tx: mpsc::UnboundedSender<Event>,
+    ) -> Result<FsWatcher, WatcherError>;
+}
+
+/// Creates real watchers via [`FsWatcher::start`].
+pub struct SystemWatcherFactory;
+
+impl WatcherFactory for SystemWatcherFactory {
+    fn start(
+        &self,
+        root: &Path,
+        config: &AppConfig,
+        mode: WatchMode,
+        event_tx: mpsc::UnboundedSender<Event>,
+    ) -> Result<FsWatcher, WatcherError> {
+        FsWatcher::start(root, config, mode, event_tx)
+    }
+}
+
+/// Owns the running watcher and the outcome of the last attempt to start
+/// one, so a failed start can be retried or switched to polling later.
+pub struct WatcherSupervisor {
+    factory: Box<dyn WatcherFactory>,
+    watcher: Option<FsWatcher>,
+    /// Mode used by the next start.
+    pub mode: WatchMode,
+    /// Why the last start failed.
+    pub error: Option<WatcherError>,
+    /// Events are filtered by `.gitignore` (see
+    /// [`FsWatcher::set_respect_gitignore`]).
+    pub respect_gitignore: bool,
+}
+
+impl WatcherSupervisor {
+    pub fn new(factory: Box<dyn WatcherFactory>) -> Self {
+        Self {
+            factory,
+            watcher: None,
+            mode: WatchMode::default(),
+            error: None,
+            respect_gitignore: false,
+        }
+    }
+
+    /// (Re)start watching `root` in the current mode, replacing any
+    /// running watcher.
+    pub fn start(
+        &mut self,
+        root: &Path,
+        config: &AppConfig,
+        event_tx: mpsc::UnboundedSender<Event>,
+    ) -> Result<(), WatcherError> {
+        // Drop the old watches before adding new ones
+        self.watcher = None;
+        match self.factory.start(root, config, self.mode, event_tx) {
+            Ok(watcher) => {
+                watcher.set_respect_gitignore(self.respect_gitignore);
+                self.watcher = Some(watcher);
+                self.error = None;
+                Ok(())
+            }
+            Err(e) => {
+                self.error = Some(e.clone());
+                Err(e)
+            }
+        }
+    }
+
+    /// Filter events by `.gitignore`, now and after restarts.
+    pub fn set_respect_gitignore(&mut self, on: bool) {
+        self.respect_gitignore = on;
+        if let Some(watcher) = &self.watcher {
+            watcher.set_respect_gitignore(on);
+        }
+    }
+
+    /// Stop watching.
+    pub fn stop(&mut self) {
+        self.watcher = None;
+    }
+
+    /// The running watcher, if any.
+    pub fn watcher(&self) -> Option<&FsWatcher> {
+        self.watcher.as_ref()
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.watcher.is_some()
+    }
+
+    /// Watches the running watcher holds (see [`FsWatcher::watch_count`]).
+    pub fn watch_count(&self) -> Option<usize> {
+        self.watcher.as_ref().map(FsWatcher::watch_count)
+    }
+
+    /// Watch exactly the directories in `expanded` (besides the root),
+    /// when the running watcher needs watches of its own for them.
+    /// Returns the first failure.
+    pub fn sync_dirs(&mut self, expanded: &HashSet<PathBuf>) -> Result<(), WatcherError> {
+        let Some(watcher) = self.watcher.as_mut().filter(|w| w.needs_dir_watches()) else {
+            return Ok(());
+        };
+        let collapsed: Vec<PathBuf> = watcher
+            .watched_paths()
+            .filter(|path| !expanded.contains(*path))
+            .cloned()
+            .collect();
+        for dir in collapsed {
+            watcher.unwatch_path(&dir);
+        }
+        let mut first_error = None;
+        for dir in expanded {
+            if let Err(e) = watcher.watch_path(dir) {
+                first_error.get_or_insert_with(|| WatcherError::from_notify(&e, dir));
+            }
+        }
+        first_error.map_or(Ok(()), Err)
+    }
+
+    /// Subtrees the running watcher could not watch.
+    pub fn unwatched(&self) -> &[PathBuf] {
+        self.watcher.as_ref().map(|w| w.unwatched()).unwrap_or(&[])
+    }
+
+    /// The error worth explaining: a failed start, or why a running
+    /// watcher is partial.
+    pub fn problem(&self) -> Option<&WatcherError> {
+        self.error
+            .as_ref()
+            .or_else(|| self.watcher.as_ref().and_then(|w| w.partial_error()))
+    }
+}
+
+/// [`DEFAULT_IGNORE_PATTERNS`] as owned strings; the fuzzy finder's index
+/// skips the same directories.
+pub fn default_ignore_patterns() -> Vec<String> {
+    DEFAULT_IGNORE_PATTERNS
+        .iter()
+        .map(|s| s.to_string())
+        .collect()
 }
 
 /// Check if a path should be ignored based on ignore patterns.
@@ -139,6 +741,19 @@ pub fn should_ignore(path: &Path, patterns: &[String]) -> bool {
     false
 }
 
+/// Current `fs.inotify.max_user_watches` value, or `None` where the
+/// setting does not exist (non-Linux) or cannot be read.
+pub fn inotify_max_user_watches() -> Option<u64> {
+    std::fs::read_to_string(INOTIFY_MAX_WATCHES_PATH)
+        .ok()
+        .and_then(|text| parse_inotify_limit(&text))
+}
+
+/// Parse the contents of the `max_user_watches` proc file.
+pub fn parse_inotify_limit(text: &str) -> Option<u64> {
+    text.trim().parse().ok()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -256,4 +871,277 @@ mod tests {
 
         assert_eq!(final_paths.len(), 50);
     }
+
+    #[test]
+    fn parse_inotify_limit_trims_newline() {
+        assert_eq!(parse_inotify_limit("8192\n"), Some(8192));
+        assert_eq!(parse_inotify_limit(" 524288 "), Some(524_288));
+        assert_eq!(parse_inotify_limit("lots"), None);
+    }
+
+    fn raw(kind: EventKind, paths: &[&str]) -> notify::Event {
+        paths.iter().fold(notify::Event::new(kind), |e, p| {
+            e.add_path(PathBuf::from(p))
+        })
+    }
+
+    #[test]
+    fn record_pairs_rename_halves_and_drops_noise() {
+        let root = Path::new("/p");
+        let mut events = EventBatch::default();
+        for event in [
+            raw(
+                EventKind::Create(notify::event::CreateKind::File),
+                &["/p/new"],
+            ),
+            raw(
+                EventKind::Modify(ModifyKind::Data(notify::event::DataChange::Any)),
+                &["/p/new"],
+            ),
+            raw(
+                EventKind::Access(AccessKind::Open(AccessMode::Read)),
+                &["/p/old"],
+            ),
+            raw(
+                EventKind::Modify(ModifyKind::Name(RenameMode::From)),
+                &["/p/old"],
+            ),
+            raw(
+                EventKind::Modify(ModifyKind::Name(RenameMode::To)),
+                &["/p/renamed"],
+            ),
+            raw(
+                EventKind::Modify(ModifyKind::Name(RenameMode::Both)),
+                &["/p/old", "/p/renamed"],
+            ),
+            raw(
+                EventKind::Remove(notify::event::RemoveKind::File),
+                &["/p/gone"],
+            ),
+            raw(
+                EventKind::Remove(notify::event::RemoveKind::File),
+                &["/p/gone"],
+            ),
+            raw(
+                EventKind::Access(AccessKind::Close(AccessMode::Write)),
+                &["/p/edited"],
+            ),
+            raw(EventKind::Other, &[]),
+        ] {
+            record(&mut events, event, root);
+        }
+        assert_eq!(
+            events.take(),
+            vec![
+                FsEvent::Created(PathBuf::from("/p/new")),
+                FsEvent::Renamed {
+                    from: PathBuf::from("/p/old"),
+                    to: PathBuf::from("/p/renamed"),
+                },
+                FsEvent::Removed(PathBuf::from("/p/gone")),
+                FsEvent::Changed(PathBuf::from("/p/edited")),
+                FsEvent::Changed(PathBuf::from("/p")),
+            ]
+        );
+    }
+
+    #[test]
+    fn record_keeps_unpaired_rename_halves() {
+        let mut events = EventBatch::default();
+        let out = raw(
+            EventKind::Modify(ModifyKind::Name(RenameMode::From)),
+            &["/p/out"],
+        );
+        let into = raw(
+            EventKind::Modify(ModifyKind::Name(RenameMode::To)),
+            &["/p/in"],
+        );
+        record(&mut events, out, Path::new("/p"));
+        record(&mut events, into, Path::new("/p"));
+        assert_eq!(
+            events.take(),
+            vec![
+                FsEvent::Removed(PathBuf::from("/p/out")),
+                FsEvent::Created(PathBuf::from("/p/in")),
+            ]
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn native_watcher_reports_a_rename() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        std::fs::create_dir(tmp.path().join("old")).unwrap();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let _watcher = FsWatcher::new(
+            tmp.path(),
+            Duration::from_millis(50),
+            Vec::new(),
+            DEFAULT_FLOOD_THRESHOLD,
+            WatchMode::Native,
+            true,
+            tx,
+        )
+        .expect("watcher");
+        std::fs::rename(tmp.path().join("old"), tmp.path().join("new")).unwrap();
+        let received = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .expect("no event")
+            .expect("closed");
+        let Event::FsEvents(events) = received else {
+            panic!("unexpected event");
+        };
+        assert_eq!(
+            events,
+            vec![FsEvent::Renamed {
+                from: tmp.path().join("old"),
+                to: tmp.path().join("new"),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn from_config_watches_directory() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let watcher = FsWatcher::from_config(tmp.path(), &AppConfig::default(), tx);
+        assert!(watcher.is_ok());
+    }
+
+    #[tokio::test]
+    async fn poll_mode_watches_directory() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let watcher = FsWatcher::start(tmp.path(), &AppConfig::default(), WatchMode::Poll, tx)
+            .expect("poll watcher");
+        assert!(watcher.unwatched().is_empty());
+    }
+
+    #[tokio::test]
+    async fn directories_get_watches_only_outside_the_recursive_watch() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let sub = tmp.path().join("sub");
+        std::fs::create_dir_all(sub.join("deeper")).unwrap();
+        let start = |recursive| {
+            let (tx, _rx) = mpsc::unbounded_channel();
+            let config = AppConfig {
+                watcher: crate::config::WatcherConfig {
+                    recursive: Some(recursive),
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+            FsWatcher::start(tmp.path(), &config, WatchMode::Native, tx).expect("watcher")
+        };
+
+        let mut recursive = start(true);
+        recursive.watch_path(&sub).unwrap();
+        assert_eq!(recursive.watch_count(), 1);
+
+        let mut shallow = start(false);
+        shallow.watch_path(&sub).unwrap();
+        shallow.watch_path(&sub.join("deeper")).unwrap();
+        shallow.watch_path(&sub).unwrap();
+        assert_eq!(shallow.watch_count(), 3);
+        shallow.unwatch_path(&sub);
+        assert_eq!(shallow.watch_count(), 1);
+    }
+
+    /// Refuses recursive watches of the root and any path named `locked`.
+    struct PickyWatcher {
+        root: PathBuf,
+        watched: Vec<(PathBuf, RecursiveMode)>,
+    }
+
+    impl Watcher for PickyWatcher {
+        fn new<F: notify::EventHandler>(_: F, _: notify::Config) -> notify::Result<Self> {
+            Ok(Self {
+                root: PathBuf::new(),
+                watched: Vec::new(),
+            })
+        }
+
+        fn watch(&mut self, path: &Path, mode: RecursiveMode) -> notify::Result<()> {
+            let refused =
+                (path == self.root && mode == RecursiveMode::Recursive) || path.ends_with("locked");
+            if refused {
+                return Err(notify::Error::io(std::io::Error::from_raw_os_error(28))
+                    .add_path(path.to_path_buf()));
+            }
+            self.watched.push((path.to_path_buf(), mode));
+            Ok(())
+        }
+
+        fn unwatch(&mut self, _: &Path) -> notify::Result<()> {
+            Ok(())
+        }
+
+        fn kind() -> notify::WatcherKind {
+            notify::WatcherKind::NullWatcher
+        }
+    }
+
+    #[test]
+    fn watch_tree_falls_back_to_subtrees() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        for dir in ["locked", "open", "other"] {
+            std::fs::create_dir(tmp.path().join(dir)).unwrap();
+        }
+        std::fs::write(tmp.path().join("file.txt"), "x").unwrap();
+        let mut watcher = PickyWatcher {
+            root: tmp.path().to_path_buf(),
+            watched: Vec::new(),
+        };
+
+        let (unwatched, error) = watch_tree(&mut watcher, tmp.path()).expect("partial watch");
+        assert_eq!(unwatched, vec![tmp.path().join("locked")]);
+        assert!(error.is_some());
+        assert_eq!(
+            watcher.watched,
+            vec![
+                (tmp.path().to_path_buf(), RecursiveMode::NonRecursive),
+                (tmp.path().join("open"), RecursiveMode::Recursive),
+                (tmp.path().join("other"), RecursiveMode::Recursive),
+            ]
+        );
+    }
+
+    #[test]
+    fn watch_tree_reports_no_error_when_every_subtree_is_watched() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        for dir in ["open", "other"] {
+            std::fs::create_dir(tmp.path().join(dir)).unwrap();
+        }
+        let mut watcher = PickyWatcher {
+            root: tmp.path().to_path_buf(),
+            watched: Vec::new(),
+        };
+
+        let (unwatched, error) = watch_tree(&mut watcher, tmp.path()).expect("fallback watch");
+        assert!(unwatched.is_empty());
+        assert!(error.is_none());
+        assert_eq!(watcher.watched.len(), 3);
+    }
+
+    #[test]
+    fn watch_tree_fails_when_root_cannot_be_watched() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let root = tmp.path().join("locked");
+        std::fs::create_dir(&root).unwrap();
+        let mut watcher = PickyWatcher {
+            root: root.clone(),
+            watched: Vec::new(),
+        };
+        assert!(watch_tree(&mut watcher, &root).is_err());
+    }
+
+    #[test]
+    fn count_directories_includes_root_and_respects_cap() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        std::fs::create_dir_all(tmp.path().join("a/b")).unwrap();
+        std::fs::create_dir(tmp.path().join("c")).unwrap();
+        std::fs::write(tmp.path().join("a/file"), "x").unwrap();
+        assert_eq!(count_directories(tmp.path(), 100), 4);
+        assert_eq!(count_directories(tmp.path(), 2), 2);
+    }
 }
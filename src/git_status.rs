@@ -0,0 +1,393 @@
+//! Git status markers in the tree.
+//!
+//! When the tree root is inside a git repository (and `tree.git_status` is
+//! on), `App::poll_git_status` runs `git status --porcelain -z` for the
+//! root on a `TaskKind::GitStatus` task and the tree colors each changed
+//! entry and follows its name with a [`GitStatus::marker`]. Directories
+//! holding changes are marked modified; entries inside an untracked
+//! directory are untracked too.
+//!
+//! A filesystem change or F5 asks for a new run through
+//! [`GitStatusState::request`]. Runs never overlap and start at most once
+//! per [`MIN_INTERVAL`], so a flood of watcher events ends in a single
+//! follow-up run. A root outside any repository (or without `git`) is
+//! remembered and not asked about again until the tree is re-rooted.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// Shortest time between the starts of two runs.
+pub const MIN_INTERVAL: Duration = Duration::from_secs(1);
+
+/// What git reports for an entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GitStatus {
+    /// Changed in the worktree or the index, renamed, or in conflict.
+    Modified,
+    /// Newly added to the index.
+    Added,
+    Untracked,
+    Deleted,
+}
+
+impl GitStatus {
+    /// One-character marker drawn after the name.
+    pub fn marker(self) -> char {
+        match self {
+            GitStatus::Modified => 'M',
+            GitStatus::Added => 'A',
+            GitStatus::Untracked => '?',
+            GitStatus::Deleted => 'D',
+        }
+    }
+
+    /// Status of a porcelain `XY` code; `None` for ignored entries.
+    fn from_code(x: u8, y: u8) -> Option<Self> {
+        match (x, y) {
+            (b'?', b'?') => Some(GitStatus::Untracked),
+            (b'!', b'!') => None,
+            (b'A', _) => Some(GitStatus::Added),
+            (b'D', _) | (_, b'D') => Some(GitStatus::Deleted),
+            _ => Some(GitStatus::Modified),
+        }
+    }
+}
+
+/// Statuses of the entries under a tree root.
+#[derive(Debug, Default)]
+pub struct GitStatuses {
+    /// Changed entries, untracked directories as a whole, and directories
+    /// holding changes (as modified).
+    map: HashMap<PathBuf, GitStatus>,
+    /// Bumped whenever the statuses change, so cached tree rows are redrawn.
+    pub generation: u64,
+}
+
+impl GitStatuses {
+    /// Status of `path`: its own, or untracked when it lies inside an
+    /// untracked directory.
+    pub fn get(&self, path: &Path) -> Option<GitStatus> {
+        if let Some(status) = self.map.get(path) {
+            return Some(*status);
+        }
+        path.ancestors()
+            .skip(1)
+            .any(|dir| self.map.get(dir) == Some(&GitStatus::Untracked))
+            .then_some(GitStatus::Untracked)
+    }
+
+    fn replace(&mut self, map: HashMap<PathBuf, GitStatus>) {
+        if map != self.map {
+            self.map = map;
+            self.generation += 1;
+        }
+    }
+}
+
+/// Parse `git status --porcelain -z` output into statuses keyed by path
+/// under `root`. Porcelain paths are relative to the repository's top
+/// level; `prefix` is the root's place within it (`rev-parse
+/// --show-prefix`). Directories between a change and the root are marked
+/// modified unless they have a status of their own.
+pub fn parse_porcelain(output: &[u8], prefix: &str, root: &Path) -> HashMap<PathBuf, GitStatus> {
+    let mut map = HashMap::new();
+    let mut records = output.split(|&b| b == 0);
+    while let Some(record) = records.next() {
+        if record.len() < 4 {
+            continue;
+        }
+        let (x, y) = (record[0], record[1]);
+        if matches!(x, b'R' | b'C') {
+            // The source path follows as its own record
+            records.next();
+        }
+        let Some(status) = GitStatus::from_code(x, y) else {
+            continue;
+        };
+        let path = String::from_utf8_lossy(&record[3..]);
+        let Some(rest) = path.strip_prefix(prefix) else {
+            continue;
+        };
+        let rest = rest.trim_end_matches('/');
+        if rest.is_empty() {
+            continue;
+        }
+        map.insert(root.join(rest), status);
+    }
+
+    let changed: Vec<PathBuf> = map.keys().cloned().collect();
+    for path in changed {
+        for dir in path.ancestors().skip(1) {
+            if dir == root || !dir.starts_with(root) {
+                break;
+            }
+            map.entry(dir.to_path_buf()).or_insert(GitStatus::Modified);
+        }
+    }
+    map
+}
+
+/// Result of a background status run.
+#[derive(Debug)]
+pub struct GitStatusResult {
+    /// Tree root the run was for.
+    pub root: PathBuf,
+    /// `None` when the root is outside a repository or git failed.
+    pub statuses: Option<HashMap<PathBuf, GitStatus>>,
+}
+
+/// Run git for `root` (blocking; call from `spawn_blocking`).
+pub fn fetch(root: &Path) -> GitStatusResult {
+    let statuses = git(root, &["rev-parse", "--show-prefix"]).and_then(|prefix| {
+        let prefix = String::from_utf8_lossy(&prefix).trim_end().to_string();
+        let output = git(
+            root,
+            &[
+                "status",
+                "--porcelain",
+                "-z",
+                "--untracked-files=normal",
+                "--",
+                ".",
+            ],
+        )?;
+        Some(parse_porcelain(&output, &prefix, root))
+    });
+    GitStatusResult {
+        root: root.to_path_buf(),
+        statuses,
+    }
+}
+
+/// Stdout of `git args` run in `dir`, if it succeeds. Optional locks are
+/// off so the run doesn't rewrite the index and wake the watcher.
+fn git(dir: &Path, args: &[&str]) -> Option<Vec<u8>> {
+    let output = Command::new("git")
+        .arg("--no-optional-locks")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    output.status.success().then_some(output.stdout)
+}
+
+/// Git status held by the app, and when to ask git again.
+#[derive(Debug, Default)]
+pub struct GitStatusState {
+    /// `tree.git_status`.
+    pub enabled: bool,
+    pub statuses: GitStatuses,
+    /// Root the statuses are for.
+    root: Option<PathBuf>,
+    /// A run was asked for since the last one started.
+    dirty: bool,
+    in_flight: bool,
+    last_start: Option<Instant>,
+    /// The root is outside any repository.
+    not_a_repo: bool,
+}
+
+impl GitStatusState {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            dirty: true,
+            ..Default::default()
+        }
+    }
+
+    /// Ask for a run once the current one, if any, is done.
+    pub fn request(&mut self) {
+        self.dirty = true;
+    }
+
+    /// Whether a run for `root` should start at `now`; if so it counts as
+    /// started. A new root drops the statuses of the old one.
+    pub fn start(&mut self, root: &Path, now: Instant) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        if self.root.as_deref() != Some(root) {
+            self.root = Some(root.to_path_buf());
+            self.statuses.replace(HashMap::new());
+            self.not_a_repo = false;
+            self.dirty = true;
+        }
+        let too_soon = self
+            .last_start
+            .is_some_and(|last| now.duration_since(last) < MIN_INTERVAL);
+        if !self.dirty || self.in_flight || self.not_a_repo || too_soon {
+            return false;
+        }
+        self.dirty = false;
+        self.in_flight = true;
+        self.last_start = Some(now);
+        true
+    }
+
+    /// Take in a finished run; one for a root since left is dropped.
+    pub fn finish(&mut self, result: GitStatusResult) {
+        self.in_flight = false;
+        if self.root.as_ref() != Some(&result.root) {
+            return;
+        }
+        match result.statuses {
+            Some(map) => self.statuses.replace(map),
+            None => {
+                self.not_a_repo = true;
+                self.statuses.replace(HashMap::new());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_porcelain_records() {
+        let output = b" M src/main.rs\0A  src/new.rs\0?? notes/\0 D old.txt\0\
+R  src/renamed.rs\0src/before.rs\0!! target/\0MM README.md\0";
+        let root = Path::new("/repo");
+        let map = parse_porcelain(output, "", root);
+        let get = |p: &str| map.get(&root.join(p)).copied();
+        assert_eq!(get("src/main.rs"), Some(GitStatus::Modified));
+        assert_eq!(get("src/new.rs"), Some(GitStatus::Added));
+        assert_eq!(get("notes"), Some(GitStatus::Untracked));
+        assert_eq!(get("old.txt"), Some(GitStatus::Deleted));
+        assert_eq!(get("src/renamed.rs"), Some(GitStatus::Modified));
+        assert_eq!(get("src/before.rs"), None);
+        assert_eq!(get("target"), None);
+        assert_eq!(get("README.md"), Some(GitStatus::Modified));
+        // The directory holding changes, but not the root
+        assert_eq!(get("src"), Some(GitStatus::Modified));
+        assert_eq!(map.get(root), None);
+        assert_eq!(map.len(), 7);
+    }
+
+    #[test]
+    fn paths_are_taken_relative_to_the_root_prefix() {
+        let output = b" M app/src/lib.rs\0?? docs/x.md\0";
+        let root = Path::new("/work/app");
+        let map = parse_porcelain(output, "app/", root);
+        assert_eq!(
+            map.get(Path::new("/work/app/src/lib.rs")),
+            Some(&GitStatus::Modified)
+        );
+        assert_eq!(map.len(), 2, "{:?}", map);
+    }
+
+    #[test]
+    fn entries_inside_untracked_directories_are_untracked() {
+        let mut statuses = GitStatuses::default();
+        statuses.replace(parse_porcelain(
+            b"?? new/\0 M a/b.rs\0",
+            "",
+            Path::new("/r"),
+        ));
+        let get = |p: &str| statuses.get(Path::new(p));
+        assert_eq!(get("/r/new/deep/file"), Some(GitStatus::Untracked));
+        assert_eq!(get("/r/a"), Some(GitStatus::Modified));
+        // A directory holding changes passes nothing down
+        assert_eq!(get("/r/a/other.rs"), None);
+        assert_eq!(get("/r/clean.rs"), None);
+        assert_eq!(GitStatus::Untracked.marker(), '?');
+    }
+
+    #[test]
+    fn runs_never_overlap_and_are_spaced_out() {
+        let root = Path::new("/r");
+        let mut state = GitStatusState::new(true);
+        let t0 = Instant::now();
+        assert!(state.start(root, t0));
+        // A flood of requests while running waits for the run to finish
+        for _ in 0..50 {
+            state.request();
+            assert!(!state.start(root, t0 + MIN_INTERVAL * 2));
+        }
+        let map = HashMap::from([(root.join("a"), GitStatus::Modified)]);
+        let generation = state.statuses.generation;
+        state.finish(GitStatusResult {
+            root: root.to_path_buf(),
+            statuses: Some(map),
+        });
+        assert!(state.statuses.generation > generation);
+        assert!(!state.start(root, t0 + MIN_INTERVAL / 2));
+        assert!(state.start(root, t0 + MIN_INTERVAL));
+        // Nothing asked for since
+        state.finish(GitStatusResult {
+            root: root.to_path_buf(),
+            statuses: Some(HashMap::new()),
+        });
+        assert!(!state.start(root, t0 + MIN_INTERVAL * 5));
+    }
+
+    #[test]
+    fn outside_a_repository_or_disabled_nothing_runs() {
+        let root = Path::new("/r");
+        let mut state = GitStatusState::new(true);
+        assert!(state.start(root, Instant::now()));
+        state.finish(GitStatusResult {
+            root: root.to_path_buf(),
+            statuses: None,
+        });
+        state.request();
+        assert!(!state.start(root, Instant::now() + MIN_INTERVAL));
+        // Until re-rooted
+        assert!(state.start(Path::new("/other"), Instant::now() + MIN_INTERVAL));
+
+        let mut off = GitStatusState::new(false);
+        assert!(!off.start(root, Instant::now()));
+    }
+
+    #[test]
+    fn results_for_a_previous_root_are_dropped() {
+        let mut state = GitStatusState::new(true);
+        let t0 = Instant::now();
+        assert!(state.start(Path::new("/a"), t0));
+        assert!(!state.start(Path::new("/b"), t0));
+        state.finish(GitStatusResult {
+            root: PathBuf::from("/a"),
+            statuses: Some(HashMap::from([(PathBuf::from("/a/x"), GitStatus::Added)])),
+        });
+        assert_eq!(state.statuses.get(Path::new("/a/x")), None);
+        assert!(state.start(Path::new("/b"), t0 + MIN_INTERVAL));
+    }
+
+    #[test]
+    fn fetch_reads_a_real_repository() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let root = dir.path();
+        if git(root, &["init", "-q"]).is_none() {
+            return; // No git here
+        }
+        std::fs::create_dir(root.join("sub")).unwrap();
+        std::fs::write(root.join("sub/staged.txt"), "a").unwrap();
+        std::fs::write(root.join("loose.txt"), "b").unwrap();
+        git(root, &["add", "sub/staged.txt"]).unwrap();
+
+        let map = fetch(root).statuses.unwrap();
+        assert_eq!(
+            map.get(&root.join("sub/staged.txt")),
+            Some(&GitStatus::Added)
+        );
+        assert_eq!(map.get(&root.join("sub")), Some(&GitStatus::Modified));
+        assert_eq!(
+            map.get(&root.join("loose.txt")),
+            Some(&GitStatus::Untracked)
+        );
+
+        // From a subdirectory, only what lies below it
+        let sub = fetch(&root.join("sub")).statuses.unwrap();
+        assert_eq!(
+            sub.keys().collect::<Vec<_>>(),
+            [&root.join("sub/staged.txt")]
+        );
+    }
+}
@@ -0,0 +1,458 @@
+//! Content search across the tree (`Ctrl+Shift+F`).
+//!
+//! [`search`] walks the files under the root on a `TaskKind::Grep` task,
+//! in name order and no deeper than the tree goes. It skips hidden entries
+//! unless the tree shows them, binary files (by `is_binary_file`) and files
+//! over the size cap. Matching lines stream back as `Event::GrepResults`
+//! batches, at most every [`FLUSH_INTERVAL`], so the overlay fills in while
+//! the scan runs; the scan stops at the result cap. A search starts once
+//! the query has rested for [`SETTLE`], or on Enter. Each search has a job
+//! id, and batches from a search that was replaced or cancelled are
+//! dropped.
+
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::pattern::Pattern;
+use crate::preview_content;
+
+/// Quiet time after the last edit of the query before it is searched.
+pub const SETTLE: Duration = Duration::from_millis(300);
+
+/// Longest wait between batches while a search runs.
+pub const FLUSH_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Characters kept before a match that starts far into its line.
+const SNIPPET_LEAD: usize = 24;
+
+/// Longest line snippet kept for a match, in characters.
+const MAX_SNIPPET_CHARS: usize = 200;
+
+/// Limits of one search.
+#[derive(Debug, Clone, Copy)]
+pub struct GrepOptions {
+    pub max_file_bytes: u64,
+    pub max_results: usize,
+    pub max_depth: usize,
+    pub show_hidden: bool,
+}
+
+/// A matching line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GrepMatch {
+    pub path: PathBuf,
+    /// 1-based line number.
+    pub line: usize,
+    /// The line, trimmed around the match when it is long.
+    pub text: String,
+    /// Byte range of the match in `text`.
+    pub range: Range<usize>,
+}
+
+/// Counts for the overlay's status row.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GrepStats {
+    /// Files searched so far.
+    pub files: usize,
+    /// Binary files and files over the size cap.
+    pub skipped: usize,
+    /// Stopped at the result cap.
+    pub capped: bool,
+}
+
+/// Results since the previous batch of a search.
+#[derive(Debug)]
+pub struct GrepBatch {
+    pub job: u64,
+    pub matches: Vec<GrepMatch>,
+    pub stats: GrepStats,
+    /// The last batch of the search.
+    pub done: bool,
+}
+
+/// Search the files under `root` for `pattern`, handing results to `emit`
+/// as they are found. Stops without a final batch once `cancel` is raised.
+pub fn search(
+    root: &Path,
+    pattern: &Pattern,
+    options: &GrepOptions,
+    job: u64,
+    cancel: &AtomicBool,
+    emit: &mut dyn FnMut(GrepBatch),
+) {
+    let mut stats = GrepStats::default();
+    let mut pending: Vec<GrepMatch> = Vec::new();
+    let mut found = 0;
+    let mut flushed = Instant::now();
+    let mut stack = vec![(root.to_path_buf(), 0)];
+
+    'walk: while let Some((dir, depth)) = stack.pop() {
+        let Ok(read) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        let mut entries: Vec<(String, PathBuf, bool)> = read
+            .flatten()
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().to_string();
+                let is_dir = entry.file_type().ok()?.is_dir();
+                (options.show_hidden || !name.starts_with('.'))
+                    .then(|| (name, entry.path(), is_dir))
+            })
+            .collect();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+        // Files first, then subdirectories in name order
+        let mut subdirs = Vec::new();
+        for (_, path, is_dir) in entries {
+            if cancel.load(Ordering::Relaxed) {
+                return;
+            }
+            if is_dir {
+                if depth + 1 < options.max_depth {
+                    subdirs.push((path, depth + 1));
+                }
+                continue;
+            }
+            let too_big =
+                std::fs::metadata(&path).map_or(true, |m| m.len() > options.max_file_bytes);
+            if too_big || preview_content::is_binary_file(&path) {
+                stats.skipped += 1;
+                continue;
+            }
+            stats.files += 1;
+            let Ok(bytes) = std::fs::read(&path) else {
+                continue;
+            };
+            for (i, line) in String::from_utf8_lossy(&bytes).lines().enumerate() {
+                let Some(range) = pattern.find(line) else {
+                    continue;
+                };
+                let (text, range) = snippet(line, range);
+                pending.push(GrepMatch {
+                    path: path.clone(),
+                    line: i + 1,
+                    text,
+                    range,
+                });
+                found += 1;
+                if found >= options.max_results {
+                    stats.capped = true;
+                    break 'walk;
+                }
+            }
+            if flushed.elapsed() >= FLUSH_INTERVAL {
+                emit(GrepBatch {
+                    job,
+                    matches: std::mem::take(&mut pending),
+                    stats,
+                    done: false,
+                });
+                flushed = Instant::now();
+            }
+        }
+        stack.extend(subdirs.into_iter().rev());
+    }
+    if cancel.load(Ordering::Relaxed) {
+        return;
+    }
+    emit(GrepBatch {
+        job,
+        matches: pending,
+        stats,
+        done: true,
+    });
+}
+
+/// `line` cut to at most [`MAX_SNIPPET_CHARS`], starting a little before
+/// the match when it lies far in, with tabs as spaces; and where the match
+/// lands in it.
+fn snippet(line: &str, range: Range<usize>) -> (String, Range<usize>) {
+    let lead = line[..range.start].chars().count();
+    let skip = lead.saturating_sub(SNIPPET_LEAD);
+    let start = line.char_indices().nth(skip).map_or(line.len(), |(i, _)| i);
+    let prefix = if skip > 0 { "…" } else { "" };
+    let kept: String = line[start..].chars().take(MAX_SNIPPET_CHARS).collect();
+    let text = format!("{}{}", prefix, kept.replace('\t', " "))
+        .trim_end()
+        .to_string();
+    let shift = prefix.len() + range.start - start;
+    let end = (shift + range.len()).min(text.len());
+    (text, shift.min(end)..end)
+}
+
+/// The content search overlay: query, results and the running search.
+#[derive(Debug, Default)]
+pub struct GrepState {
+    pub query: String,
+    /// Byte position of the cursor in the query.
+    pub cursor_position: usize,
+    /// Match the query as a regex instead of a substring.
+    pub regex: bool,
+    pub results: Vec<GrepMatch>,
+    pub selected_index: usize,
+    /// Query and mode the results are for.
+    pub searched: Option<(String, bool)>,
+    pub stats: GrepStats,
+    /// Why the query could not be searched.
+    pub error: Option<String>,
+    running: Option<(u64, Arc<AtomicBool>)>,
+    last_job: u64,
+}
+
+impl GrepState {
+    /// Whether the results are for something other than the query.
+    pub fn is_stale(&self) -> bool {
+        self.searched
+            .as_ref()
+            .is_none_or(|(query, regex)| *query != self.query || *regex != self.regex)
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.is_some()
+    }
+
+    /// Id for the next search, carried by its batches.
+    pub fn next_job(&mut self) -> u64 {
+        self.last_job += 1;
+        self.last_job
+    }
+
+    /// Start over for the current query with search `job`, cancelling the
+    /// running one.
+    pub fn begin(&mut self, job: u64, cancel: Arc<AtomicBool>) {
+        self.cancel();
+        self.running = Some((job, cancel));
+        self.reset_results();
+    }
+
+    /// Record the current query as one that cannot be searched.
+    pub fn fail(&mut self, error: String) {
+        self.cancel();
+        self.reset_results();
+        self.error = Some(error);
+    }
+
+    fn reset_results(&mut self) {
+        self.results.clear();
+        self.selected_index = 0;
+        self.stats = GrepStats::default();
+        self.error = None;
+        self.searched = Some((self.query.clone(), self.regex));
+    }
+
+    /// Cancel the running search. Its partial results stay, but count as
+    /// stale, so the query is searched again.
+    pub fn cancel(&mut self) {
+        if let Some((_, cancel)) = self.running.take() {
+            cancel.store(true, Ordering::Relaxed);
+            self.searched = None;
+        }
+    }
+
+    /// Add a batch of the running search; false for a batch from another.
+    pub fn accept(&mut self, batch: GrepBatch) -> bool {
+        if self
+            .running
+            .as_ref()
+            .is_none_or(|(job, _)| *job != batch.job)
+        {
+            return false;
+        }
+        self.results.extend(batch.matches);
+        self.stats = batch.stats;
+        if batch.done {
+            self.running = None;
+        }
+        true
+    }
+
+    pub fn select_next(&mut self) {
+        if self.selected_index + 1 < self.results.len() {
+            self.selected_index += 1;
+        }
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected_index = self.selected_index.saturating_sub(1);
+    }
+
+    pub fn selected(&self) -> Option<&GrepMatch> {
+        self.results.get(self.selected_index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn options() -> GrepOptions {
+        GrepOptions {
+            max_file_bytes: 1024,
+            max_results: 100,
+            max_depth: 8,
+            show_hidden: false,
+        }
+    }
+
+    fn run(root: &Path, query: &str, regex: bool, options: &GrepOptions) -> Vec<GrepBatch> {
+        let pattern = Pattern::new(query, regex).unwrap();
+        let mut batches = Vec::new();
+        search(
+            root,
+            &pattern,
+            options,
+            7,
+            &AtomicBool::new(false),
+            &mut |b| batches.push(b),
+        );
+        batches
+    }
+
+    fn lines(batches: &[GrepBatch], root: &Path) -> Vec<String> {
+        batches
+            .iter()
+            .flat_map(|b| &b.matches)
+            .map(|m| {
+                let path = m.path.strip_prefix(root).unwrap().display();
+                format!("{}:{}: {}", path, m.line, &m.text[m.range.clone()])
+            })
+            .collect()
+    }
+
+    fn sample() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("src/deep")).unwrap();
+        fs::write(dir.path().join("b.txt"), "todo: b\n").unwrap();
+        fs::write(dir.path().join("a.txt"), "one\nTODO two\n\ttodo three\n").unwrap();
+        fs::write(dir.path().join("src/lib.rs"), "// todo lib\n").unwrap();
+        fs::write(dir.path().join("src/deep/x.rs"), "todo deep\n").unwrap();
+        fs::write(dir.path().join(".hidden"), "todo hidden\n").unwrap();
+        fs::write(dir.path().join("blob.bin"), b"todo\0\0").unwrap();
+        fs::write(
+            dir.path().join("big.txt"),
+            format!("todo{}", "x".repeat(2000)),
+        )
+        .unwrap();
+        dir
+    }
+
+    #[test]
+    fn finds_lines_in_name_order_skipping_what_it_should() {
+        let dir = sample();
+        let batches = run(dir.path(), "todo", false, &options());
+        assert_eq!(
+            lines(&batches, dir.path()),
+            [
+                "a.txt:2: TODO",
+                "a.txt:3: todo",
+                "b.txt:1: todo",
+                "src/lib.rs:1: todo",
+                "src/deep/x.rs:1: todo",
+            ]
+        );
+        let last = batches.last().unwrap();
+        assert!(last.done && last.job == 7);
+        // blob.bin is binary, big.txt over the cap
+        assert_eq!(last.stats.skipped, 2);
+        assert!(!last.stats.capped);
+    }
+
+    #[test]
+    fn limits_depth_and_results_and_can_show_hidden() {
+        let dir = sample();
+        let shallow = GrepOptions {
+            max_depth: 2,
+            show_hidden: true,
+            ..options()
+        };
+        let found = lines(&run(dir.path(), "todo", false, &shallow), dir.path());
+        assert!(found.contains(&".hidden:1: todo".to_string()));
+        assert!(!found.iter().any(|l| l.starts_with("src/deep")));
+
+        let capped = GrepOptions {
+            max_results: 2,
+            ..options()
+        };
+        let batches = run(dir.path(), "todo", false, &capped);
+        assert_eq!(lines(&batches, dir.path()).len(), 2);
+        assert!(batches.last().unwrap().stats.capped);
+
+        let regex = lines(
+            &run(dir.path(), r"^\s*todo \w+$", true, &options()),
+            dir.path(),
+        );
+        // Smart case: a lowercase pattern also matches "TODO"
+        assert_eq!(
+            regex,
+            [
+                "a.txt:2: TODO two",
+                "a.txt:3:  todo three",
+                "src/deep/x.rs:1: todo deep"
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn fifos_are_skipped_unread() {
+        let dir = sample();
+        if !crate::fs::tree::mkfifo(&dir.path().join("pipe")) {
+            return;
+        }
+        // Reading the FIFO would block with no writer
+        let batches = run(dir.path(), "todo", false, &options());
+        assert_eq!(lines(&batches, dir.path()).len(), 5);
+        assert_eq!(batches.last().unwrap().stats.skipped, 3);
+    }
+
+    #[test]
+    fn long_lines_are_cut_around_the_match() {
+        let line = format!("{}needle{}", "a".repeat(100), "b".repeat(300));
+        let (text, range) = snippet(&line, 100..106);
+        assert_eq!(&text[range], "needle");
+        assert!(text.starts_with('…'));
+        assert_eq!(text.chars().count(), 1 + MAX_SNIPPET_CHARS);
+    }
+
+    #[test]
+    fn stale_and_foreign_batches() {
+        let mut state = GrepState {
+            query: "x".to_string(),
+            ..Default::default()
+        };
+        assert!(state.is_stale());
+        let first = Arc::new(AtomicBool::new(false));
+        let old = state.next_job();
+        state.begin(old, Arc::clone(&first));
+        assert!(!state.is_stale());
+        let job = state.next_job();
+        state.begin(job, Arc::new(AtomicBool::new(false)));
+        assert!(first.load(Ordering::Relaxed));
+
+        let batch = |job, done| GrepBatch {
+            job,
+            matches: vec![GrepMatch {
+                path: PathBuf::from("/a"),
+                line: 1,
+                text: "x".to_string(),
+                range: 0..1,
+            }],
+            stats: GrepStats::default(),
+            done,
+        };
+        assert!(!state.accept(batch(old, true)));
+        assert!(state.accept(batch(job, false)));
+        assert!(state.is_running());
+        assert!(state.accept(batch(job, true)));
+        assert!(!state.is_running());
+        assert_eq!(state.results.len(), 2);
+
+        state.query.push('y');
+        assert!(state.is_stale());
+    }
+}
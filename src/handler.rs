@@ -1,13 +1,22 @@
 use crossterm::event::{
     KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
 };
+use std::time::Instant;
+
 use tokio::sync::mpsc;
 
-use crate::app::{App, AppMode, DialogKind, FocusedPanel};
+use crate::app::{App, AppMode, DialogKind, FocusedPanel, Sizing, DELETE_LIST_ROWS};
 use crate::components::help::HelpOverlay;
 use crate::event::Event;
+use crate::fs::name_check;
 use crate::fs::operations;
+use crate::fs::overwrite::ConflictChoice;
+use crate::fs::retry::RetryOp;
 use crate::fs::tree::NodeType;
+use crate::fs::watcher::WatchMode;
+use crate::paste_guard;
+use crate::path_display::PathStyle;
+use crate::permissions::PermissionsField;
 
 /// Handle a mouse event.
 pub fn handle_mouse_event(
@@ -15,7 +24,23 @@ pub fn handle_mouse_event(
     mouse: MouseEvent,
     _event_tx: &mpsc::UnboundedSender<Event>,
 ) {
+    // Panels moved since the areas were stored: the click would land on
+    // whatever is now under the old rects. The next frame restores them.
+    if !app.areas_current() {
+        return;
+    }
+
+    // The watcher glyph in the tree title explains itself, editing or not
+    if mouse.kind == MouseEventKind::Down(MouseButton::Left)
+        && matches!(app.mode, AppMode::Normal | AppMode::Edit)
+        && is_in_rect(mouse.column, mouse.row, app.watcher_glyph_area)
+    {
+        app.explain_watcher_state();
+        return;
+    }
+
     // Handle mouse in Edit mode for editor cursor positioning
+    #[cfg(feature = "editor")]
     if app.mode == AppMode::Edit {
         handle_editor_mouse(app, mouse);
         return;
@@ -38,10 +63,12 @@ pub fn handle_mouse_event(
 
                 // Map click to tree item index
                 // Inner area: subtract border (1 top, 1 left)
-                let inner_y = row.saturating_sub(app.tree_area.y + 1);
-                let clicked_index = app.tree_state.scroll_offset + inner_y as usize;
+                let inner_y = row.saturating_sub(app.tree_area.y + 1) as usize;
+                let clicked_index = app.tree_state.scroll_offset + inner_y;
 
-                if clicked_index < app.tree_state.flat_items.len() {
+                // Only rows that were drawn: below the last one is empty space
+                if inner_y < app.tree_rows_drawn && clicked_index < app.tree_state.flat_items.len()
+                {
                     let already_selected = app.tree_state.selected_index == clicked_index;
                     app.tree_state.selected_index = clicked_index;
                     app.last_previewed_index = None; // Force preview update
@@ -60,7 +87,7 @@ pub fn handle_mouse_event(
                                         app.invalidate_search_cache();
                                     }
                                 }
-                            } else if item.node_type == NodeType::Directory {
+                            } else if item.is_expandable() {
                                 if item.is_expanded {
                                     app.collapse_selected();
                                 } else {
@@ -74,8 +101,12 @@ pub fn handle_mouse_event(
                 // Switch focus to preview
                 app.focused_panel = FocusedPanel::Preview;
             } else if app.terminal_state.visible && is_in_rect(col, row, app.terminal_area) {
-                // Switch focus to terminal
-                app.focused_panel = FocusedPanel::Terminal;
+                if mouse.modifiers.contains(KeyModifiers::CONTROL) {
+                    app.follow_terminal_link_at(col, row);
+                } else {
+                    // Switch focus to terminal
+                    app.focused_panel = FocusedPanel::Terminal;
+                }
             }
         }
         MouseEventKind::ScrollUp => {
@@ -95,8 +126,7 @@ pub fn handle_mouse_event(
                 app.focused_panel = FocusedPanel::Preview;
                 app.preview_scroll_down();
             } else if app.terminal_state.visible && is_in_rect(col, row, app.terminal_area) {
-                app.terminal_state.scroll_offset =
-                    app.terminal_state.scroll_offset.saturating_sub(1);
+                app.terminal_state.scroll_down(1);
             }
         }
         _ => {}
@@ -104,6 +134,7 @@ pub fn handle_mouse_event(
 }
 
 /// Handle mouse events when in editor mode.
+#[cfg(feature = "editor")]
 fn handle_editor_mouse(app: &mut App, mouse: MouseEvent) {
     let col = mouse.column;
     let row = mouse.row;
@@ -164,6 +195,7 @@ fn handle_editor_mouse(app: &mut App, mouse: MouseEvent) {
 }
 
 /// Convert mouse screen coordinates to editor (line, col) position.
+#[cfg(feature = "editor")]
 fn mouse_to_editor_pos(
     editor: &crate::editor::EditorState,
     preview_area: ratatui::layout::Rect,
@@ -178,13 +210,13 @@ fn mouse_to_editor_pos(
     let click_row = row.saturating_sub(inner_y) as usize;
     let target_line = editor.scroll_offset + click_row;
 
-    let target_col = if col >= code_x {
+    let display_col = if col >= code_x {
         (col - code_x) as usize
     } else {
         0
     };
 
-    (target_line, target_col)
+    (target_line, editor.col_at_display(target_line, display_col))
 }
 
 /// Check if a position (col, row) is inside a Rect.
@@ -194,23 +226,109 @@ fn is_in_rect(col: u16, row: u16, rect: ratatui::layout::Rect) -> bool {
 
 /// Handle a key event and dispatch to the appropriate app method.
 pub fn handle_key_event(app: &mut App, key: KeyEvent, event_tx: &mpsc::UnboundedSender<Event>) {
+    handle_key_event_at(app, key, Instant::now(), event_tx);
+}
+
+/// [`handle_key_event`] for a key that arrived at `now`, which decides
+/// whether a held navigation key is accelerated.
+fn handle_key_event_at(
+    app: &mut App,
+    key: KeyEvent,
+    now: Instant,
+    event_tx: &mpsc::UnboundedSender<Event>,
+) {
     // Ignore key release events to prevent duplicate actions from press/release pairs.
     if key.kind == KeyEventKind::Release {
         return;
     }
+    app.note_key(&key, now);
+
+    // F1: context help from the panels, the editor and dialogs (not while
+    // a file operation is running)
+    if key.code == KeyCode::F(1)
+        && matches!(
+            app.mode,
+            AppMode::Normal | AppMode::Edit | AppMode::Dialog(_)
+        )
+        && !matches!(app.mode, AppMode::Dialog(DialogKind::Progress { .. }))
+    {
+        app.open_help();
+        return;
+    }
 
     match &app.mode {
         AppMode::Normal => handle_normal_mode(app, key, event_tx),
-        AppMode::Dialog(_) => handle_dialog_mode(app, key),
-        AppMode::Search => handle_search_mode(app, key),
+        AppMode::Dialog(_) => handle_dialog_mode(app, key, event_tx),
+        AppMode::Search => handle_search_mode(app, key, event_tx),
         AppMode::SearchAction => handle_search_action_mode(app, key, event_tx),
+        AppMode::Grep => handle_grep_mode(app, key, event_tx),
         AppMode::Filter => handle_filter_mode(app, key),
         AppMode::Help => handle_help_mode(app, key),
+        #[cfg(feature = "editor")]
         AppMode::Edit => handle_editor_keys(app, key),
+        #[cfg(not(feature = "editor"))]
+        AppMode::Edit => {}
+        AppMode::SendTo => handle_send_to_mode(app, key, event_tx),
+        AppMode::Places => handle_places_mode(app, key),
+        AppMode::Bookmarks => handle_bookmarks_mode(app, key),
+        AppMode::PreviewSearch => handle_preview_search_mode(app, key),
+    }
+}
+
+/// Handle a bracketed paste. One-line inputs take it flattened and never
+/// confirm from it; the editor and terminal take it as typed; elsewhere it
+/// is ignored rather than run as a string of commands.
+pub fn handle_paste_event(app: &mut App, text: &str) {
+    match &app.mode {
+        AppMode::Dialog(kind) if kind.takes_text() => app.dialog_paste(text),
+        AppMode::Search => app.search_paste(text),
+        AppMode::Grep => app.grep_paste(text),
+        AppMode::Filter => app.filter_paste(text),
+        AppMode::PreviewSearch => app.preview_search_paste(text),
+        #[cfg(feature = "editor")]
+        AppMode::Edit => {
+            if let Some(editor) = app.editor_state.as_mut() {
+                for c in text.chars() {
+                    match c {
+                        '\n' => editor.insert_newline(),
+                        '\r' => {}
+                        c => editor.insert_char(c),
+                    }
+                }
+            }
+        }
+        AppMode::Normal if app.focused_panel == FocusedPanel::Terminal => {
+            app.terminal_state.jump_to_bottom();
+            if let Some(ref pty) = app.terminal_state.pty {
+                let _ = pty.write(text.replace('\n', "\r").as_bytes());
+            }
+        }
+        _ => {}
     }
 }
 
+/// Type `c` through the paste guard: a line break swallowed earlier in the
+/// same burst goes in as a space first.
+fn guarded_char(app: &mut App, c: char, insert: fn(&mut App, char)) {
+    if app.paste_guard.note_char(Instant::now()) {
+        insert(app, ' ');
+    }
+    insert(app, c);
+}
+
+/// Whether Enter is part of a pasted key burst. If so it is swallowed
+/// with a warning instead of confirming.
+fn swallow_pasted_enter(app: &mut App) -> bool {
+    if !app.paste_guard.swallow_enter(Instant::now()) {
+        return false;
+    }
+    let warning = paste_guard::burst_warning(app.paste_guard.line_breaks());
+    app.warn_paste(warning);
+    true
+}
+
 /// Handle keys when in Edit mode (editing a file in the preview panel).
+#[cfg(feature = "editor")]
 fn handle_editor_keys(app: &mut App, key: KeyEvent) {
     // If find bar is active, handle find/replace keys first
     if app
@@ -443,6 +561,7 @@ fn handle_editor_keys(app: &mut App, key: KeyEvent) {
 }
 
 /// Handle keys when the find/replace bar is active in editor mode.
+#[cfg(feature = "editor")]
 fn handle_editor_find_keys(app: &mut App, key: KeyEvent) {
     let editor = match app.editor_state.as_mut() {
         Some(e) => e,
@@ -546,6 +665,15 @@ fn handle_normal_mode(app: &mut App, key: KeyEvent, event_tx: &mpsc::UnboundedSe
             app.focus_down();
             return;
         }
+        // Tab switching: Ctrl+PageUp / Ctrl+PageDown
+        KeyCode::PageUp if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.cycle_tab(-1);
+            return;
+        }
+        KeyCode::PageDown if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.cycle_tab(1);
+            return;
+        }
         // Terminal resize: Ctrl+Shift+Arrow
         KeyCode::Up
             if key.modifiers.contains(KeyModifiers::CONTROL)
@@ -566,12 +694,59 @@ fn handle_normal_mode(app: &mut App, key: KeyEvent, event_tx: &mpsc::UnboundedSe
 
     // If terminal is focused, forward all other keys to the PTY
     if app.focused_panel == FocusedPanel::Terminal {
+        app.pending_count = None;
         handle_terminal_keys(app, key);
         return;
     }
 
+    // Count prefix: digits (a leading 0 excepted) build a count that the
+    // next key consumes; Esc drops it
+    if app.config.count_prefix()
+        && !key
+            .modifiers
+            .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+    {
+        match key.code {
+            KeyCode::Char(c @ '0'..='9') if c != '0' || app.pending_count.is_some() => {
+                app.push_count_digit(c.to_digit(10).unwrap_or(0));
+                return;
+            }
+            KeyCode::Esc if app.pending_count.is_some() => {
+                app.pending_count = None;
+                return;
+            }
+            _ => {}
+        }
+    }
+    let count = app.take_count();
+
+    // `gt` / `gT` in the tree: the `g` already jumped to the top, so its
+    // selection is put back before switching
+    if let Some(selected) = app.pending_g.take() {
+        if app.focused_panel == FocusedPanel::Tree
+            && !key
+                .modifiers
+                .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+        {
+            let steps = match key.code {
+                KeyCode::Char('t') => Some(1),
+                KeyCode::Char('T') => Some(-1),
+                _ => None,
+            };
+            if let Some(steps) = steps {
+                app.tree_state.selected_index = selected;
+                app.cycle_tab(steps);
+                return;
+            }
+        }
+    }
+
     // Global keys (work regardless of focus for tree/preview panels)
     match key.code {
+        KeyCode::Char('n') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.new_tab();
+            return;
+        }
         KeyCode::Char('q') => {
             app.quit();
             return;
@@ -592,21 +767,62 @@ fn handle_normal_mode(app: &mut App, key: KeyEvent, event_tx: &mpsc::UnboundedSe
             app.open_search();
             return;
         }
+        KeyCode::Char('f') | KeyCode::Char('F')
+            if key
+                .modifiers
+                .contains(KeyModifiers::CONTROL | KeyModifiers::SHIFT) =>
+        {
+            app.open_grep();
+            return;
+        }
+        // Ctrl+Shift+X: terminals report the letter in either case
+        KeyCode::Char('x') | KeyCode::Char('X')
+            if key
+                .modifiers
+                .contains(KeyModifiers::CONTROL | KeyModifiers::SHIFT) =>
+        {
+            app.clear_clipboard();
+            return;
+        }
+        // Search the preview's text, or filter the tree
+        KeyCode::Char('/') if app.focused_panel == FocusedPanel::Preview => {
+            app.open_preview_search();
+            return;
+        }
         KeyCode::Char('/') => {
             app.start_filter();
             return;
         }
+        KeyCode::Char('F') => {
+            app.cycle_filter_class(false);
+            return;
+        }
         KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
             app.toggle_watcher();
             return;
         }
+        KeyCode::Char('W') => {
+            app.open_watcher_details();
+            return;
+        }
+        KeyCode::Char('O') => {
+            app.open_recent_operations();
+            return;
+        }
+        KeyCode::Char('J') => {
+            app.open_tasks();
+            return;
+        }
         KeyCode::F(5) => {
             app.full_refresh();
             return;
         }
+        KeyCode::F(12) => {
+            app.toggle_debug_overlay();
+            return;
+        }
         KeyCode::Char('?') => {
-            app.help_state.scroll_offset = 0;
-            app.mode = AppMode::Help;
+            app.open_help();
             return;
         }
         _ => {}
@@ -614,19 +830,68 @@ fn handle_normal_mode(app: &mut App, key: KeyEvent, event_tx: &mpsc::UnboundedSe
 
     // Dispatch based on focused panel
     match app.focused_panel {
-        FocusedPanel::Tree => handle_tree_keys(app, key, event_tx),
-        FocusedPanel::Preview => handle_preview_keys(app, key),
+        FocusedPanel::Tree => handle_tree_keys(app, key, count, event_tx),
+        FocusedPanel::Preview => handle_preview_keys(app, key, count),
         FocusedPanel::Terminal => {} // Already handled above
     }
 }
 
-fn handle_tree_keys(app: &mut App, key: KeyEvent, event_tx: &mpsc::UnboundedSender<Event>) {
+fn handle_tree_keys(
+    app: &mut App,
+    key: KeyEvent,
+    count: usize,
+    event_tx: &mpsc::UnboundedSender<Event>,
+) {
     match key.code {
+        // Per-directory sort overrides (Alt variants of the sort keys)
+        KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::ALT) => app.cycle_dir_sort(),
+        KeyCode::Char('S') if key.modifiers.contains(KeyModifiers::ALT) => {
+            app.toggle_dir_dirs_first();
+        }
+        KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::ALT) => app.clear_dir_sort(),
+        KeyCode::Char('o') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.request_external_edit();
+        }
+        KeyCode::Char('o') if key.modifiers.contains(KeyModifiers::ALT) => {
+            app.open_selected_external();
+        }
+        KeyCode::Char('a') if key.modifiers.contains(KeyModifiers::ALT) => {
+            app.cycle_path_style();
+        }
+        // Copy paths to the system clipboard: relative to the root, absolute
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::ALT) => {
+            app.copy_selected_paths(PathStyle::Relative, event_tx);
+        }
+        KeyCode::Char('C') if key.modifiers.contains(KeyModifiers::ALT) => {
+            app.copy_selected_paths(PathStyle::Absolute, event_tx);
+        }
+        KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::ALT) => {
+            app.open_export_profile();
+        }
+
         // Navigation
-        KeyCode::Char('j') | KeyCode::Down => app.select_next(),
-        KeyCode::Char('k') | KeyCode::Up => app.select_previous(),
-        KeyCode::Char('g') | KeyCode::Home => app.select_first(),
+        KeyCode::Char('j') | KeyCode::Down => {
+            (0..count * app.repeat_step()).for_each(|_| app.select_next());
+        }
+        KeyCode::Char('k') | KeyCode::Up => {
+            (0..count * app.repeat_step()).for_each(|_| app.select_previous());
+        }
+        KeyCode::Char('g') => {
+            app.pending_g = Some(app.tree_state.selected_index);
+            app.select_first();
+        }
+        KeyCode::Home => app.select_first(),
         KeyCode::Char('G') | KeyCode::End => app.select_last(),
+        KeyCode::Char('w') if key.modifiers.contains(KeyModifiers::CONTROL) => app.close_tab(),
+
+        // Enter on a file: per-file-type [open] action (Shift+Enter: system opener)
+        KeyCode::Enter if app.selected_file_path().is_some() => {
+            if key.modifiers.contains(KeyModifiers::SHIFT) {
+                app.open_selected_external();
+            } else {
+                app.open_selected_file(event_tx);
+            }
+        }
 
         // Tree expand/collapse / Load more
         KeyCode::Enter | KeyCode::Char('l') | KeyCode::Right => {
@@ -650,6 +915,9 @@ fn handle_tree_keys(app: &mut App, key: KeyEvent, event_tx: &mpsc::UnboundedSend
         // Toggle hidden files
         KeyCode::Char('.') => app.toggle_hidden(),
 
+        // Toggle hiding gitignored files
+        KeyCode::Char('I') => app.toggle_gitignore(),
+
         // Multi-select toggle
         KeyCode::Char(' ') => app.tree_state.toggle_multi_select(),
 
@@ -678,10 +946,25 @@ fn handle_tree_keys(app: &mut App, key: KeyEvent, event_tx: &mpsc::UnboundedSend
             }
         }
         KeyCode::Char('p') => app.paste_clipboard_async(event_tx.clone()),
+        KeyCode::Char('>')
+            if app
+                .tree_state
+                .flat_items
+                .get(app.tree_state.selected_index)
+                .is_some_and(|i| i.node_type != NodeType::LoadMore) =>
+        {
+            app.open_send_to();
+        }
+        KeyCode::Char('P') => app.open_places(),
+        KeyCode::Char('b') => app.add_bookmark(),
+        KeyCode::Char('B') => app.open_bookmarks(),
+        KeyCode::Char(':') => app.open_dialog(DialogKind::GotoPath),
+        KeyCode::Char('c') => app.jump_to_changed(),
 
         // File operations — open dialogs
         KeyCode::Char('a') => app.open_dialog(DialogKind::CreateFile),
         KeyCode::Char('A') => app.open_dialog(DialogKind::CreateDirectory),
+        KeyCode::Char('r') if !app.tree_state.multi_selected.is_empty() => app.open_bulk_rename(),
         KeyCode::Char('r') => {
             if let Some(item) = app.tree_state.flat_items.get(app.tree_state.selected_index) {
                 if item.node_type == NodeType::LoadMore {
@@ -691,60 +974,108 @@ fn handle_tree_keys(app: &mut App, key: KeyEvent, event_tx: &mpsc::UnboundedSend
                 app.open_dialog(DialogKind::Rename { original });
             }
         }
+        KeyCode::Char('R') => app.open_sequence_rename(),
+        KeyCode::Char('T') => app.open_terminal_here(event_tx),
+        KeyCode::Char('o') => app.request_external_edit(),
+        KeyCode::Char('E') => app.open_export_dialog(),
+        KeyCode::Char('U') => app.extract_selected(event_tx.clone()),
+        KeyCode::Char('Y') => app.duplicate_selected(event_tx.clone()),
+        KeyCode::Char('N') => app.open_scaffold_picker(),
+        KeyCode::Char('C') => app.start_portability_check(event_tx),
+        KeyCode::Char('z') => app.measure_dir_size(event_tx),
+        KeyCode::Char('i') => app.open_file_info(event_tx),
+        KeyCode::Char('M') => app.open_permissions(),
         KeyCode::Char('d') => {
-            if let Some(item) = app.tree_state.flat_items.get(app.tree_state.selected_index) {
-                // Don't allow deleting the root or LoadMore nodes
-                if item.depth > 0 && item.node_type != NodeType::LoadMore {
-                    let targets = vec![item.path.clone()];
-                    app.open_dialog(DialogKind::DeleteConfirm { targets });
-                }
-            }
+            let targets = app.delete_targets();
+            app.open_delete_confirm(targets, false, event_tx);
+        }
+        KeyCode::Char('D') => {
+            let targets = app.delete_targets();
+            app.open_delete_confirm(targets, true, event_tx);
         }
 
         // Sort options
         KeyCode::Char('s') => {
-            app.tree_state.cycle_sort();
-            app.set_status_message(format!("Sort: {}", app.tree_state.sort_by.label()));
+            app.cycle_sort();
+            let msg = format!("Sort: {}", app.tree_state.sort_by.label());
+            app.set_status_message(with_override_note(app, msg));
         }
         KeyCode::Char('S') => {
-            app.tree_state.toggle_dirs_first();
-            app.set_status_message(format!(
+            app.toggle_dirs_first();
+            let msg = format!(
                 "Dirs first: {}",
                 if app.tree_state.dirs_first {
                     "on"
                 } else {
                     "off"
                 }
-            ));
+            );
+            app.set_status_message(with_override_note(app, msg));
         }
 
         _ => {}
     }
 }
 
-fn handle_preview_keys(app: &mut App, key: KeyEvent) {
+/// Mention the selected directory's sort override, which a global sort
+/// change doesn't affect.
+fn with_override_note(app: &App, msg: String) -> String {
+    match app.selected_sort_override() {
+        Some(note) => format!("{} (override kept for {})", msg, note),
+        None => msg,
+    }
+}
+
+fn handle_preview_keys(app: &mut App, key: KeyEvent, count: usize) {
     match key.code {
+        // Cancel a stuck preview load, retry a stopped one
+        KeyCode::Esc | KeyCode::Char('x') if app.preview_load.is_loading() => {
+            app.cancel_preview_load();
+        }
+        KeyCode::Char('r') if app.preview_load.is_stopped() => app.retry_preview_load(),
+        // Preview search: next / previous match, clear the highlights
+        KeyCode::Char('n') => app.preview_search_step(true, count),
+        KeyCode::Char('N') => app.preview_search_step(false, count),
+        KeyCode::Esc if app.preview_search.is_active() => app.close_preview_search(),
         // Enter edit mode
         KeyCode::Char('e') => {
-            app.enter_edit_mode();
+            app.enter_edit_mode(None);
         }
         // Line-by-line scroll
-        KeyCode::Char('j') | KeyCode::Down => app.preview_scroll_down(),
-        KeyCode::Char('k') | KeyCode::Up => app.preview_scroll_up(),
+        KeyCode::Char('j') | KeyCode::Down => {
+            (0..count * app.repeat_step()).for_each(|_| app.preview_scroll_down());
+        }
+        KeyCode::Char('k') | KeyCode::Up => {
+            (0..count * app.repeat_step()).for_each(|_| app.preview_scroll_up());
+        }
+        // Horizontal scroll
+        KeyCode::Char('h') | KeyCode::Left => (0..count).for_each(|_| app.preview_scroll_left()),
+        KeyCode::Char('l') | KeyCode::Right => {
+            (0..count).for_each(|_| app.preview_scroll_right());
+        }
         // Jump to top/bottom
         KeyCode::Char('g') | KeyCode::Home => app.preview_jump_top(),
         KeyCode::Char('G') | KeyCode::End => app.preview_jump_bottom(),
         // Half-page scroll
         KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-            app.preview_half_page_down(30);
+            (0..count).for_each(|_| app.preview_half_page_down(30));
         }
         KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-            app.preview_half_page_up(30);
+            (0..count).for_each(|_| app.preview_half_page_up(30));
         }
         // Toggle line wrap
         KeyCode::Char('w') if key.modifiers.contains(KeyModifiers::CONTROL) => {
             app.preview_state.line_wrap = !app.preview_state.line_wrap;
         }
+        // Toggle git blame gutter
+        KeyCode::Char('b') => app.toggle_blame(),
+        // Cycle tab width 2 → 4 → 8
+        KeyCode::Char('t') => app.cycle_preview_tab_width(),
+        // Markdown front matter as a card or raw YAML
+        KeyCode::Char('m') => app.toggle_front_matter(),
+        // Previous / next file in the same directory
+        KeyCode::Char('[') => app.preview_step_file(false, count),
+        KeyCode::Char(']') => app.preview_step_file(true, count),
         // Adjust head/tail line counts
         KeyCode::Char('+') | KeyCode::Char('=') => {
             app.adjust_preview_lines(crate::preview_content::LINE_COUNT_STEP as isize);
@@ -772,42 +1103,38 @@ fn handle_terminal_keys(app: &mut App, key: KeyEvent) {
         //
         // Scrollback navigation (Shift+Up/Down)
         KeyCode::Up if key.modifiers.contains(KeyModifiers::SHIFT) => {
-            if app.terminal_state.scroll_offset
-                < app
-                    .terminal_state
-                    .emulator
-                    .total_lines()
-                    .saturating_sub(app.terminal_state.emulator.visible_rows())
-            {
-                app.terminal_state.scroll_offset += 1;
-            }
+            let step = app.repeat_step();
+            app.terminal_state.scroll_up(step);
             return;
         }
         KeyCode::Down if key.modifiers.contains(KeyModifiers::SHIFT) => {
-            app.terminal_state.scroll_offset = app.terminal_state.scroll_offset.saturating_sub(1);
+            let step = app.repeat_step();
+            app.terminal_state.scroll_down(step);
             return;
         }
         KeyCode::PageUp if key.modifiers.contains(KeyModifiers::SHIFT) => {
-            let jump = app.terminal_state.emulator.visible_rows() / 2;
-            let max = app
-                .terminal_state
-                .emulator
-                .total_lines()
-                .saturating_sub(app.terminal_state.emulator.visible_rows());
-            app.terminal_state.scroll_offset = (app.terminal_state.scroll_offset + jump).min(max);
+            let jump = app.terminal_state.emulator.visible_rows() / 2 * app.repeat_step();
+            app.terminal_state.scroll_up(jump);
             return;
         }
         KeyCode::PageDown if key.modifiers.contains(KeyModifiers::SHIFT) => {
-            let jump = app.terminal_state.emulator.visible_rows() / 2;
-            app.terminal_state.scroll_offset =
-                app.terminal_state.scroll_offset.saturating_sub(jump);
+            let jump = app.terminal_state.emulator.visible_rows() / 2 * app.repeat_step();
+            app.terminal_state.scroll_down(jump);
+            return;
+        }
+        KeyCode::Char('g') if key.modifiers == KeyModifiers::ALT => {
+            app.follow_next_terminal_link();
+            return;
+        }
+        _ if jumps_to_live(&app.terminal_state, &key) => {
+            app.terminal_state.jump_to_bottom();
             return;
         }
         _ => {}
     }
 
     // Reset scroll offset on any input (auto-scroll to bottom)
-    app.terminal_state.scroll_offset = 0;
+    app.terminal_state.jump_to_bottom();
 
     // Convert KeyEvent to bytes and send to PTY
     let bytes = key_event_to_bytes(&key);
@@ -818,6 +1145,17 @@ fn handle_terminal_keys(app: &mut App, key: KeyEvent) {
     }
 }
 
+/// Whether `key` returns a scrolled-back terminal to live output instead of
+/// reaching the shell. End and G are only taken while scrolled back; at the
+/// bottom the shell gets them as usual.
+fn jumps_to_live(state: &crate::terminal::TerminalState, key: &KeyEvent) -> bool {
+    state.scroll_offset > 0
+        && matches!(key.code, KeyCode::End | KeyCode::Char('G'))
+        && !key
+            .modifiers
+            .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+}
+
 /// Convert a crossterm KeyEvent into the byte sequence expected by a PTY.
 fn key_event_to_bytes(key: &KeyEvent) -> Vec<u8> {
     match key.code {
@@ -868,10 +1206,14 @@ fn key_event_to_bytes(key: &KeyEvent) -> Vec<u8> {
     }
 }
 
-fn handle_search_mode(app: &mut App, key: KeyEvent) {
+fn handle_search_mode(app: &mut App, key: KeyEvent, event_tx: &mpsc::UnboundedSender<Event>) {
     match key.code {
         KeyCode::Esc => app.close_search(),
+        KeyCode::Enter if swallow_pasted_enter(app) => {}
         KeyCode::Enter => app.search_confirm(),
+        KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.reindex_search(event_tx);
+        }
         KeyCode::Down | KeyCode::Char('j') if key.modifiers.contains(KeyModifiers::CONTROL) => {
             app.search_select_next();
         }
@@ -881,7 +1223,31 @@ fn handle_search_mode(app: &mut App, key: KeyEvent) {
         KeyCode::Down => app.search_select_next(),
         KeyCode::Up => app.search_select_previous(),
         KeyCode::Backspace => app.search_delete_char(),
-        KeyCode::Char(c) => app.search_input_char(c),
+        KeyCode::Char(c) => guarded_char(app, c, App::search_input_char),
+        _ => {}
+    }
+}
+
+/// Typing edits the query, which is searched once it rests; Enter searches
+/// it at once, or goes to the selected match once it has been searched.
+fn handle_grep_mode(app: &mut App, key: KeyEvent, event_tx: &mpsc::UnboundedSender<Event>) {
+    match key.code {
+        KeyCode::Esc => app.close_grep(),
+        KeyCode::Enter if swallow_pasted_enter(app) => {}
+        KeyCode::Enter => app.grep_confirm(event_tx),
+        KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.grep_toggle_regex();
+        }
+        KeyCode::Down | KeyCode::Char('j') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.grep_state.select_next();
+        }
+        KeyCode::Up | KeyCode::Char('k') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.grep_state.select_previous();
+        }
+        KeyCode::Down => app.grep_state.select_next(),
+        KeyCode::Up => app.grep_state.select_previous(),
+        KeyCode::Backspace => app.grep_delete_char(),
+        KeyCode::Char(c) => guarded_char(app, c, App::grep_input_char),
         _ => {}
     }
 }
@@ -915,7 +1281,21 @@ fn handle_search_action_mode(
         }
         // Copy path — always available
         KeyCode::Char('y') => {
-            app.search_action_copy_path();
+            app.search_action_copy_path(event_tx);
+        }
+        // Copy path to PRIMARY only (Linux)
+        KeyCode::Char('Y') => {
+            app.search_action_copy_path_primary(event_tx);
+        }
+        // Copy path in an explicit form, whatever the path style
+        KeyCode::Char('A') => {
+            app.search_action_copy_path_as(PathStyle::Absolute, event_tx);
+        }
+        KeyCode::Char('L') => {
+            app.search_action_copy_path_as(PathStyle::Relative, event_tx);
+        }
+        KeyCode::Char('~') => {
+            app.search_action_copy_path_as(PathStyle::HomeTilde, event_tx);
         }
         // Rename — always available
         KeyCode::Char('r') => {
@@ -923,7 +1303,7 @@ fn handle_search_action_mode(
         }
         // Delete — always available
         KeyCode::Char('d') => {
-            app.search_action_delete();
+            app.search_action_delete(event_tx);
         }
         // Copy (clipboard) — always available
         KeyCode::Char('c') => {
@@ -937,6 +1317,46 @@ fn handle_search_action_mode(
         KeyCode::Char('t') => {
             app.search_action_open_terminal(event_tx);
         }
+        // Open with the system opener — always available
+        KeyCode::Char('o') => {
+            app.search_action_open_external();
+        }
+        _ => {}
+    }
+}
+
+fn handle_send_to_mode(app: &mut App, key: KeyEvent, event_tx: &mpsc::UnboundedSender<Event>) {
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('q') => app.close_send_to(),
+        KeyCode::Char('j') | KeyCode::Down => app.send_to_select_next(),
+        KeyCode::Char('k') | KeyCode::Up => app.send_to_select_previous(),
+        // Shift+Enter (or `m`) moves instead of copying
+        KeyCode::Enter => {
+            let move_items = key.modifiers.contains(KeyModifiers::SHIFT);
+            app.send_to_confirm(move_items, event_tx.clone());
+        }
+        KeyCode::Char('m') => app.send_to_confirm(true, event_tx.clone()),
+        _ => {}
+    }
+}
+
+fn handle_places_mode(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('q') => app.close_places(),
+        KeyCode::Char('j') | KeyCode::Down => app.places_select_next(),
+        KeyCode::Char('k') | KeyCode::Up => app.places_select_previous(),
+        KeyCode::Enter => app.places_confirm(),
+        _ => {}
+    }
+}
+
+fn handle_bookmarks_mode(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('q') => app.close_bookmarks(),
+        KeyCode::Char('j') | KeyCode::Down => app.bookmarks_select_next(),
+        KeyCode::Char('k') | KeyCode::Up => app.bookmarks_select_previous(),
+        KeyCode::Enter => app.bookmarks_confirm(),
+        KeyCode::Char('d') | KeyCode::Delete => app.bookmarks_delete_selected(),
         _ => {}
     }
 }
@@ -944,46 +1364,87 @@ fn handle_search_action_mode(
 fn handle_filter_mode(app: &mut App, key: KeyEvent) {
     match key.code {
         KeyCode::Esc => app.clear_filter(),
+        KeyCode::Enter if swallow_pasted_enter(app) => {}
         KeyCode::Enter => app.accept_filter(),
         KeyCode::Backspace => app.filter_delete_char(),
-        KeyCode::Char(c) => app.filter_input_char(c),
+        KeyCode::Tab => app.cycle_filter_class(false),
+        KeyCode::BackTab => app.cycle_filter_class(true),
+        KeyCode::Char(c) => guarded_char(app, c, App::filter_input_char),
+        _ => {}
+    }
+}
+
+fn handle_preview_search_mode(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Esc => app.close_preview_search(),
+        KeyCode::Enter if swallow_pasted_enter(app) => {}
+        KeyCode::Enter => app.accept_preview_search(),
+        KeyCode::Backspace => app.preview_search_delete_char(),
+        KeyCode::Char(c) => guarded_char(app, c, App::preview_search_input_char),
         _ => {}
     }
 }
 
 fn handle_help_mode(app: &mut App, key: KeyEvent) {
-    let total = HelpOverlay::total_lines();
+    let state = &mut app.help_state;
+    if state.searching {
+        match key.code {
+            KeyCode::Esc => {
+                state.query.clear();
+                state.searching = false;
+            }
+            KeyCode::Enter => state.searching = false,
+            KeyCode::Backspace => {
+                state.query.pop();
+            }
+            KeyCode::Char(c) => state.query.push(c),
+            _ => return,
+        }
+        state.scroll_offset = 0;
+        return;
+    }
+
+    let total = HelpOverlay::total_lines(&state.query);
     match key.code {
-        KeyCode::Char('?') | KeyCode::Esc => {
-            app.mode = AppMode::Normal;
+        KeyCode::Esc if !state.query.is_empty() => {
+            state.query.clear();
+            state.scroll_offset = 0;
         }
-        KeyCode::Char('j') | KeyCode::Down => {
-            if app.help_state.scroll_offset < total.saturating_sub(1) {
-                app.help_state.scroll_offset += 1;
-            }
+        KeyCode::Char('?') | KeyCode::Esc | KeyCode::F(1) => app.close_help(),
+        KeyCode::Char('/') => state.searching = true,
+        KeyCode::Char('j') | KeyCode::Down if state.scroll_offset < total.saturating_sub(1) => {
+            state.scroll_offset += 1;
         }
         KeyCode::Char('k') | KeyCode::Up => {
-            app.help_state.scroll_offset = app.help_state.scroll_offset.saturating_sub(1);
+            state.scroll_offset = state.scroll_offset.saturating_sub(1);
         }
         KeyCode::Char('g') | KeyCode::Home => {
-            app.help_state.scroll_offset = 0;
+            state.scroll_offset = 0;
         }
         KeyCode::Char('G') | KeyCode::End => {
-            app.help_state.scroll_offset = total.saturating_sub(1);
+            state.scroll_offset = total.saturating_sub(1);
         }
         _ => {}
     }
 }
 
-fn handle_dialog_mode(app: &mut App, key: KeyEvent) {
+fn handle_dialog_mode(app: &mut App, key: KeyEvent, event_tx: &mpsc::UnboundedSender<Event>) {
     let kind = match &app.mode {
         AppMode::Dialog(kind) => kind.clone(),
         _ => return,
     };
 
     match &kind {
-        DialogKind::DeleteConfirm { targets } => {
-            handle_delete_confirm(app, key, targets.clone());
+        DialogKind::DeleteConfirm {
+            targets,
+            sizing,
+            to_trash,
+            ..
+        } => {
+            handle_delete_confirm(app, key, targets.clone(), *sizing, *to_trash, event_tx);
+        }
+        DialogKind::TransferConfirm { sizing, .. } => {
+            handle_transfer_confirm(app, key, *sizing);
         }
         DialogKind::Error { .. } => {
             handle_error_dialog(app, key);
@@ -991,9 +1452,57 @@ fn handle_dialog_mode(app: &mut App, key: KeyEvent) {
         DialogKind::Progress { .. } => {
             handle_progress_dialog(app, key);
         }
+        #[cfg(feature = "editor")]
         DialogKind::SaveConfirm => {
             handle_save_confirm(app, key);
         }
+        DialogKind::UndoConfirm { .. } => {
+            handle_undo_confirm(app, key);
+        }
+        DialogKind::SequenceRename { .. } => {
+            handle_sequence_rename(app, key);
+        }
+        DialogKind::BulkRename { .. } => {
+            handle_bulk_rename(app, key);
+        }
+        DialogKind::PasteConflict { .. } => {
+            handle_paste_conflict(app, key);
+        }
+        DialogKind::TerminalHere { dir } => {
+            handle_terminal_here(app, key, dir, event_tx);
+        }
+        DialogKind::ExportTree { .. } => {
+            handle_export_tree(app, key, event_tx);
+        }
+        DialogKind::WatcherDetails { .. } => {
+            handle_watcher_details(app, key, event_tx);
+        }
+        DialogKind::Portability { .. } => {
+            handle_portability(app, key);
+        }
+        DialogKind::RecentOperations { .. } => {
+            handle_recent_operations(app, key, event_tx);
+        }
+        DialogKind::Tasks { .. } => {
+            handle_tasks(app, key);
+        }
+        DialogKind::ScaffoldPicker { .. } => {
+            handle_scaffold_picker(app, key);
+        }
+        DialogKind::ScaffoldForm { .. } => {
+            handle_scaffold_form(app, key, event_tx);
+        }
+        DialogKind::BusyRetry { attempt, .. } => {
+            handle_busy_retry(app, key, attempt.is_some(), event_tx);
+        }
+        DialogKind::Info { .. } => {
+            if matches!(key.code, KeyCode::Esc | KeyCode::Enter) {
+                app.close_dialog();
+            }
+        }
+        DialogKind::Permissions { .. } => {
+            handle_permissions(app, key);
+        }
         _ => {
             handle_input_dialog(app, key, kind);
         }
@@ -1003,6 +1512,7 @@ fn handle_dialog_mode(app: &mut App, key: KeyEvent) {
 fn handle_input_dialog(app: &mut App, key: KeyEvent, kind: DialogKind) {
     match key.code {
         KeyCode::Esc => app.close_dialog(),
+        KeyCode::Enter if swallow_pasted_enter(app) => {}
         KeyCode::Enter => {
             let input = app.dialog_state.input.clone();
             if input.is_empty() {
@@ -1011,7 +1521,8 @@ fn handle_input_dialog(app: &mut App, key: KeyEvent, kind: DialogKind) {
             }
             execute_input_operation(app, &kind, &input);
         }
-        KeyCode::Char(c) => app.dialog_input_char(c),
+        KeyCode::Char(c) => guarded_char(app, c, App::dialog_input_char),
+        KeyCode::Tab => app.accept_name_suggestion(),
         KeyCode::Backspace => app.dialog_delete_char(),
         KeyCode::Left => app.dialog_move_cursor_left(),
         KeyCode::Right => app.dialog_move_cursor_right(),
@@ -1031,16 +1542,29 @@ fn handle_input_dialog(app: &mut App, key: KeyEvent, kind: DialogKind) {
 fn execute_input_operation(app: &mut App, kind: &DialogKind, input: &str) {
     match kind {
         DialogKind::CreateFile => {
+            let (relative, is_dir) = match operations::parse_new_path(input) {
+                Ok(parsed) => parsed,
+                Err(msg) => {
+                    // Leave the dialog open to fix the path
+                    app.set_status_message(format!("Error: {}", msg));
+                    return;
+                }
+            };
             let dir = app.current_dir();
-            let path = dir.join(input);
-            match operations::create_file(&path) {
+            let path = dir.join(&relative);
+            match operations::create_nested(&path, is_dir) {
                 Ok(()) => {
-                    app.set_status_message(format!("Created file: {}", input));
+                    let kind = if is_dir { "directory" } else { "file" };
+                    app.set_status_message(format!("Created {}: {}", kind, relative.display()));
                     app.tree_state.reload_dir(&dir);
+                    if let Some(parent) = path.parent().filter(|p| *p != dir) {
+                        app.tree_state.reload_dir(parent);
+                    }
                     app.invalidate_search_cache();
+                    app.navigate_to_path(&path);
                 }
                 Err(e) => {
-                    app.set_status_message(format!("Error: {}", e));
+                    app.set_status_message(format!("Error: {}", e.user_message()));
                 }
             }
         }
@@ -1054,105 +1578,417 @@ fn execute_input_operation(app: &mut App, kind: &DialogKind, input: &str) {
                     app.invalidate_search_cache();
                 }
                 Err(e) => {
-                    app.set_status_message(format!("Error: {}", e));
+                    app.set_status_message(format!("Error: {}", e.user_message()));
                 }
             }
         }
         DialogKind::Rename { original } => {
             if let Some(parent) = original.parent() {
+                // Checked afresh: the directory may have changed while typing
+                let check = name_check::validate_and_suggest(parent, input, Some(original));
+                if let Some(problem) = check.blocking() {
+                    app.set_status_message(format!("Error: {}", problem));
+                    app.dialog_state.name_check = Some(check);
+                    return;
+                }
                 let new_path = parent.join(input);
                 match operations::rename(original, &new_path) {
                     Ok(()) => {
-                        app.last_undo = Some(crate::app::UndoAction::Rename {
+                        app.record_undo(crate::app::UndoAction::Rename {
                             from: original.clone(),
-                            to: new_path,
+                            to: crate::fs::undo_check::StampedPath::capture(new_path),
                         });
                         app.set_status_message(format!("Renamed to: {}", input));
                         app.tree_state.reload_dir(parent);
                         app.invalidate_search_cache();
                     }
+                    Err(e) if e.is_sharing_violation() => {
+                        let op = RetryOp::Rename {
+                            from: original.clone(),
+                            to: new_path,
+                        };
+                        app.offer_retry(op, &e);
+                        return;
+                    }
                     Err(e) => {
-                        app.set_status_message(format!("Error: {}", e));
+                        app.set_status_message(format!("Error: {}", e.user_message()));
                     }
                 }
             }
         }
+        DialogKind::GotoPath => {
+            let root = app.tree_state.root.path.clone();
+            app.goto_location(input, &root);
+        }
+        DialogKind::ExportProfile => app.export_profile(input),
         _ => {}
     }
     app.close_dialog();
 }
 
-fn handle_delete_confirm(app: &mut App, key: KeyEvent, targets: Vec<std::path::PathBuf>) {
-    match key.code {
-        KeyCode::Char('y') | KeyCode::Char('Y') => {
-            let mut errors = Vec::new();
-            for target in &targets {
-                if let Err(e) = operations::delete(target) {
-                    errors.push(format!("{}: {}", target.display(), e));
-                }
-            }
-            if errors.is_empty() {
-                let names: Vec<String> = targets
-                    .iter()
-                    .filter_map(|t| t.file_name().map(|n| n.to_string_lossy().to_string()))
-                    .collect();
-                app.set_status_message(format!("Deleted: {}", names.join(", ")));
-                // Reload parent directories
-                for target in &targets {
-                    if let Some(parent) = target.parent() {
-                        app.tree_state.reload_dir(parent);
-                    }
-                }
-                app.invalidate_search_cache();
-            } else {
-                app.set_status_message(format!("Error: {}", errors.join("; ")));
-            }
-            app.close_dialog();
-        }
-        KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+/// What a key does in a confirmation sized by a pre-flight count.
+enum SizedAnswer {
+    Proceed,
+    Arm,
+    Cancel,
+    Other,
+}
+
+/// `y` goes ahead, except while counting (ignored) and on large
+/// operations, where it arms and only Enter goes ahead, so a held-down
+/// `y` can't confirm them.
+fn sized_answer(key: KeyEvent, sizing: Sizing) -> SizedAnswer {
+    match (key.code, sizing) {
+        (KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc, _) => SizedAnswer::Cancel,
+        (KeyCode::Char('y') | KeyCode::Char('Y'), Sizing::Counted { large: false, .. }) => {
+            SizedAnswer::Proceed
+        }
+        (
+            KeyCode::Char('y') | KeyCode::Char('Y'),
+            Sizing::Counted {
+                large: true,
+                armed: false,
+                ..
+            },
+        ) => SizedAnswer::Arm,
+        (
+            KeyCode::Enter,
+            Sizing::Counted {
+                large: true,
+                armed: true,
+                ..
+            },
+        ) => SizedAnswer::Proceed,
+        _ => SizedAnswer::Other,
+    }
+}
+
+fn handle_delete_confirm(
+    app: &mut App,
+    key: KeyEvent,
+    targets: Vec<std::path::PathBuf>,
+    sizing: Sizing,
+    to_trash: bool,
+    event_tx: &mpsc::UnboundedSender<Event>,
+) {
+    match sized_answer(key, sizing) {
+        // The progress dialog replaces this one, so auto-repeated `y`s
+        // don't confirm again
+        SizedAnswer::Proceed => {
+            app.delete_async(targets, sizing.entries(), to_trash, event_tx.clone())
+        }
+        SizedAnswer::Arm => app.arm_large_confirm(),
+        SizedAnswer::Cancel => {
+            app.cancel_preflight();
             app.close_dialog();
         }
-        _ => {}
+        SizedAnswer::Other => handle_delete_confirm_scroll(app, key),
     }
 }
 
-fn handle_error_dialog(app: &mut App, key: KeyEvent) {
+fn handle_delete_confirm_scroll(app: &mut App, key: KeyEvent) {
     match key.code {
-        KeyCode::Enter | KeyCode::Esc => app.close_dialog(),
+        KeyCode::Down | KeyCode::Char('j') => app.scroll_delete_confirm(1),
+        KeyCode::Up | KeyCode::Char('k') => app.scroll_delete_confirm(-1),
+        KeyCode::PageDown => app.scroll_delete_confirm(DELETE_LIST_ROWS as isize),
+        KeyCode::PageUp => app.scroll_delete_confirm(-(DELETE_LIST_ROWS as isize)),
         _ => {}
     }
 }
 
-fn handle_progress_dialog(app: &mut App, key: KeyEvent) {
-    if key.code == KeyCode::Esc {
-        app.cancel_operation();
-        app.close_dialog();
-        app.set_status_message("Operation cancelled".to_string());
+fn handle_transfer_confirm(app: &mut App, key: KeyEvent, sizing: Sizing) {
+    match sized_answer(key, sizing) {
+        SizedAnswer::Proceed => app.confirm_transfer(),
+        SizedAnswer::Arm => app.arm_large_confirm(),
+        SizedAnswer::Cancel => {
+            app.cancel_preflight();
+            app.close_dialog();
+            app.set_status_message("Paste cancelled".to_string());
+        }
+        SizedAnswer::Other => {}
     }
 }
 
-/// Handle the save confirmation dialog when exiting edit mode with unsaved changes.
-/// Y/y = Save and exit, N/n = Discard and exit, Esc/C/c = Cancel (stay in edit mode).
-fn handle_save_confirm(app: &mut App, key: KeyEvent) {
+fn handle_error_dialog(app: &mut App, key: KeyEvent) {
     match key.code {
-        KeyCode::Char('y') | KeyCode::Char('Y') => {
-            let _ = app.save_editor_buffer();
-            app.close_dialog();
-            app.exit_edit_mode();
-        }
-        KeyCode::Char('n') | KeyCode::Char('N') => {
-            app.close_dialog();
-            app.exit_edit_mode();
-            app.set_status_message("Changes discarded".to_string());
-        }
-        KeyCode::Esc | KeyCode::Char('c') | KeyCode::Char('C') => {
-            // Cancel — return to edit mode
-            app.mode = AppMode::Edit;
-        }
+        KeyCode::Enter | KeyCode::Esc => app.close_dialog(),
         _ => {}
     }
 }
 
+fn handle_watcher_details(app: &mut App, key: KeyEvent, event_tx: &mpsc::UnboundedSender<Event>) {
+    match key.code {
+        KeyCode::Char('r') => app.retry_watcher(WatchMode::Native, event_tx),
+        KeyCode::Char('p') => app.retry_watcher(WatchMode::Poll, event_tx),
+        KeyCode::Enter | KeyCode::Esc | KeyCode::Char('q') => app.close_dialog(),
+        _ => {}
+    }
+}
+
+fn handle_portability(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Char('j') | KeyCode::Down => app.portability_select(true),
+        KeyCode::Char('k') | KeyCode::Up => app.portability_select(false),
+        KeyCode::Enter => app.portability_reveal(),
+        KeyCode::Char('R') => app.portability_rename_all(),
+        KeyCode::Esc | KeyCode::Char('q') => app.close_dialog(),
+        _ => {}
+    }
+}
+
+fn handle_recent_operations(app: &mut App, key: KeyEvent, event_tx: &mpsc::UnboundedSender<Event>) {
+    match key.code {
+        KeyCode::Char('j') | KeyCode::Down => app.recent_ops_select(true),
+        KeyCode::Char('k') | KeyCode::Up => app.recent_ops_select(false),
+        KeyCode::Enter => app.recent_ops_toggle_details(),
+        KeyCode::Char('g') => app.recent_ops_reveal(),
+        KeyCode::Char('u') => app.recent_ops_undo(),
+        KeyCode::Char('r') => app.recent_ops_retry(event_tx.clone()),
+        KeyCode::Esc | KeyCode::Char('q') => app.close_dialog(),
+        _ => {}
+    }
+}
+
+fn handle_tasks(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Char('j') | KeyCode::Down => app.tasks_select(true),
+        KeyCode::Char('k') | KeyCode::Up => app.tasks_select(false),
+        KeyCode::Char('x') | KeyCode::Delete => app.tasks_cancel_selected(),
+        KeyCode::Char('X') => app.tasks_cancel_all(),
+        KeyCode::Esc | KeyCode::Char('q') => app.close_dialog(),
+        _ => {}
+    }
+}
+
+fn handle_scaffold_picker(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Char('j') | KeyCode::Down => app.scaffold_picker_select(true),
+        KeyCode::Char('k') | KeyCode::Up => app.scaffold_picker_select(false),
+        KeyCode::Enter => app.scaffold_pick(),
+        KeyCode::Esc | KeyCode::Char('q') => app.close_dialog(),
+        _ => {}
+    }
+}
+
+/// Values are typed into the focused variable; once existing targets are
+/// listed, Ctrl+F goes ahead over them.
+fn handle_scaffold_form(app: &mut App, key: KeyEvent, event_tx: &mpsc::UnboundedSender<Event>) {
+    match key.code {
+        KeyCode::Esc => app.close_dialog(),
+        KeyCode::Enter => app.scaffold_confirm(false, event_tx.clone()),
+        KeyCode::Char('f') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            if app
+                .scaffold_form_mut()
+                .is_some_and(|form| !form.conflicts.is_empty())
+            {
+                app.scaffold_confirm(true, event_tx.clone());
+            }
+        }
+        code => {
+            let form = match app.scaffold_form_mut() {
+                Some(form) => form,
+                None => return,
+            };
+            match code {
+                KeyCode::Tab | KeyCode::Down => form.next_field(),
+                KeyCode::BackTab | KeyCode::Up => form.prev_field(),
+                KeyCode::Backspace => form.delete_char(),
+                KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    form.input_char(c)
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn handle_progress_dialog(app: &mut App, key: KeyEvent) {
+    if key.code == KeyCode::Esc {
+        app.cancel_operation();
+        app.close_dialog();
+        app.set_status_message("Operation cancelled".to_string());
+    }
+}
+
+/// Handle the save confirmation dialog when exiting edit mode with unsaved changes.
+/// Y/y = Save and exit, N/n = Discard and exit, Esc/C/c = Cancel (stay in edit mode).
+#[cfg(feature = "editor")]
+fn handle_save_confirm(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Char('y') | KeyCode::Char('Y') => {
+            let _ = app.save_editor_buffer();
+            app.close_dialog();
+            app.exit_edit_mode();
+        }
+        KeyCode::Char('n') | KeyCode::Char('N') => {
+            app.close_dialog();
+            app.exit_edit_mode();
+            app.set_status_message("Changes discarded".to_string());
+        }
+        KeyCode::Esc | KeyCode::Char('c') | KeyCode::Char('C') => {
+            // Cancel — return to edit mode
+            app.mode = AppMode::Edit;
+        }
+        _ => {}
+    }
+}
+
+fn handle_paste_conflict(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Char('o') | KeyCode::Char('O') => {
+            app.resolve_paste_conflict(ConflictChoice::Overwrite, false);
+        }
+        KeyCode::Char('a') | KeyCode::Char('A') => {
+            app.resolve_paste_conflict(ConflictChoice::Overwrite, true);
+        }
+        KeyCode::Char('k') | KeyCode::Char('K') => {
+            app.resolve_paste_conflict(ConflictChoice::KeepBoth, false);
+        }
+        KeyCode::Char('s') | KeyCode::Char('S') => {
+            app.resolve_paste_conflict(ConflictChoice::Skip, false);
+        }
+        KeyCode::Esc => app.cancel_paste_conflict(),
+        _ => {}
+    }
+}
+
+fn handle_terminal_here(
+    app: &mut App,
+    key: KeyEvent,
+    dir: &std::path::Path,
+    event_tx: &mpsc::UnboundedSender<Event>,
+) {
+    match key.code {
+        KeyCode::Char('c') | KeyCode::Char('C') => {
+            app.close_dialog();
+            app.terminal_cd(dir);
+        }
+        KeyCode::Char('r') | KeyCode::Char('R') => {
+            app.close_dialog();
+            app.restart_terminal_in(dir, event_tx);
+        }
+        KeyCode::Esc | KeyCode::Char('n') | KeyCode::Char('N') => app.close_dialog(),
+        _ => {}
+    }
+}
+
+/// While a retry runs only Esc does anything: it stops the retry.
+fn handle_busy_retry(
+    app: &mut App,
+    key: KeyEvent,
+    running: bool,
+    event_tx: &mpsc::UnboundedSender<Event>,
+) {
+    if running {
+        if key.code == KeyCode::Esc {
+            app.stop_retry();
+        }
+        return;
+    }
+    match key.code {
+        KeyCode::Char('r') | KeyCode::Char('R') => app.retry_busy(false, event_tx),
+        KeyCode::Char('a') | KeyCode::Char('A') => app.retry_busy(true, event_tx),
+        KeyCode::Char('s') | KeyCode::Char('S') | KeyCode::Esc => app.skip_retry(),
+        _ => {}
+    }
+}
+
+fn handle_undo_confirm(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Char('j') | KeyCode::Down => app.undo_confirm_select(true),
+        KeyCode::Char('k') | KeyCode::Up => app.undo_confirm_select(false),
+        KeyCode::Char(' ') => app.undo_confirm_toggle(),
+        KeyCode::Char('a') => app.undo_confirm_set_all(true),
+        KeyCode::Char('s') => app.undo_confirm_set_all(false),
+        KeyCode::Enter => app.undo_confirm_accept(),
+        KeyCode::Esc | KeyCode::Char('n') => app.undo_confirm_cancel(),
+        _ => {}
+    }
+}
+
+fn handle_sequence_rename(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Esc => app.close_dialog(),
+        KeyCode::Enter => app.sequence_confirm(),
+        code => {
+            let form = match app.sequence_form_mut() {
+                Some(form) => form,
+                None => return,
+            };
+            match code {
+                KeyCode::Tab | KeyCode::Down => form.next_field(),
+                KeyCode::BackTab | KeyCode::Up => form.prev_field(),
+                KeyCode::Backspace => form.delete_char(),
+                KeyCode::Char(c) => form.input_char(c),
+                _ => {}
+            }
+        }
+    }
+}
+
+fn handle_bulk_rename(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Esc => app.close_dialog(),
+        KeyCode::Enter => app.bulk_rename_confirm(),
+        code => {
+            let form = match app.bulk_rename_form_mut() {
+                Some(form) => form,
+                None => return,
+            };
+            match code {
+                KeyCode::Tab => form.accept_suggestion(),
+                KeyCode::Backspace => form.delete_char(),
+                KeyCode::Char(c) => form.input_char(c),
+                _ => {}
+            }
+        }
+    }
+}
+
+fn handle_permissions(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Esc => app.close_dialog(),
+        KeyCode::Enter => app.permissions_confirm(),
+        code => {
+            let form = match app.permissions_form_mut() {
+                Some(form) => form,
+                None => return,
+            };
+            match code {
+                KeyCode::Up => form.move_cursor(-1, 0),
+                KeyCode::Down => form.move_cursor(1, 0),
+                KeyCode::Left => form.move_cursor(0, -1),
+                KeyCode::Right => form.move_cursor(0, 1),
+                KeyCode::Char(' ') if form.field == PermissionsField::Grid => form.toggle(),
+                KeyCode::Tab | KeyCode::BackTab => form.next_field(),
+                KeyCode::Backspace => form.delete_char(),
+                KeyCode::Char(c) => form.input_char(c),
+                _ => {}
+            }
+        }
+    }
+}
+
+fn handle_export_tree(app: &mut App, key: KeyEvent, event_tx: &mpsc::UnboundedSender<Event>) {
+    match key.code {
+        KeyCode::Esc => app.close_dialog(),
+        KeyCode::Enter => app.export_confirm(event_tx),
+        code => {
+            let form = match app.export_form_mut() {
+                Some(form) => form,
+                None => return,
+            };
+            match code {
+                KeyCode::Tab | KeyCode::Down => form.next_field(),
+                KeyCode::BackTab | KeyCode::Up => form.prev_field(),
+                KeyCode::Backspace => form.delete_char(),
+                KeyCode::Char(c) => form.input_char(c),
+                _ => {}
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1199,6 +2035,13 @@ mod tests {
         (dir, app)
     }
 
+    /// Press Enter the way a person would after typing: slower than a
+    /// paste burst, so it confirms.
+    fn confirm_typed(app: &mut App) {
+        std::thread::sleep(paste_guard::BURST_GAP);
+        handle_key(app, make_key(KeyCode::Enter));
+    }
+
     // === Normal mode tests (existing) ===
 
     #[test]
@@ -1331,6 +2174,23 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn create_existing_dir_reports_hint() {
+        let (_dir, mut app) = setup_app();
+        app.tree_state.selected_index = 0; // root
+        handle_key(
+            &mut app,
+            make_key_with_modifiers(KeyCode::Char('A'), KeyModifiers::SHIFT),
+        );
+        for c in "alpha".chars() {
+            handle_key(&mut app, make_key(KeyCode::Char(c)));
+        }
+        confirm_typed(&mut app);
+        let msg = &app.status_message.as_ref().unwrap().0;
+        assert!(msg.starts_with("Error: "));
+        assert!(msg.contains("alpha already exists — choose a different name"));
+    }
+
     #[test]
     fn key_r_opens_rename_dialog() {
         let (_dir, mut app) = setup_app();
@@ -1344,6 +2204,72 @@ mod tests {
         assert_eq!(app.dialog_state.input, "file_a.txt");
     }
 
+    /// Open Rename on `file_a.txt` and replace its name with `name`.
+    fn rename_typing(app: &mut App, name: &str) {
+        app.tree_state.selected_index = 3; // file_a.txt
+        handle_key(app, make_key(KeyCode::Char('r')));
+        while !app.dialog_state.input.is_empty() {
+            handle_key(app, make_key(KeyCode::Backspace));
+        }
+        for c in name.chars() {
+            handle_key(app, make_key(KeyCode::Char(c)));
+        }
+    }
+
+    #[test]
+    fn rename_offers_the_dropped_extension_back_on_tab() {
+        let (dir, mut app) = setup_app();
+        rename_typing(&mut app, "notes");
+        let check = app.dialog_state.name_check.clone().unwrap();
+        assert_eq!(check.summary().unwrap(), "drops the .txt extension");
+        assert_eq!(check.suggestion.as_deref(), Some("notes.txt"));
+
+        handle_key(&mut app, make_key(KeyCode::Tab));
+        assert_eq!(app.dialog_state.input, "notes.txt");
+        assert_eq!(app.dialog_state.cursor_position, "notes.txt".len());
+        assert_eq!(app.dialog_state.name_check, Some(Default::default()));
+        confirm_typed(&mut app);
+        assert_eq!(app.mode, AppMode::Normal);
+        assert!(dir.path().join("notes.txt").exists());
+        assert!(!dir.path().join("file_a.txt").exists());
+    }
+
+    #[test]
+    fn rename_without_the_extension_still_goes_through() {
+        let (dir, mut app) = setup_app();
+        rename_typing(&mut app, "notes");
+        confirm_typed(&mut app);
+        assert_eq!(app.mode, AppMode::Normal);
+        assert!(dir.path().join("notes").exists());
+    }
+
+    #[test]
+    fn rename_onto_an_existing_name_keeps_the_dialog_open() {
+        let (dir, mut app) = setup_app();
+        File::create(dir.path().join("taken.txt")).unwrap();
+        app.tree_state.reload_dir(dir.path());
+        rename_typing(&mut app, "TAKEN.txt");
+        let check = app.dialog_state.name_check.clone().unwrap();
+        assert_eq!(
+            check.summary().unwrap(),
+            "clashes with taken.txt ignoring case"
+        );
+
+        // Created after the dialog opened: caught when confirming
+        rename_typing(&mut app, "late.txt");
+        assert_eq!(app.dialog_state.name_check, Some(Default::default()));
+        File::create(dir.path().join("late.txt")).unwrap();
+        confirm_typed(&mut app);
+        assert!(matches!(
+            app.mode,
+            AppMode::Dialog(DialogKind::Rename { .. })
+        ));
+        let (msg, _) = app.status_message.clone().unwrap();
+        assert_eq!(msg, "Error: late.txt already exists");
+        assert!(dir.path().join("file_a.txt").exists());
+        assert_eq!(fs::read(dir.path().join("late.txt")).unwrap(), b"");
+    }
+
     #[test]
     fn key_d_opens_delete_dialog() {
         let (_dir, mut app) = setup_app();
@@ -1355,6 +2281,101 @@ mod tests {
         ));
     }
 
+    /// App over a directory of 20 files, `f00.txt` .. `f19.txt`.
+    fn setup_long_app() -> (TempDir, App) {
+        let dir = TempDir::new().unwrap();
+        for i in 0..20 {
+            File::create(dir.path().join(format!("f{:02}.txt", i))).unwrap();
+        }
+        let app = App::new(dir.path(), crate::config::AppConfig::default()).unwrap();
+        (dir, app)
+    }
+
+    #[test]
+    fn count_prefix_repeats_motion() {
+        let (_dir, mut app) = setup_long_app();
+        handle_key(&mut app, make_key(KeyCode::Char('1')));
+        handle_key(&mut app, make_key(KeyCode::Char('2')));
+        assert_eq!(app.pending_count, Some(12));
+        handle_key(&mut app, make_key(KeyCode::Char('j')));
+        assert_eq!(app.tree_state.selected_index, 12);
+        assert_eq!(app.pending_count, None);
+
+        handle_key(&mut app, make_key(KeyCode::Char('5')));
+        handle_key(&mut app, make_key(KeyCode::Char('k')));
+        assert_eq!(app.tree_state.selected_index, 7);
+        // Counts past the end stop at the last row
+        for c in "99999".chars() {
+            handle_key(&mut app, make_key(KeyCode::Char(c)));
+        }
+        assert_eq!(app.pending_count, Some(crate::app::MAX_COUNT));
+        handle_key(&mut app, make_key(KeyCode::Char('j')));
+        assert_eq!(app.tree_state.selected_index, 20);
+    }
+
+    #[test]
+    fn esc_clears_pending_count() {
+        let (_dir, mut app) = setup_long_app();
+        handle_key(&mut app, make_key(KeyCode::Char('5')));
+        handle_key(&mut app, make_key(KeyCode::Esc));
+        assert_eq!(app.pending_count, None);
+        handle_key(&mut app, make_key(KeyCode::Char('j')));
+        assert_eq!(app.tree_state.selected_index, 1);
+    }
+
+    #[test]
+    fn leading_zero_and_other_keys_do_not_count() {
+        let (_dir, mut app) = setup_long_app();
+        handle_key(&mut app, make_key(KeyCode::Char('0')));
+        assert_eq!(app.pending_count, None);
+        // A non-motion key consumes the count
+        handle_key(&mut app, make_key(KeyCode::Char('3')));
+        handle_key(&mut app, make_key(KeyCode::Char('.')));
+        assert_eq!(app.pending_count, None);
+        handle_key(&mut app, make_key(KeyCode::Char('j')));
+        assert_eq!(app.tree_state.selected_index, 1);
+    }
+
+    #[test]
+    fn digits_pass_through_to_the_terminal() {
+        let (_dir, mut app) = setup_long_app();
+        handle_key(&mut app, make_key(KeyCode::Char('4')));
+        app.focused_panel = FocusedPanel::Terminal;
+        handle_key(&mut app, make_key(KeyCode::Char('7')));
+        assert_eq!(app.pending_count, None);
+        assert_eq!(app.tree_state.selected_index, 0);
+    }
+
+    #[test]
+    fn count_prefix_can_be_disabled() {
+        let dir = TempDir::new().unwrap();
+        for i in 0..5 {
+            File::create(dir.path().join(format!("f{}.txt", i))).unwrap();
+        }
+        let config = crate::config::AppConfig {
+            general: crate::config::GeneralConfig {
+                count_prefix: Some(false),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut app = App::new(dir.path(), config).unwrap();
+        handle_key(&mut app, make_key(KeyCode::Char('3')));
+        assert_eq!(app.pending_count, None);
+        handle_key(&mut app, make_key(KeyCode::Char('j')));
+        assert_eq!(app.tree_state.selected_index, 1);
+    }
+
+    #[test]
+    fn count_prefix_steps_preview_files() {
+        let (dir, mut app) = setup_long_app();
+        app.tree_state.selected_index = 1; // f00.txt
+        app.focused_panel = FocusedPanel::Preview;
+        handle_key(&mut app, make_key(KeyCode::Char('3')));
+        handle_key(&mut app, make_key(KeyCode::Char(']')));
+        assert_eq!(app.selected_file_path(), Some(dir.path().join("f03.txt")));
+    }
+
     #[test]
     fn key_d_on_root_is_noop() {
         let (_dir, mut app) = setup_app();
@@ -1394,44 +2415,181 @@ mod tests {
         assert_eq!(app.dialog_state.input, "a");
     }
 
-    // === Integration tests: actual file operations ===
-
     #[test]
-    fn create_file_via_dialog() {
+    fn alt_sort_keys_override_containing_dir() {
         let (dir, mut app) = setup_app();
-        // Open create file dialog
-        handle_key(&mut app, make_key(KeyCode::Char('a')));
-        // Type filename
-        for c in "new_file.txt".chars() {
-            handle_key(&mut app, make_key(KeyCode::Char(c)));
-        }
-        // Confirm
-        handle_key(&mut app, make_key(KeyCode::Enter));
-        // Verify file was created
-        assert!(dir.path().join("new_file.txt").exists());
-        assert!(matches!(app.mode, AppMode::Normal));
-        assert!(app.status_message.is_some());
-    }
+        let file_a = dir.path().join("file_a.txt");
+        app.tree_state.selected_index = 3;
 
-    #[test]
-    fn create_dir_via_dialog() {
-        let (dir, mut app) = setup_app();
+        // A file selects its containing directory (here the root)
         handle_key(
             &mut app,
-            make_key_with_modifiers(KeyCode::Char('A'), KeyModifiers::SHIFT),
+            make_key_with_modifiers(KeyCode::Char('s'), KeyModifiers::ALT),
         );
-        for c in "new_dir".chars() {
-            handle_key(&mut app, make_key(KeyCode::Char(c)));
-        }
-        handle_key(&mut app, make_key(KeyCode::Enter));
-        assert!(dir.path().join("new_dir").exists());
-        assert!(dir.path().join("new_dir").is_dir());
-    }
+        assert_eq!(
+            app.tree_state.sort_for(dir.path()),
+            (crate::fs::tree::SortBy::Size, true)
+        );
+        assert_eq!(app.tree_state.sort_by, crate::fs::tree::SortBy::Name);
+        let selected = &app.tree_state.flat_items[app.tree_state.selected_index];
+        assert_eq!(selected.path, file_a);
 
-    #[test]
-    fn rename_file_via_dialog() {
-        let (dir, mut app) = setup_app();
-        // Select file_a.txt (index 3)
+        handle_key(
+            &mut app,
+            make_key_with_modifiers(KeyCode::Char('S'), KeyModifiers::ALT | KeyModifiers::SHIFT),
+        );
+        assert_eq!(
+            app.tree_state.sort_for(dir.path()),
+            (crate::fs::tree::SortBy::Size, false)
+        );
+        assert!(app
+            .selected_sort_override()
+            .unwrap()
+            .ends_with("Size, dirs mixed"));
+
+        // The global sort moves on; the status notes the kept override
+        handle_key(&mut app, make_key(KeyCode::Char('s')));
+        assert_eq!(app.tree_state.sort_by, crate::fs::tree::SortBy::Size);
+        let (msg, _) = app.status_message.clone().unwrap();
+        assert!(msg.contains("override kept"), "{}", msg);
+
+        handle_key(
+            &mut app,
+            make_key_with_modifiers(KeyCode::Char('r'), KeyModifiers::ALT),
+        );
+        assert!(app.tree_state.sort_overrides.is_empty());
+        assert!(app.selected_sort_override().is_none());
+        // Alt+r never falls through to rename
+        handle_key(
+            &mut app,
+            make_key_with_modifiers(KeyCode::Char('r'), KeyModifiers::ALT),
+        );
+        assert!(matches!(app.mode, AppMode::Normal));
+        let (msg, _) = app.status_message.clone().unwrap();
+        assert!(msg.starts_with("No sort override"), "{}", msg);
+    }
+
+    #[test]
+    fn preview_brackets_step_through_sibling_files() {
+        let (dir, mut app) = setup_app();
+        std::fs::write(dir.path().join("file_b.rs"), "b").unwrap();
+        app.tree_state.reload_dir(dir.path());
+        app.tree_state.selected_index = 3;
+        app.update_preview();
+        app.focused_panel = FocusedPanel::Preview;
+        assert_eq!(app.preview_file_position().as_deref(), Some("1/2"));
+
+        handle_key(&mut app, make_key(KeyCode::Char(']')));
+        assert_eq!(app.tree_state.selected_index, 4);
+        assert_eq!(app.focused_panel, FocusedPanel::Preview);
+        assert_eq!(
+            app.preview_state.current_path,
+            Some(dir.path().join("file_b.rs"))
+        );
+        assert_eq!(app.preview_file_position().as_deref(), Some("2/2"));
+
+        // No wrapping by default
+        handle_key(&mut app, make_key(KeyCode::Char(']')));
+        assert_eq!(app.tree_state.selected_index, 4);
+        let (msg, _) = app.status_message.clone().unwrap();
+        assert_eq!(msg, "Last file in directory");
+
+        handle_key(&mut app, make_key(KeyCode::Char('[')));
+        assert_eq!(app.tree_state.selected_index, 3);
+    }
+
+    // === Integration tests: actual file operations ===
+
+    #[test]
+    fn create_file_via_dialog() {
+        let (dir, mut app) = setup_app();
+        // Open create file dialog
+        handle_key(&mut app, make_key(KeyCode::Char('a')));
+        // Type filename
+        for c in "new_file.txt".chars() {
+            handle_key(&mut app, make_key(KeyCode::Char(c)));
+        }
+        // Confirm
+        confirm_typed(&mut app);
+        // Verify file was created
+        assert!(dir.path().join("new_file.txt").exists());
+        assert!(matches!(app.mode, AppMode::Normal));
+        assert!(app.status_message.is_some());
+    }
+
+    #[test]
+    fn create_file_with_a_nested_path_makes_parents_and_selects_it() {
+        let (dir, mut app) = setup_app();
+        app.tree_state.selected_index = 0; // root
+        handle_key(&mut app, make_key(KeyCode::Char('a')));
+        for c in "src/utils/helpers.rs".chars() {
+            handle_key(&mut app, make_key(KeyCode::Char(c)));
+        }
+        confirm_typed(&mut app);
+        let file = dir.path().join("src/utils/helpers.rs");
+        assert!(file.is_file());
+        assert_eq!(app.mode, AppMode::Normal);
+        let selected = &app.tree_state.flat_items[app.tree_state.selected_index];
+        assert_eq!(selected.path, file);
+        let (msg, _) = app.status_message.clone().unwrap();
+        assert_eq!(msg, "Created file: src/utils/helpers.rs");
+    }
+
+    #[test]
+    fn create_file_with_a_trailing_slash_makes_a_directory() {
+        let (dir, mut app) = setup_app();
+        app.tree_state.selected_index = 0; // root
+        handle_key(&mut app, make_key(KeyCode::Char('a')));
+        for c in "build/out/".chars() {
+            handle_key(&mut app, make_key(KeyCode::Char(c)));
+        }
+        confirm_typed(&mut app);
+        let out = dir.path().join("build/out");
+        assert!(out.is_dir());
+        let selected = &app.tree_state.flat_items[app.tree_state.selected_index];
+        assert_eq!(selected.path, out);
+        let (msg, _) = app.status_message.clone().unwrap();
+        assert_eq!(msg, "Created directory: build/out");
+    }
+
+    #[test]
+    fn create_file_refuses_paths_leaving_the_directory() {
+        let (dir, mut app) = setup_app();
+        for input in ["../outside.txt", "/tmp/absolute.txt"] {
+            app.tree_state.selected_index = 0; // root
+            app.open_dialog(DialogKind::CreateFile);
+            for c in input.chars() {
+                handle_key(&mut app, make_key(KeyCode::Char(c)));
+            }
+            confirm_typed(&mut app);
+            // The dialog stays open with the reason
+            assert_eq!(app.mode, AppMode::Dialog(DialogKind::CreateFile));
+            let (msg, _) = app.status_message.clone().unwrap();
+            assert!(msg.starts_with("Error: "), "{msg}");
+            handle_key(&mut app, make_key(KeyCode::Esc));
+        }
+        assert!(!dir.path().parent().unwrap().join("outside.txt").exists());
+    }
+
+    #[test]
+    fn create_dir_via_dialog() {
+        let (dir, mut app) = setup_app();
+        handle_key(
+            &mut app,
+            make_key_with_modifiers(KeyCode::Char('A'), KeyModifiers::SHIFT),
+        );
+        for c in "new_dir".chars() {
+            handle_key(&mut app, make_key(KeyCode::Char(c)));
+        }
+        confirm_typed(&mut app);
+        assert!(dir.path().join("new_dir").exists());
+        assert!(dir.path().join("new_dir").is_dir());
+    }
+
+    #[test]
+    fn rename_file_via_dialog() {
+        let (dir, mut app) = setup_app();
+        // Select file_a.txt (index 3)
         app.tree_state.selected_index = 3;
         handle_key(&mut app, make_key(KeyCode::Char('r')));
         // Clear existing name and type new one
@@ -1441,31 +2599,581 @@ mod tests {
         for c in "renamed.txt".chars() {
             handle_key(&mut app, make_key(KeyCode::Char(c)));
         }
-        handle_key(&mut app, make_key(KeyCode::Enter));
+        confirm_typed(&mut app);
         assert!(!dir.path().join("file_a.txt").exists());
         assert!(dir.path().join("renamed.txt").exists());
     }
 
-    #[test]
-    fn delete_file_via_dialog() {
+    /// Press `y` `presses` times in the delete dialog and apply the
+    /// background delete's events until it completes.
+    async fn confirm_delete(app: &mut App, presses: usize) {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        for _ in 0..presses {
+            handle_key_event(app, make_key(KeyCode::Char('y')), &tx);
+        }
+        drop(tx);
+        while let Some(event) = rx.recv().await {
+            match event {
+                Event::Progress(update) => app.handle_progress(update),
+                Event::DeleteComplete(result) => app.handle_delete_complete(result),
+                _ => {}
+            }
+        }
+    }
+
+    /// Press `d` and wait for the pre-flight count of any directories.
+    async fn press_delete(app: &mut App) {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        handle_key_event(app, make_key(KeyCode::Char('d')), &tx);
+        drop(tx);
+        pump_preflight(app, &mut rx).await;
+    }
+
+    /// Hand pre-flight results to the app until the senders are gone.
+    async fn pump_preflight(app: &mut App, rx: &mut mpsc::UnboundedReceiver<Event>) {
+        let (tx, _events) = mpsc::unbounded_channel();
+        while let Some(event) = rx.recv().await {
+            if let Event::PreflightComplete { id, usage } = event {
+                app.handle_preflight_complete(id, usage, &tx);
+            }
+        }
+    }
+
+    #[test]
+    fn o_and_ctrl_o_ask_for_an_external_edit() {
+        let (dir, mut app) = setup_app();
+        app.tree_state.selected_index = 3; // file_a.txt
+        handle_key(&mut app, make_key(KeyCode::Char('o')));
+        assert_eq!(
+            app.external_edit.take(),
+            Some(dir.path().join("file_a.txt"))
+        );
+        handle_key(
+            &mut app,
+            make_key_with_modifiers(KeyCode::Char('o'), KeyModifiers::CONTROL),
+        );
+        assert_eq!(app.external_edit, Some(dir.path().join("file_a.txt")));
+    }
+
+    #[tokio::test]
+    async fn key_shift_y_duplicates_in_place() {
+        let (dir, mut app) = setup_app();
+        app.tree_state.selected_index = 3;
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        handle_key_event(&mut app, make_key(KeyCode::Char('Y')), &tx);
+        assert!(matches!(
+            app.mode,
+            AppMode::Dialog(DialogKind::Progress { total: None, .. })
+        ));
+        while let Some(event) = rx.recv().await {
+            if let Event::DuplicateComplete(result) = event {
+                app.handle_duplicate_complete(result);
+                break;
+            }
+        }
+        assert!(dir.path().join("file_a (copy).txt").exists());
+    }
+
+    #[tokio::test]
+    async fn delete_file_via_dialog() {
+        let (dir, mut app) = setup_app();
+        // Select file_a.txt (index 3)
+        app.tree_state.selected_index = 3;
+        handle_key(&mut app, make_key(KeyCode::Char('d')));
+        // Confirm delete
+        confirm_delete(&mut app, 1).await;
+        assert!(!dir.path().join("file_a.txt").exists());
+        assert!(matches!(app.mode, AppMode::Normal));
+    }
+
+    #[tokio::test]
+    async fn deletes_skip_the_dialog_without_confirm_delete() {
+        let (dir, mut app) = setup_app();
+        app.config.general.confirm_delete = Some(false);
+        // A file, then a directory once it has been counted
+        for name in ["file_a.txt", "alpha"] {
+            app.tree_state.selected_index = app
+                .tree_state
+                .flat_items
+                .iter()
+                .position(|item| item.name == name)
+                .unwrap();
+            let (tx, mut rx) = mpsc::unbounded_channel();
+            handle_key_event(&mut app, make_key(KeyCode::Char('d')), &tx);
+            while let Some(event) = rx.recv().await {
+                assert!(!matches!(
+                    app.mode,
+                    AppMode::Dialog(DialogKind::DeleteConfirm {
+                        sizing: Sizing::Counted { .. },
+                        ..
+                    })
+                ));
+                match event {
+                    Event::PreflightComplete { id, usage } => {
+                        app.handle_preflight_complete(id, usage, &tx)
+                    }
+                    Event::DeleteComplete(result) => {
+                        app.handle_delete_complete(result);
+                        break;
+                    }
+                    _ => {}
+                }
+            }
+            assert!(!dir.path().join(name).exists());
+            assert!(matches!(app.mode, AppMode::Normal));
+        }
+    }
+
+    #[tokio::test]
+    async fn large_deletes_ask_twice_without_confirm_delete() {
+        let (_dir, mut app) = setup_app();
+        app.config.general.confirm_delete = Some(false);
+        app.config.general.large_op_entries = Some(1);
+        app.tree_state.selected_index = 1; // alpha
+        press_delete(&mut app).await;
+        assert!(matches!(
+            delete_sizing(&app),
+            Sizing::Counted { large: true, .. }
+        ));
+    }
+
+    #[test]
+    fn pasting_a_folder_into_itself_is_refused() {
+        let (dir, mut app) = setup_app();
+        app.tree_state.selected_index = 1; // alpha
+        app.copy_to_clipboard();
+        let (tx, _rx) = mpsc::unbounded_channel();
+        app.paste_clipboard_async(tx);
+        assert!(matches!(app.mode, AppMode::Normal));
+        let (msg, _) = app.status_message.clone().unwrap();
+        assert_eq!(msg, "Can't paste alpha into itself");
+        assert_eq!(
+            std::fs::read_dir(dir.path().join("alpha")).unwrap().count(),
+            0
+        );
+    }
+
+    #[test]
+    fn deleting_the_root_is_refused() {
+        let (dir, mut app) = setup_app();
+        let tx = make_event_tx();
+        app.open_delete_confirm(vec![dir.path().to_path_buf()], false, &tx);
+        assert!(matches!(app.mode, AppMode::Normal));
+        let (msg, _) = app.status_message.clone().unwrap();
+        assert_eq!(msg, "Can't delete the tree root");
+    }
+
+    #[tokio::test]
+    async fn repeated_delete_confirm_deletes_once() {
+        let (dir, mut app) = setup_app();
+        app.tree_state.selected_index = 3;
+        handle_key(&mut app, make_key(KeyCode::Char('d')));
+        // Auto-repeat delivers several `y`s; only the first confirms
+        confirm_delete(&mut app, 4).await;
+        assert!(!dir.path().join("file_a.txt").exists());
+        assert!(dir.path().join("alpha").exists());
+        assert!(dir.path().join("beta").exists());
+        assert!(matches!(app.mode, AppMode::Normal));
+    }
+
+    #[tokio::test]
+    async fn delete_acts_on_every_multi_selected_item() {
+        let (dir, mut app) = setup_app();
+        app.tree_state.multi_selected.extend([1, 3]); // alpha, file_a.txt
+        app.tree_state.selected_index = 2; // focus on beta is ignored
+        press_delete(&mut app).await;
+        match &app.mode {
+            AppMode::Dialog(DialogKind::DeleteConfirm { targets, .. }) => {
+                assert_eq!(
+                    targets,
+                    &vec![dir.path().join("alpha"), dir.path().join("file_a.txt")]
+                );
+            }
+            other => panic!("expected delete confirm, got {:?}", other),
+        }
+
+        confirm_delete(&mut app, 1).await;
+        assert!(!dir.path().join("alpha").exists());
+        assert!(!dir.path().join("file_a.txt").exists());
+        assert!(dir.path().join("beta").exists());
+        assert!(app.tree_state.multi_selected.is_empty());
+        let (msg, _) = app.status_message.as_ref().unwrap();
+        assert_eq!(msg, "Deleted: alpha, file_a.txt");
+    }
+
+    #[tokio::test]
+    async fn partial_delete_failure_is_reported_per_item() {
+        let (dir, mut app) = setup_app();
+        fs::write(dir.path().join("beta").join("inner.txt"), "x").unwrap();
+        app.tree_state.multi_selected.extend([2, 3]); // beta, file_a.txt
+        press_delete(&mut app).await;
+        // beta disappears between confirming and deleting
+        fs::remove_dir_all(dir.path().join("beta")).unwrap();
+
+        confirm_delete(&mut app, 1).await;
+        assert!(!dir.path().join("file_a.txt").exists());
+        let (msg, _) = app.status_message.as_ref().unwrap();
+        assert!(msg.starts_with("Error: deleted 1 of 2;"), "{msg}");
+        assert!(msg.contains("beta"), "{msg}");
+        assert!(matches!(app.mode, AppMode::Normal));
+    }
+
+    #[test]
+    fn failed_delete_targets_stay_selected() {
+        let (dir, mut app) = setup_app();
+        let alpha = dir.path().join("alpha");
+        let file_a = dir.path().join("file_a.txt");
+        fs::remove_file(&file_a).unwrap();
+        app.handle_delete_complete(crate::event::DeleteResult {
+            targets: vec![alpha.clone(), file_a.clone()],
+            deleted: vec![file_a],
+            trashed: Vec::new(),
+            errors: vec![crate::error::OperationError::PermissionDenied {
+                path: alpha.clone(),
+            }],
+            to_trash: false,
+        });
+        let alpha_idx = app.tree_state.find_index_by_path(&alpha).unwrap();
+        assert_eq!(
+            app.tree_state.multi_selected,
+            std::collections::HashSet::from([alpha_idx])
+        );
+        let (msg, _) = app.status_message.as_ref().unwrap();
+        assert!(msg.starts_with("Error: deleted 1 of 2;"), "{msg}");
+    }
+
+    #[test]
+    fn delete_never_includes_the_root() {
+        let (dir, mut app) = setup_app();
+        app.tree_state.multi_selected.extend([0, 3]); // root, file_a.txt
+        assert_eq!(app.delete_targets(), vec![dir.path().join("file_a.txt")]);
+
+        app.tree_state.multi_selected.clear();
+        app.tree_state.multi_selected.insert(0);
+        handle_key(&mut app, make_key(KeyCode::Char('d')));
+        assert!(matches!(app.mode, AppMode::Normal));
+        assert!(dir.path().exists());
+    }
+
+    #[test]
+    fn delete_skips_items_inside_another_target() {
+        let (dir, mut app) = setup_app();
+        fs::write(dir.path().join("alpha").join("inner.txt"), "x").unwrap();
+        app.tree_state.selected_index = 1;
+        app.tree_state.expand_selected();
+        let inner = app
+            .tree_state
+            .find_index_by_path(&dir.path().join("alpha").join("inner.txt"))
+            .unwrap();
+        app.tree_state.multi_selected.extend([1, inner]);
+        assert_eq!(app.delete_targets(), vec![dir.path().join("alpha")]);
+    }
+
+    #[test]
+    fn delete_cancel_preserves_file() {
+        let (dir, mut app) = setup_app();
+        app.tree_state.selected_index = 3;
+        handle_key(&mut app, make_key(KeyCode::Char('d')));
+        handle_key(&mut app, make_key(KeyCode::Char('n')));
+        assert!(dir.path().join("file_a.txt").exists());
+        assert!(matches!(app.mode, AppMode::Normal));
+    }
+
+    /// `setup_app` with `use_trash` on and a trash outside the tree.
+    fn setup_trash_app() -> (TempDir, TempDir, App) {
+        let (dir, mut app) = setup_app();
+        let trash_home = TempDir::new().unwrap();
+        app.config.general.use_trash = Some(true);
+        app.trash = Some(crate::fs::trash::Trash {
+            dir: trash_home.path().join("Trash"),
+            layout: crate::fs::trash::TrashLayout::Freedesktop,
+        });
+        (dir, trash_home, app)
+    }
+
+    fn delete_to_trash(app: &App) -> bool {
+        match &app.mode {
+            AppMode::Dialog(DialogKind::DeleteConfirm { to_trash, .. }) => *to_trash,
+            other => panic!("expected delete confirm, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn trashed_delete_is_restored_by_undo() {
+        let (dir, trash_home, mut app) = setup_trash_app();
+        let file_a = dir.path().join("file_a.txt");
+        app.tree_state.selected_index = 3;
+        handle_key(&mut app, make_key(KeyCode::Char('d')));
+        assert!(delete_to_trash(&app));
+
+        confirm_delete(&mut app, 1).await;
+        assert!(!file_a.exists());
+        assert!(trash_home.path().join("Trash/files/file_a.txt").exists());
+        assert!(trash_home
+            .path()
+            .join("Trash/info/file_a.txt.trashinfo")
+            .exists());
+        let (msg, _) = app.status_message.as_ref().unwrap();
+        assert_eq!(msg, "Moved to trash: file_a.txt");
+
+        handle_key(
+            &mut app,
+            make_key_with_modifiers(KeyCode::Char('z'), KeyModifiers::CONTROL),
+        );
+        assert!(file_a.exists());
+        assert!(!trash_home.path().join("Trash/files/file_a.txt").exists());
+        assert!(app.tree_state.find_index_by_path(&file_a).is_some());
+        let (msg, _) = app.status_message.as_ref().unwrap();
+        assert_eq!(msg, "Undo: restored 1 item from the trash");
+    }
+
+    #[tokio::test]
+    async fn shift_d_skips_the_trash() {
+        let (dir, trash_home, mut app) = setup_trash_app();
+        app.tree_state.selected_index = 3;
+        handle_key(&mut app, make_key(KeyCode::Char('D')));
+        assert!(!delete_to_trash(&app));
+
+        confirm_delete(&mut app, 1).await;
+        assert!(!dir.path().join("file_a.txt").exists());
+        assert!(!trash_home.path().join("Trash").exists());
+        assert!(app.last_undo.is_none());
+        let (msg, _) = app.status_message.as_ref().unwrap();
+        assert_eq!(msg, "Deleted: file_a.txt");
+    }
+
+    fn delete_sizing(app: &App) -> Sizing {
+        match &app.mode {
+            AppMode::Dialog(DialogKind::DeleteConfirm { sizing, .. }) => *sizing,
+            other => panic!("expected delete confirm, got {:?}", other),
+        }
+    }
+
+    /// `alpha/` with three files: four entries with the directory.
+    fn fill_alpha(dir: &TempDir) {
+        for name in ["a.txt", "b.txt", "c.txt"] {
+            fs::write(dir.path().join("alpha").join(name), "x").unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn delete_waits_for_the_count() {
+        let (dir, mut app) = setup_app();
+        fill_alpha(&dir);
+        app.tree_state.selected_index = 1; // alpha
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        handle_key_event(&mut app, make_key(KeyCode::Char('d')), &tx);
+        assert!(matches!(delete_sizing(&app), Sizing::Counting { .. }));
+
+        // `y` before the count is in does nothing
+        handle_key_event(&mut app, make_key(KeyCode::Char('y')), &tx);
+        assert!(matches!(delete_sizing(&app), Sizing::Counting { .. }));
+        assert!(dir.path().join("alpha").exists());
+
+        drop(tx);
+        pump_preflight(&mut app, &mut rx).await;
+        match delete_sizing(&app) {
+            Sizing::Counted { usage, large, .. } => {
+                assert_eq!(usage.entries, 4);
+                assert_eq!(usage.files, 3);
+                assert!(!large);
+            }
+            other => panic!("expected a count, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn large_delete_needs_y_then_enter() {
+        let (dir, mut app) = setup_app();
+        fill_alpha(&dir);
+        app.config.general.large_op_entries = Some(4);
+        app.tree_state.selected_index = 1; // alpha
+        press_delete(&mut app).await;
+        assert!(matches!(
+            delete_sizing(&app),
+            Sizing::Counted {
+                large: true,
+                armed: false,
+                ..
+            }
+        ));
+
+        // Held-down `y` only arms it
+        for _ in 0..3 {
+            handle_key(&mut app, make_key(KeyCode::Char('y')));
+        }
+        assert!(matches!(
+            delete_sizing(&app),
+            Sizing::Counted { armed: true, .. }
+        ));
+        match &app.mode {
+            AppMode::Dialog(kind) => {
+                assert_eq!(kind.title(), "Delete 1 item permanently — confirm 2/2")
+            }
+            other => panic!("expected a dialog, got {:?}", other),
+        }
+        assert!(dir.path().join("alpha").exists());
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        handle_key_event(&mut app, make_key(KeyCode::Enter), &tx);
+        // The progress dialog counts the pre-counted entries
+        assert!(matches!(
+            app.mode,
+            AppMode::Dialog(DialogKind::Progress { total: Some(4), .. })
+        ));
+        drop(tx);
+        while let Some(event) = rx.recv().await {
+            match event {
+                Event::Progress(update) => {
+                    assert_eq!(update.total, Some(4));
+                    app.handle_progress(update);
+                }
+                Event::DeleteComplete(result) => app.handle_delete_complete(result),
+                _ => {}
+            }
+        }
+        assert!(!dir.path().join("alpha").exists());
+        assert!(matches!(app.mode, AppMode::Normal));
+    }
+
+    #[test]
+    fn preflight_count_can_be_turned_off() {
+        let (dir, mut app) = setup_app();
+        fill_alpha(&dir);
+        app.config.general.preflight_count = Some(false);
+        app.config.general.large_op_entries = Some(1);
+        app.tree_state.selected_index = 1; // alpha
+
+        // Counted on the spot and never "large", so no runtime is needed
+        handle_key(&mut app, make_key(KeyCode::Char('d')));
+        assert!(matches!(
+            delete_sizing(&app),
+            Sizing::Counted { large: false, .. }
+        ));
+    }
+
+    /// Copy alpha/ and paste it into beta/, returning the event receiver
+    /// with the pre-flight count (if any) still pending.
+    fn paste_alpha_into_beta(app: &mut App) -> mpsc::UnboundedReceiver<Event> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        app.tree_state.selected_index = 1; // alpha
+        app.copy_to_clipboard();
+        app.tree_state.selected_index = 2; // beta
+        app.expand_selected();
+        app.paste_clipboard_async(tx);
+        rx
+    }
+
+    #[tokio::test]
+    async fn precount_feeds_paste_progress_totals() {
+        let (dir, mut app) = setup_app();
+        fill_alpha(&dir);
+        let mut rx = paste_alpha_into_beta(&mut app);
+        assert!(matches!(
+            app.mode,
+            AppMode::Dialog(DialogKind::TransferConfirm {
+                sizing: Sizing::Counting { .. },
+                ..
+            })
+        ));
+
+        // Small: starts as soon as the count is in, sized by it
+        let mut totals = Vec::new();
+        while let Some(event) = rx.recv().await {
+            match event {
+                Event::PreflightComplete { id, usage } => {
+                    app.handle_preflight_complete(id, usage, &mpsc::unbounded_channel().0);
+                    assert!(matches!(
+                        app.mode,
+                        AppMode::Dialog(DialogKind::Progress { total: Some(4), .. })
+                    ));
+                }
+                Event::Progress(update) => totals.push(update.total),
+                Event::OperationComplete(result) => {
+                    app.handle_operation_complete(result);
+                    break;
+                }
+                _ => {}
+            }
+        }
+        assert!(!totals.is_empty());
+        assert!(totals.iter().all(|&total| total == Some(4)));
+        assert!(dir.path().join("beta").join("alpha").join("c.txt").exists());
+    }
+
+    #[tokio::test]
+    async fn large_paste_can_be_cancelled() {
+        let (dir, mut app) = setup_app();
+        fill_alpha(&dir);
+        app.config.general.large_op_bytes = Some(3);
+        let mut rx = paste_alpha_into_beta(&mut app);
+        if let Some(Event::PreflightComplete { id, usage }) = rx.recv().await {
+            app.handle_preflight_complete(id, usage, &mpsc::unbounded_channel().0);
+        }
+        match &app.mode {
+            AppMode::Dialog(DialogKind::TransferConfirm { sizing, .. }) => {
+                assert!(matches!(sizing, Sizing::Counted { large: true, .. }));
+            }
+            other => panic!("expected transfer confirm, got {:?}", other),
+        }
+        assert!(!app.in_flight.is_busy());
+
+        handle_key(&mut app, make_key(KeyCode::Esc));
+        assert!(matches!(app.mode, AppMode::Normal));
+        assert!(app.preflight_transfer.is_none());
+        assert!(!dir.path().join("beta").join("alpha").exists());
+        let (msg, _) = app.status_message.as_ref().unwrap();
+        assert_eq!(msg, "Paste cancelled");
+    }
+
+    #[tokio::test]
+    async fn paste_without_preflight_starts_right_away() {
         let (dir, mut app) = setup_app();
-        // Select file_a.txt (index 3)
-        app.tree_state.selected_index = 3;
-        handle_key(&mut app, make_key(KeyCode::Char('d')));
-        // Confirm delete
-        handle_key(&mut app, make_key(KeyCode::Char('y')));
-        assert!(!dir.path().join("file_a.txt").exists());
-        assert!(matches!(app.mode, AppMode::Normal));
+        fill_alpha(&dir);
+        app.config.general.preflight_count = Some(false);
+        let mut rx = paste_alpha_into_beta(&mut app);
+        assert!(app.in_flight.is_busy());
+        assert!(matches!(
+            app.mode,
+            AppMode::Dialog(DialogKind::Progress { total: Some(1), .. })
+        ));
+        assert_eq!(drain_completions(&mut app, &mut rx).await, 1);
+        assert!(dir.path().join("beta").join("alpha").join("a.txt").exists());
     }
 
-    #[test]
-    fn delete_cancel_preserves_file() {
+    #[tokio::test]
+    async fn single_file_paste_reports_bytes() {
         let (dir, mut app) = setup_app();
-        app.tree_state.selected_index = 3;
-        handle_key(&mut app, make_key(KeyCode::Char('d')));
-        handle_key(&mut app, make_key(KeyCode::Char('n')));
-        assert!(dir.path().join("file_a.txt").exists());
-        assert!(matches!(app.mode, AppMode::Normal));
+        let size = 9 * 1024 * 1024;
+        fs::write(dir.path().join("file_a.txt"), vec![b'x'; size]).unwrap();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        app.tree_state.selected_index = 3; // file_a.txt
+        app.copy_to_clipboard();
+        app.tree_state.selected_index = 2; // beta
+        app.paste_clipboard_async(tx);
+
+        let mut bytes_seen = Vec::new();
+        while let Some(event) = rx.recv().await {
+            match event {
+                Event::Progress(update) => {
+                    assert_eq!(update.total, Some(1));
+                    assert_eq!(update.bytes_total, size as u64);
+                    bytes_seen.push(update.bytes_done);
+                    app.handle_progress(update);
+                }
+                Event::OperationComplete(result) => {
+                    app.handle_operation_complete(result);
+                    break;
+                }
+                _ => {}
+            }
+        }
+        // Within the one file, every few megabytes
+        assert!(bytes_seen.len() >= 3);
+        assert!(bytes_seen.windows(2).all(|w| w[0] <= w[1]));
+        assert!(*bytes_seen.last().unwrap() >= 8 * 1024 * 1024);
+        let copied = dir.path().join("beta").join("file_a.txt");
+        assert_eq!(fs::metadata(copied).unwrap().len(), size as u64);
     }
 
     #[test]
@@ -1507,7 +3215,7 @@ mod tests {
         for c in "brand_new.txt".chars() {
             handle_key(&mut app, make_key(KeyCode::Char(c)));
         }
-        handle_key(&mut app, make_key(KeyCode::Enter));
+        confirm_typed(&mut app);
         // Tree should have one more item
         assert_eq!(app.tree_state.flat_items.len(), before_count + 1);
     }
@@ -1598,6 +3306,86 @@ mod tests {
         assert!(!app.preview_state.line_wrap);
     }
 
+    #[test]
+    fn tab_keys_open_switch_and_close_tabs() {
+        let (dir, mut app) = setup_app();
+        let ctrl = |c| make_key_with_modifiers(KeyCode::Char(c), KeyModifiers::CONTROL);
+        handle_key(&mut app, ctrl('n'));
+        handle_key(&mut app, ctrl('n'));
+        assert_eq!((app.tabs.active(), app.tabs.len()), (2, 3));
+
+        // `gt` wraps to the first tab, putting back the selection `g` moved
+        handle_key(&mut app, make_key(KeyCode::Char('j')));
+        handle_key(&mut app, make_key(KeyCode::Char('g')));
+        handle_key(&mut app, make_key(KeyCode::Char('t')));
+        assert_eq!(app.tabs.active(), 0);
+        handle_key(&mut app, make_key(KeyCode::Char('g')));
+        handle_key(&mut app, make_key(KeyCode::Char('T')));
+        assert_eq!(app.tabs.active(), 2);
+        assert_eq!(app.tree_state.selected_index, 1);
+        // A `g` followed by anything else stays a plain jump
+        handle_key(&mut app, make_key(KeyCode::Char('g')));
+        handle_key(&mut app, make_key(KeyCode::Char('j')));
+        handle_key(&mut app, make_key(KeyCode::Char('t')));
+        assert_eq!(app.tabs.active(), 2);
+        assert_eq!(app.tree_state.selected_index, 1);
+
+        let ctrl_page = |code| make_key_with_modifiers(code, KeyModifiers::CONTROL);
+        handle_key(&mut app, ctrl_page(KeyCode::PageUp));
+        assert_eq!(app.tabs.active(), 1);
+        handle_key(&mut app, ctrl_page(KeyCode::PageDown));
+        assert_eq!(app.tabs.active(), 2);
+
+        // Ctrl+W closes the tab from the tree, and wraps lines in the preview
+        app.focused_panel = FocusedPanel::Preview;
+        handle_key(&mut app, ctrl('w'));
+        assert_eq!(app.tabs.len(), 3);
+        assert!(app.preview_state.line_wrap);
+        app.focused_panel = FocusedPanel::Tree;
+        handle_key(&mut app, ctrl('w'));
+        assert_eq!((app.tabs.active(), app.tabs.len()), (1, 2));
+        assert_eq!(app.tree_state.root.path, dir.path());
+    }
+
+    #[test]
+    fn preview_esc_cancels_a_stuck_load_and_r_retries() {
+        use crate::preview_content::PendingHighlight;
+        use crate::preview_load::{LoadRequest, Loaded, PreviewLoader};
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        struct StuckLoader;
+        impl PreviewLoader for StuckLoader {
+            fn load(
+                &self,
+                _: &LoadRequest,
+                cancel: &AtomicBool,
+            ) -> Option<(Loaded, Option<PendingHighlight>)> {
+                while !cancel.load(Ordering::SeqCst) {
+                    std::thread::sleep(std::time::Duration::from_millis(5));
+                }
+                None
+            }
+        }
+
+        let (_dir, mut app) = setup_app();
+        app.preview_loader = std::sync::Arc::new(StuckLoader);
+        app.preview_load.inline_wait = std::time::Duration::ZERO;
+        app.tree_state.selected_index = 3;
+        app.update_preview();
+        app.focused_panel = FocusedPanel::Preview;
+        assert!(app.preview_load.is_loading());
+
+        handle_key(&mut app, make_key(KeyCode::Esc));
+        assert!(app.preview_load.is_stopped());
+        // Esc stays in the preview rather than leaving it
+        assert_eq!(app.focused_panel, FocusedPanel::Preview);
+
+        handle_key(&mut app, make_key(KeyCode::Char('r')));
+        assert!(app.preview_load.is_loading());
+        handle_key(&mut app, make_key(KeyCode::Char('x')));
+        assert!(app.preview_load.is_stopped());
+    }
+
     #[test]
     fn preview_j_does_not_navigate_tree() {
         let (_dir, mut app) = setup_app();
@@ -1756,6 +3544,65 @@ mod tests {
         assert!(app.clipboard.is_empty());
     }
 
+    /// Count `OperationComplete` events until the channel stays quiet.
+    async fn drain_completions(app: &mut App, rx: &mut mpsc::UnboundedReceiver<Event>) -> usize {
+        let mut completed = 0;
+        while let Ok(Some(evt)) =
+            tokio::time::timeout(std::time::Duration::from_millis(100), rx.recv()).await
+        {
+            if let Event::OperationComplete(result) = evt {
+                app.handle_operation_complete(result);
+                completed += 1;
+            }
+        }
+        completed
+    }
+
+    #[tokio::test]
+    async fn repeated_paste_runs_one_operation() {
+        let (dir, mut app) = setup_app();
+        let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+        app.tree_state.selected_index = 3;
+        app.copy_to_clipboard();
+        app.tree_state.selected_index = 2;
+        app.expand_selected();
+
+        // Repeats while the first paste is running, then after it finished
+        for _ in 0..5 {
+            app.paste_clipboard_async(tx.clone());
+        }
+        assert_eq!(drain_completions(&mut app, &mut rx).await, 1);
+        for _ in 0..5 {
+            handle_key_event(&mut app, make_key(KeyCode::Char('p')), &tx);
+        }
+        assert!(matches!(app.mode, AppMode::Normal));
+        assert_eq!(drain_completions(&mut app, &mut rx).await, 0);
+
+        let pasted = fs::read_dir(dir.path().join("beta")).unwrap().count();
+        assert_eq!(pasted, 1);
+    }
+
+    #[tokio::test]
+    async fn paste_during_running_operation_is_rejected() {
+        let (dir, mut app) = setup_app();
+        let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+        app.tree_state.selected_index = 3;
+        app.copy_to_clipboard();
+        app.paste_clipboard_async(tx.clone());
+        assert!(app.in_flight.is_busy());
+
+        // A different paste (new destination) while the first still runs
+        app.tree_state.selected_index = 2;
+        app.expand_selected();
+        app.paste_clipboard_async(tx.clone());
+        let (msg, _) = app.status_message.as_ref().unwrap();
+        assert!(msg.contains("already in progress"));
+
+        assert_eq!(drain_completions(&mut app, &mut rx).await, 1);
+        assert!(!app.in_flight.is_busy());
+        assert!(!dir.path().join("beta").join("file_a.txt").exists());
+    }
+
     #[test]
     fn paste_empty_clipboard_shows_message() {
         let (_dir, mut app) = setup_app();
@@ -1766,118 +3613,523 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn paste_copy_preserves_clipboard() {
+    async fn paste_copy_preserves_clipboard() {
+        let (dir, mut app) = setup_app();
+        let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+        app.tree_state.selected_index = 3;
+        app.copy_to_clipboard();
+        // Paste into beta
+        app.tree_state.selected_index = 2;
+        app.expand_selected();
+        app.paste_clipboard_async(tx);
+        loop {
+            if let Some(evt) = rx.recv().await {
+                if let Event::OperationComplete(result) = evt {
+                    app.handle_operation_complete(result);
+                    break;
+                }
+            }
+        }
+        assert!(dir.path().join("beta").join("file_a.txt").exists());
+        // Clipboard still populated (copy doesn't clear it)
+        assert!(!app.clipboard.is_empty());
+    }
+
+    // === Undo tests ===
+
+    #[test]
+    fn undo_rename() {
+        let (dir, mut app) = setup_app();
+        // Rename file_a.txt -> renamed.txt
+        app.tree_state.selected_index = 3;
+        handle_key(&mut app, make_key(KeyCode::Char('r')));
+        // Clear and type new name
+        for _ in 0..app.dialog_state.input.len() {
+            handle_key(&mut app, make_key(KeyCode::Backspace));
+        }
+        for c in "renamed.txt".chars() {
+            handle_key(&mut app, make_key(KeyCode::Char(c)));
+        }
+        confirm_typed(&mut app);
+        assert!(dir.path().join("renamed.txt").exists());
+        assert!(!dir.path().join("file_a.txt").exists());
+        // Undo
+        handle_key(
+            &mut app,
+            make_key_with_modifiers(KeyCode::Char('z'), KeyModifiers::CONTROL),
+        );
+        assert!(dir.path().join("file_a.txt").exists());
+        assert!(!dir.path().join("renamed.txt").exists());
+    }
+
+    #[tokio::test]
+    async fn undo_copy_paste() {
+        let (dir, mut app) = setup_app();
+        let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+        app.tree_state.selected_index = 3;
+        app.copy_to_clipboard();
+        app.tree_state.selected_index = 2;
+        app.expand_selected();
+        app.paste_clipboard_async(tx);
+        loop {
+            if let Some(evt) = rx.recv().await {
+                if let Event::OperationComplete(result) = evt {
+                    app.handle_operation_complete(result);
+                    break;
+                }
+            }
+        }
+        assert!(dir.path().join("beta").join("file_a.txt").exists());
+        // Undo should delete the copy
+        app.undo();
+        assert!(!dir.path().join("beta").join("file_a.txt").exists());
+        // Original still exists
+        assert!(dir.path().join("file_a.txt").exists());
+    }
+
+    #[test]
+    fn undo_nothing_shows_message() {
+        let (_dir, mut app) = setup_app();
+        handle_key(
+            &mut app,
+            make_key_with_modifiers(KeyCode::Char('z'), KeyModifiers::CONTROL),
+        );
+        assert!(app.status_message.is_some());
+        let (msg, _) = app.status_message.as_ref().unwrap();
+        assert!(msg.contains("Nothing to undo"));
+    }
+
+    #[test]
+    fn undo_only_works_once() {
+        let (dir, mut app) = setup_app();
+        // Rename
+        app.tree_state.selected_index = 3;
+        handle_key(&mut app, make_key(KeyCode::Char('r')));
+        for _ in 0..app.dialog_state.input.len() {
+            handle_key(&mut app, make_key(KeyCode::Backspace));
+        }
+        for c in "renamed.txt".chars() {
+            handle_key(&mut app, make_key(KeyCode::Char(c)));
+        }
+        confirm_typed(&mut app);
+        // Undo once
+        handle_key(
+            &mut app,
+            make_key_with_modifiers(KeyCode::Char('z'), KeyModifiers::CONTROL),
+        );
+        assert!(dir.path().join("file_a.txt").exists());
+        // Second undo should say "nothing"
+        handle_key(
+            &mut app,
+            make_key_with_modifiers(KeyCode::Char('z'), KeyModifiers::CONTROL),
+        );
+        let (msg, _) = app.status_message.as_ref().unwrap();
+        assert!(msg.contains("Nothing to undo"));
+    }
+
+    /// Paste the clipboard into beta/ and wait for the operation to finish.
+    async fn paste_into_beta(app: &mut App) {
+        let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+        app.tree_state.selected_index = 2;
+        app.expand_selected();
+        app.paste_clipboard_async(tx);
+        loop {
+            if let Some(Event::OperationComplete(result)) = rx.recv().await {
+                app.handle_operation_complete(result);
+                break;
+            }
+        }
+    }
+
+    fn ctrl_z(app: &mut App) {
+        handle_key(
+            app,
+            make_key_with_modifiers(KeyCode::Char('z'), KeyModifiers::CONTROL),
+        );
+    }
+
+    fn undo_conflicts(app: &App) -> Vec<crate::fs::undo_check::UndoConflict> {
+        match &app.mode {
+            AppMode::Dialog(DialogKind::UndoConfirm { conflicts, .. }) => conflicts.clone(),
+            other => panic!("expected undo confirmation, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn undo_copy_of_modified_file_asks_before_deleting() {
+        let (dir, mut app) = setup_app();
+        app.tree_state.selected_index = 3;
+        app.copy_to_clipboard();
+        paste_into_beta(&mut app).await;
+        let copy = dir.path().join("beta").join("file_a.txt");
+        fs::write(&copy, "edited after the copy").unwrap();
+
+        ctrl_z(&mut app);
+        let conflicts = undo_conflicts(&app);
+        assert_eq!(conflicts.len(), 1);
+        assert!(conflicts[0]
+            .message
+            .contains("beta/file_a.txt was modified after the copy"));
+        assert!(copy.exists());
+
+        // Approve the item and confirm
+        handle_key(&mut app, make_key(KeyCode::Char(' ')));
+        handle_key(&mut app, make_key(KeyCode::Enter));
+        assert_eq!(app.mode, AppMode::Normal);
+        assert!(!copy.exists());
+    }
+
+    #[tokio::test]
+    async fn undo_copy_skips_unapproved_items() {
+        let (dir, mut app) = setup_app();
+        app.tree_state.selected_index = 3;
+        app.copy_to_clipboard();
+        paste_into_beta(&mut app).await;
+        let copy = dir.path().join("beta").join("file_a.txt");
+        fs::write(&copy, "keep me").unwrap();
+
+        ctrl_z(&mut app);
+        handle_key(&mut app, make_key(KeyCode::Enter));
+        assert!(copy.exists());
+        let (msg, _) = app.status_message.as_ref().unwrap();
+        assert!(msg.contains("1 skipped"));
+    }
+
+    #[tokio::test]
+    async fn undo_copy_of_deleted_file_cannot_be_applied() {
+        let (dir, mut app) = setup_app();
+        app.tree_state.selected_index = 3;
+        app.copy_to_clipboard();
+        paste_into_beta(&mut app).await;
+        fs::remove_file(dir.path().join("beta").join("file_a.txt")).unwrap();
+
+        ctrl_z(&mut app);
+        handle_key(&mut app, make_key(KeyCode::Char('a')));
+        let conflicts = undo_conflicts(&app);
+        assert!(conflicts[0].message.contains("no longer exists"));
+        assert!(!conflicts[0].apply);
+        handle_key(&mut app, make_key(KeyCode::Enter));
+        assert_eq!(app.mode, AppMode::Normal);
+        assert!(dir.path().join("file_a.txt").exists());
+    }
+
+    #[tokio::test]
+    async fn undo_move_onto_recreated_original_asks_and_can_cancel() {
         let (dir, mut app) = setup_app();
-        let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
         app.tree_state.selected_index = 3;
-        app.copy_to_clipboard();
-        // Paste into beta
-        app.tree_state.selected_index = 2;
-        app.expand_selected();
-        app.paste_clipboard_async(tx);
-        loop {
-            if let Some(evt) = rx.recv().await {
-                if let Event::OperationComplete(result) = evt {
-                    app.handle_operation_complete(result);
-                    break;
-                }
-            }
-        }
+        app.cut_to_clipboard();
+        paste_into_beta(&mut app).await;
+        let original = dir.path().join("file_a.txt");
+        assert!(!original.exists());
+        fs::write(&original, "recreated").unwrap();
+
+        ctrl_z(&mut app);
+        let conflicts = undo_conflicts(&app);
+        assert_eq!(conflicts.len(), 1);
+        assert!(conflicts[0].message.contains("file_a.txt was recreated"));
+
+        // Cancel keeps everything in place and the undo available
+        handle_key(&mut app, make_key(KeyCode::Esc));
+        assert_eq!(app.mode, AppMode::Normal);
+        assert_eq!(fs::read_to_string(&original).unwrap(), "recreated");
         assert!(dir.path().join("beta").join("file_a.txt").exists());
-        // Clipboard still populated (copy doesn't clear it)
-        assert!(!app.clipboard.is_empty());
+        assert!(app.last_undo.is_some());
     }
 
-    // === Undo tests ===
+    #[tokio::test]
+    async fn undo_move_of_modified_file_applies_when_approved() {
+        let (dir, mut app) = setup_app();
+        app.tree_state.selected_index = 3;
+        app.cut_to_clipboard();
+        paste_into_beta(&mut app).await;
+        let moved = dir.path().join("beta").join("file_a.txt");
+        fs::write(&moved, "edited after the move").unwrap();
+
+        ctrl_z(&mut app);
+        assert!(undo_conflicts(&app)[0]
+            .message
+            .contains("modified after the move"));
+        handle_key(&mut app, make_key(KeyCode::Char('a')));
+        handle_key(&mut app, make_key(KeyCode::Enter));
+        assert!(!moved.exists());
+        assert_eq!(
+            fs::read_to_string(dir.path().join("file_a.txt")).unwrap(),
+            "edited after the move"
+        );
+    }
 
     #[test]
-    fn undo_rename() {
+    fn undo_rename_of_modified_file_asks_first() {
         let (dir, mut app) = setup_app();
-        // Rename file_a.txt -> renamed.txt
         app.tree_state.selected_index = 3;
         handle_key(&mut app, make_key(KeyCode::Char('r')));
-        // Clear and type new name
         for _ in 0..app.dialog_state.input.len() {
             handle_key(&mut app, make_key(KeyCode::Backspace));
         }
         for c in "renamed.txt".chars() {
             handle_key(&mut app, make_key(KeyCode::Char(c)));
         }
-        handle_key(&mut app, make_key(KeyCode::Enter));
+        confirm_typed(&mut app);
+        fs::write(dir.path().join("renamed.txt"), "changed").unwrap();
+        fs::write(dir.path().join("file_a.txt"), "new file").unwrap();
+
+        ctrl_z(&mut app);
+        let conflicts = undo_conflicts(&app);
+        assert_eq!(conflicts.len(), 2);
+        assert!(conflicts[0].message.contains("modified after the rename"));
+        assert!(conflicts[1].message.contains("exists again"));
+
+        // Approving only one of the two changes still skips the item
+        handle_key(&mut app, make_key(KeyCode::Char(' ')));
+        confirm_typed(&mut app);
         assert!(dir.path().join("renamed.txt").exists());
-        assert!(!dir.path().join("file_a.txt").exists());
-        // Undo
-        handle_key(
-            &mut app,
-            make_key_with_modifiers(KeyCode::Char('z'), KeyModifiers::CONTROL),
+        assert_eq!(
+            fs::read_to_string(dir.path().join("file_a.txt")).unwrap(),
+            "new file"
         );
+    }
+
+    /// Rename file_a.txt to renamed.txt, save the undo to `session` and
+    /// start a fresh app on the same directory from it.
+    fn rename_and_restart(dir: &TempDir, session: &TempDir) -> App {
+        let mut app = App::new(dir.path(), crate::config::AppConfig::default()).unwrap();
+        app.tree_state.selected_index = 3;
+        handle_key(&mut app, make_key(KeyCode::Char('r')));
+        for _ in 0..app.dialog_state.input.len() {
+            handle_key(&mut app, make_key(KeyCode::Backspace));
+        }
+        for c in "renamed.txt".chars() {
+            handle_key(&mut app, make_key(KeyCode::Char(c)));
+        }
+        confirm_typed(&mut app);
+        crate::session::save_undo(session.path(), app.persistable_undo()).unwrap();
+        drop(app);
+
+        let mut app = App::new(dir.path(), crate::config::AppConfig::default()).unwrap();
+        if let Some(action) = crate::session::load_undo(session.path()) {
+            app.restore_undo(action);
+        }
+        app
+    }
+
+    #[test]
+    fn undo_survives_restart() {
+        let (dir, _app) = setup_app();
+        let session = TempDir::new().unwrap();
+        let mut app = rename_and_restart(&dir, &session);
+        assert!(app.undo_from_previous_session);
+        let (msg, _) = app.status_message.as_ref().unwrap();
+        assert!(msg.contains("previous session"), "{}", msg);
+
+        ctrl_z(&mut app);
+        assert_eq!(app.mode, AppMode::Normal);
         assert!(dir.path().join("file_a.txt").exists());
         assert!(!dir.path().join("renamed.txt").exists());
+        assert!(!app.undo_from_previous_session);
+    }
+
+    #[test]
+    fn restored_undo_of_changed_file_goes_through_review() {
+        let (dir, _app) = setup_app();
+        let session = TempDir::new().unwrap();
+        let mut app = rename_and_restart(&dir, &session);
+        fs::write(dir.path().join("renamed.txt"), "edited after restart").unwrap();
+
+        ctrl_z(&mut app);
+        match &app.mode {
+            AppMode::Dialog(DialogKind::UndoConfirm {
+                conflicts,
+                from_previous_session,
+                ..
+            }) => {
+                assert!(*from_previous_session);
+                assert!(conflicts[0].message.contains("modified after the rename"));
+            }
+            other => panic!("expected undo confirmation, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn restored_undo_with_nothing_left_is_dropped() {
+        let (dir, _app) = setup_app();
+        let session = TempDir::new().unwrap();
+        let mut app = rename_and_restart(&dir, &session);
+        assert!(app.last_undo.is_some());
+        fs::remove_file(dir.path().join("renamed.txt")).unwrap();
+        assert_eq!(app.persistable_undo(), None);
+
+        let mut restarted = App::new(dir.path(), crate::config::AppConfig::default()).unwrap();
+        if let Some(action) = app.last_undo.take() {
+            restarted.restore_undo(action);
+        }
+        assert!(restarted.last_undo.is_none());
+        assert!(!restarted.undo_from_previous_session);
     }
 
     #[tokio::test]
-    async fn undo_copy_paste() {
-        let (dir, mut app) = setup_app();
-        let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+    async fn trashed_delete_is_restored_after_restart() {
+        let (dir, trash_home, mut app) = setup_trash_app();
+        let file_a = dir.path().join("file_a.txt");
+        let info = trash_home.path().join("Trash/info/file_a.txt.trashinfo");
         app.tree_state.selected_index = 3;
-        app.copy_to_clipboard();
+        handle_key(&mut app, make_key(KeyCode::Char('d')));
+        confirm_delete(&mut app, 1).await;
+        assert!(!file_a.exists());
+        assert!(info.exists());
+
+        let session = TempDir::new().unwrap();
+        crate::session::save_undo(session.path(), app.persistable_undo()).unwrap();
+        drop(app);
+        let mut app = App::new(dir.path(), crate::config::AppConfig::default()).unwrap();
+        app.restore_undo(crate::session::load_undo(session.path()).unwrap());
+        assert!(app.undo_from_previous_session);
+
+        ctrl_z(&mut app);
+        assert_eq!(app.mode, AppMode::Normal);
+        assert!(file_a.exists());
+        assert!(!trash_home.path().join("Trash/files/file_a.txt").exists());
+        assert!(!info.exists());
+        assert!(app.tree_state.find_index_by_path(&file_a).is_some());
+    }
+
+    // === Paste conflict tests ===
+
+    /// Copy `names` from the root into `beta`, where each already exists
+    /// with "old" content, and return the conflicts the paste asks about.
+    fn paste_over_beta(
+        dir: &TempDir,
+        app: &mut App,
+        names: &[&str],
+        tx: mpsc::UnboundedSender<Event>,
+    ) -> usize {
+        let sources = names
+            .iter()
+            .map(|name| {
+                fs::write(dir.path().join(name), format!("new {}", name)).unwrap();
+                fs::write(dir.path().join("beta").join(name), format!("old {}", name)).unwrap();
+                dir.path().join(name)
+            })
+            .collect();
+        app.clipboard
+            .set(sources, crate::fs::clipboard::ClipboardOp::Copy);
         app.tree_state.selected_index = 2;
         app.expand_selected();
         app.paste_clipboard_async(tx);
+        match &app.mode {
+            AppMode::Dialog(DialogKind::PasteConflict { collisions, .. }) => collisions.len(),
+            other => panic!("expected paste conflict, got {:?}", other),
+        }
+    }
+
+    fn backups_in(dir: &std::path::Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .filter(|name| name.contains(crate::fs::overwrite::BACKUP_MARKER))
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[tokio::test]
+    async fn overwrite_all_backs_up_each_file_and_undo_puts_them_back() {
+        let (dir, mut app) = setup_app();
+        app.config.general.backup_on_overwrite = Some(true);
+        let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+        assert_eq!(paste_over_beta(&dir, &mut app, &["a.txt", "b.txt"], tx), 2);
+
+        handle_key(&mut app, make_key(KeyCode::Char('a')));
+        let mut totals = Vec::new();
         loop {
-            if let Some(evt) = rx.recv().await {
-                if let Event::OperationComplete(result) = evt {
+            match rx.recv().await {
+                Some(Event::Progress(update)) => totals.push(update.total),
+                Some(Event::OperationComplete(result)) => {
                     app.handle_operation_complete(result);
                     break;
                 }
+                _ => {}
             }
         }
-        assert!(dir.path().join("beta").join("file_a.txt").exists());
-        // Undo should delete the copy
-        app.undo();
-        assert!(!dir.path().join("beta").join("file_a.txt").exists());
-        // Original still exists
-        assert!(dir.path().join("file_a.txt").exists());
-    }
+        // Two files and the two backups made before overwriting them
+        assert!(totals.iter().all(|total| *total == Some(4)));
+        assert_eq!(totals.last(), Some(&Some(4)));
 
-    #[test]
-    fn undo_nothing_shows_message() {
-        let (_dir, mut app) = setup_app();
-        handle_key(
-            &mut app,
-            make_key_with_modifiers(KeyCode::Char('z'), KeyModifiers::CONTROL),
+        let beta = dir.path().join("beta");
+        assert_eq!(fs::read_to_string(beta.join("a.txt")).unwrap(), "new a.txt");
+        assert_eq!(fs::read_to_string(beta.join("b.txt")).unwrap(), "new b.txt");
+        let backups = backups_in(&beta);
+        assert_eq!(backups.len(), 2);
+        assert!(backups[0].starts_with("a.txt.fm-bak-"));
+        assert_eq!(
+            fs::read_to_string(beta.join(&backups[0])).unwrap(),
+            "old a.txt"
         );
-        assert!(app.status_message.is_some());
         let (msg, _) = app.status_message.as_ref().unwrap();
-        assert!(msg.contains("Nothing to undo"));
+        assert_eq!(msg, "Pasted 2 items (2 backed up)");
+
+        ctrl_z(&mut app);
+        assert_eq!(app.mode, AppMode::Normal);
+        assert_eq!(fs::read_to_string(beta.join("a.txt")).unwrap(), "old a.txt");
+        assert_eq!(fs::read_to_string(beta.join("b.txt")).unwrap(), "old b.txt");
+        assert!(backups_in(&beta).is_empty());
+        let (msg, _) = app.status_message.as_ref().unwrap();
+        assert!(msg.contains("restored 2 overwritten files"));
     }
 
-    #[test]
-    fn undo_only_works_once() {
+    #[tokio::test]
+    async fn conflicts_are_settled_one_at_a_time() {
         let (dir, mut app) = setup_app();
-        // Rename
-        app.tree_state.selected_index = 3;
-        handle_key(&mut app, make_key(KeyCode::Char('r')));
-        for _ in 0..app.dialog_state.input.len() {
-            handle_key(&mut app, make_key(KeyCode::Backspace));
-        }
-        for c in "renamed.txt".chars() {
-            handle_key(&mut app, make_key(KeyCode::Char(c)));
+        let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+        paste_over_beta(&dir, &mut app, &["a.txt", "b.txt", "c.txt"], tx);
+
+        handle_key(&mut app, make_key(KeyCode::Char('s')));
+        handle_key(&mut app, make_key(KeyCode::Char('k')));
+        assert!(matches!(
+            app.mode,
+            AppMode::Dialog(DialogKind::PasteConflict { current: 2, .. })
+        ));
+        handle_key(&mut app, make_key(KeyCode::Char('o')));
+        loop {
+            if let Some(Event::OperationComplete(result)) = rx.recv().await {
+                app.handle_operation_complete(result);
+                break;
+            }
         }
-        handle_key(&mut app, make_key(KeyCode::Enter));
-        // Undo once
-        handle_key(
-            &mut app,
-            make_key_with_modifiers(KeyCode::Char('z'), KeyModifiers::CONTROL),
-        );
-        assert!(dir.path().join("file_a.txt").exists());
-        // Second undo should say "nothing"
-        handle_key(
-            &mut app,
-            make_key_with_modifiers(KeyCode::Char('z'), KeyModifiers::CONTROL),
+
+        let beta = dir.path().join("beta");
+        assert_eq!(fs::read_to_string(beta.join("a.txt")).unwrap(), "old a.txt");
+        assert_eq!(fs::read_to_string(beta.join("b.txt")).unwrap(), "old b.txt");
+        assert_eq!(
+            fs::read_to_string(beta.join("b_copy.txt")).unwrap(),
+            "new b.txt"
         );
+        // Overwritten without a backup: gone for good, and not undoable
+        assert_eq!(fs::read_to_string(beta.join("c.txt")).unwrap(), "new c.txt");
+        assert!(backups_in(&beta).is_empty());
+        match &app.last_undo {
+            Some(crate::app::UndoAction::CopyPaste { created_paths, .. }) => {
+                let created: Vec<_> = created_paths.iter().map(|c| c.path.clone()).collect();
+                assert_eq!(created, vec![beta.join("b_copy.txt")]);
+            }
+            other => panic!("expected copy undo, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn cancelling_a_conflict_pastes_nothing() {
+        let (dir, mut app) = setup_app();
+        let (tx, _rx) = mpsc::unbounded_channel::<Event>();
+        paste_over_beta(&dir, &mut app, &["a.txt"], tx);
+
+        handle_key(&mut app, make_key(KeyCode::Esc));
+        assert_eq!(app.mode, AppMode::Normal);
+        assert!(app.preflight_transfer.is_none());
         let (msg, _) = app.status_message.as_ref().unwrap();
-        assert!(msg.contains("Nothing to undo"));
+        assert_eq!(msg, "Paste cancelled");
+        assert_eq!(
+            fs::read_to_string(dir.path().join("beta").join("a.txt")).unwrap(),
+            "old a.txt"
+        );
     }
 
     // === Search (Ctrl+P) handler tests ===
@@ -1929,10 +4181,10 @@ mod tests {
         handle_key(&mut app, make_key(KeyCode::Char('l')));
         handle_key(&mut app, make_key(KeyCode::Char('e')));
         assert!(!app.search_state.results.is_empty());
-        handle_key(&mut app, make_key(KeyCode::Enter));
+        confirm_typed(&mut app);
         assert_eq!(app.mode, AppMode::SearchAction);
         // Press Enter again to navigate
-        handle_key(&mut app, make_key(KeyCode::Enter));
+        confirm_typed(&mut app);
         assert_eq!(app.mode, AppMode::Normal);
     }
 
@@ -1974,53 +4226,235 @@ mod tests {
 
     // === Filter (/) handler tests ===
 
-    #[test]
-    fn slash_opens_filter() {
-        let (_dir, mut app) = setup_app();
-        handle_key(&mut app, make_key(KeyCode::Char('/')));
-        assert_eq!(app.mode, AppMode::Filter);
+    #[test]
+    fn slash_opens_filter() {
+        let (_dir, mut app) = setup_app();
+        handle_key(&mut app, make_key(KeyCode::Char('/')));
+        assert_eq!(app.mode, AppMode::Filter);
+    }
+
+    #[test]
+    fn filter_esc_clears() {
+        let (_dir, mut app) = setup_app();
+        handle_key(&mut app, make_key(KeyCode::Char('/')));
+        handle_key(&mut app, make_key(KeyCode::Char('f')));
+        handle_key(&mut app, make_key(KeyCode::Esc));
+        assert_eq!(app.mode, AppMode::Normal);
+        assert!(!app.tree_state.is_filtering);
+    }
+
+    #[test]
+    fn filter_enter_accepts() {
+        let (_dir, mut app) = setup_app();
+        handle_key(&mut app, make_key(KeyCode::Char('/')));
+        handle_key(&mut app, make_key(KeyCode::Char('f')));
+        handle_key(&mut app, make_key(KeyCode::Enter));
+        assert_eq!(app.mode, AppMode::Normal);
+        // Filter view should persist
+        assert!(app.tree_state.is_filtering);
+    }
+
+    #[test]
+    fn filter_typing_filters_tree() {
+        let (_dir, mut app) = setup_app();
+        let total = app.tree_state.flat_items.len();
+        handle_key(&mut app, make_key(KeyCode::Char('/')));
+        handle_key(&mut app, make_key(KeyCode::Char('a')));
+        handle_key(&mut app, make_key(KeyCode::Char('l')));
+        handle_key(&mut app, make_key(KeyCode::Char('p')));
+        assert!(app.tree_state.flat_items.len() <= total);
+    }
+
+    #[test]
+    fn filter_backspace_updates() {
+        let (_dir, mut app) = setup_app();
+        handle_key(&mut app, make_key(KeyCode::Char('/')));
+        handle_key(&mut app, make_key(KeyCode::Char('z')));
+        handle_key(&mut app, make_key(KeyCode::Backspace));
+        // Filter cleared, back to full tree
+        assert!(!app.tree_state.is_filtering);
+    }
+
+    #[test]
+    fn slash_in_preview_searches_its_text() {
+        let (dir, mut app) = setup_app();
+        app.preview_state.current_path = Some(dir.path().join("file_a.txt"));
+        app.preview_state.content_lines = ["one cat", "dog", "two cats"]
+            .into_iter()
+            .map(ratatui::text::Line::from)
+            .collect();
+        app.focused_panel = FocusedPanel::Preview;
+
+        handle_key(&mut app, make_key(KeyCode::Char('/')));
+        assert_eq!(app.mode, AppMode::PreviewSearch);
+        // Keys go into the query, not to the tree filter or the preview
+        for c in "cat".chars() {
+            handle_key(&mut app, make_key(KeyCode::Char(c)));
+        }
+        assert!(!app.tree_state.is_filtering);
+        assert_eq!(app.preview_search.matches.len(), 2);
+        confirm_typed(&mut app);
+        assert_eq!(app.mode, AppMode::Normal);
+
+        handle_key(&mut app, make_key(KeyCode::Char('n')));
+        assert_eq!(app.preview_search.current().map(|m| m.row), Some(2));
+        handle_key(&mut app, make_key(KeyCode::Char('N')));
+        assert_eq!(app.preview_search.current().map(|m| m.row), Some(0));
+        handle_key(&mut app, make_key(KeyCode::Esc));
+        assert!(!app.preview_search.is_active());
+        assert_eq!(app.focused_panel, FocusedPanel::Preview);
+    }
+
+    #[test]
+    fn i_shows_file_info_until_esc_or_enter() {
+        let (_dir, mut app) = setup_app();
+        app.tree_state.selected_index = app
+            .tree_state
+            .flat_items
+            .iter()
+            .position(|item| item.name == "file_a.txt")
+            .unwrap();
+        handle_key(&mut app, make_key(KeyCode::Char('i')));
+        assert!(matches!(app.mode, AppMode::Dialog(DialogKind::Info { .. })));
+        // Other keys leave it open
+        handle_key(&mut app, make_key(KeyCode::Char('j')));
+        assert!(matches!(app.mode, AppMode::Dialog(DialogKind::Info { .. })));
+        handle_key(&mut app, make_key(KeyCode::Esc));
+        assert_eq!(app.mode, AppMode::Normal);
+
+        handle_key(&mut app, make_key(KeyCode::Char('i')));
+        handle_key(&mut app, make_key(KeyCode::Enter));
+        assert_eq!(app.mode, AppMode::Normal);
+    }
+
+    #[test]
+    fn r_with_a_selection_bulk_renames_it() {
+        let (dir, mut app) = setup_app();
+        File::create(dir.path().join("file_b.rs")).unwrap();
+        app.tree_state.reload_dir(dir.path());
+        for name in ["file_a.txt", "file_b.rs"] {
+            app.tree_state.selected_index = app
+                .tree_state
+                .flat_items
+                .iter()
+                .position(|item| item.name == name)
+                .unwrap();
+            app.tree_state.toggle_multi_select();
+        }
+        handle_key(&mut app, make_key(KeyCode::Char('r')));
+        assert!(matches!(
+            app.mode,
+            AppMode::Dialog(DialogKind::BulkRename { .. })
+        ));
+        for c in "{n}_{name}.{ext}".chars() {
+            handle_key(&mut app, make_key(KeyCode::Char(c)));
+        }
+        assert_eq!(
+            app.bulk_rename_form_mut().unwrap().preview,
+            Ok(vec![
+                "file_a.txt → 1_file_a.txt".to_string(),
+                "file_b.rs → 2_file_b.rs".to_string(),
+            ])
+        );
+        handle_key(&mut app, make_key(KeyCode::Enter));
+        assert_eq!(app.mode, AppMode::Normal);
+        assert!(dir.path().join("1_file_a.txt").exists());
+        assert!(dir.path().join("2_file_b.rs").exists());
+        assert!(app.tree_state.multi_selected.is_empty());
+
+        // Without a selection `r` renames the focused item
+        handle_key(&mut app, make_key(KeyCode::Char('r')));
+        assert!(matches!(
+            app.mode,
+            AppMode::Dialog(DialogKind::Rename { .. })
+        ));
     }
 
+    #[cfg(unix)]
     #[test]
-    fn filter_esc_clears() {
-        let (_dir, mut app) = setup_app();
-        handle_key(&mut app, make_key(KeyCode::Char('/')));
-        handle_key(&mut app, make_key(KeyCode::Char('f')));
-        handle_key(&mut app, make_key(KeyCode::Esc));
+    fn shift_m_edits_permissions_with_the_grid() {
+        let (dir, mut app) = setup_app();
+        let file = dir.path().join("file_a.txt");
+        crate::fs::operations::set_permissions(&file, 0o644).unwrap();
+        app.tree_state.selected_index = app
+            .tree_state
+            .flat_items
+            .iter()
+            .position(|item| item.name == "file_a.txt")
+            .unwrap();
+        handle_key(&mut app, make_key(KeyCode::Char('M')));
+        assert!(matches!(
+            app.mode,
+            AppMode::Dialog(DialogKind::Permissions { .. })
+        ));
+        // Owner exec, then group write
+        for code in [
+            KeyCode::Right,
+            KeyCode::Right,
+            KeyCode::Char(' '),
+            KeyCode::Down,
+            KeyCode::Left,
+            KeyCode::Char(' '),
+        ] {
+            handle_key(&mut app, make_key(code));
+        }
+        assert_eq!(app.permissions_form_mut().unwrap().octal, "764");
+        handle_key(&mut app, make_key(KeyCode::Enter));
         assert_eq!(app.mode, AppMode::Normal);
-        assert!(!app.tree_state.is_filtering);
+        assert_eq!(crate::fs::operations::file_mode(&file).unwrap(), 0o764);
+
+        // Esc leaves the file alone
+        handle_key(&mut app, make_key(KeyCode::Char('M')));
+        handle_key(&mut app, make_key(KeyCode::Char('7')));
+        handle_key(&mut app, make_key(KeyCode::Char('0')));
+        handle_key(&mut app, make_key(KeyCode::Char('0')));
+        handle_key(&mut app, make_key(KeyCode::Esc));
+        assert_eq!(crate::fs::operations::file_mode(&file).unwrap(), 0o764);
     }
 
     #[test]
-    fn filter_enter_accepts() {
+    fn class_chip_keys_cycle_in_normal_and_filter_mode() {
         let (_dir, mut app) = setup_app();
-        handle_key(&mut app, make_key(KeyCode::Char('/')));
-        handle_key(&mut app, make_key(KeyCode::Char('f')));
-        handle_key(&mut app, make_key(KeyCode::Enter));
+        handle_key(&mut app, make_key(KeyCode::Char('F')));
+        assert_eq!(app.tree_state.filter_label(), "[Code]");
         assert_eq!(app.mode, AppMode::Normal);
-        // Filter view should persist
-        assert!(app.tree_state.is_filtering);
-    }
 
-    #[test]
-    fn filter_typing_filters_tree() {
-        let (_dir, mut app) = setup_app();
-        let total = app.tree_state.flat_items.len();
         handle_key(&mut app, make_key(KeyCode::Char('/')));
-        handle_key(&mut app, make_key(KeyCode::Char('a')));
-        handle_key(&mut app, make_key(KeyCode::Char('l')));
-        handle_key(&mut app, make_key(KeyCode::Char('p')));
-        assert!(app.tree_state.flat_items.len() <= total);
+        handle_key(&mut app, make_key(KeyCode::Tab));
+        assert_eq!(app.tree_state.filter_label(), "[Docs]");
+        handle_key(&mut app, make_key(KeyCode::BackTab));
+        assert_eq!(app.tree_state.filter_label(), "[Code]");
+        // Tab isn't typed into the query
+        assert!(app.tree_state.filter_query.is_empty());
+
+        handle_key(&mut app, make_key(KeyCode::Esc));
+        assert!(app.tree_state.filter_class.is_none());
+        assert!(!app.tree_state.is_filtering);
     }
 
     #[test]
-    fn filter_backspace_updates() {
+    fn ctrl_shift_f_opens_content_search() {
         let (_dir, mut app) = setup_app();
-        handle_key(&mut app, make_key(KeyCode::Char('/')));
-        handle_key(&mut app, make_key(KeyCode::Char('z')));
+        let ctrl_shift = KeyModifiers::CONTROL | KeyModifiers::SHIFT;
+        handle_key(
+            &mut app,
+            make_key_with_modifiers(KeyCode::Char('F'), ctrl_shift),
+        );
+        assert_eq!(app.mode, AppMode::Grep);
+
+        // Typing edits the query; j and k are letters here
+        handle_key(&mut app, make_key(KeyCode::Char('j')));
+        handle_key(&mut app, make_key(KeyCode::Char('k')));
         handle_key(&mut app, make_key(KeyCode::Backspace));
-        // Filter cleared, back to full tree
-        assert!(!app.tree_state.is_filtering);
+        assert_eq!(app.grep_state.query, "j");
+        handle_key(
+            &mut app,
+            make_key_with_modifiers(KeyCode::Char('r'), KeyModifiers::CONTROL),
+        );
+        assert!(app.grep_state.regex);
+
+        handle_key(&mut app, make_key(KeyCode::Esc));
+        assert_eq!(app.mode, AppMode::Normal);
     }
 
     // === Integration tests ===
@@ -2047,11 +4481,11 @@ mod tests {
         assert!(!app.search_state.results.is_empty());
 
         // Confirm -> goes to SearchAction
-        handle_key(&mut app, make_key(KeyCode::Enter));
+        confirm_typed(&mut app);
         assert_eq!(app.mode, AppMode::SearchAction);
 
         // Navigate from action menu
-        handle_key(&mut app, make_key(KeyCode::Enter));
+        confirm_typed(&mut app);
         assert_eq!(app.mode, AppMode::Normal);
 
         // Verify tree selection
@@ -2073,7 +4507,7 @@ mod tests {
         assert!(app.tree_state.is_filtering);
 
         // Accept filter
-        handle_key(&mut app, make_key(KeyCode::Enter));
+        confirm_typed(&mut app);
         assert_eq!(app.mode, AppMode::Normal);
         assert!(app.tree_state.is_filtering);
 
@@ -2093,15 +4527,15 @@ mod tests {
         for c in "new_file.txt".chars() {
             handle_key(&mut app, make_key(KeyCode::Char(c)));
         }
-        handle_key(&mut app, make_key(KeyCode::Enter));
+        confirm_typed(&mut app);
 
         // Cache should be invalidated
         assert!(app.search_state.cached_paths.is_none());
         assert!(dir.path().join("new_file.txt").exists());
     }
 
-    #[test]
-    fn search_cache_invalidated_after_delete() {
+    #[tokio::test]
+    async fn search_cache_invalidated_after_delete() {
         let (_dir, mut app) = setup_app();
         // Directly set a cached path list to simulate a prior search
         app.search_state.cached_paths = Some(vec![]);
@@ -2110,7 +4544,7 @@ mod tests {
         // Select file_a.txt (index 3) and delete
         app.tree_state.selected_index = 3;
         handle_key(&mut app, make_key(KeyCode::Char('d')));
-        handle_key(&mut app, make_key(KeyCode::Char('y')));
+        confirm_delete(&mut app, 1).await;
 
         // Cache should be invalidated
         assert!(app.search_state.cached_paths.is_none());
@@ -2145,9 +4579,10 @@ mod tests {
         assert_eq!(app.mode, AppMode::Search);
         handle_key(&mut app, make_key(KeyCode::Esc));
 
-        // / should work from preview panel (global key)
+        // / searches the preview's text rather than filtering the tree
+        app.preview_state.content_lines = vec![ratatui::text::Line::from("text")];
         handle_key(&mut app, make_key(KeyCode::Char('/')));
-        assert_eq!(app.mode, AppMode::Filter);
+        assert_eq!(app.mode, AppMode::PreviewSearch);
     }
 
     #[test]
@@ -2221,6 +4656,330 @@ mod tests {
         assert!(names.contains(&"f5_preview.txt"));
     }
 
+    #[test]
+    fn f12_toggles_debug_overlay() {
+        let (_dir, mut app) = setup_app();
+        handle_key(&mut app, make_key(KeyCode::F(12)));
+        assert!(app.show_debug_overlay);
+        handle_key(&mut app, make_key(KeyCode::F(12)));
+        assert!(!app.show_debug_overlay);
+    }
+
+    // === Send-to tests ===
+
+    #[test]
+    fn greater_than_opens_send_to_overlay() {
+        let (_dir, mut app) = setup_app();
+        app.tree_state.selected_index = 3;
+        handle_key(&mut app, make_key(KeyCode::Char('>')));
+        assert_eq!(app.mode, AppMode::SendTo);
+        handle_key(&mut app, make_key(KeyCode::Esc));
+        assert_eq!(app.mode, AppMode::Normal);
+    }
+
+    #[test]
+    fn capital_p_opens_places_and_enter_reroots() {
+        let (_dir, mut app) = setup_app();
+        let other = TempDir::new().unwrap();
+        app.config.bookmarks.insert(
+            "other".to_string(),
+            other.path().to_string_lossy().to_string(),
+        );
+        handle_key(&mut app, make_key(KeyCode::Char('P')));
+        assert_eq!(app.mode, AppMode::Places);
+
+        // Bookmarks are listed last
+        handle_key(&mut app, make_key(KeyCode::Char('k')));
+        handle_key(&mut app, make_key(KeyCode::Enter));
+        assert_eq!(app.mode, AppMode::Normal);
+        assert_eq!(app.tree_state.root.path, other.path());
+    }
+
+    #[test]
+    fn b_bookmarks_and_capital_b_lists_bookmarks() {
+        let (_dir, mut app) = setup_app();
+        // Row 1 is the alpha directory
+        handle_key(&mut app, make_key(KeyCode::Down));
+        handle_key(&mut app, make_key(KeyCode::Char('b')));
+        assert_eq!(app.bookmarks.paths().len(), 1);
+
+        handle_key(&mut app, make_key(KeyCode::Char('B')));
+        assert_eq!(app.mode, AppMode::Bookmarks);
+        handle_key(&mut app, make_key(KeyCode::Char('d')));
+        assert!(app.bookmarks.paths().is_empty());
+        handle_key(&mut app, make_key(KeyCode::Esc));
+        assert_eq!(app.mode, AppMode::Normal);
+    }
+
+    #[test]
+    fn colon_prompt_jumps_to_path_and_line() {
+        let (dir, mut app) = setup_app();
+        fs::write(
+            dir.path().join("alpha").join("notes.md"),
+            "one\ntwo\nthree\n",
+        )
+        .unwrap();
+        handle_key(&mut app, make_key(KeyCode::Char(':')));
+        assert_eq!(app.mode, AppMode::Dialog(DialogKind::GotoPath));
+        for c in "alpha/notes.md:2".chars() {
+            handle_key(&mut app, make_key(KeyCode::Char(c)));
+        }
+        confirm_typed(&mut app);
+        assert_eq!(app.mode, AppMode::Normal);
+        assert_eq!(
+            app.selected_file_path(),
+            Some(dir.path().join("alpha").join("notes.md"))
+        );
+        assert_eq!(app.line_flash_step().unwrap().0, 1);
+    }
+
+    #[tokio::test]
+    async fn send_to_shift_enter_moves_items() {
+        let (dir, mut app) = setup_app();
+        let target = tempfile::TempDir::new().unwrap();
+        app.config.send_to.insert(
+            "dest".to_string(),
+            target.path().to_string_lossy().to_string(),
+        );
+        app.tree_state.selected_index = 3; // file_a.txt
+        let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+        handle_key_event(&mut app, make_key(KeyCode::Char('>')), &tx);
+        handle_key_event(
+            &mut app,
+            make_key_with_modifiers(KeyCode::Enter, KeyModifiers::SHIFT),
+            &tx,
+        );
+        loop {
+            if let Some(Event::OperationComplete(result)) = rx.recv().await {
+                assert!(result.was_cut);
+                app.handle_operation_complete(result);
+                break;
+            }
+        }
+        assert!(target.path().join("file_a.txt").exists());
+        assert!(!dir.path().join("file_a.txt").exists());
+        assert!(app
+            .status_message
+            .as_ref()
+            .unwrap()
+            .0
+            .starts_with("Moved 1 item to dest"));
+    }
+
+    #[tokio::test]
+    async fn send_to_plain_enter_copies_items() {
+        let (dir, mut app) = setup_app();
+        let target = tempfile::TempDir::new().unwrap();
+        app.config.send_to.insert(
+            "dest".to_string(),
+            target.path().to_string_lossy().to_string(),
+        );
+        app.tree_state.selected_index = 3; // file_a.txt
+        let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+        handle_key_event(&mut app, make_key(KeyCode::Char('>')), &tx);
+        handle_key_event(&mut app, make_key(KeyCode::Enter), &tx);
+        loop {
+            if let Some(Event::OperationComplete(result)) = rx.recv().await {
+                assert!(!result.was_cut);
+                app.handle_operation_complete(result);
+                break;
+            }
+        }
+        assert!(target.path().join("file_a.txt").exists());
+        assert!(dir.path().join("file_a.txt").exists());
+    }
+
+    // === Open action (Enter on files) tests ===
+
+    /// Launcher that records launches instead of spawning processes.
+    #[derive(Clone, Default)]
+    struct RecordingLauncher {
+        launched: std::sync::Arc<std::sync::Mutex<Vec<String>>>,
+    }
+
+    impl crate::open_action::Launcher for RecordingLauncher {
+        fn open_external(&mut self, path: &std::path::Path) -> Result<(), String> {
+            self.launched
+                .lock()
+                .unwrap()
+                .push(format!("external {}", path.display()));
+            Ok(())
+        }
+
+        fn run_in_terminal(
+            &mut self,
+            _terminal: &crate::terminal::TerminalState,
+            command: &str,
+        ) -> Result<(), String> {
+            self.launched
+                .lock()
+                .unwrap()
+                .push(format!("terminal {}", command));
+            Ok(())
+        }
+    }
+
+    fn setup_open_app(rules: &[(&str, &str)]) -> (TempDir, App, RecordingLauncher) {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        for name in ["main.rs", "doc.pdf", "server.log", "plain.txt"] {
+            fs::write(dir.path().join(name), "hello\n").unwrap();
+        }
+        let mut config = crate::config::AppConfig::default();
+        for (pattern, action) in rules {
+            config
+                .open
+                .rules
+                .insert(pattern.to_string(), action.to_string());
+        }
+        let mut app = App::new(dir.path(), config).unwrap();
+        let launcher = RecordingLauncher::default();
+        app.launcher = Box::new(launcher.clone());
+        (dir, app, launcher)
+    }
+
+    fn select(app: &mut App, path: &std::path::Path) {
+        app.tree_state.selected_index = app.tree_state.find_index_by_path(path).unwrap();
+    }
+
+    const OPEN_RULES: &[(&str, &str)] = &[
+        ("rs", "edit"),
+        ("pdf", "external"),
+        ("*.log", "terminal:tail -f {path}"),
+    ];
+
+    #[test]
+    fn enter_on_unmatched_file_focuses_preview() {
+        let (dir, mut app, launcher) = setup_open_app(OPEN_RULES);
+        select(&mut app, &dir.path().join("plain.txt"));
+        handle_key(&mut app, make_key(KeyCode::Enter));
+        assert_eq!(app.focused_panel, FocusedPanel::Preview);
+        assert_eq!(app.mode, AppMode::Normal);
+        assert!(launcher.launched.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "editor")]
+    fn enter_on_edit_rule_opens_editor() {
+        let (dir, mut app, _launcher) = setup_open_app(OPEN_RULES);
+        select(&mut app, &dir.path().join("main.rs"));
+        handle_key(&mut app, make_key(KeyCode::Enter));
+        assert_eq!(app.mode, AppMode::Edit);
+        assert!(app.editor_state.is_some());
+    }
+
+    #[test]
+    fn enter_on_external_rule_uses_launcher() {
+        let (dir, mut app, launcher) = setup_open_app(OPEN_RULES);
+        let pdf = dir.path().join("doc.pdf");
+        select(&mut app, &pdf);
+        handle_key(&mut app, make_key(KeyCode::Enter));
+        assert_eq!(
+            *launcher.launched.lock().unwrap(),
+            vec![format!("external {}", pdf.display())]
+        );
+        assert_eq!(app.focused_panel, FocusedPanel::Tree);
+    }
+
+    #[test]
+    fn enter_on_terminal_rule_runs_expanded_command() {
+        let (dir, mut app, launcher) = setup_open_app(OPEN_RULES);
+        let log = dir.path().join("server.log");
+        select(&mut app, &log);
+        // Already visible, so no PTY is spawned in the test
+        app.terminal_state.visible = true;
+        handle_key(&mut app, make_key(KeyCode::Enter));
+        assert_eq!(
+            *launcher.launched.lock().unwrap(),
+            vec![format!("terminal tail -f '{}'", log.display())]
+        );
+        assert_eq!(app.focused_panel, FocusedPanel::Terminal);
+    }
+
+    #[test]
+    fn shift_enter_always_opens_externally() {
+        let (dir, mut app, launcher) = setup_open_app(OPEN_RULES);
+        let rs = dir.path().join("main.rs");
+        select(&mut app, &rs);
+        handle_key(
+            &mut app,
+            make_key_with_modifiers(KeyCode::Enter, KeyModifiers::SHIFT),
+        );
+        assert_eq!(app.mode, AppMode::Normal);
+        assert_eq!(
+            *launcher.launched.lock().unwrap(),
+            vec![format!("external {}", rs.display())]
+        );
+    }
+
+    #[test]
+    fn alt_o_and_search_action_o_open_externally() {
+        let (dir, mut app, launcher) = setup_open_app(OPEN_RULES);
+        let rs = dir.path().join("main.rs");
+        select(&mut app, &rs);
+        handle_key(
+            &mut app,
+            make_key_with_modifiers(KeyCode::Char('o'), KeyModifiers::ALT),
+        );
+        assert_eq!(app.mode, AppMode::Normal);
+        assert!(app.external_edit.is_none());
+
+        let pdf = dir.path().join("doc.pdf");
+        app.search_action_state = Some(crate::app::SearchActionState {
+            path: pdf.clone(),
+            display: "doc.pdf".to_string(),
+            is_directory: false,
+            is_binary: false,
+            line: None,
+            col: None,
+        });
+        app.mode = AppMode::SearchAction;
+        handle_key(&mut app, make_key(KeyCode::Char('o')));
+        assert_eq!(app.mode, AppMode::Normal);
+        assert_eq!(
+            *launcher.launched.lock().unwrap(),
+            vec![
+                format!("external {}", rs.display()),
+                format!("external {}", pdf.display())
+            ]
+        );
+    }
+
+    #[test]
+    fn alt_a_cycles_the_path_style() {
+        let (_dir, mut app) = setup_app();
+        handle_key(
+            &mut app,
+            make_key_with_modifiers(KeyCode::Char('a'), KeyModifiers::ALT),
+        );
+        assert_eq!(app.path_style, PathStyle::Absolute);
+        assert_eq!(app.mode, AppMode::Normal);
+    }
+
+    #[tokio::test]
+    async fn alt_c_copies_the_relative_path_through_osc52() {
+        let (_dir, mut app) = setup_app();
+        app.tree_state.selected_index = 3;
+        handle_key(
+            &mut app,
+            make_key_with_modifiers(KeyCode::Char('c'), KeyModifiers::ALT),
+        );
+        assert_eq!(
+            app.pending_osc52.as_deref(),
+            Some("\x1b]52;c;ZmlsZV9hLnR4dA==\x07")
+        );
+    }
+
+    #[test]
+    fn enter_on_directory_still_expands() {
+        let (dir, mut app, launcher) = setup_open_app(&[("*", "external")]);
+        select(&mut app, &dir.path().join("sub"));
+        handle_key(&mut app, make_key(KeyCode::Enter));
+        let idx = app.tree_state.selected_index;
+        assert!(app.tree_state.flat_items[idx].is_expanded);
+        assert!(launcher.launched.lock().unwrap().is_empty());
+    }
+
     // === Help mode tests ===
 
     #[test]
@@ -2276,6 +5035,65 @@ mod tests {
         assert_eq!(app.tree_state.selected_index, idx);
     }
 
+    #[test]
+    #[cfg(feature = "editor")]
+    fn help_opens_at_focused_section_and_returns() {
+        use crate::components::help::HelpSection;
+        let (dir, mut app) = setup_app();
+        app.focused_panel = FocusedPanel::Preview;
+        handle_key(&mut app, make_key(KeyCode::Char('?')));
+        assert_eq!(app.mode, AppMode::Help);
+        assert_eq!(
+            app.help_state.scroll_offset,
+            HelpOverlay::section_start(HelpSection::Preview)
+        );
+        handle_key(&mut app, make_key(KeyCode::Esc));
+        assert_eq!(app.mode, AppMode::Normal);
+
+        // F1 from the editor jumps to the editor keys and goes back to editing
+        fs::write(dir.path().join("file_a.txt"), "hello").unwrap();
+        app.tree_state.selected_index = 3;
+        app.update_preview();
+        assert!(app.enter_edit_mode(None));
+        handle_key(&mut app, make_key(KeyCode::F(1)));
+        assert_eq!(app.mode, AppMode::Help);
+        assert_eq!(
+            app.help_state.scroll_offset,
+            HelpOverlay::section_start(HelpSection::Editor)
+        );
+        handle_key(&mut app, make_key(KeyCode::F(1)));
+        assert_eq!(app.mode, AppMode::Edit);
+    }
+
+    #[test]
+    fn help_search_filters_then_esc_clears_and_closes() {
+        let (_dir, mut app) = setup_app();
+        handle_key(&mut app, make_key(KeyCode::Char('?')));
+        handle_key(&mut app, make_key(KeyCode::Char('/')));
+        for c in "blame".chars() {
+            handle_key(&mut app, make_key(KeyCode::Char(c)));
+        }
+        assert_eq!(app.help_state.query, "blame");
+        assert!(HelpOverlay::total_lines("blame") < HelpOverlay::total_lines(""));
+
+        // Enter keeps the filter; j scrolls within the filtered view only
+        handle_key(&mut app, make_key(KeyCode::Enter));
+        assert!(!app.help_state.searching);
+        for _ in 0..50 {
+            handle_key(&mut app, make_key(KeyCode::Char('j')));
+        }
+        assert_eq!(
+            app.help_state.scroll_offset,
+            HelpOverlay::total_lines("blame") - 1
+        );
+
+        handle_key(&mut app, make_key(KeyCode::Esc));
+        assert_eq!(app.help_state.query, "");
+        assert_eq!(app.mode, AppMode::Help);
+        handle_key(&mut app, make_key(KeyCode::Esc));
+        assert_eq!(app.mode, AppMode::Normal);
+    }
+
     // === Mouse handler tests ===
 
     use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
@@ -2313,6 +5131,7 @@ mod tests {
         // Simulate tree area: starts at (0,0) with width 40, height 20
         app.tree_area = ratatui::layout::Rect::new(0, 0, 40, 20);
         app.preview_area = ratatui::layout::Rect::new(40, 0, 60, 20);
+        app.tree_rows_drawn = app.tree_state.flat_items.len();
         assert_eq!(app.tree_state.selected_index, 0);
 
         // Click on row 2 (inner row 1 = index 1, accounting for top border)
@@ -2322,6 +5141,74 @@ mod tests {
         assert_eq!(app.focused_panel, FocusedPanel::Tree);
     }
 
+    #[test]
+    fn mouse_click_with_stale_layout_is_dropped() {
+        let (_dir, mut app) = setup_app();
+        app.tree_area = ratatui::layout::Rect::new(0, 0, 40, 20);
+        app.preview_area = ratatui::layout::Rect::new(40, 0, 60, 20);
+        app.tree_rows_drawn = app.tree_state.flat_items.len();
+        app.terminal_state.visible = true;
+        // Resizing the terminal moves the panels; no frame has shown it yet
+        app.resize_terminal_down();
+        assert!(!app.areas_current());
+
+        let tx = make_event_tx();
+        handle_mouse_event(&mut app, make_mouse_click(10, 2), &tx);
+        handle_mouse_event(&mut app, make_mouse_click(50, 5), &tx);
+        assert_eq!(app.tree_state.selected_index, 0);
+        assert_eq!(app.focused_panel, FocusedPanel::Tree);
+
+        // The next frame stores fresh areas
+        app.areas_generation = app.layout_generation;
+        handle_mouse_event(&mut app, make_mouse_click(10, 2), &tx);
+        assert_eq!(app.tree_state.selected_index, 1);
+    }
+
+    #[test]
+    fn mouse_click_from_before_a_tab_change_is_dropped() {
+        let (_dir, mut app) = setup_app();
+        app.tree_area = ratatui::layout::Rect::new(0, 0, 40, 20);
+        app.tree_rows_drawn = app.tree_state.flat_items.len();
+        let tx = make_event_tx();
+
+        // Each tab change moves the rows under the stored areas
+        for change in [App::new_tab, App::close_tab, App::new_tab] {
+            app.areas_generation = app.layout_generation;
+            change(&mut app);
+            assert!(!app.areas_current());
+            handle_mouse_event(&mut app, make_mouse_click(10, 2), &tx);
+            assert_eq!(app.tree_state.selected_index, 0);
+        }
+
+        app.areas_generation = app.layout_generation;
+        app.select_tab(0);
+        assert!(!app.areas_current());
+        handle_mouse_event(&mut app, make_mouse_click(10, 2), &tx);
+        assert_eq!(app.tree_state.selected_index, 0);
+    }
+
+    #[test]
+    fn mouse_click_below_the_drawn_rows_selects_nothing() {
+        let (_dir, mut app) = setup_app();
+        // Two inner rows: the bottom border sits where a third would be
+        app.tree_area = ratatui::layout::Rect::new(0, 0, 40, 4);
+        app.tree_rows_drawn = 2;
+        assert!(app.tree_state.flat_items.len() > 2);
+
+        let tx = make_event_tx();
+        handle_mouse_event(&mut app, make_mouse_click(10, 3), &tx);
+        assert_eq!(app.tree_state.selected_index, 0);
+        handle_mouse_event(&mut app, make_mouse_click(10, 2), &tx);
+        assert_eq!(app.tree_state.selected_index, 1);
+
+        // A short list ends above the panel's bottom
+        app.tree_area = ratatui::layout::Rect::new(0, 0, 40, 20);
+        app.tree_rows_drawn = 1;
+        app.tree_state.selected_index = 0;
+        handle_mouse_event(&mut app, make_mouse_click(10, 2), &tx);
+        assert_eq!(app.tree_state.selected_index, 0);
+    }
+
     #[test]
     fn mouse_click_preview_switches_focus() {
         let (_dir, mut app) = setup_app();
@@ -2334,6 +5221,42 @@ mod tests {
         assert_eq!(app.focused_panel, FocusedPanel::Preview);
     }
 
+    #[test]
+    fn mouse_hits_follow_capped_layout() {
+        let (_dir, mut app) = setup_app();
+        let layout = crate::layout::split_main(
+            ratatui::layout::Rect::new(0, 0, 300, 20),
+            Some(60),
+            Some(100),
+        );
+        app.tree_area = layout.tree;
+        app.preview_area = layout.preview;
+
+        let tx = make_event_tx();
+        // The centering margin left of the preview belongs to neither panel
+        handle_mouse_event(&mut app, make_mouse_click(100, 5), &tx);
+        assert_eq!(app.focused_panel, FocusedPanel::Tree);
+        assert_eq!(app.tree_state.selected_index, 0);
+
+        handle_mouse_event(&mut app, make_mouse_click(layout.preview.x, 5), &tx);
+        assert_eq!(app.focused_panel, FocusedPanel::Preview);
+    }
+
+    #[test]
+    fn mouse_click_on_watcher_glyph_explains_it() {
+        let (_dir, mut app) = setup_app();
+        app.tree_area = ratatui::layout::Rect::new(0, 0, 40, 20);
+        app.watcher_glyph_area = ratatui::layout::Rect::new(20, 0, 1, 1);
+        app.toggle_watcher();
+
+        let tx = make_event_tx();
+        handle_mouse_event(&mut app, make_mouse_click(20, 0), &tx);
+        let msg = app.status_message.as_ref().map(|(m, _)| m.clone()).unwrap();
+        assert!(msg.starts_with("⏸ Watcher paused (Ctrl+R"), "{}", msg);
+        assert!(msg.ends_with("last refresh just now"), "{}", msg);
+        assert_eq!(app.tree_state.selected_index, 0);
+    }
+
     #[test]
     fn mouse_scroll_tree_navigates() {
         let (_dir, mut app) = setup_app();
@@ -2372,6 +5295,35 @@ mod tests {
         assert_eq!(app.tree_state.selected_index, idx);
     }
 
+    #[test]
+    #[cfg(feature = "editor")]
+    fn mouse_to_editor_pos_accounts_for_wide_chars() {
+        let editor =
+            crate::editor::EditorState::new("日本x", std::path::PathBuf::from("/tmp/t.txt"));
+        let area = ratatui::layout::Rect::new(0, 0, 40, 10);
+        // Border at x = 0, gutter of 3 cells, so code starts at x = 4.
+        assert_eq!(mouse_to_editor_pos(&editor, area, 4, 1), (0, 0));
+        assert_eq!(mouse_to_editor_pos(&editor, area, 7, 1), (0, 1));
+        assert_eq!(mouse_to_editor_pos(&editor, area, 8, 1), (0, 2));
+    }
+
+    #[test]
+    fn preview_h_and_l_scroll_horizontally() {
+        let (_dir, mut app) = setup_app();
+        app.focused_panel = FocusedPanel::Preview;
+        app.preview_area = ratatui::layout::Rect::new(0, 0, 12, 5);
+        app.preview_state.content_lines = vec![ratatui::text::Line::from("x".repeat(30))];
+
+        handle_key(&mut app, make_key(KeyCode::Char('l')));
+        assert_eq!(app.preview_state.h_offset, 4);
+        for _ in 0..10 {
+            handle_key(&mut app, make_key(KeyCode::Right));
+        }
+        assert_eq!(app.preview_state.h_offset, 20);
+        handle_key(&mut app, make_key(KeyCode::Char('h')));
+        assert_eq!(app.preview_state.h_offset, 16);
+    }
+
     // === Directional focus keybinding tests ===
 
     #[test]
@@ -2444,6 +5396,32 @@ mod tests {
         assert_eq!(app.terminal_state.height_percent, 35);
     }
 
+    #[test]
+    fn jump_to_live_key_is_taken_only_while_scrolled_back() {
+        let (_dir, mut app) = setup_app();
+        app.terminal_state.visible = true;
+        app.focused_panel = FocusedPanel::Terminal;
+        let g = make_key(KeyCode::Char('G'));
+        let end = make_key(KeyCode::End);
+        // At the live bottom the shell gets them
+        assert!(!jumps_to_live(&app.terminal_state, &g));
+        assert!(!jumps_to_live(&app.terminal_state, &end));
+
+        app.terminal_state.scroll_offset = 5;
+        app.terminal_state.new_lines = 214;
+        assert!(jumps_to_live(&app.terminal_state, &g));
+        assert!(jumps_to_live(&app.terminal_state, &end));
+        assert!(!jumps_to_live(
+            &app.terminal_state,
+            &make_key_with_modifiers(KeyCode::End, KeyModifiers::CONTROL)
+        ));
+
+        handle_key(&mut app, g);
+        assert_eq!(app.terminal_state.scroll_offset, 0);
+        assert_eq!(app.terminal_state.new_lines, 0);
+        assert_eq!(app.focused_panel, FocusedPanel::Terminal);
+    }
+
     #[test]
     fn ctrl_arrow_intercepted_when_terminal_focused() {
         let (_dir, mut app) = setup_app();
@@ -2464,4 +5442,361 @@ mod tests {
         handle_key(&mut app, make_key(KeyCode::Tab));
         assert_eq!(app.focused_panel, FocusedPanel::Preview);
     }
+
+    // === Sequence rename ===
+
+    fn setup_sequence_app(names: &[&str]) -> (TempDir, App) {
+        let (dir, mut app) = setup_app();
+        for name in names {
+            fs::write(dir.path().join("alpha").join(name), name).unwrap();
+        }
+        app.tree_state.selected_index = 1; // alpha
+        (dir, app)
+    }
+
+    fn alpha_listing(dir: &TempDir) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(dir.path().join("alpha"))
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        names
+    }
+
+    fn type_str(app: &mut App, text: &str) {
+        for c in text.chars() {
+            handle_key(app, make_key(KeyCode::Char(c)));
+        }
+    }
+
+    #[test]
+    fn sequence_rename_directory_and_undo() {
+        let (dir, mut app) = setup_sequence_app(&["IMG_2031.jpg", "IMG_2032.jpg", "IMG_2040.jpg"]);
+        handle_key(
+            &mut app,
+            make_key_with_modifiers(KeyCode::Char('R'), KeyModifiers::SHIFT),
+        );
+        let form = app.sequence_form_mut().expect("dialog open").clone();
+        assert_eq!(form.sources.len(), 3);
+        assert_eq!(form.base, "IMG_");
+        assert_eq!(
+            form.preview.unwrap()[0],
+            "IMG_2031.jpg → IMG_001.jpg".to_string()
+        );
+
+        handle_key(&mut app, make_key(KeyCode::Enter));
+        assert_eq!(app.mode, AppMode::Normal);
+        assert_eq!(
+            alpha_listing(&dir),
+            vec!["IMG_001.jpg", "IMG_002.jpg", "IMG_003.jpg"]
+        );
+        assert_eq!(
+            fs::read_to_string(dir.path().join("alpha/IMG_003.jpg")).unwrap(),
+            "IMG_2040.jpg"
+        );
+
+        ctrl_z(&mut app);
+        assert_eq!(
+            alpha_listing(&dir),
+            vec!["IMG_2031.jpg", "IMG_2032.jpg", "IMG_2040.jpg"]
+        );
+    }
+
+    #[test]
+    fn sequence_rename_shift_by_one_in_place_and_undo() {
+        let (dir, mut app) = setup_sequence_app(&["f2.txt", "f3.txt", "f4.txt"]);
+        handle_key(
+            &mut app,
+            make_key_with_modifiers(KeyCode::Char('R'), KeyModifiers::SHIFT),
+        );
+        // Base defaults to "f"; set padding width to 1
+        handle_key(&mut app, make_key(KeyCode::Tab));
+        handle_key(&mut app, make_key(KeyCode::Tab));
+        handle_key(&mut app, make_key(KeyCode::Backspace));
+        type_str(&mut app, "1");
+        handle_key(&mut app, make_key(KeyCode::Enter));
+        assert_eq!(alpha_listing(&dir), vec!["f1.txt", "f2.txt", "f3.txt"]);
+        assert_eq!(
+            fs::read_to_string(dir.path().join("alpha/f1.txt")).unwrap(),
+            "f2.txt"
+        );
+
+        // Undo is the reverse overlap (f1→f2, f2→f3, f3→f4)
+        ctrl_z(&mut app);
+        assert_eq!(alpha_listing(&dir), vec!["f2.txt", "f3.txt", "f4.txt"]);
+        assert_eq!(
+            fs::read_to_string(dir.path().join("alpha/f4.txt")).unwrap(),
+            "f4.txt"
+        );
+    }
+
+    #[test]
+    fn sequence_rename_collision_keeps_dialog_open() {
+        let (dir, mut app) = setup_sequence_app(&["a.txt", "b.txt"]);
+        fs::create_dir(dir.path().join("alpha").join("n2.txt")).unwrap();
+        handle_key(
+            &mut app,
+            make_key_with_modifiers(KeyCode::Char('R'), KeyModifiers::SHIFT),
+        );
+        // Clear the default base and type "n", width 1
+        while !app.sequence_form_mut().unwrap().base.is_empty() {
+            handle_key(&mut app, make_key(KeyCode::Backspace));
+        }
+        type_str(&mut app, "n");
+        handle_key(&mut app, make_key(KeyCode::Tab));
+        handle_key(&mut app, make_key(KeyCode::Tab));
+        handle_key(&mut app, make_key(KeyCode::Backspace));
+        type_str(&mut app, "1");
+        let preview = app.sequence_form_mut().unwrap().preview.clone();
+        assert!(preview.unwrap_err().contains("n2.txt already exists"));
+
+        handle_key(&mut app, make_key(KeyCode::Enter));
+        assert!(app.sequence_form_mut().is_some());
+        assert!(app
+            .status_message
+            .as_ref()
+            .unwrap()
+            .0
+            .contains("already exists"));
+        assert_eq!(alpha_listing(&dir), vec!["a.txt", "b.txt", "n2.txt"]);
+    }
+
+    #[test]
+    fn sequence_rename_needs_selection_or_directory() {
+        let (_dir, mut app) = setup_app();
+        app.tree_state.selected_index = 3; // file_a.txt
+        handle_key(
+            &mut app,
+            make_key_with_modifiers(KeyCode::Char('R'), KeyModifiers::SHIFT),
+        );
+        assert_eq!(app.mode, AppMode::Normal);
+        assert!(app
+            .status_message
+            .as_ref()
+            .unwrap()
+            .0
+            .contains("Select files or a directory"));
+    }
+
+    #[test]
+    fn export_dialog_writes_tree_file() {
+        let (dir, mut app) = setup_app();
+        handle_key(
+            &mut app,
+            make_key_with_modifiers(KeyCode::Char('E'), KeyModifiers::SHIFT),
+        );
+        assert!(matches!(
+            app.mode,
+            AppMode::Dialog(DialogKind::ExportTree { .. })
+        ));
+        // Switch the format to JSON; the default file name follows
+        handle_key(&mut app, make_key(KeyCode::Tab));
+        handle_key(&mut app, make_key(KeyCode::Char(' ')));
+        handle_key(&mut app, make_key(KeyCode::Enter));
+        assert_eq!(app.mode, AppMode::Normal);
+
+        let written = fs::read_to_string(dir.path().join("tree.json")).unwrap();
+        let rows: Vec<serde_json::Value> = serde_json::from_str(&written).unwrap();
+        assert_eq!(rows.len(), 4); // root, alpha, beta, file_a.txt
+        assert_eq!(rows[1]["type"], "directory");
+        assert!(app
+            .status_message
+            .as_ref()
+            .unwrap()
+            .0
+            .starts_with("Exported 4 rows as JSON"));
+    }
+
+    #[test]
+    fn export_dialog_resolves_relative_paths_against_root() {
+        let (dir, mut app) = setup_app();
+        app.open_export_dialog();
+        let form = app.export_form_mut().unwrap();
+        form.path = "out.txt".to_string();
+        form.options.scope = crate::fs::export::ExportScope::Loaded;
+        handle_key(&mut app, make_key(KeyCode::Enter));
+
+        let written = fs::read_to_string(dir.path().join("out.txt")).unwrap();
+        assert!(written.contains("├── alpha/\n"));
+        assert!(written.ends_with("└── file_a.txt\n"));
+    }
+
+    // === Pasted text in one-line inputs ===
+
+    /// Deliver `text` the way a terminal without bracketed paste does: a
+    /// burst of keys with an Enter per line break.
+    fn paste_burst(app: &mut App, text: &str) {
+        for c in text.chars() {
+            let code = if c == '\n' {
+                KeyCode::Enter
+            } else {
+                KeyCode::Char(c)
+            };
+            handle_key(app, make_key(code));
+        }
+    }
+
+    #[test]
+    fn pasted_burst_into_create_file_does_not_confirm() {
+        let (dir, mut app) = setup_app();
+        handle_key(&mut app, make_key(KeyCode::Char('a')));
+        paste_burst(&mut app, "notes\ndraft\n");
+        assert_eq!(app.mode, AppMode::Dialog(DialogKind::CreateFile));
+        assert_eq!(app.dialog_state.input, "notes draft");
+        assert_eq!(
+            app.dialog_state.warning.as_deref(),
+            Some("pasted text contained 2 line breaks — removed")
+        );
+        assert!(!dir.path().join("notes").exists());
+
+        confirm_typed(&mut app);
+        assert!(dir.path().join("notes draft").exists());
+    }
+
+    #[test]
+    fn pasted_burst_into_rename_does_not_confirm() {
+        let (dir, mut app) = setup_app();
+        app.tree_state.selected_index = app
+            .tree_state
+            .find_index_by_path(&dir.path().join("file_a.txt"))
+            .unwrap();
+        handle_key(&mut app, make_key(KeyCode::Char('r')));
+        handle_key(&mut app, make_key(KeyCode::Home));
+        paste_burst(&mut app, "v2\n");
+        assert!(matches!(
+            app.mode,
+            AppMode::Dialog(DialogKind::Rename { .. })
+        ));
+        assert_eq!(app.dialog_state.input, "v2file_a.txt");
+        assert!(dir.path().join("file_a.txt").exists());
+    }
+
+    #[test]
+    fn pasted_burst_into_search_and_filter_does_not_confirm() {
+        let (_dir, mut app) = setup_app();
+        handle_key(
+            &mut app,
+            make_key_with_modifiers(KeyCode::Char('p'), KeyModifiers::CONTROL),
+        );
+        paste_burst(&mut app, "file\n");
+        assert_eq!(app.mode, AppMode::Search);
+        assert_eq!(app.search_state.query, "file");
+        handle_key(&mut app, make_key(KeyCode::Esc));
+
+        handle_key(&mut app, make_key(KeyCode::Char('/')));
+        paste_burst(&mut app, "alpha\n");
+        assert_eq!(app.mode, AppMode::Filter);
+        assert_eq!(app.tree_state.filter_query, "alpha");
+        assert!(app
+            .status_message
+            .as_ref()
+            .unwrap()
+            .0
+            .contains("1 line break — removed"));
+    }
+
+    #[test]
+    fn bracketed_paste_is_sanitized_in_each_input() {
+        let (dir, mut app) = setup_app();
+        handle_key(&mut app, make_key(KeyCode::Char('a')));
+        handle_paste_event(&mut app, "report\r\n2024\x1b.txt\n");
+        assert_eq!(app.mode, AppMode::Dialog(DialogKind::CreateFile));
+        assert_eq!(app.dialog_state.input, "report 2024.txt");
+        assert_eq!(
+            app.dialog_state.warning.as_deref(),
+            Some("pasted text contained 2 line breaks and 1 control character — removed")
+        );
+        assert!(!dir.path().join("report").exists());
+        handle_key(&mut app, make_key(KeyCode::Esc));
+
+        handle_key(
+            &mut app,
+            make_key_with_modifiers(KeyCode::Char('p'), KeyModifiers::CONTROL),
+        );
+        handle_paste_event(&mut app, "file_a\n");
+        assert_eq!(app.mode, AppMode::Search);
+        assert_eq!(app.search_state.query, "file_a");
+        handle_key(&mut app, make_key(KeyCode::Esc));
+
+        handle_key(&mut app, make_key(KeyCode::Char('/')));
+        handle_paste_event(&mut app, "bet\na");
+        assert_eq!(app.mode, AppMode::Filter);
+        assert_eq!(app.tree_state.filter_query, "bet a");
+    }
+
+    #[test]
+    fn pasted_name_is_cut_at_the_name_limit() {
+        let (_dir, mut app) = setup_app();
+        handle_key(&mut app, make_key(KeyCode::Char('a')));
+        handle_paste_event(&mut app, &"é".repeat(200));
+        // 127 two-byte chars fit in 255 bytes
+        assert_eq!(app.dialog_state.input.len(), 254);
+        assert_eq!(
+            app.dialog_state.warning.as_deref(),
+            Some("name cut at 255 bytes")
+        );
+        handle_key(&mut app, make_key(KeyCode::Char('x')));
+        assert_eq!(app.dialog_state.input.len(), paste_guard::NAME_MAX);
+        handle_key(&mut app, make_key(KeyCode::Char('y')));
+        assert_eq!(app.dialog_state.input.len(), paste_guard::NAME_MAX);
+    }
+
+    #[test]
+    fn paste_in_normal_mode_runs_no_commands() {
+        let (_dir, mut app) = setup_app();
+        handle_paste_event(&mut app, "dq");
+        assert_eq!(app.mode, AppMode::Normal);
+        assert!(!app.should_quit);
+    }
+
+    // === Held-key acceleration ===
+
+    /// App over 60 files, `f00.txt` .. `f59.txt`, with `config`.
+    fn setup_held_app(config: crate::config::AppConfig) -> (TempDir, App) {
+        let dir = TempDir::new().unwrap();
+        for i in 0..60 {
+            File::create(dir.path().join(format!("f{:02}.txt", i))).unwrap();
+        }
+        let app = App::new(dir.path(), config).unwrap();
+        (dir, app)
+    }
+
+    /// Press `code` `times` times, `gap_ms` apart.
+    fn press_timed(app: &mut App, code: KeyCode, times: u64, gap_ms: u64) {
+        let tx = make_event_tx();
+        let start = Instant::now();
+        for i in 0..times {
+            let at = start + std::time::Duration::from_millis(i * gap_ms);
+            handle_key_event_at(app, make_key(code), at, &tx);
+        }
+    }
+
+    #[test]
+    fn held_j_accelerates_to_a_fixed_row() {
+        let (_dir, mut app) = setup_held_app(crate::config::AppConfig::default());
+        press_timed(&mut app, KeyCode::Char('j'), 12, 30);
+        // 1 + 1 + 1 + 2 + 3 + 4 + 5 + 6 + 7 + 8 + 8 + 8
+        assert_eq!(app.tree_state.selected_index, 54);
+        assert!(app.accelerating());
+
+        press_timed(&mut app, KeyCode::Char('k'), 5, 30);
+        assert_eq!(app.tree_state.selected_index, 54 - 8);
+    }
+
+    #[test]
+    fn separate_presses_move_one_row_each() {
+        let (_dir, mut app) = setup_held_app(crate::config::AppConfig::default());
+        press_timed(&mut app, KeyCode::Down, 8, 250);
+        assert_eq!(app.tree_state.selected_index, 8);
+        assert!(!app.accelerating());
+    }
+
+    #[test]
+    fn acceleration_can_be_turned_off() {
+        let mut config = crate::config::AppConfig::default();
+        config.navigation.key_acceleration = Some(false);
+        let (_dir, mut app) = setup_held_app(config);
+        press_timed(&mut app, KeyCode::Char('j'), 12, 30);
+        assert_eq!(app.tree_state.selected_index, 12);
+    }
 }
@@ -0,0 +1,187 @@
+//! Background refresh of stale paginated directories while the user is idle.
+//!
+//! `handle_fs_change` only marks paginated directories stale, leaving the
+//! re-scan for the next interaction. Once nothing has been typed or clicked
+//! for `watcher.idle_refresh_ms` and no file operation is running,
+//! `App::poll_idle_refresh` re-collects up to `watcher.idle_refresh_batch`
+//! stale directories in the viewport on a blocking task, reloading as many
+//! entries as were loaded before. Input raises the batch's cancel flag and
+//! bumps the generation, so running scans stop early and late results are
+//! dropped.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::fs::tree::{DirSnapshot, SortBy, TreeNode};
+
+/// Entries stat-ed between cancellation checks.
+const STAT_CHUNK: usize = 64;
+
+/// A directory re-collected by an idle refresh.
+#[derive(Debug)]
+pub struct IdleRefreshResult {
+    pub path: PathBuf,
+    /// Generation the refresh was started in.
+    pub generation: u64,
+    /// Fresh snapshot, sorted with the tree's settings.
+    pub snapshot: DirSnapshot,
+    /// Nodes for the first `consumed` snapshot entries (minus stat failures).
+    pub children: Vec<TreeNode>,
+    /// Number of snapshot entries covered by `children`.
+    pub consumed: usize,
+}
+
+/// Idle clock and in-flight bookkeeping.
+#[derive(Debug)]
+pub struct IdleRefreshState {
+    /// Time of the last key or mouse event.
+    pub last_input: Instant,
+    /// Bumped whenever input aborts a batch; results from older generations
+    /// are ignored.
+    pub generation: u64,
+    /// Directories whose refresh has not reported back yet.
+    pub in_flight: HashSet<PathBuf>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl Default for IdleRefreshState {
+    fn default() -> Self {
+        Self {
+            last_input: Instant::now(),
+            generation: 0,
+            in_flight: HashSet::new(),
+            cancel: Arc::new(AtomicBool::new(false)),
+        }
+    }
+}
+
+impl IdleRefreshState {
+    /// Restart the idle clock and abort any running batch.
+    pub fn note_input(&mut self, now: Instant) {
+        self.last_input = now;
+        if !self.in_flight.is_empty() {
+            self.cancel.store(true, Ordering::SeqCst);
+            self.in_flight.clear();
+            self.generation = self.generation.wrapping_add(1);
+        }
+    }
+
+    /// Whether no input has arrived for at least `period`.
+    pub fn is_idle(&self, now: Instant, period: Duration) -> bool {
+        now.duration_since(self.last_input) >= period
+    }
+
+    /// Whether a batch is still running.
+    pub fn is_busy(&self) -> bool {
+        !self.in_flight.is_empty()
+    }
+
+    /// Start a batch for `paths`, returning its generation and a fresh
+    /// cancel flag.
+    pub fn begin(&mut self, paths: impl IntoIterator<Item = PathBuf>) -> (u64, Arc<AtomicBool>) {
+        self.cancel = Arc::new(AtomicBool::new(false));
+        self.in_flight.extend(paths);
+        (self.generation, Arc::clone(&self.cancel))
+    }
+
+    /// Drop `path` from the running batch so its result is ignored, e.g.
+    /// because it changed again after the scan started.
+    pub fn forget(&mut self, path: &Path) {
+        self.in_flight.remove(path);
+    }
+
+    /// Claim a finished refresh. Returns `false` if it was aborted or
+    /// superseded and should be discarded.
+    pub fn accept(&mut self, path: &Path, generation: u64) -> bool {
+        generation == self.generation && self.in_flight.remove(path)
+    }
+}
+
+/// Re-collect `path` and stat its first `want` entries (or, with
+/// `defer_stats`, leave their stats to the stat service).
+///
+/// Returns `None` if the directory can't be read or `cancel` is raised
+/// before the work finishes.
+pub fn collect(
+    path: &Path,
+    want: usize,
+    child_depth: usize,
+    sort_by: &SortBy,
+    dirs_first: bool,
+    defer_stats: bool,
+    cancel: &AtomicBool,
+) -> Option<(DirSnapshot, Vec<TreeNode>, usize)> {
+    let mut snapshot = DirSnapshot::collect(path).ok()?;
+    if cancel.load(Ordering::SeqCst) {
+        return None;
+    }
+    snapshot.sort(sort_by, dirs_first);
+
+    let page = snapshot.page(0, want);
+    let consumed = page.len();
+    let mut children = Vec::with_capacity(consumed);
+    for chunk in page.chunks(STAT_CHUNK) {
+        if cancel.load(Ordering::SeqCst) {
+            return None;
+        }
+        children.extend(TreeNode::load_nodes_from_snapshot(
+            chunk,
+            path,
+            child_depth,
+            defer_stats,
+        ));
+    }
+    Some((snapshot, children, consumed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+
+    #[test]
+    fn input_aborts_running_batch() {
+        let mut state = IdleRefreshState::default();
+        let start = state.last_input;
+        assert!(state.is_idle(start + Duration::from_secs(3), Duration::from_secs(2)));
+
+        let dir = PathBuf::from("/tmp/big");
+        let (generation, cancel) = state.begin([dir.clone()]);
+        assert!(state.is_busy());
+
+        state.note_input(start + Duration::from_secs(4));
+        assert!(cancel.load(Ordering::SeqCst));
+        assert!(!state.is_busy());
+        assert!(!state.accept(&dir, generation));
+        assert!(!state.is_idle(start + Duration::from_secs(5), Duration::from_secs(2)));
+
+        // A new batch gets a clean flag and the bumped generation
+        let (next, cancel) = state.begin([dir.clone()]);
+        assert_ne!(next, generation);
+        assert!(!cancel.load(Ordering::SeqCst));
+        assert!(state.accept(&dir, next));
+        assert!(!state.accept(&dir, next));
+    }
+
+    #[test]
+    fn collect_loads_requested_depth_and_honors_cancel() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        for i in 0..5 {
+            File::create(dir.path().join(format!("f{}.txt", i))).unwrap();
+        }
+        let cancel = AtomicBool::new(false);
+        let (snapshot, children, consumed) =
+            collect(dir.path(), 3, 1, &SortBy::Name, true, false, &cancel).unwrap();
+        assert_eq!(snapshot.len(), 5);
+        assert_eq!(consumed, 3);
+        let names: Vec<_> = children.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["f0.txt", "f1.txt", "f2.txt"]);
+        assert!(children.iter().all(|c| c.depth == 1));
+
+        cancel.store(true, Ordering::SeqCst);
+        assert!(collect(dir.path(), 3, 1, &SortBy::Name, true, false, &cancel).is_none());
+    }
+}
@@ -0,0 +1,225 @@
+//! Registry of running file operations.
+//!
+//! Key auto-repeat can deliver a second `p` while the first paste is still
+//! copying, which starts a duplicate transfer with its own "(copy)" files
+//! and progress dialog. Actions that start an async operation are admitted
+//! here first: while one runs, further requests are rejected, or queued
+//! when the caller allows it and started once the running one completes.
+//! Identical paste requests are also debounced, since a small paste can
+//! finish before the next repeat arrives.
+
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use tokio::sync::mpsc;
+
+use crate::event::Event;
+use crate::fs::overwrite::BackupTo;
+
+/// Identical paste requests closer together than this are dropped. The
+/// window slides with each dropped request, so a held key stays debounced.
+pub const PASTE_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// What to do with a request that arrives while an operation is running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WhenBusy {
+    /// Refuse it ("operation already in progress").
+    Reject,
+    /// Start it after the running operations finish.
+    Queue,
+}
+
+/// A copy/move waiting to be started.
+#[derive(Debug)]
+pub struct PendingTransfer {
+    pub paths: Vec<PathBuf>,
+    pub dest_dir: PathBuf,
+    pub was_cut: bool,
+    /// Target name for "send to" transfers.
+    pub send_to: Option<String>,
+    /// Entries under `paths`, when counted beforehand; progress then counts
+    /// entries instead of top-level items.
+    pub entries: Option<usize>,
+    /// Bytes under `paths`, when counted beforehand.
+    pub bytes: Option<u64>,
+    /// Sources that replace the item of the same name in `dest_dir`
+    /// rather than going next to it.
+    pub overwrite: Vec<PathBuf>,
+    /// Where files being overwritten are backed up first, if anywhere.
+    pub backup: Option<BackupTo>,
+    pub event_tx: mpsc::UnboundedSender<Event>,
+}
+
+/// Outcome of [`InFlightOps::admit`].
+#[derive(Debug)]
+pub enum Admission {
+    /// Nothing was running; the caller should start the transfer now.
+    Start(PendingTransfer),
+    /// Queued behind the running operation.
+    Queued,
+    /// Rejected because an operation is running.
+    Busy,
+}
+
+/// Running operation and queued transfers.
+#[derive(Debug, Default)]
+pub struct InFlightOps {
+    running: bool,
+    queue: VecDeque<PendingTransfer>,
+    /// Sources, destination and time of the last paste request.
+    last_paste: Option<(Vec<PathBuf>, PathBuf, Instant)>,
+}
+
+impl InFlightOps {
+    /// Whether an operation is running.
+    pub fn is_busy(&self) -> bool {
+        self.running
+    }
+
+    /// Number of transfers waiting to start.
+    pub fn queued(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Register a transfer, marking it running if nothing else is.
+    pub fn admit(&mut self, transfer: PendingTransfer, when_busy: WhenBusy) -> Admission {
+        if !self.running {
+            self.running = true;
+            return Admission::Start(transfer);
+        }
+        match when_busy {
+            WhenBusy::Reject => Admission::Busy,
+            WhenBusy::Queue => {
+                self.queue.push_back(transfer);
+                Admission::Queued
+            }
+        }
+    }
+
+    /// Mark an operation that is never queued (a delete) running, unless
+    /// something else already is.
+    pub fn begin(&mut self) -> bool {
+        if self.running {
+            return false;
+        }
+        self.running = true;
+        true
+    }
+
+    /// Mark the running operation finished. Returns the next queued
+    /// transfer, already marked running, for the caller to start.
+    pub fn finish(&mut self) -> Option<PendingTransfer> {
+        let next = self.queue.pop_front();
+        self.running = next.is_some();
+        next
+    }
+
+    /// Drop every queued transfer (the running one is cancelled separately).
+    pub fn clear_queue(&mut self) {
+        self.queue.clear();
+    }
+
+    /// Record a paste of `paths` into `dest_dir` at `now`, returning `true`
+    /// if it repeats the previous request within [`PASTE_DEBOUNCE`].
+    pub fn is_repeated_paste(&mut self, paths: &[PathBuf], dest_dir: &Path, now: Instant) -> bool {
+        let repeated = match self.last_paste {
+            Some((ref last_paths, ref last_dest, at)) => {
+                last_paths.as_slice() == paths
+                    && last_dest == dest_dir
+                    && now.duration_since(at) < PASTE_DEBOUNCE
+            }
+            None => false,
+        };
+        self.last_paste = Some((paths.to_vec(), dest_dir.to_path_buf(), now));
+        repeated
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transfer(name: &str) -> PendingTransfer {
+        let (event_tx, _rx) = mpsc::unbounded_channel();
+        PendingTransfer {
+            paths: vec![PathBuf::from(name)],
+            dest_dir: PathBuf::from("/dest"),
+            was_cut: false,
+            send_to: None,
+            entries: None,
+            bytes: None,
+            overwrite: Vec::new(),
+            backup: None,
+            event_tx,
+        }
+    }
+
+    #[test]
+    fn second_request_is_rejected_while_running() {
+        let mut ops = InFlightOps::default();
+        assert!(matches!(
+            ops.admit(transfer("a"), WhenBusy::Reject),
+            Admission::Start(_)
+        ));
+        assert!(ops.is_busy());
+        assert!(matches!(
+            ops.admit(transfer("a"), WhenBusy::Reject),
+            Admission::Busy
+        ));
+        assert!(ops.finish().is_none());
+        assert!(!ops.is_busy());
+        assert!(matches!(
+            ops.admit(transfer("a"), WhenBusy::Reject),
+            Admission::Start(_)
+        ));
+    }
+
+    #[test]
+    fn deletes_share_the_running_slot() {
+        let mut ops = InFlightOps::default();
+        assert!(ops.begin());
+        assert!(!ops.begin());
+        assert!(matches!(
+            ops.admit(transfer("a"), WhenBusy::Reject),
+            Admission::Busy
+        ));
+        assert!(ops.finish().is_none());
+        assert!(ops.begin());
+    }
+
+    #[test]
+    fn queued_transfers_start_in_order() {
+        let mut ops = InFlightOps::default();
+        ops.admit(transfer("a"), WhenBusy::Queue);
+        assert!(matches!(
+            ops.admit(transfer("b"), WhenBusy::Queue),
+            Admission::Queued
+        ));
+        ops.admit(transfer("c"), WhenBusy::Queue);
+        assert_eq!(ops.queued(), 2);
+
+        let next = ops.finish().unwrap();
+        assert_eq!(next.paths, vec![PathBuf::from("b")]);
+        assert!(ops.is_busy());
+        ops.clear_queue();
+        assert!(ops.finish().is_none());
+        assert!(!ops.is_busy());
+    }
+
+    #[test]
+    fn identical_pastes_are_debounced() {
+        let mut ops = InFlightOps::default();
+        let paths = vec![PathBuf::from("/src/a.txt")];
+        let dest = PathBuf::from("/dest");
+        let t0 = Instant::now();
+        assert!(!ops.is_repeated_paste(&paths, &dest, t0));
+        assert!(ops.is_repeated_paste(&paths, &dest, t0 + Duration::from_millis(30)));
+        // Each repeat slides the window
+        assert!(ops.is_repeated_paste(&paths, &dest, t0 + Duration::from_millis(450)));
+        assert!(!ops.is_repeated_paste(&paths, &dest, t0 + Duration::from_millis(1000)));
+        // A different destination is a new request
+        let other = PathBuf::from("/elsewhere");
+        assert!(!ops.is_repeated_paste(&paths, &other, t0 + Duration::from_millis(1010)));
+    }
+}
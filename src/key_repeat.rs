@@ -0,0 +1,131 @@
+//! Acceleration for held navigation keys.
+//!
+//! A held `j` arrives as a stream of identical key events, in bursts over
+//! SSH, and moving one row per event makes the selection crawl and then
+//! lurch while redraws lag behind. [`KeyRepeat`] follows runs of the same
+//! key where each event comes within the repeat window of the one before.
+//! The first `after` events of a run move one row each, so a discrete press
+//! is never accelerated; each later event moves one row more than the last,
+//! up to `max_step`, like mouse wheel acceleration. A different key, or a
+//! pause longer than the window, ends the run. The step depends only on the
+//! keys and their timestamps, so a burst always lands on the same row.
+
+use std::time::{Duration, Instant};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// Default gap between events that still counts as holding the key.
+pub const DEFAULT_WINDOW_MS: u64 = 100;
+
+/// Default run length moved at one row per event.
+pub const DEFAULT_AFTER: usize = 3;
+
+/// Default largest step per event.
+pub const DEFAULT_MAX_STEP: usize = 8;
+
+/// Acceleration parameters, from the `[navigation]` config section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RepeatSettings {
+    pub window: Duration,
+    pub after: usize,
+    pub max_step: usize,
+}
+
+impl Default for RepeatSettings {
+    fn default() -> Self {
+        Self {
+            window: Duration::from_millis(DEFAULT_WINDOW_MS),
+            after: DEFAULT_AFTER,
+            max_step: DEFAULT_MAX_STEP,
+        }
+    }
+}
+
+/// The run the latest key press belongs to.
+#[derive(Debug, Default)]
+pub struct KeyRepeat {
+    last: Option<(KeyCode, KeyModifiers, Instant)>,
+    /// Events in the run so far, the latest included.
+    run: usize,
+}
+
+impl KeyRepeat {
+    /// Note a key press at `now`: it extends the run when it repeats the
+    /// previous key within `window`, and starts a new one otherwise.
+    pub fn press(&mut self, key: &KeyEvent, now: Instant, window: Duration) {
+        let repeats = self.last.is_some_and(|(code, modifiers, at)| {
+            code == key.code
+                && modifiers == key.modifiers
+                && now.saturating_duration_since(at) <= window
+        });
+        self.run = if repeats { self.run + 1 } else { 1 };
+        self.last = Some((key.code, key.modifiers, now));
+    }
+
+    /// Rows the latest press moves.
+    pub fn step(&self, settings: &RepeatSettings) -> usize {
+        if self.run <= settings.after {
+            1
+        } else {
+            (1 + self.run - settings.after).min(settings.max_step.max(1))
+        }
+    }
+
+    /// Whether the latest press moves more than one row.
+    pub fn accelerating(&self, settings: &RepeatSettings) -> bool {
+        self.step(settings) > 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(c: char) -> KeyEvent {
+        KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE)
+    }
+
+    /// Steps for presses of `keys` at the given millisecond offsets.
+    fn steps(presses: &[(char, u64)]) -> Vec<usize> {
+        let settings = RepeatSettings::default();
+        let start = Instant::now();
+        let mut repeat = KeyRepeat::default();
+        presses
+            .iter()
+            .map(|&(c, at)| {
+                repeat.press(&key(c), start + Duration::from_millis(at), settings.window);
+                repeat.step(&settings)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn held_key_accelerates_up_to_the_cap() {
+        let held: Vec<(char, u64)> = (0..12).map(|i| ('j', i * 30)).collect();
+        assert_eq!(steps(&held), [1, 1, 1, 2, 3, 4, 5, 6, 7, 8, 8, 8]);
+    }
+
+    #[test]
+    fn discrete_presses_move_one_row() {
+        let taps: Vec<(char, u64)> = (0..6).map(|i| ('j', i * 250)).collect();
+        assert_eq!(steps(&taps), [1; 6]);
+    }
+
+    #[test]
+    fn another_key_or_a_pause_ends_the_run() {
+        assert_eq!(
+            steps(&[
+                ('j', 0),
+                ('j', 30),
+                ('j', 60),
+                ('j', 90),
+                ('k', 120),
+                ('k', 150),
+                ('k', 180),
+                ('k', 210),
+                ('k', 500),
+            ]),
+            [1, 1, 1, 2, 1, 1, 1, 2, 1]
+        );
+    }
+}
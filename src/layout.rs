@@ -0,0 +1,177 @@
+//! Horizontal split of the main area into tree and preview panels.
+//!
+//! On ultra-wide terminals a plain 40/60 split gives a huge tree and a
+//! preview far wider than any source file, so both panels can be capped
+//! (`tree.max_width`, `preview.max_content_width`). The split is resolved
+//! in this order:
+//!
+//! 1. Minimums: if the area can't fit both panels at their minimum widths,
+//!    the plain percentage split is used and the caps are ignored.
+//! 2. Percentage: the tree starts at [`TREE_PERCENT`] of the width.
+//! 3. Tree cap: the tree shrinks to `tree.max_width` (never below its
+//!    minimum) and the preview takes the rest.
+//! 4. Preview cap: columns beyond `preview.max_content_width` (plus its
+//!    borders) go back to the tree up to the tree cap; whatever is still
+//!    left is split evenly on both sides of the preview panel.
+//!
+//! The returned rects are the ones drawn, so mouse hit-testing uses them
+//! directly.
+
+use ratatui::layout::Rect;
+
+/// Share of the main area given to the tree before caps apply.
+pub const TREE_PERCENT: u16 = 40;
+/// Narrowest useful tree panel, borders included.
+pub const MIN_TREE_WIDTH: u16 = 20;
+/// Narrowest useful preview panel, borders included.
+pub const MIN_PREVIEW_WIDTH: u16 = 20;
+/// Columns taken by a panel's left and right borders.
+const BORDER_COLUMNS: u16 = 2;
+
+/// Final tree and preview rects for one frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MainLayout {
+    pub tree: Rect,
+    pub preview: Rect,
+}
+
+/// Split `area` between the tree and the preview, honoring the optional
+/// width caps (see the module docs for the precedence).
+pub fn split_main(
+    area: Rect,
+    tree_max_width: Option<u16>,
+    preview_max_content_width: Option<u16>,
+) -> MainLayout {
+    let width = area.width;
+    let percent_tree = (width as u32 * TREE_PERCENT as u32 / 100) as u16;
+
+    if width < MIN_TREE_WIDTH + MIN_PREVIEW_WIDTH {
+        return columns(area, percent_tree, 0, width - percent_tree);
+    }
+
+    let tree_cap = tree_max_width.map(|w| w.max(MIN_TREE_WIDTH));
+    let preview_cap =
+        preview_max_content_width.map(|w| w.saturating_add(BORDER_COLUMNS).max(MIN_PREVIEW_WIDTH));
+
+    let mut tree = percent_tree.max(MIN_TREE_WIDTH);
+    if let Some(cap) = tree_cap {
+        tree = tree.min(cap);
+    }
+    // Keep the preview at its minimum before honoring any cap
+    tree = tree.min(width - MIN_PREVIEW_WIDTH);
+
+    let mut preview = width - tree;
+    let mut margin = 0;
+    if let Some(cap) = preview_cap {
+        if preview > cap {
+            let surplus = preview - cap;
+            let grow = match tree_cap {
+                Some(tree_cap) => surplus.min(tree_cap.saturating_sub(tree)),
+                None => surplus,
+            };
+            tree += grow;
+            preview = cap;
+            margin = (surplus - grow) / 2;
+        }
+    }
+    columns(area, tree, margin, preview)
+}
+
+/// Tree at the left edge, then `gap` blank columns, then the preview.
+fn columns(area: Rect, tree: u16, gap: u16, preview: u16) -> MainLayout {
+    MainLayout {
+        tree: Rect::new(area.x, area.y, tree, area.height),
+        preview: Rect::new(area.x + tree + gap, area.y, preview, area.height),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn area(width: u16) -> Rect {
+        Rect::new(0, 0, width, 40)
+    }
+
+    /// (tree x, tree width, preview x, preview width)
+    fn spans(layout: MainLayout) -> (u16, u16, u16, u16) {
+        (
+            layout.tree.x,
+            layout.tree.width,
+            layout.preview.x,
+            layout.preview.width,
+        )
+    }
+
+    #[test]
+    fn uncapped_split_is_plain_percentage() {
+        assert_eq!(spans(split_main(area(100), None, None)), (0, 40, 40, 60));
+        assert_eq!(spans(split_main(area(400), None, None)), (0, 160, 160, 240));
+        // Caps wider than the panels change nothing
+        assert_eq!(
+            spans(split_main(area(100), Some(80), Some(200))),
+            (0, 40, 40, 60)
+        );
+    }
+
+    #[test]
+    fn tree_cap_gives_surplus_to_preview() {
+        assert_eq!(
+            spans(split_main(area(300), Some(50), None)),
+            (0, 50, 50, 250)
+        );
+    }
+
+    #[test]
+    fn preview_cap_gives_surplus_to_uncapped_tree() {
+        // 100 content columns + 2 borders
+        assert_eq!(
+            spans(split_main(area(300), None, Some(100))),
+            (0, 198, 198, 102)
+        );
+    }
+
+    #[test]
+    fn both_caps_center_the_preview() {
+        // Tree 60, preview 102; 138 spare columns split 69 / 69
+        let layout = split_main(area(300), Some(60), Some(100));
+        assert_eq!(spans(layout), (0, 60, 129, 102));
+        assert_eq!(layout.preview.right() + 69, 300);
+
+        // Tree grows from its 40% share up to the cap before centering
+        let layout = split_main(area(200), Some(90), Some(80));
+        assert_eq!(spans(layout), (0, 90, 104, 82));
+    }
+
+    #[test]
+    fn caps_below_minimums_are_raised() {
+        assert_eq!(
+            spans(split_main(area(120), Some(5), Some(5))),
+            (0, 20, 60, 20)
+        );
+    }
+
+    #[test]
+    fn tree_cap_never_starves_preview_minimum() {
+        // 40% of 45 is 18, raised to the 20-column minimum; preview keeps 25
+        assert_eq!(spans(split_main(area(45), Some(30), None)), (0, 20, 20, 25));
+    }
+
+    #[test]
+    fn very_narrow_area_ignores_caps() {
+        assert_eq!(
+            spans(split_main(area(30), Some(5), Some(5))),
+            (0, 12, 12, 18)
+        );
+        assert_eq!(spans(split_main(area(0), Some(5), Some(5))), (0, 0, 0, 0));
+    }
+
+    #[test]
+    fn layout_keeps_area_offset_and_height() {
+        let layout = split_main(Rect::new(3, 2, 300, 17), Some(60), Some(100));
+        assert_eq!(layout.tree.x, 3);
+        assert_eq!(layout.tree.y, 2);
+        assert_eq!(layout.preview.height, 17);
+        assert_eq!(layout.preview.x, 3 + 129);
+    }
+}
@@ -1,32 +1,87 @@
 mod app;
+#[cfg(feature = "archives")]
+mod archive;
+mod blame;
+mod bookmarks;
+mod bounded_map;
 mod components;
 mod config;
+mod confirm;
+mod dir_size;
+mod doctor;
+mod edit_positions;
+#[cfg(feature = "editor")]
 mod editor;
 mod error;
 mod event;
+mod external_edit;
+mod file_info;
+mod flash;
+mod front_matter;
 mod fs;
+mod git_status;
+mod grep;
 mod handler;
+mod idle_refresh;
+mod in_flight;
+mod key_repeat;
+mod layout;
+mod open_action;
+mod paste_guard;
+mod path_display;
+mod path_index;
+mod pattern;
+#[cfg(feature = "pdf-preview")]
+mod pdf;
+mod permissions;
+mod prefetch;
+mod preview_cache;
 mod preview_content;
+mod preview_load;
+mod preview_search;
+mod profile;
+mod recent_ops;
+mod refresh_defer;
+mod safe_mode;
+mod schema;
+mod session;
+mod stat_service;
+mod syntax;
+mod system_clipboard;
+mod tabs;
+mod tasks;
+mod template;
 mod terminal;
+mod text;
 mod theme;
+mod title;
 mod tui;
 mod ui;
+mod whitespace;
+mod work_queue;
+mod wrap;
 
-use std::path::PathBuf;
-use std::time::Duration;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 
 use crate::app::App;
+use crate::bookmarks::Bookmarks;
 use crate::config::{AppConfig, GeneralConfig, PreviewConfig, TreeConfig, WatcherConfig};
+use crate::edit_positions::EditPositions;
 use crate::event::{Event, EventHandler};
-use crate::fs::watcher::FsWatcher;
+use crate::fs::watcher::FsEvent;
+use crate::schema::Artifact;
 use crate::tui::{install_panic_hook, Tui};
 
 /// A terminal-based file manager TUI.
 #[derive(Parser, Debug)]
 #[command(name = "fm", version, about)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Root path to display (defaults to current directory)
     #[arg(default_value = ".")]
     path: PathBuf,
@@ -70,9 +125,82 @@ struct Cli {
     /// Color theme: dark, light
     #[arg(long)]
     theme: Option<String>,
+
+    /// Select a file on startup, optionally scrolled to a line
+    #[arg(long, value_name = "PATH[:LINE[:COL]]")]
+    select: Option<String>,
+
+    /// Start with built-in defaults only: no config file, no watcher,
+    /// terminal, mouse or icons
+    #[arg(long)]
+    safe_mode: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Check the terminal, config, watcher, shell and directories, then exit
+    Doctor,
+    /// Export or import settings as one shareable archive
+    Profile {
+        #[command(subcommand)]
+        action: ProfileAction,
+    },
+    /// Rewrite the saved session, undo, editor positions and bookmarks in
+    /// this version's format
+    Migrate {
+        /// List what would be migrated or moved aside, and stop
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ProfileAction {
+    /// Pack the effective config, syntaxes and bookmarks into FILE (.tar.gz)
+    Export { file: PathBuf },
+    /// Check every file in FILE, then unpack it into the config directory
+    Import {
+        file: PathBuf,
+        /// Replace existing files that differ from the profile's
+        #[arg(long)]
+        force: bool,
+        /// List what would be created or overwritten, and stop
+        #[arg(long)]
+        dry_run: bool,
+    },
 }
 
 impl Cli {
+    /// Safe mode: drop the config file and the theme and preview flags,
+    /// and turn off the watcher, embedded terminal, mouse and icons. Done
+    /// before the config is loaded, so nothing can turn them back on.
+    fn into_safe_mode(self) -> Self {
+        Cli {
+            config: None,
+            no_watcher: true,
+            no_icons: true,
+            no_mouse: true,
+            no_terminal: true,
+            head_lines: None,
+            tail_lines: None,
+            max_preview: None,
+            theme: None,
+            safe_mode: true,
+            ..self
+        }
+    }
+
+    /// Merged configuration for these flags. Safe mode reads no config
+    /// file at all: built-in defaults plus the flags.
+    fn load_config(&self) -> AppConfig {
+        let cli_overrides = self.as_config_overrides();
+        if self.safe_mode {
+            AppConfig::default().merge(&cli_overrides)
+        } else {
+            AppConfig::load(self.config.as_deref(), Some(&cli_overrides))
+        }
+    }
+
     /// Convert CLI flags into a partial `AppConfig` for the merge chain.
     /// Only flags that were explicitly set produce `Some` values.
     fn as_config_overrides(&self) -> AppConfig {
@@ -81,10 +209,25 @@ impl Cli {
                 default_path: None, // path is handled separately via positional arg
                 show_hidden: None,
                 confirm_delete: None,
+                use_trash: None,
+                backup_on_overwrite: None,
                 mouse: if self.no_mouse { Some(false) } else { None },
                 max_entries_per_page: None,
                 search_max_entries: None,
                 snapshot_max_entries: None,
+                primary_selection: None,
+                osc52: None,
+                autosave_secs: None,
+                count_prefix: None,
+                preflight_count: None,
+                large_op_entries: None,
+                large_op_bytes: None,
+                set_title: None,
+                title_template: None,
+                grep_max_file_bytes: None,
+                grep_max_results: None,
+                opener: None,
+                path_display: None,
             },
             preview: PreviewConfig {
                 max_full_preview_bytes: self.max_preview,
@@ -95,15 +238,38 @@ impl Cli {
                 line_wrap: None,
                 syntax_theme: None,
                 enabled: if self.no_preview { Some(false) } else { None },
+                cache_max_bytes: None,
+                max_content_width: None,
+                wrap_file_navigation: None,
+                prefetch: None,
+                wrap_marker: None,
+                wrap_hang_indent: None,
+                load_timeout_secs: None,
             },
             tree: TreeConfig {
                 sort_by: None,
                 dirs_first: None,
                 use_icons: if self.no_icons { Some(false) } else { None },
+                max_width: None,
+                max_depth: None,
+                min_name_width: None,
+                deferred_stats: None,
+                dir_sizes: None,
+                apparent_sizes: None,
+                one_file_system: None,
+                git_status: None,
+                respect_gitignore: None,
             },
+            filter: Default::default(),
             watcher: WatcherConfig {
                 enabled: if self.no_watcher { Some(false) } else { None },
                 debounce_ms: None,
+                highlight_changes: None,
+                idle_refresh_ms: None,
+                idle_refresh_batch: None,
+                defer_during_builds: None,
+                changed_window_secs: None,
+                recursive: None,
             },
             terminal: crate::config::TerminalConfig {
                 enabled: if self.no_terminal { Some(false) } else { None },
@@ -114,88 +280,237 @@ impl Cli {
                 scheme: self.theme.clone(),
                 custom: None,
             },
+            send_to: Default::default(),
+            bookmarks: Default::default(),
+            open: Default::default(),
+            accessibility: Default::default(),
+            editor: Default::default(),
+            navigation: Default::default(),
+            memory: Default::default(),
         }
     }
 }
 
 #[tokio::main]
 async fn main() -> error::Result<()> {
-    let cli = Cli::parse();
+    let mut cli = Cli::parse();
+    if let Some(Command::Migrate { dry_run }) = cli.command {
+        std::process::exit(run_migrate(dry_run));
+    }
+
+    // Move unreadable saved files aside before anything reads them
+    let mut schema_warnings = Vec::new();
+    if cli.command.is_none() {
+        if let Some(dir) = session::session_dir() {
+            schema_warnings.extend(schema::check_startup(&Artifact::SESSION, &dir));
+        }
+        if let Some(dir) = crate::config::config_dir() {
+            schema_warnings.extend(schema::check_startup(&Artifact::CONFIG, &dir));
+        }
+    }
+
+    // Offer safe mode if the previous run didn't exit cleanly
+    let mut safe_mode = cli.safe_mode.then_some(safe_mode::Trigger::Flag);
+    if safe_mode.is_none() && cli.command.is_none() {
+        let crashed = session::session_dir().is_some_and(|dir| session::previous_run_crashed(&dir));
+        if crashed && session::confirm(safe_mode::PROMPT) {
+            safe_mode = Some(safe_mode::Trigger::Crash);
+        }
+    }
+    if safe_mode.is_some() {
+        cli = cli.into_safe_mode();
+    }
 
     let path = cli.path.canonicalize().map_err(|_| {
         error::AppError::InvalidPath(format!("{} does not exist", cli.path.display()))
     })?;
 
     // Load configuration: file sources + CLI overrides
-    let cli_overrides = cli.as_config_overrides();
-    let config = AppConfig::load(cli.config.as_deref(), Some(&cli_overrides));
+    let config = cli.load_config();
+
+    if let Some(Command::Doctor) = cli.command {
+        std::process::exit(doctor::run(&path, cli.config.as_deref(), &config));
+    }
+    if let Some(Command::Profile { action }) = &cli.command {
+        std::process::exit(run_profile(action, cli.config.as_deref()));
+    }
+
+    // Offer the last autosave if the previous run didn't exit cleanly
+    let session_dir = session::session_dir().filter(|_| config.autosave_secs() > 0);
+    let mut recovered = None;
+    if let Some(dir) = session_dir.as_deref() {
+        if let Some(snapshot) = session::recovery_offer(dir) {
+            if session::confirm_restore(&snapshot) {
+                recovered = Some(snapshot);
+            } else {
+                // Declined: don't offer this autosave again
+                let _ = session::mark_clean_exit(dir, session::now_ms());
+            }
+        }
+    }
+    let path = recovered
+        .as_ref()
+        .map(|snapshot| snapshot.root.clone())
+        .unwrap_or(path);
 
     install_panic_hook();
 
     let mut app = App::new(&path, config)?;
+    app.autosave.dir = session_dir.clone();
+    if let Some(snapshot) = &recovered {
+        app.restore_session(snapshot);
+        app.set_status_message("Session restored".to_string());
+    }
+    if let Some(trigger) = safe_mode {
+        app.safe_mode = true;
+        app.set_status_message(safe_mode::banner(trigger));
+    }
+    // The last undo survives restarts, independent of autosave
+    let undo_dir = session::session_dir();
+    if let Some(action) = undo_dir.as_deref().and_then(session::load_undo) {
+        app.restore_undo(action);
+    }
+    if let Some(dir) = undo_dir.as_deref() {
+        app.edit_positions = EditPositions::load(dir);
+    }
+    app.config_dir = crate::config::config_dir();
+    app.cli_config_path = cli.config.clone();
+    if let Some(dir) = app.config_dir.as_deref() {
+        app.bookmarks = Bookmarks::load(dir);
+    }
+    if !schema_warnings.is_empty() {
+        app.set_status_message(format!("⚠ {}", schema_warnings.join("; ")));
+    }
     let mut tui = Tui::new(app.config.mouse_enabled())?;
+    if app.config.set_title() {
+        let _ = tui.save_title();
+    }
     let mut events = EventHandler::new(Duration::from_millis(16));
     let event_tx = events.sender();
+    app.preview_load.notify = Some(event_tx.clone());
 
     // Initialize filesystem watcher (using merged config)
-    let _watcher = if !app.config.watcher_enabled() {
-        app.watcher_active = false;
-        None
-    } else {
-        let ignore_patterns: Vec<String> = fs::watcher::DEFAULT_IGNORE_PATTERNS
-            .iter()
-            .map(|s| s.to_string())
-            .collect();
-
-        match FsWatcher::new(
-            &path,
-            Duration::from_millis(app.config.debounce_ms()),
-            ignore_patterns,
-            fs::watcher::DEFAULT_FLOOD_THRESHOLD,
-            event_tx.clone(),
-        ) {
-            Ok(watcher) => Some(watcher),
-            Err(e) => {
-                app.watcher_active = false;
-                app.set_status_message(format!("⚠ Watcher unavailable: {}", e));
-                None
+    let mut watched_root = app.tree_state.root.path.clone();
+    app.start_watcher(&event_tx);
+
+    // Applied after the first draw, so the preview has its real size
+    let mut select = cli.select.clone();
+    let cwd = std::env::current_dir().unwrap_or_else(|_| path.clone());
+
+    // Skipped between the events of a held, accelerated navigation key
+    // that are already queued, so a burst draws once where it lands
+    let mut redraw = true;
+    loop {
+        if redraw {
+            tui.terminal_mut().draw(|frame| {
+                ui::render(&mut app, frame);
+            })?;
+            if let Some(title) = app.poll_title(Instant::now()) {
+                let _ = tui.set_title(&title);
             }
         }
-    };
+        if let Some(escape) = app.pending_osc52.take() {
+            let _ = tui.write_escape(&escape);
+        }
+        redraw = true;
 
-    loop {
-        tui.terminal_mut().draw(|frame| {
-            ui::render(&mut app, frame);
-        })?;
+        if let Some(target) = select.take() {
+            app.goto_location(&target, &cwd);
+            continue;
+        }
 
         match events.next().await? {
-            Event::Key(key) => handler::handle_key_event(&mut app, key, &event_tx),
-            Event::Mouse(mouse) => handler::handle_mouse_event(&mut app, mouse, &event_tx),
-            Event::Tick => {}
-            Event::Resize(_, _) => {}
+            Event::Key(key) => {
+                app.note_input();
+                handler::handle_key_event(&mut app, key, &event_tx);
+                redraw = !(app.accelerating() && events.has_pending());
+            }
+            Event::Mouse(mouse) => {
+                app.note_input();
+                handler::handle_mouse_event(&mut app, mouse, &event_tx);
+            }
+            Event::Paste(text) => {
+                app.note_input();
+                handler::handle_paste_event(&mut app, &text);
+            }
+            Event::Tick => {
+                app.handle_tick();
+                app.poll_blame(&event_tx);
+                app.poll_git_status(&event_tx);
+                app.poll_idle_refresh(&event_tx);
+                app.poll_prefetch(&event_tx);
+                app.poll_stats(&event_tx);
+                app.poll_dir_size(&event_tx);
+                app.poll_grep(&event_tx);
+                app.poll_path_index(&event_tx);
+                app.poll_preview_load(Instant::now());
+                app.poll_refresh_defer(Instant::now());
+                app.poll_fs_events(Instant::now());
+                app.poll_autosave(&event_tx);
+            }
+            Event::Resize(_, _) => app.layout_changed(),
             Event::Progress(update) => app.handle_progress(update),
             Event::OperationComplete(result) => app.handle_operation_complete(result),
-            Event::FsChange(paths) => app.handle_fs_change(paths),
-            Event::TerminalOutput(data) => app.terminal_state.emulator.process(&data),
+            Event::DeleteComplete(result) => app.handle_delete_complete(result),
+            Event::RetryAttempt { job, attempt } => app.handle_retry_attempt(job, attempt),
+            Event::RetryComplete(result) => app.handle_retry_complete(result),
+            Event::DuplicateComplete(result) => app.handle_duplicate_complete(result),
+            #[cfg(feature = "archives")]
+            Event::ExtractComplete(result) => app.handle_extract_complete(result),
+            Event::ScaffoldComplete(result) => app.handle_scaffold_complete(result),
+            Event::GrepResults(batch) => app.handle_grep_results(batch),
+            Event::FsChange(paths) => {
+                app.queue_fs_events(paths.into_iter().map(FsEvent::Changed).collect())
+            }
+            Event::FsEvents(events) => app.queue_fs_events(events),
+            Event::TerminalOutput(data) => app.handle_terminal_output(&data),
             Event::DirScanComplete { path, snapshot } => {
                 app.handle_dir_scan_complete(&path, snapshot);
             }
             Event::DirCountComplete { path, count } => {
                 app.handle_dir_count_complete(&path, count);
             }
-            Event::DirSummaryUpdate {
-                path,
-                files,
-                dirs,
-                size,
-                done,
-            } => {
-                app.handle_dir_summary_update(&path, files, dirs, size, done);
+            Event::DirSizeUpdate { path, job, size } => {
+                app.handle_dir_size_update(&path, job, size);
+            }
+            Event::BlameComplete(result) => app.handle_blame_complete(result),
+            Event::GitStatusComplete(result) => app.handle_git_status_complete(result),
+            Event::IndexComplete(root, index) => app.handle_index_complete(root, index),
+            Event::IndexBatch(batch) => app.handle_index_batch(batch),
+            Event::IdleRefreshComplete(result) => app.handle_idle_refresh_complete(result),
+            Event::ClipboardComplete(report) => app.handle_clipboard_complete(report),
+            Event::AutosaveComplete(result) => app.handle_autosave_complete(result),
+            Event::PreviewReady { generation } => app.handle_preview_ready(generation),
+            Event::PreviewHighlighted { generation } => app.handle_preview_highlighted(generation),
+            Event::PrefetchComplete(result) => app.handle_prefetch_complete(result),
+            Event::StatsReady(batch) => app.handle_stats_ready(batch),
+            Event::PreflightComplete { id, usage } => {
+                app.handle_preflight_complete(id, usage, &event_tx)
+            }
+            Event::PortabilityComplete { id, report } => {
+                app.handle_portability_complete(id, report)
+            }
+            Event::ContinueWork(id) => app.continue_work(id),
+        }
+
+        if let Some(path) = app.external_edit.take() {
+            edit_externally(&mut tui, &mut app, &events, &path)?;
+        }
+
+        app.sync_terminal_pacing();
+        // The next slice of tree work comes after the next draw
+        app.poll_work(&event_tx);
+
+        // Follow the tree when it is re-rooted (places overlay)
+        if app.tree_state.root.path != watched_root {
+            watched_root = app.tree_state.root.path.clone();
+            if app.watcher.is_running() || app.watcher.error.is_some() {
+                app.start_watcher(&event_tx);
             }
         }
 
         // Sync watcher pause/resume state
-        if let Some(ref watcher) = _watcher {
+        if let Some(watcher) = app.watcher.watcher() {
             if app.watcher_active && !watcher.is_active() {
                 watcher.resume();
             } else if !app.watcher_active && watcher.is_active() {
@@ -210,5 +525,200 @@ async fn main() -> error::Result<()> {
 
     app.shutdown_terminal();
     tui.restore()?;
+    if let Some(dir) = undo_dir.as_deref() {
+        let _ = session::save_undo(dir, app.persistable_undo());
+        #[cfg(feature = "editor")]
+        app.remember_edit_position();
+        let _ = app.edit_positions.save(dir);
+        // Even with autosave off, so a stale autosave doesn't look like a crash
+        let _ = session::mark_clean_exit(dir, session::now_ms());
+    }
     Ok(())
 }
+
+/// Run `$EDITOR` on `path` in the foreground. Input and the watcher are
+/// paused until it exits, and the terminal is restored even when the
+/// editor can't start.
+fn edit_externally(
+    tui: &mut Tui,
+    app: &mut App,
+    events: &EventHandler,
+    path: &Path,
+) -> error::Result<()> {
+    let watching = app.watcher.watcher().filter(|w| w.is_active());
+    if let Some(watcher) = watching {
+        watcher.pause();
+    }
+    events.pause_input();
+    let result = match tui.suspend() {
+        Ok(()) => external_edit::run(path),
+        Err(e) => Err(format!("Cannot suspend the terminal: {}", e)),
+    };
+    let resumed = tui.resume();
+    events.resume_input();
+    if let Some(watcher) = watching {
+        watcher.resume();
+    }
+    app.finish_external_edit(path, result);
+    resumed
+}
+
+/// `fm profile export|import`: report on the terminal and return the
+/// exit code.
+fn run_profile(action: &ProfileAction, cli_config: Option<&Path>) -> i32 {
+    let config_dir = match crate::config::config_dir() {
+        Some(dir) => dir,
+        None => {
+            eprintln!("fm: no config directory (is $HOME set?)");
+            return 1;
+        }
+    };
+    let result = match action {
+        ProfileAction::Export { file } => {
+            let sources = crate::config::source_paths(cli_config);
+            profile::export(&sources, &config_dir, file).map(|names| {
+                println!("Exported {} to {}", names.join(", "), file.display());
+            })
+        }
+        ProfileAction::Import {
+            file,
+            force,
+            dry_run,
+        } => {
+            let options = profile::ImportOptions {
+                force: *force,
+                dry_run: *dry_run,
+            };
+            profile::import(file, &config_dir, options).map(|plan| {
+                for planned in &plan {
+                    println!(
+                        "{:<10} {}",
+                        planned.change.label(),
+                        config_dir.join(&planned.name).display()
+                    );
+                }
+                if *dry_run {
+                    println!("Dry run: nothing written");
+                }
+            })
+        }
+    };
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("fm: {}", e);
+            1
+        }
+    }
+}
+
+/// `fm migrate`: bring every saved file up to this version, or with
+/// `dry_run` list what that would do.
+fn run_migrate(dry_run: bool) -> i32 {
+    let dirs = [
+        (session::session_dir(), &Artifact::SESSION[..]),
+        (crate::config::config_dir(), &Artifact::CONFIG[..]),
+    ];
+    for (dir, artifacts) in dirs {
+        let Some(dir) = dir else {
+            continue;
+        };
+        match schema::migrate_dir(artifacts, &dir, dry_run) {
+            Ok(steps) => {
+                for step in &steps {
+                    println!("{}", step.describe(dry_run));
+                }
+            }
+            Err(e) => {
+                eprintln!("fm: {}: {}", dir.display(), e);
+                return 1;
+            }
+        }
+    }
+    if dry_run {
+        println!("Dry run: nothing written");
+    }
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cli(args: &[&str]) -> Cli {
+        Cli::try_parse_from(std::iter::once("fm").chain(args.iter().copied())).unwrap()
+    }
+
+    #[test]
+    fn safe_mode_disables_each_subsystem() {
+        let cli = cli(&["--safe-mode", "--theme", "light", "--head-lines", "5"]).into_safe_mode();
+        let config = cli.load_config();
+        assert!(!config.watcher_enabled());
+        assert!(!config.terminal_enabled());
+        assert!(!config.mouse_enabled());
+        assert!(!config.use_icons());
+        assert_eq!(config.theme_scheme(), "dark");
+        assert_eq!(config.head_lines(), AppConfig::default().head_lines());
+    }
+
+    #[test]
+    fn safe_mode_does_not_read_the_config_file() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let file = dir.path().join("config.toml");
+        std::fs::write(
+            &file,
+            "[general]\nshow_hidden = true\nmouse = true\n\n[theme]\nscheme = \"light\"\n\n[watcher]\nenabled = true\n",
+        )
+        .unwrap();
+        let file = file.to_str().unwrap();
+
+        // Read normally...
+        let normal = cli(&["-c", file]).load_config();
+        assert!(normal.show_hidden());
+        assert_eq!(normal.theme_scheme(), "light");
+
+        // ...but not in safe mode
+        let safe = cli(&["--safe-mode", "-c", file]).into_safe_mode();
+        assert_eq!(safe.config, None);
+        let config = safe.load_config();
+        assert!(!config.show_hidden());
+        assert_eq!(config.theme_scheme(), "dark");
+        assert!(!config.watcher_enabled());
+        assert!(!config.mouse_enabled());
+    }
+
+    #[test]
+    fn safe_mode_keeps_the_path_and_selection() {
+        let cli = cli(&["--select", "src/app.rs:12", "--no-preview", "/tmp"]).into_safe_mode();
+        assert_eq!(cli.path, PathBuf::from("/tmp"));
+        assert_eq!(cli.select.as_deref(), Some("src/app.rs:12"));
+        assert!(cli.no_preview);
+        assert!(cli.safe_mode);
+    }
+
+    #[test]
+    fn profile_subcommands_parse() {
+        let import = cli(&["profile", "import", "team.tar.gz", "--dry-run"]);
+        match import.command {
+            Some(Command::Profile {
+                action:
+                    ProfileAction::Import {
+                        file,
+                        force,
+                        dry_run,
+                    },
+            }) => {
+                assert_eq!(file, PathBuf::from("team.tar.gz"));
+                assert!(!force);
+                assert!(dry_run);
+            }
+            other => panic!("expected profile import, got {:?}", other),
+        }
+        assert!(matches!(
+            cli(&["profile", "export", "out.tar.gz"]).command,
+            Some(Command::Profile {
+                action: ProfileAction::Export { .. }
+            })
+        ));
+    }
+}
@@ -0,0 +1,444 @@
+//! Per-file-type default action for Enter (the `[open]` config section).
+//!
+//! Rules are compiled once at startup into an [`OpenRules`] table. Patterns
+//! containing `*` or `?` are globs matched against the file name; anything
+//! else is an extension (leading `.` optional). Globs are tried first, most
+//! specific (longest) pattern first, then the extension, then the default.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+
+use crate::config::OpenConfig;
+use crate::terminal::TerminalState;
+
+/// What Enter does on a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OpenAction {
+    /// Open the file in the inline editor.
+    Edit,
+    /// Move focus to the preview panel.
+    PreviewFocus,
+    /// Hand the file to the system opener (xdg-open / open).
+    External,
+    /// Run a command template in the embedded terminal.
+    Terminal(String),
+}
+
+impl OpenAction {
+    /// Parse a config value. Returns `None` for unknown actions.
+    pub fn parse(raw: &str) -> Option<Self> {
+        let raw = raw.trim();
+        if let Some(template) = raw.strip_prefix("terminal:") {
+            let template = template.trim();
+            if template.is_empty() {
+                return None;
+            }
+            return Some(OpenAction::Terminal(template.to_string()));
+        }
+        match raw {
+            "edit" => Some(OpenAction::Edit),
+            "preview_focus" => Some(OpenAction::PreviewFocus),
+            "external" => Some(OpenAction::External),
+            _ => None,
+        }
+    }
+
+    /// Config spelling of the action (for help and status messages).
+    pub fn label(&self) -> String {
+        match self {
+            OpenAction::Edit => "edit".to_string(),
+            OpenAction::PreviewFocus => "preview_focus".to_string(),
+            OpenAction::External => "external".to_string(),
+            OpenAction::Terminal(template) => format!("terminal:{}", template),
+        }
+    }
+}
+
+/// Compiled lookup table built from `[open]`.
+#[derive(Debug, Clone)]
+pub struct OpenRules {
+    /// Glob rules, longest pattern first.
+    globs: Vec<(String, OpenAction)>,
+    /// Lowercased extension → action.
+    extensions: HashMap<String, OpenAction>,
+    /// Action for files matching no rule.
+    default: OpenAction,
+}
+
+impl Default for OpenRules {
+    fn default() -> Self {
+        Self {
+            globs: Vec::new(),
+            extensions: HashMap::new(),
+            default: OpenAction::PreviewFocus,
+        }
+    }
+}
+
+impl OpenRules {
+    /// Compile the config section. Unknown actions are skipped (or, for
+    /// `default`, replaced with `preview_focus`) and reported as warnings.
+    pub fn compile(config: &OpenConfig) -> (Self, Vec<String>) {
+        let mut rules = OpenRules::default();
+        let mut warnings = Vec::new();
+
+        if let Some(ref raw) = config.default {
+            match OpenAction::parse(raw) {
+                Some(action) => rules.default = action,
+                None => warnings.push(format!(
+                    "unknown open action '{}' for default, using preview_focus",
+                    raw
+                )),
+            }
+        }
+
+        for (pattern, raw) in &config.rules {
+            let action = match OpenAction::parse(raw) {
+                Some(action) => action,
+                None => {
+                    warnings.push(format!(
+                        "unknown open action '{}' for '{}', using {}",
+                        raw,
+                        pattern,
+                        rules.default.label()
+                    ));
+                    continue;
+                }
+            };
+            if is_glob(pattern) {
+                rules.globs.push((pattern.to_lowercase(), action));
+            } else {
+                let ext = pattern.trim_start_matches('.').to_lowercase();
+                rules.extensions.insert(ext, action);
+            }
+        }
+        rules.globs.sort_by_key(|g| std::cmp::Reverse(g.0.len()));
+
+        (rules, warnings)
+    }
+
+    /// Action for Enter on `path`.
+    pub fn action_for(&self, path: &Path) -> &OpenAction {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        if let Some((_, action)) = self
+            .globs
+            .iter()
+            .find(|(pattern, _)| glob_match(pattern, &name))
+        {
+            return action;
+        }
+
+        path.extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .and_then(|ext| self.extensions.get(&ext))
+            .unwrap_or(&self.default)
+    }
+
+    /// Action for files matching no rule.
+    pub fn default_action(&self) -> &OpenAction {
+        &self.default
+    }
+}
+
+fn is_glob(pattern: &str) -> bool {
+    pattern.contains('*') || pattern.contains('?')
+}
+
+/// Match `name` against a glob supporting `*` (any run) and `?` (one char).
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let n: Vec<char> = name.chars().collect();
+    let (mut pi, mut ni) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+
+    while ni < n.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == n[ni]) {
+            pi += 1;
+            ni += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, ni));
+            pi += 1;
+        } else if let Some((sp, sn)) = star {
+            pi = sp + 1;
+            ni = sn + 1;
+            star = Some((sp, sn + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|&c| c == '*')
+}
+
+/// Expand `{path}`, `{dir}` and `{name}` in a terminal command template.
+/// Substituted values are single-quoted for the shell.
+pub fn expand_command(template: &str, path: &Path) -> String {
+    let dir = path.parent().unwrap_or(path);
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    template
+        .replace("{path}", &shell_quote(&path.to_string_lossy()))
+        .replace("{dir}", &shell_quote(&dir.to_string_lossy()))
+        .replace("{name}", &shell_quote(&name))
+}
+
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
+
+/// Side effects of the `external` and `terminal:` actions.
+///
+/// Kept behind a trait so tests can record launches instead of spawning.
+pub trait Launcher {
+    /// Open `path` with the system's default application.
+    fn open_external(&mut self, path: &Path) -> Result<(), String>;
+    /// Failures of openers that exited since the last call, as status
+    /// messages.
+    fn finished(&mut self) -> Vec<String> {
+        Vec::new()
+    }
+    /// Type `command` into the (already visible) embedded terminal.
+    fn run_in_terminal(&mut self, terminal: &TerminalState, command: &str) -> Result<(), String>;
+}
+
+/// The platform's opener: `xdg-open`, `open` on macOS, `start` on
+/// Windows.
+pub fn default_opener() -> Vec<String> {
+    let command: &[&str] = if cfg!(target_os = "macos") {
+        &["open"]
+    } else if cfg!(windows) {
+        // `start` is a shell builtin; its first quoted argument is a title
+        &["cmd", "/C", "start", ""]
+    } else {
+        &["xdg-open"]
+    };
+    command.iter().map(|s| s.to_string()).collect()
+}
+
+/// Launcher that spawns real processes.
+pub struct SystemLauncher {
+    /// Opener program and arguments; the path is appended.
+    opener: Vec<String>,
+    /// Openers not yet exited, with the file each opens.
+    running: Vec<(String, Child)>,
+}
+
+impl SystemLauncher {
+    /// Launcher running `opener` (`general.opener`), split on whitespace,
+    /// or the platform default.
+    pub fn new(opener: Option<&str>) -> Self {
+        let opener = opener
+            .map(|o| o.split_whitespace().map(str::to_string).collect())
+            .filter(|o: &Vec<String>| !o.is_empty())
+            .unwrap_or_else(default_opener);
+        Self {
+            opener,
+            running: Vec::new(),
+        }
+    }
+}
+
+impl Launcher for SystemLauncher {
+    fn open_external(&mut self, path: &Path) -> Result<(), String> {
+        let (program, args) = self.opener.split_first().ok_or("No opener configured")?;
+        // Detached from the TUI's terminal; reaped by `finished`
+        let child = Command::new(program)
+            .args(args)
+            .arg(path)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::NotFound => {
+                    format!("Opener `{}` not found (set general.opener)", program)
+                }
+                _ => format!("Cannot run `{}`: {}", program, e),
+            })?;
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        self.running.push((name, child));
+        Ok(())
+    }
+
+    fn finished(&mut self) -> Vec<String> {
+        let program = &self.opener[0];
+        let mut failures = Vec::new();
+        self.running
+            .retain_mut(|(name, child)| match child.try_wait() {
+                Ok(None) => true,
+                Ok(Some(status)) => {
+                    if !status.success() {
+                        failures.push(format!(
+                            "Error: `{}` could not open {} ({})",
+                            program, name, status
+                        ));
+                    }
+                    false
+                }
+                Err(_) => false,
+            });
+        failures
+    }
+
+    fn run_in_terminal(&mut self, terminal: &TerminalState, command: &str) -> Result<(), String> {
+        let pty = terminal.pty.as_ref().ok_or("Terminal is not running")?;
+        pty.write(format!("{}\n", command).as_bytes())
+            .map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use std::path::PathBuf;
+
+    fn config(default: Option<&str>, rules: &[(&str, &str)]) -> OpenConfig {
+        OpenConfig {
+            default: default.map(String::from),
+            rules: rules
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect::<BTreeMap<_, _>>(),
+        }
+    }
+
+    #[test]
+    fn parse_known_actions() {
+        assert_eq!(OpenAction::parse("edit"), Some(OpenAction::Edit));
+        assert_eq!(
+            OpenAction::parse("preview_focus"),
+            Some(OpenAction::PreviewFocus)
+        );
+        assert_eq!(OpenAction::parse("external"), Some(OpenAction::External));
+        assert_eq!(
+            OpenAction::parse("terminal: less {path}"),
+            Some(OpenAction::Terminal("less {path}".to_string()))
+        );
+        assert_eq!(OpenAction::parse("terminal:"), None);
+        assert_eq!(OpenAction::parse("launch"), None);
+    }
+
+    #[test]
+    fn extension_and_glob_lookup() {
+        let (rules, warnings) = OpenRules::compile(&config(
+            None,
+            &[
+                ("rs", "edit"),
+                (".PDF", "external"),
+                ("*.test.rs", "terminal:cargo test"),
+            ],
+        ));
+        assert!(warnings.is_empty());
+        assert_eq!(rules.action_for(Path::new("/a/main.rs")), &OpenAction::Edit);
+        assert_eq!(
+            rules.action_for(Path::new("/a/doc.pdf")),
+            &OpenAction::External
+        );
+        // Glob beats the extension rule
+        assert_eq!(
+            rules.action_for(Path::new("/a/foo.test.rs")),
+            &OpenAction::Terminal("cargo test".to_string())
+        );
+        assert_eq!(
+            rules.action_for(Path::new("/a/notes.txt")),
+            &OpenAction::PreviewFocus
+        );
+    }
+
+    #[test]
+    fn longer_glob_wins() {
+        let (rules, _) = OpenRules::compile(&config(
+            None,
+            &[("*.md", "edit"), ("readme*.md", "preview_focus")],
+        ));
+        assert_eq!(
+            rules.action_for(Path::new("README.md")),
+            &OpenAction::PreviewFocus
+        );
+        assert_eq!(rules.action_for(Path::new("guide.md")), &OpenAction::Edit);
+    }
+
+    #[test]
+    fn unknown_actions_warn_and_fall_back() {
+        let (rules, warnings) = OpenRules::compile(&config(
+            Some("explode"),
+            &[("rs", "compile"), ("md", "edit")],
+        ));
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(rules.default_action(), &OpenAction::PreviewFocus);
+        assert_eq!(
+            rules.action_for(Path::new("x.rs")),
+            &OpenAction::PreviewFocus
+        );
+        assert_eq!(rules.action_for(Path::new("x.md")), &OpenAction::Edit);
+    }
+
+    #[test]
+    fn configured_default_applies() {
+        let (rules, _) = OpenRules::compile(&config(Some("edit"), &[]));
+        assert_eq!(rules.action_for(Path::new("any.bin")), &OpenAction::Edit);
+    }
+
+    #[test]
+    fn opener_defaults_per_platform() {
+        let launcher = SystemLauncher::new(Some("gio  open"));
+        assert_eq!(launcher.opener, ["gio", "open"]);
+        assert_eq!(SystemLauncher::new(Some(" ")).opener, default_opener());
+        assert_eq!(SystemLauncher::new(None).opener, default_opener());
+        if cfg!(target_os = "linux") {
+            assert_eq!(default_opener(), ["xdg-open"]);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn opener_failures_are_reported_once_it_exits() {
+        let mut missing = SystemLauncher::new(Some("fm-no-such-opener"));
+        assert_eq!(
+            missing.open_external(Path::new("/dev/null")),
+            Err("Opener `fm-no-such-opener` not found (set general.opener)".to_string())
+        );
+
+        let mut failing = SystemLauncher::new(Some("false"));
+        failing.open_external(Path::new("/tmp/a.pdf")).unwrap();
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        let mut failures = Vec::new();
+        while failures.is_empty() && std::time::Instant::now() < deadline {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            failures = failing.finished();
+        }
+        assert_eq!(failures.len(), 1);
+        assert!(failures[0].starts_with("Error: `false` could not open a.pdf"));
+        assert!(failing.finished().is_empty());
+    }
+
+    #[test]
+    fn glob_matching() {
+        assert!(glob_match("*.rs", "main.rs"));
+        assert!(glob_match("makefile", "makefile"));
+        assert!(glob_match("file?.txt", "file1.txt"));
+        assert!(glob_match("*", "anything"));
+        assert!(!glob_match("*.rs", "main.rsx"));
+        assert!(!glob_match("file?.txt", "file.txt"));
+    }
+
+    #[test]
+    fn expand_command_quotes_placeholders() {
+        let path = PathBuf::from("/tmp/it's here/a b.log");
+        let cmd = expand_command("less {path} # {name} in {dir}", &path);
+        assert_eq!(
+            cmd,
+            "less '/tmp/it'\\''s here/a b.log' # 'a b.log' in '/tmp/it'\\''s here'"
+        );
+    }
+}
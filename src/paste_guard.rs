@@ -0,0 +1,253 @@
+//! Pasted text in the one-line inputs (name dialogs, search, filter).
+//!
+//! Pastes arrive either as one bracketed `Paste` event or, in terminals
+//! without bracketed paste, as a burst of key events where each line break
+//! is an Enter. Neither may confirm the input: [`sanitize`] turns line
+//! breaks into spaces and drops control characters from a bracketed
+//! paste, and [`PasteGuard`] recognises an Enter arriving within
+//! [`BURST_GAP`] of a run of typed characters as part of a burst and
+//! swallows it. Name inputs are also held to [`NAME_MAX`] bytes.
+
+use std::time::{Duration, Instant};
+
+/// Longest gap between two keys of one paste burst. Typing, even key
+/// auto-repeat, is far slower.
+pub const BURST_GAP: Duration = Duration::from_millis(10);
+
+/// Characters in a burst before an Enter counts as pasted.
+const MIN_BURST: usize = 2;
+
+/// Longest file name in bytes (`NAME_MAX` on common filesystems).
+pub const NAME_MAX: usize = 255;
+
+/// Bytes left under [`NAME_MAX`] at which the name dialogs start counting
+/// down.
+pub const NAME_WARN_BYTES: usize = 32;
+
+/// Pasted text made fit for a one-line input.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Sanitized {
+    pub text: String,
+    /// Line breaks replaced (`\r\n` counts once).
+    pub line_breaks: usize,
+    /// Other control characters dropped.
+    pub controls: usize,
+}
+
+impl Sanitized {
+    /// Status line explaining what was removed, if anything.
+    pub fn warning(&self) -> Option<String> {
+        let mut parts = Vec::new();
+        if self.line_breaks > 0 {
+            parts.push(count_label(self.line_breaks, "line break"));
+        }
+        if self.controls > 0 {
+            parts.push(count_label(self.controls, "control character"));
+        }
+        if parts.is_empty() {
+            return None;
+        }
+        Some(format!(
+            "pasted text contained {} — removed",
+            parts.join(" and ")
+        ))
+    }
+}
+
+/// "1 line break", "2 line breaks".
+fn count_label(n: usize, noun: &str) -> String {
+    if n == 1 {
+        format!("1 {}", noun)
+    } else {
+        format!("{} {}s", n, noun)
+    }
+}
+
+/// Warning for `n` line breaks swallowed from a key burst.
+pub fn burst_warning(n: usize) -> String {
+    format!(
+        "pasted text contained {} — removed",
+        count_label(n, "line break")
+    )
+}
+
+/// Flatten pasted text onto one line: line breaks between text become one
+/// space, leading and trailing ones go, tabs become spaces and other
+/// control characters are dropped.
+pub fn sanitize(pasted: &str) -> Sanitized {
+    let mut text = String::with_capacity(pasted.len());
+    let mut line_breaks = 0;
+    let mut controls = 0;
+    let mut pending_break = false;
+    let mut chars = pasted.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\r' | '\n' => {
+                if c == '\r' && chars.peek() == Some(&'\n') {
+                    chars.next();
+                }
+                line_breaks += 1;
+                pending_break = true;
+            }
+            '\t' => text.push(' '),
+            c if c.is_control() => controls += 1,
+            c => {
+                if pending_break && !text.is_empty() {
+                    text.push(' ');
+                }
+                pending_break = false;
+                text.push(c);
+            }
+        }
+    }
+    Sanitized {
+        text,
+        line_breaks,
+        controls,
+    }
+}
+
+/// The longest prefix of `text` that fits in `room` bytes, cut on a char
+/// boundary.
+pub fn fit(text: &str, room: usize) -> &str {
+    if text.len() <= room {
+        return text;
+    }
+    let mut end = room;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+/// Countdown shown in a name dialog once the name nears [`NAME_MAX`].
+pub fn name_feedback(len: usize) -> Option<String> {
+    let left = NAME_MAX.saturating_sub(len);
+    if left == 0 {
+        Some(format!("name limit reached ({} bytes)", NAME_MAX))
+    } else if left <= NAME_WARN_BYTES {
+        Some(format!("{} of {} bytes left", left, NAME_MAX))
+    } else {
+        None
+    }
+}
+
+/// Spots paste bursts in the key events of a one-line input.
+#[derive(Debug, Default)]
+pub struct PasteGuard {
+    last_key: Option<Instant>,
+    /// Keys in the current burst.
+    burst: usize,
+    /// Enters swallowed from the current burst.
+    line_breaks: usize,
+    /// An Enter was swallowed and the next character should be preceded by
+    /// a space, as [`sanitize`] would.
+    pending_space: bool,
+}
+
+impl PasteGuard {
+    fn in_burst(&self, now: Instant) -> bool {
+        self.last_key
+            .is_some_and(|last| now.saturating_duration_since(last) < BURST_GAP)
+    }
+
+    /// Note a typed character at `now`. Returns whether a space should go
+    /// in before it, standing for a swallowed line break.
+    pub fn note_char(&mut self, now: Instant) -> bool {
+        if !self.in_burst(now) {
+            *self = Self::default();
+        }
+        self.last_key = Some(now);
+        self.burst += 1;
+        std::mem::take(&mut self.pending_space)
+    }
+
+    /// Whether an Enter at `now` belongs to a paste burst and must not
+    /// confirm. A swallowed Enter counts as a line break of the burst.
+    pub fn swallow_enter(&mut self, now: Instant) -> bool {
+        if self.burst >= MIN_BURST && self.in_burst(now) {
+            self.last_key = Some(now);
+            self.line_breaks += 1;
+            self.pending_space = true;
+            true
+        } else {
+            *self = Self::default();
+            false
+        }
+    }
+
+    /// Line breaks swallowed from the current burst.
+    pub fn line_breaks(&self) -> usize {
+        self.line_breaks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitize_flattens_lines_and_drops_controls() {
+        let s = sanitize("notes\r\nfinal\x07 draft\n\n");
+        assert_eq!(s.text, "notes final draft");
+        assert_eq!(s.line_breaks, 3);
+        assert_eq!(s.controls, 1);
+        assert_eq!(
+            s.warning().unwrap(),
+            "pasted text contained 3 line breaks and 1 control character — removed"
+        );
+
+        let plain = sanitize("a\tb");
+        assert_eq!(plain.text, "a b");
+        assert_eq!(plain.warning(), None);
+        assert_eq!(sanitize("\nx").text, "x");
+    }
+
+    #[test]
+    fn fit_cuts_on_char_boundary() {
+        assert_eq!(fit("abc", 5), "abc");
+        assert_eq!(fit("abc", 2), "ab");
+        assert_eq!(fit("aé", 2), "a");
+        assert_eq!(fit("aé", 0), "");
+    }
+
+    #[test]
+    fn name_feedback_counts_down() {
+        assert_eq!(name_feedback(10), None);
+        assert_eq!(name_feedback(NAME_MAX - 5).unwrap(), "5 of 255 bytes left");
+        assert_eq!(
+            name_feedback(NAME_MAX).unwrap(),
+            "name limit reached (255 bytes)"
+        );
+    }
+
+    #[test]
+    fn enter_inside_a_burst_is_swallowed() {
+        let start = Instant::now();
+        let ms = |n| start + Duration::from_millis(n);
+        let mut guard = PasteGuard::default();
+        assert!(!guard.note_char(ms(0)));
+        assert!(!guard.note_char(ms(1)));
+        assert!(guard.swallow_enter(ms(2)));
+        assert_eq!(guard.line_breaks(), 1);
+        // The next pasted character stands in for the line break
+        assert!(guard.note_char(ms(3)));
+        assert!(!guard.note_char(ms(4)));
+
+        // A deliberate Enter after a pause confirms
+        assert!(!guard.swallow_enter(ms(200)));
+        assert_eq!(guard.line_breaks(), 0);
+    }
+
+    #[test]
+    fn typed_keys_are_not_a_burst() {
+        let start = Instant::now();
+        let mut guard = PasteGuard::default();
+        guard.note_char(start);
+        guard.note_char(start + Duration::from_millis(80));
+        assert!(!guard.swallow_enter(start + Duration::from_millis(160)));
+        // One fast key isn't enough either
+        guard.note_char(start + Duration::from_millis(300));
+        assert!(!guard.swallow_enter(start + Duration::from_millis(301)));
+    }
+}
@@ -0,0 +1,181 @@
+//! How paths are shown: relative to the tree root, absolute, or with the
+//! home directory as `~`.
+//!
+//! `general.path_display` picks the starting [`PathStyle`] and Alt+A
+//! cycles it. The status bar, the fuzzy-finder results, the search action
+//! header and its copy-path actions all render through [`show`] (or
+//! [`prefix`], for results already relative to the root), so they switch
+//! together. Messages naming paths inside the tree (undo conflicts,
+//! unwatched subtrees, content search matches) always use [`relative`].
+
+use std::path::{Path, PathBuf};
+
+/// A way of showing paths.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathStyle {
+    /// Relative to the tree root, default.
+    Relative,
+    Absolute,
+    /// Absolute, with the home directory shown as `~`.
+    HomeTilde,
+}
+
+impl PathStyle {
+    /// Parse `general.path_display`; unknown values read as relative.
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "absolute" => PathStyle::Absolute,
+            "home-tilde" => PathStyle::HomeTilde,
+            _ => PathStyle::Relative,
+        }
+    }
+
+    /// Get the display label for the style.
+    pub fn label(&self) -> &'static str {
+        match self {
+            PathStyle::Relative => "Relative",
+            PathStyle::Absolute => "Absolute",
+            PathStyle::HomeTilde => "~/",
+        }
+    }
+
+    /// Cycle to the next style.
+    pub fn next(&self) -> Self {
+        match self {
+            PathStyle::Relative => PathStyle::Absolute,
+            PathStyle::Absolute => PathStyle::HomeTilde,
+            PathStyle::HomeTilde => PathStyle::Relative,
+        }
+    }
+}
+
+/// `path` relative to `root`: `.` for the root itself, and unchanged when
+/// outside it.
+pub fn relative(path: &Path, root: &Path) -> String {
+    match path.strip_prefix(root) {
+        Ok(rest) if rest.as_os_str().is_empty() => ".".to_string(),
+        Ok(rest) => rest.display().to_string(),
+        Err(_) => path.display().to_string(),
+    }
+}
+
+/// `path` with the first of `homes` it lies under shown as `~`.
+pub fn home_relative(path: &Path, homes: &[PathBuf]) -> String {
+    match homes.iter().find_map(|home| path.strip_prefix(home).ok()) {
+        Some(rest) if rest.as_os_str().is_empty() => "~".to_string(),
+        Some(rest) => format!("~/{}", rest.display()),
+        None => path.display().to_string(),
+    }
+}
+
+/// The home directory, followed by where it resolves to when that differs
+/// (a symlinked home), so canonical paths under it get a `~` too.
+pub fn homes() -> Vec<PathBuf> {
+    let Some(home) = dirs::home_dir() else {
+        return Vec::new();
+    };
+    let resolved = home.canonicalize().ok().filter(|r| *r != home);
+    std::iter::once(home).chain(resolved).collect()
+}
+
+/// `path` in `style`.
+pub fn show(path: &Path, style: PathStyle, root: &Path, homes: &[PathBuf]) -> String {
+    match style {
+        PathStyle::Relative => relative(path, root),
+        PathStyle::Absolute => path.display().to_string(),
+        PathStyle::HomeTilde => home_relative(path, homes),
+    }
+}
+
+/// What [`show`] puts before a path already relative to `root`: nothing
+/// for [`PathStyle::Relative`], otherwise the root and a separator.
+pub fn prefix(style: PathStyle, root: &Path, homes: &[PathBuf]) -> String {
+    if style == PathStyle::Relative {
+        return String::new();
+    }
+    let shown = show(root, style, root, homes);
+    if shown.ends_with(std::path::MAIN_SEPARATOR) {
+        shown
+    } else {
+        format!("{}{}", shown, std::path::MAIN_SEPARATOR)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STYLES: [PathStyle; 3] = [
+        PathStyle::Relative,
+        PathStyle::Absolute,
+        PathStyle::HomeTilde,
+    ];
+
+    #[test]
+    fn paths_under_the_root() {
+        let root = Path::new("/home/me/src");
+        let homes = [PathBuf::from("/home/me")];
+        let path = Path::new("/home/me/src/fm/main.rs");
+        let shown: Vec<String> = STYLES
+            .iter()
+            .map(|&style| show(path, style, root, &homes))
+            .collect();
+        assert_eq!(
+            shown,
+            ["fm/main.rs", "/home/me/src/fm/main.rs", "~/src/fm/main.rs"]
+        );
+        for style in STYLES {
+            let joined = format!("{}fm/main.rs", prefix(style, root, &homes));
+            assert_eq!(joined, show(path, style, root, &homes));
+        }
+    }
+
+    #[test]
+    fn the_root_itself() {
+        let root = Path::new("/home/me");
+        let homes = [PathBuf::from("/home/me")];
+        assert_eq!(show(root, PathStyle::Relative, root, &homes), ".");
+        assert_eq!(show(root, PathStyle::Absolute, root, &homes), "/home/me");
+        assert_eq!(show(root, PathStyle::HomeTilde, root, &homes), "~");
+        assert_eq!(prefix(PathStyle::HomeTilde, root, &homes), "~/");
+        assert_eq!(prefix(PathStyle::Absolute, Path::new("/"), &homes), "/");
+    }
+
+    #[test]
+    fn paths_outside_the_root_stay_whole() {
+        let root = Path::new("/home/me/src");
+        let homes = [PathBuf::from("/home/me")];
+        let rel = |p: &str| relative(Path::new(p), root);
+        assert_eq!(rel("/etc/hosts"), "/etc/hosts");
+        // A sibling sharing the root's prefix is outside it
+        assert_eq!(rel("/home/me/srcs/a"), "/home/me/srcs/a");
+        assert_eq!(home_relative(Path::new("/home/meow"), &homes), "/home/meow");
+        assert_eq!(home_relative(Path::new("/etc"), &[]), "/etc");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinked_home_resolves_to_tilde() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let real = dir.path().join("data/me");
+        std::fs::create_dir_all(real.join("src")).unwrap();
+        let link = dir.path().join("home-me");
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+        let resolved = link.canonicalize().unwrap();
+        let homes = [link.clone(), resolved.clone()];
+
+        assert_eq!(home_relative(&link.join("src"), &homes), "~/src");
+        assert_eq!(home_relative(&resolved.join("src"), &homes), "~/src");
+        assert_eq!(home_relative(&resolved, &homes), "~");
+    }
+
+    #[test]
+    fn styles_parse_and_cycle() {
+        let names = ["relative", "absolute", "home-tilde"];
+        for (name, style) in names.into_iter().zip(STYLES) {
+            assert_eq!(PathStyle::from_str(name), style);
+            assert_eq!(style.next().next().next(), style);
+        }
+        assert_eq!(PathStyle::from_str("bogus"), PathStyle::Relative);
+    }
+}
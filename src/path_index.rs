@@ -0,0 +1,630 @@
+//! The path index behind the fuzzy finder (Ctrl+P).
+//!
+//! [`build`] lists the tree breadth first: everything one level below the
+//! root before anything two levels below, so when `general.search_max_entries`
+//! or the time limit cuts the walk short, what is left out is deep (vendored
+//! trees, build output) rather than the shallow files people look for.
+//! Directories already loaded in the tree are read from it, the rest from
+//! disk, no deeper than `tree.max_depth`.
+//!
+//! Past the cap the walk goes on counting without storing until the time
+//! limit, so [`Coverage`] can tell how much was left out and which
+//! top-level directories are incomplete. The finder shows that as a
+//! warning line, and Ctrl+R there re-indexes once with the cap raised
+//! [`RAISE_FACTOR`] times on a `TaskKind::Index` task.
+//!
+//! The index itself is built in the background as soon as the app starts
+//! and whenever the root changes: [`stream`] hands over [`BATCH_SIZE`]
+//! paths at a time, and the finder matches whatever has arrived, with an
+//! "indexing…" line until the walk is done. Watcher events then update it
+//! in place ([`apply_changes`]) rather than throwing it away. Neither walk
+//! enters the directories the watcher ignores (`.git`, `node_modules`, …),
+//! nor, with `tree.respect_gitignore`, what `.gitignore` leaves out.
+
+use std::collections::{BTreeSet, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::components::dialog::group_thousands;
+use crate::fs::gitignore::GitIgnore;
+use crate::fs::tree::{NodeType, TreeNode};
+use crate::path_display;
+
+/// Longest the index built on opening the finder takes.
+pub const TIME_LIMIT: Duration = Duration::from_millis(500);
+
+/// Longest the background index takes.
+pub const BACKGROUND_TIME_LIMIT: Duration = Duration::from_secs(30);
+
+/// Paths handed over per batch by [`stream`].
+pub const BATCH_SIZE: usize = 2_000;
+
+/// Longest a re-index with the cap raised takes.
+pub const RAISED_TIME_LIMIT: Duration = Duration::from_secs(30);
+
+/// How many times the usual cap a re-index allows.
+pub const RAISE_FACTOR: usize = 10;
+
+/// Incomplete directories named in the warning before "N more".
+const MAX_NAMED: usize = 3;
+
+/// Bounds of a walk.
+#[derive(Debug, Clone)]
+pub struct IndexOptions {
+    pub max_entries: usize,
+    /// Deepest level listed below the root.
+    pub max_depth: usize,
+    pub deadline: Instant,
+    /// Names left out, with everything under them.
+    pub ignore: Vec<String>,
+    /// `.gitignore` rules around the root, when they are respected.
+    pub gitignore: Option<Arc<GitIgnore>>,
+}
+
+/// How much of the tree an index holds.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Coverage {
+    /// The cap or the time limit left entries out.
+    pub truncated: bool,
+    /// Cap the index was built with.
+    pub cap: usize,
+    /// Entries seen, indexed or not.
+    pub seen: usize,
+    /// The count went through the whole tree, making `seen` exact rather
+    /// than a lower bound.
+    pub counted_all: bool,
+    /// Top-level directories with entries left out, relative to the root
+    /// and in name order; `.` stands for entries directly in the root.
+    pub partial: Vec<String>,
+}
+
+impl Coverage {
+    /// The finder's warning, e.g. "index truncated — 10,000 of 87,000
+    /// entries".
+    pub fn summary(&self) -> String {
+        let total = if self.counted_all {
+            group_thousands(self.seen)
+        } else {
+            format!("{}+", group_thousands(self.seen))
+        };
+        format!(
+            "index truncated — {} of {} entries",
+            group_thousands(self.cap.min(self.seen)),
+            total
+        )
+    }
+
+    /// The directories the index misses, e.g. "target/ and vendor/ not
+    /// fully indexed".
+    pub fn missing(&self) -> Option<String> {
+        let mut names: Vec<String> = self
+            .partial
+            .iter()
+            .take(MAX_NAMED)
+            .map(|name| format!("{}/", name))
+            .collect();
+        if self.partial.len() > MAX_NAMED {
+            names.push(format!("{} more", self.partial.len() - MAX_NAMED));
+        }
+        let names = match names.split_last()? {
+            (last, []) => last.clone(),
+            (last, rest) => format!("{} and {}", rest.join(", "), last),
+        };
+        Some(format!("{} not fully indexed", names))
+    }
+}
+
+/// Paths for the finder and how complete they are.
+#[derive(Debug, Clone, Default)]
+pub struct PathIndex {
+    pub paths: Vec<PathBuf>,
+    pub coverage: Coverage,
+}
+
+/// Paths found by a background index since its previous batch.
+#[derive(Debug)]
+pub struct IndexBatch {
+    pub job: u64,
+    pub root: PathBuf,
+    pub paths: Vec<PathBuf>,
+    /// Entries seen so far, indexed or not.
+    pub seen: usize,
+    /// Set on the last batch.
+    pub coverage: Option<Coverage>,
+}
+
+/// A directory waiting to be listed.
+enum Pending<'a> {
+    Loaded(&'a TreeNode),
+    Unloaded(PathBuf),
+}
+
+impl Pending<'_> {
+    fn path(&self) -> &Path {
+        match self {
+            Pending::Loaded(node) => &node.path,
+            Pending::Unloaded(path) => path,
+        }
+    }
+}
+
+/// Index the entries below `root`, reading loaded directories from `tree`
+/// when given. Returns `None` once `cancel` is raised.
+pub fn build(
+    root: &Path,
+    tree: Option<&TreeNode>,
+    options: &IndexOptions,
+    cancel: &AtomicBool,
+) -> Option<PathIndex> {
+    let mut paths = Vec::new();
+    let coverage = stream(root, tree, options, cancel, usize::MAX, &mut |batch, _| {
+        paths.extend(batch)
+    })?;
+    Some(PathIndex { paths, coverage })
+}
+
+/// Walk like [`build`], handing the paths to `emit` `batch_size` at a time
+/// along with the count of entries seen so far. Returns the coverage once
+/// done, or `None` once `cancel` is raised.
+pub fn stream(
+    root: &Path,
+    tree: Option<&TreeNode>,
+    options: &IndexOptions,
+    cancel: &AtomicBool,
+    batch_size: usize,
+    emit: &mut dyn FnMut(Vec<PathBuf>, usize),
+) -> Option<Coverage> {
+    let mut batch = Vec::new();
+    let mut indexed = 0;
+    let mut seen = 0;
+    let mut partial = BTreeSet::new();
+    let mut timed_out = false;
+    // Each directory with its depth and the top-level entry it is under
+    let first = match tree {
+        Some(node) if node.children.is_some() => Pending::Loaded(node),
+        _ => Pending::Unloaded(root.to_path_buf()),
+    };
+    // ...and the `.gitignore` rules around it
+    let mut queue = VecDeque::from([(first, 0, root.to_path_buf(), options.gitignore.clone())]);
+
+    while let Some((dir, depth, top, around)) = queue.pop_front() {
+        if cancel.load(Ordering::Relaxed) {
+            return None;
+        }
+        if Instant::now() >= options.deadline {
+            timed_out = true;
+            partial.insert(top);
+            partial.extend(queue.drain(..).map(|(_, _, top, _)| top));
+            break;
+        }
+        let inside = around.map(|rules| rules.enter(dir.path()));
+        for (path, child) in list(&dir) {
+            if is_ignored(&path, &options.ignore)
+                || inside
+                    .as_ref()
+                    .is_some_and(|rules| rules.is_ignored(&path, child.is_some()))
+            {
+                continue;
+            }
+            seen += 1;
+            let child_top = if depth == 0 {
+                path.clone()
+            } else {
+                top.clone()
+            };
+            if indexed < options.max_entries {
+                indexed += 1;
+                batch.push(path.clone());
+                if batch.len() >= batch_size {
+                    emit(std::mem::take(&mut batch), seen);
+                }
+            } else {
+                // A root file's top is itself; report the root for it
+                partial.insert(if depth == 0 {
+                    root.to_path_buf()
+                } else {
+                    top.clone()
+                });
+            }
+            if depth + 1 < options.max_depth {
+                if let Some(next) = child {
+                    queue.push_back((next, depth + 1, child_top, inside.clone()));
+                }
+            }
+        }
+    }
+    emit(batch, seen);
+
+    let truncated = timed_out || seen > indexed;
+    Some(Coverage {
+        truncated,
+        cap: options.max_entries,
+        seen,
+        counted_all: !timed_out,
+        partial: if truncated {
+            partial
+                .iter()
+                .map(|top| path_display::relative(top, root))
+                .collect()
+        } else {
+            Vec::new()
+        },
+    })
+}
+
+/// Whether `path` is one of the names a walk leaves out.
+fn is_ignored(path: &Path, ignore: &[String]) -> bool {
+    path.file_name()
+        .is_some_and(|name| ignore.iter().any(|pattern| name == pattern.as_str()))
+}
+
+/// Bring an index of `root` up to date with watcher events for `changed`:
+/// a path that is gone is dropped along with everything under it, and one
+/// that appeared is added, unless it is too deep, ignored or over the cap.
+/// `gitignore` holds the `.gitignore` rules around `root`, when respected.
+pub fn apply_changes(
+    paths: &mut Vec<PathBuf>,
+    coverage: &mut Coverage,
+    root: &Path,
+    changed: &[PathBuf],
+    max_depth: usize,
+    ignore: &[String],
+    gitignore: Option<&Arc<GitIgnore>>,
+) {
+    let inside = gitignore.map(|rules| rules.enter(root));
+    for path in changed {
+        let Ok(relative) = path.strip_prefix(root) else {
+            continue;
+        };
+        if relative.as_os_str().is_empty() {
+            continue;
+        }
+        if fs::symlink_metadata(path).is_err() {
+            paths.retain(|indexed| !indexed.starts_with(path));
+            continue;
+        }
+        let under_ignored = relative.components().any(|part| {
+            ignore
+                .iter()
+                .any(|pattern| part.as_os_str() == pattern.as_str())
+        });
+        let under_ignored = under_ignored
+            || inside
+                .as_ref()
+                .is_some_and(|rules| rules.is_ignored_below(root, path));
+        if under_ignored || relative.components().count() > max_depth || paths.contains(path) {
+            continue;
+        }
+        if paths.len() >= coverage.cap {
+            coverage.seen += 1;
+            coverage.truncated = true;
+            continue;
+        }
+        paths.push(path.clone());
+    }
+}
+
+/// Entries of `dir`, each with the directory to list next when it is one.
+fn list<'a>(dir: &Pending<'a>) -> Vec<(PathBuf, Option<Pending<'a>>)> {
+    match dir {
+        Pending::Loaded(node) => node
+            .children
+            .iter()
+            .flatten()
+            .map(|child| {
+                let next = match (&child.children, &child.node_type) {
+                    (Some(_), _) => Some(Pending::Loaded(child)),
+                    (None, NodeType::Directory) => Some(Pending::Unloaded(child.path.clone())),
+                    _ => None,
+                };
+                (child.path.clone(), next)
+            })
+            .collect(),
+        Pending::Unloaded(path) => match fs::read_dir(path) {
+            Ok(entries) => entries
+                .flatten()
+                .map(|entry| {
+                    // `file_type` doesn't follow links, so linked trees aren't walked twice
+                    let is_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+                    let path = entry.path();
+                    let next = is_dir.then(|| Pending::Unloaded(path.clone()));
+                    (path, next)
+                })
+                .collect(),
+            Err(_) => Vec::new(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::tree::TreeState;
+    use tempfile::TempDir;
+
+    fn options(max_entries: usize) -> IndexOptions {
+        IndexOptions {
+            max_entries,
+            max_depth: 128,
+            deadline: Instant::now() + Duration::from_secs(60),
+            ignore: Vec::new(),
+            gitignore: None,
+        }
+    }
+
+    /// Shallow files next to a deep tree holding far more entries.
+    fn monorepo() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("README.md"), "").unwrap();
+        fs::create_dir(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("src/main.rs"), "").unwrap();
+        let deep = dir.path().join("vendor/a/b/c");
+        fs::create_dir_all(&deep).unwrap();
+        for i in 0..50 {
+            fs::write(deep.join(format!("dep{}.rs", i)), "").unwrap();
+        }
+        dir
+    }
+
+    fn index(root: &Path, max_entries: usize) -> PathIndex {
+        build(root, None, &options(max_entries), &AtomicBool::new(false)).unwrap()
+    }
+
+    #[test]
+    fn shallow_files_are_indexed_before_deep_trees() {
+        let dir = monorepo();
+        let root = dir.path();
+        // README.md, src, vendor, src/main.rs, vendor/a fill the cap
+        let index = index(root, 5);
+        for shallow in ["README.md", "src", "src/main.rs", "vendor", "vendor/a"] {
+            assert!(index.paths.contains(&root.join(shallow)), "{}", shallow);
+        }
+        assert_eq!(index.paths.len(), 5);
+        assert!(!index
+            .paths
+            .iter()
+            .any(|p| p.starts_with(root.join("vendor/a/b"))));
+    }
+
+    #[test]
+    fn truncation_records_what_was_left_out() {
+        let dir = monorepo();
+        let root = dir.path();
+        let index = index(root, 5);
+        let coverage = index.coverage;
+        assert!(coverage.truncated);
+        assert!(coverage.counted_all);
+        // 3 at the top, src/main.rs, vendor/a, a/b, a/b/c and its 50 files
+        assert_eq!(coverage.seen, 3 + 1 + 3 + 50);
+        assert_eq!(coverage.cap, 5);
+        assert_eq!(coverage.partial, ["vendor"]);
+        assert_eq!(coverage.summary(), "index truncated — 5 of 57 entries");
+        assert_eq!(coverage.missing().unwrap(), "vendor/ not fully indexed");
+
+        let whole = super::tests::index(root, 1000).coverage;
+        assert!(!whole.truncated);
+        assert!(whole.partial.is_empty());
+        assert_eq!(whole.seen, coverage.seen);
+    }
+
+    #[test]
+    fn the_time_limit_leaves_the_count_open() {
+        let dir = monorepo();
+        let late = IndexOptions {
+            deadline: Instant::now(),
+            ..options(1000)
+        };
+        let index = build(dir.path(), None, &late, &AtomicBool::new(false)).unwrap();
+        assert!(index.coverage.truncated);
+        assert!(!index.coverage.counted_all);
+        assert_eq!(index.coverage.partial, ["."]);
+        assert_eq!(
+            index.coverage.summary(),
+            "index truncated — 0 of 0+ entries"
+        );
+
+        let cancel = AtomicBool::new(true);
+        assert!(build(dir.path(), None, &options(1000), &cancel).is_none());
+    }
+
+    #[test]
+    fn the_warning_names_a_few_directories() {
+        let coverage = Coverage {
+            truncated: true,
+            cap: 10_000,
+            seen: 87_000,
+            counted_all: true,
+            partial: ["a", "b", "c", "d", "e"].map(String::from).to_vec(),
+        };
+        assert_eq!(
+            coverage.summary(),
+            "index truncated — 10,000 of 87,000 entries"
+        );
+        assert_eq!(
+            coverage.missing().unwrap(),
+            "a/, b/, c/ and 2 more not fully indexed"
+        );
+        let two = Coverage {
+            partial: vec!["target".into(), "vendor".into()],
+            ..coverage
+        };
+        assert_eq!(
+            two.missing().unwrap(),
+            "target/ and vendor/ not fully indexed"
+        );
+        let none = Coverage {
+            partial: Vec::new(),
+            ..two
+        };
+        assert_eq!(none.missing(), None);
+    }
+
+    #[test]
+    fn raising_the_cap_covers_the_deep_tree() {
+        let dir = monorepo();
+        let capped = index(dir.path(), 5);
+        let raised = index(dir.path(), 5 * RAISE_FACTOR * 2);
+        assert!(!raised.coverage.truncated);
+        assert_eq!(raised.paths.len(), capped.coverage.seen);
+        assert!(raised
+            .paths
+            .contains(&dir.path().join("vendor/a/b/c/dep49.rs")));
+    }
+
+    #[test]
+    fn loaded_directories_come_from_the_tree() {
+        let dir = monorepo();
+        let state = TreeState::new(dir.path()).unwrap();
+        // Created after loading: the loaded root doesn't list it
+        fs::write(dir.path().join("late.txt"), "").unwrap();
+        let index = build(
+            dir.path(),
+            Some(&state.root),
+            &options(1000),
+            &AtomicBool::new(false),
+        )
+        .unwrap();
+        assert!(!index.paths.contains(&dir.path().join("late.txt")));
+        // Unloaded directories are read from disk
+        assert!(index.paths.contains(&dir.path().join("src/main.rs")));
+
+        let shallow = IndexOptions {
+            max_depth: 1,
+            ..options(1000)
+        };
+        let top = build(dir.path(), None, &shallow, &AtomicBool::new(false)).unwrap();
+        assert_eq!(top.paths.len(), 4);
+    }
+
+    #[test]
+    fn streaming_hands_over_batches_and_skips_ignored_trees() {
+        let dir = monorepo();
+        fs::create_dir_all(dir.path().join("node_modules/pkg")).unwrap();
+        fs::write(dir.path().join("node_modules/pkg/index.js"), "").unwrap();
+        let options = IndexOptions {
+            ignore: vec!["node_modules".to_string()],
+            ..options(1000)
+        };
+        let mut batches = Vec::new();
+        let coverage = stream(
+            dir.path(),
+            None,
+            &options,
+            &AtomicBool::new(false),
+            10,
+            &mut |batch, seen| batches.push((batch.len(), seen)),
+        )
+        .unwrap();
+        // 57 entries: five full batches and the rest
+        assert_eq!(batches.len(), 6);
+        assert!(batches[..5].iter().all(|(len, _)| *len == 10));
+        assert_eq!(batches[5], (7, 57));
+        assert_eq!(coverage.seen, 57);
+        assert!(!coverage.truncated);
+    }
+
+    #[test]
+    fn changes_are_applied_in_place() {
+        let dir = monorepo();
+        let root = dir.path();
+        let PathIndex {
+            mut paths,
+            mut coverage,
+        } = index(root, 1000);
+        let ignore = vec!["node_modules".to_string()];
+
+        fs::write(root.join("src/lib.rs"), "").unwrap();
+        fs::create_dir(root.join("node_modules")).unwrap();
+        fs::remove_dir_all(root.join("vendor")).unwrap();
+        let changed = [
+            root.join("src/lib.rs"),
+            root.join("node_modules"),
+            root.join("vendor"),
+            root.to_path_buf(),
+        ];
+        apply_changes(
+            &mut paths,
+            &mut coverage,
+            root,
+            &changed,
+            128,
+            &ignore,
+            None,
+        );
+        assert!(paths.contains(&root.join("src/lib.rs")));
+        assert!(!paths.contains(&root.join("node_modules")));
+        assert!(!paths.iter().any(|p| p.starts_with(root.join("vendor"))));
+        assert_eq!(paths.len(), 4);
+
+        // Applying the same change twice doesn't list it twice
+        apply_changes(
+            &mut paths,
+            &mut coverage,
+            root,
+            &changed,
+            128,
+            &ignore,
+            None,
+        );
+        assert_eq!(paths.len(), 4);
+
+        // Over the cap the new path is counted, not listed
+        coverage.cap = 4;
+        fs::write(root.join("late.txt"), "").unwrap();
+        apply_changes(
+            &mut paths,
+            &mut coverage,
+            root,
+            &[root.join("late.txt")],
+            128,
+            &ignore,
+            None,
+        );
+        assert_eq!(paths.len(), 4);
+        assert!(coverage.truncated);
+    }
+
+    #[test]
+    fn gitignored_entries_are_left_out() {
+        let dir = monorepo();
+        let root = dir.path();
+        fs::create_dir(root.join(".git")).unwrap();
+        fs::write(root.join(".gitignore"), "vendor/\n*.log\n").unwrap();
+        fs::write(root.join("debug.log"), "").unwrap();
+        let options = IndexOptions {
+            gitignore: Some(GitIgnore::around(root)),
+            ..options(1000)
+        };
+        let index = build(root, None, &options, &AtomicBool::new(false)).unwrap();
+        assert!(index.paths.contains(&root.join("src/main.rs")));
+        assert!(index.paths.contains(&root.join(".gitignore")));
+        assert!(!index.paths.contains(&root.join(".git")));
+        assert!(!index.paths.contains(&root.join("debug.log")));
+        assert!(!index
+            .paths
+            .iter()
+            .any(|p| p.starts_with(root.join("vendor"))));
+
+        let PathIndex {
+            mut paths,
+            mut coverage,
+        } = index;
+        fs::write(root.join("src/trace.log"), "").unwrap();
+        fs::write(root.join("src/lib.rs"), "").unwrap();
+        let changed = [root.join("src/trace.log"), root.join("src/lib.rs")];
+        apply_changes(
+            &mut paths,
+            &mut coverage,
+            root,
+            &changed,
+            128,
+            &[],
+            options.gitignore.as_ref(),
+        );
+        assert!(paths.contains(&root.join("src/lib.rs")));
+        assert!(!paths.contains(&root.join("src/trace.log")));
+    }
+}
@@ -0,0 +1,494 @@
+//! Patterns for the content search: a plain substring or a small regex.
+//!
+//! Both are smart-case: a pattern without upper-case letters matches
+//! either case. The regex dialect is a backtracking subset of the usual
+//! one, enough for searching code: literals, `.`, classes (`[a-z_]`,
+//! `[^0-9]`), the escapes `\d \w \s` (and `\D \W \S`), word boundaries
+//! `\b`, anchors `^` and `$`, groups with alternation `(a|b)`, and the
+//! greedy quantifiers `* + ?`, `{n}`, `{n,}` and `{n,m}`. A regex looks at
+//! the first [`MAX_REGEX_CHARS`] characters of a line and gives up on a
+//! line after [`MAX_STEPS`] steps, which bounds the backtracking.
+
+use std::cell::Cell;
+use std::ops::Range;
+
+/// Characters of a line a regex looks at.
+pub const MAX_REGEX_CHARS: usize = 1024;
+
+/// Backtracking steps a regex may take on one line.
+pub const MAX_STEPS: usize = 100_000;
+
+/// Most repetitions a `{n,m}` quantifier may ask for.
+const MAX_REPEAT: usize = 1000;
+
+/// A compiled search pattern.
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    Plain { needle: Vec<char>, fold: bool },
+    Regex { nodes: Vec<Node>, fold: bool },
+}
+
+#[derive(Debug, Clone)]
+pub enum Node {
+    Char(char),
+    Any,
+    Class(Class),
+    Start,
+    End,
+    /// `\b` (true) or `\B` (false).
+    WordBoundary(bool),
+    /// Alternatives, tried in order.
+    Group(Vec<Vec<Node>>),
+    Repeat {
+        node: Box<Node>,
+        min: usize,
+        max: Option<usize>,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct Class {
+    negated: bool,
+    items: Vec<ClassItem>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ClassItem {
+    Range(char, char),
+    /// A `\d`, `\w` or `\s` shorthand; false for its negated form.
+    Digit(bool),
+    Word(bool),
+    Space(bool),
+}
+
+impl Pattern {
+    /// Compile `query` as a substring, or as a regex when `regex` is set.
+    pub fn new(query: &str, regex: bool) -> Result<Self, String> {
+        let fold = !query.chars().any(char::is_uppercase);
+        if !regex {
+            return Ok(Pattern::Plain {
+                needle: query.chars().map(|c| fold_case(c, fold)).collect(),
+                fold,
+            });
+        }
+        let mut parser = Parser {
+            chars: query.chars().collect(),
+            pos: 0,
+        };
+        let branches = parser.alternation()?;
+        if parser.pos < parser.chars.len() {
+            return Err("unmatched )".to_string());
+        }
+        Ok(Pattern::Regex {
+            nodes: vec![Node::Group(branches)],
+            fold,
+        })
+    }
+
+    /// Byte range of the leftmost match in `line`.
+    pub fn find(&self, line: &str) -> Option<Range<usize>> {
+        match self {
+            Pattern::Plain {
+                needle,
+                fold: false,
+            } => {
+                let needle: String = needle.iter().collect();
+                line.find(&needle).map(|start| start..start + needle.len())
+            }
+            Pattern::Plain { needle, fold: true } => find_folded(line, needle),
+            Pattern::Regex { nodes, fold } => {
+                let mut offsets: Vec<usize> = Vec::new();
+                let mut text: Vec<char> = Vec::new();
+                for (i, c) in line.char_indices().take(MAX_REGEX_CHARS) {
+                    offsets.push(i);
+                    text.push(c);
+                }
+                offsets.push(offsets.last().map_or(0, |&i| {
+                    i + line[i..].chars().next().map_or(0, char::len_utf8)
+                }));
+                let matcher = Matcher {
+                    text: &text,
+                    fold: *fold,
+                    steps: Cell::new(0),
+                };
+                (0..=text.len()).find_map(|start| {
+                    let mut end = None;
+                    matcher
+                        .seq(nodes, start, &mut |p| {
+                            end = Some(p);
+                            true
+                        })
+                        .then(|| offsets[start]..offsets[end.unwrap_or(start)])
+                })
+            }
+        }
+    }
+}
+
+fn fold_case(c: char, fold: bool) -> char {
+    if fold {
+        c.to_lowercase().next().unwrap_or(c)
+    } else {
+        c
+    }
+}
+
+fn find_folded(line: &str, needle: &[char]) -> Option<Range<usize>> {
+    let chars: Vec<(usize, char)> = line.char_indices().collect();
+    if needle.is_empty() {
+        return Some(0..0);
+    }
+    (0..chars.len().saturating_sub(needle.len() - 1)).find_map(|start| {
+        let window = &chars[start..start + needle.len()];
+        window
+            .iter()
+            .zip(needle)
+            .all(|(&(_, c), &n)| fold_case(c, true) == n)
+            .then(|| {
+                let (last, c) = window[window.len() - 1];
+                window[0].0..last + c.len_utf8()
+            })
+    })
+}
+
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        let found = self.peek() == Some(c);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    /// Branches separated by `|`, up to a `)` or the end.
+    fn alternation(&mut self) -> Result<Vec<Vec<Node>>, String> {
+        let mut branches = vec![self.sequence()?];
+        while self.eat('|') {
+            branches.push(self.sequence()?);
+        }
+        Ok(branches)
+    }
+
+    fn sequence(&mut self) -> Result<Vec<Node>, String> {
+        let mut nodes = Vec::new();
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            let atom = self.atom()?;
+            nodes.push(self.quantified(atom)?);
+        }
+        Ok(nodes)
+    }
+
+    fn atom(&mut self) -> Result<Node, String> {
+        let c = self.peek().ok_or("unexpected end")?;
+        self.pos += 1;
+        Ok(match c {
+            '(' => {
+                // Groups don't capture, so `(?:` is the same thing
+                if self.chars[self.pos..].starts_with(&['?', ':']) {
+                    self.pos += 2;
+                }
+                let branches = self.alternation()?;
+                if !self.eat(')') {
+                    return Err("unclosed (".to_string());
+                }
+                Node::Group(branches)
+            }
+            '[' => Node::Class(self.class()?),
+            '.' => Node::Any,
+            '^' => Node::Start,
+            '$' => Node::End,
+            '*' | '+' | '?' => return Err(format!("{} has nothing to repeat", c)),
+            '\\' => self.escape()?,
+            c => Node::Char(c),
+        })
+    }
+
+    fn escape(&mut self) -> Result<Node, String> {
+        let c = self.peek().ok_or("trailing \\")?;
+        self.pos += 1;
+        let shorthand = |item| {
+            Node::Class(Class {
+                negated: false,
+                items: vec![item],
+            })
+        };
+        Ok(match c {
+            'b' => Node::WordBoundary(true),
+            'B' => Node::WordBoundary(false),
+            'd' => shorthand(ClassItem::Digit(true)),
+            'D' => shorthand(ClassItem::Digit(false)),
+            'w' => shorthand(ClassItem::Word(true)),
+            'W' => shorthand(ClassItem::Word(false)),
+            's' => shorthand(ClassItem::Space(true)),
+            'S' => shorthand(ClassItem::Space(false)),
+            't' => Node::Char('\t'),
+            c => Node::Char(c),
+        })
+    }
+
+    /// A class after its `[`.
+    fn class(&mut self) -> Result<Class, String> {
+        let negated = self.eat('^');
+        let mut items = Vec::new();
+        let mut first = true;
+        loop {
+            let c = self.peek().ok_or("unclosed [")?;
+            self.pos += 1;
+            if c == ']' && !first {
+                break;
+            }
+            first = false;
+            let low = if c == '\\' {
+                let e = self.peek().ok_or("unclosed [")?;
+                self.pos += 1;
+                match e {
+                    'd' | 'D' => {
+                        items.push(ClassItem::Digit(e == 'd'));
+                        continue;
+                    }
+                    'w' | 'W' => {
+                        items.push(ClassItem::Word(e == 'w'));
+                        continue;
+                    }
+                    's' | 'S' => {
+                        items.push(ClassItem::Space(e == 's'));
+                        continue;
+                    }
+                    't' => '\t',
+                    e => e,
+                }
+            } else {
+                c
+            };
+            let is_range =
+                self.peek() == Some('-') && self.chars.get(self.pos + 1).is_some_and(|&n| n != ']');
+            if is_range {
+                let high = self.chars[self.pos + 1];
+                self.pos += 2;
+                if high < low {
+                    return Err(format!("bad range {}-{}", low, high));
+                }
+                items.push(ClassItem::Range(low, high));
+            } else {
+                items.push(ClassItem::Range(low, low));
+            }
+        }
+        Ok(Class { negated, items })
+    }
+
+    fn quantified(&mut self, atom: Node) -> Result<Node, String> {
+        let mut node = atom;
+        loop {
+            let (min, max) = match self.peek() {
+                Some('{') => match self.braces() {
+                    Some(bounds) => bounds,
+                    // Not a quantifier: a literal `{`
+                    None => return Ok(node),
+                },
+                Some(c @ ('*' | '+' | '?')) => {
+                    self.pos += 1;
+                    match c {
+                        '*' => (0, None),
+                        '+' => (1, None),
+                        _ => (0, Some(1)),
+                    }
+                }
+                _ => return Ok(node),
+            };
+            if matches!(node, Node::Start | Node::End | Node::WordBoundary(_)) {
+                return Err("nothing to repeat".to_string());
+            }
+            if min > MAX_REPEAT || max.is_some_and(|m| m > MAX_REPEAT || m < min) {
+                return Err("bad repeat count".to_string());
+            }
+            node = Node::Repeat {
+                node: Box::new(node),
+                min,
+                max,
+            };
+        }
+    }
+
+    /// `{n}`, `{n,}` or `{n,m}` at the cursor, consumed; `None`, consuming
+    /// nothing, for anything else.
+    fn braces(&mut self) -> Option<(usize, Option<usize>)> {
+        let rest: String = self.chars[self.pos + 1..].iter().collect();
+        let close = rest.find('}')?;
+        let inner = &rest[..close];
+        let number = |s: &str| {
+            (!s.is_empty() && s.bytes().all(|b| b.is_ascii_digit()))
+                .then(|| s.parse::<usize>().ok())
+                .flatten()
+        };
+        let bounds = match inner.split_once(',') {
+            None => {
+                let n = number(inner)?;
+                (n, Some(n))
+            }
+            Some((low, "")) => (number(low)?, None),
+            Some((low, high)) => (number(low)?, Some(number(high)?)),
+        };
+        self.pos += inner.chars().count() + 2;
+        Some(bounds)
+    }
+}
+
+impl Class {
+    fn matches(&self, c: char, fold: bool) -> bool {
+        let hit = |c: char| {
+            self.items.iter().any(|item| match *item {
+                ClassItem::Range(low, high) => (low..=high).contains(&c),
+                ClassItem::Digit(want) => c.is_ascii_digit() == want,
+                ClassItem::Word(want) => is_word(c) == want,
+                ClassItem::Space(want) => c.is_whitespace() == want,
+            })
+        };
+        let found = hit(c)
+            || (fold
+                && (c.to_lowercase().any(|l| l != c && hit(l))
+                    || c.to_uppercase().any(|u| u != c && hit(u))));
+        found != self.negated
+    }
+}
+
+/// Backtracking over one line.
+struct Matcher<'t> {
+    text: &'t [char],
+    fold: bool,
+    steps: Cell<usize>,
+}
+
+impl Matcher<'_> {
+    /// Whether `nodes` match at `pos` with `next` accepting where they end.
+    fn seq(&self, nodes: &[Node], pos: usize, next: &mut dyn FnMut(usize) -> bool) -> bool {
+        self.steps.set(self.steps.get() + 1);
+        if self.steps.get() > MAX_STEPS {
+            return false;
+        }
+        let Some((node, rest)) = nodes.split_first() else {
+            return next(pos);
+        };
+        let at = self.text.get(pos).copied();
+        match node {
+            Node::Char(c) => {
+                at.is_some_and(|t| fold_case(t, self.fold) == fold_case(*c, self.fold))
+                    && self.seq(rest, pos + 1, next)
+            }
+            Node::Any => at.is_some() && self.seq(rest, pos + 1, next),
+            Node::Class(class) => {
+                at.is_some_and(|t| class.matches(t, self.fold)) && self.seq(rest, pos + 1, next)
+            }
+            Node::Start => pos == 0 && self.seq(rest, pos, next),
+            Node::End => pos == self.text.len() && self.seq(rest, pos, next),
+            Node::WordBoundary(want) => {
+                let before = pos > 0 && is_word(self.text[pos - 1]);
+                let after = at.is_some_and(is_word);
+                (before != after) == *want && self.seq(rest, pos, next)
+            }
+            Node::Group(branches) => branches
+                .iter()
+                .any(|branch| self.seq(branch, pos, &mut |p| self.seq(rest, p, next))),
+            Node::Repeat { node, min, max } => self.repeat(node, *min, *max, rest, pos, 0, next),
+        }
+    }
+
+    /// Greedy repetition: as many more `node`s as fit, then `rest`.
+    #[allow(clippy::too_many_arguments)]
+    fn repeat(
+        &self,
+        node: &Node,
+        min: usize,
+        max: Option<usize>,
+        rest: &[Node],
+        pos: usize,
+        count: usize,
+        next: &mut dyn FnMut(usize) -> bool,
+    ) -> bool {
+        if max.is_none_or(|m| count < m) {
+            let more = self.seq(std::slice::from_ref(node), pos, &mut |p| {
+                if p == pos {
+                    // An empty match could repeat forever: count it as
+                    // all the repeats still needed
+                    self.seq(rest, p, next)
+                } else {
+                    self.repeat(node, min, max, rest, p, count + 1, next)
+                }
+            });
+            if more {
+                return true;
+            }
+        }
+        count >= min && self.seq(rest, pos, next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find(query: &str, regex: bool, line: &str) -> Option<String> {
+        let pattern = Pattern::new(query, regex).unwrap();
+        pattern.find(line).map(|r| line[r].to_string())
+    }
+
+    #[test]
+    fn plain_patterns_are_smart_case() {
+        assert_eq!(find("todo", false, "// TODO: fix"), Some("TODO".into()));
+        assert_eq!(find("Todo", false, "// TODO: fix"), None);
+        assert_eq!(find("Todo", false, "a Todo b"), Some("Todo".into()));
+        assert_eq!(find("ñu", false, "El ÑU"), Some("ÑU".into()));
+        assert_eq!(find("a.b", false, "axb a.b"), Some("a.b".into()));
+    }
+
+    #[test]
+    fn regex_basics() {
+        assert_eq!(
+            find(r"fn \w+", true, "pub fn main() {"),
+            Some("fn main".into())
+        );
+        assert_eq!(find(r"\d{2,3}", true, "v1 v12345"), Some("123".into()));
+        assert_eq!(find(r"^use ", true, "  use x;"), None);
+        assert_eq!(find(r";$", true, "let x = 1;"), Some(";".into()));
+        assert_eq!(
+            find(r"(foo|ba[rz])+", true, "xbazfoo!"),
+            Some("bazfoo".into())
+        );
+        assert_eq!(find(r"[^a-z ]+", true, "abc 123 g"), Some("123".into()));
+        assert_eq!(find(r"\bid\b", true, "idle id"), Some("id".into()));
+        assert_eq!(find(r"colou?r", true, "Color"), Some("Color".into()));
+        assert_eq!(find(r"Colou?r", true, "color"), None);
+        assert_eq!(find(r"a{2}", true, "a{2} aa"), Some("aa".into()));
+        assert_eq!(find(r"x{,2}", true, "x{,2}"), Some("x{,2}".into()));
+        assert_eq!(find(r"(a*)*b", true, "aaab"), Some("aaab".into()));
+        assert_eq!(find(r"é.", true, "caféx"), Some("éx".into()));
+    }
+
+    #[test]
+    fn bad_regexes_are_reported() {
+        for bad in ["(a", "a)", "[a-", "*a", "a{3,1}", r"\", "[z-a]"] {
+            assert!(Pattern::new(bad, true).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn runaway_backtracking_gives_up() {
+        let line = "a".repeat(40);
+        assert_eq!(find("(a|aa)*c", true, &line), None);
+    }
+}
@@ -0,0 +1,267 @@
+//! PDF metadata and text for the preview panel (`pdf-preview` feature).
+//!
+//! [`read`] loads the document with `lopdf` and pulls out the page count,
+//! the info dictionary (title, author, subject, producer) and the text of
+//! the first [`TEXT_PAGES`] pages. Text extraction only follows the simple
+//! fonts `lopdf` can decode, so a page set in others comes out empty. An
+//! encrypted, oversized or malformed file is an error, and the preview
+//! shows the binary metadata card instead.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use lopdf::{Dictionary, Document, Object};
+use thiserror::Error;
+
+/// Pages whose text is shown.
+pub const TEXT_PAGES: u32 = 3;
+
+/// Files above this size are not parsed: the whole file is read into memory.
+pub const MAX_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Text lines kept per page.
+pub const MAX_PAGE_LINES: usize = 200;
+
+#[derive(Debug, Error)]
+pub enum PdfError {
+    #[error("over {} MB", MAX_BYTES / (1024 * 1024))]
+    TooLarge,
+    #[error("encrypted")]
+    Encrypted,
+    #[error("{0}")]
+    Malformed(String),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// What the preview shows of a PDF.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PdfInfo {
+    pub pages: usize,
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub subject: Option<String>,
+    pub producer: Option<String>,
+    /// Text lines of each of the first pages, by page number; empty for a
+    /// page with no text that could be decoded.
+    pub text: Vec<(u32, Vec<String>)>,
+}
+
+/// Whether `path` has a `.pdf` extension.
+pub fn is_pdf(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("pdf"))
+}
+
+/// Read the page count, info dictionary and first-page text of `path`.
+pub fn read(path: &Path) -> Result<PdfInfo, PdfError> {
+    if fs::metadata(path)?.len() > MAX_BYTES {
+        return Err(PdfError::TooLarge);
+    }
+    let doc = Document::load(path).map_err(|e| PdfError::Malformed(e.to_string()))?;
+    if doc.is_encrypted() {
+        return Err(PdfError::Encrypted);
+    }
+    let pages = doc.get_pages();
+    if pages.is_empty() {
+        return Err(PdfError::Malformed("no pages".to_string()));
+    }
+
+    let info = doc
+        .trailer
+        .get(b"Info")
+        .ok()
+        .and_then(|info| doc.dereference(info).ok())
+        .and_then(|(_, info)| info.as_dict().ok());
+    let field = |key: &[u8]| info.and_then(|info| info_string(info, key));
+
+    let text = pages
+        .keys()
+        .take(TEXT_PAGES as usize)
+        .map(|&page| {
+            // A page that fails to decode just shows no text
+            let text = doc.extract_text(&[page]).unwrap_or_default();
+            let lines = text
+                .lines()
+                .map(|line| line.trim_end().to_string())
+                .filter(|line| !line.is_empty())
+                .take(MAX_PAGE_LINES)
+                .collect();
+            (page, lines)
+        })
+        .collect();
+
+    Ok(PdfInfo {
+        pages: pages.len(),
+        title: field(b"Title"),
+        author: field(b"Author"),
+        subject: field(b"Subject"),
+        producer: field(b"Producer"),
+        text,
+    })
+}
+
+/// A text string of the info dictionary, if set and not blank.
+fn info_string(info: &Dictionary, key: &[u8]) -> Option<String> {
+    let bytes = match info.get(key).ok()? {
+        Object::String(bytes, _) => bytes,
+        _ => return None,
+    };
+    let text = decode_text_string(bytes);
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// Decode a PDF text string: UTF-16BE after a byte order mark, otherwise
+/// PDFDocEncoding, which agrees with Latin-1 on printable characters.
+fn decode_text_string(bytes: &[u8]) -> String {
+    match bytes {
+        [0xFE, 0xFF, rest @ ..] => {
+            let units: Vec<u16> = rest
+                .chunks_exact(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        _ => bytes.iter().map(|&b| char::from(b)).collect(),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use lopdf::content::{Content, Operation};
+    use lopdf::{dictionary, Stream, StringFormat};
+    use std::path::PathBuf;
+    use tempfile::TempDir;
+
+    /// Write a PDF with one page per entry of `pages`, each showing its
+    /// lines, and `info` as its info dictionary.
+    pub(crate) fn write_pdf(path: &Path, pages: &[&[&str]], info: &[(&str, Object)]) {
+        let mut doc = Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let font_id = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Courier",
+        });
+        let resources_id = doc.add_object(dictionary! {
+            "Font" => dictionary! { "F1" => font_id },
+        });
+        let kids: Vec<Object> = pages
+            .iter()
+            .map(|lines| {
+                let mut operations = Vec::new();
+                for (i, line) in lines.iter().enumerate() {
+                    operations.push(Operation::new("BT", vec![]));
+                    operations.push(Operation::new("Tf", vec!["F1".into(), 12.into()]));
+                    let y = 700 - 20 * i as i64;
+                    operations.push(Operation::new("Td", vec![72.into(), y.into()]));
+                    operations.push(Operation::new("Tj", vec![Object::string_literal(*line)]));
+                    operations.push(Operation::new("ET", vec![]));
+                }
+                let content = Content { operations };
+                let content_id =
+                    doc.add_object(Stream::new(dictionary! {}, content.encode().unwrap()));
+                doc.add_object(dictionary! {
+                    "Type" => "Page",
+                    "Parent" => pages_id,
+                    "Contents" => content_id,
+                })
+                .into()
+            })
+            .collect();
+        let count = kids.len() as i64;
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Kids" => kids,
+                "Count" => count,
+                "Resources" => resources_id,
+                "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+            }),
+        );
+        let catalog_id = doc.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages_id,
+        });
+        doc.trailer.set("Root", catalog_id);
+        if !info.is_empty() {
+            let mut dict = Dictionary::new();
+            for (key, value) in info {
+                dict.set(*key, value.clone());
+            }
+            let info_id = doc.add_object(dict);
+            doc.trailer.set("Info", info_id);
+        }
+        doc.save(path).unwrap();
+    }
+
+    fn sample(dir: &TempDir) -> PathBuf {
+        let path = dir.path().join("report.pdf");
+        let title: Vec<u8> = [0xFE, 0xFF]
+            .into_iter()
+            .chain("Résumé".encode_utf16().flat_map(u16::to_be_bytes))
+            .collect();
+        write_pdf(
+            &path,
+            &[
+                &["Quarterly report", "Revenue grew"],
+                &[],
+                &["Appendix"],
+                &["Not shown"],
+            ],
+            &[
+                ("Title", Object::String(title, StringFormat::Hexadecimal)),
+                ("Author", Object::string_literal("Ada")),
+                ("Subject", Object::string_literal("  ")),
+            ],
+        );
+        path
+    }
+
+    #[test]
+    fn reads_page_count_info_and_first_pages_text() {
+        let dir = TempDir::new().unwrap();
+        let info = read(&sample(&dir)).unwrap();
+        assert_eq!(info.pages, 4);
+        assert_eq!(info.title.as_deref(), Some("Résumé"));
+        assert_eq!(info.author.as_deref(), Some("Ada"));
+        // Blank fields count as unset
+        assert_eq!(info.subject, None);
+        assert_eq!(info.producer, None);
+        assert_eq!(
+            info.text,
+            vec![
+                (
+                    1,
+                    vec!["Quarterly report".to_string(), "Revenue grew".to_string()]
+                ),
+                (2, vec![]),
+                (3, vec!["Appendix".to_string()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn encrypted_and_malformed_files_are_errors() {
+        let dir = TempDir::new().unwrap();
+        let garbage = dir.path().join("broken.pdf");
+        fs::write(&garbage, b"%PDF-1.4\nnot really a pdf").unwrap();
+        assert!(matches!(read(&garbage), Err(PdfError::Malformed(_))));
+
+        let locked = dir.path().join("locked.pdf");
+        write_pdf(&locked, &[&["secret"]], &[]);
+        let mut doc = Document::load(&locked).unwrap();
+        let encrypt = doc.add_object(dictionary! { "Filter" => "Standard" });
+        doc.trailer.set("Encrypt", encrypt);
+        doc.save(&locked).unwrap();
+        assert!(matches!(read(&locked), Err(PdfError::Encrypted)));
+
+        assert!(is_pdf(Path::new("a/B.PDF")));
+        assert!(!is_pdf(Path::new("pdf")));
+    }
+}
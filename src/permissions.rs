@@ -0,0 +1,252 @@
+//! The permissions editor (`M`): a chmod for the selected items.
+//!
+//! [`PermissionsForm`] holds the mode being edited, shown two ways: a
+//! grid of the nine rwx bits (owner, group and others by row) moved
+//! through with the arrow keys and toggled with Space, and an octal field
+//! that takes `644` or `4755`. Either edits the same mode, so they always
+//! agree. `App::permissions_confirm` applies it to every target through
+//! `operations::set_permissions` and records the previous modes as
+//! `UndoAction::Permissions`.
+
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::preview_content::format_permissions;
+
+/// Bits the editor changes: rwx for all three classes, plus setuid,
+/// setgid and sticky.
+pub const MODE_MASK: u32 = 0o7777;
+
+/// Rows of the grid, top to bottom.
+pub const CLASSES: [&str; 3] = ["Owner", "Group", "Others"];
+
+/// Columns of the grid, left to right.
+pub const BITS: [&str; 3] = ["read", "write", "exec"];
+
+/// Part of the dialog taking keys.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PermissionsField {
+    #[default]
+    Grid,
+    Octal,
+}
+
+/// Input state of the permissions dialog.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PermissionsForm {
+    /// Items that get the mode.
+    pub paths: Vec<PathBuf>,
+    /// Mode as it stands; only bits in [`MODE_MASK`].
+    pub mode: u32,
+    /// The targets started out with different modes.
+    pub mixed: bool,
+    pub field: PermissionsField,
+    /// Grid cell under the cursor: class row and bit column.
+    pub row: usize,
+    pub col: usize,
+    /// Text of the octal field; follows the grid, and sets the mode once
+    /// it parses.
+    pub octal: String,
+}
+
+impl PermissionsForm {
+    /// New form starting from the first item's mode; `modes` are the
+    /// current modes of `paths`, in order.
+    pub fn new(paths: Vec<PathBuf>, modes: &[u32]) -> Self {
+        let mode = modes.first().copied().unwrap_or(0o644) & MODE_MASK;
+        let mixed = modes.iter().any(|m| m & MODE_MASK != mode);
+        Self {
+            paths,
+            mode,
+            mixed,
+            field: PermissionsField::Grid,
+            row: 0,
+            col: 0,
+            octal: octal(mode),
+        }
+    }
+
+    /// Bit of the grid cell at `row`, `col`.
+    pub fn bit(row: usize, col: usize) -> u32 {
+        0o400 >> (row * 3 + col)
+    }
+
+    /// Whether the grid cell at `row`, `col` is set.
+    pub fn is_set(&self, row: usize, col: usize) -> bool {
+        self.mode & Self::bit(row, col) != 0
+    }
+
+    /// The mode to apply, or why the octal field can't be applied.
+    pub fn parsed(&self) -> Result<u32, String> {
+        parse_octal(&self.octal).ok_or_else(|| "enter 3 or 4 octal digits, e.g. 644".to_string())
+    }
+
+    /// The mode as `rw-r--r-- (0644)`.
+    pub fn describe(&self) -> String {
+        describe(self.mode)
+    }
+
+    /// Move the grid cursor, stopping at the edges.
+    pub fn move_cursor(&mut self, rows: isize, cols: isize) {
+        if self.field == PermissionsField::Octal {
+            self.next_field();
+        }
+        self.row = self.row.saturating_add_signed(rows).min(CLASSES.len() - 1);
+        self.col = self.col.saturating_add_signed(cols).min(BITS.len() - 1);
+    }
+
+    /// Flip the bit under the grid cursor.
+    pub fn toggle(&mut self) {
+        self.mode ^= Self::bit(self.row, self.col);
+        self.octal = octal(self.mode);
+    }
+
+    /// Type into the octal field; from the grid, a digit starts it over.
+    pub fn input_char(&mut self, c: char) {
+        if !('0'..='7').contains(&c) {
+            return;
+        }
+        if self.field == PermissionsField::Grid {
+            self.field = PermissionsField::Octal;
+            self.octal.clear();
+        }
+        if self.octal.len() < 4 {
+            self.octal.push(c);
+            self.sync_octal();
+        }
+    }
+
+    /// Delete the last digit of the octal field.
+    pub fn delete_char(&mut self) {
+        self.field = PermissionsField::Octal;
+        self.octal.pop();
+        self.sync_octal();
+    }
+
+    /// Switch between the grid and the octal field.
+    pub fn next_field(&mut self) {
+        self.field = match self.field {
+            PermissionsField::Grid => PermissionsField::Octal,
+            PermissionsField::Octal => {
+                // Leaving a half-typed field shows the mode in effect
+                self.octal = octal(self.mode);
+                PermissionsField::Grid
+            }
+        };
+    }
+
+    fn sync_octal(&mut self) {
+        if let Some(mode) = parse_octal(&self.octal) {
+            self.mode = mode;
+        }
+    }
+}
+
+/// A mode as 3 octal digits, or 4 when setuid, setgid or sticky is set.
+pub fn octal(mode: u32) -> String {
+    if mode & 0o7000 != 0 {
+        format!("{:04o}", mode & MODE_MASK)
+    } else {
+        format!("{:03o}", mode & 0o777)
+    }
+}
+
+/// A mode as `rw-r--r-- (0644)`.
+pub fn describe(mode: u32) -> String {
+    format!("{} ({:04o})", format_permissions(mode), mode & MODE_MASK)
+}
+
+/// Parse 3 or 4 octal digits.
+fn parse_octal(text: &str) -> Option<u32> {
+    if !(3..=4).contains(&text.len()) {
+        return None;
+    }
+    u32::from_str_radix(text, 8).ok()
+}
+
+/// One item's mode before and after a permissions change, for undo.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModeChange {
+    pub path: PathBuf,
+    pub old_mode: u32,
+    pub new_mode: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn form(mode: u32) -> PermissionsForm {
+        PermissionsForm::new(vec![PathBuf::from("a")], &[mode])
+    }
+
+    #[test]
+    fn grid_toggles_follow_the_cursor() {
+        let mut form = form(0o644);
+        assert!(form.is_set(0, 0) && form.is_set(0, 1) && !form.is_set(0, 2));
+        // Owner exec
+        form.move_cursor(0, 2);
+        form.toggle();
+        assert_eq!(form.mode, 0o744);
+        assert_eq!(form.octal, "744");
+        // Others write; the cursor stops at the edges
+        form.move_cursor(5, -1);
+        assert_eq!((form.row, form.col), (2, 1));
+        form.toggle();
+        assert_eq!(form.mode, 0o746);
+        form.move_cursor(-9, -9);
+        assert_eq!((form.row, form.col), (0, 0));
+        assert_eq!(form.describe(), "rwxr--rw- (0746)");
+    }
+
+    #[test]
+    fn octal_input_sets_the_mode_once_it_parses() {
+        let mut form = form(0o644);
+        // A digit on the grid starts the field over
+        form.input_char('7');
+        assert_eq!(form.field, PermissionsField::Octal);
+        assert_eq!(form.octal, "7");
+        assert_eq!(form.mode, 0o644);
+        assert!(form.parsed().is_err());
+        form.input_char('5');
+        form.input_char('9');
+        form.input_char('5');
+        assert_eq!(form.mode, 0o755);
+        assert_eq!(form.parsed(), Ok(0o755));
+        form.input_char('5');
+        assert_eq!(form.mode, 0o7555);
+        assert_eq!(form.describe(), "r-xr-xr-x (7555)");
+        // A fifth digit is ignored
+        form.input_char('1');
+        assert_eq!(form.octal, "7555");
+
+        form.delete_char();
+        assert_eq!(form.octal, "755");
+        assert_eq!(form.mode, 0o755);
+        form.delete_char();
+        // Leaving the field restores the mode in effect
+        form.next_field();
+        assert_eq!(form.field, PermissionsField::Grid);
+        assert_eq!(form.octal, "755");
+    }
+
+    #[test]
+    fn special_bits_show_as_four_digits() {
+        assert_eq!(octal(0o644), "644");
+        assert_eq!(octal(0o4755), "4755");
+        assert_eq!(octal(0o100644), "644");
+        let form = form(0o1777);
+        assert_eq!(form.octal, "1777");
+    }
+
+    #[test]
+    fn differing_targets_are_flagged() {
+        let paths = vec![PathBuf::from("a"), PathBuf::from("b")];
+        let same = PermissionsForm::new(paths.clone(), &[0o100644, 0o644]);
+        assert!(!same.mixed);
+        let mixed = PermissionsForm::new(paths, &[0o644, 0o600]);
+        assert!(mixed.mixed);
+        assert_eq!(mixed.mode, 0o644);
+    }
+}
@@ -0,0 +1,286 @@
+//! Speculative preview loading for the files next to the selection.
+//!
+//! Once the selected preview has been shown and input has been quiet for
+//! [`PREFETCH_IDLE`], `App::poll_prefetch` highlights the previous and next
+//! previewable siblings on blocking tasks and stores them in the preview
+//! cache, so `j`/`k` lands on a warm entry. At most [`MAX_IN_FLIGHT`] loads
+//! run at once. Each carries a cancel flag, raised when the selection moves
+//! more than [`PREFETCH_RADIUS`] rows from where the loads were started or
+//! when a file operation starts; cancelled results are dropped. Large and
+//! binary files are skipped, and results are inserted without evicting the
+//! preview on screen. Prefetched paths are remembered for the hit/miss
+//! counters until the preview looks them up, at most
+//! `memory.prefetch_entries` of them.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use ratatui::text::Line;
+
+use crate::bounded_map::{BoundedMap, MapStats, DEFAULT_PREFETCH_CAPACITY};
+use crate::fs::tree::{FlatItem, NodeType};
+use crate::preview_cache::CacheKey;
+use crate::preview_content;
+use crate::syntax::LazySyntax;
+
+/// Quiet time after the last input before neighbours are loaded.
+pub const PREFETCH_IDLE: Duration = Duration::from_millis(150);
+
+/// Most prefetch loads running at once.
+pub const MAX_IN_FLIGHT: usize = 2;
+
+/// Rows the selection may move from the prefetch origin before running
+/// loads are cancelled.
+pub const PREFETCH_RADIUS: usize = 2;
+
+/// A finished prefetch load.
+#[derive(Debug)]
+pub struct PrefetchResult {
+    pub key: CacheKey,
+    /// Rendered lines and total line count; `None` if the load was
+    /// cancelled or the file turned out to be binary.
+    pub content: Option<(Vec<Line<'static>>, usize)>,
+}
+
+#[derive(Debug)]
+struct PrefetchTask {
+    path: PathBuf,
+    cancel: Arc<AtomicBool>,
+}
+
+/// Running loads and hit/miss counters.
+#[derive(Debug)]
+pub struct PrefetchState {
+    /// Selection index the current loads were started for.
+    pub origin: Option<usize>,
+    running: Vec<PrefetchTask>,
+    /// Prefetched paths the preview has not looked up yet.
+    warmed: BoundedMap<PathBuf, ()>,
+    /// Previews of prefetched files served from the cache.
+    pub hits: u64,
+    /// Previews of prefetched files that had to be loaded anyway.
+    pub misses: u64,
+}
+
+impl Default for PrefetchState {
+    fn default() -> Self {
+        Self::with_capacity(DEFAULT_PREFETCH_CAPACITY)
+    }
+}
+
+impl PrefetchState {
+    /// Remember at most `capacity` prefetched paths.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            origin: None,
+            running: Vec::new(),
+            warmed: BoundedMap::new(capacity),
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Number of loads still running.
+    pub fn running(&self) -> usize {
+        self.running.len()
+    }
+
+    pub fn is_running(&self, path: &Path) -> bool {
+        self.running.iter().any(|t| t.path == path)
+    }
+
+    /// Register the load for `path`, cancelled through `cancel` (its
+    /// task's flag).
+    pub fn start(&mut self, path: PathBuf, cancel: Arc<AtomicBool>) {
+        self.running.push(PrefetchTask { path, cancel });
+    }
+
+    /// Cancel every running load.
+    pub fn cancel_all(&mut self) {
+        for task in self.running.drain(..) {
+            task.cancel.store(true, Ordering::SeqCst);
+        }
+        self.origin = None;
+    }
+
+    /// Cancel running loads once the selection is more than
+    /// [`PREFETCH_RADIUS`] rows from where they were started.
+    pub fn cancel_if_far(&mut self, selected: usize) {
+        if let Some(origin) = self.origin {
+            if origin.abs_diff(selected) > PREFETCH_RADIUS {
+                self.cancel_all();
+            }
+        }
+    }
+
+    /// Mark the load for `path` finished. Returns `false` if it had been
+    /// cancelled, in which case its result should be dropped.
+    pub fn finish(&mut self, path: &Path) -> bool {
+        match self.running.iter().position(|t| t.path == path) {
+            Some(pos) => {
+                let task = self.running.remove(pos);
+                !task.cancel.load(Ordering::SeqCst)
+            }
+            None => false,
+        }
+    }
+
+    /// Remember that `path` was put in the cache by a prefetch.
+    pub fn mark_warmed(&mut self, path: PathBuf) {
+        self.warmed.insert(path, ());
+    }
+
+    /// Count a preview cache lookup for `path` if it was prefetched.
+    pub fn record_lookup(&mut self, path: &Path, hit: bool) {
+        if self.warmed.remove(path).is_some() {
+            if hit {
+                self.hits += 1;
+            } else {
+                self.misses += 1;
+            }
+        }
+    }
+
+    pub fn stats(&self) -> MapStats {
+        self.warmed.stats()
+    }
+}
+
+/// The nearest file siblings before and after `selected`, skipping the
+/// contents of expanded directories.
+pub fn neighbors(items: &[FlatItem], selected: usize) -> Vec<PathBuf> {
+    let depth = match items.get(selected) {
+        Some(item) => item.depth,
+        None => return Vec::new(),
+    };
+    let sibling_file = |item: &FlatItem| item.depth == depth && item.node_type == NodeType::File;
+
+    let next = items[selected + 1..]
+        .iter()
+        .take_while(|item| item.depth >= depth)
+        .find(|item| sibling_file(item));
+    let previous = items[..selected]
+        .iter()
+        .rev()
+        .take_while(|item| item.depth >= depth)
+        .find(|item| sibling_file(item));
+    next.into_iter()
+        .chain(previous)
+        .map(|item| item.path.clone())
+        .collect()
+}
+
+/// Highlight the file behind `key`, checking `cancel` before and after the
+/// expensive part.
+pub fn load(key: CacheKey, syntax: &LazySyntax, cancel: &AtomicBool) -> PrefetchResult {
+    if cancel.load(Ordering::SeqCst) || preview_content::is_binary_file(&key.path) {
+        return PrefetchResult { key, content: None };
+    }
+    let resources = syntax.get();
+    let content = preview_content::load_highlighted_content(
+        &key.path,
+        resources,
+        key.tab_width,
+        key.raw_front_matter,
+    );
+    if cancel.load(Ordering::SeqCst) {
+        return PrefetchResult { key, content: None };
+    }
+    PrefetchResult {
+        key,
+        content: Some(content),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(name: &str, depth: usize, node_type: NodeType) -> FlatItem {
+        FlatItem {
+            name: name.to_string(),
+            path: PathBuf::from(name),
+            node_type,
+            depth,
+            is_expanded: false,
+            is_last_sibling: false,
+            is_hidden: false,
+            is_ignored: false,
+            load_more_parent: None,
+            load_more_remaining: None,
+            child_count: None,
+            stat_pending: false,
+            link: None,
+            special: None,
+        }
+    }
+
+    #[test]
+    fn neighbors_skip_directories_and_their_contents() {
+        let items = vec![
+            item("root", 0, NodeType::Directory),
+            item("a.txt", 1, NodeType::File),
+            item("dir", 1, NodeType::Directory),
+            item("dir/inner.txt", 2, NodeType::File),
+            item("b.txt", 1, NodeType::File),
+            item("c.txt", 1, NodeType::File),
+        ];
+        assert_eq!(
+            neighbors(&items, 4),
+            vec![PathBuf::from("c.txt"), PathBuf::from("a.txt")]
+        );
+        assert_eq!(neighbors(&items, 1), vec![PathBuf::from("b.txt")]);
+        // Inside a directory, siblings stop at its end
+        assert!(neighbors(&items, 3).is_empty());
+    }
+
+    #[test]
+    fn moving_far_cancels_running_loads() {
+        let mut state = PrefetchState {
+            origin: Some(10),
+            ..Default::default()
+        };
+        let near = Arc::new(AtomicBool::new(false));
+        state.start(PathBuf::from("a"), Arc::clone(&near));
+        state.cancel_if_far(12);
+        assert_eq!(state.running(), 1);
+        assert!(!near.load(Ordering::SeqCst));
+
+        state.cancel_if_far(13);
+        assert_eq!(state.running(), 0);
+        assert!(near.load(Ordering::SeqCst));
+        assert!(!state.finish(Path::new("a")));
+    }
+
+    #[test]
+    fn lookups_count_only_prefetched_paths() {
+        let mut state = PrefetchState::default();
+        state.mark_warmed(PathBuf::from("a"));
+        state.mark_warmed(PathBuf::from("b"));
+        state.record_lookup(Path::new("a"), true);
+        state.record_lookup(Path::new("b"), false);
+        state.record_lookup(Path::new("c"), true);
+        // Counted once per prefetch
+        state.record_lookup(Path::new("a"), true);
+        assert_eq!((state.hits, state.misses), (1, 1));
+    }
+
+    #[test]
+    fn warmed_paths_beyond_capacity_drop_oldest_uncounted() {
+        let mut state = PrefetchState::with_capacity(2);
+        state.mark_warmed(PathBuf::from("a"));
+        state.mark_warmed(PathBuf::from("b"));
+        assert_eq!(state.stats().evictions, 0);
+        state.mark_warmed(PathBuf::from("c"));
+        assert_eq!(state.stats().entries, 2);
+        assert_eq!(state.stats().evictions, 1);
+
+        // The evicted path no longer counts as a prefetch
+        state.record_lookup(Path::new("a"), true);
+        state.record_lookup(Path::new("b"), true);
+        state.record_lookup(Path::new("c"), false);
+        assert_eq!((state.hits, state.misses), (1, 1));
+    }
+}
@@ -0,0 +1,309 @@
+//! Byte-budgeted cache of rendered preview content.
+//!
+//! Highlighting a file with syntect is the most expensive part of a preview,
+//! so recently rendered files are kept around and reused when the selection
+//! returns to them. Entries are keyed by path + size + mtime so an edited file
+//! never serves stale lines, and the oldest entries are evicted once the total
+//! approximate size exceeds the configured budget.
+
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use ratatui::text::Line;
+
+use crate::preview_content;
+
+/// Identity of a cached preview: the file path plus the metadata that
+/// changes whenever the file content does, the tab width the lines were
+/// expanded with, and whether Markdown front matter was left raw.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheKey {
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+    pub tab_width: usize,
+    pub raw_front_matter: bool,
+}
+
+impl CacheKey {
+    /// Build a key from the current on-disk metadata of `path`.
+    pub fn for_path(path: &Path, tab_width: usize, raw_front_matter: bool) -> Option<Self> {
+        let meta = std::fs::metadata(path).ok()?;
+        Some(Self {
+            path: path.to_path_buf(),
+            size: meta.len(),
+            modified: meta.modified().ok(),
+            tab_width,
+            raw_front_matter,
+        })
+    }
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    key: CacheKey,
+    lines: Vec<Line<'static>>,
+    total_lines: usize,
+    bytes: usize,
+}
+
+/// LRU cache of rendered preview lines bounded by approximate byte size.
+#[derive(Debug)]
+pub struct PreviewCache {
+    /// Entries ordered from least to most recently used.
+    entries: VecDeque<CacheEntry>,
+    /// Sum of `bytes` over all entries.
+    total_bytes: usize,
+    /// Budget; entries are evicted oldest-first while above it.
+    max_bytes: usize,
+}
+
+impl PreviewCache {
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            total_bytes: 0,
+            max_bytes,
+        }
+    }
+
+    /// Look up a cached preview, marking it most recently used.
+    /// Returns a copy of the lines and the total line count.
+    pub fn get(&mut self, key: &CacheKey) -> Option<(Vec<Line<'static>>, usize)> {
+        let pos = self.entries.iter().position(|e| &e.key == key)?;
+        let entry = self.entries.remove(pos)?;
+        let result = (entry.lines.clone(), entry.total_lines);
+        self.entries.push_back(entry);
+        Some(result)
+    }
+
+    /// Whether `key` is cached, without touching its recency.
+    pub fn contains(&self, key: &CacheKey) -> bool {
+        self.entries.iter().any(|e| &e.key == key)
+    }
+
+    /// Key of the entry cached for `path` at `tab_width` and front matter
+    /// view, without touching the disk or its recency. The caller checks it
+    /// against fresh metadata before trusting it.
+    pub fn key_for(
+        &self,
+        path: &Path,
+        tab_width: usize,
+        raw_front_matter: bool,
+    ) -> Option<CacheKey> {
+        self.entries
+            .iter()
+            .find(|e| {
+                e.key.path == path
+                    && e.key.tab_width == tab_width
+                    && e.key.raw_front_matter == raw_front_matter
+            })
+            .map(|e| e.key.clone())
+    }
+
+    /// Store rendered lines for `key`, evicting older entries as needed.
+    /// Content larger than the whole budget is not cached at all.
+    pub fn insert(&mut self, key: CacheKey, lines: Vec<Line<'static>>, total_lines: usize) {
+        self.insert_keeping(key, lines, total_lines, None);
+    }
+
+    /// Like [`insert`](Self::insert), but never evicts the entry for `keep`
+    /// (the preview on screen). Returns `false` if the content could not
+    /// fit next to it.
+    pub fn insert_keeping(
+        &mut self,
+        key: CacheKey,
+        lines: Vec<Line<'static>>,
+        total_lines: usize,
+        keep: Option<&Path>,
+    ) -> bool {
+        self.remove_path(&key.path);
+        let bytes = preview_content::approx_lines_bytes(&lines);
+        let kept: usize = self
+            .entries
+            .iter()
+            .filter(|e| Some(e.key.path.as_path()) == keep)
+            .map(|e| e.bytes)
+            .sum();
+        if bytes + kept > self.max_bytes {
+            return false;
+        }
+        self.total_bytes += bytes;
+        self.entries.push_back(CacheEntry {
+            key,
+            lines,
+            total_lines,
+            bytes,
+        });
+        self.evict_to_budget(keep);
+        true
+    }
+
+    /// Drop any entry for `path` regardless of its metadata.
+    pub fn remove_path(&mut self, path: &Path) {
+        let mut removed = 0;
+        self.entries.retain(|e| {
+            if e.key.path == path {
+                removed += e.bytes;
+                false
+            } else {
+                true
+            }
+        });
+        self.total_bytes -= removed;
+    }
+
+    /// Remove all entries.
+    #[allow(dead_code)]
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.total_bytes = 0;
+    }
+
+    /// Approximate bytes held by all cached entries.
+    pub fn total_bytes(&self) -> usize {
+        self.total_bytes
+    }
+
+    /// Number of cached entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn evict_to_budget(&mut self, keep: Option<&Path>) {
+        while self.total_bytes > self.max_bytes {
+            let oldest = self
+                .entries
+                .iter()
+                .position(|e| Some(e.key.path.as_path()) != keep);
+            match oldest.and_then(|pos| self.entries.remove(pos)) {
+                Some(entry) => self.total_bytes -= entry.bytes,
+                None => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(name: &str, size: u64) -> CacheKey {
+        CacheKey {
+            path: PathBuf::from(name),
+            size,
+            modified: None,
+            tab_width: 4,
+            raw_front_matter: false,
+        }
+    }
+
+    fn lines(n: usize) -> Vec<Line<'static>> {
+        (0..n).map(|i| Line::from(format!("line {}", i))).collect()
+    }
+
+    #[test]
+    fn insert_and_get_roundtrip() {
+        let mut cache = PreviewCache::new(1_000_000);
+        cache.insert(key("a.rs", 1), lines(3), 3);
+        let (got, total) = cache.get(&key("a.rs", 1)).expect("cached");
+        assert_eq!(got.len(), 3);
+        assert_eq!(total, 3);
+        assert_eq!(cache.len(), 1);
+        assert!(cache.total_bytes() > 0);
+    }
+
+    #[test]
+    fn changed_metadata_misses() {
+        let mut cache = PreviewCache::new(1_000_000);
+        cache.insert(key("a.rs", 1), lines(3), 3);
+        assert!(cache.get(&key("a.rs", 2)).is_none());
+    }
+
+    #[test]
+    fn key_for_finds_the_entry_by_path_and_tab_width() {
+        let mut cache = PreviewCache::new(1_000_000);
+        cache.insert(key("a.rs", 7), lines(3), 3);
+        assert_eq!(
+            cache.key_for(Path::new("a.rs"), 4, false),
+            Some(key("a.rs", 7))
+        );
+        assert_eq!(cache.key_for(Path::new("a.rs"), 8, false), None);
+        assert_eq!(cache.key_for(Path::new("a.rs"), 4, true), None);
+        assert_eq!(cache.key_for(Path::new("b.rs"), 4, false), None);
+    }
+
+    #[test]
+    fn reinserting_path_replaces_old_entry() {
+        let mut cache = PreviewCache::new(1_000_000);
+        cache.insert(key("a.rs", 1), lines(3), 3);
+        cache.insert(key("a.rs", 2), lines(5), 5);
+        assert_eq!(cache.len(), 1);
+        assert!(cache.get(&key("a.rs", 1)).is_none());
+        assert_eq!(cache.get(&key("a.rs", 2)).map(|(_, t)| t), Some(5));
+    }
+
+    #[test]
+    fn evicts_oldest_when_over_budget() {
+        let one = preview_content::approx_lines_bytes(&lines(10));
+        let mut cache = PreviewCache::new(one * 2);
+        cache.insert(key("a", 1), lines(10), 10);
+        cache.insert(key("b", 1), lines(10), 10);
+        // Touch "a" so "b" becomes the least recently used.
+        assert!(cache.get(&key("a", 1)).is_some());
+        cache.insert(key("c", 1), lines(10), 10);
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.total_bytes() <= one * 2);
+        assert!(cache.get(&key("b", 1)).is_none());
+        assert!(cache.get(&key("a", 1)).is_some());
+        assert!(cache.get(&key("c", 1)).is_some());
+    }
+
+    #[test]
+    fn insert_keeping_never_evicts_kept_entry() {
+        let one = preview_content::approx_lines_bytes(&lines(10));
+        let mut cache = PreviewCache::new(one * 2);
+        cache.insert(key("shown", 1), lines(10), 10);
+        cache.insert(key("b", 1), lines(10), 10);
+        // "shown" is the oldest, but it is on screen
+        assert!(cache.insert_keeping(key("c", 1), lines(10), 10, Some(Path::new("shown"))));
+        assert!(cache.contains(&key("shown", 1)));
+        assert!(!cache.contains(&key("b", 1)));
+        assert!(cache.contains(&key("c", 1)));
+
+        // No room next to the kept entry: nothing is inserted
+        let mut tight = PreviewCache::new(one);
+        tight.insert(key("shown", 1), lines(10), 10);
+        assert!(!tight.insert_keeping(key("d", 1), lines(10), 10, Some(Path::new("shown"))));
+        assert!(tight.contains(&key("shown", 1)));
+        assert_eq!(tight.len(), 1);
+    }
+
+    #[test]
+    fn oversized_entry_not_cached() {
+        let mut cache = PreviewCache::new(16);
+        cache.insert(key("big", 1), lines(100), 100);
+        assert!(cache.is_empty());
+        assert_eq!(cache.total_bytes(), 0);
+    }
+
+    #[test]
+    fn remove_path_updates_bytes() {
+        let mut cache = PreviewCache::new(1_000_000);
+        cache.insert(key("a", 1), lines(4), 4);
+        cache.insert(key("b", 1), lines(4), 4);
+        let before = cache.total_bytes();
+        cache.remove_path(Path::new("a"));
+        assert_eq!(cache.len(), 1);
+        assert!(cache.total_bytes() < before);
+        cache.clear();
+        assert_eq!(cache.total_bytes(), 0);
+    }
+}
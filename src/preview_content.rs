@@ -5,94 +5,56 @@ use std::path::Path;
 
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
-use syntect::highlighting::{Theme, ThemeSet};
-use syntect::parsing::SyntaxSet;
 
 use crate::app::ViewMode;
+#[cfg(feature = "archives")]
+use crate::archive;
+use crate::dir_size::DirSize;
+use crate::front_matter;
+use crate::fs::tree::SpecialFile;
+#[cfg(feature = "pdf-preview")]
+use crate::pdf;
+use crate::syntax::{Highlighter, SuspendedHighlighter, SyntaxResources};
+use crate::text;
 
 /// Line count adjustment step for +/- keys.
 pub const LINE_COUNT_STEP: usize = 10;
 
-/// Detect the syntax name for a file based on its extension.
-pub fn detect_syntax_name(path: &Path) -> &str {
-    match path.extension().and_then(|e| e.to_str()) {
-        Some("py") => "Python",
-        Some("rs") => "Rust",
-        Some("yaml" | "yml") => "YAML",
-        Some("json") => "JSON",
-        Some("toml") => "TOML",
-        Some("sh" | "bash" | "zsh") => "Bash",
-        Some("sql") => "SQL",
-        Some("md" | "markdown") => "Markdown",
-        Some("html" | "htm") => "HTML",
-        Some("css") => "CSS",
-        Some("js" | "jsx") => "JavaScript",
-        Some("ts" | "tsx") => "TypeScript",
-        Some("c" | "h") => "C",
-        Some("cpp" | "hpp" | "cc") => "C++",
-        Some("java") => "Java",
-        Some("go") => "Go",
-        Some("rb") => "Ruby",
-        Some("txt" | "log" | "csv" | "cfg" | "conf" | "ini") => "Plain Text",
-        Some("ipynb") => "Python",
-        None => detect_from_shebang(path),
-        _ => "Plain Text",
-    }
-}
-
-/// Detect syntax from shebang line for extensionless files.
-fn detect_from_shebang(path: &Path) -> &str {
-    let file = match fs::File::open(path) {
-        Ok(f) => f,
-        Err(_) => return "Plain Text",
-    };
-    let mut reader = BufReader::new(file);
-    let mut first_line = String::new();
-    if reader.read_line(&mut first_line).is_err() {
-        return "Plain Text";
-    }
-    if !first_line.starts_with("#!") {
-        return "Plain Text";
-    }
-    let line = first_line.to_lowercase();
-    if line.contains("python") {
-        "Python"
-    } else if line.contains("bash") || line.contains("/sh") {
-        "Bash"
-    } else if line.contains("ruby") {
-        "Ruby"
-    } else if line.contains("node") || line.contains("deno") {
-        "JavaScript"
-    } else if line.contains("perl") {
-        "Perl"
-    } else {
-        "Plain Text"
-    }
-}
-
-/// Load a theme from the built-in theme set by name, with fallback.
-pub fn load_theme(theme_name: Option<&str>) -> Theme {
-    let ts = ThemeSet::load_defaults();
-    let name = theme_name.unwrap_or("base16-ocean.dark");
-    ts.themes
-        .get(name)
-        .cloned()
-        .unwrap_or_else(|| ts.themes["base16-ocean.dark"].clone())
-}
+/// Maximum characters of a single line materialized in the preview.
+/// Longer lines (minified JS, single-line JSON dumps) are cut with a marker
+/// so one huge line can't balloon the styled span memory.
+pub const MAX_PREVIEW_LINE_CHARS: usize = 2_000;
 
-/// Convert syntect color to ratatui Color.
-fn syntect_color_to_ratatui(c: syntect::highlighting::Color) -> Color {
-    Color::Rgb(c.r, c.g, c.b)
-}
+/// Separator ending the line-number gutter of a code line.
+pub const LINE_NUMBER_SEP: &str = " │ ";
 
 /// Load and syntax-highlight a file's content, returning styled lines for ratatui.
 ///
+/// Markdown front matter is shown as a card in place of its lines unless
+/// `raw_front_matter`; the line count is still that of the file.
+///
 /// Returns `(lines, total_line_count)`. On error, returns a single error-message line.
 pub fn load_highlighted_content(
     path: &Path,
-    ss: &SyntaxSet,
-    theme: &Theme,
+    syntax: &SyntaxResources,
+    tab_width: usize,
+    raw_front_matter: bool,
 ) -> (Vec<Line<'static>>, usize) {
+    let (lines, total, _) =
+        load_highlighted_head(path, syntax, tab_width, raw_front_matter, usize::MAX);
+    (lines, total)
+}
+
+/// Like [`load_highlighted_content`], but only the first `highlight_lines`
+/// lines are highlighted; the rest come back as plain text, with what is
+/// needed to highlight them later in chunks.
+pub fn load_highlighted_head(
+    path: &Path,
+    syntax: &SyntaxResources,
+    tab_width: usize,
+    raw_front_matter: bool,
+    highlight_lines: usize,
+) -> (Vec<Line<'static>>, usize, Option<PendingHighlight>) {
     let content = match fs::read(path) {
         Ok(bytes) => match String::from_utf8(bytes) {
             Ok(s) => s,
@@ -109,43 +71,44 @@ pub fn load_highlighted_content(
                     Style::default().fg(Color::Red),
                 ))],
                 1,
+                None,
             );
         }
     };
 
-    let syntax_name = detect_syntax_name(path);
-    let syntax = ss
-        .find_syntax_by_name(syntax_name)
-        .or_else(|| ss.find_syntax_by_extension(path.extension()?.to_str()?))
-        .unwrap_or_else(|| ss.find_syntax_plain_text());
-
-    let mut highlighter = syntect::easy::HighlightLines::new(syntax, theme);
+    let mut highlighter = syntax.highlighter(path);
     let lines_text: Vec<&str> = content.lines().collect();
     let total = lines_text.len().max(1);
     let line_num_width = total.to_string().len();
 
     let mut result_lines = Vec::with_capacity(total);
-    for (i, line_str) in lines_text.iter().enumerate() {
-        let mut spans: Vec<Span<'static>> = Vec::new();
-
-        // Line number
-        let num = format!("{:>width$} │ ", i + 1, width = line_num_width);
-        spans.push(Span::styled(num, Style::default().fg(Color::DarkGray)));
-
-        // Highlighted content
-        match highlighter.highlight_line(line_str, ss) {
-            Ok(ranges) => {
-                for (style, text) in ranges {
-                    let fg = syntect_color_to_ratatui(style.foreground);
-                    spans.push(Span::styled(text.to_string(), Style::default().fg(fg)));
-                }
-            }
-            Err(_) => {
-                spans.push(Span::raw(line_str.to_string()));
-            }
+    let mut body_start = 0;
+    if !raw_front_matter && front_matter::applies_to(path) {
+        if let Some((card, replaced)) = front_matter::card(&lines_text) {
+            result_lines.extend(card);
+            body_start = replaced;
+        }
+    }
+    let plain_from = body_start.saturating_add(highlight_lines);
+    let mut rest = None;
+    for (i, line_str) in lines_text.iter().enumerate().skip(body_start) {
+        if i < plain_from {
+            result_lines.push(highlight_single_line(
+                line_str,
+                i + 1,
+                line_num_width,
+                &mut highlighter,
+                tab_width,
+            ));
+        } else {
+            rest.get_or_insert((i, result_lines.len()));
+            result_lines.push(plain_single_line(
+                line_str,
+                i + 1,
+                line_num_width,
+                tab_width,
+            ));
         }
-
-        result_lines.push(Line::from(spans));
     }
 
     if result_lines.is_empty() {
@@ -155,7 +118,78 @@ pub fn load_highlighted_content(
         )));
     }
 
-    (result_lines, total)
+    let pending = rest.map(|(next_line, next_row)| PendingHighlight {
+        next_line,
+        next_row,
+        line_num_width,
+        tab_width,
+        highlighter: highlighter.suspend(),
+        content,
+    });
+    (result_lines, total, pending)
+}
+
+/// The lines of a preview still shown as plain text, highlighted a chunk
+/// at a time. The parse state is carried from one chunk to the next, so a
+/// block comment opened above the first plain line still colours it.
+#[derive(Debug)]
+pub struct PendingHighlight {
+    content: String,
+    /// Index of the first file line not highlighted yet.
+    next_line: usize,
+    /// Row of `next_line` in the preview; rows differ from lines below a
+    /// front matter card.
+    next_row: usize,
+    line_num_width: usize,
+    tab_width: usize,
+    highlighter: SuspendedHighlighter,
+}
+
+/// Highlighted rows replacing the plain ones from row `start` on.
+#[derive(Debug)]
+pub struct HighlightedRows {
+    pub start: usize,
+    pub lines: Vec<Line<'static>>,
+}
+
+impl PendingHighlight {
+    /// Highlight up to `max_lines` more lines. Returns them with what is
+    /// left, `None` once the file is done.
+    pub fn next_chunk(
+        self,
+        syntax: &SyntaxResources,
+        max_lines: usize,
+    ) -> (HighlightedRows, Option<Self>) {
+        let mut highlighter = syntax.resume(self.highlighter);
+        let mut lines = Vec::new();
+        let mut more = false;
+        for (i, line_str) in self.content.lines().enumerate().skip(self.next_line) {
+            if lines.len() == max_lines {
+                more = true;
+                break;
+            }
+            lines.push(highlight_single_line(
+                line_str,
+                i + 1,
+                self.line_num_width,
+                &mut highlighter,
+                self.tab_width,
+            ));
+        }
+        let rows = HighlightedRows {
+            start: self.next_row,
+            lines,
+        };
+        let rest = more.then(|| Self {
+            next_line: self.next_line + rows.lines.len(),
+            next_row: self.next_row + rows.lines.len(),
+            line_num_width: self.line_num_width,
+            tab_width: self.tab_width,
+            highlighter: highlighter.suspend(),
+            content: self.content,
+        });
+        (rows, rest)
+    }
 }
 
 /// Count lines in a file using fast byte scanning (64KB chunks).
@@ -187,11 +221,11 @@ pub fn fast_line_count(path: &Path) -> std::io::Result<usize> {
 /// Returns styled lines with head section, separator, and tail section.
 pub fn load_head_tail_content(
     path: &Path,
-    ss: &SyntaxSet,
-    theme: &Theme,
+    syntax: &SyntaxResources,
     head_lines: usize,
     tail_lines: usize,
     view_mode: ViewMode,
+    tab_width: usize,
 ) -> (Vec<Line<'static>>, usize) {
     let total_lines = match fast_line_count(path) {
         Ok(n) => n,
@@ -221,11 +255,7 @@ pub fn load_head_tail_content(
     let reader = BufReader::new(file);
     let all_lines: Vec<String> = reader.lines().map_while(Result::ok).collect();
 
-    let syntax_name = detect_syntax_name(path);
-    let syntax = ss
-        .find_syntax_by_name(syntax_name)
-        .unwrap_or_else(|| ss.find_syntax_plain_text());
-    let mut highlighter = syntect::easy::HighlightLines::new(syntax, theme);
+    let mut highlighter = syntax.highlighter(path);
 
     let line_num_width = total_lines.to_string().len();
     let mut result_lines: Vec<Line<'static>> = Vec::new();
@@ -243,7 +273,7 @@ pub fn load_head_tail_content(
                     i + 1,
                     line_num_width,
                     &mut highlighter,
-                    ss,
+                    tab_width,
                 ));
             }
 
@@ -266,7 +296,7 @@ pub fn load_head_tail_content(
                     tail_start + i + 1,
                     line_num_width,
                     &mut highlighter,
-                    ss,
+                    tab_width,
                 ));
             }
         }
@@ -277,7 +307,7 @@ pub fn load_head_tail_content(
                     i + 1,
                     line_num_width,
                     &mut highlighter,
-                    ss,
+                    tab_width,
                 ));
             }
         }
@@ -288,7 +318,7 @@ pub fn load_head_tail_content(
                     tail_start + i + 1,
                     line_num_width,
                     &mut highlighter,
-                    ss,
+                    tab_width,
                 ));
             }
         }
@@ -299,33 +329,117 @@ pub fn load_head_tail_content(
 }
 
 /// Highlight a single line with line number prefix.
+///
+/// Tabs are expanded to `tab_width` stops counted from the first code
+/// column, so they line up the same way as in the editor.
 fn highlight_single_line(
     line_str: &str,
     line_num: usize,
     line_num_width: usize,
-    highlighter: &mut syntect::easy::HighlightLines,
-    ss: &SyntaxSet,
+    highlighter: &mut Highlighter,
+    tab_width: usize,
 ) -> Line<'static> {
     let mut spans: Vec<Span<'static>> = Vec::new();
 
-    let num = format!("{:>width$} │ ", line_num, width = line_num_width);
+    let num = format!(
+        "{:>width$}{}",
+        line_num,
+        LINE_NUMBER_SEP,
+        width = line_num_width
+    );
     spans.push(Span::styled(num, Style::default().fg(Color::DarkGray)));
 
-    match highlighter.highlight_line(line_str, ss) {
-        Ok(ranges) => {
-            for (style, text) in ranges {
-                let fg = syntect_color_to_ratatui(style.foreground);
-                spans.push(Span::styled(text.to_string(), Style::default().fg(fg)));
-            }
-        }
-        Err(_) => {
-            spans.push(Span::raw(line_str.to_string()));
-        }
+    let (visible, omitted) = cap_line_chars(line_str, MAX_PREVIEW_LINE_CHARS);
+    spans.extend(highlight_code(visible, highlighter, tab_width));
+
+    if omitted > 0 {
+        spans.push(Span::styled(
+            format!(" … [+{} chars]", omitted),
+            Style::default().fg(Color::DarkGray),
+        ));
     }
 
     Line::from(spans)
 }
 
+/// A numbered line of code as plain text, shown until it is highlighted.
+fn plain_single_line(
+    line_str: &str,
+    line_num: usize,
+    line_num_width: usize,
+    tab_width: usize,
+) -> Line<'static> {
+    let num = format!(
+        "{:>width$}{}",
+        line_num,
+        LINE_NUMBER_SEP,
+        width = line_num_width
+    );
+    let (visible, omitted) = cap_line_chars(line_str, MAX_PREVIEW_LINE_CHARS);
+    let mut spans = vec![
+        Span::styled(num, Style::default().fg(Color::DarkGray)),
+        Span::raw(text::expand_tabs(visible, 0, tab_width).0),
+    ];
+    if omitted > 0 {
+        spans.push(Span::styled(
+            format!(" … [+{} chars]", omitted),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+    Line::from(spans)
+}
+
+/// Syntax-highlight one line of code into spans, expanding tabs with the
+/// column carried across spans.
+fn highlight_code(
+    line_str: &str,
+    highlighter: &mut Highlighter,
+    tab_width: usize,
+) -> Vec<Span<'static>> {
+    match highlighter.line(line_str) {
+        Some(parts) => {
+            let mut col = 0;
+            parts
+                .into_iter()
+                .map(|(fg, part)| {
+                    let (expanded, end) = text::expand_tabs(part, col, tab_width);
+                    col = end;
+                    Span::styled(expanded, Style::default().fg(fg))
+                })
+                .collect()
+        }
+        None => vec![Span::raw(text::expand_tabs(line_str, 0, tab_width).0)],
+    }
+}
+
+/// Split `line` after at most `max_chars` characters.
+///
+/// Returns the visible prefix and the number of characters cut off.
+fn cap_line_chars(line: &str, max_chars: usize) -> (&str, usize) {
+    match line.char_indices().nth(max_chars) {
+        Some((byte_idx, _)) => (&line[..byte_idx], line[byte_idx..].chars().count()),
+        None => (line, 0),
+    }
+}
+
+/// Approximate heap bytes held by a set of rendered preview lines.
+///
+/// Counts span text plus the per-span and per-line bookkeeping; good
+/// enough for budgeting, not an exact allocator measurement.
+pub fn approx_lines_bytes(lines: &[Line<'_>]) -> usize {
+    lines
+        .iter()
+        .map(|line| {
+            std::mem::size_of::<Line>()
+                + line
+                    .spans
+                    .iter()
+                    .map(|span| std::mem::size_of::<Span>() + span.content.len())
+                    .sum::<usize>()
+        })
+        .sum()
+}
+
 /// Known binary file extensions.
 const BINARY_EXTENSIONS: &[&str] = &[
     "pt", "pth", "h5", "hdf5", "pkl", "pickle", "onnx", "zip", "tar", "gz", "bz2", "xz", "so",
@@ -344,6 +458,11 @@ pub fn is_binary_file(path: &Path) -> bool {
         }
     }
 
+    // Opening a FIFO blocks, and device nodes have no end to scan
+    if SpecialFile::at(path).is_some() {
+        return true;
+    }
+
     // Fallback: scan first 8KB for null bytes
     let file = match fs::File::open(path) {
         Ok(f) => f,
@@ -359,7 +478,7 @@ pub fn is_binary_file(path: &Path) -> bool {
 }
 
 /// Format bytes into human-readable size string.
-fn format_size(bytes: u64) -> String {
+pub fn format_size(bytes: u64) -> String {
     const KB: u64 = 1024;
     const MB: u64 = 1024 * KB;
     const GB: u64 = 1024 * MB;
@@ -379,7 +498,7 @@ fn format_size(bytes: u64) -> String {
 }
 
 /// Format Unix permissions as rwxrwxrwx string.
-fn format_permissions(mode: u32) -> String {
+pub(crate) fn format_permissions(mode: u32) -> String {
     let mut s = String::with_capacity(9);
     let flags = [
         (0o400, 'r'),
@@ -430,25 +549,7 @@ pub fn load_binary_metadata(path: &Path) -> (Vec<Line<'static>>, usize) {
 
     let size_str = format_size(meta.len());
 
-    let modified_str = meta
-        .modified()
-        .ok()
-        .and_then(|t| {
-            t.duration_since(std::time::UNIX_EPOCH).ok().map(|d| {
-                let secs = d.as_secs();
-                let days = secs / 86400;
-                let remaining = secs % 86400;
-                let hours = remaining / 3600;
-                let minutes = (remaining % 3600) / 60;
-                // Simple date calculation from epoch days
-                let (year, month, day) = epoch_days_to_date(days);
-                format!(
-                    "{:04}-{:02}-{:02} {:02}:{:02}",
-                    year, month, day, hours, minutes
-                )
-            })
-        })
-        .unwrap_or_else(|| "Unknown".to_string());
+    let modified_str = format_modified(&meta);
 
     let perms_str = format_permissions(meta.permissions().mode());
 
@@ -485,8 +586,325 @@ pub fn load_binary_metadata(path: &Path) -> (Vec<Line<'static>>, usize) {
     (lines, total)
 }
 
+/// Format a file's modification time as `YYYY-MM-DD HH:MM` (UTC).
+fn format_modified(meta: &fs::Metadata) -> String {
+    meta.modified()
+        .ok()
+        .and_then(|t| {
+            t.duration_since(std::time::UNIX_EPOCH).ok().map(|d| {
+                let secs = d.as_secs();
+                let days = secs / 86400;
+                let remaining = secs % 86400;
+                let hours = remaining / 3600;
+                let minutes = (remaining % 3600) / 60;
+                // Simple date calculation from epoch days
+                let (year, month, day) = epoch_days_to_date(days);
+                format!(
+                    "{:04}-{:02}-{:02} {:02}:{:02}",
+                    year, month, day, hours, minutes
+                )
+            })
+        })
+        .unwrap_or_else(|| "Unknown".to_string())
+}
+
+/// Describe a FIFO, socket or device node from its metadata alone. The
+/// file is never opened: reading a FIFO with no writer blocks, and device
+/// nodes can hang or stream without end.
+pub fn load_special_summary(path: &Path, special: SpecialFile) -> (Vec<Line<'static>>, usize) {
+    let label_style = Style::default()
+        .fg(Color::Cyan)
+        .add_modifier(Modifier::BOLD);
+    let value_style = Style::default().fg(Color::White);
+    let dim_style = Style::default().fg(Color::DarkGray);
+
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string_lossy().to_string());
+
+    let mut lines = vec![
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("  File: ", label_style),
+            Span::styled(file_name, value_style),
+        ]),
+        Line::from(vec![
+            Span::styled("  Type: ", label_style),
+            Span::styled(special.label(), value_style),
+        ]),
+    ];
+    if let Ok(meta) = fs::metadata(path) {
+        lines.push(Line::from(vec![
+            Span::styled("  Modified: ", label_style),
+            Span::styled(format_modified(&meta), value_style),
+        ]));
+        lines.push(Line::from(vec![
+            Span::styled("  Permissions: ", label_style),
+            Span::styled(format_permissions(meta.permissions().mode()), value_style),
+        ]));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        format!("  [{} — contents not previewable]", special.label()),
+        dim_style,
+    )));
+
+    let total = lines.len();
+    (lines, total)
+}
+
+/// Whether `path` is an archive whose contents the preview can list.
+#[cfg(feature = "archives")]
+pub fn is_listable_archive(path: &Path) -> bool {
+    archive::Format::detect(path).is_some()
+}
+
+/// List the contents of a zip or tar archive: a summary, then the entries
+/// as an indented tree with their sizes.
+///
+/// Only headers are read; at most [`archive::MAX_ENTRIES`] entries are
+/// listed. Returns `(lines, total_line_count)`. A corrupt archive yields an
+/// error line under the file name.
+#[cfg(feature = "archives")]
+pub fn load_archive_listing(path: &Path) -> (Vec<Line<'static>>, usize) {
+    let label_style = Style::default()
+        .fg(Color::Cyan)
+        .add_modifier(Modifier::BOLD);
+    let value_style = Style::default().fg(Color::White);
+    let dim_style = Style::default().fg(Color::DarkGray);
+    let dir_style = Style::default()
+        .fg(Color::Blue)
+        .add_modifier(Modifier::BOLD);
+
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string_lossy().to_string());
+    let Some(format) = archive::Format::detect(path) else {
+        return load_binary_metadata(path);
+    };
+
+    let mut lines = vec![
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("  Archive: ", label_style),
+            Span::styled(file_name, value_style),
+            Span::styled(format!(" ({})", format.label()), dim_style),
+        ]),
+    ];
+
+    let listing = match archive::list(path, format, archive::MAX_ENTRIES) {
+        Ok(listing) => listing,
+        Err(e) => {
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled(
+                format!("  Error reading archive: {}", e),
+                Style::default().fg(Color::Red),
+            )));
+            let total = lines.len();
+            return (lines, total);
+        }
+    };
+
+    let mut entries = listing.entries;
+    entries.sort_by(|a, b| a.path.split('/').cmp(b.path.split('/')));
+    let dirs = entries.iter().filter(|e| e.is_dir).count();
+    let files = entries.len() - dirs;
+    let size: u64 = entries.iter().map(|e| e.size).sum();
+    let packed: Option<u64> = entries.iter().map(|e| e.compressed).sum();
+    let partial = listing.remaining != archive::Remaining::None;
+
+    lines.push(Line::from(vec![
+        Span::styled("  Contents: ", label_style),
+        Span::styled(
+            format!(
+                "{} file{}, {} director{}",
+                files,
+                if files == 1 { "" } else { "s" },
+                dirs,
+                if dirs == 1 { "y" } else { "ies" }
+            ),
+            value_style,
+        ),
+        Span::styled(if partial { " (listed)" } else { "" }, dim_style),
+    ]));
+    let mut size_spans = vec![
+        Span::styled("  Size: ", label_style),
+        Span::styled(format_size(size), value_style),
+    ];
+    if let Some(packed) = packed {
+        size_spans.push(Span::styled(
+            format!(" ({} packed)", format_size(packed)),
+            dim_style,
+        ));
+    }
+    lines.push(Line::from(size_spans));
+    lines.push(Line::from(""));
+
+    let with_packed = format == archive::Format::Zip;
+    // Size columns of an entry; blank for directories
+    let sizes = |entry: Option<&archive::Entry>| {
+        let column = |value: Option<u64>| value.map(format_size).unwrap_or_default();
+        let size = column(entry.map(|e| e.size));
+        if with_packed {
+            let packed = column(entry.and_then(|e| e.compressed));
+            format!("  {:>10}  {:>10}  ", size, packed)
+        } else {
+            format!("  {:>10}  ", size)
+        }
+    };
+    lines.push(Line::from(Span::styled(
+        if with_packed {
+            format!("  {:>10}  {:>10}  Name", "Size", "Packed")
+        } else {
+            format!("  {:>10}  Name", "Size")
+        },
+        dim_style,
+    )));
+
+    // Directories already shown above the current entry, outermost first
+    let mut open: Vec<&str> = Vec::new();
+    for entry in &entries {
+        let components: Vec<&str> = entry.path.split('/').collect();
+        let (name, parents) = components.split_last().expect("split yields one item");
+        let shared = open.iter().zip(parents).take_while(|(a, b)| a == b).count();
+        open.truncate(shared);
+        // Parents the archive has no entry of are still shown
+        for parent in &parents[shared..] {
+            lines.push(Line::from(vec![
+                Span::styled(sizes(None), dim_style),
+                Span::raw("  ".repeat(open.len())),
+                Span::styled(format!("{}/", parent), dir_style),
+            ]));
+            open.push(parent);
+        }
+        let indent = Span::raw("  ".repeat(open.len()));
+        if entry.is_dir {
+            lines.push(Line::from(vec![
+                Span::styled(sizes(None), dim_style),
+                indent,
+                Span::styled(format!("{}/", name), dir_style),
+            ]));
+            open.push(name);
+        } else {
+            lines.push(Line::from(vec![
+                Span::styled(sizes(Some(entry)), dim_style),
+                indent,
+                Span::styled(name.to_string(), value_style),
+            ]));
+        }
+    }
+
+    match listing.remaining {
+        archive::Remaining::None => {}
+        archive::Remaining::Counted(more) => lines.push(Line::from(Span::styled(
+            format!("  … and {} more", more),
+            dim_style,
+        ))),
+        archive::Remaining::Unknown => lines.push(Line::from(Span::styled(
+            "  … and more (listing stopped early)",
+            dim_style,
+        ))),
+    }
+
+    let total = lines.len();
+    (lines, total)
+}
+
+/// Show a PDF: page count and info dictionary, then the text of the first
+/// [`pdf::TEXT_PAGES`] pages.
+///
+/// Returns `(lines, total_line_count)`. An encrypted, oversized or
+/// malformed PDF falls back to [`load_binary_metadata`] with the reason
+/// underneath.
+#[cfg(feature = "pdf-preview")]
+pub fn load_pdf_preview(path: &Path) -> (Vec<Line<'static>>, usize) {
+    let info = match pdf::read(path) {
+        Ok(info) => info,
+        Err(e) => {
+            let (mut lines, _) = load_binary_metadata(path);
+            lines.push(Line::from(Span::styled(
+                format!("  PDF not read: {}", e),
+                Style::default().fg(Color::DarkGray),
+            )));
+            let total = lines.len();
+            return (lines, total);
+        }
+    };
+
+    let label_style = Style::default()
+        .fg(Color::Cyan)
+        .add_modifier(Modifier::BOLD);
+    let value_style = Style::default().fg(Color::White);
+    let dim_style = Style::default().fg(Color::DarkGray);
+
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string_lossy().to_string());
+
+    let mut lines = vec![
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("  PDF: ", label_style),
+            Span::styled(file_name, value_style),
+        ]),
+        Line::from(vec![
+            Span::styled("  Pages: ", label_style),
+            Span::styled(info.pages.to_string(), value_style),
+        ]),
+    ];
+    let fields = [
+        ("  Title: ", info.title),
+        ("  Author: ", info.author),
+        ("  Subject: ", info.subject),
+        ("  Producer: ", info.producer),
+    ];
+    for (label, value) in fields {
+        if let Some(value) = value {
+            lines.push(Line::from(vec![
+                Span::styled(label, label_style),
+                Span::styled(value, value_style),
+            ]));
+        }
+    }
+
+    if info.text.iter().all(|(_, page)| page.is_empty()) {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            "  [No extractable text]",
+            dim_style,
+        )));
+    } else {
+        for (page, text) in info.text {
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled(
+                format!("  ── Page {} ──", page),
+                dim_style,
+            )));
+            lines.extend(
+                text.into_iter()
+                    .map(|line| Line::from(format!("  {}", line))),
+            );
+        }
+    }
+    let shown = info.pages.min(pdf::TEXT_PAGES as usize);
+    if info.pages > shown {
+        let more = info.pages - shown;
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            format!("  … {} more page{}", more, if more == 1 { "" } else { "s" }),
+            dim_style,
+        )));
+    }
+
+    let total = lines.len();
+    (lines, total)
+}
+
 /// Convert days since Unix epoch to (year, month, day).
-fn epoch_days_to_date(days: u64) -> (u64, u64, u64) {
+pub(crate) fn epoch_days_to_date(days: u64) -> (u64, u64, u64) {
     // Simple algorithm: iterate years/months
     let mut remaining = days as i64;
     let mut year = 1970u64;
@@ -579,35 +997,124 @@ pub fn load_directory_summary(path: &Path) -> (Vec<Line<'static>>, usize) {
         }
     }
 
-    let size_str = format_size(total_size);
-
     let mut lines = vec![
         Line::from(""),
         Line::from(vec![
             Span::styled("  Directory: ", label_style),
             Span::styled(dir_name, value_style),
         ]),
+    ];
+    lines.extend(summary_totals(
+        file_count,
+        dir_count,
+        format_size(total_size),
+    ));
+
+    if capped {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            format!("  (scan capped at {} entries)", MAX_ENTRIES),
+            Style::default().fg(Color::Yellow),
+        )));
+    }
+
+    let total = lines.len();
+    (lines, total)
+}
+
+/// Row of the first total in a directory summary.
+const SUMMARY_TOTALS_ROW: usize = 2;
+
+const TOTAL_SIZE_LABEL: &str = "  Total Size: ";
+
+/// The file count, subdirectory count and size rows of a directory summary.
+fn summary_totals(files: u64, dirs: u64, size: String) -> [Line<'static>; 3] {
+    let label_style = Style::default()
+        .fg(Color::Cyan)
+        .add_modifier(Modifier::BOLD);
+    let value_style = Style::default().fg(Color::White);
+    [
         Line::from(vec![
             Span::styled("  Files: ", label_style),
-            Span::styled(file_count.to_string(), value_style),
+            Span::styled(files.to_string(), value_style),
         ]),
         Line::from(vec![
             Span::styled("  Subdirectories: ", label_style),
-            Span::styled(dir_count.to_string(), value_style),
+            Span::styled(dirs.to_string(), value_style),
         ]),
         Line::from(vec![
-            Span::styled("  Total Size: ", label_style),
-            Span::styled(size_str, value_style),
+            Span::styled(TOTAL_SIZE_LABEL, label_style),
+            Span::styled(size, value_style),
         ]),
-    ];
+    ]
+}
 
-    if capped {
-        lines.push(Line::from(""));
-        lines.push(Line::from(Span::styled(
-            format!("  (scan capped at {} entries)", MAX_ENTRIES),
-            Style::default().fg(Color::Yellow),
-        )));
+/// Replace the totals of a summary from [`load_directory_summary`] with
+/// those of a size walk. A finished walk also drops the scan cap note,
+/// which no longer applies. Lines that are not a directory summary are
+/// left alone. `apparent` picks the size shown first, as in the tree.
+pub fn update_directory_summary(lines: &mut Vec<Line<'static>>, size: &DirSize, apparent: bool) {
+    let size_row = SUMMARY_TOTALS_ROW + 2;
+    let is_summary = lines
+        .get(size_row)
+        .and_then(|line| line.spans.first())
+        .is_some_and(|span| span.content == TOTAL_SIZE_LABEL);
+    if !is_summary {
+        return;
     }
+    let totals = summary_totals(size.files, size.dirs, size.detail(apparent));
+    lines.splice(SUMMARY_TOTALS_ROW..=size_row, totals);
+    if size.done() {
+        lines.truncate(size_row + 1);
+    }
+}
+
+/// Describe the symlink at `path`: what it points to as written, where
+/// that resolves, and whether the target exists.
+pub fn load_symlink_summary(path: &Path) -> (Vec<Line<'static>>, usize) {
+    let label_style = Style::default()
+        .fg(Color::Cyan)
+        .add_modifier(Modifier::BOLD);
+    let value_style = Style::default().fg(Color::White);
+
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string_lossy().to_string());
+    let target = match fs::read_link(path) {
+        Ok(target) => target.display().to_string(),
+        Err(e) => format!("unreadable ({})", e),
+    };
+    let (status, status_style) = match fs::metadata(path) {
+        Ok(meta) if meta.is_dir() => ("valid (directory)".to_string(), Color::Green),
+        Ok(meta) => match SpecialFile::of(meta.file_type()) {
+            Some(special) => (format!("valid ({})", special.label()), Color::Green),
+            None => ("valid (file)".to_string(), Color::Green),
+        },
+        Err(e) => (format!("broken ({})", e), Color::Red),
+    };
+
+    let mut lines = vec![
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("  Symlink: ", label_style),
+            Span::styled(name, value_style),
+        ]),
+        Line::from(vec![
+            Span::styled("  Target: ", label_style),
+            Span::styled(target, value_style),
+        ]),
+    ];
+    if let Ok(resolved) = fs::canonicalize(path) {
+        lines.push(Line::from(vec![
+            Span::styled("  Resolves to: ", label_style),
+            Span::styled(resolved.display().to_string(), value_style),
+        ]));
+    }
+    lines.push(Line::from(vec![
+        Span::styled("  Status: ", label_style),
+        Span::styled(status, Style::default().fg(status_style)),
+    ]));
 
     let total = lines.len();
     (lines, total)
@@ -619,8 +1126,8 @@ pub fn load_directory_summary(path: &Path) -> (Vec<Line<'static>>, usize) {
 /// (syntax-highlighted for code cells), and text outputs.
 pub fn load_notebook_content(
     path: &Path,
-    ss: &SyntaxSet,
-    theme: &Theme,
+    syntax: &SyntaxResources,
+    tab_width: usize,
 ) -> (Vec<Line<'static>>, usize) {
     let content = match fs::read_to_string(path) {
         Ok(s) => s,
@@ -666,8 +1173,6 @@ pub fn load_notebook_content(
         .pointer("/metadata/kernelspec/language")
         .and_then(|v| v.as_str())
         .unwrap_or("python");
-    let kernel_ext = format!("_.{}", kernel_lang);
-    let kernel_syntax_name = detect_syntax_name(Path::new(&kernel_ext));
 
     let header_style = Style::default()
         .fg(Color::Yellow)
@@ -696,30 +1201,19 @@ pub fn load_notebook_content(
         if !source.is_empty() {
             if cell_type == "code" {
                 // Syntax-highlight code cells
-                let syntax = ss
-                    .find_syntax_by_name(kernel_syntax_name)
-                    .unwrap_or_else(|| ss.find_syntax_plain_text());
-                let mut highlighter = syntect::easy::HighlightLines::new(syntax, theme);
+                let mut highlighter = syntax.language_highlighter(kernel_lang);
 
                 for line_str in source.lines() {
-                    let mut spans: Vec<Span<'static>> = Vec::new();
-                    match highlighter.highlight_line(line_str, ss) {
-                        Ok(ranges) => {
-                            for (style, text) in ranges {
-                                let fg = syntect_color_to_ratatui(style.foreground);
-                                spans.push(Span::styled(text.to_string(), Style::default().fg(fg)));
-                            }
-                        }
-                        Err(_) => {
-                            spans.push(Span::raw(line_str.to_string()));
-                        }
-                    }
-                    lines.push(Line::from(spans));
+                    lines.push(Line::from(highlight_code(
+                        line_str,
+                        &mut highlighter,
+                        tab_width,
+                    )));
                 }
             } else {
                 // Markdown/raw cells: plain text
                 for line_str in source.lines() {
-                    lines.push(Line::from(line_str.to_string()));
+                    lines.push(Line::from(text::expand_tabs(line_str, 0, tab_width).0));
                 }
             }
         }
@@ -838,73 +1332,8 @@ mod tests {
     use std::io::Write;
     use tempfile::TempDir;
 
-    #[test]
-    fn detect_syntax_by_extension() {
-        assert_eq!(detect_syntax_name(Path::new("foo.rs")), "Rust");
-        assert_eq!(detect_syntax_name(Path::new("bar.py")), "Python");
-        assert_eq!(detect_syntax_name(Path::new("baz.yml")), "YAML");
-        assert_eq!(detect_syntax_name(Path::new("config.toml")), "TOML");
-        assert_eq!(detect_syntax_name(Path::new("style.css")), "CSS");
-        assert_eq!(detect_syntax_name(Path::new("page.html")), "HTML");
-        assert_eq!(detect_syntax_name(Path::new("app.tsx")), "TypeScript");
-        assert_eq!(detect_syntax_name(Path::new("Makefile")), "Plain Text");
-        assert_eq!(detect_syntax_name(Path::new("readme.md")), "Markdown");
-    }
-
-    #[test]
-    fn detect_syntax_unknown_extension() {
-        assert_eq!(detect_syntax_name(Path::new("file.xyz")), "Plain Text");
-    }
-
-    #[test]
-    fn detect_shebang_python() {
-        let dir = TempDir::new().unwrap();
-        let path = dir.path().join("script");
-        let mut f = File::create(&path).unwrap();
-        writeln!(f, "#!/usr/bin/env python3").unwrap();
-        writeln!(f, "print('hello')").unwrap();
-        assert_eq!(detect_syntax_name(&path), "Python");
-    }
-
-    #[test]
-    fn detect_shebang_bash() {
-        let dir = TempDir::new().unwrap();
-        let path = dir.path().join("run");
-        let mut f = File::create(&path).unwrap();
-        writeln!(f, "#!/bin/bash").unwrap();
-        assert_eq!(detect_syntax_name(&path), "Bash");
-    }
-
-    #[test]
-    fn detect_shebang_sh() {
-        let dir = TempDir::new().unwrap();
-        let path = dir.path().join("run2");
-        let mut f = File::create(&path).unwrap();
-        writeln!(f, "#!/bin/sh").unwrap();
-        assert_eq!(detect_syntax_name(&path), "Bash");
-    }
-
-    #[test]
-    fn detect_no_shebang() {
-        let dir = TempDir::new().unwrap();
-        let path = dir.path().join("data");
-        let mut f = File::create(&path).unwrap();
-        writeln!(f, "just some text").unwrap();
-        assert_eq!(detect_syntax_name(&path), "Plain Text");
-    }
-
-    #[test]
-    fn load_theme_default() {
-        let theme = load_theme(None);
-        // Just verify it doesn't panic and returns something
-        assert!(!theme.scopes.is_empty() || theme.settings.background.is_some());
-    }
-
-    #[test]
-    fn load_theme_invalid_falls_back() {
-        let theme = load_theme(Some("nonexistent-theme"));
-        // Should fall back to base16-ocean.dark
-        assert!(!theme.scopes.is_empty() || theme.settings.background.is_some());
+    fn resources() -> SyntaxResources {
+        SyntaxResources::load("base16-ocean.dark", None)
     }
 
     #[test]
@@ -916,9 +1345,8 @@ mod tests {
         writeln!(f, "    println!(\"hello\");").unwrap();
         writeln!(f, "}}").unwrap();
 
-        let ss = SyntaxSet::load_defaults_newlines();
-        let theme = load_theme(None);
-        let (lines, total) = load_highlighted_content(&path, &ss, &theme);
+        let syntax = resources();
+        let (lines, total) = load_highlighted_content(&path, &syntax, 4, false);
         assert_eq!(total, 3);
         assert_eq!(lines.len(), 3);
     }
@@ -929,18 +1357,16 @@ mod tests {
         let path = dir.path().join("empty.txt");
         File::create(&path).unwrap();
 
-        let ss = SyntaxSet::load_defaults_newlines();
-        let theme = load_theme(None);
-        let (lines, total) = load_highlighted_content(&path, &ss, &theme);
+        let syntax = resources();
+        let (lines, total) = load_highlighted_content(&path, &syntax, 4, false);
         assert_eq!(total, 1);
         assert!(!lines.is_empty());
     }
 
     #[test]
     fn highlight_nonexistent_file() {
-        let ss = SyntaxSet::load_defaults_newlines();
-        let theme = load_theme(None);
-        let (lines, total) = load_highlighted_content(Path::new("/nonexistent"), &ss, &theme);
+        let syntax = resources();
+        let (lines, total) = load_highlighted_content(Path::new("/nonexistent"), &syntax, 4, false);
         assert_eq!(total, 1);
         // Should contain error message
         let text: String = lines[0].spans.iter().map(|s| s.content.as_ref()).collect();
@@ -987,9 +1413,8 @@ mod tests {
         for i in 1..=100 {
             writeln!(f, "line {}", i).unwrap();
         }
-        let ss = SyntaxSet::load_defaults_newlines();
-        let theme = load_theme(None);
-        let (lines, _) = load_head_tail_content(&path, &ss, &theme, 10, 5, ViewMode::HeadAndTail);
+        let syntax = resources();
+        let (lines, _) = load_head_tail_content(&path, &syntax, 10, 5, ViewMode::HeadAndTail, 4);
         // Should have 10 head + 1 separator + 5 tail = 16 lines
         assert_eq!(lines.len(), 16);
     }
@@ -1002,9 +1427,8 @@ mod tests {
         for i in 1..=100 {
             writeln!(f, "line {}", i).unwrap();
         }
-        let ss = SyntaxSet::load_defaults_newlines();
-        let theme = load_theme(None);
-        let (lines, _) = load_head_tail_content(&path, &ss, &theme, 10, 5, ViewMode::HeadOnly);
+        let syntax = resources();
+        let (lines, _) = load_head_tail_content(&path, &syntax, 10, 5, ViewMode::HeadOnly, 4);
         assert_eq!(lines.len(), 10);
     }
 
@@ -1016,9 +1440,8 @@ mod tests {
         for i in 1..=100 {
             writeln!(f, "line {}", i).unwrap();
         }
-        let ss = SyntaxSet::load_defaults_newlines();
-        let theme = load_theme(None);
-        let (lines, _) = load_head_tail_content(&path, &ss, &theme, 10, 5, ViewMode::TailOnly);
+        let syntax = resources();
+        let (lines, _) = load_head_tail_content(&path, &syntax, 10, 5, ViewMode::TailOnly, 4);
         assert_eq!(lines.len(), 5);
     }
 
@@ -1097,6 +1520,124 @@ mod tests {
         assert!(text.contains("Error"));
     }
 
+    // === Archive listing tests ===
+
+    #[cfg(any(feature = "archives", feature = "pdf-preview"))]
+    fn line_texts(lines: &[Line]) -> Vec<String> {
+        lines
+            .iter()
+            .map(|l| l.spans.iter().map(|s| s.content.as_ref()).collect())
+            .collect()
+    }
+
+    #[cfg(feature = "archives")]
+    #[test]
+    fn archive_listing_shows_an_indented_tree() {
+        use crate::archive::tests::{write, zip_bytes};
+        let dir = TempDir::new().unwrap();
+        // No entry for `src/` itself; the listing still shows it
+        let path = write(
+            &dir,
+            "app.zip",
+            &zip_bytes(&[
+                ("src/main.rs", &[1u8; 2048]),
+                ("README.md", b"hi"),
+                ("src/lib/", b""),
+            ]),
+        );
+        let (lines, total) = load_archive_listing(&path);
+        assert_eq!(total, lines.len());
+        let texts = line_texts(&lines);
+        assert!(texts
+            .iter()
+            .any(|t| t.contains("app.zip") && t.contains("(zip)")));
+        assert!(texts.iter().any(|t| t.contains("2 files, 1 directory")));
+        let tree: Vec<&String> = texts.iter().skip_while(|t| !t.ends_with("Name")).collect();
+        assert_eq!(tree.len(), 5);
+        assert!(tree[1].ends_with("    2 B  README.md"));
+        assert!(tree[2].ends_with("  src/"));
+        assert!(tree[3].ends_with("    lib/"));
+        assert!(tree[4].contains("2.00 KB") && tree[4].ends_with("    main.rs"));
+    }
+
+    #[cfg(feature = "archives")]
+    #[test]
+    fn archive_listing_reports_entries_past_the_cap() {
+        use crate::archive::tests::{tar_bytes, write};
+        let dir = TempDir::new().unwrap();
+        let names: Vec<String> = (0..crate::archive::MAX_ENTRIES + 3)
+            .map(|i| format!("f{i}"))
+            .collect();
+        let members: Vec<(&str, &[u8])> = names.iter().map(|n| (n.as_str(), &b""[..])).collect();
+        let path = write(&dir, "many.tar", &tar_bytes(&members));
+        let (lines, _) = load_archive_listing(&path);
+        let texts = line_texts(&lines);
+        assert!(texts.last().unwrap().contains("… and more"));
+        assert!(texts.iter().any(|t| t.contains("5000 files")));
+    }
+
+    #[cfg(feature = "archives")]
+    #[test]
+    fn corrupt_archive_shows_an_error_line() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("broken.zip");
+        File::create(&path)
+            .unwrap()
+            .write_all(b"PK not really")
+            .unwrap();
+        let (lines, _) = load_archive_listing(&path);
+        let error = lines.last().unwrap();
+        assert_eq!(error.spans[0].style.fg, Some(Color::Red));
+        assert!(line_texts(&lines)
+            .last()
+            .unwrap()
+            .contains("Error reading archive"));
+    }
+
+    #[cfg(feature = "pdf-preview")]
+    #[test]
+    fn pdf_preview_shows_info_and_text_or_falls_back_to_metadata() {
+        use crate::pdf::tests::write_pdf;
+        use lopdf::Object;
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("notes.pdf");
+        write_pdf(
+            &path,
+            &[&["Hello"], &[], &[], &[], &[]],
+            &[("Author", Object::string_literal("Ada"))],
+        );
+        let (lines, total) = load_pdf_preview(&path);
+        assert_eq!(total, lines.len());
+        let texts = line_texts(&lines);
+        assert_eq!(
+            texts,
+            vec![
+                "",
+                "  PDF: notes.pdf",
+                "  Pages: 5",
+                "  Author: Ada",
+                "",
+                "  ── Page 1 ──",
+                "  Hello",
+                "",
+                "  ── Page 2 ──",
+                "",
+                "  ── Page 3 ──",
+                "",
+                "  … 2 more pages",
+            ]
+        );
+
+        let broken = dir.path().join("broken.pdf");
+        File::create(&broken)
+            .unwrap()
+            .write_all(b"%PDF-1.4\n\x00\x01 truncated")
+            .unwrap();
+        let texts = line_texts(&load_pdf_preview(&broken).0);
+        assert!(texts.iter().any(|t| t.contains("[Binary file")));
+        assert!(texts.last().unwrap().starts_with("  PDF not read: "));
+    }
+
     // === Format size tests ===
 
     #[test]
@@ -1184,6 +1725,46 @@ mod tests {
         assert!(all_text.contains("2")); // 2 subdirs (a, b)
     }
 
+    #[test]
+    fn directory_summary_takes_walked_totals() {
+        let dir = TempDir::new().unwrap();
+        File::create(dir.path().join("file.txt")).unwrap();
+        let (mut lines, _) = load_directory_summary(dir.path());
+        lines.push(Line::from(""));
+        lines.push(Line::from("  (scan capped at 10000 entries)"));
+        let text =
+            |lines: &[Line]| -> Vec<String> { lines.iter().map(|l| l.to_string()).collect() };
+
+        let mut size = DirSize {
+            bytes: 2048,
+            unique_bytes: 2048,
+            files: 12_000,
+            dirs: 3,
+            progress: crate::dir_size::Progress::Scanning,
+        };
+        update_directory_summary(&mut lines, &size, false);
+        let shown = text(&lines);
+        assert_eq!(shown[2], "  Files: 12000");
+        assert_eq!(shown[3], "  Subdirectories: 3");
+        assert_eq!(shown[4], "  Total Size: 2.00 KB…");
+        assert_eq!(shown.len(), 7);
+
+        size.progress = crate::dir_size::Progress::Done;
+        update_directory_summary(&mut lines, &size, false);
+        let shown = text(&lines);
+        assert_eq!(shown[4], "  Total Size: 2.00 KB");
+        assert_eq!(shown.len(), 5);
+
+        // Hard links: both sizes, the configured one first
+        size.unique_bytes = 1024;
+        update_directory_summary(&mut lines, &size, false);
+        assert_eq!(text(&lines)[4], "  Total Size: 1.00 KB (2.00 KB apparent)");
+
+        let mut other = vec![Line::from("fn main() {}")];
+        update_directory_summary(&mut other, &size, false);
+        assert_eq!(text(&other), ["fn main() {}"]);
+    }
+
     // === Notebook rendering tests ===
 
     #[test]
@@ -1216,9 +1797,8 @@ mod tests {
         let mut f = File::create(&path).unwrap();
         f.write_all(notebook.as_bytes()).unwrap();
 
-        let ss = SyntaxSet::load_defaults_newlines();
-        let theme = load_theme(None);
-        let (lines, total) = load_notebook_content(&path, &ss, &theme);
+        let syntax = resources();
+        let (lines, total) = load_notebook_content(&path, &syntax, 4);
         assert!(total > 0);
         let all_text: String = lines
             .iter()
@@ -1256,9 +1836,8 @@ mod tests {
         let mut f = File::create(&path).unwrap();
         f.write_all(notebook.as_bytes()).unwrap();
 
-        let ss = SyntaxSet::load_defaults_newlines();
-        let theme = load_theme(None);
-        let (lines, _) = load_notebook_content(&path, &ss, &theme);
+        let syntax = resources();
+        let (lines, _) = load_notebook_content(&path, &syntax, 4);
         let all_text: String = lines
             .iter()
             .flat_map(|l| l.spans.iter().map(|s| s.content.as_ref()))
@@ -1274,9 +1853,8 @@ mod tests {
         let mut f = File::create(&path).unwrap();
         f.write_all(b"not json").unwrap();
 
-        let ss = SyntaxSet::load_defaults_newlines();
-        let theme = load_theme(None);
-        let (lines, total) = load_notebook_content(&path, &ss, &theme);
+        let syntax = resources();
+        let (lines, total) = load_notebook_content(&path, &syntax, 4);
         assert_eq!(total, 1);
         let text: String = lines[0].spans.iter().map(|s| s.content.as_ref()).collect();
         assert!(text.contains("Error"));
@@ -1289,9 +1867,8 @@ mod tests {
         let mut f = File::create(&path).unwrap();
         f.write_all(b"{}").unwrap();
 
-        let ss = SyntaxSet::load_defaults_newlines();
-        let theme = load_theme(None);
-        let (lines, total) = load_notebook_content(&path, &ss, &theme);
+        let syntax = resources();
+        let (lines, total) = load_notebook_content(&path, &syntax, 4);
         assert_eq!(total, 1);
         let text: String = lines[0].spans.iter().map(|s| s.content.as_ref()).collect();
         assert!(text.contains("no cells"));
@@ -1314,9 +1891,8 @@ mod tests {
         let path = dir.path().join("empty.txt");
         File::create(&path).unwrap();
 
-        let ss = SyntaxSet::load_defaults_newlines();
-        let theme = load_theme(None);
-        let (lines, total) = load_highlighted_content(&path, &ss, &theme);
+        let syntax = resources();
+        let (lines, total) = load_highlighted_content(&path, &syntax, 4, false);
         assert_eq!(total, 1);
         let text: String = lines[0].spans.iter().map(|s| s.content.as_ref()).collect();
         assert!(text.contains("empty file"));
@@ -1324,15 +1900,57 @@ mod tests {
 
     #[test]
     fn highlight_permission_denied_shows_error() {
-        let ss = SyntaxSet::load_defaults_newlines();
-        let theme = load_theme(None);
+        let syntax = resources();
         // Non-existent path simulates permission denied scenario
-        let (lines, total) = load_highlighted_content(Path::new("/nonexistent/file"), &ss, &theme);
+        let (lines, total) =
+            load_highlighted_content(Path::new("/nonexistent/file"), &syntax, 4, false);
         assert_eq!(total, 1);
         let text: String = lines[0].spans.iter().map(|s| s.content.as_ref()).collect();
         assert!(text.contains("Error"));
     }
 
+    #[test]
+    fn chunked_highlighting_matches_highlighting_in_one_go() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("long.rs");
+        // The block comment opened in the first lines spans the chunk edges
+        let mut source = String::from("fn a() {}\n/*\n");
+        for i in 0..25 {
+            source.push_str(&format!("let x{} = {};\n", i, i));
+        }
+        source.push_str("*/\nfn b() {}\n");
+        fs::write(&path, source).unwrap();
+        let syntax = resources();
+
+        let (whole, whole_total) = load_highlighted_content(&path, &syntax, 4, false);
+        let (mut lines, total, mut pending) = load_highlighted_head(&path, &syntax, 4, false, 3);
+        assert_eq!(total, whole_total);
+        assert_eq!(lines.len(), whole.len());
+        // Plain until highlighted, with the same text
+        assert_eq!(lines[10].spans.len(), 2);
+        assert_eq!(lines[10].to_string(), whole[10].to_string());
+
+        let mut chunks = 0;
+        while let Some(next) = pending.take() {
+            let (rows, rest) = next.next_chunk(&syntax, 10);
+            let end = rows.start + rows.lines.len();
+            lines.splice(rows.start..end, rows.lines);
+            pending = rest;
+            chunks += 1;
+        }
+        assert_eq!(chunks, 3);
+        assert_eq!(lines, whole);
+    }
+
+    #[test]
+    fn short_file_is_highlighted_whole() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("short.rs");
+        fs::write(&path, "fn a() {}\n").unwrap();
+        let (_, _, pending) = load_highlighted_head(&path, &resources(), 4, false, 1);
+        assert!(pending.is_none());
+    }
+
     #[test]
     fn binary_detection_empty_file_not_binary() {
         let dir = TempDir::new().unwrap();
@@ -1342,6 +1960,34 @@ mod tests {
         assert!(!is_binary_file(&path));
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn symlink_summary_shows_target_and_validity() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir(dir.path().join("real")).unwrap();
+        std::os::unix::fs::symlink("real", dir.path().join("good")).unwrap();
+        std::os::unix::fs::symlink("gone", dir.path().join("bad")).unwrap();
+        let text = |path: &Path| -> String {
+            load_symlink_summary(path)
+                .0
+                .iter()
+                .map(|line| line.to_string())
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+
+        let good = text(&dir.path().join("good"));
+        assert!(good.contains("Target: real"), "{}", good);
+        let resolved = dir.path().join("real").canonicalize().unwrap();
+        assert!(good.contains(&format!("Resolves to: {}", resolved.display())));
+        assert!(good.contains("Status: valid (directory)"));
+
+        let bad = text(&dir.path().join("bad"));
+        assert!(bad.contains("Target: gone"));
+        assert!(!bad.contains("Resolves to"));
+        assert!(bad.contains("Status: broken ("), "{}", bad);
+    }
+
     #[test]
     fn format_size_large_values() {
         assert_eq!(format_size(1024 * 1024 * 1024 * 1024), "1.00 TB");
@@ -1356,9 +2002,8 @@ mod tests {
         let mut f = File::create(&path).unwrap();
         f.write_all(notebook.as_bytes()).unwrap();
 
-        let ss = SyntaxSet::load_defaults_newlines();
-        let theme = load_theme(None);
-        let (lines, total) = load_notebook_content(&path, &ss, &theme);
+        let syntax = resources();
+        let (lines, total) = load_notebook_content(&path, &syntax, 4);
         assert!(total > 0);
         let all_text: String = lines
             .iter()
@@ -1374,4 +2019,95 @@ mod tests {
         File::create(&path).unwrap();
         assert_eq!(fast_line_count(&path).unwrap(), 0);
     }
+
+    #[test]
+    fn highlight_caps_huge_lines() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("minified.js");
+        let long = "x".repeat(MAX_PREVIEW_LINE_CHARS + 500);
+        std::fs::write(&path, &long).unwrap();
+        let syntax = resources();
+        let (lines, total) = load_highlighted_content(&path, &syntax, 4, false);
+        assert_eq!(total, 1);
+        let text: String = lines[0].spans.iter().map(|s| s.content.as_ref()).collect();
+        assert!(text.contains("[+500 chars]"));
+        assert!(text.len() < long.len());
+    }
+
+    #[test]
+    fn highlight_expands_tabs_to_stops() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("tabs.rs");
+        std::fs::write(&path, "\tlet x = 1;\n日\t// c\n").unwrap();
+        let syntax = resources();
+
+        let code = |lines: &[Line<'static>], i: usize| -> String {
+            // Skip the line-number gutter span
+            lines[i].spans[1..]
+                .iter()
+                .map(|s| s.content.as_ref())
+                .collect()
+        };
+        let (lines, _) = load_highlighted_content(&path, &syntax, 4, false);
+        assert_eq!(code(&lines, 0), "    let x = 1;");
+        assert_eq!(code(&lines, 1), "日  // c");
+        let (lines, _) = load_highlighted_content(&path, &syntax, 8, false);
+        assert_eq!(code(&lines, 0), "        let x = 1;");
+        assert_eq!(code(&lines, 1), "日      // c");
+    }
+
+    #[test]
+    fn front_matter_card_replaces_the_block() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("post.md");
+        // The body's own `---` rule is not part of the block
+        std::fs::write(&path, "---\ntitle: Post\n---\n# Post\n---\nend\n").unwrap();
+        let syntax = resources();
+        let text = |line: &Line<'static>| -> String {
+            line.spans.iter().map(|s| s.content.as_ref()).collect()
+        };
+
+        let (lines, total) = load_highlighted_content(&path, &syntax, 4, false);
+        assert_eq!(total, 6);
+        let shown: Vec<String> = lines.iter().map(text).collect();
+        assert_eq!(shown.len(), 6);
+        assert!(shown[0].starts_with("╭─ Front matter"));
+        assert_eq!(shown[1], "│ title  Post");
+        assert_eq!(shown[2], "╰─");
+        assert_eq!(shown[3], "4 │ # Post");
+        assert_eq!(shown[4], "5 │ ---");
+        assert_eq!(shown[5], "6 │ end");
+
+        let (lines, total) = load_highlighted_content(&path, &syntax, 4, true);
+        assert_eq!(total, 6);
+        assert_eq!(text(&lines[0]), "1 │ ---");
+    }
+
+    #[test]
+    fn malformed_front_matter_is_shown_raw() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("post.md");
+        std::fs::write(&path, "---\ntitle: [oops\n---\nbody\n").unwrap();
+        let syntax = resources();
+        let (lines, total) = load_highlighted_content(&path, &syntax, 4, false);
+        assert_eq!(total, 4);
+        assert_eq!(lines.len(), 4);
+        let first: String = lines[0].spans.iter().map(|s| s.content.as_ref()).collect();
+        assert_eq!(first, "1 │ ---");
+    }
+
+    #[test]
+    fn cap_line_chars_respects_char_boundaries() {
+        assert_eq!(cap_line_chars("short", 10), ("short", 0));
+        assert_eq!(cap_line_chars("héllo", 2), ("hé", 3));
+    }
+
+    #[test]
+    fn approx_lines_bytes_grows_with_content() {
+        let small = vec![Line::from("ab")];
+        let large = vec![Line::from("ab"), Line::from("a".repeat(1000))];
+        assert!(approx_lines_bytes(&small) > 0);
+        assert!(approx_lines_bytes(&large) > approx_lines_bytes(&small) + 1000);
+        assert_eq!(approx_lines_bytes(&[]), 0);
+    }
 }
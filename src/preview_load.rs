@@ -0,0 +1,773 @@
+//! Preview loads off the UI thread, with cancel, retry and timeout.
+//!
+//! Every file or directory preview is read by a [`PreviewLoader`] on its
+//! own thread. `App::update_preview` waits up to [`INLINE_WAIT`] for it, so
+//! a local file still shows up in the same frame; past that the preview
+//! shows a placeholder, and the load thread sends `Event::PreviewReady` for
+//! the main loop to pick the result up with `App::poll_preview_load`. A file
+//! on a dead network mount can block its thread forever, so the user gets
+//! a way out: Esc or `x` in the preview cancels, `r` retries, and
+//! `preview.load_timeout_secs` cancels on its own.
+//!
+//! A held `j` moves the selection every frame, and reading and
+//! highlighting each file it passes would only queue work for previews
+//! nobody sees. A load asked for within [`DEBOUNCE`] of the one before
+//! waits, behind the placeholder, until the selection has stayed put that
+//! long; the tick then starts it without waiting inline.
+//!
+//! Highlighting a long source file takes longer than reading it, so a
+//! file's first [`FIRST_HIGHLIGHT_LINES`] lines are highlighted by the load
+//! and the rest are shown as plain text. The load's thread then carries
+//! on from where it stopped, parse state and all (syntect's can't move to
+//! another thread), and hands back
+//! [`HIGHLIGHT_CHUNK_LINES`] rows at a time (`Event::PreviewHighlighted`)
+//! for `App::poll_preview_highlight` to put over the plain ones. The
+//! preview is cached once the last chunk is in.
+//!
+//! Cancelling raises the load's flag (the loader checks it between reading
+//! and highlighting) and drops its channel. Each load has its own
+//! generation, and only the running one's results are accepted, so a load
+//! that finishes after being cancelled or replaced can't bring its file
+//! back over what is on screen.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, TryRecvError};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use ratatui::text::Line;
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::app::ViewMode;
+use crate::event::Event;
+use crate::fs::tree::SpecialFile;
+use crate::preview_cache::CacheKey;
+use crate::preview_content::{self, HighlightedRows, PendingHighlight};
+use crate::syntax::LazySyntax;
+use crate::whitespace::{self, FinalNewline};
+
+/// How long `update_preview` blocks for a load before showing the
+/// placeholder.
+pub const INLINE_WAIT: Duration = Duration::from_millis(50);
+
+/// A load asked for this soon after the previous one waits until the
+/// selection has settled for this long.
+pub const DEBOUNCE: Duration = Duration::from_millis(80);
+
+/// Lines a load highlights before the preview shows: a screenful, with
+/// room to scroll.
+pub const FIRST_HIGHLIGHT_LINES: usize = 200;
+
+/// Lines highlighted per chunk after the preview shows.
+pub const HIGHLIGHT_CHUNK_LINES: usize = 1_000;
+
+/// The placeholder starts counting seconds once a load has run this long.
+pub const SHOW_ELAPSED_AFTER: Duration = Duration::from_secs(2);
+
+/// Everything a loader needs to render one preview.
+#[derive(Clone)]
+pub struct LoadRequest {
+    pub path: PathBuf,
+    pub is_dir: bool,
+    pub tab_width: usize,
+    pub head_lines: usize,
+    pub tail_lines: usize,
+    /// Files above this size get the head+tail view.
+    pub max_full_bytes: u64,
+    /// Show Markdown front matter as raw YAML rather than a card.
+    pub raw_front_matter: bool,
+    /// Key of a cached preview of `path`, reused if the file is unchanged.
+    pub cached: Option<CacheKey>,
+    /// Only loaded by the branches that highlight.
+    pub syntax: Arc<LazySyntax>,
+}
+
+/// Scroll and tab width carried over when the same path is reloaded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct KeptView {
+    pub scroll: usize,
+    pub h_offset: usize,
+    pub tab_width: Option<usize>,
+}
+
+/// A finished load.
+#[derive(Debug)]
+pub struct Loaded {
+    pub content: LoadedContent,
+    /// Head+tail view of a file over `max_full_bytes`.
+    pub is_large: bool,
+    pub final_newline: Option<FinalNewline>,
+    /// Some lines were left plain; highlighted rows for them follow.
+    pub partial: bool,
+}
+
+#[derive(Debug)]
+pub enum LoadedContent {
+    /// The cached preview under this key is still current.
+    Cached(CacheKey),
+    /// Freshly rendered lines; `cache_key` is set for plain text files,
+    /// the only previews that are cached.
+    Rendered {
+        lines: Vec<Line<'static>>,
+        total: usize,
+        cache_key: Option<CacheKey>,
+    },
+}
+
+impl Loaded {
+    fn rendered((lines, total): (Vec<Line<'static>>, usize)) -> Self {
+        Self {
+            content: LoadedContent::Rendered {
+                lines,
+                total,
+                cache_key: None,
+            },
+            is_large: false,
+            final_newline: None,
+            partial: false,
+        }
+    }
+}
+
+/// Reads and renders previews. Runs on a load thread.
+pub trait PreviewLoader: Send + Sync {
+    /// Render the preview for `request`, or `None` once `cancel` is raised.
+    /// Lines left plain come back alongside, for the same thread to
+    /// highlight once the preview shows.
+    fn load(
+        &self,
+        request: &LoadRequest,
+        cancel: &AtomicBool,
+    ) -> Option<(Loaded, Option<PendingHighlight>)>;
+}
+
+/// Loads from the filesystem.
+pub struct SystemLoader;
+
+impl PreviewLoader for SystemLoader {
+    fn load(
+        &self,
+        request: &LoadRequest,
+        cancel: &AtomicBool,
+    ) -> Option<(Loaded, Option<PendingHighlight>)> {
+        let path = &request.path;
+        let cancelled = || cancel.load(Ordering::SeqCst);
+        if request.is_dir {
+            let summary = preview_content::load_directory_summary(path);
+            return (!cancelled()).then(|| (Loaded::rendered(summary), None));
+        }
+        // Rows whose stat is still pending reach here unchecked
+        if let Some(special) = SpecialFile::at(path) {
+            let summary = preview_content::load_special_summary(path, special);
+            return (!cancelled()).then(|| (Loaded::rendered(summary), None));
+        }
+        if path.extension().and_then(|e| e.to_str()) == Some("ipynb") {
+            let syntax = request.syntax.get();
+            let notebook = preview_content::load_notebook_content(path, syntax, request.tab_width);
+            return (!cancelled()).then(|| (Loaded::rendered(notebook), None));
+        }
+        #[cfg(feature = "archives")]
+        if preview_content::is_listable_archive(path) {
+            let listing = preview_content::load_archive_listing(path);
+            return (!cancelled()).then(|| (Loaded::rendered(listing), None));
+        }
+        #[cfg(feature = "pdf-preview")]
+        if crate::pdf::is_pdf(path) {
+            let document = preview_content::load_pdf_preview(path);
+            return (!cancelled()).then(|| (Loaded::rendered(document), None));
+        }
+        if preview_content::is_binary_file(path) {
+            let metadata = preview_content::load_binary_metadata(path);
+            return (!cancelled()).then(|| (Loaded::rendered(metadata), None));
+        }
+
+        let key = CacheKey::for_path(path, request.tab_width, request.raw_front_matter);
+        // Reads only the tail, and never writes: the preview is read-only.
+        let final_newline = whitespace::file_final_newline(path);
+        if cancelled() {
+            return None;
+        }
+        let size = key.as_ref().map(|k| k.size).unwrap_or(0);
+        if size > request.max_full_bytes {
+            let syntax = request.syntax.get();
+            let (lines, total) = preview_content::load_head_tail_content(
+                path,
+                syntax,
+                request.head_lines,
+                request.tail_lines,
+                ViewMode::HeadAndTail,
+                request.tab_width,
+            );
+            return (!cancelled()).then_some((
+                Loaded {
+                    content: LoadedContent::Rendered {
+                        lines,
+                        total,
+                        cache_key: None,
+                    },
+                    is_large: true,
+                    final_newline,
+                    partial: false,
+                },
+                None,
+            ));
+        }
+        let (content, pending) = match key {
+            Some(key) if request.cached.as_ref() == Some(&key) => {
+                (LoadedContent::Cached(key), None)
+            }
+            key => {
+                let syntax = request.syntax.get();
+                let (lines, total, pending) = preview_content::load_highlighted_head(
+                    path,
+                    syntax,
+                    request.tab_width,
+                    request.raw_front_matter,
+                    FIRST_HIGHLIGHT_LINES,
+                );
+                let content = LoadedContent::Rendered {
+                    lines,
+                    total,
+                    cache_key: key,
+                };
+                (content, pending)
+            }
+        };
+        (!cancelled()).then_some((
+            Loaded {
+                content,
+                is_large: false,
+                final_newline,
+                partial: pending.is_some(),
+            },
+            pending,
+        ))
+    }
+}
+
+/// Why a load stopped without a result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    /// Esc or `x` in the preview.
+    Cancelled,
+    /// Ran past `preview.load_timeout_secs`.
+    TimedOut(Duration),
+}
+
+struct RunningLoad {
+    generation: u64,
+    request: LoadRequest,
+    view: KeptView,
+    cancel: Arc<AtomicBool>,
+    rx: mpsc::Receiver<(u64, Option<Loaded>)>,
+    /// Rows highlighted after the result, if it was partial.
+    chunks: mpsc::Receiver<(HighlightedRows, bool)>,
+    started: Instant,
+}
+
+struct StoppedLoad {
+    request: LoadRequest,
+    view: KeptView,
+    reason: StopReason,
+}
+
+/// Highlighting of the rest of a finished load's file.
+struct Highlighting {
+    generation: u64,
+    path: PathBuf,
+    cancel: Arc<AtomicBool>,
+    /// Each chunk, and whether it is the last.
+    rx: mpsc::Receiver<(HighlightedRows, bool)>,
+    /// Held back from the load: the preview is only cached once whole.
+    cache_key: Option<CacheKey>,
+}
+
+/// Rows highlighted since the last `take_highlighted`.
+#[derive(Default)]
+pub struct Highlighted {
+    pub rows: Vec<HighlightedRows>,
+    /// Key to cache the preview under, once the last rows are in.
+    pub cache_key: Option<CacheKey>,
+}
+
+/// A load held back while the selection is still moving.
+struct DeferredLoad {
+    loader: Arc<dyn PreviewLoader>,
+    request: LoadRequest,
+    view: KeptView,
+}
+
+/// What `poll` found.
+pub enum LoadPoll {
+    /// No load running or stopped.
+    Idle,
+    /// Still running, deferred, or stopped and waiting for a retry: the
+    /// placeholder is current.
+    Waiting,
+    /// Finished just now; the caller shows it.
+    Finished(Box<(LoadRequest, KeptView, Loaded)>),
+}
+
+/// The preview load in progress, or the last one that was stopped.
+pub struct PreviewLoadState {
+    /// Generation of the newest load; results of any other are dropped.
+    generation: u64,
+    running: Option<RunningLoad>,
+    stopped: Option<StoppedLoad>,
+    deferred: Option<DeferredLoad>,
+    highlighting: Option<Highlighting>,
+    /// When a load was last asked for, deferred or not.
+    last_start: Option<Instant>,
+    /// How long `start` blocks for a result (see [`INLINE_WAIT`]).
+    pub inline_wait: Duration,
+    /// How long the selection has to settle after a load before the next
+    /// one starts (see [`DEBOUNCE`]).
+    pub debounce: Duration,
+    /// Where load threads send `Event::PreviewReady` and highlight threads
+    /// `Event::PreviewHighlighted`; without it results wait for the tick.
+    pub notify: Option<UnboundedSender<Event>>,
+}
+
+impl Default for PreviewLoadState {
+    fn default() -> Self {
+        Self {
+            generation: 0,
+            running: None,
+            stopped: None,
+            deferred: None,
+            highlighting: None,
+            last_start: None,
+            // Tests see real loads finish inline, however slow the machine;
+            // the ones about stuck loads set this to zero.
+            inline_wait: if cfg!(test) {
+                Duration::from_secs(30)
+            } else {
+                INLINE_WAIT
+            },
+            // Tests select one file after another at full speed
+            debounce: if cfg!(test) { Duration::ZERO } else { DEBOUNCE },
+            notify: None,
+        }
+    }
+}
+
+impl PreviewLoadState {
+    /// Start loading `request` on a new thread, dropping any earlier load,
+    /// and wait up to `inline_wait` for it. Returns the result if it came
+    /// in time; otherwise the load keeps running for `poll` to collect.
+    /// Within `debounce` of the previous load, it is only started by a
+    /// later `poll`, once the selection has settled.
+    pub fn start(
+        &mut self,
+        loader: Arc<dyn PreviewLoader>,
+        request: LoadRequest,
+        view: KeptView,
+        now: Instant,
+    ) -> Option<(LoadRequest, KeptView, Loaded)> {
+        self.abandon();
+        let hurried = self
+            .last_start
+            .is_some_and(|last| now.duration_since(last) < self.debounce);
+        self.last_start = Some(now);
+        if hurried {
+            self.deferred = Some(DeferredLoad {
+                loader,
+                request,
+                view,
+            });
+            return None;
+        }
+        let running = self.spawn(loader, request, view, now);
+        match running.rx.recv_timeout(self.inline_wait) {
+            Ok((_, Some(loaded))) => return Some(self.finish(running, loaded)),
+            Ok((_, None)) | Err(RecvTimeoutError::Disconnected) => {
+                self.stop(running, StopReason::Cancelled);
+            }
+            Err(RecvTimeoutError::Timeout) => self.running = Some(running),
+        }
+        None
+    }
+
+    /// Run the loader for `request` on its own thread as the newest load.
+    fn spawn(
+        &mut self,
+        loader: Arc<dyn PreviewLoader>,
+        request: LoadRequest,
+        view: KeptView,
+        now: Instant,
+    ) -> RunningLoad {
+        self.generation += 1;
+        let generation = self.generation;
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (tx, rx) = mpsc::channel();
+        let (chunk_tx, chunks) = mpsc::channel();
+        {
+            let request = request.clone();
+            let cancel = Arc::clone(&cancel);
+            let notify = self.notify.clone();
+            std::thread::spawn(move || {
+                let (loaded, pending) = match loader.load(&request, &cancel) {
+                    Some((loaded, pending)) => (Some(loaded), pending),
+                    None => (None, None),
+                };
+                // The receiver is gone once the load was dropped
+                if tx.send((generation, loaded)).is_err() {
+                    return;
+                }
+                if let Some(notify) = &notify {
+                    let _ = notify.send(Event::PreviewReady { generation });
+                }
+                let syntax = request.syntax.get();
+                let mut pending = pending;
+                while let Some(next) = pending.take() {
+                    if cancel.load(Ordering::SeqCst) {
+                        return;
+                    }
+                    let (rows, rest) = next.next_chunk(syntax, HIGHLIGHT_CHUNK_LINES);
+                    if chunk_tx.send((rows, rest.is_none())).is_err() {
+                        return;
+                    }
+                    if let Some(notify) = &notify {
+                        let _ = notify.send(Event::PreviewHighlighted { generation });
+                    }
+                    pending = rest;
+                }
+            });
+        }
+        RunningLoad {
+            generation,
+            request,
+            view,
+            cancel,
+            rx,
+            chunks,
+            started: now,
+        }
+    }
+
+    /// Generation of the newest load started.
+    #[cfg(test)]
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Hand over a finished load, keeping hold of the rows still to be
+    /// highlighted if it was partial.
+    fn finish(
+        &mut self,
+        running: RunningLoad,
+        mut loaded: Loaded,
+    ) -> (LoadRequest, KeptView, Loaded) {
+        if loaded.partial {
+            let cache_key = match &mut loaded.content {
+                LoadedContent::Rendered { cache_key, .. } => cache_key.take(),
+                LoadedContent::Cached(_) => None,
+            };
+            self.highlighting = Some(Highlighting {
+                generation: running.generation,
+                path: running.request.path.clone(),
+                cancel: Arc::clone(&running.cancel),
+                rx: running.chunks,
+                cache_key,
+            });
+        }
+        (running.request, running.view, loaded)
+    }
+
+    /// Whether load `generation` is still being highlighted.
+    pub fn is_highlighting(&self, generation: u64) -> bool {
+        self.highlighting
+            .as_ref()
+            .is_some_and(|highlighting| highlighting.generation == generation)
+    }
+
+    /// Path of the preview being highlighted.
+    pub fn highlighting_path(&self) -> Option<&Path> {
+        self.highlighting
+            .as_ref()
+            .map(|highlighting| highlighting.path.as_path())
+    }
+
+    /// Collect the chunks highlighted so far.
+    pub fn take_highlighted(&mut self) -> Highlighted {
+        let mut highlighted = Highlighted::default();
+        let Some(highlighting) = &mut self.highlighting else {
+            return highlighted;
+        };
+        loop {
+            match highlighting.rx.try_recv() {
+                Ok((rows, last)) => {
+                    highlighted.rows.push(rows);
+                    if last {
+                        highlighted.cache_key = highlighting.cache_key.take();
+                        self.highlighting = None;
+                        break;
+                    }
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.highlighting = None;
+                    break;
+                }
+            }
+        }
+        highlighted
+    }
+
+    /// Whether load `generation` is the one running, whose result the
+    /// next `poll` collects.
+    pub fn is_running(&self, generation: u64) -> bool {
+        self.running
+            .as_ref()
+            .is_some_and(|running| running.generation == generation)
+    }
+
+    /// Collect a finished load, or stop one that ran past `timeout`.
+    /// A deferred load is started here once the selection has settled.
+    pub fn poll(&mut self, now: Instant, timeout: Option<Duration>) -> LoadPoll {
+        if let Some(deferred) = self.deferred.take() {
+            let settled = self
+                .last_start
+                .is_none_or(|last| now.duration_since(last) >= self.debounce);
+            if settled {
+                let DeferredLoad {
+                    loader,
+                    request,
+                    view,
+                } = deferred;
+                self.running = Some(self.spawn(loader, request, view, now));
+            } else {
+                self.deferred = Some(deferred);
+            }
+            return LoadPoll::Waiting;
+        }
+        let running = match self.running.take() {
+            Some(running) => running,
+            None if self.stopped.is_some() => return LoadPoll::Waiting,
+            None => return LoadPoll::Idle,
+        };
+        match running.rx.try_recv() {
+            Ok((generation, Some(loaded))) if generation == running.generation => {
+                LoadPoll::Finished(Box::new(self.finish(running, loaded)))
+            }
+            Ok(_) | Err(TryRecvError::Disconnected) => {
+                self.stop(running, StopReason::Cancelled);
+                LoadPoll::Waiting
+            }
+            Err(TryRecvError::Empty) => {
+                match timeout {
+                    Some(limit) if now.duration_since(running.started) >= limit => {
+                        self.stop(running, StopReason::TimedOut(limit));
+                    }
+                    _ => self.running = Some(running),
+                }
+                LoadPoll::Waiting
+            }
+        }
+    }
+
+    /// Cancel the running load, keeping it for a retry. Returns `false`
+    /// when nothing was loading.
+    pub fn cancel(&mut self) -> bool {
+        if let Some(deferred) = self.deferred.take() {
+            self.stopped = Some(StoppedLoad {
+                request: deferred.request,
+                view: deferred.view,
+                reason: StopReason::Cancelled,
+            });
+            return true;
+        }
+        match self.running.take() {
+            Some(running) => {
+                self.stop(running, StopReason::Cancelled);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Take the stopped load to start it again.
+    pub fn take_retry(&mut self) -> Option<(LoadRequest, KeptView)> {
+        self.stopped
+            .take()
+            .map(|stopped| (stopped.request, stopped.view))
+    }
+
+    /// Drop the running or stopped load without keeping it, e.g. when the
+    /// selection moves on, and stop highlighting the last one.
+    pub fn abandon(&mut self) {
+        if let Some(running) = self.running.take() {
+            running.cancel.store(true, Ordering::SeqCst);
+        }
+        if let Some(highlighting) = self.highlighting.take() {
+            highlighting.cancel.store(true, Ordering::SeqCst);
+        }
+        self.stopped = None;
+        self.deferred = None;
+    }
+
+    /// Path of the running, deferred or stopped load.
+    pub fn path(&self) -> Option<&Path> {
+        match (&self.running, &self.deferred, &self.stopped) {
+            (Some(running), _, _) => Some(&running.request.path),
+            (None, Some(deferred), _) => Some(&deferred.request.path),
+            (None, None, Some(stopped)) => Some(&stopped.request.path),
+            (None, None, None) => None,
+        }
+    }
+
+    /// Whether a load is running or waiting for the selection to settle.
+    pub fn is_loading(&self) -> bool {
+        self.running.is_some() || self.deferred.is_some()
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stopped.is_some()
+    }
+
+    /// Placeholder text for the preview while a load is running or stopped.
+    pub fn placeholder(&self, now: Instant) -> Option<String> {
+        if self.deferred.is_some() {
+            return Some("Loading…".to_string());
+        }
+        if let Some(running) = &self.running {
+            let elapsed = now.duration_since(running.started);
+            return Some(if elapsed < SHOW_ELAPSED_AFTER {
+                "Loading…".to_string()
+            } else {
+                format!(
+                    "Loading… {}s — Esc in the preview cancels",
+                    elapsed.as_secs()
+                )
+            });
+        }
+        self.stopped.as_ref().map(|stopped| match stopped.reason {
+            StopReason::Cancelled => "Load cancelled — press r to retry".to_string(),
+            StopReason::TimedOut(limit) => format!(
+                "Load timed out after {}s — press r to retry",
+                limit.as_secs()
+            ),
+        })
+    }
+
+    fn stop(&mut self, running: RunningLoad, reason: StopReason) {
+        running.cancel.store(true, Ordering::SeqCst);
+        self.stopped = Some(StoppedLoad {
+            request: running.request,
+            view: running.view,
+            reason,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn request(path: PathBuf, cached: Option<CacheKey>) -> LoadRequest {
+        LoadRequest {
+            path,
+            is_dir: false,
+            tab_width: 4,
+            head_lines: 10,
+            tail_lines: 5,
+            max_full_bytes: 1_048_576,
+            raw_front_matter: false,
+            cached,
+            syntax: Arc::new(LazySyntax::new("base16-ocean.dark", None)),
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn fifos_are_described_without_being_opened() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("pipe");
+        if !crate::fs::tree::mkfifo(&path) {
+            return;
+        }
+        // Opening the FIFO would block with no writer
+        let (loaded, _) = SystemLoader
+            .load(&request(path.clone(), None), &AtomicBool::new(false))
+            .unwrap();
+        // Prefetch and content search skip what counts as binary
+        assert!(preview_content::is_binary_file(&path));
+        match loaded.content {
+            LoadedContent::Rendered {
+                lines, cache_key, ..
+            } => {
+                let text: String = lines.iter().map(crate::wrap::line_text).collect();
+                assert!(text.contains("named pipe — contents not previewable"));
+                assert!(cache_key.is_none());
+            }
+            LoadedContent::Cached(_) => panic!("nothing to cache"),
+        }
+    }
+
+    #[test]
+    fn unchanged_file_reuses_the_cached_preview() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("a.rs");
+        std::fs::write(&path, "fn a() {}\n").unwrap();
+        let key = CacheKey::for_path(&path, 4, false).unwrap();
+        let cancel = AtomicBool::new(false);
+
+        let (loaded, _) = SystemLoader
+            .load(&request(path.clone(), Some(key.clone())), &cancel)
+            .unwrap();
+        assert!(matches!(loaded.content, LoadedContent::Cached(k) if k == key));
+
+        std::fs::write(&path, "fn a() { changed(); }\n").unwrap();
+        let (loaded, _) = SystemLoader
+            .load(&request(path, Some(key)), &cancel)
+            .unwrap();
+        match loaded.content {
+            LoadedContent::Rendered {
+                total, cache_key, ..
+            } => {
+                assert_eq!(total, 1);
+                assert!(cache_key.is_some());
+            }
+            LoadedContent::Cached(_) => panic!("stale cache entry reused"),
+        }
+        assert_eq!(loaded.final_newline, Some(FinalNewline::Single));
+    }
+
+    #[test]
+    fn cancelled_load_returns_nothing() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("a.txt");
+        std::fs::write(&path, "hello\n").unwrap();
+        let cancel = AtomicBool::new(true);
+        assert!(SystemLoader.load(&request(path, None), &cancel).is_none());
+        let dir_request = LoadRequest {
+            is_dir: true,
+            ..request(dir.path().to_path_buf(), None)
+        };
+        assert!(SystemLoader.load(&dir_request, &cancel).is_none());
+    }
+
+    #[cfg(feature = "archives")]
+    #[test]
+    fn archives_are_listed_instead_of_shown_as_binary() {
+        let dir = TempDir::new().unwrap();
+        let path = crate::archive::tests::write(
+            &dir,
+            "a.tar",
+            &crate::archive::tests::tar_bytes(&[("inner.txt", b"x")]),
+        );
+        let cancel = AtomicBool::new(false);
+        let (loaded, _) = SystemLoader.load(&request(path, None), &cancel).unwrap();
+        let LoadedContent::Rendered { lines, .. } = loaded.content else {
+            panic!("archive listing is never cached");
+        };
+        let text: String = lines
+            .iter()
+            .flat_map(|l| l.spans.iter().map(|s| s.content.as_ref()))
+            .collect();
+        assert!(text.contains("inner.txt"));
+        assert!(!text.contains("Binary file"));
+    }
+}
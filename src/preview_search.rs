@@ -0,0 +1,309 @@
+//! Text search inside the preview (`/` with the preview focused).
+//!
+//! Matching runs on the plain text of each preview row (its spans joined
+//! up, minus the line-number gutter), not on the styled spans, so a match
+//! can cross the token boundaries syntax highlighting splits a line at.
+//! The widget then re-splits the spans of the rows it draws at the match
+//! boundaries to highlight them.
+
+use std::path::PathBuf;
+
+use ratatui::style::Style;
+use ratatui::text::{Line, Span};
+
+use crate::preview_content::LINE_NUMBER_SEP;
+use crate::text;
+
+/// One occurrence of the query: byte range `start..end` of row `row`'s
+/// plain text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PreviewMatch {
+    pub row: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Query and matches of the preview search.
+#[derive(Debug, Default)]
+pub struct PreviewSearch {
+    pub query: String,
+    /// Whether keys currently go into `query`.
+    pub typing: bool,
+    /// File the search was started in; moving to another file ends it.
+    pub path: Option<PathBuf>,
+    /// Top row of the view when the search opened; typing jumps to the
+    /// first match from there.
+    pub origin: usize,
+    /// Matches in row order.
+    pub matches: Vec<PreviewMatch>,
+    /// Index into `matches` of the one `n`/`N` last landed on.
+    pub current: usize,
+}
+
+impl PreviewSearch {
+    /// Whether the query bar is shown (being typed in, or a query kept).
+    pub fn is_active(&self) -> bool {
+        self.typing || !self.query.is_empty()
+    }
+
+    /// Find every case-insensitive occurrence of the query in `rows`.
+    pub fn find(&mut self, rows: &[String]) {
+        self.matches.clear();
+        if !self.query.is_empty() {
+            for (row, line) in rows.iter().enumerate() {
+                let mut from = code_start(line);
+                while let Some((start, end)) = text::find_ignore_case(&line[from..], &self.query) {
+                    self.matches.push(PreviewMatch {
+                        row,
+                        start: from + start,
+                        end: from + end,
+                    });
+                    from += end;
+                }
+            }
+        }
+        self.current = self.current.min(self.matches.len().saturating_sub(1));
+    }
+
+    pub fn current(&self) -> Option<PreviewMatch> {
+        self.matches.get(self.current).copied()
+    }
+
+    /// Make the first match on or after `row` current, wrapping to the
+    /// first match.
+    pub fn select_from(&mut self, row: usize) -> Option<PreviewMatch> {
+        let at = self.matches.partition_point(|m| m.row < row);
+        self.current = if at < self.matches.len() { at } else { 0 };
+        self.current()
+    }
+
+    /// Move `count` matches forward or back. Returns the match landed on
+    /// and whether the move wrapped around the end of the preview.
+    pub fn step(&mut self, forward: bool, count: usize) -> Option<(PreviewMatch, bool)> {
+        let len = self.matches.len();
+        if len == 0 {
+            return None;
+        }
+        let count = count % len;
+        let (next, wrapped) = if forward {
+            let next = self.current + count;
+            (next % len, next >= len)
+        } else {
+            ((self.current + len - count) % len, count > self.current)
+        };
+        self.current = next;
+        self.current().map(|m| (m, wrapped))
+    }
+
+    /// Matches on `row` as `(start, end, is_current)` byte ranges.
+    pub fn on_row(&self, row: usize) -> Vec<(usize, usize, bool)> {
+        let first = self.matches.partition_point(|m| m.row < row);
+        self.matches[first..]
+            .iter()
+            .take_while(|m| m.row == row)
+            .enumerate()
+            .map(|(i, m)| (m.start, m.end, first + i == self.current))
+            .collect()
+    }
+
+    /// "(3/12)", "(no matches)", or nothing while the query is empty.
+    pub fn counter(&self) -> String {
+        if self.query.is_empty() {
+            String::new()
+        } else if self.matches.is_empty() {
+            "(no matches)".to_string()
+        } else {
+            format!("({}/{})", self.current + 1, self.matches.len())
+        }
+    }
+}
+
+/// Plain text of a preview row.
+pub fn row_text(line: &Line<'_>) -> String {
+    line.spans
+        .iter()
+        .map(|span| span.content.as_ref())
+        .collect()
+}
+
+/// Byte offset where the file text of a row starts: past the
+/// line-number gutter of code lines, 0 for anything else.
+fn code_start(row: &str) -> usize {
+    match row.split_once(LINE_NUMBER_SEP) {
+        Some((number, _)) if number.trim_start().bytes().all(|b| b.is_ascii_digit()) => {
+            number.len() + LINE_NUMBER_SEP.len()
+        }
+        _ => 0,
+    }
+}
+
+/// `line` with the byte ranges from [`PreviewSearch::on_row`] restyled:
+/// `hit` patched onto every match, `current` onto the current one.
+pub fn mark(
+    line: &Line<'_>,
+    ranges: &[(usize, usize, bool)],
+    hit: Style,
+    current: Style,
+) -> Line<'static> {
+    let mut spans = Vec::new();
+    let mut offset = 0;
+    for span in &line.spans {
+        let content = span.content.as_ref();
+        let end = offset + content.len();
+        let mut cut = offset;
+        for &(start, stop, is_current) in ranges {
+            let (from, to) = (start.max(cut), stop.min(end));
+            if from >= to {
+                continue;
+            }
+            if from > cut {
+                spans.push(Span::styled(
+                    content[cut - offset..from - offset].to_string(),
+                    span.style,
+                ));
+            }
+            let style = if is_current { current } else { hit };
+            spans.push(Span::styled(
+                content[from - offset..to - offset].to_string(),
+                span.style.patch(style),
+            ));
+            cut = to;
+        }
+        if cut < end {
+            spans.push(Span::styled(
+                content[cut - offset..].to_string(),
+                span.style,
+            ));
+        }
+        offset = end;
+    }
+    Line::from(spans).style(line.style)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::style::{Color, Modifier};
+
+    fn rows(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|l| l.to_string()).collect()
+    }
+
+    fn search(query: &str, lines: &[&str]) -> PreviewSearch {
+        let mut search = PreviewSearch {
+            query: query.to_string(),
+            ..Default::default()
+        };
+        search.find(&rows(lines));
+        search
+    }
+
+    #[test]
+    fn finds_every_match_past_the_line_number_gutter() {
+        let fns = search(
+            "Fn",
+            &[
+                " 9 │ fn main() { fn_x(); }",
+                "10 │ let fn9 = 10;",
+                "plain fn",
+            ],
+        );
+        let found: Vec<_> = fns
+            .matches
+            .iter()
+            .map(|m| (m.row, m.start, m.end))
+            .collect();
+        // The gutter is skipped, matching ignores case
+        let gutter = " 9 │ ".len();
+        assert_eq!(
+            found,
+            vec![
+                (0, gutter, gutter + 2),
+                (0, gutter + 12, gutter + 14),
+                (1, "10 │ let ".len(), "10 │ let fn".len()),
+                (2, 6, 8),
+            ]
+        );
+        assert_eq!(fns.counter(), "(1/4)");
+
+        // Digits in the gutter never match
+        assert!(search("10", &[" 9 │ x", "10 │ y"]).matches.is_empty());
+        assert_eq!(search("zz", &["abc"]).counter(), "(no matches)");
+        assert_eq!(search("", &["abc"]).counter(), "");
+    }
+
+    #[test]
+    fn step_wraps_both_ways_and_select_from_starts_at_a_row() {
+        let mut search = search("x", &["x", "-", "x x", "-", "x"]);
+        assert_eq!(search.matches.len(), 4);
+
+        assert_eq!(search.select_from(1).map(|m| m.row), Some(2));
+        assert_eq!(search.current, 1);
+        assert_eq!(
+            search.step(true, 2).map(|(m, w)| (m.row, w)),
+            Some((4, false))
+        );
+        assert_eq!(
+            search.step(true, 1).map(|(m, w)| (m.row, w)),
+            Some((0, true))
+        );
+        assert_eq!(
+            search.step(false, 1).map(|(m, w)| (m.row, w)),
+            Some((4, true))
+        );
+        assert_eq!(
+            search.step(false, 2).map(|(m, w)| (m.start, w)),
+            Some((0, false))
+        );
+        // Past the last match wraps to the first
+        assert_eq!(search.select_from(9).map(|m| m.row), Some(0));
+
+        assert_eq!(search.on_row(2), vec![(0, 1, false), (2, 3, false)]);
+        search.step(true, 2);
+        assert_eq!(search.on_row(2), vec![(0, 1, false), (2, 3, true)]);
+        assert!(search.on_row(1).is_empty());
+
+        assert_eq!(PreviewSearch::default().step(true, 1), None);
+    }
+
+    #[test]
+    fn mark_splits_spans_at_match_boundaries() {
+        let keyword = Style::default().fg(Color::Red);
+        let line = Line::from(vec![
+            Span::raw("1 │ "),
+            Span::styled("let", keyword),
+            Span::raw(" letter"),
+        ]);
+        let text = row_text(&line);
+        assert_eq!(text, "1 │ let letter");
+
+        let hit = Style::default().bg(Color::Yellow);
+        let current = hit.add_modifier(Modifier::BOLD);
+        let start = "1 │ ".len();
+        // One match across the span boundary, one inside a span
+        let marked = mark(
+            &line,
+            &[(start + 1, start + 5, true), (start + 7, start + 9, false)],
+            hit,
+            current,
+        );
+        assert_eq!(row_text(&marked), text);
+        let parts: Vec<(&str, Style)> = marked
+            .spans
+            .iter()
+            .map(|s| (s.content.as_ref(), s.style))
+            .collect();
+        assert_eq!(
+            parts,
+            vec![
+                ("1 │ ", Style::default()),
+                ("l", keyword),
+                ("et", keyword.patch(current)),
+                (" l", current),
+                ("et", Style::default()),
+                ("te", hit),
+                ("r", Style::default()),
+            ]
+        );
+    }
+}
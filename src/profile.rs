@@ -0,0 +1,637 @@
+//! Settings profiles: one `.tar.gz` carrying a whole setup to share.
+//!
+//! A profile holds the effective `config.toml` (every config file fm reads,
+//! merged in priority order, each setting preceded by a comment naming the
+//! file it came from), the language syntaxes in `syntaxes/` and
+//! `bookmarks.toml`. `fm profile export <file>` writes one and `Alt+p`
+//! does the same from inside fm. CLI flags are per run and not included.
+//!
+//! `fm profile import <file>` unpacks into the config directory. Every
+//! file is checked first: the config must parse and its theme resolve,
+//! syntaxes must be UTF-8 and bookmarks must parse. Nothing is written
+//! unless all of them pass, existing files are only replaced with
+//! `--force`, and a write that fails part way puts back the files already
+//! replaced. `--dry-run` lists what would change and stops.
+//!
+//! Archives are packed and unpacked by the system `tar`.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use thiserror::Error;
+
+use crate::bookmarks::{Bookmarks, BOOKMARKS_FILE};
+use crate::config::AppConfig;
+use crate::session;
+use crate::syntax::SYNTAXES_DIR;
+use crate::theme;
+
+/// File offered by the in-app export, relative to the root.
+pub const DEFAULT_FILE: &str = "fm-profile.tar.gz";
+
+/// Config file name inside a profile and the config directory.
+pub const CONFIG_FILE: &str = "config.toml";
+
+/// Why a profile could not be exported or imported.
+#[derive(Debug, Error)]
+pub enum ProfileError {
+    #[error("{0}")]
+    Io(#[from] io::Error),
+
+    #[error("tar failed: {0}")]
+    Tar(String),
+
+    /// A file that would not load as it is.
+    #[error("{file}: {reason}")]
+    Invalid { file: String, reason: String },
+
+    /// Something a profile never contains.
+    #[error("unexpected entry in profile: {0}")]
+    Unexpected(String),
+
+    #[error("would overwrite {}; re-run with --force", .0.join(", "))]
+    WouldOverwrite(Vec<String>),
+}
+
+/// What importing does to one file of the config directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    Create,
+    Overwrite,
+    /// Already there with the same contents.
+    Unchanged,
+}
+
+impl Change {
+    pub fn label(self) -> &'static str {
+        match self {
+            Change::Create => "create",
+            Change::Overwrite => "overwrite",
+            Change::Unchanged => "unchanged",
+        }
+    }
+}
+
+/// One file of a profile and what importing it does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedFile {
+    /// Path relative to the config directory, e.g. `syntaxes/Nix.sublime-syntax`.
+    pub name: String,
+    pub change: Change,
+}
+
+/// How an import treats existing files.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ImportOptions {
+    /// Replace files that differ from the profile's.
+    pub force: bool,
+    /// Only list the changes.
+    pub dry_run: bool,
+}
+
+// ── Effective config ────────────────────────────────────────────────────────
+
+/// The config files among `sources` (highest priority first, missing ones
+/// skipped) merged into one TOML document. Each top-level setting is
+/// preceded by a comment naming the files it came from.
+pub fn effective_config(sources: &[PathBuf]) -> Result<String, ProfileError> {
+    let mut merged = toml::Table::new();
+    let mut origins: BTreeMap<String, Vec<&Path>> = BTreeMap::new();
+    let mut used = Vec::new();
+    // Lowest priority first, so later files win
+    for path in sources.iter().rev() {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(_) => continue,
+        };
+        let table: toml::Table = toml::from_str(&text).map_err(|e| ProfileError::Invalid {
+            file: path.display().to_string(),
+            reason: e.message().to_string(),
+        })?;
+        for (key, value) in table {
+            let from = origins.entry(key.clone()).or_default();
+            match (merged.get_mut(&key), value) {
+                (Some(toml::Value::Table(into)), toml::Value::Table(value)) => {
+                    merge_tables(into, value);
+                    from.insert(0, path);
+                }
+                (_, value) => {
+                    merged.insert(key, value);
+                    *from = vec![path];
+                }
+            }
+        }
+        used.insert(0, path);
+    }
+
+    let mut out = String::from("# fm profile: effective config\n");
+    if used.is_empty() {
+        out.push_str("# No config files; built-in defaults\n");
+    } else {
+        out.push_str("# Merged from, highest priority first:\n");
+        for path in &used {
+            out.push_str(&format!("#   {}\n", path.display()));
+        }
+    }
+    // TOML wants plain keys before tables
+    let (plain, tables): (Vec<_>, Vec<_>) = merged.into_iter().partition(|(_, v)| !v.is_table());
+    for (key, value) in plain.into_iter().chain(tables) {
+        let from: Vec<String> = origins[&key]
+            .iter()
+            .map(|p| p.display().to_string())
+            .collect();
+        let mut single = toml::Table::new();
+        single.insert(key, value);
+        let text = toml::to_string(&single).map_err(io::Error::other)?;
+        out.push_str(&format!("\n# from {}\n{}", from.join(", "), text));
+    }
+    Ok(out)
+}
+
+/// Merge `from` into `into`: tables key by key, anything else replaced.
+fn merge_tables(into: &mut toml::Table, from: toml::Table) {
+    for (key, value) in from {
+        match (into.get_mut(&key), value) {
+            (Some(toml::Value::Table(into)), toml::Value::Table(value)) => {
+                merge_tables(into, value)
+            }
+            (_, value) => {
+                into.insert(key, value);
+            }
+        }
+    }
+}
+
+// ── Validation ──────────────────────────────────────────────────────────────
+
+/// Check one profile file by its name, returning why it would not load.
+fn check_file(name: &str, contents: &[u8]) -> Result<(), String> {
+    let text = std::str::from_utf8(contents).map_err(|_| "not UTF-8".to_string())?;
+    if name == CONFIG_FILE {
+        let config: AppConfig = toml::from_str(text).map_err(|e| e.message().to_string())?;
+        theme::check_theme(&config.theme)
+    } else if name == BOOKMARKS_FILE {
+        toml::from_str::<Bookmarks>(text)
+            .map(|_| ())
+            .map_err(|e| e.message().to_string())
+    } else {
+        // Syntaxes: UTF-8 is all that can be checked without syntect
+        Ok(())
+    }
+}
+
+/// Whether `name` (relative, `/`-separated) belongs in a profile.
+fn is_profile_file(name: &str) -> bool {
+    if name == CONFIG_FILE || name == BOOKMARKS_FILE {
+        return true;
+    }
+    match name.split_once('/') {
+        Some((dir, file)) => {
+            dir == SYNTAXES_DIR
+                && !file.contains('/')
+                && !file.starts_with('.')
+                && file.ends_with(".sublime-syntax")
+        }
+        None => false,
+    }
+}
+
+// ── Export ──────────────────────────────────────────────────────────────────
+
+/// Write a profile of the config `sources` (highest priority first) and
+/// the syntaxes and bookmarks in `config_dir` to `out`. Returns the names
+/// of the files packed.
+pub fn export(
+    sources: &[PathBuf],
+    config_dir: &Path,
+    out: &Path,
+) -> Result<Vec<String>, ProfileError> {
+    let mut files = vec![(
+        CONFIG_FILE.to_string(),
+        effective_config(sources)?.into_bytes(),
+    )];
+    if let Ok(bookmarks) = fs::read(config_dir.join(BOOKMARKS_FILE)) {
+        files.push((BOOKMARKS_FILE.to_string(), bookmarks));
+    }
+    if let Ok(entries) = fs::read_dir(config_dir.join(SYNTAXES_DIR)) {
+        let mut syntaxes: Vec<(String, Vec<u8>)> = entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let name = format!("{}/{}", SYNTAXES_DIR, entry.file_name().to_str()?);
+                if !is_profile_file(&name) {
+                    return None;
+                }
+                Some((name, fs::read(entry.path()).ok()?))
+            })
+            .collect();
+        syntaxes.sort();
+        files.extend(syntaxes);
+    }
+    // A profile that can't be imported is no use to anyone
+    for (name, contents) in &files {
+        check_file(name, contents).map_err(|reason| ProfileError::Invalid {
+            file: name.clone(),
+            reason,
+        })?;
+    }
+
+    let staging = Scratch::new("export")?;
+    for (name, contents) in &files {
+        let path = staging.path().join(name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, contents)?;
+    }
+    let names: Vec<String> = files.into_iter().map(|(name, _)| name).collect();
+    pack(staging.path(), &names, out)?;
+    Ok(names)
+}
+
+/// `tar -czf out` of `names` inside `dir`.
+fn pack(dir: &Path, names: &[String], out: &Path) -> Result<(), ProfileError> {
+    let out = std::env::current_dir()?.join(out);
+    let output = Command::new("tar")
+        .arg("-czf")
+        .arg(&out)
+        .arg("-C")
+        .arg(dir)
+        .arg("--")
+        .args(names)
+        .output()
+        .map_err(|e| ProfileError::Tar(e.to_string()))?;
+    if !output.status.success() {
+        return Err(ProfileError::Tar(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    Ok(())
+}
+
+// ── Import ──────────────────────────────────────────────────────────────────
+
+/// Unpack `archive`, check every file and write them into `config_dir`.
+/// Returns what was (or, for a dry run or refusal, would be) done to
+/// each file.
+pub fn import(
+    archive: &Path,
+    config_dir: &Path,
+    options: ImportOptions,
+) -> Result<Vec<PlannedFile>, ProfileError> {
+    let files = unpack(archive)?;
+    for (name, contents) in &files {
+        check_file(name, contents).map_err(|reason| ProfileError::Invalid {
+            file: name.clone(),
+            reason,
+        })?;
+    }
+
+    let plan: Vec<PlannedFile> = files
+        .iter()
+        .map(|(name, contents)| {
+            let change = match fs::read(config_dir.join(name)) {
+                Ok(existing) if &existing == contents => Change::Unchanged,
+                Ok(_) => Change::Overwrite,
+                Err(_) if config_dir.join(name).exists() => Change::Overwrite,
+                Err(_) => Change::Create,
+            };
+            PlannedFile {
+                name: name.clone(),
+                change,
+            }
+        })
+        .collect();
+    if options.dry_run {
+        return Ok(plan);
+    }
+    let overwrites: Vec<String> = plan
+        .iter()
+        .filter(|file| file.change == Change::Overwrite)
+        .map(|file| file.name.clone())
+        .collect();
+    if !overwrites.is_empty() && !options.force {
+        return Err(ProfileError::WouldOverwrite(overwrites));
+    }
+
+    let changed = files
+        .iter()
+        .zip(&plan)
+        .filter(|(_, planned)| planned.change != Change::Unchanged)
+        .map(|((name, contents), _)| (name.as_str(), contents.as_slice()));
+    commit(config_dir, changed)?;
+    Ok(plan)
+}
+
+/// Profile files in `archive`, config first, then bookmarks and syntaxes.
+fn unpack(archive: &Path) -> Result<Vec<(String, Vec<u8>)>, ProfileError> {
+    let staging = Scratch::new("import")?;
+    let output = Command::new("tar")
+        .arg("-xzf")
+        .arg(archive)
+        .arg("-C")
+        .arg(staging.path())
+        .output()
+        .map_err(|e| ProfileError::Tar(e.to_string()))?;
+    if !output.status.success() {
+        return Err(ProfileError::Tar(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+
+    let mut files = Vec::new();
+    collect(staging.path(), staging.path(), &mut files)?;
+    files.sort_by_key(|(name, _)| {
+        let rank = match name.as_str() {
+            CONFIG_FILE => 0,
+            BOOKMARKS_FILE => 1,
+            _ => 2,
+        };
+        (rank, name.clone())
+    });
+    Ok(files)
+}
+
+/// Regular files under `dir`, named relative to `root`. Anything that is
+/// not a profile file, symlinks included, is refused.
+fn collect(
+    root: &Path,
+    dir: &Path,
+    files: &mut Vec<(String, Vec<u8>)>,
+) -> Result<(), ProfileError> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let relative = path.strip_prefix(root).unwrap_or(&path);
+        let name = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        let meta = fs::symlink_metadata(&path)?;
+        if meta.is_dir() && name == SYNTAXES_DIR {
+            collect(root, &path, files)?;
+        } else if meta.is_file() && is_profile_file(&name) {
+            files.push((name, fs::read(&path)?));
+        } else {
+            return Err(ProfileError::Unexpected(name));
+        }
+    }
+    Ok(())
+}
+
+/// Write `files` into `config_dir`. If one fails, the files already
+/// written are put back as they were before returning the error.
+fn commit<'a>(
+    config_dir: &Path,
+    files: impl Iterator<Item = (&'a str, &'a [u8])>,
+) -> Result<(), ProfileError> {
+    let mut written: Vec<(PathBuf, Option<Vec<u8>>)> = Vec::new();
+    for (name, contents) in files {
+        let target = config_dir.join(name);
+        let previous = fs::read(&target).ok();
+        if let Err(e) = session::write_atomic(&target, contents) {
+            for (path, previous) in written.iter().rev() {
+                let _ = match previous {
+                    Some(previous) => session::write_atomic(path, previous),
+                    None => fs::remove_file(path),
+                };
+            }
+            return Err(e.into());
+        }
+        written.push((target, previous));
+    }
+    Ok(())
+}
+
+/// Temporary directory removed again on drop.
+struct Scratch(PathBuf);
+
+impl Scratch {
+    fn new(purpose: &str) -> io::Result<Self> {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "fm-profile-{}-{}-{}",
+            purpose,
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path)?;
+        Ok(Self(path))
+    }
+
+    fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// A global config, a higher-priority local one, a syntax and bookmarks.
+    fn setup() -> (TempDir, Vec<PathBuf>, PathBuf) {
+        let tmp = TempDir::new().unwrap();
+        let config_dir = tmp.path().join("fm-tui");
+        fs::create_dir_all(config_dir.join(SYNTAXES_DIR)).unwrap();
+        let global = config_dir.join(CONFIG_FILE);
+        fs::write(
+            &global,
+            "[general]\nshow_hidden = true\n\n[theme]\nscheme = \"light\"\n",
+        )
+        .unwrap();
+        let local = tmp.path().join(".fm-tui.toml");
+        fs::write(
+            &local,
+            "[theme]\nscheme = \"custom\"\n\n[theme.custom]\ntree_bg = \"#101010\"\n",
+        )
+        .unwrap();
+        fs::write(
+            config_dir.join(SYNTAXES_DIR).join("Nix.sublime-syntax"),
+            "name: Nix\n",
+        )
+        .unwrap();
+        fs::write(config_dir.join(BOOKMARKS_FILE), "paths = [\"/srv/data\"]\n").unwrap();
+        (tmp, vec![local, global], config_dir)
+    }
+
+    #[test]
+    fn effective_config_names_where_settings_came_from() {
+        let (_tmp, sources, _) = setup();
+        let text = effective_config(&sources).unwrap();
+        let config: AppConfig = toml::from_str(&text).unwrap();
+        assert_eq!(config.theme_scheme(), "custom");
+        assert!(config.show_hidden());
+
+        let local = sources[0].display().to_string();
+        let global = sources[1].display().to_string();
+        assert!(
+            text.contains(&format!("# from {}\n[general]", global)),
+            "{}",
+            text
+        );
+        // [theme] is set in both: the local file wins the scheme
+        assert!(text.contains(&format!("# from {}, {}\n[theme]", local, global)));
+    }
+
+    #[test]
+    fn profile_round_trips() {
+        let (tmp, sources, config_dir) = setup();
+        let archive = tmp.path().join("team.tar.gz");
+        let packed = export(&sources, &config_dir, &archive).unwrap();
+        assert_eq!(
+            packed,
+            vec![CONFIG_FILE, BOOKMARKS_FILE, "syntaxes/Nix.sublime-syntax"]
+        );
+
+        let target = tmp.path().join("elsewhere");
+        let dry = ImportOptions {
+            dry_run: true,
+            ..Default::default()
+        };
+        let plan = import(&archive, &target, dry).unwrap();
+        assert!(plan.iter().all(|file| file.change == Change::Create));
+        assert!(!target.exists(), "a dry run writes nothing");
+
+        import(&archive, &target, ImportOptions::default()).unwrap();
+        let imported: AppConfig =
+            toml::from_str(&fs::read_to_string(target.join(CONFIG_FILE)).unwrap()).unwrap();
+        assert_eq!(imported.theme_scheme(), "custom");
+        assert_eq!(
+            fs::read_to_string(target.join("syntaxes/Nix.sublime-syntax")).unwrap(),
+            "name: Nix\n"
+        );
+        assert_eq!(
+            Bookmarks::load(&target).paths(),
+            &[PathBuf::from("/srv/data")]
+        );
+
+        // Importing again changes nothing
+        let again = import(&archive, &target, ImportOptions::default()).unwrap();
+        assert!(again.iter().all(|file| file.change == Change::Unchanged));
+    }
+
+    #[test]
+    fn existing_files_need_force() {
+        let (tmp, sources, config_dir) = setup();
+        let archive = tmp.path().join("team.tar.gz");
+        export(&sources, &config_dir, &archive).unwrap();
+        fs::write(config_dir.join(BOOKMARKS_FILE), "paths = []\n").unwrap();
+
+        match import(&archive, &config_dir, ImportOptions::default()) {
+            Err(ProfileError::WouldOverwrite(names)) => {
+                assert!(names.contains(&BOOKMARKS_FILE.to_string()))
+            }
+            other => panic!("expected a refusal, got {:?}", other),
+        }
+        assert_eq!(
+            fs::read_to_string(config_dir.join(BOOKMARKS_FILE)).unwrap(),
+            "paths = []\n"
+        );
+        let force = ImportOptions {
+            force: true,
+            ..Default::default()
+        };
+        import(&archive, &config_dir, force).unwrap();
+        assert_eq!(Bookmarks::load(&config_dir).paths().len(), 1);
+    }
+
+    /// Pack `files` by hand, as a profile from elsewhere would be.
+    fn hand_made(tmp: &Path, files: &[(&str, &str)]) -> PathBuf {
+        let dir = tmp.join("hand-made");
+        for (name, contents) in files {
+            let path = dir.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+        let names: Vec<String> = files.iter().map(|(name, _)| name.to_string()).collect();
+        let archive = tmp.join("hand-made.tar.gz");
+        pack(&dir, &names, &archive).unwrap();
+        archive
+    }
+
+    #[test]
+    fn invalid_theme_leaves_the_config_dir_alone() {
+        let (tmp, _, config_dir) = setup();
+        let before = fs::read_to_string(config_dir.join(CONFIG_FILE)).unwrap();
+        let archive = hand_made(
+            tmp.path(),
+            &[
+                (BOOKMARKS_FILE, "paths = [\"/new\"]\n"),
+                (
+                    CONFIG_FILE,
+                    "[theme]\nscheme = \"custom\"\n[theme.custom]\ntree_bg = \"#nothex\"\n",
+                ),
+            ],
+        );
+        let force = ImportOptions {
+            force: true,
+            ..Default::default()
+        };
+        match import(&archive, &config_dir, force) {
+            Err(ProfileError::Invalid { file, reason }) => {
+                assert_eq!(file, CONFIG_FILE);
+                assert!(reason.contains("tree_bg"), "{}", reason);
+            }
+            other => panic!("expected the theme to be rejected, got {:?}", other),
+        }
+        assert_eq!(
+            fs::read_to_string(config_dir.join(CONFIG_FILE)).unwrap(),
+            before
+        );
+        assert_eq!(
+            Bookmarks::load(&config_dir).paths(),
+            &[PathBuf::from("/srv/data")]
+        );
+    }
+
+    #[test]
+    fn failed_write_puts_back_earlier_files() {
+        let (tmp, _, config_dir) = setup();
+        let config_before = fs::read_to_string(config_dir.join(CONFIG_FILE)).unwrap();
+        let archive = hand_made(
+            tmp.path(),
+            &[
+                (CONFIG_FILE, "[general]\nshow_hidden = false\n"),
+                (BOOKMARKS_FILE, "paths = []\n"),
+                ("syntaxes/Zig.sublime-syntax", "name: Zig\n"),
+            ],
+        );
+        // The syntax is written last and its target is in the way
+        fs::create_dir_all(config_dir.join("syntaxes/Zig.sublime-syntax/x")).unwrap();
+        fs::remove_file(config_dir.join(BOOKMARKS_FILE)).unwrap();
+
+        let force = ImportOptions {
+            force: true,
+            ..Default::default()
+        };
+        assert!(matches!(
+            import(&archive, &config_dir, force),
+            Err(ProfileError::Io(_))
+        ));
+        assert_eq!(
+            fs::read_to_string(config_dir.join(CONFIG_FILE)).unwrap(),
+            config_before
+        );
+        assert!(!config_dir.join(BOOKMARKS_FILE).exists());
+    }
+
+    #[test]
+    fn stray_entries_are_refused() {
+        let tmp = TempDir::new().unwrap();
+        let archive = hand_made(tmp.path(), &[("hooks/run.sh", "rm -rf ~\n")]);
+        let target = tmp.path().join("target");
+        assert!(import(&archive, &target, ImportOptions::default()).is_err());
+        assert!(!target.exists());
+    }
+}
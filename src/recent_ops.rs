@@ -0,0 +1,374 @@
+//! Recently completed copy/move operations.
+//!
+//! The status line announcing a finished paste is gone after a few
+//! seconds. The last [`CAPACITY`] results are kept here, newest first, so
+//! the overlay (`O`) can show what each operation did and offer to jump to
+//! its destination, undo it or retry the items that failed.
+
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::error::{self, DirErrors, OperationError};
+use crate::event::OperationResult;
+
+/// Completed operations kept for review.
+pub const CAPACITY: usize = 20;
+
+/// Paths listed per section of the details before the rest is summarised.
+const DETAIL_LIMIT: usize = 8;
+
+/// What one finished copy/move did.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompletedOp {
+    /// Tells operations apart; links an operation to the pending undo.
+    pub id: u64,
+    pub was_cut: bool,
+    /// Target name for "send to" transfers.
+    pub send_to: Option<String>,
+    pub dest_dir: PathBuf,
+    /// Top-level items the operation was asked to transfer.
+    pub sources: Vec<PathBuf>,
+    /// Items created in the destination.
+    pub created: Vec<PathBuf>,
+    /// Files the operation overwrote, as "original → backup".
+    pub backups: Vec<String>,
+    /// Items skipped because the destination already had them, sorted.
+    pub skipped: Vec<String>,
+    /// Other failures, with their hints, by directory.
+    pub errors: Vec<DirErrors>,
+    /// Sources to try again: the failed ones and any never started
+    /// because the operation was cancelled.
+    pub retry: Vec<PathBuf>,
+    /// Sources never started because the operation was cancelled.
+    pub not_started: usize,
+    pub elapsed: Duration,
+}
+
+impl CompletedOp {
+    /// Record `result` as operation `id`.
+    pub fn from_result(id: u64, result: &OperationResult) -> Self {
+        let (conflicts, failures): (Vec<&OperationError>, Vec<&OperationError>) = result
+            .errors
+            .iter()
+            .partition(|e| matches!(e, OperationError::DestinationExists { .. }));
+        let skipped = error::sorted_errors(conflicts)
+            .into_iter()
+            .map(ToString::to_string)
+            .collect();
+        let errors = error::errors_by_dir(failures);
+        // Items are tried in order, so the untried ones are at the end
+        let tried = result.success_count + result.failed_sources.len();
+        let untried = result.source_paths.get(tried..).unwrap_or(&[]);
+        let mut retry = result.failed_sources.clone();
+        retry.extend(untried.iter().cloned());
+        let backups = result
+            .backups
+            .iter()
+            .map(|b| format!("{} → {}", b.original().display(), b.kept().path.display()))
+            .collect();
+        Self {
+            id,
+            was_cut: result.was_cut,
+            send_to: result.send_to.clone(),
+            dest_dir: result.dest_dir.clone(),
+            sources: result.source_paths.clone(),
+            created: result.created_paths.clone(),
+            backups,
+            skipped,
+            errors,
+            retry,
+            not_started: untried.len(),
+            elapsed: result.elapsed,
+        }
+    }
+
+    /// One-line summary for the list: "Moved 2 of 3 items to /tmp · 1 failed · 0.4s".
+    pub fn summary(&self) -> String {
+        let verb = match (self.was_cut, self.send_to.is_some()) {
+            (true, _) => "Moved",
+            (false, true) => "Sent",
+            (false, false) => "Pasted",
+        };
+        let total = self.sources.len();
+        let done = self.created.len();
+        let count = if done == total {
+            items(total)
+        } else {
+            format!("{} of {}", done, items(total))
+        };
+        let target = match &self.send_to {
+            Some(name) => name.clone(),
+            None => self.dest_dir.display().to_string(),
+        };
+        let mut summary = format!("{} {} to {}", verb, count, target);
+        if !self.backups.is_empty() {
+            summary.push_str(&format!(" · {} backed up", self.backups.len()));
+        }
+        let failed = self.retry.len() - self.not_started;
+        if failed > 0 {
+            summary.push_str(&format!(" · {} failed", failed));
+        }
+        if self.not_started > 0 {
+            summary.push_str(" · cancelled");
+        }
+        summary.push_str(&format!(" · {}", elapsed(self.elapsed)));
+        summary
+    }
+
+    /// Per-item lines for the expanded entry.
+    pub fn details(&self) -> Vec<String> {
+        let mut lines = vec![format!("Destination: {}", self.dest_dir.display())];
+        let created: Vec<String> = self
+            .created
+            .iter()
+            .map(|p| p.display().to_string())
+            .collect();
+        section(&mut lines, "Created", &created);
+        section(&mut lines, "Backed up (overwritten)", &self.backups);
+        section(&mut lines, "Skipped (already there)", &self.skipped);
+        error_section(&mut lines, &self.errors);
+        if self.not_started > 0 {
+            lines.push(format!("Cancelled before {}", items(self.not_started)));
+        }
+        lines
+    }
+}
+
+fn items(n: usize) -> String {
+    format!("{} item{}", n, if n == 1 { "" } else { "s" })
+}
+
+fn elapsed(duration: Duration) -> String {
+    if duration < Duration::from_secs(1) {
+        format!("{}ms", duration.as_millis())
+    } else {
+        format!("{:.1}s", duration.as_secs_f64())
+    }
+}
+
+/// Append "heading (n):" and up to [`DETAIL_LIMIT`] entries, if any.
+fn section(lines: &mut Vec<String>, heading: &str, entries: &[String]) {
+    if entries.is_empty() {
+        return;
+    }
+    lines.push(format!("{} ({}):", heading, entries.len()));
+    for entry in entries.iter().take(DETAIL_LIMIT) {
+        lines.push(format!("  {}", entry));
+    }
+    if entries.len() > DETAIL_LIMIT {
+        lines.push(format!("  … and {} more", entries.len() - DETAIL_LIMIT));
+    }
+}
+
+/// Append "Errors (n):" and up to [`DETAIL_LIMIT`] failed items under
+/// their directories, if any.
+fn error_section(lines: &mut Vec<String>, groups: &[DirErrors]) {
+    let total: usize = groups.iter().map(|g| g.items.len()).sum();
+    if total == 0 {
+        return;
+    }
+    lines.push(format!("Errors ({}):", total));
+    let mut shown = 0;
+    for group in groups {
+        if shown == DETAIL_LIMIT {
+            break;
+        }
+        if let Some(dir) = &group.dir {
+            lines.push(format!("  {}", dir.display()));
+        }
+        let indent = if group.dir.is_some() { "    " } else { "  " };
+        for item in group.items.iter().take(DETAIL_LIMIT - shown) {
+            lines.push(format!("{}{}", indent, item));
+            shown += 1;
+        }
+    }
+    if total > shown {
+        lines.push(format!("  … and {} more", total - shown));
+    }
+}
+
+/// Ring buffer of the last [`CAPACITY`] completed operations.
+#[derive(Debug, Default)]
+pub struct RecentOps {
+    /// Newest first.
+    entries: VecDeque<CompletedOp>,
+    next_id: u64,
+    /// Operation that recorded the app's pending undo, if one did.
+    undo_owner: Option<u64>,
+}
+
+impl RecentOps {
+    /// Keep `result`, dropping the oldest entry when full. Returns its id.
+    pub fn record(&mut self, result: &OperationResult) -> u64 {
+        self.next_id += 1;
+        let id = self.next_id;
+        self.entries
+            .push_front(CompletedOp::from_result(id, result));
+        self.entries.truncate(CAPACITY);
+        id
+    }
+
+    /// Kept operations, newest first.
+    pub fn entries(&self) -> impl Iterator<Item = &CompletedOp> {
+        self.entries.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Note which operation the pending undo belongs to (`None` when the
+    /// undo came from anything else).
+    pub fn set_undo_owner(&mut self, id: Option<u64>) {
+        self.undo_owner = id;
+    }
+
+    pub fn undo_owner(&self) -> Option<u64> {
+        self.undo_owner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn result(sources: &[&str], created: usize, errors: Vec<OperationError>) -> OperationResult {
+        let source_paths: Vec<PathBuf> = sources.iter().map(PathBuf::from).collect();
+        let failed_sources = source_paths[created..created + errors.len()].to_vec();
+        OperationResult {
+            success_count: created,
+            created_paths: source_paths[..created]
+                .iter()
+                .map(|p| Path::new("/dest").join(p.file_name().unwrap()))
+                .collect(),
+            replaced: Vec::new(),
+            backups: Vec::new(),
+            errors,
+            failed_sources,
+            source_paths,
+            dest_dir: PathBuf::from("/dest"),
+            was_cut: false,
+            send_to: None,
+            elapsed: Duration::from_millis(1500),
+        }
+    }
+
+    #[test]
+    fn newest_first_and_capped() {
+        let mut ops = RecentOps::default();
+        for _ in 0..CAPACITY + 3 {
+            ops.record(&result(&["/src/a"], 1, vec![]));
+        }
+        assert_eq!(ops.entries().count(), CAPACITY);
+        let ids: Vec<u64> = ops.entries().map(|op| op.id).collect();
+        assert_eq!(ids.first(), Some(&(CAPACITY as u64 + 3)));
+        assert_eq!(ids.last(), Some(&4));
+    }
+
+    #[test]
+    fn summary_counts_failures() {
+        let op = CompletedOp::from_result(
+            1,
+            &result(
+                &["/src/a", "/src/b", "/src/c"],
+                2,
+                vec![OperationError::PermissionDenied {
+                    path: PathBuf::from("/src/c"),
+                }],
+            ),
+        );
+        assert_eq!(
+            op.summary(),
+            "Pasted 2 of 3 items to /dest · 1 failed · 1.5s"
+        );
+        assert_eq!(op.retry, vec![PathBuf::from("/src/c")]);
+    }
+
+    #[test]
+    fn details_split_conflicts_from_errors() {
+        let op = CompletedOp::from_result(
+            1,
+            &result(
+                &["/src/a", "/src/b", "/src/c"],
+                1,
+                vec![
+                    OperationError::DestinationExists {
+                        path: PathBuf::from("/dest/b"),
+                    },
+                    OperationError::NoSpace {
+                        path: PathBuf::from("/dest/c"),
+                    },
+                ],
+            ),
+        );
+        let details = op.details();
+        assert_eq!(details[0], "Destination: /dest");
+        assert!(details.contains(&"Created (1):".to_string()));
+        assert!(details.contains(&"  /dest/a".to_string()));
+        assert!(details.contains(&"Skipped (already there) (1):".to_string()));
+        assert!(details.contains(&"  /dest/b already exists".to_string()));
+        assert!(details.contains(&"Errors (1):".to_string()));
+        assert!(details.contains(&"  /dest".to_string()));
+        assert!(details
+            .iter()
+            .any(|l| l.starts_with("    c: no space left") && l.contains("free up space")));
+    }
+
+    #[test]
+    fn details_are_the_same_whatever_order_items_failed_in() {
+        let denied = |p: &str| OperationError::PermissionDenied {
+            path: PathBuf::from(p),
+        };
+        let sources = ["/src/a", "/src/b", "/src/c"];
+        let first = CompletedOp::from_result(
+            1,
+            &result(&sources, 1, vec![denied("/dest/c"), denied("/dest/b")]),
+        );
+        let second = CompletedOp::from_result(
+            1,
+            &result(&sources, 1, vec![denied("/dest/b"), denied("/dest/c")]),
+        );
+        assert_eq!(first.details(), second.details());
+        let errors = first.details();
+        let at = errors.iter().position(|l| l == "Errors (2):").unwrap();
+        assert!(errors[at + 2].starts_with("    b: permission denied"));
+        assert!(errors[at + 3].starts_with("    c: permission denied"));
+    }
+
+    #[test]
+    fn backups_are_counted_and_listed() {
+        use crate::fs::overwrite::Backup;
+        use crate::fs::undo_check::StampedPath;
+
+        let mut pasted = result(&["/src/a"], 1, vec![]);
+        pasted.replaced = vec![PathBuf::from("/dest/a")];
+        pasted.backups = vec![Backup::Renamed {
+            original: PathBuf::from("/dest/a"),
+            backup: StampedPath {
+                path: PathBuf::from("/dest/a.fm-bak-20261016-083841"),
+                stamp: None,
+            },
+        }];
+        let op = CompletedOp::from_result(1, &pasted);
+        assert_eq!(op.summary(), "Pasted 1 item to /dest · 1 backed up · 1.5s");
+        let details = op.details();
+        assert!(details.contains(&"Backed up (overwritten) (1):".to_string()));
+        assert!(details.contains(&"  /dest/a → /dest/a.fm-bak-20261016-083841".to_string()));
+    }
+
+    #[test]
+    fn cancelled_items_are_retried() {
+        // Cancelled after the first item: the rest never started
+        let op = CompletedOp::from_result(1, &result(&["/src/a", "/src/b", "/src/c"], 1, vec![]));
+        assert_eq!(op.not_started, 2);
+        assert_eq!(
+            op.retry,
+            vec![PathBuf::from("/src/b"), PathBuf::from("/src/c")]
+        );
+        assert!(op.summary().contains("cancelled"));
+        assert!(!op.summary().contains("failed"));
+        assert_eq!(op.details().last().unwrap(), "Cancelled before 2 items");
+    }
+}
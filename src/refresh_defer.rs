@@ -0,0 +1,248 @@
+//! Deferring auto-refresh of the directory a busy terminal is working in.
+//!
+//! A build running in the embedded terminal rewrites files under its
+//! working directory many times a second, and refreshing the tree for each
+//! burst fights with navigation. [`DeferState`] watches the terminal's
+//! output rate: once it stays above [`BUSY_BYTES_PER_SEC`] for
+//! [`BUSY_AFTER`], changes under the shell's working directory stop being
+//! applied (their nearest loaded directory is only marked stale). When the
+//! rate has stayed below the threshold for [`QUIET_AFTER`], the deferral is
+//! lifted and everything it held back is refreshed at once. Directories
+//! outside the deferred subtree refresh as usual.
+
+use std::collections::{HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Output rate above which the terminal counts as busy.
+pub const BUSY_BYTES_PER_SEC: usize = 4096;
+
+/// How long the output must stay busy before refreshes are deferred.
+pub const BUSY_AFTER: Duration = Duration::from_secs(2);
+
+/// How long the output must stay quiet before the deferral is lifted.
+pub const QUIET_AFTER: Duration = Duration::from_secs(1);
+
+/// Window the output rate is measured over.
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+/// What [`DeferState::poll`] decided.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeferChange {
+    /// Start deferring changes under this directory.
+    Started(PathBuf),
+    /// Stop deferring; these directories were held back and need a refresh.
+    Lifted {
+        dir: PathBuf,
+        pending: HashSet<PathBuf>,
+    },
+}
+
+/// Terminal output rate and the active deferral, if any.
+#[derive(Debug, Default)]
+pub struct DeferState {
+    /// Output chunks within the rate window: arrival time and byte count.
+    samples: VecDeque<(Instant, usize)>,
+    /// When the rate last went above the threshold.
+    busy_since: Option<Instant>,
+    /// When the rate last dropped below the threshold.
+    quiet_since: Option<Instant>,
+    /// Directory whose subtree is not being refreshed.
+    dir: Option<PathBuf>,
+    /// Directories held back while deferred.
+    pending: HashSet<PathBuf>,
+}
+
+impl DeferState {
+    /// Count `bytes` of terminal output received at `now`.
+    pub fn record_output(&mut self, now: Instant, bytes: usize) {
+        self.samples.push_back((now, bytes));
+        self.update_rate(now);
+    }
+
+    /// Bytes per second over the last window.
+    pub fn rate(&self) -> usize {
+        self.samples.iter().map(|&(_, bytes)| bytes).sum()
+    }
+
+    fn update_rate(&mut self, now: Instant) {
+        while let Some(&(at, _)) = self.samples.front() {
+            if now.duration_since(at) < RATE_WINDOW {
+                break;
+            }
+            self.samples.pop_front();
+        }
+        if self.rate() > BUSY_BYTES_PER_SEC {
+            self.busy_since.get_or_insert(now);
+            self.quiet_since = None;
+        } else {
+            self.busy_since = None;
+            self.quiet_since.get_or_insert(now);
+        }
+    }
+
+    /// Start or lift the deferral. `cwd` is the shell's working directory,
+    /// considered only when it lies under `root`.
+    pub fn poll(&mut self, now: Instant, cwd: Option<&Path>, root: &Path) -> Option<DeferChange> {
+        self.update_rate(now);
+        match &self.dir {
+            None => {
+                let since = self.busy_since?;
+                if now.duration_since(since) < BUSY_AFTER {
+                    return None;
+                }
+                let cwd = cwd.filter(|cwd| cwd.starts_with(root))?;
+                self.dir = Some(cwd.to_path_buf());
+                Some(DeferChange::Started(cwd.to_path_buf()))
+            }
+            Some(_) => {
+                let since = self.quiet_since?;
+                if now.duration_since(since) < QUIET_AFTER {
+                    return None;
+                }
+                self.lift()
+            }
+        }
+    }
+
+    /// End the deferral now (F5), returning what it held back. Output must
+    /// stay busy for another [`BUSY_AFTER`] before deferring again.
+    pub fn lift(&mut self) -> Option<DeferChange> {
+        let dir = self.dir.take()?;
+        self.busy_since = None;
+        Some(DeferChange::Lifted {
+            dir,
+            pending: std::mem::take(&mut self.pending),
+        })
+    }
+
+    /// The deferred directory, if any.
+    pub fn dir(&self) -> Option<&Path> {
+        self.dir.as_deref()
+    }
+
+    /// Whether changes in `dir` are being held back.
+    pub fn covers(&self, dir: &Path) -> bool {
+        self.dir.as_deref().is_some_and(|d| dir.starts_with(d))
+    }
+
+    /// Remember that `dir` needs a refresh once the deferral is lifted.
+    pub fn hold(&mut self, dir: PathBuf) {
+        self.pending.insert(dir);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROOT: &str = "/work";
+
+    /// Feed `rate` bytes per second in 100 ms chunks for `duration`,
+    /// polling after each chunk; returns the time reached and the changes.
+    fn feed(
+        state: &mut DeferState,
+        start: Instant,
+        rate: usize,
+        duration: Duration,
+        cwd: &str,
+    ) -> (Instant, Vec<DeferChange>) {
+        let step = Duration::from_millis(100);
+        let mut now = start;
+        let mut changes = Vec::new();
+        while now < start + duration {
+            now += step;
+            if rate > 0 {
+                state.record_output(now, rate / 10);
+            }
+            changes.extend(state.poll(now, Some(Path::new(cwd)), Path::new(ROOT)));
+        }
+        (now, changes)
+    }
+
+    #[test]
+    fn sustained_output_defers_the_cwd() {
+        let mut state = DeferState::default();
+        let t0 = Instant::now();
+        let (t1, changes) = feed(
+            &mut state,
+            t0,
+            20_000,
+            Duration::from_millis(1500),
+            "/work/app",
+        );
+        assert!(changes.is_empty(), "too early: {changes:?}");
+
+        let (_, changes) = feed(&mut state, t1, 20_000, Duration::from_secs(2), "/work/app");
+        assert_eq!(
+            changes,
+            vec![DeferChange::Started(PathBuf::from("/work/app"))]
+        );
+        assert!(state.covers(Path::new("/work/app/target/debug")));
+        assert!(!state.covers(Path::new("/work/docs")));
+    }
+
+    #[test]
+    fn short_bursts_and_slow_output_do_not_defer() {
+        let mut state = DeferState::default();
+        let t0 = Instant::now();
+        let (t1, changes) = feed(&mut state, t0, 2_000, Duration::from_secs(5), "/work");
+        assert!(changes.is_empty());
+        // A one-second burst, then quiet
+        let (t2, changes) = feed(&mut state, t1, 50_000, Duration::from_secs(1), "/work");
+        assert!(changes.is_empty());
+        let (_, changes) = feed(&mut state, t2, 0, Duration::from_secs(3), "/work");
+        assert!(changes.is_empty());
+    }
+
+    #[test]
+    fn cwd_outside_the_tree_is_not_deferred() {
+        let mut state = DeferState::default();
+        let (_, changes) = feed(
+            &mut state,
+            Instant::now(),
+            20_000,
+            Duration::from_secs(5),
+            "/elsewhere",
+        );
+        assert!(changes.is_empty());
+        assert!(state.dir().is_none());
+    }
+
+    #[test]
+    fn quiet_output_lifts_with_everything_held_back() {
+        let mut state = DeferState::default();
+        let t0 = Instant::now();
+        let (t1, _) = feed(&mut state, t0, 20_000, Duration::from_secs(3), "/work/app");
+        state.hold(PathBuf::from("/work/app/target"));
+        state.hold(PathBuf::from("/work/app/target"));
+        state.hold(PathBuf::from("/work/app/src"));
+
+        // Still within QUIET_AFTER of the last busy window
+        let (t2, changes) = feed(&mut state, t1, 0, Duration::from_millis(1500), "/work/app");
+        assert!(changes.is_empty(), "{changes:?}");
+        let (_, changes) = feed(&mut state, t2, 0, Duration::from_secs(1), "/work/app");
+        match changes.as_slice() {
+            [DeferChange::Lifted { dir, pending }] => {
+                assert_eq!(dir, Path::new("/work/app"));
+                assert_eq!(pending.len(), 2);
+            }
+            other => panic!("expected one lift, got {other:?}"),
+        }
+        assert!(!state.covers(Path::new("/work/app/target")));
+    }
+
+    #[test]
+    fn manual_lift_requires_a_new_busy_period() {
+        let mut state = DeferState::default();
+        let t0 = Instant::now();
+        let (t1, _) = feed(&mut state, t0, 20_000, Duration::from_secs(3), "/work");
+        assert!(state.lift().is_some());
+        assert!(state.lift().is_none());
+
+        let (t2, changes) = feed(&mut state, t1, 20_000, Duration::from_secs(1), "/work");
+        assert!(changes.is_empty());
+        let (_, changes) = feed(&mut state, t2, 20_000, Duration::from_millis(1500), "/work");
+        assert_eq!(changes, vec![DeferChange::Started(PathBuf::from("/work"))]);
+    }
+}
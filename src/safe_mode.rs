@@ -0,0 +1,61 @@
+//! `--safe-mode`: start with nothing that could be the problem.
+//!
+//! A corrupt config, a watcher stuck re-scanning or a theme that hides
+//! text can make fm unusable. Safe mode starts from the built-in defaults
+//! (no config file is read and the theme and preview flags are dropped)
+//! with the watcher, embedded terminal, mouse and icons off. It lasts for
+//! one run; the status bar carries a `SAFE MODE` badge meanwhile.
+
+/// Why safe mode is on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trigger {
+    /// `--safe-mode` was given.
+    Flag,
+    /// Accepted at startup after the previous run crashed.
+    Crash,
+}
+
+/// What safe mode turns off, and why.
+pub const SUBSYSTEMS: &[(&str, &str)] = &[
+    ("config", "not read, it may be corrupt"),
+    ("watcher", "off in case it loops, F5 refreshes"),
+    ("terminal", "off, no shell is started"),
+    ("mouse", "off, keys only"),
+    ("icons", "off in case the font lacks them"),
+];
+
+/// Question asked before the TUI starts when the last run crashed.
+pub const PROMPT: &str = "fm did not exit cleanly last time - start in safe mode? [y/N] ";
+
+/// Status bar badge shown for the whole run.
+pub const BADGE: &str = "SAFE MODE";
+
+/// Startup message listing each subsystem that is off and why. ASCII
+/// only, so the status bar can cut it anywhere.
+pub fn banner(trigger: Trigger) -> String {
+    let why = match trigger {
+        Trigger::Flag => "--safe-mode",
+        Trigger::Crash => "previous run crashed",
+    };
+    let reasons: Vec<String> = SUBSYSTEMS
+        .iter()
+        .map(|(name, reason)| format!("{} {}", name, reason))
+        .collect();
+    format!("Safe mode ({}): {}", why, reasons.join("; "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn banner_gives_each_subsystem_a_reason() {
+        let banner = banner(Trigger::Flag);
+        assert!(banner.starts_with("Safe mode (--safe-mode): config not read"));
+        for name in ["config", "watcher", "terminal", "mouse", "icons"] {
+            assert!(banner.contains(&format!("{} ", name)), "{}", name);
+        }
+        assert!(banner.is_ascii());
+        assert!(super::banner(Trigger::Crash).starts_with("Safe mode (previous run crashed)"));
+    }
+}
@@ -0,0 +1,509 @@
+//! Versions of the files fm keeps between runs.
+//!
+//! Every persisted artifact (the session autosave, the last undo, editor
+//! positions, bookmarks) carries a `schema_version`; files written before
+//! versioning have none and count as version 1. Loading reads a file into
+//! a generic value, walks it forward through the artifact's migrations
+//! (v1 → v2 …) and only then deserializes it, so an upgrade never misreads
+//! or drops an older file.
+//!
+//! A file from a newer fm is read as far as this build understands it, and
+//! [`save`] refuses to overwrite it. A file that doesn't parse is moved
+//! aside to `<name>.corrupt-<ms>` by [`check_startup`] rather than being
+//! replaced by the next save. `fm migrate` rewrites every artifact at the
+//! current version, and `--dry-run` lists what it would do.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::bookmarks::BOOKMARKS_FILE;
+use crate::edit_positions::POSITIONS_FILE;
+use crate::session::{self, AUTOSAVE_FILE, UNDO_FILE};
+
+/// Key holding the version in every artifact.
+pub const VERSION_KEY: &str = "schema_version";
+
+/// A kind of file fm persists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Artifact {
+    /// Session autosave for crash recovery (session directory).
+    Autosave,
+    /// The last undo (session directory).
+    Undo,
+    /// Editor cursor positions (session directory).
+    Positions,
+    /// Bookmarked directories (config directory).
+    Bookmarks,
+}
+
+impl Artifact {
+    /// Artifacts kept in the session directory.
+    pub const SESSION: [Artifact; 3] = [Artifact::Autosave, Artifact::Undo, Artifact::Positions];
+
+    /// Artifacts kept in the config directory.
+    pub const CONFIG: [Artifact; 1] = [Artifact::Bookmarks];
+
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Artifact::Autosave => AUTOSAVE_FILE,
+            Artifact::Undo => UNDO_FILE,
+            Artifact::Positions => POSITIONS_FILE,
+            Artifact::Bookmarks => BOOKMARKS_FILE,
+        }
+    }
+
+    /// Version this build writes.
+    pub fn current(self) -> u32 {
+        2
+    }
+
+    fn is_toml(self) -> bool {
+        self == Artifact::Bookmarks
+    }
+
+    /// Turn `value`, a file at `version`, into one at `version + 1`.
+    fn migrate(self, version: u32, value: &mut Value) -> Result<(), String> {
+        let map = value
+            .as_object_mut()
+            .ok_or_else(|| "not a table".to_string())?;
+        match (self, version) {
+            // Autosaves from before per-directory sort overrides
+            (Artifact::Autosave, 1) => {
+                map.entry("sort_overrides")
+                    .or_insert_with(|| Value::Array(Vec::new()));
+            }
+            // The undo had its own version field
+            (Artifact::Undo, 1) => {
+                map.remove("version");
+            }
+            // Only the version was added
+            (Artifact::Positions | Artifact::Bookmarks, 1) => {}
+            _ => return Err(format!("no migration from version {}", version)),
+        }
+        Ok(())
+    }
+}
+
+/// What is on disk for an artifact.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    Missing,
+    Current,
+    /// At an older version, brought up to date on load.
+    Outdated(u32),
+    /// Written by a newer fm; read-only here.
+    Newer(u32),
+    /// Doesn't parse, or fails to migrate.
+    Corrupt(String),
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Status::Missing => write!(f, "missing"),
+            Status::Current => write!(f, "up to date"),
+            Status::Outdated(version) => write!(f, "version {}", version),
+            Status::Newer(version) => write!(f, "version {} (newer fm)", version),
+            Status::Corrupt(reason) => write!(f, "unreadable: {}", reason),
+        }
+    }
+}
+
+/// `bytes` as a generic value, TOML files included.
+fn parse(artifact: Artifact, bytes: &[u8]) -> Result<Value, String> {
+    if artifact.is_toml() {
+        let text = std::str::from_utf8(bytes).map_err(|e| e.to_string())?;
+        let table: toml::Value = toml::from_str(text).map_err(|e| e.message().to_string())?;
+        serde_json::to_value(table).map_err(|e| e.to_string())
+    } else {
+        serde_json::from_slice(bytes).map_err(|e| e.to_string())
+    }
+}
+
+/// Version recorded in `value`; 1 when there is none.
+fn version_of(value: &Value) -> Result<u32, String> {
+    match value.get(VERSION_KEY) {
+        None => Ok(1),
+        Some(v) => v
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .filter(|v| *v >= 1)
+            .ok_or_else(|| format!("bad {}: {}", VERSION_KEY, v)),
+    }
+}
+
+/// `bytes` brought up to the current version, with the version they were
+/// at. A newer file is returned as it is.
+fn upgrade(artifact: Artifact, bytes: &[u8]) -> Result<(Value, u32), String> {
+    let mut value = parse(artifact, bytes)?;
+    if !value.is_object() {
+        return Err("not a table".to_string());
+    }
+    let from = version_of(&value)?;
+    for version in from..artifact.current() {
+        artifact.migrate(version, &mut value)?;
+    }
+    Ok((value, from))
+}
+
+/// Where the artifact is kept in `dir`.
+pub fn path(artifact: Artifact, dir: &Path) -> PathBuf {
+    dir.join(artifact.file_name())
+}
+
+/// What `bytes`, the contents of an artifact file, are.
+pub fn status_of(artifact: Artifact, bytes: &[u8]) -> Status {
+    match upgrade(artifact, bytes) {
+        Ok((_, from)) if from > artifact.current() => Status::Newer(from),
+        Ok((_, from)) if from < artifact.current() => Status::Outdated(from),
+        Ok(_) => Status::Current,
+        Err(reason) => Status::Corrupt(reason),
+    }
+}
+
+/// What is on disk for `artifact` in `dir`.
+pub fn status(artifact: Artifact, dir: &Path) -> Status {
+    match fs::read(path(artifact, dir)) {
+        Ok(bytes) => status_of(artifact, &bytes),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Status::Missing,
+        Err(e) => Status::Corrupt(e.to_string()),
+    }
+}
+
+/// Decode an artifact from `bytes`, migrating older versions. A newer
+/// version is decoded as far as this build understands it.
+pub fn decode<T: DeserializeOwned>(artifact: Artifact, bytes: &[u8]) -> Result<T, String> {
+    let (mut value, _) = upgrade(artifact, bytes)?;
+    if let Some(map) = value.as_object_mut() {
+        map.remove(VERSION_KEY);
+    }
+    serde_json::from_value(value).map_err(|e| e.to_string())
+}
+
+/// Read `artifact` from `dir`; `None` when missing or unreadable.
+pub fn load<T: DeserializeOwned>(artifact: Artifact, dir: &Path) -> Option<T> {
+    let bytes = fs::read(path(artifact, dir)).ok()?;
+    decode(artifact, &bytes).ok()
+}
+
+/// `value` as the artifact's file contents at the current version.
+pub fn encode<T: Serialize>(artifact: Artifact, value: &T) -> io::Result<Vec<u8>> {
+    let mut value = serde_json::to_value(value).map_err(io::Error::other)?;
+    let map = value
+        .as_object_mut()
+        .ok_or_else(|| io::Error::other("not a table"))?;
+    map.insert(VERSION_KEY.to_string(), artifact.current().into());
+    if artifact.is_toml() {
+        let text = toml::to_string_pretty(&value).map_err(io::Error::other)?;
+        Ok(text.into_bytes())
+    } else {
+        serde_json::to_vec_pretty(&value).map_err(io::Error::other)
+    }
+}
+
+/// Error for a write that would replace a newer fm's file.
+fn newer_file(artifact: Artifact, version: u32) -> io::Error {
+    io::Error::other(format!(
+        "{} is from a newer fm (version {}); left unchanged",
+        artifact.file_name(),
+        version
+    ))
+}
+
+/// Refuse to touch `artifact` in `dir` when a newer fm wrote it.
+pub fn check_writable(artifact: Artifact, dir: &Path) -> io::Result<()> {
+    match status(artifact, dir) {
+        Status::Newer(version) => Err(newer_file(artifact, version)),
+        _ => Ok(()),
+    }
+}
+
+/// Atomically write `value` as `artifact` in `dir`, unless the file there
+/// is from a newer fm.
+pub fn save<T: Serialize>(artifact: Artifact, dir: &Path, value: &T) -> io::Result<()> {
+    check_writable(artifact, dir)?;
+    session::write_atomic(&path(artifact, dir), &encode(artifact, value)?)
+}
+
+/// Move an unreadable file aside to `<name>.corrupt-<ms>`, returning the
+/// new path.
+pub fn quarantine(path: &Path) -> io::Result<PathBuf> {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".corrupt-{}", session::now_ms()));
+    let aside = path.with_file_name(name);
+    fs::rename(path, &aside)?;
+    Ok(aside)
+}
+
+/// Check `artifacts` in `dir` before they are loaded: unreadable files
+/// are moved aside and newer ones noted. Returns a warning for each.
+pub fn check_startup(artifacts: &[Artifact], dir: &Path) -> Vec<String> {
+    let mut warnings = Vec::new();
+    for &artifact in artifacts {
+        let file = path(artifact, dir);
+        match status(artifact, dir) {
+            Status::Corrupt(reason) => match quarantine(&file) {
+                Ok(aside) => warnings.push(format!(
+                    "{} was unreadable ({}); moved aside to {}",
+                    artifact.file_name(),
+                    reason,
+                    aside.display()
+                )),
+                Err(e) => warnings.push(format!(
+                    "{} is unreadable ({}) and could not be moved aside: {}",
+                    artifact.file_name(),
+                    reason,
+                    e
+                )),
+            },
+            Status::Newer(version) => warnings.push(format!(
+                "{} is from a newer fm (version {}); read-only",
+                artifact.file_name(),
+                version
+            )),
+            Status::Missing | Status::Current | Status::Outdated(_) => {}
+        }
+    }
+    warnings
+}
+
+/// What `fm migrate` does (or would do) to one artifact.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    pub path: PathBuf,
+    pub artifact: Artifact,
+    pub status: Status,
+}
+
+impl Step {
+    /// One line for the migrate report.
+    pub fn describe(&self, dry_run: bool) -> String {
+        let action = match (&self.status, dry_run) {
+            (Status::Missing, _) => return format!("{:<10} {}", "missing", self.path.display()),
+            (Status::Current, _) => "current",
+            (Status::Outdated(_), true) => "migrate",
+            (Status::Outdated(_), false) => "migrated",
+            (Status::Newer(_), _) => "skip",
+            (Status::Corrupt(_), true) => "move aside",
+            (Status::Corrupt(_), false) => "moved aside",
+        };
+        let detail = match &self.status {
+            Status::Outdated(from) => {
+                format!("version {} → {}", from, self.artifact.current())
+            }
+            status => status.to_string(),
+        };
+        format!("{:<10} {} ({})", action, self.path.display(), detail)
+    }
+}
+
+/// Bring every artifact in `dir` up to the current version: outdated
+/// files are rewritten, unreadable ones moved aside and newer ones left
+/// alone. With `dry_run` nothing is touched.
+pub fn migrate_dir(artifacts: &[Artifact], dir: &Path, dry_run: bool) -> io::Result<Vec<Step>> {
+    let mut steps = Vec::new();
+    for &artifact in artifacts {
+        let file = path(artifact, dir);
+        let status = status(artifact, dir);
+        if !dry_run {
+            match &status {
+                Status::Outdated(_) => {
+                    let (mut value, _) =
+                        upgrade(artifact, &fs::read(&file)?).map_err(io::Error::other)?;
+                    if let Some(map) = value.as_object_mut() {
+                        map.remove(VERSION_KEY);
+                    }
+                    session::write_atomic(&file, &encode(artifact, &value)?)?;
+                }
+                Status::Corrupt(_) => {
+                    quarantine(&file)?;
+                }
+                Status::Missing | Status::Current | Status::Newer(_) => {}
+            }
+        }
+        steps.push(Step {
+            path: file,
+            artifact,
+            status,
+        });
+    }
+    Ok(steps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bookmarks::Bookmarks;
+    use crate::edit_positions::EditPositions;
+    use crate::session::{PersistedUndo, SessionSnapshot};
+    use tempfile::TempDir;
+
+    /// Files as each version of fm wrote them.
+    fn fixture(artifact: Artifact, version: u32) -> &'static str {
+        match (artifact, version) {
+            (Artifact::Autosave, 1) => include_str!("../tests/fixtures/schema/autosave.v1.json"),
+            (Artifact::Autosave, 2) => include_str!("../tests/fixtures/schema/autosave.v2.json"),
+            (Artifact::Undo, 1) => include_str!("../tests/fixtures/schema/undo.v1.json"),
+            (Artifact::Undo, 2) => include_str!("../tests/fixtures/schema/undo.v2.json"),
+            (Artifact::Positions, 1) => {
+                include_str!("../tests/fixtures/schema/positions.v1.json")
+            }
+            (Artifact::Positions, 2) => {
+                include_str!("../tests/fixtures/schema/positions.v2.json")
+            }
+            (Artifact::Bookmarks, 1) => include_str!("../tests/fixtures/schema/bookmarks.v1.toml"),
+            (Artifact::Bookmarks, 2) => include_str!("../tests/fixtures/schema/bookmarks.v2.toml"),
+            _ => unreachable!("no fixture for {:?} v{}", artifact, version),
+        }
+    }
+
+    /// Decode every historical version of `artifact` and check that each
+    /// comes out as the current file, byte for byte, once written back.
+    fn round_trip<T: Serialize + DeserializeOwned + PartialEq + fmt::Debug>(artifact: Artifact) {
+        let current = fixture(artifact, artifact.current());
+        let expected: T = decode(artifact, current.as_bytes()).unwrap();
+        for version in 1..=artifact.current() {
+            let old = fixture(artifact, version);
+            let decoded: T = decode(artifact, old.as_bytes()).unwrap();
+            assert_eq!(decoded, expected, "{:?} v{}", artifact, version);
+            let written = encode(artifact, &decoded).unwrap();
+            assert_eq!(
+                String::from_utf8(written).unwrap(),
+                current,
+                "{:?} v{} re-encoded",
+                artifact,
+                version
+            );
+        }
+    }
+
+    #[test]
+    fn every_version_round_trips() {
+        round_trip::<SessionSnapshot>(Artifact::Autosave);
+        round_trip::<PersistedUndo>(Artifact::Undo);
+        round_trip::<EditPositions>(Artifact::Positions);
+        round_trip::<Bookmarks>(Artifact::Bookmarks);
+    }
+
+    #[test]
+    fn statuses_of_each_version() {
+        for artifact in Artifact::SESSION.into_iter().chain(Artifact::CONFIG) {
+            assert_eq!(
+                status_of(artifact, fixture(artifact, 1).as_bytes()),
+                Status::Outdated(1)
+            );
+            assert_eq!(
+                status_of(artifact, fixture(artifact, 2).as_bytes()),
+                Status::Current
+            );
+        }
+        assert!(matches!(
+            status_of(Artifact::Undo, b"{ not json"),
+            Status::Corrupt(_)
+        ));
+        assert!(matches!(
+            status_of(Artifact::Undo, b"[1, 2]"),
+            Status::Corrupt(_)
+        ));
+        assert!(matches!(
+            status_of(Artifact::Bookmarks, b"schema_version = \"two\""),
+            Status::Corrupt(_)
+        ));
+    }
+
+    #[test]
+    fn newer_files_are_read_but_never_overwritten() {
+        let dir = TempDir::new().unwrap();
+        let newer = r#"{"schema_version": 7, "paths": ["/srv"], "tags": {"/srv": "work"}}"#;
+        // Bookmarks are TOML; write the newer file as such
+        let table: toml::Value = serde_json::from_str(newer).unwrap();
+        let text = toml::to_string(&table).unwrap();
+        fs::write(path(Artifact::Bookmarks, dir.path()), &text).unwrap();
+
+        assert_eq!(status(Artifact::Bookmarks, dir.path()), Status::Newer(7));
+        let loaded: Bookmarks = load(Artifact::Bookmarks, dir.path()).unwrap();
+        assert_eq!(loaded.paths(), &[PathBuf::from("/srv")]);
+
+        let err = save(Artifact::Bookmarks, dir.path(), &Bookmarks::default()).unwrap_err();
+        assert!(err.to_string().contains("newer fm (version 7)"));
+        assert_eq!(
+            fs::read_to_string(path(Artifact::Bookmarks, dir.path())).unwrap(),
+            text
+        );
+        assert_eq!(
+            check_startup(&Artifact::CONFIG, dir.path()),
+            vec!["bookmarks.toml is from a newer fm (version 7); read-only".to_string()]
+        );
+        let steps = migrate_dir(&Artifact::CONFIG, dir.path(), false).unwrap();
+        assert_eq!(steps[0].status, Status::Newer(7));
+        assert_eq!(
+            fs::read_to_string(path(Artifact::Bookmarks, dir.path())).unwrap(),
+            text
+        );
+    }
+
+    #[test]
+    fn corrupt_files_are_moved_aside_at_startup() {
+        let dir = TempDir::new().unwrap();
+        let file = path(Artifact::Autosave, dir.path());
+        fs::write(&file, "{ torn").unwrap();
+
+        let warnings = check_startup(&Artifact::SESSION, dir.path());
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("autosave.json was unreadable ("));
+        assert!(!file.exists());
+        let aside: Vec<String> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(aside.len(), 1);
+        assert!(aside[0].starts_with("autosave.json.corrupt-"));
+        assert_eq!(
+            fs::read_to_string(dir.path().join(&aside[0])).unwrap(),
+            "{ torn"
+        );
+
+        // Nothing left to warn about
+        assert!(check_startup(&Artifact::SESSION, dir.path()).is_empty());
+    }
+
+    #[test]
+    fn migrate_rewrites_outdated_files_unless_dry_run() {
+        let dir = TempDir::new().unwrap();
+        for artifact in Artifact::SESSION {
+            fs::write(path(artifact, dir.path()), fixture(artifact, 1)).unwrap();
+        }
+
+        let planned = migrate_dir(&Artifact::SESSION, dir.path(), true).unwrap();
+        assert!(planned.iter().all(|s| s.status == Status::Outdated(1)));
+        assert_eq!(
+            planned[1].describe(true),
+            format!(
+                "migrate    {} (version 1 → 2)",
+                path(Artifact::Undo, dir.path()).display()
+            )
+        );
+        for artifact in Artifact::SESSION {
+            assert_eq!(
+                fs::read_to_string(path(artifact, dir.path())).unwrap(),
+                fixture(artifact, 1)
+            );
+        }
+
+        migrate_dir(&Artifact::SESSION, dir.path(), false).unwrap();
+        for artifact in Artifact::SESSION {
+            assert_eq!(
+                fs::read_to_string(path(artifact, dir.path())).unwrap(),
+                fixture(artifact, 2)
+            );
+        }
+        let again = migrate_dir(&Artifact::SESSION, dir.path(), false).unwrap();
+        assert!(again.iter().all(|s| s.status == Status::Current));
+    }
+}
@@ -0,0 +1,558 @@
+//! Periodic session autosave and crash recovery.
+//!
+//! While the app runs, `App::poll_autosave` serializes a [`SessionSnapshot`]
+//! on a background task every `general.autosave_secs` of activity, and soon
+//! after layout changes (expansion, root, sort, hidden files). Each save
+//! goes to a temp file that is renamed over `autosave.json`, so a crash
+//! mid-write never leaves a torn file. A clean shutdown writes the
+//! `clean-exit` marker; on the next start an autosave newer than the marker
+//! means the last run didn't exit cleanly, and the user is offered the
+//! session back before the tree is built.
+//!
+//! The last undo is kept separately in `undo.json`, written on every exit
+//! and read on every start, so Ctrl+Z still works after a restart. Editor
+//! positions are kept the same way (see `edit_positions`).
+
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::app::UndoAction;
+use crate::schema::{self, Artifact};
+
+/// Autosave file name inside the session directory.
+pub const AUTOSAVE_FILE: &str = "autosave.json";
+
+/// Marker written on clean shutdown, holding the exit time in ms.
+pub const CLEAN_EXIT_FILE: &str = "clean-exit";
+
+/// How often the tick checks whether a save is due.
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Persisted undo file name inside the session directory.
+pub const UNDO_FILE: &str = "undo.json";
+
+/// The last undo as written to [`UNDO_FILE`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PersistedUndo {
+    /// Unix time of the save in milliseconds.
+    pub saved_at_ms: u64,
+    pub action: UndoAction,
+}
+
+/// Lightweight copy of the state worth restoring after a crash.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionSnapshot {
+    /// Tree root.
+    pub root: PathBuf,
+    /// Expanded directories, sorted for stable output.
+    pub expanded: Vec<PathBuf>,
+    /// Selected path, if any.
+    pub selected: Option<PathBuf>,
+    pub scroll_offset: usize,
+    /// Sort mode as in the config: "name", "size" or "modified".
+    pub sort_by: String,
+    pub dirs_first: bool,
+    pub show_hidden: bool,
+    /// Per-directory sort overrides, sorted by directory. Missing in
+    /// autosaves written before overrides existed.
+    #[serde(default)]
+    pub sort_overrides: Vec<SortOverride>,
+    /// Unix time of the snapshot in milliseconds.
+    pub saved_at_ms: u64,
+}
+
+/// A directory whose children are sorted differently from the global sort.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SortOverride {
+    pub dir: PathBuf,
+    /// Sort mode as in the config: "name", "size" or "modified".
+    pub sort_by: String,
+    pub dirs_first: bool,
+}
+
+impl SessionSnapshot {
+    /// Whether the tree layout differs, ignoring selection, scroll and time.
+    pub fn layout_differs(&self, other: &SessionSnapshot) -> bool {
+        self.root != other.root
+            || self.expanded != other.expanded
+            || self.sort_by != other.sort_by
+            || self.dirs_first != other.dirs_first
+            || self.show_hidden != other.show_hidden
+            || self.sort_overrides != other.sort_overrides
+    }
+
+    /// Whether anything besides the timestamp differs.
+    pub fn differs(&self, other: &SessionSnapshot) -> bool {
+        self.layout_differs(other)
+            || self.selected != other.selected
+            || self.scroll_offset != other.scroll_offset
+    }
+
+    /// Expanded directories as a set, for `TreeState::restore_expanded`.
+    pub fn expanded_set(&self) -> HashSet<PathBuf> {
+        self.expanded.iter().cloned().collect()
+    }
+}
+
+/// Autosave bookkeeping kept on `App`.
+#[derive(Debug)]
+pub struct AutosaveState {
+    /// Session directory; `None` disables autosave.
+    pub dir: Option<PathBuf>,
+    /// Snapshot handed to the most recent save task.
+    pub last_saved: Option<SessionSnapshot>,
+    /// When that save was started.
+    pub last_saved_at: Instant,
+    /// Last time the tick checked whether a save was due.
+    pub last_check: Instant,
+    /// A save task hasn't reported back yet.
+    pub in_flight: bool,
+    /// The current run of failures has already been reported.
+    pub error_reported: bool,
+}
+
+impl Default for AutosaveState {
+    fn default() -> Self {
+        let now = Instant::now();
+        Self {
+            dir: None,
+            last_saved: None,
+            last_saved_at: now,
+            last_check: now,
+            in_flight: false,
+            error_reported: false,
+        }
+    }
+}
+
+/// Whether `current` should be autosaved now.
+///
+/// Layout changes save right away; selection and scroll changes wait until
+/// there has been input since the last save and `interval` has passed.
+pub fn save_due(
+    last: Option<&SessionSnapshot>,
+    current: &SessionSnapshot,
+    active_since_save: bool,
+    since_save: Duration,
+    interval: Duration,
+) -> bool {
+    match last {
+        None => true,
+        Some(last) => {
+            current.layout_differs(last)
+                || (active_since_save && since_save >= interval && current.differs(last))
+        }
+    }
+}
+
+/// Current Unix time in milliseconds.
+pub fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Default session directory: `<data dir>/fm-tui/session`.
+pub fn session_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|d| d.join("fm-tui").join("session"))
+}
+
+/// Write `contents` to a sibling temp file, flush it to disk and rename it
+/// over `path`. Readers see either the old file or the new one.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(".tmp");
+    let tmp = path.with_file_name(tmp_name);
+    {
+        let mut file = fs::File::create(&tmp)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+    }
+    fs::rename(&tmp, path).inspect_err(|_| {
+        let _ = fs::remove_file(&tmp);
+    })
+}
+
+/// Atomically replace the autosave in `dir` with `snapshot`.
+pub fn save(dir: &Path, snapshot: &SessionSnapshot) -> io::Result<()> {
+    schema::save(Artifact::Autosave, dir, snapshot)
+}
+
+/// Record a clean shutdown (or a declined recovery) at `at_ms`.
+pub fn mark_clean_exit(dir: &Path, at_ms: u64) -> io::Result<()> {
+    write_atomic(&dir.join(CLEAN_EXIT_FILE), at_ms.to_string().as_bytes())
+}
+
+/// Read the autosave in `dir`, if present and parseable.
+pub fn load_autosave(dir: &Path) -> Option<SessionSnapshot> {
+    schema::load(Artifact::Autosave, dir)
+}
+
+/// Persist `action` to `dir`, or remove the saved one when there is none.
+pub fn save_undo(dir: &Path, action: Option<&UndoAction>) -> io::Result<()> {
+    let action = match action {
+        Some(action) => action,
+        None => {
+            schema::check_writable(Artifact::Undo, dir)?;
+            return match fs::remove_file(dir.join(UNDO_FILE)) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+                _ => Ok(()),
+            };
+        }
+    };
+    let persisted = PersistedUndo {
+        saved_at_ms: now_ms(),
+        action: action.clone(),
+    };
+    schema::save(Artifact::Undo, dir, &persisted)
+}
+
+/// Read the undo saved in `dir`, if present and of an action kind this
+/// build understands.
+pub fn load_undo(dir: &Path) -> Option<UndoAction> {
+    schema::load::<PersistedUndo>(Artifact::Undo, dir).map(|persisted| persisted.action)
+}
+
+/// Read the clean-exit marker time in `dir`, if present.
+pub fn clean_exit_at(dir: &Path) -> Option<u64> {
+    fs::read_to_string(dir.join(CLEAN_EXIT_FILE))
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// Whether an autosave should be offered for recovery: it must postdate
+/// the last clean exit (if any) and its root must still exist.
+pub fn should_offer(autosave: &SessionSnapshot, clean_exit_ms: Option<u64>) -> bool {
+    let newer = clean_exit_ms.is_none_or(|exit| autosave.saved_at_ms > exit);
+    newer && autosave.root.is_dir()
+}
+
+/// The autosave in `dir` worth offering after an unclean exit, if any.
+pub fn recovery_offer(dir: &Path) -> Option<SessionSnapshot> {
+    let autosave = load_autosave(dir)?;
+    should_offer(&autosave, clean_exit_at(dir)).then_some(autosave)
+}
+
+/// Whether the last run that autosaved in `dir` ended without writing
+/// the clean-exit marker, i.e. crashed or was killed.
+pub fn previous_run_crashed(dir: &Path) -> bool {
+    load_autosave(dir)
+        .is_some_and(|autosave| clean_exit_at(dir).is_none_or(|exit| autosave.saved_at_ms > exit))
+}
+
+/// `HH:MM` (UTC) of a Unix time in milliseconds.
+pub fn clock_time(ms: u64) -> String {
+    let secs = ms / 1000;
+    let remaining = secs % 86400;
+    format!("{:02}:{:02}", remaining / 3600, (remaining % 3600) / 60)
+}
+
+/// Question shown before restoring `snapshot`.
+pub fn offer_prompt(snapshot: &SessionSnapshot) -> String {
+    format!(
+        "recovered session from {} ({}) — restore? [y/N] ",
+        clock_time(snapshot.saved_at_ms),
+        snapshot.root.display()
+    )
+}
+
+/// Whether a prompt answer means yes.
+pub fn is_yes(answer: &str) -> bool {
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+/// Ask on the terminal whether to restore `snapshot`. Runs before the TUI
+/// takes over the screen; anything but "y" declines.
+pub fn confirm_restore(snapshot: &SessionSnapshot) -> bool {
+    confirm(&offer_prompt(snapshot))
+}
+
+/// Ask `prompt` on the terminal before the TUI starts; anything but "y"
+/// declines.
+pub fn confirm(prompt: &str) -> bool {
+    print!("{}", prompt);
+    if io::stdout().flush().is_err() {
+        return false;
+    }
+    let mut answer = String::new();
+    match io::stdin().lock().read_line(&mut answer) {
+        Ok(_) => is_yes(&answer),
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(root: &Path, saved_at_ms: u64) -> SessionSnapshot {
+        SessionSnapshot {
+            root: root.to_path_buf(),
+            expanded: vec![root.join("alpha")],
+            selected: Some(root.join("alpha").join("a.txt")),
+            scroll_offset: 3,
+            sort_by: "size".to_string(),
+            dirs_first: false,
+            show_hidden: true,
+            sort_overrides: vec![SortOverride {
+                dir: root.join("alpha"),
+                sort_by: "modified".to_string(),
+                dirs_first: true,
+            }],
+            saved_at_ms,
+        }
+    }
+
+    #[test]
+    fn offer_only_after_unclean_exit() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let autosave = snapshot(dir.path(), 2_000);
+        // Never exited cleanly
+        assert!(should_offer(&autosave, None));
+        // Crashed after the last clean exit
+        assert!(should_offer(&autosave, Some(1_000)));
+        // Exited cleanly after the autosave
+        assert!(!should_offer(&autosave, Some(2_000)));
+        assert!(!should_offer(&autosave, Some(3_000)));
+        // Root is gone
+        let gone = snapshot(&dir.path().join("missing"), 2_000);
+        assert!(!should_offer(&gone, None));
+    }
+
+    #[test]
+    fn recovery_offer_reads_files() {
+        let session = tempfile::tempdir().expect("tempdir");
+        let root = tempfile::tempdir().expect("tempdir");
+        assert_eq!(recovery_offer(session.path()), None);
+
+        let saved = snapshot(root.path(), 5_000);
+        save(session.path(), &saved).unwrap();
+        assert_eq!(recovery_offer(session.path()), Some(saved.clone()));
+
+        mark_clean_exit(session.path(), 6_000).unwrap();
+        assert_eq!(clean_exit_at(session.path()), Some(6_000));
+        assert_eq!(recovery_offer(session.path()), None);
+
+        // A corrupt autosave is never offered
+        fs::write(session.path().join(AUTOSAVE_FILE), "{ not json").unwrap();
+        fs::remove_file(session.path().join(CLEAN_EXIT_FILE)).unwrap();
+        assert_eq!(recovery_offer(session.path()), None);
+    }
+
+    #[test]
+    fn crash_detected_from_clean_exit_marker() {
+        let session = tempfile::tempdir().expect("tempdir");
+        assert!(!previous_run_crashed(session.path()));
+
+        // Even when the autosaved root is gone
+        save(
+            session.path(),
+            &snapshot(&session.path().join("gone"), 5_000),
+        )
+        .unwrap();
+        assert!(previous_run_crashed(session.path()));
+
+        mark_clean_exit(session.path(), 6_000).unwrap();
+        assert!(!previous_run_crashed(session.path()));
+    }
+
+    #[test]
+    fn write_atomic_replaces_without_leftovers() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let target = dir.path().join("nested").join(AUTOSAVE_FILE);
+        write_atomic(&target, b"first").unwrap();
+        write_atomic(&target, b"second").unwrap();
+        assert_eq!(fs::read(&target).unwrap(), b"second");
+        let names: Vec<_> = fs::read_dir(target.parent().unwrap())
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(names, vec![std::ffi::OsString::from(AUTOSAVE_FILE)]);
+    }
+
+    #[test]
+    fn write_atomic_keeps_old_file_when_rename_fails() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        // A directory in the way makes the rename fail
+        let target = dir.path().join(AUTOSAVE_FILE);
+        fs::create_dir(&target).unwrap();
+        fs::write(target.join("keep"), "old").unwrap();
+        assert!(write_atomic(&target, b"new").is_err());
+        assert_eq!(fs::read_to_string(target.join("keep")).unwrap(), "old");
+        assert!(!dir.path().join("autosave.json.tmp").exists());
+    }
+
+    #[test]
+    fn save_due_matrix() {
+        let root = Path::new("/tmp/root");
+        let base = snapshot(root, 1);
+        let interval = Duration::from_secs(30);
+        let early = Duration::from_secs(5);
+        let late = Duration::from_secs(31);
+        let mut scrolled = base.clone();
+        scrolled.scroll_offset = 9;
+        let mut expanded = base.clone();
+        expanded.expanded.push(root.join("beta"));
+
+        // Nothing saved yet this run
+        assert!(save_due(None, &base, false, early, interval));
+        // Layout changes don't wait for the interval
+        assert!(save_due(Some(&base), &expanded, false, early, interval));
+        // Other changes wait for activity and the interval
+        assert!(!save_due(Some(&base), &scrolled, true, early, interval));
+        assert!(!save_due(Some(&base), &scrolled, false, late, interval));
+        assert!(save_due(Some(&base), &scrolled, true, late, interval));
+        // Unchanged state is never rewritten
+        assert!(!save_due(Some(&base), &base, true, late, interval));
+    }
+
+    #[test]
+    fn change_detection() {
+        let root = Path::new("/tmp/root");
+        let base = snapshot(root, 1);
+        let mut moved = base.clone();
+        moved.saved_at_ms = 99;
+        assert!(!moved.differs(&base));
+        moved.scroll_offset = 0;
+        assert!(moved.differs(&base));
+        assert!(!moved.layout_differs(&base));
+        moved.sort_by = "name".to_string();
+        assert!(moved.layout_differs(&base));
+
+        let mut overridden = base.clone();
+        overridden.sort_overrides.clear();
+        assert!(overridden.layout_differs(&base));
+    }
+
+    #[test]
+    fn autosave_without_overrides_still_loads() {
+        let session = tempfile::tempdir().expect("tempdir");
+        let json = r#"{"root":"/srv","expanded":[],"selected":null,"scroll_offset":0,
+            "sort_by":"name","dirs_first":true,"show_hidden":false,"saved_at_ms":1}"#;
+        fs::write(session.path().join(AUTOSAVE_FILE), json).unwrap();
+        let loaded = load_autosave(session.path()).expect("parses");
+        assert!(loaded.sort_overrides.is_empty());
+    }
+
+    #[test]
+    fn undo_round_trips_every_variant() {
+        use crate::fs::overwrite::Backup;
+        use crate::fs::undo_check::{PathStamp, StampedPath};
+
+        let stamped = |p: &str| StampedPath {
+            path: PathBuf::from(p),
+            stamp: Some(PathStamp {
+                size: 42,
+                modified: Some(UNIX_EPOCH + Duration::from_millis(1_700_000_000_123)),
+            }),
+        };
+        let actions = vec![
+            UndoAction::Rename {
+                from: PathBuf::from("/r/old.txt"),
+                to: stamped("/r/new.txt"),
+            },
+            UndoAction::CopyPaste {
+                created_paths: vec![
+                    stamped("/r/a (copy).txt"),
+                    StampedPath {
+                        path: PathBuf::from("/r/b"),
+                        stamp: None,
+                    },
+                ],
+                backups: Vec::new(),
+            },
+            UndoAction::MovePaste {
+                moves: vec![(PathBuf::from("/r/a.txt"), stamped("/r/beta/a.txt"))],
+                backups: vec![Backup::Renamed {
+                    original: PathBuf::from("/r/beta/a.txt"),
+                    backup: stamped("/r/beta/a.txt.fm-bak-20261016-083841"),
+                }],
+            },
+            UndoAction::SequenceRename {
+                renames: vec![
+                    (PathBuf::from("/r/x.jpg"), stamped("/r/img001.jpg")),
+                    (PathBuf::from("/r/y.jpg"), stamped("/r/img002.jpg")),
+                ],
+            },
+            UndoAction::Permissions {
+                changes: vec![crate::permissions::ModeChange {
+                    path: PathBuf::from("/r/run.sh"),
+                    old_mode: 0o644,
+                    new_mode: 0o755,
+                }],
+            },
+            UndoAction::Trash {
+                entries: vec![crate::fs::trash::TrashedEntry {
+                    original: PathBuf::from("/r/gone.txt"),
+                    trashed: stamped("/t/files/gone.txt"),
+                    info: Some(PathBuf::from("/t/info/gone.txt.trashinfo")),
+                }],
+            },
+        ];
+        let session = tempfile::tempdir().expect("tempdir");
+        for action in actions {
+            save_undo(session.path(), Some(&action)).unwrap();
+            assert_eq!(load_undo(session.path()), Some(action));
+        }
+
+        save_undo(session.path(), None).unwrap();
+        assert_eq!(load_undo(session.path()), None);
+        // Nothing to remove is fine too
+        save_undo(session.path(), None).unwrap();
+    }
+
+    #[test]
+    fn undo_from_newer_version_is_read_but_kept() {
+        let session = tempfile::tempdir().expect("tempdir");
+        let action = UndoAction::Rename {
+            from: PathBuf::from("/r/old.txt"),
+            to: crate::fs::undo_check::StampedPath {
+                path: PathBuf::from("/r/new.txt"),
+                stamp: None,
+            },
+        };
+        let persisted = PersistedUndo {
+            saved_at_ms: 1,
+            action: action.clone(),
+        };
+        let mut value = serde_json::to_value(&persisted).unwrap();
+        value[schema::VERSION_KEY] = (Artifact::Undo.current() + 1).into();
+        let bytes = serde_json::to_vec(&value).unwrap();
+        let path = session.path().join(UNDO_FILE);
+        fs::write(&path, &bytes).unwrap();
+        assert_eq!(load_undo(session.path()), Some(action));
+
+        // Clearing it would lose the newer fm's undo, so it stays put
+        assert!(save_undo(session.path(), None).is_err());
+        assert_eq!(fs::read(&path).unwrap(), bytes);
+
+        // Unknown action kinds are ignored
+        let json = r#"{"version":1,"saved_at_ms":1,"action":{"kind":"compress","paths":[]}}"#;
+        fs::write(&path, json).unwrap();
+        assert_eq!(load_undo(session.path()), None);
+    }
+
+    #[test]
+    fn prompt_and_answers() {
+        let prompt = offer_prompt(&snapshot(Path::new("/srv/data"), 52_320_000));
+        assert_eq!(
+            prompt,
+            "recovered session from 14:32 (/srv/data) — restore? [y/N] "
+        );
+        assert!(is_yes("y\n"));
+        assert!(is_yes(" YES "));
+        assert!(!is_yes("\n"));
+        assert!(!is_yes("no"));
+    }
+}
@@ -0,0 +1,260 @@
+//! Deferred metadata for tree rows on slow filesystems.
+//!
+//! On NFS or SMB every stat is a network round trip, so stat-ing each entry
+//! while expanding a 2,000-entry directory blocks for seconds. With
+//! deferral on (`tree.deferred_stats`, see [`StatMode`]) directory loads
+//! build nodes from the snapshot's names and file types alone and leave
+//! their `FileMeta` pending. On each tick `App::poll_stats` takes pending
+//! rows — the viewport first, then the rest of the tree — in batches of
+//! [`BATCH_SIZE`], stats them on `TaskKind::Stat` tasks and patches the
+//! nodes when `StatsReady` arrives. A directory sorted by size or mtime is
+//! re-sorted once none of its loaded children is pending; until then the
+//! tree title marks its order as provisional.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::fs::places::{self, Mount};
+use crate::fs::tree::{FileMeta, FlatItem};
+
+/// Paths stat-ed by one task.
+pub const BATCH_SIZE: usize = 64;
+
+/// Most stat batches running at once.
+pub const MAX_IN_FLIGHT: usize = 2;
+
+/// When directory loads leave stats to the background.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatMode {
+    /// Only on network filesystems.
+    Auto,
+    Always,
+    Never,
+}
+
+impl StatMode {
+    /// Parse `tree.deferred_stats`; unknown values mean `Auto`.
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "always" => StatMode::Always,
+            "never" => StatMode::Never,
+            _ => StatMode::Auto,
+        }
+    }
+
+    /// Whether a tree rooted at `root` defers its stats.
+    pub fn defers(self, root: &Path, mounts: &[Mount]) -> bool {
+        match self {
+            StatMode::Auto => places::is_network_path(root, mounts),
+            StatMode::Always => true,
+            StatMode::Never => false,
+        }
+    }
+}
+
+/// Where stats come from; swapped out in tests to simulate a slow mount.
+pub trait StatSource: Send + Sync {
+    /// Metadata for `path`, or `None` if it can't be read.
+    fn stat(&self, path: &Path) -> Option<FileMeta>;
+}
+
+/// Stats from the filesystem.
+pub struct SystemStat;
+
+impl StatSource for SystemStat {
+    fn stat(&self, path: &Path) -> Option<FileMeta> {
+        FileMeta::stat(path).ok()
+    }
+}
+
+/// A finished stat batch.
+#[derive(Debug)]
+pub struct StatBatch {
+    /// Generation the batch was started in.
+    pub generation: u64,
+    /// Every path the batch was given.
+    pub paths: Vec<PathBuf>,
+    /// Metadata for the paths stat-ed before the batch finished or was
+    /// cancelled; `None` for paths that couldn't be read.
+    pub results: Vec<(PathBuf, Option<FileMeta>)>,
+}
+
+/// Paths being stat-ed and the batches carrying them.
+#[derive(Debug, Default)]
+pub struct StatState {
+    in_flight: HashSet<PathBuf>,
+    batches: usize,
+    /// Bumped by `reset`; batches from older generations are ignored.
+    pub generation: u64,
+}
+
+impl StatState {
+    /// Forget the running batches, e.g. after re-rooting.
+    pub fn reset(&mut self) {
+        self.in_flight.clear();
+        self.batches = 0;
+        self.generation = self.generation.wrapping_add(1);
+    }
+
+    /// Split `pending` (in priority order) into as many batches as there
+    /// are free slots, skipping paths already being stat-ed.
+    pub fn take_batches(&mut self, pending: Vec<PathBuf>) -> Vec<Vec<PathBuf>> {
+        let slots = MAX_IN_FLIGHT.saturating_sub(self.batches);
+        let mut batches: Vec<Vec<PathBuf>> = Vec::new();
+        for path in pending {
+            if batches.len() == slots && batches.last().is_none_or(|b| b.len() == BATCH_SIZE) {
+                break;
+            }
+            if self.in_flight.contains(&path) {
+                continue;
+            }
+            match batches.last_mut() {
+                Some(batch) if batch.len() < BATCH_SIZE => batch.push(path.clone()),
+                _ => batches.push(vec![path.clone()]),
+            }
+            self.in_flight.insert(path);
+        }
+        self.batches += batches.len();
+        batches
+    }
+
+    /// Claim a finished batch. Returns `false` if it belongs to an older
+    /// generation and should be discarded.
+    pub fn accept(&mut self, batch: &StatBatch) -> bool {
+        if batch.generation != self.generation {
+            return false;
+        }
+        self.batches = self.batches.saturating_sub(1);
+        for path in &batch.paths {
+            self.in_flight.remove(path);
+        }
+        true
+    }
+
+    /// Number of batches running.
+    pub fn batches_in_flight(&self) -> usize {
+        self.batches
+    }
+}
+
+/// Pending rows of `items` in the order they should be stat-ed: the
+/// `rows` rows from `scroll` first, then the rest from the top.
+pub fn pending_in_priority(items: &[FlatItem], scroll: usize, rows: usize) -> Vec<PathBuf> {
+    let end = scroll.saturating_add(rows).min(items.len());
+    let start = scroll.min(end);
+    let visible = &items[start..end];
+    let rest = items[..start].iter().chain(&items[end..]);
+    visible
+        .iter()
+        .chain(rest)
+        .filter(|item| item.stat_pending)
+        .map(|item| item.path.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::tree::NodeType;
+
+    fn item(name: &str, pending: bool) -> FlatItem {
+        FlatItem {
+            name: name.to_string(),
+            path: PathBuf::from("/r").join(name),
+            node_type: NodeType::File,
+            depth: 1,
+            is_expanded: false,
+            is_last_sibling: false,
+            is_hidden: false,
+            is_ignored: false,
+            load_more_parent: None,
+            load_more_remaining: None,
+            child_count: None,
+            stat_pending: pending,
+            link: None,
+            special: None,
+        }
+    }
+
+    fn paths(names: &[&str]) -> Vec<PathBuf> {
+        names.iter().map(|n| PathBuf::from("/r").join(n)).collect()
+    }
+
+    #[test]
+    fn viewport_rows_come_first() {
+        let items = vec![
+            item("a", true),
+            item("b", false),
+            item("c", true),
+            item("d", true),
+            item("e", true),
+        ];
+        assert_eq!(
+            pending_in_priority(&items, 2, 2),
+            paths(&["c", "d", "a", "e"])
+        );
+        // A viewport past the end leaves plain top-down order
+        assert_eq!(
+            pending_in_priority(&items, 10, 5),
+            paths(&["a", "c", "d", "e"])
+        );
+    }
+
+    #[test]
+    fn batches_fill_free_slots_and_skip_in_flight() {
+        let mut state = StatState::default();
+        let many: Vec<PathBuf> = (0..BATCH_SIZE * 3)
+            .map(|i| PathBuf::from(format!("/r/{}", i)))
+            .collect();
+        let batches = state.take_batches(many.clone());
+        assert_eq!(batches.len(), MAX_IN_FLIGHT);
+        assert!(batches.iter().all(|b| b.len() == BATCH_SIZE));
+        assert_eq!(batches[0][0], many[0]);
+        assert!(state.take_batches(many.clone()).is_empty());
+
+        let done = StatBatch {
+            generation: state.generation,
+            paths: batches[0].clone(),
+            results: Vec::new(),
+        };
+        assert!(state.accept(&done));
+        // The still-running batch's paths aren't handed out twice
+        let next = state.take_batches(many.clone());
+        assert_eq!(next.len(), 1);
+        assert_eq!(next[0][0], many[0]);
+        assert!(!next[0].contains(&batches[1][0]));
+    }
+
+    #[test]
+    fn reset_discards_old_batches() {
+        let mut state = StatState::default();
+        let batches = state.take_batches(paths(&["a"]));
+        let old = StatBatch {
+            generation: state.generation,
+            paths: batches[0].clone(),
+            results: Vec::new(),
+        };
+        state.reset();
+        assert!(!state.accept(&old));
+        assert_eq!(state.batches_in_flight(), 0);
+        assert_eq!(state.take_batches(paths(&["a"])).len(), 1);
+    }
+
+    #[test]
+    fn mode_parses_and_decides() {
+        let nfs = Mount {
+            device: "nas:/export".to_string(),
+            path: PathBuf::from("/mnt/nas"),
+            fs_type: "nfs4".to_string(),
+        };
+        let mounts = vec![nfs];
+        let remote = Path::new("/mnt/nas/projects");
+        let local = Path::new("/home/me");
+        assert_eq!(StatMode::from_str("auto"), StatMode::Auto);
+        assert_eq!(StatMode::from_str("bogus"), StatMode::Auto);
+        assert!(StatMode::Auto.defers(remote, &mounts));
+        assert!(!StatMode::Auto.defers(local, &mounts));
+        assert!(StatMode::from_str("always").defers(local, &mounts));
+        assert!(!StatMode::from_str("never").defers(remote, &mounts));
+    }
+}
@@ -0,0 +1,531 @@
+//! Syntax highlighting resources shared by the preview, the editor and
+//! notebook rendering.
+//!
+//! syntect's syntax and theme sets take a noticeable part of startup to
+//! load, and a session spent moving between directories never uses them.
+//! [`LazySyntax`] loads them the first time something is highlighted, on
+//! whichever thread gets there first, and shares the one copy after that.
+//!
+//! Which syntax a file gets is decided in one place, [`resolve`]: the
+//! extension table, then the shebang of an extensionless file, then the
+//! file name and extension as syntect knows them. The preview, the editor
+//! and notebook code cells all go through it, so a file never highlights
+//! one way in the preview and another in the editor.
+//!
+//! `.sublime-syntax` files in `~/.config/fm-tui/syntaxes/` are added to the
+//! set when it loads. They cover extensions the table doesn't list; a file
+//! that fails to parse is skipped and reported once in the status bar.
+//!
+//! syntect is only used in this module, behind [`Highlighter`]. A build
+//! without the `syntax-highlighting` feature leaves it out, and every
+//! highlighter then shows lines as plain text.
+
+#[cfg(feature = "syntax-highlighting")]
+use std::fs;
+#[cfg(feature = "syntax-highlighting")]
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+use ratatui::style::Color;
+#[cfg(feature = "syntax-highlighting")]
+use syntect::easy::HighlightLines;
+#[cfg(feature = "syntax-highlighting")]
+use syntect::highlighting::{HighlightState, Theme, ThemeSet};
+#[cfg(feature = "syntax-highlighting")]
+use syntect::parsing::{
+    ParseState, SyntaxDefinition, SyntaxReference, SyntaxSet, SyntaxSetBuilder,
+};
+
+/// Directory under the config dir holding user `.sublime-syntax` files.
+pub const SYNTAXES_DIR: &str = "syntaxes";
+
+/// Name the table gives files it has no syntax for.
+#[cfg(feature = "syntax-highlighting")]
+const PLAIN_TEXT: &str = "Plain Text";
+
+/// Detect the syntax name for a file based on its extension.
+#[cfg(feature = "syntax-highlighting")]
+pub fn detect_syntax_name(path: &Path) -> &str {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("py") => "Python",
+        Some("rs") => "Rust",
+        Some("yaml" | "yml") => "YAML",
+        Some("json") => "JSON",
+        Some("toml") => "TOML",
+        Some("sh" | "bash" | "zsh") => "Bash",
+        Some("sql") => "SQL",
+        Some("md" | "markdown") => "Markdown",
+        Some("html" | "htm") => "HTML",
+        Some("css") => "CSS",
+        Some("js" | "jsx") => "JavaScript",
+        Some("ts" | "tsx") => "TypeScript",
+        Some("c" | "h") => "C",
+        Some("cpp" | "hpp" | "cc") => "C++",
+        Some("java") => "Java",
+        Some("go") => "Go",
+        Some("rb") => "Ruby",
+        Some("txt" | "log" | "csv" | "cfg" | "conf" | "ini") => "Plain Text",
+        Some("ipynb") => "Python",
+        None => detect_from_shebang(path),
+        _ => "Plain Text",
+    }
+}
+
+/// Detect syntax from shebang line for extensionless files.
+#[cfg(feature = "syntax-highlighting")]
+fn detect_from_shebang(path: &Path) -> &str {
+    let file = match fs::File::open(path) {
+        Ok(f) => f,
+        Err(_) => return "Plain Text",
+    };
+    let mut reader = BufReader::new(file);
+    let mut first_line = String::new();
+    if reader.read_line(&mut first_line).is_err() {
+        return "Plain Text";
+    }
+    if !first_line.starts_with("#!") {
+        return "Plain Text";
+    }
+    let line = first_line.to_lowercase();
+    if line.contains("python") {
+        "Python"
+    } else if line.contains("bash") || line.contains("/sh") {
+        "Bash"
+    } else if line.contains("ruby") {
+        "Ruby"
+    } else if line.contains("node") || line.contains("deno") {
+        "JavaScript"
+    } else if line.contains("perl") {
+        "Perl"
+    } else {
+        "Plain Text"
+    }
+}
+
+/// Pick the syntax for `path`. Every highlighter goes through this.
+///
+/// The extension table (or shebang) wins; anything it leaves as plain
+/// text is looked up by file name (`Makefile`, `.bashrc`) and then by
+/// extension or language name, which is where user syntaxes come in.
+#[cfg(feature = "syntax-highlighting")]
+pub fn resolve<'a>(set: &'a SyntaxSet, path: &Path) -> &'a SyntaxReference {
+    let name = detect_syntax_name(path);
+    let named = if name == PLAIN_TEXT {
+        None
+    } else {
+        set.find_syntax_by_name(name)
+    };
+    named
+        .or_else(|| {
+            let file_name = path.file_name()?.to_str()?;
+            set.find_syntax_by_extension(file_name)
+        })
+        .or_else(|| set.find_syntax_by_token(path.extension()?.to_str()?))
+        .unwrap_or_else(|| set.find_syntax_plain_text())
+}
+
+/// Syntax for code in `language` (a notebook kernel's `python`, `r`, …),
+/// resolved as a file with that extension would be.
+#[cfg(feature = "syntax-highlighting")]
+pub fn resolve_language<'a>(set: &'a SyntaxSet, language: &str) -> &'a SyntaxReference {
+    resolve(set, Path::new(&format!("_.{}", language)))
+}
+
+/// Load a theme from the built-in theme set by name, with fallback.
+#[cfg(feature = "syntax-highlighting")]
+pub fn load_theme(theme_name: Option<&str>) -> Theme {
+    let ts = ThemeSet::load_defaults();
+    let name = theme_name.unwrap_or("base16-ocean.dark");
+    ts.themes
+        .get(name)
+        .cloned()
+        .unwrap_or_else(|| ts.themes["base16-ocean.dark"].clone())
+}
+
+/// The loaded syntax set and theme.
+pub struct SyntaxResources {
+    #[cfg(feature = "syntax-highlighting")]
+    pub set: SyntaxSet,
+    #[cfg(feature = "syntax-highlighting")]
+    pub theme: Theme,
+    /// User syntax files that failed to load, with the reason.
+    pub warnings: Vec<String>,
+}
+
+#[cfg(feature = "syntax-highlighting")]
+impl SyntaxResources {
+    /// Load the built-in syntaxes plus any in `user_dir`, and the theme
+    /// named `theme_name`.
+    pub fn load(theme_name: &str, user_dir: Option<&Path>) -> Self {
+        // Highlighters are fed lines without their trailing newline
+        let mut builder = SyntaxSet::load_defaults_nonewlines().into_builder();
+        let warnings = match user_dir {
+            Some(dir) => add_user_syntaxes(&mut builder, dir),
+            None => Vec::new(),
+        };
+        Self {
+            set: builder.build(),
+            theme: load_theme(Some(theme_name)),
+            warnings,
+        }
+    }
+
+    /// Highlighter for the lines of `path`.
+    pub fn highlighter(&self, path: &Path) -> Highlighter<'_> {
+        self.highlighter_for(resolve(&self.set, path))
+    }
+
+    /// Highlighter for code in `language`.
+    pub fn language_highlighter(&self, language: &str) -> Highlighter<'_> {
+        self.highlighter_for(resolve_language(&self.set, language))
+    }
+
+    /// Highlighter carrying on where `suspended` stopped.
+    pub fn resume(&self, suspended: SuspendedHighlighter) -> Highlighter<'_> {
+        Highlighter {
+            lines: HighlightLines::from_state(&self.theme, suspended.highlight, suspended.parse),
+            set: &self.set,
+        }
+    }
+
+    fn highlighter_for<'a>(&'a self, syntax: &'a SyntaxReference) -> Highlighter<'a> {
+        Highlighter {
+            lines: HighlightLines::new(syntax, &self.theme),
+            set: &self.set,
+        }
+    }
+}
+
+#[cfg(not(feature = "syntax-highlighting"))]
+impl SyntaxResources {
+    /// Nothing to load without syntect.
+    pub fn load(_theme_name: &str, _user_dir: Option<&Path>) -> Self {
+        Self {
+            warnings: Vec::new(),
+        }
+    }
+
+    pub fn highlighter(&self, _path: &Path) -> Highlighter<'_> {
+        Highlighter {
+            _resources: std::marker::PhantomData,
+        }
+    }
+
+    pub fn language_highlighter(&self, _language: &str) -> Highlighter<'_> {
+        Highlighter {
+            _resources: std::marker::PhantomData,
+        }
+    }
+
+    pub fn resume(&self, _suspended: SuspendedHighlighter) -> Highlighter<'_> {
+        Highlighter {
+            _resources: std::marker::PhantomData,
+        }
+    }
+}
+
+/// Highlights the lines of one file in order: how a line is coloured
+/// depends on the lines before it.
+pub struct Highlighter<'a> {
+    #[cfg(feature = "syntax-highlighting")]
+    lines: HighlightLines<'a>,
+    #[cfg(feature = "syntax-highlighting")]
+    set: &'a SyntaxSet,
+    #[cfg(not(feature = "syntax-highlighting"))]
+    _resources: std::marker::PhantomData<&'a SyntaxResources>,
+}
+
+impl Highlighter<'_> {
+    /// The next line split into coloured pieces, or `None` if it is to be
+    /// shown as plain text.
+    #[cfg(feature = "syntax-highlighting")]
+    pub fn line<'l>(&mut self, line: &'l str) -> Option<Vec<(Color, &'l str)>> {
+        let ranges = self.lines.highlight_line(line, self.set).ok()?;
+        Some(
+            ranges
+                .into_iter()
+                .map(|(style, part)| {
+                    let c = style.foreground;
+                    (Color::Rgb(c.r, c.g, c.b), part)
+                })
+                .collect(),
+        )
+    }
+
+    #[cfg(not(feature = "syntax-highlighting"))]
+    pub fn line<'l>(&mut self, _line: &'l str) -> Option<Vec<(Color, &'l str)>> {
+        None
+    }
+
+    /// Stop here, keeping the parse state (an open block comment, a
+    /// string) for [`SyntaxResources::resume`].
+    #[cfg(feature = "syntax-highlighting")]
+    pub fn suspend(self) -> SuspendedHighlighter {
+        let (highlight, parse) = self.lines.state();
+        SuspendedHighlighter { highlight, parse }
+    }
+
+    #[cfg(not(feature = "syntax-highlighting"))]
+    pub fn suspend(self) -> SuspendedHighlighter {
+        SuspendedHighlighter {}
+    }
+}
+
+/// Where a [`Highlighter`] stopped in a file. Owns its state, so it can be
+/// sent to another thread and resumed for the next lines.
+#[derive(Debug)]
+pub struct SuspendedHighlighter {
+    #[cfg(feature = "syntax-highlighting")]
+    highlight: HighlightState,
+    #[cfg(feature = "syntax-highlighting")]
+    parse: ParseState,
+}
+
+/// Syntax resources, loaded on first use.
+pub struct LazySyntax {
+    theme_name: String,
+    user_dir: Option<PathBuf>,
+    loaded: OnceLock<SyntaxResources>,
+    /// Whether the load warnings were handed out.
+    warned: AtomicBool,
+}
+
+impl LazySyntax {
+    pub fn new(theme_name: &str, user_dir: Option<PathBuf>) -> Self {
+        Self {
+            theme_name: theme_name.to_string(),
+            user_dir,
+            loaded: OnceLock::new(),
+            warned: AtomicBool::new(false),
+        }
+    }
+
+    /// The resources, loading them if this is the first use.
+    pub fn get(&self) -> &SyntaxResources {
+        self.loaded
+            .get_or_init(|| SyntaxResources::load(&self.theme_name, self.user_dir.as_deref()))
+    }
+
+    /// Whether anything has been highlighted yet.
+    #[allow(dead_code)]
+    pub fn is_loaded(&self) -> bool {
+        self.loaded.get().is_some()
+    }
+
+    /// Warnings from loading user syntaxes: once, after the load.
+    pub fn take_warnings(&self) -> Vec<String> {
+        match self.loaded.get() {
+            Some(resources) if !self.warned.swap(true, Ordering::SeqCst) => {
+                resources.warnings.clone()
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// `~/.config/fm-tui/syntaxes`, where user syntaxes are read from.
+pub fn user_syntaxes_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("fm-tui").join(SYNTAXES_DIR))
+}
+
+/// Add every `.sublime-syntax` file in `dir` to `builder`, in name order.
+/// Returns a message per file that couldn't be read or parsed; a missing
+/// directory is the usual case and not worth one.
+#[cfg(feature = "syntax-highlighting")]
+fn add_user_syntaxes(builder: &mut SyntaxSetBuilder, dir: &Path) -> Vec<String> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().and_then(|e| e.to_str()) == Some("sublime-syntax"))
+        .collect();
+    paths.sort();
+
+    let mut warnings = Vec::new();
+    for path in paths {
+        let fallback_name = path.file_stem().and_then(|s| s.to_str());
+        let loaded = fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|source| {
+                SyntaxDefinition::load_from_str(&source, false, fallback_name)
+                    .map_err(|e| e.to_string())
+            });
+        match loaded {
+            Ok(definition) => builder.add(definition),
+            Err(e) => {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                warnings.push(format!("{}: {}", name, e));
+            }
+        }
+    }
+    warnings
+}
+
+#[cfg(all(test, feature = "syntax-highlighting"))]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    #[test]
+    fn detect_syntax_by_extension() {
+        assert_eq!(detect_syntax_name(Path::new("foo.rs")), "Rust");
+        assert_eq!(detect_syntax_name(Path::new("bar.py")), "Python");
+        assert_eq!(detect_syntax_name(Path::new("baz.yml")), "YAML");
+        assert_eq!(detect_syntax_name(Path::new("config.toml")), "TOML");
+        assert_eq!(detect_syntax_name(Path::new("style.css")), "CSS");
+        assert_eq!(detect_syntax_name(Path::new("page.html")), "HTML");
+        assert_eq!(detect_syntax_name(Path::new("app.tsx")), "TypeScript");
+        assert_eq!(detect_syntax_name(Path::new("Makefile")), "Plain Text");
+        assert_eq!(detect_syntax_name(Path::new("readme.md")), "Markdown");
+    }
+
+    #[test]
+    fn detect_syntax_unknown_extension() {
+        assert_eq!(detect_syntax_name(Path::new("file.xyz")), "Plain Text");
+    }
+
+    #[test]
+    fn detect_shebang_python() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("script");
+        let mut f = File::create(&path).unwrap();
+        writeln!(f, "#!/usr/bin/env python3").unwrap();
+        writeln!(f, "print('hello')").unwrap();
+        assert_eq!(detect_syntax_name(&path), "Python");
+    }
+
+    #[test]
+    fn detect_shebang_bash() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("run");
+        let mut f = File::create(&path).unwrap();
+        writeln!(f, "#!/bin/bash").unwrap();
+        assert_eq!(detect_syntax_name(&path), "Bash");
+    }
+
+    #[test]
+    fn detect_shebang_sh() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("run2");
+        let mut f = File::create(&path).unwrap();
+        writeln!(f, "#!/bin/sh").unwrap();
+        assert_eq!(detect_syntax_name(&path), "Bash");
+    }
+
+    #[test]
+    fn detect_no_shebang() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("data");
+        let mut f = File::create(&path).unwrap();
+        writeln!(f, "just some text").unwrap();
+        assert_eq!(detect_syntax_name(&path), "Plain Text");
+    }
+
+    #[test]
+    fn load_theme_default() {
+        let theme = load_theme(None);
+        // Just verify it doesn't panic and returns something
+        assert!(!theme.scopes.is_empty() || theme.settings.background.is_some());
+    }
+
+    #[test]
+    fn load_theme_invalid_falls_back() {
+        let theme = load_theme(Some("nonexistent-theme"));
+        // Should fall back to base16-ocean.dark
+        assert!(!theme.scopes.is_empty() || theme.settings.background.is_some());
+    }
+
+    const LOG_SYNTAX: &str = r#"%YAML 1.2
+---
+name: Build Log
+file_extensions: [buildlog]
+scope: text.buildlog
+contexts:
+  main:
+    - match: '\bERROR\b'
+      scope: invalid.illegal.buildlog
+"#;
+
+    #[test]
+    fn resolution_falls_back_to_syntect_lookups() {
+        let set = SyntaxSet::load_defaults_newlines();
+        let name = |p: &str| resolve(&set, Path::new(p)).name.clone();
+        assert_eq!(name("main.rs"), "Rust");
+        assert_eq!(name("notes.txt"), "Plain Text");
+        assert_eq!(name("file.xyz"), "Plain Text");
+        // Not in the table, but known to syntect
+        assert_eq!(name("init.lua"), "Lua");
+        assert_eq!(name("Makefile"), "Makefile");
+        // Notebook kernels name a language, not an extension
+        assert_eq!(resolve_language(&set, "python").name, "Python");
+        assert_eq!(resolve_language(&set, "rust").name, "Rust");
+        assert_eq!(resolve_language(&set, "brainfudge").name, "Plain Text");
+    }
+
+    #[test]
+    fn user_syntaxes_are_loaded_and_bad_ones_reported() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("buildlog.sublime-syntax"), LOG_SYNTAX).unwrap();
+        fs::write(dir.path().join("broken.sublime-syntax"), "name: [unclosed").unwrap();
+        fs::write(dir.path().join("README.md"), "not a syntax").unwrap();
+
+        let resources = SyntaxResources::load("base16-ocean.dark", Some(dir.path()));
+        assert_eq!(
+            resolve(&resources.set, Path::new("ci.buildlog")).name,
+            "Build Log"
+        );
+        assert_eq!(resources.warnings.len(), 1);
+        assert!(resources.warnings[0].starts_with("broken.sublime-syntax: "));
+        // The built-ins are all still there
+        assert_eq!(resolve(&resources.set, Path::new("a.rs")).name, "Rust");
+    }
+
+    #[test]
+    fn missing_user_dir_loads_the_defaults_quietly() {
+        let dir = TempDir::new().unwrap();
+        let resources = SyntaxResources::load("base16-ocean.dark", Some(&dir.path().join("none")));
+        assert!(resources.warnings.is_empty());
+        assert_eq!(resolve(&resources.set, Path::new("a.py")).name, "Python");
+    }
+
+    #[test]
+    fn lazy_syntax_loads_once_and_warns_once() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("broken.sublime-syntax"), "name: [unclosed").unwrap();
+        let lazy = LazySyntax::new("base16-ocean.dark", Some(dir.path().to_path_buf()));
+        assert!(!lazy.is_loaded());
+        assert!(lazy.take_warnings().is_empty());
+
+        let first: *const SyntaxResources = lazy.get();
+        assert!(lazy.is_loaded());
+        assert!(std::ptr::eq(first, lazy.get()));
+        assert_eq!(lazy.take_warnings().len(), 1);
+        assert!(lazy.take_warnings().is_empty());
+    }
+}
+
+#[cfg(all(test, not(feature = "syntax-highlighting")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn highlighters_leave_lines_plain() {
+        let lazy = LazySyntax::new("base16-ocean.dark", None);
+        let resources = lazy.get();
+        assert!(resources
+            .highlighter(Path::new("main.rs"))
+            .line("fn main() {}")
+            .is_none());
+        assert!(resources
+            .language_highlighter("python")
+            .line("x = 1")
+            .is_none());
+        assert!(lazy.take_warnings().is_empty());
+    }
+}
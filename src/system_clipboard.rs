@@ -0,0 +1,448 @@
+//! Writes to the system clipboard (path copying).
+//!
+//! Text goes to the CLIPBOARD selection through whichever platform tool is
+//! installed (xclip, xsel, wl-copy, pbcopy). On Linux it can also go to the
+//! PRIMARY selection for middle-click paste (`general.primary_selection`);
+//! elsewhere the primary target doesn't exist. Providers can hang, e.g.
+//! wl-copy under Wayland without a portal, so every write runs on a
+//! blocking task and gives up after [`WRITE_TIMEOUT`].
+//!
+//! With `general.osc52` on, the text is also sent to the terminal as an
+//! OSC 52 escape ([`osc52`]), which reaches the local clipboard over SSH
+//! where no tool can. Terminals give no answer to it, so the tools still
+//! run as the fallback for terminals that ignore it.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// How long a single selection write may take before it is abandoned.
+pub const WRITE_TIMEOUT: Duration = Duration::from_millis(1_500);
+
+/// Longest text sent as OSC 52; terminals drop larger sequences (xterm
+/// stops near 100 KB of base64).
+pub const OSC52_MAX_BYTES: usize = 64 * 1024;
+
+/// Mechanism name for writes made through OSC 52.
+pub const OSC52: &str = "OSC 52";
+
+/// A system selection that text can be copied into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Selection {
+    /// The regular Ctrl+V clipboard.
+    Clipboard,
+    /// The X11/Wayland middle-click selection.
+    #[cfg(target_os = "linux")]
+    Primary,
+}
+
+impl Selection {
+    /// Name used in status messages.
+    pub fn label(self) -> &'static str {
+        match self {
+            Selection::Clipboard => "clipboard",
+            #[cfg(target_os = "linux")]
+            Selection::Primary => "primary",
+        }
+    }
+
+    /// Selection parameter of the OSC 52 escape.
+    fn osc52_target(self) -> char {
+        match self {
+            Selection::Clipboard => 'c',
+            #[cfg(target_os = "linux")]
+            Selection::Primary => 'p',
+        }
+    }
+}
+
+/// Something that can put text into a selection.
+///
+/// Kept behind a trait so tests can record writes instead of spawning tools.
+pub trait ClipboardProvider: Send + Sync {
+    /// Write `text` to `selection`, taking no longer than `timeout`.
+    /// Returns the name of the tool that took it.
+    fn set_text(
+        &self,
+        selection: Selection,
+        text: &str,
+        timeout: Duration,
+    ) -> Result<&'static str, String>;
+}
+
+/// Provider that pipes text into the platform's clipboard tools.
+pub struct CommandClipboard;
+
+impl CommandClipboard {
+    /// Tools to try for `selection`, in order, with their arguments.
+    fn commands(selection: Selection) -> &'static [(&'static str, &'static [&'static str])] {
+        match selection {
+            Selection::Clipboard => &[
+                ("xclip", &["-selection", "clipboard"]),
+                ("xsel", &["--clipboard", "--input"]),
+                ("wl-copy", &[]),
+                ("pbcopy", &[]),
+            ],
+            #[cfg(target_os = "linux")]
+            Selection::Primary => &[
+                ("xclip", &["-selection", "primary"]),
+                ("xsel", &["--primary", "--input"]),
+                ("wl-copy", &["--primary"]),
+            ],
+        }
+    }
+}
+
+impl ClipboardProvider for CommandClipboard {
+    fn set_text(
+        &self,
+        selection: Selection,
+        text: &str,
+        timeout: Duration,
+    ) -> Result<&'static str, String> {
+        let deadline = Instant::now() + timeout;
+        let mut last_error = None;
+        for (cmd, args) in Self::commands(selection) {
+            match run_tool(cmd, args, text, deadline) {
+                Ok(()) => return Ok(cmd),
+                // Not installed: try the next tool
+                Err(None) => continue,
+                Err(Some(msg)) => last_error = Some(msg),
+            }
+        }
+        Err(last_error.unwrap_or_else(|| {
+            let tools: Vec<&str> = Self::commands(selection)
+                .iter()
+                .map(|(cmd, _)| *cmd)
+                .collect();
+            format!("no clipboard tool found (install {})", tools.join(" or "))
+        }))
+    }
+}
+
+/// Pipe `text` into `cmd`, killing it if it outlives `deadline`.
+///
+/// `Err(None)` means the tool couldn't be started at all.
+fn run_tool(cmd: &str, args: &[&str], text: &str, deadline: Instant) -> Result<(), Option<String>> {
+    let mut child = Command::new(cmd)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|_| None)?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        if stdin.write_all(text.as_bytes()).is_err() {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(Some(format!("{} closed its input", cmd)));
+        }
+        // Dropping stdin signals EOF
+    }
+
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) if status.success() => return Ok(()),
+            Ok(Some(status)) => return Err(Some(format!("{} exited with {}", cmd, status))),
+            Ok(None) if Instant::now() >= deadline => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(Some(format!("{} timed out", cmd)));
+            }
+            Ok(None) => std::thread::sleep(Duration::from_millis(10)),
+            Err(e) => return Err(Some(format!("{}: {}", cmd, e))),
+        }
+    }
+}
+
+/// OSC 52 escapes putting `text` into each of `targets`, or `None` when
+/// it is too long for terminals to accept.
+pub fn osc52(text: &str, targets: &[Selection]) -> Option<String> {
+    if text.len() > OSC52_MAX_BYTES {
+        return None;
+    }
+    let payload = base64(text.as_bytes());
+    Some(
+        targets
+            .iter()
+            .map(|s| format!("\x1b]52;{};{}\x07", s.osc52_target(), payload))
+            .collect(),
+    )
+}
+
+/// Standard padded base64, as OSC 52 expects.
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, b)| n | (*b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Selections a copy action writes to: the clipboard, plus PRIMARY on
+/// Linux when `primary_selection` is enabled.
+pub fn copy_targets(primary_selection: bool) -> Vec<Selection> {
+    #[allow(unused_mut)]
+    let mut targets = vec![Selection::Clipboard];
+    #[cfg(target_os = "linux")]
+    if primary_selection {
+        targets.push(Selection::Primary);
+    }
+    #[cfg(not(target_os = "linux"))]
+    let _ = primary_selection;
+    targets
+}
+
+/// Selections for the "copy to primary only" action.
+#[cfg(target_os = "linux")]
+pub fn primary_only() -> Vec<Selection> {
+    vec![Selection::Primary]
+}
+
+/// Selections for the "copy to primary only" action (none off Linux).
+#[cfg(not(target_os = "linux"))]
+pub fn primary_only() -> Vec<Selection> {
+    Vec::new()
+}
+
+/// Outcome of copying one piece of text to one or more selections.
+#[derive(Debug)]
+pub struct ClipboardReport {
+    /// What was copied, for the status bar ("Path", "3 paths").
+    pub what: String,
+    /// The copied text.
+    pub text: String,
+    /// Selections that now hold `text`, with the mechanism that put it
+    /// there. A selection sent through OSC 52 is listed once for that and
+    /// again for a tool that also took it.
+    pub written: Vec<(Selection, &'static str)>,
+    /// Selections that failed, with the reason.
+    pub failed: Vec<(Selection, String)>,
+}
+
+impl ClipboardReport {
+    /// One-line status bar summary: what was copied, where, and how. Text
+    /// over several lines is left out.
+    pub fn status_message(&self) -> String {
+        let mut written: Vec<&str> = Vec::new();
+        let mut via: Vec<&str> = Vec::new();
+        for (selection, mechanism) in &self.written {
+            if !written.contains(&selection.label()) {
+                written.push(selection.label());
+            }
+            if !via.contains(mechanism) {
+                via.push(mechanism);
+            }
+        }
+        let failed: Vec<String> = self
+            .failed
+            .iter()
+            .map(|(s, msg)| format!("{} failed: {}", s.label(), msg))
+            .collect();
+        let shown = if self.text.contains('\n') {
+            String::new()
+        } else {
+            format!(": {}", self.text)
+        };
+        if written.is_empty() {
+            return format!("📋 {}{}", failed.join("; "), shown);
+        }
+        let mut message = format!("📋 {} copied", self.what);
+        if written != ["clipboard"] || !failed.is_empty() {
+            message.push_str(&format!(" ({})", written.join(" + ")));
+        }
+        message.push_str(&format!(" via {}", via.join(" + ")));
+        if !failed.is_empty() {
+            message.push_str(&format!(", {}", failed.join("; ")));
+        }
+        message + &shown
+    }
+}
+
+/// Write `text` to each of `targets` in turn on blocking tasks, giving each
+/// at most `timeout` so a hung provider can't stall the caller. `osc52`
+/// says the text also went out as an OSC 52 escape; a selection it covered
+/// counts as written, and a tool failing for it is not reported.
+pub async fn write(
+    provider: Arc<dyn ClipboardProvider>,
+    targets: Vec<Selection>,
+    what: String,
+    text: String,
+    timeout: Duration,
+    osc52: bool,
+) -> ClipboardReport {
+    let mut report = ClipboardReport {
+        what,
+        text,
+        written: Vec::new(),
+        failed: Vec::new(),
+    };
+    if osc52 {
+        report.written = targets.iter().map(|s| (*s, OSC52)).collect();
+    }
+    for selection in targets {
+        let provider = Arc::clone(&provider);
+        let text = report.text.clone();
+        let job = tokio::task::spawn_blocking(move || provider.set_text(selection, &text, timeout));
+        let failure = match tokio::time::timeout(timeout, job).await {
+            Ok(Ok(Ok(tool))) => {
+                report.written.push((selection, tool));
+                continue;
+            }
+            Ok(Ok(Err(msg))) => msg,
+            Ok(Err(_)) => "clipboard task panicked".to_string(),
+            Err(_) => format!("timed out after {} ms", timeout.as_millis()),
+        };
+        if !osc52 {
+            report.failed.push((selection, failure));
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Provider that records writes; selections in `failing` report an
+    /// error and, when `hang` is set, every write blocks past its timeout.
+    #[derive(Default)]
+    struct MockClipboard {
+        writes: Mutex<Vec<(Selection, String)>>,
+        failing: Vec<Selection>,
+        hang: bool,
+    }
+
+    impl ClipboardProvider for MockClipboard {
+        fn set_text(
+            &self,
+            selection: Selection,
+            text: &str,
+            timeout: Duration,
+        ) -> Result<&'static str, String> {
+            if self.hang {
+                std::thread::sleep(timeout * 20);
+            }
+            if self.failing.contains(&selection) {
+                return Err("no portal".to_string());
+            }
+            self.writes
+                .lock()
+                .unwrap()
+                .push((selection, text.to_string()));
+            Ok("mock")
+        }
+    }
+
+    #[test]
+    fn copy_targets_follow_setting() {
+        assert_eq!(copy_targets(false), vec![Selection::Clipboard]);
+        #[cfg(target_os = "linux")]
+        assert_eq!(
+            copy_targets(true),
+            vec![Selection::Clipboard, Selection::Primary]
+        );
+        #[cfg(not(target_os = "linux"))]
+        assert_eq!(copy_targets(true), vec![Selection::Clipboard]);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn write_reports_which_selection_failed() {
+        let mock = Arc::new(MockClipboard {
+            failing: vec![Selection::Primary],
+            ..Default::default()
+        });
+        let report = write(
+            mock.clone(),
+            copy_targets(true),
+            "Path".to_string(),
+            "/tmp/a".to_string(),
+            WRITE_TIMEOUT,
+            false,
+        )
+        .await;
+        assert_eq!(report.written, vec![(Selection::Clipboard, "mock")]);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(
+            report.status_message(),
+            "📋 Path copied (clipboard) via mock, primary failed: no portal: /tmp/a"
+        );
+        assert_eq!(mock.writes.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn osc52_covers_a_failing_tool() {
+        let mock = Arc::new(MockClipboard {
+            failing: vec![Selection::Clipboard],
+            ..Default::default()
+        });
+        let report = write(
+            mock,
+            copy_targets(false),
+            "2 paths".to_string(),
+            "a\nb".to_string(),
+            WRITE_TIMEOUT,
+            true,
+        )
+        .await;
+        assert!(report.failed.is_empty());
+        assert_eq!(report.status_message(), "📋 2 paths copied via OSC 52");
+    }
+
+    #[test]
+    fn osc52_escape_carries_base64() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64("/tmp/é".as_bytes()), "L3RtcC/DqQ==");
+        assert_eq!(
+            osc52("foo", &[Selection::Clipboard]).unwrap(),
+            "\x1b]52;c;Zm9v\x07"
+        );
+        assert_eq!(
+            osc52(&"x".repeat(OSC52_MAX_BYTES + 1), &[Selection::Clipboard]),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn hung_provider_times_out() {
+        let mock = Arc::new(MockClipboard {
+            hang: true,
+            ..Default::default()
+        });
+        let started = Instant::now();
+        let timeout = Duration::from_millis(50);
+        let report = write(
+            mock,
+            copy_targets(false),
+            "Path".to_string(),
+            "/tmp/a".to_string(),
+            timeout,
+            false,
+        )
+        .await;
+        assert!(started.elapsed() < timeout * 10);
+        assert!(report.written.is_empty());
+        assert_eq!(
+            report.status_message(),
+            "📋 clipboard failed: timed out after 50 ms: /tmp/a"
+        );
+    }
+}
@@ -0,0 +1,151 @@
+//! Browser-style tabs, each with its own tree root.
+//!
+//! The active tab's tree, preview and focus stay in their `App` fields,
+//! where everything that works on them already looks. [`Tabs`] keeps the
+//! other tabs parked as [`TabState`]s and trades one for the `App` fields
+//! on a switch. The clipboard, undo, bookmarks, terminal and caches are
+//! shared by every tab. The watcher follows the root of the active tab, so
+//! a tab switched back to is refreshed from its root: changes under it went
+//! unwatched while it was parked.
+
+use crate::app::{FocusedPanel, PreviewState};
+use crate::fs::tree::TreeState;
+use crate::preview_search::PreviewSearch;
+
+/// What a tab keeps while another one is active.
+pub struct TabState {
+    pub tree_state: TreeState,
+    pub preview_state: PreviewState,
+    pub preview_search: PreviewSearch,
+    pub focused_panel: FocusedPanel,
+}
+
+/// The open tabs, in tab bar order.
+pub struct Tabs {
+    /// One slot per tab; `None` for the active one, whose state is in `App`.
+    slots: Vec<Option<TabState>>,
+    active: usize,
+}
+
+impl Default for Tabs {
+    fn default() -> Self {
+        Self {
+            slots: vec![None],
+            active: 0,
+        }
+    }
+}
+
+impl Tabs {
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Index of the active tab.
+    pub fn active(&self) -> usize {
+        self.active
+    }
+
+    /// Park `current`, the active tab's state, and make a new tab right
+    /// after it active.
+    pub fn open(&mut self, current: TabState) {
+        self.slots[self.active] = Some(current);
+        self.active += 1;
+        self.slots.insert(self.active, None);
+    }
+
+    /// Take the parked state of tab `index`, to [`park`](Self::park) the
+    /// active tab's state in exchange. `None` for the active tab or an
+    /// index out of range.
+    pub fn take(&mut self, index: usize) -> Option<TabState> {
+        self.slots.get_mut(index).and_then(Option::take)
+    }
+
+    /// Park `current`, the active tab's state, and make tab `index`, whose
+    /// state was just taken, active.
+    pub fn park(&mut self, current: TabState, index: usize) {
+        self.slots[self.active] = Some(current);
+        self.active = index;
+    }
+
+    /// Drop the active tab and make its right neighbour active (the left
+    /// one for the last tab), returning that tab's state. `None` when it
+    /// is the only tab.
+    pub fn close(&mut self) -> Option<TabState> {
+        if self.slots.len() == 1 {
+            return None;
+        }
+        self.slots.remove(self.active);
+        self.active = self.active.min(self.slots.len() - 1);
+        self.slots[self.active].take()
+    }
+
+    /// Index of the tab `steps` to the right (left when negative) of the
+    /// active one, wrapping around.
+    pub fn offset(&self, steps: isize) -> usize {
+        let len = self.slots.len() as isize;
+        (self.active as isize + steps).rem_euclid(len) as usize
+    }
+
+    /// Root name of every tab, the active one given as `active_name`.
+    pub fn names<'a>(&'a self, active_name: &'a str) -> Vec<&'a str> {
+        self.slots
+            .iter()
+            .map(|slot| match slot {
+                Some(tab) => tab.tree_state.root.name.as_str(),
+                None => active_name,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn tab(dir: &TempDir, name: &str) -> TabState {
+        let path = dir.path().join(name);
+        std::fs::create_dir_all(&path).unwrap();
+        TabState {
+            tree_state: TreeState::new(&path).unwrap(),
+            preview_state: PreviewState::default(),
+            preview_search: PreviewSearch::default(),
+            focused_panel: FocusedPanel::Tree,
+        }
+    }
+
+    #[test]
+    fn open_switch_and_close_keep_the_other_tabs_parked() {
+        let dir = TempDir::new().unwrap();
+        let mut tabs = Tabs::default();
+        assert_eq!(tabs.names("a"), vec!["a"]);
+        assert!(tabs.close().is_none());
+
+        tabs.open(tab(&dir, "a"));
+        tabs.open(tab(&dir, "b"));
+        assert_eq!(tabs.active(), 2);
+        assert_eq!(tabs.names("c"), vec!["a", "b", "c"]);
+        assert_eq!(
+            (tabs.offset(1), tabs.offset(-1), tabs.offset(-5)),
+            (0, 1, 0)
+        );
+
+        let a = tabs.take(0).unwrap();
+        assert_eq!(a.tree_state.root.name, "a");
+        tabs.park(tab(&dir, "c"), 0);
+        assert_eq!(tabs.names("a"), vec!["a", "b", "c"]);
+        // The active tab has no parked state to take
+        assert!(tabs.take(0).is_none() && tabs.take(3).is_none());
+
+        // Closing the first tab activates its right neighbour
+        let b = tabs.close().unwrap();
+        assert_eq!(b.tree_state.root.name, "b");
+        assert_eq!((tabs.active(), tabs.len()), (0, 2));
+        assert_eq!(tabs.take(1).unwrap().tree_state.root.name, "c");
+        tabs.park(b, 1);
+        // Closing the last tab activates its left neighbour
+        assert_eq!(tabs.close().unwrap().tree_state.root.name, "b");
+        assert_eq!((tabs.active(), tabs.len()), (0, 1));
+    }
+}
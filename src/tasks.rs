@@ -0,0 +1,646 @@
+//! Background tasks started by the app.
+//!
+//! Copies, deletes, retries of blocked renames and deletes, extractions,
+//! scaffolds, counts, content searches, blame, autosave, prefetch loads
+//! and deferred stats each run on a tokio task. Spawning them through
+//! [`TaskManager`] gives every task an id, a kind, a description and a
+//! priority, plus a [`TaskToken`] carrying its cancel flag and reported
+//! progress. The manager lists running and waiting tasks for the tasks
+//! overlay (`J`), cancels one or all of them, and holds a task back while
+//! its kind is at its concurrency limit; waiting tasks start in priority
+//! order as slots free up. A task leaves the list when its job returns.
+//!
+//! Tree work sliced across frames on the UI thread (see `work_queue`) is
+//! listed too: it is registered rather than spawned, and finished by the
+//! queue.
+//!
+//! Every spawned job runs exactly once, so the completion events callers
+//! wait for still arrive: a waiting task that is cancelled starts at once
+//! with its flag raised, and is expected to return early.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Instant;
+
+use crate::prefetch;
+use crate::stat_service;
+
+/// What a task does; decides its concurrency limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskKind {
+    Transfer,
+    Delete,
+    /// Copies made next to their originals (`Y`).
+    Duplicate,
+    #[cfg_attr(not(feature = "archives"), allow(dead_code))]
+    Extract,
+    Scaffold,
+    /// Retry of a rename or delete blocked by another program.
+    Retry,
+    /// Pre-flight count before a large paste or delete.
+    Preflight,
+    Portability,
+    /// Content search (`Ctrl+Shift+F`).
+    Grep,
+    /// Fuzzy finder re-index with the cap raised.
+    Index,
+    DirScan,
+    ChildCount,
+    DirSummary,
+    Blame,
+    GitStatus,
+    Autosave,
+    Prefetch,
+    /// Deferred stats for tree rows on a slow filesystem.
+    Stat,
+    /// Sort, filter or refresh of a large tree, run a slice per frame.
+    TreeWork,
+}
+
+impl TaskKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            TaskKind::Transfer => "transfer",
+            TaskKind::Delete => "delete",
+            TaskKind::Duplicate => "duplicate",
+            TaskKind::Extract => "extract",
+            TaskKind::Scaffold => "scaffold",
+            TaskKind::Retry => "retry",
+            TaskKind::Preflight => "count",
+            TaskKind::Portability => "portability",
+            TaskKind::Grep => "grep",
+            TaskKind::Index => "index",
+            TaskKind::DirScan => "scan",
+            TaskKind::ChildCount => "child count",
+            TaskKind::DirSummary => "dir size",
+            TaskKind::Blame => "blame",
+            TaskKind::GitStatus => "git status",
+            TaskKind::Autosave => "autosave",
+            TaskKind::Prefetch => "prefetch",
+            TaskKind::Stat => "stat",
+            TaskKind::TreeWork => "tree",
+        }
+    }
+
+    /// Most tasks of this kind running at once; `None` for no limit.
+    pub fn limit(self) -> Option<usize> {
+        match self {
+            // Already one at a time: admitted by `InFlightOps`, or superseded
+            // by the next request, which must not wait behind the old one
+            TaskKind::Transfer
+            | TaskKind::Delete
+            | TaskKind::Duplicate
+            | TaskKind::Extract
+            | TaskKind::Scaffold
+            | TaskKind::Retry
+            | TaskKind::Preflight
+            | TaskKind::Portability
+            | TaskKind::Grep => None,
+            // Runs on the UI thread, one job at a time
+            TaskKind::TreeWork => None,
+            TaskKind::Prefetch => Some(prefetch::MAX_IN_FLIGHT),
+            TaskKind::Stat => Some(stat_service::MAX_IN_FLIGHT),
+            TaskKind::DirScan | TaskKind::DirSummary => Some(2),
+            TaskKind::ChildCount => Some(4),
+            TaskKind::Index | TaskKind::Blame | TaskKind::GitStatus | TaskKind::Autosave => Some(1),
+        }
+    }
+
+    /// Whether stopping it midway leaves files half copied or deleted.
+    pub fn touches_files(self) -> bool {
+        matches!(
+            self,
+            TaskKind::Transfer
+                | TaskKind::Delete
+                | TaskKind::Duplicate
+                | TaskKind::Extract
+                | TaskKind::Scaffold
+                | TaskKind::Retry
+        )
+    }
+}
+
+/// Order in which waiting tasks get a free slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    /// Speculative or housekeeping work nobody is waiting for.
+    Background,
+    /// Results shown when they arrive.
+    Normal,
+    /// Something the user started and is watching.
+    User,
+}
+
+pub type TaskId = u64;
+
+#[derive(Debug, Default)]
+struct Progress {
+    current: AtomicUsize,
+    /// 0 until progress is first reported.
+    total: AtomicUsize,
+}
+
+/// Handed to a task's job (and returned to whoever spawned it): the
+/// task's cancel flag and progress.
+#[derive(Debug, Clone)]
+pub struct TaskToken {
+    id: TaskId,
+    cancel: Arc<AtomicBool>,
+    progress: Arc<Progress>,
+}
+
+impl TaskToken {
+    pub fn id(&self) -> TaskId {
+        self.id
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::SeqCst)
+    }
+
+    /// The cancel flag itself, for code that polls an `AtomicBool`.
+    pub fn cancel_flag(&self) -> &Arc<AtomicBool> {
+        &self.cancel
+    }
+
+    /// Report `current` of `total` done, for the tasks overlay.
+    pub fn progress(&self, current: usize, total: usize) {
+        self.progress.current.store(current, Ordering::Relaxed);
+        self.progress.total.store(total, Ordering::Relaxed);
+    }
+}
+
+/// A task as listed in the tasks overlay.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskInfo {
+    pub id: TaskId,
+    pub kind: TaskKind,
+    pub description: String,
+    pub priority: Priority,
+    /// When it started running; `None` while it waits for a slot.
+    pub started: Option<Instant>,
+    /// `(current, total)`, once the job reports any.
+    pub progress: Option<(usize, usize)>,
+    pub cancelled: bool,
+}
+
+impl TaskInfo {
+    /// One row of the overlay: "copy 3 items · 12/40 · 4s".
+    pub fn summary(&self, now: Instant) -> String {
+        let mut summary = format!("{:<11} {}", self.kind.label(), self.description);
+        if let Some((current, total)) = self.progress {
+            summary.push_str(&format!(" · {}/{}", current, total));
+        }
+        match self.started {
+            Some(started) => {
+                summary.push_str(&format!(" · {}s", now.duration_since(started).as_secs()))
+            }
+            None => summary.push_str(" · waiting"),
+        }
+        if self.cancelled {
+            summary.push_str(" · cancelling");
+        }
+        summary
+    }
+}
+
+/// Numbers for the debug overlay.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TaskCounters {
+    pub running: usize,
+    pub waiting: usize,
+    /// Tasks whose job returned without being cancelled.
+    pub finished: u64,
+    /// Tasks whose job returned after being cancelled.
+    pub cancelled: u64,
+    /// Tasks that had to wait for a slot.
+    pub deferred: u64,
+}
+
+type Job = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+struct Entry {
+    token: TaskToken,
+    kind: TaskKind,
+    description: String,
+    priority: Priority,
+    started: Option<Instant>,
+    /// The job, until it starts.
+    job: Option<Job>,
+}
+
+impl Entry {
+    fn info(&self) -> TaskInfo {
+        let total = self.token.progress.total.load(Ordering::Relaxed);
+        TaskInfo {
+            id: self.token.id,
+            kind: self.kind,
+            description: self.description.clone(),
+            priority: self.priority,
+            started: self.started,
+            progress: (total > 0)
+                .then(|| (self.token.progress.current.load(Ordering::Relaxed), total)),
+            cancelled: self.token.is_cancelled(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Inner {
+    entries: Vec<Entry>,
+    next_id: TaskId,
+    finished: u64,
+    cancelled: u64,
+    deferred: u64,
+}
+
+impl Inner {
+    fn running(&self, kind: TaskKind) -> usize {
+        self.entries
+            .iter()
+            .filter(|e| e.kind == kind && e.started.is_some())
+            .count()
+    }
+
+    /// Mark the waiting tasks that may start now as started and take
+    /// their jobs: cancelled ones, then the highest priority (oldest
+    /// first) while their kind has a free slot.
+    fn ready(&mut self) -> Vec<(TaskId, Job)> {
+        let mut waiting: Vec<usize> = (0..self.entries.len())
+            .filter(|&i| self.entries[i].job.is_some())
+            .collect();
+        waiting.sort_by_key(|&i| {
+            let e = &self.entries[i];
+            (
+                !e.token.is_cancelled(),
+                std::cmp::Reverse(e.priority),
+                e.token.id,
+            )
+        });
+        let mut ready = Vec::new();
+        for i in waiting {
+            let kind = self.entries[i].kind;
+            let has_slot = kind.limit().is_none_or(|limit| self.running(kind) < limit);
+            let entry = &mut self.entries[i];
+            if entry.token.is_cancelled() || has_slot {
+                entry.started = Some(Instant::now());
+                if let Some(job) = entry.job.take() {
+                    ready.push((entry.token.id, job));
+                }
+            }
+        }
+        ready
+    }
+}
+
+/// Registry of the app's background tasks. Clones share it; a task's
+/// entry is removed by the tokio task running it.
+#[derive(Clone, Default)]
+pub struct TaskManager {
+    inner: Arc<Mutex<Inner>>,
+}
+
+/// Removes its task's entry when the job returns or is dropped.
+struct Finish {
+    manager: TaskManager,
+    id: TaskId,
+}
+
+impl Drop for Finish {
+    fn drop(&mut self) {
+        self.manager.finish(self.id);
+    }
+}
+
+impl TaskManager {
+    fn lock(&self) -> MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Run the future `job` builds from its token as a task of `kind`,
+    /// now or once its kind has a free slot. Returns the token, whose id
+    /// and flag cancel it.
+    pub fn spawn<F, Fut>(
+        &self,
+        kind: TaskKind,
+        description: impl Into<String>,
+        priority: Priority,
+        job: F,
+    ) -> TaskToken
+    where
+        F: FnOnce(TaskToken) -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let token = {
+            let mut inner = self.lock();
+            inner.next_id += 1;
+            TaskToken {
+                id: inner.next_id,
+                cancel: Arc::new(AtomicBool::new(false)),
+                progress: Arc::new(Progress::default()),
+            }
+        };
+        // Built without the lock, in case building it looks at the tasks
+        let job: Job = Box::pin(job(token.clone()));
+        let ready = {
+            let mut inner = self.lock();
+            inner.entries.push(Entry {
+                token: token.clone(),
+                kind,
+                description: description.into(),
+                priority,
+                started: None,
+                job: Some(job),
+            });
+            let ready = inner.ready();
+            if !ready.iter().any(|(id, _)| *id == token.id) {
+                inner.deferred += 1;
+            }
+            ready
+        };
+        self.launch(ready);
+        token
+    }
+
+    /// Start `jobs` on the runtime. Called without the lock held: a job
+    /// that cannot be spawned is dropped on the spot, and its `Finish`
+    /// takes the lock.
+    fn launch(&self, jobs: Vec<(TaskId, Job)>) {
+        for (id, job) in jobs {
+            let finish = Finish {
+                manager: self.clone(),
+                id,
+            };
+            let task = async move {
+                let _finish = finish;
+                job.await;
+            };
+            match tokio::runtime::Handle::try_current() {
+                Ok(runtime) => {
+                    runtime.spawn(task);
+                }
+                Err(_) => drop(task),
+            }
+        }
+    }
+
+    /// List work the caller runs itself as a running task of `kind`.
+    /// Returns its token; the caller polls the cancel flag and calls
+    /// [`finish`](Self::finish) when the work is done or dropped.
+    pub fn register(
+        &self,
+        kind: TaskKind,
+        description: impl Into<String>,
+        priority: Priority,
+    ) -> TaskToken {
+        let mut inner = self.lock();
+        inner.next_id += 1;
+        let token = TaskToken {
+            id: inner.next_id,
+            cancel: Arc::new(AtomicBool::new(false)),
+            progress: Arc::new(Progress::default()),
+        };
+        inner.entries.push(Entry {
+            token: token.clone(),
+            kind,
+            description: description.into(),
+            priority,
+            started: Some(Instant::now()),
+            job: None,
+        });
+        token
+    }
+
+    /// Drop the entry of task `id` and start whatever its slot frees.
+    pub fn finish(&self, id: TaskId) {
+        let ready = {
+            let mut inner = self.lock();
+            let before = inner.entries.len();
+            let cancelled = inner
+                .entries
+                .iter()
+                .find(|e| e.token.id == id)
+                .is_some_and(|e| e.token.is_cancelled());
+            inner.entries.retain(|e| e.token.id != id);
+            if inner.entries.len() < before {
+                if cancelled {
+                    inner.cancelled += 1;
+                } else {
+                    inner.finished += 1;
+                }
+            }
+            inner.ready()
+        };
+        self.launch(ready);
+    }
+
+    /// Raise the cancel flag of every task matching `pred`; waiting ones
+    /// start right away so they can return. Returns how many were not
+    /// cancelled already.
+    pub fn cancel_where(&self, pred: impl Fn(&TaskInfo) -> bool) -> usize {
+        let (count, ready) = {
+            let mut inner = self.lock();
+            let mut count = 0;
+            for entry in &inner.entries {
+                if !entry.token.is_cancelled() && pred(&entry.info()) {
+                    entry.token.cancel.store(true, Ordering::SeqCst);
+                    count += 1;
+                }
+            }
+            (count, inner.ready())
+        };
+        self.launch(ready);
+        count
+    }
+
+    /// Cancel task `id`; `false` if it is gone or already cancelled.
+    pub fn cancel(&self, id: TaskId) -> bool {
+        self.cancel_where(|task| task.id == id) > 0
+    }
+
+    /// Cancel every task (the panic button).
+    pub fn cancel_all(&self) -> usize {
+        self.cancel_where(|_| true)
+    }
+
+    /// Running and waiting tasks: running first, by priority then age.
+    pub fn tasks(&self) -> Vec<TaskInfo> {
+        let mut tasks: Vec<TaskInfo> = self.lock().entries.iter().map(Entry::info).collect();
+        tasks.sort_by_key(|t| (t.started.is_none(), std::cmp::Reverse(t.priority), t.id));
+        tasks
+    }
+
+    /// Running tasks matching `pred`.
+    pub fn running_where(&self, pred: impl Fn(&TaskInfo) -> bool) -> usize {
+        self.lock()
+            .entries
+            .iter()
+            .map(Entry::info)
+            .filter(|t| t.started.is_some() && pred(t))
+            .count()
+    }
+
+    pub fn counters(&self) -> TaskCounters {
+        let inner = self.lock();
+        let waiting = inner.entries.iter().filter(|e| e.job.is_some()).count();
+        TaskCounters {
+            running: inner.entries.len() - waiting,
+            waiting,
+            finished: inner.finished,
+            cancelled: inner.cancelled,
+            deferred: inner.deferred,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::sync::oneshot;
+
+    /// Wait until the manager's entries are all gone.
+    async fn drained(manager: &TaskManager) {
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while manager.counters().running + manager.counters().waiting > 0 {
+                tokio::task::yield_now().await;
+            }
+        })
+        .await
+        .expect("tasks did not finish");
+    }
+
+    /// A task that runs until its gate is opened.
+    fn gated(
+        manager: &TaskManager,
+        kind: TaskKind,
+        priority: Priority,
+    ) -> (TaskToken, oneshot::Sender<()>) {
+        let (open, gate) = oneshot::channel::<()>();
+        let token = manager.spawn(kind, "gated", priority, |_| async move {
+            let _ = gate.await;
+        });
+        (token, open)
+    }
+
+    #[tokio::test]
+    async fn completion_removes_entries() {
+        let manager = TaskManager::default();
+        let (done_tx, done_rx) = oneshot::channel();
+        let token = manager.spawn(
+            TaskKind::Autosave,
+            "save session",
+            Priority::Background,
+            |_| async move {
+                let _ = done_tx.send(());
+            },
+        );
+        assert_eq!(manager.tasks()[0].id, token.id());
+        done_rx.await.unwrap();
+        drained(&manager).await;
+        assert!(manager.tasks().is_empty());
+        assert_eq!(manager.counters().finished, 1);
+    }
+
+    #[tokio::test]
+    async fn kinds_at_their_limit_wait_for_a_slot() {
+        let manager = TaskManager::default();
+        let (_, open_first) = gated(&manager, TaskKind::Blame, Priority::Normal);
+        let (low, open_low) = gated(&manager, TaskKind::Blame, Priority::Background);
+        let (high, open_high) = gated(&manager, TaskKind::Blame, Priority::User);
+        // Other kinds are not held back
+        let (_, open_prefetch) = gated(&manager, TaskKind::Prefetch, Priority::Background);
+
+        let counters = manager.counters();
+        assert_eq!(
+            (counters.running, counters.waiting, counters.deferred),
+            (2, 2, 2)
+        );
+        let listed = manager.tasks();
+        assert!(listed[2].summary(Instant::now()).ends_with("waiting"));
+
+        // The freed slot goes to the higher priority
+        open_first.send(()).unwrap();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while manager.counters().waiting > 1 {
+                tokio::task::yield_now().await;
+            }
+        })
+        .await
+        .unwrap();
+        let started = |id| {
+            manager
+                .tasks()
+                .iter()
+                .any(|t| t.id == id && t.started.is_some())
+        };
+        assert!(started(high.id()));
+        assert!(!started(low.id()));
+
+        open_high.send(()).unwrap();
+        open_low.send(()).unwrap();
+        open_prefetch.send(()).unwrap();
+        drained(&manager).await;
+        assert_eq!(manager.counters().finished, 4);
+    }
+
+    #[tokio::test]
+    async fn cancellation_reaches_the_job() {
+        let manager = TaskManager::default();
+        let (seen_tx, seen_rx) = oneshot::channel();
+        let token = manager.spawn(
+            TaskKind::Transfer,
+            "copy",
+            Priority::User,
+            |token| async move {
+                while !token.is_cancelled() {
+                    tokio::task::yield_now().await;
+                }
+                let _ = seen_tx.send(());
+            },
+        );
+        assert!(manager.cancel(token.id()));
+        assert!(!manager.cancel(token.id()), "already cancelled");
+        seen_rx.await.unwrap();
+        drained(&manager).await;
+        assert_eq!(manager.counters().cancelled, 1);
+    }
+
+    #[tokio::test]
+    async fn cancelled_waiting_tasks_still_run() {
+        let manager = TaskManager::default();
+        let (_, open) = gated(&manager, TaskKind::Autosave, Priority::Background);
+        let (ran_tx, ran_rx) = oneshot::channel();
+        manager.spawn(
+            TaskKind::Autosave,
+            "second",
+            Priority::Background,
+            |token| async move {
+                let _ = ran_tx.send(token.is_cancelled());
+            },
+        );
+        assert_eq!(manager.counters().waiting, 1);
+
+        assert_eq!(manager.cancel_all(), 2);
+        // Started at once, over the limit, with its flag up
+        assert!(ran_rx.await.unwrap());
+        open.send(()).unwrap();
+        drained(&manager).await;
+        assert_eq!(manager.counters().cancelled, 2);
+    }
+
+    #[tokio::test]
+    async fn progress_is_reported() {
+        let manager = TaskManager::default();
+        let (token, open) = gated(&manager, TaskKind::Delete, Priority::User);
+        assert_eq!(manager.tasks()[0].progress, None);
+        token.progress(3, 10);
+        assert_eq!(manager.tasks()[0].progress, Some((3, 10)));
+        assert!(manager.tasks()[0].summary(Instant::now()).contains("3/10"));
+        assert_eq!(manager.running_where(|t| t.kind.touches_files()), 1);
+        open.send(()).unwrap();
+        drained(&manager).await;
+    }
+}
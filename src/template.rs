@@ -0,0 +1,119 @@
+//! `{{name}}` placeholders filled in from a set of variables.
+//!
+//! The one rendering engine for anything instantiated from a template:
+//! scaffolds run both their file contents and their file and directory
+//! names through it. Spaces inside the braces are ignored, so
+//! `{{ project_name }}` works too. A name is ASCII letters, digits, `_`
+//! and `-`. Anything else between double braces, and placeholders naming
+//! no known variable, is left as written, so templates can carry other
+//! `{{…}}` syntax (Handlebars, Jinja, GitHub Actions) through unchanged.
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+/// Variable values by name.
+pub type Vars = BTreeMap<String, String>;
+
+/// Whether `name` can appear in a placeholder.
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-')
+}
+
+/// `text` with every placeholder naming one of `vars` replaced by its value.
+pub fn render(text: &str, vars: &Vars) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut copied = 0;
+    for (range, name) in scan(text) {
+        if let Some(value) = vars.get(name) {
+            out.push_str(&text[copied..range.start]);
+            out.push_str(value);
+            copied = range.end;
+        }
+    }
+    out.push_str(&text[copied..]);
+    out
+}
+
+/// Distinct placeholder names in `text`, in order of first appearance.
+pub fn placeholders(text: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for (_, name) in scan(text) {
+        if !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+        }
+    }
+    names
+}
+
+/// Every well-formed placeholder in `text`: its byte range, braces
+/// included, and the name inside.
+fn scan(text: &str) -> Vec<(Range<usize>, &str)> {
+    let mut found = Vec::new();
+    let mut from = 0;
+    while let Some(open) = text[from..].find("{{").map(|i| from + i) {
+        let inner_start = open + 2;
+        let Some(close) = text[inner_start..].find("}}").map(|i| inner_start + i) else {
+            break;
+        };
+        let name = text[inner_start..close].trim_matches(' ');
+        if is_valid_name(name) {
+            found.push((open..close + 2, name));
+            from = close + 2;
+        } else {
+            // `{{{x}}}` and friends: retry from the next brace
+            from = open + 1;
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> Vars {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn fills_known_placeholders() {
+        let vars = vars(&[("project_name", "demo"), ("author", "Ana")]);
+        assert_eq!(
+            render("# {{project_name}} by {{ author }}", &vars),
+            "# demo by Ana"
+        );
+        assert_eq!(
+            render("{{project_name}}{{project_name}}", &vars),
+            "demodemo"
+        );
+        assert_eq!(render("{{{project_name}}}", &vars), "{demo}");
+    }
+
+    #[test]
+    fn leaves_other_braces_alone() {
+        let vars = vars(&[("name", "x")]);
+        for text in [
+            "{{ secrets.TOKEN }}",
+            "{{unknown}}",
+            "{{#each items}}",
+            "{{name",
+            "{ {name} }",
+        ] {
+            assert_eq!(render(text, &vars), text);
+        }
+    }
+
+    #[test]
+    fn lists_placeholders_once_in_order() {
+        assert_eq!(
+            placeholders("{{b}} {{a}} {{ b }} {{not valid}}"),
+            vec!["b".to_string(), "a".to_string()]
+        );
+    }
+}
@@ -0,0 +1,183 @@
+//! Coalescing bridge from the PTY reader to the main event loop.
+//!
+//! The reader task sends every `read` as its own chunk, so a command like
+//! `yes` produces thousands of chunks per second. Forwarded one by one,
+//! each becomes an event and a redraw, and key handling starves. The
+//! bridge gathers chunks into batches and forwards at most one batch per
+//! frame interval, or sooner once a batch reaches a size cap. While the
+//! terminal panel is hidden or unfocused, the interval and cap are larger.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+/// How often batches may be forwarded, and how large one may grow first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pacing {
+    pub interval: Duration,
+    pub max_bytes: usize,
+}
+
+/// Pacing while the terminal panel is visible and focused (~60 batches/s).
+pub const FOREGROUND: Pacing = Pacing {
+    interval: Duration::from_millis(16),
+    max_bytes: 64 * 1024,
+};
+
+/// Pacing while the panel is hidden or another panel has focus.
+pub const BACKGROUND: Pacing = Pacing {
+    interval: Duration::from_millis(100),
+    max_bytes: 1024 * 1024,
+};
+
+/// Forward PTY output from `rx` to `forward` in batches until the channel
+/// closes or `forward` returns false.
+///
+/// The first chunk after a quiet period goes out at once, so typing echo
+/// isn't delayed; later chunks wait for the interval to pass. `foreground`
+/// selects [`FOREGROUND`] or [`BACKGROUND`] pacing and is re-read per batch.
+pub async fn run<F>(
+    mut rx: mpsc::UnboundedReceiver<Vec<u8>>,
+    foreground: Arc<AtomicBool>,
+    mut forward: F,
+) where
+    F: FnMut(Vec<u8>) -> bool,
+{
+    let mut last_sent: Option<Instant> = None;
+    loop {
+        let mut batch = match rx.recv().await {
+            Some(chunk) => chunk,
+            None => return,
+        };
+        let pacing = if foreground.load(Ordering::Relaxed) {
+            FOREGROUND
+        } else {
+            BACKGROUND
+        };
+        let deadline = match last_sent {
+            Some(sent) => sent + pacing.interval,
+            None => Instant::now(),
+        };
+
+        let mut open = true;
+        while batch.len() < pacing.max_bytes {
+            // Drain ready chunks before checking the deadline
+            tokio::select! {
+                biased;
+                chunk = rx.recv() => match chunk {
+                    Some(chunk) => batch.extend_from_slice(&chunk),
+                    None => {
+                        open = false;
+                        break;
+                    }
+                },
+                _ = tokio::time::sleep_until(deadline) => break,
+            }
+        }
+
+        if !forward(batch) || !open {
+            return;
+        }
+        last_sent = Some(Instant::now());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "embedded-terminal")]
+    use crate::terminal::emulator::TerminalEmulator;
+
+    const CHUNK: usize = 4096;
+
+    /// About 10 MB of colored lines with multi-byte characters, so chunk
+    /// boundaries split both escape sequences and UTF-8.
+    fn heavy_output() -> Vec<u8> {
+        let mut out = Vec::with_capacity(10 * 1024 * 1024 + 64);
+        let mut i = 0usize;
+        while out.len() < 10 * 1024 * 1024 {
+            out.extend_from_slice(format!("line {} \x1b[32mok\x1b[0m café ✓\r\n", i).as_bytes());
+            i += 1;
+        }
+        out
+    }
+
+    /// Feed `data` through the bridge in reader-sized chunks.
+    async fn pump(data: &[u8], foreground: bool) -> Vec<Vec<u8>> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        for chunk in data.chunks(CHUNK) {
+            tx.send(chunk.to_vec()).unwrap();
+        }
+        drop(tx);
+        let mut batches = Vec::new();
+        run(rx, Arc::new(AtomicBool::new(foreground)), |batch| {
+            batches.push(batch);
+            true
+        })
+        .await;
+        batches
+    }
+
+    #[tokio::test]
+    async fn heavy_output_is_coalesced() {
+        let data = heavy_output();
+        let chunks = data.len().div_ceil(CHUNK);
+
+        let batches = pump(&data, true).await;
+        assert!(batches.len() <= data.len() / FOREGROUND.max_bytes + 1);
+        assert!(batches.len() * 10 < chunks);
+        assert_eq!(batches.concat(), data);
+
+        let background = pump(&data, false).await;
+        assert!(background.len() <= data.len() / BACKGROUND.max_bytes + 1);
+        assert_eq!(background.concat(), data);
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "embedded-terminal")]
+    async fn batching_leaves_the_emulator_state_unchanged() {
+        let data = heavy_output();
+        let batches = pump(&data, true).await;
+
+        // Same state as with unbatched input, scrollback trimming included
+        let mut unbatched = TerminalEmulator::with_scrollback(24, 80, 200);
+        for chunk in data.chunks(CHUNK) {
+            unbatched.process(chunk);
+        }
+        let mut batched = TerminalEmulator::with_scrollback(24, 80, 200);
+        for batch in &batches {
+            batched.process(batch);
+        }
+        assert_eq!(batched.render_lines(), unbatched.render_lines());
+        assert_eq!(batched.scrollback_lines(), unbatched.scrollback_lines());
+        assert_eq!(batched.scrollback_len(), 200);
+        assert_eq!(batched.cursor_position(), unbatched.cursor_position());
+    }
+
+    #[tokio::test]
+    async fn first_chunk_is_forwarded_without_waiting() {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let (out_tx, mut out_rx) = mpsc::unbounded_channel();
+        tokio::spawn(run(rx, Arc::new(AtomicBool::new(false)), move |batch| {
+            out_tx.send(batch).is_ok()
+        }));
+
+        tx.send(b"$ ".to_vec()).unwrap();
+        let batch = tokio::time::timeout(Duration::from_millis(50), out_rx.recv())
+            .await
+            .expect("forwarded before the background interval");
+        assert_eq!(batch, Some(b"$ ".to_vec()));
+
+        // Closing the reader side flushes and stops the bridge
+        tx.send(b"exit".to_vec()).unwrap();
+        drop(tx);
+        let rest = tokio::time::timeout(Duration::from_secs(1), out_rx.recv())
+            .await
+            .unwrap();
+        assert_eq!(rest, Some(b"exit".to_vec()));
+        assert_eq!(out_rx.recv().await, None);
+    }
+}
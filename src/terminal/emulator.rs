@@ -2,10 +2,17 @@
 //!
 //! Uses the `vte` crate (from Alacritty) to parse ANSI sequences and
 //! maintains a grid of cells that map to ratatui styled spans for rendering.
+//! A build without the `embedded-terminal` feature has no PTY to read from
+//! and leaves the parser out; the grid stays blank.
+
+use std::path::PathBuf;
 
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
 
+/// Default number of scrollback lines kept by the emulator.
+pub const DEFAULT_MAX_SCROLLBACK: usize = 1000;
+
 /// A single character cell in the terminal grid.
 #[derive(Debug, Clone)]
 pub struct Cell {
@@ -27,6 +34,7 @@ impl Default for Cell {
 }
 
 /// The terminal emulator with screen buffer and VTE parser.
+#[cfg_attr(not(feature = "embedded-terminal"), allow(dead_code))]
 pub struct TerminalEmulator {
     /// Current visible screen grid (rows × cols).
     grid: Vec<Vec<Cell>>,
@@ -47,19 +55,31 @@ pub struct TerminalEmulator {
     current_bg: Color,
     current_modifiers: Modifier,
     /// VTE state machine parser.
+    #[cfg(feature = "embedded-terminal")]
     parser: vte::Parser,
     /// Saved cursor position (for ESC 7 / ESC 8).
     saved_cursor: Option<(usize, usize)>,
+    /// Working directory last reported by the shell (OSC 7).
+    reported_cwd: Option<PathBuf>,
+    /// Lines scrolled off the top of the grid since last taken.
+    scrolled_lines: usize,
 }
 
 impl TerminalEmulator {
     /// Create a new terminal emulator with the given dimensions.
+    #[allow(dead_code)]
     pub fn new(rows: usize, cols: usize) -> Self {
+        Self::with_scrollback(rows, cols, DEFAULT_MAX_SCROLLBACK)
+    }
+
+    /// Create a new terminal emulator keeping at most `max_scrollback` lines
+    /// of history above the visible grid.
+    pub fn with_scrollback(rows: usize, cols: usize, max_scrollback: usize) -> Self {
         let grid = vec![vec![Cell::default(); cols]; rows];
         Self {
             grid,
             scrollback: Vec::new(),
-            max_scrollback: 1000,
+            max_scrollback,
             cursor_row: 0,
             cursor_col: 0,
             rows,
@@ -67,12 +87,27 @@ impl TerminalEmulator {
             current_fg: Color::Reset,
             current_bg: Color::Reset,
             current_modifiers: Modifier::empty(),
+            #[cfg(feature = "embedded-terminal")]
             parser: vte::Parser::new(),
             saved_cursor: None,
+            reported_cwd: None,
+            scrolled_lines: 0,
         }
     }
 
+    /// Working directory last reported by the shell via OSC 7, for shells
+    /// configured to emit it.
+    pub fn reported_cwd(&self) -> Option<&std::path::Path> {
+        self.reported_cwd.as_deref()
+    }
+
+    /// Forget the reported directory (the shell was replaced).
+    pub fn clear_reported_cwd(&mut self) {
+        self.reported_cwd = None;
+    }
+
     /// Process raw bytes from the PTY through the VTE parser.
+    #[cfg(feature = "embedded-terminal")]
     pub fn process(&mut self, data: &[u8]) {
         for &byte in data {
             // The VTE parser calls methods on Perform trait via advance()
@@ -89,11 +124,17 @@ impl TerminalEmulator {
                 current_bg: &mut self.current_bg,
                 current_modifiers: &mut self.current_modifiers,
                 saved_cursor: &mut self.saved_cursor,
+                reported_cwd: &mut self.reported_cwd,
+                scrolled_lines: &mut self.scrolled_lines,
             };
             self.parser.advance(&mut performer, byte);
         }
     }
 
+    /// Without a PTY nothing ever arrives to parse.
+    #[cfg(not(feature = "embedded-terminal"))]
+    pub fn process(&mut self, _data: &[u8]) {}
+
     /// Resize the emulator grid.
     pub fn resize(&mut self, new_rows: usize, new_cols: usize) {
         let mut new_grid = vec![vec![Cell::default(); new_cols]; new_rows];
@@ -118,25 +159,42 @@ impl TerminalEmulator {
     }
 
     /// Render the visible grid as ratatui Lines (for the widget).
+    #[allow(dead_code)]
     pub fn render_lines(&self) -> Vec<Line<'static>> {
-        self.grid
-            .iter()
-            .map(|row| {
-                let spans: Vec<Span<'static>> = row
-                    .iter()
-                    .map(|cell| {
-                        let style = Style::default()
-                            .fg(cell.fg)
-                            .bg(cell.bg)
-                            .add_modifier(cell.modifiers);
-                        Span::styled(cell.ch.to_string(), style)
-                    })
-                    .collect();
-                Line::from(spans)
-            })
+        self.render_view(0)
+    }
+
+    /// Render a screenful `offset` lines above the live bottom: the last
+    /// `offset` scrollback lines followed by the top of the grid. The
+    /// offset is clamped to the scrollback held.
+    pub fn render_view(&self, offset: usize) -> Vec<Line<'static>> {
+        self.view_rows(offset).map(|row| render_row(row)).collect()
+    }
+
+    /// The plain text of the screenful `render_view` shows at `offset`,
+    /// one string per row and one char per cell, so char indices are
+    /// columns.
+    pub fn view_text(&self, offset: usize) -> Vec<String> {
+        self.view_rows(offset)
+            .map(|row| row.iter().map(|cell| cell.ch).collect())
             .collect()
     }
 
+    /// Rows of the screenful `offset` lines above the live bottom.
+    fn view_rows(&self, offset: usize) -> impl Iterator<Item = &Vec<Cell>> {
+        let offset = offset.min(self.scrollback.len());
+        self.scrollback[self.scrollback.len() - offset..]
+            .iter()
+            .chain(&self.grid)
+            .take(self.rows)
+    }
+
+    /// Lines scrolled off the top of the grid since the last call, for
+    /// keeping a scrolled-back view in place as output arrives.
+    pub fn take_scrolled_lines(&mut self) -> usize {
+        std::mem::take(&mut self.scrolled_lines)
+    }
+
     /// Total lines including scrollback.
     #[allow(dead_code)]
     pub fn total_lines(&self) -> usize {
@@ -146,22 +204,34 @@ impl TerminalEmulator {
     /// Get scrollback lines for rendering (oldest first).
     #[allow(dead_code)]
     pub fn scrollback_lines(&self) -> Vec<Line<'static>> {
+        self.scrollback.iter().map(|row| render_row(row)).collect()
+    }
+
+    /// Maximum number of scrollback lines retained.
+    #[allow(dead_code)]
+    pub fn max_scrollback(&self) -> usize {
+        self.max_scrollback
+    }
+
+    /// Change the scrollback limit, trimming the oldest lines if needed.
+    #[allow(dead_code)]
+    pub fn set_max_scrollback(&mut self, max: usize) {
+        self.max_scrollback = max;
+        trim_scrollback(&mut self.scrollback, max);
+    }
+
+    /// Number of lines currently held in scrollback.
+    pub fn scrollback_len(&self) -> usize {
+        self.scrollback.len()
+    }
+
+    /// Approximate heap bytes held by the scrollback buffer.
+    pub fn scrollback_bytes(&self) -> usize {
         self.scrollback
             .iter()
-            .map(|row| {
-                let spans: Vec<Span<'static>> = row
-                    .iter()
-                    .map(|cell| {
-                        let style = Style::default()
-                            .fg(cell.fg)
-                            .bg(cell.bg)
-                            .add_modifier(cell.modifiers);
-                        Span::styled(cell.ch.to_string(), style)
-                    })
-                    .collect();
-                Line::from(spans)
-            })
-            .collect()
+            .map(|row| row.capacity() * std::mem::size_of::<Cell>())
+            .sum::<usize>()
+            + self.scrollback.capacity() * std::mem::size_of::<Vec<Cell>>()
     }
 
     /// Get visible rows count.
@@ -180,8 +250,59 @@ impl TerminalEmulator {
     }
 }
 
+/// One grid row as a line of single-cell spans.
+fn render_row(row: &[Cell]) -> Line<'static> {
+    let spans: Vec<Span<'static>> = row
+        .iter()
+        .map(|cell| {
+            let style = Style::default()
+                .fg(cell.fg)
+                .bg(cell.bg)
+                .add_modifier(cell.modifiers);
+            Span::styled(cell.ch.to_string(), style)
+        })
+        .collect();
+    Line::from(spans)
+}
+
+/// Drop the oldest scrollback lines so at most `max` remain.
+fn trim_scrollback(scrollback: &mut Vec<Vec<Cell>>, max: usize) {
+    if scrollback.len() > max {
+        let excess = scrollback.len() - max;
+        scrollback.drain(..excess);
+    }
+}
+
+/// Path of an OSC 7 `file://host/path` URI, percent-decoded.
+#[cfg(feature = "embedded-terminal")]
+fn parse_osc7(uri: &[u8]) -> Option<PathBuf> {
+    let rest = uri.strip_prefix(b"file://")?;
+    // Skip the host part
+    let path = &rest[rest.iter().position(|&b| b == b'/')?..];
+    let mut decoded = Vec::with_capacity(path.len());
+    let mut i = 0;
+    while i < path.len() {
+        let hex = path
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (path[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).ok().map(PathBuf::from)
+}
+
 /// Internal performer struct that receives VTE callbacks.
 /// Separated from TerminalEmulator to avoid borrow-checker issues with the parser.
+#[cfg(feature = "embedded-terminal")]
 struct Performer<'a> {
     grid: &'a mut Vec<Vec<Cell>>,
     scrollback: &'a mut Vec<Vec<Cell>>,
@@ -194,19 +315,22 @@ struct Performer<'a> {
     current_bg: &'a mut Color,
     current_modifiers: &'a mut Modifier,
     saved_cursor: &'a mut Option<(usize, usize)>,
+    reported_cwd: &'a mut Option<PathBuf>,
+    scrolled_lines: &'a mut usize,
 }
 
+#[cfg(feature = "embedded-terminal")]
 impl<'a> Performer<'a> {
     /// Scroll the grid up by one line, moving the top line to scrollback.
     fn scroll_up(&mut self) {
         if !self.grid.is_empty() {
             let line = self.grid.remove(0);
-            self.scrollback.push(line);
-            // Trim scrollback
-            if self.scrollback.len() > self.max_scrollback {
-                self.scrollback.remove(0);
+            if self.max_scrollback > 0 {
+                self.scrollback.push(line);
+                trim_scrollback(self.scrollback, self.max_scrollback);
             }
             self.grid.push(vec![Cell::default(); self.cols]);
+            *self.scrolled_lines += 1;
         }
     }
 
@@ -220,6 +344,7 @@ impl<'a> Performer<'a> {
     }
 }
 
+#[cfg(feature = "embedded-terminal")]
 impl<'a> vte::Perform for Performer<'a> {
     /// Handle printable characters.
     fn print(&mut self, c: char) {
@@ -556,8 +681,15 @@ impl<'a> vte::Perform for Performer<'a> {
         }
     }
 
-    fn osc_dispatch(&mut self, _params: &[&[u8]], _bell_terminated: bool) {
-        // OSC sequences (terminal title, etc.) — store but ignore for now
+    fn osc_dispatch(&mut self, params: &[&[u8]], _bell_terminated: bool) {
+        // OSC 7 reports the working directory; others (title, etc.) are ignored
+        if let [b"7", rest @ ..] = params {
+            // The parser splits on ';', which may occur in the path
+            let uri = rest.join(&b';');
+            if let Some(path) = parse_osc7(&uri) {
+                *self.reported_cwd = Some(path);
+            }
+        }
     }
 
     fn hook(&mut self, _params: &vte::Params, _intermediates: &[u8], _ignore: bool, _action: char) {
@@ -568,6 +700,7 @@ impl<'a> vte::Perform for Performer<'a> {
     fn put(&mut self, _byte: u8) {}
 }
 
+#[cfg(feature = "embedded-terminal")]
 impl<'a> Performer<'a> {
     /// Handle SGR (Select Graphic Rendition) parameters.
     fn handle_sgr(&mut self, params: &[u16]) {
@@ -681,7 +814,7 @@ impl<'a> Performer<'a> {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "embedded-terminal"))]
 mod tests {
     use super::*;
 
@@ -706,6 +839,14 @@ mod tests {
         assert_eq!(emu.grid[0][4].ch, 'o');
     }
 
+    #[test]
+    fn view_text_follows_the_scrolled_view() {
+        let mut emu = TerminalEmulator::new(2, 6);
+        emu.process(b"one\r\ntwo\r\nthree");
+        assert_eq!(emu.view_text(0), ["two   ", "three "]);
+        assert_eq!(emu.view_text(1), ["one   ", "two   "]);
+    }
+
     #[test]
     fn test_newline_and_carriage_return() {
         let mut emu = TerminalEmulator::new(24, 80);
@@ -775,6 +916,37 @@ mod tests {
         assert_eq!(lines[0].spans.len(), 5);
     }
 
+    #[test]
+    fn scrolled_lines_count_lines_leaving_the_grid() {
+        let mut emu = TerminalEmulator::new(3, 10);
+        emu.process(b"a\r\nb\r\n");
+        assert_eq!(emu.take_scrolled_lines(), 0);
+        emu.process(b"c\r\nd\r\ne");
+        assert_eq!(emu.take_scrolled_lines(), 2);
+        assert_eq!(emu.take_scrolled_lines(), 0);
+        // Resizing neither scrolls nor forgets a pending count
+        emu.process(b"\r\nf");
+        emu.resize(2, 8);
+        assert_eq!(emu.take_scrolled_lines(), 1);
+    }
+
+    #[test]
+    fn render_view_shows_history_above_the_grid() {
+        let mut emu = TerminalEmulator::new(2, 3);
+        emu.process(b"1\r\n2\r\n3\r\n4");
+        let first = |lines: Vec<Line>| -> Vec<String> {
+            lines
+                .iter()
+                .map(|l| l.spans[0].content.to_string())
+                .collect()
+        };
+        assert_eq!(first(emu.render_view(0)), ["3", "4"]);
+        assert_eq!(first(emu.render_view(1)), ["2", "3"]);
+        assert_eq!(first(emu.render_view(2)), ["1", "2"]);
+        // Clamped to the history held
+        assert_eq!(first(emu.render_view(9)), ["1", "2"]);
+    }
+
     #[test]
     fn test_tab() {
         let mut emu = TerminalEmulator::new(24, 80);
@@ -822,4 +994,66 @@ mod tests {
         emu.process(b"\x1b[38;2;255;128;0mX");
         assert_eq!(emu.grid[0][0].fg, Color::Rgb(255, 128, 0));
     }
+
+    #[test]
+    fn test_scrollback_trimmed_to_limit() {
+        let mut emu = TerminalEmulator::with_scrollback(3, 10, 5);
+        for i in 0..20 {
+            emu.process(format!("L{}\r\n", i).as_bytes());
+        }
+        assert_eq!(emu.scrollback_len(), 5);
+        // Oldest retained line is the 13th one written (18 scrolled off, 5 kept)
+        assert_eq!(emu.scrollback[0][0].ch, 'L');
+        assert_eq!(emu.scrollback[0][1].ch, '1');
+        assert_eq!(emu.scrollback[0][2].ch, '3');
+    }
+
+    #[test]
+    fn test_zero_scrollback_keeps_nothing() {
+        let mut emu = TerminalEmulator::with_scrollback(2, 10, 0);
+        emu.process(b"a\r\nb\r\nc\r\nd");
+        assert_eq!(emu.scrollback_len(), 0);
+        assert_eq!(emu.scrollback_bytes(), 0);
+    }
+
+    #[test]
+    fn test_set_max_scrollback_trims_existing() {
+        let mut emu = TerminalEmulator::new(2, 10);
+        for _ in 0..10 {
+            emu.process(b"x\r\n");
+        }
+        assert_eq!(emu.scrollback_len(), 9);
+        let before = emu.scrollback_bytes();
+        emu.set_max_scrollback(3);
+        assert_eq!(emu.max_scrollback(), 3);
+        assert_eq!(emu.scrollback_len(), 3);
+        assert!(emu.scrollback_bytes() <= before);
+    }
+
+    #[test]
+    fn test_osc7_reports_cwd() {
+        let mut emu = TerminalEmulator::new(2, 10);
+        assert!(emu.reported_cwd().is_none());
+        emu.process(b"\x1b]7;file://host/home/me/my%20project\x07");
+        assert_eq!(
+            emu.reported_cwd(),
+            Some(std::path::Path::new("/home/me/my project"))
+        );
+        // ST-terminated, with a ';' in the path
+        emu.process(b"\x1b]7;file:///tmp/a;b\x1b\\");
+        assert_eq!(emu.reported_cwd(), Some(std::path::Path::new("/tmp/a;b")));
+        // Other OSCs leave it alone
+        emu.process(b"\x1b]0;title\x07");
+        assert_eq!(emu.reported_cwd(), Some(std::path::Path::new("/tmp/a;b")));
+    }
+
+    #[test]
+    fn test_child_titles_are_swallowed() {
+        // A shell or program setting its own title must not print it, or
+        // reach the outer terminal's title (see `title`).
+        let mut emu = TerminalEmulator::new(2, 20);
+        emu.process(b"\x1b]0;vim\x07\x1b]2;htop\x1b\\$ ");
+        assert_eq!(emu.cursor_position(), (0, 2));
+        assert_eq!(emu.grid[0][0].ch, '$');
+    }
 }
@@ -0,0 +1,143 @@
+//! `path[:line[:col]]` references in terminal output, such as
+//! `src/handler.rs:123:5` in a compiler error, found in the emulator's
+//! plain line text so the panel can underline them and follow one into the
+//! tree and preview.
+//!
+//! Only the shape is checked here: a reference is a run of path
+//! characters holding a `/` or ending in a file extension. Whether it
+//! names something is left to whoever follows it.
+
+use crate::fs::location::{self, Location};
+
+/// A reference on one line: the columns `start..end` it covers and what
+/// it points at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathLink {
+    pub start: usize,
+    pub end: usize,
+    pub location: Location,
+}
+
+/// Every reference on `line`, left to right.
+pub fn find(line: &str) -> Vec<PathLink> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut links = Vec::new();
+    let mut start = 0;
+    while start < chars.len() {
+        if is_separator(chars[start]) {
+            start += 1;
+            continue;
+        }
+        let mut end = start;
+        while end < chars.len() && !is_separator(chars[end]) {
+            end += 1;
+        }
+        // Sentence punctuation after a reference isn't part of it; a
+        // colon may be (`path:12:` before grep's matched text)
+        let mut last = end;
+        while last > start && matches!(chars[last - 1], '.' | '!' | '?') {
+            last -= 1;
+        }
+        let token: String = chars[start..last].iter().collect();
+        if let Some(location) = reference(&token) {
+            links.push(PathLink {
+                start,
+                end: last,
+                location,
+            });
+        }
+        start = end;
+    }
+    links
+}
+
+/// What `token` points at, if it looks like a reference at all.
+fn reference(token: &str) -> Option<Location> {
+    if token.contains("://") {
+        return None;
+    }
+    let location = location::parse(token);
+    let path = location.path.to_str()?;
+    if !path.chars().any(char::is_alphabetic) {
+        return None;
+    }
+    let has_extension = path
+        .rsplit('/')
+        .next()
+        .and_then(|name| name.rsplit_once('.'))
+        .is_some_and(|(stem, ext)| {
+            !stem.chars().all(|c| c.is_ascii_digit() || c == '.')
+                && (1..=10).contains(&ext.len())
+                && ext.chars().all(|c| c.is_ascii_alphanumeric())
+                && ext.chars().any(|c| c.is_ascii_alphabetic())
+        });
+    (path.contains('/') || has_extension).then_some(location)
+}
+
+/// Characters that end a reference.
+fn is_separator(c: char) -> bool {
+    c.is_whitespace()
+        || matches!(
+            c,
+            '"' | '\'' | '`' | '(' | ')' | '[' | ']' | '<' | '>' | '{' | '}' | ',' | ';' | '|'
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn found(line: &str) -> Vec<(String, Option<usize>, Option<usize>)> {
+        find(line)
+            .into_iter()
+            .map(|link| {
+                let text: String = line.chars().take(link.end).skip(link.start).collect();
+                assert_eq!(link.location, location::parse(&text));
+                (
+                    link.location.path.display().to_string(),
+                    link.location.line,
+                    link.location.col,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn compiler_and_test_runner_references_are_found() {
+        assert_eq!(
+            found("   --> src/handler.rs:123:5"),
+            [("src/handler.rs".to_string(), Some(123), Some(5))]
+        );
+        assert_eq!(
+            found("  File \"/srv/app/main.py\", line 4, in <module>"),
+            [("/srv/app/main.py".to_string(), None, None)]
+        );
+        assert_eq!(
+            found("at (./lib/index.js:10:2). See README.md."),
+            [
+                ("./lib/index.js".to_string(), Some(10), Some(2)),
+                ("README.md".to_string(), None, None)
+            ]
+        );
+        assert_eq!(
+            found("src/app.rs:42: let x = 1;"),
+            [("src/app.rs".to_string(), Some(42), None)]
+        );
+    }
+
+    #[test]
+    fn words_numbers_and_urls_are_not_references() {
+        assert!(found("Finished dev profile in 1.5s, v0.1.0").is_empty());
+        assert!(found("see https://example.com/docs.html").is_empty());
+        assert!(found("12:30 -- / //").is_empty());
+    }
+
+    #[test]
+    fn columns_are_char_indices() {
+        let links = find("é src/a.rs");
+        assert_eq!(links.len(), 1);
+        assert_eq!((links[0].start, links[0].end), (2, 10));
+        assert_eq!(links[0].location.path, PathBuf::from("src/a.rs"));
+    }
+}
@@ -1,10 +1,17 @@
 //! Embedded terminal panel: PTY process management, terminal emulation, and state.
 
+pub mod bridge;
 pub mod emulator;
+pub mod links;
 pub mod pty;
 
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
 use ratatui::text::Line;
 
+use crate::fs::location::Location;
 use crate::theme::ThemeColors;
 
 /// Overall state for the embedded terminal panel.
@@ -19,19 +26,46 @@ pub struct TerminalState {
     pub height_percent: u16,
     /// Scrollback scroll offset (0 = at bottom / live).
     pub scroll_offset: usize,
+    /// Lines of output that arrived below the view while scrolled back;
+    /// shown as the "↓ N new lines" badge.
+    pub new_lines: usize,
     /// Whether the shell process has exited.
     pub exited: bool,
+    /// Session title shown in the panel border, set when the shell was
+    /// opened in a specific directory ("Open terminal here").
+    pub title: Option<String>,
+    /// Whether the panel is visible and focused; read by the output bridge
+    /// to pick its batching pace.
+    pub output_foreground: Arc<AtomicBool>,
+    /// Directory the shell was started in or last sent to with `cd`.
+    pub started_in: Option<PathBuf>,
+    /// The reference in view last followed with Alt+G, counted up from the
+    /// bottom; new output and scrolling start over at the bottom.
+    pub link_pick: Option<usize>,
 }
 
 impl Default for TerminalState {
     fn default() -> Self {
+        Self::with_scrollback(emulator::DEFAULT_MAX_SCROLLBACK)
+    }
+}
+
+impl TerminalState {
+    /// Create a hidden terminal state whose emulator keeps at most
+    /// `scrollback_lines` lines of history.
+    pub fn with_scrollback(scrollback_lines: usize) -> Self {
         Self {
-            emulator: emulator::TerminalEmulator::new(24, 80),
+            emulator: emulator::TerminalEmulator::with_scrollback(24, 80, scrollback_lines),
             pty: None,
             visible: false,
             height_percent: 30,
             scroll_offset: 0,
+            new_lines: 0,
             exited: false,
+            title: None,
+            output_foreground: Arc::new(AtomicBool::new(false)),
+            started_in: None,
+            link_pick: None,
         }
     }
 }
@@ -42,16 +76,84 @@ impl std::fmt::Debug for TerminalState {
             .field("visible", &self.visible)
             .field("height_percent", &self.height_percent)
             .field("scroll_offset", &self.scroll_offset)
+            .field("new_lines", &self.new_lines)
             .field("exited", &self.exited)
+            .field("title", &self.title)
             .field("pty_active", &self.pty.is_some())
             .finish()
     }
 }
 
 impl TerminalState {
-    /// Get rendered lines from the emulator for display.
+    /// The shell's working directory: what it last reported via OSC 7, or
+    /// else where it was started or sent with `cd`.
+    pub fn cwd(&self) -> Option<&Path> {
+        self.emulator.reported_cwd().or(self.started_in.as_deref())
+    }
+
+    /// Get rendered lines from the emulator for display, from the
+    /// scrolled-back position.
     pub fn render_lines(&self, _theme: &ThemeColors) -> Vec<Line<'static>> {
-        self.emulator.render_lines()
+        self.emulator.render_view(self.scroll_offset)
+    }
+
+    /// Feed PTY output to the emulator. A scrolled-back view stays on the
+    /// lines it shows, and the lines arriving below it are counted.
+    pub fn process(&mut self, data: &[u8]) {
+        self.emulator.process(data);
+        self.link_pick = None;
+        let scrolled = self.emulator.take_scrolled_lines();
+        if self.scroll_offset > 0 && scrolled > 0 {
+            self.scroll_offset = (self.scroll_offset + scrolled).min(self.max_scroll());
+            self.new_lines += scrolled;
+        }
+    }
+
+    /// Furthest the view can scroll back.
+    pub fn max_scroll(&self) -> usize {
+        self.emulator.scrollback_len()
+    }
+
+    /// Scroll back into history by `lines`.
+    pub fn scroll_up(&mut self, lines: usize) {
+        self.scroll_offset = (self.scroll_offset + lines).min(self.max_scroll());
+        self.link_pick = None;
+    }
+
+    /// Scroll toward the live bottom by `lines`; reaching it clears the
+    /// new-lines badge.
+    pub fn scroll_down(&mut self, lines: usize) {
+        self.scroll_offset = self.scroll_offset.saturating_sub(lines);
+        self.link_pick = None;
+        if self.scroll_offset == 0 {
+            self.new_lines = 0;
+        }
+    }
+
+    /// Return to live output.
+    pub fn jump_to_bottom(&mut self) {
+        self.scroll_offset = 0;
+        self.new_lines = 0;
+        self.link_pick = None;
+    }
+
+    /// `path[:line[:col]]` references in the shown screenful, each with
+    /// the row it sits on.
+    pub fn links(&self) -> Vec<(usize, links::PathLink)> {
+        self.emulator
+            .view_text(self.scroll_offset)
+            .iter()
+            .enumerate()
+            .flat_map(|(row, text)| links::find(text).into_iter().map(move |link| (row, link)))
+            .collect()
+    }
+
+    /// The reference under `row`, `col` of the shown screenful.
+    pub fn link_at(&self, row: usize, col: usize) -> Option<Location> {
+        self.links()
+            .into_iter()
+            .find(|(r, link)| *r == row && (link.start..link.end).contains(&col))
+            .map(|(_, link)| link.location)
     }
 
     /// Total number of lines (visible screen + scrollback).
@@ -60,3 +162,49 @@ impl TerminalState {
         self.emulator.total_lines()
     }
 }
+
+/// Title for a session opened in `dir`: its basename, or the whole path
+/// for roots like `/`.
+pub fn session_title(dir: &Path) -> String {
+    dir.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| dir.to_string_lossy().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "embedded-terminal")]
+    fn output_while_scrolled_back_is_counted_and_keeps_the_view() {
+        let mut state = TerminalState::default();
+        state.emulator.resize(3, 20);
+        state.process(b"1\r\n2\r\n3\r\n4\r\n5");
+        // At the live bottom nothing is counted
+        assert_eq!(state.new_lines, 0);
+
+        state.scroll_up(1);
+        let before = state.render_lines(&crate::theme::dark_theme());
+        state.process(b"\r\n6\r\n7");
+        assert_eq!(state.new_lines, 2);
+        assert_eq!(state.scroll_offset, 3);
+        assert_eq!(state.render_lines(&crate::theme::dark_theme()), before);
+
+        // A resize keeps the count; reaching the bottom clears it
+        state.emulator.resize(2, 20);
+        state.process(b"\r\n8");
+        assert_eq!(state.new_lines, 3);
+        state.scroll_down(1);
+        assert_eq!(state.new_lines, 3);
+        state.scroll_down(state.scroll_offset);
+        assert_eq!(state.new_lines, 0);
+    }
+
+    #[test]
+    fn session_title_is_dir_basename() {
+        assert_eq!(session_title(Path::new("/home/u/projects/api")), "api");
+        assert_eq!(session_title(Path::new("/home/u/my dir/")), "my dir");
+        assert_eq!(session_title(Path::new("/")), "/");
+    }
+}
@@ -1,13 +1,23 @@
 //! PTY process management: spawning, I/O, resize, and lifecycle.
+//!
+//! A build without the `embedded-terminal` feature has no PTY backend:
+//! [`PtyProcess::spawn`] and [`open_pty`] fail with [`UNSUPPORTED`].
 
+#[cfg(feature = "embedded-terminal")]
 use std::io::{Read, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+#[cfg(feature = "embedded-terminal")]
 use std::sync::{Arc, Mutex};
 
-use portable_pty::{native_pty_system, CommandBuilder, MasterPty, PtySize};
+#[cfg(feature = "embedded-terminal")]
+use portable_pty::{native_pty_system, CommandBuilder, MasterPty, PtyPair, PtySize};
 use tokio::sync::mpsc;
 
+/// Why the terminal can't start in a build without it.
+pub const UNSUPPORTED: &str = "built without terminal support";
+
 /// A PTY child process wrapping a system shell.
+#[cfg(feature = "embedded-terminal")]
 pub struct PtyProcess {
     /// Writer to send input to the shell.
     writer: Arc<Mutex<Box<dyn Write + Send>>>,
@@ -19,6 +29,7 @@ pub struct PtyProcess {
     _reader_handle: tokio::task::JoinHandle<()>,
 }
 
+#[cfg(feature = "embedded-terminal")]
 impl PtyProcess {
     /// Spawn a new PTY shell process.
     ///
@@ -33,19 +44,8 @@ impl PtyProcess {
         cols: u16,
         output_tx: mpsc::UnboundedSender<Vec<u8>>,
     ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
-        let pty_system = native_pty_system();
-
-        let pair = pty_system.openpty(PtySize {
-            rows,
-            cols,
-            pixel_width: 0,
-            pixel_height: 0,
-        })?;
-
-        let mut cmd = CommandBuilder::new(shell);
-        cmd.cwd(cwd);
-
-        let child = pair.slave.spawn_command(cmd)?;
+        let pair = open_pty(rows, cols)?;
+        let child = pair.slave.spawn_command(shell_command(shell, cwd))?;
 
         // Get writer and reader from master
         let writer = pair.master.take_writer()?;
@@ -135,13 +135,96 @@ impl PtyProcess {
     }
 }
 
+/// Command that starts `shell` with `cwd` as its working directory.
+#[cfg(feature = "embedded-terminal")]
+pub fn shell_command(shell: &str, cwd: &Path) -> CommandBuilder {
+    let mut cmd = CommandBuilder::new(shell);
+    cmd.cwd(cwd);
+    cmd
+}
+
+/// Open a PTY master/slave pair of the given size with the native backend.
+#[cfg(feature = "embedded-terminal")]
+pub fn open_pty(rows: u16, cols: u16) -> Result<PtyPair, Box<dyn std::error::Error + Send + Sync>> {
+    let pair = native_pty_system().openpty(PtySize {
+        rows,
+        cols,
+        pixel_width: 0,
+        pixel_height: 0,
+    })?;
+    Ok(pair)
+}
+
+/// Never constructed: without the `embedded-terminal` feature spawning
+/// always fails.
+#[cfg(not(feature = "embedded-terminal"))]
+pub enum PtyProcess {}
+
+#[cfg(not(feature = "embedded-terminal"))]
+impl PtyProcess {
+    pub fn spawn(
+        _shell: &str,
+        _cwd: &Path,
+        _rows: u16,
+        _cols: u16,
+        _output_tx: mpsc::UnboundedSender<Vec<u8>>,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        Err(UNSUPPORTED.into())
+    }
+
+    pub fn write(&self, _data: &[u8]) -> std::io::Result<()> {
+        match *self {}
+    }
+
+    pub fn resize(&self, _rows: u16, _cols: u16) -> std::io::Result<()> {
+        match *self {}
+    }
+
+    pub fn is_alive(&self) -> bool {
+        match *self {}
+    }
+
+    pub fn shutdown(&self) {
+        match *self {}
+    }
+}
+
+#[cfg(not(feature = "embedded-terminal"))]
+pub fn open_pty(
+    _rows: u16,
+    _cols: u16,
+) -> Result<std::convert::Infallible, Box<dyn std::error::Error + Send + Sync>> {
+    Err(UNSUPPORTED.into())
+}
+
+/// Locate the executable for `shell`: a path containing `/` must exist as a
+/// file, a bare name is looked up in `$PATH`.
+pub fn resolve_shell(shell: &str) -> Option<PathBuf> {
+    let path_var = std::env::var_os("PATH").unwrap_or_default();
+    resolve_shell_in(shell, &path_var)
+}
+
+fn resolve_shell_in(shell: &str, path_var: &std::ffi::OsStr) -> Option<PathBuf> {
+    if shell.is_empty() {
+        return None;
+    }
+    if shell.contains('/') {
+        let path = PathBuf::from(shell);
+        return path.is_file().then_some(path);
+    }
+    std::env::split_paths(path_var)
+        .map(|dir| dir.join(shell))
+        .find(|candidate| candidate.is_file())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    #[cfg(feature = "embedded-terminal")]
     use std::env;
-    use tokio::sync::mpsc;
 
     #[tokio::test]
+    #[cfg(feature = "embedded-terminal")]
     async fn test_spawn_and_is_alive() {
         let (tx, _rx) = mpsc::unbounded_channel();
         let cwd = env::temp_dir();
@@ -153,6 +236,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[cfg(feature = "embedded-terminal")]
     async fn test_write_to_pty() {
         let (tx, _rx) = mpsc::unbounded_channel();
         let cwd = env::temp_dir();
@@ -163,6 +247,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[cfg(feature = "embedded-terminal")]
     async fn test_shutdown() {
         let (tx, _rx) = mpsc::unbounded_channel();
         let cwd = env::temp_dir();
@@ -174,6 +259,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[cfg(feature = "embedded-terminal")]
     async fn test_resize() {
         let (tx, _rx) = mpsc::unbounded_channel();
         let cwd = env::temp_dir();
@@ -182,4 +268,56 @@ mod tests {
         assert!(result.is_ok(), "Resize should succeed");
         pty.shutdown();
     }
+
+    #[test]
+    #[cfg(feature = "embedded-terminal")]
+    fn test_shell_command_sets_cwd() {
+        let cmd = shell_command("/bin/sh", Path::new("/tmp/project"));
+        assert_eq!(
+            cmd.get_cwd().map(|c| c.as_os_str()),
+            Some(std::ffi::OsStr::new("/tmp/project"))
+        );
+        assert_eq!(cmd.get_argv()[0], "/bin/sh");
+    }
+
+    #[test]
+    fn test_resolve_shell_absolute_and_path_lookup() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let shell = tmp.path().join("myshell");
+        std::fs::write(&shell, "").unwrap();
+
+        let abs = shell.to_string_lossy().to_string();
+        assert_eq!(
+            resolve_shell_in(&abs, tmp.path().as_os_str()),
+            Some(shell.clone())
+        );
+        assert_eq!(
+            resolve_shell_in("myshell", tmp.path().as_os_str()),
+            Some(shell)
+        );
+        assert_eq!(
+            resolve_shell_in("nosuchshell", tmp.path().as_os_str()),
+            None
+        );
+        assert_eq!(
+            resolve_shell_in("/no/such/shell", tmp.path().as_os_str()),
+            None
+        );
+        assert_eq!(resolve_shell_in("", tmp.path().as_os_str()), None);
+    }
+
+    #[test]
+    #[cfg(feature = "embedded-terminal")]
+    fn test_open_pty() {
+        assert!(open_pty(24, 80).is_ok(), "PTY pair should open");
+    }
+
+    #[tokio::test]
+    #[cfg(not(feature = "embedded-terminal"))]
+    async fn test_spawn_fails_without_terminal_support() {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let err = PtyProcess::spawn("/bin/sh", Path::new("/"), 24, 80, tx).err();
+        assert_eq!(err.map(|e| e.to_string()).as_deref(), Some(UNSUPPORTED));
+        assert!(open_pty(24, 80).is_err());
+    }
 }
@@ -0,0 +1,446 @@
+//! Display-width and grapheme helpers.
+//!
+//! Terminal columns are not chars: CJK and most emoji take two cells, and
+//! combining marks or zero-width joiners take none. Everything that maps
+//! text to cells (tree names, preview scrolling, editor and dialog cursors)
+//! goes through here so the math agrees with what ratatui actually draws.
+//!
+//! Tabs are the one grapheme whose width depends on where it starts: it
+//! runs to the next multiple of the tab width. The `tab_width` helpers
+//! below are shared by the preview and the editor so both put a tab stop
+//! in the same column.
+//!
+//! Soft hyphens (U+00AD) only mark where a word may break. Terminals
+//! disagree on whether to draw them, so they count as zero columns and the
+//! preview and editor leave them out of what they draw; the text itself
+//! keeps them, so copies do too.
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Marker appended to text cut by [`truncate`].
+pub const ELLIPSIS: &str = "…";
+
+/// U+00AD, an invisible break opportunity.
+pub const SOFT_HYPHEN: &str = "\u{AD}";
+
+/// Columns grapheme `g` occupies; a soft hyphen takes none.
+fn grapheme_width(g: &str) -> usize {
+    if g == SOFT_HYPHEN {
+        0
+    } else {
+        g.width()
+    }
+}
+
+/// Number of terminal columns `s` occupies.
+pub fn width(s: &str) -> usize {
+    s.graphemes(true).map(grapheme_width).sum()
+}
+
+/// Shorten `s` to at most `max` columns, ending in [`ELLIPSIS`] when
+/// anything was cut. Never splits a grapheme.
+pub fn truncate(s: &str, max: usize) -> String {
+    if width(s) <= max {
+        return s.to_string();
+    }
+    if max == 0 {
+        return String::new();
+    }
+    let budget = max - 1;
+    let mut out = String::new();
+    let mut used = 0;
+    for g in s.graphemes(true) {
+        let w = grapheme_width(g);
+        if used + w > budget {
+            break;
+        }
+        out.push_str(g);
+        used += w;
+    }
+    out.push_str(ELLIPSIS);
+    out
+}
+
+/// Shorten `s` to at most `max` columns by cutting the middle, keeping the
+/// extension (`very_long_name.rs` → `very…name.rs`). Falls back to cutting
+/// the end when the extension itself does not fit. Never splits a grapheme.
+pub fn truncate_middle(s: &str, max: usize) -> String {
+    if width(s) <= max {
+        return s.to_string();
+    }
+    // A leading dot (".bashrc") is not an extension
+    let ext = match s.rfind('.') {
+        Some(dot) if dot > 0 => &s[dot..],
+        _ => "",
+    };
+    let ext_width = width(ext);
+    // Room for at least one leading grapheme and the ellipsis
+    if max < ext_width + 2 {
+        return truncate(s, max);
+    }
+    let stem = &s[..s.len() - ext.len()];
+    let room = max - 1 - ext_width;
+    let head_room = room.div_ceil(2);
+
+    let mut head = String::new();
+    let mut used = 0;
+    for g in stem.graphemes(true) {
+        if used + grapheme_width(g) > head_room {
+            break;
+        }
+        head.push_str(g);
+        used += grapheme_width(g);
+    }
+    let tail_room = room - used;
+    let mut tail: Vec<&str> = Vec::new();
+    let mut tail_used = 0;
+    for g in stem[head.len()..].graphemes(true).rev() {
+        if tail_used + grapheme_width(g) > tail_room {
+            break;
+        }
+        tail.push(g);
+        tail_used += grapheme_width(g);
+    }
+    tail.reverse();
+    format!("{}{}{}{}", head, ELLIPSIS, tail.concat(), ext)
+}
+
+/// Columns `start..start + len` of `s`.
+///
+/// A wide grapheme cut by either edge is replaced by spaces for its visible
+/// half, so the result never contains half a glyph and keeps later text
+/// aligned.
+pub fn slice_columns(s: &str, start: usize, len: usize) -> String {
+    let end = start + len;
+    let mut out = String::new();
+    let mut col = 0;
+    for g in s.graphemes(true) {
+        let g_start = col;
+        let g_end = col + grapheme_width(g);
+        col = g_end;
+        if g_start >= end {
+            break;
+        }
+        if g == SOFT_HYPHEN {
+            continue;
+        }
+        if g_start >= start && g_end <= end {
+            out.push_str(g);
+        } else if g_end > start {
+            let visible = g_end.min(end) - g_start.max(start);
+            out.push_str(&" ".repeat(visible));
+        }
+    }
+    out
+}
+
+/// Byte offset of the grapheme boundary after `byte`, or `s.len()`.
+pub fn next_boundary(s: &str, byte: usize) -> usize {
+    s[byte..]
+        .graphemes(true)
+        .next()
+        .map(|g| byte + g.len())
+        .unwrap_or(s.len())
+}
+
+/// Byte offset of the grapheme boundary before `byte`, or 0.
+pub fn prev_boundary(s: &str, byte: usize) -> usize {
+    s[..byte]
+        .graphemes(true)
+        .next_back()
+        .map(|g| byte - g.len())
+        .unwrap_or(0)
+}
+
+/// Start of the grapheme containing `byte` (`byte` itself on a boundary).
+#[cfg_attr(not(feature = "editor"), allow(dead_code))]
+pub fn floor_boundary(s: &str, byte: usize) -> usize {
+    let mut start = 0;
+    while start < byte {
+        let next = next_boundary(s, start);
+        if next > byte {
+            break;
+        }
+        start = next;
+    }
+    start
+}
+
+/// The grapheme starting at `byte`, if any.
+pub fn grapheme_at(s: &str, byte: usize) -> Option<&str> {
+    s.get(byte..).and_then(|rest| rest.graphemes(true).next())
+}
+
+/// Columns grapheme `g` occupies when it starts at column `col`.
+///
+/// A tab runs to the next multiple of `tab_width`; everything else is its
+/// display width, none for a soft hyphen.
+pub fn cell_width(g: &str, col: usize, tab_width: usize) -> usize {
+    if g == "\t" {
+        let tab_width = tab_width.max(1);
+        tab_width - col % tab_width
+    } else {
+        grapheme_width(g)
+    }
+}
+
+/// `s` with every tab replaced by spaces up to its tab stop, counting
+/// columns from `start_col` (the text may continue a line), and soft
+/// hyphens dropped.
+///
+/// Returns the expanded text and the column just past it.
+pub fn expand_tabs(s: &str, start_col: usize, tab_width: usize) -> (String, usize) {
+    if !s.contains(['\t', '\u{AD}']) {
+        return (s.to_string(), start_col + width(s));
+    }
+    let mut out = String::with_capacity(s.len());
+    let mut col = start_col;
+    for g in s.graphemes(true) {
+        let w = cell_width(g, col, tab_width);
+        if g == "\t" {
+            out.push_str(&" ".repeat(w));
+        } else if g != SOFT_HYPHEN {
+            out.push_str(g);
+        }
+        col += w;
+    }
+    (out, col)
+}
+
+/// Display column at which byte offset `byte` of `s` starts.
+#[cfg_attr(not(feature = "editor"), allow(dead_code))]
+pub fn column_at_byte(s: &str, byte: usize, tab_width: usize) -> usize {
+    let mut col = 0;
+    for (offset, g) in s.grapheme_indices(true) {
+        if offset >= byte {
+            break;
+        }
+        col += cell_width(g, col, tab_width);
+    }
+    col
+}
+
+/// Byte offset of the grapheme covering display column `col`.
+///
+/// Clicking either half of a wide glyph, or anywhere inside a tab, lands on
+/// its start; columns past the end map to `s.len()`.
+#[cfg_attr(not(feature = "editor"), allow(dead_code))]
+pub fn byte_at_column(s: &str, col: usize, tab_width: usize) -> usize {
+    let mut start = 0;
+    for (offset, g) in s.grapheme_indices(true) {
+        let w = cell_width(g, start, tab_width);
+        if col < start + w {
+            return offset;
+        }
+        start += w;
+    }
+    s.len()
+}
+
+/// Byte range of the first case-insensitive occurrence of `needle`.
+pub fn find_ignore_case(haystack: &str, needle: &str) -> Option<(usize, usize)> {
+    if needle.is_empty() {
+        return None;
+    }
+    for (start, _) in haystack.char_indices() {
+        let mut rest = haystack[start..].char_indices();
+        let mut end = start;
+        let matched = needle.chars().all(|n| match rest.next() {
+            Some((offset, h)) if h.to_lowercase().eq(n.to_lowercase()) => {
+                end = start + offset + h.len_utf8();
+                true
+            }
+            _ => false,
+        });
+        if matched {
+            return Some((start, end));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ASCII: &str = "hello";
+    const CJK: &str = "日本語";
+    /// Woman, ZWJ, laptop: one grapheme.
+    const EMOJI_ZWJ: &str = "a\u{1F469}\u{200D}\u{1F4BB}b";
+    /// "e" + combining acute, twice.
+    const COMBINING: &str = "e\u{301}e\u{301}x";
+
+    fn graphemes(s: &str) -> Vec<&str> {
+        s.graphemes(true).collect()
+    }
+
+    #[test]
+    fn width_matrix() {
+        assert_eq!(width(ASCII), 5);
+        assert_eq!(width(CJK), 6);
+        assert_eq!(width(COMBINING), 3);
+        // The emoji cluster is at least one wide cell plus the two letters.
+        assert!(width(EMOJI_ZWJ) >= 4);
+    }
+
+    #[test]
+    fn truncate_matrix() {
+        assert_eq!(truncate(ASCII, 10), "hello");
+        assert_eq!(truncate(ASCII, 4), "hel…");
+        assert_eq!(truncate(CJK, 6), CJK);
+        assert_eq!(truncate(CJK, 5), "日本…");
+        assert_eq!(truncate(CJK, 4), "日…");
+        assert_eq!(truncate(COMBINING, 3), COMBINING);
+        assert_eq!(truncate(COMBINING, 2), "e\u{301}…");
+        assert_eq!(truncate(ASCII, 0), "");
+
+        let cut = truncate(EMOJI_ZWJ, width(EMOJI_ZWJ) - 1);
+        assert!(width(&cut) < width(EMOJI_ZWJ));
+        // Either the whole cluster survives or none of it does.
+        let kept = cut.trim_end_matches(ELLIPSIS);
+        assert!(
+            kept == "a" || kept == "a\u{1F469}\u{200D}\u{1F4BB}",
+            "{:?}",
+            kept
+        );
+    }
+
+    #[test]
+    fn truncate_middle_matrix() {
+        assert_eq!(truncate_middle("short.rs", 12), "short.rs");
+        assert_eq!(
+            truncate_middle("very_long_file_name.rs", 12),
+            "very…name.rs"
+        );
+        assert_eq!(truncate_middle("no_extension_here", 9), "no_e…here");
+        // Dotfiles have no extension to keep
+        assert_eq!(truncate_middle(".bash_history", 8), ".bas…ory");
+        // An extension wider than the room falls back to cutting the end
+        assert_eq!(truncate_middle("a.verylongextension", 6), "a.ver…");
+        assert_eq!(truncate_middle("日本語日本語.txt", 10), "日…語.txt");
+        assert_eq!(truncate_middle(ASCII, 0), "");
+        for max in 0..20 {
+            assert!(width(&truncate_middle("some_file_name.tar.gz", max)) <= max);
+        }
+    }
+
+    #[test]
+    fn slice_matrix() {
+        assert_eq!(slice_columns(ASCII, 1, 3), "ell");
+        assert_eq!(slice_columns(CJK, 2, 2), "本");
+        // Starting inside a wide glyph pads its visible half.
+        assert_eq!(slice_columns(CJK, 1, 4), " 本 ");
+        assert_eq!(width(&slice_columns(CJK, 1, 4)), 4);
+        assert_eq!(slice_columns(COMBINING, 1, 2), "e\u{301}x");
+        assert_eq!(slice_columns(ASCII, 10, 3), "");
+
+        let emoji_width = width(EMOJI_ZWJ) - 2;
+        assert_eq!(
+            slice_columns(EMOJI_ZWJ, 1, emoji_width),
+            "\u{1F469}\u{200D}\u{1F4BB}"
+        );
+        assert!(!slice_columns(EMOJI_ZWJ, 2, emoji_width).contains('\u{200D}'));
+    }
+
+    #[test]
+    fn cursor_advance_matrix() {
+        for s in [ASCII, CJK, EMOJI_ZWJ, COMBINING] {
+            let mut forward = vec![0];
+            let mut pos = 0;
+            while pos < s.len() {
+                pos = next_boundary(s, pos);
+                forward.push(pos);
+            }
+            assert_eq!(forward.len() - 1, graphemes(s).len(), "{:?}", s);
+
+            let mut backward = vec![s.len()];
+            let mut pos = s.len();
+            while pos > 0 {
+                pos = prev_boundary(s, pos);
+                backward.push(pos);
+            }
+            backward.reverse();
+            assert_eq!(forward, backward, "{:?}", s);
+        }
+        // Landing inside a cluster snaps back to its start.
+        assert_eq!(floor_boundary(EMOJI_ZWJ, 5), 1);
+        assert_eq!(floor_boundary(COMBINING, 1), 0);
+        assert_eq!(floor_boundary(CJK, 3), 3);
+        assert_eq!(floor_boundary(ASCII, 5), 5);
+        assert_eq!(
+            grapheme_at(EMOJI_ZWJ, 1),
+            Some("\u{1F469}\u{200D}\u{1F4BB}")
+        );
+        assert_eq!(grapheme_at(COMBINING, 0), Some("e\u{301}"));
+        assert_eq!(grapheme_at(ASCII, 5), None);
+    }
+
+    #[test]
+    fn column_mapping_matrix() {
+        assert_eq!(byte_at_column(ASCII, 2, 4), 2);
+        // Both halves of 本 map to its first byte.
+        assert_eq!(byte_at_column(CJK, 2, 4), 3);
+        assert_eq!(byte_at_column(CJK, 3, 4), 3);
+        assert_eq!(byte_at_column(CJK, 99, 4), CJK.len());
+        assert_eq!(byte_at_column(COMBINING, 1, 4), "e\u{301}".len());
+        assert_eq!(byte_at_column(EMOJI_ZWJ, 2, 4), 1);
+    }
+
+    #[test]
+    fn tab_expansion_matrix() {
+        assert_eq!(expand_tabs("a\tb", 0, 4), ("a   b".to_string(), 5));
+        assert_eq!(expand_tabs("\tx", 0, 8).0, format!("{}x", " ".repeat(8)));
+        // Continuing a line: the first stop is relative to `start_col`.
+        assert_eq!(expand_tabs("\t", 6, 4), ("  ".to_string(), 8));
+        assert_eq!(expand_tabs("plain", 3, 4), ("plain".to_string(), 8));
+
+        // 日本 fills columns 0..4, so the tab jumps a whole stop; 日 alone
+        // ends at 2 and the tab only pads to 4.
+        let (out, end) = expand_tabs("日本\tx", 0, 4);
+        assert_eq!(out, "日本    x");
+        assert_eq!(end, 9);
+        assert_eq!(expand_tabs("日\tx", 0, 4).0, "日  x");
+        // Combining marks take no cells before the tab.
+        assert_eq!(expand_tabs("e\u{301}\tx", 0, 4).0, "e\u{301}   x");
+        assert_eq!(
+            width(&expand_tabs("a\u{1F469}\u{200D}\u{1F4BB}\tb", 0, 8).0),
+            9
+        );
+    }
+
+    #[test]
+    fn tab_column_mapping_matrix() {
+        let line = "日\tx";
+        // The tab covers columns 2..4; x starts at 4.
+        assert_eq!(column_at_byte(line, "日".len(), 4), 2);
+        assert_eq!(column_at_byte(line, "日\t".len(), 4), 4);
+        assert_eq!(byte_at_column(line, 2, 4), "日".len());
+        assert_eq!(byte_at_column(line, 3, 4), "日".len());
+        assert_eq!(byte_at_column(line, 4, 4), "日\t".len());
+        assert_eq!(byte_at_column(line, 4, 8), "日".len());
+        assert_eq!(column_at_byte(line, line.len(), 8), 9);
+    }
+
+    #[test]
+    fn soft_hyphens_take_no_columns() {
+        let word = "hy\u{AD}phen\tx";
+        assert_eq!(width("hy\u{AD}phen"), 6);
+        // Dropped from what is drawn; the tab stop ignores it
+        assert_eq!(expand_tabs(word, 0, 4), ("hyphen  x".to_string(), 9));
+        assert_eq!(slice_columns("hy\u{AD}phen", 1, 3), "yph");
+        // The column after it is where the next letter starts
+        assert_eq!(column_at_byte(word, "hy\u{AD}".len(), 4), 2);
+        assert_eq!(byte_at_column(word, 2, 4), "hy\u{AD}".len());
+        assert_eq!(truncate("hy\u{AD}phen", 6), "hy\u{AD}phen");
+    }
+
+    #[test]
+    fn find_ignore_case_matches() {
+        assert_eq!(find_ignore_case("Toggle Blame", "blame"), Some((7, 12)));
+        assert_eq!(find_ignore_case("Ctrl+T", "ctrl+t"), Some((0, 6)));
+        assert_eq!(find_ignore_case("j / ↓", "↓"), Some((4, 7)));
+        assert_eq!(find_ignore_case("abc", "abcd"), None);
+        assert_eq!(find_ignore_case("abc", ""), None);
+    }
+}
@@ -12,7 +12,7 @@ use crate::config::{ThemeColorsConfig, ThemeConfig};
 /// All runtime colors used in the UI.
 ///
 /// Constructed from a config-level `ThemeConfig` via `resolve_theme()`.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ThemeColors {
     // Tree panel
     pub tree_bg: Color,
@@ -22,6 +22,11 @@ pub struct ThemeColors {
     pub tree_dir_fg: Color,
     pub tree_file_fg: Color,
     pub tree_hidden_fg: Color,
+    pub tree_symlink_fg: Color,
+    /// Symlinks whose target is missing.
+    pub tree_broken_link_fg: Color,
+    /// FIFOs, sockets and device nodes.
+    pub tree_special_fg: Color,
 
     // Preview panel
     pub preview_bg: Color,
@@ -58,6 +63,7 @@ pub struct ThemeColors {
     pub editor_find_match_bg: Color,
     pub editor_find_bar_bg: Color,
     pub editor_selection_bg: Color,
+    pub editor_trailing_ws_bg: Color,
 }
 
 // ── Built-in palettes ────────────────────────────────────────────────────────
@@ -73,6 +79,9 @@ pub fn dark_theme() -> ThemeColors {
         tree_dir_fg: Color::Rgb(137, 180, 250),   // #89b4fa (blue)
         tree_file_fg: Color::Rgb(205, 214, 244),  // #cdd6f4
         tree_hidden_fg: Color::Rgb(108, 112, 134), // #6c7086 (overlay0)
+        tree_symlink_fg: Color::Rgb(148, 226, 213), // #94e2d5 (teal)
+        tree_broken_link_fg: Color::Rgb(235, 160, 172), // #eba0ac (maroon)
+        tree_special_fg: Color::Rgb(250, 179, 135), // #fab387 (peach)
 
         // Preview — same base
         preview_bg: Color::Reset,
@@ -109,6 +118,7 @@ pub fn dark_theme() -> ThemeColors {
         editor_find_match_bg: Color::Rgb(249, 226, 175), // #f9e2af (yellow)
         editor_find_bar_bg: Color::Rgb(49, 50, 68), // #313244 (surface0)
         editor_selection_bg: Color::Rgb(68, 71, 90), // #44475a (selection blue)
+        editor_trailing_ws_bg: Color::Rgb(84, 52, 64), // #543440 (muted red)
     }
 }
 
@@ -123,6 +133,9 @@ pub fn light_theme() -> ThemeColors {
         tree_dir_fg: Color::Rgb(30, 102, 245), // #1e66f5 (blue)
         tree_file_fg: Color::Rgb(76, 79, 105),
         tree_hidden_fg: Color::Rgb(156, 160, 176), // #9ca0b0 (overlay0)
+        tree_symlink_fg: Color::Rgb(23, 146, 153), // #179299 (teal)
+        tree_broken_link_fg: Color::Rgb(230, 69, 83), // #e64553 (maroon)
+        tree_special_fg: Color::Rgb(254, 100, 11), // #fe640b (peach)
 
         // Preview
         preview_bg: Color::Reset,
@@ -159,6 +172,7 @@ pub fn light_theme() -> ThemeColors {
         editor_find_match_bg: Color::Rgb(223, 142, 29), // #df8e1d (yellow)
         editor_find_bar_bg: Color::Rgb(230, 233, 239), // #e6e9ef
         editor_selection_bg: Color::Rgb(172, 209, 233), // #acd1e9 (selection blue)
+        editor_trailing_ws_bg: Color::Rgb(242, 213, 218), // #f2d5da (muted red)
     }
 }
 
@@ -168,7 +182,8 @@ pub fn light_theme() -> ThemeColors {
 /// Returns `None` for malformed input.
 pub fn parse_hex_color(hex: &str) -> Option<Color> {
     let hex = hex.strip_prefix('#').unwrap_or(hex);
-    if hex.len() != 6 {
+    // Byte slicing below needs ASCII
+    if hex.len() != 6 || !hex.is_ascii() {
         return None;
     }
     let r = u8::from_str_radix(&hex[0..2], 16).ok()?;
@@ -184,6 +199,51 @@ fn parse_or(hex_opt: Option<&str>, fallback: Color) -> Color {
 
 // ── Theme resolution ─────────────────────────────────────────────────────────
 
+/// Why `config` does not resolve as written: an unknown scheme or a custom
+/// color that isn't `#rrggbb`. [`resolve_theme`] falls back quietly in
+/// both cases, so this is for checking a config before it is used.
+pub fn check_theme(config: &ThemeConfig) -> Result<(), String> {
+    let scheme = config.scheme.as_deref().unwrap_or("dark");
+    if !matches!(scheme, "dark" | "light" | "custom") {
+        return Err(format!(
+            "unknown theme scheme '{}' (dark, light or custom)",
+            scheme
+        ));
+    }
+    let custom = match &config.custom {
+        Some(custom) => custom,
+        None => return Ok(()),
+    };
+    let colors = [
+        ("tree_bg", &custom.tree_bg),
+        ("tree_fg", &custom.tree_fg),
+        ("tree_selected_bg", &custom.tree_selected_bg),
+        ("tree_selected_fg", &custom.tree_selected_fg),
+        ("tree_dir_fg", &custom.tree_dir_fg),
+        ("tree_file_fg", &custom.tree_file_fg),
+        ("tree_hidden_fg", &custom.tree_hidden_fg),
+        ("tree_symlink_fg", &custom.tree_symlink_fg),
+        ("tree_broken_link_fg", &custom.tree_broken_link_fg),
+        ("tree_special_fg", &custom.tree_special_fg),
+        ("preview_bg", &custom.preview_bg),
+        ("preview_fg", &custom.preview_fg),
+        ("preview_line_nr_fg", &custom.preview_line_nr_fg),
+        ("status_bg", &custom.status_bg),
+        ("status_fg", &custom.status_fg),
+        ("border_fg", &custom.border_fg),
+        ("dialog_bg", &custom.dialog_bg),
+        ("dialog_border_fg", &custom.dialog_border_fg),
+    ];
+    for (name, value) in colors {
+        if let Some(value) = value {
+            if parse_hex_color(value).is_none() {
+                return Err(format!("theme color {} = '{}' is not #rrggbb", name, value));
+            }
+        }
+    }
+    Ok(())
+}
+
 /// Resolve the final `ThemeColors` from config.
 ///
 /// - `"dark"` (default): dark Catppuccin palette
@@ -227,6 +287,15 @@ fn apply_custom_colors(theme: &mut ThemeColors, custom: &ThemeColorsConfig) {
     if let Some(ref c) = custom.tree_hidden_fg {
         theme.tree_hidden_fg = parse_or(Some(c), theme.tree_hidden_fg);
     }
+    if let Some(ref c) = custom.tree_symlink_fg {
+        theme.tree_symlink_fg = parse_or(Some(c), theme.tree_symlink_fg);
+    }
+    if let Some(ref c) = custom.tree_broken_link_fg {
+        theme.tree_broken_link_fg = parse_or(Some(c), theme.tree_broken_link_fg);
+    }
+    if let Some(ref c) = custom.tree_special_fg {
+        theme.tree_special_fg = parse_or(Some(c), theme.tree_special_fg);
+    }
     if let Some(ref c) = custom.preview_bg {
         theme.preview_bg = parse_or(Some(c), theme.preview_bg);
     }
@@ -314,6 +383,7 @@ mod tests {
             custom: Some(ThemeColorsConfig {
                 tree_bg: Some("#1a1b26".to_string()),
                 tree_fg: Some("#c0caf5".to_string()),
+                tree_broken_link_fg: Some("#ff0000".to_string()),
                 ..Default::default()
             }),
         };
@@ -321,6 +391,7 @@ mod tests {
         // Custom values applied
         assert_eq!(theme.tree_bg, Color::Rgb(26, 27, 38));
         assert_eq!(theme.tree_fg, Color::Rgb(192, 202, 245));
+        assert_eq!(theme.tree_broken_link_fg, Color::Rgb(255, 0, 0));
         // Non-custom values fall back to dark theme
         assert_eq!(theme.tree_dir_fg, Color::Rgb(137, 180, 250));
     }
@@ -339,6 +410,25 @@ mod tests {
         assert_eq!(theme.tree_bg, Color::Reset);
     }
 
+    #[test]
+    fn test_check_theme_names_the_bad_value() {
+        let mut config = ThemeConfig {
+            scheme: Some("custom".to_string()),
+            custom: Some(ThemeColorsConfig {
+                tree_bg: Some("#1a1b26".to_string()),
+                dialog_bg: Some("#ééé".to_string()),
+                ..Default::default()
+            }),
+        };
+        let err = check_theme(&config).unwrap_err();
+        assert!(err.contains("dialog_bg"), "{}", err);
+
+        config.custom = None;
+        assert_eq!(check_theme(&config), Ok(()));
+        config.scheme = Some("neon".to_string());
+        assert!(check_theme(&config).unwrap_err().contains("neon"));
+    }
+
     #[test]
     fn test_unknown_scheme_falls_back_to_dark() {
         let config = ThemeConfig {
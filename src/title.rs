@@ -0,0 +1,136 @@
+//! Outer terminal window title.
+//!
+//! With `general.set_title` on, the title follows the selection through
+//! `general.title_template` (`{path}`, `{root}`, `{mode}`). Holding a key
+//! down moves the selection every frame, so a new title is only written
+//! once it has stayed the same for [`DEBOUNCE`]. The escapes themselves are
+//! written by `Tui`, which saves the terminal's own title first and puts it
+//! back on exit.
+//!
+//! Titles set by programs in the embedded terminal never get here: its
+//! emulator swallows OSC 0/2 instead of passing them through.
+
+use std::time::{Duration, Instant};
+
+/// How long a title has to stay the same before it is written.
+pub const DEBOUNCE: Duration = Duration::from_millis(150);
+
+/// Fill in `template`. Control characters (a newline or ESC in a file
+/// name) are dropped, since they would end or corrupt the escape sequence.
+pub fn render(template: &str, path: &str, root: &str, mode: &str) -> String {
+    template
+        .replace("{path}", path)
+        .replace("{root}", root)
+        .replace("{mode}", mode)
+        .chars()
+        .filter(|c| !c.is_control())
+        .collect()
+}
+
+/// Debounces title writes.
+#[derive(Debug, Default)]
+pub struct TitleState {
+    /// Title last handed to the terminal.
+    written: Option<String>,
+    /// A different title waiting to settle, and when it was first seen.
+    pending: Option<(String, Instant)>,
+}
+
+impl TitleState {
+    /// Offer the title the app wants at `now`, called every frame. Returns
+    /// the title to write, if one is due: the first title right away, later
+    /// ones once unchanged for [`DEBOUNCE`].
+    pub fn poll(&mut self, wanted: String, now: Instant) -> Option<String> {
+        if self.written.as_deref() == Some(wanted.as_str()) {
+            self.pending = None;
+            return None;
+        }
+        if self.written.is_none() {
+            self.written = Some(wanted.clone());
+            return Some(wanted);
+        }
+        match &self.pending {
+            Some((title, since)) if *title == wanted => {
+                if now.duration_since(*since) < DEBOUNCE {
+                    return None;
+                }
+                self.pending = None;
+                self.written = Some(wanted.clone());
+                Some(wanted)
+            }
+            _ => {
+                self.pending = Some((wanted, now));
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn template_fills_every_placeholder() {
+        assert_eq!(
+            render("fm — {path}", "~/src/main.rs", "~/src", "normal"),
+            "fm — ~/src/main.rs"
+        );
+        assert_eq!(
+            render("{mode}: {path} in {root}", "~/a", "~", "edit"),
+            "edit: ~/a in ~"
+        );
+        assert_eq!(render("static", "~/a", "~", "normal"), "static");
+    }
+
+    #[test]
+    fn control_characters_are_dropped() {
+        assert_eq!(
+            render("{path}", "~/evil\u{1b}]0;pwned\u{7}\nname", "~", ""),
+            "~/evil]0;pwnedname"
+        );
+    }
+
+    #[test]
+    fn first_title_is_written_at_once() {
+        let mut state = TitleState::default();
+        let now = Instant::now();
+        assert_eq!(state.poll("a".into(), now), Some("a".into()));
+        assert_eq!(state.poll("a".into(), now + DEBOUNCE * 2), None);
+    }
+
+    #[test]
+    fn rapid_navigation_writes_once_settled() {
+        let mut state = TitleState::default();
+        let start = Instant::now();
+        let mut writes = vec![state.poll("fm — ~/0".into(), start)];
+
+        // Holding `j`: a new selection every 30ms for 100 rows, polled at
+        // ~60 fps in between.
+        let mut now = start;
+        for row in 1..=100 {
+            for _ in 0..2 {
+                now += Duration::from_millis(15);
+                writes.push(state.poll(format!("fm — ~/{}", row), now));
+            }
+        }
+        // Then idle frames until well past the debounce
+        for _ in 0..20 {
+            now += Duration::from_millis(16);
+            writes.push(state.poll("fm — ~/100".into(), now));
+        }
+
+        let written: Vec<String> = writes.into_iter().flatten().collect();
+        assert_eq!(written, vec!["fm — ~/0", "fm — ~/100"]);
+    }
+
+    #[test]
+    fn returning_to_the_written_title_cancels_the_pending_one() {
+        let mut state = TitleState::default();
+        let now = Instant::now();
+        state.poll("a".into(), now);
+        assert_eq!(state.poll("b".into(), now), None);
+        assert_eq!(state.poll("a".into(), now + DEBOUNCE / 2), None);
+        assert_eq!(state.poll("a".into(), now + DEBOUNCE * 2), None);
+    }
+}
@@ -1,9 +1,9 @@
-use std::io::{self, Stdout};
+use std::io::{self, Stdout, Write};
 
 use crossterm::{
-    event::{DisableMouseCapture, EnableMouseCapture},
-    execute,
-    terminal::{self, EnterAlternateScreen, LeaveAlternateScreen},
+    event::{DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture},
+    execute, queue,
+    terminal::{self, EnterAlternateScreen, LeaveAlternateScreen, SetTitle},
 };
 use ratatui::{backend::CrosstermBackend, Terminal};
 
@@ -13,15 +13,17 @@ use crate::error::Result;
 pub struct Tui {
     terminal: Terminal<CrosstermBackend<Stdout>>,
     mouse_enabled: bool,
+    /// The terminal's own title was pushed by `save_title`.
+    title_saved: bool,
 }
 
 impl Tui {
-    /// Initialize the terminal: enter alternate screen and enable raw mode.
-    /// Optionally enables mouse capture.
+    /// Initialize the terminal: enter alternate screen, enable raw mode and
+    /// bracketed paste. Optionally enables mouse capture.
     pub fn new(enable_mouse: bool) -> Result<Self> {
         let mut stdout = io::stdout();
         terminal::enable_raw_mode()?;
-        execute!(stdout, EnterAlternateScreen)?;
+        execute!(stdout, EnterAlternateScreen, EnableBracketedPaste)?;
         if enable_mouse {
             execute!(stdout, EnableMouseCapture)?;
         }
@@ -30,33 +32,130 @@ impl Tui {
         Ok(Self {
             terminal,
             mouse_enabled: enable_mouse,
+            title_saved: false,
         })
     }
 
     /// Restore the terminal to its original state.
     pub fn restore(&mut self) -> Result<()> {
+        self.suspend()?;
+        if self.title_saved {
+            pop_title(self.terminal.backend_mut())?;
+            self.title_saved = false;
+        }
+        Ok(())
+    }
+
+    /// Hand the terminal back to the shell for a foreground program: leave
+    /// the alternate screen and raw mode. Undone by `resume`.
+    pub fn suspend(&mut self) -> Result<()> {
         if self.mouse_enabled {
             execute!(self.terminal.backend_mut(), DisableMouseCapture)?;
         }
         terminal::disable_raw_mode()?;
-        execute!(self.terminal.backend_mut(), LeaveAlternateScreen)?;
+        execute!(
+            self.terminal.backend_mut(),
+            DisableBracketedPaste,
+            LeaveAlternateScreen
+        )?;
         self.terminal.show_cursor()?;
         Ok(())
     }
 
+    /// Take the terminal back after `suspend`, redrawing from scratch on
+    /// the next frame.
+    pub fn resume(&mut self) -> Result<()> {
+        terminal::enable_raw_mode()?;
+        execute!(
+            self.terminal.backend_mut(),
+            EnterAlternateScreen,
+            EnableBracketedPaste
+        )?;
+        if self.mouse_enabled {
+            execute!(self.terminal.backend_mut(), EnableMouseCapture)?;
+        }
+        self.terminal.clear()?;
+        Ok(())
+    }
+
+    /// Save the terminal's current window title so `restore` can put it
+    /// back. Call before the first `set_title`.
+    pub fn save_title(&mut self) -> Result<()> {
+        push_title(self.terminal.backend_mut())?;
+        self.title_saved = true;
+        Ok(())
+    }
+
+    /// Set the terminal's window title.
+    pub fn set_title(&mut self, title: &str) -> Result<()> {
+        write_title(self.terminal.backend_mut(), title)?;
+        Ok(())
+    }
+
+    /// Write a raw escape sequence (OSC 52 clipboard copies) between
+    /// frames.
+    pub fn write_escape(&mut self, escape: &str) -> Result<()> {
+        let out = self.terminal.backend_mut();
+        out.write_all(escape.as_bytes())?;
+        out.flush()?;
+        Ok(())
+    }
+
     /// Get a mutable reference to the underlying terminal for drawing.
     pub fn terminal_mut(&mut self) -> &mut Terminal<CrosstermBackend<Stdout>> {
         &mut self.terminal
     }
 }
 
+/// Write the OSC 0 sequence setting the window (and icon) title.
+fn write_title(out: &mut impl Write, title: &str) -> io::Result<()> {
+    queue!(out, SetTitle(title))?;
+    out.flush()
+}
+
+/// Push the current title onto the terminal's title stack (XTWINOPS 22).
+/// Terminals without a stack ignore it.
+fn push_title(out: &mut impl Write) -> io::Result<()> {
+    out.write_all(b"\x1b[22;0t")?;
+    out.flush()
+}
+
+/// Pop the title saved by [`push_title`] (XTWINOPS 23).
+fn pop_title(out: &mut impl Write) -> io::Result<()> {
+    out.write_all(b"\x1b[23;0t")?;
+    out.flush()
+}
+
 /// Install a panic hook that restores the terminal before printing panic info.
 pub fn install_panic_hook() {
     let original_hook = std::panic::take_hook();
     std::panic::set_hook(Box::new(move |panic_info| {
         let _ = terminal::disable_raw_mode();
-        let _ = execute!(io::stdout(), DisableMouseCapture);
+        let _ = execute!(io::stdout(), DisableMouseCapture, DisableBracketedPaste);
         let _ = execute!(io::stdout(), LeaveAlternateScreen);
+        // Harmless when no title was pushed: the stack is just empty
+        let _ = pop_title(&mut io::stdout());
         original_hook(panic_info);
     }));
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn title_is_written_as_osc_0() {
+        let mut out = Vec::new();
+        write_title(&mut out, "fm — ~/src").unwrap();
+        assert_eq!(out, "\x1b]0;fm — ~/src\x07".as_bytes());
+    }
+
+    #[test]
+    fn title_is_saved_and_restored_with_the_stack() {
+        let mut out = Vec::new();
+        push_title(&mut out).unwrap();
+        write_title(&mut out, "x").unwrap();
+        pop_title(&mut out).unwrap();
+        assert_eq!(out, b"\x1b[22;0t\x1b]0;x\x07\x1b[23;0t");
+    }
+}
@@ -1,21 +1,37 @@
 use ratatui::{
-    layout::{Constraint, Direction, Layout},
+    layout::{Constraint, Direction, Layout, Rect},
     style::Style,
+    text::Line,
     widgets::{Block, Borders},
     Frame,
 };
 
 use crate::app::{App, AppMode, FocusedPanel};
+use crate::components::bookmarks::BookmarksWidget;
+use crate::components::debug::DebugOverlay;
 use crate::components::dialog::DialogWidget;
+#[cfg(feature = "editor")]
 use crate::components::editor::EditorWidget;
+use crate::components::focus;
+use crate::components::grep::GrepWidget;
 use crate::components::help::HelpOverlay;
+use crate::components::places::PlacesWidget;
 use crate::components::preview::PreviewWidget;
 use crate::components::search::SearchWidget;
 use crate::components::search_action::SearchActionWidget;
+use crate::components::send_to::SendToWidget;
 use crate::components::status_bar::StatusBarWidget;
+use crate::components::tab_bar::tab_bar;
 use crate::components::terminal::TerminalWidget;
 use crate::components::tree::TreeWidget;
+use crate::components::watch_indicator;
 use crate::fs::tree::NodeType;
+use crate::path_display;
+use crate::safe_mode;
+use crate::theme::ThemeColors;
+#[cfg(feature = "editor")]
+use crate::whitespace;
+use crate::wrap::WrapStyle;
 
 /// Render the application UI.
 pub fn render(app: &mut App, frame: &mut Frame) {
@@ -25,6 +41,17 @@ pub fn render(app: &mut App, frame: &mut Frame) {
     let area = frame.area();
     let theme = app.theme_colors.clone();
 
+    // Tab bar on the top row while more than one tab is open; overlays
+    // still center on the whole screen
+    let mut body = area;
+    if app.tabs.len() > 1 && area.height > 1 {
+        let names = app.tabs.names(&app.tree_state.root.name);
+        let bar = tab_bar(&names, app.tabs.active(), area.width as usize, &theme);
+        frame.render_widget(bar, Rect { height: 1, ..area });
+        body.y += 1;
+        body.height -= 1;
+    }
+
     // Determine vertical layout:
     // If terminal is visible: [main_area, terminal_area, status_bar]
     // If terminal is hidden:  [main_area, status_bar]
@@ -42,12 +69,12 @@ pub fn render(app: &mut App, frame: &mut Frame) {
                 Constraint::Length(term_rows), // terminal panel
                 Constraint::Length(1),         // status bar
             ])
-            .split(area)
+            .split(body)
     } else {
         Layout::default()
             .direction(Direction::Vertical)
             .constraints([Constraint::Min(3), Constraint::Length(1)])
-            .split(area)
+            .split(body)
     };
 
     let main_area = chunks[0];
@@ -60,18 +87,20 @@ pub fn render(app: &mut App, frame: &mut Frame) {
     // Store terminal area for mouse mapping and resize
     app.terminal_area = terminal_area_rect;
 
-    // Split main area: tree (40%) + preview (60%)
-    let panels = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
-        .split(main_area);
+    // Split main area: tree (40%) + preview (60%), subject to width caps
+    let panels = crate::layout::split_main(
+        main_area,
+        app.config.tree_max_width(),
+        app.config.preview_max_content_width(),
+    );
 
-    let tree_area = panels[0];
-    let preview_area = panels[1];
+    let tree_area = panels.tree;
+    let preview_area = panels.preview;
 
     // Store areas for mouse click mapping
     app.tree_area = tree_area;
     app.preview_area = preview_area;
+    app.areas_generation = app.layout_generation;
     app.clamp_preview_scroll();
 
     // Determine border styles based on focus (using theme colors)
@@ -83,58 +112,86 @@ pub fn render(app: &mut App, frame: &mut Frame) {
         FocusedPanel::Preview => (unfocused_border, focused_border, unfocused_border),
         FocusedPanel::Terminal => (unfocused_border, unfocused_border, focused_border),
     };
+    // Focus spelled out in the titles too, for screen readers
+    let markers = app.config.focus_markers();
+    let panel_label = |panel| focus::panel_label(panel, app.focused_panel, markers);
+    let (tree_label, preview_label, terminal_label) = (
+        panel_label(FocusedPanel::Tree),
+        panel_label(FocusedPanel::Preview),
+        panel_label(FocusedPanel::Terminal),
+    );
 
     // Update scroll offset to keep selected item visible
     let visible_height = tree_area.height.saturating_sub(2) as usize; // account for border
     app.tree_state.update_scroll(visible_height);
-
+    app.tree_rows_drawn = app
+        .tree_state
+        .flat_items
+        .len()
+        .saturating_sub(app.tree_state.scroll_offset)
+        .min(visible_height);
+
+    // Flag a per-directory sort override on the selected directory
+    let mut title_label = match app.selected_sort_override() {
+        Some(note) => format!(
+            "{} {} [sort {}]",
+            tree_label, app.tree_state.root.name, note
+        ),
+        None => format!("{} {}", tree_label, app.tree_state.root.name),
+    };
+    // The filter class chip, with the query it combines with
+    if app.tree_state.filter_class.is_some() {
+        title_label.push(' ');
+        title_label.push_str(&app.tree_state.filter_label());
+    }
+    // Rows in view are still waiting for the stats their sort needs
+    if app.sort_is_provisional() {
+        title_label.push_str(" [order provisional]");
+    }
+    // A sort, filter or refresh still running a slice per frame
+    if let Some(kind) = app.work.busy() {
+        title_label.push_str(&format!(" [{}…]", kind.label()));
+    }
+    // Ends in the watcher state glyph, which a click explains
+    let tree_title = watch_indicator::tree_title(&title_label, app.watcher_state(), &theme);
+    app.watcher_glyph_area = Rect::new(
+        tree_area.x + 1 + tree_title.glyph_offset,
+        tree_area.y,
+        tree_title.glyph_width,
+        1,
+    )
+    .intersection(tree_area);
     let tree_block = Block::default()
-        .title(format!(" {} ", app.tree_state.root.name))
+        .title(tree_title.line)
         .borders(Borders::ALL)
         .border_style(tree_border_style);
 
-    let tree_widget =
-        TreeWidget::new(&app.tree_state, &theme, app.config.use_icons()).block(tree_block);
+    let tree_widget = TreeWidget::new(&app.tree_state, &theme, app.config.use_icons())
+        .block(tree_block)
+        .flash(&app.flash)
+        .clipboard(&app.clipboard)
+        .dir_sizes(&app.dir_sizes, app.config.apparent_sizes())
+        .git_status(&app.git_status.statuses)
+        .min_name_width(app.config.tree_min_name_width())
+        .cache(&mut app.tree_rows);
     frame.render_widget(tree_widget, tree_area);
+    // A compacted or shortened selection gets its full path in the status bar
+    let selection_shortened = app.tree_rows.is_shortened(app.tree_state.selected_index);
 
     // Render preview panel (or editor if in edit mode)
-    if app.mode == AppMode::Edit && app.editor_state.is_some() {
-        // Edit mode: render editor widget
-        let dirty = app.editor_state.as_ref().is_some_and(|e| e.modified);
-        let editor_title = match &app.preview_state.current_path {
-            Some(path) => {
-                let name = path
-                    .file_name()
-                    .map(|n| n.to_string_lossy().to_string())
-                    .unwrap_or_else(|| "Editor".to_string());
-                if dirty {
-                    format!(" {} ● [EDIT] ", name)
-                } else {
-                    format!(" {} [EDIT] ", name)
-                }
-            }
-            None => " Editor ".to_string(),
-        };
-
-        let editor_block = Block::default()
-            .title(editor_title)
-            .borders(Borders::ALL)
-            .border_style(preview_border_style);
-
-        // Update visible_height before rendering
-        if let Some(ref mut editor) = app.editor_state {
-            let inner_height = preview_area.height.saturating_sub(2) as usize;
-            editor.visible_height = inner_height;
-            editor.ensure_cursor_visible();
-        }
-
-        if let Some(ref editor) = app.editor_state {
-            let editor_widget =
-                EditorWidget::new(editor, &theme, &app.syntax_set, &app.syntax_theme)
-                    .block(editor_block);
-            frame.render_widget(editor_widget, preview_area);
-        }
-    } else {
+    // Help opened from the editor keeps the editor underneath
+    let editing = app.mode == AppMode::Edit
+        || (app.mode == AppMode::Help && app.help_state.return_mode == AppMode::Edit);
+    let drew_editor = editing
+        && draw_editor(
+            app,
+            frame,
+            preview_area,
+            &preview_label,
+            preview_border_style,
+            &theme,
+        );
+    if !drew_editor {
         // Normal preview mode
         let preview_title = match &app.preview_state.current_path {
             Some(path) => {
@@ -154,29 +211,64 @@ pub fn render(app: &mut App, frame: &mut Frame) {
                                 .unwrap_or(false)
                         })
                         .count();
-                    format!(" Notebook: {} cells ", cell_count)
+                    format!(" {} Notebook: {} cells ", preview_label, cell_count)
                 } else {
-                    format!(" {} ", name)
+                    format!(" {} {} ", preview_label, name)
                 }
             }
-            None => " Preview ".to_string(),
+            None => format!(" {} ", preview_label),
         };
 
-        let preview_block = Block::default()
-            .title(preview_title)
-            .borders(Borders::ALL)
-            .border_style(preview_border_style);
+        let preview_title = match app.preview_file_position() {
+            Some(position) => format!("{}{} ", preview_title, position),
+            None => preview_title,
+        };
+        let preview_title = match app.blame_label() {
+            Some(label) => format!("{}[{}] ", preview_title, label),
+            None => preview_title,
+        };
+        let preview_title = match app.preview_state.tab_width {
+            Some(width) => format!("{}[tab {}] ", preview_title, width),
+            None => preview_title,
+        };
+
+        let preview_note = app
+            .preview_state
+            .final_newline
+            .and_then(|ending| ending.note());
+        let preview_block = with_footer(
+            Block::default()
+                .title(preview_title)
+                .borders(Borders::ALL)
+                .border_style(preview_border_style),
+            preview_note,
+            &theme,
+        );
 
-        let preview_widget = PreviewWidget::new(&app.preview_state, &theme).block(preview_block);
+        app.sync_preview_search();
+        let mut preview_widget = PreviewWidget::new(&app.preview_state, &theme)
+            .block(preview_block)
+            .search(&app.preview_search)
+            .wrap(WrapStyle {
+                marker: app.config.wrap_marker(),
+                hang_indent: app.config.wrap_hang_indent(),
+            });
+        if let Some(gutter) = app.blame_gutter() {
+            preview_widget = preview_widget.blame(gutter);
+        }
+        if let Some((row, step)) = app.line_flash_step() {
+            preview_widget = preview_widget.highlight(row, step);
+        }
         frame.render_widget(preview_widget, preview_area);
     }
 
     // Render terminal panel if visible
     if terminal_visible {
-        let terminal_title = if app.terminal_state.exited {
-            " Terminal [exited] ".to_string()
-        } else {
-            " Terminal ".to_string()
+        let terminal_title = match (&app.terminal_state.title, app.terminal_state.exited) {
+            (Some(title), true) => format!(" {} {} [exited] ", terminal_label, title),
+            (Some(title), false) => format!(" {} {} ", terminal_label, title),
+            (None, true) => format!(" {} [exited] ", terminal_label),
+            (None, false) => format!(" {} ", terminal_label),
         };
 
         let terminal_block = Block::default()
@@ -212,7 +304,7 @@ pub fn render(app: &mut App, frame: &mut Frame) {
     let selected_item = app.tree_state.flat_items.get(app.tree_state.selected_index);
 
     let path_str = selected_item
-        .map(|item| item.path.to_string_lossy().to_string())
+        .map(|item| app.show_path(&item.path))
         .unwrap_or_default();
 
     let file_info = selected_item
@@ -230,6 +322,12 @@ pub fn render(app: &mut App, frame: &mut Frame) {
             }
             NodeType::File => "File".to_string(),
             NodeType::Symlink => "Symlink".to_string(),
+            NodeType::LoadMore if item.is_depth_limit() => {
+                format!(
+                    "Depth limit reached (tree.max_depth = {})",
+                    app.tree_state.max_depth
+                )
+            }
             NodeType::LoadMore => {
                 if let Some(remaining) = item.load_more_remaining {
                     format!("Load more... (~{} remaining)", remaining)
@@ -241,6 +339,17 @@ pub fn render(app: &mut App, frame: &mut Frame) {
         .unwrap_or_default();
 
     let mut status_widget = StatusBarWidget::new(&path_str, &file_info, &theme);
+    if selection_shortened {
+        status_widget = status_widget.full_path();
+    }
+    let state_label =
+        focus::state_label(app.focused_panel, &app.mode, app.config.state_separator());
+    if app.config.announce_state() {
+        status_widget = status_widget.state(&state_label);
+    }
+    if app.safe_mode {
+        status_widget = status_widget.badge(safe_mode::BADGE);
+    }
 
     // Show clipboard info if clipboard has content
     let clipboard_info_str;
@@ -251,18 +360,26 @@ pub fn render(app: &mut App, frame: &mut Frame) {
             Some(ClipboardOp::Cut) => "✂",
             None => "",
         };
-        clipboard_info_str = format!(
-            "{} {} item{}",
-            icon,
-            app.clipboard.len(),
-            if app.clipboard.len() == 1 { "" } else { "s" }
-        );
+        clipboard_info_str = match app.selected_clipboard_note() {
+            Some(note) => format!("{} {}", icon, note),
+            None => format!(
+                "{} {} item{}",
+                icon,
+                app.clipboard.len(),
+                if app.clipboard.len() == 1 { "" } else { "s" }
+            ),
+        };
         status_widget = status_widget.clipboard_info(&clipboard_info_str);
     }
 
     // Show watcher status indicator
-    let watcher_indicator = if app.watcher_active {
-        "👁".to_string()
+    let watcher_indicator = if let Some(note) = app.deferred_refresh_note() {
+        format!("👁 {}", note)
+    } else if app.watcher_active {
+        match app.watcher.watch_count() {
+            Some(count) => format!("👁 {}", count),
+            None => "👁".to_string(),
+        }
     } else {
         "⏸".to_string()
     };
@@ -270,14 +387,21 @@ pub fn render(app: &mut App, frame: &mut Frame) {
 
     // Show filter query in status bar when filtering
     let filter_display;
-    if app.mode == AppMode::Filter || app.tree_state.is_filtering {
-        filter_display = format!("Filter: {}_", app.tree_state.filter_query);
+    let count_display;
+    if let Some(count) = app.pending_count {
+        count_display = format!("Count: {}", count);
+        status_widget = status_widget.status_message(&count_display, false);
+    } else if app.mode == AppMode::Filter || app.tree_state.is_filtering {
+        filter_display = match &app.tree_state.filter_class {
+            Some(class) => format!("Filter: [{}] {}_", class.name, app.tree_state.filter_query),
+            None => format!("Filter: {}_", app.tree_state.filter_query),
+        };
         status_widget = status_widget.status_message(&filter_display, false);
     } else if let Some((ref msg, _)) = app.status_message {
         let is_error = msg.starts_with("Error");
         status_widget = status_widget.status_message(msg, is_error);
     }
-    frame.render_widget(status_widget, status_area);
+    frame.render_widget(status_widget.cache(&mut app.status_bar), status_area);
 
     // Render dialog overlay on top if in dialog mode
     if matches!(app.mode, AppMode::Dialog(_)) {
@@ -287,10 +411,17 @@ pub fn render(app: &mut App, frame: &mut Frame) {
 
     // Render search overlay on top if in search mode
     if app.mode == AppMode::Search {
-        let search_widget = SearchWidget::new(&app.search_state, &theme);
+        let prefix = path_display::prefix(app.path_style, &app.tree_state.root.path, &app.homes);
+        let search_widget = SearchWidget::new(&app.search_state, &theme).path_prefix(&prefix);
         frame.render_widget(search_widget, area);
     }
 
+    // Render content search overlay on top if in grep mode
+    if app.mode == AppMode::Grep {
+        let grep_widget = GrepWidget::new(&app.grep_state, &app.tree_state.root.path, &theme);
+        frame.render_widget(grep_widget, area);
+    }
+
     // Render search action overlay on top if in search action mode
     if app.mode == AppMode::SearchAction {
         if let Some(ref state) = app.search_action_state {
@@ -299,9 +430,111 @@ pub fn render(app: &mut App, frame: &mut Frame) {
         }
     }
 
+    // Render send-to overlay on top if in send-to mode
+    if app.mode == AppMode::SendTo {
+        let send_to_widget = SendToWidget::new(&app.send_to_state, &theme);
+        frame.render_widget(send_to_widget, area);
+    }
+
+    // Render places overlay on top if in places mode
+    if app.mode == AppMode::Places {
+        let places_widget = PlacesWidget::new(&app.places_state, &theme);
+        frame.render_widget(places_widget, area);
+    }
+
+    // Render bookmarks overlay on top if in bookmarks mode
+    if app.mode == AppMode::Bookmarks {
+        let bookmarks_widget = BookmarksWidget::new(&app.bookmarks_state, &theme);
+        frame.render_widget(bookmarks_widget, area);
+    }
+
     // Render help overlay on top if in help mode
     if app.mode == AppMode::Help {
-        let help_widget = HelpOverlay::new(&theme, app.help_state.scroll_offset);
+        let open_default = app.open_rules.default_action().label();
+        let help_widget = HelpOverlay::new(&theme, app.help_state.scroll_offset)
+            .open_default(&open_default)
+            .query(&app.help_state.query, app.help_state.searching);
         frame.render_widget(help_widget, area);
     }
+
+    // Render debug overlay last so it stays visible over everything else
+    if app.show_debug_overlay {
+        let stats = app.memory_stats();
+        frame.render_widget(DebugOverlay::new(&stats, &theme), area);
+    }
+}
+
+/// Draw the open editor in the preview area, for Edit mode and the help
+/// opened from it. False, drawing nothing, when no editor is open.
+#[cfg(feature = "editor")]
+fn draw_editor(
+    app: &mut App,
+    frame: &mut Frame,
+    preview_area: Rect,
+    preview_label: &str,
+    preview_border_style: Style,
+    theme: &ThemeColors,
+) -> bool {
+    if app.editor_state.is_none() {
+        return false;
+    }
+    // Edit mode: render editor widget
+    let dirty = app.editor_state.as_ref().is_some_and(|e| e.modified);
+    let editor_title = match &app.preview_state.current_path {
+        Some(path) => {
+            let name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| "Editor".to_string());
+            if dirty {
+                format!(" {} {} ● [EDIT] ", preview_label, name)
+            } else {
+                format!(" {} {} [EDIT] ", preview_label, name)
+            }
+        }
+        None => format!(" {} [EDIT] ", preview_label),
+    };
+
+    let editor_note = app
+        .editor_state
+        .as_ref()
+        .and_then(|e| whitespace::lines_final_newline(&e.buffer))
+        .and_then(|ending| ending.note());
+    let editor_block = with_footer(
+        Block::default()
+            .title(editor_title)
+            .borders(Borders::ALL)
+            .border_style(preview_border_style),
+        editor_note,
+        theme,
+    );
+
+    // Update visible_height before rendering
+    if let Some(ref mut editor) = app.editor_state {
+        let inner_height = preview_area.height.saturating_sub(2) as usize;
+        editor.visible_height = inner_height;
+        editor.ensure_cursor_visible();
+    }
+
+    if let Some(ref editor) = app.editor_state {
+        let syntax = app.syntax.get();
+        let editor_widget = EditorWidget::new(editor, theme, syntax).block(editor_block);
+        frame.render_widget(editor_widget, preview_area);
+    }
+    true
+}
+
+#[cfg(not(feature = "editor"))]
+fn draw_editor(_: &mut App, _: &mut Frame, _: Rect, _: &str, _: Style, _: &ThemeColors) -> bool {
+    false
+}
+
+/// Put `note` (e.g. "no trailing newline") in the bottom border of `block`.
+fn with_footer<'a>(block: Block<'a>, note: Option<String>, theme: &ThemeColors) -> Block<'a> {
+    match note {
+        Some(note) => block.title_bottom(
+            Line::from(format!(" {} ", note)).style(Style::default().fg(theme.dim_fg)),
+        ),
+        None => block,
+    }
 }
@@ -0,0 +1,195 @@
+//! Trailing whitespace and final newline checks.
+//!
+//! The editor highlights trailing spaces and can clean them up on save; the
+//! preview and editor footers say when a file doesn't end with exactly one
+//! newline. Nothing here writes to disk: the preview only reads the tail of
+//! the file it shows.
+
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Extensions where trailing whitespace means something (two trailing
+/// spaces are a Markdown hard line break), so it is neither highlighted nor
+/// trimmed.
+#[cfg_attr(not(feature = "editor"), allow(dead_code))]
+const SIGNIFICANT_TRAILING_WS: &[&str] = &["md", "markdown", "mdown", "mkd", "mdx"];
+
+/// Bytes read from the end of a file to find how it ends.
+const TAIL_BYTES: u64 = 4096;
+
+/// How a text ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FinalNewline {
+    /// The last line has no newline.
+    Missing,
+    /// Exactly one newline, the usual case.
+    Single,
+    /// This many blank lines follow the last newline.
+    Extra(usize),
+}
+
+impl FinalNewline {
+    /// Footer note for this ending, `None` when there's nothing to flag.
+    pub fn note(self) -> Option<String> {
+        match self {
+            FinalNewline::Missing => Some("no trailing newline".to_string()),
+            FinalNewline::Single => None,
+            FinalNewline::Extra(1) => Some("1 extra trailing newline".to_string()),
+            FinalNewline::Extra(n) => Some(format!("{} extra trailing newlines", n)),
+        }
+    }
+
+    /// Ending for `count` trailing newlines after some non-newline content.
+    fn from_count(count: usize) -> Self {
+        match count {
+            0 => FinalNewline::Missing,
+            1 => FinalNewline::Single,
+            n => FinalNewline::Extra(n - 1),
+        }
+    }
+}
+
+/// Whether trailing whitespace is significant for `path`'s file type.
+#[cfg_attr(not(feature = "editor"), allow(dead_code))]
+pub fn trailing_ws_significant(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|ext| {
+            SIGNIFICANT_TRAILING_WS
+                .iter()
+                .any(|s| s.eq_ignore_ascii_case(ext))
+        })
+        .unwrap_or(false)
+}
+
+/// Char index where the trailing spaces and tabs of `line` begin, `None`
+/// when there are none.
+#[cfg_attr(not(feature = "editor"), allow(dead_code))]
+pub fn trailing_ws_start(line: &str) -> Option<usize> {
+    let kept = line.trim_end_matches([' ', '\t']);
+    if kept.len() == line.len() {
+        None
+    } else {
+        Some(kept.chars().count())
+    }
+}
+
+/// Ending of an editor buffer, where a trailing newline is an empty last
+/// line. `None` for a buffer with no content at all.
+#[cfg_attr(not(feature = "editor"), allow(dead_code))]
+pub fn lines_final_newline(lines: &[String]) -> Option<FinalNewline> {
+    let blank = lines.iter().rev().take_while(|l| l.is_empty()).count();
+    if blank == lines.len() {
+        None
+    } else {
+        Some(FinalNewline::from_count(blank))
+    }
+}
+
+/// Ending of `bytes`, counting `\r\n` as one newline. `None` when there is
+/// nothing but newlines.
+fn bytes_final_newline(bytes: &[u8]) -> Option<FinalNewline> {
+    let mut count = 0;
+    let mut end = bytes.len();
+    while end > 0 && bytes[end - 1] == b'\n' {
+        end -= 1;
+        if end > 0 && bytes[end - 1] == b'\r' {
+            end -= 1;
+        }
+        count += 1;
+    }
+    if end == 0 {
+        None
+    } else {
+        Some(FinalNewline::from_count(count))
+    }
+}
+
+/// Ending of the file at `path`, reading only its last few kilobytes.
+/// `None` for empty or unreadable files.
+pub fn file_final_newline(path: &Path) -> Option<FinalNewline> {
+    let mut file = File::open(path).ok()?;
+    let len = file.metadata().ok()?.len();
+    let start = len.saturating_sub(TAIL_BYTES);
+    file.seek(SeekFrom::Start(start)).ok()?;
+    let mut tail = Vec::new();
+    file.read_to_end(&mut tail).ok()?;
+    bytes_final_newline(&tail)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn markdown_extensions_are_exempt() {
+        assert!(trailing_ws_significant(Path::new("README.md")));
+        assert!(trailing_ws_significant(Path::new("notes.MARKDOWN")));
+        assert!(trailing_ws_significant(Path::new("page.mdx")));
+        assert!(!trailing_ws_significant(Path::new("main.rs")));
+        assert!(!trailing_ws_significant(Path::new("Makefile")));
+    }
+
+    #[test]
+    fn trailing_ws_start_counts_chars() {
+        assert_eq!(trailing_ws_start("fn main() {"), None);
+        assert_eq!(trailing_ws_start("let x = 1;  "), Some(10));
+        assert_eq!(trailing_ws_start("héllo\t "), Some(5));
+        assert_eq!(trailing_ws_start("   "), Some(0));
+        assert_eq!(trailing_ws_start(""), None);
+    }
+
+    #[test]
+    fn buffer_endings() {
+        let lines = |v: &[&str]| v.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            lines_final_newline(&lines(&["a"])),
+            Some(FinalNewline::Missing)
+        );
+        assert_eq!(
+            lines_final_newline(&lines(&["a", ""])),
+            Some(FinalNewline::Single)
+        );
+        assert_eq!(
+            lines_final_newline(&lines(&["a", "", "", ""])),
+            Some(FinalNewline::Extra(2))
+        );
+        assert_eq!(lines_final_newline(&lines(&[""])), None);
+    }
+
+    #[test]
+    fn file_endings_read_from_disk() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let check = |name: &str, content: &[u8]| {
+            let path = tmp.path().join(name);
+            std::fs::write(&path, content).unwrap();
+            file_final_newline(&path)
+        };
+        assert_eq!(check("a", b"one\ntwo"), Some(FinalNewline::Missing));
+        assert_eq!(check("b", b"one\r\ntwo\r\n"), Some(FinalNewline::Single));
+        assert_eq!(check("c", b"one\n\n\n"), Some(FinalNewline::Extra(2)));
+        assert_eq!(check("d", b""), None);
+
+        let mut big = vec![b'x'; 10_000];
+        big.push(b'\n');
+        assert_eq!(check("e", &big), Some(FinalNewline::Single));
+    }
+
+    #[test]
+    fn notes_only_for_unusual_endings() {
+        assert_eq!(FinalNewline::Single.note(), None);
+        assert_eq!(
+            FinalNewline::Missing.note().as_deref(),
+            Some("no trailing newline")
+        );
+        assert_eq!(
+            FinalNewline::Extra(1).note().as_deref(),
+            Some("1 extra trailing newline")
+        );
+        assert_eq!(
+            FinalNewline::Extra(3).note().as_deref(),
+            Some("3 extra trailing newlines")
+        );
+    }
+}
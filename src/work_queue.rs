@@ -0,0 +1,332 @@
+//! Whole-tree work spread over several frames.
+//!
+//! Sorting every loaded directory, re-filtering, and re-expanding saved
+//! directories after a refresh each walk the loaded tree, which takes
+//! longer than a frame once it holds 100k nodes. They run as jobs of
+//! [`TreeStep`]s instead, a [`Budget`] at a time: the first slice runs at
+//! once (a small tree is done in it), and each later one is asked for with
+//! `Event::ContinueWork` after the frame is drawn, so keys and redraws get
+//! in between.
+//!
+//! Jobs run in the order they were queued. A new job rebuilds the view
+//! itself, so it drops the view rebuilds of older jobs and cancels the
+//! ones left with nothing else to do: the next filter keystroke cancels
+//! the filter job of the one before. Jobs are listed in the tasks overlay
+//! (`J`) as `tree` tasks; one cancelled there is dropped at its next slice.
+
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use crate::fs::tree::{TreeState, TreeStep};
+use crate::tasks::{Priority, TaskKind, TaskManager, TaskToken};
+
+pub type WorkId = u64;
+
+/// Time a slice may take: half a 16 ms frame, leaving the rest for
+/// drawing.
+pub const SLICE_TIME: Duration = Duration::from_millis(8);
+/// Nodes a slice may visit.
+pub const SLICE_NODES: usize = 4_096;
+
+/// How much work a slice has left.
+#[derive(Debug, Clone, Copy)]
+pub struct Budget {
+    nodes: usize,
+    deadline: Instant,
+}
+
+impl Budget {
+    pub fn new(nodes: usize, time: Duration) -> Self {
+        Self {
+            nodes,
+            deadline: Instant::now() + time,
+        }
+    }
+
+    /// The budget of one slice.
+    pub fn slice() -> Self {
+        Self::new(SLICE_NODES, SLICE_TIME)
+    }
+
+    /// Charge `nodes` visited. Returns false once the slice is used up.
+    pub fn spend(&mut self, nodes: usize) -> bool {
+        self.nodes = self.nodes.saturating_sub(nodes);
+        self.nodes > 0 && Instant::now() < self.deadline
+    }
+
+    /// Nodes left to visit in this slice.
+    #[cfg(test)]
+    pub fn nodes_left(&self) -> usize {
+        self.nodes
+    }
+}
+
+/// What a job was queued for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkKind {
+    Sort,
+    Filter,
+    /// Re-expanding directories after the tree was reloaded.
+    Refresh,
+}
+
+impl WorkKind {
+    /// Shown in the tree title while the job runs, and in the tasks overlay.
+    pub fn label(self) -> &'static str {
+        match self {
+            WorkKind::Sort => "sorting",
+            WorkKind::Filter => "filtering",
+            WorkKind::Refresh => "refreshing",
+        }
+    }
+}
+
+/// Selection and scroll to put back once a refresh has rebuilt the view.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ViewRestore {
+    pub selected: Option<PathBuf>,
+    pub scroll_offset: usize,
+}
+
+/// What a slice left behind.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Slice {
+    /// Nothing ran: the queue is empty or its front job was cancelled.
+    Idle,
+    /// The front job needs more slices.
+    More,
+    /// The front job finished, leaving a view to restore.
+    Done(Option<ViewRestore>),
+}
+
+struct Job {
+    id: WorkId,
+    kind: WorkKind,
+    steps: VecDeque<TreeStep>,
+    then: Option<ViewRestore>,
+    token: TaskToken,
+    /// `TreeState::generation` after the last slice; a step whose tree
+    /// changed since then starts over.
+    generation: Option<u64>,
+}
+
+/// Tree jobs waiting for their next slice.
+#[derive(Default)]
+pub struct WorkQueue {
+    jobs: VecDeque<Job>,
+    next_id: WorkId,
+    /// Job a `ContinueWork` event is on its way for.
+    scheduled: Option<WorkId>,
+}
+
+impl WorkQueue {
+    /// Queue a job of `steps`, restoring `then` once they are done, and
+    /// cancel what it supersedes.
+    pub fn push(
+        &mut self,
+        tasks: &TaskManager,
+        kind: WorkKind,
+        steps: Vec<TreeStep>,
+        then: Option<ViewRestore>,
+    ) -> WorkId {
+        for job in &mut self.jobs {
+            job.steps.retain(|step| !step.rebuilds_view());
+            job.then = None;
+        }
+        self.jobs.retain(|job| {
+            let keep = !job.steps.is_empty();
+            if !keep {
+                tasks.cancel(job.token.id());
+                tasks.finish(job.token.id());
+            }
+            keep
+        });
+        self.next_id += 1;
+        self.jobs.push_back(Job {
+            id: self.next_id,
+            kind,
+            steps: steps.into(),
+            then,
+            token: tasks.register(TaskKind::TreeWork, kind.label(), Priority::User),
+            generation: None,
+        });
+        self.next_id
+    }
+
+    /// What the job running now is doing, for the busy indicator.
+    pub fn busy(&self) -> Option<WorkKind> {
+        self.jobs.front().map(|job| job.kind)
+    }
+
+    /// The job to ask the next slice for, unless one was asked for already.
+    pub fn schedule(&mut self) -> Option<WorkId> {
+        if self.scheduled.is_some() {
+            return None;
+        }
+        self.scheduled = self.jobs.front().map(|job| job.id);
+        self.scheduled
+    }
+
+    /// Handle the slice asked for job `id`: run it if that job is still
+    /// the front one, otherwise (it was superseded) do nothing.
+    pub fn continue_job(&mut self, id: WorkId, tasks: &TaskManager, tree: &mut TreeState) -> Slice {
+        if self.scheduled == Some(id) {
+            self.scheduled = None;
+        }
+        if self.jobs.front().is_some_and(|job| job.id == id) {
+            self.run(tasks, tree)
+        } else {
+            Slice::Idle
+        }
+    }
+
+    /// Run one slice of the front job.
+    pub fn run(&mut self, tasks: &TaskManager, tree: &mut TreeState) -> Slice {
+        let Some(job) = self.jobs.front_mut() else {
+            return Slice::Idle;
+        };
+        if job.token.is_cancelled() {
+            tasks.finish(job.token.id());
+            self.jobs.pop_front();
+            return Slice::Idle;
+        }
+        if job.generation.is_some_and(|seen| seen != tree.generation) {
+            if let Some(step) = job.steps.front_mut() {
+                step.restart();
+            }
+        }
+        let mut budget = Budget::slice();
+        while let Some(step) = job.steps.front_mut() {
+            if !step.run(tree, &mut budget) {
+                break;
+            }
+            job.steps.pop_front();
+        }
+        job.generation = Some(tree.generation);
+        if let Some((done, total)) = job.steps.front().and_then(TreeStep::progress) {
+            job.token.progress(done, total);
+        }
+        if !job.steps.is_empty() {
+            return Slice::More;
+        }
+        tasks.finish(job.token.id());
+        let then = job.then.take();
+        self.jobs.pop_front();
+        Slice::Done(then)
+    }
+
+    /// Drop every job, for a tree that is being replaced.
+    pub fn clear(&mut self, tasks: &TaskManager) {
+        for job in self.jobs.drain(..) {
+            tasks.cancel(job.token.id());
+            tasks.finish(job.token.id());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{self, File};
+    use tempfile::TempDir;
+
+    fn setup() -> (TempDir, TreeState) {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir(dir.path().join("alpha")).unwrap();
+        File::create(dir.path().join("beta.rs")).unwrap();
+        File::create(dir.path().join("gamma.txt")).unwrap();
+        let tree = TreeState::new(dir.path()).unwrap();
+        (dir, tree)
+    }
+
+    fn names(tree: &TreeState) -> Vec<&str> {
+        tree.flat_items[1..]
+            .iter()
+            .map(|item| item.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn a_new_job_drops_the_view_rebuilds_it_supersedes() {
+        let (_dir, mut tree) = setup();
+        let tasks = TaskManager::default();
+        let mut queue = WorkQueue::default();
+        tree.dirs_first = false;
+        queue.push(
+            &tasks,
+            WorkKind::Sort,
+            vec![TreeState::sort_step(false), TreeStep::flatten()],
+            None,
+        );
+        tree.filter_query = "a".to_string();
+        queue.push(&tasks, WorkKind::Filter, vec![TreeStep::filter()], None);
+        tree.filter_query = "ta".to_string();
+        let last = queue.push(&tasks, WorkKind::Filter, vec![TreeStep::filter()], None);
+
+        // The first filter job had nothing else to do; the sort keeps its
+        // sorting but no longer flattens
+        assert_eq!(tasks.counters().cancelled, 1);
+        let listed = tasks.tasks();
+        assert_eq!(listed.len(), 2);
+        assert!(listed.iter().all(|task| task.kind == TaskKind::TreeWork));
+        assert_eq!(listed[0].description, "sorting");
+
+        assert_eq!(queue.busy(), Some(WorkKind::Sort));
+        assert_eq!(queue.run(&tasks, &mut tree), Slice::Done(None));
+        assert_eq!(names(&tree), vec!["alpha", "beta.rs", "gamma.txt"]);
+        assert_eq!(queue.busy(), Some(WorkKind::Filter));
+        assert_eq!(
+            queue.continue_job(last, &tasks, &mut tree),
+            Slice::Done(None)
+        );
+        assert_eq!(names(&tree), vec!["beta.rs"]);
+        assert!(tree.is_filtering);
+
+        assert_eq!(queue.busy(), None);
+        assert!(tasks.tasks().is_empty());
+        assert_eq!(tasks.counters().finished, 2);
+    }
+
+    #[test]
+    fn superseded_and_cancelled_jobs_do_not_run() {
+        let (dir, mut tree) = setup();
+        let tasks = TaskManager::default();
+        let mut queue = WorkQueue::default();
+        let view = ViewRestore {
+            selected: Some(dir.path().join("gamma.txt")),
+            scroll_offset: 0,
+        };
+        let first = queue.push(
+            &tasks,
+            WorkKind::Refresh,
+            vec![TreeStep::flatten()],
+            Some(view.clone()),
+        );
+        // One slice is asked for at a time
+        assert_eq!(queue.schedule(), Some(first));
+        assert_eq!(queue.schedule(), None);
+
+        // Cancelled from the tasks overlay: dropped at its next slice
+        assert!(tasks.cancel(tasks.tasks()[0].id));
+        assert_eq!(queue.continue_job(first, &tasks, &mut tree), Slice::Idle);
+        assert_eq!(queue.busy(), None);
+        assert!(tasks.tasks().is_empty());
+        assert_eq!(tasks.counters().cancelled, 1);
+
+        // A slice asked for a job that is gone does nothing
+        let second = queue.push(
+            &tasks,
+            WorkKind::Refresh,
+            vec![TreeStep::flatten()],
+            Some(view.clone()),
+        );
+        assert_eq!(queue.continue_job(first, &tasks, &mut tree), Slice::Idle);
+        assert_eq!(queue.schedule(), Some(second));
+        assert_eq!(
+            queue.continue_job(second, &tasks, &mut tree),
+            Slice::Done(Some(view))
+        );
+        assert_eq!(queue.schedule(), None);
+    }
+}
@@ -0,0 +1,295 @@
+//! Soft wrapping for the preview.
+//!
+//! A wrapped line becomes several [`WrapRow`]s that point back into the
+//! logical line they came from instead of new strings. The continuation
+//! marker and hang indent are drawn in front of a row, never inserted into
+//! the text, so anything reading `content_lines` (search, copying a line)
+//! still sees the file as it is.
+
+use ratatui::text::Line;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::text;
+
+/// Narrowest content a continuation row is allowed to shrink to; the hang
+/// indent and then the marker are dropped before going below it.
+const MIN_SEGMENT_WIDTH: usize = 8;
+
+/// How continuation rows are drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WrapStyle<'a> {
+    /// Drawn before each continuation row ("" for none).
+    pub marker: &'a str,
+    /// Indent continuation rows to the line's leading whitespace.
+    pub hang_indent: bool,
+}
+
+/// One screen row of a wrapped line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WrapRow {
+    /// Index of the logical line.
+    pub line: usize,
+    /// 0 for the line's first row, then 1, 2, … for continuations.
+    pub segment: usize,
+    /// First column of the line shown on this row.
+    pub start: usize,
+    /// Columns of the line shown on this row.
+    pub len: usize,
+    /// Blank columns in front of the marker: the line-number gutter plus
+    /// the hang indent. Always 0 on a first row.
+    pub lead: usize,
+    /// Whether the marker is drawn (never on a first row).
+    pub marker: bool,
+}
+
+/// Plain text of `line`, its spans joined.
+pub fn line_text(line: &Line<'_>) -> String {
+    line.spans.iter().map(|s| s.content.as_ref()).collect()
+}
+
+/// Width of the `  12 │ ` line-number gutter leading a preview line, 0 when
+/// the line has none (directory summaries, notebooks).
+pub fn number_gutter(line: &Line<'_>) -> usize {
+    match line.spans.first() {
+        Some(span) if line.spans.len() > 1 && span.content.ends_with("│ ") => {
+            text::width(&span.content)
+        }
+        _ => 0,
+    }
+}
+
+/// Split `text`, logical line `line`, into rows of at most `width` columns.
+///
+/// Breaks fall on grapheme boundaries, so a wide glyph moves whole to the
+/// next row. Continuation rows line up after the first `gutter` columns
+/// (the line-number gutter) and, with a hang indent, after the line's
+/// leading whitespace.
+pub fn wrap_line(
+    text_str: &str,
+    line: usize,
+    width: usize,
+    gutter: usize,
+    style: WrapStyle<'_>,
+) -> Vec<WrapRow> {
+    let total = text::width(text_str);
+    let first = WrapRow {
+        line,
+        segment: 0,
+        start: 0,
+        len: total,
+        lead: 0,
+        marker: false,
+    };
+    if width == 0 || total <= width {
+        return vec![first];
+    }
+
+    let indent = if style.hang_indent {
+        leading_whitespace(text_str, gutter)
+    } else {
+        0
+    };
+    let marker_width = text::width(style.marker);
+    let (lead, marker) = [
+        (gutter + indent, true),
+        (gutter, true),
+        (gutter, false),
+        (0, false),
+    ]
+    .into_iter()
+    .find(|&(lead, marker)| {
+        let prefix = lead + if marker { marker_width } else { 0 };
+        prefix + MIN_SEGMENT_WIDTH <= width
+    })
+    .unwrap_or((0, false));
+    let marker = marker && marker_width > 0;
+    let prefix = lead + if marker { marker_width } else { 0 };
+
+    let mut rows = Vec::new();
+    let mut row_start = 0;
+    let mut available = width;
+    let mut col = 0;
+    for g in text_str.graphemes(true) {
+        let g_width = text::width(g);
+        if col > row_start && col + g_width > row_start + available {
+            rows.push((row_start, col - row_start));
+            row_start = col;
+            available = width - prefix;
+        }
+        col += g_width;
+    }
+    rows.push((row_start, col - row_start));
+
+    rows.into_iter()
+        .enumerate()
+        .map(|(segment, (start, len))| {
+            if segment == 0 {
+                WrapRow { len, ..first }
+            } else {
+                WrapRow {
+                    line,
+                    segment,
+                    start,
+                    len,
+                    lead,
+                    marker,
+                }
+            }
+        })
+        .collect()
+}
+
+/// Rows for `lines` from logical line `first` on, stopping after `max_rows`.
+pub fn wrap_rows(
+    lines: &[Line<'_>],
+    first: usize,
+    width: usize,
+    max_rows: usize,
+    style: WrapStyle<'_>,
+) -> Vec<WrapRow> {
+    let mut rows = Vec::new();
+    for (i, line) in lines.iter().enumerate().skip(first) {
+        if rows.len() >= max_rows {
+            break;
+        }
+        rows.extend(wrap_line(
+            &line_text(line),
+            i,
+            width,
+            number_gutter(line),
+            style,
+        ));
+    }
+    rows.truncate(max_rows);
+    rows
+}
+
+/// Columns of spaces and tabs that start the text after its first `skip`
+/// columns.
+fn leading_whitespace(text_str: &str, skip: usize) -> usize {
+    let total = text::width(text_str);
+    let rest = text::slice_columns(text_str, skip.min(total), total.saturating_sub(skip));
+    let trimmed = rest.trim_start_matches([' ', '\t']);
+    text::width(&rest[..rest.len() - trimmed.len()])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::text::Span;
+
+    const ARROW: WrapStyle<'static> = WrapStyle {
+        marker: "↪ ",
+        hang_indent: true,
+    };
+
+    fn spans(rows: &[WrapRow]) -> Vec<(usize, usize, usize, usize, bool)> {
+        rows.iter()
+            .map(|r| (r.segment, r.start, r.len, r.lead, r.marker))
+            .collect()
+    }
+
+    #[test]
+    fn short_lines_stay_whole() {
+        let rows = wrap_line("fn main() {}", 3, 20, 0, ARROW);
+        assert_eq!(spans(&rows), vec![(0, 0, 12, 0, false)]);
+        assert_eq!(rows[0].line, 3);
+    }
+
+    #[test]
+    fn long_indented_line_hangs_after_its_indent() {
+        // 4 columns of indent, then 36 of code: 40 columns in a 20 wide view.
+        let line = format!("    {}", "x".repeat(36));
+        let rows = wrap_line(&line, 0, 20, 0, ARROW);
+        // Continuations: 4 lead + 2 marker leaves 14 columns each.
+        assert_eq!(
+            spans(&rows),
+            vec![
+                (0, 0, 20, 0, false),
+                (1, 20, 14, 4, true),
+                (2, 34, 6, 4, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn continuation_lines_up_after_the_number_gutter() {
+        let line = Line::from(vec![
+            Span::raw(" 7 │ "),
+            Span::raw("  "),
+            Span::raw("y".repeat(30)),
+        ]);
+        assert_eq!(number_gutter(&line), 5);
+        let rows = wrap_rows(std::slice::from_ref(&line), 0, 20, 10, ARROW);
+        assert_eq!(
+            spans(&rows),
+            vec![
+                (0, 0, 20, 0, false),
+                (1, 20, 11, 7, true),
+                (2, 31, 6, 7, true)
+            ]
+        );
+    }
+
+    #[test]
+    fn hang_indent_is_optional_and_dropped_when_too_deep() {
+        let line = format!("{}{}", " ".repeat(16), "z".repeat(20));
+        let flat = WrapStyle {
+            hang_indent: false,
+            ..ARROW
+        };
+        assert_eq!(wrap_line(&line, 0, 20, 0, flat)[1].lead, 0);
+        // 16 + 2 + 8 > 20: the indent goes, the marker stays.
+        let deep = wrap_line(&line, 0, 20, 0, ARROW);
+        assert_eq!((deep[1].lead, deep[1].marker), (0, true));
+    }
+
+    #[test]
+    fn empty_marker_draws_nothing() {
+        let style = WrapStyle {
+            marker: "",
+            hang_indent: false,
+        };
+        let rows = wrap_line(&"a".repeat(25), 0, 10, 0, style);
+        assert_eq!(
+            spans(&rows),
+            vec![
+                (0, 0, 10, 0, false),
+                (1, 10, 10, 0, false),
+                (2, 20, 5, 0, false)
+            ]
+        );
+    }
+
+    #[test]
+    fn wide_glyphs_are_not_split() {
+        // Nine columns of ASCII, then a two-column glyph that doesn't fit.
+        let rows = wrap_line("abcdefghi中文", 0, 10, 0, ARROW);
+        assert_eq!(rows[0].len, 9);
+        assert_eq!((rows[1].start, rows[1].len), (9, 4));
+    }
+
+    #[test]
+    fn rows_stop_at_the_viewport() {
+        let lines: Vec<Line> = (0..5).map(|_| Line::from("w".repeat(25))).collect();
+        let rows = wrap_rows(&lines, 1, 10, 4, ARROW);
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[0].line, 1);
+        assert_eq!((rows[3].line, rows[3].segment), (2, 0));
+    }
+
+    #[test]
+    fn segments_rejoin_into_the_logical_line() {
+        // The marker and indent are layout only: the row ranges cover the
+        // line exactly, so search and copy keep working on plain lines.
+        let line = format!("      let total = {};", "a + ".repeat(20));
+        let rows = wrap_line(&line, 0, 24, 0, ARROW);
+        assert!(rows.len() > 2);
+        let rejoined: String = rows
+            .iter()
+            .map(|r| text::slice_columns(&line, r.start, r.len))
+            .collect();
+        assert_eq!(rejoined, line);
+        assert!(!rejoined.contains('↪'));
+    }
+}